@@ -1,5 +1,70 @@
 use crate::prelude::*;
 use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Number of inventory samples used to estimate the half-life
+const HALF_LIFE_WINDOW: usize = 500;
+
+/// External view of the net inventory held per symbol
+///
+/// Lets a portfolio system that books fills from every venue drive the
+/// quoting engine, instead of the engine counting only its own fills.
+pub trait InventorySource: Send + Sync {
+    /// Net inventory for a symbol (long positive), or `None` if unknown
+    fn net_inventory(&self, symbol: &str) -> Option<f64>;
+}
+
+/// Estimates how quickly inventory reverts to target
+///
+/// Fits an AR(1) model `x[t] = phi * x[t-1]` to the inventory deviation from
+/// target and converts `phi` into a half-life using the mean sample spacing.
+pub struct InventoryHalfLife {
+    samples: VecDeque<(Instant, f64)>,
+    window: usize,
+}
+
+impl InventoryHalfLife {
+    pub fn new(window: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(window),
+            window: window.max(2),
+        }
+    }
+
+    /// Record the inventory deviation from target at a point in time
+    pub fn record(&mut self, ts: Instant, deviation: f64) {
+        self.samples.push_back((ts, deviation));
+        if self.samples.len() > self.window {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Estimated half-life, or `None` if inventory is not mean reverting
+    pub fn half_life(&self) -> Option<Duration> {
+        if self.samples.len() < 3 {
+            return None;
+        }
+
+        let (mut cross, mut lagged_sq) = (0.0, 0.0);
+        for (prev, next) in self.samples.iter().zip(self.samples.iter().skip(1)) {
+            cross += prev.1 * next.1;
+            lagged_sq += prev.1 * prev.1;
+        }
+        if lagged_sq <= 0.0 {
+            return None;
+        }
+        let phi = cross / lagged_sq;
+        if phi <= 0.0 || phi >= 1.0 {
+            return None;
+        }
+
+        let (first, last) = (self.samples.front()?.0, self.samples.back()?.0);
+        let step = last.duration_since(first).as_secs_f64() / (self.samples.len() - 1) as f64;
+        let steps = 0.5f64.ln() / phi.ln();
+        Some(Duration::from_secs_f64(steps * step))
+    }
+}
 
 /// Enhanced Market Making Strategy with advanced features
 pub struct EnhancedMarketMaking {
@@ -20,6 +85,9 @@ pub struct EnhancedMarketMaking {
     // Performance tracking
     total_pnl: f64,
     trades_count: u64,
+    // External inventory
+    inventory_source: Option<Arc<dyn InventorySource>>,
+    inventory_half_life: InventoryHalfLife,
 }
 
 impl EnhancedMarketMaking {
@@ -42,13 +110,32 @@ impl EnhancedMarketMaking {
             // Performance tracking
             total_pnl: 0.0,
             trades_count: 0,
+            // External inventory
+            inventory_source: None,
+            inventory_half_life: InventoryHalfLife::new(HALF_LIFE_WINDOW),
         }
     }
 
+    /// Read net inventory for the configured symbol from an external source
+    pub fn with_inventory_source(mut self, source: Arc<dyn InventorySource>) -> Self {
+        self.inventory_source = Some(source);
+        self
+    }
+
     /// Main function to process quotes and generate orders
     pub fn on_quote(&mut self, q: &Quote) -> Vec<Order> {
         self.quotes_received += 1;
         
+        // Sync inventory from the external source, if any
+        if let Some(inventory) = self
+            .inventory_source
+            .as_ref()
+            .and_then(|source| source.net_inventory(&self.cfg.symbol))
+        {
+            self.inventory = inventory;
+        }
+        self.inventory_half_life.record(q.ts, self.inventory - self.target_inventory);
+        
         // Update queue position tracking
         self.update_queue_position(q);
         
@@ -125,11 +212,12 @@ impl EnhancedMarketMaking {
 
     /// Calculate inventory-adjusted quotes
     fn calculate_inventory_adjusted_quotes(&self, q: &Quote, spread: f64) -> (f64, f64) {
-        // Adjust quotes based on inventory position
+        // Shift both quotes against the inventory: when long, bid less and
+        // offer cheaper so sells fill first; when short, the reverse
         let inventory_skew = self.calculate_inventory_skew();
         
         let bid_price = q.bid - spread / 2.0 - inventory_skew;
-        let ask_price = q.ask + spread / 2.0 + inventory_skew;
+        let ask_price = q.ask + spread / 2.0 - inventory_skew;
         
         (bid_price, ask_price)
     }
@@ -212,6 +300,11 @@ impl EnhancedMarketMaking {
     pub fn quotes_received(&self) -> u64 {
         self.quotes_received
     }
+
+    /// Estimated time for inventory to revert halfway to target
+    pub fn inventory_half_life(&self) -> Option<Duration> {
+        self.inventory_half_life.half_life()
+    }
 }
 
 #[cfg(test)]
@@ -338,4 +431,53 @@ mod tests {
         let skew = mm.calculate_inventory_skew();
         assert_eq!(skew, 0.0);
     }
+
+    struct FixedInventory(f64);
+
+    impl InventorySource for FixedInventory {
+        fn net_inventory(&self, _symbol: &str) -> Option<f64> {
+            Some(self.0)
+        }
+    }
+
+    #[test]
+    fn test_quotes_skew_with_external_inventory() {
+        let quote = Quote {
+            bid: 99.50,
+            ask: 100.50,
+            ts: std::time::Instant::now(),
+        };
+        let mut flat = EnhancedMarketMaking::new(Cfg::default());
+        let mut long = EnhancedMarketMaking::new(Cfg::default())
+            .with_inventory_source(Arc::new(FixedInventory(5_000.0)));
+        
+        let flat_orders = flat.on_quote(&quote);
+        let long_orders = long.on_quote(&quote);
+        assert_eq!(long.inventory(), 5_000.0);
+        
+        // Long inventory lowers both quotes so our offer fills first
+        assert!(long_orders[0].px < flat_orders[0].px);
+        assert!(long_orders[1].px < flat_orders[1].px);
+    }
+
+    #[test]
+    fn test_inventory_half_life() {
+        let start = Instant::now();
+        let mut tracker = InventoryHalfLife::new(100);
+        assert!(tracker.half_life().is_none());
+        
+        // Inventory halves every second
+        for i in 0..10 {
+            tracker.record(start + Duration::from_secs(i), 1_000.0 * 0.5f64.powi(i as i32));
+        }
+        let half_life = tracker.half_life().unwrap();
+        assert!((half_life.as_secs_f64() - 1.0).abs() < 1e-6);
+        
+        // Inventory that keeps growing never reverts
+        let mut growing = InventoryHalfLife::new(100);
+        for i in 0..10 {
+            growing.record(start + Duration::from_secs(i), 100.0 * (i + 1) as f64);
+        }
+        assert!(growing.half_life().is_none());
+    }
 }
\ No newline at end of file
@@ -5,6 +5,7 @@ pub mod enhanced_risk;
 pub mod enhanced_mm;
 pub mod enhanced_arb;
 pub mod monitoring;
+pub mod order_book;
 
 #[cfg(test)]
 mod tests {
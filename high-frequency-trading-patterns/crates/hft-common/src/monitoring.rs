@@ -1,7 +1,7 @@
 //! Performance monitoring and metrics collection for HFT patterns
 use crate::prelude::*;
 use std::sync::atomic::{AtomicU64, AtomicI64, Ordering};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tracing::{info, debug};
 
 /// Performance metrics tracker
@@ -19,6 +19,8 @@ pub struct PerformanceMonitor {
     avg_latency_us: AtomicI64,
     /// Maximum latency observed in microseconds
     max_latency_us: AtomicU64,
+    /// Latest inventory half-life estimate in milliseconds (0 when unknown)
+    inventory_half_life_ms: AtomicU64,
     /// Start time of monitoring
     start_time: Instant,
     /// Pattern name for identification
@@ -35,6 +37,7 @@ impl PerformanceMonitor {
             total_pnl: AtomicI64::new(0),
             avg_latency_us: AtomicI64::new(0),
             max_latency_us: AtomicU64::new(0),
+            inventory_half_life_ms: AtomicU64::new(0),
             start_time: Instant::now(),
             pattern_name: pattern_name.to_string(),
         }
@@ -81,6 +84,12 @@ impl PerformanceMonitor {
         self.avg_latency_us.store(scaled_new_avg, Ordering::Relaxed);
     }
 
+    /// Record the latest inventory half-life estimate
+    pub fn record_inventory_half_life(&self, half_life: Option<Duration>) {
+        let ms = half_life.map(|h| (h.as_millis() as u64).max(1)).unwrap_or(0);
+        self.inventory_half_life_ms.store(ms, Ordering::Relaxed);
+    }
+
     /// Get current metrics snapshot
     pub fn get_metrics(&self) -> PerformanceMetrics {
         let scaled_pnl = self.total_pnl.load(Ordering::Relaxed) as f64 / 10000.0;
@@ -93,6 +102,10 @@ impl PerformanceMonitor {
             total_pnl: scaled_pnl,
             avg_latency_us: scaled_avg_latency,
             max_latency_us: self.max_latency_us.load(Ordering::Relaxed),
+            inventory_half_life_secs: match self.inventory_half_life_ms.load(Ordering::Relaxed) {
+                0 => None,
+                ms => Some(ms as f64 / 1000.0),
+            },
             uptime_seconds: self.start_time.elapsed().as_secs_f64(),
             pattern_name: self.pattern_name.clone(),
         }
@@ -102,7 +115,7 @@ impl PerformanceMonitor {
     pub fn log_metrics(&self) {
         let metrics = self.get_metrics();
         info!(
-            "[{}] Metrics - Quotes: {}, Orders: {}, Fills: {}, PnL: {:.2}, Avg Latency: {:.2}μs, Max Latency: {}μs, Uptime: {:.1}s",
+            "[{}] Metrics - Quotes: {}, Orders: {}, Fills: {}, PnL: {:.2}, Avg Latency: {:.2}μs, Max Latency: {}μs, Inventory Half-Life: {}, Uptime: {:.1}s",
             metrics.pattern_name,
             metrics.quotes_processed,
             metrics.orders_sent,
@@ -110,6 +123,10 @@ impl PerformanceMonitor {
             metrics.total_pnl,
             metrics.avg_latency_us,
             metrics.max_latency_us,
+            metrics
+                .inventory_half_life_secs
+                .map(|secs| format!("{:.1}s", secs))
+                .unwrap_or_else(|| "n/a".to_string()),
             metrics.uptime_seconds
         );
     }
@@ -122,6 +139,7 @@ impl PerformanceMonitor {
         self.total_pnl.store(0, Ordering::Relaxed);
         self.avg_latency_us.store(0, Ordering::Relaxed);
         self.max_latency_us.store(0, Ordering::Relaxed);
+        self.inventory_half_life_ms.store(0, Ordering::Relaxed);
         // Note: We don't reset start_time to maintain uptime tracking
     }
 }
@@ -135,6 +153,7 @@ pub struct PerformanceMetrics {
     pub total_pnl: f64,
     pub avg_latency_us: f64,
     pub max_latency_us: u64,
+    pub inventory_half_life_secs: Option<f64>,
     pub uptime_seconds: f64,
     pub pattern_name: String,
 }
@@ -221,6 +240,18 @@ mod tests {
         assert!(metrics.avg_latency_us > 0.0);
     }
 
+    #[test]
+    fn test_inventory_half_life_metric() {
+        let monitor = PerformanceMonitor::new("test_pattern");
+        assert_eq!(monitor.get_metrics().inventory_half_life_secs, None);
+        
+        monitor.record_inventory_half_life(Some(Duration::from_millis(2500)));
+        assert_eq!(monitor.get_metrics().inventory_half_life_secs, Some(2.5));
+        
+        monitor.record_inventory_half_life(None);
+        assert_eq!(monitor.get_metrics().inventory_half_life_secs, None);
+    }
+
     #[test]
     fn test_rate_calculations() {
         let monitor = PerformanceMonitor::new("test_pattern");
@@ -0,0 +1,436 @@
+//! Limit order book maintained from exchange snapshots and deltas
+//!
+//! Prices are kept as whole ticks so levels sort exactly in a `BTreeMap`,
+//! and the best level of each side is cached so quoting code reads the top
+//! of book in O(1). L2 feeds set the quantity of a level (zero removes it);
+//! L3 feeds add, modify and cancel individual orders, whose quantities are
+//! summed into the same levels. Deltas carry the feed's sequence number and
+//! a gap is reported rather than applied, so the caller can resnapshot.
+
+use crate::models::{Quote, Side};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::time::Instant;
+
+/// Levels per side covered by the book checksum, as on OKX
+pub const CHECKSUM_DEPTH: usize = 25;
+
+/// Why an update could not be applied
+#[derive(Debug, Clone, PartialEq)]
+pub enum BookError {
+    /// Deltas were missed; the book must be rebuilt from a snapshot
+    SequenceGap { expected: u64, received: u64 },
+    /// The book no longer matches the exchange's
+    ChecksumMismatch { expected: u32, computed: u32 },
+    UnknownOrder(u64),
+    DuplicateOrder(u64),
+    InvalidPrice(f64),
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BookError::SequenceGap { expected, received } => {
+                write!(f, "expected update {} but received {}", expected, received)
+            }
+            BookError::ChecksumMismatch { expected, computed } => {
+                write!(f, "book checksum {} does not match the exchange's {}", computed, expected)
+            }
+            BookError::UnknownOrder(id) => write!(f, "order {} is not in the book", id),
+            BookError::DuplicateOrder(id) => write!(f, "order {} is already in the book", id),
+            BookError::InvalidPrice(px) => write!(f, "price {} is not a positive number", px),
+        }
+    }
+}
+
+impl std::error::Error for BookError {}
+
+/// Quantity resting at one price
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Level {
+    pub px: f64,
+    pub qty: f64,
+    /// Orders making up the level; 0 for levels set from L2 data
+    pub orders: usize,
+}
+
+/// Change to one L2 price level
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LevelDelta {
+    pub side: Side,
+    pub px: f64,
+    /// New quantity at the level; zero removes it
+    pub qty: f64,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct RestingOrder {
+    side: Side,
+    tick: i64,
+    qty: f64,
+}
+
+/// Price-level order book of one symbol
+#[derive(Debug, Clone)]
+pub struct OrderBook {
+    tick_sz: f64,
+    bids: BTreeMap<i64, Level>,
+    asks: BTreeMap<i64, Level>,
+    best_bid: Option<Level>,
+    best_ask: Option<Level>,
+    orders: HashMap<u64, RestingOrder>,
+    seq: Option<u64>,
+}
+
+impl OrderBook {
+    /// Empty book for prices on a grid of `tick_sz`
+    pub fn new(tick_sz: f64) -> Self {
+        Self {
+            tick_sz,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            best_bid: None,
+            best_ask: None,
+            orders: HashMap::new(),
+            seq: None,
+        }
+    }
+
+    /// Replace the book with a snapshot taken at update `seq`
+    pub fn apply_snapshot(&mut self, seq: u64, bids: &[(f64, f64)], asks: &[(f64, f64)]) -> Result<(), BookError> {
+        self.bids.clear();
+        self.asks.clear();
+        self.orders.clear();
+        for (side, levels) in [(Side::Buy, bids), (Side::Sell, asks)] {
+            for &(px, qty) in levels {
+                self.set_level(side, px, qty)?;
+            }
+        }
+        self.refresh_best(Side::Buy);
+        self.refresh_best(Side::Sell);
+        self.seq = Some(seq);
+        Ok(())
+    }
+
+    /// Apply the L2 deltas of update `seq`
+    ///
+    /// Before the first snapshot any sequence is accepted.
+    pub fn apply_deltas(&mut self, seq: u64, deltas: &[LevelDelta]) -> Result<(), BookError> {
+        self.check_seq(seq)?;
+        for delta in deltas {
+            self.set_level(delta.side, delta.px, delta.qty)?;
+            self.refresh_best(delta.side);
+        }
+        self.seq = Some(seq);
+        Ok(())
+    }
+
+    /// Add an L3 order
+    pub fn add_order(&mut self, id: u64, side: Side, px: f64, qty: f64) -> Result<(), BookError> {
+        if self.orders.contains_key(&id) {
+            return Err(BookError::DuplicateOrder(id));
+        }
+        let tick = self.tick(px)?;
+        let tick_sz = self.tick_sz;
+        let level = self.side_mut(side).entry(tick).or_insert(Level {
+            px: round(tick as f64 * tick_sz, tick_sz),
+            qty: 0.0,
+            orders: 0,
+        });
+        level.qty += qty;
+        level.orders += 1;
+        self.orders.insert(id, RestingOrder { side, tick, qty });
+        self.refresh_best(side);
+        Ok(())
+    }
+
+    /// Change the remaining quantity of an L3 order, e.g. after a partial fill
+    pub fn modify_order(&mut self, id: u64, qty: f64) -> Result<(), BookError> {
+        if qty <= 0.0 {
+            return self.cancel_order(id);
+        }
+        let order = self.orders.get_mut(&id).ok_or(BookError::UnknownOrder(id))?;
+        let change = qty - order.qty;
+        order.qty = qty;
+        let (side, tick) = (order.side, order.tick);
+        if let Some(level) = self.side_mut(side).get_mut(&tick) {
+            level.qty += change;
+        }
+        self.refresh_best(side);
+        Ok(())
+    }
+
+    /// Remove an L3 order, whether cancelled or fully filled
+    pub fn cancel_order(&mut self, id: u64) -> Result<(), BookError> {
+        let order = self.orders.remove(&id).ok_or(BookError::UnknownOrder(id))?;
+        let levels = self.side_mut(order.side);
+        if let Some(level) = levels.get_mut(&order.tick) {
+            level.qty -= order.qty;
+            level.orders = level.orders.saturating_sub(1);
+            if level.orders == 0 || level.qty <= 0.0 {
+                levels.remove(&order.tick);
+            }
+        }
+        self.refresh_best(order.side);
+        Ok(())
+    }
+
+    /// Sequence number of the last update applied
+    pub fn seq(&self) -> Option<u64> {
+        self.seq
+    }
+
+    pub fn best_bid(&self) -> Option<Level> {
+        self.best_bid
+    }
+
+    pub fn best_ask(&self) -> Option<Level> {
+        self.best_ask
+    }
+
+    /// Levels of a side, best first
+    pub fn levels(&self, side: Side) -> Box<dyn Iterator<Item = &Level> + '_> {
+        match side {
+            Side::Buy => Box::new(self.bids.values().rev()),
+            Side::Sell => Box::new(self.asks.values()),
+        }
+    }
+
+    pub fn mid(&self) -> Option<f64> {
+        Some((self.best_bid?.px + self.best_ask?.px) / 2.0)
+    }
+
+    pub fn spread(&self) -> Option<f64> {
+        Some(self.best_ask?.px - self.best_bid?.px)
+    }
+
+    /// Spread relative to the mid, in basis points
+    pub fn spread_bps(&self) -> Option<f64> {
+        Some(self.spread()? / self.mid()? * 10_000.0)
+    }
+
+    /// Quantity resting on a side within `bps` of the mid
+    pub fn depth_within_bps(&self, side: Side, bps: f64) -> f64 {
+        let Some(mid) = self.mid() else {
+            return 0.0;
+        };
+        let reach = mid * bps / 10_000.0;
+        self.levels(side)
+            .take_while(|level| (level.px - mid).abs() <= reach + f64::EPSILON * mid)
+            .map(|level| level.qty)
+            .sum()
+    }
+
+    /// Bid against ask quantity over the top `depth` levels, from -1 (all
+    /// asks) to 1 (all bids)
+    pub fn imbalance(&self, depth: usize) -> Option<f64> {
+        let bid_qty: f64 = self.levels(Side::Buy).take(depth).map(|level| level.qty).sum();
+        let ask_qty: f64 = self.levels(Side::Sell).take(depth).map(|level| level.qty).sum();
+        let total = bid_qty + ask_qty;
+        (total > 0.0).then(|| (bid_qty - ask_qty) / total)
+    }
+
+    /// Top of book as a quote
+    pub fn quote(&self) -> Option<Quote> {
+        Some(Quote {
+            bid: self.best_bid?.px,
+            ask: self.best_ask?.px,
+            ts: Instant::now(),
+        })
+    }
+
+    /// CRC32 of the top `depth` levels in OKX's layout
+    ///
+    /// Bids and asks are interleaved as `bidPx:bidQty:askPx:askQty:...`,
+    /// numbers written in their shortest form, so the exchange must send
+    /// them without trailing zeros for checksums to match.
+    pub fn checksum(&self, depth: usize) -> u32 {
+        let mut bids = self.levels(Side::Buy).take(depth);
+        let mut asks = self.levels(Side::Sell).take(depth);
+        let mut fields = Vec::with_capacity(depth * 4);
+        loop {
+            let (bid, ask) = (bids.next(), asks.next());
+            if bid.is_none() && ask.is_none() {
+                break;
+            }
+            for level in [bid, ask].into_iter().flatten() {
+                fields.push(format!("{}:{}", level.px, level.qty));
+            }
+        }
+        crc32(fields.join(":").as_bytes())
+    }
+
+    /// Check the book against the checksum the exchange sent with an update
+    pub fn verify_checksum(&self, expected: u32, depth: usize) -> Result<(), BookError> {
+        let computed = self.checksum(depth);
+        if computed != expected {
+            return Err(BookError::ChecksumMismatch { expected, computed });
+        }
+        Ok(())
+    }
+
+    fn check_seq(&self, seq: u64) -> Result<(), BookError> {
+        match self.seq {
+            Some(last) if seq != last + 1 => Err(BookError::SequenceGap {
+                expected: last + 1,
+                received: seq,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn tick(&self, px: f64) -> Result<i64, BookError> {
+        if !px.is_finite() || px <= 0.0 {
+            return Err(BookError::InvalidPrice(px));
+        }
+        Ok((px / self.tick_sz).round() as i64)
+    }
+
+    fn side_mut(&mut self, side: Side) -> &mut BTreeMap<i64, Level> {
+        match side {
+            Side::Buy => &mut self.bids,
+            Side::Sell => &mut self.asks,
+        }
+    }
+
+    fn set_level(&mut self, side: Side, px: f64, qty: f64) -> Result<(), BookError> {
+        let tick = self.tick(px)?;
+        let tick_sz = self.tick_sz;
+        if qty <= 0.0 {
+            self.side_mut(side).remove(&tick);
+        } else {
+            self.side_mut(side).insert(tick, Level {
+                px: round(tick as f64 * tick_sz, tick_sz),
+                qty,
+                orders: 0,
+            });
+        }
+        Ok(())
+    }
+
+    fn refresh_best(&mut self, side: Side) {
+        match side {
+            Side::Buy => self.best_bid = self.bids.values().next_back().copied(),
+            Side::Sell => self.best_ask = self.asks.values().next().copied(),
+        }
+    }
+}
+
+/// Price snapped to the tick grid, without float noise in its decimals
+fn round(px: f64, tick_sz: f64) -> f64 {
+    let decimals = (-tick_sz.log10().floor()).max(0.0) as i32;
+    let scale = 10f64.powi(decimals);
+    (px * scale).round() / scale
+}
+
+/// CRC-32 (IEEE) of `data`
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book() -> OrderBook {
+        let mut book = OrderBook::new(0.01);
+        book.apply_snapshot(
+            10,
+            &[(99.99, 5.0), (99.98, 10.0), (99.90, 20.0)],
+            &[(100.01, 4.0), (100.03, 8.0), (100.50, 50.0)],
+        )
+        .unwrap();
+        book
+    }
+
+    #[test]
+    fn test_l2_deltas_keep_best_levels() {
+        let mut book = book();
+        assert_eq!(book.best_bid().unwrap().px, 99.99);
+        assert_eq!(book.best_ask().unwrap().qty, 4.0);
+
+        // A better bid, the best ask taken out and a level resized
+        book.apply_deltas(11, &[
+            LevelDelta { side: Side::Buy, px: 100.00, qty: 1.0 },
+            LevelDelta { side: Side::Sell, px: 100.01, qty: 0.0 },
+            LevelDelta { side: Side::Sell, px: 100.03, qty: 6.0 },
+        ])
+        .unwrap();
+        assert_eq!(book.best_bid().unwrap().px, 100.00);
+        assert_eq!(book.best_ask().unwrap(), Level { px: 100.03, qty: 6.0, orders: 0 });
+        assert_eq!(book.levels(Side::Buy).count(), 4);
+        assert_eq!(book.seq(), Some(11));
+
+        // A missed update is reported and nothing is applied
+        let gap = book.apply_deltas(13, &[LevelDelta { side: Side::Buy, px: 100.02, qty: 1.0 }]);
+        assert_eq!(gap, Err(BookError::SequenceGap { expected: 12, received: 13 }));
+        assert_eq!(book.best_bid().unwrap().px, 100.00);
+        assert!(book.apply_deltas(12, &[LevelDelta { side: Side::Buy, px: -1.0, qty: 1.0 }]).is_err());
+    }
+
+    #[test]
+    fn test_l3_orders_aggregate_into_levels() {
+        let mut book = OrderBook::new(0.5);
+        book.add_order(1, Side::Buy, 100.0, 3.0).unwrap();
+        book.add_order(2, Side::Buy, 100.0, 2.0).unwrap();
+        book.add_order(3, Side::Buy, 99.5, 7.0).unwrap();
+        book.add_order(4, Side::Sell, 101.0, 1.0).unwrap();
+        assert_eq!(book.add_order(4, Side::Sell, 101.0, 1.0), Err(BookError::DuplicateOrder(4)));
+        assert_eq!(book.best_bid().unwrap(), Level { px: 100.0, qty: 5.0, orders: 2 });
+
+        book.modify_order(1, 1.0).unwrap();
+        assert_eq!(book.best_bid().unwrap().qty, 3.0);
+        book.cancel_order(1).unwrap();
+        book.cancel_order(2).unwrap();
+        assert_eq!(book.best_bid().unwrap(), Level { px: 99.5, qty: 7.0, orders: 1 });
+        // Filling an order completely removes it
+        book.modify_order(4, 0.0).unwrap();
+        assert!(book.best_ask().is_none());
+        assert_eq!(book.cancel_order(4), Err(BookError::UnknownOrder(4)));
+    }
+
+    #[test]
+    fn test_derived_metrics() {
+        let book = book();
+        assert!((book.mid().unwrap() - 100.0).abs() < 1e-9);
+        assert!((book.spread().unwrap() - 0.02).abs() < 1e-9);
+        assert!((book.spread_bps().unwrap() - 2.0).abs() < 1e-6);
+
+        // Within 3 bps of 100 are 99.97..100.03
+        assert_eq!(book.depth_within_bps(Side::Buy, 3.0), 15.0);
+        assert_eq!(book.depth_within_bps(Side::Sell, 3.0), 12.0);
+        assert_eq!(book.depth_within_bps(Side::Sell, 100.0), 62.0);
+
+        let imbalance = book.imbalance(1).unwrap();
+        assert!((imbalance - 1.0 / 9.0).abs() < 1e-9);
+        assert!(OrderBook::new(0.01).imbalance(5).is_none());
+
+        let quote = book.quote().unwrap();
+        assert_eq!((quote.bid, quote.ask), (99.99, 100.01));
+    }
+
+    #[test]
+    fn test_checksum() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+
+        let mut book = OrderBook::new(0.1);
+        book.apply_snapshot(1, &[(3366.1, 7.0), (3366.0, 6.0)], &[(3366.8, 9.0), (3368.0, 8.0), (3372.0, 8.0)])
+            .unwrap();
+        let expected = crc32(b"3366.1:7:3366.8:9:3366:6:3368:8:3372:8");
+        assert_eq!(book.checksum(CHECKSUM_DEPTH), expected);
+        assert!(book.verify_checksum(expected, CHECKSUM_DEPTH).is_ok());
+
+        book.apply_deltas(2, &[LevelDelta { side: Side::Buy, px: 3366.0, qty: 5.0 }]).unwrap();
+        assert!(matches!(
+            book.verify_checksum(expected, CHECKSUM_DEPTH),
+            Err(BookError::ChecksumMismatch { .. })
+        ));
+    }
+}
@@ -1,3 +1,3 @@
-pub use crate::{config::Cfg, models::*, enhanced_risk::EnhancedRisk, enhanced_mm::EnhancedMarketMaking, enhanced_arb::{EnhancedArbitrage, ArbitrageType}, monitoring::PerformanceMonitor};
+pub use crate::{config::Cfg, models::*, enhanced_risk::EnhancedRisk, enhanced_mm::EnhancedMarketMaking, enhanced_arb::{EnhancedArbitrage, ArbitrageType}, monitoring::PerformanceMonitor, order_book::{OrderBook, LevelDelta}};
 pub use tokio::sync::mpsc;
 pub use tracing::{info, warn, debug};
\ No newline at end of file
@@ -83,6 +83,7 @@ fn create_test_plan() -> TradePlan {
             trailing_pct: Some(2.0),
         },
         idem_key: "benchmark-key".to_string(),
+        quote: None,
     }
 }
 
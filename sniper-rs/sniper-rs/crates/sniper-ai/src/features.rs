@@ -0,0 +1,92 @@
+//! Training rows and feature scaling.
+//!
+//! Rows are computed by a [`FeaturePipeline`] and labelled with the price
+//! return over the prediction horizon, so points still in the pipeline's
+//! warmup and the last `horizon` points have no row.
+
+use crate::pipeline::FeaturePipeline;
+use crate::MarketDataPoint;
+use serde::{Deserialize, Serialize};
+
+/// Feature rows labelled with their forward returns
+#[derive(Debug, Clone, Default)]
+pub struct Dataset {
+    pub rows: Vec<Vec<f64>>,
+    pub returns: Vec<f64>,
+    /// Timestamp of the point each row was built from
+    pub timestamps: Vec<u64>,
+}
+
+impl Dataset {
+    /// Label the rows of `data` with the return `horizon` points ahead
+    pub fn build(pipeline: &FeaturePipeline, data: &[MarketDataPoint], horizon: usize) -> Self {
+        Self::from_rows(&pipeline.transform(data), data, horizon)
+    }
+
+    /// Label rows already computed for `data`, one per point
+    pub fn from_rows(rows: &[Option<Vec<f64>>], data: &[MarketDataPoint], horizon: usize) -> Self {
+        let horizon = horizon.max(1);
+        let mut dataset = Dataset::default();
+        for (index, row) in rows.iter().enumerate().take(data.len().saturating_sub(horizon)) {
+            let Some(row) = row else {
+                continue;
+            };
+            let (price, future) = (data[index].price, data[index + horizon].price);
+            dataset.rows.push(row.clone());
+            dataset.returns.push(if price != 0.0 { future / price - 1.0 } else { 0.0 });
+            dataset.timestamps.push(data[index].timestamp);
+        }
+        dataset
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Rows in `range`, keeping their labels
+    pub fn slice(&self, range: std::ops::Range<usize>) -> Dataset {
+        Dataset {
+            rows: self.rows[range.clone()].to_vec(),
+            returns: self.returns[range.clone()].to_vec(),
+            timestamps: self.timestamps[range].to_vec(),
+        }
+    }
+}
+
+/// Per-feature standardisation fitted on training rows
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Scaler {
+    pub means: Vec<f64>,
+    pub stds: Vec<f64>,
+}
+
+impl Scaler {
+    /// Fit means and standard deviations; constant features keep a deviation of one
+    pub fn fit(rows: &[Vec<f64>]) -> Self {
+        let width = rows.first().map(Vec::len).unwrap_or(0);
+        let count = rows.len().max(1) as f64;
+        let means: Vec<f64> = (0..width).map(|column| rows.iter().map(|row| row[column]).sum::<f64>() / count).collect();
+        let stds = (0..width)
+            .map(|column| {
+                let variance = rows.iter().map(|row| (row[column] - means[column]).powi(2)).sum::<f64>() / count;
+                if variance > 0.0 {
+                    variance.sqrt()
+                } else {
+                    1.0
+                }
+            })
+            .collect();
+        Self { means, stds }
+    }
+
+    pub fn transform(&self, row: &[f64]) -> Vec<f64> {
+        row.iter()
+            .zip(self.means.iter().zip(&self.stds))
+            .map(|(value, (mean, std))| (value - mean) / std)
+            .collect()
+    }
+}
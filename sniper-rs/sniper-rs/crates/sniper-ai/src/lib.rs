@@ -2,6 +2,17 @@
 //! 
 //! This module provides machine learning-based trading strategies that can
 //! predict market movements and generate profitable trade plans.
+//! [`AiTradingStrategy::train`] fits logistic and ridge regressions over the
+//! features of a [`FeaturePipeline`], scored by walk-forward cross-validation,
+//! and the resulting [`TrainedModel`] is versioned and persisted with
+//! [`AiTradingStrategy::save_model`] and [`load_model`]. Feature rows are
+//! kept in a [`FeatureStore`] so training and inference read the same values.
+
+pub mod features;
+pub mod model;
+pub mod pipeline;
+pub mod store;
+pub mod training;
 
 use anyhow::Result;
 use async_trait::async_trait;
@@ -10,11 +21,19 @@ use serde_json::Value;
 use sniper_core::types::Signal;
 use sniper_plugin::{Strategy, PluginMetadata};
 use std::collections::HashMap;
+use std::path::Path;
+
+pub use features::{Dataset, Scaler};
+pub use model::{load_model, LinearModel, TrainedModel};
+pub use pipeline::{FeatureExpr, FeaturePipeline, Source, Transform};
+pub use store::FeatureStore;
+pub use training::{walk_forward, FittedModels, FoldReport, TrainingConfig, WalkForwardReport};
 
 /// AI model configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AiModelConfig {
     pub model_type: String, // "lstm", "transformer", "regression", etc.
+    /// Feature expressions, see [`pipeline`]
     pub features: Vec<String>,
     pub lookback_period: usize,
     pub prediction_horizon: usize,
@@ -36,7 +55,9 @@ pub struct AiTradingStrategy {
     metadata: PluginMetadata,
     config: AiModelConfig,
     model_weights: HashMap<String, f64>,
-    historical_data: Vec<MarketDataPoint>,
+    store: FeatureStore,
+    training: TrainingConfig,
+    model: Option<TrainedModel>,
 }
 
 /// Market data point for training/prediction
@@ -51,11 +72,25 @@ pub struct MarketDataPoint {
     pub rsi: f64,
     pub macd: f64,
     pub signal: Option<Signal>,
+    /// Taker buy and sell volume, for order-flow imbalance
+    #[serde(default)]
+    pub buy_volume: f64,
+    #[serde(default)]
+    pub sell_volume: f64,
 }
 
 impl AiTradingStrategy {
     /// Create a new AI trading strategy
+    ///
+    /// Invalid feature expressions fall back to price returns until the
+    /// strategy is trained, which reports them.
     pub fn new(config: AiModelConfig) -> Self {
+        let training = TrainingConfig::default();
+        let pipeline = FeaturePipeline::new(&config.features).unwrap_or_else(|e| {
+            tracing::warn!("Invalid AI features {:?}: {}", config.features, e);
+            FeaturePipeline::new(&["price".to_string()]).unwrap()
+        });
+        let store = FeatureStore::new(pipeline, config.lookback_period.max(training.max_history));
         Self {
             metadata: PluginMetadata {
                 id: "ai-trading-strategy".to_string(),
@@ -68,26 +103,84 @@ impl AiTradingStrategy {
             },
             config,
             model_weights: HashMap::new(),
-            historical_data: Vec::new(),
+            store,
+            training,
+            model: None,
         }
     }
     
+    /// Train with these hyperparameters
+    pub fn with_training(mut self, training: TrainingConfig) -> Self {
+        self.store.set_capacity(self.config.lookback_period.max(training.max_history));
+        self.training = training;
+        self
+    }
+    
+    /// Get the stored data points and their feature rows
+    pub fn feature_store(&self) -> &FeatureStore {
+        &self.store
+    }
+    
+    /// Get the trained model, if any
+    pub fn model(&self) -> Option<&TrainedModel> {
+        self.model.as_ref()
+    }
+    
+    /// Direction weight of each feature in the trained model
+    pub fn model_weights(&self) -> &HashMap<String, f64> {
+        &self.model_weights
+    }
+    
+    /// Write the trained model to `path`
+    pub fn save_model(&self, path: &Path) -> Result<()> {
+        let model = self.model.as_ref().ok_or_else(|| anyhow::anyhow!("No trained model to save"))?;
+        model.save(path)?;
+        tracing::info!("AI model v{} saved to {}", model.version, path.display());
+        Ok(())
+    }
+    
+    /// Replace the model with one saved at `path`
+    pub fn load_model(&mut self, path: &Path) -> Result<&TrainedModel> {
+        let model = load_model(path)?;
+        let pipeline = FeaturePipeline::new(&model.features)?;
+        tracing::info!("AI model v{} loaded from {}", model.version, path.display());
+        self.set_model(model, pipeline);
+        Ok(self.model.as_ref().unwrap())
+    }
+    
+    /// Serve `model`, computing rows with the pipeline it was trained with
+    fn set_model(&mut self, model: TrainedModel, pipeline: FeaturePipeline) {
+        self.store.set_pipeline(pipeline);
+        self.model_weights = model
+            .features
+            .iter()
+            .zip(&model.direction.weights)
+            .map(|(feature, weight)| (format!("{}_weight", feature), *weight))
+            .collect();
+        self.model = Some(model);
+    }
+    
     /// Add market data point for training/prediction
+    ///
+    /// The store keeps the lookback period for prediction and the training
+    /// history, and computes the point's feature row on arrival.
     pub fn add_data_point(&mut self, data_point: MarketDataPoint) {
-        self.historical_data.push(data_point);
-        
-        // Keep only the required lookback period
-        if self.historical_data.len() > self.config.lookback_period {
-            self.historical_data.remove(0);
-        }
+        self.store.push(data_point);
+    }
+    
+    /// Most recent points within the lookback period
+    fn lookback(&self) -> &[MarketDataPoint] {
+        let points = self.store.points();
+        &points[points.len().saturating_sub(self.config.lookback_period)..]
     }
     
     /// Generate market prediction using the AI model
+    ///
+    /// Until a model is trained or loaded, the prediction follows the
+    /// momentum over the lookback period.
     pub fn predict(&self) -> Result<MarketPrediction> {
-        // This is a simplified prediction logic
-        // In a real implementation, this would use a trained ML model
-        
-        if self.historical_data.is_empty() {
+        let history = self.lookback();
+        if history.is_empty() {
             return Ok(MarketPrediction {
                 confidence: 0.5,
                 predicted_direction: 0.0,
@@ -100,17 +193,33 @@ impl AiTradingStrategy {
             });
         }
         
-        // Simple momentum-based prediction
-        let latest_price = self.historical_data.last().unwrap().price;
-        let oldest_price = self.historical_data.first().unwrap().price;
-        let price_change = (latest_price - oldest_price) / oldest_price;
-        
         // Calculate simple volatility
-        let prices: Vec<f64> = self.historical_data.iter().map(|d| d.price).collect();
+        let prices: Vec<f64> = history.iter().map(|d| d.price).collect();
         let mean: f64 = prices.iter().sum::<f64>() / prices.len() as f64;
         let variance: f64 = prices.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / prices.len() as f64;
         let volatility = variance.sqrt();
         
+        if let Some(model) = &self.model {
+            if let Some(row) = self.store.latest() {
+                let (probability, predicted_return) = model.predict(row);
+                return Ok(MarketPrediction {
+                    confidence: probability.max(1.0 - probability),
+                    predicted_direction: 2.0 * probability - 1.0,
+                    predicted_volatility: volatility,
+                    predicted_return,
+                    timestamp: std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap()
+                        .as_secs(),
+                });
+            }
+        }
+        
+        // Simple momentum-based prediction
+        let latest_price = history.last().unwrap().price;
+        let oldest_price = history.first().unwrap().price;
+        let price_change = (latest_price - oldest_price) / oldest_price;
+        
         Ok(MarketPrediction {
             confidence: 0.7, // Simplified confidence calculation
             predicted_direction: price_change.signum(),
@@ -123,17 +232,50 @@ impl AiTradingStrategy {
         })
     }
     
-    /// Train the AI model (simplified implementation)
-    pub fn train(&mut self) -> Result<()> {
-        // In a real implementation, this would train an actual ML model
-        // For now, we'll just set some dummy weights
-        self.model_weights.insert("momentum_weight".to_string(), 0.3);
-        self.model_weights.insert("volatility_weight".to_string(), 0.2);
-        self.model_weights.insert("rsi_weight".to_string(), 0.25);
-        self.model_weights.insert("macd_weight".to_string(), 0.25);
+    /// Train a new model version on the historical data
+    ///
+    /// The model is scored by walk-forward cross-validation, then refitted
+    /// on every row before it replaces the current one. Without enough data
+    /// the current model, if any, is kept and `None` is returned.
+    pub fn train(&mut self) -> Result<Option<&TrainedModel>> {
+        let pipeline = FeaturePipeline::new(&self.config.features)?;
+        let dataset = if &pipeline == self.store.pipeline() {
+            self.store.dataset(self.config.prediction_horizon)
+        } else {
+            Dataset::build(&pipeline, self.store.points(), self.config.prediction_horizon)
+        };
+        let min_samples = self.training.min_samples.max(2);
+        if dataset.len() < min_samples {
+            tracing::warn!("AI model not trained: needs {} samples, have {}", min_samples, dataset.len());
+            return Ok(None);
+        }
         
-        tracing::info!("AI model trained with {} data points", self.historical_data.len());
-        Ok(())
+        let validation = walk_forward(&dataset, &self.training);
+        let fitted = FittedModels::fit(&dataset, &self.training);
+        let model = TrainedModel {
+            version: self.model.as_ref().map(|model| model.version + 1).unwrap_or(1),
+            model_type: self.config.model_type.clone(),
+            features: self.config.features.clone(),
+            prediction_horizon: self.config.prediction_horizon,
+            scaler: fitted.scaler,
+            direction: fitted.direction,
+            returns: fitted.returns,
+            trained_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            samples: dataset.len(),
+            data_start: dataset.timestamps[0],
+            data_end: dataset.timestamps[dataset.len() - 1],
+            validation,
+        };
+        
+        tracing::info!(
+            "AI model v{} trained on {} samples, walk-forward accuracy {:.3}",
+            model.version, model.samples, model.validation.mean_accuracy
+        );
+        self.set_model(model, pipeline);
+        Ok(self.model.as_ref())
     }
 }
 
@@ -223,6 +365,8 @@ mod tests {
                 rsi: 50.0,
                 macd: 0.0,
                 signal: None,
+                buy_volume: 0.0,
+                sell_volume: 0.0,
             });
         }
         
@@ -235,6 +379,62 @@ mod tests {
         Ok(())
     }
     
+    #[test]
+    fn test_train_save_and_load_model() -> Result<()> {
+        let config = AiModelConfig {
+            model_type: "logistic".to_string(),
+            features: vec!["price".to_string(), "momentum".to_string()],
+            lookback_period: 20,
+            prediction_horizon: 1,
+            confidence_threshold: 0.6,
+        };
+        let mut strategy = AiTradingStrategy::new(config.clone()).with_training(TrainingConfig {
+            folds: 4,
+            ..TrainingConfig::default()
+        });
+        assert!(strategy.train()?.is_none());
+        
+        // Momentum announces the next move: up two steps, down one
+        let mut price = 100.0;
+        for i in 0..120u64 {
+            let up_next = (i + 1) % 3 != 0;
+            strategy.add_data_point(MarketDataPoint {
+                timestamp: i * 60,
+                price,
+                volume: 1000.0,
+                liquidity: 50000.0,
+                volatility: 0.1,
+                momentum: if up_next { 1.0 } else { -1.0 },
+                rsi: 50.0,
+                macd: 0.0,
+                signal: None,
+                buy_volume: 0.0,
+                sell_volume: 0.0,
+            });
+            price *= if up_next { 1.01 } else { 0.99 };
+        }
+        
+        let model = strategy.train()?.unwrap();
+        assert_eq!((model.version, model.samples), (1, 118));
+        assert_eq!(model.validation.folds.len(), 4);
+        assert!(model.validation.mean_accuracy > 0.9);
+        assert!(strategy.model_weights()["momentum_weight"] > 0.0);
+        let prediction = strategy.predict()?;
+        assert!(prediction.predicted_direction < 0.0); // the last point announces a drop
+        assert!(prediction.confidence > 0.6);
+        assert_eq!(strategy.train()?.unwrap().version, 2);
+        
+        let path = std::env::temp_dir().join(format!("sniper-ai-model-{}.json", std::process::id()));
+        strategy.save_model(&path)?;
+        let mut restored = AiTradingStrategy::new(config);
+        assert!(restored.save_model(&path).is_err());
+        assert_eq!(restored.load_model(&path)?.version, 2);
+        assert_eq!(restored.model().unwrap().direction, strategy.model().unwrap().direction);
+        std::fs::remove_file(&path)?;
+        assert!(load_model(&path).is_err());
+        Ok(())
+    }
+    
     #[tokio::test]
     async fn test_ai_strategy_plan_generation() -> Result<()> {
         let config = AiModelConfig {
@@ -0,0 +1,192 @@
+//! Linear models and trained model persistence.
+//!
+//! Direction is predicted by an L2-regularised logistic regression fitted by
+//! gradient descent, and the size of the move by a ridge regression solved
+//! in closed form. A [`TrainedModel`] bundles both with the feature scaler
+//! and its walk-forward validation, and is saved to and loaded from JSON so
+//! a strategy can be retrained, versioned and restored.
+
+use crate::features::Scaler;
+use crate::training::WalkForwardReport;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Weights and bias of a linear model over standardised features
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LinearModel {
+    pub weights: Vec<f64>,
+    pub bias: f64,
+}
+
+impl LinearModel {
+    /// Weighted sum of a standardised row
+    pub fn score(&self, row: &[f64]) -> f64 {
+        self.bias + self.weights.iter().zip(row).map(|(weight, value)| weight * value).sum::<f64>()
+    }
+
+    /// Probability of the positive class under a logistic model
+    pub fn probability(&self, row: &[f64]) -> f64 {
+        1.0 / (1.0 + (-self.score(row)).exp())
+    }
+
+    /// Fit a logistic regression of `labels` (0 or 1) by batch gradient descent
+    pub fn fit_logistic(rows: &[Vec<f64>], labels: &[f64], learning_rate: f64, epochs: usize, l2: f64) -> Self {
+        let width = rows.first().map(Vec::len).unwrap_or(0);
+        let mut model = LinearModel {
+            weights: vec![0.0; width],
+            bias: 0.0,
+        };
+        if rows.is_empty() {
+            return model;
+        }
+        let count = rows.len() as f64;
+        for _ in 0..epochs {
+            let mut gradient = vec![0.0; width];
+            let mut bias_gradient = 0.0;
+            for (row, label) in rows.iter().zip(labels) {
+                let error = model.probability(row) - label;
+                for (gradient, value) in gradient.iter_mut().zip(row) {
+                    *gradient += error * value;
+                }
+                bias_gradient += error;
+            }
+            for (weight, gradient) in model.weights.iter_mut().zip(&gradient) {
+                *weight -= learning_rate * (gradient / count + l2 * *weight);
+            }
+            model.bias -= learning_rate * bias_gradient / count;
+        }
+        model
+    }
+
+    /// Fit a ridge regression of `targets`, leaving the bias unpenalised
+    pub fn fit_ridge(rows: &[Vec<f64>], targets: &[f64], l2: f64) -> Self {
+        let width = rows.first().map(Vec::len).unwrap_or(0);
+        if rows.is_empty() {
+            return LinearModel {
+                weights: vec![0.0; width],
+                bias: 0.0,
+            };
+        }
+        let count = rows.len() as f64;
+        let target_mean = targets.iter().sum::<f64>() / count;
+        let row_means: Vec<f64> = (0..width).map(|column| rows.iter().map(|row| row[column]).sum::<f64>() / count).collect();
+
+        // Normal equations on centred data: (XᵀX + λnI) w = Xᵀy
+        let mut gram = vec![vec![0.0; width]; width];
+        let mut moment = vec![0.0; width];
+        for (row, target) in rows.iter().zip(targets) {
+            let centred: Vec<f64> = row.iter().zip(&row_means).map(|(value, mean)| value - mean).collect();
+            for (i, x_i) in centred.iter().enumerate() {
+                moment[i] += x_i * (target - target_mean);
+                for (cell, x_j) in gram[i].iter_mut().zip(&centred) {
+                    *cell += x_i * x_j;
+                }
+            }
+        }
+        for (i, row) in gram.iter_mut().enumerate() {
+            row[i] += l2 * count;
+        }
+        let weights = solve(gram, moment).unwrap_or_else(|| vec![0.0; width]);
+        let bias = target_mean - weights.iter().zip(&row_means).map(|(weight, mean)| weight * mean).sum::<f64>();
+        LinearModel { weights, bias }
+    }
+}
+
+/// Solve `a x = b` by Gaussian elimination with partial pivoting
+fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    for column in 0..n {
+        let pivot = (column..n).max_by(|&x, &y| a[x][column].abs().total_cmp(&a[y][column].abs()))?;
+        if a[pivot][column].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(column, pivot);
+        b.swap(column, pivot);
+        for row in column + 1..n {
+            let factor = a[row][column] / a[column][column];
+            let (upper, lower) = a.split_at_mut(row);
+            for (cell, pivot) in lower[0][column..].iter_mut().zip(&upper[column][column..]) {
+                *cell -= factor * pivot;
+            }
+            b[row] -= factor * b[column];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+/// A trained direction and return model with its provenance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrainedModel {
+    /// Incremented every time the strategy is retrained
+    pub version: u32,
+    pub model_type: String,
+    pub features: Vec<String>,
+    pub prediction_horizon: usize,
+    pub scaler: Scaler,
+    /// Logistic model of the probability that the price rises over the horizon
+    pub direction: LinearModel,
+    /// Ridge model of the return over the horizon
+    pub returns: LinearModel,
+    pub trained_at: u64,
+    pub samples: usize,
+    /// Timestamps of the first and last training rows
+    pub data_start: u64,
+    pub data_end: u64,
+    pub validation: WalkForwardReport,
+}
+
+impl TrainedModel {
+    /// Probability of a rise and predicted return for a raw feature row
+    pub fn predict(&self, row: &[f64]) -> (f64, f64) {
+        let row = self.scaler.transform(row);
+        (self.direction.probability(&row), self.returns.score(&row))
+    }
+
+    /// Write the model as JSON, creating parent directories
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Read a model saved with [`TrainedModel::save`]
+pub fn load_model(path: &Path) -> Result<TrainedModel> {
+    let model: TrainedModel = serde_json::from_slice(&std::fs::read(path)?)
+        .map_err(|e| anyhow::anyhow!("Invalid model file {}: {}", path.display(), e))?;
+    if model.scaler.means.len() != model.features.len() || model.direction.weights.len() != model.features.len() {
+        return Err(anyhow::anyhow!("Model file {} has weights that do not match its features", path.display()));
+    }
+    Ok(model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fits_linear_models() {
+        // y = 2a - b + 1
+        let rows: Vec<Vec<f64>> = (0..20).map(|i| vec![i as f64 / 10.0, ((i * 7) % 5) as f64 / 5.0]).collect();
+        let targets: Vec<f64> = rows.iter().map(|row| 2.0 * row[0] - row[1] + 1.0).collect();
+        let ridge = LinearModel::fit_ridge(&rows, &targets, 0.0);
+        assert!((ridge.weights[0] - 2.0).abs() < 1e-9);
+        assert!((ridge.weights[1] + 1.0).abs() < 1e-9);
+        assert!((ridge.bias - 1.0).abs() < 1e-9);
+
+        // Separable on the first feature
+        let labels: Vec<f64> = rows.iter().map(|row| if row[0] > 1.0 { 1.0 } else { 0.0 }).collect();
+        let logistic = LinearModel::fit_logistic(&rows, &labels, 0.5, 2_000, 0.0);
+        assert!(logistic.weights[0] > 0.0);
+        assert!(logistic.probability(&[1.8, 0.0]) > 0.9);
+        assert!(logistic.probability(&[0.2, 0.0]) < 0.1);
+    }
+}
@@ -0,0 +1,410 @@
+//! Declarative feature pipelines.
+//!
+//! Every entry of [`AiModelConfig::features`](crate::AiModelConfig) is a
+//! feature expression: a source series, optionally wrapped in transformers,
+//! such as `zscore(volume, 50)` or `rolling_vol(price, 20)`. Sources are
+//! `price`, `volume`, `liquidity`, `volatility`, `momentum`, `rsi` (recentred
+//! to [-1, 1]), `macd` and `imbalance`, the order-flow imbalance of buy and
+//! sell volume. Transformers are:
+//!
+//! - `returns(x, n)` and `log_returns(x, n)`: change over `n` points
+//! - `diff(x, n)` and `lag(x, n)`: difference with and value of the point `n` back
+//! - `mean(x, w)`: rolling mean over `w` points
+//! - `rolling_vol(x, w)`: standard deviation of one-point returns over `w` points
+//! - `zscore(x, w)`: distance from the rolling mean in rolling standard deviations
+//!
+//! Periods and windows default to 1 and 20. A bare `price`, `volume` or
+//! `liquidity` stands for its one-point returns, as the models were first
+//! trained on.
+//!
+//! The value of a feature at a point only depends on that point and the
+//! [`warmup`](FeaturePipeline::warmup) points before it. Training and
+//! inference both compute rows with the same pipeline over the same history,
+//! so a model is served exactly the features it was trained on.
+
+use crate::MarketDataPoint;
+use anyhow::Result;
+use std::fmt;
+
+/// Series read straight from market data points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Price,
+    Volume,
+    Liquidity,
+    Volatility,
+    Momentum,
+    Rsi,
+    Macd,
+    Imbalance,
+}
+
+impl Source {
+    const ALL: [(&'static str, Source); 8] = [
+        ("price", Source::Price),
+        ("volume", Source::Volume),
+        ("liquidity", Source::Liquidity),
+        ("volatility", Source::Volatility),
+        ("momentum", Source::Momentum),
+        ("rsi", Source::Rsi),
+        ("macd", Source::Macd),
+        ("imbalance", Source::Imbalance),
+    ];
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|(source, _)| *source == name).map(|(_, source)| *source)
+    }
+
+    fn name(self) -> &'static str {
+        Self::ALL.iter().find(|(_, source)| *source == self).map(|(name, _)| *name).unwrap()
+    }
+
+    fn value(self, point: &MarketDataPoint) -> f64 {
+        match self {
+            Source::Price => point.price,
+            Source::Volume => point.volume,
+            Source::Liquidity => point.liquidity,
+            Source::Volatility => point.volatility,
+            Source::Momentum => point.momentum,
+            Source::Rsi => (point.rsi - 50.0) / 50.0,
+            Source::Macd => point.macd,
+            Source::Imbalance => {
+                let total = point.buy_volume + point.sell_volume;
+                if total > 0.0 {
+                    (point.buy_volume - point.sell_volume) / total
+                } else {
+                    0.0
+                }
+            },
+        }
+    }
+}
+
+/// Transformer applied to an input series
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transform {
+    Returns,
+    LogReturns,
+    Diff,
+    Lag,
+    Mean,
+    RollingVol,
+    ZScore,
+}
+
+impl Transform {
+    const ALL: [(&'static str, Transform, usize); 7] = [
+        ("returns", Transform::Returns, 1),
+        ("log_returns", Transform::LogReturns, 1),
+        ("diff", Transform::Diff, 1),
+        ("lag", Transform::Lag, 1),
+        ("mean", Transform::Mean, 20),
+        ("rolling_vol", Transform::RollingVol, 20),
+        ("zscore", Transform::ZScore, 20),
+    ];
+
+    fn parse(name: &str) -> Option<(Self, usize)> {
+        Self::ALL
+            .iter()
+            .find(|(transform, _, _)| *transform == name)
+            .map(|(_, transform, default)| (*transform, *default))
+    }
+
+    fn name(self) -> &'static str {
+        Self::ALL.iter().find(|(_, transform, _)| *transform == self).map(|(name, _, _)| *name).unwrap()
+    }
+
+    /// Points before the current one the transformer reads
+    fn warmup(self, n: usize) -> usize {
+        match self {
+            Transform::Returns | Transform::LogReturns | Transform::Diff | Transform::Lag => n,
+            Transform::Mean | Transform::ZScore => n - 1,
+            Transform::RollingVol => n,
+        }
+    }
+
+    fn apply(self, n: usize, input: &[Option<f64>]) -> Vec<Option<f64>> {
+        (0..input.len())
+            .map(|index| {
+                let start = index.checked_sub(self.warmup(n))?;
+                let window = input[start..=index].iter().copied().collect::<Option<Vec<f64>>>()?;
+                let (first, last) = (window[0], window[window.len() - 1]);
+                match self {
+                    Transform::Returns => Some(if first != 0.0 { last / first - 1.0 } else { 0.0 }),
+                    Transform::LogReturns => (first > 0.0 && last > 0.0).then(|| (last / first).ln()),
+                    Transform::Diff => Some(last - first),
+                    Transform::Lag => Some(first),
+                    Transform::Mean => Some(mean(&window)),
+                    Transform::RollingVol => {
+                        let returns: Vec<f64> = window
+                            .windows(2)
+                            .map(|pair| if pair[0] != 0.0 { pair[1] / pair[0] - 1.0 } else { 0.0 })
+                            .collect();
+                        Some(std_dev(&returns))
+                    },
+                    Transform::ZScore => {
+                        let std = std_dev(&window);
+                        Some(if std > 0.0 { (last - mean(&window)) / std } else { 0.0 })
+                    },
+                }
+            })
+            .collect()
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len().max(1) as f64
+}
+
+fn std_dev(values: &[f64]) -> f64 {
+    let mean = mean(values);
+    (values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / values.len().max(1) as f64).sqrt()
+}
+
+/// A parsed feature expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeatureExpr {
+    Source(Source),
+    Transform {
+        transform: Transform,
+        input: Box<FeatureExpr>,
+        n: usize,
+    },
+}
+
+impl FeatureExpr {
+    /// Parse an expression, expanding a bare `price`, `volume` or `liquidity` to its returns
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut parser = Parser {
+            text,
+            chars: text.char_indices().peekable(),
+        };
+        let expr = parser.expr()?;
+        parser.skip_whitespace();
+        if let Some((at, _)) = parser.chars.peek() {
+            return Err(anyhow::anyhow!("Unexpected input at {} in feature {}", at, text));
+        }
+        Ok(match expr {
+            FeatureExpr::Source(source @ (Source::Price | Source::Volume | Source::Liquidity)) => FeatureExpr::Transform {
+                transform: Transform::Returns,
+                input: Box::new(FeatureExpr::Source(source)),
+                n: 1,
+            },
+            expr => expr,
+        })
+    }
+
+    /// Points before the current one the expression reads
+    pub fn warmup(&self) -> usize {
+        match self {
+            FeatureExpr::Source(_) => 0,
+            FeatureExpr::Transform { transform, input, n } => input.warmup() + transform.warmup(*n),
+        }
+    }
+
+    /// Value at every point, `None` until the warmup is covered
+    pub fn evaluate(&self, data: &[MarketDataPoint]) -> Vec<Option<f64>> {
+        match self {
+            FeatureExpr::Source(source) => data.iter().map(|point| Some(source.value(point))).collect(),
+            FeatureExpr::Transform { transform, input, n } => transform.apply(*n, &input.evaluate(data)),
+        }
+    }
+}
+
+impl fmt::Display for FeatureExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeatureExpr::Source(source) => write!(f, "{}", source.name()),
+            FeatureExpr::Transform { transform, input, n } => write!(f, "{}({}, {})", transform.name(), input, n),
+        }
+    }
+}
+
+/// Recursive-descent parser of feature expressions
+struct Parser<'a> {
+    text: &'a str,
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn word(&mut self) -> String {
+        self.skip_whitespace();
+        let mut word = String::new();
+        while let Some((_, c)) = self.chars.next_if(|(_, c)| c.is_ascii_alphanumeric() || *c == '_') {
+            word.push(c.to_ascii_lowercase());
+        }
+        word
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        self.chars.next_if(|(_, c)| *c == expected).is_some()
+    }
+
+    fn expr(&mut self) -> Result<FeatureExpr> {
+        let name = self.word();
+        if name.is_empty() {
+            return Err(anyhow::anyhow!("Expected a source or transformer in feature {}", self.text));
+        }
+        if !self.eat('(') {
+            return Source::parse(&name)
+                .map(FeatureExpr::Source)
+                .ok_or_else(|| anyhow::anyhow!("Unknown source {} in feature {}", name, self.text));
+        }
+
+        let (transform, default) = Transform::parse(&name)
+            .ok_or_else(|| anyhow::anyhow!("Unknown transformer {} in feature {}", name, self.text))?;
+        let input = self.expr()?;
+        let n = if self.eat(',') {
+            self.word()
+                .parse::<usize>()
+                .ok()
+                .filter(|n| *n > 0)
+                .ok_or_else(|| anyhow::anyhow!("{} needs a positive integer period in feature {}", name, self.text))?
+        } else {
+            default
+        };
+        if transform == Transform::RollingVol && n < 2 {
+            return Err(anyhow::anyhow!("rolling_vol needs a window of at least 2 in feature {}", self.text));
+        }
+        if !self.eat(')') {
+            return Err(anyhow::anyhow!("Expected ) after {} in feature {}", name, self.text));
+        }
+        Ok(FeatureExpr::Transform {
+            transform,
+            input: Box::new(input),
+            n,
+        })
+    }
+}
+
+/// Named feature expressions computed together into rows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeaturePipeline {
+    names: Vec<String>,
+    exprs: Vec<FeatureExpr>,
+}
+
+impl FeaturePipeline {
+    /// Parse a pipeline from feature expressions
+    pub fn new(features: &[String]) -> Result<Self> {
+        if features.is_empty() {
+            return Err(anyhow::anyhow!("At least one feature is required"));
+        }
+        Ok(Self {
+            names: features.to_vec(),
+            exprs: features.iter().map(|feature| FeatureExpr::parse(feature)).collect::<Result<_>>()?,
+        })
+    }
+
+    /// Feature expressions as configured
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Points before the current one needed to compute a row
+    pub fn warmup(&self) -> usize {
+        self.exprs.iter().map(FeatureExpr::warmup).max().unwrap_or(0)
+    }
+
+    /// Row at every point, `None` until every feature's warmup is covered
+    pub fn transform(&self, data: &[MarketDataPoint]) -> Vec<Option<Vec<f64>>> {
+        let columns: Vec<Vec<Option<f64>>> = self.exprs.iter().map(|expr| expr.evaluate(data)).collect();
+        (0..data.len())
+            .map(|index| columns.iter().map(|column| column[index]).collect())
+            .collect()
+    }
+
+    /// Row at the last point, computed from the warmup window only
+    pub fn latest(&self, data: &[MarketDataPoint]) -> Option<Vec<f64>> {
+        let start = data.len().checked_sub(self.warmup() + 1)?;
+        self.transform(&data[start..]).pop().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(price: f64, buy_volume: f64, sell_volume: f64) -> MarketDataPoint {
+        MarketDataPoint {
+            timestamp: 0,
+            price,
+            volume: buy_volume + sell_volume,
+            liquidity: 50_000.0,
+            volatility: 0.1,
+            momentum: 0.0,
+            rsi: 75.0,
+            macd: 0.0,
+            signal: None,
+            buy_volume,
+            sell_volume,
+        }
+    }
+
+    #[test]
+    fn test_parses_expressions() {
+        let expr = FeatureExpr::parse("zscore( returns(PRICE), 50 )").unwrap();
+        assert_eq!(expr.to_string(), "zscore(returns(price, 1), 50)");
+        assert_eq!(expr.warmup(), 50);
+        assert_eq!(FeatureExpr::parse("volume").unwrap().to_string(), "returns(volume, 1)");
+        assert_eq!(FeatureExpr::parse("rolling_vol(price)").unwrap().warmup(), 20);
+
+        for invalid in ["", "spread", "zscore(price", "zscore(price, 0)", "mean(price, x)", "rolling_vol(price, 1)", "price)"] {
+            assert!(FeatureExpr::parse(invalid).is_err(), "{}", invalid);
+        }
+        assert!(FeaturePipeline::new(&[]).is_err());
+    }
+
+    #[test]
+    fn test_transforms() {
+        let data: Vec<MarketDataPoint> = [100.0, 110.0, 99.0, 99.0]
+            .iter()
+            .map(|price| point(*price, 30.0, 10.0))
+            .collect();
+        let pipeline = FeaturePipeline::new(&[
+            "price".to_string(),
+            "log_returns(price, 2)".to_string(),
+            "lag(rsi, 1)".to_string(),
+            "imbalance".to_string(),
+            "mean(price, 3)".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(pipeline.warmup(), 2);
+
+        let rows = pipeline.transform(&data);
+        assert!(rows[0].is_none() && rows[1].is_none());
+        let row = rows[2].clone().unwrap();
+        assert!((row[0] + 0.1).abs() < 1e-12);
+        assert!((row[1] - (0.99f64).ln()).abs() < 1e-12);
+        assert_eq!((row[2], row[3]), (0.5, 0.5));
+        assert!((row[4] - 103.0).abs() < 1e-12);
+
+        let vol = FeatureExpr::parse("rolling_vol(price, 2)").unwrap().evaluate(&data);
+        assert!((vol[2].unwrap() - 0.1).abs() < 1e-12); // returns +10% and -10%
+        let zscore = FeatureExpr::parse("zscore(price, 2)").unwrap().evaluate(&data);
+        assert_eq!((zscore[1], zscore[3]), (Some(1.0), Some(0.0)));
+    }
+
+    #[test]
+    fn test_latest_row_matches_batch() {
+        let data: Vec<MarketDataPoint> = (0..40)
+            .map(|i| point(100.0 + ((i * 7) % 11) as f64, 10.0 + i as f64, 20.0))
+            .collect();
+        let pipeline = FeaturePipeline::new(&[
+            "zscore(returns(price), 10)".to_string(),
+            "rolling_vol(price, 5)".to_string(),
+            "diff(imbalance, 3)".to_string(),
+        ])
+        .unwrap();
+        let batch = pipeline.transform(&data);
+        for end in pipeline.warmup() + 1..=data.len() {
+            assert_eq!(pipeline.latest(&data[..end]), batch[end - 1]);
+        }
+        assert!(pipeline.latest(&data[..pipeline.warmup()]).is_none());
+    }
+}
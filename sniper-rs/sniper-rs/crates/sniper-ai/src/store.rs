@@ -0,0 +1,135 @@
+//! Feature store.
+//!
+//! The store keeps recent market data points alongside the feature row of
+//! each, computed once by its [`FeaturePipeline`] when the point arrives.
+//! Training reads the stored rows and inference reads the latest one, so both
+//! see the same values for the same point.
+
+use crate::features::Dataset;
+use crate::pipeline::FeaturePipeline;
+use crate::MarketDataPoint;
+
+/// Recent points and their feature rows
+#[derive(Debug, Clone)]
+pub struct FeatureStore {
+    pipeline: FeaturePipeline,
+    capacity: usize,
+    points: Vec<MarketDataPoint>,
+    rows: Vec<Option<Vec<f64>>>,
+}
+
+impl FeatureStore {
+    /// Create an empty store keeping at most `capacity` points
+    pub fn new(pipeline: FeaturePipeline, capacity: usize) -> Self {
+        Self {
+            pipeline,
+            capacity: capacity.max(1),
+            points: Vec::new(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Get the pipeline rows are computed with
+    pub fn pipeline(&self) -> &FeaturePipeline {
+        &self.pipeline
+    }
+
+    /// Keep at most `capacity` points, dropping the oldest
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        self.truncate();
+    }
+
+    /// Recompute every stored row with another pipeline
+    pub fn set_pipeline(&mut self, pipeline: FeaturePipeline) {
+        if pipeline != self.pipeline {
+            self.rows = pipeline.transform(&self.points);
+            self.pipeline = pipeline;
+        }
+    }
+
+    /// Store a point and compute its row, `None` while in the pipeline's warmup
+    pub fn push(&mut self, point: MarketDataPoint) -> Option<&[f64]> {
+        self.points.push(point);
+        let row = self.pipeline.latest(&self.points);
+        self.rows.push(row);
+        self.truncate();
+        self.rows.last()?.as_deref()
+    }
+
+    fn truncate(&mut self) {
+        if self.points.len() > self.capacity {
+            let excess = self.points.len() - self.capacity;
+            self.points.drain(..excess);
+            self.rows.drain(..excess);
+        }
+    }
+
+    /// Stored points, oldest first
+    pub fn points(&self) -> &[MarketDataPoint] {
+        &self.points
+    }
+
+    /// Row of each stored point, oldest first
+    pub fn rows(&self) -> &[Option<Vec<f64>>] {
+        &self.rows
+    }
+
+    /// Row of the most recent point
+    pub fn latest(&self) -> Option<&[f64]> {
+        self.rows.last()?.as_deref()
+    }
+
+    /// Stored rows labelled with the return `horizon` points ahead
+    pub fn dataset(&self, horizon: usize) -> Dataset {
+        Dataset::from_rows(&self.rows, &self.points, horizon)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(timestamp: u64, price: f64) -> MarketDataPoint {
+        MarketDataPoint {
+            timestamp,
+            price,
+            volume: 1_000.0,
+            liquidity: 50_000.0,
+            volatility: 0.1,
+            momentum: 0.0,
+            rsi: 50.0,
+            macd: 0.0,
+            signal: None,
+            buy_volume: 0.0,
+            sell_volume: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_rows_computed_once_match_batch() {
+        let pipeline = FeaturePipeline::new(&["zscore(price, 3)".to_string(), "returns(price, 2)".to_string()]).unwrap();
+        let mut store = FeatureStore::new(pipeline.clone(), 8);
+        let data: Vec<MarketDataPoint> = (0..12).map(|i| point(i, 100.0 + ((i * 5) % 7) as f64)).collect();
+        for point in &data {
+            store.push(point.clone());
+        }
+        assert_eq!(store.points().len(), 8);
+        assert_eq!(store.points()[0].timestamp, 4);
+
+        // Rows computed on arrival equal a batch over all the data, even once
+        // the points they were computed from have been dropped
+        let batch = pipeline.transform(&data);
+        assert_eq!(store.rows(), &batch[4..]);
+        assert_eq!(store.latest(), batch[11].as_deref());
+
+        let dataset = store.dataset(1);
+        assert_eq!(dataset.len(), 7);
+        assert_eq!(dataset.timestamps[0], 4);
+
+        // A new pipeline recomputes from the points still stored
+        store.set_pipeline(FeaturePipeline::new(&["price".to_string()]).unwrap());
+        assert!(store.rows()[0].is_none());
+        assert_eq!(store.rows()[1], Some(vec![data[5].price / data[4].price - 1.0]));
+    }
+}
@@ -0,0 +1,155 @@
+//! Training and walk-forward cross-validation.
+//!
+//! Walk-forward validation splits the rows into `folds + 1` consecutive
+//! blocks. Fold `k` trains on every block up to and including `k` and tests
+//! on block `k + 1`, so the model is only ever scored on data that comes
+//! after everything it was trained on.
+
+use crate::features::{Dataset, Scaler};
+use crate::model::LinearModel;
+use serde::{Deserialize, Serialize};
+
+/// Hyperparameters of the training loop
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrainingConfig {
+    pub learning_rate: f64,
+    pub epochs: usize,
+    /// L2 penalty of both models
+    pub l2: f64,
+    /// Walk-forward folds
+    pub folds: usize,
+    /// Rows required to train
+    pub min_samples: usize,
+    /// Data points kept for training, beyond the prediction lookback
+    pub max_history: usize,
+}
+
+impl Default for TrainingConfig {
+    fn default() -> Self {
+        Self {
+            learning_rate: 0.1,
+            epochs: 500,
+            l2: 0.01,
+            folds: 5,
+            min_samples: 20,
+            max_history: 10_000,
+        }
+    }
+}
+
+/// Out-of-sample scores of one walk-forward fold
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FoldReport {
+    pub train_rows: usize,
+    pub test_rows: usize,
+    /// Share of test rows whose direction was predicted correctly
+    pub accuracy: f64,
+    /// Mean squared error of the predicted returns
+    pub mse: f64,
+}
+
+/// Scores of every walk-forward fold
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WalkForwardReport {
+    pub folds: Vec<FoldReport>,
+    pub mean_accuracy: f64,
+    pub mean_mse: f64,
+}
+
+/// A scaler with the direction and return models fitted on the same rows
+#[derive(Debug, Clone)]
+pub struct FittedModels {
+    pub scaler: Scaler,
+    pub direction: LinearModel,
+    pub returns: LinearModel,
+}
+
+impl FittedModels {
+    /// Fit both models on standardised rows
+    pub fn fit(dataset: &Dataset, config: &TrainingConfig) -> Self {
+        let scaler = Scaler::fit(&dataset.rows);
+        let rows: Vec<Vec<f64>> = dataset.rows.iter().map(|row| scaler.transform(row)).collect();
+        let labels: Vec<f64> = dataset.returns.iter().map(|ret| if *ret > 0.0 { 1.0 } else { 0.0 }).collect();
+        Self {
+            direction: LinearModel::fit_logistic(&rows, &labels, config.learning_rate, config.epochs, config.l2),
+            returns: LinearModel::fit_ridge(&rows, &dataset.returns, config.l2),
+            scaler,
+        }
+    }
+
+    /// Score the models on rows they were not trained on
+    pub fn evaluate(&self, train_rows: usize, test: &Dataset) -> FoldReport {
+        let mut correct = 0;
+        let mut squared_error = 0.0;
+        for (row, ret) in test.rows.iter().zip(&test.returns) {
+            let row = self.scaler.transform(row);
+            if (self.direction.probability(&row) > 0.5) == (*ret > 0.0) {
+                correct += 1;
+            }
+            squared_error += (self.returns.score(&row) - ret).powi(2);
+        }
+        let count = test.len().max(1) as f64;
+        FoldReport {
+            train_rows,
+            test_rows: test.len(),
+            accuracy: correct as f64 / count,
+            mse: squared_error / count,
+        }
+    }
+}
+
+/// Train and score a model on each walk-forward fold
+pub fn walk_forward(dataset: &Dataset, config: &TrainingConfig) -> WalkForwardReport {
+    let folds = config.folds.max(1);
+    let block = dataset.len() / (folds + 1);
+    if block == 0 {
+        return WalkForwardReport::default();
+    }
+
+    let reports: Vec<FoldReport> = (0..folds)
+        .map(|fold| {
+            let train_end = block * (fold + 1);
+            // The last fold tests on every remaining row
+            let test_end = if fold + 1 == folds { dataset.len() } else { train_end + block };
+            let models = FittedModels::fit(&dataset.slice(0..train_end), config);
+            models.evaluate(train_end, &dataset.slice(train_end..test_end))
+        })
+        .collect();
+    let count = reports.len() as f64;
+    WalkForwardReport {
+        mean_accuracy: reports.iter().map(|fold| fold.accuracy).sum::<f64>() / count,
+        mean_mse: reports.iter().map(|fold| fold.mse).sum::<f64>() / count,
+        folds: reports,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_walk_forward_scores_later_blocks() {
+        // The forward return follows the sign of the single feature
+        let rows: Vec<Vec<f64>> = (0..60).map(|i| vec![if i % 3 == 0 { 1.0 } else { -1.0 }]).collect();
+        let dataset = Dataset {
+            returns: rows.iter().map(|row| row[0] * 0.01).collect(),
+            timestamps: (0..60).collect(),
+            rows,
+        };
+        let report = walk_forward(&dataset, &TrainingConfig {
+            folds: 3,
+            ..TrainingConfig::default()
+        });
+        assert_eq!(report.folds.len(), 3);
+        assert_eq!(
+            report.folds.iter().map(|fold| (fold.train_rows, fold.test_rows)).collect::<Vec<_>>(),
+            vec![(15, 15), (30, 15), (45, 15)]
+        );
+        assert_eq!(report.mean_accuracy, 1.0);
+        assert!(report.mean_mse < 1e-4);
+
+        let tiny = dataset.slice(0..3);
+        assert!(walk_forward(&tiny, &TrainingConfig::default()).folds.is_empty());
+    }
+}
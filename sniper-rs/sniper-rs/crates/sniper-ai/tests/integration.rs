@@ -49,6 +49,8 @@ async fn test_ai_strategy_data_handling() -> Result<()> {
             rsi: 50.0,
             macd: 0.0,
             signal: None,
+            buy_volume: 0.0,
+            sell_volume: 0.0,
         };
         
         strategy.add_data_point(data_point);
@@ -111,6 +113,8 @@ async fn test_ai_strategy_prediction_accuracy() -> Result<()> {
             rsi: 60.0,
             macd: 0.05,
             signal: None,
+            buy_volume: 0.0,
+            sell_volume: 0.0,
         });
     }
     
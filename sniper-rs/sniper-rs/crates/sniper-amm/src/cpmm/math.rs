@@ -1 +1,112 @@
-pub fn placeholder() { /* TODO: implement */ }
+//! Constant-product swap math.
+//!
+//! Follows the Uniswap V2 library: the fee is taken from the input, amounts
+//! round down on the way out and up on the way in, so quotes never promise
+//! more than the pool will pay. Products of reserves and amounts are carried
+//! in 256 bits so 18-decimal pools don't overflow.
+
+use crate::stableswap::math::mul_div;
+use anyhow::Result;
+
+/// Fee denominator (basis points)
+pub const FEE_DENOMINATOR: u128 = 10_000;
+
+/// Output amount for an exact input swap
+pub fn get_amount_out(amount_in: u128, reserve_in: u128, reserve_out: u128, fee_bps: u32) -> Result<u128> {
+    check_reserves(reserve_in, reserve_out, fee_bps)?;
+    if amount_in == 0 {
+        return Err(anyhow::anyhow!("Swap amount must be positive"));
+    }
+
+    let amount_in_with_fee = amount_in
+        .checked_mul(FEE_DENOMINATOR - fee_bps as u128)
+        .ok_or_else(|| anyhow::anyhow!("Swap amount overflow"))?;
+    let denominator = reserve_in
+        .checked_mul(FEE_DENOMINATOR)
+        .and_then(|reserve| reserve.checked_add(amount_in_with_fee))
+        .ok_or_else(|| anyhow::anyhow!("Swap amount overflow"))?;
+    mul_div(amount_in_with_fee, reserve_out, denominator)
+}
+
+/// Input amount needed for an exact output swap
+pub fn get_amount_in(amount_out: u128, reserve_in: u128, reserve_out: u128, fee_bps: u32) -> Result<u128> {
+    check_reserves(reserve_in, reserve_out, fee_bps)?;
+    if amount_out == 0 {
+        return Err(anyhow::anyhow!("Swap amount must be positive"));
+    }
+    if amount_out >= reserve_out {
+        return Err(anyhow::anyhow!(
+            "Output {} exceeds pool reserve {}",
+            amount_out, reserve_out
+        ));
+    }
+
+    let scaled_out = amount_out
+        .checked_mul(FEE_DENOMINATOR)
+        .ok_or_else(|| anyhow::anyhow!("Swap amount overflow"))?;
+    let denominator = (reserve_out - amount_out)
+        .checked_mul(FEE_DENOMINATOR - fee_bps as u128)
+        .ok_or_else(|| anyhow::anyhow!("Swap amount overflow"))?;
+    Ok(mul_div(reserve_in, scaled_out, denominator)? + 1)
+}
+
+/// Price impact of an exact input swap, in percent
+///
+/// The shortfall of the output against trading the post-fee input at the
+/// spot price, so it measures the curve alone and excludes the fee.
+pub fn price_impact_pct(amount_in: u128, reserve_in: u128, reserve_out: u128, fee_bps: u32) -> Result<f64> {
+    check_reserves(reserve_in, reserve_out, fee_bps)?;
+    let effective_in = amount_in as f64 * (FEE_DENOMINATOR - fee_bps as u128) as f64 / FEE_DENOMINATOR as f64;
+    Ok(effective_in / (reserve_in as f64 + effective_in) * 100.0)
+}
+
+fn check_reserves(reserve_in: u128, reserve_out: u128, fee_bps: u32) -> Result<()> {
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(anyhow::anyhow!("Pool has no liquidity"));
+    }
+    if fee_bps as u128 >= FEE_DENOMINATOR {
+        return Err(anyhow::anyhow!("Fee of {} bps is not below 100%", fee_bps));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_amount_out_matches_uniswap_v2() {
+        // 10_000 in against 1M/1M at 30 bps
+        assert_eq!(get_amount_out(10_000, 1_000_000, 1_000_000, 30).unwrap(), 9_871);
+        assert!(get_amount_out(10_000, 0, 1_000_000, 30).is_err());
+        assert!(get_amount_out(0, 1_000_000, 1_000_000, 30).is_err());
+    }
+
+    #[test]
+    fn test_amount_in_round_trips() {
+        let amount_in = get_amount_in(9_871, 1_000_000, 1_000_000, 30).unwrap();
+        assert!(amount_in <= 10_000);
+        assert!(get_amount_out(amount_in, 1_000_000, 1_000_000, 30).unwrap() >= 9_871);
+
+        // The whole reserve can never be bought
+        assert!(get_amount_in(1_000_000, 1_000_000, 1_000_000, 30).is_err());
+    }
+
+    #[test]
+    fn test_eighteen_decimal_pool() {
+        // 1,000 in against a 1M/1M pool of 18-decimal coins, 4 bps fee
+        const E18: u128 = 1_000_000_000_000_000_000;
+        let reserve = 1_000_000 * E18;
+        let amount_out = get_amount_out(1_000 * E18, reserve, reserve, 4).unwrap();
+        assert_eq!(amount_out, 998_601_797_643_075_981_249);
+        assert_eq!(get_amount_in(amount_out, reserve, reserve, 4).unwrap(), 1_000 * E18);
+    }
+
+    #[test]
+    fn test_price_impact_grows_with_size() {
+        let small = price_impact_pct(1_000, 1_000_000, 1_000_000, 0).unwrap();
+        let large = price_impact_pct(100_000, 1_000_000, 1_000_000, 0).unwrap();
+        assert!((small - 0.0999).abs() < 1e-3);
+        assert!((large - 9.0909).abs() < 1e-3);
+    }
+}
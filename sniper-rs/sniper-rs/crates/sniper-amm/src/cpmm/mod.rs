@@ -1 +1,10 @@
 //! Constant Product Market Maker (Uniswap V2 style) implementation
+//!
+//! [`math`] holds the reserve-based swap formulas; [`router`] keeps pair
+//! reserves and quotes single and multi-hop paths over them.
+
+pub mod math;
+pub mod router;
+
+pub use math::{get_amount_in, get_amount_out, price_impact_pct};
+pub use router::{CpmmRouter, PairReserves, PathQuote};
@@ -1 +1,236 @@
-pub fn placeholder() { /* TODO: implement */ }
+//! Path quoting over constant-product pairs.
+//!
+//! `CpmmRouter` holds the reserves of known pairs and quotes swaps along a
+//! token path hop by hop, the way the Uniswap V2 router's `getAmountsOut` and
+//! `getAmountsIn` do. `best_quote` also tries every two-hop path through a
+//! token paired with both ends and keeps the one paying out the most.
+
+use super::math::{get_amount_in, get_amount_out, price_impact_pct};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Reserves of one constant-product pair
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairReserves {
+    pub token0: String,
+    pub token1: String,
+    pub reserve0: u128,
+    pub reserve1: u128,
+    pub fee_bps: u32,
+}
+
+impl PairReserves {
+    /// Reserves ordered as (in, out) for a swap from `token_in`
+    pub fn oriented(&self, token_in: &str) -> Option<(u128, u128)> {
+        if token_in == self.token0 {
+            Some((self.reserve0, self.reserve1))
+        } else if token_in == self.token1 {
+            Some((self.reserve1, self.reserve0))
+        } else {
+            None
+        }
+    }
+}
+
+/// Quote for a swap along a token path
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathQuote {
+    /// Tokens visited, from input to output
+    pub path: Vec<String>,
+    /// Amount held after each hop, starting with the input
+    pub amounts: Vec<u128>,
+    /// Combined price impact of all hops, in percent
+    pub price_impact_pct: f64,
+}
+
+impl PathQuote {
+    /// Amount put into the first pair
+    pub fn amount_in(&self) -> u128 {
+        self.amounts.first().copied().unwrap_or(0)
+    }
+
+    /// Amount received from the last pair
+    pub fn amount_out(&self) -> u128 {
+        self.amounts.last().copied().unwrap_or(0)
+    }
+}
+
+/// Quotes swaps over known constant-product pairs
+#[derive(Debug, Clone, Default)]
+pub struct CpmmRouter {
+    pairs: HashMap<(String, String), PairReserves>,
+}
+
+impl CpmmRouter {
+    /// Create a router with no pairs
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or update the reserves of a pair
+    pub fn set_reserves(&mut self, token_a: &str, token_b: &str, reserve_a: u128, reserve_b: u128, fee_bps: u32) {
+        self.pairs.insert(
+            Self::pair_key(token_a, token_b),
+            PairReserves {
+                token0: token_a.to_string(),
+                token1: token_b.to_string(),
+                reserve0: reserve_a,
+                reserve1: reserve_b,
+                fee_bps,
+            },
+        );
+    }
+
+    /// Get the reserves of a pair
+    pub fn pair(&self, token_a: &str, token_b: &str) -> Option<&PairReserves> {
+        self.pairs.get(&Self::pair_key(token_a, token_b))
+    }
+
+    /// Known pairs
+    pub fn pairs(&self) -> impl Iterator<Item = &PairReserves> {
+        self.pairs.values()
+    }
+
+    /// Check if any pair trades a token
+    pub fn has_token(&self, token: &str) -> bool {
+        self.pairs.values().any(|pair| pair.token0 == token || pair.token1 == token)
+    }
+
+    /// Number of known pairs
+    pub fn pair_count(&self) -> usize {
+        self.pairs.len()
+    }
+
+    /// Quote an exact input swap along a path
+    pub fn quote_exact_in(&self, path: &[String], amount_in: u128) -> Result<PathQuote> {
+        let hops = self.hops(path)?;
+        let mut amounts = vec![amount_in];
+        let mut retained = 1.0;
+        for (token_in, pair) in &hops {
+            let (reserve_in, reserve_out) = pair.oriented(token_in).expect("hop pair holds its input token");
+            let amount = *amounts.last().expect("amounts starts with the input");
+            retained *= 1.0 - price_impact_pct(amount, reserve_in, reserve_out, pair.fee_bps)? / 100.0;
+            amounts.push(get_amount_out(amount, reserve_in, reserve_out, pair.fee_bps)?);
+        }
+
+        Ok(PathQuote {
+            path: path.to_vec(),
+            amounts,
+            price_impact_pct: (1.0 - retained) * 100.0,
+        })
+    }
+
+    /// Quote an exact output swap along a path
+    pub fn quote_exact_out(&self, path: &[String], amount_out: u128) -> Result<PathQuote> {
+        let hops = self.hops(path)?;
+        let mut amounts = vec![amount_out];
+        for (token_in, pair) in hops.iter().rev() {
+            let (reserve_in, reserve_out) = pair.oriented(token_in).expect("hop pair holds its input token");
+            let amount = *amounts.last().expect("amounts starts with the output");
+            amounts.push(get_amount_in(amount, reserve_in, reserve_out, pair.fee_bps)?);
+        }
+        amounts.reverse();
+
+        // Impact is measured on the input the path actually needs
+        let mut quote = self.quote_exact_in(path, amounts[0])?;
+        quote.amounts = amounts;
+        Ok(quote)
+    }
+
+    /// Best exact input quote from `token_in` to `token_out`, direct or through one intermediate token
+    pub fn best_quote(&self, token_in: &str, token_out: &str, amount_in: u128) -> Result<PathQuote> {
+        let mut paths = vec![vec![token_in.to_string(), token_out.to_string()]];
+        for pair in self.pairs.values() {
+            for (from, via) in [(&pair.token0, &pair.token1), (&pair.token1, &pair.token0)] {
+                if from == token_in && via != token_out && self.pair(via, token_out).is_some() {
+                    paths.push(vec![token_in.to_string(), via.clone(), token_out.to_string()]);
+                }
+            }
+        }
+
+        paths
+            .iter()
+            .filter_map(|path| self.quote_exact_in(path, amount_in).ok())
+            .max_by_key(|quote| quote.amount_out())
+            .ok_or_else(|| anyhow::anyhow!("No CPMM route from {} to {}", token_in, token_out))
+    }
+
+    /// Pairs traversed by a path, with the token going into each
+    fn hops<'a>(&'a self, path: &'a [String]) -> Result<Vec<(&'a String, &'a PairReserves)>> {
+        if path.len() < 2 {
+            return Err(anyhow::anyhow!("Path needs at least two tokens"));
+        }
+        path.windows(2)
+            .map(|hop| {
+                self.pair(&hop[0], &hop[1])
+                    .map(|pair| (&hop[0], pair))
+                    .ok_or_else(|| anyhow::anyhow!("No CPMM pair for {} -> {}", hop[0], hop[1]))
+            })
+            .collect()
+    }
+
+    /// Order-independent key of a pair
+    fn pair_key(token_a: &str, token_b: &str) -> (String, String) {
+        if token_a <= token_b {
+            (token_a.to_string(), token_b.to_string())
+        } else {
+            (token_b.to_string(), token_a.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(tokens: &[&str]) -> Vec<String> {
+        tokens.iter().map(|token| token.to_string()).collect()
+    }
+
+    #[test]
+    fn test_direct_quote_either_direction() {
+        let mut router = CpmmRouter::new();
+        router.set_reserves("WETH", "USDC", 1_000_000, 2_000_000, 30);
+
+        let quote = router.quote_exact_in(&path(&["WETH", "USDC"]), 10_000).unwrap();
+        assert_eq!(quote.amount_out(), get_amount_out(10_000, 1_000_000, 2_000_000, 30).unwrap());
+        let reverse = router.quote_exact_in(&path(&["USDC", "WETH"]), 10_000).unwrap();
+        assert_eq!(reverse.amount_out(), get_amount_out(10_000, 2_000_000, 1_000_000, 30).unwrap());
+
+        assert!(router.quote_exact_in(&path(&["WETH", "DAI"]), 10_000).is_err());
+    }
+
+    #[test]
+    fn test_multi_hop_quotes() {
+        let mut router = CpmmRouter::new();
+        router.set_reserves("PEPE", "WETH", 1_000_000_000, 1_000_000, 30);
+        router.set_reserves("WETH", "USDC", 1_000_000, 2_000_000, 30);
+
+        let route = path(&["PEPE", "WETH", "USDC"]);
+        let quote = router.quote_exact_in(&route, 1_000_000).unwrap();
+        assert_eq!(quote.amounts.len(), 3);
+        let weth = get_amount_out(1_000_000, 1_000_000_000, 1_000_000, 30).unwrap();
+        assert_eq!(quote.amounts[1], weth);
+        assert_eq!(quote.amount_out(), get_amount_out(weth, 1_000_000, 2_000_000, 30).unwrap());
+        assert!(quote.price_impact_pct > 0.0);
+
+        // Exact output needs no more than the exact input quote spent
+        let exact_out = router.quote_exact_out(&route, quote.amount_out()).unwrap();
+        assert!(exact_out.amount_in() <= 1_000_000);
+        assert_eq!(exact_out.amount_out(), quote.amount_out());
+    }
+
+    #[test]
+    fn test_best_quote_prefers_deeper_route() {
+        let mut router = CpmmRouter::new();
+        // The direct pair is shallow; routing through WETH pays more
+        router.set_reserves("PEPE", "USDC", 10_000, 20, 30);
+        router.set_reserves("PEPE", "WETH", 1_000_000_000, 1_000_000, 30);
+        router.set_reserves("WETH", "USDC", 1_000_000, 2_000_000, 30);
+
+        let quote = router.best_quote("PEPE", "USDC", 1_000_000).unwrap();
+        assert_eq!(quote.path, path(&["PEPE", "WETH", "USDC"]));
+        assert!(router.best_quote("PEPE", "DAI", 1_000_000).is_err());
+    }
+}
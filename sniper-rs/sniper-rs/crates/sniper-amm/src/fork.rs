@@ -0,0 +1,410 @@
+//! In-memory fork of pool state for pre-trade simulation.
+//!
+//! A `PoolFork` snapshots constant-product reserves and concentrated
+//! liquidity ticks at a block. Swaps applied to the fork mutate that state, so
+//! a sequence of our swaps interleaved with anticipated competitor swaps can
+//! be replayed and checked against each step's minimum output before anything
+//! is sent on-chain.
+
+use crate::cpmm::math::get_amount_out;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Fee denominator for concentrated liquidity pools (hundredths of a bip)
+const PIPS: f64 = 1_000_000.0;
+
+/// Constant-product (Uniswap V2 style) pool state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpmmPool {
+    pub reserve0: u128,
+    pub reserve1: u128,
+    pub fee_bps: u32,
+}
+
+impl CpmmPool {
+    /// Output amount for an exact input swap
+    pub fn amount_out(&self, amount_in: u128, zero_for_one: bool) -> Result<u128> {
+        let (reserve_in, reserve_out) = if zero_for_one {
+            (self.reserve0, self.reserve1)
+        } else {
+            (self.reserve1, self.reserve0)
+        };
+        get_amount_out(amount_in, reserve_in, reserve_out, self.fee_bps)
+    }
+
+    /// Apply an exact input swap to the reserves
+    pub fn swap(&mut self, amount_in: u128, zero_for_one: bool) -> Result<u128> {
+        let amount_out = self.amount_out(amount_in, zero_for_one)?;
+        if zero_for_one {
+            self.reserve0 += amount_in;
+            self.reserve1 -= amount_out;
+        } else {
+            self.reserve1 += amount_in;
+            self.reserve0 -= amount_out;
+        }
+        Ok(amount_out)
+    }
+
+    /// Spot price of token0 in token1
+    pub fn price(&self) -> f64 {
+        if self.reserve0 == 0 {
+            return 0.0;
+        }
+        self.reserve1 as f64 / self.reserve0 as f64
+    }
+}
+
+/// Concentrated liquidity (Uniswap V3 style) pool state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConcentratedPool {
+    /// Square root of the price of token0 in token1
+    pub sqrt_price: f64,
+    /// Liquidity active at the current price
+    pub liquidity: f64,
+    /// Net liquidity added when crossing each initialized tick upwards
+    pub ticks: BTreeMap<i32, f64>,
+    pub fee_pips: u32,
+}
+
+impl ConcentratedPool {
+    /// Square root price at a tick
+    pub fn tick_sqrt_price(tick: i32) -> f64 {
+        1.0001f64.powf(tick as f64 / 2.0)
+    }
+
+    /// Spot price of token0 in token1
+    pub fn price(&self) -> f64 {
+        self.sqrt_price * self.sqrt_price
+    }
+
+    /// Output amount for an exact input swap
+    pub fn amount_out(&self, amount_in: u128, zero_for_one: bool) -> Result<u128> {
+        self.clone().swap(amount_in, zero_for_one)
+    }
+
+    /// Apply an exact input swap, crossing initialized ticks as needed
+    pub fn swap(&mut self, amount_in: u128, zero_for_one: bool) -> Result<u128> {
+        let mut remaining = amount_in as f64 * (1.0 - self.fee_pips as f64 / PIPS);
+        let mut amount_out = 0.0;
+
+        while remaining > 0.0 {
+            // Next initialized tick in the swap direction
+            let next_tick = if zero_for_one {
+                self.ticks
+                    .iter()
+                    .rev()
+                    .find(|(tick, _)| Self::tick_sqrt_price(**tick) < self.sqrt_price)
+                    .map(|(tick, net)| (*tick, *net))
+            } else {
+                self.ticks
+                    .iter()
+                    .find(|(tick, _)| Self::tick_sqrt_price(**tick) > self.sqrt_price)
+                    .map(|(tick, net)| (*tick, *net))
+            };
+
+            if self.liquidity <= 0.0 {
+                match next_tick {
+                    Some((tick, net)) => {
+                        self.cross(tick, net, zero_for_one);
+                        continue;
+                    },
+                    None => break,
+                }
+            }
+
+            let l = self.liquidity;
+            let s = self.sqrt_price;
+            let target = next_tick.map(|(tick, _)| Self::tick_sqrt_price(tick));
+
+            // Input needed to reach the next tick: token0 in moves 1/sqrt(P) up by
+            // amount / L, token1 in moves sqrt(P) up by amount / L
+            let needed = match target {
+                Some(t) if zero_for_one => l * (1.0 / t - 1.0 / s),
+                Some(t) => l * (t - s),
+                None => f64::INFINITY,
+            };
+
+            match next_tick {
+                Some((tick, net)) if remaining >= needed => {
+                    let t = Self::tick_sqrt_price(tick);
+                    amount_out += if zero_for_one { l * (s - t) } else { l * (1.0 / s - 1.0 / t) };
+                    remaining -= needed;
+                    self.cross(tick, net, zero_for_one);
+                },
+                _ => {
+                    let new_s = if zero_for_one {
+                        1.0 / (1.0 / s + remaining / l)
+                    } else {
+                        s + remaining / l
+                    };
+                    amount_out += if zero_for_one { l * (s - new_s) } else { l * (1.0 / s - 1.0 / new_s) };
+                    self.sqrt_price = new_s;
+                    remaining = 0.0;
+                },
+            }
+        }
+
+        if remaining > 0.0 {
+            return Err(anyhow::anyhow!("Insufficient liquidity to fill swap"));
+        }
+        Ok(amount_out as u128)
+    }
+
+    /// Cross an initialized tick, updating active liquidity
+    fn cross(&mut self, tick: i32, liquidity_net: f64, zero_for_one: bool) {
+        self.sqrt_price = Self::tick_sqrt_price(tick);
+        if zero_for_one {
+            self.liquidity -= liquidity_net;
+        } else {
+            self.liquidity += liquidity_net;
+        }
+        // Nudge past the tick so it is not crossed again
+        self.sqrt_price *= if zero_for_one { 1.0 - 1e-12 } else { 1.0 + 1e-12 };
+    }
+}
+
+/// Forked state of one pool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PoolState {
+    ConstantProduct(CpmmPool),
+    Concentrated(ConcentratedPool),
+}
+
+impl PoolState {
+    /// Output amount for an exact input swap
+    pub fn amount_out(&self, amount_in: u128, zero_for_one: bool) -> Result<u128> {
+        match self {
+            PoolState::ConstantProduct(pool) => pool.amount_out(amount_in, zero_for_one),
+            PoolState::Concentrated(pool) => pool.amount_out(amount_in, zero_for_one),
+        }
+    }
+
+    /// Apply an exact input swap
+    pub fn swap(&mut self, amount_in: u128, zero_for_one: bool) -> Result<u128> {
+        match self {
+            PoolState::ConstantProduct(pool) => pool.swap(amount_in, zero_for_one),
+            PoolState::Concentrated(pool) => pool.swap(amount_in, zero_for_one),
+        }
+    }
+
+    /// Spot price of token0 in token1
+    pub fn price(&self) -> f64 {
+        match self {
+            PoolState::ConstantProduct(pool) => pool.price(),
+            PoolState::Concentrated(pool) => pool.price(),
+        }
+    }
+}
+
+/// Who submits a simulated swap
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SwapActor {
+    Ours,
+    Competitor(String),
+}
+
+/// A swap to replay against the fork
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimSwap {
+    pub actor: SwapActor,
+    pub pool: String,
+    pub zero_for_one: bool,
+    pub amount_in: u128,
+    pub min_out: Option<u128>,
+}
+
+/// Outcome of one simulated swap
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimStep {
+    pub actor: SwapActor,
+    pub pool: String,
+    pub amount_in: u128,
+    pub amount_out: u128,
+    pub price_before: f64,
+    pub price_after: f64,
+}
+
+/// Outcome of a simulated swap sequence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationReport {
+    pub block: u64,
+    pub steps: Vec<SimStep>,
+}
+
+impl SimulationReport {
+    /// Total output of our swaps
+    pub fn our_output(&self) -> u128 {
+        self.steps
+            .iter()
+            .filter(|step| step.actor == SwapActor::Ours)
+            .map(|step| step.amount_out)
+            .sum()
+    }
+}
+
+/// In-memory fork of pool state at a block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolFork {
+    block: u64,
+    pools: HashMap<String, PoolState>,
+}
+
+impl PoolFork {
+    /// Create an empty fork at a block
+    pub fn new(block: u64) -> Self {
+        Self {
+            block,
+            pools: HashMap::new(),
+        }
+    }
+
+    /// Block the fork was taken at
+    pub fn block(&self) -> u64 {
+        self.block
+    }
+
+    /// Add or replace a pool's state
+    pub fn insert_pool(&mut self, address: &str, state: PoolState) {
+        self.pools.insert(address.to_string(), state);
+    }
+
+    /// Get a pool's state
+    pub fn pool(&self, address: &str) -> Option<&PoolState> {
+        self.pools.get(address)
+    }
+
+    /// Quote a swap without mutating the fork
+    pub fn quote(&self, swap: &SimSwap) -> Result<u128> {
+        self.pools
+            .get(&swap.pool)
+            .ok_or_else(|| anyhow::anyhow!("Pool {} not in fork", swap.pool))?
+            .amount_out(swap.amount_in, swap.zero_for_one)
+    }
+
+    /// Apply a swap to the fork
+    pub fn apply(&mut self, swap: &SimSwap) -> Result<SimStep> {
+        let pool = self
+            .pools
+            .get_mut(&swap.pool)
+            .ok_or_else(|| anyhow::anyhow!("Pool {} not in fork", swap.pool))?;
+
+        let price_before = pool.price();
+        let amount_out = pool.swap(swap.amount_in, swap.zero_for_one)?;
+        Ok(SimStep {
+            actor: swap.actor.clone(),
+            pool: swap.pool.clone(),
+            amount_in: swap.amount_in,
+            amount_out,
+            price_before,
+            price_after: pool.price(),
+        })
+    }
+
+    /// Replay a sequence of swaps on a copy of the fork
+    ///
+    /// Fails if any of our swaps would receive less than its minimum output;
+    /// competitor swaps are applied as given.
+    pub fn simulate(&self, swaps: &[SimSwap]) -> Result<SimulationReport> {
+        let mut fork = self.clone();
+        let mut steps = Vec::with_capacity(swaps.len());
+
+        for (i, swap) in swaps.iter().enumerate() {
+            let step = fork.apply(swap)?;
+            if swap.actor == SwapActor::Ours {
+                if let Some(min_out) = swap.min_out {
+                    if step.amount_out < min_out {
+                        return Err(anyhow::anyhow!(
+                            "Step {} on {} returns {} below min_out {}",
+                            i, swap.pool, step.amount_out, min_out
+                        ));
+                    }
+                }
+            }
+            steps.push(step);
+        }
+
+        Ok(SimulationReport {
+            block: self.block,
+            steps,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn our_swap(pool: &str, amount_in: u128, min_out: Option<u128>) -> SimSwap {
+        SimSwap {
+            actor: SwapActor::Ours,
+            pool: pool.to_string(),
+            zero_for_one: true,
+            amount_in,
+            min_out,
+        }
+    }
+
+    #[test]
+    fn test_cpmm_swap() {
+        let mut pool = CpmmPool {
+            reserve0: 1_000_000,
+            reserve1: 1_000_000,
+            fee_bps: 30,
+        };
+        let out = pool.swap(10_000, true).unwrap();
+        assert_eq!(out, 9_871);
+        assert_eq!(pool.reserve0, 1_010_000);
+        assert_eq!(pool.reserve1, 1_000_000 - 9_871);
+    }
+
+    #[test]
+    fn test_competitor_front_run_fails_plan() {
+        let mut fork = PoolFork::new(100);
+        fork.insert_pool("0xPool", PoolState::ConstantProduct(CpmmPool {
+            reserve0: 1_000_000,
+            reserve1: 1_000_000,
+            fee_bps: 30,
+        }));
+
+        let alone = fork.simulate(&[our_swap("0xPool", 10_000, Some(9_800))]).unwrap();
+        assert_eq!(alone.our_output(), 9_871);
+
+        let front_run = SimSwap {
+            actor: SwapActor::Competitor("searcher".to_string()),
+            ..our_swap("0xPool", 50_000, None)
+        };
+        assert!(fork
+            .simulate(&[front_run, our_swap("0xPool", 10_000, Some(9_800))])
+            .is_err());
+
+        // Simulation does not touch the fork itself
+        match fork.pool("0xPool").unwrap() {
+            PoolState::ConstantProduct(pool) => assert_eq!(pool.reserve0, 1_000_000),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_concentrated_tick_crossing() {
+        let mut ticks = BTreeMap::new();
+        // Liquidity between ticks -100 and 100, thinner outside down to -1000
+        ticks.insert(-1000, 500_000.0);
+        ticks.insert(-100, 500_000.0);
+        ticks.insert(100, -1_000_000.0);
+        let mut pool = ConcentratedPool {
+            sqrt_price: 1.0,
+            liquidity: 1_000_000.0,
+            ticks,
+            fee_pips: 3000,
+        };
+
+        let small = pool.amount_out(1_000, true).unwrap();
+        assert!(small > 990 && small < 1_000);
+
+        // A large swap crosses tick -100 into thinner liquidity
+        pool.swap(20_000, true).unwrap();
+        assert!(pool.price() < ConcentratedPool::tick_sqrt_price(-100).powi(2));
+        assert!((pool.liquidity - 500_000.0).abs() < 1e-6);
+    }
+}
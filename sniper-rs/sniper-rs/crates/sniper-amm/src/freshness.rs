@@ -0,0 +1,82 @@
+//! Quote freshness SLA.
+//!
+//! Quotes carry the time and head block they were taken at. A quote is stale
+//! once it is older than `max_age_ms` or the chain head has advanced more than
+//! `max_block_lag` blocks past it; stale quotes must be refreshed before a
+//! trade is submitted.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sniper_core::types::QuoteStamp;
+
+/// Freshness bounds for quotes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuoteFreshness {
+    pub max_age_ms: i64,
+    pub max_block_lag: u64,
+}
+
+impl Default for QuoteFreshness {
+    fn default() -> Self {
+        Self {
+            max_age_ms: 3000,
+            max_block_lag: 1,
+        }
+    }
+}
+
+impl QuoteFreshness {
+    /// Check a quote against the freshness bounds
+    pub fn check(&self, quote: Option<&QuoteStamp>, now_ms: i64, head_block: u64) -> Result<()> {
+        let quote = quote.ok_or_else(|| anyhow::anyhow!("Trade plan has no quote"))?;
+
+        let age_ms = now_ms - quote.quoted_at_ms;
+        if age_ms > self.max_age_ms {
+            return Err(anyhow::anyhow!(
+                "Quote is {}ms old (max {}ms)",
+                age_ms, self.max_age_ms
+            ));
+        }
+
+        let block_lag = head_block.saturating_sub(quote.block);
+        if block_lag > self.max_block_lag {
+            return Err(anyhow::anyhow!(
+                "Head advanced {} blocks since quote (max {})",
+                block_lag, self.max_block_lag
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Current time as a Unix timestamp in milliseconds
+pub fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_freshness() {
+        let freshness = QuoteFreshness {
+            max_age_ms: 1000,
+            max_block_lag: 2,
+        };
+        let quote = QuoteStamp {
+            quoted_at_ms: 10_000,
+            block: 100,
+            expected_out: None,
+        };
+
+        assert!(freshness.check(Some(&quote), 10_500, 102).is_ok());
+        assert!(freshness.check(Some(&quote), 11_500, 100).is_err());
+        assert!(freshness.check(Some(&quote), 10_500, 103).is_err());
+        assert!(freshness.check(None, 10_000, 100).is_err());
+    }
+}
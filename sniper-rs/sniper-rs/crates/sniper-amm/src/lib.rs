@@ -1,13 +1,30 @@
 //! AMM (Automated Market Maker) module for the sniper bot.
 //! 
 //! This module provides functionality for interacting with various AMM protocols
-//! including Uniswap V2-style constant product markets, stableswap, and Uniswap V3.
+//! including Uniswap V2-style constant product markets, stableswap, and Uniswap V3,
+//! for searching multi-hop routes across their pools, and for splitting large
+//! orders across them. With a bus attached, every route cache lookup is
+//! published as a trading event so monitoring can track the cache hit rate.
 
 pub mod cpmm;
+pub mod routing;
+pub mod split;
 pub mod stableswap;
 pub mod univ3;
+pub mod slippage;
+pub mod freshness;
+pub mod fork;
 
-use sniper_core::types::{TradePlan, ExecReceipt};
+use sniper_core::bus::InMemoryBus;
+use sniper_core::events::TradingEvent;
+use sniper_core::types::{TradePlan, ExecReceipt, QuoteStamp};
+use slippage::SlippageModel;
+use freshness::{now_ms, QuoteFreshness};
+use fork::{PoolFork, SimSwap, SimulationReport};
+use cpmm::{CpmmRouter, PathQuote};
+use stableswap::{StablePool, StableSwapRouter};
+use routing::{Route, RouteSearch};
+use split::{SplitLeg, SplitOptimizer, SplitRoute};
 use anyhow::Result;
 use std::collections::HashMap;
 
@@ -25,35 +42,264 @@ pub trait AmmRouter {
 pub struct OptimizedPath {
     pub amm_type: String,
     pub router_address: String,
+    /// Tokens visited, from input to output
+    pub route: Vec<String>,
     pub expected_output: u128,
     pub price_impact: f64,
     pub gas_estimate: u64,
     pub execution_time_ms: u64,
+    pub quote: QuoteStamp,
 }
 
 /// Main AMM router that can route trades to different AMM protocols
 pub struct Router {
     // In a real implementation, this would contain connections to different AMMs
     path_cache: HashMap<String, OptimizedPath>,
+    slippage: SlippageModel,
+    freshness: QuoteFreshness,
+    head_block: u64,
+    fork: Option<PoolFork>,
+    cpmm: CpmmRouter,
+    stableswap: StableSwapRouter,
+    route_search: RouteSearch,
+    split: SplitOptimizer,
+    bus: Option<InMemoryBus>,
 }
 
 impl Router {
     /// Create a new router instance
     pub fn new() -> Self {
+        Self::with_slippage_model(SlippageModel::default())
+    }
+    
+    /// Create a new router instance with a slippage model
+    pub fn with_slippage_model(slippage: SlippageModel) -> Self {
         Self {
             path_cache: HashMap::new(),
+            slippage,
+            freshness: QuoteFreshness::default(),
+            head_block: 0,
+            fork: None,
+            cpmm: CpmmRouter::new(),
+            stableswap: StableSwapRouter::new(),
+            route_search: RouteSearch::default(),
+            split: SplitOptimizer::default(),
+            bus: None,
+        }
+    }
+    
+    /// Get the slippage model
+    pub fn slippage_model(&self) -> &SlippageModel {
+        &self.slippage
+    }
+    
+    /// Set the quote freshness bounds
+    pub fn set_quote_freshness(&mut self, freshness: QuoteFreshness) {
+        self.freshness = freshness;
+    }
+    
+    /// Publish route cache lookups on a bus
+    pub fn set_bus(&mut self, bus: InMemoryBus) {
+        self.bus = Some(bus);
+    }
+    
+    /// Publish a route cache lookup if a bus is attached
+    fn emit_cache_lookup(&self, plan: &TradePlan, hit: bool) {
+        if let Some(bus) = &self.bus {
+            let event = TradingEvent::RouteCacheLookup {
+                chain: plan.chain.name.clone(),
+                hit,
+                timestamp: (now_ms() / 1000) as u64,
+            };
+            if let Err(e) = bus.publish_now(event.subject(), &event) {
+                tracing::warn!("Failed to publish route cache event: {}", e);
+            }
+        }
+    }
+    
+    /// Update the latest known chain head
+    pub fn update_head_block(&mut self, block: u64) {
+        self.head_block = self.head_block.max(block);
+    }
+    
+    /// Get the latest known chain head
+    pub fn head_block(&self) -> u64 {
+        self.head_block
+    }
+    
+    /// Stamp a quote of `expected_out` taken now at the current head
+    fn quote_stamp(&self, expected_out: u128) -> QuoteStamp {
+        QuoteStamp {
+            quoted_at_ms: now_ms(),
+            block: self.head_block,
+            expected_out: Some(expected_out),
+        }
+    }
+    
+    /// Check that a plan's quote is within the freshness bounds
+    pub fn check_quote(&self, plan: &TradePlan) -> Result<()> {
+        self.freshness.check(plan.quote.as_ref(), now_ms(), self.head_block)
+    }
+    
+    /// Re-quote a plan, refreshing its quote and min_out
+    pub fn requote(&mut self, plan: &mut TradePlan) -> Result<OptimizedPath> {
+        let path = self.optimize_path(plan)?;
+        plan.min_out = self.calibrated_min_out(plan, &path);
+        plan.quote = Some(path.quote.clone());
+        Ok(path)
+    }
+    
+    /// Re-quote a plan if its quote is stale
+    ///
+    /// Returns true if the plan was re-quoted.
+    pub fn ensure_fresh_quote(&mut self, plan: &mut TradePlan) -> Result<bool> {
+        match self.check_quote(plan) {
+            Ok(()) => Ok(false),
+            Err(e) => {
+                tracing::info!("re-quoting plan {}: {}", plan.idem_key, e);
+                self.requote(plan)?;
+                Ok(true)
+            }
+        }
+    }
+    
+    /// Re-quote a plan if needed, then execute it
+    pub fn submit_trade(&mut self, plan: &mut TradePlan) -> Result<ExecReceipt> {
+        self.ensure_fresh_quote(plan)?;
+        self.execute_trade(plan)
+    }
+    
+    /// Add or update the reserves of a constant-product pair used for quoting
+    pub fn set_cpmm_reserves(&mut self, token_a: &str, token_b: &str, reserve_a: u128, reserve_b: u128, fee_bps: u32) {
+        self.cpmm.set_reserves(token_a, token_b, reserve_a, reserve_b, fee_bps);
+    }
+    
+    /// Get the constant-product pair reserves
+    pub fn cpmm(&self) -> &CpmmRouter {
+        &self.cpmm
+    }
+    
+    /// Quote a plan over the known constant-product pairs, if reserves for its tokens are supplied
+    pub fn cpmm_quote(&self, plan: &TradePlan) -> Option<Result<PathQuote>> {
+        if !self.cpmm.has_token(&plan.token_in) || !self.cpmm.has_token(&plan.token_out) {
+            return None;
+        }
+        Some(self.cpmm.best_quote(&plan.token_in, &plan.token_out, plan.amount_in))
+    }
+    
+    /// Add or update a StableSwap pool used for quoting
+    pub fn set_stable_pool(&mut self, pool: StablePool) {
+        self.stableswap.set_pool(pool);
+    }
+    
+    /// Get the StableSwap pools
+    pub fn stableswap(&self) -> &StableSwapRouter {
+        &self.stableswap
+    }
+    
+    /// Quote a plan over the known StableSwap pools, if one trades its tokens
+    pub fn stableswap_quote(&self, plan: &TradePlan) -> Option<Result<PathQuote>> {
+        if !self.stableswap.has_pair(&plan.token_in, &plan.token_out) {
+            return None;
+        }
+        Some(self.stableswap.best_quote(&plan.token_in, &plan.token_out, plan.amount_in))
+    }
+    
+    /// Limit the number of pools a searched route may trade through
+    pub fn set_max_hops(&mut self, max_hops: usize) {
+        self.route_search = RouteSearch::new(max_hops);
+        self.path_cache.clear();
+    }
+    
+    /// Best route for a plan across all known pools, if pools trade both of its tokens
+    pub fn best_route(&self, plan: &TradePlan) -> Option<Result<Route>> {
+        let known = |token: &str| self.cpmm.has_token(token) || self.stableswap.has_token(token);
+        if !known(&plan.token_in) || !known(&plan.token_out) {
+            return None;
+        }
+        Some(self.route_search.best_route(
+            &self.cpmm,
+            &self.stableswap,
+            &plan.token_in,
+            &plan.token_out,
+            plan.amount_in,
+        ))
+    }
+    
+    /// Set the number of chunks large orders are split in
+    pub fn set_split_parts(&mut self, parts: usize) {
+        self.split = SplitOptimizer::new(parts);
+    }
+    
+    /// Split a plan's input across the known pool families to maximize total output
+    ///
+    /// Constant-product and StableSwap pools are separate venues, each taking
+    /// its best route for the share it is given; venues left without a share
+    /// are dropped from the route.
+    pub fn split_route(&self, plan: &TradePlan) -> Result<SplitRoute> {
+        let no_cpmm = CpmmRouter::new();
+        let no_stableswap = StableSwapRouter::new();
+        let venues: Vec<(&CpmmRouter, &StableSwapRouter)> = [(&self.cpmm, &no_stableswap), (&no_cpmm, &self.stableswap)]
+            .into_iter()
+            .filter(|(cpmm, stableswap)| {
+                let known = |token: &str| cpmm.has_token(token) || stableswap.has_token(token);
+                known(&plan.token_in) && known(&plan.token_out)
+            })
+            .collect();
+        if venues.is_empty() {
+            return Err(anyhow::anyhow!("No pools trade {} and {}", plan.token_in, plan.token_out));
+        }
+        
+        let venue_route = |venue: usize, amount_in: u128| {
+            let (cpmm, stableswap) = venues[venue];
+            self.route_search.best_route(cpmm, stableswap, &plan.token_in, &plan.token_out, amount_in)
+        };
+        let allocation = self.split.allocate(plan.amount_in, venues.len(), |venue, amount_in| {
+            venue_route(venue, amount_in).ok().map(|route| route.amount_out())
+        })?;
+        
+        let mut legs = Vec::new();
+        for (venue, amount_in) in allocation.into_iter().enumerate().filter(|(_, amount_in)| *amount_in > 0) {
+            let route = venue_route(venue, amount_in)?;
+            legs.push(SplitLeg {
+                path: self.route_path(plan, &route),
+                amount_in,
+                share_pct: amount_in as f64 / plan.amount_in as f64 * 100.0,
+            });
+        }
+        Ok(SplitRoute::new(legs))
+    }
+    
+    /// Path executing a searched route for a plan
+    fn route_path(&self, plan: &TradePlan, route: &Route) -> OptimizedPath {
+        OptimizedPath {
+            amm_type: route.amm_type(),
+            router_address: plan.router.clone(),
+            route: route.path(),
+            expected_output: route.amount_out(),
+            price_impact: route.price_impact_pct(),
+            gas_estimate: route.gas_estimate(),
+            execution_time_ms: 200,
+            quote: self.quote_stamp(route.amount_out()),
         }
     }
     
     /// Get a quote for a trade
+    ///
+    /// Quotes the best route, direct or multi-hop, over the constant-product
+    /// reserves and StableSwap pools supplied for the plan's tokens; otherwise
+    /// the plan's own `min_out` is returned.
     pub fn get_quote(&self, plan: &TradePlan) -> Result<u128> {
-        // Placeholder implementation - in a real implementation, this would
-        // route to the appropriate AMM based on the plan and get a quote
-        Ok(plan.min_out)
+        match self.best_route(plan) {
+            Some(route) => Ok(route?.amount_out()),
+            None => Ok(plan.min_out),
+        }
     }
     
-    /// Execute a trade
+    /// Execute a trade, rejecting plans with a stale quote
     pub fn execute_trade(&self, plan: &TradePlan) -> Result<ExecReceipt> {
+        self.check_quote(plan)?;
+        
         // Placeholder implementation - in a real implementation, this would
         // route to the appropriate AMM and execute the trade
         Ok(ExecReceipt {
@@ -63,6 +309,8 @@ impl Router {
             gas_used: 100000,
             fees_paid_wei: 2100000000000000, // 0.0021 ETH
             failure_reason: None,
+            amount_out: None,
+            endpoint: None,
         })
     }
     
@@ -78,19 +326,28 @@ impl Router {
         let cache_key = format!("{}-{}-{}-{}", 
             plan.token_in, plan.token_out, plan.amount_in, plan.chain.id);
         
-        // Check cache first
+        // Check cache first, skipping quotes that are no longer fresh
         if let Some(cached_path) = self.path_cache.get(&cache_key) {
-            return Ok(cached_path.clone());
+            if self.freshness.check(Some(&cached_path.quote), now_ms(), self.head_block).is_ok() {
+                self.emit_cache_lookup(plan, true);
+                return Ok(cached_path.clone());
+            }
         }
+        self.emit_cache_lookup(plan, false);
         
-        // Simulate path optimization
-        let optimized_path = OptimizedPath {
-            amm_type: "CPMM".to_string(),
-            router_address: plan.router.clone(),
-            expected_output: plan.min_out,
-            price_impact: 0.5,
-            gas_estimate: 150000,
-            execution_time_ms: 200,
+        // Search routes over the known pools when supplied, else simulate path optimization
+        let optimized_path = match self.best_route(plan) {
+            Some(route) => self.route_path(plan, &route?),
+            None => OptimizedPath {
+                amm_type: "CPMM".to_string(),
+                router_address: plan.router.clone(),
+                route: vec![plan.token_in.clone(), plan.token_out.clone()],
+                expected_output: plan.min_out,
+                price_impact: 0.5,
+                gas_estimate: 150000,
+                execution_time_ms: 200,
+                quote: self.quote_stamp(plan.min_out),
+            },
         };
         
         // Cache the result
@@ -99,39 +356,106 @@ impl Router {
         Ok(optimized_path)
     }
     
-    /// Get multiple path options for comparison
+    /// Get multiple path options for comparison, best calibrated output first
     pub fn get_path_options(&self, plan: &TradePlan) -> Result<Vec<OptimizedPath>> {
         // In a real implementation, this would return multiple path options
-        let paths = vec![
+        let route = vec![plan.token_in.clone(), plan.token_out.clone()];
+        
+        // Price the StableSwap route off pool balances when supplied
+        let (stable_output, stable_impact) = match self.stableswap_quote(plan) {
+            Some(stable) => {
+                let stable = stable?;
+                (stable.amount_out(), stable.price_impact_pct)
+            },
+            None => (plan.min_out, 0.3),
+        };
+        let univ3_output = (plan.min_out as f64 * 0.98) as u128; // 2% worse
+        let mut paths = vec![
             OptimizedPath {
                 amm_type: "CPMM".to_string(),
                 router_address: plan.router.clone(),
+                route: route.clone(),
                 expected_output: plan.min_out,
                 price_impact: 0.5,
                 gas_estimate: 150000,
                 execution_time_ms: 200,
+                quote: self.quote_stamp(plan.min_out),
             },
             OptimizedPath {
                 amm_type: "StableSwap".to_string(),
                 router_address: "0xStableRouter".to_string(),
-                expected_output: (plan.min_out as f64 * 1.02) as u128, // 2% better
-                price_impact: 0.3,
+                route: route.clone(),
+                expected_output: stable_output,
+                price_impact: stable_impact,
                 gas_estimate: 180000,
                 execution_time_ms: 250,
+                quote: self.quote_stamp(stable_output),
             },
             OptimizedPath {
                 amm_type: "UniV3".to_string(),
                 router_address: "0xUniV3Router".to_string(),
-                expected_output: (plan.min_out as f64 * 0.98) as u128, // 2% worse
+                route,
+                expected_output: univ3_output,
                 price_impact: 0.7,
                 gas_estimate: 120000,
                 execution_time_ms: 150,
+                quote: self.quote_stamp(univ3_output),
             },
         ];
         
+        // Rank by output after the slippage each route has realized historically
+        paths.sort_by_key(|path| {
+            std::cmp::Reverse(self.slippage.adjusted_output(
+                &path.router_address,
+                plan.amount_in,
+                path.expected_output,
+            ))
+        });
+        
         Ok(paths)
     }
     
+    /// Minimum acceptable output for a path, using calibrated slippage
+    pub fn calibrated_min_out(&self, plan: &TradePlan, path: &OptimizedPath) -> u128 {
+        self.slippage.min_out(&path.router_address, plan.amount_in, path.expected_output)
+    }
+    
+    /// Feed an execution receipt back into the slippage model
+    pub fn record_fill(&mut self, plan: &TradePlan, path: &OptimizedPath, receipt: &ExecReceipt) {
+        self.slippage.record_receipt(&path.router_address, plan.amount_in, path.expected_output, receipt);
+    }
+    
+    /// Load a fork of pool state to simulate against
+    pub fn load_fork(&mut self, fork: PoolFork) {
+        self.update_head_block(fork.block());
+        self.fork = Some(fork);
+    }
+    
+    /// Get the loaded pool state fork
+    pub fn fork(&self) -> Option<&PoolFork> {
+        self.fork.as_ref()
+    }
+    
+    /// Get the loaded pool state fork for mutation, e.g. to apply pending swaps
+    pub fn fork_mut(&mut self) -> Option<&mut PoolFork> {
+        self.fork.as_mut()
+    }
+    
+    /// Validate a multi-step plan by replaying it, with anticipated competitor swaps, on the fork
+    pub fn simulate_plan(&self, swaps: &[SimSwap]) -> Result<SimulationReport> {
+        let fork = self.fork.as_ref().ok_or_else(|| anyhow::anyhow!("No pool state fork loaded"))?;
+        
+        let block_lag = self.head_block.saturating_sub(fork.block());
+        if block_lag > self.freshness.max_block_lag {
+            return Err(anyhow::anyhow!(
+                "Fork at block {} is {} blocks behind head (max {})",
+                fork.block(), block_lag, self.freshness.max_block_lag
+            ));
+        }
+        
+        fork.simulate(swaps)
+    }
+    
     /// Clear path cache
     pub fn clear_cache(&mut self) {
         self.path_cache.clear();
@@ -188,12 +512,190 @@ mod tests {
                 trailing_pct: Some(2.0),
             },
             idem_key: "test-key".to_string(),
+            quote: None,
+            correlation_id: None,
         };
         
         let quote = router.get_quote(&plan).unwrap();
         assert_eq!(quote, 900000000000000000);
     }
     
+    #[test]
+    fn test_get_quote_from_cpmm_reserves() {
+        let mut router = Router::new();
+        let mut plan = TradePlan {
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            router: "0xRouter".to_string(),
+            token_in: "0xTokenIn".to_string(),
+            token_out: "0xTokenOut".to_string(),
+            amount_in: 10_000,
+            min_out: 1,
+            mode: ExecMode::Mempool,
+            gas: GasPolicy {
+                max_fee_gwei: 50,
+                max_priority_gwei: 2,
+            },
+            exits: ExitRules {
+                take_profit_pct: Some(10.0),
+                stop_loss_pct: Some(5.0),
+                trailing_pct: Some(2.0),
+            },
+            idem_key: "test-key".to_string(),
+            quote: None,
+            correlation_id: None,
+        };
+        
+        router.set_cpmm_reserves("0xTokenIn", "0xTokenOut", 1_000_000, 1_000_000, 30);
+        assert_eq!(router.get_quote(&plan).unwrap(), 9_871);
+        let path = router.optimize_path(&plan).unwrap();
+        assert_eq!(path.expected_output, 9_871);
+        assert!((path.price_impact - 0.987).abs() < 1e-3);
+        
+        // Reserves that cannot fill the trade surface an error
+        plan.token_out = "0xOther".to_string();
+        router.set_cpmm_reserves("0xTokenIn", "0xWeth", 1_000_000, 1_000_000, 30);
+        router.set_cpmm_reserves("0xUsdc", "0xOther", 1_000_000, 1_000_000, 30);
+        assert!(router.get_quote(&plan).is_err());
+    }
+    
+    #[test]
+    fn test_get_quote_from_stable_pool() {
+        let mut router = Router::new();
+        let plan = TradePlan {
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            router: "0xRouter".to_string(),
+            token_in: "0xTokenIn".to_string(),
+            token_out: "0xTokenOut".to_string(),
+            amount_in: 100_000,
+            min_out: 1,
+            mode: ExecMode::Mempool,
+            gas: GasPolicy {
+                max_fee_gwei: 50,
+                max_priority_gwei: 2,
+            },
+            exits: ExitRules {
+                take_profit_pct: Some(10.0),
+                stop_loss_pct: Some(5.0),
+                trailing_pct: Some(2.0),
+            },
+            idem_key: "test-key".to_string(),
+            quote: None,
+            correlation_id: None,
+        };
+        
+        router.set_stable_pool(StablePool::new(&["0xTokenIn", "0xTokenOut"], &[1_000_000, 1_000_000], 100, 0).unwrap());
+        assert_eq!(router.get_quote(&plan).unwrap(), 99_900);
+        
+        // The better of the stable pool and constant-product reserves wins
+        router.set_cpmm_reserves("0xTokenIn", "0xTokenOut", 1_000_000, 1_000_000, 30);
+        assert_eq!(router.get_quote(&plan).unwrap(), 99_900);
+        
+        let paths = router.get_path_options(&plan).unwrap();
+        let stable = paths.iter().find(|path| path.amm_type == "StableSwap").unwrap();
+        assert_eq!(stable.expected_output, 99_900);
+        assert!(stable.price_impact < 0.2);
+    }
+    
+    #[test]
+    fn test_optimize_multi_hop_route() {
+        let mut router = Router::new();
+        let plan = TradePlan {
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            router: "0xRouter".to_string(),
+            token_in: "0xWeth".to_string(),
+            token_out: "0xToken".to_string(),
+            amount_in: 1_000,
+            min_out: 1,
+            mode: ExecMode::Mempool,
+            gas: GasPolicy {
+                max_fee_gwei: 50,
+                max_priority_gwei: 2,
+            },
+            exits: ExitRules {
+                take_profit_pct: Some(10.0),
+                stop_loss_pct: Some(5.0),
+                trailing_pct: Some(2.0),
+            },
+            idem_key: "test-key".to_string(),
+            quote: None,
+            correlation_id: None,
+        };
+        
+        // The direct pair is thin; the liquidity sits behind USDC
+        router.set_cpmm_reserves("0xWeth", "0xToken", 1_000, 1_000_000, 30);
+        router.set_cpmm_reserves("0xWeth", "0xUsdc", 1_000_000, 2_000_000_000, 30);
+        router.set_cpmm_reserves("0xUsdc", "0xToken", 2_000_000_000, 1_000_000_000, 30);
+        
+        let path = router.optimize_path(&plan).unwrap();
+        assert_eq!(path.route, vec!["0xWeth".to_string(), "0xUsdc".to_string(), "0xToken".to_string()]);
+        assert_eq!(path.amm_type, "CPMM");
+        assert_eq!(path.expected_output, 992_033);
+        assert_eq!(path.gas_estimate, 200000);
+        assert_eq!(router.get_quote(&plan).unwrap(), 992_033);
+        
+        // Limited to one hop, only the thin direct pair is left
+        router.set_max_hops(1);
+        let path = router.optimize_path(&plan).unwrap();
+        assert_eq!(path.route, vec!["0xWeth".to_string(), "0xToken".to_string()]);
+        assert_eq!(path.expected_output, 499_248);
+        assert_eq!(path.gas_estimate, 150000);
+    }
+    
+    #[test]
+    fn test_split_route_across_pool_families() {
+        let mut router = Router::new();
+        let plan = TradePlan {
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            router: "0xRouter".to_string(),
+            token_in: "0xUsdc".to_string(),
+            token_out: "0xUsdt".to_string(),
+            amount_in: 400_000,
+            min_out: 1,
+            mode: ExecMode::Mempool,
+            gas: GasPolicy {
+                max_fee_gwei: 50,
+                max_priority_gwei: 2,
+            },
+            exits: ExitRules {
+                take_profit_pct: Some(10.0),
+                stop_loss_pct: Some(5.0),
+                trailing_pct: Some(2.0),
+            },
+            idem_key: "test-key".to_string(),
+            quote: None,
+            correlation_id: None,
+        };
+        assert!(router.split_route(&plan).is_err());
+        
+        router.set_cpmm_reserves("0xUsdc", "0xUsdt", 1_000_000, 1_000_000, 30);
+        router.set_stable_pool(StablePool::new(&["0xUsdc", "0xUsdt"], &[1_000_000, 1_000_000], 10, 4).unwrap());
+        
+        let split = router.split_route(&plan).unwrap();
+        assert_eq!(split.len(), 2);
+        assert_eq!(split.amount_in, 400_000);
+        assert_eq!(split.iter().map(|leg| leg.amount_in).sum::<u128>(), 400_000);
+        assert!((split.iter().map(|leg| leg.share_pct).sum::<f64>() - 100.0).abs() < 1e-9);
+        assert_eq!(split.gas_estimate, 150000 + 180000);
+        
+        // Both venues beat sending the whole order to the better one
+        assert!(split.expected_output > router.get_quote(&plan).unwrap());
+        for leg in &split {
+            assert!(leg.path.expected_output > 0);
+        }
+    }
+    
     #[test]
     fn test_path_optimization() {
         let mut router = Router::new();
@@ -218,6 +720,8 @@ mod tests {
                 trailing_pct: Some(2.0),
             },
             idem_key: "test-key".to_string(),
+            quote: None,
+            correlation_id: None,
         };
         
         let optimized_path = router.optimize_path(&plan).unwrap();
@@ -253,6 +757,8 @@ mod tests {
                 trailing_pct: Some(2.0),
             },
             idem_key: "test-key".to_string(),
+            quote: None,
+            correlation_id: None,
         };
         
         let paths = router.get_path_options(&plan).unwrap();
@@ -265,9 +771,120 @@ mod tests {
         assert!(amm_types.contains(&"UniV3".to_string()));
     }
     
+    #[test]
+    fn test_calibrated_route_ranking() {
+        let mut router = Router::new();
+        let plan = TradePlan {
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            router: "0xRouter".to_string(),
+            token_in: "0xTokenIn".to_string(),
+            token_out: "0xTokenOut".to_string(),
+            amount_in: 1000000000000000000, // 1 ETH
+            min_out: 980000000000000000,    // 0.98 ETH worth of tokens
+            mode: ExecMode::Mempool,
+            gas: GasPolicy {
+                max_fee_gwei: 50,
+                max_priority_gwei: 2,
+            },
+            exits: ExitRules {
+                take_profit_pct: Some(10.0),
+                stop_loss_pct: Some(5.0),
+                trailing_pct: Some(2.0),
+            },
+            idem_key: "test-key".to_string(),
+            quote: None,
+            correlation_id: None,
+        };
+        
+        // A deep stable pool quotes about 2% above the CPMM route's min_out
+        router.set_stable_pool(
+            StablePool::new(
+                &["0xTokenIn", "0xTokenOut"],
+                &[1_000_000_000_000_000_000_000_000, 1_000_000_000_000_000_000_000_000],
+                100,
+                4,
+            ).unwrap(),
+        );
+        
+        // With uncalibrated slippage the best quote ranks first
+        let paths = router.get_path_options(&plan).unwrap();
+        assert_eq!(paths[0].amm_type, "StableSwap");
+        let stable = paths[0].clone();
+        
+        // StableSwap fills consistently come in 10% below quote
+        for _ in 0..5 {
+            let receipt = ExecReceipt {
+                tx_hash: "0xfill".to_string(),
+                success: true,
+                block: 12345678,
+                gas_used: 180000,
+                fees_paid_wei: 0,
+                failure_reason: None,
+                amount_out: Some((stable.expected_output as f64 * 0.9) as u128),
+                endpoint: None,
+            };
+            router.record_fill(&plan, &stable, &receipt);
+        }
+        
+        let paths = router.get_path_options(&plan).unwrap();
+        assert_eq!(paths[0].amm_type, "CPMM");
+        assert!(router.calibrated_min_out(&plan, &stable) <= (stable.expected_output as f64 * 0.9) as u128);
+    }
+    
+    #[test]
+    fn test_stale_quote_rejection() {
+        let mut router = Router::new();
+        router.update_head_block(100);
+        let mut plan = TradePlan {
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            router: "0xRouter".to_string(),
+            token_in: "0xTokenIn".to_string(),
+            token_out: "0xTokenOut".to_string(),
+            amount_in: 1000000000000000000, // 1 ETH
+            min_out: 900000000000000000,    // 0.9 ETH worth of tokens
+            mode: ExecMode::Mempool,
+            gas: GasPolicy {
+                max_fee_gwei: 50,
+                max_priority_gwei: 2,
+            },
+            exits: ExitRules {
+                take_profit_pct: Some(10.0),
+                stop_loss_pct: Some(5.0),
+                trailing_pct: Some(2.0),
+            },
+            idem_key: "test-key".to_string(),
+            quote: None,
+            correlation_id: None,
+        };
+        
+        // Unquoted plans are rejected until quoted
+        assert!(router.execute_trade(&plan).is_err());
+        assert!(router.ensure_fresh_quote(&mut plan).unwrap());
+        assert_eq!(plan.quote.as_ref().unwrap().block, 100);
+        assert!(router.execute_trade(&plan).is_ok());
+        
+        // The head moving past the lag bound makes the quote stale
+        router.update_head_block(105);
+        assert!(router.execute_trade(&plan).is_err());
+        
+        // Submitting re-quotes automatically
+        let receipt = router.submit_trade(&mut plan).unwrap();
+        assert!(receipt.success);
+        assert_eq!(plan.quote.as_ref().unwrap().block, 105);
+    }
+    
     #[test]
     fn test_cache_clearing() {
+        let bus = InMemoryBus::new(16);
+        let mut rx = bus.subscribe(sniper_core::events::ROUTE_CACHE_LOOKUP_SUBJECT);
         let mut router = Router::new();
+        router.set_bus(bus);
         let plan = TradePlan {
             chain: ChainRef {
                 name: "ethereum".to_string(),
@@ -289,11 +906,23 @@ mod tests {
                 trailing_pct: Some(2.0),
             },
             idem_key: "test-key".to_string(),
+            quote: None,
+            correlation_id: None,
         };
         
+        router.optimize_path(&plan).unwrap();
         router.optimize_path(&plan).unwrap();
         assert_eq!(router.cache_size(), 1);
         
+        // The first lookup misses and the second hits
+        let hits: Vec<bool> = std::iter::from_fn(|| rx.try_recv().ok())
+            .filter_map(|bytes| match TradingEvent::decode(&bytes) {
+                Some(TradingEvent::RouteCacheLookup { hit, .. }) => Some(hit),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(hits, vec![false, true]);
+        
         router.clear_cache();
         assert_eq!(router.cache_size(), 0);
     }
@@ -328,6 +957,8 @@ mod phase3_tests {
                 trailing_pct: Some(2.0),
             },
             idem_key: "test-key".to_string(),
+            quote: None,
+            correlation_id: None,
         };
         
         // Test path optimization
@@ -382,6 +1013,8 @@ mod phase3_tests {
                 trailing_pct: Some(2.0),
             },
             idem_key: "integration-test-key".to_string(),
+            quote: None,
+            correlation_id: None,
         };
         
         // 3. Optimize routing path
@@ -394,4 +1027,32 @@ mod phase3_tests {
         
         println!("Phase 3 integration tests passed!");
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_simulate_plan_on_fork() {
+        use fork::{CpmmPool, PoolState, SwapActor};
+        
+        let mut router = Router::new();
+        let swaps = vec![SimSwap {
+            actor: SwapActor::Ours,
+            pool: "0xPool".to_string(),
+            zero_for_one: true,
+            amount_in: 10_000,
+            min_out: Some(9_800),
+        }];
+        assert!(router.simulate_plan(&swaps).is_err());
+        
+        let mut fork = PoolFork::new(100);
+        fork.insert_pool("0xPool", PoolState::ConstantProduct(CpmmPool {
+            reserve0: 1_000_000,
+            reserve1: 1_000_000,
+            fee_bps: 30,
+        }));
+        router.load_fork(fork);
+        assert_eq!(router.simulate_plan(&swaps).unwrap().our_output(), 9_871);
+        
+        // A fork older than the freshness bound is rejected
+        router.update_head_block(105);
+        assert!(router.simulate_plan(&swaps).is_err());
+    }
+}
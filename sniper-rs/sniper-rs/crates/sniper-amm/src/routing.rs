@@ -0,0 +1,353 @@
+//! Multi-hop route search across pools.
+//!
+//! The known constant-product pairs and StableSwap pools form a token graph.
+//! `RouteSearch` walks every simple path of up to `max_hops` pools from the
+//! input token with a depth-first search, quoting each hop on what the
+//! previous hop pays out, and keeps the route paying out the most. Ties go to
+//! the route with fewer hops, since every hop costs gas.
+
+use crate::cpmm::math::{get_amount_out, price_impact_pct};
+use crate::cpmm::{CpmmRouter, PairReserves};
+use crate::stableswap::{StablePool, StableSwapRouter};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Longest route searched by default
+pub const DEFAULT_MAX_HOPS: usize = 3;
+
+/// Gas of a swap transaction before any pool is touched
+pub const BASE_SWAP_GAS: u64 = 100_000;
+
+/// Kind of pool a hop trades through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PoolKind {
+    Cpmm,
+    StableSwap,
+}
+
+impl PoolKind {
+    /// Name used for the AMM type of a path
+    pub fn name(&self) -> &'static str {
+        match self {
+            PoolKind::Cpmm => "CPMM",
+            PoolKind::StableSwap => "StableSwap",
+        }
+    }
+
+    /// Gas one swap through a pool of this kind adds
+    pub fn hop_gas(&self) -> u64 {
+        match self {
+            PoolKind::Cpmm => 50_000,
+            PoolKind::StableSwap => 80_000,
+        }
+    }
+}
+
+/// One swap along a route
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteHop {
+    pub kind: PoolKind,
+    pub token_in: String,
+    pub token_out: String,
+    pub amount_in: u128,
+    pub amount_out: u128,
+    /// Price impact of this hop, in percent
+    pub price_impact_pct: f64,
+}
+
+/// Swaps taking the input token to the output token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Route {
+    pub hops: Vec<RouteHop>,
+}
+
+impl Route {
+    /// Amount put into the first pool
+    pub fn amount_in(&self) -> u128 {
+        self.hops.first().map(|hop| hop.amount_in).unwrap_or(0)
+    }
+
+    /// Amount received from the last pool
+    pub fn amount_out(&self) -> u128 {
+        self.hops.last().map(|hop| hop.amount_out).unwrap_or(0)
+    }
+
+    /// Tokens visited, from input to output
+    pub fn path(&self) -> Vec<String> {
+        let mut path: Vec<String> = self.hops.iter().map(|hop| hop.token_in.clone()).collect();
+        if let Some(last) = self.hops.last() {
+            path.push(last.token_out.clone());
+        }
+        path
+    }
+
+    /// Combined price impact of all hops, in percent
+    pub fn price_impact_pct(&self) -> f64 {
+        let retained: f64 = self
+            .hops
+            .iter()
+            .map(|hop| 1.0 - hop.price_impact_pct / 100.0)
+            .product();
+        (1.0 - retained) * 100.0
+    }
+
+    /// Estimated gas of executing the route in one transaction
+    pub fn gas_estimate(&self) -> u64 {
+        BASE_SWAP_GAS + self.hops.iter().map(|hop| hop.kind.hop_gas()).sum::<u64>()
+    }
+
+    /// AMM types traded through, e.g. `CPMM` or `StableSwap+CPMM`
+    pub fn amm_type(&self) -> String {
+        let mut kinds: Vec<&str> = Vec::new();
+        for hop in &self.hops {
+            if !kinds.contains(&hop.kind.name()) {
+                kinds.push(hop.kind.name());
+            }
+        }
+        kinds.join("+")
+    }
+}
+
+/// Pool an edge of the token graph trades through
+#[derive(Clone, Copy)]
+enum PoolRef<'a> {
+    Cpmm(&'a PairReserves),
+    Stable(&'a StablePool),
+}
+
+#[derive(Clone, Copy)]
+struct Edge<'a> {
+    token_out: &'a str,
+    pool: PoolRef<'a>,
+}
+
+impl Edge<'_> {
+    fn quote(&self, token_in: &str, amount_in: u128) -> Result<RouteHop> {
+        let (kind, amount_out, price_impact_pct) = match self.pool {
+            PoolRef::Cpmm(pair) => {
+                let (reserve_in, reserve_out) = pair
+                    .oriented(token_in)
+                    .ok_or_else(|| anyhow::anyhow!("Pair does not trade {}", token_in))?;
+                (
+                    PoolKind::Cpmm,
+                    get_amount_out(amount_in, reserve_in, reserve_out, pair.fee_bps)?,
+                    price_impact_pct(amount_in, reserve_in, reserve_out, pair.fee_bps)?,
+                )
+            }
+            PoolRef::Stable(pool) => {
+                let quote = pool.quote(token_in, self.token_out, amount_in)?;
+                (PoolKind::StableSwap, quote.amount_out(), quote.price_impact_pct)
+            }
+        };
+        if amount_out == 0 {
+            return Err(anyhow::anyhow!("Swap of {} {} pays out nothing", amount_in, token_in));
+        }
+
+        Ok(RouteHop {
+            kind,
+            token_in: token_in.to_string(),
+            token_out: self.token_out.to_string(),
+            amount_in,
+            amount_out,
+            price_impact_pct,
+        })
+    }
+}
+
+/// Depth-limited search for the best route over known pools
+#[derive(Debug, Clone)]
+pub struct RouteSearch {
+    max_hops: usize,
+}
+
+impl Default for RouteSearch {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_HOPS)
+    }
+}
+
+impl RouteSearch {
+    /// Create a search over routes of at most `max_hops` pools
+    pub fn new(max_hops: usize) -> Self {
+        Self {
+            max_hops: max_hops.max(1),
+        }
+    }
+
+    /// Longest route searched
+    pub fn max_hops(&self) -> usize {
+        self.max_hops
+    }
+
+    /// Route from `token_in` to `token_out` paying out the most for `amount_in`
+    pub fn best_route(
+        &self,
+        cpmm: &CpmmRouter,
+        stableswap: &StableSwapRouter,
+        token_in: &str,
+        token_out: &str,
+        amount_in: u128,
+    ) -> Result<Route> {
+        if token_in == token_out {
+            return Err(anyhow::anyhow!("Cannot route {} to itself", token_in));
+        }
+
+        let mut graph: HashMap<&str, Vec<Edge>> = HashMap::new();
+        for pair in cpmm.pairs() {
+            graph.entry(pair.token0.as_str()).or_default().push(Edge {
+                token_out: pair.token1.as_str(),
+                pool: PoolRef::Cpmm(pair),
+            });
+            graph.entry(pair.token1.as_str()).or_default().push(Edge {
+                token_out: pair.token0.as_str(),
+                pool: PoolRef::Cpmm(pair),
+            });
+        }
+        for pool in stableswap.pools() {
+            for from in &pool.tokens {
+                for to in pool.tokens.iter().filter(|to| *to != from) {
+                    graph.entry(from.as_str()).or_default().push(Edge {
+                        token_out: to.as_str(),
+                        pool: PoolRef::Stable(pool),
+                    });
+                }
+            }
+        }
+
+        let mut best = None;
+        let mut visited = vec![token_in];
+        let mut hops = Vec::new();
+        self.search(&graph, token_in, token_out, amount_in, &mut visited, &mut hops, &mut best);
+        best.ok_or_else(|| anyhow::anyhow!(
+            "No route from {} to {} within {} hops",
+            token_in, token_out, self.max_hops
+        ))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn search<'a>(
+        &self,
+        graph: &HashMap<&'a str, Vec<Edge<'a>>>,
+        token: &str,
+        target: &str,
+        amount: u128,
+        visited: &mut Vec<&'a str>,
+        hops: &mut Vec<RouteHop>,
+        best: &mut Option<Route>,
+    ) {
+        if hops.len() >= self.max_hops {
+            return;
+        }
+
+        for edge in graph.get(token).into_iter().flatten() {
+            if visited.contains(&edge.token_out) {
+                continue;
+            }
+            // Pools that cannot fill this amount are simply not part of a route
+            let hop = match edge.quote(token, amount) {
+                Ok(hop) => hop,
+                Err(_) => continue,
+            };
+            let amount_out = hop.amount_out;
+            hops.push(hop);
+
+            if edge.token_out == target {
+                let better = best
+                    .as_ref()
+                    .map(|best| {
+                        amount_out > best.amount_out()
+                            || (amount_out == best.amount_out() && hops.len() < best.hops.len())
+                    })
+                    .unwrap_or(true);
+                if better {
+                    *best = Some(Route { hops: hops.clone() });
+                }
+            } else {
+                visited.push(edge.token_out);
+                self.search(graph, edge.token_out, target, amount_out, visited, hops, best);
+                visited.pop();
+            }
+            hops.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(tokens: &[&str]) -> Vec<String> {
+        tokens.iter().map(|token| token.to_string()).collect()
+    }
+
+    #[test]
+    fn test_finds_multi_hop_route() {
+        let mut cpmm = CpmmRouter::new();
+        // Thin direct pool; the liquidity sits behind USDC
+        cpmm.set_reserves("WETH", "TOKEN", 1_000, 1_000_000, 30);
+        cpmm.set_reserves("WETH", "USDC", 1_000_000, 2_000_000_000, 30);
+        cpmm.set_reserves("USDC", "TOKEN", 2_000_000_000, 1_000_000_000, 30);
+
+        let route = RouteSearch::default()
+            .best_route(&cpmm, &StableSwapRouter::new(), "WETH", "TOKEN", 1_000)
+            .unwrap();
+        assert_eq!(route.path(), path(&["WETH", "USDC", "TOKEN"]));
+        assert_eq!(route.amount_in(), 1_000);
+        assert_eq!(route.amount_out(), route.hops[1].amount_out);
+        assert_eq!(route.hops[1].amount_in, route.hops[0].amount_out);
+        assert_eq!(route.amm_type(), "CPMM");
+        assert_eq!(route.gas_estimate(), BASE_SWAP_GAS + 2 * PoolKind::Cpmm.hop_gas());
+
+        let combined = 1.0 - (1.0 - route.hops[0].price_impact_pct / 100.0) * (1.0 - route.hops[1].price_impact_pct / 100.0);
+        assert!((route.price_impact_pct() - combined * 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_routes_through_stable_pools() {
+        let mut cpmm = CpmmRouter::new();
+        cpmm.set_reserves("USDC", "TOKEN", 1_000_000_000, 1_000_000_000, 30);
+        let mut stableswap = StableSwapRouter::new();
+        stableswap.set_pool(StablePool::new(&["USDT", "USDC", "DAI"], &[1_000_000_000; 3], 100, 4).unwrap());
+
+        let route = RouteSearch::default()
+            .best_route(&cpmm, &stableswap, "USDT", "TOKEN", 1_000_000)
+            .unwrap();
+        assert_eq!(route.path(), path(&["USDT", "USDC", "TOKEN"]));
+        assert_eq!(route.amm_type(), "StableSwap+CPMM");
+        assert_eq!(
+            route.gas_estimate(),
+            BASE_SWAP_GAS + PoolKind::StableSwap.hop_gas() + PoolKind::Cpmm.hop_gas()
+        );
+    }
+
+    #[test]
+    fn test_respects_hop_limit() {
+        let mut cpmm = CpmmRouter::new();
+        cpmm.set_reserves("A", "B", 1_000_000, 1_000_000, 30);
+        cpmm.set_reserves("B", "C", 1_000_000, 1_000_000, 30);
+        cpmm.set_reserves("C", "D", 1_000_000, 1_000_000, 30);
+        let stableswap = StableSwapRouter::new();
+
+        let route = RouteSearch::new(3).best_route(&cpmm, &stableswap, "A", "D", 1_000).unwrap();
+        assert_eq!(route.hops.len(), 3);
+        assert!(RouteSearch::new(2).best_route(&cpmm, &stableswap, "A", "D", 1_000).is_err());
+        assert!(RouteSearch::new(3).best_route(&cpmm, &stableswap, "A", "A", 1_000).is_err());
+        assert!(RouteSearch::new(3).best_route(&cpmm, &stableswap, "A", "E", 1_000).is_err());
+    }
+
+    #[test]
+    fn test_prefers_fewer_hops_on_ties() {
+        let mut cpmm = CpmmRouter::new();
+        // Both the direct and the two-hop route pay out 9
+        cpmm.set_reserves("A", "C", 1_000_000_000, 1_000_000_000, 0);
+        cpmm.set_reserves("A", "B", 1_000_000_000, 2_000_000_000, 0);
+        cpmm.set_reserves("B", "C", 2_000_000_000, 1_000_000_000, 0);
+
+        let route = RouteSearch::default()
+            .best_route(&cpmm, &StableSwapRouter::new(), "A", "C", 10)
+            .unwrap();
+        assert_eq!(route.hops.len(), 1);
+        assert_eq!(route.amount_out(), 9);
+    }
+}
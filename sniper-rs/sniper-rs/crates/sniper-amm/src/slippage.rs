@@ -0,0 +1,235 @@
+//! Slippage model calibrated from realized fills.
+//!
+//! Each fill is compared against the quoted expected output and folded into
+//! an exponentially weighted estimate for its pool and trade-size bucket.
+//! Once a bucket has enough samples, the calibrated slippage replaces the
+//! fixed default when setting `min_out` and ranking routes.
+
+use serde::{Deserialize, Serialize};
+use sniper_core::types::ExecReceipt;
+use std::collections::HashMap;
+
+/// Slippage assumed before a bucket is calibrated
+pub const DEFAULT_SLIPPAGE: f64 = 0.05;
+
+/// Slippage model configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlippageModelConfig {
+    /// Slippage used for uncalibrated buckets
+    pub default_slippage: f64,
+    /// Weight of each new fill in the moving estimates
+    pub smoothing: f64,
+    /// Fills required before a bucket's estimate is trusted
+    pub min_samples: u32,
+    /// Standard deviations of headroom added to the mean when setting min_out
+    pub tolerance_stddevs: f64,
+    /// Lower bound on the min_out tolerance
+    pub min_tolerance: f64,
+    /// Upper bound on the min_out tolerance
+    pub max_tolerance: f64,
+}
+
+impl Default for SlippageModelConfig {
+    fn default() -> Self {
+        Self {
+            default_slippage: DEFAULT_SLIPPAGE,
+            smoothing: 0.2,
+            min_samples: 5,
+            tolerance_stddevs: 2.0,
+            min_tolerance: 0.005,
+            max_tolerance: 0.15,
+        }
+    }
+}
+
+/// Calibrated slippage estimate for one pool and size bucket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlippageEstimate {
+    pub pool: String,
+    pub size_bucket: u32,
+    pub mean: f64,
+    pub variance: f64,
+    pub samples: u32,
+}
+
+impl SlippageEstimate {
+    /// Standard deviation of observed slippage
+    pub fn stddev(&self) -> f64 {
+        self.variance.max(0.0).sqrt()
+    }
+}
+
+/// Slippage model fitted from realized fills
+pub struct SlippageModel {
+    config: SlippageModelConfig,
+    estimates: HashMap<(String, u32), SlippageEstimate>,
+}
+
+impl SlippageModel {
+    /// Create a new slippage model
+    pub fn new(config: SlippageModelConfig) -> Self {
+        Self {
+            config,
+            estimates: HashMap::new(),
+        }
+    }
+
+    /// Size bucket for a trade, one bucket per order of magnitude of amount_in
+    pub fn size_bucket(amount_in: u128) -> u32 {
+        if amount_in == 0 {
+            0
+        } else {
+            amount_in.ilog10()
+        }
+    }
+
+    /// Record a realized fill against its quoted expected output
+    pub fn record_fill(&mut self, pool: &str, amount_in: u128, expected_output: u128, realized_output: u128) {
+        if expected_output == 0 {
+            return;
+        }
+
+        let slippage = 1.0 - realized_output as f64 / expected_output as f64;
+        let bucket = Self::size_bucket(amount_in);
+        let alpha = self.config.smoothing;
+
+        let estimate = self
+            .estimates
+            .entry((pool.to_string(), bucket))
+            .or_insert_with(|| SlippageEstimate {
+                pool: pool.to_string(),
+                size_bucket: bucket,
+                mean: slippage,
+                variance: 0.0,
+                samples: 0,
+            });
+
+        // Exponentially weighted mean and variance
+        let delta = slippage - estimate.mean;
+        estimate.mean += alpha * delta;
+        estimate.variance = (1.0 - alpha) * (estimate.variance + alpha * delta * delta);
+        estimate.samples += 1;
+
+        tracing::debug!(
+            "slippage fill on {} bucket {}: {:.4} (mean {:.4})",
+            pool, bucket, slippage, estimate.mean
+        );
+    }
+
+    /// Record the realized output from an execution receipt
+    ///
+    /// Failed receipts and receipts without a realized amount are ignored.
+    pub fn record_receipt(&mut self, pool: &str, amount_in: u128, expected_output: u128, receipt: &ExecReceipt) {
+        if !receipt.success {
+            return;
+        }
+        if let Some(realized_output) = receipt.amount_out {
+            self.record_fill(pool, amount_in, expected_output, realized_output);
+        }
+    }
+
+    /// Get the calibrated estimate for a pool and trade size, if trusted
+    pub fn estimate(&self, pool: &str, amount_in: u128) -> Option<&SlippageEstimate> {
+        self.estimates
+            .get(&(pool.to_string(), Self::size_bucket(amount_in)))
+            .filter(|estimate| estimate.samples >= self.config.min_samples)
+    }
+
+    /// Expected slippage for a pool and trade size
+    pub fn expected_slippage(&self, pool: &str, amount_in: u128) -> f64 {
+        self.estimate(pool, amount_in)
+            .map(|estimate| estimate.mean)
+            .unwrap_or(self.config.default_slippage)
+    }
+
+    /// Slippage tolerance to apply when setting min_out
+    pub fn tolerance(&self, pool: &str, amount_in: u128) -> f64 {
+        match self.estimate(pool, amount_in) {
+            Some(estimate) => (estimate.mean + self.config.tolerance_stddevs * estimate.stddev())
+                .clamp(self.config.min_tolerance, self.config.max_tolerance),
+            None => self.config.default_slippage,
+        }
+    }
+
+    /// Minimum acceptable output for a quote
+    pub fn min_out(&self, pool: &str, amount_in: u128, expected_output: u128) -> u128 {
+        (expected_output as f64 * (1.0 - self.tolerance(pool, amount_in))) as u128
+    }
+
+    /// Output expected after calibrated slippage, used to rank routes
+    pub fn adjusted_output(&self, pool: &str, amount_in: u128, expected_output: u128) -> u128 {
+        let slippage = self.expected_slippage(pool, amount_in).max(0.0);
+        (expected_output as f64 * (1.0 - slippage)) as u128
+    }
+
+    /// Get all estimates for a pool
+    pub fn pool_estimates(&self, pool: &str) -> Vec<&SlippageEstimate> {
+        self.estimates
+            .values()
+            .filter(|estimate| estimate.pool == pool)
+            .collect()
+    }
+}
+
+impl Default for SlippageModel {
+    fn default() -> Self {
+        Self::new(SlippageModelConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ONE_ETH: u128 = 1_000_000_000_000_000_000;
+
+    #[test]
+    fn test_uncalibrated_uses_default() {
+        let mut model = SlippageModel::default();
+        model.record_fill("0xPool", ONE_ETH, 1000, 990);
+
+        // Not enough samples yet
+        assert_eq!(model.expected_slippage("0xPool", ONE_ETH), DEFAULT_SLIPPAGE);
+        assert_eq!(model.min_out("0xPool", ONE_ETH, 1000), 950);
+    }
+
+    #[test]
+    fn test_calibration_tightens_min_out() {
+        let mut model = SlippageModel::default();
+        for _ in 0..10 {
+            model.record_fill("0xPool", ONE_ETH, 1_000_000, 990_000);
+        }
+
+        let slippage = model.expected_slippage("0xPool", ONE_ETH);
+        assert!((slippage - 0.01).abs() < 1e-9);
+        assert!(model.min_out("0xPool", ONE_ETH, 1_000_000) > 950_000);
+
+        // Other size buckets stay uncalibrated
+        assert_eq!(model.expected_slippage("0xPool", ONE_ETH * 100), DEFAULT_SLIPPAGE);
+    }
+
+    #[test]
+    fn test_record_receipt() {
+        let mut model = SlippageModel::new(SlippageModelConfig {
+            min_samples: 1,
+            ..SlippageModelConfig::default()
+        });
+        let mut receipt = ExecReceipt {
+            tx_hash: "0xabc".to_string(),
+            success: true,
+            block: 1,
+            gas_used: 100000,
+            fees_paid_wei: 0,
+            failure_reason: None,
+            amount_out: None,
+            endpoint: None,
+        };
+
+        model.record_receipt("0xPool", ONE_ETH, 1000, &receipt);
+        assert!(model.estimate("0xPool", ONE_ETH).is_none());
+
+        receipt.amount_out = Some(980);
+        model.record_receipt("0xPool", ONE_ETH, 1000, &receipt);
+        assert_eq!(model.estimate("0xPool", ONE_ETH).unwrap().samples, 1);
+    }
+}
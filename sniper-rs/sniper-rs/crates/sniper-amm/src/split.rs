@@ -0,0 +1,232 @@
+//! Split routing of large orders across venues.
+//!
+//! A single pool charges price impact that grows with the trade, so a large
+//! order usually pays out more when spread over several venues. The order is
+//! cut into `parts` equal chunks and each chunk goes to the venue whose
+//! output grows the most from taking it; for venues with concave output, as
+//! AMM pools have, this greedy fill finds the best split at that granularity.
+
+use crate::OptimizedPath;
+use anyhow::Result;
+
+/// Chunks an order is cut into by default
+pub const DEFAULT_SPLIT_PARTS: usize = 20;
+
+/// Share of a split order sent down one path
+#[derive(Debug, Clone)]
+pub struct SplitLeg {
+    pub path: OptimizedPath,
+    pub amount_in: u128,
+    /// Share of the order's input, in percent
+    pub share_pct: f64,
+}
+
+/// Order split across several paths, executed leg by leg
+#[derive(Debug, Clone)]
+pub struct SplitRoute {
+    pub legs: Vec<SplitLeg>,
+    pub amount_in: u128,
+    pub expected_output: u128,
+    /// Input-weighted price impact of the legs, in percent
+    pub price_impact: f64,
+    pub gas_estimate: u64,
+}
+
+impl SplitRoute {
+    /// Combine legs into a route
+    pub fn new(legs: Vec<SplitLeg>) -> Self {
+        let amount_in: u128 = legs.iter().map(|leg| leg.amount_in).sum();
+        let price_impact = if amount_in == 0 {
+            0.0
+        } else {
+            legs.iter()
+                .map(|leg| leg.path.price_impact * leg.amount_in as f64)
+                .sum::<f64>()
+                / amount_in as f64
+        };
+        Self {
+            amount_in,
+            expected_output: legs.iter().map(|leg| leg.path.expected_output).sum(),
+            price_impact,
+            gas_estimate: legs.iter().map(|leg| leg.path.gas_estimate).sum(),
+            legs,
+        }
+    }
+
+    /// Legs in execution order
+    pub fn iter(&self) -> std::slice::Iter<'_, SplitLeg> {
+        self.legs.iter()
+    }
+
+    /// Number of legs
+    pub fn len(&self) -> usize {
+        self.legs.len()
+    }
+
+    /// Check if the route has no legs
+    pub fn is_empty(&self) -> bool {
+        self.legs.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a SplitRoute {
+    type Item = &'a SplitLeg;
+    type IntoIter = std::slice::Iter<'a, SplitLeg>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.legs.iter()
+    }
+}
+
+impl IntoIterator for SplitRoute {
+    type Item = SplitLeg;
+    type IntoIter = std::vec::IntoIter<SplitLeg>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.legs.into_iter()
+    }
+}
+
+/// Allocates an order across venues to maximize the combined output
+#[derive(Debug, Clone)]
+pub struct SplitOptimizer {
+    parts: usize,
+}
+
+impl Default for SplitOptimizer {
+    fn default() -> Self {
+        Self::new(DEFAULT_SPLIT_PARTS)
+    }
+}
+
+impl SplitOptimizer {
+    /// Create an optimizer cutting orders into `parts` chunks
+    pub fn new(parts: usize) -> Self {
+        Self { parts: parts.max(1) }
+    }
+
+    /// Chunks an order is cut into
+    pub fn parts(&self) -> usize {
+        self.parts
+    }
+
+    /// Amount of `amount_in` allocated to each of `venues` venues
+    ///
+    /// `quote(venue, amount)` is the venue's output for an input, or `None`
+    /// when the venue cannot fill it.
+    pub fn allocate<F>(&self, amount_in: u128, venues: usize, quote: F) -> Result<Vec<u128>>
+    where
+        F: Fn(usize, u128) -> Option<u128>,
+    {
+        if amount_in == 0 {
+            return Err(anyhow::anyhow!("Cannot split an empty order"));
+        }
+
+        let parts = (self.parts as u128).min(amount_in);
+        let (chunk, remainder) = (amount_in / parts, amount_in % parts);
+        let mut allocation = vec![0u128; venues];
+        let mut outputs = vec![0u128; venues];
+        for part in 0..parts {
+            // The remainder is spread over the first chunks
+            let size = chunk + u128::from(part < remainder);
+            let mut best: Option<(usize, u128, u128)> = None;
+            for (venue, (allocated, filled)) in allocation.iter().zip(&outputs).enumerate() {
+                let Some(output) = quote(venue, allocated + size) else {
+                    continue;
+                };
+                let gain = output.saturating_sub(*filled);
+                if best.map(|(_, best_gain, _)| gain > best_gain).unwrap_or(true) {
+                    best = Some((venue, gain, output));
+                }
+            }
+
+            let (venue, _, output) =
+                best.ok_or_else(|| anyhow::anyhow!("No venue can fill {} more", size))?;
+            allocation[venue] += size;
+            outputs[venue] = output;
+        }
+        Ok(allocation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpmm::get_amount_out;
+    use sniper_core::types::QuoteStamp;
+
+    fn path(expected_output: u128, price_impact: f64) -> OptimizedPath {
+        OptimizedPath {
+            amm_type: "CPMM".to_string(),
+            router_address: "0xRouter".to_string(),
+            route: vec!["A".to_string(), "B".to_string()],
+            expected_output,
+            price_impact,
+            gas_estimate: 150000,
+            execution_time_ms: 200,
+            quote: QuoteStamp {
+                quoted_at_ms: 0,
+                block: 0,
+                expected_out: Some(expected_output),
+            },
+        }
+    }
+
+    #[test]
+    fn test_splits_evenly_across_equal_pools() {
+        let pools = [(1_000_000u128, 1_000_000u128); 2];
+        let quote = |venue: usize, amount| {
+            let (reserve_in, reserve_out) = pools[venue];
+            get_amount_out(amount, reserve_in, reserve_out, 30).ok()
+        };
+
+        let allocation = SplitOptimizer::new(10).allocate(200_000, 2, quote).unwrap();
+        assert_eq!(allocation, vec![100_000, 100_000]);
+        let split: u128 = allocation.iter().enumerate().map(|(venue, amount)| quote(venue, *amount).unwrap()).sum();
+        assert!(split > quote(0, 200_000).unwrap());
+    }
+
+    #[test]
+    fn test_deeper_pool_takes_more() {
+        let pools = [(1_000_000u128, 1_000_000u128), (3_000_000, 3_000_000)];
+        let quote = |venue: usize, amount| {
+            let (reserve_in, reserve_out) = pools[venue];
+            get_amount_out(amount, reserve_in, reserve_out, 30).ok()
+        };
+
+        let allocation = SplitOptimizer::default().allocate(400_000, 2, quote).unwrap();
+        assert_eq!(allocation.iter().sum::<u128>(), 400_000);
+        assert_eq!(allocation, vec![100_000, 300_000]);
+    }
+
+    #[test]
+    fn test_skips_venues_that_cannot_fill() {
+        let quote = |venue: usize, amount: u128| (venue == 1).then_some(amount / 2);
+        assert_eq!(SplitOptimizer::default().allocate(7, 3, quote).unwrap(), vec![0, 7, 0]);
+
+        assert!(SplitOptimizer::default().allocate(100, 2, |_, _| None).is_err());
+        assert!(SplitOptimizer::default().allocate(0, 2, |_, amount| Some(amount)).is_err());
+    }
+
+    #[test]
+    fn test_split_route_totals() {
+        let route = SplitRoute::new(vec![
+            SplitLeg {
+                path: path(590, 1.0),
+                amount_in: 600,
+                share_pct: 60.0,
+            },
+            SplitLeg {
+                path: path(395, 2.0),
+                amount_in: 400,
+                share_pct: 40.0,
+            },
+        ]);
+        assert_eq!(route.len(), 2);
+        assert_eq!(route.amount_in, 1_000);
+        assert_eq!(route.expected_output, 985);
+        assert_eq!(route.gas_estimate, 300000);
+        assert!((route.price_impact - 1.4).abs() < 1e-9);
+        assert_eq!(route.iter().map(|leg| leg.amount_in).collect::<Vec<_>>(), vec![600, 400]);
+    }
+}
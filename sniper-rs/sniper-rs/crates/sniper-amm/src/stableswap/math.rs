@@ -1 +1,258 @@
-pub fn placeholder() { /* TODO: implement */ }
+//! StableSwap invariant math.
+//!
+//! Follows Curve's pool contracts: `D` is found by Newton iteration on
+//! `A·n^n·Σx + D = A·D·n^n + D^(n+1) / (n^n·Πx)`, and the balance of the
+//! output coin after a swap by Newton iteration on the same invariant with
+//! `D` held fixed. Balances are expected in a common precision. Products of
+//! two balances are carried in 256 bits so 18-decimal pools don't overflow;
+//! results can differ from the on-chain pool by a unit of rounding.
+
+use crate::cpmm::math::FEE_DENOMINATOR;
+use anyhow::Result;
+
+/// Newton iterations allowed before giving up, as in the Curve contracts
+pub const MAX_ITERATIONS: usize = 255;
+
+/// Invariant `D` of a pool with balances `xp` and amplification `amp`
+pub fn get_d(xp: &[u128], amp: u128) -> Result<u128> {
+    check_pool(xp, amp)?;
+    let n = xp.len() as u128;
+    let sum = checked_sum(xp.iter().copied())?;
+    let ann = amp.checked_mul(n).ok_or_else(overflow)?;
+
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        let mut d_p = d;
+        for &x in xp {
+            d_p = mul_div(d_p, d, x.checked_mul(n).ok_or_else(overflow)?)?;
+        }
+        let previous = d;
+        let numerator = ann
+            .checked_mul(sum)
+            .and_then(|value| value.checked_add(d_p.checked_mul(n)?))
+            .ok_or_else(overflow)?;
+        let denominator = (ann - 1)
+            .checked_mul(d)
+            .and_then(|value| value.checked_add(d_p.checked_mul(n + 1)?))
+            .ok_or_else(overflow)?;
+        d = mul_div(numerator, d, denominator)?;
+        if d.abs_diff(previous) <= 1 {
+            return Ok(d);
+        }
+    }
+    Err(anyhow::anyhow!("StableSwap invariant did not converge"))
+}
+
+/// Balance of coin `j` once coin `i`'s balance becomes `x`, keeping `D` fixed
+pub fn get_y(i: usize, j: usize, x: u128, xp: &[u128], amp: u128) -> Result<u128> {
+    check_coins(i, j, xp)?;
+    if x == 0 {
+        return Err(anyhow::anyhow!("Pool has no liquidity"));
+    }
+    let d = get_d(xp, amp)?;
+    let n = xp.len() as u128;
+    let ann = amp * n;
+
+    // c = D^(n+1) / (n^n·Πx'·Ann), kept as c·Ann·n/D so it stays near D
+    let mut c = d;
+    let mut sum = 0u128;
+    for (k, &balance) in xp.iter().enumerate() {
+        let x_k = if k == i {
+            x
+        } else if k != j {
+            balance
+        } else {
+            continue;
+        };
+        sum = sum.checked_add(x_k).ok_or_else(overflow)?;
+        c = mul_div(c, d, x_k.checked_mul(n).ok_or_else(overflow)?)?;
+    }
+    let b = sum.checked_add(d / ann).ok_or_else(overflow)?;
+
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let previous = y;
+        // y' = (y² + c) / (2y + b - D)
+        let denominator = y
+            .checked_mul(2)
+            .and_then(|value| value.checked_add(b))
+            .and_then(|value| value.checked_sub(d))
+            .filter(|value| *value > 0)
+            .ok_or_else(|| anyhow::anyhow!("StableSwap balance did not converge"))?;
+        let c_denominator = ann
+            .checked_mul(n)
+            .and_then(|value| value.checked_mul(denominator))
+            .ok_or_else(overflow)?;
+        y = mul_div(y, y, denominator)?
+            .checked_add(mul_div(c, d, c_denominator)?)
+            .ok_or_else(overflow)?;
+        if y.abs_diff(previous) <= 1 {
+            return Ok(y);
+        }
+    }
+    Err(anyhow::anyhow!("StableSwap balance did not converge"))
+}
+
+/// Output of coin `j`, after the fee, for `dx` of coin `i`
+pub fn get_dy(i: usize, j: usize, dx: u128, xp: &[u128], amp: u128, fee_bps: u32) -> Result<u128> {
+    check_fee(fee_bps)?;
+    let dy = get_dy_before_fee(i, j, dx, xp, amp)?;
+    Ok(dy - dy * fee_bps as u128 / FEE_DENOMINATOR)
+}
+
+/// Price impact of swapping `dx` of coin `i` for coin `j`, in percent
+///
+/// The shortfall of the pre-fee output against a 1:1 exchange, so for a
+/// balanced pool it measures the curve alone; trading into the scarcer
+/// coin of an imbalanced pool reports the premium paid as impact too.
+pub fn price_impact_pct(i: usize, j: usize, dx: u128, xp: &[u128], amp: u128) -> Result<f64> {
+    let dy = get_dy_before_fee(i, j, dx, xp, amp)?;
+    Ok(((1.0 - dy as f64 / dx as f64) * 100.0).max(0.0))
+}
+
+fn get_dy_before_fee(i: usize, j: usize, dx: u128, xp: &[u128], amp: u128) -> Result<u128> {
+    check_coins(i, j, xp)?;
+    if dx == 0 {
+        return Err(anyhow::anyhow!("Swap amount must be positive"));
+    }
+    let x = xp[i].checked_add(dx).ok_or_else(overflow)?;
+    let y = get_y(i, j, x, xp, amp)?;
+    // One unit is held back against rounding in the pool's favour
+    match xp[j].checked_sub(y).and_then(|dy| dy.checked_sub(1)) {
+        Some(dy) if dy > 0 => Ok(dy),
+        _ => Err(anyhow::anyhow!("Swap of {} is too small to pay out", dx)),
+    }
+}
+
+/// `a * b / denominator` with a 256-bit intermediate product
+pub(crate) fn mul_div(a: u128, b: u128, denominator: u128) -> Result<u128> {
+    if denominator == 0 {
+        return Err(anyhow::anyhow!("Division by zero in swap math"));
+    }
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+    let lo_lo = a_lo * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_lo = a_hi * b_lo;
+    let middle = (lo_lo >> 64) + (lo_hi & MASK) + (hi_lo & MASK);
+    let lo = (lo_lo & MASK) | (middle << 64);
+    let hi = a_hi * b_hi + (lo_hi >> 64) + (hi_lo >> 64) + (middle >> 64);
+
+    if hi >= denominator {
+        return Err(overflow());
+    }
+    // Long division of (hi, lo) by the denominator, one bit at a time
+    let mut remainder = hi;
+    let mut quotient = 0u128;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((lo >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= denominator {
+            remainder = remainder.wrapping_sub(denominator);
+            quotient |= 1;
+        }
+    }
+    Ok(quotient)
+}
+
+fn checked_sum(mut values: impl Iterator<Item = u128>) -> Result<u128> {
+    values.try_fold(0u128, |sum, value| sum.checked_add(value)).ok_or_else(overflow)
+}
+
+fn overflow() -> anyhow::Error {
+    anyhow::anyhow!("Swap amount overflow")
+}
+
+fn check_pool(xp: &[u128], amp: u128) -> Result<()> {
+    if xp.len() < 2 {
+        return Err(anyhow::anyhow!("StableSwap pool needs at least two coins"));
+    }
+    if xp.iter().any(|balance| *balance == 0) {
+        return Err(anyhow::anyhow!("Pool has no liquidity"));
+    }
+    if amp == 0 {
+        return Err(anyhow::anyhow!("Amplification must be positive"));
+    }
+    Ok(())
+}
+
+fn check_coins(i: usize, j: usize, xp: &[u128]) -> Result<()> {
+    if i == j || i >= xp.len() || j >= xp.len() {
+        return Err(anyhow::anyhow!("Invalid coin pair {} -> {} for a {}-coin pool", i, j, xp.len()));
+    }
+    Ok(())
+}
+
+fn check_fee(fee_bps: u32) -> Result<()> {
+    if fee_bps as u128 >= FEE_DENOMINATOR {
+        return Err(anyhow::anyhow!("Fee of {} bps is not below 100%", fee_bps));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpmm::math::get_amount_out;
+
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    #[test]
+    fn test_balanced_invariant_is_the_sum() {
+        assert_eq!(get_d(&[1_000_000, 1_000_000], 100).unwrap(), 2_000_000);
+        assert_eq!(get_d(&[1_000_000 * E18; 3], 200).unwrap(), 3_000_000 * E18);
+        assert!(get_d(&[1_000_000, 0], 100).is_err());
+        assert!(get_d(&[1_000_000, 1_000_000], 0).is_err());
+    }
+
+    #[test]
+    fn test_get_dy_beats_constant_product() {
+        // 1,000 in against a 1M/1M pool of 18-decimal coins, 4 bps fee
+        let xp = [1_000_000 * E18, 1_000_000 * E18];
+        let dy = get_dy(0, 1, 1_000 * E18, &xp, 100, 4).unwrap();
+        assert_eq!(dy, 999_590_103_058_584_712_249);
+
+        let cpmm = get_amount_out(1_000 * E18, xp[0], xp[1], 4).unwrap();
+        assert!(dy > cpmm);
+
+        let impact = price_impact_pct(0, 1, 1_000 * E18, &xp, 100).unwrap();
+        assert!(impact < 0.001);
+    }
+
+    #[test]
+    fn test_amplification_flattens_the_curve() {
+        let xp = [1_000_000, 1_000_000];
+        assert_eq!(get_dy(0, 1, 100_000, &xp, 100, 0).unwrap(), 99_900);
+        assert_eq!(get_dy(0, 1, 100_000, &xp, 1, 0).unwrap(), 95_228);
+        assert!(price_impact_pct(0, 1, 100_000, &xp, 1).unwrap() > price_impact_pct(0, 1, 100_000, &xp, 100).unwrap());
+    }
+
+    #[test]
+    fn test_swap_preserves_invariant() {
+        let xp = [1_000_000 * E18, 1_000_000 * E18];
+        let d = get_d(&xp, 100).unwrap();
+        let x = xp[0] + 1_000 * E18;
+        let y = get_y(0, 1, x, &xp, 100).unwrap();
+        assert!(get_d(&[x, y], 100).unwrap().abs_diff(d) <= 1);
+    }
+
+    #[test]
+    fn test_invalid_swaps() {
+        let xp = [1_000_000, 1_000_000];
+        assert!(get_dy(0, 0, 1_000, &xp, 100, 4).is_err());
+        assert!(get_dy(0, 2, 1_000, &xp, 100, 4).is_err());
+        assert!(get_dy(0, 1, 0, &xp, 100, 4).is_err());
+        assert!(get_dy(0, 1, 1_000, &xp, 100, 10_000).is_err());
+    }
+
+    #[test]
+    fn test_mul_div_wide_intermediate() {
+        assert_eq!(mul_div(u128::MAX, u128::MAX, u128::MAX).unwrap(), u128::MAX);
+        assert_eq!(mul_div(1 << 100, 1 << 100, 1 << 120).unwrap(), 1 << 80);
+        assert_eq!(mul_div(7, 3, 2).unwrap(), 10);
+        assert!(mul_div(u128::MAX, 2, 1).is_err());
+        assert!(mul_div(1, 1, 0).is_err());
+    }
+}
@@ -1 +1,10 @@
 //! Stableswap implementation (Curve-style)
+//!
+//! [`math`] solves the StableSwap invariant for `D` and swap outputs;
+//! [`router`] keeps pool balances and quotes swaps over them.
+
+pub mod math;
+pub mod router;
+
+pub use math::{get_d, get_dy, get_y};
+pub use router::{StablePool, StableSwapRouter};
@@ -1 +1,218 @@
-pub fn placeholder() { /* TODO: implement */ }
+//! Quoting over StableSwap pools.
+//!
+//! `StablePool` holds a pool's balances in each coin's own units along with
+//! the multipliers that bring them to a common precision, as Curve's `rates`
+//! do; `StableSwapRouter` keeps the known pools and quotes a swap in the pool
+//! paying out the most.
+
+use super::math::{get_dy, price_impact_pct};
+use crate::cpmm::PathQuote;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Precision balances are normalised to
+pub const PRECISION_DECIMALS: u32 = 18;
+
+/// State of one StableSwap pool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StablePool {
+    pub tokens: Vec<String>,
+    /// Balances in each coin's own units
+    pub balances: Vec<u128>,
+    /// Multipliers bringing each balance to the common precision
+    pub rates: Vec<u128>,
+    /// Amplification coefficient `A`
+    pub amp: u128,
+    pub fee_bps: u32,
+}
+
+impl StablePool {
+    /// Create a pool whose coins already share a precision
+    pub fn new(tokens: &[&str], balances: &[u128], amp: u128, fee_bps: u32) -> Result<Self> {
+        if tokens.len() < 2 || tokens.len() != balances.len() {
+            return Err(anyhow::anyhow!(
+                "StableSwap pool needs a balance for each of at least two tokens"
+            ));
+        }
+        Ok(Self {
+            tokens: tokens.iter().map(|token| token.to_string()).collect(),
+            balances: balances.to_vec(),
+            rates: vec![1; tokens.len()],
+            amp,
+            fee_bps,
+        })
+    }
+
+    /// Set each coin's decimals, e.g. 6 for USDC alongside 18 for DAI
+    pub fn with_decimals(mut self, decimals: &[u32]) -> Result<Self> {
+        if decimals.len() != self.tokens.len() {
+            return Err(anyhow::anyhow!("Expected decimals for {} tokens", self.tokens.len()));
+        }
+        self.rates = decimals
+            .iter()
+            .map(|decimals| {
+                PRECISION_DECIMALS
+                    .checked_sub(*decimals)
+                    .map(|shift| 10u128.pow(shift))
+                    .ok_or_else(|| anyhow::anyhow!("Decimals above {} are not supported", PRECISION_DECIMALS))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(self)
+    }
+
+    /// Index of a token in the pool
+    pub fn index_of(&self, token: &str) -> Option<usize> {
+        self.tokens.iter().position(|pool_token| pool_token == token)
+    }
+
+    /// Check if the pool trades a token pair
+    pub fn has_pair(&self, token_a: &str, token_b: &str) -> bool {
+        token_a != token_b && self.index_of(token_a).is_some() && self.index_of(token_b).is_some()
+    }
+
+    /// Balances at the common precision
+    pub fn normalized_balances(&self) -> Result<Vec<u128>> {
+        self.balances
+            .iter()
+            .zip(&self.rates)
+            .map(|(balance, rate)| {
+                balance
+                    .checked_mul(*rate)
+                    .ok_or_else(|| anyhow::anyhow!("Swap amount overflow"))
+            })
+            .collect()
+    }
+
+    /// Quote an exact input swap through the pool
+    pub fn quote(&self, token_in: &str, token_out: &str, amount_in: u128) -> Result<PathQuote> {
+        let i = self
+            .index_of(token_in)
+            .ok_or_else(|| anyhow::anyhow!("Pool does not trade {}", token_in))?;
+        let j = self
+            .index_of(token_out)
+            .ok_or_else(|| anyhow::anyhow!("Pool does not trade {}", token_out))?;
+        let xp = self.normalized_balances()?;
+        let dx = amount_in
+            .checked_mul(self.rates[i])
+            .ok_or_else(|| anyhow::anyhow!("Swap amount overflow"))?;
+
+        let amount_out = get_dy(i, j, dx, &xp, self.amp, self.fee_bps)? / self.rates[j];
+        Ok(PathQuote {
+            path: vec![token_in.to_string(), token_out.to_string()],
+            amounts: vec![amount_in, amount_out],
+            price_impact_pct: price_impact_pct(i, j, dx, &xp, self.amp)?,
+        })
+    }
+}
+
+/// Quotes swaps over known StableSwap pools
+#[derive(Debug, Clone, Default)]
+pub struct StableSwapRouter {
+    pools: Vec<StablePool>,
+}
+
+impl StableSwapRouter {
+    /// Create a router with no pools
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a pool, replacing the state of a known pool over the same tokens
+    pub fn set_pool(&mut self, pool: StablePool) {
+        match self.pools.iter_mut().find(|known| known.tokens == pool.tokens) {
+            Some(known) => *known = pool,
+            None => self.pools.push(pool),
+        }
+    }
+
+    /// Known pools
+    pub fn pools(&self) -> &[StablePool] {
+        &self.pools
+    }
+
+    /// Number of known pools
+    pub fn pool_count(&self) -> usize {
+        self.pools.len()
+    }
+
+    /// Check if any pool trades a token
+    pub fn has_token(&self, token: &str) -> bool {
+        self.pools.iter().any(|pool| pool.index_of(token).is_some())
+    }
+
+    /// Check if any pool trades a token pair
+    pub fn has_pair(&self, token_a: &str, token_b: &str) -> bool {
+        self.pools.iter().any(|pool| pool.has_pair(token_a, token_b))
+    }
+
+    /// Best exact input quote over the pools trading the pair
+    pub fn best_quote(&self, token_in: &str, token_out: &str, amount_in: u128) -> Result<PathQuote> {
+        let mut best: Option<PathQuote> = None;
+        let mut last_error = None;
+        for pool in self.pools.iter().filter(|pool| pool.has_pair(token_in, token_out)) {
+            match pool.quote(token_in, token_out, amount_in) {
+                Ok(quote) => {
+                    if best.as_ref().map(|best| quote.amount_out() > best.amount_out()).unwrap_or(true) {
+                        best = Some(quote);
+                    }
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        match (best, last_error) {
+            (Some(quote), _) => Ok(quote),
+            (None, Some(e)) => Err(e),
+            (None, None) => Err(anyhow::anyhow!(
+                "No StableSwap pool trades {} for {}",
+                token_in, token_out
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    #[test]
+    fn test_quote_across_decimals() {
+        // USDC (6 decimals) into DAI (18 decimals)
+        let pool = StablePool::new(&["USDC", "DAI"], &[1_000_000 * 1_000_000, 1_000_000 * E18], 100, 4)
+            .unwrap()
+            .with_decimals(&[6, 18])
+            .unwrap();
+
+        let quote = pool.quote("USDC", "DAI", 1_000 * 1_000_000).unwrap();
+        assert_eq!(quote.path, vec!["USDC".to_string(), "DAI".to_string()]);
+        assert_eq!(quote.amount_out(), 999_590_103_058_584_712_249);
+
+        // And back, rounded down to USDC's precision
+        let quote = pool.quote("DAI", "USDC", 1_000 * E18).unwrap();
+        assert_eq!(quote.amount_out(), 999_590_103);
+        assert!(pool.quote("DAI", "USDT", 1_000 * E18).is_err());
+    }
+
+    #[test]
+    fn test_best_quote_prefers_deeper_pool() {
+        let mut router = StableSwapRouter::new();
+        router.set_pool(StablePool::new(&["USDC", "USDT"], &[10_000_000, 10_000_000], 100, 4).unwrap());
+        router.set_pool(StablePool::new(&["USDC", "USDT", "DAI"], &[100_000, 100_000, 100_000], 100, 4).unwrap());
+        assert_eq!(router.pool_count(), 2);
+        assert!(router.has_pair("USDT", "DAI"));
+        assert!(!router.has_pair("USDC", "WETH"));
+
+        let deep = router.pools()[0].quote("USDC", "USDT", 50_000).unwrap();
+        let best = router.best_quote("USDC", "USDT", 50_000).unwrap();
+        assert_eq!(best.amount_out(), deep.amount_out());
+
+        // Updating a pool's balances replaces it
+        router.set_pool(StablePool::new(&["USDC", "USDT"], &[1_000, 1_000], 100, 4).unwrap());
+        assert_eq!(router.pool_count(), 2);
+        assert!(router.best_quote("USDC", "USDT", 50_000).unwrap().amount_out() > 1_000);
+
+        assert!(router.best_quote("USDC", "WETH", 50_000).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+//! Historical market data.
+//!
+//! A replay is a sequence of [`MarketEvent`]s, each a candle or a single
+//! trade in one symbol. Events are read from JSON lines, one event per line,
+//! or built from candle CSV exports with a
+//! `timestamp,open,high,low,close,volume` header.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// OHLCV bar
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candle {
+    /// Close time of the bar, in seconds
+    pub timestamp: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+/// A single historical trade print
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoricalTrade {
+    pub timestamp: u64,
+    pub price: f64,
+    pub quantity: f64,
+}
+
+/// A candle or trade replayed through the backtest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MarketEvent {
+    Candle { symbol: String, candle: Candle },
+    Trade { symbol: String, trade: HistoricalTrade },
+}
+
+impl MarketEvent {
+    /// Symbol the event is in
+    pub fn symbol(&self) -> &str {
+        match self {
+            MarketEvent::Candle { symbol, .. } | MarketEvent::Trade { symbol, .. } => symbol,
+        }
+    }
+
+    /// Time of the event, in seconds
+    pub fn timestamp(&self) -> u64 {
+        match self {
+            MarketEvent::Candle { candle, .. } => candle.timestamp,
+            MarketEvent::Trade { trade, .. } => trade.timestamp,
+        }
+    }
+
+    /// Price orders are evaluated and positions marked at: the close of a candle
+    pub fn price(&self) -> f64 {
+        match self {
+            MarketEvent::Candle { candle, .. } => candle.close,
+            MarketEvent::Trade { trade, .. } => trade.price,
+        }
+    }
+
+    /// Quantity traded in the event
+    pub fn volume(&self) -> f64 {
+        match self {
+            MarketEvent::Candle { candle, .. } => candle.volume,
+            MarketEvent::Trade { trade, .. } => trade.quantity,
+        }
+    }
+}
+
+/// Wrap candles of one symbol as events
+pub fn candle_events(symbol: &str, candles: Vec<Candle>) -> Vec<MarketEvent> {
+    candles
+        .into_iter()
+        .map(|candle| MarketEvent::Candle {
+            symbol: symbol.to_string(),
+            candle,
+        })
+        .collect()
+}
+
+/// Parse events from JSON lines, skipping blank lines
+pub fn parse_events(text: &str) -> Result<Vec<MarketEvent>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|e| anyhow::anyhow!("Invalid event on line {}: {}", index + 1, e))
+        })
+        .collect()
+}
+
+/// Read events from a JSON lines file
+pub fn load_events(path: &Path) -> Result<Vec<MarketEvent>> {
+    parse_events(&std::fs::read_to_string(path)?)
+}
+
+/// Parse candles from CSV with a `timestamp,open,high,low,close,volume` header
+pub fn parse_candles_csv(text: &str) -> Result<Vec<Candle>> {
+    let mut lines = text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+    let header: Vec<String> = match lines.next() {
+        Some((_, header)) => header.split(',').map(|column| column.trim().to_lowercase()).collect(),
+        None => return Ok(Vec::new()),
+    };
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|column| column == name)
+            .ok_or_else(|| anyhow::anyhow!("Candle CSV has no {} column", name))
+    };
+    let columns = [column("timestamp")?, column("open")?, column("high")?, column("low")?, column("close")?, column("volume")?];
+
+    lines
+        .map(|(index, line)| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let field = |column: usize| -> Result<f64> {
+                fields
+                    .get(column)
+                    .and_then(|field| field.parse::<f64>().ok())
+                    .ok_or_else(|| anyhow::anyhow!("Invalid candle on line {}", index + 1))
+            };
+            Ok(Candle {
+                timestamp: field(columns[0])? as u64,
+                open: field(columns[1])?,
+                high: field(columns[2])?,
+                low: field(columns[3])?,
+                close: field(columns[4])?,
+                volume: field(columns[5])?,
+            })
+        })
+        .collect()
+}
+
+/// Read candles from a CSV file
+pub fn load_candles_csv(path: &Path) -> Result<Vec<Candle>> {
+    parse_candles_csv(&std::fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_events_and_csv() {
+        let events = parse_events(concat!(
+            r#"{"type": "candle", "symbol": "ETH/USDT", "candle": {"timestamp": 60, "open": 1, "high": 2, "low": 1, "close": 2, "volume": 10}}"#,
+            "\n\n",
+            r#"{"type": "trade", "symbol": "ETH/USDT", "trade": {"timestamp": 61, "price": 2.5, "quantity": 1}}"#,
+        ))
+        .unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!((events[0].price(), events[0].volume()), (2.0, 10.0));
+        assert_eq!((events[1].timestamp(), events[1].price()), (61, 2.5));
+        assert!(parse_events("{}").is_err());
+
+        let candles = parse_candles_csv("Timestamp,Open,High,Low,Close,Volume\n60,1,2,0.5,1.5,100\n120,1.5,3,1.5,2.5,80\n").unwrap();
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles[1].close, 2.5);
+        assert!(parse_candles_csv("timestamp,close\n60,1\n").is_err());
+        assert!(parse_candles_csv("timestamp,open,high,low,close,volume\n60,1,2,x,1,1\n").is_err());
+    }
+}
@@ -0,0 +1,140 @@
+//! Simulated fills.
+//!
+//! Orders that take liquidity fill at the event price moved against them by
+//! the slippage model and pay the taker fee; limit orders rest on the book,
+//! fill at the event price without slippage and pay the maker fee.
+
+use serde::{Deserialize, Serialize};
+use sniper_orders::OrderType;
+
+/// How far a taker fill moves from the event price
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case")]
+pub enum SlippageModel {
+    None,
+    /// Constant slippage in basis points
+    Fixed { bps: f64 },
+    /// Slippage growing with the order's share of the event volume
+    VolumeShare {
+        /// Slippage at any size
+        base_bps: f64,
+        /// Additional slippage per percent of the event volume taken
+        bps_per_pct: f64,
+    },
+}
+
+impl Default for SlippageModel {
+    fn default() -> Self {
+        SlippageModel::Fixed { bps: 5.0 }
+    }
+}
+
+impl SlippageModel {
+    /// Slippage of a fill of `quantity` out of `volume`, in basis points
+    pub fn slippage_bps(&self, quantity: f64, volume: f64) -> f64 {
+        match self {
+            SlippageModel::None => 0.0,
+            SlippageModel::Fixed { bps } => *bps,
+            SlippageModel::VolumeShare { base_bps, bps_per_pct } => {
+                let share_pct = if volume > 0.0 { quantity / volume * 100.0 } else { 100.0 };
+                base_bps + bps_per_pct * share_pct
+            },
+        }
+    }
+}
+
+/// Trading fees charged on every fill
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeeModel {
+    pub maker_bps: f64,
+    pub taker_bps: f64,
+    /// Flat fee per fill, such as gas, in quote units
+    pub fixed: f64,
+}
+
+impl Default for FeeModel {
+    fn default() -> Self {
+        Self {
+            maker_bps: 2.0,
+            taker_bps: 5.0,
+            fixed: 0.0,
+        }
+    }
+}
+
+impl FeeModel {
+    /// Fee on a fill of `notional` quote units
+    pub fn fee(&self, notional: f64, maker: bool) -> f64 {
+        let bps = if maker { self.maker_bps } else { self.taker_bps };
+        notional * bps / 10_000.0 + self.fixed
+    }
+}
+
+/// A simulated fill
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimulatedFill {
+    pub price: f64,
+    pub slippage_bps: f64,
+    pub fee: f64,
+    pub maker: bool,
+}
+
+/// Whether an order of this type rests on the book rather than taking liquidity
+pub fn is_maker(order_type: &OrderType) -> bool {
+    matches!(order_type, OrderType::Limit { .. })
+}
+
+/// Fill `quantity` on `side` at an event with `price` and `volume`
+pub fn simulate_fill(
+    slippage: &SlippageModel,
+    fees: &FeeModel,
+    order_type: &OrderType,
+    side: &str,
+    quantity: f64,
+    price: f64,
+    volume: f64,
+) -> SimulatedFill {
+    let maker = is_maker(order_type);
+    let slippage_bps = if maker { 0.0 } else { slippage.slippage_bps(quantity, volume) };
+    let direction = if side == "buy" { 1.0 } else { -1.0 };
+    let fill_price = price * (1.0 + direction * slippage_bps / 10_000.0);
+    SimulatedFill {
+        price: fill_price,
+        slippage_bps,
+        fee: fees.fee(fill_price * quantity, maker),
+        maker,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slippage_and_fees() {
+        let slippage = SlippageModel::VolumeShare {
+            base_bps: 2.0,
+            bps_per_pct: 1.0,
+        };
+        assert_eq!(slippage.slippage_bps(10.0, 100.0), 12.0);
+        assert_eq!(slippage.slippage_bps(1.0, 0.0), 102.0);
+
+        let fees = FeeModel {
+            maker_bps: 1.0,
+            taker_bps: 10.0,
+            fixed: 0.5,
+        };
+        let buy = simulate_fill(&SlippageModel::Fixed { bps: 100.0 }, &fees, &OrderType::Market, "buy", 2.0, 100.0, 50.0);
+        assert!((buy.price - 101.0).abs() < 1e-9);
+        assert!((buy.fee - (202.0 * 0.001 + 0.5)).abs() < 1e-9);
+        assert!(!buy.maker);
+
+        let sell = simulate_fill(&SlippageModel::Fixed { bps: 100.0 }, &fees, &OrderType::Market, "sell", 2.0, 100.0, 50.0);
+        assert!((sell.price - 99.0).abs() < 1e-9);
+
+        let limit = simulate_fill(&SlippageModel::Fixed { bps: 100.0 }, &fees, &OrderType::Limit { price: 100.0 }, "buy", 2.0, 100.0, 50.0);
+        assert_eq!((limit.price, limit.slippage_bps), (100.0, 0.0));
+        assert!((limit.fee - (200.0 * 0.0001 + 0.5)).abs() < 1e-9);
+    }
+}
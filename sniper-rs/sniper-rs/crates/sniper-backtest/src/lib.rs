@@ -0,0 +1,431 @@
+//! Backtesting of strategies and order types against historical data.
+//!
+//! A [`Backtester`] replays candles and trades in time order through a
+//! [`Strategy`] plugin and an [`OrderManager`]. Each event first evaluates
+//! the open orders at the event price, filling those that trigger with the
+//! configured slippage and fee models, then marks the portfolio and samples
+//! its equity, and finally hands the event to the strategy. Orders the
+//! strategy places are evaluated from the next event on, so a strategy never
+//! trades at a price it has already seen.
+//!
+//! Strategies receive each event as JSON with `symbol`, `timestamp`,
+//! `price`, `volume`, the event itself under `event`, and the strategy's net
+//! `position` and `equity`. They answer with a [`StrategyOrder`], or nothing.
+//! Fills are booked in a [`PortfolioManager`] netting fills per symbol, with
+//! fees folded into the fill price, so the [`BacktestResult`] carries the same
+//! [`PerformanceMetrics`] as a live portfolio, alongside the trade log.
+//!
+//! Good-till-time orders expire at replay time, but the order manager also
+//! refuses to trigger orders whose expiry is before the wall clock, so
+//! historical good-till-time orders never fill.
+
+pub mod data;
+pub mod fills;
+pub mod report;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sniper_core::types::{ChainRef, Decimal};
+use sniper_orders::{AdvancedOrder, OrderManager, OrderStatus, OrderType, TimeInForce};
+use sniper_plugin::Strategy;
+use sniper_portfolio::{
+    AllocationSettings, EquityPoint, HistoricalMetrics, NettingMode, PerformanceMetrics, PortfolioManager, PositionFill,
+};
+use std::collections::HashMap;
+
+pub use data::{Candle, HistoricalTrade, MarketEvent};
+pub use fills::{FeeModel, SimulatedFill, SlippageModel};
+pub use report::{compare_trades, TradeComparison, TradeDiff, TradeRecord};
+
+/// Backtest settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BacktestConfig {
+    pub initial_capital: Decimal,
+    pub slippage: SlippageModel,
+    pub fees: FeeModel,
+    /// Chain orders and positions are placed on
+    pub chain: ChainRef,
+    /// Largest position allowed, as a percentage of the portfolio
+    pub max_position_size_pct: f64,
+    /// Minimum interval between equity samples
+    pub equity_sample_secs: u64,
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        Self {
+            initial_capital: Decimal::from(100_000),
+            slippage: SlippageModel::default(),
+            fees: FeeModel::default(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            max_position_size_pct: 100.0,
+            equity_sample_secs: 60,
+        }
+    }
+}
+
+/// An order a strategy places in answer to an event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyOrder {
+    /// Symbol to trade, defaulting to the event's
+    #[serde(default)]
+    pub symbol: Option<String>,
+    pub side: String, // "buy" or "sell"
+    pub amount: Decimal,
+    #[serde(default = "market_order")]
+    pub order_type: OrderType,
+    #[serde(default = "good_till_cancelled")]
+    pub time_in_force: TimeInForce,
+}
+
+fn market_order() -> OrderType {
+    OrderType::Market
+}
+
+fn good_till_cancelled() -> TimeInForce {
+    TimeInForce::GoodTillCancelled
+}
+
+/// Outcome of a backtest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestResult {
+    pub metrics: PerformanceMetrics,
+    pub history: HistoricalMetrics,
+    pub equity_curve: Vec<EquityPoint>,
+    pub trades: Vec<TradeRecord>,
+    pub total_fees: f64,
+    pub events: usize,
+    pub orders_placed: usize,
+    /// Strategy answers that were not valid orders, or orders refused by the order manager or portfolio
+    pub orders_rejected: usize,
+}
+
+/// Replays historical events through a strategy
+pub struct Backtester {
+    config: BacktestConfig,
+    strategy: Box<dyn Strategy>,
+}
+
+impl Backtester {
+    /// Create a backtester for a strategy
+    pub fn new(config: BacktestConfig, strategy: Box<dyn Strategy>) -> Self {
+        Self { config, strategy }
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &BacktestConfig {
+        &self.config
+    }
+
+    /// Replay `events` in time order, events at the same time in the given order
+    pub async fn run(&self, mut events: Vec<MarketEvent>) -> Result<BacktestResult> {
+        events.sort_by_key(|event| event.timestamp());
+        let mut run = Run::new(&self.config);
+
+        for event in &events {
+            run.fill_orders(event)?;
+            if let Some(price) = Decimal::from_f64(event.price()) {
+                run.portfolio.mark_to_market(event.symbol(), price)?;
+            }
+            run.portfolio.record_equity(event.timestamp());
+
+            let signal = run.signal(event);
+            let answer = match self.strategy.generate_plan(&signal).await {
+                Ok(answer) => answer,
+                Err(e) => {
+                    tracing::warn!("strategy {} failed at {}: {}", self.strategy.metadata().id, event.timestamp(), e);
+                    None
+                },
+            };
+            if let Some(answer) = answer {
+                run.place_order(event, answer);
+            }
+        }
+
+        Ok(BacktestResult {
+            metrics: run.portfolio.calculate_performance()?,
+            history: run.portfolio.historical_metrics(),
+            equity_curve: run.portfolio.equity_curve().points().to_vec(),
+            total_fees: run.trades.iter().map(|trade| trade.fee).sum(),
+            trades: run.trades,
+            events: events.len(),
+            orders_placed: run.orders_placed,
+            orders_rejected: run.orders_rejected,
+        })
+    }
+}
+
+/// State of one replay
+struct Run<'a> {
+    config: &'a BacktestConfig,
+    orders: OrderManager,
+    portfolio: PortfolioManager,
+    /// Sequence number of each order, to fill orders in the order they were placed
+    sequence: HashMap<String, usize>,
+    trades: Vec<TradeRecord>,
+    orders_placed: usize,
+    orders_rejected: usize,
+}
+
+impl<'a> Run<'a> {
+    fn new(config: &'a BacktestConfig) -> Self {
+        let mut portfolio = PortfolioManager::new(config.initial_capital, AllocationSettings {
+            max_position_size_pct: config.max_position_size_pct,
+            max_portfolio_risk_pct: 100.0,
+            diversification_targets: HashMap::new(),
+            // Exits are the strategy's to place as orders
+            stop_loss_pct: 0.0,
+            take_profit_pct: 0.0,
+            netting: NettingMode::Net,
+        });
+        portfolio.set_equity_sample_interval(config.equity_sample_secs);
+        Self {
+            config,
+            orders: OrderManager::new(),
+            portfolio,
+            sequence: HashMap::new(),
+            trades: Vec::new(),
+            orders_placed: 0,
+            orders_rejected: 0,
+        }
+    }
+
+    /// Net position in a symbol, positive when long
+    fn net_position(&self, symbol: &str) -> f64 {
+        self.portfolio.net_inventory(symbol).to_f64()
+    }
+
+    fn signal(&self, event: &MarketEvent) -> serde_json::Value {
+        json!({
+            "symbol": event.symbol(),
+            "timestamp": event.timestamp(),
+            "price": event.price(),
+            "volume": event.volume(),
+            "event": event,
+            "position": self.net_position(event.symbol()),
+            "equity": self.equity(),
+        })
+    }
+
+    /// Capital plus realized and unrealized PnL, `None` if out of the decimal range
+    fn equity(&self) -> Option<f64> {
+        let realized_pnl = self.portfolio.realized_pnl().ok()?;
+        let unrealized_pnl = self.portfolio.unrealized_pnl().ok()?;
+        Decimal::checked_sum([self.config.initial_capital, realized_pnl, unrealized_pnl]).map(Decimal::to_f64)
+    }
+
+    /// Evaluate the open orders in the event's symbol and fill those that trigger
+    fn fill_orders(&mut self, event: &MarketEvent) -> Result<()> {
+        let (symbol, price) = (event.symbol(), event.price());
+        self.orders.tick(event.timestamp());
+        self.orders.update_market_price(symbol, price);
+
+        let mut triggered: Vec<(usize, AdvancedOrder)> = self
+            .orders
+            .list_orders()
+            .into_iter()
+            .filter(|order| order.symbol == symbol && order.is_open())
+            .filter(|order| self.orders.to_trade_plan(&order.id, price).is_ok())
+            .map(|order| (self.sequence[&order.id], order.clone()))
+            .collect();
+        triggered.sort_by_key(|(sequence, _)| *sequence);
+
+        for (_, order) in triggered {
+            let amount = order.remaining_amount();
+            let quantity = amount.to_f64();
+            let fill = fills::simulate_fill(
+                &self.config.slippage,
+                &self.config.fees,
+                &order.order_type,
+                &order.side,
+                quantity,
+                price,
+                event.volume(),
+            );
+            // Fees are folded into the price so every portfolio metric is net of them
+            let fee_per_unit = fill.fee / quantity;
+            let booked_price = if order.side == "buy" { fill.price + fee_per_unit } else { fill.price - fee_per_unit };
+            let (Some(fill_price), Some(booked_price)) = (Decimal::from_f64(fill.price), Decimal::from_f64(booked_price)) else {
+                tracing::warn!("order {} cancelled, its fill price {} is invalid", order.id, fill.price);
+                self.orders.cancel_order(&order.id)?;
+                self.orders_rejected += 1;
+                continue;
+            };
+            let outcome = match self.portfolio.apply_fill(PositionFill {
+                symbol: order.symbol.clone(),
+                chain: order.chain.clone(),
+                side: if order.side == "buy" { "long" } else { "short" }.to_string(),
+                amount,
+                price: booked_price,
+                leverage: 1.0,
+                fee: Decimal::ZERO,
+            }) {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                    tracing::warn!("order {} cancelled, its fill was refused by the portfolio: {}", order.id, e);
+                    self.orders.cancel_order(&order.id)?;
+                    self.orders_rejected += 1;
+                    continue;
+                },
+            };
+            self.orders.record_fill(&order.id, amount, fill_price)?;
+
+            self.trades.push(TradeRecord {
+                timestamp: event.timestamp(),
+                order_id: order.id.clone(),
+                symbol: order.symbol.clone(),
+                side: order.side.clone(),
+                order_type: order.order_type.clone(),
+                quantity,
+                market_price: price,
+                fill_price: fill.price,
+                slippage_bps: fill.slippage_bps,
+                fee: fill.fee,
+                realized_pnl: outcome.realized.map(|realized| realized.realized_pnl.to_f64()),
+            });
+        }
+        Ok(())
+    }
+
+    /// Turn a strategy answer into an order
+    fn place_order(&mut self, event: &MarketEvent, answer: serde_json::Value) {
+        let request: StrategyOrder = match serde_json::from_value(answer) {
+            Ok(request) => request,
+            Err(e) => {
+                tracing::warn!("strategy answer at {} is not an order: {}", event.timestamp(), e);
+                self.orders_rejected += 1;
+                return;
+            },
+        };
+        if !request.amount.is_positive() || !matches!(request.side.as_str(), "buy" | "sell") {
+            tracing::warn!("strategy order at {} has side {} and amount {}", event.timestamp(), request.side, request.amount);
+            self.orders_rejected += 1;
+            return;
+        }
+
+        let order_id = format!("backtest-{}", self.orders_placed + self.orders_rejected);
+        let order = AdvancedOrder {
+            id: order_id.clone(),
+            symbol: request.symbol.unwrap_or_else(|| event.symbol().to_string()),
+            chain: self.config.chain.clone(),
+            order_type: request.order_type,
+            side: request.side,
+            amount: request.amount,
+            time_in_force: request.time_in_force,
+            created_at: event.timestamp(),
+            updated_at: event.timestamp(),
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
+        };
+        match self.orders.create_order(order) {
+            Ok(_) => {
+                self.sequence.insert(order_id, self.sequence.len());
+                self.orders_placed += 1;
+            },
+            Err(e) => {
+                tracing::warn!("order {} refused: {}", order_id, e);
+                self.orders_rejected += 1;
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use sniper_plugin::PluginMetadata;
+
+    /// Buys one unit on the first event, then offers it at 110
+    struct BuyThenTakeProfit {
+        metadata: PluginMetadata,
+    }
+
+    #[async_trait]
+    impl Strategy for BuyThenTakeProfit {
+        async fn generate_plan(&self, signal: &serde_json::Value) -> Result<Option<serde_json::Value>> {
+            let position = signal["position"].as_f64().unwrap();
+            Ok(match signal["timestamp"].as_u64().unwrap() {
+                60 => Some(json!({"side": "buy", "amount": 1.0})),
+                120 if position > 0.0 => Some(json!({"side": "sell", "amount": position, "order_type": {"Limit": {"price": 110.0}}})),
+                180 => Some(json!({"side": "hold"})),
+                _ => None,
+            })
+        }
+
+        fn metadata(&self) -> &PluginMetadata {
+            &self.metadata
+        }
+    }
+
+    fn candle(timestamp: u64, close: f64) -> Candle {
+        Candle {
+            timestamp,
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1_000.0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replays_strategy_orders() {
+        let strategy = BuyThenTakeProfit {
+            metadata: PluginMetadata {
+                id: "buy-then-tp".to_string(),
+                name: "Buy then take profit".to_string(),
+                version: "0.1.0".to_string(),
+                description: String::new(),
+                author: String::new(),
+                capabilities: vec!["strategy".to_string()],
+                config_schema: None,
+            },
+        };
+        let config = BacktestConfig {
+            initial_capital: Decimal::from(1_000),
+            slippage: SlippageModel::Fixed { bps: 10.0 },
+            fees: FeeModel {
+                maker_bps: 0.0,
+                taker_bps: 0.0,
+                fixed: 0.1,
+            },
+            ..BacktestConfig::default()
+        };
+        let backtester = Backtester::new(config, Box::new(strategy));
+        // Out of order on purpose: the replay sorts by time
+        let mut events = data::candle_events("ETH/USDT", vec![candle(120, 100.0), candle(60, 95.0), candle(180, 105.0)]);
+        events.extend(data::candle_events("ETH/USDT", vec![candle(240, 112.0), candle(300, 108.0)]));
+
+        let result = backtester.run(events).await.unwrap();
+        assert_eq!(result.events, 5);
+        assert_eq!((result.orders_placed, result.orders_rejected), (2, 1));
+        assert_eq!(result.trades.len(), 2);
+
+        // The market order placed at 60 fills at the next event, 100 plus 10 bps
+        let buy = &result.trades[0];
+        assert_eq!((buy.timestamp, buy.side.as_str()), (120, "buy"));
+        assert!((buy.fill_price - 100.1).abs() < 1e-9);
+        assert!(buy.realized_pnl.is_none());
+
+        // The limit order rests until 110 trades, then fills at 112 without slippage
+        let sell = &result.trades[1];
+        assert_eq!((sell.timestamp, sell.order_type.clone()), (240, OrderType::Limit { price: 110.0 }));
+        assert_eq!(sell.fill_price, 112.0);
+        let expected = (112.0 - 0.1) - (100.1 + 0.1);
+        assert!((sell.realized_pnl.unwrap() - expected).abs() < 1e-6);
+
+        assert!((result.total_fees - 0.2).abs() < 1e-9);
+        assert!((result.metrics.realized_pnl.to_f64() - expected).abs() < 1e-6);
+        assert_eq!(result.metrics.positions_count, 0);
+        assert_eq!(result.equity_curve.len(), 5);
+    }
+}
@@ -0,0 +1,134 @@
+//! Trade logs and backtest-versus-live comparison.
+//!
+//! Every simulated fill is logged as a [`TradeRecord`]. Logs recorded from a
+//! live run in the same format can be compared trade by trade with
+//! [`compare_trades`], pairing the n-th trade of a symbol and side in one log
+//! with the n-th in the other, to see how far live fills drift from the
+//! simulated ones.
+
+use serde::{Deserialize, Serialize};
+use sniper_orders::OrderType;
+use std::collections::HashMap;
+
+/// One fill in a trade log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeRecord {
+    pub timestamp: u64,
+    pub order_id: String,
+    pub symbol: String,
+    pub side: String, // "buy" or "sell"
+    pub order_type: OrderType,
+    pub quantity: f64,
+    /// Price of the event the order filled at
+    pub market_price: f64,
+    /// Price after slippage
+    pub fill_price: f64,
+    pub slippage_bps: f64,
+    pub fee: f64,
+    /// PnL booked by the fill, net of its fee, when it reduced a position
+    #[serde(default)]
+    pub realized_pnl: Option<f64>,
+}
+
+/// A backtest trade paired with the matching live trade
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeDiff {
+    pub symbol: String,
+    pub side: String,
+    pub backtest_price: f64,
+    pub live_price: f64,
+    /// How much worse the live fill was, in basis points of the backtest price
+    pub price_diff_bps: f64,
+    pub quantity_diff: f64,
+    pub fee_diff: f64,
+}
+
+/// Trade-by-trade comparison of a backtest with a live run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TradeComparison {
+    pub matched: Vec<TradeDiff>,
+    /// Trades only in the backtest
+    pub backtest_only: usize,
+    /// Trades only in the live run
+    pub live_only: usize,
+    pub avg_price_diff_bps: f64,
+    pub total_fee_diff: f64,
+}
+
+/// Pair the trades of a backtest with those of a live run, per symbol and side
+pub fn compare_trades(backtest: &[TradeRecord], live: &[TradeRecord]) -> TradeComparison {
+    let mut live_by_key: HashMap<(&str, &str), Vec<&TradeRecord>> = HashMap::new();
+    for trade in live {
+        live_by_key.entry((&trade.symbol, &trade.side)).or_default().push(trade);
+    }
+    let mut next: HashMap<(&str, &str), usize> = HashMap::new();
+
+    let mut comparison = TradeComparison::default();
+    for trade in backtest {
+        let key = (trade.symbol.as_str(), trade.side.as_str());
+        let index = next.entry(key).or_default();
+        let Some(live_trade) = live_by_key.get(&key).and_then(|trades| trades.get(*index)) else {
+            comparison.backtest_only += 1;
+            continue;
+        };
+        *index += 1;
+
+        let direction = if trade.side == "buy" { 1.0 } else { -1.0 };
+        let price_diff_bps = if trade.fill_price > 0.0 {
+            (live_trade.fill_price - trade.fill_price) / trade.fill_price * 10_000.0 * direction
+        } else {
+            0.0
+        };
+        comparison.matched.push(TradeDiff {
+            symbol: trade.symbol.clone(),
+            side: trade.side.clone(),
+            backtest_price: trade.fill_price,
+            live_price: live_trade.fill_price,
+            price_diff_bps,
+            quantity_diff: live_trade.quantity - trade.quantity,
+            fee_diff: live_trade.fee - trade.fee,
+        });
+    }
+    comparison.live_only = live.len() - comparison.matched.len();
+    if !comparison.matched.is_empty() {
+        comparison.avg_price_diff_bps =
+            comparison.matched.iter().map(|diff| diff.price_diff_bps).sum::<f64>() / comparison.matched.len() as f64;
+    }
+    comparison.total_fee_diff = comparison.matched.iter().map(|diff| diff.fee_diff).sum();
+    comparison
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(side: &str, fill_price: f64, fee: f64) -> TradeRecord {
+        TradeRecord {
+            timestamp: 0,
+            order_id: "order".to_string(),
+            symbol: "ETH/USDT".to_string(),
+            side: side.to_string(),
+            order_type: OrderType::Market,
+            quantity: 1.0,
+            market_price: fill_price,
+            fill_price,
+            slippage_bps: 0.0,
+            fee,
+            realized_pnl: None,
+        }
+    }
+
+    #[test]
+    fn test_compares_trade_by_trade() {
+        let backtest = vec![trade("buy", 100.0, 0.1), trade("sell", 110.0, 0.1), trade("buy", 105.0, 0.1)];
+        let live = vec![trade("sell", 109.0, 0.2), trade("buy", 101.0, 0.2)];
+
+        let comparison = compare_trades(&backtest, &live);
+        assert_eq!(comparison.matched.len(), 2);
+        assert_eq!((comparison.backtest_only, comparison.live_only), (1, 0));
+        // Live bought 1% higher and sold ~0.9% lower: both worse
+        assert!((comparison.matched[0].price_diff_bps - 100.0).abs() < 1e-9);
+        assert!(comparison.matched[1].price_diff_bps > 90.0);
+        assert!((comparison.total_fee_diff - 0.2).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,184 @@
+//! Hot path benchmarks
+//!
+//! This benchmark suite times the paths every trade goes through: triggering
+//! resting orders on a price update, searching routes over the pool graph,
+//! reading and writing the shared cache, and moving messages over the bus.
+//! Results are compared against `baselines/main.json` by `sniper-bench compare`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use sniper_amm::cpmm::CpmmRouter;
+use sniper_amm::routing::RouteSearch;
+use sniper_amm::stableswap::{StablePool, StableSwapRouter};
+use sniper_core::bus::InMemoryBus;
+use sniper_core::cache::Cache;
+use sniper_core::types::{ChainRef, Decimal};
+use sniper_orders::{AdvancedOrder, OrderManager, OrderStatus, OrderType, TimeInForce};
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+const SYMBOLS: usize = 16;
+const ORDERS_PER_SYMBOL: usize = 250;
+const BUS_MESSAGES: usize = 1000;
+const CACHE_KEYS: usize = 10_000;
+
+/// Benchmark evaluating a symbol's resting orders on a price update
+fn bench_order_triggering(c: &mut Criterion) {
+    let mut manager = OrderManager::new();
+    for order in create_test_orders() {
+        manager.create_order(order).unwrap();
+    }
+
+    let mut group = c.benchmark_group("order_triggering");
+    group.throughput(Throughput::Elements(ORDERS_PER_SYMBOL as u64));
+    // Above every buy limit nothing triggers; at 100 half of the orders do
+    for price in [120.0, 100.0] {
+        group.bench_with_input(BenchmarkId::new("price_update", price), &price, |b, price| {
+            b.iter(|| black_box(manager.update_market_price(&symbol(0), *price)))
+        });
+    }
+    group.finish();
+}
+
+/// Benchmark the best route search as the hop limit grows
+fn bench_route_search(c: &mut Criterion) {
+    let (cpmm, stableswap) = create_pool_graph();
+
+    let mut group = c.benchmark_group("route_search");
+    for max_hops in [1, 2, 3] {
+        let search = RouteSearch::new(max_hops);
+        group.bench_with_input(BenchmarkId::new("best_route", max_hops), &search, |b, search| {
+            b.iter(|| black_box(search.best_route(&cpmm, &stableswap, "WETH", "TOKEN7", 1_000_000_000).ok()))
+        });
+    }
+    group.finish();
+}
+
+/// Benchmark cache hits, misses and inserts
+fn bench_cache_access(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let cache: Cache<String, u128> = Cache::new(Duration::from_secs(60), CACHE_KEYS * 2);
+    let keys: Vec<String> = (0..CACHE_KEYS).map(|i| format!("pool-{}", i)).collect();
+    rt.block_on(async {
+        for (i, key) in keys.iter().enumerate() {
+            cache.insert(key.clone(), i as u128).await.unwrap();
+        }
+    });
+
+    let mut group = c.benchmark_group("cache_access");
+    let (cache, keys) = (&cache, &keys);
+    group.bench_function("get_hit", |b| {
+        let mut i = 0;
+        b.to_async(&rt).iter(move || {
+            i = (i + 1) % CACHE_KEYS;
+            let key = &keys[i];
+            async move { black_box(cache.get(key).await) }
+        })
+    });
+    group.bench_function("get_miss", |b| {
+        let missing = &"pool-missing".to_string();
+        b.to_async(&rt).iter(move || async move { black_box(cache.get(missing).await) })
+    });
+    group.bench_function("insert", |b| {
+        let mut i = 0;
+        b.to_async(&rt).iter(move || {
+            i = (i + 1) % CACHE_KEYS;
+            let key = keys[i].clone();
+            async move { cache.insert(key, i as u128).await.unwrap() }
+        })
+    });
+    group.finish();
+}
+
+/// Benchmark publishing a burst of messages and draining them from a subscriber
+fn bench_bus_throughput(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let plain = InMemoryBus::new(BUS_MESSAGES * 2);
+    // A short window keeps the history bounded over millions of messages
+    let with_history = InMemoryBus::with_history(BUS_MESSAGES * 2, Duration::from_secs(1));
+    let message = &serde_json::json!({
+        "token": "0xToken",
+        "amount_in": "1000000000000000000",
+        "seen_at_ms": 1700000000000u64,
+    });
+
+    let mut group = c.benchmark_group("bus_throughput");
+    group.throughput(Throughput::Elements(BUS_MESSAGES as u64));
+    for (name, bus) in [("publish_recv", &plain), ("publish_recv_history", &with_history)] {
+        group.bench_function(name, |b| {
+            b.to_async(&rt).iter(move || async move {
+                let mut rx = bus.subscribe("signals.>");
+                for _ in 0..BUS_MESSAGES {
+                    bus.publish("signals.dex.pair_created", message).await.unwrap();
+                }
+                for _ in 0..BUS_MESSAGES {
+                    black_box(rx.recv().await.unwrap());
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Symbol name for an index
+fn symbol(i: usize) -> String {
+    format!("TOKEN{}/USDT", i)
+}
+
+/// Create limit orders spread across the test symbols
+fn create_test_orders() -> Vec<AdvancedOrder> {
+    let mut orders = Vec::new();
+    for i in 0..SYMBOLS {
+        for j in 0..ORDERS_PER_SYMBOL {
+            orders.push(AdvancedOrder {
+                id: format!("order-{}-{}", i, j),
+                symbol: symbol(i),
+                chain: ChainRef {
+                    name: "ethereum".to_string(),
+                    id: 1,
+                },
+                order_type: OrderType::Limit { price: 90.0 + j as f64 * 0.08 },
+                side: "buy".to_string(),
+                amount: Decimal::ONE,
+                time_in_force: TimeInForce::GoodTillCancelled,
+                created_at: 1234567890,
+                updated_at: 1234567890,
+                status: OrderStatus::Pending,
+                fills: Vec::new(),
+                owner_id: None,
+                tenant_id: None,
+                trigger: None,
+            });
+        }
+    }
+    orders
+}
+
+/// Pools linking WETH, two stablecoins and ten tokens, each token paired with
+/// its neighbours so longer routes exist
+fn create_pool_graph() -> (CpmmRouter, StableSwapRouter) {
+    let mut cpmm = CpmmRouter::new();
+    for i in 0..10 {
+        let token = format!("TOKEN{}", i);
+        cpmm.set_reserves("WETH", &token, 1_000_000_000_000, 2_000_000_000_000, 30);
+        cpmm.set_reserves("USDC", &token, 5_000_000_000_000, 2_000_000_000_000, 30);
+        if i > 0 {
+            cpmm.set_reserves(&format!("TOKEN{}", i - 1), &token, 1_000_000_000_000, 1_000_000_000_000, 30);
+        }
+    }
+    cpmm.set_reserves("WETH", "USDT", 1_000_000_000_000, 2_500_000_000_000, 5);
+
+    let mut stableswap = StableSwapRouter::new();
+    stableswap.set_pool(
+        StablePool::new(&["USDC", "USDT"], &[10_000_000_000_000, 10_000_000_000_000], 200, 4).unwrap(),
+    );
+    (cpmm, stableswap)
+}
+
+criterion_group!(
+    benches,
+    bench_order_triggering,
+    bench_route_search,
+    bench_cache_access,
+    bench_bus_throughput
+);
+criterion_main!(benches);
@@ -0,0 +1,256 @@
+//! Benchmark baselines and the performance regression gate.
+//!
+//! The `hot_paths` criterion suite times the paths a trade goes through:
+//! order triggering, route search, cache access and bus throughput. After a
+//! run criterion leaves its estimates under `target/criterion`;
+//! [`Baseline::from_criterion`] collects them into a JSON artifact checked in
+//! under `baselines/`, and [`compare`] flags every benchmark that got slower
+//! than its baseline by more than a threshold.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Slowdown tolerated before a benchmark counts as regressed, in percent
+pub const DEFAULT_THRESHOLD_PCT: f64 = 10.0;
+
+/// Timing of one benchmark
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Estimate {
+    pub mean_ns: f64,
+    pub median_ns: f64,
+}
+
+/// Timings of every benchmark, keyed by criterion ID (`group/function`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub benchmarks: BTreeMap<String, Estimate>,
+}
+
+/// Estimates file criterion writes per benchmark
+#[derive(Deserialize)]
+struct CriterionEstimates {
+    mean: PointEstimate,
+    median: PointEstimate,
+}
+
+#[derive(Deserialize)]
+struct PointEstimate {
+    point_estimate: f64,
+}
+
+/// Benchmark description criterion writes next to its estimates
+#[derive(Deserialize)]
+struct CriterionBenchmark {
+    full_id: String,
+}
+
+impl Baseline {
+    /// Collect the latest run of every benchmark from a criterion output directory
+    pub fn from_criterion(dir: &Path) -> Result<Self> {
+        if !dir.is_dir() {
+            return Err(anyhow::anyhow!("No criterion output at {}", dir.display()));
+        }
+        let mut baseline = Self::default();
+        baseline.collect(dir)?;
+        Ok(baseline)
+    }
+
+    /// Load a baseline artifact
+    pub fn load(path: &Path) -> Result<Self> {
+        let txt = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Cannot read baseline {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&txt)?)
+    }
+
+    /// Write the baseline artifact
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+
+    fn collect(&mut self, dir: &Path) -> Result<()> {
+        // Each benchmark's latest run sits in a `new` directory
+        let latest = dir.join("new");
+        if latest.join("estimates.json").is_file() && latest.join("benchmark.json").is_file() {
+            let benchmark: CriterionBenchmark =
+                serde_json::from_str(&std::fs::read_to_string(latest.join("benchmark.json"))?)?;
+            let estimates: CriterionEstimates =
+                serde_json::from_str(&std::fs::read_to_string(latest.join("estimates.json"))?)?;
+            self.benchmarks.insert(
+                benchmark.full_id,
+                Estimate {
+                    mean_ns: estimates.mean.point_estimate,
+                    median_ns: estimates.median.point_estimate,
+                },
+            );
+            return Ok(());
+        }
+
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                self.collect(&path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of one benchmark against its baseline
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Verdict {
+    Regressed,
+    Improved,
+    Unchanged,
+}
+
+/// One benchmark compared with its baseline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchComparison {
+    pub name: String,
+    pub baseline_ns: f64,
+    pub current_ns: f64,
+    /// Change of the mean time, in percent; positive is slower
+    pub change_pct: f64,
+    pub verdict: Verdict,
+}
+
+/// Current run compared with a baseline
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Comparison {
+    pub threshold_pct: f64,
+    pub benchmarks: Vec<BenchComparison>,
+    /// In the baseline but not in the current run
+    pub missing: Vec<String>,
+    /// In the current run but not in the baseline
+    pub added: Vec<String>,
+}
+
+impl Comparison {
+    /// Benchmarks slower than the threshold allows
+    pub fn regressions(&self) -> impl Iterator<Item = &BenchComparison> {
+        self.benchmarks.iter().filter(|bench| bench.verdict == Verdict::Regressed)
+    }
+
+    /// Whether no benchmark regressed
+    pub fn passed(&self) -> bool {
+        self.regressions().next().is_none()
+    }
+}
+
+/// Compare mean times of a run against a baseline
+///
+/// A benchmark regresses when it is more than `threshold_pct` slower and
+/// improves when it is more than `threshold_pct` faster.
+pub fn compare(baseline: &Baseline, current: &Baseline, threshold_pct: f64) -> Comparison {
+    let mut comparison = Comparison {
+        threshold_pct,
+        ..Comparison::default()
+    };
+    for (name, base) in &baseline.benchmarks {
+        let Some(now) = current.benchmarks.get(name) else {
+            comparison.missing.push(name.clone());
+            continue;
+        };
+        let change_pct = (now.mean_ns - base.mean_ns) / base.mean_ns * 100.0;
+        let verdict = if change_pct > threshold_pct {
+            Verdict::Regressed
+        } else if change_pct < -threshold_pct {
+            Verdict::Improved
+        } else {
+            Verdict::Unchanged
+        };
+        comparison.benchmarks.push(BenchComparison {
+            name: name.clone(),
+            baseline_ns: base.mean_ns,
+            current_ns: now.mean_ns,
+            change_pct,
+            verdict,
+        });
+    }
+    comparison.added = current
+        .benchmarks
+        .keys()
+        .filter(|name| !baseline.benchmarks.contains_key(*name))
+        .cloned()
+        .collect();
+    comparison
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn baseline(benchmarks: &[(&str, f64)]) -> Baseline {
+        Baseline {
+            benchmarks: benchmarks
+                .iter()
+                .map(|(name, mean_ns)| {
+                    (
+                        name.to_string(),
+                        Estimate {
+                            mean_ns: *mean_ns,
+                            median_ns: *mean_ns,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_compare_flags_regressions() {
+        let base = baseline(&[("bus/publish", 100.0), ("cache/get", 50.0), ("route/3_hops", 1000.0), ("old", 1.0)]);
+        let current = baseline(&[("bus/publish", 115.0), ("cache/get", 40.0), ("route/3_hops", 1050.0), ("new", 1.0)]);
+
+        let comparison = compare(&base, &current, DEFAULT_THRESHOLD_PCT);
+        let verdicts: Vec<_> = comparison.benchmarks.iter().map(|bench| (bench.name.as_str(), bench.verdict)).collect();
+        assert_eq!(
+            verdicts,
+            vec![
+                ("bus/publish", Verdict::Regressed),
+                ("cache/get", Verdict::Improved),
+                ("route/3_hops", Verdict::Unchanged),
+            ]
+        );
+        assert!((comparison.benchmarks[0].change_pct - 15.0).abs() < 1e-9);
+        assert_eq!(comparison.missing, vec!["old".to_string()]);
+        assert_eq!(comparison.added, vec!["new".to_string()]);
+        assert!(!comparison.passed());
+
+        assert!(compare(&base, &current, 20.0).passed());
+    }
+
+    #[test]
+    fn test_collects_criterion_output() {
+        let dir = std::env::temp_dir().join(format!("sniper-bench-{}", std::process::id()));
+        let bench = dir.join("bus").join("publish").join("new");
+        std::fs::create_dir_all(&bench).unwrap();
+        std::fs::create_dir_all(dir.join("report")).unwrap();
+        std::fs::write(bench.join("benchmark.json"), r#"{"group_id":"bus","full_id":"bus/publish"}"#).unwrap();
+        std::fs::write(
+            bench.join("estimates.json"),
+            r#"{"mean":{"point_estimate":120.5,"standard_error":1.0},"median":{"point_estimate":118.0}}"#,
+        )
+        .unwrap();
+
+        let collected = Baseline::from_criterion(&dir).unwrap();
+        assert_eq!(collected, {
+            let mut expected = baseline(&[("bus/publish", 120.5)]);
+            expected.benchmarks.get_mut("bus/publish").unwrap().median_ns = 118.0;
+            expected
+        });
+
+        let artifact = dir.join("baselines").join("main.json");
+        collected.save(&artifact).unwrap();
+        assert_eq!(Baseline::load(&artifact).unwrap(), collected);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(Baseline::from_criterion(&dir).is_err());
+    }
+}
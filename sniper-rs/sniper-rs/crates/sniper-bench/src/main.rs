@@ -0,0 +1,107 @@
+//! sniper-bench binary: snapshots benchmark baselines and gates on regressions.
+
+use clap::{Parser, Subcommand};
+use sniper_bench::{compare, Baseline, Verdict, DEFAULT_THRESHOLD_PCT};
+use std::path::PathBuf;
+
+/// CLI arguments for the benchmark tool
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Criterion output directory of the latest `cargo bench` run
+    #[clap(long, default_value = "target/criterion")]
+    criterion_dir: PathBuf,
+
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Write the latest run as a baseline artifact
+    Snapshot {
+        #[clap(long, default_value = "crates/sniper-bench/baselines/main.json")]
+        out: PathBuf,
+    },
+    /// Compare the latest run with a baseline, failing on regressions
+    Compare {
+        #[clap(long, default_value = "crates/sniper-bench/baselines/main.json")]
+        baseline: PathBuf,
+        /// Slowdown tolerated before a benchmark counts as regressed, in percent
+        #[clap(long, default_value_t = DEFAULT_THRESHOLD_PCT)]
+        threshold_pct: f64,
+        /// Write the comparison as JSON
+        #[clap(long)]
+        report: Option<PathBuf>,
+    },
+}
+
+fn main() -> eyre::Result<()> {
+    let args = Args::parse();
+    let current = Baseline::from_criterion(&args.criterion_dir).map_err(|e| eyre::eyre!("{}", e))?;
+
+    match args.command {
+        Command::Snapshot { out } => {
+            current.save(&out).map_err(|e| eyre::eyre!("{}", e))?;
+            println!("wrote {} benchmarks to {}", current.benchmarks.len(), out.display());
+        }
+        Command::Compare {
+            baseline,
+            threshold_pct,
+            report,
+        } => {
+            let baseline = Baseline::load(&baseline).map_err(|e| eyre::eyre!("{}", e))?;
+            let comparison = compare(&baseline, &current, threshold_pct);
+            for bench in &comparison.benchmarks {
+                let marker = match bench.verdict {
+                    Verdict::Regressed => "REGRESSED",
+                    Verdict::Improved => "improved",
+                    Verdict::Unchanged => "ok",
+                };
+                println!(
+                    "{:<40} {:>14.1} ns -> {:>14.1} ns {:>+8.2}%  {}",
+                    bench.name, bench.baseline_ns, bench.current_ns, bench.change_pct, marker
+                );
+            }
+            for name in &comparison.missing {
+                println!("{:<40} missing from this run", name);
+            }
+            for name in &comparison.added {
+                println!("{:<40} not in the baseline", name);
+            }
+            if let Some(report) = report {
+                std::fs::write(&report, serde_json::to_string_pretty(&comparison)?)?;
+            }
+
+            let regressions = comparison.regressions().count();
+            if regressions > 0 {
+                eprintln!("{} benchmarks regressed by more than {}%", regressions, threshold_pct);
+                std::process::exit(1);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args_parsing() {
+        let args = Args::parse_from(["sniper-bench", "compare", "--threshold-pct", "5"]);
+        match args.command {
+            Command::Compare {
+                baseline,
+                threshold_pct,
+                report,
+            } => {
+                assert_eq!(baseline, PathBuf::from("crates/sniper-bench/baselines/main.json"));
+                assert_eq!(threshold_pct, 5.0);
+                assert!(report.is_none());
+            }
+            other => panic!("unexpected command {:?}", other),
+        }
+        assert_eq!(args.criterion_dir, PathBuf::from("target/criterion"));
+    }
+}
@@ -0,0 +1,449 @@
+//! Binance spot adapter.
+//!
+//! Orders, cancellations and balances go through the spot REST API, and
+//! market data comes from combined WebSocket streams: the top 20 levels of
+//! each book every 100ms and the trade tape. [`BinanceAdapter::testnet`]
+//! points both at the spot testnet. Binance needs an order's symbol to look
+//! it up, so the order IDs handed out read `<SYMBOL>:<orderId>`.
+
+use crate::auth::Credentials;
+use crate::rest::RestClient;
+use crate::ws::{spawn_stream, STREAM_BUFFER};
+use crate::{
+    CexClient, ExchangeAdapter, ExchangeId, MarketEvent, MarketStream, MarketTrade, OrderBook, OrderSide, OrderStatus,
+    OrderType, PriceLevel, Symbol,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Method;
+use serde::Deserialize;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+
+/// REST endpoint of the spot testnet
+pub const TESTNET_REST_ENDPOINT: &str = "https://testnet.binance.vision";
+/// WebSocket endpoint of the spot testnet
+pub const TESTNET_WS_ENDPOINT: &str = "wss://testnet.binance.vision";
+/// Levels per side of the books fetched and streamed
+pub const BOOK_DEPTH: usize = 20;
+
+const API_KEY_HEADER: &str = "X-MBX-APIKEY";
+
+#[derive(Deserialize)]
+struct Depth {
+    bids: Vec<[String; 2]>,
+    asks: Vec<[String; 2]>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OrderAck {
+    symbol: String,
+    order_id: u64,
+}
+
+#[derive(Deserialize)]
+struct OrderState {
+    status: String,
+}
+
+#[derive(Deserialize)]
+struct Account {
+    balances: Vec<Balance>,
+}
+
+#[derive(Deserialize)]
+struct Balance {
+    asset: String,
+    free: String,
+}
+
+/// Frame of a combined stream
+#[derive(Deserialize)]
+struct StreamFrame {
+    stream: String,
+    data: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct TradeData {
+    #[serde(rename = "t")]
+    trade_id: u64,
+    #[serde(rename = "p")]
+    price: String,
+    #[serde(rename = "q")]
+    amount: String,
+    #[serde(rename = "T")]
+    time: u64,
+    /// Whether the buyer was the maker, making the taker a seller
+    #[serde(rename = "m")]
+    buyer_is_maker: bool,
+}
+
+/// Binance spot exchange
+pub struct BinanceAdapter {
+    id: ExchangeId,
+    rest: RestClient,
+    ws_endpoint: String,
+}
+
+impl BinanceAdapter {
+    /// Create an adapter for the given endpoints, trading with `credentials`
+    pub fn new(rest_endpoint: &str, ws_endpoint: &str, credentials: Option<Credentials>) -> Result<Self> {
+        Ok(Self {
+            id: ExchangeId("binance".to_string()),
+            rest: RestClient::new(rest_endpoint, credentials, API_KEY_HEADER)?,
+            ws_endpoint: ws_endpoint.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Create an adapter for the spot testnet
+    pub fn testnet(credentials: Option<Credentials>) -> Result<Self> {
+        Self::new(TESTNET_REST_ENDPOINT, TESTNET_WS_ENDPOINT, credentials)
+    }
+
+    /// Combined stream URL carrying the books and trades of `symbols`
+    pub fn stream_url(&self, symbols: &[Symbol]) -> String {
+        let streams: Vec<String> = symbols
+            .iter()
+            .flat_map(|symbol| {
+                let name = market_symbol(symbol).to_lowercase();
+                [format!("{}@depth{}@100ms", name, BOOK_DEPTH), format!("{}@trade", name)]
+            })
+            .collect();
+        format!("{}/stream?streams={}", self.ws_endpoint, streams.join("/"))
+    }
+}
+
+#[async_trait]
+impl CexClient for BinanceAdapter {
+    async fn get_order_book(&self, symbol: &Symbol) -> Result<OrderBook> {
+        let params = [("symbol", market_symbol(symbol)), ("limit", BOOK_DEPTH.to_string())];
+        let depth: Depth = self.rest.public(Method::GET, "/api/v3/depth", &params).await?;
+        Ok(OrderBook {
+            symbol: symbol.clone(),
+            bids: levels(&depth.bids)?,
+            asks: levels(&depth.asks)?,
+            timestamp: now_ms(),
+        })
+    }
+
+    async fn place_order(
+        &self,
+        symbol: &Symbol,
+        side: OrderSide,
+        order_type: OrderType,
+        price: Option<f64>,
+        amount: f64,
+    ) -> Result<String> {
+        let mut params = vec![
+            ("symbol", market_symbol(symbol)),
+            ("side", match side {
+                OrderSide::Buy => "BUY",
+                OrderSide::Sell => "SELL",
+            }
+            .to_string()),
+            ("quantity", amount.to_string()),
+            ("newOrderRespType", "ACK".to_string()),
+        ];
+        let price = || price.ok_or_else(|| anyhow::anyhow!("{:?} orders need a price", order_type));
+        match order_type {
+            OrderType::Market => params.push(("type", "MARKET".to_string())),
+            OrderType::Limit => params.extend([
+                ("type", "LIMIT".to_string()),
+                ("timeInForce", "GTC".to_string()),
+                ("price", price()?.to_string()),
+            ]),
+            // Triggered at the price given, then filled at market
+            OrderType::StopLoss => params.extend([("type", "STOP_LOSS".to_string()), ("stopPrice", price()?.to_string())]),
+            OrderType::TakeProfit => {
+                params.extend([("type", "TAKE_PROFIT".to_string()), ("stopPrice", price()?.to_string())])
+            }
+        }
+        let ack: OrderAck = self.rest.signed(Method::POST, "/api/v3/order", &params).await?;
+        Ok(format!("{}:{}", ack.symbol, ack.order_id))
+    }
+
+    async fn get_order_status(&self, order_id: &str) -> Result<OrderStatus> {
+        let state: OrderState = self.rest.signed(Method::GET, "/api/v3/order", &order_params(order_id)?).await?;
+        order_status(&state.status)
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> Result<()> {
+        let _: serde_json::Value = self.rest.signed(Method::DELETE, "/api/v3/order", &order_params(order_id)?).await?;
+        Ok(())
+    }
+
+    /// Free balance of every asset held
+    async fn get_balances(&self) -> Result<HashMap<String, f64>> {
+        let account: Account = self.rest.signed(Method::GET, "/api/v3/account", &[]).await?;
+        let mut balances = HashMap::new();
+        for balance in account.balances {
+            let free: f64 = balance.free.parse()?;
+            if free > 0.0 {
+                balances.insert(balance.asset, free);
+            }
+        }
+        Ok(balances)
+    }
+}
+
+#[async_trait]
+impl ExchangeAdapter for BinanceAdapter {
+    fn exchange_id(&self) -> &ExchangeId {
+        &self.id
+    }
+
+    async fn subscribe_market(&self, symbols: &[Symbol]) -> Result<MarketStream> {
+        if symbols.is_empty() {
+            return Err(anyhow::anyhow!("No symbols to stream"));
+        }
+        let names: HashMap<String, Symbol> = symbols
+            .iter()
+            .map(|symbol| (market_symbol(symbol).to_lowercase(), symbol.clone()))
+            .collect();
+        let (events, receiver) = mpsc::channel(STREAM_BUFFER);
+        let task = spawn_stream(self.stream_url(symbols), move |text| parse_frame(&names, text), events);
+        Ok(MarketStream::new(receiver, task))
+    }
+}
+
+/// Symbol as Binance writes it, e.g. `BTCUSDT` for `BTC/USDT`
+pub fn market_symbol(symbol: &Symbol) -> String {
+    symbol.0.replace(['/', '-', '_'], "").to_uppercase()
+}
+
+/// Events in a combined stream frame; `names` maps stream symbols back
+fn parse_frame(names: &HashMap<String, Symbol>, text: &str) -> Result<Vec<MarketEvent>> {
+    let frame: StreamFrame = serde_json::from_str(text)?;
+    let (name, channel) = frame
+        .stream
+        .split_once('@')
+        .ok_or_else(|| anyhow::anyhow!("Unexpected stream {}", frame.stream))?;
+    let symbol = names
+        .get(name)
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("Not subscribed to {}", name))?;
+    let event = if channel.starts_with("depth") {
+        let depth: Depth = serde_json::from_value(frame.data)?;
+        MarketEvent::Book(OrderBook {
+            symbol,
+            bids: levels(&depth.bids)?,
+            asks: levels(&depth.asks)?,
+            timestamp: now_ms(),
+        })
+    } else if channel == "trade" {
+        let trade: TradeData = serde_json::from_value(frame.data)?;
+        MarketEvent::Trade(MarketTrade {
+            symbol,
+            side: if trade.buyer_is_maker { OrderSide::Sell } else { OrderSide::Buy },
+            price: trade.price.parse()?,
+            amount: trade.amount.parse()?,
+            timestamp: trade.time,
+            trade_id: trade.trade_id.to_string(),
+        })
+    } else {
+        return Ok(Vec::new());
+    };
+    Ok(vec![event])
+}
+
+fn levels(levels: &[[String; 2]]) -> Result<Vec<PriceLevel>> {
+    levels
+        .iter()
+        .map(|[price, amount]| {
+            Ok(PriceLevel {
+                price: price.parse()?,
+                amount: amount.parse()?,
+            })
+        })
+        .collect()
+}
+
+/// Symbol and order ID parameters of an order ID handed out by the adapter
+fn order_params(order_id: &str) -> Result<Vec<(&'static str, String)>> {
+    let (symbol, id) = order_id
+        .split_once(':')
+        .ok_or_else(|| anyhow::anyhow!("Order ID {} is not <SYMBOL>:<orderId>", order_id))?;
+    Ok(vec![("symbol", symbol.to_string()), ("orderId", id.to_string())])
+}
+
+fn order_status(status: &str) -> Result<OrderStatus> {
+    Ok(match status {
+        "NEW" | "PENDING_NEW" => OrderStatus::New,
+        "PARTIALLY_FILLED" => OrderStatus::PartiallyFilled,
+        "FILLED" => OrderStatus::Filled,
+        "CANCELED" | "PENDING_CANCEL" | "EXPIRED" | "EXPIRED_IN_MATCH" => OrderStatus::Cancelled,
+        "REJECTED" => OrderStatus::Rejected,
+        other => return Err(anyhow::anyhow!("Unknown order status {}", other)),
+    })
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::RawQuery;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use futures::{SinkExt, StreamExt};
+    use serde_json::{json, Value};
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
+
+    const API_KEY: &str = "testnet-key";
+    const API_SECRET: &str = "testnet-secret";
+
+    fn credentials() -> Credentials {
+        Credentials::new(API_KEY, API_SECRET)
+    }
+
+    /// Parameters of a request whose key and signature check out
+    fn verified(headers: &HeaderMap, query: Option<String>) -> Result<HashMap<String, String>, (StatusCode, Json<Value>)> {
+        let query = query.unwrap_or_default();
+        let (payload, signature) = query.rsplit_once("&signature=").unwrap_or((&query, ""));
+        if headers.get(API_KEY_HEADER).map(|key| key.to_str().unwrap()) != Some(API_KEY)
+            || credentials().sign(payload) != signature
+        {
+            let error = json!({"code": -1022, "msg": "Signature for this request is not valid."});
+            return Err((StatusCode::UNAUTHORIZED, Json(error)));
+        }
+        Ok(url::form_urlencoded::parse(payload.as_bytes()).into_owned().collect())
+    }
+
+    async fn place(headers: HeaderMap, RawQuery(query): RawQuery) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+        let params = verified(&headers, query)?;
+        assert!(params.contains_key("timestamp") && params.contains_key("recvWindow"));
+        if params["type"] == "LIMIT" && (!params.contains_key("price") || params["timeInForce"] != "GTC") {
+            return Err((StatusCode::BAD_REQUEST, Json(json!({"code": -1102, "msg": "Mandatory parameter missing"}))));
+        }
+        Ok(Json(json!({"symbol": params["symbol"], "orderId": 42, "transactTime": 1})))
+    }
+
+    async fn query(headers: HeaderMap, RawQuery(query): RawQuery) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+        let params = verified(&headers, query)?;
+        assert_eq!((params["symbol"].as_str(), params["orderId"].as_str()), ("BTCUSDT", "42"));
+        Ok(Json(json!({"symbol": "BTCUSDT", "orderId": 42, "status": "PARTIALLY_FILLED"})))
+    }
+
+    async fn cancel(headers: HeaderMap, RawQuery(query): RawQuery) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+        verified(&headers, query)?;
+        Ok(Json(json!({"symbol": "BTCUSDT", "orderId": 42, "status": "CANCELED"})))
+    }
+
+    async fn account(headers: HeaderMap, RawQuery(query): RawQuery) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+        verified(&headers, query)?;
+        Ok(Json(json!({"balances": [
+            {"asset": "BTC", "free": "1.50000000", "locked": "0.50000000"},
+            {"asset": "USDT", "free": "10000.00000000", "locked": "0.00000000"},
+            {"asset": "BNB", "free": "0.00000000", "locked": "0.00000000"},
+        ]})))
+    }
+
+    async fn depth(RawQuery(query): RawQuery) -> Json<Value> {
+        assert_eq!(query.as_deref(), Some("symbol=BTCUSDT&limit=20"));
+        Json(json!({"lastUpdateId": 7, "bids": [["30000.10", "0.5"]], "asks": [["30000.20", "1.25"], ["30001.00", "2"]]}))
+    }
+
+    async fn rest_endpoint() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let app = Router::new()
+            .route("/api/v3/depth", get(depth))
+            .route("/api/v3/order", get(query).post(place).delete(cancel))
+            .route("/api/v3/account", get(account));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_trades_through_rest_api() {
+        let endpoint = rest_endpoint().await;
+        let adapter = BinanceAdapter::new(&endpoint, TESTNET_WS_ENDPOINT, Some(credentials())).unwrap();
+        let symbol = Symbol("BTC/USDT".to_string());
+
+        let book = adapter.get_order_book(&symbol).await.unwrap();
+        assert_eq!(book.bids[0].price, 30000.1);
+        assert_eq!(book.asks.len(), 2);
+        assert_eq!(book.asks[0].amount, 1.25);
+
+        let order_id = adapter
+            .place_order(&symbol, OrderSide::Buy, OrderType::Limit, Some(30000.0), 0.01)
+            .await
+            .unwrap();
+        assert_eq!(order_id, "BTCUSDT:42");
+        assert!(adapter
+            .place_order(&symbol, OrderSide::Buy, OrderType::Limit, None, 0.01)
+            .await
+            .is_err());
+        assert_eq!(adapter.get_order_status(&order_id).await.unwrap(), OrderStatus::PartiallyFilled);
+        adapter.cancel_order(&order_id).await.unwrap();
+        assert!(adapter.cancel_order("42").await.is_err());
+
+        let balances = adapter.get_balances().await.unwrap();
+        assert_eq!(balances.len(), 2);
+        assert_eq!(balances["BTC"], 1.5);
+
+        // Requests signed with the wrong secret are refused with the exchange's error
+        let forged = BinanceAdapter::new(&endpoint, TESTNET_WS_ENDPOINT, Some(Credentials::new(API_KEY, "wrong"))).unwrap();
+        let error = forged.get_balances().await.unwrap_err().to_string();
+        assert!(error.contains("-1022"), "{}", error);
+        let anonymous = BinanceAdapter::new(&endpoint, TESTNET_WS_ENDPOINT, None).unwrap();
+        assert!(anonymous.get_balances().await.is_err());
+    }
+
+    #[test]
+    fn test_maps_symbols_and_statuses() {
+        assert_eq!(market_symbol(&Symbol("eth/usdt".to_string())), "ETHUSDT");
+        assert_eq!(market_symbol(&Symbol("BTC-USDT".to_string())), "BTCUSDT");
+        assert_eq!(order_status("EXPIRED").unwrap(), OrderStatus::Cancelled);
+        assert!(order_status("UNKNOWN").is_err());
+
+        let adapter = BinanceAdapter::testnet(None).unwrap();
+        assert_eq!(
+            adapter.stream_url(&[Symbol("BTC/USDT".to_string())]),
+            "wss://testnet.binance.vision/stream?streams=btcusdt@depth20@100ms/btcusdt@trade"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_streams_normalized_books_and_trades() {
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = server.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let frames = [
+                json!({"stream": "btcusdt@depth20@100ms", "data": {"lastUpdateId": 8, "bids": [["30000.10", "0.5"]], "asks": [["30000.20", "1"]]}}),
+                json!({"result": null, "id": 1}),
+                json!({"stream": "btcusdt@trade", "data": {"e": "trade", "E": 2, "s": "BTCUSDT", "t": 99, "p": "30000.15", "q": "0.2", "T": 1700000000000u64, "m": true}}),
+            ];
+            for frame in frames {
+                socket.send(Message::Text(frame.to_string())).await.unwrap();
+            }
+            while socket.next().await.is_some() {}
+        });
+
+        let adapter = BinanceAdapter::new("http://127.0.0.1:1", &format!("ws://{}", addr), None).unwrap();
+        let symbol = Symbol("BTC/USDT".to_string());
+        let mut stream = adapter.subscribe_market(&[symbol]).await.unwrap();
+        let book = tokio::time::timeout(Duration::from_secs(5), stream.recv()).await.unwrap().unwrap();
+        assert_eq!(book.subject(adapter.exchange_id()), "cex.binance.book.BTCUSDT");
+        let trade = match tokio::time::timeout(Duration::from_secs(5), stream.recv()).await.unwrap().unwrap() {
+            MarketEvent::Trade(trade) => trade,
+            other => panic!("expected a trade, got {:?}", other),
+        };
+        assert_eq!(trade.symbol.0, "BTC/USDT");
+        // The buyer made the market, so the taker sold
+        assert_eq!(trade.side, OrderSide::Sell);
+        assert_eq!((trade.price, trade.amount, trade.trade_id.as_str()), (30000.15, 0.2, "99"));
+    }
+}
@@ -2,14 +2,20 @@
 //! 
 //! This module provides functionality for interacting with centralized exchanges
 //! including REST APIs and WebSocket feeds for price data and order management.
+//! Each exchange is connected through an [`ExchangeAdapter`], which adds a
+//! normalized order book and trade stream to the [`CexClient`] operations;
+//! [`binance`] is the first such adapter.
 
 pub mod rest;
 pub mod ws;
 pub mod auth;
+pub mod binance;
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use async_trait::async_trait;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
 /// CEX exchange identifier
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,14 +26,14 @@ pub struct ExchangeId(pub String);
 pub struct Symbol(pub String);
 
 /// Order side
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OrderSide {
     Buy,
     Sell,
 }
 
 /// Order type
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OrderType {
     Market,
     Limit,
@@ -36,7 +42,7 @@ pub enum OrderType {
 }
 
 /// Order status
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OrderStatus {
     New,
     PartiallyFilled,
@@ -72,6 +78,70 @@ pub struct Trade {
     pub exchange_order_id: String,
 }
 
+/// Trade printed on an exchange's public tape
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketTrade {
+    pub symbol: Symbol,
+    /// Side of the taker
+    pub side: OrderSide,
+    pub price: f64,
+    pub amount: f64,
+    pub timestamp: u64,
+    pub trade_id: String,
+}
+
+/// Market data event, the same whichever exchange it came from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MarketEvent {
+    /// Top of the order book as it stands now
+    Book(OrderBook),
+    Trade(MarketTrade),
+}
+
+impl MarketEvent {
+    pub fn symbol(&self) -> &Symbol {
+        match self {
+            MarketEvent::Book(book) => &book.symbol,
+            MarketEvent::Trade(trade) => &trade.symbol,
+        }
+    }
+
+    /// Bus subject the event is published on, e.g. `cex.binance.book.BTCUSDT`
+    pub fn subject(&self, exchange: &ExchangeId) -> String {
+        let kind = match self {
+            MarketEvent::Book(_) => "book",
+            MarketEvent::Trade(_) => "trade",
+        };
+        format!("cex.{}.{}.{}", exchange.0, kind, self.symbol().0.replace('/', ""))
+    }
+}
+
+/// Live market data of the symbols subscribed to
+///
+/// The connection behind it is closed when the stream is dropped.
+pub struct MarketStream {
+    events: mpsc::Receiver<MarketEvent>,
+    task: JoinHandle<()>,
+}
+
+impl MarketStream {
+    pub fn new(events: mpsc::Receiver<MarketEvent>, task: JoinHandle<()>) -> Self {
+        Self { events, task }
+    }
+
+    /// Next event, or `None` once the stream has ended
+    pub async fn recv(&mut self) -> Option<MarketEvent> {
+        self.events.recv().await
+    }
+}
+
+impl Drop for MarketStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
 /// CEX client trait that all exchange implementations should implement
 #[async_trait]
 pub trait CexClient {
@@ -98,6 +168,15 @@ pub trait CexClient {
     async fn get_balances(&self) -> Result<std::collections::HashMap<String, f64>>;
 }
 
+/// Connection to one exchange, for trading and market data
+#[async_trait]
+pub trait ExchangeAdapter: CexClient + Send + Sync {
+    fn exchange_id(&self) -> &ExchangeId;
+
+    /// Stream order book snapshots and trades of `symbols`
+    async fn subscribe_market(&self, symbols: &[Symbol]) -> Result<MarketStream>;
+}
+
 /// Main CEX client that can connect to different exchanges
 pub struct Client {
     exchange_id: ExchangeId,
@@ -133,6 +212,20 @@ impl Client {
     pub fn ws_endpoint(&self) -> &str {
         &self.ws_endpoint
     }
+    
+    /// Adapter for the client's exchange
+    pub fn adapter(&self) -> Result<Box<dyn ExchangeAdapter>> {
+        let credentials = (!self.api_key.is_empty())
+            .then(|| auth::Credentials::new(self.api_key.clone(), self.api_secret.clone()));
+        match self.exchange_id.0.as_str() {
+            "binance" => Ok(Box::new(binance::BinanceAdapter::new(
+                &self.rest_endpoint,
+                &self.ws_endpoint,
+                credentials,
+            )?)),
+            other => Err(anyhow::anyhow!("No adapter for exchange {}", other)),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -154,6 +247,27 @@ mod tests {
         assert_eq!(client.ws_endpoint(), "wss://stream.binance.com:9443");
     }
     
+    #[test]
+    fn test_client_adapter() {
+        let client = Client::new(
+            ExchangeId("binance".to_string()),
+            String::new(),
+            String::new(),
+            binance::TESTNET_REST_ENDPOINT.to_string(),
+            binance::TESTNET_WS_ENDPOINT.to_string(),
+        );
+        assert_eq!(client.adapter().unwrap().exchange_id().0, "binance");
+        
+        let unknown = Client::new(
+            ExchangeId("mtgox".to_string()),
+            String::new(),
+            String::new(),
+            String::new(),
+            String::new(),
+        );
+        assert!(unknown.adapter().is_err());
+    }
+    
     #[test]
     fn test_symbol_creation() {
         let symbol = Symbol("BTC/USDT".to_string());
@@ -1 +1,120 @@
-pub fn placeholder() { /* TODO: implement */ }
+//! REST transport shared by exchange adapters.
+//!
+//! Public requests go out as they are. Signed requests get a timestamp and
+//! receive window appended to their query, then a signature over the whole
+//! query, with the API key in a header. Non-success responses are turned
+//! into errors carrying the exchange's own code and message when it sends
+//! them as `{"code", "msg"}`.
+
+use crate::auth::Credentials;
+use anyhow::Result;
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Window a signed request stays valid for after its timestamp
+pub const DEFAULT_RECV_WINDOW_MS: u64 = 5_000;
+
+/// Error body sent by exchanges with Binance style APIs
+#[derive(Deserialize)]
+struct ApiError {
+    code: i64,
+    msg: String,
+}
+
+/// HTTP client bound to an exchange's REST endpoint
+#[derive(Debug, Clone)]
+pub struct RestClient {
+    http: reqwest::Client,
+    endpoint: String,
+    credentials: Option<Credentials>,
+    api_key_header: String,
+    recv_window_ms: u64,
+}
+
+impl RestClient {
+    /// Create a client for `endpoint`, signing with `credentials` if given
+    pub fn new(endpoint: &str, credentials: Option<Credentials>, api_key_header: &str) -> Result<Self> {
+        Ok(Self {
+            http: reqwest::Client::builder().timeout(Duration::from_secs(10)).build()?,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            credentials,
+            api_key_header: api_key_header.to_string(),
+            recv_window_ms: DEFAULT_RECV_WINDOW_MS,
+        })
+    }
+
+    pub fn with_recv_window(mut self, recv_window_ms: u64) -> Self {
+        self.recv_window_ms = recv_window_ms;
+        self
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Send an unauthenticated request
+    pub async fn public<T: DeserializeOwned>(&self, method: Method, path: &str, params: &[(&str, String)]) -> Result<T> {
+        let url = format!("{}{}?{}", self.endpoint, path, encode(params));
+        self.send(self.http.request(method, url)).await
+    }
+
+    /// Send a request signed with the client's credentials
+    pub async fn signed<T: DeserializeOwned>(&self, method: Method, path: &str, params: &[(&str, String)]) -> Result<T> {
+        let credentials = self
+            .credentials
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("{} needs API credentials", path))?;
+        let mut query = encode(params);
+        if !query.is_empty() {
+            query.push('&');
+        }
+        query.push_str(&format!("recvWindow={}&timestamp={}", self.recv_window_ms, now_ms()));
+        let signature = credentials.sign(&query);
+        let url = format!("{}{}?{}&signature={}", self.endpoint, path, query, signature);
+        self.send(self.http.request(method, url).header(&self.api_key_header, &credentials.api_key))
+            .await
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<T> {
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(match serde_json::from_str::<ApiError>(&body) {
+                Ok(error) => anyhow::anyhow!("Exchange error {}: {}", error.code, error.msg),
+                Err(_) => anyhow::anyhow!("Exchange returned {}: {}", status, body),
+            });
+        }
+        serde_json::from_str(&body).map_err(|e| anyhow::anyhow!("Unexpected exchange response {}: {}", body, e))
+    }
+}
+
+/// Query string of `params`, percent-encoding values
+fn encode(params: &[(&str, String)]) -> String {
+    params
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, url::form_urlencoded::byte_serialize(value.as_bytes()).collect::<String>()))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encodes_query() {
+        let params = [("symbol", "BTCUSDT".to_string()), ("newClientOrderId", "a b&c".to_string())];
+        assert_eq!(encode(&params), "symbol=BTCUSDT&newClientOrderId=a+b%26c");
+        assert_eq!(encode(&[]), "");
+    }
+}
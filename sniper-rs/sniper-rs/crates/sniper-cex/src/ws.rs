@@ -1 +1,72 @@
-pub fn placeholder() { /* TODO: implement */ }
+//! WebSocket market data transport shared by exchange adapters.
+//!
+//! [`spawn_stream`] keeps a connection to a stream URL open, reconnecting
+//! with exponential backoff, and hands every text frame to the adapter's
+//! parser; pings are answered by the socket itself. The normalized events
+//! the parser returns are forwarded to the stream's receiver, and the task
+//! ends once the receiver is dropped.
+
+use crate::MarketEvent;
+use anyhow::Result;
+use futures::StreamExt;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Events buffered for a slow consumer before the connection waits on it
+pub const STREAM_BUFFER: usize = 1_024;
+
+/// Stream `url`, parsing each text frame with `parse`, until `events` is dropped
+pub fn spawn_stream<F>(url: String, parse: F, events: mpsc::Sender<MarketEvent>) -> JoinHandle<()>
+where
+    F: Fn(&str) -> Result<Vec<MarketEvent>> + Send + Sync + 'static,
+{
+    tokio::spawn(async move {
+        let mut backoff = MIN_BACKOFF;
+        loop {
+            let connected_at = Instant::now();
+            match forward(&url, &parse, &events).await {
+                Ok(()) if events.is_closed() => return,
+                Ok(()) => tracing::warn!("Market stream {} closed, reconnecting", url),
+                Err(e) => tracing::warn!("Market stream {} failed: {}", url, e),
+            }
+            if events.is_closed() {
+                return;
+            }
+            if connected_at.elapsed() > MAX_BACKOFF {
+                backoff = MIN_BACKOFF;
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    })
+}
+
+async fn forward<F>(url: &str, parse: &F, events: &mpsc::Sender<MarketEvent>) -> Result<()>
+where
+    F: Fn(&str) -> Result<Vec<MarketEvent>>,
+{
+    let (mut socket, _) = tokio_tungstenite::connect_async(url).await?;
+    tracing::info!("Market stream connected to {}", url);
+    while let Some(message) = socket.next().await {
+        match message? {
+            Message::Text(text) => match parse(&text) {
+                Ok(parsed) => {
+                    for event in parsed {
+                        if events.send(event).await.is_err() {
+                            return Ok(());
+                        }
+                    }
+                }
+                Err(e) => tracing::debug!("Skipping market stream frame: {}", e),
+            },
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+    Ok(())
+}
@@ -1,3 +1,17 @@
+//! Chain access for the sniper bot.
+//!
+//! This crate manages connections to chain RPC endpoints, including latency
+//! tracking and hedged requests across the fastest healthy endpoints, speaks
+//! JSON-RPC over them and encodes the transactions submitted.
+
+pub mod providers;
+pub mod rpc;
+pub mod tx;
+
+pub use providers::{EndpointStats, HedgeConfig, HedgedResponse, ProviderPool, RpcEndpoint};
+pub use rpc::{FeeHistory, JsonRpcClient, TransactionReceipt};
+pub use tx::Eip1559Transaction;
+
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
 }
@@ -1 +1,378 @@
-pub fn placeholder() { /* TODO: implement */ }
+//! RPC endpoint pool with latency tracking and hedged requests
+//!
+//! Every call records the endpoint's latency and outcome. Time-critical reads
+//! and submissions go through [`ProviderPool::hedged`]: the request is sent to
+//! the fastest healthy endpoint, then to the runner-up after a short stagger
+//! if the first has not answered, and the first success wins. The losing
+//! request is dropped, which cancels it. Win rates per endpoint show whether
+//! the hedge is paying for itself.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// An RPC endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcEndpoint {
+    pub name: String,
+    pub url: String,
+}
+
+/// Hedging and health settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HedgeConfig {
+    /// Delay before the request is also sent to the second endpoint
+    pub stagger_ms: u64,
+    /// Consecutive failures after which an endpoint is skipped
+    pub max_consecutive_failures: u32,
+    /// Time after which an unhealthy endpoint is tried again
+    pub retry_after_ms: u64,
+    /// Weight of the newest sample in the latency moving average
+    pub latency_alpha: f64,
+}
+
+impl Default for HedgeConfig {
+    fn default() -> Self {
+        Self {
+            stagger_ms: 20,
+            max_consecutive_failures: 3,
+            retry_after_ms: 30_000,
+            latency_alpha: 0.2,
+        }
+    }
+}
+
+/// Observed behaviour of one endpoint
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EndpointStats {
+    /// Moving average latency of successful calls, `None` until one succeeds
+    pub latency_ms: Option<f64>,
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    /// Hedged races the endpoint took part in
+    pub races: u64,
+    /// Hedged races the endpoint answered first
+    pub wins: u64,
+    #[serde(skip)]
+    last_failure: Option<Instant>,
+}
+
+impl EndpointStats {
+    /// Share of hedged races won
+    pub fn win_rate(&self) -> f64 {
+        if self.races == 0 {
+            return 0.0;
+        }
+        self.wins as f64 / self.races as f64
+    }
+}
+
+/// Result of a hedged request
+#[derive(Debug, Clone)]
+pub struct HedgedResponse<T> {
+    pub value: T,
+    /// Endpoint whose answer was used
+    pub endpoint: String,
+    /// Whether the request was also sent to a second endpoint
+    pub hedged: bool,
+    pub latency: Duration,
+}
+
+/// Pool of RPC endpoints for one chain
+pub struct ProviderPool {
+    endpoints: Vec<RpcEndpoint>,
+    stats: Mutex<HashMap<String, EndpointStats>>,
+    config: HedgeConfig,
+}
+
+impl ProviderPool {
+    /// Create a pool over a set of endpoints
+    pub fn new(endpoints: Vec<RpcEndpoint>, config: HedgeConfig) -> Self {
+        let stats = endpoints
+            .iter()
+            .map(|endpoint| (endpoint.name.clone(), EndpointStats::default()))
+            .collect();
+        Self {
+            endpoints,
+            stats: Mutex::new(stats),
+            config,
+        }
+    }
+
+    /// Get the hedging settings
+    pub fn config(&self) -> &HedgeConfig {
+        &self.config
+    }
+
+    /// Snapshot of every endpoint's stats
+    pub fn stats(&self) -> HashMap<String, EndpointStats> {
+        self.stats.lock().map(|stats| stats.clone()).unwrap_or_default()
+    }
+
+    /// Hedged race win rate per endpoint
+    pub fn win_rates(&self) -> HashMap<String, f64> {
+        self.stats()
+            .into_iter()
+            .map(|(name, stats)| (name, stats.win_rate()))
+            .collect()
+    }
+
+    /// Healthy endpoints, fastest first
+    ///
+    /// Endpoints without a latency sample yet rank first so they get measured.
+    pub fn ranked_endpoints(&self) -> Vec<RpcEndpoint> {
+        let stats = match self.stats.lock() {
+            Ok(stats) => stats,
+            Err(_) => return self.endpoints.clone(),
+        };
+        let retry_after = Duration::from_millis(self.config.retry_after_ms);
+        let mut ranked: Vec<(f64, &RpcEndpoint)> = self
+            .endpoints
+            .iter()
+            .filter_map(|endpoint| {
+                let stats = stats.get(&endpoint.name)?;
+                let healthy = stats.consecutive_failures < self.config.max_consecutive_failures
+                    || stats.last_failure.map(|at| at.elapsed() >= retry_after).unwrap_or(true);
+                healthy.then(|| (stats.latency_ms.unwrap_or(0.0), endpoint))
+            })
+            .collect();
+        ranked.sort_by(|a, b| a.0.total_cmp(&b.0));
+        ranked.into_iter().map(|(_, endpoint)| endpoint.clone()).collect()
+    }
+
+    /// Record a successful call
+    pub fn record_success(&self, endpoint: &str, latency: Duration) {
+        let alpha = self.config.latency_alpha;
+        self.update(endpoint, |stats| {
+            let sample = latency.as_secs_f64() * 1000.0;
+            stats.latency_ms = Some(match stats.latency_ms {
+                Some(average) => average * (1.0 - alpha) + sample * alpha,
+                None => sample,
+            });
+            stats.successes += 1;
+            stats.consecutive_failures = 0;
+        });
+    }
+
+    /// Record a failed call
+    pub fn record_failure(&self, endpoint: &str) {
+        self.update(endpoint, |stats| {
+            stats.failures += 1;
+            stats.consecutive_failures += 1;
+            stats.last_failure = Some(Instant::now());
+        });
+    }
+
+    /// Send a request to the fastest healthy endpoint
+    pub async fn call<T, F, Fut>(&self, request: F) -> Result<HedgedResponse<T>>
+    where
+        F: Fn(RpcEndpoint) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let endpoint = self
+            .ranked_endpoints()
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No healthy RPC endpoint"))?;
+        let started = Instant::now();
+        let value = self.observe(&endpoint.name, started, request(endpoint.clone()).await)?;
+        Ok(HedgedResponse {
+            value,
+            endpoint: endpoint.name,
+            hedged: false,
+            latency: started.elapsed(),
+        })
+    }
+
+    /// Race a request across the two fastest healthy endpoints
+    ///
+    /// The second endpoint is only tried once the stagger elapses without an
+    /// answer, or straight away if the first fails. The first success is
+    /// returned and the other request is cancelled.
+    pub async fn hedged<T, F, Fut>(&self, request: F) -> Result<HedgedResponse<T>>
+    where
+        F: Fn(RpcEndpoint) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut ranked = self.ranked_endpoints().into_iter();
+        let primary = ranked.next().ok_or_else(|| anyhow::anyhow!("No healthy RPC endpoint"))?;
+        let secondary = match ranked.next() {
+            Some(secondary) => secondary,
+            None => return self.call(request).await,
+        };
+
+        let started = Instant::now();
+        let primary_call = request(primary.clone());
+        tokio::pin!(primary_call);
+        let stagger = tokio::time::sleep(Duration::from_millis(self.config.stagger_ms));
+        tokio::pin!(stagger);
+
+        // Give the fastest endpoint a head start
+        let mut primary_error = None;
+        tokio::select! {
+            result = &mut primary_call => match self.observe(&primary.name, started, result) {
+                Ok(value) => {
+                    return Ok(HedgedResponse {
+                        value,
+                        endpoint: primary.name,
+                        hedged: false,
+                        latency: started.elapsed(),
+                    });
+                },
+                Err(e) => primary_error = Some(e),
+            },
+            _ = &mut stagger => {},
+        }
+
+        let secondary_started = Instant::now();
+        let secondary_call = request(secondary.clone());
+        tokio::pin!(secondary_call);
+        self.update(&primary.name, |stats| stats.races += 1);
+        self.update(&secondary.name, |stats| stats.races += 1);
+
+        let mut secondary_error = None;
+        loop {
+            tokio::select! {
+                result = &mut primary_call, if primary_error.is_none() => {
+                    match self.observe(&primary.name, started, result) {
+                        Ok(value) => return Ok(self.won(&primary, value, started)),
+                        Err(e) => primary_error = Some(e),
+                    }
+                },
+                result = &mut secondary_call, if secondary_error.is_none() => {
+                    match self.observe(&secondary.name, secondary_started, result) {
+                        Ok(value) => return Ok(self.won(&secondary, value, started)),
+                        Err(e) => secondary_error = Some(e),
+                    }
+                },
+            }
+
+            if let (Some(first), Some(second)) = (&primary_error, &secondary_error) {
+                return Err(anyhow::anyhow!(
+                    "Hedged request failed on {} ({}) and {} ({})",
+                    primary.name, first, secondary.name, second
+                ));
+            }
+        }
+    }
+
+    /// Record the winner of a hedged race
+    fn won<T>(&self, endpoint: &RpcEndpoint, value: T, started: Instant) -> HedgedResponse<T> {
+        self.update(&endpoint.name, |stats| stats.wins += 1);
+        tracing::debug!("hedged RPC request won by {}", endpoint.name);
+        HedgedResponse {
+            value,
+            endpoint: endpoint.name.clone(),
+            hedged: true,
+            latency: started.elapsed(),
+        }
+    }
+
+    /// Record the outcome of a call and pass it through
+    fn observe<T>(&self, endpoint: &str, started: Instant, result: Result<T>) -> Result<T> {
+        match &result {
+            Ok(_) => self.record_success(endpoint, started.elapsed()),
+            Err(e) => {
+                tracing::warn!("RPC request to {} failed: {}", endpoint, e);
+                self.record_failure(endpoint);
+            },
+        }
+        result
+    }
+
+    fn update(&self, endpoint: &str, apply: impl FnOnce(&mut EndpointStats)) {
+        if let Ok(mut stats) = self.stats.lock() {
+            if let Some(stats) = stats.get_mut(endpoint) {
+                apply(stats);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn pool(names: &[&str]) -> ProviderPool {
+        let endpoints = names
+            .iter()
+            .map(|name| RpcEndpoint {
+                name: name.to_string(),
+                url: format!("https://{}.example", name),
+            })
+            .collect();
+        ProviderPool::new(endpoints, HedgeConfig::default())
+    }
+
+    #[tokio::test]
+    async fn test_fast_primary_is_not_hedged() {
+        let pool = pool(&["a", "b"]);
+        let sent = AtomicUsize::new(0);
+
+        let response = pool
+            .hedged(|endpoint| {
+                sent.fetch_add(1, Ordering::SeqCst);
+                async move { Ok(endpoint.name) }
+            })
+            .await
+            .unwrap();
+        assert!(!response.hedged);
+        assert_eq!(response.value, response.endpoint);
+        assert_eq!(sent.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_slow_primary_loses_race() {
+        let pool = pool(&["slow", "fast"]);
+        pool.record_success("slow", Duration::from_millis(1));
+        pool.record_success("fast", Duration::from_millis(5));
+
+        let response = pool
+            .hedged(|endpoint| async move {
+                if endpoint.name == "slow" {
+                    tokio::time::sleep(Duration::from_millis(500)).await;
+                }
+                Ok(endpoint.name)
+            })
+            .await
+            .unwrap();
+        assert!(response.hedged);
+        assert_eq!(response.endpoint, "fast");
+
+        let rates = pool.win_rates();
+        assert_eq!(rates["fast"], 1.0);
+        assert_eq!(rates["slow"], 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_failures_fall_over_and_mark_unhealthy() {
+        let pool = pool(&["broken", "ok"]);
+        pool.record_success("broken", Duration::from_millis(1));
+        pool.record_success("ok", Duration::from_millis(5));
+
+        for _ in 0..3 {
+            let response = pool
+                .hedged(|endpoint| async move {
+                    if endpoint.name == "broken" {
+                        return Err(anyhow::anyhow!("connection refused"));
+                    }
+                    Ok(endpoint.name)
+                })
+                .await
+                .unwrap();
+            assert_eq!(response.endpoint, "ok");
+        }
+
+        // The broken endpoint is skipped until its retry window passes
+        let ranked: Vec<String> = pool.ranked_endpoints().into_iter().map(|e| e.name).collect();
+        assert_eq!(ranked, vec!["ok".to_string()]);
+
+        let result = pool.hedged(|_| async { Err::<(), _>(anyhow::anyhow!("down")) }).await;
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,256 @@
+//! Ethereum JSON-RPC client over the provider pool.
+//!
+//! Reads go to the fastest healthy endpoint. Raw transactions are submitted
+//! hedged across the two fastest: both requests carry the same signed
+//! transaction, so whichever node answers first, only one can be mined.
+
+use crate::providers::{ProviderPool, RpcEndpoint};
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Timeout of a single HTTP request to an endpoint
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Base fees, fullness and priority fees of recent blocks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeHistory {
+    pub oldest_block: String,
+    /// One base fee per block, followed by the base fee of the next block
+    pub base_fee_per_gas: Vec<String>,
+    /// Gas used over the gas limit, per block
+    pub gas_used_ratio: Vec<f64>,
+    /// Priority fees at the requested percentiles, per block
+    #[serde(default)]
+    pub reward: Option<Vec<Vec<String>>>,
+}
+
+/// Receipt of a mined transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionReceipt {
+    pub transaction_hash: String,
+    pub block_number: String,
+    /// `0x1` on success, `0x0` if the transaction reverted
+    pub status: String,
+    pub gas_used: String,
+    #[serde(default)]
+    pub effective_gas_price: Option<String>,
+}
+
+impl TransactionReceipt {
+    /// Block the transaction was mined in
+    pub fn block(&self) -> Result<u64> {
+        Ok(parse_quantity(&self.block_number)? as u64)
+    }
+
+    /// Whether the transaction executed without reverting
+    pub fn succeeded(&self) -> bool {
+        parse_quantity(&self.status).map(|status| status == 1).unwrap_or(false)
+    }
+
+    /// Gas used by the transaction
+    pub fn gas_used(&self) -> Result<u64> {
+        Ok(parse_quantity(&self.gas_used)? as u64)
+    }
+
+    /// Fees paid, from the gas used and the effective gas price
+    pub fn fees_paid_wei(&self) -> Result<u128> {
+        let price = match &self.effective_gas_price {
+            Some(price) => parse_quantity(price)?,
+            None => 0,
+        };
+        Ok(self.gas_used()? as u128 * price)
+    }
+}
+
+/// JSON-RPC client for one chain
+pub struct JsonRpcClient {
+    http: reqwest::Client,
+    pool: Arc<ProviderPool>,
+    next_id: AtomicU64,
+}
+
+impl JsonRpcClient {
+    /// Create a client sending requests through a provider pool
+    pub fn new(pool: Arc<ProviderPool>) -> Result<Self> {
+        let http = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+        Ok(Self {
+            http,
+            pool,
+            next_id: AtomicU64::new(1),
+        })
+    }
+
+    /// Get the provider pool
+    pub fn pool(&self) -> &Arc<ProviderPool> {
+        &self.pool
+    }
+
+    /// Send a request to the fastest healthy endpoint
+    ///
+    /// Errors answered by the node, such as reverted calls, are returned
+    /// without counting against the endpoint's health.
+    pub async fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let response = self.pool.call(|endpoint| self.send(endpoint, method, params.clone())).await?;
+        response.value
+    }
+
+    /// Latest block number
+    pub async fn block_number(&self) -> Result<u64> {
+        let block: String = self.request("eth_blockNumber", json!([])).await?;
+        Ok(parse_quantity(&block)? as u64)
+    }
+
+    /// Next nonce of an account, counting its pending transactions
+    pub async fn transaction_count(&self, address: &str) -> Result<u64> {
+        let count: String = self.request("eth_getTransactionCount", json!([address, "pending"])).await?;
+        Ok(parse_quantity(&count)? as u64)
+    }
+
+    /// Number of mined transactions of an account, which is the nonce its
+    /// oldest unmined transaction must carry
+    pub async fn mined_transaction_count(&self, address: &str) -> Result<u64> {
+        let count: String = self.request("eth_getTransactionCount", json!([address, "latest"])).await?;
+        Ok(parse_quantity(&count)? as u64)
+    }
+
+    /// Chain ID of the connected chain
+    pub async fn chain_id(&self) -> Result<u64> {
+        let chain_id: String = self.request("eth_chainId", json!([])).await?;
+        Ok(parse_quantity(&chain_id)? as u64)
+    }
+
+    /// Fee history of the latest `block_count` blocks, with the priority
+    /// fees paid at each of `percentiles`
+    pub async fn fee_history(&self, block_count: u64, percentiles: &[f64]) -> Result<FeeHistory> {
+        let params = json!([format_quantity(block_count as u128), "latest", percentiles]);
+        self.request("eth_feeHistory", params).await
+    }
+
+    /// Gas a call is estimated to use
+    pub async fn estimate_gas(&self, call: Value) -> Result<u64> {
+        let gas: String = self.request("eth_estimateGas", json!([call])).await?;
+        Ok(parse_quantity(&gas)? as u64)
+    }
+
+    /// Return data of a call executed against `block` without sending a
+    /// transaction, with optional state overrides by address
+    pub async fn call(&self, call: Value, block: &str, overrides: Option<Value>) -> Result<Vec<u8>> {
+        let params = match overrides {
+            Some(overrides) => json!([call, block, overrides]),
+            None => json!([call, block]),
+        };
+        let data: String = self.request("eth_call", params).await?;
+        let digits = data.strip_prefix("0x").unwrap_or(&data);
+        hex::decode(digits).map_err(|e| anyhow::anyhow!("Invalid eth_call return data {}: {}", data, e))
+    }
+
+    /// Submit a raw signed transaction, returning its hash
+    pub async fn send_raw_transaction(&self, raw: &[u8]) -> Result<String> {
+        let params = json!([format!("0x{}", hex::encode(raw))]);
+        let response = self
+            .pool
+            .hedged(|endpoint| {
+                let params = params.clone();
+                // A rejection by one node falls over to the other, which may still accept it
+                async move { self.send::<String>(endpoint, "eth_sendRawTransaction", params).await? }
+            })
+            .await?;
+        tracing::debug!("raw transaction submitted via {} (hedged: {})", response.endpoint, response.hedged);
+        Ok(response.value)
+    }
+
+    /// Receipt of a transaction, `None` until it is mined
+    pub async fn transaction_receipt(&self, hash: &str) -> Result<Option<TransactionReceipt>> {
+        self.request("eth_getTransactionReceipt", json!([hash])).await
+    }
+
+    /// Send a request, failing on transport errors and returning the node's answer
+    async fn send<T: DeserializeOwned>(&self, endpoint: RpcEndpoint, method: &str, params: Value) -> Result<Result<T>> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id.fetch_add(1, Ordering::Relaxed),
+            "method": method,
+            "params": params,
+        });
+        let mut response: Value = self
+            .http
+            .post(&endpoint.url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if let Some(error) = response.get("error") {
+            let message = error.get("message").and_then(Value::as_str).unwrap_or("unknown error");
+            return Ok(Err(anyhow::anyhow!("{} failed on {}: {}", method, endpoint.name, message)));
+        }
+        let result = response
+            .get_mut("result")
+            .map(Value::take)
+            .ok_or_else(|| anyhow::anyhow!("{} response from {} has no result", method, endpoint.name))?;
+        Ok(serde_json::from_value(result).map_err(Into::into))
+    }
+}
+
+/// Parse a `0x` hex quantity
+pub fn parse_quantity(quantity: &str) -> Result<u128> {
+    let digits = quantity
+        .strip_prefix("0x")
+        .ok_or_else(|| anyhow::anyhow!("Quantity {} is not 0x-prefixed", quantity))?;
+    if digits.is_empty() {
+        return Err(anyhow::anyhow!("Quantity {} has no digits", quantity));
+    }
+    u128::from_str_radix(digits, 16).map_err(|e| anyhow::anyhow!("Invalid quantity {}: {}", quantity, e))
+}
+
+/// Format a value as a `0x` hex quantity
+pub fn format_quantity(value: u128) -> String {
+    format!("0x{:x}", value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantities() {
+        assert_eq!(parse_quantity("0x0").unwrap(), 0);
+        assert_eq!(parse_quantity("0x1b4").unwrap(), 436);
+        assert!(parse_quantity("1b4").is_err());
+        assert!(parse_quantity("0x").is_err());
+        assert_eq!(format_quantity(436), "0x1b4");
+        assert_eq!(format_quantity(0), "0x0");
+    }
+
+    #[test]
+    fn test_receipt_fields() {
+        let receipt: TransactionReceipt = serde_json::from_value(json!({
+            "transactionHash": "0xabc",
+            "blockNumber": "0x10",
+            "status": "0x1",
+            "gasUsed": "0x5208",
+            "effectiveGasPrice": "0x3b9aca00",
+            "logs": [],
+        }))
+        .unwrap();
+        assert_eq!(receipt.block().unwrap(), 16);
+        assert!(receipt.succeeded());
+        assert_eq!(receipt.gas_used().unwrap(), 21_000);
+        assert_eq!(receipt.fees_paid_wei().unwrap(), 21_000_000_000_000);
+
+        let reverted = TransactionReceipt {
+            status: "0x0".to_string(),
+            ..receipt
+        };
+        assert!(!reverted.succeeded());
+    }
+}
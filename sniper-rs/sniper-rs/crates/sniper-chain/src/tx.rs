@@ -0,0 +1,176 @@
+//! EIP-1559 transaction encoding.
+//!
+//! Builds the payload a signer signs and the raw signed transaction sent with
+//! `eth_sendRawTransaction`: the type byte `0x02` followed by the RLP list of
+//! the fields, with the signature's y parity, `r` and `s` appended once
+//! signed. Access lists are always empty.
+
+use anyhow::Result;
+use sha3::{Digest, Keccak256};
+
+/// EIP-2718 type of EIP-1559 transactions
+pub const EIP1559_TX_TYPE: u8 = 0x02;
+
+/// Unsigned EIP-1559 transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Eip1559Transaction {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub max_priority_fee_per_gas: u128,
+    pub max_fee_per_gas: u128,
+    pub gas_limit: u64,
+    pub to: [u8; 20],
+    pub value: u128,
+    pub data: Vec<u8>,
+}
+
+impl Eip1559Transaction {
+    /// Hash the signer signs
+    pub fn signing_hash(&self) -> [u8; 32] {
+        let mut payload = vec![EIP1559_TX_TYPE];
+        payload.extend(rlp::encode_list(&self.fields()));
+        Keccak256::digest(&payload).into()
+    }
+
+    /// Raw transaction carrying a signature over [`Self::signing_hash`]
+    pub fn encode_signed(&self, y_parity: bool, r: &[u8; 32], s: &[u8; 32]) -> Vec<u8> {
+        let mut fields = self.fields();
+        fields.push(rlp::encode_uint(y_parity as u128));
+        fields.push(rlp::encode_bytes(strip_leading_zeros(r)));
+        fields.push(rlp::encode_bytes(strip_leading_zeros(s)));
+
+        let mut raw = vec![EIP1559_TX_TYPE];
+        raw.extend(rlp::encode_list(&fields));
+        raw
+    }
+
+    fn fields(&self) -> Vec<Vec<u8>> {
+        vec![
+            rlp::encode_uint(self.chain_id as u128),
+            rlp::encode_uint(self.nonce as u128),
+            rlp::encode_uint(self.max_priority_fee_per_gas),
+            rlp::encode_uint(self.max_fee_per_gas),
+            rlp::encode_uint(self.gas_limit as u128),
+            rlp::encode_bytes(&self.to),
+            rlp::encode_uint(self.value),
+            rlp::encode_bytes(&self.data),
+            // Empty access list
+            rlp::encode_list(&[]),
+        ]
+    }
+}
+
+/// Hash of a raw signed transaction, as reported by nodes
+pub fn transaction_hash(raw: &[u8]) -> [u8; 32] {
+    Keccak256::digest(raw).into()
+}
+
+/// Parse a `0x`-prefixed hex address
+pub fn parse_address(address: &str) -> Result<[u8; 20]> {
+    let bytes = hex::decode(address.trim_start_matches("0x"))
+        .map_err(|e| anyhow::anyhow!("Invalid address {}: {}", address, e))?;
+    bytes
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Invalid address {}: expected 20 bytes", address))
+}
+
+fn strip_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|byte| *byte != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+/// Recursive length prefix encoding
+mod rlp {
+    pub fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+        if bytes.len() == 1 && bytes[0] < 0x80 {
+            return bytes.to_vec();
+        }
+        let mut encoded = length_prefix(bytes.len(), 0x80);
+        encoded.extend_from_slice(bytes);
+        encoded
+    }
+
+    /// Integers are big-endian without leading zeros, zero being empty
+    pub fn encode_uint(value: u128) -> Vec<u8> {
+        let bytes = value.to_be_bytes();
+        encode_bytes(super::strip_leading_zeros(&bytes))
+    }
+
+    pub fn encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+        let payload: Vec<u8> = items.concat();
+        let mut encoded = length_prefix(payload.len(), 0xc0);
+        encoded.extend(payload);
+        encoded
+    }
+
+    fn length_prefix(len: usize, offset: u8) -> Vec<u8> {
+        if len <= 55 {
+            return vec![offset + len as u8];
+        }
+        let len_bytes = len.to_be_bytes();
+        let len_bytes = super::strip_leading_zeros(&len_bytes);
+        let mut prefix = vec![offset + 55 + len_bytes.len() as u8];
+        prefix.extend_from_slice(len_bytes);
+        prefix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swap() -> Eip1559Transaction {
+        Eip1559Transaction {
+            chain_id: 1,
+            nonce: 7,
+            max_priority_fee_per_gas: 2_000_000_000,
+            max_fee_per_gas: 50_000_000_000,
+            gas_limit: 210_000,
+            to: parse_address("0x7a250d5630b4cf539739df2c5dacb4c659f2488d").unwrap(),
+            value: 1_000_000_000_000_000_000,
+            data: hex::decode("7ff36ab5").unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_rlp() {
+        assert_eq!(rlp::encode_bytes(b"dog"), hex::decode("83646f67").unwrap());
+        assert_eq!(
+            rlp::encode_list(&[rlp::encode_bytes(b"cat"), rlp::encode_bytes(b"dog")]),
+            hex::decode("c88363617483646f67").unwrap()
+        );
+        assert_eq!(rlp::encode_uint(0), vec![0x80]);
+        assert_eq!(rlp::encode_uint(15), vec![0x0f]);
+        assert_eq!(rlp::encode_uint(1024), vec![0x82, 0x04, 0x00]);
+        assert_eq!(rlp::encode_bytes(&[b'a'; 56])[..2], [0xb8, 0x38]);
+    }
+
+    #[test]
+    fn test_signing_hash_and_raw_encoding() {
+        let tx = swap();
+        assert_eq!(
+            hex::encode(tx.signing_hash()),
+            "4d8f2cdbf2c487be79cd2eb7f13b01766f54a8175df7911cc82580ed353bcaad"
+        );
+
+        let raw = tx.encode_signed(true, &[1; 32], &[2; 32]);
+        assert_eq!(
+            hex::encode(&raw),
+            "02f87801078477359400850ba43b740083033450947a250d5630b4cf539739df2c5dacb4c659f2488d\
+             880de0b6b3a7640000847ff36ab5c001a0010101010101010101010101010101010101010101010101\
+             0101010101010101a002020202020202020202020202020202020202020202020202020202020202\
+             02"
+        );
+        assert_eq!(
+            hex::encode(transaction_hash(&raw)),
+            "cbf1b257dc8623f1bd55e110329bf84e88eb734106265510468142adbc14a6b4"
+        );
+    }
+
+    #[test]
+    fn test_parse_address() {
+        assert!(parse_address("0x7a250d5630b4cf539739df2c5dacb4c659f2488d").is_ok());
+        assert!(parse_address("0x7a250d").is_err());
+        assert!(parse_address("0xRouter").is_err());
+    }
+}
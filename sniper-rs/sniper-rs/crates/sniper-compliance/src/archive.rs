@@ -0,0 +1,110 @@
+//! Minimal ustar archive writer and reader for backup archives.
+//!
+//! Backups only ever hold flat, regular files, so this covers exactly that:
+//! entry names up to 100 bytes, no directories, links or extended headers.
+
+use anyhow::Result;
+
+const BLOCK: usize = 512;
+const NAME_LEN: usize = 100;
+
+/// Build a tar archive from named file contents
+pub(crate) fn write(entries: &[(String, Vec<u8>)], mtime: u64) -> Result<Vec<u8>> {
+    let mut archive = Vec::new();
+    for (name, data) in entries {
+        archive.extend_from_slice(&header(name, data.len(), mtime)?);
+        archive.extend_from_slice(data);
+        archive.resize(archive.len().next_multiple_of(BLOCK), 0);
+    }
+    // End of archive marker
+    archive.resize(archive.len() + 2 * BLOCK, 0);
+    Ok(archive)
+}
+
+/// Read the named file contents back out of a tar archive
+pub(crate) fn read(archive: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    while offset + BLOCK <= archive.len() {
+        let header = &archive[offset..offset + BLOCK];
+        if header.iter().all(|&byte| byte == 0) {
+            return Ok(entries);
+        }
+        if checksum(header) != parse_octal(&header[148..156])? {
+            return Err(anyhow::anyhow!("Corrupt tar header at offset {}", offset));
+        }
+        let name_end = header[..NAME_LEN].iter().position(|&byte| byte == 0).unwrap_or(NAME_LEN);
+        let name = std::str::from_utf8(&header[..name_end])?.to_string();
+        let size = parse_octal(&header[124..136])? as usize;
+        let start = offset + BLOCK;
+        let data = archive
+            .get(start..start + size)
+            .ok_or_else(|| anyhow::anyhow!("Tar entry {} is truncated", name))?;
+        entries.push((name, data.to_vec()));
+        offset = start + size.next_multiple_of(BLOCK);
+    }
+    Err(anyhow::anyhow!("Tar archive is missing its end marker"))
+}
+
+fn header(name: &str, size: usize, mtime: u64) -> Result<[u8; BLOCK]> {
+    if name.is_empty() || name.len() > NAME_LEN {
+        return Err(anyhow::anyhow!("Tar entry name {:?} must be 1 to {} bytes", name, NAME_LEN));
+    }
+    let mut header = [0u8; BLOCK];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut header[100..108], 0o644);
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+    write_octal(&mut header[124..136], size as u64);
+    write_octal(&mut header[136..148], mtime);
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    let sum = checksum(&header);
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", sum).as_bytes());
+    Ok(header)
+}
+
+/// Header checksum, counting the checksum field itself as spaces
+fn checksum(header: &[u8]) -> u64 {
+    header
+        .iter()
+        .enumerate()
+        .map(|(i, &byte)| if (148..156).contains(&i) { b' ' as u64 } else { byte as u64 })
+        .sum()
+}
+
+/// Write a NUL-terminated, zero-padded octal number filling the field
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = format!("{:0width$o}\0", value, width = field.len() - 1);
+    field.copy_from_slice(digits.as_bytes());
+}
+
+fn parse_octal(field: &[u8]) -> Result<u64> {
+    let digits = std::str::from_utf8(field)?.trim_matches(|c: char| c == '\0' || c == ' ');
+    u64::from_str_radix(digits, 8).map_err(|e| anyhow::anyhow!("Invalid octal field {:?}: {}", digits, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_and_corruption() {
+        let entries = vec![
+            ("manifest.json".to_string(), b"{}".to_vec()),
+            ("orders.json".to_string(), vec![b'x'; 1500]),
+            ("empty.json".to_string(), Vec::new()),
+        ];
+        let archive = write(&entries, 1_700_000_000).unwrap();
+        assert_eq!(archive.len() % BLOCK, 0);
+        assert_eq!(&archive[257..262], b"ustar");
+        assert_eq!(read(&archive).unwrap(), entries);
+
+        let mut corrupt = archive.clone();
+        corrupt[0] = b'M';
+        assert!(read(&corrupt).is_err());
+        assert!(read(&archive[..BLOCK + 10]).is_err());
+        assert!(write(&[("x".repeat(101), Vec::new())], 0).is_err());
+    }
+}
@@ -0,0 +1,544 @@
+//! Scheduled backups with rotation.
+//!
+//! Each tenant can have backup schedules with a five-field cron expression
+//! (`minute hour day-of-month month day-of-week`, in UTC). A run backs up
+//! every schedule whose next cron time has passed since the previous run,
+//! verifies the new archive against its checksum, then rotates the
+//! schedule's older backups by its retention policy. Missed cron times
+//! collapse into a single backup. Failures are logged and, with a bus
+//! attached, published as [`TradingEvent::BackupFailed`] so monitoring opens
+//! an incident.
+
+use crate::backup::{BackupManager, BackupMetadata};
+use crate::error::ComplianceError;
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use sniper_core::bus::InMemoryBus;
+use sniper_core::events::TradingEvent;
+use sniper_core::shutdown::Shutdown;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// How far ahead `CronSchedule::next_after` looks for a matching time
+const MAX_LOOKAHEAD_DAYS: i64 = 366 * 5;
+
+/// Parsed five-field cron expression, evaluated in UTC
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CronSchedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// Whether day-of-month and day-of-week were both restricted, in which
+    /// case a day matching either one counts, as in classic cron
+    either_day: bool,
+}
+
+impl CronSchedule {
+    /// Whether the schedule fires during the minute containing `at`
+    pub fn matches(&self, at: DateTime<Utc>) -> bool {
+        self.matches_day(at.date_naive()) && bit(self.hours, at.hour()) && bit(self.minutes, at.minute())
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        if !bit(self.months, date.month()) {
+            return false;
+        }
+        let day_of_month = bit(self.days_of_month, date.day());
+        let day_of_week = bit(self.days_of_week, date.weekday().num_days_from_sunday());
+        if self.either_day {
+            day_of_month || day_of_week
+        } else {
+            day_of_month && day_of_week
+        }
+    }
+
+    /// First time the schedule fires strictly after `after`
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let mut date = start.date_naive();
+        for _ in 0..MAX_LOOKAHEAD_DAYS {
+            if self.matches_day(date) {
+                let earliest = if date == start.date_naive() { (start.hour(), start.minute()) } else { (0, 0) };
+                let time = (0..24u32)
+                    .filter(|&hour| bit(self.hours, hour))
+                    .flat_map(|hour| (0..60u32).filter(|&minute| bit(self.minutes, minute)).map(move |minute| (hour, minute)))
+                    .find(|&time| time >= earliest);
+                if let Some((hour, minute)) = time {
+                    return Utc.from_utc_datetime(&date.and_hms_opt(hour, minute, 0)?).into();
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = anyhow::Error;
+
+    fn from_str(expression: &str) -> Result<Self> {
+        let expanded = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return Err(anyhow::anyhow!("Cron expression {:?} must have five fields", expression));
+        };
+        let mut days_of_week = parse_field(days_of_week, 0, 7)?;
+        // Both 0 and 7 mean Sunday
+        if bit(days_of_week, 7) {
+            days_of_week |= 1;
+        }
+        Ok(Self {
+            expression: expression.trim().to_string(),
+            minutes: parse_field(minutes, 0, 59)?,
+            hours: parse_field(hours, 0, 23)?,
+            days_of_month: parse_field(days_of_month, 1, 31)?,
+            months: parse_field(months, 1, 12)?,
+            days_of_week,
+            either_day: !days_of_month.starts_with('*') && !fields[4].starts_with('*'),
+        })
+    }
+}
+
+impl TryFrom<String> for CronSchedule {
+    type Error = anyhow::Error;
+
+    fn try_from(expression: String) -> Result<Self> {
+        expression.parse()
+    }
+}
+
+impl From<CronSchedule> for String {
+    fn from(schedule: CronSchedule) -> Self {
+        schedule.expression
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+fn bit(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+/// Parse a comma-separated list of `*`, `n`, `a-b` and their `/step` forms into a bit set
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64> {
+    let invalid = || anyhow::anyhow!("Invalid cron field {:?}, values must be within {}-{}", field, min, max);
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (start.parse().map_err(|_| invalid())?, end.parse().map_err(|_| invalid())?),
+                None => {
+                    let value = range.parse().map_err(|_| invalid())?;
+                    // `n/step` runs from n to the end of the range
+                    (value, if part.contains('/') { max } else { value })
+                },
+            },
+        };
+        if step == 0 || start < min || end > max || start > end {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+/// Which of a schedule's backups survive rotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RetentionPolicy {
+    /// Keep the newest backup of each of the last N days with backups
+    #[serde(default)]
+    pub keep_daily: usize,
+    /// Keep the newest backup of each of the last N ISO weeks with backups
+    #[serde(default)]
+    pub keep_weekly: usize,
+}
+
+impl RetentionPolicy {
+    /// IDs of the backups to delete, keeping the newest per day and per week
+    pub fn expired(&self, backups: &[&BackupMetadata]) -> Vec<String> {
+        let mut backups = backups.to_vec();
+        backups.sort_by_key(|backup| std::cmp::Reverse(backup.created_at));
+
+        let mut keep = HashSet::new();
+        let mut days = HashSet::new();
+        let mut weeks = HashSet::new();
+        for backup in &backups {
+            let day = backup.created_at.date_naive();
+            if days.len() < self.keep_daily && days.insert(day) {
+                keep.insert(backup.id.as_str());
+            }
+            let week = day.iso_week();
+            let week = (week.year(), week.week());
+            if weeks.len() < self.keep_weekly && weeks.insert(week) {
+                keep.insert(backup.id.as_str());
+            }
+        }
+        backups
+            .iter()
+            .filter(|backup| !keep.contains(backup.id.as_str()))
+            .map(|backup| backup.id.clone())
+            .collect()
+    }
+}
+
+/// Recurring backup of some of a tenant's components
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BackupSchedule {
+    pub id: String,
+    pub tenant_id: String,
+    pub components: Vec<String>,
+    /// Five-field cron expression, evaluated in UTC
+    #[schema(value_type = String, example = "0 3 * * *")]
+    pub cron: CronSchedule,
+    /// Rotation of older backups; without one every backup is kept
+    #[serde(default)]
+    pub retention: Option<RetentionPolicy>,
+}
+
+/// Outcome of one scheduled backup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledBackupRun {
+    pub schedule_id: String,
+    pub tenant_id: String,
+    pub backup: Option<BackupMetadata>,
+    /// Backups deleted by rotation
+    pub rotated: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// Scheduler running backup schedules against a backup manager
+#[derive(Default)]
+pub struct BackupScheduler {
+    schedules: HashMap<String, BackupSchedule>,
+    checked_until: HashMap<String, DateTime<Utc>>,
+    bus: Option<InMemoryBus>,
+}
+
+impl BackupScheduler {
+    /// Create a scheduler with no schedules
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Publish failed backups on a bus
+    pub fn with_bus(mut self, bus: InMemoryBus) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Add a schedule, or replace the one with the same ID
+    pub fn add_schedule(&mut self, schedule: BackupSchedule) -> Result<()> {
+        if schedule.components.is_empty() {
+            return Err(ComplianceError::InvalidSchedule { id: schedule.id, reason: "it needs at least one component" }.into());
+        }
+        if schedule.retention.is_some_and(|retention| retention.keep_daily == 0 && retention.keep_weekly == 0) {
+            return Err(ComplianceError::InvalidSchedule { id: schedule.id, reason: "its retention would keep no backups" }.into());
+        }
+        self.checked_until.remove(&schedule.id);
+        self.schedules.insert(schedule.id.clone(), schedule);
+        Ok(())
+    }
+
+    /// Remove a schedule, keeping the backups it made
+    pub fn remove_schedule(&mut self, schedule_id: &str) -> Option<BackupSchedule> {
+        self.checked_until.remove(schedule_id);
+        self.schedules.remove(schedule_id)
+    }
+
+    /// Get a schedule by ID
+    pub fn get_schedule(&self, schedule_id: &str) -> Option<&BackupSchedule> {
+        self.schedules.get(schedule_id)
+    }
+
+    /// List schedules for a tenant
+    pub fn list_tenant_schedules(&self, tenant_id: &str) -> Vec<&BackupSchedule> {
+        self.schedules
+            .values()
+            .filter(|schedule| schedule.tenant_id == tenant_id)
+            .collect()
+    }
+
+    /// Back up every schedule due at `now`
+    ///
+    /// A schedule checked for the first time is due only if it fires in the
+    /// minute containing `now`.
+    pub async fn run_due(&mut self, backups: &mut BackupManager, now: DateTime<Utc>) -> Vec<ScheduledBackupRun> {
+        let mut due: Vec<BackupSchedule> = Vec::new();
+        for schedule in self.schedules.values() {
+            let since = self.checked_until.get(&schedule.id).copied().unwrap_or(now - Duration::minutes(1));
+            if schedule.cron.next_after(since).is_some_and(|next| next <= now) {
+                due.push(schedule.clone());
+            }
+            self.checked_until.insert(schedule.id.clone(), now);
+        }
+        due.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let mut runs = Vec::new();
+        for schedule in due {
+            let mut run = ScheduledBackupRun {
+                schedule_id: schedule.id.clone(),
+                tenant_id: schedule.tenant_id.clone(),
+                backup: None,
+                rotated: Vec::new(),
+                error: None,
+            };
+            if let Err(e) = Self::run_schedule(&schedule, backups, &mut run).await {
+                self.report_failure(&schedule, &e, now);
+                run.error = Some(format!("{:#}", e));
+            }
+            runs.push(run);
+        }
+        runs
+    }
+
+    async fn run_schedule(schedule: &BackupSchedule, backups: &mut BackupManager, run: &mut ScheduledBackupRun) -> Result<()> {
+        let backup = backups
+            .create_scheduled_backup(schedule.components.clone(), &schedule.tenant_id, &schedule.id)
+            .await?;
+        run.backup = Some(backup.clone());
+        backups.verify_backup(&backup.id).await?;
+
+        let Some(retention) = schedule.retention else {
+            return Ok(());
+        };
+        let own: Vec<&BackupMetadata> = backups
+            .list_tenant_backups(&schedule.tenant_id)
+            .into_iter()
+            .filter(|backup| backup.schedule_id.as_deref() == Some(schedule.id.as_str()))
+            .collect();
+        for backup_id in retention.expired(&own) {
+            backups.delete_backup(&backup_id).await?;
+            run.rotated.push(backup_id);
+        }
+        Ok(())
+    }
+
+    fn report_failure(&self, schedule: &BackupSchedule, error: &anyhow::Error, now: DateTime<Utc>) {
+        tracing::error!("Scheduled backup {} of tenant {} failed: {:#}", schedule.id, schedule.tenant_id, error);
+        if let Some(bus) = &self.bus {
+            let event = TradingEvent::BackupFailed {
+                schedule_id: schedule.id.clone(),
+                tenant_id: schedule.tenant_id.clone(),
+                error: format!("{:#}", error),
+                timestamp: now.timestamp().max(0) as u64,
+            };
+            if let Err(e) = bus.publish_now(event.subject(), &event) {
+                tracing::warn!("Failed to publish backup failure: {}", e);
+            }
+        }
+    }
+
+    /// Run due schedules every `interval` until shutdown
+    ///
+    /// Shutdown is only checked between runs, so backups in progress complete
+    /// and [`Shutdown::join`] waits for them.
+    pub fn spawn(
+        scheduler: Arc<RwLock<BackupScheduler>>,
+        backups: Arc<RwLock<BackupManager>>,
+        interval: std::time::Duration,
+        shutdown: &Shutdown,
+    ) {
+        let stop = shutdown.clone();
+        shutdown.track(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {},
+                    _ = stop.wait() => return,
+                }
+                let mut backups = backups.write().await;
+                scheduler.write().await.run_due(&mut backups, Utc::now()).await;
+            }
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::BackupComponent;
+    use crate::destination::BackupDestination;
+    use async_trait::async_trait;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    struct Settings;
+
+    #[async_trait]
+    impl BackupComponent for Settings {
+        async fn snapshot(&self, tenant_id: &str) -> Result<serde_json::Value> {
+            Ok(serde_json::json!({ "tenant": tenant_id }))
+        }
+
+        async fn restore(&self, _tenant_id: &str, _snapshot: serde_json::Value) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Destination refusing every write
+    struct ReadOnly;
+
+    #[async_trait]
+    impl BackupDestination for ReadOnly {
+        async fn put(&self, key: &str, _archive: Vec<u8>) -> Result<()> {
+            Err(anyhow::anyhow!("{} is read-only", key))
+        }
+
+        async fn get(&self, key: &str) -> Result<Vec<u8>> {
+            Err(anyhow::anyhow!("{} not found", key))
+        }
+
+        async fn delete(&self, _key: &str) -> Result<()> {
+            Ok(())
+        }
+
+        fn location(&self, key: &str) -> String {
+            key.to_string()
+        }
+    }
+
+    fn schedule(cron: &str, retention: Option<RetentionPolicy>) -> BackupSchedule {
+        BackupSchedule {
+            id: "nightly".to_string(),
+            tenant_id: "tenant-1".to_string(),
+            components: vec!["settings".to_string()],
+            cron: cron.parse().unwrap(),
+            retention,
+        }
+    }
+
+    #[test]
+    fn test_cron_parsing_and_next_run() {
+        let nightly: CronSchedule = "30 2 * * *".parse().unwrap();
+        assert_eq!(nightly.next_after(at("2024-03-10T01:00:00Z")), Some(at("2024-03-10T02:30:00Z")));
+        assert_eq!(nightly.next_after(at("2024-03-10T02:30:00Z")), Some(at("2024-03-11T02:30:00Z")));
+
+        let weekdays: CronSchedule = "*/15 9-17 * * 1-5".parse().unwrap();
+        // 2024-03-09 is a Saturday
+        assert_eq!(weekdays.next_after(at("2024-03-09T12:00:00Z")), Some(at("2024-03-11T09:00:00Z")));
+        assert!(weekdays.matches(at("2024-03-11T17:45:10Z")));
+        assert!(!weekdays.matches(at("2024-03-11T17:50:00Z")));
+
+        // Restricting both day fields fires on either
+        let either: CronSchedule = "0 0 1 * 0".parse().unwrap();
+        assert!(either.matches(at("2024-03-01T00:00:00Z")));
+        assert!(either.matches(at("2024-03-03T00:00:00Z")));
+        assert!(!either.matches(at("2024-03-04T00:00:00Z")));
+        assert!("0 0 * * 7".parse::<CronSchedule>().unwrap().matches(at("2024-03-03T00:00:00Z")));
+        assert_eq!("@weekly".parse::<CronSchedule>().unwrap().to_string(), "@weekly");
+        assert_eq!("0 0 30 2 *".parse::<CronSchedule>().unwrap().next_after(at("2024-01-01T00:00:00Z")), None);
+
+        for invalid in ["* * * *", "60 * * * *", "*/0 * * * *", "5-1 * * * *", "a * * * *"] {
+            assert!(invalid.parse::<CronSchedule>().is_err(), "{}", invalid);
+        }
+        let json = serde_json::to_string(&nightly).unwrap();
+        assert_eq!(json, "\"30 2 * * *\"");
+        assert_eq!(serde_json::from_str::<CronSchedule>(&json).unwrap(), nightly);
+    }
+
+    #[test]
+    fn test_retention_keeps_newest_per_day_and_week() {
+        let backups: Vec<BackupMetadata> = [
+            "2024-03-11T02:00:00Z",
+            "2024-03-11T14:00:00Z",
+            "2024-03-10T02:00:00Z",
+            "2024-03-09T02:00:00Z",
+            "2024-03-03T02:00:00Z",
+            "2024-02-25T02:00:00Z",
+        ]
+        .iter()
+        .map(|created_at| BackupMetadata {
+            id: created_at.to_string(),
+            created_at: at(created_at),
+            size_bytes: 0,
+            checksum: String::new(),
+            components: Vec::new(),
+            tenant_id: "tenant-1".to_string(),
+            location: String::new(),
+            schedule_id: Some("nightly".to_string()),
+        })
+        .collect();
+        let backups: Vec<&BackupMetadata> = backups.iter().collect();
+
+        let policy = RetentionPolicy { keep_daily: 2, keep_weekly: 2 };
+        let mut expired = policy.expired(&backups);
+        expired.sort();
+        // Kept: 03-11 14:00 (day, week 11), 03-10 (day, week 10)
+        assert_eq!(
+            expired,
+            ["2024-02-25T02:00:00Z", "2024-03-03T02:00:00Z", "2024-03-09T02:00:00Z", "2024-03-11T02:00:00Z"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_due_schedules_back_up_and_rotate() {
+        let mut backups = BackupManager::new().with_component("settings", Arc::new(Settings));
+        let manual = backups.create_backup(vec!["settings".to_string()], "tenant-1").await.unwrap();
+        let mut scheduler = BackupScheduler::new();
+        let retention = RetentionPolicy { keep_daily: 1, keep_weekly: 0 };
+        scheduler.add_schedule(schedule("0 2 * * *", Some(retention))).unwrap();
+        assert!(scheduler.add_schedule(schedule("0 2 * * *", Some(RetentionPolicy { keep_daily: 0, keep_weekly: 0 }))).is_err());
+
+        assert!(scheduler.run_due(&mut backups, at("2024-03-10T01:59:00Z")).await.is_empty());
+        let runs = scheduler.run_due(&mut backups, at("2024-03-10T02:05:00Z")).await;
+        assert_eq!(runs.len(), 1);
+        let first = runs[0].backup.clone().unwrap();
+        assert_eq!(first.schedule_id.as_deref(), Some("nightly"));
+        assert!(scheduler.run_due(&mut backups, at("2024-03-10T02:10:00Z")).await.is_empty());
+
+        // Backups are stamped with the real time, so the next one lands on the same day
+        let runs = scheduler.run_due(&mut backups, at("2024-03-11T03:00:00Z")).await;
+        assert_eq!(runs[0].rotated, vec![first.id.clone()]);
+        assert!(backups.get_backup(&first.id).is_none());
+        // Manual backups are never rotated
+        assert!(backups.get_backup(&manual.id).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_failed_backup_is_published() {
+        let bus = InMemoryBus::new(16);
+        let mut rx = bus.subscribe(sniper_core::events::BACKUP_FAILED_SUBJECT);
+        let mut backups = BackupManager::new()
+            .with_component("settings", Arc::new(Settings))
+            .with_destination(Arc::new(ReadOnly));
+        let mut scheduler = BackupScheduler::new().with_bus(bus);
+        scheduler.add_schedule(schedule("@hourly", None)).unwrap();
+
+        let runs = scheduler.run_due(&mut backups, at("2024-03-10T02:00:30Z")).await;
+        assert!(runs[0].error.as_deref().unwrap().contains("read-only"));
+        match TradingEvent::decode(&rx.recv().await.unwrap()) {
+            Some(TradingEvent::BackupFailed { schedule_id, tenant_id, .. }) => {
+                assert_eq!((schedule_id.as_str(), tenant_id.as_str()), ("nightly", "tenant-1"));
+            },
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,275 @@
+//! Backup components for the managers owning each kind of data.
+//!
+//! Orders, positions, users, compliance reports and recovery plans are
+//! snapshotted per tenant as JSON arrays of their records and restored
+//! through their managers' `restore_*` methods, which replace the tenant's
+//! records wholesale. Configuration files are deployment-wide: their
+//! snapshot maps each file name to its contents.
+
+use crate::backup::BackupComponent;
+use crate::{ComplianceManager, DisasterRecoveryManager};
+use anyhow::Result;
+use async_trait::async_trait;
+use sniper_orders::ShardedOrderManager;
+use sniper_portfolio::PortfolioManager;
+use sniper_users::UserManager;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tokio::sync::RwLock;
+
+/// Component name of a tenant's orders
+pub const ORDERS: &str = "orders";
+
+/// Component name of a tenant's portfolio positions
+pub const POSITIONS: &str = "positions";
+
+/// Component name of a tenant's users
+pub const USERS: &str = "users";
+
+/// Component name of configuration files
+pub const CONFIGS: &str = "configs";
+
+/// Component name of a tenant's compliance reports
+pub const REPORTS: &str = "reports";
+
+/// Component name of a tenant's disaster recovery plans
+pub const RECOVERY_PLANS: &str = "recovery_plans";
+
+#[async_trait]
+impl BackupComponent for ShardedOrderManager {
+    async fn snapshot(&self, tenant_id: &str) -> Result<serde_json::Value> {
+        Ok(serde_json::to_value(self.list_tenant_orders(tenant_id).await)?)
+    }
+
+    async fn restore(&self, tenant_id: &str, snapshot: serde_json::Value) -> Result<()> {
+        Ok(self.restore_orders(tenant_id, serde_json::from_value(snapshot)?).await?)
+    }
+}
+
+#[async_trait]
+impl BackupComponent for RwLock<PortfolioManager> {
+    async fn snapshot(&self, tenant_id: &str) -> Result<serde_json::Value> {
+        Ok(serde_json::to_value(self.read().await.tenant_positions(tenant_id))?)
+    }
+
+    async fn restore(&self, tenant_id: &str, snapshot: serde_json::Value) -> Result<()> {
+        Ok(self.write().await.restore_positions(tenant_id, serde_json::from_value(snapshot)?)?)
+    }
+}
+
+#[async_trait]
+impl BackupComponent for RwLock<UserManager> {
+    async fn snapshot(&self, tenant_id: &str) -> Result<serde_json::Value> {
+        Ok(serde_json::to_value(self.read().await.get_tenant_users(tenant_id))?)
+    }
+
+    async fn restore(&self, tenant_id: &str, snapshot: serde_json::Value) -> Result<()> {
+        self.write().await.restore_tenant_users(tenant_id, serde_json::from_value(snapshot)?)
+    }
+}
+
+#[async_trait]
+impl BackupComponent for RwLock<ComplianceManager> {
+    async fn snapshot(&self, tenant_id: &str) -> Result<serde_json::Value> {
+        Ok(serde_json::to_value(self.read().await.get_tenant_reports(tenant_id))?)
+    }
+
+    async fn restore(&self, tenant_id: &str, snapshot: serde_json::Value) -> Result<()> {
+        self.write().await.restore_tenant_reports(tenant_id, serde_json::from_value(snapshot)?)
+    }
+}
+
+#[async_trait]
+impl BackupComponent for RwLock<DisasterRecoveryManager> {
+    async fn snapshot(&self, tenant_id: &str) -> Result<serde_json::Value> {
+        Ok(serde_json::to_value(self.read().await.list_tenant_plans(tenant_id))?)
+    }
+
+    async fn restore(&self, tenant_id: &str, snapshot: serde_json::Value) -> Result<()> {
+        self.write().await.restore_tenant_plans(tenant_id, serde_json::from_value(snapshot)?)
+    }
+}
+
+/// Configuration files, backed up by file name
+pub struct ConfigFiles {
+    paths: Vec<PathBuf>,
+}
+
+impl ConfigFiles {
+    /// Back up the files at `paths`, whose file names must be distinct
+    pub fn new(paths: Vec<PathBuf>) -> Result<Self> {
+        let mut names = Vec::new();
+        for path in &paths {
+            let name = file_name(path)?;
+            if names.contains(&name) {
+                return Err(anyhow::anyhow!("Config file name {} is used twice", name));
+            }
+            names.push(name);
+        }
+        Ok(Self { paths })
+    }
+}
+
+fn file_name(path: &std::path::Path) -> Result<String> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_string)
+        .ok_or_else(|| anyhow::anyhow!("Config path {} has no file name", path.display()))
+}
+
+#[async_trait]
+impl BackupComponent for ConfigFiles {
+    async fn snapshot(&self, _tenant_id: &str) -> Result<serde_json::Value> {
+        let mut files = BTreeMap::new();
+        for path in &self.paths {
+            let contents = tokio::fs::read_to_string(path)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to read config file {}: {}", path.display(), e))?;
+            files.insert(file_name(path)?, contents);
+        }
+        Ok(serde_json::to_value(files)?)
+    }
+
+    async fn restore(&self, _tenant_id: &str, snapshot: serde_json::Value) -> Result<()> {
+        let files: BTreeMap<String, String> = serde_json::from_value(snapshot)?;
+        let mut targets = Vec::new();
+        for (name, contents) in files {
+            let path = self
+                .paths
+                .iter()
+                .find(|path| file_name(path).is_ok_and(|file| file == name))
+                .ok_or_else(|| anyhow::anyhow!("Backup holds unknown config file {}", name))?;
+            targets.push((path, contents));
+        }
+        for (path, contents) in targets {
+            tokio::fs::write(path, contents).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::BackupManager;
+    use crate::destination::LocalDirDestination;
+    use sniper_core::types::{ChainRef, Decimal};
+    use sniper_orders::{AdvancedOrder, OrderStatus, OrderType, TimeInForce};
+    use sniper_portfolio::{AllocationSettings, Position};
+    use sniper_users::UserRole;
+    use std::sync::Arc;
+
+    fn order(id: &str, tenant_id: &str) -> AdvancedOrder {
+        AdvancedOrder {
+            id: id.to_string(),
+            symbol: "ETH".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type: OrderType::Market,
+            side: "buy".to_string(),
+            amount: Decimal::ONE,
+            time_in_force: TimeInForce::GoodTillCancelled,
+            created_at: 0,
+            updated_at: 0,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: Some(tenant_id.to_string()),
+            trigger: None,
+        }
+    }
+
+    fn position(id: &str) -> Position {
+        Position {
+            id: id.to_string(),
+            symbol: "ETH".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            amount: Decimal::ONE,
+            entry_price: Decimal::from(100),
+            current_price: Decimal::from(100),
+            side: "long".to_string(),
+            leverage: 1.0,
+            pnl: Decimal::ZERO,
+            pnl_percentage: 0.0,
+            created_at: 0,
+            updated_at: 0,
+            tenant_id: None,
+            fees: Decimal::ZERO,
+            funding: Decimal::ZERO,
+        }
+    }
+
+    fn portfolio() -> PortfolioManager {
+        let settings = AllocationSettings {
+            max_position_size_pct: 5.0,
+            max_portfolio_risk_pct: 2.0,
+            diversification_targets: Default::default(),
+            stop_loss_pct: 5.0,
+            take_profit_pct: 10.0,
+            netting: Default::default(),
+        };
+        PortfolioManager::new(Decimal::from(100_000), settings).with_tenant("tenant-1")
+    }
+
+    #[tokio::test]
+    async fn test_restore_hands_data_back_to_managers() {
+        let root = std::env::temp_dir().join(format!("sniper-backups-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        let config = root.join("risk.toml");
+        std::fs::write(&config, "max_position = 10\n").unwrap();
+
+        let orders = Arc::new(ShardedOrderManager::new());
+        orders.create_order(order("order-1", "tenant-1")).await.unwrap();
+        orders.create_order(order("order-2", "tenant-2")).await.unwrap();
+        let portfolio = Arc::new(RwLock::new(portfolio()));
+        portfolio.write().await.add_position(position("position-1")).unwrap();
+        let users = Arc::new(RwLock::new(UserManager::new()));
+        let alice = users.write().await.create_user("alice", "alice@example.com", vec![UserRole::Trader], "tenant-1").unwrap();
+
+        let mut backup_manager = BackupManager::new()
+            .with_component(ORDERS, orders.clone())
+            .with_component(POSITIONS, portfolio.clone())
+            .with_component(USERS, users.clone())
+            .with_component(CONFIGS, Arc::new(ConfigFiles::new(vec![config.clone()]).unwrap()))
+            .with_destination(Arc::new(LocalDirDestination::new(root.join("backups"))));
+        let components = [ORDERS, POSITIONS, USERS, CONFIGS].map(str::to_string).to_vec();
+        let backup = backup_manager.create_backup(components, "tenant-1").await.unwrap();
+
+        // Lose tenant-1's data, then restore it
+        orders.cancel_order("order-1").await.unwrap();
+        orders.create_order(order("order-3", "tenant-1")).await.unwrap();
+        portfolio.write().await.remove_position("position-1").unwrap();
+        users.write().await.create_user("mallory", "mallory@example.com", vec![UserRole::Admin], "tenant-1").unwrap();
+        std::fs::write(&config, "max_position = 1000\n").unwrap();
+        backup_manager.restore_from_backup(&backup.id).await.unwrap();
+
+        assert_eq!(orders.get_order("order-1").await.unwrap().status, OrderStatus::Pending);
+        assert!(orders.get_order("order-3").await.is_none());
+        assert!(orders.get_order("order-2").await.is_some());
+        assert_eq!(orders.len(), 2);
+        let portfolio = portfolio.read().await;
+        assert_eq!(portfolio.get_position("position-1").unwrap().tenant_id.as_deref(), Some("tenant-1"));
+        let users = users.read().await;
+        let restored: Vec<&str> = users.get_tenant_users("tenant-1").iter().map(|user| user.id.as_str()).collect();
+        assert_eq!(restored, [alice.id.as_str()]);
+        assert_eq!(std::fs::read_to_string(&config).unwrap(), "max_position = 10\n");
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_restore_refuses_other_tenants_records() {
+        let orders = ShardedOrderManager::new();
+        assert!(orders.restore_orders("tenant-1", vec![order("order-1", "tenant-2")]).await.is_err());
+
+        let mut portfolio = portfolio();
+        let mut foreign = position("position-1");
+        foreign.tenant_id = Some("tenant-2".to_string());
+        assert!(portfolio.restore_positions("tenant-1", vec![foreign]).is_err());
+        assert!(ConfigFiles::new(vec![PathBuf::from("a/risk.toml"), PathBuf::from("b/risk.toml")]).is_err());
+    }
+}
@@ -0,0 +1,57 @@
+//! Errors returned by the compliance, backup and recovery managers.
+
+use sniper_core::errors::{DomainError, ErrorKind};
+use thiserror::Error;
+
+/// Failure of a compliance, backup or recovery operation
+#[derive(Debug, Error)]
+pub enum ComplianceError {
+    #[error("Report {0} not found")]
+    ReportNotFound(String),
+    #[error("Backup {0} not found")]
+    BackupNotFound(String),
+    #[error("Disaster recovery plan {0} not found")]
+    PlanNotFound(String),
+    #[error("Unsupported export format {0}")]
+    UnsupportedFormat(String),
+    #[error("Unknown backup component {0}")]
+    UnknownComponent(String),
+    #[error("Backup needs at least one component")]
+    NoComponents,
+    #[error("Component {0} is listed twice")]
+    DuplicateComponent(String),
+    #[error("Invalid backup schedule {id}: {reason}")]
+    InvalidSchedule { id: String, reason: &'static str },
+    #[error("Invalid disaster recovery plan: {0}")]
+    InvalidPlan(String),
+    #[error("Backup {id} {reason}")]
+    CorruptBackup { id: String, reason: String },
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+/// Errors carrying a [`ComplianceError`], such as a missing backup raised
+/// inside the managers, keep their variant
+impl From<anyhow::Error> for ComplianceError {
+    fn from(error: anyhow::Error) -> Self {
+        error.downcast::<ComplianceError>().unwrap_or_else(ComplianceError::Other)
+    }
+}
+
+impl DomainError for ComplianceError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            ComplianceError::ReportNotFound(_)
+            | ComplianceError::BackupNotFound(_)
+            | ComplianceError::PlanNotFound(_) => ErrorKind::NotFound,
+            ComplianceError::UnsupportedFormat(_)
+            | ComplianceError::UnknownComponent(_)
+            | ComplianceError::NoComponents
+            | ComplianceError::DuplicateComponent(_)
+            | ComplianceError::InvalidSchedule { .. }
+            | ComplianceError::InvalidPlan(_) => ErrorKind::Invalid,
+            ComplianceError::CorruptBackup { .. } => ErrorKind::Conflict,
+            ComplianceError::Other(_) => ErrorKind::Internal,
+        }
+    }
+}
@@ -1,25 +1,58 @@
 //! Compliance reporting system for the sniper-rs enterprise features.
 //! 
 //! This module provides functionality for compliance reporting, disaster recovery,
-//! and backup/restore capabilities.
+//! backup/restore capabilities, and report digests delivered according to
+//! each user's notification preferences. Trade audit reports include the
+//! tenant's entries from the persistent audit log when one is configured,
+//! and given a report data provider, trade audits and financial summaries
+//! are built from the tenant's actual orders, fills and positions.
+//! Disaster recovery plans run their steps in dependency order, concurrently
+//! where independent, and roll back when a step fails.
+//! Backups are compressed archives of the owning managers' data, stored in a
+//! local directory or an S3-compatible bucket, and can be taken on per-tenant
+//! cron schedules with rotation.
+
+mod archive;
+pub mod backup;
+pub mod backup_scheduler;
+pub mod components;
+pub mod destination;
+pub mod error;
+pub mod recovery;
+pub mod reporting;
+pub mod scheduler;
+
+pub use backup::{BackupComponent, BackupManager, BackupMetadata};
+pub use backup_scheduler::{BackupSchedule, BackupScheduler, CronSchedule, RetentionPolicy, ScheduledBackupRun};
+pub use components::ConfigFiles;
+pub use destination::{BackupDestination, InMemoryDestination, LocalDirDestination, S3Config, S3Destination};
+pub use error::ComplianceError;
+pub use recovery::{RecoveryAction, RecoveryExecution, RecoveryHandle, RecoveryProgress, RecoveryRun, RunStatus, StepRun, StepStatus};
+pub use reporting::{ReportDataProvider, ReportDataSet, TradeFill};
+pub use scheduler::{ReportDigest, ReportDigestScheduler};
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use chrono::{DateTime, Utc};
-use std::collections::HashMap;
+use sniper_users::{AuditLog, AuditQuery, AuditStore};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
 
 /// Report types for compliance
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
 pub enum ReportType {
     DailyActivity,
     TradeAudit,
     RiskAssessment,
     RegulatoryCompliance,
     FinancialSummary,
+    IncidentAnalytics,
 }
 
 /// Compliance report
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ComplianceReport {
     pub id: String,
     pub report_type: ReportType,
@@ -31,17 +64,6 @@ pub struct ComplianceReport {
     pub tenant_id: String,
 }
 
-/// Backup metadata
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BackupMetadata {
-    pub id: String,
-    pub created_at: DateTime<Utc>,
-    pub size_bytes: u64,
-    pub checksum: String,
-    pub components: Vec<String>,
-    pub tenant_id: String,
-}
-
 /// Disaster recovery plan
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DisasterRecoveryPlan {
@@ -55,18 +77,26 @@ pub struct DisasterRecoveryPlan {
 }
 
 /// Recovery step in a disaster recovery plan
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct RecoveryStep {
     pub id: String,
     pub order: u32,
     pub description: String,
     pub expected_duration_minutes: u32,
     pub dependencies: Vec<String>,
+    /// Registered recovery action carrying out the step; a manual checkpoint if unset
+    #[serde(default)]
+    pub action: Option<String>,
+    /// Fail the step if its action runs longer than this
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
 }
 
 /// Compliance manager for generating reports
 pub struct ComplianceManager {
     reports: HashMap<String, ComplianceReport>,
+    audit_log: Option<PathBuf>,
+    data_provider: Option<Arc<dyn ReportDataProvider>>,
 }
 
 impl ComplianceManager {
@@ -74,9 +104,23 @@ impl ComplianceManager {
     pub fn new() -> Self {
         Self {
             reports: HashMap::new(),
+            audit_log: None,
+            data_provider: None,
         }
     }
     
+    /// Read trade audit entries from the audit log file svc-users writes
+    pub fn with_audit_log(mut self, path: impl Into<PathBuf>) -> Self {
+        self.audit_log = Some(path.into());
+        self
+    }
+    
+    /// Build trade audits and financial summaries from a provider's trading data
+    pub fn with_data_provider(mut self, provider: Arc<dyn ReportDataProvider>) -> Self {
+        self.data_provider = Some(provider);
+        self
+    }
+    
     /// Generate a compliance report
     pub fn generate_report(
         &mut self,
@@ -86,7 +130,7 @@ impl ComplianceManager {
         generated_by: &str,
         tenant_id: &str,
     ) -> Result<ComplianceReport> {
-        let report_content = self.create_report_content(&report_type, period_start, period_end)?;
+        let report_content = self.create_report_content(&report_type, period_start, period_end, tenant_id)?;
         
         let report = ComplianceReport {
             id: uuid::Uuid::new_v4().to_string(),
@@ -103,12 +147,39 @@ impl ComplianceManager {
         Ok(report)
     }
     
+    /// Record a report whose content was produced by another component,
+    /// such as the incident analytics summaries from the monitoring service
+    pub fn record_report(
+        &mut self,
+        report_type: ReportType,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+        content: String,
+        generated_by: &str,
+        tenant_id: &str,
+    ) -> ComplianceReport {
+        let report = ComplianceReport {
+            id: uuid::Uuid::new_v4().to_string(),
+            report_type,
+            generated_at: Utc::now(),
+            period_start,
+            period_end,
+            content,
+            generated_by: generated_by.to_string(),
+            tenant_id: tenant_id.to_string(),
+        };
+        
+        self.reports.insert(report.id.clone(), report.clone());
+        report
+    }
+    
     /// Create report content based on report type
     fn create_report_content(
         &self,
         report_type: &ReportType,
         period_start: DateTime<Utc>,
         period_end: DateTime<Utc>,
+        tenant_id: &str,
     ) -> Result<String> {
         let content = match report_type {
             ReportType::DailyActivity => {
@@ -118,10 +189,19 @@ impl ComplianceManager {
                 )
             }
             ReportType::TradeAudit => {
-                format!(
+                let mut content = format!(
                     "Trade Audit Report\nPeriod: {} to {}\n\nDetailed audit of all trades executed during the reporting period.",
                     period_start, period_end
-                )
+                );
+                if let Some(provider) = &self.data_provider {
+                    let orders = provider.orders(tenant_id, period_start, period_end)?;
+                    let fills = provider.fills(tenant_id, period_start, period_end)?;
+                    content.push_str(&reporting::trade_section(&orders, &fills));
+                }
+                if self.audit_log.is_some() || self.data_provider.is_some() {
+                    content.push_str(&audit_section(self.audit_entries(tenant_id, period_start, period_end)?));
+                }
+                content
             }
             ReportType::RiskAssessment => {
                 format!(
@@ -136,9 +216,22 @@ impl ComplianceManager {
                 )
             }
             ReportType::FinancialSummary => {
-                format!(
+                let mut content = format!(
                     "Financial Summary Report\nPeriod: {} to {}\n\nSummary of financial performance during the reporting period.",
                     period_start, period_end
+                );
+                if let Some(provider) = &self.data_provider {
+                    let fills = provider.fills(tenant_id, period_start, period_end)?;
+                    let funding = provider.funding(tenant_id, period_start, period_end)?;
+                    let positions = provider.positions(tenant_id)?;
+                    content.push_str(&reporting::financial_section(&fills, &funding, &positions)?);
+                }
+                content
+            }
+            ReportType::IncidentAnalytics => {
+                format!(
+                    "Incident Analytics Report\nPeriod: {} to {}\n\nResponse times and alert noise for incidents raised during the reporting period.",
+                    period_start, period_end
                 )
             }
         };
@@ -146,6 +239,21 @@ impl ComplianceManager {
         Ok(content)
     }
     
+    /// Audit entries of a period from the audit log file and the data provider, oldest first
+    fn audit_entries(&self, tenant_id: &str, period_start: DateTime<Utc>, period_end: DateTime<Utc>) -> Result<Vec<AuditLog>> {
+        let mut entries = match &self.audit_log {
+            Some(path) => AuditStore::open(path)?.query(&AuditQuery::tenant_range(tenant_id, period_start, period_end)),
+            None => Vec::new(),
+        };
+        if let Some(provider) = &self.data_provider {
+            let logged: HashSet<String> = entries.iter().map(|entry| entry.id.clone()).collect();
+            let provided = provider.audit_logs(tenant_id, period_start, period_end)?;
+            entries.extend(provided.into_iter().filter(|entry| !logged.contains(&entry.id)));
+        }
+        entries.sort_by_key(|entry| entry.timestamp);
+        Ok(entries)
+    }
+    
     /// Get a report by ID
     pub fn get_report(&self, report_id: &str) -> Option<&ComplianceReport> {
         self.reports.get(report_id)
@@ -159,89 +267,59 @@ impl ComplianceManager {
             .collect()
     }
     
+    /// Replace all reports of a tenant, e.g. with ones restored from a backup
+    pub fn restore_tenant_reports(&mut self, tenant_id: &str, reports: Vec<ComplianceReport>) -> Result<()> {
+        if let Some(report) = reports.iter().find(|report| report.tenant_id != tenant_id) {
+            return Err(anyhow::anyhow!("Report {} belongs to tenant {}, not {}", report.id, report.tenant_id, tenant_id));
+        }
+        self.reports.retain(|_, report| report.tenant_id != tenant_id);
+        self.reports.extend(reports.into_iter().map(|report| (report.id.clone(), report)));
+        Ok(())
+    }
+    
     /// Export a report in a specific format
     pub fn export_report(&self, report_id: &str, format: &str) -> Result<Vec<u8>> {
         if let Some(report) = self.get_report(report_id) {
             let exported_data = match format {
                 "json" => serde_json::to_vec(report)?,
                 "text" => report.content.clone().into_bytes(),
-                _ => return Err(anyhow::anyhow!("Unsupported export format")),
+                _ => return Err(ComplianceError::UnsupportedFormat(format.to_string()).into()),
             };
             Ok(exported_data)
         } else {
-            Err(anyhow::anyhow!("Report not found"))
+            Err(ComplianceError::ReportNotFound(report_id.to_string()).into())
         }
     }
 }
 
-/// Backup manager for backup and restore capabilities
-pub struct BackupManager {
-    backups: HashMap<String, BackupMetadata>,
-}
-
-impl BackupManager {
-    /// Create a new backup manager
-    pub fn new() -> Self {
-        Self {
-            backups: HashMap::new(),
-        }
+/// Audit log entries of a report period, summarized by action and listed in order
+fn audit_section(entries: Vec<AuditLog>) -> String {
+    let mut by_action: BTreeMap<&str, usize> = BTreeMap::new();
+    for entry in &entries {
+        *by_action.entry(entry.action.as_str()).or_insert(0) += 1;
     }
     
-    /// Create a backup
-    pub fn create_backup(&mut self, components: Vec<String>, tenant_id: &str) -> Result<BackupMetadata> {
-        // In a real implementation, this would actually perform the backup
-        // For now, we'll just create metadata
-        
-        let metadata = BackupMetadata {
-            id: uuid::Uuid::new_v4().to_string(),
-            created_at: Utc::now(),
-            size_bytes: 1024 * 1024, // Placeholder size
-            checksum: "placeholder_checksum".to_string(),
-            components,
-            tenant_id: tenant_id.to_string(),
-        };
-        
-        self.backups.insert(metadata.id.clone(), metadata.clone());
-        Ok(metadata)
+    let mut section = format!("\n\nAudit log entries: {}\n", entries.len());
+    for (action, count) in by_action {
+        section.push_str(&format!("  {}: {}\n", action, count));
     }
-    
-    /// Get backup metadata by ID
-    pub fn get_backup(&self, backup_id: &str) -> Option<&BackupMetadata> {
-        self.backups.get(backup_id)
-    }
-    
-    /// List backups for a tenant
-    pub fn list_tenant_backups(&self, tenant_id: &str) -> Vec<&BackupMetadata> {
-        self.backups
-            .values()
-            .filter(|backup| backup.tenant_id == tenant_id)
-            .collect()
-    }
-    
-    /// Restore from a backup
-    pub fn restore_from_backup(&self, backup_id: &str) -> Result<()> {
-        if self.backups.contains_key(backup_id) {
-            // In a real implementation, this would actually perform the restore
-            tracing::info!("Restoring from backup {}", backup_id);
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("Backup not found"))
-        }
-    }
-    
-    /// Delete a backup
-    pub fn delete_backup(&mut self, backup_id: &str) -> Result<()> {
-        if self.backups.remove(backup_id).is_some() {
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("Backup not found"))
-        }
+    for entry in &entries {
+        section.push_str(&format!(
+            "\n{} {} {} {} {}",
+            entry.timestamp.to_rfc3339(),
+            entry.user_id,
+            entry.action,
+            entry.resource,
+            entry.details.as_deref().unwrap_or_default(),
+        ));
     }
+    section
 }
 
 /// Disaster recovery manager
 pub struct DisasterRecoveryManager {
     plans: HashMap<String, DisasterRecoveryPlan>,
+    actions: HashMap<String, Arc<dyn recovery::RecoveryAction>>,
 }
 
 impl DisasterRecoveryManager {
@@ -249,9 +327,16 @@ impl DisasterRecoveryManager {
     pub fn new() -> Self {
         Self {
             plans: HashMap::new(),
+            actions: HashMap::new(),
         }
     }
     
+    /// Register an action that plan steps can name
+    pub fn with_action(mut self, name: &str, action: Arc<dyn recovery::RecoveryAction>) -> Self {
+        self.actions.insert(name.to_string(), action);
+        self
+    }
+    
     /// Create a disaster recovery plan
     pub fn create_plan(
         &mut self,
@@ -287,23 +372,25 @@ impl DisasterRecoveryManager {
             .collect()
     }
     
-    /// Execute a disaster recovery plan
-    pub fn execute_plan(&self, plan_id: &str) -> Result<()> {
-        if let Some(plan) = self.get_plan(plan_id) {
-            tracing::info!("Executing disaster recovery plan: {}", plan.name);
-            
-            // In a real implementation, this would execute the recovery steps
-            for step in &plan.steps {
-                tracing::info!("Executing step {}: {}", step.order, step.description);
-                // Simulate step execution time
-                std::thread::sleep(std::time::Duration::from_millis(100));
-            }
-            
-            tracing::info!("Disaster recovery plan execution completed");
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("Disaster recovery plan not found"))
+    /// Replace all plans of a tenant, e.g. with ones restored from a backup
+    pub fn restore_tenant_plans(&mut self, tenant_id: &str, plans: Vec<DisasterRecoveryPlan>) -> Result<()> {
+        if let Some(plan) = plans.iter().find(|plan| plan.tenant_id != tenant_id) {
+            return Err(anyhow::anyhow!("Plan {} belongs to tenant {}, not {}", plan.id, plan.tenant_id, tenant_id));
         }
+        self.plans.retain(|_, plan| plan.tenant_id != tenant_id);
+        self.plans.extend(plans.into_iter().map(|plan| (plan.id.clone(), plan)));
+        Ok(())
+    }
+    
+    /// Validate a plan for execution, which can then run without borrowing the manager
+    pub fn prepare_execution(&self, plan_id: &str) -> Result<RecoveryExecution> {
+        let plan = self.get_plan(plan_id).ok_or_else(|| ComplianceError::PlanNotFound(plan_id.to_string()))?;
+        RecoveryExecution::new(plan, &self.actions)
+    }
+    
+    /// Execute a disaster recovery plan to completion
+    pub async fn execute_plan(&self, plan_id: &str) -> Result<RecoveryRun> {
+        Ok(self.prepare_execution(plan_id)?.run().await)
     }
     
     /// Update a disaster recovery plan
@@ -327,7 +414,7 @@ impl DisasterRecoveryManager {
             plan.last_updated = Utc::now();
             Ok(())
         } else {
-            Err(anyhow::anyhow!("Disaster recovery plan not found"))
+            Err(ComplianceError::PlanNotFound(plan_id.to_string()).into())
         }
     }
 }
@@ -336,6 +423,7 @@ impl DisasterRecoveryManager {
 mod tests {
     use super::*;
     use chrono::Duration;
+    use std::sync::Arc;
 
     #[test]
     fn test_compliance_report_generation() {
@@ -358,17 +446,116 @@ mod tests {
     }
 
     #[test]
-    fn test_backup_management() {
-        let mut backup_manager = BackupManager::new();
-        let components = vec!["database".to_string(), "config".to_string()];
+    fn test_trade_audit_includes_audit_log() {
+        let path = std::env::temp_dir().join(format!("sniper-compliance-audit-{}.jsonl", uuid::Uuid::new_v4()));
+        let mut store = AuditStore::open(&path).unwrap();
+        let now = Utc::now();
+        for (tenant_id, action, age_hours) in [("tenant-1", "PLACE_ORDER", 2), ("tenant-1", "PLACE_ORDER", 1), ("tenant-2", "PLACE_ORDER", 1), ("tenant-1", "LOGIN", 48)] {
+            store.append(sniper_users::AuditLog {
+                id: uuid::Uuid::new_v4().to_string(),
+                user_id: "trader-1".to_string(),
+                tenant_id: tenant_id.to_string(),
+                action: action.to_string(),
+                resource: "orders".to_string(),
+                timestamp: now - Duration::hours(age_hours),
+                details: None,
+                correlation_id: None,
+            }).unwrap();
+        }
         
-        let backup = backup_manager.create_backup(components.clone(), "tenant-1").unwrap();
+        let mut compliance_manager = ComplianceManager::new().with_audit_log(&path);
+        let report = compliance_manager
+            .generate_report(ReportType::TradeAudit, now - Duration::days(1), now, "auditor", "tenant-1")
+            .unwrap();
+        assert!(report.content.contains("Audit log entries: 2"));
+        assert!(report.content.contains("  PLACE_ORDER: 2"));
+        assert!(!report.content.contains("LOGIN"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_reports_use_provided_trade_data() {
+        let now = Utc::now();
+        let fill = |side: &str, price: f64, fee: f64, venue: &str, age_hours: i64| TradeFill {
+            order_id: format!("order-{}", age_hours),
+            tenant_id: "tenant-1".to_string(),
+            symbol: "ETH".to_string(),
+            side: side.to_string(),
+            chain: "ethereum".to_string(),
+            venue: venue.to_string(),
+            quantity: 2.0,
+            price,
+            fee,
+            timestamp: now - Duration::hours(age_hours),
+        };
+        let data = ReportDataSet {
+            fills: vec![fill("buy", 100.0, 1.5, "uniswap", 3), fill("sell", 110.0, 0.5, "binance", 2), fill("buy", 90.0, 1.0, "uniswap", 30)],
+            ..ReportDataSet::default()
+        };
+        let mut compliance_manager = ComplianceManager::new().with_data_provider(Arc::new(data));
+        
+        let summary = compliance_manager
+            .generate_report(ReportType::FinancialSummary, now - Duration::days(1), now, "auditor", "tenant-1")
+            .unwrap();
+        assert!(summary.content.contains("Fills: 2\nVolume: 420.00"));
+        assert!(summary.content.contains("Realized PnL: 20.00"));
+        assert!(summary.content.contains("Fees: 2.00"));
+        assert!(summary.content.contains("Net PnL: 18.00"));
+        
+        let audit = compliance_manager
+            .generate_report(ReportType::TradeAudit, now - Duration::days(1), now, "auditor", "tenant-1")
+            .unwrap();
+        assert!(audit.content.contains("  ETH     ethereum  buy   1      2.00      200.00"));
+        assert!(audit.content.contains("Audit log entries: 0"));
+        let other = compliance_manager
+            .generate_report(ReportType::TradeAudit, now - Duration::days(1), now, "auditor", "tenant-2")
+            .unwrap();
+        assert!(other.content.contains("Fills: 0"));
+    }
+
+    #[test]
+    fn test_record_external_report() {
+        let mut compliance_manager = ComplianceManager::new();
+        let now = Utc::now();
+        
+        let report = compliance_manager.record_report(
+            ReportType::IncidentAnalytics,
+            now - Duration::hours(1),
+            now,
+            "Incident Analytics Report\nIncidents: 3 (1 open)".to_string(),
+            "svc-monitoring",
+            "tenant-1",
+        );
+        
+        assert_eq!(report.report_type, ReportType::IncidentAnalytics);
+        assert_eq!(compliance_manager.get_tenant_reports("tenant-1").len(), 1);
+        assert!(compliance_manager.get_report(&report.id).unwrap().content.contains("Incidents: 3"));
+    }
+
+    #[tokio::test]
+    async fn test_backup_management() {
+        let compliance_manager = Arc::new(tokio::sync::RwLock::new(ComplianceManager::new()));
+        let now = Utc::now();
+        let report = compliance_manager
+            .write()
+            .await
+            .generate_report(ReportType::DailyActivity, now - Duration::days(1), now, "user1", "tenant-1")
+            .unwrap();
+        let mut backup_manager = BackupManager::new().with_component(components::REPORTS, compliance_manager.clone());
+        let components = vec![components::REPORTS.to_string()];
+        
+        let backup = backup_manager.create_backup(components.clone(), "tenant-1").await.unwrap();
         assert_eq!(backup.components, components);
         assert_eq!(backup.tenant_id, "tenant-1");
+        assert_eq!(backup.checksum.len(), 64);
         
         let retrieved_backup = backup_manager.get_backup(&backup.id);
         assert!(retrieved_backup.is_some());
         assert_eq!(retrieved_backup.unwrap().id, backup.id);
+        
+        compliance_manager.write().await.restore_tenant_reports("tenant-1", Vec::new()).unwrap();
+        backup_manager.restore_from_backup(&backup.id).await.unwrap();
+        assert_eq!(compliance_manager.read().await.get_report(&report.id).unwrap().content, report.content);
     }
 
     #[test]
@@ -381,6 +568,8 @@ mod tests {
                 description: "Stop all services".to_string(),
                 expected_duration_minutes: 5,
                 dependencies: vec![],
+                action: None,
+                timeout_secs: None,
             },
             RecoveryStep {
                 id: "step-2".to_string(),
@@ -388,6 +577,8 @@ mod tests {
                 description: "Restore from backup".to_string(),
                 expected_duration_minutes: 30,
                 dependencies: vec!["step-1".to_string()],
+                action: None,
+                timeout_secs: None,
             },
         ];
         
@@ -432,11 +623,11 @@ mod tests {
         assert!(unsupported_result.is_err());
     }
 
-    #[test]
-    fn test_tenant_isolation() {
+    #[tokio::test]
+    async fn test_tenant_isolation() {
         let mut compliance_manager = ComplianceManager::new();
-        let mut backup_manager = BackupManager::new();
-        let mut dr_manager = DisasterRecoveryManager::new();
+        let dr_manager = Arc::new(tokio::sync::RwLock::new(DisasterRecoveryManager::new()));
+        let mut backup_manager = BackupManager::new().with_component(components::RECOVERY_PLANS, dr_manager.clone());
         
         let now = Utc::now();
         let yesterday = now - Duration::days(1);
@@ -450,18 +641,18 @@ mod tests {
             "tenant-1",
         ).unwrap();
         
-        let backup1 = backup_manager.create_backup(
-            vec!["db1".to_string()],
-            "tenant-1",
-        ).unwrap();
-        
-        let plan1 = dr_manager.create_plan(
+        let plan1 = dr_manager.write().await.create_plan(
             "Plan 1",
             "Tenant 1 plan",
             vec![],
             "tenant-1",
         );
         
+        let backup1 = backup_manager.create_backup(
+            vec![components::RECOVERY_PLANS.to_string()],
+            "tenant-1",
+        ).await.unwrap();
+        
         // Create resources for tenant-2
         let report2 = compliance_manager.generate_report(
             ReportType::DailyActivity,
@@ -471,18 +662,18 @@ mod tests {
             "tenant-2",
         ).unwrap();
         
-        let backup2 = backup_manager.create_backup(
-            vec!["db2".to_string()],
-            "tenant-2",
-        ).unwrap();
-        
-        let plan2 = dr_manager.create_plan(
+        let plan2 = dr_manager.write().await.create_plan(
             "Plan 2",
             "Tenant 2 plan",
             vec![],
             "tenant-2",
         );
         
+        let backup2 = backup_manager.create_backup(
+            vec![components::RECOVERY_PLANS.to_string()],
+            "tenant-2",
+        ).await.unwrap();
+        
         // Verify tenant isolation
         let tenant1_reports = compliance_manager.get_tenant_reports("tenant-1");
         let tenant2_reports = compliance_manager.get_tenant_reports("tenant-2");
@@ -496,6 +687,7 @@ mod tests {
         assert_eq!(tenant2_backups.len(), 1);
         assert_ne!(tenant1_backups[0].id, tenant2_backups[0].id);
         
+        let dr_manager = dr_manager.read().await;
         let tenant1_plans = dr_manager.list_tenant_plans("tenant-1");
         let tenant2_plans = dr_manager.list_tenant_plans("tenant-2");
         assert_eq!(tenant1_plans.len(), 1);
@@ -0,0 +1,496 @@
+//! Execution of disaster recovery plans.
+//!
+//! A plan's steps form a dependency graph. Execution validates it up front,
+//! then starts every step whose dependencies have succeeded, so independent
+//! steps run concurrently. A step runs the [`RecoveryAction`] it names, or
+//! is a manual checkpoint that completes at once if it names none. When a
+//! step fails or exceeds its timeout, no further steps start; once running
+//! steps finish, the succeeded ones are rolled back newest first. Every
+//! status change is published as [`RecoveryProgress`].
+
+use crate::error::ComplianceError;
+use crate::{DisasterRecoveryPlan, RecoveryStep};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::{broadcast, RwLock};
+use tokio::task::JoinSet;
+
+/// Progress updates buffered per subscriber before it lags
+const PROGRESS_BUFFER: usize = 256;
+
+/// Work behind a recovery step, with an optional hook undoing it
+#[async_trait]
+pub trait RecoveryAction: Send + Sync {
+    /// Carry out the step
+    async fn execute(&self, step: &RecoveryStep) -> Result<()>;
+
+    /// Undo the step after a later step failed
+    async fn rollback(&self, _step: &RecoveryStep) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Status of one step within a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum StepStatus {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    TimedOut,
+    /// Not started because an earlier step failed
+    Skipped,
+    RolledBack,
+    RollbackFailed,
+}
+
+/// Status of a whole run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum RunStatus {
+    Running,
+    Succeeded,
+    /// A step failed and every succeeded step was rolled back
+    RolledBack,
+    /// A step failed and rolling back another one failed too
+    Failed,
+}
+
+/// Record of one step within a run
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StepRun {
+    pub step_id: String,
+    pub status: StepStatus,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub duration_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Record of one execution of a plan
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RecoveryRun {
+    pub id: String,
+    pub plan_id: String,
+    pub tenant_id: String,
+    pub status: RunStatus,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Steps in the order they may run
+    pub steps: Vec<StepRun>,
+}
+
+impl RecoveryRun {
+    /// Record of a step by ID
+    pub fn step(&self, step_id: &str) -> Option<&StepRun> {
+        self.steps.iter().find(|step| step.step_id == step_id)
+    }
+
+    fn step_mut(&mut self, step_id: &str) -> &mut StepRun {
+        self.steps
+            .iter_mut()
+            .find(|step| step.step_id == step_id)
+            .expect("runs hold a record of every plan step")
+    }
+}
+
+/// Change to a run: a step's new record, or the run finishing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryProgress {
+    pub run_id: String,
+    pub status: RunStatus,
+    pub step: Option<StepRun>,
+}
+
+/// Shared view of a run, updated while it executes
+#[derive(Clone)]
+pub struct RecoveryHandle {
+    run: Arc<RwLock<RecoveryRun>>,
+    progress: broadcast::Sender<RecoveryProgress>,
+}
+
+impl RecoveryHandle {
+    /// Current state of the run
+    pub async fn snapshot(&self) -> RecoveryRun {
+        self.run.read().await.clone()
+    }
+
+    /// Receive progress from now on; subscribe before taking a snapshot to miss nothing
+    pub fn subscribe(&self) -> broadcast::Receiver<RecoveryProgress> {
+        self.progress.subscribe()
+    }
+
+    async fn update_step(&self, step_id: &str, update: impl FnOnce(&mut StepRun)) {
+        let progress = {
+            let mut run = self.run.write().await;
+            let step = run.step_mut(step_id);
+            update(step);
+            let step = step.clone();
+            RecoveryProgress {
+                run_id: run.id.clone(),
+                status: run.status,
+                step: Some(step),
+            }
+        };
+        // Nobody may be listening
+        let _ = self.progress.send(progress);
+    }
+
+    async fn finish(&self, status: RunStatus) -> RecoveryRun {
+        let run = {
+            let mut run = self.run.write().await;
+            run.status = status;
+            run.finished_at = Some(Utc::now());
+            run.clone()
+        };
+        let _ = self.progress.send(RecoveryProgress {
+            run_id: run.id.clone(),
+            status,
+            step: None,
+        });
+        run
+    }
+}
+
+/// Validated plan ready to run
+pub struct RecoveryExecution {
+    steps: Vec<RecoveryStep>,
+    actions: HashMap<String, Arc<dyn RecoveryAction>>,
+    handle: RecoveryHandle,
+}
+
+impl RecoveryExecution {
+    /// Check the plan's steps form a dependency graph of registered actions
+    pub(crate) fn new(plan: &DisasterRecoveryPlan, actions: &HashMap<String, Arc<dyn RecoveryAction>>) -> Result<Self> {
+        let steps = topological_order(&plan.steps)?;
+        let mut step_actions = HashMap::new();
+        for step in &steps {
+            if let Some(name) = &step.action {
+                let action = actions
+                    .get(name)
+                    .ok_or_else(|| invalid_plan(format!("step {} uses unknown recovery action {}", step.id, name)))?;
+                step_actions.insert(step.id.clone(), action.clone());
+            }
+        }
+
+        let run = RecoveryRun {
+            id: uuid::Uuid::new_v4().to_string(),
+            plan_id: plan.id.clone(),
+            tenant_id: plan.tenant_id.clone(),
+            status: RunStatus::Running,
+            started_at: Utc::now(),
+            finished_at: None,
+            steps: steps
+                .iter()
+                .map(|step| StepRun {
+                    step_id: step.id.clone(),
+                    status: StepStatus::Pending,
+                    started_at: None,
+                    finished_at: None,
+                    duration_ms: None,
+                    error: None,
+                })
+                .collect(),
+        };
+        let (progress, _) = broadcast::channel(PROGRESS_BUFFER);
+        Ok(Self {
+            steps,
+            actions: step_actions,
+            handle: RecoveryHandle {
+                run: Arc::new(RwLock::new(run)),
+                progress,
+            },
+        })
+    }
+
+    /// Handle to follow the run with
+    pub fn handle(&self) -> RecoveryHandle {
+        self.handle.clone()
+    }
+
+    /// Run the plan to completion, rolling back if a step fails
+    pub async fn run(self) -> RecoveryRun {
+        let Self { steps, actions, handle } = self;
+        let plan_id = handle.run.read().await.plan_id.clone();
+        tracing::info!("Executing disaster recovery plan {}", plan_id);
+
+        let mut started: HashSet<&str> = HashSet::new();
+        let mut succeeded: Vec<&RecoveryStep> = Vec::new();
+        let mut failed = false;
+        let mut running = JoinSet::new();
+        loop {
+            if !failed {
+                let done: HashSet<&str> = succeeded.iter().map(|step| step.id.as_str()).collect();
+                for step in &steps {
+                    let ready = step.dependencies.iter().all(|dependency| done.contains(dependency.as_str()));
+                    if ready && started.insert(step.id.as_str()) {
+                        handle
+                            .update_step(&step.id, |record| {
+                                record.status = StepStatus::Running;
+                                record.started_at = Some(Utc::now());
+                            })
+                            .await;
+                        let (step, action) = (step.clone(), actions.get(&step.id).cloned());
+                        running.spawn(async move {
+                            let step_id = step.id.clone();
+                            // A panicking action fails its step
+                            match tokio::spawn(execute_step(step, action)).await {
+                                Ok(outcome) => outcome,
+                                Err(e) => (step_id, StepStatus::Failed, Some(e.to_string())),
+                            }
+                        });
+                    }
+                }
+            }
+            let Some(finished) = running.join_next().await else {
+                break;
+            };
+            let Ok((step_id, status, error)) = finished else {
+                continue;
+            };
+            let Some(step) = steps.iter().find(|step| step.id == step_id) else {
+                continue;
+            };
+            if status == StepStatus::Succeeded {
+                succeeded.push(step);
+            } else {
+                tracing::error!("Recovery step {} failed: {}", step.id, error.as_deref().unwrap_or_default());
+                failed = true;
+            }
+            handle
+                .update_step(&step.id, |record| {
+                    let finished_at = Utc::now();
+                    record.status = status;
+                    record.duration_ms = record
+                        .started_at
+                        .map(|started_at| (finished_at - started_at).num_milliseconds().max(0) as u64);
+                    record.finished_at = Some(finished_at);
+                    record.error = error;
+                })
+                .await;
+        }
+
+        if !failed {
+            tracing::info!("Disaster recovery plan {} completed", plan_id);
+            return handle.finish(RunStatus::Succeeded).await;
+        }
+        for step in steps.iter().filter(|step| !started.contains(step.id.as_str())) {
+            handle.update_step(&step.id, |record| record.status = StepStatus::Skipped).await;
+        }
+        let mut rolled_back = true;
+        for step in succeeded.into_iter().rev() {
+            let result = match actions.get(&step.id) {
+                Some(action) => action.rollback(step).await,
+                None => Ok(()),
+            };
+            if let Err(e) = &result {
+                tracing::error!("Rolling back recovery step {} failed: {}", step.id, e);
+                rolled_back = false;
+            }
+            handle
+                .update_step(&step.id, |record| match result {
+                    Ok(()) => record.status = StepStatus::RolledBack,
+                    Err(e) => {
+                        record.status = StepStatus::RollbackFailed;
+                        record.error = Some(e.to_string());
+                    },
+                })
+                .await;
+        }
+        handle
+            .finish(if rolled_back { RunStatus::RolledBack } else { RunStatus::Failed })
+            .await
+    }
+}
+
+async fn execute_step(step: RecoveryStep, action: Option<Arc<dyn RecoveryAction>>) -> (String, StepStatus, Option<String>) {
+    tracing::info!("Executing step {}: {}", step.order, step.description);
+    let Some(action) = action else {
+        return (step.id, StepStatus::Succeeded, None);
+    };
+    let result = match step.timeout_secs {
+        Some(secs) => match tokio::time::timeout(std::time::Duration::from_secs(secs), action.execute(&step)).await {
+            Ok(result) => result,
+            Err(_) => return (step.id, StepStatus::TimedOut, Some(format!("Timed out after {}s", secs))),
+        },
+        None => action.execute(&step).await,
+    };
+    match result {
+        Ok(()) => (step.id, StepStatus::Succeeded, None),
+        Err(e) => (step.id, StepStatus::Failed, Some(e.to_string())),
+    }
+}
+
+/// Steps ordered so each follows its dependencies, ties broken by `order`
+fn topological_order(steps: &[RecoveryStep]) -> Result<Vec<RecoveryStep>> {
+    let mut ids = HashSet::new();
+    for step in steps {
+        if !ids.insert(step.id.as_str()) {
+            return Err(invalid_plan(format!("step {} appears twice", step.id)));
+        }
+    }
+    for step in steps {
+        if let Some(dependency) = step.dependencies.iter().find(|dependency| !ids.contains(dependency.as_str())) {
+            return Err(invalid_plan(format!("step {} depends on unknown step {}", step.id, dependency)));
+        }
+    }
+
+    let mut remaining: Vec<&RecoveryStep> = steps.iter().collect();
+    remaining.sort_by_key(|step| step.order);
+    let mut ordered: Vec<RecoveryStep> = Vec::new();
+    while !remaining.is_empty() {
+        let placed: HashSet<&str> = ordered.iter().map(|step| step.id.as_str()).collect();
+        let Some(index) = remaining
+            .iter()
+            .position(|step| step.dependencies.iter().all(|dependency| placed.contains(dependency.as_str())))
+        else {
+            let cycle: Vec<&str> = remaining.iter().map(|step| step.id.as_str()).collect();
+            return Err(invalid_plan(format!("steps {} depend on each other", cycle.join(", "))));
+        };
+        ordered.push(remaining.remove(index).clone());
+    }
+    Ok(ordered)
+}
+
+fn invalid_plan(reason: String) -> anyhow::Error {
+    ComplianceError::InvalidPlan(reason).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DisasterRecoveryManager;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    fn step(id: &str, order: u32, dependencies: &[&str], action: Option<&str>) -> RecoveryStep {
+        RecoveryStep {
+            id: id.to_string(),
+            order,
+            description: format!("Step {}", id),
+            expected_duration_minutes: 1,
+            dependencies: dependencies.iter().map(|dependency| dependency.to_string()).collect(),
+            action: action.map(str::to_string),
+            timeout_secs: None,
+        }
+    }
+
+    /// Records calls, sleeping before each step and failing the named ones
+    #[derive(Default)]
+    struct Recorder {
+        calls: Mutex<Vec<String>>,
+        fail: Vec<String>,
+        delay_ms: u64,
+    }
+
+    #[async_trait]
+    impl RecoveryAction for Recorder {
+        async fn execute(&self, step: &RecoveryStep) -> Result<()> {
+            self.calls.lock().unwrap().push(format!("start {}", step.id));
+            tokio::time::sleep(Duration::from_millis(self.delay_ms)).await;
+            self.calls.lock().unwrap().push(format!("end {}", step.id));
+            if self.fail.contains(&step.id) {
+                return Err(anyhow::anyhow!("{} broke", step.id));
+            }
+            Ok(())
+        }
+
+        async fn rollback(&self, step: &RecoveryStep) -> Result<()> {
+            self.calls.lock().unwrap().push(format!("rollback {}", step.id));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_invalid_graphs_are_rejected() {
+        assert!(topological_order(&[step("a", 1, &["b"], None), step("b", 2, &["a"], None)]).is_err());
+        assert!(topological_order(&[step("a", 1, &["missing"], None)]).is_err());
+        assert!(topological_order(&[step("a", 1, &[], None), step("a", 2, &[], None)]).is_err());
+
+        let ordered = topological_order(&[step("c", 1, &["b"], None), step("b", 2, &[], None), step("a", 3, &[], None)]).unwrap();
+        let ids: Vec<&str> = ordered.iter().map(|step| step.id.as_str()).collect();
+        assert_eq!(ids, ["b", "c", "a"]);
+    }
+
+    #[tokio::test]
+    async fn test_independent_steps_run_concurrently() {
+        let recorder = Arc::new(Recorder {
+            delay_ms: 50,
+            ..Recorder::default()
+        });
+        let mut manager = DisasterRecoveryManager::new().with_action("record", recorder.clone());
+        let steps = vec![
+            step("stop", 1, &[], Some("record")),
+            step("restore-db", 2, &["stop"], Some("record")),
+            step("restore-cache", 3, &["stop"], Some("record")),
+            step("start", 4, &["restore-db", "restore-cache"], None),
+        ];
+        let plan = manager.create_plan("Full restore", "", steps, "tenant-1");
+
+        let execution = manager.prepare_execution(&plan.id).unwrap();
+        let mut progress = execution.handle().subscribe();
+        let run = execution.run().await;
+        assert_eq!(run.status, RunStatus::Succeeded);
+        assert!(run.steps.iter().all(|step| step.status == StepStatus::Succeeded));
+        assert!(run.step("restore-db").unwrap().duration_ms.unwrap() >= 50);
+
+        let calls = recorder.calls.lock().unwrap().clone();
+        assert_eq!(calls[..2], ["start stop", "end stop"]);
+        // Both restores start before either ends
+        assert!(calls[2].starts_with("start") && calls[3].starts_with("start"));
+
+        let mut updates = Vec::new();
+        while let Ok(update) = progress.try_recv() {
+            updates.push(update);
+        }
+        // Running and finished for each step, then the run itself
+        assert_eq!(updates.len(), 9);
+        assert!(updates.last().unwrap().step.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_failed_step_rolls_back_succeeded_steps() {
+        let recorder = Arc::new(Recorder {
+            fail: vec!["restore-db".to_string()],
+            ..Recorder::default()
+        });
+        let mut manager = DisasterRecoveryManager::new().with_action("record", recorder.clone());
+        let steps = vec![
+            step("stop", 1, &[], Some("record")),
+            step("restore-db", 2, &["stop"], Some("record")),
+            step("start", 3, &["restore-db"], Some("record")),
+        ];
+        let plan = manager.create_plan("Full restore", "", steps, "tenant-1");
+
+        let run = manager.execute_plan(&plan.id).await.unwrap();
+        assert_eq!(run.status, RunStatus::RolledBack);
+        assert_eq!(run.step("stop").unwrap().status, StepStatus::RolledBack);
+        assert_eq!(run.step("restore-db").unwrap().status, StepStatus::Failed);
+        assert_eq!(run.step("restore-db").unwrap().error.as_deref(), Some("restore-db broke"));
+        assert_eq!(run.step("start").unwrap().status, StepStatus::Skipped);
+        assert_eq!(recorder.calls.lock().unwrap().last().unwrap(), "rollback stop");
+    }
+
+    #[tokio::test]
+    async fn test_step_timeout() {
+        let recorder = Arc::new(Recorder {
+            delay_ms: 5_000,
+            ..Recorder::default()
+        });
+        let mut manager = DisasterRecoveryManager::new().with_action("record", recorder);
+        let mut slow = step("slow", 1, &[], Some("record"));
+        slow.timeout_secs = Some(0);
+        let plan = manager.create_plan("Slow", "", vec![slow], "tenant-1");
+
+        let run = manager.execute_plan(&plan.id).await.unwrap();
+        assert_eq!(run.step("slow").unwrap().status, StepStatus::TimedOut);
+        assert_eq!(run.status, RunStatus::RolledBack);
+
+        let unknown = manager.create_plan("Unknown", "", vec![step("a", 1, &[], Some("missing"))], "tenant-1");
+        assert!(manager.execute_plan(&unknown.id).await.is_err());
+    }
+}
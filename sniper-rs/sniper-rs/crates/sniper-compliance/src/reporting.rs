@@ -0,0 +1,469 @@
+//! Trading data behind compliance reports.
+//!
+//! A [`ReportDataProvider`] supplies a tenant's orders, fills, funding
+//! charges, positions and audit log entries for a report period. Trade audit
+//! reports list the period's orders and fills; financial summaries total
+//! volumes, realized and unrealized PnL, fees per venue and chain and funding
+//! per symbol, and report PnL both gross and net of fees and funding.
+//! Realized PnL only counts fills within the period, so inventory bought
+//! before it is not matched.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sniper_core::types::Decimal;
+use sniper_orders::AdvancedOrder;
+use sniper_portfolio::{FundingCharge, Position};
+use sniper_users::AuditLog;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+/// Venue of fills recorded without one, such as those taken from orders
+pub const UNKNOWN_VENUE: &str = "unknown";
+
+/// Execution of part of an order at a venue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradeFill {
+    pub order_id: String,
+    pub tenant_id: String,
+    pub symbol: String,
+    pub side: String, // "buy" or "sell"
+    pub chain: String,
+    pub venue: String,
+    pub quantity: f64,
+    pub price: f64,
+    /// Fee paid for the fill, in the quote currency
+    #[serde(default)]
+    pub fee: f64,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl TradeFill {
+    /// Fills recorded on an order, which carry no venue or fee
+    pub fn from_order(order: &AdvancedOrder) -> Vec<TradeFill> {
+        order
+            .fills
+            .iter()
+            .map(|fill| TradeFill {
+                order_id: order.id.clone(),
+                tenant_id: order.tenant_id.clone().unwrap_or_default(),
+                symbol: order.symbol.clone(),
+                side: order.side.clone(),
+                chain: order.chain.name.clone(),
+                venue: UNKNOWN_VENUE.to_string(),
+                quantity: fill.quantity.to_f64(),
+                price: fill.price.to_f64(),
+                fee: 0.0,
+                timestamp: from_unix(fill.timestamp),
+            })
+            .collect()
+    }
+
+    /// Quantity times price
+    pub fn notional(&self) -> f64 {
+        self.quantity * self.price
+    }
+
+    fn is_buy(&self) -> bool {
+        self.side.eq_ignore_ascii_case("buy")
+    }
+}
+
+fn from_unix(seconds: u64) -> DateTime<Utc> {
+    DateTime::from_timestamp(seconds as i64, 0).unwrap_or_default()
+}
+
+/// Source of the trading data a tenant's reports cover
+pub trait ReportDataProvider: Send + Sync {
+    /// Orders created or filled during the period
+    fn orders(&self, tenant_id: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<AdvancedOrder>>;
+
+    /// Fills executed during the period
+    fn fills(&self, tenant_id: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<TradeFill>>;
+
+    /// Funding charged to positions during the period
+    fn funding(&self, tenant_id: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<FundingCharge>>;
+
+    /// Positions currently held
+    fn positions(&self, tenant_id: &str) -> Result<Vec<Position>>;
+
+    /// Audit log entries of the period
+    fn audit_logs(&self, tenant_id: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<AuditLog>>;
+}
+
+/// Report data held in memory, e.g. exported by the trading services
+///
+/// Orders whose fills are not listed separately contribute their own fills,
+/// without venue or fee.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReportDataSet {
+    #[serde(default)]
+    pub orders: Vec<AdvancedOrder>,
+    #[serde(default)]
+    pub fills: Vec<TradeFill>,
+    #[serde(default)]
+    pub funding: Vec<FundingCharge>,
+    #[serde(default)]
+    pub positions: Vec<Position>,
+    #[serde(default)]
+    pub audit_logs: Vec<AuditLog>,
+}
+
+impl ReportDataSet {
+    /// Load a data set from a JSON file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read report data {}: {}", path.display(), e))?;
+        serde_json::from_str(&contents).map_err(|e| anyhow::anyhow!("Invalid report data {}: {}", path.display(), e))
+    }
+}
+
+impl ReportDataProvider for ReportDataSet {
+    fn orders(&self, tenant_id: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<AdvancedOrder>> {
+        let in_period = |seconds: u64| (start..=end).contains(&from_unix(seconds));
+        Ok(self
+            .orders
+            .iter()
+            .filter(|order| order.tenant_id.as_deref() == Some(tenant_id))
+            .filter(|order| in_period(order.created_at) || order.fills.iter().any(|fill| in_period(fill.timestamp)))
+            .cloned()
+            .collect())
+    }
+
+    fn fills(&self, tenant_id: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<TradeFill>> {
+        let listed: HashSet<&str> = self.fills.iter().map(|fill| fill.order_id.as_str()).collect();
+        let from_orders = self
+            .orders
+            .iter()
+            .filter(|order| !listed.contains(order.id.as_str()))
+            .flat_map(TradeFill::from_order);
+        Ok(self
+            .fills
+            .iter()
+            .cloned()
+            .chain(from_orders)
+            .filter(|fill| fill.tenant_id == tenant_id && (start..=end).contains(&fill.timestamp))
+            .collect())
+    }
+
+    fn funding(&self, tenant_id: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<FundingCharge>> {
+        Ok(self
+            .funding
+            .iter()
+            .filter(|charge| charge.tenant_id.as_deref() == Some(tenant_id))
+            .filter(|charge| (start..=end).contains(&from_unix(charge.charged_at)))
+            .cloned()
+            .collect())
+    }
+
+    fn positions(&self, tenant_id: &str) -> Result<Vec<Position>> {
+        Ok(self
+            .positions
+            .iter()
+            .filter(|position| position.tenant_id.as_deref() == Some(tenant_id))
+            .cloned()
+            .collect())
+    }
+
+    fn audit_logs(&self, tenant_id: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<AuditLog>> {
+        Ok(self
+            .audit_logs
+            .iter()
+            .filter(|entry| entry.tenant_id == tenant_id && (start..=end).contains(&entry.timestamp))
+            .cloned()
+            .collect())
+    }
+}
+
+/// Orders by status, fill totals per symbol and the fills themselves, oldest first
+pub(crate) fn trade_section(orders: &[AdvancedOrder], fills: &[TradeFill]) -> String {
+    let mut by_status: BTreeMap<String, usize> = BTreeMap::new();
+    for order in orders {
+        *by_status.entry(format!("{:?}", order.status)).or_insert(0) += 1;
+    }
+    let mut section = format!("\n\nOrders: {}\n", orders.len());
+    for (status, count) in by_status {
+        section.push_str(&format!("  {}: {}\n", status, count));
+    }
+
+    let mut by_symbol: BTreeMap<(&str, &str, &str), (usize, f64, f64)> = BTreeMap::new();
+    for fill in fills {
+        let totals = by_symbol
+            .entry((fill.symbol.as_str(), fill.chain.as_str(), fill.side.as_str()))
+            .or_insert((0, 0.0, 0.0));
+        totals.0 += 1;
+        totals.1 += fill.quantity;
+        totals.2 += fill.notional();
+    }
+    section.push_str(&format!("\nFills: {}\n", fills.len()));
+    let rows = by_symbol
+        .into_iter()
+        .map(|((symbol, chain, side), (count, quantity, notional))| {
+            vec![symbol.to_string(), chain.to_string(), side.to_string(), count.to_string(), amount(quantity), amount(notional)]
+        })
+        .collect();
+    section.push_str(&table(&["Symbol", "Chain", "Side", "Fills", "Quantity", "Notional"], rows));
+
+    let mut fills: Vec<&TradeFill> = fills.iter().collect();
+    fills.sort_by_key(|fill| fill.timestamp);
+    let rows = fills
+        .into_iter()
+        .map(|fill| {
+            vec![
+                fill.timestamp.to_rfc3339(),
+                fill.order_id.clone(),
+                fill.symbol.clone(),
+                fill.side.clone(),
+                amount(fill.quantity),
+                amount(fill.price),
+                fill.venue.clone(),
+                amount(fill.fee),
+            ]
+        })
+        .collect();
+    section.push('\n');
+    section.push_str(&table(&["Time", "Order", "Symbol", "Side", "Quantity", "Price", "Venue", "Fee"], rows));
+    section
+}
+
+/// Volumes, PnL, fees and funding of the period's fills and the open positions
+///
+/// Fails if the funding or unrealized PnL totals overflow the decimal range.
+pub(crate) fn financial_section(fills: &[TradeFill], funding: &[FundingCharge], positions: &[Position]) -> Result<String> {
+    let volume: f64 = fills.iter().map(TradeFill::notional).sum();
+    let fees: f64 = fills.iter().map(|fill| fill.fee).sum();
+    let funding_paid = Decimal::checked_sum(funding.iter().map(|charge| charge.amount))
+        .ok_or_else(|| anyhow::anyhow!("Funding total is out of the decimal range"))?
+        .to_f64();
+    let realized = realized_pnl(fills);
+    let unrealized = Decimal::checked_sum(positions.iter().map(|position| position.pnl))
+        .ok_or_else(|| anyhow::anyhow!("Unrealized PnL total is out of the decimal range"))?
+        .to_f64();
+    let gross = realized + unrealized;
+
+    let mut section = format!("\n\nFills: {}\nVolume: {}\n", fills.len(), amount(volume));
+    section.push_str(&format!("Realized PnL: {}\n", amount(realized)));
+    section.push_str(&format!("Unrealized PnL: {}\n", amount(unrealized)));
+    section.push_str(&format!("Gross PnL: {}\n", amount(gross)));
+    section.push_str(&format!("Fees: {}\n", amount(fees)));
+    section.push_str(&format!("Funding: {}\n", amount(funding_paid)));
+    section.push_str(&format!("Net PnL: {}\n", amount(gross - fees - funding_paid)));
+
+    let mut by_venue: BTreeMap<(&str, &str), (usize, f64, f64)> = BTreeMap::new();
+    for fill in fills {
+        let totals = by_venue.entry((fill.venue.as_str(), fill.chain.as_str())).or_insert((0, 0.0, 0.0));
+        totals.0 += 1;
+        totals.1 += fill.notional();
+        totals.2 += fill.fee;
+    }
+    let rows = by_venue
+        .into_iter()
+        .map(|((venue, chain), (count, volume, fees))| {
+            vec![venue.to_string(), chain.to_string(), count.to_string(), amount(volume), amount(fees)]
+        })
+        .collect();
+    section.push_str("\nFees by venue and chain\n");
+    section.push_str(&table(&["Venue", "Chain", "Fills", "Volume", "Fees"], rows));
+
+    let mut by_symbol: BTreeMap<&str, (usize, Decimal)> = BTreeMap::new();
+    for charge in funding {
+        let totals = by_symbol.entry(charge.symbol.as_str()).or_insert((0, Decimal::ZERO));
+        totals.0 += 1;
+        totals.1 = totals
+            .1
+            .checked_add(charge.amount)
+            .ok_or_else(|| anyhow::anyhow!("Funding of {} is out of the decimal range", charge.symbol))?;
+    }
+    let rows = by_symbol
+        .into_iter()
+        .map(|(symbol, (count, paid))| vec![symbol.to_string(), count.to_string(), amount(paid.to_f64())])
+        .collect();
+    section.push_str("\nFunding by symbol\n");
+    section.push_str(&table(&["Symbol", "Charges", "Funding"], rows));
+
+    let rows = positions
+        .iter()
+        .map(|position| {
+            vec![
+                position.symbol.clone(),
+                position.chain.name.clone(),
+                position.side.clone(),
+                amount(position.amount.to_f64()),
+                amount(position.entry_price.to_f64()),
+                amount(position.current_price.to_f64()),
+                amount(position.pnl.to_f64()),
+            ]
+        })
+        .collect();
+    section.push_str(&format!("\nOpen positions: {}\n", positions.len()));
+    section.push_str(&table(&["Symbol", "Chain", "Side", "Amount", "Entry", "Current", "PnL"], rows));
+    Ok(section)
+}
+
+/// PnL of fills closing inventory opened by earlier fills, at average cost per symbol and chain
+fn realized_pnl(fills: &[TradeFill]) -> f64 {
+    let mut fills: Vec<&TradeFill> = fills.iter().collect();
+    fills.sort_by_key(|fill| fill.timestamp);
+
+    // Signed inventory and its average cost
+    let mut inventory: BTreeMap<(&str, &str), (f64, f64)> = BTreeMap::new();
+    let mut realized = 0.0;
+    for fill in fills {
+        let (held, cost) = inventory.entry((fill.symbol.as_str(), fill.chain.as_str())).or_insert((0.0, 0.0));
+        let traded = if fill.is_buy() { fill.quantity } else { -fill.quantity };
+        if *held == 0.0 || held.signum() == traded.signum() {
+            *cost = (*held * *cost + traded * fill.price) / (*held + traded);
+            *held += traded;
+            continue;
+        }
+        let closed = traded.abs().min(held.abs());
+        realized += closed * (fill.price - *cost) * held.signum();
+        let remaining = traded.abs() - closed;
+        *held -= closed * held.signum();
+        if remaining > 0.0 {
+            *held = remaining * traded.signum();
+            *cost = fill.price;
+        } else if *held == 0.0 {
+            *cost = 0.0;
+        }
+    }
+    realized
+}
+
+fn amount(value: f64) -> String {
+    format!("{:.2}", value)
+}
+
+/// Rows indented by two spaces, with columns padded to their widest cell
+fn table(headers: &[&str], rows: Vec<Vec<String>>) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|header| header.len()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    let line = |cells: Vec<&str>| {
+        let padded: Vec<String> = cells.iter().zip(&widths).map(|(cell, width)| format!("{:<1$}", cell, width)).collect();
+        format!("  {}\n", padded.join("  ").trim_end())
+    };
+    let mut table = line(headers.to_vec());
+    for row in &rows {
+        table.push_str(&line(row.iter().map(String::as_str).collect()));
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sniper_core::types::ChainRef;
+    use sniper_orders::{OrderFill, OrderStatus, OrderType, TimeInForce};
+
+    fn fill(side: &str, quantity: f64, price: f64, fee: f64, venue: &str, minute: i64) -> TradeFill {
+        TradeFill {
+            order_id: format!("order-{}", minute),
+            tenant_id: "tenant-1".to_string(),
+            symbol: "ETH".to_string(),
+            side: side.to_string(),
+            chain: "ethereum".to_string(),
+            venue: venue.to_string(),
+            quantity,
+            price,
+            fee,
+            timestamp: DateTime::from_timestamp(1_700_000_000 + minute * 60, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_realized_pnl_at_average_cost() {
+        let fills = [
+            fill("buy", 1.0, 100.0, 0.0, "uniswap", 0),
+            fill("buy", 1.0, 200.0, 0.0, "uniswap", 1),
+            // Sells two at 170 against a cost of 150, then goes short one at 170
+            fill("sell", 3.0, 170.0, 0.0, "uniswap", 2),
+            fill("buy", 1.0, 160.0, 0.0, "uniswap", 3),
+        ];
+        assert_eq!(realized_pnl(&fills), 2.0 * 20.0 + 10.0);
+    }
+
+    #[test]
+    fn test_data_set_derives_fills_from_orders() {
+        let order = AdvancedOrder {
+            id: "order-9".to_string(),
+            symbol: "ARB".to_string(),
+            chain: ChainRef {
+                name: "arbitrum".to_string(),
+                id: 42161,
+            },
+            order_type: OrderType::Market,
+            side: "buy".to_string(),
+            amount: Decimal::from(10),
+            time_in_force: TimeInForce::GoodTillCancelled,
+            created_at: 1_700_000_000,
+            updated_at: 1_700_000_000,
+            status: OrderStatus::Filled,
+            fills: vec![OrderFill {
+                quantity: Decimal::from(10),
+                price: "1.5".parse().unwrap(),
+                timestamp: 1_700_000_030,
+            }],
+            owner_id: None,
+            tenant_id: Some("tenant-1".to_string()),
+            trigger: None,
+        };
+        let data = ReportDataSet {
+            orders: vec![order],
+            fills: vec![fill("buy", 1.0, 100.0, 0.3, "uniswap", 0), fill("buy", 1.0, 100.0, 0.3, "uniswap", 60 * 24 * 2)],
+            ..ReportDataSet::default()
+        };
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let end = start + chrono::Duration::days(1);
+
+        let fills = data.fills("tenant-1", start, end).unwrap();
+        assert_eq!(fills.len(), 2);
+        assert_eq!((fills[1].venue.as_str(), fills[1].chain.as_str()), (UNKNOWN_VENUE, "arbitrum"));
+        assert_eq!(data.orders("tenant-1", start, end).unwrap().len(), 1);
+        assert!(data.fills("tenant-2", start, end).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fee_totals_per_venue_and_chain() {
+        let fills = [
+            fill("buy", 1.0, 100.0, 0.3, "uniswap", 0),
+            fill("sell", 1.0, 110.0, 0.2, "binance", 1),
+            fill("buy", 2.0, 100.0, 0.5, "uniswap", 2),
+        ];
+        let funding = [FundingCharge {
+            position_id: "pos-1".to_string(),
+            symbol: "ETH".to_string(),
+            rate: Some(0.0001),
+            amount: "0.5".parse().unwrap(),
+            charged_at: 1_700_000_000,
+            tenant_id: Some("tenant-1".to_string()),
+        }];
+        let section = financial_section(&fills, &funding, &[]).unwrap();
+        assert!(section.contains("Volume: 410.00"));
+        assert!(section.contains("Realized PnL: 10.00"));
+        assert!(section.contains("Gross PnL: 10.00"));
+        assert!(section.contains("Fees: 1.00"));
+        assert!(section.contains("Funding: 0.50"));
+        assert!(section.contains("Net PnL: 8.50"));
+        assert!(section.contains("  ETH     1        0.50"));
+        assert!(section.contains("  binance  ethereum  1      110.00  0.20"));
+        assert!(section.contains("  uniswap  ethereum  2      300.00  0.80"));
+    }
+
+    #[test]
+    fn test_funding_overflow_is_an_error() {
+        let charge = FundingCharge {
+            position_id: "pos-1".to_string(),
+            symbol: "ETH".to_string(),
+            rate: None,
+            amount: Decimal::MAX,
+            charged_at: 1_700_000_000,
+            tenant_id: Some("tenant-1".to_string()),
+        };
+        assert!(financial_section(&[], &[charge.clone(), charge], &[]).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+//! Compliance report digests scheduled by user preferences.
+//!
+//! Each run collects the reports generated for a user's tenant since their
+//! last digest and, once their digest period has elapsed, bundles them into
+//! a digest addressed to the channels the user chose. Users whose
+//! preferences exclude compliance reports receive nothing.
+
+use crate::{ComplianceManager, ComplianceReport};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sniper_users::notifications::EVENT_COMPLIANCE_REPORT;
+use sniper_users::{DigestFrequency, NotificationChannel, NotificationSeverity, UserManager};
+use std::collections::HashMap;
+
+/// Compliance reports bundled for one user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportDigest {
+    pub user_id: String,
+    pub email: String,
+    pub channels: Vec<NotificationChannel>,
+    pub frequency: DigestFrequency,
+    pub period_start: Option<DateTime<Utc>>,
+    pub period_end: DateTime<Utc>,
+    pub reports: Vec<ComplianceReport>,
+    pub content: String,
+}
+
+/// Scheduler delivering compliance report digests
+#[derive(Default)]
+pub struct ReportDigestScheduler {
+    last_sent: HashMap<String, DateTime<Utc>>,
+}
+
+impl ReportDigestScheduler {
+    /// Create a new digest scheduler
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// When a user's last digest was sent
+    pub fn last_sent(&self, user_id: &str) -> Option<DateTime<Utc>> {
+        self.last_sent.get(user_id).copied()
+    }
+
+    /// Build the digests due at `now` for every user in a tenant
+    pub fn run(
+        &mut self,
+        compliance: &ComplianceManager,
+        users: &UserManager,
+        tenant_id: &str,
+        now: DateTime<Utc>,
+    ) -> Vec<ReportDigest> {
+        let mut digests = Vec::new();
+
+        for user in users.get_tenant_users(tenant_id) {
+            let preferences = match users.get_notification_preferences(&user.id) {
+                Some(preferences) => preferences,
+                None => continue,
+            };
+            if !preferences.accepts(EVENT_COMPLIANCE_REPORT, NotificationSeverity::Info) {
+                continue;
+            }
+
+            let last_sent = self.last_sent(&user.id);
+            if !preferences.digest_frequency.is_due(last_sent, now) {
+                continue;
+            }
+
+            let mut reports: Vec<ComplianceReport> = compliance
+                .get_tenant_reports(tenant_id)
+                .into_iter()
+                .filter(|report| report.generated_at <= now)
+                .filter(|report| last_sent.map(|since| report.generated_at > since).unwrap_or(true))
+                .cloned()
+                .collect();
+            if reports.is_empty() {
+                continue;
+            }
+            reports.sort_by_key(|report| report.generated_at);
+
+            self.last_sent.insert(user.id.clone(), now);
+            digests.push(ReportDigest {
+                user_id: user.id.clone(),
+                email: user.email.clone(),
+                channels: preferences.channels,
+                frequency: preferences.digest_frequency,
+                period_start: last_sent,
+                period_end: now,
+                content: digest_content(&reports),
+                reports,
+            });
+        }
+
+        digests
+    }
+}
+
+/// Render a plain-text summary of the reports in a digest
+fn digest_content(reports: &[ComplianceReport]) -> String {
+    let mut content = format!("Compliance Report Digest\n{} new report(s)\n", reports.len());
+    for report in reports {
+        content.push_str(&format!(
+            "\n- {:?} for {} to {} (generated {})",
+            report.report_type, report.period_start, report.period_end, report.generated_at
+        ));
+    }
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ReportType;
+    use chrono::Duration;
+    use sniper_users::{NotificationPreferences, UserRole};
+
+    #[test]
+    fn test_report_digests_follow_preferences() {
+        let mut users = UserManager::new();
+        let daily = users.create_user("alice", "alice@example.com", vec![UserRole::Auditor], "tenant-1").unwrap();
+        let opted_out = users.create_user("bob", "bob@example.com", vec![UserRole::Auditor], "tenant-1").unwrap();
+
+        users
+            .set_notification_preferences(
+                &daily.id,
+                NotificationPreferences {
+                    digest_frequency: DigestFrequency::Daily,
+                    ..NotificationPreferences::default()
+                },
+            )
+            .unwrap();
+        users
+            .set_notification_preferences(
+                &opted_out.id,
+                NotificationPreferences {
+                    event_types: vec!["incident".to_string()],
+                    ..NotificationPreferences::default()
+                },
+            )
+            .unwrap();
+
+        let mut compliance = ComplianceManager::new();
+        let now = Utc::now();
+        compliance
+            .generate_report(ReportType::DailyActivity, now - Duration::days(1), now, "system", "tenant-1")
+            .unwrap();
+
+        let mut scheduler = ReportDigestScheduler::new();
+        let run_at = Utc::now();
+        let digests = scheduler.run(&compliance, &users, "tenant-1", run_at);
+        assert_eq!(digests.len(), 1);
+        assert_eq!(digests[0].user_id, daily.id);
+        assert_eq!(digests[0].reports.len(), 1);
+        assert!(digests[0].content.contains("DailyActivity"));
+
+        // A new report waits for the next daily digest
+        compliance
+            .generate_report(ReportType::TradeAudit, now - Duration::days(1), now, "system", "tenant-1")
+            .unwrap();
+        assert!(scheduler.run(&compliance, &users, "tenant-1", run_at + Duration::hours(2)).is_empty());
+
+        let digests = scheduler.run(&compliance, &users, "tenant-1", run_at + Duration::days(1));
+        assert_eq!(digests.len(), 1);
+        assert_eq!(digests[0].reports.len(), 1);
+        assert_eq!(digests[0].reports[0].report_type, ReportType::TradeAudit);
+    }
+}
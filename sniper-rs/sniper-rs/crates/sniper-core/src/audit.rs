@@ -0,0 +1,128 @@
+//! Structured audit events for trading actions.
+//!
+//! The order and portfolio managers, the executor and the divergence guard
+//! publish an [`AuditEvent`] on the core bus for every action an auditor may
+//! need to reconstruct, and the shared audit log collects them. Events of one
+//! order carry the order ID as their correlation ID, which is copied onto its
+//! trade plans and from there onto the execution receipt's event, so the whole
+//! order → plan → receipt chain can be queried at once.
+
+use serde::{Deserialize, Serialize};
+
+/// Bus subject audit events are published under
+pub const AUDIT_SUBJECT: &str = "audit";
+
+/// An order was accepted
+pub const ORDER_CREATED: &str = "ORDER_CREATED";
+
+/// An order was cancelled
+pub const ORDER_CANCELLED: &str = "ORDER_CANCELLED";
+
+/// An open order's terms were amended
+pub const ORDER_AMENDED: &str = "ORDER_AMENDED";
+
+/// An order armed with a trigger was activated by it
+pub const ORDER_TRIGGERED: &str = "ORDER_TRIGGERED";
+
+/// An order triggered and its trade plan was handed off for execution
+pub const TRADE_PLANNED: &str = "TRADE_PLANNED";
+
+/// A trade plan was executed, successfully or not
+pub const TRADE_EXECUTED: &str = "TRADE_EXECUTED";
+
+/// A position was opened
+pub const POSITION_OPENED: &str = "POSITION_OPENED";
+
+/// A position was closed in full
+pub const POSITION_CLOSED: &str = "POSITION_CLOSED";
+
+/// An operator overrode a risk limit
+pub const RISK_OVERRIDE_GRANTED: &str = "RISK_OVERRIDE_GRANTED";
+
+/// A risk limit override was withdrawn
+pub const RISK_OVERRIDE_REVOKED: &str = "RISK_OVERRIDE_REVOKED";
+
+/// Actor recorded for actions taken by the system rather than a user
+pub const SYSTEM_ACTOR: &str = "system";
+
+/// Audit record of one trading action
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditEvent {
+    pub action: String,
+    /// Affected resource, e.g. `orders/<id>`
+    pub resource: String,
+    /// Links the events of one order, its plans and their receipts
+    pub correlation_id: String,
+    /// User who caused the action, [`SYSTEM_ACTOR`] if none
+    pub actor: String,
+    pub tenant_id: Option<String>,
+    pub details: Option<String>,
+    pub timestamp_ms: i64,
+}
+
+impl AuditEvent {
+    /// Event for an action on a resource, taken by the system until an actor is set
+    pub fn new(action: &str, resource: impl Into<String>, correlation_id: impl Into<String>) -> Self {
+        Self {
+            action: action.to_string(),
+            resource: resource.into(),
+            correlation_id: correlation_id.into(),
+            actor: SYSTEM_ACTOR.to_string(),
+            tenant_id: None,
+            details: None,
+            timestamp_ms: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as i64,
+        }
+    }
+
+    /// Set the acting user, if known
+    pub fn with_actor(mut self, actor: Option<&str>) -> Self {
+        if let Some(actor) = actor {
+            self.actor = actor.to_string();
+        }
+        self
+    }
+
+    /// Set the tenant the resource belongs to
+    pub fn with_tenant(mut self, tenant_id: Option<&str>) -> Self {
+        self.tenant_id = tenant_id.map(str::to_string);
+        self
+    }
+
+    /// Set human-readable details
+    pub fn with_details(mut self, details: impl Into<String>) -> Self {
+        self.details = Some(details.into());
+        self
+    }
+
+    /// Decode a bus message, returning `None` for messages that are not audit events
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        serde_json::from_slice(bytes).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::InMemoryBus;
+    use crate::events::TradingEvent;
+
+    #[tokio::test]
+    async fn test_audit_event_roundtrip() {
+        let bus = InMemoryBus::new(16);
+        let mut rx = bus.subscribe(AUDIT_SUBJECT);
+
+        let event = AuditEvent::new(ORDER_CREATED, "orders/order-1", "order-1")
+            .with_actor(Some("alice"))
+            .with_tenant(Some("tenant-1"))
+            .with_details("buy 1 ETH");
+        bus.publish_now(AUDIT_SUBJECT, &event).unwrap();
+
+        let bytes = rx.recv().await.unwrap();
+        assert_eq!(AuditEvent::decode(&bytes), Some(event));
+        assert_eq!(TradingEvent::decode(&bytes), None);
+        assert_eq!(AuditEvent::new(ORDER_CANCELLED, "orders/order-1", "order-1").with_actor(None).actor, SYSTEM_ACTOR);
+    }
+}
@@ -1,26 +1,110 @@
 use crate::errors::SniperError;
+use crate::telemetry;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::sync::broadcast;
 
+/// Message kept in the bus history for replay
+#[derive(Debug, Clone)]
+pub struct BusMessage {
+    pub subject: String,
+    pub published_at_ms: i64,
+    pub payload: Vec<u8>,
+}
+
 #[derive(Clone)]
 pub struct InMemoryBus {
     tx: broadcast::Sender<Vec<u8>>,
+    history: Option<Arc<Mutex<VecDeque<BusMessage>>>>,
+    history_window_ms: i64,
 }
 
 impl InMemoryBus {
     pub fn new(buffer: usize) -> Self {
         let (tx, _rx) = broadcast::channel(buffer);
-        Self { tx }
+        Self {
+            tx,
+            history: None,
+            history_window_ms: 0,
+        }
+    }
+    /// Bus keeping the messages of the last `window` for replay
+    pub fn with_history(buffer: usize, window: Duration) -> Self {
+        Self {
+            history: Some(Arc::new(Mutex::new(VecDeque::new()))),
+            history_window_ms: window.as_millis() as i64,
+            ..Self::new(buffer)
+        }
     }
     pub async fn publish<T: serde::Serialize>(
         &self,
-        _subject: &str,
+        subject: &str,
+        msg: &T,
+    ) -> Result<(), SniperError> {
+        self.publish_now(subject, msg)
+    }
+    pub fn publish_now<T: serde::Serialize>(
+        &self,
+        subject: &str,
+        msg: &T,
+    ) -> Result<(), SniperError> {
+        let bytes = serde_json::to_vec(msg).map_err(|e| SniperError::Bus(e.to_string()))?;
+        self.publish_bytes(subject, bytes)
+    }
+    /// Publish a message carrying the trace context of `cx`
+    ///
+    /// Subscribers continue the trace with [`telemetry::message_context`].
+    pub fn publish_in<T: serde::Serialize>(
+        &self,
+        subject: &str,
         msg: &T,
+        cx: &telemetry::Context,
     ) -> Result<(), SniperError> {
         let bytes = serde_json::to_vec(msg).map_err(|e| SniperError::Bus(e.to_string()))?;
+        self.publish_bytes(subject, telemetry::inject_message(cx, bytes))
+    }
+    /// Publish an already encoded message, e.g. one received from a bridge
+    pub fn publish_bytes(&self, subject: &str, bytes: Vec<u8>) -> Result<(), SniperError> {
+        if let Some(history) = &self.history {
+            let now = now_ms();
+            let mut history = history.lock().unwrap_or_else(|e| e.into_inner());
+            while history
+                .front()
+                .is_some_and(|oldest| oldest.published_at_ms < now - self.history_window_ms)
+            {
+                history.pop_front();
+            }
+            history.push_back(BusMessage {
+                subject: subject.to_string(),
+                published_at_ms: now,
+                payload: bytes.clone(),
+            });
+        }
         let _ = self.tx.send(bytes);
         Ok(())
     }
     pub fn subscribe(&self, _subject: &str) -> broadcast::Receiver<Vec<u8>> {
         self.tx.subscribe()
     }
+    /// Messages kept in the history published at or after `since_ms`, oldest first
+    pub fn history_since(&self, since_ms: i64) -> Vec<BusMessage> {
+        match &self.history {
+            Some(history) => history
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .iter()
+                .filter(|message| message.published_at_ms >= since_ms)
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
 }
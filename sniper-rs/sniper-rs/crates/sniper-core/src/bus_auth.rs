@@ -0,0 +1,442 @@
+//! Signing and replay protection for bus messages bridged between processes.
+//!
+//! Inside one process the bus is trusted, but once it is bridged across
+//! processes or brokers any component on the broker could publish a forged
+//! plan or receipt. Each service signs what it sends with its own HMAC-SHA256
+//! key, and the receiving side only republishes a message locally if the
+//! signature checks out, the sender may publish on the subject, the message is
+//! within the replay window and its sequence number hasn't been seen from that
+//! sender before. A compromised service can therefore only speak as itself,
+//! on its own subjects.
+
+use crate::bus::InMemoryBus;
+use crate::errors::SniperError;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use thiserror::Error;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Signing settings of a bridged bus
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BusAuthConfig {
+    /// Largest difference between a message's send time and its arrival
+    pub replay_window_ms: i64,
+    /// Senders allowed to publish per subject; a pattern ending in `>` covers
+    /// every subject with that prefix, and unlisted subjects accept any sender
+    pub publishers: HashMap<String, Vec<String>>,
+    /// Environment variable holding each sender's hex key
+    pub key_envs: HashMap<String, String>,
+}
+
+impl Default for BusAuthConfig {
+    fn default() -> Self {
+        Self {
+            replay_window_ms: 30_000,
+            publishers: HashMap::new(),
+            key_envs: HashMap::new(),
+        }
+    }
+}
+
+/// Bus message as sent over a bridge
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedEnvelope {
+    pub subject: String,
+    pub sender: String,
+    /// Per-sender sequence number, never reused within the replay window
+    pub seq: u64,
+    pub sent_at_ms: i64,
+    /// Hex encoded message
+    pub payload: String,
+    /// Hex encoded HMAC-SHA256 over the other fields
+    pub signature: String,
+}
+
+/// Reason a bridged message was rejected
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum BusAuthError {
+    #[error("malformed message: {0}")]
+    Malformed(String),
+    #[error("unknown sender {0}")]
+    UnknownSender(String),
+    #[error("bad signature from {0}")]
+    BadSignature(String),
+    #[error("{sender} may not publish on {subject}")]
+    Unauthorized { sender: String, subject: String },
+    #[error("message from {sender} is {age_ms} ms off the current time")]
+    Stale { sender: String, age_ms: i64 },
+    #[error("message {seq} from {sender} was already received")]
+    Replayed { sender: String, seq: u64 },
+}
+
+/// Signs the messages a service sends over a bridge
+pub struct MessageSigner {
+    sender: String,
+    key: Vec<u8>,
+    seq: AtomicU64,
+}
+
+impl MessageSigner {
+    /// Create a signer for `sender`
+    pub fn new(sender: &str, key: &[u8]) -> Self {
+        Self {
+            sender: sender.to_string(),
+            key: key.to_vec(),
+            // Start from the clock so a restarted service doesn't reuse sequence numbers
+            seq: AtomicU64::new(now_ms() as u64 * 1_000),
+        }
+    }
+
+    /// Create a signer with the hex key held in `key_env`
+    pub fn from_env(sender: &str, key_env: &str) -> Result<Self, SniperError> {
+        Ok(Self::new(sender, &key_from_env(key_env)?))
+    }
+
+    /// Get the sender name
+    pub fn sender(&self) -> &str {
+        &self.sender
+    }
+
+    /// Sign a message for `subject`
+    pub fn seal(&self, subject: &str, payload: &[u8]) -> SignedEnvelope {
+        self.seal_at(subject, payload, now_ms())
+    }
+
+    fn seal_at(&self, subject: &str, payload: &[u8], sent_at_ms: i64) -> SignedEnvelope {
+        let seq = self.seq.fetch_add(1, Ordering::Relaxed);
+        let signature = mac(&self.key, subject, &self.sender, seq, sent_at_ms, payload).finalize();
+        SignedEnvelope {
+            subject: subject.to_string(),
+            sender: self.sender.clone(),
+            seq,
+            sent_at_ms,
+            payload: hex::encode(payload),
+            signature: hex::encode(signature.into_bytes()),
+        }
+    }
+}
+
+/// Checks the messages received over a bridge
+pub struct MessageVerifier {
+    config: BusAuthConfig,
+    keys: HashMap<String, Vec<u8>>,
+    // Sequence numbers seen per sender, with their send time
+    seen: Mutex<HashMap<String, HashMap<u64, i64>>>,
+}
+
+impl MessageVerifier {
+    /// Create a verifier without any sender keys
+    pub fn new(config: BusAuthConfig) -> Self {
+        Self {
+            config,
+            keys: HashMap::new(),
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Create a verifier with the sender keys named in the configuration
+    pub fn from_config(config: BusAuthConfig) -> Result<Self, SniperError> {
+        let mut verifier = Self::new(config.clone());
+        for (sender, key_env) in &config.key_envs {
+            verifier = verifier.with_key(sender, &key_from_env(key_env)?);
+        }
+        Ok(verifier)
+    }
+
+    /// Trust messages from `sender` signed with `key`
+    pub fn with_key(mut self, sender: &str, key: &[u8]) -> Self {
+        self.keys.insert(sender.to_string(), key.to_vec());
+        self
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &BusAuthConfig {
+        &self.config
+    }
+
+    /// Check a message and return its payload
+    pub fn open(&self, envelope: &SignedEnvelope) -> Result<Vec<u8>, BusAuthError> {
+        self.open_at(envelope, now_ms())
+    }
+
+    fn open_at(&self, envelope: &SignedEnvelope, now_ms: i64) -> Result<Vec<u8>, BusAuthError> {
+        let key = self
+            .keys
+            .get(&envelope.sender)
+            .ok_or_else(|| BusAuthError::UnknownSender(envelope.sender.clone()))?;
+        let payload = hex::decode(&envelope.payload).map_err(|e| BusAuthError::Malformed(e.to_string()))?;
+        let signature = hex::decode(&envelope.signature).map_err(|e| BusAuthError::Malformed(e.to_string()))?;
+        mac(key, &envelope.subject, &envelope.sender, envelope.seq, envelope.sent_at_ms, &payload)
+            .verify_slice(&signature)
+            .map_err(|_| BusAuthError::BadSignature(envelope.sender.clone()))?;
+
+        if !self.may_publish(&envelope.sender, &envelope.subject) {
+            return Err(BusAuthError::Unauthorized {
+                sender: envelope.sender.clone(),
+                subject: envelope.subject.clone(),
+            });
+        }
+
+        let age_ms = now_ms - envelope.sent_at_ms;
+        if age_ms.abs() > self.config.replay_window_ms {
+            return Err(BusAuthError::Stale {
+                sender: envelope.sender.clone(),
+                age_ms,
+            });
+        }
+
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        let seen = seen.entry(envelope.sender.clone()).or_default();
+        // Older messages are rejected as stale, so their sequence numbers can be forgotten
+        seen.retain(|_, sent_at_ms| *sent_at_ms >= now_ms - self.config.replay_window_ms);
+        if seen.insert(envelope.seq, envelope.sent_at_ms).is_some() {
+            return Err(BusAuthError::Replayed {
+                sender: envelope.sender.clone(),
+                seq: envelope.seq,
+            });
+        }
+        Ok(payload)
+    }
+
+    fn may_publish(&self, sender: &str, subject: &str) -> bool {
+        let mut listed = false;
+        for (pattern, senders) in &self.config.publishers {
+            if subject_matches(pattern, subject) {
+                if senders.iter().any(|allowed| allowed == sender) {
+                    return true;
+                }
+                listed = true;
+            }
+        }
+        !listed
+    }
+}
+
+/// Local bus bridged to other processes, signing what leaves and checking what arrives
+pub struct SignedBridge {
+    bus: InMemoryBus,
+    signer: MessageSigner,
+    verifier: MessageVerifier,
+}
+
+impl SignedBridge {
+    /// Create a bridge for the local `bus`
+    pub fn new(bus: InMemoryBus, signer: MessageSigner, verifier: MessageVerifier) -> Self {
+        Self { bus, signer, verifier }
+    }
+
+    /// Encode a message for the bridge transport
+    pub fn outbound<T: Serialize>(&self, subject: &str, msg: &T) -> Result<Vec<u8>, SniperError> {
+        let payload = serde_json::to_vec(msg).map_err(|e| SniperError::Bus(e.to_string()))?;
+        serde_json::to_vec(&self.signer.seal(subject, &payload)).map_err(|e| SniperError::Bus(e.to_string()))
+    }
+
+    /// Check a message from the bridge transport and publish it on the local bus
+    pub fn inbound(&self, bytes: &[u8]) -> Result<(), SniperError> {
+        let envelope: SignedEnvelope = serde_json::from_slice(bytes).map_err(|e| SniperError::Bus(e.to_string()))?;
+        match self.verifier.open(&envelope) {
+            Ok(payload) => self.bus.publish_bytes(&envelope.subject, payload),
+            Err(e) => {
+                tracing::warn!("rejected bridged message on {}: {}", envelope.subject, e);
+                Err(SniperError::Bus(e.to_string()))
+            }
+        }
+    }
+}
+
+fn mac(key: &[u8], subject: &str, sender: &str, seq: u64, sent_at_ms: i64, payload: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    // Length prefixes keep field boundaries unambiguous
+    for field in [subject.as_bytes(), sender.as_bytes(), payload] {
+        mac.update(&(field.len() as u64).to_be_bytes());
+        mac.update(field);
+    }
+    mac.update(&seq.to_be_bytes());
+    mac.update(&sent_at_ms.to_be_bytes());
+    mac
+}
+
+fn subject_matches(pattern: &str, subject: &str) -> bool {
+    match pattern.strip_suffix('>') {
+        Some(prefix) => subject.starts_with(prefix),
+        None => pattern == subject,
+    }
+}
+
+fn key_from_env(key_env: &str) -> Result<Vec<u8>, SniperError> {
+    let hex_key = std::env::var(key_env).map_err(|_| SniperError::Config(format!("{} is not set", key_env)))?;
+    let key = hex::decode(hex_key.trim().trim_start_matches("0x"))
+        .map_err(|e| SniperError::Config(format!("{} is not hex: {}", key_env, e)))?;
+    if key.len() < 32 {
+        return Err(SniperError::Config(format!("{} must hold at least 32 bytes", key_env)));
+    }
+    Ok(key)
+}
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ChainRef, ExecMode, ExitRules, GasPolicy, TradePlan};
+
+    const STRATEGY_KEY: &[u8] = &[7; 32];
+    const EXECUTOR_KEY: &[u8] = &[9; 32];
+
+    fn verifier() -> MessageVerifier {
+        let config = BusAuthConfig {
+            publishers: HashMap::from([
+                ("plan.created".to_string(), vec!["svc-strategy".to_string()]),
+                ("exec.>".to_string(), vec!["svc-executor".to_string()]),
+            ]),
+            ..BusAuthConfig::default()
+        };
+        MessageVerifier::new(config)
+            .with_key("svc-strategy", STRATEGY_KEY)
+            .with_key("svc-executor", EXECUTOR_KEY)
+    }
+
+    fn plan() -> TradePlan {
+        TradePlan {
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            router: "0xRouter".to_string(),
+            token_in: "ETH".to_string(),
+            token_out: "0xToken".to_string(),
+            amount_in: 1_000_000_000_000_000_000,
+            min_out: 1,
+            mode: ExecMode::Mempool,
+            gas: GasPolicy {
+                max_fee_gwei: 50,
+                max_priority_gwei: 2,
+            },
+            exits: ExitRules::default(),
+            idem_key: "plan-1".to_string(),
+            quote: None,
+            correlation_id: None,
+        }
+    }
+
+    #[test]
+    fn test_forged_and_tampered_messages_rejected() {
+        let verifier = verifier();
+        let strategy = MessageSigner::new("svc-strategy", STRATEGY_KEY);
+
+        let envelope = strategy.seal("plan.created", b"{\"amount_in\":1}");
+        assert_eq!(verifier.open(&envelope).unwrap(), b"{\"amount_in\":1}");
+
+        let tampered = SignedEnvelope {
+            payload: hex::encode(b"{\"amount_in\":9}"),
+            seq: envelope.seq + 1,
+            ..envelope.clone()
+        };
+        assert_eq!(verifier.open(&tampered), Err(BusAuthError::BadSignature("svc-strategy".to_string())));
+
+        // Claiming to be the strategy service without its key
+        let forger = MessageSigner::new("svc-strategy", &[1; 32]);
+        let forged = forger.seal("plan.created", b"{}");
+        assert!(matches!(verifier.open(&forged), Err(BusAuthError::BadSignature(_))));
+
+        let stranger = MessageSigner::new("svc-stranger", &[1; 32]);
+        let unknown = stranger.seal("plan.created", b"{}");
+        assert_eq!(verifier.open(&unknown), Err(BusAuthError::UnknownSender("svc-stranger".to_string())));
+
+        let malformed = SignedEnvelope {
+            payload: "zz".to_string(),
+            ..envelope
+        };
+        assert!(matches!(verifier.open(&malformed), Err(BusAuthError::Malformed(_))));
+    }
+
+    #[test]
+    fn test_publishers_limited_per_subject() {
+        let verifier = verifier();
+        let strategy = MessageSigner::new("svc-strategy", STRATEGY_KEY);
+        let executor = MessageSigner::new("svc-executor", EXECUTOR_KEY);
+
+        assert!(verifier.open(&executor.seal("exec.result", b"{}")).is_ok());
+        assert_eq!(
+            verifier.open(&strategy.seal("exec.result", b"{}")),
+            Err(BusAuthError::Unauthorized {
+                sender: "svc-strategy".to_string(),
+                subject: "exec.result".to_string(),
+            })
+        );
+        assert!(verifier.open(&executor.seal("plan.created", b"{}")).is_err());
+        // Subjects nobody is listed for accept any known sender
+        assert!(verifier.open(&executor.seal("signals.dex.pair_created", b"{}")).is_ok());
+    }
+
+    #[test]
+    fn test_replay_window() {
+        let verifier = verifier();
+        let strategy = MessageSigner::new("svc-strategy", STRATEGY_KEY);
+        let now = now_ms();
+
+        let envelope = strategy.seal_at("plan.created", b"{}", now);
+        assert!(verifier.open_at(&envelope, now).is_ok());
+        assert_eq!(
+            verifier.open_at(&envelope, now + 10),
+            Err(BusAuthError::Replayed {
+                sender: "svc-strategy".to_string(),
+                seq: envelope.seq,
+            })
+        );
+
+        let old = strategy.seal_at("plan.created", b"{}", now - 31_000);
+        assert!(matches!(verifier.open_at(&old, now), Err(BusAuthError::Stale { age_ms: 31_000, .. })));
+        let future = strategy.seal_at("plan.created", b"{}", now + 31_000);
+        assert!(matches!(verifier.open_at(&future, now), Err(BusAuthError::Stale { .. })));
+
+        // Once the first message ages out it is rejected as stale and its sequence number forgotten
+        assert!(matches!(
+            verifier.open_at(&envelope, now + 31_000),
+            Err(BusAuthError::Stale { .. })
+        ));
+        let later = strategy.seal_at("plan.created", b"{}", now + 31_000);
+        assert!(verifier.open_at(&later, now + 31_000).is_ok());
+        assert_eq!(verifier.seen.lock().unwrap()["svc-strategy"].len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_bridge_republishes_verified_messages() {
+        let strategy_bus = InMemoryBus::new(16);
+        let strategy = SignedBridge::new(
+            strategy_bus,
+            MessageSigner::new("svc-strategy", STRATEGY_KEY),
+            verifier(),
+        );
+        let executor_bus = InMemoryBus::new(16);
+        let executor = SignedBridge::new(
+            executor_bus.clone(),
+            MessageSigner::new("svc-executor", EXECUTOR_KEY),
+            verifier(),
+        );
+        let mut rx = executor_bus.subscribe("plan.created");
+
+        let wire = strategy.outbound("plan.created", &plan()).unwrap();
+        executor.inbound(&wire).unwrap();
+        let received: TradePlan = serde_json::from_slice(&rx.recv().await.unwrap()).unwrap();
+        assert_eq!(received.idem_key, "plan-1");
+
+        // Redelivered by the broker, or replayed by an attacker
+        assert!(executor.inbound(&wire).is_err());
+        assert!(executor.inbound(b"not an envelope").is_err());
+        let forged = executor.outbound("plan.created", &plan()).unwrap();
+        assert!(executor.inbound(&forged).is_err());
+        assert!(rx.try_recv().is_err());
+    }
+}
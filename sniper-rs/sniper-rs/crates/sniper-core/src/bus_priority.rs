@@ -0,0 +1,299 @@
+//! Prioritized draining of a backed-up bus.
+//!
+//! When messages arrive faster than they are handled, e.g. during a gas war,
+//! handling them in arrival order delays launch and trigger signals behind
+//! bulk data such as telemetry ticks. `PriorityQueue` sits between a bus
+//! subscriber and its handler: messages are sorted into classes by topic, each
+//! class holds a bounded backlog with its own drop policy, and the handler
+//! always takes from the highest-priority class with a backlog. Every class
+//! counts what it queued, handled and dropped.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tokio::sync::Notify;
+
+/// What a full class does with a new message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DropPolicy {
+    /// Drop the oldest queued message to make room, for data where only the latest matters
+    DropOldest,
+    /// Reject the new message, keeping what was queued first
+    DropNewest,
+}
+
+/// Class of topics sharing a priority and backlog
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicClass {
+    pub name: String,
+    /// Classes with a higher priority are drained first
+    pub priority: u8,
+    /// Topic patterns; `*` matches one token and a trailing `>` the rest
+    pub topics: Vec<String>,
+    /// Messages queued before the drop policy applies
+    pub capacity: usize,
+    pub drop_policy: DropPolicy,
+}
+
+/// Topic classes of a priority queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PriorityConfig {
+    /// A topic goes to the first class with a matching pattern, or to the
+    /// lowest-priority class if none matches
+    pub classes: Vec<TopicClass>,
+}
+
+impl Default for PriorityConfig {
+    fn default() -> Self {
+        Self {
+            classes: vec![
+                TopicClass {
+                    name: "launch".to_string(),
+                    priority: 10,
+                    topics: vec![
+                        "signals.*.pair_created".to_string(),
+                        "signals.*.trading_enabled".to_string(),
+                        "signals.*.liquidity_added".to_string(),
+                    ],
+                    capacity: 1024,
+                    drop_policy: DropPolicy::DropNewest,
+                },
+                TopicClass {
+                    name: "bulk".to_string(),
+                    priority: 0,
+                    topics: vec![">".to_string()],
+                    capacity: 256,
+                    drop_policy: DropPolicy::DropOldest,
+                },
+            ],
+        }
+    }
+}
+
+/// Counters of one topic class
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClassStats {
+    pub name: String,
+    pub priority: u8,
+    /// Messages currently waiting
+    pub queued: usize,
+    pub enqueued: u64,
+    pub processed: u64,
+    pub dropped: u64,
+}
+
+struct ClassQueue<T> {
+    class: TopicClass,
+    items: VecDeque<T>,
+    stats: ClassStats,
+}
+
+struct Inner<T> {
+    // Highest priority first
+    classes: Vec<ClassQueue<T>>,
+    closed: bool,
+}
+
+/// Bounded multi-class queue drained in priority order
+pub struct PriorityQueue<T> {
+    inner: Mutex<Inner<T>>,
+    notify: Notify,
+}
+
+impl<T> PriorityQueue<T> {
+    /// Create a queue with the configured classes
+    pub fn new(config: PriorityConfig) -> Self {
+        let mut classes: Vec<ClassQueue<T>> = config
+            .classes
+            .into_iter()
+            .map(|class| ClassQueue {
+                stats: ClassStats {
+                    name: class.name.clone(),
+                    priority: class.priority,
+                    ..ClassStats::default()
+                },
+                items: VecDeque::new(),
+                class,
+            })
+            .collect();
+        // Stable, so classes of equal priority keep their configured order
+        classes.sort_by_key(|c| std::cmp::Reverse(c.class.priority));
+        Self {
+            inner: Mutex::new(Inner { classes, closed: false }),
+            notify: Notify::new(),
+        }
+    }
+
+    /// Queue a message published under `topic`
+    ///
+    /// Returns false if the message was dropped, either because its class is
+    /// full and keeps what it has or because the queue has no classes or is closed.
+    pub fn push(&self, topic: &str, item: T) -> bool {
+        let mut inner = self.lock();
+        if inner.closed {
+            return false;
+        }
+        let index = inner
+            .classes
+            .iter()
+            .position(|queue| queue.class.topics.iter().any(|pattern| topic_matches(pattern, topic)))
+            .or_else(|| inner.classes.len().checked_sub(1));
+        let Some(queue) = index.map(|index| &mut inner.classes[index]) else {
+            return false;
+        };
+
+        if queue.items.len() >= queue.class.capacity {
+            queue.stats.dropped += 1;
+            tracing::debug!(class = %queue.class.name, "queue full, dropping message on {}", topic);
+            match queue.class.drop_policy {
+                DropPolicy::DropOldest if queue.class.capacity > 0 => {
+                    queue.items.pop_front();
+                }
+                _ => return false,
+            }
+        }
+        queue.items.push_back(item);
+        queue.stats.enqueued += 1;
+        drop(inner);
+        self.notify.notify_one();
+        true
+    }
+
+    /// Take the next message from the highest-priority class with a backlog
+    pub fn try_pop(&self) -> Option<T> {
+        let mut inner = self.lock();
+        let queue = inner.classes.iter_mut().find(|queue| !queue.items.is_empty())?;
+        queue.stats.processed += 1;
+        queue.items.pop_front()
+    }
+
+    /// Wait for the next message; `None` once the queue is closed and drained
+    pub async fn pop(&self) -> Option<T> {
+        loop {
+            // Register for a wake-up before checking, so a push in between isn't missed
+            let notified = self.notify.notified();
+            if let Some(item) = self.try_pop() {
+                return Some(item);
+            }
+            if self.lock().closed {
+                return None;
+            }
+            notified.await;
+        }
+    }
+
+    /// Stop accepting messages; waiting consumers return once the backlog is drained
+    pub fn close(&self) {
+        self.lock().closed = true;
+        self.notify.notify_waiters();
+    }
+
+    /// Get the counters of every class, highest priority first
+    pub fn stats(&self) -> Vec<ClassStats> {
+        self.lock()
+            .classes
+            .iter()
+            .map(|queue| ClassStats {
+                queued: queue.items.len(),
+                ..queue.stats.clone()
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner<T>> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Whether a topic matches a pattern, token by token
+pub fn topic_matches(pattern: &str, topic: &str) -> bool {
+    let mut topic_tokens = topic.split('.');
+    for token in pattern.split('.') {
+        if token == ">" {
+            return topic_tokens.next().is_some();
+        }
+        match topic_tokens.next() {
+            Some(topic_token) if token == "*" || token == topic_token => {}
+            _ => return false,
+        }
+    }
+    topic_tokens.next().is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn class(name: &str, priority: u8, topics: &[&str], capacity: usize, drop_policy: DropPolicy) -> TopicClass {
+        TopicClass {
+            name: name.to_string(),
+            priority,
+            topics: topics.iter().map(|topic| topic.to_string()).collect(),
+            capacity,
+            drop_policy,
+        }
+    }
+
+    #[test]
+    fn test_topic_matches() {
+        assert!(topic_matches("signals.*.pair_created", "signals.dex.pair_created"));
+        assert!(!topic_matches("signals.*.pair_created", "signals.dex.pair_created.v2"));
+        assert!(!topic_matches("signals.*.pair_created", "signals.pair_created"));
+        assert!(topic_matches("signals.>", "signals.cex.tick"));
+        assert!(!topic_matches("signals.>", "signals"));
+        assert!(topic_matches(">", "telemetry"));
+        assert!(topic_matches("plan.created", "plan.created"));
+    }
+
+    #[test]
+    fn test_drains_by_priority_and_drops_per_policy() {
+        let queue = PriorityQueue::new(PriorityConfig {
+            classes: vec![
+                class("bulk", 0, &[">"], 2, DropPolicy::DropOldest),
+                class("launch", 10, &["signals.*.pair_created"], 2, DropPolicy::DropNewest),
+            ],
+        });
+
+        for tick in 1..=3 {
+            assert!(queue.push("signals.cex.tick", format!("tick{}", tick)));
+        }
+        assert!(queue.push("signals.dex.pair_created", "launch1".to_string()));
+        assert!(queue.push("signals.dex.pair_created", "launch2".to_string()));
+        assert!(!queue.push("signals.dex.pair_created", "launch3".to_string()));
+
+        let drained: Vec<String> = std::iter::from_fn(|| queue.try_pop()).collect();
+        assert_eq!(drained, vec!["launch1", "launch2", "tick2", "tick3"]);
+
+        let stats = queue.stats();
+        assert_eq!(stats[0].name, "launch");
+        assert_eq!((stats[0].enqueued, stats[0].processed, stats[0].dropped), (2, 2, 1));
+        assert_eq!((stats[1].enqueued, stats[1].processed, stats[1].dropped), (3, 2, 1));
+        assert!(stats.iter().all(|class| class.queued == 0));
+    }
+
+    #[tokio::test]
+    async fn test_pop_waits_until_closed() {
+        let queue = Arc::new(PriorityQueue::new(PriorityConfig::default()));
+        let consumer = {
+            let queue = queue.clone();
+            tokio::spawn(async move {
+                let mut received = Vec::new();
+                while let Some(item) = queue.pop().await {
+                    received.push(item);
+                }
+                received
+            })
+        };
+
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        assert!(queue.push("signals.dex.pair_created", 1));
+        assert!(queue.push("telemetry.gas", 2));
+        queue.close();
+        assert!(!queue.push("signals.dex.pair_created", 3));
+        assert_eq!(consumer.await.unwrap(), vec![1, 2]);
+        assert_eq!(queue.stats()[1].name, "bulk");
+    }
+}
@@ -1,5 +1,21 @@
+//! Application and service configuration.
+//!
+//! Services resolve a [`ServiceConfig`] through a [`ConfigLoader`], which
+//! layers, from lowest to highest precedence, the service's defaults, a
+//! TOML, YAML or JSON file, `SNIPER__`-prefixed environment variables and
+//! command-line values. String values in the file may reference environment
+//! variables as `${NAME}`, which keeps secrets out of the file. Every service
+//! accepts the same [`ConfigArgs`], including `--print-config` to show the
+//! resolved configuration with secrets redacted.
+
+use crate::bus_auth::BusAuthConfig;
 use crate::errors::SniperError;
+use crate::types::GasPolicy;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
@@ -10,6 +26,9 @@ pub struct AppConfig {
 pub struct Bus {
     pub kind: String,
     pub nats_url: Option<String>,
+    /// Message signing when the bus is bridged across processes
+    #[serde(default)]
+    pub auth: Option<BusAuthConfig>,
 }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Execution {
@@ -26,3 +45,502 @@ impl AppConfig {
         toml::from_str(&txt).map_err(|e| SniperError::Config(e.to_string()))
     }
 }
+
+/// Directory holding each service's default configuration file
+pub const CONFIG_DIR: &str = "configs";
+
+/// Environment variable naming the configuration file
+pub const CONFIG_FILE_ENV: &str = "SNIPER_CONFIG";
+
+/// Prefix of environment variables overriding configuration values
+///
+/// Nested keys are separated by a double underscore, so
+/// `SNIPER__CHAINS__ETHEREUM__CHAIN_ID=1` sets `chains.ethereum.chain_id`.
+pub const ENV_PREFIX: &str = "SNIPER__";
+
+/// Shown instead of secrets when a configuration is printed
+pub const REDACTED: &str = "<redacted>";
+
+/// Configuration flags shared by every service
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct ConfigArgs {
+    /// Configuration file (TOML, YAML or JSON), defaulting to $SNIPER_CONFIG or configs/<service>.toml
+    #[clap(long = "config")]
+    pub config: Option<PathBuf>,
+
+    /// Override a configuration value, e.g. --set chains.ethereum.chain_id=1
+    #[clap(long = "set", value_name = "KEY=VALUE")]
+    pub overrides: Vec<String>,
+
+    /// Print the resolved configuration with secrets redacted and exit
+    #[clap(long)]
+    pub print_config: bool,
+}
+
+impl ConfigArgs {
+    /// Loader for a service's configuration from these flags
+    ///
+    /// Without `--config`, the file named by `SNIPER_CONFIG` is used, then
+    /// `configs/<service>.toml` if it exists.
+    pub fn loader(&self, service: &str) -> ConfigLoader {
+        let file = self
+            .config
+            .clone()
+            .or_else(|| std::env::var_os(CONFIG_FILE_ENV).map(PathBuf::from))
+            .or_else(|| {
+                let path = Path::new(CONFIG_DIR).join(format!("{}.toml", service));
+                path.exists().then_some(path)
+            });
+        let mut loader = ConfigLoader::new(service);
+        if let Some(file) = file {
+            loader = loader.with_file(file);
+        }
+        self.overrides.iter().fold(loader, |loader, assignment| loader.with_set(assignment))
+    }
+}
+
+/// Settings every service resolves at startup
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServiceConfig {
+    /// Port the service listens on, if it serves HTTP
+    pub port: Option<u16>,
+    /// Port the service serves gRPC on, if it has gRPC services
+    pub grpc_port: Option<u16>,
+    /// Chains by name
+    pub chains: BTreeMap<String, ChainConfig>,
+    /// Gas policy per chain name
+    pub gas: BTreeMap<String, GasPolicy>,
+    /// Access token settings
+    pub auth: AuthConfig,
+    /// Service-specific sections, such as the portfolio's `allocation`
+    #[serde(flatten)]
+    pub sections: BTreeMap<String, Value>,
+}
+
+/// RPC endpoints of a chain
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChainConfig {
+    pub chain_id: u64,
+    /// HTTP endpoints in order of preference
+    pub rpc_http: Vec<String>,
+    pub rpc_ws: Option<String>,
+    /// Blocks after which a transaction counts as final
+    pub confirmations: u64,
+}
+
+/// Access token settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    /// Token signing secret, usually `${JWT_SECRET}`; services fall back to the environment
+    pub jwt_secret: Option<String>,
+    pub jwt_ttl_secs: Option<u64>,
+}
+
+impl ServiceConfig {
+    /// Check ports, chain IDs, endpoint schemes and gas policies
+    pub fn validate(&self) -> Result<(), SniperError> {
+        if self.port == Some(0) {
+            return Err(SniperError::Config("port must be positive".to_string()));
+        }
+        if self.grpc_port == Some(0) {
+            return Err(SniperError::Config("grpc_port must be positive".to_string()));
+        }
+        let mut chain_ids = HashSet::new();
+        for (name, chain) in &self.chains {
+            if chain.chain_id == 0 {
+                return Err(SniperError::Config(format!("chain {} needs a chain_id", name)));
+            }
+            if !chain_ids.insert(chain.chain_id) {
+                return Err(SniperError::Config(format!("chain {} reuses chain_id {}", name, chain.chain_id)));
+            }
+            if chain.rpc_http.is_empty() {
+                return Err(SniperError::Config(format!("chain {} needs at least one rpc_http endpoint", name)));
+            }
+            for url in &chain.rpc_http {
+                check_scheme(name, url, &["http://", "https://"])?;
+            }
+            if let Some(url) = &chain.rpc_ws {
+                check_scheme(name, url, &["ws://", "wss://"])?;
+            }
+        }
+        for (name, gas) in &self.gas {
+            if gas.max_fee_gwei == 0 {
+                return Err(SniperError::Config(format!("gas policy of {} needs a positive max_fee_gwei", name)));
+            }
+            if gas.max_priority_gwei > gas.max_fee_gwei {
+                return Err(SniperError::Config(format!(
+                    "gas policy of {} has a priority fee above its max fee",
+                    name
+                )));
+            }
+        }
+        if self.auth.jwt_secret.as_deref() == Some("") {
+            return Err(SniperError::Config("auth.jwt_secret is empty".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Deserialize a service-specific section, if present
+    pub fn section<T: DeserializeOwned>(&self, name: &str) -> Result<Option<T>, SniperError> {
+        self.sections
+            .get(name)
+            .map(|value| {
+                serde_json::from_value(value.clone())
+                    .map_err(|e| SniperError::Config(format!("invalid {} section: {}", name, e)))
+            })
+            .transpose()
+    }
+
+    /// The configuration with secrets replaced by [`REDACTED`]
+    ///
+    /// Besides the signing secret, this covers any `password` or `secret`
+    /// key in a service-specific section.
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        if config.auth.jwt_secret.is_some() {
+            config.auth.jwt_secret = Some(REDACTED.to_string());
+        }
+        config.sections.values_mut().for_each(redact_secrets);
+        config
+    }
+
+    /// Render the configuration with secrets redacted, as TOML
+    pub fn render(&self) -> Result<String, SniperError> {
+        toml::to_string_pretty(&self.redacted()).map_err(|e| SniperError::Config(e.to_string()))
+    }
+}
+
+/// Keys of service-specific sections whose values are secrets
+const SECRET_KEYS: [&str; 2] = ["password", "secret"];
+
+fn redact_secrets(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if SECRET_KEYS.contains(&key.as_str()) && value.is_string() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_secrets(value);
+                }
+            }
+        },
+        Value::Array(items) => items.iter_mut().for_each(redact_secrets),
+        _ => {},
+    }
+}
+
+fn check_scheme(chain: &str, url: &str, schemes: &[&str]) -> Result<(), SniperError> {
+    if schemes.iter().any(|scheme| url.starts_with(scheme)) {
+        Ok(())
+    } else {
+        Err(SniperError::Config(format!(
+            "endpoint {} of chain {} must start with {}",
+            url,
+            chain,
+            schemes.join(" or ")
+        )))
+    }
+}
+
+/// Layered configuration source
+///
+/// Later layers override earlier ones key by key: defaults, the file,
+/// environment variables, then `--set` and command-line values in the order
+/// they were added.
+#[derive(Debug, Clone)]
+pub struct ConfigLoader {
+    service: String,
+    file: Option<PathBuf>,
+    defaults: Vec<(String, Value)>,
+    overrides: Vec<Override>,
+    env: Option<Vec<(String, String)>>,
+}
+
+#[derive(Debug, Clone)]
+enum Override {
+    Value(String, Value),
+    Assignment(String),
+}
+
+impl ConfigLoader {
+    /// Loader for a service without a file
+    pub fn new(service: &str) -> Self {
+        Self {
+            service: service.to_string(),
+            file: None,
+            defaults: Vec::new(),
+            overrides: Vec::new(),
+            env: None,
+        }
+    }
+
+    /// Read a file, picking the format from its extension
+    pub fn with_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.file = Some(path.into());
+        self
+    }
+
+    /// Value used when no layer sets the dotted `key`
+    pub fn with_default(mut self, key: &str, value: impl Serialize) -> Self {
+        if let Ok(value) = serde_json::to_value(value) {
+            self.defaults.push((key.to_string(), value));
+        }
+        self
+    }
+
+    /// Value overriding every other layer, typically a command-line flag;
+    /// `None` leaves the key alone
+    pub fn with_override(mut self, key: &str, value: impl Serialize) -> Self {
+        match serde_json::to_value(value) {
+            Ok(Value::Null) | Err(_) => {},
+            Ok(value) => self.overrides.push(Override::Value(key.to_string(), value)),
+        }
+        self
+    }
+
+    /// Override given as `key=value`, the value parsed as JSON if possible
+    pub fn with_set(mut self, assignment: &str) -> Self {
+        self.overrides.push(Override::Assignment(assignment.to_string()));
+        self
+    }
+
+    /// Read overrides and `${NAME}` references from these variables instead of the process environment
+    pub fn with_env(mut self, vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.env = Some(vars.into_iter().collect());
+        self
+    }
+
+    /// Resolve the layers into `T`
+    pub fn load<T: DeserializeOwned>(&self) -> Result<T, SniperError> {
+        let env = self.env.clone().unwrap_or_else(|| std::env::vars().collect());
+        let mut root = Value::Object(Map::new());
+        for (key, value) in &self.defaults {
+            set_path(&mut root, key, value.clone())?;
+        }
+        if let Some(path) = &self.file {
+            let mut file = read_file(path)?;
+            interpolate(&mut file, &env)?;
+            merge(&mut root, file);
+        }
+        let mut vars: Vec<_> = env
+            .iter()
+            .filter_map(|(name, value)| {
+                let key = name.strip_prefix(ENV_PREFIX)?;
+                Some((key.to_lowercase().replace("__", "."), value))
+            })
+            .collect();
+        vars.sort();
+        for (key, value) in vars {
+            set_path(&mut root, &key, parse_value(value))?;
+        }
+        for item in &self.overrides {
+            match item {
+                Override::Value(key, value) => set_path(&mut root, key, value.clone())?,
+                Override::Assignment(assignment) => {
+                    let (key, value) = assignment.split_once('=').ok_or_else(|| {
+                        SniperError::Config(format!("override {} is not of the form key=value", assignment))
+                    })?;
+                    set_path(&mut root, key.trim(), parse_value(value))?;
+                },
+            }
+        }
+        serde_json::from_value(root)
+            .map_err(|e| SniperError::Config(format!("invalid {} configuration: {}", self.service, e)))
+    }
+
+    /// Resolve and validate a [`ServiceConfig`]
+    pub fn load_service(&self) -> Result<ServiceConfig, SniperError> {
+        let config: ServiceConfig = self.load()?;
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+fn read_file(path: &Path) -> Result<Value, SniperError> {
+    let txt = std::fs::read_to_string(path)
+        .map_err(|e| SniperError::Config(format!("cannot read {}: {}", path.display(), e)))?;
+    let parsed = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("yaml" | "yml") => serde_yaml::from_str(&txt).map_err(|e| e.to_string()),
+        Some("json") => serde_json::from_str(&txt).map_err(|e| e.to_string()),
+        _ => toml::from_str(&txt).map_err(|e| e.to_string()),
+    };
+    parsed.map_err(|e| SniperError::Config(format!("cannot parse {}: {}", path.display(), e)))
+}
+
+/// Replace `${NAME}` in string values with the variable's value
+fn interpolate(value: &mut Value, env: &[(String, String)]) -> Result<(), SniperError> {
+    match value {
+        Value::String(text) => {
+            while let Some(start) = text.find("${") {
+                let end = text[start..]
+                    .find('}')
+                    .map(|end| start + end)
+                    .ok_or_else(|| SniperError::Config(format!("unterminated variable in {}", text)))?;
+                let name = &text[start + 2..end];
+                let replacement = env
+                    .iter()
+                    .find(|(var, _)| var == name)
+                    .map(|(_, value)| value.clone())
+                    .ok_or_else(|| SniperError::Config(format!("{} is not set", name)))?;
+                text.replace_range(start..=end, &replacement);
+            }
+        },
+        Value::Array(items) => {
+            for item in items {
+                interpolate(item, env)?;
+            }
+        },
+        Value::Object(map) => {
+            for item in map.values_mut() {
+                interpolate(item, env)?;
+            }
+        },
+        _ => {},
+    }
+    Ok(())
+}
+
+fn merge(base: &mut Value, layer: Value) {
+    match (base, layer) {
+        (Value::Object(base), Value::Object(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    },
+                }
+            }
+        },
+        (base, layer) => *base = layer,
+    }
+}
+
+fn set_path(root: &mut Value, key: &str, value: Value) -> Result<(), SniperError> {
+    let mut node = root;
+    let mut parts = key.split('.').peekable();
+    while let Some(part) = parts.next() {
+        if part.is_empty() {
+            return Err(SniperError::Config(format!("invalid configuration key {}", key)));
+        }
+        if !node.is_object() {
+            *node = Value::Object(Map::new());
+        }
+        let Value::Object(map) = node else {
+            unreachable!("node was just made an object");
+        };
+        if parts.peek().is_none() {
+            map.insert(part.to_string(), value);
+            return Ok(());
+        }
+        node = map.entry(part).or_insert_with(|| Value::Object(Map::new()));
+    }
+    Ok(())
+}
+
+/// Parse an override as JSON, falling back to the raw string
+fn parse_value(raw: &str) -> Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    fn write(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("sniper-config-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_layers_override_in_order() {
+        let file = write(
+            "svc.toml",
+            r#"
+port = 9000
+
+[chains.ethereum]
+chain_id = 1
+rpc_http = ["${RPC_HTTP}"]
+
+[gas.ethereum]
+max_fee_gwei = 50
+max_priority_gwei = 2
+
+[auth]
+jwt_secret = "${JWT_SECRET}"
+
+[allocation]
+max_position_size_pct = 20.0
+
+[bootstrap_admin]
+username = "admin"
+password = "${ADMIN_PASSWORD}"
+"#,
+        );
+        let loader = ConfigLoader::new("svc-test")
+            .with_default("port", 8080)
+            .with_file(&file)
+            .with_env(env(&[
+                ("RPC_HTTP", "https://eth.example"),
+                ("JWT_SECRET", "secret"),
+                ("ADMIN_PASSWORD", "admin password"),
+                ("SNIPER__GAS__ETHEREUM__MAX_FEE_GWEI", "80"),
+                ("SNIPER__PORT", "9100"),
+            ]))
+            .with_set("chains.ethereum.rpc_ws=wss://eth.example")
+            .with_override("port", Some(9200))
+            .with_override("auth.jwt_ttl_secs", None::<u64>);
+        let config = loader.load_service().unwrap();
+        std::fs::remove_file(file).unwrap();
+
+        assert_eq!(config.port, Some(9200));
+        let chain = &config.chains["ethereum"];
+        assert_eq!(chain.rpc_http, vec!["https://eth.example".to_string()]);
+        assert_eq!(chain.rpc_ws.as_deref(), Some("wss://eth.example"));
+        assert_eq!(config.gas["ethereum"].max_fee_gwei, 80);
+        assert_eq!(config.gas["ethereum"].max_priority_gwei, 2);
+        assert_eq!(config.auth.jwt_ttl_secs, None);
+
+        #[derive(Deserialize)]
+        struct Allocation {
+            max_position_size_pct: f64,
+        }
+        let allocation: Allocation = config.section("allocation").unwrap().unwrap();
+        assert_eq!(allocation.max_position_size_pct, 20.0);
+        assert!(config.section::<Allocation>("missing").unwrap().is_none());
+
+        let rendered = config.render().unwrap();
+        assert!(rendered.contains(REDACTED));
+        assert!(!rendered.contains("\"secret\""));
+        assert!(!rendered.contains("admin password"));
+    }
+
+    #[test]
+    fn test_yaml_and_validation() {
+        let file = write("svc.yaml", "chains:\n  bsc:\n    chain_id: 56\n    rpc_http: [\"https://bsc.example\"]\n");
+        let loader = ConfigLoader::new("svc-test").with_file(&file).with_env(Vec::new());
+        assert_eq!(loader.load_service().unwrap().chains["bsc"].chain_id, 56);
+
+        let invalid = loader.clone().with_set("chains.bsc.rpc_http=[\"bsc.example\"]");
+        assert!(invalid.load_service().unwrap_err().to_string().contains("must start with"));
+        let invalid = loader.clone().with_set("gas.bsc={\"max_fee_gwei\": 5, \"max_priority_gwei\": 6}");
+        assert!(invalid.load_service().is_err());
+        assert!(loader.clone().with_set("port").load_service().is_err());
+        assert!(loader.clone().with_set("grpc_port=0").load_service().is_err());
+        std::fs::remove_file(file).unwrap();
+
+        let missing = write("missing.toml", "[auth]\njwt_secret = \"${UNSET_SECRET}\"\n");
+        let error = ConfigLoader::new("svc-test").with_file(&missing).with_env(Vec::new()).load_service().unwrap_err();
+        assert!(error.to_string().contains("UNSET_SECRET is not set"));
+        std::fs::remove_file(missing).unwrap();
+    }
+}
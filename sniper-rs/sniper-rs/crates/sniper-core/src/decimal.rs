@@ -0,0 +1,488 @@
+//! Fixed-point decimal for money.
+//!
+//! Amounts, prices and PnL are held as a signed count of 10^-18 units, the
+//! precision of an ERC-20 token with 18 decimals, so sums never drift and
+//! conversions to and from on-chain integer amounts are exact. Products and
+//! quotients are computed on 256 bits and rounded half away from zero to the
+//! 18th digit. Operators panic on overflow of the ±1.7e20 range; the
+//! `checked_*` methods and [`Decimal::checked_sum`] return `None` instead,
+//! and are what order sizes and PnL totals taken from requests go through.
+//!
+//! In JSON a decimal is written as a string, e.g. `"1234.5"`, so clients
+//! read it back without loss; numbers are accepted as well, so requests
+//! written against the old `f64` fields keep working.
+
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+/// Fractional digits of a [`Decimal`]
+pub const DECIMAL_SCALE: u32 = 18;
+
+const UNIT: i128 = 10i128.pow(DECIMAL_SCALE);
+
+/// Signed fixed-point number with 18 fractional digits
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Decimal(i128);
+
+/// Why a string is not a decimal
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseDecimalError {
+    #[error("invalid decimal {0:?}")]
+    Invalid(String),
+    #[error("decimal {0} is out of range")]
+    Overflow(String),
+}
+
+impl Decimal {
+    pub const ZERO: Decimal = Decimal(0);
+    pub const ONE: Decimal = Decimal(UNIT);
+    pub const MAX: Decimal = Decimal(i128::MAX);
+    pub const MIN: Decimal = Decimal(i128::MIN);
+
+    /// Decimal of `raw` 10^-18 units
+    pub const fn from_raw(raw: i128) -> Self {
+        Decimal(raw)
+    }
+
+    /// Count of 10^-18 units
+    pub const fn raw(self) -> i128 {
+        self.0
+    }
+
+    /// Nearest decimal to `value`, `None` if it is not finite or out of range
+    ///
+    /// Goes through the shortest string that reads back as `value`, so
+    /// `0.1` becomes exactly 0.1 rather than 0.1000000000000000055.
+    pub fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        value.to_string().parse().ok()
+    }
+
+    pub fn to_f64(self) -> f64 {
+        (self.0 / UNIT) as f64 + (self.0 % UNIT) as f64 / UNIT as f64
+    }
+
+    /// Decimal of an integer amount of a token with `decimals` decimals,
+    /// e.g. wei for `decimals = 18`
+    pub fn from_units(units: u128, decimals: u32) -> Option<Self> {
+        let units = i128::try_from(units).ok()?;
+        if decimals <= DECIMAL_SCALE {
+            units.checked_mul(10i128.pow(DECIMAL_SCALE - decimals)).map(Decimal)
+        } else {
+            Some(Decimal(div_round(units, 10i128.checked_pow(decimals - DECIMAL_SCALE)?)))
+        }
+    }
+
+    /// Integer amount of a token with `decimals` decimals, rounded down
+    ///
+    /// `None` if the decimal is negative or the amount overflows.
+    pub fn to_units(self, decimals: u32) -> Option<u128> {
+        if self.0 < 0 {
+            return None;
+        }
+        let units = if decimals <= DECIMAL_SCALE {
+            self.0 / 10i128.pow(DECIMAL_SCALE - decimals)
+        } else {
+            self.0.checked_mul(10i128.checked_pow(decimals - DECIMAL_SCALE)?)?
+        };
+        Some(units as u128)
+    }
+
+    /// Amount in wei of a decimal amount of ETH or any 18 decimal token
+    pub fn to_wei(self) -> Option<u128> {
+        self.to_units(18)
+    }
+
+    pub fn from_wei(wei: u128) -> Option<Self> {
+        Self::from_units(wei, 18)
+    }
+
+    pub fn checked_add(self, other: Decimal) -> Option<Decimal> {
+        self.0.checked_add(other.0).map(Decimal)
+    }
+
+    pub fn checked_sub(self, other: Decimal) -> Option<Decimal> {
+        self.0.checked_sub(other.0).map(Decimal)
+    }
+
+    pub fn checked_mul(self, other: Decimal) -> Option<Decimal> {
+        mul_div(self.0, other.0, UNIT).map(Decimal)
+    }
+
+    /// Quotient, `None` on division by zero or overflow
+    pub fn checked_div(self, other: Decimal) -> Option<Decimal> {
+        if other.0 == 0 {
+            return None;
+        }
+        mul_div(self.0, UNIT, other.0).map(Decimal)
+    }
+
+    /// Sum of `values`, `None` if it overflows at any step
+    pub fn checked_sum(values: impl IntoIterator<Item = Decimal>) -> Option<Decimal> {
+        values.into_iter().try_fold(Decimal::ZERO, Decimal::checked_add)
+    }
+
+    pub fn abs(self) -> Decimal {
+        Decimal(self.0.abs())
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    pub fn is_positive(self) -> bool {
+        self.0 > 0
+    }
+
+    /// Rounded half away from zero to `digits` fractional digits
+    pub fn round_dp(self, digits: u32) -> Decimal {
+        if digits >= DECIMAL_SCALE {
+            return self;
+        }
+        let step = 10i128.pow(DECIMAL_SCALE - digits);
+        Decimal(div_round(self.0, step) * step)
+    }
+}
+
+/// `value / divisor` rounded half away from zero
+fn div_round(value: i128, divisor: i128) -> i128 {
+    let quotient = value / divisor;
+    let remainder = value % divisor;
+    if remainder.unsigned_abs() >= divisor.unsigned_abs() - remainder.unsigned_abs() {
+        quotient + value.signum() * divisor.signum()
+    } else {
+        quotient
+    }
+}
+
+/// `a * b / c` on 256 bits, rounded half away from zero
+fn mul_div(a: i128, b: i128, c: i128) -> Option<i128> {
+    let negative = (a < 0) ^ (b < 0) ^ (c < 0);
+    let (a, b, c) = (a.unsigned_abs(), b.unsigned_abs(), c.unsigned_abs());
+    let (hi, lo) = mul_wide(a, b);
+    if hi >= c {
+        return None;
+    }
+    // Long division of the 256 bit product, one bit at a time
+    let (mut quotient, mut remainder) = (0u128, hi);
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((lo >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= c {
+            remainder = remainder.wrapping_sub(c);
+            quotient |= 1;
+        }
+    }
+    if remainder >= c - remainder {
+        quotient = quotient.checked_add(1)?;
+    }
+    let quotient = i128::try_from(quotient).ok()?;
+    Some(if negative { -quotient } else { quotient })
+}
+
+/// Full 256 bit product as (high, low) halves
+fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_hi = a_hi * b_hi;
+    let middle = (lo_lo >> 64) + (hi_lo & MASK) + (lo_hi & MASK);
+    let lo = (middle << 64) | (lo_lo & MASK);
+    let hi = hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (middle >> 64);
+    (hi, lo)
+}
+
+impl Add for Decimal {
+    type Output = Decimal;
+
+    fn add(self, other: Decimal) -> Decimal {
+        self.checked_add(other).expect("decimal overflow")
+    }
+}
+
+impl Sub for Decimal {
+    type Output = Decimal;
+
+    fn sub(self, other: Decimal) -> Decimal {
+        self.checked_sub(other).expect("decimal overflow")
+    }
+}
+
+impl Mul for Decimal {
+    type Output = Decimal;
+
+    fn mul(self, other: Decimal) -> Decimal {
+        self.checked_mul(other).expect("decimal overflow")
+    }
+}
+
+impl Div for Decimal {
+    type Output = Decimal;
+
+    fn div(self, other: Decimal) -> Decimal {
+        assert!(!other.is_zero(), "decimal division by zero");
+        self.checked_div(other).expect("decimal overflow")
+    }
+}
+
+impl Neg for Decimal {
+    type Output = Decimal;
+
+    fn neg(self) -> Decimal {
+        Decimal(-self.0)
+    }
+}
+
+impl AddAssign for Decimal {
+    fn add_assign(&mut self, other: Decimal) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Decimal {
+    fn sub_assign(&mut self, other: Decimal) {
+        *self = *self - other;
+    }
+}
+
+impl Sum for Decimal {
+    fn sum<I: Iterator<Item = Decimal>>(iter: I) -> Decimal {
+        iter.fold(Decimal::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Decimal> for Decimal {
+    fn sum<I: Iterator<Item = &'a Decimal>>(iter: I) -> Decimal {
+        iter.copied().sum()
+    }
+}
+
+macro_rules! from_int {
+    ($($int:ty),*) => {$(
+        impl From<$int> for Decimal {
+            fn from(value: $int) -> Decimal {
+                Decimal(value as i128 * UNIT)
+            }
+        }
+    )*};
+}
+
+from_int!(i8, i16, i32, i64, u8, u16, u32, u64);
+
+impl FromStr for Decimal {
+    type Err = ParseDecimalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseDecimalError::Invalid(s.to_string());
+        let (negative, digits) = match s.trim().strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.trim().strip_prefix('+').unwrap_or(s.trim())),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        let all_digits = |part: &str| part.bytes().all(|byte| byte.is_ascii_digit());
+        if (whole.is_empty() && fraction.is_empty()) || !all_digits(whole) || !all_digits(fraction) {
+            return Err(invalid());
+        }
+
+        let overflow = || ParseDecimalError::Overflow(s.to_string());
+        let mut raw: i128 = 0;
+        for byte in whole.bytes() {
+            raw = raw
+                .checked_mul(10)
+                .and_then(|raw| raw.checked_add((byte - b'0') as i128))
+                .ok_or_else(overflow)?;
+        }
+        raw = raw.checked_mul(UNIT).ok_or_else(overflow)?;
+        // Digits past the 18th round the last one kept
+        let mut place = UNIT;
+        for (index, byte) in fraction.bytes().enumerate() {
+            let digit = (byte - b'0') as i128;
+            if index as u32 == DECIMAL_SCALE {
+                if digit >= 5 {
+                    raw = raw.checked_add(1).ok_or_else(overflow)?;
+                }
+                break;
+            }
+            place /= 10;
+            raw += digit * place;
+        }
+        Ok(Decimal(if negative { -raw } else { raw }))
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let magnitude = self.0.unsigned_abs();
+        let unit = UNIT as u128;
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let fraction = magnitude % unit;
+        if fraction == 0 {
+            return write!(f, "{}{}", sign, magnitude / unit);
+        }
+        let fraction = format!("{:018}", fraction);
+        write!(f, "{}{}.{}", sign, magnitude / unit, fraction.trim_end_matches('0'))
+    }
+}
+
+impl fmt::Debug for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Serialize for Decimal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct DecimalVisitor;
+
+        impl Visitor<'_> for DecimalVisitor {
+            type Value = Decimal;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a decimal string or number")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Decimal, E> {
+                value.parse().map_err(E::custom)
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Decimal, E> {
+                Ok(Decimal::from(value))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Decimal, E> {
+                Ok(Decimal::from(value))
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Decimal, E> {
+                Decimal::from_f64(value).ok_or_else(|| E::custom(format!("decimal {} is out of range", value)))
+            }
+        }
+
+        deserializer.deserialize_any(DecimalVisitor)
+    }
+}
+
+impl utoipa::PartialSchema for Decimal {
+    fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+        utoipa::openapi::ObjectBuilder::new()
+            .schema_type(utoipa::openapi::schema::Type::String)
+            .format(Some(utoipa::openapi::SchemaFormat::Custom("decimal".to_string())))
+            .description(Some("Fixed-point number with up to 18 fractional digits; numbers are accepted on input"))
+            .examples(["1234.5"])
+            .into()
+    }
+}
+
+impl utoipa::ToSchema for Decimal {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display_round_trip() {
+        for s in ["0", "1", "-1", "0.1", "1234.5678", "-0.000000000000000001", "170141183460469231731.687303715884105727"] {
+            assert_eq!(dec(s).to_string(), s);
+        }
+        assert_eq!(dec("+2.50").to_string(), "2.5");
+        assert_eq!(dec(".5"), dec("0.5"));
+        // Past the 18th digit the value is rounded
+        assert_eq!(dec("0.0000000000000000015"), Decimal::from_raw(2));
+        assert!(matches!("1.2.3".parse::<Decimal>(), Err(ParseDecimalError::Invalid(_))));
+        assert!(matches!("abc".parse::<Decimal>(), Err(ParseDecimalError::Invalid(_))));
+        assert!(matches!("-".parse::<Decimal>(), Err(ParseDecimalError::Invalid(_))));
+        assert!(matches!("1e40".parse::<Decimal>(), Err(ParseDecimalError::Invalid(_))));
+        assert!(matches!("1000000000000000000000".parse::<Decimal>(), Err(ParseDecimalError::Overflow(_))));
+    }
+
+    #[test]
+    fn test_arithmetic_does_not_drift() {
+        // Ten additions of 0.1 are exactly 1, unlike with f64
+        let total: Decimal = (0..10).map(|_| dec("0.1")).sum();
+        assert_eq!(total, Decimal::ONE);
+        assert_eq!(dec("1.5") - dec("2.25"), dec("-0.75"));
+        assert_eq!(dec("1.5") * dec("-2.25"), dec("-3.375"));
+        assert_eq!(dec("1") / dec("3"), dec("0.333333333333333333"));
+        assert_eq!(dec("2") / dec("3"), dec("0.666666666666666667"));
+        assert_eq!(dec("-2") / dec("3"), dec("-0.666666666666666667"));
+        assert_eq!(dec("1").checked_div(Decimal::ZERO), None);
+
+        // Products wider than 128 bits before scaling back
+        let large = dec("100000000000000000000");
+        assert_eq!(large * dec("0.5"), dec("50000000000000000000"));
+        assert_eq!(large / dec("2000000"), dec("50000000000000"));
+        assert_eq!(large.checked_mul(large), None);
+        assert_eq!(Decimal::MAX.checked_mul(dec("2")), None);
+        assert_eq!(Decimal::MAX.checked_add(Decimal::from_raw(1)), None);
+        assert_eq!(Decimal::checked_sum([dec("1.5"), dec("-0.25")]), Some(dec("1.25")));
+        assert_eq!(Decimal::checked_sum([Decimal::MAX, dec("1"), dec("-1")]), None);
+
+        assert_eq!(dec("2.345").round_dp(2), dec("2.35"));
+        assert_eq!(dec("-2.345").round_dp(2), dec("-2.35"));
+        assert_eq!(dec("2.344").round_dp(0), dec("2"));
+        assert_eq!(dec("-3").abs(), dec("3"));
+        assert!(dec("-0.1") < Decimal::ZERO && dec("-0.1").is_negative());
+    }
+
+    #[test]
+    fn test_unit_conversions_are_exact() {
+        // 1.1 ETH in f64 converts to 1100000000000000128 wei
+        assert_eq!(dec("1.1").to_wei(), Some(1_100_000_000_000_000_000));
+        assert_eq!(Decimal::from_wei(1_234_567_890_123_456_789), Some(dec("1.234567890123456789")));
+        assert_eq!(dec("12.3456789").to_units(6), Some(12_345_678));
+        assert_eq!(Decimal::from_units(12_345_678, 6), Some(dec("12.345678")));
+        assert_eq!(Decimal::from_units(15, 19), Some(dec("0.000000000000000002")));
+        assert_eq!(dec("0.000000000000000002").to_units(20), Some(200));
+        assert_eq!(dec("-1").to_wei(), None);
+        assert_eq!(Decimal::from_units(u128::MAX, 18), None);
+
+        assert_eq!(Decimal::from_f64(0.1), Some(dec("0.1")));
+        assert_eq!(Decimal::from_f64(-1234.5), Some(dec("-1234.5")));
+        assert_eq!(Decimal::from_f64(f64::NAN), None);
+        assert_eq!(Decimal::from_f64(1e30), None);
+        assert_eq!(dec("-1234.5").to_f64(), -1234.5);
+        assert_eq!(Decimal::from(42u64), dec("42"));
+    }
+
+    #[test]
+    fn test_serde() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Fill {
+            amount: Decimal,
+            price: Decimal,
+        }
+
+        let fill = Fill { amount: dec("1.5"), price: dec("0.1") };
+        let json = serde_json::to_string(&fill).unwrap();
+        assert_eq!(json, r#"{"amount":"1.5","price":"0.1"}"#);
+        assert_eq!(serde_json::from_str::<Fill>(&json).unwrap(), fill);
+        // Numbers from clients of the f64 API are accepted
+        assert_eq!(serde_json::from_str::<Fill>(r#"{"amount":1.5,"price":0.1}"#).unwrap(), fill);
+        assert_eq!(serde_json::from_str::<Decimal>("3").unwrap(), dec("3"));
+        assert!(serde_json::from_str::<Decimal>(r#""x""#).is_err());
+    }
+}
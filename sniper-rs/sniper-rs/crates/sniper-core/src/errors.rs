@@ -1,3 +1,14 @@
+//! Error types shared across the sniper crates.
+//!
+//! Library crates return typed errors that implement [`DomainError`], which
+//! classifies each failure with an [`ErrorKind`]. Services return
+//! [`ApiError`] from their handlers: it converts from any domain error and
+//! responds with the kind's status code and the usual `success: false` body.
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::json;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -11,3 +22,161 @@ pub enum SniperError {
     #[error("other: {0}")]
     Other(String),
 }
+
+/// Class of a domain failure, deciding how services report it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The addressed resource does not exist
+    NotFound,
+    /// The request is well formed but its values are not acceptable
+    Invalid,
+    /// The request conflicts with the resource's current state
+    Conflict,
+    /// The caller's credentials were not accepted
+    Unauthenticated,
+    /// The caller may not act on the resource
+    Forbidden,
+    /// Trading is halted or a dependency is not available
+    Unavailable,
+    /// A venue or upstream service failed
+    Upstream,
+    /// Anything else
+    Internal,
+}
+
+impl ErrorKind {
+    /// HTTP status reported for the kind
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorKind::NotFound => StatusCode::NOT_FOUND,
+            ErrorKind::Invalid => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorKind::Conflict => StatusCode::CONFLICT,
+            ErrorKind::Unauthenticated => StatusCode::UNAUTHORIZED,
+            ErrorKind::Forbidden => StatusCode::FORBIDDEN,
+            ErrorKind::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorKind::Upstream => StatusCode::BAD_GATEWAY,
+            ErrorKind::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Error of a library crate that services can map to a response
+pub trait DomainError: std::error::Error + Send + Sync + 'static {
+    /// Class of the failure
+    fn kind(&self) -> ErrorKind;
+}
+
+impl DomainError for SniperError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            SniperError::Config(_) => ErrorKind::Invalid,
+            SniperError::Bus(_) => ErrorKind::Unavailable,
+            SniperError::Io(_) | SniperError::Other(_) => ErrorKind::Internal,
+        }
+    }
+}
+
+impl DomainError for crate::kill_switch::KillSwitchTripped {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Unavailable
+    }
+}
+
+/// Error returned by service handlers
+///
+/// Responds with the status of its kind and a JSON body shaped like the
+/// services' `ApiResponse`, so clients read the message where they always did.
+#[derive(Debug)]
+pub struct ApiError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl ApiError {
+    /// Create an error of a kind
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    /// Resource not found
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::NotFound, message)
+    }
+
+    /// Unacceptable request values
+    pub fn invalid(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Invalid, message)
+    }
+
+    /// Prefix the message with what was being attempted
+    pub fn context(mut self, context: &str) -> Self {
+        self.message = format!("{}: {}", context, self.message);
+        self
+    }
+
+    /// HTTP status of the error
+    pub fn status(&self) -> StatusCode {
+        self.kind.status()
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl<E: DomainError> From<E> for ApiError {
+    fn from(error: E) -> Self {
+        Self::new(error.kind(), error.to_string())
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::new(ErrorKind::Internal, error.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = json!({
+            "success": false,
+            "data": null,
+            "message": self.message,
+        });
+        (self.status(), Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kill_switch::{KillSwitchTripped, TripReason};
+
+    #[tokio::test]
+    async fn test_api_error_response() {
+        let tripped = KillSwitchTripped {
+            reason: TripReason::Manual {
+                by: "ops".to_string(),
+                reason: "maintenance".to_string(),
+            },
+        };
+        let error = ApiError::from(tripped).context("Failed to create order");
+        assert_eq!(error.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let response = error.into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["success"], false);
+        assert_eq!(body["message"], "Failed to create order: kill switch is tripped: tripped by ops: maintenance");
+
+        let error = ApiError::from(anyhow::anyhow!("boom"));
+        assert_eq!(error.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(ApiError::not_found("Order not found").status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,199 @@
+//! Typed trading events published on the core bus.
+//!
+//! The order and portfolio managers publish these as their state changes, the
+//! executor after every trade, the AMM router on every route cache lookup, and
+//! the runner when it retires or re-enables a strategy, the plugin manager
+//! when it disables a failing plugin and the backup scheduler when a
+//! scheduled backup fails, so that monitoring can
+//! update metrics and open incidents without polling.
+//! The in-memory bus delivers every message to every subscriber, so events are
+//! tagged with their `type` and subscribers decode them with [`TradingEvent::decode`].
+
+use serde::{Deserialize, Serialize};
+
+/// Wildcard subject for subscribers interested in every trading event
+pub const TRADING_EVENTS_SUBJECT: &str = ">";
+
+/// Bus subject for order creation events
+pub const ORDER_CREATED_SUBJECT: &str = "orders.created";
+
+/// Bus subject for order fill events
+pub const ORDER_FILLED_SUBJECT: &str = "orders.filled";
+
+/// Bus subject for order cancellation events
+pub const ORDER_CANCELLED_SUBJECT: &str = "orders.cancelled";
+
+/// Bus subject for trade execution events
+pub const TRADE_EXECUTED_SUBJECT: &str = "exec.trade_executed";
+
+/// Bus subject for route cache lookups
+pub const ROUTE_CACHE_LOOKUP_SUBJECT: &str = "amm.route_cache_lookup";
+
+/// Bus subject for position open events
+pub const POSITION_OPENED_SUBJECT: &str = "portfolio.position_opened";
+
+/// Bus subject for position close events
+pub const POSITION_CLOSED_SUBJECT: &str = "portfolio.position_closed";
+
+/// Bus subject for drawdown breach events
+pub const DRAWDOWN_BREACHED_SUBJECT: &str = "portfolio.drawdown_breached";
+
+/// Bus subject for strategy retirement events
+pub const STRATEGY_RETIRED_SUBJECT: &str = "strategy.retired";
+
+/// Bus subject for strategy re-enable events
+pub const STRATEGY_REENABLED_SUBJECT: &str = "strategy.reenabled";
+
+/// Bus subject for plugin disable events
+pub const PLUGIN_DISABLED_SUBJECT: &str = "plugin.disabled";
+
+/// Bus subject for failed scheduled backups
+pub const BACKUP_FAILED_SUBJECT: &str = "backup.failed";
+
+/// Event emitted by the order and portfolio managers
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type")]
+pub enum TradingEvent {
+    OrderCreated {
+        order_id: String,
+        symbol: String,
+        side: String,
+        amount: f64,
+        timestamp: u64,
+    },
+    OrderFilled {
+        order_id: String,
+        symbol: String,
+        quantity: f64,
+        price: f64,
+        /// Whether the order has no quantity left
+        complete: bool,
+        timestamp: u64,
+    },
+    OrderCancelled {
+        order_id: String,
+        symbol: String,
+        timestamp: u64,
+    },
+    TradeExecuted {
+        idem_key: String,
+        chain: String,
+        /// Execution mode the plan was submitted with
+        venue: String,
+        success: bool,
+        /// Time from submission to receipt, in microseconds
+        latency_us: u64,
+        /// Fees paid, in the chain's native token
+        gas_paid: f64,
+        /// Shortfall of the realized output against the plan's quoted output,
+        /// in basis points, negative when the quote was beaten; absent for
+        /// plans without a quote
+        slippage_bps: Option<f64>,
+        timestamp: u64,
+    },
+    RouteCacheLookup {
+        chain: String,
+        hit: bool,
+        timestamp: u64,
+    },
+    PositionOpened {
+        position_id: String,
+        symbol: String,
+        side: String,
+        amount: f64,
+        entry_price: f64,
+        timestamp: u64,
+    },
+    PositionClosed {
+        position_id: String,
+        symbol: String,
+        realized_pnl: f64,
+        timestamp: u64,
+    },
+    DrawdownBreached {
+        /// Current drawdown from the equity peak, in percent
+        drawdown_pct: f64,
+        threshold_pct: f64,
+        equity: f64,
+        timestamp: u64,
+    },
+    StrategyRetired {
+        strategy_id: String,
+        /// Kill criteria the strategy breached
+        reasons: Vec<String>,
+        /// Positions closed by flattening
+        flattened_positions: usize,
+        timestamp: u64,
+    },
+    StrategyReEnabled {
+        strategy_id: String,
+        actor: String,
+        reason: String,
+        timestamp: u64,
+    },
+    PluginDisabled {
+        plugin_id: String,
+        /// Consecutive failed calls that crossed the threshold
+        failures: u32,
+        /// Error of the last failed call
+        last_error: String,
+        timestamp: u64,
+    },
+    BackupFailed {
+        schedule_id: String,
+        tenant_id: String,
+        error: String,
+        timestamp: u64,
+    },
+}
+
+impl TradingEvent {
+    /// Bus subject the event is published under
+    pub fn subject(&self) -> &'static str {
+        match self {
+            TradingEvent::OrderCreated { .. } => ORDER_CREATED_SUBJECT,
+            TradingEvent::OrderFilled { .. } => ORDER_FILLED_SUBJECT,
+            TradingEvent::OrderCancelled { .. } => ORDER_CANCELLED_SUBJECT,
+            TradingEvent::TradeExecuted { .. } => TRADE_EXECUTED_SUBJECT,
+            TradingEvent::RouteCacheLookup { .. } => ROUTE_CACHE_LOOKUP_SUBJECT,
+            TradingEvent::PositionOpened { .. } => POSITION_OPENED_SUBJECT,
+            TradingEvent::PositionClosed { .. } => POSITION_CLOSED_SUBJECT,
+            TradingEvent::DrawdownBreached { .. } => DRAWDOWN_BREACHED_SUBJECT,
+            TradingEvent::StrategyRetired { .. } => STRATEGY_RETIRED_SUBJECT,
+            TradingEvent::StrategyReEnabled { .. } => STRATEGY_REENABLED_SUBJECT,
+            TradingEvent::PluginDisabled { .. } => PLUGIN_DISABLED_SUBJECT,
+            TradingEvent::BackupFailed { .. } => BACKUP_FAILED_SUBJECT,
+        }
+    }
+
+    /// Decode a bus message, returning `None` for messages that are not trading events
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        serde_json::from_slice(bytes).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::InMemoryBus;
+
+    #[tokio::test]
+    async fn test_trading_event_roundtrip() {
+        let bus = InMemoryBus::new(16);
+        let mut rx = bus.subscribe(ORDER_FILLED_SUBJECT);
+
+        let event = TradingEvent::OrderFilled {
+            order_id: "order-1".to_string(),
+            symbol: "ETH".to_string(),
+            quantity: 1.0,
+            price: 2000.0,
+            complete: true,
+            timestamp: 1,
+        };
+        bus.publish_now(event.subject(), &event).unwrap();
+
+        let bytes = rx.recv().await.unwrap();
+        assert_eq!(TradingEvent::decode(&bytes), Some(event));
+        assert_eq!(TradingEvent::decode(br#"{"tenant_id":"default"}"#), None);
+    }
+}
@@ -0,0 +1,341 @@
+//! Process-wide kill switch and circuit breaker.
+//!
+//! A `KillSwitch` is a cloneable handle that executors and order managers
+//! query before every trade. Operators trip and reset it by hand; it also
+//! trips itself when the portfolio drawdown or the recent trade error rate
+//! crosses its thresholds. An automatic trip resets after a cooldown, like
+//! hft-common's `circuit_breaker_duration`; a manual trip holds until an
+//! operator resets it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, OnceLock, RwLock};
+use utoipa::ToSchema;
+use thiserror::Error;
+
+/// Circuit breaker thresholds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CircuitBreakerConfig {
+    /// Drawdown from the equity peak, in percent, that trips the breaker
+    pub max_drawdown_pct: Option<f64>,
+    /// Share of failed trades in the window, in percent, that trips the breaker
+    pub max_error_rate_pct: Option<f64>,
+    /// Window trade outcomes are counted over
+    pub error_window_secs: u64,
+    /// Outcomes needed in the window before the error rate is trusted
+    pub min_samples: usize,
+    /// Time after which an automatic trip resets, `None` to hold until reset by hand
+    pub cooldown_secs: Option<u64>,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            max_drawdown_pct: Some(20.0),
+            max_error_rate_pct: Some(50.0),
+            error_window_secs: 60,
+            min_samples: 10,
+            cooldown_secs: Some(60),
+        }
+    }
+}
+
+/// Why the kill switch tripped
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TripReason {
+    Manual { by: String, reason: String },
+    Drawdown { drawdown_pct: f64, limit_pct: f64 },
+    ErrorRate { error_rate_pct: f64, limit_pct: f64, samples: usize },
+}
+
+impl std::fmt::Display for TripReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TripReason::Manual { by, reason } => write!(f, "tripped by {}: {}", by, reason),
+            TripReason::Drawdown { drawdown_pct, limit_pct } => {
+                write!(f, "drawdown {:.2}% exceeds {:.2}%", drawdown_pct, limit_pct)
+            },
+            TripReason::ErrorRate { error_rate_pct, limit_pct, samples } => {
+                write!(f, "error rate {:.1}% over {} trades exceeds {:.1}%", error_rate_pct, samples, limit_pct)
+            },
+        }
+    }
+}
+
+/// Error returned for a trade attempted while the kill switch is tripped
+#[derive(Debug, Clone, Error)]
+#[error("kill switch is tripped: {reason}")]
+pub struct KillSwitchTripped {
+    pub reason: TripReason,
+}
+
+/// Current state of the kill switch
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct KillSwitchStatus {
+    pub tripped: bool,
+    pub reason: Option<TripReason>,
+    pub tripped_at: Option<u64>,
+    /// When an automatic trip resets itself
+    pub resets_at: Option<u64>,
+    /// Times the switch has tripped since startup
+    pub trips: u64,
+    /// Failed share of the trades in the window, in percent
+    pub error_rate_pct: f64,
+    pub window_samples: usize,
+}
+
+/// Trip state and recent trade outcomes
+#[derive(Debug, Default)]
+struct SwitchState {
+    reason: Option<TripReason>,
+    tripped_at: Option<u64>,
+    trips: u64,
+    outcomes: VecDeque<(u64, bool)>,
+}
+
+/// Kill switch shared by everything that can trade
+#[derive(Debug, Clone, Default)]
+pub struct KillSwitch {
+    config: Arc<CircuitBreakerConfig>,
+    state: Arc<RwLock<SwitchState>>,
+}
+
+impl KillSwitch {
+    /// Create a new, armed kill switch
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            state: Arc::default(),
+        }
+    }
+
+    /// Get the process-wide kill switch, created with the default thresholds
+    pub fn global() -> &'static KillSwitch {
+        static GLOBAL: OnceLock<KillSwitch> = OnceLock::new();
+        GLOBAL.get_or_init(KillSwitch::default)
+    }
+
+    /// Get the circuit breaker thresholds
+    pub fn config(&self) -> &CircuitBreakerConfig {
+        &self.config
+    }
+
+    /// Whether trading is halted
+    pub fn is_tripped(&self) -> bool {
+        self.active_reason().is_some()
+    }
+
+    /// Fail if trading is halted
+    pub fn ensure_armed(&self) -> Result<(), KillSwitchTripped> {
+        match self.active_reason() {
+            Some(reason) => Err(KillSwitchTripped { reason }),
+            None => Ok(()),
+        }
+    }
+
+    /// Reason trading is halted, clearing an automatic trip whose cooldown has passed
+    fn active_reason(&self) -> Option<TripReason> {
+        let now = now_secs();
+        {
+            let state = self.state.read().ok()?;
+            match (&state.reason, self.resets_at(&state)) {
+                (None, _) => return None,
+                (Some(reason), Some(resets_at)) if now < resets_at => return Some(reason.clone()),
+                (Some(reason), None) => return Some(reason.clone()),
+                _ => {},
+            }
+        }
+        let mut state = self.state.write().ok()?;
+        if self.resets_at(&state).is_some_and(|resets_at| now >= resets_at) {
+            tracing::info!("kill switch cooldown elapsed, trading resumed");
+            state.reason = None;
+            state.tripped_at = None;
+            state.outcomes.clear();
+        }
+        state.reason.clone()
+    }
+
+    /// When an automatic trip resets itself
+    fn resets_at(&self, state: &SwitchState) -> Option<u64> {
+        match state.reason {
+            Some(TripReason::Manual { .. }) | None => None,
+            Some(_) => Some(state.tripped_at? + self.config.cooldown_secs?),
+        }
+    }
+
+    /// Halt trading, keeping the first reason if already tripped
+    pub fn trip(&self, reason: TripReason) {
+        let Ok(mut state) = self.state.write() else {
+            return;
+        };
+        if state.reason.is_some() {
+            return;
+        }
+        tracing::error!("kill switch tripped, trading halted: {}", reason);
+        state.reason = Some(reason);
+        state.tripped_at = Some(now_secs());
+        state.trips += 1;
+    }
+
+    /// Resume trading, returning whether the switch was tripped
+    pub fn reset(&self, by: &str) -> bool {
+        let Ok(mut state) = self.state.write() else {
+            return false;
+        };
+        let was_tripped = state.reason.take().is_some();
+        state.tripped_at = None;
+        state.outcomes.clear();
+        if was_tripped {
+            tracing::warn!("kill switch reset by {}, trading resumed", by);
+        }
+        was_tripped
+    }
+
+    /// Trip if a drawdown from the equity peak, in percent, exceeds the limit
+    pub fn record_drawdown(&self, drawdown_pct: f64) {
+        if let Some(limit_pct) = self.config.max_drawdown_pct {
+            if drawdown_pct > limit_pct {
+                self.trip(TripReason::Drawdown { drawdown_pct, limit_pct });
+            }
+        }
+    }
+
+    /// Record whether a trade succeeded, tripping if the window's error rate exceeds the limit
+    pub fn record_outcome(&self, success: bool) {
+        let now = now_secs();
+        let breach = {
+            let Ok(mut state) = self.state.write() else {
+                return;
+            };
+            state.outcomes.push_back((now, success));
+            Self::prune(&mut state.outcomes, now, self.config.error_window_secs);
+            let (error_rate_pct, samples) = Self::error_rate(&state.outcomes);
+            self.config
+                .max_error_rate_pct
+                .filter(|limit_pct| samples >= self.config.min_samples && error_rate_pct > *limit_pct)
+                .map(|limit_pct| TripReason::ErrorRate { error_rate_pct, limit_pct, samples })
+        };
+        if let Some(reason) = breach {
+            self.trip(reason);
+        }
+    }
+
+    /// Drop outcomes older than the window
+    fn prune(outcomes: &mut VecDeque<(u64, bool)>, now: u64, window_secs: u64) {
+        while outcomes.front().is_some_and(|(at, _)| at + window_secs < now) {
+            outcomes.pop_front();
+        }
+    }
+
+    /// Failed share of the outcomes, in percent, and their count
+    fn error_rate(outcomes: &VecDeque<(u64, bool)>) -> (f64, usize) {
+        if outcomes.is_empty() {
+            return (0.0, 0);
+        }
+        let failures = outcomes.iter().filter(|(_, success)| !success).count();
+        (failures as f64 / outcomes.len() as f64 * 100.0, outcomes.len())
+    }
+
+    /// Get the current state of the kill switch
+    pub fn status(&self) -> KillSwitchStatus {
+        let reason = self.active_reason();
+        let Ok(mut state) = self.state.write() else {
+            return KillSwitchStatus::default();
+        };
+        Self::prune(&mut state.outcomes, now_secs(), self.config.error_window_secs);
+        let (error_rate_pct, window_samples) = Self::error_rate(&state.outcomes);
+        KillSwitchStatus {
+            tripped: reason.is_some(),
+            reason,
+            tripped_at: state.tripped_at,
+            resets_at: self.resets_at(&state),
+            trips: state.trips,
+            error_rate_pct,
+            window_samples,
+        }
+    }
+}
+
+/// Current Unix time in seconds
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_trip_holds_until_reset() {
+        let switch = KillSwitch::new(CircuitBreakerConfig {
+            cooldown_secs: Some(0),
+            ..CircuitBreakerConfig::default()
+        });
+        assert!(switch.ensure_armed().is_ok());
+
+        switch.trip(TripReason::Manual {
+            by: "ops".to_string(),
+            reason: "exchange incident".to_string(),
+        });
+        // Handles share state, and a manual trip ignores the cooldown
+        let handle = switch.clone();
+        assert!(handle.is_tripped());
+        let err = handle.ensure_armed().unwrap_err();
+        assert!(err.to_string().contains("exchange incident"));
+
+        assert!(switch.reset("ops"));
+        assert!(!handle.is_tripped());
+        assert!(!switch.reset("ops"));
+        assert_eq!(switch.status().trips, 1);
+    }
+
+    #[test]
+    fn test_drawdown_trips_and_cools_down() {
+        let switch = KillSwitch::new(CircuitBreakerConfig {
+            max_drawdown_pct: Some(10.0),
+            cooldown_secs: None,
+            ..CircuitBreakerConfig::default()
+        });
+        switch.record_drawdown(9.0);
+        assert!(!switch.is_tripped());
+        switch.record_drawdown(12.5);
+        let status = switch.status();
+        assert!(status.tripped);
+        assert_eq!(status.reason, Some(TripReason::Drawdown { drawdown_pct: 12.5, limit_pct: 10.0 }));
+        assert_eq!(status.resets_at, None);
+
+        let cooling = KillSwitch::new(CircuitBreakerConfig {
+            max_drawdown_pct: Some(10.0),
+            cooldown_secs: Some(0),
+            ..CircuitBreakerConfig::default()
+        });
+        cooling.record_drawdown(12.5);
+        assert!(!cooling.is_tripped());
+        assert_eq!(cooling.status().trips, 1);
+    }
+
+    #[test]
+    fn test_error_rate_trips_after_min_samples() {
+        let switch = KillSwitch::new(CircuitBreakerConfig {
+            max_error_rate_pct: Some(50.0),
+            min_samples: 4,
+            ..CircuitBreakerConfig::default()
+        });
+        for _ in 0..3 {
+            switch.record_outcome(false);
+        }
+        // Too few samples to judge
+        assert!(!switch.is_tripped());
+
+        switch.record_outcome(true);
+        assert!(matches!(
+            switch.status().reason,
+            Some(TripReason::ErrorRate { samples: 4, .. })
+        ));
+    }
+}
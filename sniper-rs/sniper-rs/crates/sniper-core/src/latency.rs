@@ -0,0 +1,453 @@
+//! Tick-to-trade latency measurement.
+//!
+//! A [`LatencyTrace`] timestamps one signal on the monotonic clock as it
+//! passes each [`Stage`] of the pipeline, from ingest to the venue's ack.
+//! A [`LatencyRecorder`] aggregates finished traces into HDR-style
+//! [`LatencyHistogram`]s, one per stage for the time since the previous
+//! stage plus one for the whole tick-to-trade, and reports their
+//! percentiles. `Instant`s can't cross the bus, so a trace handed from one
+//! task to another is parked in the recorder under the plan's key and
+//! resumed on the other side.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Traces parked at once before stale ones are dropped
+pub const MAX_PARKED_TRACES: usize = 10_000;
+
+/// Age after which a parked trace is considered abandoned
+const PARKED_TRACE_TTL: Duration = Duration::from_secs(60);
+
+/// Low bits of a value kept exactly, bounding the relative error of a
+/// recorded value to 1/128
+const SUB_BUCKET_BITS: u32 = 8;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+const HALF_SUB_BUCKETS: usize = SUB_BUCKETS / 2;
+const BUCKETS: usize = SUB_BUCKETS + (64 - SUB_BUCKET_BITS as usize) * HALF_SUB_BUCKETS;
+
+/// Point of the pipeline a trace is timestamped at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    /// Signal taken off the bus
+    SignalIngest,
+    /// Strategy produced a plan
+    Decision,
+    /// Plan passed the risk check
+    RiskCheck,
+    /// Order handed to the venue
+    OrderSend,
+    /// Venue acknowledged the order
+    Ack,
+}
+
+impl Stage {
+    /// Stages in pipeline order
+    pub const ALL: [Stage; 5] = [Stage::SignalIngest, Stage::Decision, Stage::RiskCheck, Stage::OrderSend, Stage::Ack];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::SignalIngest => "signal_ingest",
+            Stage::Decision => "decision",
+            Stage::RiskCheck => "risk_check",
+            Stage::OrderSend => "order_send",
+            Stage::Ack => "ack",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Monotonic timestamps of one signal's way through the pipeline
+#[derive(Debug, Clone, Default)]
+pub struct LatencyTrace {
+    marks: [Option<Instant>; 5],
+}
+
+impl LatencyTrace {
+    /// Start a trace at signal ingest, now
+    pub fn start() -> Self {
+        Self::starting_at(Instant::now())
+    }
+
+    /// Start a trace at a signal ingested at `ingest`
+    pub fn starting_at(ingest: Instant) -> Self {
+        let mut trace = Self::default();
+        trace.mark_at(Stage::SignalIngest, ingest);
+        trace
+    }
+
+    /// Timestamp `stage` now
+    pub fn mark(&mut self, stage: Stage) {
+        self.mark_at(stage, Instant::now());
+    }
+
+    pub fn mark_at(&mut self, stage: Stage, at: Instant) {
+        self.marks[stage.index()] = Some(at);
+    }
+
+    /// When the trace passed `stage`
+    pub fn at(&self, stage: Stage) -> Option<Instant> {
+        self.marks[stage.index()]
+    }
+
+    /// Time from the previous timestamped stage to `stage`
+    pub fn stage_latency(&self, stage: Stage) -> Option<Duration> {
+        let at = self.at(stage)?;
+        let previous = self.marks[..stage.index()].iter().rev().find_map(|mark| *mark)?;
+        Some(at.saturating_duration_since(previous))
+    }
+
+    /// Time from signal ingest to the venue's ack
+    pub fn tick_to_trade(&self) -> Option<Duration> {
+        Some(self.at(Stage::Ack)?.saturating_duration_since(self.at(Stage::SignalIngest)?))
+    }
+}
+
+/// Histogram of durations with bounded relative error, in the manner of HdrHistogram
+///
+/// Values are counted in nanoseconds. Below 256ns every value has its own
+/// bucket; above, each power of two is split into 128 buckets, so a
+/// reported percentile is within 0.8% of the recorded value.
+#[derive(Debug, Clone)]
+pub struct LatencyHistogram {
+    counts: Vec<u64>,
+    count: u64,
+    min: u64,
+    max: u64,
+    sum: u128,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self {
+            counts: vec![0; BUCKETS],
+            count: 0,
+            min: u64::MAX,
+            max: 0,
+            sum: 0,
+        }
+    }
+
+    pub fn record(&mut self, value: Duration) {
+        self.record_nanos(u64::try_from(value.as_nanos()).unwrap_or(u64::MAX));
+    }
+
+    pub fn record_nanos(&mut self, nanos: u64) {
+        self.counts[bucket_index(nanos)] += 1;
+        self.count += 1;
+        self.min = self.min.min(nanos);
+        self.max = self.max.max(nanos);
+        self.sum += nanos as u128;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn min(&self) -> Duration {
+        if self.count == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_nanos(self.min)
+    }
+
+    pub fn max(&self) -> Duration {
+        Duration::from_nanos(self.max)
+    }
+
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_nanos((self.sum / self.count as u128) as u64)
+    }
+
+    /// Value below which `quantile` (0 to 1) of the recorded values fall
+    pub fn value_at_quantile(&self, quantile: f64) -> Duration {
+        if self.count == 0 {
+            return Duration::ZERO;
+        }
+        let rank = ((quantile.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Duration::from_nanos(bucket_high(index).clamp(self.min, self.max));
+            }
+        }
+        self.max()
+    }
+
+    /// Add the values recorded in `other`
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other;
+        }
+        self.count += other.count;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.sum += other.sum;
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+/// Bucket counting `value`
+fn bucket_index(value: u64) -> usize {
+    if value < SUB_BUCKETS as u64 {
+        return value as usize;
+    }
+    // Shift leaving the value's top SUB_BUCKET_BITS bits
+    let shift = 63 - value.leading_zeros() - (SUB_BUCKET_BITS - 1);
+    SUB_BUCKETS + (shift as usize - 1) * HALF_SUB_BUCKETS + ((value >> shift) as usize - HALF_SUB_BUCKETS)
+}
+
+/// Highest value counted in bucket `index`
+fn bucket_high(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        return index as u64;
+    }
+    let shift = (index - SUB_BUCKETS) / HALF_SUB_BUCKETS + 1;
+    let top = ((index - SUB_BUCKETS) % HALF_SUB_BUCKETS + HALF_SUB_BUCKETS) as u128;
+    (((top + 1) << shift) - 1).min(u64::MAX as u128) as u64
+}
+
+/// Percentiles of one histogram, in microseconds
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub p50_us: f64,
+    pub p99_us: f64,
+    pub p999_us: f64,
+    pub max_us: f64,
+}
+
+impl From<&LatencyHistogram> for LatencySummary {
+    fn from(histogram: &LatencyHistogram) -> Self {
+        let us = |value: Duration| value.as_nanos() as f64 / 1_000.0;
+        Self {
+            count: histogram.count(),
+            p50_us: us(histogram.value_at_quantile(0.5)),
+            p99_us: us(histogram.value_at_quantile(0.99)),
+            p999_us: us(histogram.value_at_quantile(0.999)),
+            max_us: us(histogram.max()),
+        }
+    }
+}
+
+/// Latency of one stage, measured from the stage before it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageLatency {
+    pub stage: Stage,
+    #[serde(flatten)]
+    pub latency: LatencySummary,
+}
+
+/// Percentiles of the recorded traces
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyReport {
+    pub tick_to_trade: LatencySummary,
+    /// Every stage after ingest, in pipeline order
+    pub stages: Vec<StageLatency>,
+}
+
+struct RecorderState {
+    tick_to_trade: LatencyHistogram,
+    stages: Vec<(Stage, LatencyHistogram)>,
+    parked: HashMap<String, LatencyTrace>,
+}
+
+/// Aggregates finished traces, shared by every stage of the pipeline
+pub struct LatencyRecorder {
+    state: Mutex<RecorderState>,
+}
+
+impl Default for LatencyRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LatencyRecorder {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(RecorderState {
+                tick_to_trade: LatencyHistogram::new(),
+                stages: Stage::ALL[1..].iter().map(|stage| (*stage, LatencyHistogram::new())).collect(),
+                parked: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Hand a trace over to whichever task resumes `key`
+    ///
+    /// Once [`MAX_PARKED_TRACES`] are waiting, traces parked for over a
+    /// minute are dropped, and the new one is too if that frees no room.
+    pub fn park(&self, key: impl Into<String>, trace: LatencyTrace) {
+        let mut state = self.lock();
+        if state.parked.len() >= MAX_PARKED_TRACES {
+            state.parked.retain(|_, trace| {
+                trace.at(Stage::SignalIngest).is_some_and(|start| start.elapsed() < PARKED_TRACE_TTL)
+            });
+            if state.parked.len() >= MAX_PARKED_TRACES {
+                tracing::warn!("{} latency traces parked, dropping the newest", MAX_PARKED_TRACES);
+                return;
+            }
+        }
+        state.parked.insert(key.into(), trace);
+    }
+
+    /// Take back the trace parked under `key`
+    pub fn resume(&self, key: &str) -> Option<LatencyTrace> {
+        self.lock().parked.remove(key)
+    }
+
+    /// Count a trace's stage latencies and, once acked, its tick-to-trade
+    pub fn record(&self, trace: &LatencyTrace) {
+        let mut state = self.lock();
+        for (stage, histogram) in state.stages.iter_mut() {
+            if let Some(latency) = trace.stage_latency(*stage) {
+                histogram.record(latency);
+            }
+        }
+        if let Some(latency) = trace.tick_to_trade() {
+            state.tick_to_trade.record(latency);
+        }
+    }
+
+    /// Percentiles of everything recorded so far
+    pub fn report(&self) -> LatencyReport {
+        report(&self.lock())
+    }
+
+    /// Percentiles of everything recorded since the last call, then start over
+    pub fn take_report(&self) -> LatencyReport {
+        let mut state = self.lock();
+        let report = report(&state);
+        state.tick_to_trade.reset();
+        for (_, histogram) in state.stages.iter_mut() {
+            histogram.reset();
+        }
+        report
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, RecorderState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn report(state: &RecorderState) -> LatencyReport {
+    LatencyReport {
+        tick_to_trade: LatencySummary::from(&state.tick_to_trade),
+        stages: state
+            .stages
+            .iter()
+            .map(|(stage, histogram)| StageLatency {
+                stage: *stage,
+                latency: LatencySummary::from(histogram),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_percentiles_within_precision() {
+        assert_eq!(bucket_index(255), 255);
+        assert_eq!(bucket_index(256), 256);
+        assert_eq!(bucket_high(bucket_index(u64::MAX)), u64::MAX);
+        for value in [0, 1, 255, 256, 1_000, 123_456, 987_654_321, u64::MAX / 3] {
+            let high = bucket_high(bucket_index(value));
+            assert!(high >= value && (high - value) as f64 <= value as f64 / 128.0, "{}", value);
+        }
+
+        // 1..=10_000µs
+        let mut histogram = LatencyHistogram::new();
+        for us in 1..=10_000 {
+            histogram.record(Duration::from_micros(us));
+        }
+        assert_eq!(histogram.count(), 10_000);
+        assert_eq!(histogram.min(), Duration::from_micros(1));
+        assert_eq!(histogram.max(), Duration::from_micros(10_000));
+        for (quantile, exact) in [(0.5, 5_000.0), (0.99, 9_900.0), (0.999, 9_990.0)] {
+            let value = histogram.value_at_quantile(quantile).as_nanos() as f64 / 1_000.0;
+            assert!(value >= exact && value <= exact * 1.008, "p{} = {}", quantile, value);
+        }
+        assert_eq!(histogram.value_at_quantile(1.0), Duration::from_micros(10_000));
+        assert_eq!(LatencyHistogram::new().value_at_quantile(0.99), Duration::ZERO);
+
+        let mut merged = LatencyHistogram::new();
+        merged.record(Duration::from_secs(1));
+        merged.merge(&histogram);
+        assert_eq!(merged.count(), 10_001);
+        assert_eq!(merged.max(), Duration::from_secs(1));
+        merged.reset();
+        assert_eq!(merged.count(), 0);
+    }
+
+    #[test]
+    fn test_trace_stage_latencies() {
+        let ingest = Instant::now();
+        let mut trace = LatencyTrace::starting_at(ingest);
+        trace.mark_at(Stage::Decision, ingest + Duration::from_micros(40));
+        trace.mark_at(Stage::OrderSend, ingest + Duration::from_micros(70));
+        assert_eq!(trace.stage_latency(Stage::Decision), Some(Duration::from_micros(40)));
+        // A skipped stage is measured from the last one reached
+        assert_eq!(trace.stage_latency(Stage::RiskCheck), None);
+        assert_eq!(trace.stage_latency(Stage::OrderSend), Some(Duration::from_micros(30)));
+        assert_eq!(trace.tick_to_trade(), None);
+
+        trace.mark_at(Stage::Ack, ingest + Duration::from_micros(250));
+        assert_eq!(trace.tick_to_trade(), Some(Duration::from_micros(250)));
+    }
+
+    #[test]
+    fn test_recorder_reports_parked_traces() {
+        let recorder = LatencyRecorder::new();
+        let ingest = Instant::now();
+        for i in 0..100u64 {
+            let mut trace = LatencyTrace::starting_at(ingest);
+            trace.mark_at(Stage::Decision, ingest + Duration::from_micros(10));
+            recorder.park(format!("plan_{}", i), trace);
+        }
+        for i in 0..100u64 {
+            let mut trace = recorder.resume(&format!("plan_{}", i)).unwrap();
+            trace.mark_at(Stage::RiskCheck, ingest + Duration::from_micros(15));
+            trace.mark_at(Stage::OrderSend, ingest + Duration::from_micros(20));
+            trace.mark_at(Stage::Ack, ingest + Duration::from_micros(100 + i));
+            recorder.record(&trace);
+        }
+        assert!(recorder.resume("plan_0").is_none());
+
+        let report = recorder.take_report();
+        assert_eq!(report.tick_to_trade.count, 100);
+        assert!((report.tick_to_trade.p50_us - 149.0).abs() < 1.5);
+        assert!((report.tick_to_trade.p99_us - 198.0).abs() < 2.0);
+        assert_eq!(report.tick_to_trade.max_us, 199.0);
+        let expected = [(Stage::Decision, 10.0), (Stage::RiskCheck, 5.0), (Stage::OrderSend, 5.0), (Stage::Ack, 129.0)];
+        assert_eq!(report.stages.len(), expected.len());
+        for (stage, (name, p50_us)) in report.stages.iter().zip(expected) {
+            assert_eq!(stage.stage, name);
+            assert!((stage.latency.p50_us - p50_us).abs() <= p50_us / 128.0, "{:?}", stage);
+        }
+
+        // Taking the report started a new interval
+        assert_eq!(recorder.report().tick_to_trade, LatencySummary::default());
+    }
+}
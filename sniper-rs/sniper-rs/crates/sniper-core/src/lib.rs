@@ -3,12 +3,24 @@
 //! This module provides core functionality shared across all sniper components.
 
 pub mod types;
+pub mod audit;
 pub mod bus;
+pub mod bus_auth;
+pub mod bus_priority;
+pub mod events;
 pub mod config;
+pub mod decimal;
 pub mod errors;
 pub mod env;
 pub mod prelude;
 pub mod cache;
+pub mod warmup;
+pub mod kill_switch;
+pub mod latency;
+pub mod telemetry;
+pub mod shutdown;
+pub mod rate_limit;
+pub mod openapi;
 
 use anyhow::Result;
 
@@ -0,0 +1,91 @@
+//! OpenAPI documents for the svc-* REST APIs.
+//!
+//! Services derive their document with utoipa from the handlers' request
+//! and response types and mount it with [`router`], which serves it on
+//! `/openapi.json` and a Swagger UI on `/docs`. [`BearerAuth`] declares the
+//! `Authorization: Bearer` tokens svc-users issues, so authenticated calls
+//! can be tried from the UI.
+
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::OpenApi;
+use utoipa::{Modify, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
+
+/// Path the document is served on
+pub const OPENAPI_PATH: &str = "/openapi.json";
+
+/// Path the Swagger UI is served on
+pub const DOCS_PATH: &str = "/docs";
+
+/// Name of the bearer token security scheme
+pub const BEARER_AUTH: &str = "bearer_auth";
+
+/// Body of every error response
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ErrorResponse {
+    /// Always false
+    pub success: bool,
+    /// Always null
+    #[schema(value_type = Option<Object>)]
+    pub data: Option<serde_json::Value>,
+    pub message: Option<String>,
+}
+
+/// Adds the bearer token security scheme to a document
+pub struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            BEARER_AUTH,
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// Router serving `api` on `/openapi.json` and a Swagger UI for it on `/docs`
+pub fn router(api: OpenApi) -> Router {
+    SwaggerUi::new(DOCS_PATH).url(OPENAPI_PATH, api).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+    use utoipa::OpenApi as _;
+
+    /// Health check
+    #[utoipa::path(get, path = "/health", responses((status = 200, body = ErrorResponse)))]
+    #[allow(dead_code)]
+    async fn health() {}
+
+    #[derive(utoipa::OpenApi)]
+    #[openapi(paths(health), modifiers(&BearerAuth))]
+    struct ApiDoc;
+
+    #[tokio::test]
+    async fn test_router_serves_document_and_ui() {
+        let app = router(ApiDoc::openapi());
+
+        let response = app.clone().oneshot(Request::get(OPENAPI_PATH).body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let document: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(document["paths"]["/health"]["get"].is_object());
+        assert_eq!(document["components"]["securitySchemes"][BEARER_AUTH]["scheme"], "bearer");
+        assert!(document["components"]["schemas"]["ErrorResponse"].is_object());
+
+        let response = app.oneshot(Request::get("/docs/").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,329 @@
+//! Per-tenant rate limits for the svc-* routers.
+//!
+//! Every caller gets a token bucket per route class: reads (GET, HEAD and
+//! OPTIONS) and trades (everything that mutates). A bucket holds up to
+//! `burst` requests and refills at `per_second`; a request finding its
+//! bucket empty is answered 429 with a `Retry-After` header. Callers are told
+//! apart by the [`RateLimitKey`] the auth layer puts in the request, i.e. by
+//! tenant, and otherwise by client address, so an unauthenticated flood
+//! doesn't eat into a tenant's quota. Throttled requests are counted in
+//! `http_requests_throttled_total` when the limiter has a registry.
+
+use axum::extract::ConnectInfo;
+use axum::http::{header::RETRY_AFTER, HeaderValue, Method, Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use futures::future::{ready, Either, Ready};
+use prometheus::{IntCounterVec, Opts, Registry};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tower::{Layer, Service};
+
+/// Identity a request is rate limited by, set by the auth layer
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RateLimitKey(pub String);
+
+impl RateLimitKey {
+    /// Key of a tenant's callers
+    pub fn tenant(tenant_id: &str) -> Self {
+        Self(format!("tenant:{}", tenant_id))
+    }
+
+    /// Key of a caller without a tenant
+    pub fn user(user_id: &str) -> Self {
+        Self(format!("user:{}", user_id))
+    }
+}
+
+/// Kind of route a limit applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteClass {
+    Read,
+    Trade,
+}
+
+impl RouteClass {
+    /// Class of a request by its method
+    pub fn of(method: &Method) -> Self {
+        match *method {
+            Method::GET | Method::HEAD | Method::OPTIONS => RouteClass::Read,
+            _ => RouteClass::Trade,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RouteClass::Read => "read",
+            RouteClass::Trade => "trade",
+        }
+    }
+}
+
+/// Token bucket size and refill rate
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// Requests added to the bucket per second
+    pub per_second: f64,
+    /// Requests the bucket holds when full
+    pub burst: u32,
+}
+
+/// Rate limit settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub read: RateLimit,
+    pub trade: RateLimit,
+    /// Callers tracked at most; idle full buckets are dropped beyond it
+    pub max_keys: usize,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            read: RateLimit {
+                per_second: 50.0,
+                burst: 100,
+            },
+            trade: RateLimit {
+                per_second: 10.0,
+                burst: 20,
+            },
+            max_keys: 100_000,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// Limit of a route class
+    pub fn limit(&self, class: RouteClass) -> RateLimit {
+        match class {
+            RouteClass::Read => self.read,
+            RouteClass::Trade => self.trade,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    /// Refill the bucket up to `now`
+    fn refill(&mut self, limit: RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.per_second).min(limit.burst as f64);
+        self.updated = now;
+    }
+}
+
+/// Token buckets of all callers, shared by the layers of a router
+#[derive(Clone)]
+pub struct RateLimiter {
+    config: Arc<RateLimitConfig>,
+    buckets: Arc<Mutex<HashMap<(String, RouteClass), Bucket>>>,
+    throttled: Option<IntCounterVec>,
+}
+
+impl RateLimiter {
+    /// Create a limiter with empty buckets
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            buckets: Arc::default(),
+            throttled: None,
+        }
+    }
+
+    /// Count throttled requests in `registry`, labelled with the service and route class
+    pub fn with_registry(mut self, registry: &Registry, service: &str) -> anyhow::Result<Self> {
+        let throttled = IntCounterVec::new(
+            Opts::new("http_requests_throttled_total", "Total HTTP requests rejected by rate limits")
+                .const_label("service", service),
+            &["class"],
+        )?;
+        registry.register(Box::new(throttled.clone()))?;
+        self.throttled = Some(throttled);
+        Ok(self)
+    }
+
+    /// Layer applying the limits to a router
+    pub fn layer(&self) -> RateLimitLayer {
+        RateLimitLayer { limiter: self.clone() }
+    }
+
+    /// Take a request from a caller's bucket, or return how long until one is available
+    pub fn check(&self, key: &str, class: RouteClass) -> Result<(), Duration> {
+        self.check_at(key, class, Instant::now())
+    }
+
+    fn check_at(&self, key: &str, class: RouteClass, now: Instant) -> Result<(), Duration> {
+        let limit = self.config.limit(class);
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() >= self.config.max_keys && !buckets.contains_key(&(key.to_string(), class)) {
+            // Buckets that refilled completely hold no state worth keeping
+            let config = self.config.clone();
+            buckets.retain(|(_, class), bucket| {
+                let limit = config.limit(*class);
+                let mut refilled = *bucket;
+                refilled.refill(limit, now);
+                refilled.tokens < limit.burst as f64
+            });
+        }
+
+        let bucket = buckets.entry((key.to_string(), class)).or_insert(Bucket {
+            tokens: limit.burst as f64,
+            updated: now,
+        });
+        bucket.refill(limit, now);
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        if limit.per_second <= 0.0 {
+            return Err(Duration::from_secs(u64::MAX / 4));
+        }
+        Err(Duration::from_secs_f64((1.0 - bucket.tokens) / limit.per_second))
+    }
+
+    fn throttle(&self, class: RouteClass, retry_after: Duration) -> Response {
+        if let Some(throttled) = &self.throttled {
+            throttled.with_label_values(&[class.as_str()]).inc();
+        }
+        let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+        let body = serde_json::json!({
+            "success": false,
+            "data": null,
+            "message": format!("Rate limit exceeded, retry in {} seconds", seconds.max(1)),
+        });
+        let mut response = (StatusCode::TOO_MANY_REQUESTS, Json(body)).into_response();
+        response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(seconds.max(1)));
+        response
+    }
+}
+
+/// Caller a request is counted against
+fn request_key<B>(request: &Request<B>) -> String {
+    if let Some(RateLimitKey(key)) = request.extensions().get::<RateLimitKey>() {
+        return key.clone();
+    }
+    match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+        None => "anonymous".to_string(),
+    }
+}
+
+/// Layer returned by [`RateLimiter::layer`]
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    limiter: RateLimiter,
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            limiter: self.limiter.clone(),
+        }
+    }
+}
+
+/// Service produced by [`RateLimitLayer`]
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    limiter: RateLimiter,
+}
+
+impl<S, B> Service<Request<B>> for RateLimitService<S>
+where
+    S: Service<Request<B>, Response = Response>,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Either<Ready<Result<Response, S::Error>>, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let class = RouteClass::of(request.method());
+        match self.limiter.check(&request_key(&request), class) {
+            Ok(()) => Either::Right(self.inner.call(request)),
+            Err(retry_after) => Either::Left(ready(Ok(self.limiter.throttle(class, retry_after)))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    fn config(burst: u32) -> RateLimitConfig {
+        RateLimitConfig {
+            read: RateLimit { per_second: 10.0, burst },
+            trade: RateLimit { per_second: 1.0, burst },
+            max_keys: 2,
+        }
+    }
+
+    #[test]
+    fn test_buckets_refill() {
+        let limiter = RateLimiter::new(config(2));
+        let start = Instant::now();
+        assert!(limiter.check_at("tenant:a", RouteClass::Trade, start).is_ok());
+        assert!(limiter.check_at("tenant:a", RouteClass::Trade, start).is_ok());
+        let retry_after = limiter.check_at("tenant:a", RouteClass::Trade, start).unwrap_err();
+        assert_eq!(retry_after, Duration::from_secs(1));
+
+        // Classes and callers have their own buckets
+        assert!(limiter.check_at("tenant:a", RouteClass::Read, start).is_ok());
+        assert!(limiter.check_at("tenant:b", RouteClass::Trade, start).is_ok());
+
+        // Half a second refills half a trade token
+        let later = start + Duration::from_millis(500);
+        assert_eq!(limiter.check_at("tenant:a", RouteClass::Trade, later).unwrap_err(), Duration::from_millis(500));
+        assert!(limiter.check_at("tenant:a", RouteClass::Trade, start + Duration::from_secs(1)).is_ok());
+
+        // Idle full buckets are dropped once too many callers are tracked
+        let idle = start + Duration::from_secs(60);
+        assert!(limiter.check_at("tenant:c", RouteClass::Read, idle).is_ok());
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_layer_throttles_with_retry_after() {
+        let registry = Registry::new();
+        let limiter = RateLimiter::new(config(1)).with_registry(&registry, "svc-test").unwrap();
+        let app = Router::new().route("/orders", get(|| async { "ok" }).post(|| async { "created" })).layer(limiter.layer());
+        let send = |method: Method, key: &str| {
+            let mut request = Request::builder().method(method).uri("/orders").body(Body::empty()).unwrap();
+            request.extensions_mut().insert(RateLimitKey::tenant(key));
+            app.clone().oneshot(request)
+        };
+
+        assert_eq!(send(Method::POST, "a").await.unwrap().status(), StatusCode::OK);
+        let throttled = send(Method::POST, "a").await.unwrap();
+        assert_eq!(throttled.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(throttled.headers()[RETRY_AFTER], "1");
+        assert_eq!(send(Method::GET, "a").await.unwrap().status(), StatusCode::OK);
+        assert_eq!(send(Method::POST, "b").await.unwrap().status(), StatusCode::OK);
+
+        let throttled = registry.gather();
+        let counter = &throttled.iter().find(|family| family.get_name() == "http_requests_throttled_total").unwrap().get_metric()[0];
+        assert_eq!(counter.get_counter().get_value(), 1.0);
+    }
+}
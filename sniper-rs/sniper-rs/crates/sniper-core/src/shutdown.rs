@@ -0,0 +1,247 @@
+//! Graceful shutdown for the services.
+//!
+//! A [`Shutdown`] is triggered once, by SIGINT or SIGTERM or by hand. The
+//! HTTP server run through [`serve`] then stops accepting connections and
+//! lets in-flight requests finish, and background loops started with
+//! [`Shutdown::spawn`] stop at their next await point. Once
+//! [`Shutdown::join`] returns nothing else touches the service's state, so it
+//! can cancel or park what is still open and flush its stores before exiting.
+
+use axum::Router;
+use std::future::{Future, IntoFuture};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// Time in-flight requests and stopping tasks get before they are abandoned
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Cloneable handle announcing that the service is shutting down
+#[derive(Clone)]
+pub struct Shutdown {
+    triggered: Arc<watch::Sender<bool>>,
+    tasks: Arc<Mutex<Vec<JoinHandle<()>>>>,
+    drain_timeout: Duration,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    /// Create a handle that is only triggered by hand
+    pub fn new() -> Self {
+        Self {
+            triggered: Arc::new(watch::channel(false).0),
+            tasks: Arc::default(),
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+        }
+    }
+
+    /// Create a handle triggered by SIGINT or SIGTERM
+    pub fn on_signal() -> Self {
+        let shutdown = Self::new();
+        let trigger = shutdown.clone();
+        tokio::spawn(async move {
+            let signal = wait_for_signal().await;
+            tracing::info!("received {}, shutting down", signal);
+            trigger.trigger();
+        });
+        shutdown
+    }
+
+    /// Give in-flight requests and stopping tasks `timeout` before abandoning them
+    pub fn with_drain_timeout(mut self, timeout: Duration) -> Self {
+        self.drain_timeout = timeout;
+        self
+    }
+
+    /// Time in-flight requests and stopping tasks get
+    pub fn drain_timeout(&self) -> Duration {
+        self.drain_timeout
+    }
+
+    /// Start shutting down; later calls do nothing
+    pub fn trigger(&self) {
+        self.triggered.send_replace(true);
+    }
+
+    /// Whether shutdown was triggered
+    pub fn is_triggered(&self) -> bool {
+        *self.triggered.borrow()
+    }
+
+    /// Wait until shutdown is triggered
+    pub async fn wait(&self) {
+        triggered(self.triggered.subscribe()).await
+    }
+
+    /// Run a background task until shutdown is triggered
+    ///
+    /// The task is dropped at its next await point after the trigger, so
+    /// anything that must not be cut short, like a file write, belongs
+    /// between two awaits.
+    pub fn spawn<F>(&self, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let rx = self.triggered.subscribe();
+        self.track(tokio::spawn(async move {
+            tokio::select! {
+                _ = task => {},
+                _ = triggered(rx) => {},
+            }
+        }));
+    }
+
+    /// Have [`Shutdown::join`] wait for a task that finishes on its own
+    ///
+    /// For work that must not be cut short, like a backup run, which checks
+    /// [`Shutdown::is_triggered`] or [`Shutdown::wait`] between steps itself.
+    pub fn track(&self, task: JoinHandle<()>) {
+        let mut tasks = self.tasks.lock().unwrap_or_else(|e| e.into_inner());
+        tasks.retain(|task| !task.is_finished());
+        tasks.push(task);
+    }
+
+    /// Wait for the tasks started with [`Shutdown::spawn`] or tracked to stop
+    ///
+    /// Called once shutdown is triggered. Returns false if some were still
+    /// running after the drain timeout.
+    pub async fn join(&self) -> bool {
+        let tasks = std::mem::take(&mut *self.tasks.lock().unwrap_or_else(|e| e.into_inner()));
+        let stopped = async {
+            for task in tasks {
+                let _ = task.await;
+            }
+        };
+        if tokio::time::timeout(self.drain_timeout, stopped).await.is_err() {
+            tracing::warn!("background tasks still running after {:?}", self.drain_timeout);
+            return false;
+        }
+        true
+    }
+}
+
+/// Resolve once the watched flag is set, or never if it no longer can be
+async fn triggered(mut rx: watch::Receiver<bool>) {
+    if rx.wait_for(|triggered| *triggered).await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Wait for SIGINT or, on Unix, SIGTERM and return the signal's name
+pub async fn wait_for_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => return "SIGINT",
+                    _ = terminate.recv() => return "SIGTERM",
+                }
+            },
+            Err(e) => tracing::warn!("Failed to listen for SIGTERM: {}", e),
+        }
+    }
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        tracing::warn!("Failed to listen for SIGINT: {}", e);
+        std::future::pending::<()>().await;
+    }
+    "SIGINT"
+}
+
+/// Serve `app` until shutdown is triggered, then let in-flight requests finish
+///
+/// Requests still running after the drain timeout are abandoned.
+pub async fn serve(listener: TcpListener, app: Router, shutdown: &Shutdown) -> std::io::Result<()> {
+    let signal = shutdown.clone();
+    // Connection addresses let the rate limiter tell anonymous callers apart
+    let server = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async move { signal.wait().await })
+        .into_future();
+    tokio::pin!(server);
+
+    tokio::select! {
+        result = &mut server => return result,
+        _ = shutdown.wait() => {},
+    }
+    tracing::info!("draining in-flight requests");
+    match tokio::time::timeout(shutdown.drain_timeout, server).await {
+        Ok(result) => result,
+        Err(_) => {
+            tracing::warn!("requests still in flight after {:?} were abandoned", shutdown.drain_timeout);
+            Ok(())
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_spawned_tasks_stop_on_trigger() {
+        let shutdown = Shutdown::new().with_drain_timeout(Duration::from_secs(1));
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let counter = ticks.clone();
+        shutdown.spawn(async move {
+            loop {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        });
+        assert!(!shutdown.is_triggered());
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        shutdown.trigger();
+        assert!(shutdown.is_triggered());
+        assert!(shutdown.join().await);
+
+        // Stopped tasks no longer run
+        let stopped_at = ticks.load(Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(ticks.load(Ordering::SeqCst), stopped_at);
+
+        // Waiting after the trigger returns at once
+        shutdown.wait().await;
+    }
+
+    #[tokio::test]
+    async fn test_serve_drains_in_flight_requests() {
+        let started = Arc::new(tokio::sync::Notify::new());
+        let handler_started = started.clone();
+        let app = Router::new().route(
+            "/slow",
+            get(|| async move {
+                handler_started.notify_one();
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                "done"
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let shutdown = Shutdown::new();
+        let server = {
+            let shutdown = shutdown.clone();
+            tokio::spawn(async move { serve(listener, app, &shutdown).await })
+        };
+
+        let request = tokio::spawn(reqwest::get(format!("http://{}/slow", addr)));
+        started.notified().await;
+        shutdown.trigger();
+
+        // The request started before the trigger still completes
+        let body = request.await.unwrap().unwrap().text().await.unwrap();
+        assert_eq!(body, "done");
+        server.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,315 @@
+//! Distributed tracing with OpenTelemetry.
+//!
+//! A service given an OTLP endpoint exports its spans to a collector such as
+//! Jaeger or Tempo; without one it still records spans and propagates their
+//! context, so traces stay connected across services that do not export and
+//! trace IDs can still be logged.
+//! The W3C trace context travels in the `traceparent` HTTP header, picked up
+//! by the [`trace_http`] middleware for every request a service handles, and
+//! in bus messages as a `traceparent` field added to the JSON payload by
+//! [`InMemoryBus::publish_in`](crate::bus::InMemoryBus::publish_in).
+//! Subscribers continue the trace with [`message_context`], which lets a
+//! single trade be followed from its signal through planning, risk and
+//! execution.
+
+use anyhow::Result;
+use axum::extract::{MatchedPath, Request};
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+use opentelemetry::global;
+use opentelemetry::propagation::{Extractor, Injector};
+use opentelemetry::trace::{SpanKind, Status, TraceContextExt, Tracer};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::TracerProvider;
+use opentelemetry_sdk::Resource;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+pub use opentelemetry::trace::FutureExt;
+pub use opentelemetry::{Context, KeyValue};
+
+/// Name of the tracer spans are created with
+pub const TRACER_NAME: &str = "sniper";
+
+/// Field of a bus message carrying its trace context
+pub const TRACEPARENT_FIELD: &str = "traceparent";
+
+/// Environment variable naming the OTLP endpoint when none is configured
+pub const OTLP_ENDPOINT_ENV: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+/// Keeps span export running; flushes and stops it when dropped
+pub struct TelemetryGuard {
+    provider: Option<TracerProvider>,
+}
+
+impl TelemetryGuard {
+    /// Whether spans are exported
+    pub fn is_exporting(&self) -> bool {
+        self.provider.is_some()
+    }
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                tracing::warn!("Failed to flush spans: {}", e);
+            }
+        }
+    }
+}
+
+/// Set up trace propagation and, with an OTLP endpoint, span export
+///
+/// The endpoint falls back to `OTEL_EXPORTER_OTLP_ENDPOINT`; with neither, or
+/// an empty one, spans are recorded but not exported. Must be called within a
+/// Tokio runtime.
+pub fn init_telemetry(service_name: &str, otlp_endpoint: Option<&str>) -> Result<TelemetryGuard> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    let endpoint = otlp_endpoint
+        .map(str::to_string)
+        .or_else(|| std::env::var(OTLP_ENDPOINT_ENV).ok())
+        .filter(|endpoint| !endpoint.is_empty());
+    let Some(endpoint) = endpoint else {
+        global::set_tracer_provider(TracerProvider::builder().build());
+        return Ok(TelemetryGuard { provider: None });
+    };
+    let resource = Resource::new(vec![KeyValue::new("service.name", service_name.to_string())]);
+    let provider = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_endpoint(endpoint.clone()))
+        .with_trace_config(opentelemetry_sdk::trace::Config::default().with_resource(resource))
+        .install_batch(opentelemetry_sdk::runtime::Tokio)
+        .map_err(|e| anyhow::anyhow!("Failed to set up span export to {}: {}", endpoint, e))?;
+    global::set_tracer_provider(provider.clone());
+    tracing::info!("Exporting spans of {} to {}", service_name, endpoint);
+    Ok(TelemetryGuard {
+        provider: Some(provider),
+    })
+}
+
+/// Start a span under `parent`, returning the context it is active in
+///
+/// The span ends when the last clone of the returned context is dropped.
+pub fn start_span(name: impl Into<Cow<'static, str>>, parent: &Context, attributes: Vec<KeyValue>) -> Context {
+    start_span_of_kind(name, SpanKind::Internal, parent, attributes)
+}
+
+fn start_span_of_kind(
+    name: impl Into<Cow<'static, str>>,
+    kind: SpanKind,
+    parent: &Context,
+    attributes: Vec<KeyValue>,
+) -> Context {
+    let tracer = global::tracer(TRACER_NAME);
+    let span = tracer
+        .span_builder(name)
+        .with_kind(kind)
+        .with_attributes(attributes)
+        .start_with_context(&tracer, parent);
+    parent.with_span(span)
+}
+
+/// Mark the span active in a context as failed
+pub fn record_error(cx: &Context, error: impl std::fmt::Display) {
+    cx.span().set_status(Status::error(error.to_string()));
+}
+
+/// ID of the trace a context belongs to, for correlating logs
+pub fn trace_id(cx: &Context) -> Option<String> {
+    let span = cx.span();
+    let span_context = span.span_context();
+    span_context.is_valid().then(|| span_context.trace_id().to_string())
+}
+
+/// W3C `traceparent` of the span active in a context
+pub fn traceparent(cx: &Context) -> Option<String> {
+    let mut carrier = HashMap::new();
+    global::get_text_map_propagator(|propagator| propagator.inject_context(cx, &mut carrier));
+    carrier.remove(TRACEPARENT_FIELD)
+}
+
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(key.as_bytes()), HeaderValue::from_str(&value)) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+/// Add the trace context to the headers of an outgoing request
+pub fn inject_headers(cx: &Context, headers: &mut HeaderMap) {
+    global::get_text_map_propagator(|propagator| propagator.inject_context(cx, &mut HeaderInjector(headers)));
+}
+
+/// Trace context of an incoming request
+pub fn headers_context(headers: &HeaderMap) -> Context {
+    global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)))
+}
+
+/// Middleware running each request in a server span continuing the caller's trace
+///
+/// Handlers reach the span through [`Context::current`].
+pub async fn trace_http(request: Request, next: Next) -> Response {
+    let parent = headers_context(request.headers());
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let cx = start_span_of_kind(
+        format!("{} {}", method, route),
+        SpanKind::Server,
+        &parent,
+        vec![
+            KeyValue::new("http.request.method", method),
+            KeyValue::new("http.route", route),
+            KeyValue::new("url.path", request.uri().path().to_string()),
+        ],
+    );
+    let response = next.run(request).with_context(cx.clone()).await;
+    let status = response.status();
+    cx.span().set_attribute(KeyValue::new("http.response.status_code", status.as_u16() as i64));
+    if status.is_server_error() {
+        record_error(&cx, status);
+    }
+    response
+}
+
+/// Add the trace context of `cx` to an encoded bus message
+///
+/// Only JSON objects carry a context; other payloads are returned unchanged.
+pub(crate) fn inject_message(cx: &Context, mut bytes: Vec<u8>) -> Vec<u8> {
+    let Some(traceparent) = traceparent(cx) else {
+        return bytes;
+    };
+    if bytes.first() != Some(&b'{') {
+        return bytes;
+    }
+    let empty = bytes[1..].iter().all(|byte| byte.is_ascii_whitespace() || *byte == b'}');
+    let field = format!(
+        "{{\"{}\":{}{}",
+        TRACEPARENT_FIELD,
+        serde_json::Value::String(traceparent),
+        if empty { "" } else { "," }
+    );
+    bytes.splice(0..1, field.into_bytes());
+    bytes
+}
+
+#[derive(Deserialize)]
+struct MessageCarrier {
+    traceparent: Option<String>,
+}
+
+/// Trace context carried by a bus message, empty if it carries none
+pub fn message_context(bytes: &[u8]) -> Context {
+    let carrier = serde_json::from_slice::<MessageCarrier>(bytes)
+        .ok()
+        .and_then(|carrier| carrier.traceparent)
+        .map(|traceparent| HashMap::from([(TRACEPARENT_FIELD.to_string(), traceparent)]))
+        .unwrap_or_default();
+    global::get_text_map_propagator(|propagator| propagator.extract(&carrier))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::InMemoryBus;
+    use crate::types::ChainRef;
+    use axum::routing::get;
+    use std::sync::Once;
+
+    fn init() {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| {
+            init_telemetry("sniper-core", Some("")).unwrap();
+        });
+    }
+
+    fn span_ids(cx: &Context) -> (String, String) {
+        let span = cx.span();
+        let span_context = span.span_context();
+        (span_context.trace_id().to_string(), span_context.span_id().to_string())
+    }
+
+    #[test]
+    fn test_context_travels_in_bus_messages() {
+        init();
+        let root = start_span("signal", &Context::new(), vec![KeyValue::new("signal.kind", "pair_created")]);
+        let bus = InMemoryBus::new(16);
+        let mut rx = bus.subscribe("signals.>");
+        let chain = ChainRef {
+            name: "ethereum".to_string(),
+            id: 1,
+        };
+        bus.publish_in("signals.dex.pair_created", &chain, &root).unwrap();
+        bus.publish_in("signals.empty", &serde_json::json!({}), &root).unwrap();
+        bus.publish_now("signals.dex.pair_created", &chain).unwrap();
+
+        let bytes = rx.try_recv().unwrap();
+        let decoded: ChainRef = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(decoded.name, "ethereum");
+        let child = start_span("plan", &message_context(&bytes), Vec::new());
+        let (root_trace, root_span) = span_ids(&root);
+        let (child_trace, child_span) = span_ids(&child);
+        assert_eq!(child_trace, root_trace);
+        assert_ne!(child_span, root_span);
+        assert_eq!(trace_id(&child), Some(root_trace));
+
+        let empty: serde_json::Value = serde_json::from_slice(&rx.try_recv().unwrap()).unwrap();
+        assert_eq!(empty.as_object().unwrap().len(), 1);
+
+        // Messages published without a context start no trace
+        assert!(trace_id(&message_context(&rx.try_recv().unwrap())).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_http_requests_continue_callers_trace() {
+        init();
+        let app = axum::Router::new()
+            .route("/trace", get(|| async { trace_id(&Context::current()).unwrap_or_default() }))
+            .layer(axum::middleware::from_fn(trace_http));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let caller = start_span("caller", &Context::new(), Vec::new());
+        let mut headers = HeaderMap::new();
+        inject_headers(&caller, &mut headers);
+        assert!(headers.contains_key(TRACEPARENT_FIELD));
+        let echoed = reqwest::Client::new()
+            .get(format!("http://{}/trace", addr))
+            .headers(headers)
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(Some(echoed), trace_id(&caller));
+
+        // A request without a trace context starts a new trace
+        let fresh = reqwest::get(format!("http://{}/trace", addr)).await.unwrap().text().await.unwrap();
+        assert_eq!(fresh.len(), 32);
+        assert_ne!(Some(fresh), trace_id(&caller));
+    }
+}
@@ -1,43 +1,47 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub use crate::decimal::Decimal;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ChainRef {
     pub name: String,
     pub id: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Signal {
     pub source: String, // dex|nft|cex|social
     pub kind: String,   // pair_created|trading_enabled|...
     pub chain: ChainRef,
     pub token0: Option<String>,
     pub token1: Option<String>,
+    #[schema(value_type = Object)]
     pub extra: serde_json::Value,
     pub seen_at_ms: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
 pub enum ExecMode {
     Bundle,
     Private,
     Mempool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct GasPolicy {
     pub max_fee_gwei: u64,
     pub max_priority_gwei: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, ToSchema)]
 pub struct ExitRules {
     pub take_profit_pct: Option<f64>,
     pub stop_loss_pct: Option<f64>,
     pub trailing_pct: Option<f64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TradePlan {
     pub chain: ChainRef,
     pub router: String,
@@ -49,15 +53,27 @@ pub struct TradePlan {
     pub gas: GasPolicy,
     pub exits: ExitRules,
     pub idem_key: String,
+    #[serde(default)]
+    pub quote: Option<QuoteStamp>, // when and at which head the route was quoted
+    #[serde(default)]
+    pub correlation_id: Option<String>, // order the plan was made for, linking its audit events
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub struct QuoteStamp {
+    pub quoted_at_ms: i64,
+    pub block: u64,
+    #[serde(default)]
+    pub expected_out: Option<u128>, // output the route was quoted at
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Decision {
     pub allow: bool,
     pub reasons: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ExecReceipt {
     pub tx_hash: String,
     pub success: bool,
@@ -65,4 +81,8 @@ pub struct ExecReceipt {
     pub gas_used: u64,
     pub fees_paid_wei: u128,
     pub failure_reason: Option<String>,
+    #[serde(default)]
+    pub amount_out: Option<u128>, // realized output, when known
+    #[serde(default)]
+    pub endpoint: Option<String>, // RPC endpoint or relay the trade went through
 }
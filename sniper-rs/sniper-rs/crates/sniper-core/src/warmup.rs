@@ -0,0 +1,372 @@
+//! Cold-start warm-up coordination.
+//!
+//! Right after a deploy a service has cold caches, has missed the recent
+//! events and hasn't exercised its quote path, so its first quotes can be
+//! slow or priced off stale data. `WarmupCoordinator` tracks the warm-up
+//! steps - preloading caches (pools, token metadata, models) and replaying
+//! the recent event window from the bus - together with the quote latency
+//! and data freshness seen meanwhile, and only reports the service ready once
+//! every step is done and both meet their thresholds. Readiness latches:
+//! latency spikes after warm-up are for monitoring to catch, not a reason to
+//! stop trading.
+
+use crate::bus::{BusMessage, InMemoryBus};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Quote latency samples the percentile is taken over
+const LATENCY_WINDOW: usize = 100;
+
+/// Thresholds a service must meet before it is ready
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WarmupConfig {
+    /// Highest p95 quote latency accepted
+    pub max_quote_latency_ms: u64,
+    /// Oldest market data accepted
+    pub max_data_age_ms: u64,
+    /// Quotes measured before latency is trusted
+    pub min_quote_samples: usize,
+    /// Recent events replayed from the bus on startup
+    pub replay_window_ms: u64,
+}
+
+impl Default for WarmupConfig {
+    fn default() -> Self {
+        Self {
+            max_quote_latency_ms: 250,
+            max_data_age_ms: 5_000,
+            min_quote_samples: 5,
+            replay_window_ms: 60_000,
+        }
+    }
+}
+
+/// Stage of the warm-up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WarmupPhase {
+    Preloading,
+    Replaying,
+    Verifying,
+    Ready,
+    Failed,
+}
+
+/// Progress of one cache preload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreloadStatus {
+    pub name: String,
+    pub done: bool,
+    /// Entries loaded
+    pub loaded: usize,
+    pub error: Option<String>,
+}
+
+/// Warm-up progress, for readiness endpoints and logs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmupStatus {
+    pub phase: WarmupPhase,
+    pub ready: bool,
+    pub preloads: Vec<PreloadStatus>,
+    pub replayed_events: Option<usize>,
+    pub quote_samples: usize,
+    pub quote_latency_p95_ms: Option<u64>,
+    pub data_age_ms: Option<i64>,
+    /// Work refused while warming up
+    pub held: usize,
+    /// What still keeps the service from being ready
+    pub blockers: Vec<String>,
+}
+
+/// Coordinates a service's warm-up and decides when it is ready
+#[derive(Debug, Clone)]
+pub struct WarmupCoordinator {
+    config: WarmupConfig,
+    preloads: Vec<PreloadStatus>,
+    replayed: Option<usize>,
+    latencies: VecDeque<u64>,
+    quote_samples: usize,
+    last_data_ms: Option<i64>,
+    held: usize,
+    ready_at_ms: Option<i64>,
+}
+
+impl WarmupCoordinator {
+    /// Create a coordinator with no steps done
+    pub fn new(config: WarmupConfig) -> Self {
+        Self {
+            config,
+            preloads: Vec::new(),
+            replayed: None,
+            latencies: VecDeque::new(),
+            quote_samples: 0,
+            last_data_ms: None,
+            held: 0,
+            ready_at_ms: None,
+        }
+    }
+
+    /// Get the thresholds
+    pub fn config(&self) -> &WarmupConfig {
+        &self.config
+    }
+
+    /// Require a preload to finish before the service is ready
+    pub fn expect_preload(&mut self, name: &str) {
+        if !self.preloads.iter().any(|preload| preload.name == name) {
+            self.preloads.push(PreloadStatus {
+                name: name.to_string(),
+                done: false,
+                loaded: 0,
+                error: None,
+            });
+        }
+    }
+
+    /// Record the outcome of a preload; a failed one can be retried
+    pub fn record_preload(&mut self, name: &str, result: Result<usize>) {
+        self.expect_preload(name);
+        if let Some(preload) = self.preloads.iter_mut().find(|preload| preload.name == name) {
+            match result {
+                Ok(loaded) => {
+                    preload.done = true;
+                    preload.loaded = loaded;
+                    preload.error = None;
+                }
+                Err(e) => {
+                    tracing::warn!("warm-up preload {} failed: {}", name, e);
+                    preload.done = false;
+                    preload.error = Some(e.to_string());
+                }
+            }
+        }
+    }
+
+    /// Take the recent event window from the bus for the caller to re-apply
+    pub fn replay(&mut self, bus: &InMemoryBus, now_ms: i64) -> Vec<BusMessage> {
+        let events = bus.history_since(now_ms - self.config.replay_window_ms as i64);
+        self.replayed = Some(events.len());
+        events
+    }
+
+    /// Record a replay done outside the bus, e.g. from a bridge
+    pub fn record_replay(&mut self, events: usize) {
+        self.replayed = Some(events);
+    }
+
+    /// Record how long a quote took
+    pub fn record_quote_latency(&mut self, latency_ms: u64) {
+        if self.latencies.len() == LATENCY_WINDOW {
+            self.latencies.pop_front();
+        }
+        self.latencies.push_back(latency_ms);
+        self.quote_samples += 1;
+    }
+
+    /// Record market data observed at `at_ms`
+    pub fn record_data(&mut self, at_ms: i64) {
+        self.last_data_ms = self.last_data_ms.max(Some(at_ms));
+    }
+
+    /// Note work refused while warming up
+    pub fn record_held(&mut self) {
+        self.held += 1;
+    }
+
+    /// p95 of the recent quote latencies
+    pub fn quote_latency_p95_ms(&self) -> Option<u64> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<u64> = self.latencies.iter().copied().collect();
+        sorted.sort_unstable();
+        let rank = (sorted.len() * 95).div_ceil(100);
+        Some(sorted[rank.max(1) - 1])
+    }
+
+    /// Age of the newest market data
+    pub fn data_age_ms(&self, now_ms: i64) -> Option<i64> {
+        self.last_data_ms.map(|at_ms| (now_ms - at_ms).max(0))
+    }
+
+    /// Current stage of the warm-up
+    pub fn phase(&self) -> WarmupPhase {
+        if self.ready_at_ms.is_some() {
+            WarmupPhase::Ready
+        } else if self.preloads.iter().any(|preload| preload.error.is_some()) {
+            WarmupPhase::Failed
+        } else if self.preloads.iter().any(|preload| !preload.done) {
+            WarmupPhase::Preloading
+        } else if self.replayed.is_none() {
+            WarmupPhase::Replaying
+        } else {
+            WarmupPhase::Verifying
+        }
+    }
+
+    /// What still keeps the service from being ready
+    pub fn blockers(&self, now_ms: i64) -> Vec<String> {
+        if self.ready_at_ms.is_some() {
+            return Vec::new();
+        }
+
+        let mut blockers = Vec::new();
+        for preload in &self.preloads {
+            match &preload.error {
+                Some(error) => blockers.push(format!("preload {} failed: {}", preload.name, error)),
+                None if !preload.done => blockers.push(format!("preload {} pending", preload.name)),
+                None => {}
+            }
+        }
+        if self.replayed.is_none() {
+            blockers.push("event replay pending".to_string());
+        }
+        if self.quote_samples < self.config.min_quote_samples {
+            blockers.push(format!(
+                "{} of {} quote latency samples",
+                self.quote_samples, self.config.min_quote_samples
+            ));
+        } else if let Some(p95) = self.quote_latency_p95_ms().filter(|p95| *p95 > self.config.max_quote_latency_ms) {
+            blockers.push(format!(
+                "quote latency p95 {}ms above {}ms",
+                p95, self.config.max_quote_latency_ms
+            ));
+        }
+        match self.data_age_ms(now_ms) {
+            None => blockers.push("no market data received".to_string()),
+            Some(age) if age > self.config.max_data_age_ms as i64 => blockers.push(format!(
+                "market data {}ms old, above {}ms",
+                age, self.config.max_data_age_ms
+            )),
+            Some(_) => {}
+        }
+        blockers
+    }
+
+    /// Check readiness, marking the service ready once nothing blocks it
+    pub fn check(&mut self, now_ms: i64) -> bool {
+        if self.ready_at_ms.is_none() && self.blockers(now_ms).is_empty() {
+            self.ready_at_ms = Some(now_ms);
+            tracing::info!(
+                quote_latency_p95_ms = ?self.quote_latency_p95_ms(),
+                held = self.held,
+                "warm-up complete"
+            );
+        }
+        self.ready_at_ms.is_some()
+    }
+
+    /// Whether the service has been marked ready
+    pub fn is_ready(&self) -> bool {
+        self.ready_at_ms.is_some()
+    }
+
+    /// Warm-up progress
+    pub fn status(&self, now_ms: i64) -> WarmupStatus {
+        WarmupStatus {
+            phase: self.phase(),
+            ready: self.is_ready(),
+            preloads: self.preloads.clone(),
+            replayed_events: self.replayed,
+            quote_samples: self.quote_samples,
+            quote_latency_p95_ms: self.quote_latency_p95_ms(),
+            data_age_ms: self.data_age_ms(now_ms),
+            held: self.held,
+            blockers: self.blockers(now_ms),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn config() -> WarmupConfig {
+        WarmupConfig {
+            max_quote_latency_ms: 100,
+            max_data_age_ms: 1_000,
+            min_quote_samples: 3,
+            replay_window_ms: 60_000,
+        }
+    }
+
+    #[test]
+    fn test_ready_once_every_step_passes() {
+        let mut warmup = WarmupCoordinator::new(config());
+        warmup.expect_preload("pools");
+        warmup.expect_preload("token_metadata");
+        assert_eq!(warmup.phase(), WarmupPhase::Preloading);
+        assert!(!warmup.check(10_000));
+
+        warmup.record_preload("pools", Ok(1_200));
+        warmup.record_preload("token_metadata", Err(anyhow::anyhow!("metadata store unreachable")));
+        assert_eq!(warmup.phase(), WarmupPhase::Failed);
+        warmup.record_preload("token_metadata", Ok(300));
+        assert_eq!(warmup.phase(), WarmupPhase::Replaying);
+
+        warmup.record_replay(42);
+        assert_eq!(warmup.phase(), WarmupPhase::Verifying);
+        for latency in [20, 30, 40] {
+            warmup.record_quote_latency(latency);
+        }
+        assert_eq!(warmup.blockers(10_000), vec!["no market data received".to_string()]);
+
+        // Stale data holds readiness back until fresh data arrives
+        warmup.record_data(8_000);
+        assert!(!warmup.check(10_000));
+        warmup.record_data(9_500);
+        assert!(warmup.check(10_000));
+        assert_eq!(warmup.phase(), WarmupPhase::Ready);
+
+        // Readiness latches
+        warmup.record_quote_latency(5_000);
+        assert!(warmup.check(60_000));
+        assert!(warmup.status(60_000).blockers.is_empty());
+    }
+
+    #[test]
+    fn test_slow_quotes_block_readiness() {
+        let mut warmup = WarmupCoordinator::new(config());
+        warmup.record_replay(0);
+        warmup.record_data(1_000);
+        for latency in [50, 60, 400] {
+            warmup.record_quote_latency(latency);
+        }
+        assert_eq!(warmup.quote_latency_p95_ms(), Some(400));
+        assert_eq!(warmup.blockers(1_000), vec!["quote latency p95 400ms above 100ms".to_string()]);
+
+        // The percentile forgets the cold quotes once enough fast ones follow
+        for _ in 0..LATENCY_WINDOW {
+            warmup.record_quote_latency(40);
+        }
+        assert_eq!(warmup.quote_latency_p95_ms(), Some(40));
+        assert!(warmup.check(1_000));
+        warmup.record_held();
+        assert_eq!(warmup.status(1_000).held, 1);
+    }
+
+    #[test]
+    fn test_replays_recent_bus_window() {
+        let bus = InMemoryBus::with_history(16, Duration::from_secs(60));
+        bus.publish_now("signals.dex.pair_created", &1).unwrap();
+        bus.publish_now("plan.created", &2).unwrap();
+
+        let mut warmup = WarmupCoordinator::new(config());
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64;
+        let events = warmup.replay(&bus, now);
+        assert_eq!(
+            events.iter().map(|event| event.subject.as_str()).collect::<Vec<_>>(),
+            vec!["signals.dex.pair_created", "plan.created"]
+        );
+        assert_eq!(warmup.status(now).replayed_events, Some(2));
+
+        // Events outside the window are not replayed
+        assert!(bus.history_since(now + 1_000).is_empty());
+        assert!(InMemoryBus::new(16).history_since(0).is_empty());
+    }
+}
@@ -0,0 +1,54 @@
+//! Errors returned by the executor.
+
+use sniper_core::errors::{DomainError, ErrorKind};
+use sniper_core::kill_switch::KillSwitchTripped;
+use sniper_core::types::ExecReceipt;
+use thiserror::Error;
+
+/// Failure of a trade execution
+#[derive(Debug, Error)]
+pub enum ExecError {
+    #[error(transparent)]
+    Halted(#[from] KillSwitchTripped),
+    #[error("Not holding the execution lease: {0}")]
+    Fenced(anyhow::Error),
+    #[error("Pre-flight simulation of {idem_key} failed: {summary}")]
+    SimulationFailed { idem_key: String, summary: String },
+    #[error("Transaction {tx_hash} reverted: {reason}")]
+    Reverted { tx_hash: String, reason: String },
+    #[error(transparent)]
+    Venue(anyhow::Error),
+}
+
+impl ExecError {
+    /// Treat a receipt of a failed transaction as an error
+    pub fn ensure_success(receipt: ExecReceipt) -> Result<ExecReceipt, ExecError> {
+        if receipt.success {
+            return Ok(receipt);
+        }
+        Err(ExecError::Reverted {
+            reason: receipt.failure_reason.unwrap_or_else(|| "unknown reason".to_string()),
+            tx_hash: receipt.tx_hash,
+        })
+    }
+}
+
+/// Venue errors carrying an [`ExecError`], such as a failed simulation
+/// passed through the execution ledger, keep their variant
+impl From<anyhow::Error> for ExecError {
+    fn from(error: anyhow::Error) -> Self {
+        error.downcast::<ExecError>().unwrap_or_else(ExecError::Venue)
+    }
+}
+
+impl DomainError for ExecError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            ExecError::Halted(tripped) => tripped.kind(),
+            ExecError::Fenced(_) => ErrorKind::Unavailable,
+            ExecError::SimulationFailed { .. } => ErrorKind::Invalid,
+            ExecError::Reverted { .. } => ErrorKind::Conflict,
+            ExecError::Venue(_) => ErrorKind::Upstream,
+        }
+    }
+}
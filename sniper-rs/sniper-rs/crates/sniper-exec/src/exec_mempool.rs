@@ -1,59 +1,612 @@
 //! Mempool execution
-//! 
-//! This module provides functionality for submitting transactions
-//! to the public mempool.
+//!
+//! This module submits trades to the public mempool over JSON-RPC. A plan is
+//! turned into a Uniswap V2 style router swap, signed as an EIP-1559
+//! transaction by a [`TxSigner`], broadcast through the provider pool and
+//! followed until it has enough confirmations or the receipt timeout passes.
+//! A trade still pending at the replacement deadline is sped up by resending
+//! it with higher fees under the same nonce. A submission fenced by an
+//! execution lease takes its nonce under the fencing token and checks the
+//! token again right before broadcasting. Token approvals for the router are
+//! expected to be in place already.
 
+use crate::nonce::{NonceManager, NonceReport, PendingTx};
+use crate::{check_fence, ExecError};
 use anyhow::Result;
-use sniper_core::types::{TradePlan, ExecReceipt};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sniper_chain::rpc::{format_quantity, JsonRpcClient, TransactionReceipt};
+use sniper_chain::tx::{parse_address, transaction_hash, Eip1559Transaction};
+use sniper_core::types::{TradePlan, ExecReceipt, GasPolicy};
+use sniper_keys::{format_address, TxSigner};
+use sniper_storage::failover::FencingToken;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// `swapExactTokensForTokens(uint256,uint256,address[],address,uint256)`
+const SWAP_EXACT_TOKENS_FOR_TOKENS: [u8; 4] = [0x38, 0xed, 0x17, 0x39];
+/// `swapExactETHForTokens(uint256,address[],address,uint256)`
+const SWAP_EXACT_ETH_FOR_TOKENS: [u8; 4] = [0x7f, 0xf3, 0x6a, 0xb5];
+/// Pseudo-address commonly used for the native token
+const NATIVE_TOKEN: &str = "0xeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee";
+const WEI_PER_GWEI: u128 = 1_000_000_000;
+/// Gas of a plain transfer, used by cancellations
+const TRANSFER_GAS_LIMIT: u64 = 21_000;
+
+/// Submission and confirmation settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MempoolConfig {
+    /// Blocks, including the inclusion block, before a trade counts as final
+    pub confirmations: u64,
+    /// Time to wait for the confirmations before giving up
+    pub receipt_timeout_ms: u64,
+    /// Interval between receipt polls
+    pub poll_interval_ms: u64,
+    /// Headroom added on top of the estimated gas, in percent
+    pub gas_limit_margin_pct: u64,
+    /// Swap deadline, relative to submission
+    pub deadline_secs: u64,
+    /// Wrapped native token, required to swap from the native token
+    pub weth: Option<String>,
+    /// Pending time after which a trade is sped up; 0 disables replacements
+    pub replacement_deadline_ms: u64,
+    /// Fee increase of each replacement, in percent; nodes require at least 10
+    pub replacement_fee_bump_pct: u64,
+    /// Replacements sent per trade before waiting out the receipt timeout
+    pub max_replacements: u32,
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        Self {
+            confirmations: 1,
+            receipt_timeout_ms: 60_000,
+            poll_interval_ms: 500,
+            gas_limit_margin_pct: 20,
+            deadline_secs: 120,
+            weth: None,
+            replacement_deadline_ms: 15_000,
+            replacement_fee_bump_pct: 15,
+            max_replacements: 3,
+        }
+    }
+}
+
+/// Kind of replacement transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Replacement {
+    /// Resend the same transaction with higher fees
+    SpeedUp,
+    /// Send an empty transfer to self with higher fees, dropping the original
+    Cancel,
+}
+
+/// Signed transaction ready to broadcast
+#[derive(Debug, Clone)]
+pub struct SignedTransaction {
+    pub raw: Vec<u8>,
+    pub hash: String,
+    /// The transaction before signing
+    pub tx: Eip1559Transaction,
+}
 
 /// Mempool executor for submitting transactions to the public mempool
 pub struct MempoolExecutor {
-    // In a real implementation, this would contain connections to RPC endpoints
+    rpc: Arc<JsonRpcClient>,
+    signer: Arc<dyn TxSigner>,
+    config: MempoolConfig,
+    nonces: NonceManager,
+    // Serializes nonce assignment so concurrent submissions never share one
+    nonce_lock: tokio::sync::Mutex<()>,
 }
 
 impl MempoolExecutor {
     /// Create a new mempool executor
-    pub fn new() -> Self {
-        Self {}
-    }
-    
-    /// Submit a trade to the public mempool
-    pub fn submit_to_mempool(&self, _plan: &TradePlan) -> Result<ExecReceipt> {
-        // Placeholder implementation - in a real implementation, this would
-        // submit the transaction to the public mempool
-        Ok(ExecReceipt {
-            tx_hash: "0xmempool-tx".to_string(),
-            success: true,
-            block: 12345678,
-            gas_used: 100000,
-            fees_paid_wei: 2100000000000000, // 0.0021 ETH
-            failure_reason: None,
+    pub fn new(rpc: Arc<JsonRpcClient>, signer: Arc<dyn TxSigner>, config: MempoolConfig) -> Self {
+        Self {
+            rpc,
+            signer,
+            config,
+            nonces: NonceManager::new(),
+            nonce_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Get the submission settings
+    pub fn config(&self) -> &MempoolConfig {
+        &self.config
+    }
+
+    /// Get the JSON-RPC client
+    pub fn rpc(&self) -> &Arc<JsonRpcClient> {
+        &self.rpc
+    }
+
+    /// Address trades are sent from
+    pub fn sender(&self) -> String {
+        format_address(&self.signer.address())
+    }
+
+    /// Router calldata and value of the swap for a plan
+    pub fn swap_calldata(&self, plan: &TradePlan, deadline: u64) -> Result<(Vec<u8>, u128)> {
+        encode_swap(plan, &self.signer.address(), self.config.weth.as_deref(), deadline)
+    }
+
+    /// Submit a trade to the public mempool and wait for it to confirm
+    ///
+    /// A trade still unconfirmed at the receipt timeout is reported as
+    /// failed rather than as an error, since it may yet be mined.
+    pub async fn submit(&self, plan: &TradePlan, fence: Option<&FencingToken>) -> Result<ExecReceipt> {
+        let from = self.sender();
+        let mut tx = self.build_transaction(plan).await?;
+
+        let tx_hash = {
+            let _guard = self.nonce_lock.lock().await;
+            tx.nonce = self.next_nonce(&from, fence).await?;
+            let sent = match self.sign(&tx).await {
+                Ok(raw) => self.send(&raw, fence).await,
+                Err(e) => Err(e),
+            };
+            match sent {
+                Ok(tx_hash) => tx_hash,
+                Err(e) => {
+                    self.nonces.release_nonce(&from, tx.nonce).await?;
+                    return Err(e);
+                }
+            }
+        };
+        tracing::info!("submitted {} as {} with nonce {}", plan.idem_key, tx_hash, tx.nonce);
+        let nonce = tx.nonce;
+        self.track(&from, tx, &tx_hash, 0).await?;
+        self.confirm_pending(nonce, tx_hash).await
+    }
+
+    /// Sign the swap for a plan without broadcasting it
+    ///
+    /// The nonce is taken as for a mempool submission, so a transaction that
+    /// was packaged into a bundle can still go out with [`Self::submit_signed`].
+    pub async fn sign_swap(&self, plan: &TradePlan, fence: Option<&FencingToken>) -> Result<SignedTransaction> {
+        let from = self.sender();
+        let mut tx = self.build_transaction(plan).await?;
+
+        let _guard = self.nonce_lock.lock().await;
+        tx.nonce = self.next_nonce(&from, fence).await?;
+        match self.sign(&tx).await {
+            Ok(raw) => Ok(SignedTransaction {
+                hash: format!("0x{}", hex::encode(transaction_hash(&raw))),
+                raw,
+                tx,
+            }),
+            Err(e) => {
+                self.nonces.release_nonce(&from, tx.nonce).await?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Broadcast a signed transaction to the public mempool and wait for it to confirm
+    pub async fn submit_signed(&self, signed: &SignedTransaction, fence: Option<&FencingToken>) -> Result<ExecReceipt> {
+        let tx_hash = self.send(&signed.raw, fence).await?;
+        tracing::info!("broadcast {} with nonce {}", tx_hash, signed.tx.nonce);
+        self.track(&self.sender(), signed.tx.clone(), &tx_hash, 0).await?;
+        self.confirm_pending(signed.tx.nonce, tx_hash).await
+    }
+
+    /// Give back the nonce of a signed transaction that was never sent
+    pub async fn release_nonce(&self, nonce: u64) -> Result<()> {
+        self.nonces.release_nonce(&self.sender(), nonce).await
+    }
+
+    /// Replace the pending transaction of a nonce, returning the replacement's hash
+    ///
+    /// Both fees are raised by `replacement_fee_bump_pct`, since nodes only
+    /// accept a replacement that outbids the original. The replacement is
+    /// tracked in place of the original.
+    pub async fn replace(&self, nonce: u64, replacement: Replacement) -> Result<String> {
+        let from = self.sender();
+        let pending = self
+            .nonces
+            .pending_tx(&from, nonce)
+            .await
+            .ok_or_else(|| anyhow::anyhow!("No pending transaction with nonce {}", nonce))?;
+
+        let mut tx = match replacement {
+            Replacement::SpeedUp => pending.tx.clone(),
+            Replacement::Cancel => Eip1559Transaction {
+                gas_limit: TRANSFER_GAS_LIMIT,
+                to: self.signer.address(),
+                value: 0,
+                data: Vec::new(),
+                ..pending.tx.clone()
+            },
+        };
+        let bump = self.config.replacement_fee_bump_pct;
+        tx.max_fee_per_gas = bump_fee(pending.tx.max_fee_per_gas, bump);
+        tx.max_priority_fee_per_gas = bump_fee(pending.tx.max_priority_fee_per_gas, bump);
+
+        let tx_hash = self.send(&self.sign(&tx).await?, None).await?;
+        tracing::info!("replaced {} with {} ({:?}, nonce {})", pending.tx_hash, tx_hash, replacement, nonce);
+        self.track(&from, tx, &tx_hash, pending.replacements + 1).await?;
+        Ok(tx_hash)
+    }
+
+    /// Fill a nonce gap with an empty transfer to self so later transactions can be mined
+    pub async fn fill_gap(&self, nonce: u64, gas: &GasPolicy) -> Result<String> {
+        let from = self.sender();
+        let tx = Eip1559Transaction {
+            chain_id: self.rpc.chain_id().await?,
+            nonce,
+            max_priority_fee_per_gas: gas.max_priority_gwei as u128 * WEI_PER_GWEI,
+            max_fee_per_gas: gas.max_fee_gwei as u128 * WEI_PER_GWEI,
+            gas_limit: TRANSFER_GAS_LIMIT,
+            to: self.signer.address(),
+            value: 0,
+            data: Vec::new(),
+        };
+        let tx_hash = self.send(&self.sign(&tx).await?, None).await?;
+        tracing::info!("filled nonce gap {} with {}", nonce, tx_hash);
+        self.track(&from, tx, &tx_hash, 0).await?;
+        Ok(tx_hash)
+    }
+
+    /// Reconcile the sender's nonces with the chain
+    ///
+    /// Reports the nonce gaps and the transactions pending past the
+    /// replacement deadline.
+    pub async fn nonce_report(&self) -> Result<NonceReport> {
+        let from = self.sender();
+        let mined = self.rpc.mined_transaction_count(&from).await?;
+        let stuck_after = Duration::from_millis(self.config.replacement_deadline_ms);
+        self.nonces.reconcile(&from, mined, stuck_after).await
+    }
+
+    /// Wait for a sent transaction to get the configured confirmations
+    ///
+    /// A transaction still unconfirmed at the receipt timeout is reported as
+    /// failed rather than as an error, since it may yet be mined.
+    pub async fn confirm(&self, tx_hash: String) -> Result<ExecReceipt> {
+        let timeout = Duration::from_millis(self.config.receipt_timeout_ms);
+        match tokio::time::timeout(timeout, self.wait_for_confirmations(&tx_hash)).await {
+            Ok(receipt) => to_exec_receipt(tx_hash, &receipt),
+            Err(_) => Ok(self.unconfirmed(tx_hash)),
+        }
+    }
+
+    /// Wait for a tracked nonce to confirm, speeding up its transaction
+    /// whenever it has been pending past the replacement deadline
+    async fn confirm_pending(&self, nonce: u64, tx_hash: String) -> Result<ExecReceipt> {
+        let from = self.sender();
+        let timeout = Duration::from_millis(self.config.receipt_timeout_ms);
+        match tokio::time::timeout(timeout, self.wait_for_nonce(&from, nonce, tx_hash.clone())).await {
+            Ok((tx_hash, receipt)) => {
+                self.nonces.confirm_mined(&from, nonce + 1).await?;
+                to_exec_receipt(tx_hash, &receipt)
+            }
+            Err(_) => {
+                let latest = self.nonces.pending_tx(&from, nonce).await.map(|pending| pending.tx_hash);
+                Ok(self.unconfirmed(latest.unwrap_or(tx_hash)))
+            }
+        }
+    }
+
+    fn unconfirmed(&self, tx_hash: String) -> ExecReceipt {
+        tracing::warn!("{} not confirmed within {} ms", tx_hash, self.config.receipt_timeout_ms);
+        ExecReceipt {
+            tx_hash,
+            success: false,
+            block: 0,
+            gas_used: 0,
+            fees_paid_wei: 0,
+            failure_reason: Some(format!("Not confirmed within {} ms", self.config.receipt_timeout_ms)),
+            amount_out: None,
+            endpoint: None,
+        }
+    }
+
+    /// Unsigned swap transaction for a plan, with its gas estimated
+    async fn build_transaction(&self, plan: &TradePlan) -> Result<Eip1559Transaction> {
+        let from = self.sender();
+        let deadline = now_secs() + self.config.deadline_secs;
+        let (data, value) = self.swap_calldata(plan, deadline)?;
+
+        let estimated = self
+            .rpc
+            .estimate_gas(json!({
+                "from": from,
+                "to": plan.router,
+                "value": format_quantity(value),
+                "data": format!("0x{}", hex::encode(&data)),
+            }))
+            .await?;
+        let gas_limit = estimated + estimated * self.config.gas_limit_margin_pct / 100;
+
+        Ok(Eip1559Transaction {
+            chain_id: plan.chain.id,
+            nonce: 0,
+            max_priority_fee_per_gas: plan.gas.max_priority_gwei as u128 * WEI_PER_GWEI,
+            max_fee_per_gas: plan.gas.max_fee_gwei as u128 * WEI_PER_GWEI,
+            gas_limit,
+            to: parse_address(&plan.router)?,
+            value,
+            data,
         })
     }
+
+    /// Next nonce, never behind the chain's pending transaction count
+    async fn next_nonce(&self, from: &str, fence: Option<&FencingToken>) -> Result<u64> {
+        if let Some(fence) = fence {
+            self.nonces.admit_fence(from, fence.token()).await.map_err(ExecError::Fenced)?;
+        }
+        let pending = self.rpc.transaction_count(from).await?;
+        if pending > self.nonces.get_current_nonce(from).await? {
+            self.nonces.reset_nonce(from, pending).await?;
+        }
+        self.nonces.get_next_nonce(from).await
+    }
+
+    async fn track(&self, from: &str, tx: Eip1559Transaction, tx_hash: &str, replacements: u32) -> Result<()> {
+        let pending = PendingTx {
+            nonce: tx.nonce,
+            tx,
+            tx_hash: tx_hash.to_string(),
+            submitted_at: Instant::now(),
+            replacements,
+        };
+        self.nonces.track_pending(from, pending).await
+    }
+
+    async fn sign(&self, tx: &Eip1559Transaction) -> Result<Vec<u8>> {
+        let signature = self.signer.sign_hash(tx.signing_hash()).await?;
+        Ok(tx.encode_signed(signature.y_parity, &signature.r, &signature.s))
+    }
+
+    async fn send(&self, raw: &[u8], fence: Option<&FencingToken>) -> Result<String> {
+        check_fence(fence).await?;
+        let tx_hash = self.rpc.send_raw_transaction(raw).await?;
+
+        let expected = format!("0x{}", hex::encode(transaction_hash(raw)));
+        if !tx_hash.eq_ignore_ascii_case(&expected) {
+            tracing::warn!("node reported hash {} for transaction {}", tx_hash, expected);
+        }
+        Ok(tx_hash)
+    }
+
+    /// Poll until the transaction has the configured confirmations
+    ///
+    /// Polling errors are logged and retried: the transaction is already out.
+    async fn wait_for_confirmations(&self, tx_hash: &str) -> TransactionReceipt {
+        let mut interval = tokio::time::interval(Duration::from_millis(self.config.poll_interval_ms.max(1)));
+        loop {
+            interval.tick().await;
+            match self.confirmed_receipt(tx_hash).await {
+                Ok(Some(receipt)) => return receipt,
+                Ok(None) => {}
+                Err(e) => tracing::warn!("polling receipt of {} failed: {}", tx_hash, e),
+            }
+        }
+    }
+
+    /// Poll every transaction sent for a nonce until one has the configured
+    /// confirmations, returning its hash and receipt
+    async fn wait_for_nonce(&self, from: &str, nonce: u64, tx_hash: String) -> (String, TransactionReceipt) {
+        let deadline = Duration::from_millis(self.config.replacement_deadline_ms);
+        let mut hashes = vec![tx_hash];
+        let mut interval = tokio::time::interval(Duration::from_millis(self.config.poll_interval_ms.max(1)));
+        loop {
+            interval.tick().await;
+            // Pick up replacements sent through `replace` as well
+            let pending = self.nonces.pending_tx(from, nonce).await;
+            if let Some(pending) = &pending {
+                if !hashes.contains(&pending.tx_hash) {
+                    hashes.push(pending.tx_hash.clone());
+                }
+            }
+            for hash in hashes.iter().rev() {
+                match self.confirmed_receipt(hash).await {
+                    Ok(Some(receipt)) => return (hash.clone(), receipt),
+                    Ok(None) => {}
+                    Err(e) => tracing::warn!("polling receipt of {} failed: {}", hash, e),
+                }
+            }
+
+            let Some(pending) = pending else { continue };
+            let due = !deadline.is_zero()
+                && pending.replacements < self.config.max_replacements
+                && pending.submitted_at.elapsed() >= deadline;
+            if due {
+                match self.replace(nonce, Replacement::SpeedUp).await {
+                    Ok(hash) => hashes.push(hash),
+                    Err(e) => tracing::warn!("speeding up nonce {} failed: {}", nonce, e),
+                }
+            }
+        }
+    }
+
+    async fn confirmed_receipt(&self, tx_hash: &str) -> Result<Option<TransactionReceipt>> {
+        let Some(receipt) = self.rpc.transaction_receipt(tx_hash).await? else {
+            return Ok(None);
+        };
+        let head = self.rpc.block_number().await?;
+        let confirmations = (head + 1).saturating_sub(receipt.block()?);
+        Ok((confirmations >= self.config.confirmations).then_some(receipt))
+    }
+}
+
+fn to_exec_receipt(tx_hash: String, receipt: &TransactionReceipt) -> Result<ExecReceipt> {
+    let success = receipt.succeeded();
+    Ok(ExecReceipt {
+        tx_hash,
+        success,
+        block: receipt.block()?,
+        gas_used: receipt.gas_used()?,
+        fees_paid_wei: receipt.fees_paid_wei()?,
+        failure_reason: (!success).then(|| "Transaction reverted".to_string()),
+        amount_out: None,
+        endpoint: None,
+    })
+}
+
+/// Fee raised by a percentage, and by at least one wei
+fn bump_fee(fee: u128, pct: u64) -> u128 {
+    (fee + fee * pct as u128 / 100).max(fee + 1)
+}
+
+/// Router calldata and value of a Uniswap V2 style swap for a plan, paying out to `to`
+pub(crate) fn encode_swap(
+    plan: &TradePlan,
+    to: &[u8; 20],
+    weth: Option<&str>,
+    deadline: u64,
+) -> Result<(Vec<u8>, u128)> {
+    let token_out = parse_address(&plan.token_out)?;
+
+    if is_native(&plan.token_in) {
+        let weth = weth.ok_or_else(|| anyhow::anyhow!("Swapping from the native token needs a WETH address"))?;
+        let data = encode_call(
+            SWAP_EXACT_ETH_FOR_TOKENS,
+            &[
+                uint_word(plan.min_out),
+                // Offset of the path array: four head words
+                uint_word(0x80),
+                address_word(to),
+                uint_word(deadline as u128),
+                uint_word(2),
+                address_word(&parse_address(weth)?),
+                address_word(&token_out),
+            ],
+        );
+        return Ok((data, plan.amount_in));
+    }
+
+    let data = encode_call(
+        SWAP_EXACT_TOKENS_FOR_TOKENS,
+        &[
+            uint_word(plan.amount_in),
+            uint_word(plan.min_out),
+            // Offset of the path array: five head words
+            uint_word(0xa0),
+            address_word(to),
+            uint_word(deadline as u128),
+            uint_word(2),
+            address_word(&parse_address(&plan.token_in)?),
+            address_word(&token_out),
+        ],
+    );
+    Ok((data, 0))
+}
+
+pub(crate) fn is_native(token: &str) -> bool {
+    token.eq_ignore_ascii_case("ETH") || token.eq_ignore_ascii_case(NATIVE_TOKEN)
+}
+
+pub(crate) fn encode_call(selector: [u8; 4], words: &[[u8; 32]]) -> Vec<u8> {
+    let mut data = selector.to_vec();
+    for word in words {
+        data.extend_from_slice(word);
+    }
+    data
+}
+
+pub(crate) fn uint_word(value: u128) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[16..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+pub(crate) fn address_word(address: &[u8; 20]) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[12..].copy_from_slice(address);
+    word
+}
+
+pub(crate) fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use axum::{routing::post, Extension, Json, Router};
+    use serde_json::Value;
+    use sniper_chain::{HedgeConfig, ProviderPool, RpcEndpoint};
     use sniper_core::types::{ChainRef, ExecMode, GasPolicy, ExitRules};
+    use sniper_keys::LocalSigner;
+    use std::sync::Mutex;
 
-    #[test]
-    fn test_mempool_executor_creation() {
-        let _executor = MempoolExecutor::new();
-        assert!(true); // Just testing that we can create an executor
+    const KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+    const ROUTER: &str = "0x7a250d5630b4cf539739df2c5dacb4c659f2488d";
+    const TOKEN_IN: &str = "0x1111111111111111111111111111111111111111";
+    const TOKEN_OUT: &str = "0x2222222222222222222222222222222222222222";
+
+    /// Node that mines the latest transaction in block 0x10
+    #[derive(Default)]
+    struct MockNode {
+        mined: bool,
+        /// Transactions to receive before mining the latest one
+        mine_after: usize,
+        head: u64,
+        raw_transactions: Mutex<Vec<String>>,
     }
-    
-    #[test]
-    fn test_submit_to_mempool() {
-        let executor = MempoolExecutor::new();
-        let plan = TradePlan {
+
+    fn raw_hash(raw: &str) -> String {
+        format!("0x{}", hex::encode(transaction_hash(&hex::decode(raw.trim_start_matches("0x")).unwrap())))
+    }
+
+    fn mined(node: &MockNode, tx_hash: &str) -> bool {
+        let raw = node.raw_transactions.lock().unwrap();
+        node.mined && raw.len() >= node.mine_after && raw.last().map(|last| raw_hash(last)).as_deref() == Some(tx_hash)
+    }
+
+    async fn handle(Extension(node): Extension<Arc<MockNode>>, Json(request): Json<Value>) -> Json<Value> {
+        let result = match request["method"].as_str().unwrap() {
+            "eth_estimateGas" => json!("0x30d40"),
+            "eth_chainId" => json!("0x1"),
+            "eth_getTransactionCount" => json!("0x3"),
+            "eth_blockNumber" => json!(format_quantity(node.head as u128)),
+            "eth_sendRawTransaction" => {
+                let raw = request["params"][0].as_str().unwrap().to_string();
+                let hash = raw_hash(&raw);
+                node.raw_transactions.lock().unwrap().push(raw);
+                json!(hash)
+            }
+            "eth_getTransactionReceipt" if mined(&node, request["params"][0].as_str().unwrap()) => json!({
+                "transactionHash": request["params"][0],
+                "blockNumber": "0x10",
+                "status": "0x1",
+                "gasUsed": "0x249f0",
+                "effectiveGasPrice": "0x3b9aca00",
+            }),
+            "eth_getTransactionReceipt" => Value::Null,
+            method => return Json(json!({"jsonrpc": "2.0", "id": request["id"], "error": {"code": -32601, "message": method}})),
+        };
+        Json(json!({"jsonrpc": "2.0", "id": request["id"], "result": result}))
+    }
+
+    async fn executor(node: Arc<MockNode>, config: MempoolConfig) -> MempoolExecutor {
+        let app = Router::new().route("/", post(handle)).layer(Extension(node));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let pool = ProviderPool::new(vec![RpcEndpoint { name: "mock".to_string(), url }], HedgeConfig::default());
+        let rpc = Arc::new(JsonRpcClient::new(Arc::new(pool)).unwrap());
+        let signer = Arc::new(LocalSigner::from_hex(KEY).unwrap());
+        MempoolExecutor::new(rpc, signer, config)
+    }
+
+    fn plan(token_in: &str) -> TradePlan {
+        TradePlan {
             chain: ChainRef {
                 name: "ethereum".to_string(),
                 id: 1,
             },
-            router: "0xRouter".to_string(),
-            token_in: "0xTokenIn".to_string(),
-            token_out: "0xTokenOut".to_string(),
+            router: ROUTER.to_string(),
+            token_in: token_in.to_string(),
+            token_out: TOKEN_OUT.to_string(),
             amount_in: 1000000000000000000, // 1 ETH
             min_out: 900000000000000000,    // 0.9 ETH worth of tokens
             mode: ExecMode::Mempool,
@@ -67,10 +620,205 @@ mod tests {
                 trailing_pct: Some(2.0),
             },
             idem_key: "mempool-test-key".to_string(),
+            quote: None,
+            correlation_id: None,
+        }
+    }
+
+    fn config() -> MempoolConfig {
+        MempoolConfig {
+            poll_interval_ms: 10,
+            weth: Some("0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2".to_string()),
+            ..MempoolConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_swap_calldata() {
+        let executor = executor(Arc::new(MockNode::default()), config()).await;
+        let sender = executor.sender();
+        assert_eq!(sender, "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23");
+
+        let (data, value) = executor.swap_calldata(&plan(TOKEN_IN), 1_700_000_000).unwrap();
+        assert_eq!(value, 0);
+        assert_eq!(data.len(), 4 + 8 * 32);
+        assert_eq!(data[..4], SWAP_EXACT_TOKENS_FOR_TOKENS);
+        assert_eq!(data[4..36], uint_word(1000000000000000000));
+        assert_eq!(data[68..100], uint_word(0xa0));
+        assert_eq!(hex::encode(&data[112..132]), sender.trim_start_matches("0x"));
+        assert_eq!(hex::encode(&data[208..228]), TOKEN_IN.trim_start_matches("0x"));
+        assert_eq!(hex::encode(&data[240..260]), TOKEN_OUT.trim_start_matches("0x"));
+
+        let (data, value) = executor.swap_calldata(&plan("ETH"), 1_700_000_000).unwrap();
+        assert_eq!(value, 1000000000000000000);
+        assert_eq!(data.len(), 4 + 7 * 32);
+        assert_eq!(data[..4], SWAP_EXACT_ETH_FOR_TOKENS);
+        assert_eq!(data[4..36], uint_word(900000000000000000));
+        assert_eq!(data[36..68], uint_word(0x80));
+
+        let no_weth = MempoolExecutor::new(executor.rpc.clone(), executor.signer.clone(), MempoolConfig::default());
+        assert!(no_weth.swap_calldata(&plan("ETH"), 0).is_err());
+        assert!(executor.swap_calldata(&plan("0xTokenIn"), 0).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_submit_waits_for_confirmations() {
+        let node = Arc::new(MockNode {
+            mined: true,
+            head: 0x11,
+            ..MockNode::default()
+        });
+        let executor = executor(
+            node.clone(),
+            MempoolConfig {
+                confirmations: 2,
+                ..config()
+            },
+        )
+        .await;
+
+        let receipt = executor.submit(&plan(TOKEN_IN), None).await.unwrap();
+        assert!(receipt.success);
+        assert_eq!(receipt.block, 0x10);
+        assert_eq!(receipt.gas_used, 150_000);
+        assert_eq!(receipt.fees_paid_wei, 150_000 * WEI_PER_GWEI);
+
+        let raw = node.raw_transactions.lock().unwrap().clone();
+        assert_eq!(raw.len(), 1);
+        let expected = format!("0x{}", hex::encode(transaction_hash(&hex::decode(&raw[0][2..]).unwrap())));
+        assert_eq!(receipt.tx_hash, expected);
+        // The chain reported three pending transactions
+        assert_eq!(executor.nonces.get_current_nonce(&executor.sender()).await.unwrap(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_stale_fence_sends_nothing() {
+        use sniper_storage::failover::{FailoverConfig, FailoverCoordinator, InMemoryLeaseStore, LeaseStore};
+
+        let node = Arc::new(MockNode {
+            mined: true,
+            head: 0x10,
+            ..MockNode::default()
+        });
+        let executor = executor(node.clone(), config()).await;
+        let store: Arc<dyn LeaseStore> = Arc::new(InMemoryLeaseStore::new());
+        let coordinator = |region: &str| {
+            FailoverCoordinator::new(FailoverConfig::new(region, "main", Duration::from_millis(20)), store.clone())
         };
-        
-        let receipt = executor.submit_to_mempool(&plan).unwrap();
-        assert_eq!(receipt.tx_hash, "0xmempool-tx");
+        let (mut primary, mut standby) = (coordinator("us-east-1"), coordinator("eu-west-1"));
+
+        primary.heartbeat().await.unwrap();
+        let fence = primary.submission_fence().await.unwrap();
+        assert!(executor.submit(&plan(TOKEN_IN), Some(&fence)).await.unwrap().success);
+
+        // The standby takes over while the old primary still holds its token
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        standby.heartbeat().await.unwrap();
+        let error = executor.submit(&plan(TOKEN_IN), Some(&fence)).await.unwrap_err();
+        assert!(matches!(error.downcast_ref::<ExecError>(), Some(ExecError::Fenced(_))));
+        assert_eq!(node.raw_transactions.lock().unwrap().len(), 1);
+        assert_eq!(executor.nonces.get_current_nonce(&executor.sender()).await.unwrap(), 4);
+
+        // Nonces are never handed out again under the superseded token
+        let successor = standby.submission_fence().await.unwrap();
+        executor.nonces.admit_fence(&executor.sender(), successor.token()).await.unwrap();
+        assert!(executor.sign_swap(&plan(TOKEN_IN), Some(&fence)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_submit_times_out_unconfirmed() {
+        let node = Arc::new(MockNode {
+            mined: true,
+            head: 0x10,
+            ..MockNode::default()
+        });
+        let executor = executor(
+            node,
+            MempoolConfig {
+                confirmations: 3,
+                receipt_timeout_ms: 100,
+                ..config()
+            },
+        )
+        .await;
+
+        let receipt = executor.submit(&plan(TOKEN_IN), None).await.unwrap();
+        assert!(!receipt.success);
+        assert!(receipt.tx_hash.starts_with("0x"));
+        assert_eq!(receipt.failure_reason.unwrap(), "Not confirmed within 100 ms");
+    }
+
+    #[tokio::test]
+    async fn test_stuck_transaction_is_sped_up() {
+        let node = Arc::new(MockNode {
+            mined: true,
+            mine_after: 2,
+            head: 0x10,
+            ..MockNode::default()
+        });
+        let executor = executor(
+            node.clone(),
+            MempoolConfig {
+                replacement_deadline_ms: 50,
+                ..config()
+            },
+        )
+        .await;
+
+        let receipt = executor.submit(&plan(TOKEN_IN), None).await.unwrap();
         assert!(receipt.success);
+        let raw = node.raw_transactions.lock().unwrap().clone();
+        assert_eq!(raw.len(), 2);
+        assert_eq!(receipt.tx_hash, raw_hash(&raw[1]));
+        assert!(executor.nonces.pending(&executor.sender()).await.is_empty());
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_replace_and_fill_gap() {
+        let node = Arc::new(MockNode::default());
+        let executor = executor(
+            node.clone(),
+            MempoolConfig {
+                receipt_timeout_ms: 100,
+                replacement_deadline_ms: 0,
+                ..config()
+            },
+        )
+        .await;
+        let sender = executor.sender();
+
+        let receipt = executor.submit(&plan(TOKEN_IN), None).await.unwrap();
+        assert!(!receipt.success);
+        let original = executor.nonces.pending_tx(&sender, 3).await.unwrap();
+        assert_eq!(original.tx_hash, receipt.tx_hash);
+        assert_eq!(original.tx.max_fee_per_gas, 50 * WEI_PER_GWEI);
+
+        let sped_up = executor.replace(3, Replacement::SpeedUp).await.unwrap();
+        let pending = executor.nonces.pending_tx(&sender, 3).await.unwrap();
+        assert_eq!(pending.tx_hash, sped_up);
+        assert_eq!(pending.replacements, 1);
+        assert_eq!(pending.tx.max_fee_per_gas, 57_500_000_000);
+        assert_eq!(pending.tx.max_priority_fee_per_gas, 2_300_000_000);
+        assert_eq!(pending.tx.data, original.tx.data);
+
+        executor.replace(3, Replacement::Cancel).await.unwrap();
+        let pending = executor.nonces.pending_tx(&sender, 3).await.unwrap();
+        assert_eq!(pending.replacements, 2);
+        assert_eq!(pending.tx.to, executor.signer.address());
+        assert_eq!((pending.tx.value, pending.tx.gas_limit), (0, TRANSFER_GAS_LIMIT));
+        assert!(pending.tx.data.is_empty());
+        assert_eq!(pending.tx.max_fee_per_gas, 66_125_000_000);
+        assert!(executor.replace(7, Replacement::Cancel).await.is_err());
+
+        // A nonce handed out and never sent blocks every later one until filled
+        assert_eq!(executor.nonces.get_next_nonce(&sender).await.unwrap(), 4);
+        let report = executor.nonce_report().await.unwrap();
+        assert_eq!((report.mined, report.next), (3, 5));
+        assert_eq!(report.gaps, vec![4]);
+        assert_eq!(report.stuck.len(), 1);
+
+        executor.fill_gap(4, &plan(TOKEN_IN).gas).await.unwrap();
+        assert!(executor.nonce_report().await.unwrap().gaps.is_empty());
+        assert_eq!(node.raw_transactions.lock().unwrap().len(), 4);
+    }
+}
@@ -1,59 +1,477 @@
 //! MEV bundle execution
-//! 
-//! This module provides functionality for submitting transactions
-//! as MEV bundles to Flashbots or similar relays.
+//!
+//! This module submits trades as bundles to block builder relays. The plan's
+//! swap is signed by the [`MempoolExecutor`], packaged into a [`Bundle`] and
+//! sent to every configured relay for the next block. Bundles only land in
+//! the block they target, so the bundle is resubmitted for each following
+//! block until its transaction is mined. After `max_missed_blocks` blocks
+//! without inclusion the signed transaction is broadcast to the public
+//! mempool instead. A fenced submission checks its fencing token before
+//! every send, relay or mempool.
 
+use crate::check_fence;
+use crate::exec_mempool::{MempoolExecutor, SignedTransaction};
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sniper_chain::rpc::format_quantity;
 use sniper_core::types::{TradePlan, ExecReceipt};
+use sniper_keys::{format_address, keccak256, TxSigner};
+use sniper_storage::failover::FencingToken;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Timeout of a single bundle submission to a relay
+const RELAY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Relay API flavour
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RelayKind {
+    /// `eth_sendBundle`, authenticated with an `X-Flashbots-Signature` header
+    Flashbots,
+    /// `blxr_submit_bundle`, authenticated with an `Authorization` header
+    Bloxroute,
+}
+
+/// Relay bundles are sent to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayEndpoint {
+    pub name: String,
+    pub url: String,
+    pub kind: RelayKind,
+    /// Environment variable holding the `Authorization` header of bloXroute style relays
+    #[serde(default)]
+    pub auth_env: Option<String>,
+}
+
+/// Relay and inclusion settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MevBundleConfig {
+    pub relays: Vec<RelayEndpoint>,
+    /// Target blocks missed before giving up on the relays
+    pub max_missed_blocks: u64,
+    /// Broadcast the transaction publicly once the bundle is given up on
+    pub fallback_to_mempool: bool,
+    /// Interval between block number polls
+    pub block_poll_interval_ms: u64,
+    /// Time to wait for a target block before failing
+    pub block_timeout_ms: u64,
+}
+
+impl Default for MevBundleConfig {
+    fn default() -> Self {
+        Self {
+            relays: Vec::new(),
+            max_missed_blocks: 3,
+            fallback_to_mempool: true,
+            block_poll_interval_ms: 1_000,
+            block_timeout_ms: 30_000,
+        }
+    }
+}
+
+/// Signed transactions to be included together, in order, in one block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bundle {
+    pub transactions: Vec<Vec<u8>>,
+    pub block_number: u64,
+}
+
+impl Bundle {
+    /// JSON-RPC method and parameters submitting the bundle to a relay
+    pub fn request(&self, kind: RelayKind) -> (&'static str, Value) {
+        let block_number = format_quantity(self.block_number as u128);
+        match kind {
+            RelayKind::Flashbots => {
+                let txs: Vec<String> = self.transactions.iter().map(|raw| format!("0x{}", hex::encode(raw))).collect();
+                ("eth_sendBundle", json!([{ "txs": txs, "blockNumber": block_number }]))
+            }
+            RelayKind::Bloxroute => {
+                let txs: Vec<String> = self.transactions.iter().map(hex::encode).collect();
+                ("blxr_submit_bundle", json!({ "transaction": txs, "block_number": block_number }))
+            }
+        }
+    }
+}
+
+/// Builder packaging signed transactions into a bundle
+#[derive(Debug, Clone, Default)]
+pub struct BundleBuilder {
+    transactions: Vec<Vec<u8>>,
+}
+
+impl BundleBuilder {
+    /// Create an empty bundle builder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a raw signed transaction
+    pub fn with_transaction(mut self, raw: Vec<u8>) -> Self {
+        self.transactions.push(raw);
+        self
+    }
+
+    /// Build the bundle for a target block
+    pub fn build(self, block_number: u64) -> Result<Bundle> {
+        if self.transactions.is_empty() {
+            return Err(anyhow::anyhow!("Bundle has no transactions"));
+        }
+        Ok(Bundle {
+            transactions: self.transactions,
+            block_number,
+        })
+    }
+}
+
+/// Bundle submission counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleStats {
+    /// Bundles sent, one per target block
+    pub submitted: u64,
+    pub included: u64,
+    pub missed_blocks: u64,
+    pub fallbacks: u64,
+}
 
 /// MEV bundle executor for submitting transactions as bundles
 pub struct MevBundleExecutor {
-    // In a real implementation, this would contain connections to MEV relays
+    http: reqwest::Client,
+    mempool: Arc<MempoolExecutor>,
+    // Signs the Flashbots header; a reputation key, not the trading key
+    auth_signer: Arc<dyn TxSigner>,
+    config: MevBundleConfig,
+    stats: Mutex<BundleStats>,
 }
 
 impl MevBundleExecutor {
     /// Create a new MEV bundle executor
-    pub fn new() -> Self {
-        Self {}
-    }
-    
-    /// Submit a trade as an MEV bundle
-    pub fn submit_mev_bundle(&self, _plan: &TradePlan) -> Result<ExecReceipt> {
-        // Placeholder implementation - in a real implementation, this would
-        // submit the transaction as a bundle to MEV relays
-        Ok(ExecReceipt {
-            tx_hash: "0xmev-bundle-tx".to_string(),
-            success: true,
-            block: 12345678,
-            gas_used: 100000,
-            fees_paid_wei: 2100000000000000, // 0.0021 ETH
-            failure_reason: None,
+    pub fn new(mempool: Arc<MempoolExecutor>, auth_signer: Arc<dyn TxSigner>, config: MevBundleConfig) -> Result<Self> {
+        if config.relays.is_empty() {
+            return Err(anyhow::anyhow!("MEV bundle executor needs at least one relay"));
+        }
+        let http = reqwest::Client::builder().timeout(RELAY_TIMEOUT).build()?;
+        Ok(Self {
+            http,
+            mempool,
+            auth_signer,
+            config,
+            stats: Mutex::new(BundleStats::default()),
         })
     }
+
+    /// Get the relay and inclusion settings
+    pub fn config(&self) -> &MevBundleConfig {
+        &self.config
+    }
+
+    /// Get the submission counters
+    pub fn stats(&self) -> BundleStats {
+        *self.stats.lock().unwrap()
+    }
+
+    /// Submit a trade as an MEV bundle and wait for it to confirm
+    ///
+    /// Falls back to the public mempool after `max_missed_blocks` target
+    /// blocks without inclusion, or reports the trade as failed if the
+    /// fallback is disabled.
+    pub async fn submit_mev_bundle(&self, plan: &TradePlan, fence: Option<&FencingToken>) -> Result<ExecReceipt> {
+        let tx = self.mempool.sign_swap(plan, fence).await?;
+        let included = match self.track_inclusion(&tx, fence).await {
+            Ok(included) => included,
+            Err(e) => {
+                self.mempool.release_nonce(tx.tx.nonce).await?;
+                return Err(e);
+            }
+        };
+        if included {
+            self.stats.lock().unwrap().included += 1;
+            return self.mempool.confirm(tx.hash).await;
+        }
+
+        if !self.config.fallback_to_mempool {
+            self.mempool.release_nonce(tx.tx.nonce).await?;
+            return Ok(ExecReceipt {
+                tx_hash: tx.hash,
+                success: false,
+                block: 0,
+                gas_used: 0,
+                fees_paid_wei: 0,
+                failure_reason: Some(format!(
+                    "Bundle not included within {} blocks",
+                    self.config.max_missed_blocks
+                )),
+                amount_out: None,
+                endpoint: None,
+            });
+        }
+        tracing::warn!(
+            "bundle for {} missed {} blocks, falling back to the public mempool",
+            plan.idem_key,
+            self.config.max_missed_blocks
+        );
+        self.stats.lock().unwrap().fallbacks += 1;
+        self.mempool.submit_signed(&tx, fence).await
+    }
+
+    /// Send a bundle to every relay, returning the relays that accepted it
+    pub async fn send_bundle(&self, bundle: &Bundle) -> Result<Vec<String>> {
+        let results =
+            futures::future::join_all(self.config.relays.iter().map(|relay| self.send_to_relay(relay, bundle))).await;
+
+        let mut accepted = Vec::new();
+        for (relay, result) in self.config.relays.iter().zip(results) {
+            match result {
+                Ok(_) => accepted.push(relay.name.clone()),
+                Err(e) => tracing::warn!("relay {} rejected bundle for block {}: {}", relay.name, bundle.block_number, e),
+            }
+        }
+        if accepted.is_empty() {
+            return Err(anyhow::anyhow!("No relay accepted the bundle for block {}", bundle.block_number));
+        }
+        Ok(accepted)
+    }
+
+    /// Resubmit the transaction's bundle for each block until it is mined or
+    /// too many blocks are missed
+    async fn track_inclusion(&self, tx: &SignedTransaction, fence: Option<&FencingToken>) -> Result<bool> {
+        let rpc = self.mempool.rpc();
+        let mut target = rpc.block_number().await? + 1;
+
+        for _ in 0..self.config.max_missed_blocks.max(1) {
+            let bundle = BundleBuilder::new().with_transaction(tx.raw.clone()).build(target)?;
+            check_fence(fence).await?;
+            // A block no relay accepted a bundle for still counts as missed
+            match self.send_bundle(&bundle).await {
+                Ok(relays) => tracing::debug!("bundle of {} for block {} accepted by {:?}", tx.hash, target, relays),
+                Err(e) => tracing::warn!("{}", e),
+            }
+            self.stats.lock().unwrap().submitted += 1;
+
+            let head = self.wait_for_block(target).await?;
+            if rpc.transaction_receipt(&tx.hash).await?.is_some() {
+                return Ok(true);
+            }
+            self.stats.lock().unwrap().missed_blocks += 1;
+            target = head + 1;
+        }
+        Ok(false)
+    }
+
+    /// Poll until the chain reaches a block, returning the head
+    async fn wait_for_block(&self, block: u64) -> Result<u64> {
+        let rpc = self.mempool.rpc();
+        let poll = async {
+            let mut interval = tokio::time::interval(Duration::from_millis(self.config.block_poll_interval_ms.max(1)));
+            loop {
+                interval.tick().await;
+                let head = rpc.block_number().await?;
+                if head >= block {
+                    return Ok::<_, anyhow::Error>(head);
+                }
+            }
+        };
+        tokio::time::timeout(Duration::from_millis(self.config.block_timeout_ms), poll)
+            .await
+            .map_err(|_| anyhow::anyhow!("Block {} not reached within {} ms", block, self.config.block_timeout_ms))?
+    }
+
+    async fn send_to_relay(&self, relay: &RelayEndpoint, bundle: &Bundle) -> Result<Value> {
+        let (method, params) = bundle.request(relay.kind);
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        })
+        .to_string();
+
+        let mut request = self.http.post(&relay.url).header("Content-Type", "application/json");
+        match relay.kind {
+            RelayKind::Flashbots => {
+                request = request.header("X-Flashbots-Signature", self.flashbots_signature(&body).await?);
+            }
+            RelayKind::Bloxroute => {
+                if let Some(var) = &relay.auth_env {
+                    let auth = std::env::var(var).map_err(|_| anyhow::anyhow!("{} is not set", var))?;
+                    request = request.header("Authorization", auth);
+                }
+            }
+        }
+
+        let mut response: Value = request.body(body).send().await?.error_for_status()?.json().await?;
+        if let Some(error) = response.get("error") {
+            let message = error.get("message").and_then(Value::as_str).unwrap_or("unknown error");
+            return Err(anyhow::anyhow!("{} failed: {}", method, message));
+        }
+        Ok(response.get_mut("result").map(Value::take).unwrap_or(Value::Null))
+    }
+
+    /// `address:signature` of the EIP-191 signed hash of a request body
+    async fn flashbots_signature(&self, body: &str) -> Result<String> {
+        let message = format!("0x{}", hex::encode(keccak256(body.as_bytes())));
+        let mut prefixed = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+        prefixed.extend_from_slice(message.as_bytes());
+
+        let signature = self.auth_signer.sign_hash(keccak256(&prefixed)).await?;
+        let mut bytes = signature.r.to_vec();
+        bytes.extend_from_slice(&signature.s);
+        bytes.push(27 + signature.y_parity as u8);
+        Ok(format!("{}:0x{}", format_address(&self.auth_signer.address()), hex::encode(bytes)))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::exec_mempool::MempoolConfig;
+    use axum::http::HeaderMap;
+    use axum::{routing::post, Extension, Json, Router};
+    use sniper_chain::rpc::JsonRpcClient;
+    use sniper_chain::tx::transaction_hash;
+    use sniper_chain::{HedgeConfig, ProviderPool, RpcEndpoint};
     use sniper_core::types::{ChainRef, ExecMode, GasPolicy, ExitRules};
+    use sniper_keys::LocalSigner;
+    use std::sync::atomic::{AtomicU64, Ordering};
 
-    #[test]
-    fn test_mev_bundle_executor_creation() {
-        let _executor = MevBundleExecutor::new();
-        assert!(true); // Just testing that we can create an executor
+    const KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+    const AUTH_KEY: &str = "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    /// Node whose head advances one block per poll, with relays that may
+    /// include the bundles they receive
+    #[derive(Default)]
+    struct MockChain {
+        relays_include: bool,
+        head: AtomicU64,
+        mined_in: Mutex<Option<u64>>,
+        /// Target block and first transaction of every bundle received
+        bundles: Mutex<Vec<(u64, String)>>,
+        public: Mutex<Vec<String>>,
     }
-    
-    #[test]
-    fn test_submit_mev_bundle() {
-        let executor = MevBundleExecutor::new();
-        let plan = TradePlan {
+
+    fn reply(request: &Value, result: Value) -> Json<Value> {
+        Json(json!({"jsonrpc": "2.0", "id": request["id"], "result": result}))
+    }
+
+    async fn node(Extension(chain): Extension<Arc<MockChain>>, Json(request): Json<Value>) -> Json<Value> {
+        let result = match request["method"].as_str().unwrap() {
+            "eth_estimateGas" => json!("0x30d40"),
+            "eth_getTransactionCount" => json!("0x0"),
+            "eth_blockNumber" => json!(format_quantity(chain.head.fetch_add(1, Ordering::SeqCst) as u128)),
+            "eth_sendRawTransaction" => {
+                let raw = request["params"][0].as_str().unwrap().to_string();
+                let hash = transaction_hash(&hex::decode(raw.trim_start_matches("0x")).unwrap());
+                *chain.mined_in.lock().unwrap() = Some(chain.head.load(Ordering::SeqCst));
+                chain.public.lock().unwrap().push(raw);
+                json!(format!("0x{}", hex::encode(hash)))
+            }
+            "eth_getTransactionReceipt" => match *chain.mined_in.lock().unwrap() {
+                Some(block) if block <= chain.head.load(Ordering::SeqCst) => json!({
+                    "transactionHash": request["params"][0],
+                    "blockNumber": format_quantity(block as u128),
+                    "status": "0x1",
+                    "gasUsed": "0x249f0",
+                    "effectiveGasPrice": "0x3b9aca00",
+                }),
+                _ => Value::Null,
+            },
+            method => panic!("unexpected method {}", method),
+        };
+        reply(&request, result)
+    }
+
+    async fn relay(
+        Extension(chain): Extension<Arc<MockChain>>,
+        headers: HeaderMap,
+        Json(request): Json<Value>,
+    ) -> Json<Value> {
+        let (auth, target, tx) = match request["method"].as_str().unwrap() {
+            "eth_sendBundle" => (
+                headers.get("X-Flashbots-Signature"),
+                &request["params"][0]["blockNumber"],
+                request["params"][0]["txs"][0].as_str().unwrap().to_string(),
+            ),
+            _ => (
+                headers.get("Authorization"),
+                &request["params"]["block_number"],
+                format!("0x{}", request["params"]["transaction"][0].as_str().unwrap()),
+            ),
+        };
+        if auth.is_none() {
+            return Json(json!({"jsonrpc": "2.0", "id": request["id"], "error": {"code": 403, "message": "unauthorized"}}));
+        }
+        let target = u64::from_str_radix(target.as_str().unwrap().trim_start_matches("0x"), 16).unwrap();
+        if chain.relays_include {
+            *chain.mined_in.lock().unwrap() = Some(target);
+        }
+        chain.bundles.lock().unwrap().push((target, tx));
+        reply(&request, json!({"bundleHash": "0xbundle"}))
+    }
+
+    async fn executor(chain: Arc<MockChain>, config: MevBundleConfig) -> MevBundleExecutor {
+        let app = Router::new()
+            .route("/", post(node))
+            .route("/flashbots", post(relay))
+            .route("/bloxroute", post(relay))
+            .layer(Extension(chain));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let pool = ProviderPool::new(
+            vec![RpcEndpoint {
+                name: "mock".to_string(),
+                url: format!("{}/", url),
+            }],
+            HedgeConfig::default(),
+        );
+        let rpc = Arc::new(JsonRpcClient::new(Arc::new(pool)).unwrap());
+        let mempool = MempoolExecutor::new(
+            rpc,
+            Arc::new(LocalSigner::from_hex(KEY).unwrap()),
+            MempoolConfig {
+                poll_interval_ms: 10,
+                ..MempoolConfig::default()
+            },
+        );
+        std::env::set_var("TEST_BLOXROUTE_AUTH", "secret");
+        let relays = vec![
+            RelayEndpoint {
+                name: "flashbots".to_string(),
+                url: format!("{}/flashbots", url),
+                kind: RelayKind::Flashbots,
+                auth_env: None,
+            },
+            RelayEndpoint {
+                name: "bloxroute".to_string(),
+                url: format!("{}/bloxroute", url),
+                kind: RelayKind::Bloxroute,
+                auth_env: Some("TEST_BLOXROUTE_AUTH".to_string()),
+            },
+        ];
+        MevBundleExecutor::new(
+            Arc::new(mempool),
+            Arc::new(LocalSigner::from_hex(AUTH_KEY).unwrap()),
+            MevBundleConfig {
+                relays,
+                block_poll_interval_ms: 10,
+                ..config
+            },
+        )
+        .unwrap()
+    }
+
+    fn plan() -> TradePlan {
+        TradePlan {
             chain: ChainRef {
                 name: "ethereum".to_string(),
                 id: 1,
             },
-            router: "0xRouter".to_string(),
-            token_in: "0xTokenIn".to_string(),
-            token_out: "0xTokenOut".to_string(),
+            router: "0x7a250d5630b4cf539739df2c5dacb4c659f2488d".to_string(),
+            token_in: "0x1111111111111111111111111111111111111111".to_string(),
+            token_out: "0x2222222222222222222222222222222222222222".to_string(),
             amount_in: 1000000000000000000, // 1 ETH
             min_out: 900000000000000000,    // 0.9 ETH worth of tokens
             mode: ExecMode::Bundle,
@@ -67,10 +485,104 @@ mod tests {
                 trailing_pct: Some(2.0),
             },
             idem_key: "mev-bundle-test-key".to_string(),
+            quote: None,
+            correlation_id: None,
+        }
+    }
+
+    #[test]
+    fn test_bundle_builder() {
+        assert!(BundleBuilder::new().build(10).is_err());
+
+        let bundle = BundleBuilder::new()
+            .with_transaction(vec![0x02, 0xaa])
+            .with_transaction(vec![0x02, 0xbb])
+            .build(0x10)
+            .unwrap();
+        let (method, params) = bundle.request(RelayKind::Flashbots);
+        assert_eq!(method, "eth_sendBundle");
+        assert_eq!(params, json!([{ "txs": ["0x02aa", "0x02bb"], "blockNumber": "0x10" }]));
+
+        let (method, params) = bundle.request(RelayKind::Bloxroute);
+        assert_eq!(method, "blxr_submit_bundle");
+        assert_eq!(params, json!({ "transaction": ["02aa", "02bb"], "block_number": "0x10" }));
+    }
+
+    #[tokio::test]
+    async fn test_bundle_included() {
+        let chain = Arc::new(MockChain {
+            relays_include: true,
+            ..MockChain::default()
+        });
+        let executor = executor(chain.clone(), MevBundleConfig::default()).await;
+
+        let receipt = executor.submit_mev_bundle(&plan(), None).await.unwrap();
+        assert!(receipt.success);
+        assert_eq!(receipt.gas_used, 150_000);
+
+        // Both relays accepted the same bundle
+        let bundles = chain.bundles.lock().unwrap().clone();
+        assert_eq!(bundles.len(), 2);
+        assert_eq!(bundles[0], bundles[1]);
+        let tx = hex::decode(&bundles[0].1[2..]).unwrap();
+        assert_eq!(receipt.tx_hash, format!("0x{}", hex::encode(transaction_hash(&tx))));
+        assert_eq!(receipt.block, bundles[0].0);
+        assert!(chain.public.lock().unwrap().is_empty());
+
+        let stats = executor.stats();
+        assert_eq!(stats.submitted, 1);
+        assert_eq!(stats.included, 1);
+        assert_eq!(stats.fallbacks, 0);
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_mempool_after_missed_blocks() {
+        let chain = Arc::new(MockChain::default());
+        let config = MevBundleConfig {
+            max_missed_blocks: 2,
+            ..MevBundleConfig::default()
         };
-        
-        let receipt = executor.submit_mev_bundle(&plan).unwrap();
-        assert_eq!(receipt.tx_hash, "0xmev-bundle-tx");
+        let executor = executor(chain.clone(), config).await;
+
+        let receipt = executor.submit_mev_bundle(&plan(), None).await.unwrap();
         assert!(receipt.success);
+
+        // Each missed block got a fresh bundle at both relays
+        let bundles = chain.bundles.lock().unwrap().clone();
+        assert_eq!(bundles.len(), 4);
+        assert_ne!(bundles[0].0, bundles[2].0);
+
+        // The signed transaction from the bundle went out unchanged
+        let public = chain.public.lock().unwrap().clone();
+        assert_eq!(public, vec![bundles[0].1.clone()]);
+
+        let stats = executor.stats();
+        assert_eq!(stats.submitted, 2);
+        assert_eq!(stats.missed_blocks, 2);
+        assert_eq!(stats.fallbacks, 1);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_without_fallback() {
+        let chain = Arc::new(MockChain::default());
+        let config = MevBundleConfig {
+            max_missed_blocks: 1,
+            fallback_to_mempool: false,
+            ..MevBundleConfig::default()
+        };
+        let executor = executor(chain.clone(), config).await;
+
+        let receipt = executor.submit_mev_bundle(&plan(), None).await.unwrap();
+        assert!(!receipt.success);
+        assert_eq!(receipt.failure_reason.unwrap(), "Bundle not included within 1 blocks");
+        assert!(chain.public.lock().unwrap().is_empty());
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_needs_a_relay() {
+        let chain = Arc::new(MockChain::default());
+        let mempool = executor(chain, MevBundleConfig::default()).await.mempool;
+        let auth = Arc::new(LocalSigner::from_hex(AUTH_KEY).unwrap());
+        assert!(MevBundleExecutor::new(mempool, auth, MevBundleConfig::default()).is_err());
+    }
+}
@@ -1,59 +1,406 @@
 //! Private RPC execution
-//! 
-//! This module provides functionality for submitting transactions
-//! to private RPC endpoints.
+//!
+//! This module submits trades through private RPC endpoints, which keep the
+//! transaction out of the public mempool until it is mined. Each chain has a
+//! prioritized list of endpoints that are health-checked in the background.
+//! A plan goes to the healthiest endpoint of its chain and fails over down
+//! the list when a submission is rejected; the receipt records the endpoint
+//! that accepted the transaction. A fenced submission checks its fencing
+//! token right before sending.
 
+use crate::check_fence;
+use crate::exec_mempool::{MempoolExecutor, SignedTransaction};
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use sniper_core::types::{TradePlan, ExecReceipt};
+use sniper_storage::failover::FencingToken;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// Private RPC endpoint of a chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivateEndpoint {
+    pub name: String,
+    pub url: String,
+    /// Lower priorities are tried first among equally healthy endpoints
+    #[serde(default)]
+    pub priority: u32,
+}
+
+/// Private RPC settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrivateRpcConfig {
+    /// Endpoints by chain name
+    pub chains: HashMap<String, Vec<PrivateEndpoint>>,
+    /// Interval between health checks
+    pub health_check_interval_ms: u64,
+    /// Timeout of a health check or submission
+    pub request_timeout_ms: u64,
+    /// Consecutive failures before an endpoint is marked unhealthy
+    pub max_consecutive_failures: u32,
+}
+
+impl Default for PrivateRpcConfig {
+    fn default() -> Self {
+        Self {
+            chains: HashMap::new(),
+            health_check_interval_ms: 5_000,
+            request_timeout_ms: 3_000,
+            max_consecutive_failures: 3,
+        }
+    }
+}
+
+/// Health of a private endpoint
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EndpointHealth {
+    pub healthy: bool,
+    pub consecutive_failures: u32,
+    /// Latency of the last successful request
+    pub latency_ms: Option<u64>,
+    pub last_error: Option<String>,
+}
 
 /// Private RPC executor for submitting transactions to private endpoints
 pub struct PrivateRpcExecutor {
-    // In a real implementation, this would contain connections to private RPC endpoints
+    http: reqwest::Client,
+    config: PrivateRpcConfig,
+    // Signs and confirms the trades of each chain
+    signers: HashMap<String, Arc<MempoolExecutor>>,
+    health: RwLock<HashMap<String, EndpointHealth>>,
 }
 
 impl PrivateRpcExecutor {
     /// Create a new private RPC executor
-    pub fn new() -> Self {
-        Self {}
-    }
-    
-    /// Submit a trade to a private RPC endpoint
-    pub fn submit_to_private_rpc(&self, _plan: &TradePlan) -> Result<ExecReceipt> {
-        // Placeholder implementation - in a real implementation, this would
-        // submit the transaction to a private RPC endpoint
-        Ok(ExecReceipt {
-            tx_hash: "0xprivate-rpc-tx".to_string(),
-            success: true,
-            block: 12345678,
-            gas_used: 100000,
-            fees_paid_wei: 2100000000000000, // 0.0021 ETH
-            failure_reason: None,
+    ///
+    /// Endpoints start out healthy until a health check or submission fails.
+    pub fn new(config: PrivateRpcConfig) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.request_timeout_ms))
+            .build()?;
+        let health = config
+            .chains
+            .values()
+            .flatten()
+            .map(|endpoint| {
+                let health = EndpointHealth {
+                    healthy: true,
+                    ..EndpointHealth::default()
+                };
+                (endpoint.name.clone(), health)
+            })
+            .collect();
+        Ok(Self {
+            http,
+            config,
+            signers: HashMap::new(),
+            health: RwLock::new(health),
         })
     }
+
+    /// Sign and confirm the trades of a chain with a mempool executor on that chain
+    pub fn with_chain(mut self, chain: &str, signer: Arc<MempoolExecutor>) -> Self {
+        self.signers.insert(chain.to_string(), signer);
+        self
+    }
+
+    /// Get the private RPC settings
+    pub fn config(&self) -> &PrivateRpcConfig {
+        &self.config
+    }
+
+    /// Get the health of every endpoint
+    pub fn health(&self) -> HashMap<String, EndpointHealth> {
+        self.health.read().unwrap().clone()
+    }
+
+    /// Endpoints of a chain, healthiest first
+    ///
+    /// Healthy endpoints come before unhealthy ones, then by priority, then by
+    /// fewest recent failures and lowest latency. Unhealthy endpoints stay in
+    /// the list as a last resort.
+    pub fn ranked_endpoints(&self, chain: &str) -> Vec<PrivateEndpoint> {
+        let mut endpoints = self.config.chains.get(chain).cloned().unwrap_or_default();
+        let health = self.health.read().unwrap();
+        endpoints.sort_by_key(|endpoint| {
+            let health = health.get(&endpoint.name).cloned().unwrap_or_default();
+            (
+                !health.healthy,
+                endpoint.priority,
+                health.consecutive_failures,
+                health.latency_ms.unwrap_or(u64::MAX),
+            )
+        });
+        endpoints
+    }
+
+    /// Probe every endpoint with `eth_blockNumber` and update its health
+    pub async fn check_health(&self) -> HashMap<String, EndpointHealth> {
+        let endpoints: Vec<&PrivateEndpoint> = self.config.chains.values().flatten().collect();
+        let probes = endpoints.iter().map(|endpoint| async move {
+            let started = Instant::now();
+            let result = self.request::<String>(endpoint, "eth_blockNumber", json!([])).await;
+            (endpoint, started.elapsed(), result)
+        });
+        for (endpoint, latency, result) in futures::future::join_all(probes).await {
+            match result {
+                Ok(_) => self.record_success(&endpoint.name, latency),
+                Err(e) => self.record_failure(&endpoint.name, &e),
+            }
+        }
+        self.health()
+    }
+
+    /// Check endpoint health at the configured interval, forever
+    pub async fn run_health_checks(&self) {
+        let mut interval = tokio::time::interval(Duration::from_millis(self.config.health_check_interval_ms.max(1)));
+        loop {
+            interval.tick().await;
+            self.check_health().await;
+        }
+    }
+
+    /// Submit a trade through the healthiest private endpoint of its chain
+    ///
+    /// A rejected submission is retried on the next endpoint; the trade only
+    /// fails once every endpoint of the chain has rejected it.
+    pub async fn submit_to_private_rpc(&self, plan: &TradePlan, fence: Option<&FencingToken>) -> Result<ExecReceipt> {
+        let signer = self
+            .signers
+            .get(&plan.chain.name)
+            .ok_or_else(|| anyhow::anyhow!("No private RPC signer for chain {}", plan.chain.name))?;
+        let endpoints = self.ranked_endpoints(&plan.chain.name);
+        if endpoints.is_empty() {
+            return Err(anyhow::anyhow!("No private RPC endpoints for chain {}", plan.chain.name));
+        }
+
+        let tx = signer.sign_swap(plan, fence).await?;
+        let sent = match check_fence(fence).await {
+            Ok(()) => self.send(&endpoints, &tx).await,
+            Err(e) => Err(e),
+        };
+        let endpoint = match sent {
+            Ok(endpoint) => endpoint,
+            Err(e) => {
+                signer.release_nonce(tx.tx.nonce).await?;
+                return Err(e);
+            }
+        };
+        tracing::info!("submitted {} as {} via {}", plan.idem_key, tx.hash, endpoint);
+
+        let mut receipt = signer.confirm(tx.hash).await?;
+        receipt.endpoint = Some(endpoint);
+        Ok(receipt)
+    }
+
+    /// Send a signed transaction down the endpoint list until one accepts it
+    async fn send(&self, endpoints: &[PrivateEndpoint], tx: &SignedTransaction) -> Result<String> {
+        let params = json!([format!("0x{}", hex::encode(&tx.raw))]);
+        let mut last_error = None;
+        for endpoint in endpoints {
+            let started = Instant::now();
+            match self.request::<String>(endpoint, "eth_sendRawTransaction", params.clone()).await {
+                Ok(hash) => {
+                    if !hash.eq_ignore_ascii_case(&tx.hash) {
+                        tracing::warn!("{} reported hash {} for transaction {}", endpoint.name, hash, tx.hash);
+                    }
+                    self.record_success(&endpoint.name, started.elapsed());
+                    return Ok(endpoint.name.clone());
+                }
+                Err(e) => {
+                    tracing::warn!("private endpoint {} rejected {}: {}, failing over", endpoint.name, tx.hash, e);
+                    self.record_failure(&endpoint.name, &e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(anyhow::anyhow!(
+            "All private endpoints rejected {}: {}",
+            tx.hash,
+            last_error.map(|e| e.to_string()).unwrap_or_default()
+        ))
+    }
+
+    async fn request<T: serde::de::DeserializeOwned>(
+        &self,
+        endpoint: &PrivateEndpoint,
+        method: &str,
+        params: Value,
+    ) -> Result<T> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let mut response: Value = self
+            .http
+            .post(&endpoint.url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if let Some(error) = response.get("error") {
+            let message = error.get("message").and_then(Value::as_str).unwrap_or("unknown error");
+            return Err(anyhow::anyhow!("{} failed: {}", method, message));
+        }
+        let result = response
+            .get_mut("result")
+            .map(Value::take)
+            .ok_or_else(|| anyhow::anyhow!("{} response has no result", method))?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    fn record_success(&self, endpoint: &str, latency: Duration) {
+        let mut health = self.health.write().unwrap();
+        let health = health.entry(endpoint.to_string()).or_default();
+        health.healthy = true;
+        health.consecutive_failures = 0;
+        health.latency_ms = Some(latency.as_millis() as u64);
+        health.last_error = None;
+    }
+
+    fn record_failure(&self, endpoint: &str, error: &anyhow::Error) {
+        let mut health = self.health.write().unwrap();
+        let health = health.entry(endpoint.to_string()).or_default();
+        health.consecutive_failures += 1;
+        health.last_error = Some(error.to_string());
+        if health.consecutive_failures >= self.config.max_consecutive_failures {
+            if health.healthy {
+                tracing::warn!("private endpoint {} marked unhealthy: {}", endpoint, error);
+            }
+            health.healthy = false;
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::exec_mempool::MempoolConfig;
+    use axum::extract::Path;
+    use axum::{routing::post, Extension, Json, Router};
+    use sniper_chain::rpc::JsonRpcClient;
+    use sniper_chain::tx::transaction_hash;
+    use sniper_chain::{HedgeConfig, ProviderPool, RpcEndpoint};
     use sniper_core::types::{ChainRef, ExecMode, GasPolicy, ExitRules};
+    use sniper_keys::LocalSigner;
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    const KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    /// Public node for receipts plus private endpoints at `/private/{name}`
+    #[derive(Default)]
+    struct MockChain {
+        down: Mutex<HashSet<String>>,
+        mined: Mutex<bool>,
+        submissions: Mutex<Vec<String>>,
+    }
+
+    fn reply(request: &Value, result: Value) -> Json<Value> {
+        Json(json!({"jsonrpc": "2.0", "id": request["id"], "result": result}))
+    }
+
+    async fn node(Extension(chain): Extension<Arc<MockChain>>, Json(request): Json<Value>) -> Json<Value> {
+        let result = match request["method"].as_str().unwrap() {
+            "eth_estimateGas" => json!("0x30d40"),
+            "eth_getTransactionCount" => json!("0x0"),
+            "eth_blockNumber" => json!("0x11"),
+            "eth_getTransactionReceipt" if *chain.mined.lock().unwrap() => json!({
+                "transactionHash": request["params"][0],
+                "blockNumber": "0x10",
+                "status": "0x1",
+                "gasUsed": "0x249f0",
+                "effectiveGasPrice": "0x3b9aca00",
+            }),
+            "eth_getTransactionReceipt" => Value::Null,
+            method => panic!("unexpected method {}", method),
+        };
+        reply(&request, result)
+    }
 
-    #[test]
-    fn test_private_rpc_executor_creation() {
-        let _executor = PrivateRpcExecutor::new();
-        assert!(true); // Just testing that we can create an executor
+    async fn private(
+        Path(name): Path<String>,
+        Extension(chain): Extension<Arc<MockChain>>,
+        Json(request): Json<Value>,
+    ) -> Json<Value> {
+        if chain.down.lock().unwrap().contains(&name) {
+            return Json(json!({"jsonrpc": "2.0", "id": request["id"], "error": {"code": -32000, "message": "unavailable"}}));
+        }
+        let result = match request["method"].as_str().unwrap() {
+            "eth_blockNumber" => json!("0x11"),
+            "eth_sendRawTransaction" => {
+                let raw = request["params"][0].as_str().unwrap();
+                let hash = transaction_hash(&hex::decode(raw.trim_start_matches("0x")).unwrap());
+                chain.submissions.lock().unwrap().push(name);
+                *chain.mined.lock().unwrap() = true;
+                json!(format!("0x{}", hex::encode(hash)))
+            }
+            method => panic!("unexpected method {}", method),
+        };
+        reply(&request, result)
     }
-    
-    #[test]
-    fn test_submit_to_private_rpc() {
-        let executor = PrivateRpcExecutor::new();
-        let plan = TradePlan {
+
+    async fn executor(chain: Arc<MockChain>) -> PrivateRpcExecutor {
+        let app = Router::new()
+            .route("/", post(node))
+            .route("/private/:name", post(private))
+            .layer(Extension(chain));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let pool = ProviderPool::new(
+            vec![RpcEndpoint {
+                name: "public".to_string(),
+                url: format!("{}/", url),
+            }],
+            HedgeConfig::default(),
+        );
+        let rpc = Arc::new(JsonRpcClient::new(Arc::new(pool)).unwrap());
+        let signer = MempoolExecutor::new(
+            rpc,
+            Arc::new(LocalSigner::from_hex(KEY).unwrap()),
+            MempoolConfig {
+                poll_interval_ms: 10,
+                ..MempoolConfig::default()
+            },
+        );
+
+        let endpoint = |name: &str, priority| PrivateEndpoint {
+            name: name.to_string(),
+            url: format!("{}/private/{}", url, name),
+            priority,
+        };
+        let config = PrivateRpcConfig {
+            chains: HashMap::from([(
+                "ethereum".to_string(),
+                vec![endpoint("backup", 1), endpoint("primary", 0), endpoint("last-resort", 2)],
+            )]),
+            max_consecutive_failures: 2,
+            ..PrivateRpcConfig::default()
+        };
+        PrivateRpcExecutor::new(config)
+            .unwrap()
+            .with_chain("ethereum", Arc::new(signer))
+    }
+
+    fn plan(chain: &str) -> TradePlan {
+        TradePlan {
             chain: ChainRef {
-                name: "ethereum".to_string(),
+                name: chain.to_string(),
                 id: 1,
             },
-            router: "0xRouter".to_string(),
-            token_in: "0xTokenIn".to_string(),
-            token_out: "0xTokenOut".to_string(),
+            router: "0x7a250d5630b4cf539739df2c5dacb4c659f2488d".to_string(),
+            token_in: "0x1111111111111111111111111111111111111111".to_string(),
+            token_out: "0x2222222222222222222222222222222222222222".to_string(),
             amount_in: 1000000000000000000, // 1 ETH
             min_out: 900000000000000000,    // 0.9 ETH worth of tokens
             mode: ExecMode::Private,
@@ -67,10 +414,72 @@ mod tests {
                 trailing_pct: Some(2.0),
             },
             idem_key: "private-rpc-test-key".to_string(),
-        };
-        
-        let receipt = executor.submit_to_private_rpc(&plan).unwrap();
-        assert_eq!(receipt.tx_hash, "0xprivate-rpc-tx");
+            quote: None,
+            correlation_id: None,
+        }
+    }
+
+    fn names(endpoints: Vec<PrivateEndpoint>) -> Vec<String> {
+        endpoints.into_iter().map(|endpoint| endpoint.name).collect()
+    }
+
+    #[tokio::test]
+    async fn test_health_checks_rank_endpoints() {
+        let chain = Arc::new(MockChain::default());
+        let executor = executor(chain.clone()).await;
+        assert_eq!(names(executor.ranked_endpoints("ethereum")), vec!["primary", "backup", "last-resort"]);
+        assert!(executor.ranked_endpoints("base").is_empty());
+
+        chain.down.lock().unwrap().insert("primary".to_string());
+        let health = executor.check_health().await;
+        // One failure is tolerated
+        assert!(health["primary"].healthy);
+        assert_eq!(health["primary"].consecutive_failures, 1);
+        assert!(health["backup"].latency_ms.is_some());
+
+        let health = executor.check_health().await;
+        assert!(!health["primary"].healthy);
+        assert_eq!(health["primary"].last_error.as_deref(), Some("eth_blockNumber failed: unavailable"));
+        assert_eq!(names(executor.ranked_endpoints("ethereum")), vec!["backup", "last-resort", "primary"]);
+
+        chain.down.lock().unwrap().clear();
+        assert!(executor.check_health().await["primary"].healthy);
+        assert_eq!(names(executor.ranked_endpoints("ethereum"))[0], "primary");
+    }
+
+    #[tokio::test]
+    async fn test_submit_fails_over_and_records_endpoint() {
+        let chain = Arc::new(MockChain::default());
+        let executor = executor(chain.clone()).await;
+
+        let receipt = executor.submit_to_private_rpc(&plan("ethereum"), None).await.unwrap();
+        assert!(receipt.success);
+        assert_eq!(receipt.endpoint.as_deref(), Some("primary"));
+
+        // The primary goes down without a health check noticing first
+        chain.down.lock().unwrap().insert("primary".to_string());
+        let receipt = executor.submit_to_private_rpc(&plan("ethereum"), None).await.unwrap();
         assert!(receipt.success);
+        assert_eq!(receipt.block, 0x10);
+        assert_eq!(receipt.endpoint.as_deref(), Some("backup"));
+        assert_eq!(*chain.submissions.lock().unwrap(), vec!["primary", "backup"]);
+        assert_eq!(executor.health()["primary"].consecutive_failures, 1);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_submit_fails_when_every_endpoint_rejects() {
+        let chain = Arc::new(MockChain::default());
+        let executor = executor(chain.clone()).await;
+        assert!(executor.submit_to_private_rpc(&plan("base"), None).await.is_err());
+
+        chain
+            .down
+            .lock()
+            .unwrap()
+            .extend(["primary", "backup", "last-resort"].map(str::to_string));
+        let err = executor.submit_to_private_rpc(&plan("ethereum"), None).await.unwrap_err();
+        assert!(err.to_string().contains("All private endpoints rejected"));
+        assert!(chain.submissions.lock().unwrap().is_empty());
+        assert!(executor.health().values().all(|health| health.consecutive_failures == 1));
+    }
+}
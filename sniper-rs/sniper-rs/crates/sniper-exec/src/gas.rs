@@ -2,13 +2,18 @@
 //! 
 //! This module provides functionality for optimizing gas bidding
 //! based on network conditions, transaction priority, and cost considerations.
+//! With a gas oracle attached, bids for a chain take their congestion, base
+//! fee prediction and priority fee from live fee history.
 
+use crate::gas_oracle::GasOracle;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+const WEI_PER_GWEI: u128 = 1_000_000_000;
+
 /// Gas policy for transactions
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GasPolicy {
@@ -47,6 +52,7 @@ pub struct GasBid {
 pub struct GasBidder {
     // Historical data for adaptive bidding
     history: Arc<RwLock<HashMap<String, Vec<GasBid>>>>,
+    oracle: Option<Arc<GasOracle>>,
 }
 
 impl GasBidder {
@@ -54,8 +60,45 @@ impl GasBidder {
     pub fn new() -> Self {
         Self {
             history: Arc::new(RwLock::new(HashMap::new())),
+            oracle: None,
         }
     }
+
+    /// Take network conditions from a gas oracle
+    pub fn with_oracle(mut self, oracle: Arc<GasOracle>) -> Self {
+        self.oracle = Some(oracle);
+        self
+    }
+
+    /// Calculate a gas bid for a chain from its live fee estimate
+    ///
+    /// Congestion comes from the oracle. The priority fee is the recent
+    /// percentile matching the strategy, and the max fee covers the base fee
+    /// predicted over the oracle's inclusion blocks plus that priority fee;
+    /// both stay within twice the policy.
+    pub async fn bid_for_chain(&self, policy: &GasPolicy, chain: &str) -> Result<GasBid> {
+        let oracle = self
+            .oracle
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Gas bidder has no oracle"))?;
+        let estimate = oracle.current(chain)?;
+        let mut bid = self.calculate_bid(policy, estimate.congestion_pct).await?;
+
+        let priority_fee_wei = match bid.strategy_used {
+            BiddingStrategy::Conservative => estimate.priority_fee_p25_wei,
+            BiddingStrategy::Balanced => estimate.priority_fee_p50_wei,
+            BiddingStrategy::Aggressive | BiddingStrategy::Adaptive => estimate.priority_fee_p90_wei,
+        };
+        bid.max_priority_gwei = to_gwei(priority_fee_wei).clamp(1, (policy.max_priority_gwei * 2).max(1));
+
+        let base_fee_gwei = to_gwei(estimate.predicted_base_fee(oracle.config().inclusion_blocks));
+        bid.max_fee_gwei = bid
+            .max_fee_gwei
+            .max(base_fee_gwei + bid.max_priority_gwei)
+            .min(policy.max_fee_gwei * 2)
+            .max(bid.max_priority_gwei);
+        Ok(bid)
+    }
     
     /// Calculate optimal gas bid based on policy and network conditions
     pub async fn calculate_bid(&self, policy: &GasPolicy, network_congestion_pct: u64) -> Result<GasBid> {
@@ -208,9 +251,15 @@ impl GasBidder {
     }
 }
 
+/// Wei rounded up to whole gwei
+fn to_gwei(wei: u128) -> u64 {
+    wei.div_ceil(WEI_PER_GWEI) as u64
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::gas_oracle::{FeeEstimate, GasOracleConfig};
 
     #[tokio::test]
     async fn test_conservative_bidding() -> Result<()> {
@@ -266,6 +315,58 @@ mod tests {
         Ok(())
     }
     
+    #[tokio::test]
+    async fn test_bid_for_chain_uses_live_fees() -> Result<()> {
+        let oracle = Arc::new(GasOracle::new(GasOracleConfig::default()));
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64;
+        oracle.update(
+            "ethereum",
+            FeeEstimate {
+                block: 19,
+                base_fee_wei: 43 * WEI_PER_GWEI,
+                next_base_fee_wei: 44 * WEI_PER_GWEI,
+                priority_fee_p25_wei: WEI_PER_GWEI,
+                priority_fee_p50_wei: 2 * WEI_PER_GWEI,
+                priority_fee_p90_wei: 6 * WEI_PER_GWEI,
+                congestion_pct: 20,
+                updated_at_ms: now_ms,
+            },
+        );
+        let bidder = GasBidder::new().with_oracle(oracle);
+
+        // The max fee covers three full blocks of base fee growth
+        let balanced = GasPolicy {
+            max_fee_gwei: 50,
+            max_priority_gwei: 2,
+        };
+        let bid = bidder.bid_for_chain(&balanced, "ethereum").await?;
+        assert_eq!(bid.strategy_used, BiddingStrategy::Balanced);
+        assert_eq!(bid.congestion_level, CongestionLevel::Low);
+        assert_eq!((bid.max_fee_gwei, bid.max_priority_gwei), (58, 2));
+
+        let aggressive = GasPolicy {
+            max_fee_gwei: 200,
+            max_priority_gwei: 10,
+        };
+        let bid = bidder.bid_for_chain(&aggressive, "ethereum").await?;
+        assert_eq!((bid.max_fee_gwei, bid.max_priority_gwei), (200, 6));
+
+        // Never more than twice the policy
+        let conservative = GasPolicy {
+            max_fee_gwei: 25,
+            max_priority_gwei: 1,
+        };
+        let bid = bidder.bid_for_chain(&conservative, "ethereum").await?;
+        assert_eq!((bid.max_fee_gwei, bid.max_priority_gwei), (50, 1));
+
+        assert!(bidder.bid_for_chain(&balanced, "base").await.is_err());
+        assert!(GasBidder::new().bid_for_chain(&balanced, "ethereum").await.is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_congestion_level_determination() {
         let bidder = GasBidder::new();
@@ -0,0 +1,291 @@
+//! EIP-1559 gas oracle
+//!
+//! This module samples `eth_feeHistory` of each configured chain in the
+//! background and keeps a fee estimate per chain: the current and next base
+//! fee, the 25th, 50th and 90th percentile priority fees paid in recent
+//! blocks, and how full those blocks were. The gas bidder reads congestion
+//! and base fee predictions from here instead of taking them from callers.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sniper_chain::rpc::{parse_quantity, FeeHistory, JsonRpcClient};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Priority fee percentiles requested from the fee history
+const REWARD_PERCENTILES: [f64; 3] = [25.0, 50.0, 90.0];
+
+/// Gas oracle settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GasOracleConfig {
+    /// Interval between fee history samples
+    pub poll_interval_ms: u64,
+    /// Blocks sampled per fee history request
+    pub block_count: u64,
+    /// Blocks a bid should stay includable for as the base fee rises
+    pub inclusion_blocks: u32,
+    /// Age after which an estimate is no longer used for bids
+    pub max_age_ms: i64,
+}
+
+impl Default for GasOracleConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_ms: 2_000,
+            block_count: 20,
+            inclusion_blocks: 3,
+            max_age_ms: 30_000,
+        }
+    }
+}
+
+/// Fee estimate of a chain from its recent blocks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeEstimate {
+    /// Newest block sampled
+    pub block: u64,
+    pub base_fee_wei: u128,
+    /// Base fee of the next block, known from the current one
+    pub next_base_fee_wei: u128,
+    pub priority_fee_p25_wei: u128,
+    pub priority_fee_p50_wei: u128,
+    pub priority_fee_p90_wei: u128,
+    /// Average gas used over the gas limit, in percent
+    pub congestion_pct: u64,
+    pub updated_at_ms: i64,
+}
+
+impl FeeEstimate {
+    /// Build an estimate from a fee history sampled at `REWARD_PERCENTILES`
+    ///
+    /// Each priority fee percentile is the median across blocks, leaving out
+    /// empty blocks, which report zero rewards.
+    pub fn from_history(history: &FeeHistory, now_ms: i64) -> Result<Self> {
+        let blocks = history.gas_used_ratio.len();
+        if blocks == 0 || history.base_fee_per_gas.len() != blocks + 1 {
+            return Err(anyhow::anyhow!(
+                "Fee history has {} blocks and {} base fees",
+                blocks,
+                history.base_fee_per_gas.len()
+            ));
+        }
+        let base_fees = history
+            .base_fee_per_gas
+            .iter()
+            .map(|fee| parse_quantity(fee))
+            .collect::<Result<Vec<_>>>()?;
+
+        let rewards = history.reward.as_deref().unwrap_or_default();
+        let mut percentiles = [0u128; REWARD_PERCENTILES.len()];
+        for (index, percentile) in percentiles.iter_mut().enumerate() {
+            let mut fees = Vec::with_capacity(rewards.len());
+            for (reward, ratio) in rewards.iter().zip(&history.gas_used_ratio) {
+                if *ratio > 0.0 {
+                    if let Some(fee) = reward.get(index) {
+                        fees.push(parse_quantity(fee)?);
+                    }
+                }
+            }
+            fees.sort_unstable();
+            *percentile = fees.get(fees.len() / 2).copied().unwrap_or(0);
+        }
+
+        let fullness = history.gas_used_ratio.iter().sum::<f64>() / blocks as f64;
+        Ok(Self {
+            block: parse_quantity(&history.oldest_block)? as u64 + blocks as u64 - 1,
+            base_fee_wei: base_fees[blocks - 1],
+            next_base_fee_wei: base_fees[blocks],
+            priority_fee_p25_wei: percentiles[0],
+            priority_fee_p50_wei: percentiles[1],
+            priority_fee_p90_wei: percentiles[2],
+            congestion_pct: (fullness * 100.0).round().clamp(0.0, 100.0) as u64,
+            updated_at_ms: now_ms,
+        })
+    }
+
+    /// Highest base fee `blocks_ahead` blocks from now, if every block until
+    /// then is full and raises it by the maximum 12.5%
+    pub fn predicted_base_fee(&self, blocks_ahead: u32) -> u128 {
+        let mut fee = self.next_base_fee_wei;
+        for _ in 1..blocks_ahead {
+            fee += fee / 8;
+        }
+        fee
+    }
+}
+
+/// Gas oracle sampling the fee history of each chain
+pub struct GasOracle {
+    config: GasOracleConfig,
+    chains: HashMap<String, Arc<JsonRpcClient>>,
+    estimates: RwLock<HashMap<String, FeeEstimate>>,
+}
+
+impl GasOracle {
+    /// Create a gas oracle without any chains
+    pub fn new(config: GasOracleConfig) -> Self {
+        Self {
+            config,
+            chains: HashMap::new(),
+            estimates: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Sample the fees of a chain through `rpc`
+    pub fn with_chain(mut self, chain: &str, rpc: Arc<JsonRpcClient>) -> Self {
+        self.chains.insert(chain.to_string(), rpc);
+        self
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &GasOracleConfig {
+        &self.config
+    }
+
+    /// Get the latest estimate of a chain, however old
+    pub fn estimate(&self, chain: &str) -> Option<FeeEstimate> {
+        self.estimates.read().unwrap().get(chain).cloned()
+    }
+
+    /// Get the estimate of a chain, failing if it is missing or older than `max_age_ms`
+    pub fn current(&self, chain: &str) -> Result<FeeEstimate> {
+        let estimate = self
+            .estimate(chain)
+            .ok_or_else(|| anyhow::anyhow!("No fee estimate for chain {}", chain))?;
+        let age_ms = now_ms() - estimate.updated_at_ms;
+        if age_ms > self.config.max_age_ms {
+            return Err(anyhow::anyhow!("Fee estimate for chain {} is {} ms old", chain, age_ms));
+        }
+        Ok(estimate)
+    }
+
+    /// Record an estimate obtained elsewhere, e.g. shared by another service
+    pub fn update(&self, chain: &str, estimate: FeeEstimate) {
+        self.estimates.write().unwrap().insert(chain.to_string(), estimate);
+    }
+
+    /// Sample the fee history of a chain and update its estimate
+    pub async fn refresh(&self, chain: &str) -> Result<FeeEstimate> {
+        let rpc = self
+            .chains
+            .get(chain)
+            .ok_or_else(|| anyhow::anyhow!("No RPC client for chain {}", chain))?;
+        let history = rpc.fee_history(self.config.block_count.max(1), &REWARD_PERCENTILES).await?;
+        let estimate = FeeEstimate::from_history(&history, now_ms())?;
+        self.update(chain, estimate.clone());
+        Ok(estimate)
+    }
+
+    /// Sample every chain, keeping the previous estimate of chains that fail
+    pub async fn refresh_all(&self) {
+        let refreshes = self.chains.keys().map(|chain| async move { (chain, self.refresh(chain).await) });
+        for (chain, result) in futures::future::join_all(refreshes).await {
+            if let Err(e) = result {
+                tracing::warn!("fee history of {} not updated: {}", chain, e);
+            }
+        }
+    }
+
+    /// Sample every chain at the configured interval, forever
+    pub async fn run(&self) {
+        let mut interval = tokio::time::interval(Duration::from_millis(self.config.poll_interval_ms.max(1)));
+        loop {
+            interval.tick().await;
+            self.refresh_all().await;
+        }
+    }
+}
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Json, Router};
+    use serde_json::{json, Value};
+    use sniper_chain::{HedgeConfig, ProviderPool, RpcEndpoint};
+
+    const GWEI: u128 = 1_000_000_000;
+
+    fn gwei(values: &[u128]) -> Vec<String> {
+        values.iter().map(|value| format!("0x{:x}", value * GWEI)).collect()
+    }
+
+    fn history() -> Value {
+        json!({
+            "oldestBlock": "0x10",
+            "baseFeePerGas": gwei(&[40, 41, 42, 43, 44]),
+            "gasUsedRatio": [0.3, 0.2, 0.0, 0.3],
+            "reward": [gwei(&[1, 2, 5]), gwei(&[1, 3, 6]), gwei(&[0, 0, 0]), gwei(&[2, 2, 9])],
+        })
+    }
+
+    async fn handle(Json(request): Json<Value>) -> Json<Value> {
+        assert_eq!(request["method"], "eth_feeHistory");
+        assert_eq!(request["params"], json!(["0x4", "latest", [25.0, 50.0, 90.0]]));
+        Json(json!({"jsonrpc": "2.0", "id": request["id"], "result": history()}))
+    }
+
+    #[test]
+    fn test_estimate_from_history() {
+        let history: FeeHistory = serde_json::from_value(history()).unwrap();
+        let estimate = FeeEstimate::from_history(&history, 7).unwrap();
+        assert_eq!(estimate.block, 0x13);
+        assert_eq!((estimate.base_fee_wei, estimate.next_base_fee_wei), (43 * GWEI, 44 * GWEI));
+        // The empty block is left out of the percentiles
+        assert_eq!(estimate.priority_fee_p25_wei, GWEI);
+        assert_eq!(estimate.priority_fee_p50_wei, 2 * GWEI);
+        assert_eq!(estimate.priority_fee_p90_wei, 6 * GWEI);
+        assert_eq!(estimate.congestion_pct, 20);
+        assert_eq!(estimate.updated_at_ms, 7);
+
+        assert_eq!(estimate.predicted_base_fee(1), 44 * GWEI);
+        assert_eq!(estimate.predicted_base_fee(3), 55_687_500_000);
+
+        let truncated = FeeHistory {
+            base_fee_per_gas: gwei(&[40]),
+            ..history
+        };
+        assert!(FeeEstimate::from_history(&truncated, 7).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_samples_fee_history() {
+        let app = Router::new().route("/", post(handle));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let pool = ProviderPool::new(vec![RpcEndpoint { name: "mock".to_string(), url }], HedgeConfig::default());
+        let rpc = Arc::new(JsonRpcClient::new(Arc::new(pool)).unwrap());
+        let oracle = GasOracle::new(GasOracleConfig {
+            block_count: 4,
+            ..GasOracleConfig::default()
+        })
+        .with_chain("ethereum", rpc);
+
+        assert!(oracle.current("ethereum").is_err());
+        oracle.refresh_all().await;
+        let estimate = oracle.current("ethereum").unwrap();
+        assert_eq!(estimate.next_base_fee_wei, 44 * GWEI);
+        assert_eq!(estimate.priority_fee_p90_wei, 6 * GWEI);
+        assert!(oracle.refresh("base").await.is_err());
+
+        oracle.update(
+            "ethereum",
+            FeeEstimate {
+                updated_at_ms: 0,
+                ..estimate
+            },
+        );
+        assert!(oracle.estimate("ethereum").is_some());
+        assert!(oracle.current("ethereum").is_err());
+    }
+}
@@ -1,34 +1,330 @@
 //! Execution module for the sniper bot.
 //! 
 //! This module provides functionality for executing trades across different venues
-//! including public mempools, private RPCs, and MEV bundles.
+//! including public mempools, private RPCs, and MEV bundles. The venue for
+//! each plan is chosen by expected value using costs learned from receipts.
+//! With an exit monitor attached, filled plans carrying exit rules are
+//! tracked, and [`Executor::execute_exits`] closes them as their rules trigger.
+//! With a bus attached, every execution is published as an audit event under
+//! the plan's correlation ID, linking the receipt to the order it came from,
+//! and as a trading event carrying its latency, fees and slippage. With a
+//! failover coordinator attached, plans are only executed while this region
+//! holds the execution lease, and every venue checks the lease's fencing token
+//! again right before sending, see [`check_fence`].
 
+pub mod error;
 pub mod gas;
+pub mod gas_oracle;
 pub mod nonce;
 pub mod mev;
 pub mod exec_mempool;
 pub mod exec_private;
 pub mod exec_mev_bundle;
 pub mod load_balancer;
+pub mod retry;
+pub mod simulator;
+pub mod venue;
 
-use sniper_core::types::{TradePlan, ExecReceipt};
+use sniper_core::audit::{self, AuditEvent, AUDIT_SUBJECT};
+use sniper_core::bus::InMemoryBus;
+use sniper_core::events::TradingEvent;
+use sniper_core::kill_switch::KillSwitch;
+use sniper_core::telemetry;
+use sniper_exit::{has_rules, ExitMonitor, ExitSignal, TrackedPosition};
+use sniper_core::types::{TradePlan, ExecReceipt, ExecMode};
+use sniper_storage::failover::{FailoverCoordinator, FencingToken};
 use anyhow::Result;
+use exec_mempool::MempoolExecutor;
+use exec_mev_bundle::MevBundleExecutor;
+use exec_private::PrivateRpcExecutor;
+use retry::{ExecutionLedger, RetryConfig};
+use simulator::{SimulationReport, Simulator};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::RwLock;
+use venue::{VenueCostModel, VenueOutcome, VenuePolicy, VenueSelection};
+
+pub use error::ExecError;
 
 /// Main execution engine that routes trades to appropriate execution methods
 pub struct Executor {
     // In a real implementation, this would contain connections to different execution venues
+    cost_model: VenueCostModel,
+    venue_policy: VenuePolicy,
+    mempool: Option<Arc<MempoolExecutor>>,
+    bundles: Option<MevBundleExecutor>,
+    private: Option<PrivateRpcExecutor>,
+    ledger: Option<Arc<ExecutionLedger>>,
+    retry: RetryConfig,
+    simulator: Option<Simulator>,
+    kill_switch: Option<KillSwitch>,
+    exits: Option<ExitMonitor>,
+    bus: Option<InMemoryBus>,
+    failover: Option<Arc<RwLock<FailoverCoordinator>>>,
 }
 
 impl Executor {
     /// Create a new executor instance
     pub fn new() -> Self {
-        Self {}
+        Self {
+            cost_model: VenueCostModel::new(),
+            venue_policy: VenuePolicy::default(),
+            mempool: None,
+            bundles: None,
+            private: None,
+            ledger: None,
+            retry: RetryConfig::default(),
+            simulator: None,
+            kill_switch: None,
+            exits: None,
+            bus: None,
+            failover: None,
+        }
+    }
+    
+    /// Use a venue selection policy
+    pub fn with_venue_policy(mut self, venue_policy: VenuePolicy) -> Self {
+        self.venue_policy = venue_policy;
+        self
+    }
+    
+    /// Submit mempool plans live through an RPC-backed executor
+    pub fn with_mempool(mut self, mempool: Arc<MempoolExecutor>) -> Self {
+        self.mempool = Some(mempool);
+        self
+    }
+    
+    /// Submit bundle plans live to MEV relays
+    pub fn with_bundles(mut self, bundles: MevBundleExecutor) -> Self {
+        self.bundles = Some(bundles);
+        self
+    }
+    
+    /// Submit private plans live through private RPC endpoints
+    pub fn with_private(mut self, private: PrivateRpcExecutor) -> Self {
+        self.private = Some(private);
+        self
+    }
+    
+    /// Execute each plan at most once per idempotency key, retrying transient errors
+    pub fn with_idempotency(mut self, ledger: Arc<ExecutionLedger>, retry: RetryConfig) -> Self {
+        self.ledger = Some(ledger);
+        self.retry = retry;
+        self
+    }
+    
+    /// Dry-run live plans before broadcasting them, refusing those that fail
+    pub fn with_simulator(mut self, simulator: Simulator) -> Self {
+        self.simulator = Some(simulator);
+        self
+    }
+    
+    /// Refuse trades while a kill switch is tripped, feeding it trade outcomes
+    pub fn with_kill_switch(mut self, kill_switch: KillSwitch) -> Self {
+        self.kill_switch = Some(kill_switch);
+        self
+    }
+    
+    /// Track filled plans against their exit rules
+    pub fn with_exit_monitor(mut self, exits: ExitMonitor) -> Self {
+        self.exits = Some(exits);
+        self
+    }
+    
+    /// Publish an audit and a trading event for every execution on a bus
+    pub fn with_bus(mut self, bus: InMemoryBus) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+    
+    /// Only execute while holding the coordinator's execution lease, fencing every send with its token
+    pub fn with_failover(mut self, failover: Arc<RwLock<FailoverCoordinator>>) -> Self {
+        self.failover = Some(failover);
+        self
+    }
+    
+    /// Get the exit monitor, if any
+    pub fn exit_monitor(&self) -> Option<&ExitMonitor> {
+        self.exits.as_ref()
+    }
+    
+    /// Get the ledger of executions by idempotency key, if any
+    pub fn ledger(&self) -> Option<&Arc<ExecutionLedger>> {
+        self.ledger.as_ref()
+    }
+    
+    /// Get the venue cost model
+    pub fn cost_model(&self) -> &VenueCostModel {
+        &self.cost_model
+    }
+    
+    /// Record the outcome of a submission in the venue cost model
+    pub fn record_outcome(&mut self, outcome: VenueOutcome) {
+        self.cost_model.record(outcome);
+    }
+    
+    /// Score the venues for a plan and pick the one with the highest expected value
+    pub fn select_venue(&self, plan: &TradePlan) -> Option<VenueSelection> {
+        self.venue_policy.select(plan, &self.cost_model)
+    }
+    
+    /// Copy of the plan routed to the venue with the highest expected value
+    ///
+    /// The plan's own mode is kept if the policy allows no venues.
+    pub fn route(&self, plan: &TradePlan) -> TradePlan {
+        let mut routed = plan.clone();
+        if let Some(selection) = self.select_venue(plan) {
+            routed.mode = selection.mode;
+        }
+        routed
+    }
+    
+    /// Dry-run a plan with the configured simulator, if any
+    pub async fn preflight(&self, plan: &TradePlan) -> Result<Option<SimulationReport>> {
+        match &self.simulator {
+            Some(simulator) => Ok(Some(simulator.simulate(plan).await?)),
+            None => Ok(None),
+        }
     }
     
     /// Execute a trade based on the plan
-    pub fn execute_trade(&self, _plan: &TradePlan) -> Result<ExecReceipt> {
-        // Placeholder implementation - in a real implementation, this would
-        // route to the appropriate execution method based on the plan
+    ///
+    /// Plans are submitted live when the executor for their mode is
+    /// configured; otherwise the trade is simulated. Live plans that fail
+    /// their pre-flight simulation are not broadcast. With an execution
+    /// ledger, a plan whose idempotency key already executed returns the
+    /// recorded receipt instead of being submitted again. Nothing is executed
+    /// while the kill switch is tripped, or without the execution lease when
+    /// a failover coordinator is attached.
+    pub async fn execute_trade(&self, plan: &TradePlan) -> Result<ExecReceipt, ExecError> {
+        if let Some(kill_switch) = &self.kill_switch {
+            kill_switch.ensure_armed()?;
+        }
+        let fence = match &self.failover {
+            Some(failover) => Some(failover.read().await.submission_fence().await.map_err(ExecError::Fenced)?),
+            None => None,
+        };
+        let fence = fence.as_ref();
+        let started = Instant::now();
+        let result = match &self.ledger {
+            Some(ledger) => ledger.execute(&plan.idem_key, &self.retry, || self.submit(plan, fence)).await,
+            None => self.submit(plan, fence).await,
+        }
+        .map_err(ExecError::from);
+        if let Some(kill_switch) = &self.kill_switch {
+            kill_switch.record_outcome(result.is_ok());
+        }
+        if let (Some(exits), Ok(receipt)) = (&self.exits, &result) {
+            let amount = receipt.amount_out.unwrap_or(plan.min_out);
+            if receipt.success && has_rules(&plan.exits) && amount > 0 {
+                exits.track(TrackedPosition::from_plan(plan, amount));
+            }
+        }
+        self.audit(plan, &result);
+        self.emit(plan, &result, started.elapsed().as_micros() as u64);
+        result
+    }
+    
+    /// Publish the outcome of an execution as a trading event if a bus is attached
+    fn emit(&self, plan: &TradePlan, result: &Result<ExecReceipt, ExecError>, latency_us: u64) {
+        let Some(bus) = &self.bus else {
+            return;
+        };
+        let receipt = result.as_ref().ok();
+        let event = TradingEvent::TradeExecuted {
+            idem_key: plan.idem_key.clone(),
+            chain: plan.chain.name.clone(),
+            venue: format!("{:?}", plan.mode).to_lowercase(),
+            success: receipt.is_some_and(|receipt| receipt.success),
+            latency_us,
+            gas_paid: receipt.map_or(0.0, |receipt| receipt.fees_paid_wei as f64 / 1e18),
+            slippage_bps: receipt.and_then(|receipt| slippage_bps(plan, receipt)),
+            timestamp: exec_mempool::now_secs(),
+        };
+        // Executions run in the caller's span, which the event continues
+        if let Err(e) = bus.publish_in(event.subject(), &event, &telemetry::Context::current()) {
+            tracing::warn!("Failed to publish execution event: {}", e);
+        }
+    }
+    
+    /// Publish the outcome of an execution as an audit event if a bus is attached
+    ///
+    /// Plans not made for an order are correlated by their idempotency key.
+    fn audit(&self, plan: &TradePlan, result: &Result<ExecReceipt, ExecError>) {
+        let Some(bus) = &self.bus else {
+            return;
+        };
+        let details = match result {
+            Ok(receipt) if receipt.success => format!(
+                "plan {} executed via {:?} in tx {} at block {}",
+                plan.idem_key, plan.mode, receipt.tx_hash, receipt.block
+            ),
+            Ok(receipt) => format!(
+                "plan {} failed via {:?} in tx {}: {}",
+                plan.idem_key,
+                plan.mode,
+                receipt.tx_hash,
+                receipt.failure_reason.as_deref().unwrap_or("unknown reason")
+            ),
+            Err(e) => format!("plan {} failed via {:?}: {}", plan.idem_key, plan.mode, e),
+        };
+        let correlation_id = plan.correlation_id.clone().unwrap_or_else(|| plan.idem_key.clone());
+        let event = AuditEvent::new(audit::TRADE_EXECUTED, format!("plans/{}", plan.idem_key), correlation_id)
+            .with_details(details);
+        if let Err(e) = bus.publish_now(AUDIT_SUBJECT, &event) {
+            tracing::warn!("Failed to publish execution audit event: {}", e);
+        }
+    }
+    
+    /// Execute the closing plans of positions whose exit rules trigger at `price`
+    ///
+    /// Prices are in the entry plan's input token per output token, in raw
+    /// units. A position whose closing plan fails is tracked again, so the
+    /// exit is retried on the next price.
+    pub async fn execute_exits(&self, symbol: &str, price: f64) -> Vec<(ExitSignal, Result<ExecReceipt, ExecError>)> {
+        let Some(exits) = &self.exits else {
+            return Vec::new();
+        };
+        let mut results = Vec::new();
+        for signal in exits.on_price(symbol, price) {
+            let Some(plan) = &signal.plan else {
+                continue;
+            };
+            let result = self.execute_trade(plan).await;
+            match &result {
+                Ok(receipt) if receipt.success => exits.record_closed(&signal, price),
+                _ => {
+                    tracing::warn!("{:?} exit of {} failed, retrying on the next price", signal.kind, signal.position.position_id);
+                    exits.track(signal.position.clone());
+                },
+            }
+            results.push((signal, result));
+        }
+        results
+    }
+    
+    async fn submit(&self, plan: &TradePlan, fence: Option<&FencingToken>) -> Result<ExecReceipt> {
+        let live = match plan.mode {
+            ExecMode::Mempool => self.mempool.is_some(),
+            ExecMode::Bundle => self.bundles.is_some(),
+            ExecMode::Private => self.private.is_some(),
+        };
+        if live {
+            if let Some(report) = self.preflight(plan).await?.filter(|report| !report.passed()) {
+                return Err(ExecError::SimulationFailed {
+                    idem_key: plan.idem_key.clone(),
+                    summary: report.summary(),
+                }
+                .into());
+            }
+        }
+        match (&plan.mode, &self.mempool, &self.bundles, &self.private) {
+            (ExecMode::Mempool, Some(mempool), _, _) => return mempool.submit(plan, fence).await,
+            (ExecMode::Bundle, _, Some(bundles), _) => return bundles.submit_mev_bundle(plan, fence).await,
+            (ExecMode::Private, _, _, Some(private)) => return private.submit_to_private_rpc(plan, fence).await,
+            _ => {}
+        }
+        check_fence(fence).await?;
         Ok(ExecReceipt {
             tx_hash: "0xplaceholder".to_string(),
             success: true,
@@ -36,14 +332,39 @@ impl Executor {
             gas_used: 100000,
             fees_paid_wei: 2100000000000000, // 0.0021 ETH
             failure_reason: None,
+            amount_out: None,
+            endpoint: None,
         })
     }
 }
 
+/// Fail with [`ExecError::Fenced`] if the fencing token of a submission went stale
+///
+/// Venues call this right before sending, so a region that lost the
+/// execution lease after taking its token sends nothing.
+pub async fn check_fence(fence: Option<&FencingToken>) -> Result<()> {
+    if let Some(fence) = fence {
+        fence.check().await.map_err(ExecError::Fenced)?;
+    }
+    Ok(())
+}
+
+/// Shortfall of a fill against the plan's quoted output, in basis points
+///
+/// None when the plan carries no quoted output or the receipt no realized
+/// output; `min_out` only bounds the fill and is not what was expected.
+fn slippage_bps(plan: &TradePlan, receipt: &ExecReceipt) -> Option<f64> {
+    let expected_out = plan.quote.as_ref()?.expected_out.filter(|expected_out| *expected_out > 0)?;
+    let amount_out = receipt.amount_out?;
+    Some((expected_out as f64 - amount_out as f64) / expected_out as f64 * 10_000.0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use sniper_core::types::{ChainRef, ExecMode, GasPolicy, ExitRules};
+    use sniper_core::errors::{DomainError, ErrorKind};
+    use sniper_core::types::{ChainRef, ExecMode, GasPolicy, ExitRules, QuoteStamp};
+    use sniper_storage::failover::{FailoverConfig, InMemoryLeaseStore, LeaseStore};
 
     #[test]
     fn test_executor_creation() {
@@ -51,8 +372,8 @@ mod tests {
         assert!(true); // Just testing that we can create an executor
     }
     
-    #[test]
-    fn test_execute_trade() {
+    #[tokio::test]
+    async fn test_execute_trade() {
         let executor = Executor::new();
         let plan = TradePlan {
             chain: ChainRef {
@@ -75,11 +396,159 @@ mod tests {
                 trailing_pct: Some(2.0),
             },
             idem_key: "test-key".to_string(),
+            quote: None,
+            correlation_id: None,
         };
         
-        let receipt = executor.execute_trade(&plan).unwrap();
+        let receipt = executor.execute_trade(&plan).await.unwrap();
         assert_eq!(receipt.tx_hash, "0xplaceholder");
         assert!(receipt.success);
+        
+        // With no history the plan is routed away from the public mempool
+        let routed = executor.route(&plan);
+        assert_eq!(routed.mode, ExecMode::Private);
+        assert_eq!(routed.idem_key, plan.idem_key);
+        
+        // With a ledger, a redelivered plan is not executed again
+        let ledger = Arc::new(ExecutionLedger::new());
+        let executor = Executor::new().with_idempotency(ledger.clone(), RetryConfig::default());
+        executor.execute_trade(&plan).await.unwrap();
+        executor.execute_trade(&plan).await.unwrap();
+        let record = ledger.record(&plan.idem_key).unwrap();
+        assert_eq!(record.state, retry::ExecutionState::Completed);
+        assert_eq!(record.attempts, 1);
+        
+        // Nothing executes while the kill switch is tripped
+        let kill_switch = KillSwitch::default();
+        let executor = Executor::new().with_kill_switch(kill_switch.clone());
+        kill_switch.trip(sniper_core::kill_switch::TripReason::Manual {
+            by: "ops".to_string(),
+            reason: "test".to_string(),
+        });
+        let err = executor.execute_trade(&plan).await.unwrap_err();
+        assert!(matches!(err, ExecError::Halted(_)));
+        assert_eq!(err.kind(), ErrorKind::Unavailable);
+        kill_switch.reset("ops");
+        executor.execute_trade(&plan).await.unwrap();
+        assert_eq!(kill_switch.status().window_samples, 1);
+        
+        // Nothing executes without the execution lease
+        let store: Arc<dyn LeaseStore> = Arc::new(InMemoryLeaseStore::new());
+        let failover = |region: &str| {
+            let config = FailoverConfig::new(region, "main", std::time::Duration::from_secs(1));
+            Arc::new(RwLock::new(FailoverCoordinator::new(config, store.clone())))
+        };
+        let (primary, standby) = (failover("us-east-1"), failover("eu-west-1"));
+        primary.write().await.heartbeat().await.unwrap();
+        standby.write().await.heartbeat().await.unwrap();
+        let err = Executor::new().with_failover(standby).execute_trade(&plan).await.unwrap_err();
+        assert!(matches!(err, ExecError::Fenced(_)));
+        assert_eq!(err.kind(), ErrorKind::Unavailable);
+        Executor::new().with_failover(primary).execute_trade(&plan).await.unwrap();
+        
+        // A filled plan with exit rules is tracked and closed once its stop-loss triggers
+        let executor = Executor::new().with_exit_monitor(ExitMonitor::new());
+        executor.execute_trade(&plan).await.unwrap();
+        let tracked = executor.exit_monitor().unwrap().tracked(&plan.idem_key).unwrap();
+        assert_eq!(tracked.symbol, "0xTokenOut/0xTokenIn");
+        assert!(executor.execute_exits(&tracked.symbol, tracked.entry_price).await.is_empty());
+        let exits = executor.execute_exits(&tracked.symbol, tracked.entry_price * 0.9).await;
+        assert_eq!(exits.len(), 1);
+        assert_eq!(exits[0].0.kind, sniper_exit::ExitKind::StopLoss);
+        assert!(exits[0].1.as_ref().unwrap().success);
+        assert!(executor.exit_monitor().unwrap().list().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_execution_is_audited_under_correlation_id() {
+        let bus = InMemoryBus::new(16);
+        let mut rx = bus.subscribe(AUDIT_SUBJECT);
+        let executor = Executor::new().with_bus(bus);
+        let plan = TradePlan {
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            router: "0xRouter".to_string(),
+            token_in: "0xTokenIn".to_string(),
+            token_out: "0xTokenOut".to_string(),
+            amount_in: 1000000000000000000,
+            min_out: 900000000000000000,
+            mode: ExecMode::Mempool,
+            gas: GasPolicy {
+                max_fee_gwei: 50,
+                max_priority_gwei: 2,
+            },
+            exits: ExitRules::default(),
+            idem_key: "plan-1".to_string(),
+            quote: None,
+            correlation_id: Some("order-1".to_string()),
+        };
+        
+        executor.execute_trade(&plan).await.unwrap();
+        let event = AuditEvent::decode(&rx.recv().await.unwrap()).unwrap();
+        assert_eq!(event.action, audit::TRADE_EXECUTED);
+        assert_eq!(event.correlation_id, "order-1");
+        assert_eq!(event.resource, "plans/plan-1");
+        assert!(event.details.unwrap().contains("0xplaceholder"));
+        match TradingEvent::decode(&rx.recv().await.unwrap()).unwrap() {
+            TradingEvent::TradeExecuted { idem_key, venue, success, gas_paid, slippage_bps, .. } => {
+                assert_eq!(idem_key, "plan-1");
+                assert_eq!(venue, "mempool");
+                assert!(success);
+                assert!(gas_paid > 0.0);
+                assert_eq!(slippage_bps, None);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        
+        // Plans made outside an order fall back to their idempotency key
+        executor.execute_trade(&TradePlan { correlation_id: None, ..plan }).await.unwrap();
+        assert_eq!(AuditEvent::decode(&rx.recv().await.unwrap()).unwrap().correlation_id, "plan-1");
+    }
+    
+    #[test]
+    fn test_slippage_is_measured_against_the_quote() {
+        let plan = TradePlan {
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            router: "0xRouter".to_string(),
+            token_in: "0xTokenIn".to_string(),
+            token_out: "0xTokenOut".to_string(),
+            amount_in: 1000000000000000000,
+            min_out: 900000000000000000,
+            mode: ExecMode::Mempool,
+            gas: GasPolicy {
+                max_fee_gwei: 50,
+                max_priority_gwei: 2,
+            },
+            exits: ExitRules::default(),
+            idem_key: "plan-1".to_string(),
+            quote: Some(QuoteStamp {
+                quoted_at_ms: 0,
+                block: 0,
+                expected_out: Some(1000000000000000000),
+            }),
+            correlation_id: None,
+        };
+        let fill = |amount_out| ExecReceipt {
+            tx_hash: "0xfill".to_string(),
+            success: true,
+            block: 1,
+            gas_used: 100000,
+            fees_paid_wei: 0,
+            failure_reason: None,
+            amount_out,
+            endpoint: None,
+        };
+        
+        // 0.99 filled against a 1.0 quote is 100 bps short, though above min_out
+        assert!((slippage_bps(&plan, &fill(Some(990000000000000000))).unwrap() - 100.0).abs() < 1e-9);
+        assert!(slippage_bps(&plan, &fill(Some(1010000000000000000))).unwrap() < 0.0);
+        assert_eq!(slippage_bps(&plan, &fill(None)), None);
+        assert_eq!(slippage_bps(&TradePlan { quote: None, ..plan }, &fill(Some(990000000000000000))), None);
     }
 }
 
@@ -275,6 +744,8 @@ mod phase3_tests {
                 trailing_pct: Some(2.0),
             },
             idem_key: "integration-test-key".to_string(),
+            quote: None,
+            correlation_id: None,
         };
         
         // 4. Optimize gas bidding
@@ -2,13 +2,21 @@
 //! 
 //! This module provides functionality for distributing trades across
 //! multiple executor instances to improve throughput and reliability.
+//! Instances are health-checked in the background: one failing several
+//! checks in a row is taken out of rotation until it passes again, and the
+//! latency of each check is kept in the instance statistics.
 
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use sniper_core::types::{TradePlan, ExecReceipt};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
+/// Weight of the latest check in the average latency
+const LATENCY_SMOOTHING: f64 = 0.2;
+
 /// Load balancing strategy
 #[derive(Debug, Clone)]
 pub enum LoadBalancingStrategy {
@@ -27,11 +35,53 @@ pub struct ExecutorInstance {
     pub healthy: bool,
 }
 
+/// Active health check settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthCheckConfig {
+    /// Interval between checks of every instance
+    pub interval_ms: u64,
+    /// Time a check may take before it counts as failed
+    pub timeout_ms: u64,
+    /// Path requested on each instance's address
+    pub path: String,
+    /// Consecutive failed checks after which an instance is taken out of rotation
+    pub unhealthy_threshold: u32,
+    /// Consecutive passed checks after which an unhealthy instance is re-admitted
+    pub healthy_threshold: u32,
+}
+
+impl Default for HealthCheckConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: 5_000,
+            timeout_ms: 1_000,
+            path: "/health".to_string(),
+            unhealthy_threshold: 3,
+            healthy_threshold: 2,
+        }
+    }
+}
+
+/// Health check history of an instance
+#[derive(Debug, Clone, Default)]
+struct InstanceHealth {
+    consecutive_failures: u32,
+    consecutive_successes: u32,
+    checks: u64,
+    failed_checks: u64,
+    last_latency_ms: Option<u64>,
+    avg_latency_ms: Option<f64>,
+}
+
 /// Load balancer for distributing trades across multiple executors
 pub struct LoadBalancer {
     instances: Arc<RwLock<HashMap<String, ExecutorInstance>>>,
     strategy: LoadBalancingStrategy,
     last_selected: Arc<RwLock<usize>>,
+    health_check: HealthCheckConfig,
+    health: Arc<RwLock<HashMap<String, InstanceHealth>>>,
+    http: reqwest::Client,
 }
 
 impl LoadBalancer {
@@ -41,9 +91,18 @@ impl LoadBalancer {
             instances: Arc::new(RwLock::new(HashMap::new())),
             strategy,
             last_selected: Arc::new(RwLock::new(0)),
+            health_check: HealthCheckConfig::default(),
+            health: Arc::new(RwLock::new(HashMap::new())),
+            http: reqwest::Client::new(),
         }
     }
     
+    /// Use health check settings
+    pub fn with_health_check(mut self, health_check: HealthCheckConfig) -> Self {
+        self.health_check = health_check;
+        self
+    }
+    
     /// Add an executor instance
     pub async fn add_instance(&self, instance: ExecutorInstance) -> Result<()> {
         let mut instances = self.instances.write().await;
@@ -55,6 +114,7 @@ impl LoadBalancer {
     pub async fn remove_instance(&self, instance_id: &str) -> Result<()> {
         let mut instances = self.instances.write().await;
         instances.remove(instance_id);
+        self.health.write().await.remove(instance_id);
         Ok(())
     }
     
@@ -141,22 +201,126 @@ impl LoadBalancer {
                 gas_used: 100000,
                 fees_paid_wei: 2100000000000000, // 0.0021 ETH
                 failure_reason: None,
+                amount_out: None,
+                endpoint: None,
             })
         } else {
             Err(anyhow::anyhow!("No healthy executor instances available"))
         }
     }
     
+    /// Check every instance once, updating its health
+    pub async fn check_health(&self) {
+        let targets: Vec<(String, String)> = self
+            .instances
+            .read()
+            .await
+            .values()
+            .map(|instance| (instance.id.clone(), instance.address.clone()))
+            .collect();
+        let checks = targets.iter().map(|(id, address)| async move { (id, self.probe(address).await) });
+        for (id, result) in futures::future::join_all(checks).await {
+            self.record_check(id, result).await;
+        }
+    }
+    
+    /// Check every instance at the configured interval, forever
+    pub async fn run(&self) {
+        let mut interval = tokio::time::interval(Duration::from_millis(self.health_check.interval_ms.max(1)));
+        loop {
+            interval.tick().await;
+            self.check_health().await;
+        }
+    }
+    
+    /// Request the health path of an address, returning the latency if it answered with success
+    async fn probe(&self, address: &str) -> Result<Duration> {
+        let base = if address.contains("://") {
+            address.to_string()
+        } else {
+            format!("http://{}", address)
+        };
+        let url = format!("{}{}", base.trim_end_matches('/'), self.health_check.path);
+        let started = Instant::now();
+        self.http
+            .get(&url)
+            .timeout(Duration::from_millis(self.health_check.timeout_ms))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(started.elapsed())
+    }
+    
+    async fn record_check(&self, instance_id: &str, result: Result<Duration>) {
+        let mut health = self.health.write().await;
+        let entry = health.entry(instance_id.to_string()).or_default();
+        entry.checks += 1;
+        let passed = match &result {
+            Ok(latency) => {
+                let latency_ms = latency.as_millis() as u64;
+                entry.consecutive_successes += 1;
+                entry.consecutive_failures = 0;
+                entry.last_latency_ms = Some(latency_ms);
+                entry.avg_latency_ms = Some(match entry.avg_latency_ms {
+                    Some(avg) => avg + LATENCY_SMOOTHING * (latency_ms as f64 - avg),
+                    None => latency_ms as f64,
+                });
+                true
+            }
+            Err(_) => {
+                entry.consecutive_failures += 1;
+                entry.consecutive_successes = 0;
+                entry.failed_checks += 1;
+                false
+            }
+        };
+        let (failures, successes) = (entry.consecutive_failures, entry.consecutive_successes);
+        drop(health);
+        
+        let mut instances = self.instances.write().await;
+        let Some(instance) = instances.get_mut(instance_id) else {
+            return;
+        };
+        if instance.healthy && !passed && failures >= self.health_check.unhealthy_threshold {
+            instance.healthy = false;
+            let reason = result.err().map(|e| e.to_string()).unwrap_or_default();
+            tracing::warn!("executor {} evicted after {} failed health checks: {}", instance_id, failures, reason);
+        } else if !instance.healthy && passed && successes >= self.health_check.healthy_threshold {
+            instance.healthy = true;
+            tracing::info!("executor {} re-admitted after {} passed health checks", instance_id, successes);
+        }
+    }
+    
     /// Get statistics about the load balancer
     pub async fn get_stats(&self) -> LoadBalancerStats {
         let instances = self.instances.read().await;
+        let health = self.health.read().await;
         let healthy_count = instances.values().filter(|i| i.healthy).count();
         let total_connections: u32 = instances.values().map(|i| i.active_connections).sum();
         
+        let mut instance_stats: Vec<InstanceStats> = instances
+            .values()
+            .map(|instance| {
+                let checks = health.get(&instance.id).cloned().unwrap_or_default();
+                InstanceStats {
+                    id: instance.id.clone(),
+                    healthy: instance.healthy,
+                    active_connections: instance.active_connections,
+                    last_latency_ms: checks.last_latency_ms,
+                    avg_latency_ms: checks.avg_latency_ms,
+                    consecutive_failures: checks.consecutive_failures,
+                    checks: checks.checks,
+                    failed_checks: checks.failed_checks,
+                }
+            })
+            .collect();
+        instance_stats.sort_by(|a, b| a.id.cmp(&b.id));
+        
         LoadBalancerStats {
             total_instances: instances.len(),
             healthy_instances: healthy_count,
             total_connections,
+            instances: instance_stats,
         }
     }
 }
@@ -167,6 +331,23 @@ pub struct LoadBalancerStats {
     pub total_instances: usize,
     pub healthy_instances: usize,
     pub total_connections: u32,
+    /// Per-instance health and latency, ordered by ID
+    pub instances: Vec<InstanceStats>,
+}
+
+/// Health and latency of one executor instance
+#[derive(Debug, Clone)]
+pub struct InstanceStats {
+    pub id: String,
+    pub healthy: bool,
+    pub active_connections: u32,
+    /// Latency of the latest passed health check
+    pub last_latency_ms: Option<u64>,
+    /// Smoothed latency of passed health checks
+    pub avg_latency_ms: Option<f64>,
+    pub consecutive_failures: u32,
+    pub checks: u64,
+    pub failed_checks: u64,
 }
 
 #[cfg(test)]
@@ -298,4 +479,67 @@ mod tests {
         
         Ok(())
     }
+    
+    #[tokio::test]
+    async fn test_health_checks_evict_and_readmit() -> Result<()> {
+        use axum::{http::StatusCode, routing::get, Extension, Router};
+        use std::sync::atomic::{AtomicBool, Ordering};
+        
+        async fn health(Extension(up): Extension<Arc<AtomicBool>>) -> StatusCode {
+            if up.load(Ordering::SeqCst) {
+                StatusCode::OK
+            } else {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+        }
+        
+        let up = Arc::new(AtomicBool::new(true));
+        let app = Router::new().route("/health", get(health)).layer(Extension(up.clone()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let live = listener.local_addr()?.to_string();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        // Nothing listens on a port once its listener is dropped
+        let dead = tokio::net::TcpListener::bind("127.0.0.1:0").await?.local_addr()?.to_string();
+        
+        let lb = LoadBalancer::new(LoadBalancingStrategy::RoundRobin).with_health_check(HealthCheckConfig {
+            unhealthy_threshold: 2,
+            healthy_threshold: 2,
+            ..HealthCheckConfig::default()
+        });
+        for (id, address) in [("executor-1", &live), ("executor-2", &dead)] {
+            lb.add_instance(ExecutorInstance {
+                id: id.to_string(),
+                address: address.clone(),
+                active_connections: 0,
+                weight: 1,
+                healthy: true,
+            })
+            .await?;
+        }
+        
+        lb.check_health().await;
+        assert_eq!(lb.get_stats().await.healthy_instances, 2);
+        lb.check_health().await;
+        let stats = lb.get_stats().await;
+        assert_eq!(stats.healthy_instances, 1);
+        assert_eq!(stats.instances[0].id, "executor-1");
+        assert!(stats.instances[0].last_latency_ms.is_some());
+        assert!(stats.instances[0].avg_latency_ms.is_some());
+        assert_eq!((stats.instances[1].consecutive_failures, stats.instances[1].failed_checks), (2, 2));
+        assert!(!stats.instances[1].healthy);
+        
+        up.store(false, Ordering::SeqCst);
+        lb.check_health().await;
+        lb.check_health().await;
+        assert!(lb.select_instance().await.is_none());
+        
+        // One passed check is not enough to re-admit
+        up.store(true, Ordering::SeqCst);
+        lb.check_health().await;
+        assert!(lb.select_instance().await.is_none());
+        lb.check_health().await;
+        assert_eq!(lb.select_instance().await.unwrap().id, "executor-1");
+        assert_eq!(lb.get_stats().await.instances[0].checks, 6);
+        Ok(())
+    }
 }
\ No newline at end of file
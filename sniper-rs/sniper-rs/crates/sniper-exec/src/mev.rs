@@ -28,6 +28,8 @@ impl MevExecutor {
             gas_used: 100000,
             fees_paid_wei: 2100000000000000, // 0.0021 ETH
             failure_reason: None,
+            amount_out: None,
+            endpoint: None,
         })
     }
 }
@@ -67,6 +69,8 @@ mod tests {
                 trailing_pct: Some(2.0),
             },
             idem_key: "mev-test-key".to_string(),
+            quote: None,
+            correlation_id: None,
         };
         
         let receipt = executor.submit_bundle(&plan).unwrap();
@@ -1,47 +1,189 @@
 //! Nonce management for transaction sequencing
-//! 
+//!
 //! This module provides functionality for managing transaction nonces
-//! to ensure proper sequencing and avoid nonce gaps.
+//! to ensure proper sequencing and avoid nonce gaps. Nonces are handed out
+//! per account under a lock so concurrent submissions never share one, and
+//! every transaction sent is tracked until it is mined. Reconciling against
+//! the chain's mined count reports gaps, nonces handed out but never sent,
+//! which block every later transaction, and transactions stuck pending past
+//! a deadline, which can be replaced with higher fees. Submissions fenced by
+//! an execution lease are admitted per account by their fencing token, so a
+//! region that lost the lease can never take a nonce after its successor.
 
 use anyhow::Result;
-use std::collections::HashMap;
+use sniper_chain::tx::Eip1559Transaction;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
+/// Transaction sent with a nonce and not yet mined
+#[derive(Debug, Clone)]
+pub struct PendingTx {
+    pub nonce: u64,
+    pub tx: Eip1559Transaction,
+    pub tx_hash: String,
+    pub submitted_at: Instant,
+    /// Replacements sent for the nonce so far
+    pub replacements: u32,
+}
+
+/// Account nonces reconciled against the chain
+#[derive(Debug, Clone, Default)]
+pub struct NonceReport {
+    /// Transactions mined, the nonce of the oldest unmined one
+    pub mined: u64,
+    /// Next nonce to hand out
+    pub next: u64,
+    /// Nonces handed out but never sent; each blocks every later nonce
+    pub gaps: Vec<u64>,
+    /// Transactions pending past the deadline
+    pub stuck: Vec<PendingTx>,
+}
+
+/// Nonces of one account
+#[derive(Debug, Default)]
+struct AccountNonces {
+    next: u64,
+    pending: BTreeMap<u64, PendingTx>,
+    // Highest fencing token admitted so far
+    fence: u64,
+}
+
 /// Nonce manager for tracking account nonces
 pub struct NonceManager {
-    // Map of address to current nonce
-    nonces: Arc<RwLock<HashMap<String, u64>>>,
+    // Map of address to its nonces
+    accounts: Arc<RwLock<HashMap<String, AccountNonces>>>,
 }
 
 impl NonceManager {
     /// Create a new nonce manager
     pub fn new() -> Self {
         Self {
-            nonces: Arc::new(RwLock::new(HashMap::new())),
+            accounts: Arc::new(RwLock::new(HashMap::new())),
         }
     }
-    
+
     /// Get the next nonce for an address
     pub async fn get_next_nonce(&self, address: &str) -> Result<u64> {
-        let mut nonces = self.nonces.write().await;
-        let nonce = nonces.entry(address.to_string()).or_insert(0);
-        let current = *nonce;
-        *nonce += 1;
+        let mut accounts = self.accounts.write().await;
+        let account = accounts.entry(address.to_string()).or_default();
+        let current = account.next;
+        account.next += 1;
         Ok(current)
     }
-    
+
+    /// Admit a submission fenced by `token`, refusing tokens older than one already admitted
+    pub async fn admit_fence(&self, address: &str, token: u64) -> Result<()> {
+        let mut accounts = self.accounts.write().await;
+        let account = accounts.entry(address.to_string()).or_default();
+        if token < account.fence {
+            return Err(anyhow::anyhow!(
+                "Fencing token {} is stale, {} already submitted for {}",
+                token, account.fence, address
+            ));
+        }
+        account.fence = token;
+        Ok(())
+    }
+
     /// Reset nonce for an address (useful after reorgs or errors)
     pub async fn reset_nonce(&self, address: &str, nonce: u64) -> Result<()> {
-        let mut nonces = self.nonces.write().await;
-        nonces.insert(address.to_string(), nonce);
+        let mut accounts = self.accounts.write().await;
+        accounts.entry(address.to_string()).or_default().next = nonce;
         Ok(())
     }
-    
+
     /// Get current nonce without incrementing
     pub async fn get_current_nonce(&self, address: &str) -> Result<u64> {
-        let nonces = self.nonces.read().await;
-        Ok(*nonces.get(address).unwrap_or(&0))
+        let accounts = self.accounts.read().await;
+        Ok(accounts.get(address).map(|account| account.next).unwrap_or(0))
+    }
+
+    /// Give back a nonce whose transaction was never sent
+    ///
+    /// The latest nonce handed out is reused by the next submission; an
+    /// earlier one is left as a gap for [`Self::reconcile`] to report.
+    pub async fn release_nonce(&self, address: &str, nonce: u64) -> Result<()> {
+        let mut accounts = self.accounts.write().await;
+        if let Some(account) = accounts.get_mut(address) {
+            if account.next == nonce + 1 {
+                account.next = nonce;
+            }
+        }
+        Ok(())
+    }
+
+    /// Track a sent transaction until it is mined, replacing any earlier
+    /// transaction with the same nonce
+    pub async fn track_pending(&self, address: &str, pending: PendingTx) -> Result<()> {
+        let mut accounts = self.accounts.write().await;
+        let account = accounts.entry(address.to_string()).or_default();
+        account.next = account.next.max(pending.nonce + 1);
+        account.pending.insert(pending.nonce, pending);
+        Ok(())
+    }
+
+    /// Get the pending transaction of a nonce
+    pub async fn pending_tx(&self, address: &str, nonce: u64) -> Option<PendingTx> {
+        let accounts = self.accounts.read().await;
+        accounts.get(address).and_then(|account| account.pending.get(&nonce)).cloned()
+    }
+
+    /// Get every pending transaction of an address, by nonce
+    pub async fn pending(&self, address: &str) -> Vec<PendingTx> {
+        let accounts = self.accounts.read().await;
+        accounts
+            .get(address)
+            .map(|account| account.pending.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Stop tracking the transactions below the chain's mined count
+    pub async fn confirm_mined(&self, address: &str, mined: u64) -> Result<()> {
+        let mut accounts = self.accounts.write().await;
+        if let Some(account) = accounts.get_mut(address) {
+            account.pending = account.pending.split_off(&mined);
+        }
+        Ok(())
+    }
+
+    /// Reconcile an address with the chain's mined count
+    ///
+    /// Mined transactions stop being tracked; the report lists the gaps and
+    /// the transactions pending for at least `stuck_after`.
+    pub async fn reconcile(&self, address: &str, mined: u64, stuck_after: Duration) -> Result<NonceReport> {
+        self.confirm_mined(address, mined).await?;
+        let accounts = self.accounts.read().await;
+        let Some(account) = accounts.get(address) else {
+            return Ok(NonceReport {
+                mined,
+                next: mined,
+                ..NonceReport::default()
+            });
+        };
+
+        let gaps = (mined..account.next)
+            .filter(|nonce| !account.pending.contains_key(nonce))
+            .collect();
+        let stuck = account
+            .pending
+            .values()
+            .filter(|pending| pending.submitted_at.elapsed() >= stuck_after)
+            .cloned()
+            .collect();
+        Ok(NonceReport {
+            mined,
+            next: account.next.max(mined),
+            gaps,
+            stuck,
+        })
+    }
+}
+
+impl Default for NonceManager {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -53,23 +195,122 @@ mod tests {
     async fn test_nonce_management() -> Result<()> {
         let manager = NonceManager::new();
         let address = "0xTestAddress";
-        
+
         // Test getting next nonce
         let nonce1 = manager.get_next_nonce(address).await?;
         assert_eq!(nonce1, 0);
-        
+
         let nonce2 = manager.get_next_nonce(address).await?;
         assert_eq!(nonce2, 1);
-        
+
         // Test getting current nonce without incrementing
         let current = manager.get_current_nonce(address).await?;
         assert_eq!(current, 2);
-        
+
         // Test resetting nonce
         manager.reset_nonce(address, 5).await?;
         let current = manager.get_current_nonce(address).await?;
         assert_eq!(current, 5);
-        
+
+        Ok(())
+    }
+
+    fn pending(nonce: u64, age: Duration) -> PendingTx {
+        PendingTx {
+            nonce,
+            tx: Eip1559Transaction {
+                chain_id: 1,
+                nonce,
+                max_priority_fee_per_gas: 2_000_000_000,
+                max_fee_per_gas: 50_000_000_000,
+                gas_limit: 21_000,
+                to: [0x11; 20],
+                value: 0,
+                data: Vec::new(),
+            },
+            tx_hash: format!("0x{:064x}", nonce),
+            submitted_at: Instant::now() - age,
+            replacements: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_nonces_are_unique() -> Result<()> {
+        let manager = Arc::new(NonceManager::new());
+        let handles: Vec<_> = (0..50)
+            .map(|_| {
+                let manager = manager.clone();
+                tokio::spawn(async move { manager.get_next_nonce("0xA").await.unwrap() })
+            })
+            .collect();
+        let mut nonces = Vec::new();
+        for handle in handles {
+            nonces.push(handle.await?);
+        }
+        nonces.sort();
+        assert_eq!(nonces, (0..50).collect::<Vec<_>>());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_older_fencing_token_is_refused() -> Result<()> {
+        let manager = NonceManager::new();
+        manager.admit_fence("0xA", 1).await?;
+        manager.admit_fence("0xA", 2).await?;
+        manager.admit_fence("0xA", 2).await?;
+        assert!(manager.admit_fence("0xA", 1).await.unwrap_err().to_string().contains("stale"));
+        manager.admit_fence("0xB", 1).await?;
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_release_rolls_back_only_the_latest_nonce() -> Result<()> {
+        let manager = NonceManager::new();
+        for _ in 0..3 {
+            manager.get_next_nonce("0xA").await?;
+        }
+        manager.release_nonce("0xA", 2).await?;
+        assert_eq!(manager.get_current_nonce("0xA").await?, 2);
+        manager.release_nonce("0xA", 0).await?;
+        assert_eq!(manager.get_current_nonce("0xA").await?, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_reports_gaps_and_stuck() -> Result<()> {
+        let manager = NonceManager::new();
+        manager.reset_nonce("0xA", 10).await?;
+        for _ in 10..15 {
+            manager.get_next_nonce("0xA").await?;
+        }
+        // Nonce 12 was handed out but its transaction never went out
+        manager.track_pending("0xA", pending(10, Duration::from_secs(60))).await?;
+        manager.track_pending("0xA", pending(11, Duration::from_secs(60))).await?;
+        manager.track_pending("0xA", pending(13, Duration::from_secs(1))).await?;
+        manager.track_pending("0xA", pending(14, Duration::ZERO)).await?;
+
+        let report = manager.reconcile("0xA", 11, Duration::from_secs(30)).await?;
+        assert_eq!(report.mined, 11);
+        assert_eq!(report.next, 15);
+        assert_eq!(report.gaps, vec![12]);
+        let stuck: Vec<u64> = report.stuck.iter().map(|pending| pending.nonce).collect();
+        assert_eq!(stuck, vec![11]);
+        assert!(manager.pending_tx("0xA", 10).await.is_none());
+
+        // A replacement takes over the nonce
+        let mut replacement = pending(11, Duration::ZERO);
+        replacement.replacements = 1;
+        manager.track_pending("0xA", replacement).await?;
+        assert_eq!(manager.pending_tx("0xA", 11).await.unwrap().replacements, 1);
+        assert_eq!(manager.pending("0xA").await.len(), 3);
+
+        let report = manager.reconcile("0xA", 15, Duration::ZERO).await?;
+        assert!(report.gaps.is_empty());
+        assert!(report.stuck.is_empty());
+        assert!(manager.pending("0xA").await.is_empty());
+
+        let report = manager.reconcile("0xB", 4, Duration::ZERO).await?;
+        assert_eq!((report.mined, report.next), (4, 4));
+        Ok(())
+    }
+}
@@ -0,0 +1,434 @@
+//! Idempotent execution with retries
+//!
+//! Every trade plan carries an `idem_key`. `ExecutionLedger` records each
+//! execution under that key before anything is submitted, so a plan that is
+//! delivered twice, retried by its producer, or replayed after a restart is
+//! not sent to the chain a second time: a completed execution returns its
+//! recorded receipt, and one still in flight is refused. The ledger can be
+//! backed by a JSON file so that records survive a crash; an execution found
+//! in flight on startup stays blocked until it is resolved, since its
+//! transaction may already have been broadcast.
+//!
+//! Within one execution, transient RPC errors such as timeouts, refused
+//! connections and rate limits are retried with exponential backoff and
+//! jitter, up to `RetryConfig::max_attempts`.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sniper_core::types::ExecReceipt;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Error fragments of failures worth retrying
+const TRANSIENT_ERRORS: [&str; 12] = [
+    "timed out",
+    "timeout",
+    "connection refused",
+    "connection reset",
+    "connection closed",
+    "error sending request",
+    "rate limit",
+    "too many requests",
+    "429",
+    "502",
+    "503",
+    "504",
+];
+
+/// Retry settings for transient errors
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// Attempts per execution, including the first
+    pub max_attempts: u32,
+    /// Backoff before the first retry
+    pub initial_backoff_ms: u64,
+    /// Upper bound of the backoff, before jitter
+    pub max_backoff_ms: u64,
+    /// Growth of the backoff per retry
+    pub multiplier: f64,
+    /// Random share of the backoff added or removed, in percent
+    pub jitter_pct: u8,
+    /// File the ledger is kept in; in memory only if unset
+    pub ledger_path: Option<String>,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 200,
+            max_backoff_ms: 2_000,
+            multiplier: 2.0,
+            jitter_pct: 20,
+            ledger_path: None,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Backoff before retry number `retry`, starting at 1, without jitter
+    pub fn base_backoff(&self, retry: u32) -> Duration {
+        let backoff = self.initial_backoff_ms as f64 * self.multiplier.max(1.0).powi(retry.saturating_sub(1) as i32);
+        Duration::from_millis(backoff.min(self.max_backoff_ms as f64) as u64)
+    }
+
+    /// Backoff before retry number `retry`, moved by `unit` in [-1, 1] times the jitter
+    pub fn backoff(&self, retry: u32, unit: f64) -> Duration {
+        let base = self.base_backoff(retry).as_millis() as f64;
+        let jitter = base * self.jitter_pct.min(100) as f64 / 100.0 * unit.clamp(-1.0, 1.0);
+        Duration::from_millis((base + jitter).max(0.0) as u64)
+    }
+}
+
+/// Whether an error is a transient RPC failure worth retrying
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    let message = format!("{:#}", error).to_lowercase();
+    TRANSIENT_ERRORS.iter().any(|fragment| message.contains(fragment))
+}
+
+/// State of a recorded execution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExecutionState {
+    /// Submission started and its outcome is not known yet
+    InFlight,
+    /// Submitted; the receipt is recorded
+    Completed,
+    /// Every attempt failed before anything was submitted
+    Failed,
+}
+
+/// Execution recorded under an idempotency key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionRecord {
+    pub idem_key: String,
+    pub state: ExecutionState,
+    pub attempts: u32,
+    pub receipt: Option<ExecReceipt>,
+    pub last_error: Option<String>,
+    pub started_at_ms: i64,
+    pub updated_at_ms: i64,
+}
+
+/// Ledger of executions by idempotency key
+pub struct ExecutionLedger {
+    records: Mutex<HashMap<String, ExecutionRecord>>,
+    path: Option<PathBuf>,
+    jitter: RandomState,
+}
+
+impl Default for ExecutionLedger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExecutionLedger {
+    /// Create a ledger kept in memory only
+    pub fn new() -> Self {
+        Self {
+            records: Mutex::new(HashMap::new()),
+            path: None,
+            jitter: RandomState::new(),
+        }
+    }
+
+    /// Open a ledger kept in a JSON file, loading the executions recorded there
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let records = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str::<Vec<ExecutionRecord>>(&contents)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let in_flight = records.iter().filter(|record| record.state == ExecutionState::InFlight).count();
+        if in_flight > 0 {
+            tracing::warn!("{} executions were in flight at shutdown and stay blocked until resolved", in_flight);
+        }
+        Ok(Self {
+            records: Mutex::new(records.into_iter().map(|record| (record.idem_key.clone(), record)).collect()),
+            path: Some(path),
+            jitter: RandomState::new(),
+        })
+    }
+
+    /// Open the ledger configured in `config`
+    pub fn from_config(config: &RetryConfig) -> Result<Self> {
+        match &config.ledger_path {
+            Some(path) => Self::open(path),
+            None => Ok(Self::new()),
+        }
+    }
+
+    /// Get the execution recorded under a key
+    pub fn record(&self, idem_key: &str) -> Option<ExecutionRecord> {
+        self.lock().get(idem_key).cloned()
+    }
+
+    /// Get the executions whose outcome is unknown
+    pub fn in_flight(&self) -> Vec<ExecutionRecord> {
+        self.lock()
+            .values()
+            .filter(|record| record.state == ExecutionState::InFlight)
+            .cloned()
+            .collect()
+    }
+
+    /// Resolve an execution left in flight, e.g. after finding its transaction on chain
+    ///
+    /// Without a receipt the execution is marked failed, which allows the plan
+    /// to be executed again.
+    pub fn resolve(&self, idem_key: &str, receipt: Option<ExecReceipt>) -> Result<()> {
+        self.update(idem_key, |record| {
+            record.state = if receipt.is_some() { ExecutionState::Completed } else { ExecutionState::Failed };
+            record.receipt = receipt;
+        })
+    }
+
+    /// Run `submit` once per idempotency key, retrying transient errors
+    ///
+    /// A key that already completed returns its recorded receipt without
+    /// submitting; a key still in flight is refused. A key whose previous
+    /// execution failed is executed again.
+    pub async fn execute<F, Fut>(&self, idem_key: &str, config: &RetryConfig, mut submit: F) -> Result<ExecReceipt>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<ExecReceipt>>,
+    {
+        if let Some(receipt) = self.begin(idem_key)? {
+            tracing::info!("{} already executed as {}, not submitting again", idem_key, receipt.tx_hash);
+            return Ok(receipt);
+        }
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            self.update(idem_key, |record| record.attempts = attempt)?;
+            match submit().await {
+                Ok(receipt) => {
+                    self.update(idem_key, |record| {
+                        record.state = ExecutionState::Completed;
+                        record.receipt = Some(receipt.clone());
+                    })?;
+                    return Ok(receipt);
+                }
+                Err(e) if attempt < config.max_attempts && is_transient(&e) => {
+                    let backoff = config.backoff(attempt, self.jitter_unit(idem_key, attempt));
+                    tracing::warn!("attempt {} of {} failed, retrying in {:?}: {:#}", attempt, idem_key, backoff, e);
+                    self.update(idem_key, |record| record.last_error = Some(format!("{:#}", e)))?;
+                    tokio::time::sleep(backoff).await;
+                }
+                Err(e) => {
+                    self.update(idem_key, |record| {
+                        record.state = ExecutionState::Failed;
+                        record.last_error = Some(format!("{:#}", e));
+                    })?;
+                    return Err(e.context(format!("Execution of {} failed after {} attempts", idem_key, attempt)));
+                }
+            }
+        }
+    }
+
+    /// Claim a key for execution, returning the receipt if it already completed
+    fn begin(&self, idem_key: &str) -> Result<Option<ExecReceipt>> {
+        let mut records = self.lock();
+        let now = now_ms();
+        match records.get_mut(idem_key) {
+            Some(record) if record.state == ExecutionState::Completed => return Ok(record.receipt.clone()),
+            Some(record) if record.state == ExecutionState::InFlight => {
+                return Err(anyhow::anyhow!(
+                    "Execution of {} is already in flight since {} ms",
+                    idem_key,
+                    record.started_at_ms
+                ));
+            }
+            Some(record) => {
+                record.state = ExecutionState::InFlight;
+                record.attempts = 0;
+                record.started_at_ms = now;
+                record.updated_at_ms = now;
+            }
+            None => {
+                records.insert(
+                    idem_key.to_string(),
+                    ExecutionRecord {
+                        idem_key: idem_key.to_string(),
+                        state: ExecutionState::InFlight,
+                        attempts: 0,
+                        receipt: None,
+                        last_error: None,
+                        started_at_ms: now,
+                        updated_at_ms: now,
+                    },
+                );
+            }
+        }
+        // Persisted before submitting, so a crash mid-submission leaves the key blocked
+        self.persist(&records)?;
+        Ok(None)
+    }
+
+    fn update(&self, idem_key: &str, change: impl FnOnce(&mut ExecutionRecord)) -> Result<()> {
+        let mut records = self.lock();
+        let record = records
+            .get_mut(idem_key)
+            .ok_or_else(|| anyhow::anyhow!("No execution recorded for {}", idem_key))?;
+        change(record);
+        record.updated_at_ms = now_ms();
+        self.persist(&records)
+    }
+
+    fn persist(&self, records: &HashMap<String, ExecutionRecord>) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut records: Vec<&ExecutionRecord> = records.values().collect();
+        records.sort_by(|a, b| a.idem_key.cmp(&b.idem_key));
+        // Written aside and renamed, so a crash never leaves a torn file
+        let temp = path.with_extension("tmp");
+        std::fs::write(&temp, serde_json::to_vec_pretty(&records)?)?;
+        std::fs::rename(&temp, path)?;
+        Ok(())
+    }
+
+    /// Random number in [-1, 1] for the jitter of a retry
+    fn jitter_unit(&self, idem_key: &str, attempt: u32) -> f64 {
+        let mut hasher = self.jitter.build_hasher();
+        hasher.write(idem_key.as_bytes());
+        hasher.write_u32(attempt);
+        hasher.write_i64(now_ms());
+        (hasher.finish() >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, ExecutionRecord>> {
+        self.records.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn receipt(tx_hash: &str) -> ExecReceipt {
+        ExecReceipt {
+            tx_hash: tx_hash.to_string(),
+            success: true,
+            block: 1,
+            gas_used: 21_000,
+            fees_paid_wei: 0,
+            failure_reason: None,
+            amount_out: None,
+            endpoint: None,
+        }
+    }
+
+    fn fast() -> RetryConfig {
+        RetryConfig {
+            initial_backoff_ms: 1,
+            max_backoff_ms: 2,
+            ..RetryConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_backoff_grows_and_stays_within_jitter() {
+        let config = RetryConfig::default();
+        assert_eq!(config.base_backoff(1), Duration::from_millis(200));
+        assert_eq!(config.base_backoff(2), Duration::from_millis(400));
+        assert_eq!(config.base_backoff(10), Duration::from_millis(2_000));
+        assert_eq!(config.backoff(1, 1.0), Duration::from_millis(240));
+        assert_eq!(config.backoff(1, -1.0), Duration::from_millis(160));
+
+        let ledger = ExecutionLedger::new();
+        for attempt in 1..20 {
+            let unit = ledger.jitter_unit("key", attempt);
+            assert!((-1.0..=1.0).contains(&unit));
+        }
+
+        assert!(is_transient(&anyhow::anyhow!("HTTP 429 Too Many Requests")));
+        assert!(is_transient(&anyhow::anyhow!("operation timed out").context("eth_sendRawTransaction")));
+        assert!(!is_transient(&anyhow::anyhow!("nonce too low")));
+    }
+
+    #[tokio::test]
+    async fn test_transient_errors_are_retried_once_per_key() {
+        let ledger = ExecutionLedger::new();
+        let calls = &AtomicU32::new(0);
+        let submit = move || async move {
+            match calls.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(anyhow::anyhow!("connection refused")),
+                _ => Ok(receipt("0xabc")),
+            }
+        };
+
+        let first = ledger.execute("plan-1", &fast(), submit).await.unwrap();
+        assert_eq!(first.tx_hash, "0xabc");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(ledger.record("plan-1").unwrap().attempts, 2);
+
+        // A duplicate delivery returns the recorded receipt without submitting
+        let again = ledger.execute("plan-1", &fast(), submit).await.unwrap();
+        assert_eq!(again.tx_hash, "0xabc");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_permanent_errors_fail_without_retry() {
+        let ledger = ExecutionLedger::new();
+        let calls = &AtomicU32::new(0);
+        let failing = move || async move {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err::<ExecReceipt, _>(anyhow::anyhow!("insufficient funds"))
+        };
+        assert!(ledger.execute("plan-2", &fast(), failing).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(ledger.record("plan-2").unwrap().state, ExecutionState::Failed);
+
+        let timeouts = move || async move {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err::<ExecReceipt, _>(anyhow::anyhow!("request timed out"))
+        };
+        assert!(ledger.execute("plan-3", &fast(), timeouts).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        // Failed executions may run again
+        let ok = ledger.execute("plan-2", &fast(), || async { Ok(receipt("0xdef")) }).await.unwrap();
+        assert_eq!(ok.tx_hash, "0xdef");
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_executions_survive_restart() {
+        let path = std::env::temp_dir().join(format!("sniper-exec-ledger-{}.json", now_ms()));
+        let ledger = ExecutionLedger::open(&path).unwrap();
+        ledger.execute("done", &fast(), || async { Ok(receipt("0x1")) }).await.unwrap();
+        // Simulate a crash after the key was claimed but before the outcome was known
+        assert!(ledger.begin("crashed").unwrap().is_none());
+        drop(ledger);
+
+        let reopened = ExecutionLedger::open(&path).unwrap();
+        assert_eq!(reopened.record("done").unwrap().receipt.unwrap().tx_hash, "0x1");
+        assert_eq!(reopened.in_flight().len(), 1);
+        let submit = || async { Ok(receipt("0x2")) };
+        assert!(reopened.execute("crashed", &fast(), submit).await.is_err());
+
+        reopened.resolve("crashed", None).unwrap();
+        assert_eq!(reopened.execute("crashed", &fast(), submit).await.unwrap().tx_hash, "0x2");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
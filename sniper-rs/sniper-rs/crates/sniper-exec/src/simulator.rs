@@ -0,0 +1,633 @@
+//! Pre-flight simulation of trade plans
+//!
+//! Before a plan is broadcast, `Simulator` dry-runs the swap it would send and
+//! reports whether it would pay out at least `min_out` and whether the token
+//! behaves like a honeypot or charges a transfer tax.
+//!
+//! Against a regular node the swap is run with `eth_call`: the router quote
+//! is checked against `min_out` and the swap itself must not revert. Transfer
+//! taxes cannot be seen this way, since the router reports the amounts it
+//! computed rather than those received. Against a fork of the chain, e.g.
+//! anvil or hardhat, the swap is executed for real from the impersonated
+//! sender, the tokens received are sold back, and both legs are measured
+//! against their quotes; the fork is reverted to its snapshot afterwards.
+
+use crate::exec_mempool::{address_word, encode_call, encode_swap, is_native, now_secs, uint_word};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sniper_chain::rpc::{format_quantity, JsonRpcClient};
+use sniper_chain::tx::parse_address;
+use sniper_core::types::TradePlan;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// `getAmountsOut(uint256,address[])`
+const GET_AMOUNTS_OUT: [u8; 4] = [0xd0, 0x6c, 0xa6, 0x1f];
+/// `balanceOf(address)`
+const BALANCE_OF: [u8; 4] = [0x70, 0xa0, 0x82, 0x31];
+/// `approve(address,uint256)`
+const APPROVE: [u8; 4] = [0x09, 0x5e, 0xa7, 0xb3];
+/// `swapExactTokensForTokensSupportingFeeOnTransferTokens(uint256,uint256,address[],address,uint256)`
+const SWAP_SUPPORTING_FEE: [u8; 4] = [0x5c, 0x11, 0xd7, 0x95];
+/// Balance given to the sender of simulated native swaps
+const SIMULATION_BALANCE_WEI: u128 = 1_000_000_000_000_000_000_000_000;
+
+/// Where plans are dry-run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SimulationBackend {
+    /// `eth_call` against the node, without transfer tax measurement
+    EthCall,
+    /// Executed and reverted on a fork exposing `evm_snapshot` and `anvil_impersonateAccount`
+    Fork,
+}
+
+/// Pre-flight simulation settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimulationConfig {
+    pub backend: SimulationBackend,
+    /// Block simulated against with `eth_call`
+    pub block: String,
+    /// Highest tax tolerated on buying the token, in percent
+    pub max_buy_tax_pct: f64,
+    /// Highest tax tolerated on selling the token back, in percent
+    pub max_sell_tax_pct: f64,
+    /// Wrapped native token, required for plans swapping from the native token
+    pub weth: Option<String>,
+    /// Time to wait for a fork to mine a simulated transaction
+    pub fork_receipt_timeout_ms: u64,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            backend: SimulationBackend::EthCall,
+            block: "latest".to_string(),
+            max_buy_tax_pct: 5.0,
+            max_sell_tax_pct: 10.0,
+            weth: None,
+            fork_receipt_timeout_ms: 5_000,
+        }
+    }
+}
+
+/// Problem found by a simulation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SimulationIssue {
+    /// The swap would pay out less than the plan's minimum
+    MinOutNotMet { expected_out: u128, min_out: u128 },
+    /// The buy reverted
+    BuyReverted(String),
+    /// The token can be bought but not sold
+    Honeypot(String),
+    BuyTaxTooHigh { tax_pct: f64 },
+    SellTaxTooHigh { tax_pct: f64 },
+}
+
+/// Outcome of a plan's pre-flight simulation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimulationReport {
+    pub backend: SimulationBackend,
+    /// Router quote for the plan's input
+    pub quoted_out: u128,
+    /// Output of the simulated swap: received on a fork, computed by the router otherwise
+    pub simulated_out: Option<u128>,
+    /// Share of the quote lost on buying, measured on forks only
+    pub buy_tax_pct: Option<f64>,
+    /// Share of the sell-back quote lost on selling, measured on forks only
+    pub sell_tax_pct: Option<f64>,
+    pub issues: Vec<SimulationIssue>,
+}
+
+impl SimulationReport {
+    /// Whether the plan is safe to broadcast
+    pub fn passed(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    fn add_issue(&mut self, issue: SimulationIssue) {
+        if !self.issues.contains(&issue) {
+            self.issues.push(issue);
+        }
+    }
+
+    /// One line listing the issues found
+    pub fn summary(&self) -> String {
+        if self.issues.is_empty() {
+            return "no issues".to_string();
+        }
+        self.issues
+            .iter()
+            .map(|issue| match issue {
+                SimulationIssue::MinOutNotMet { expected_out, min_out } => {
+                    format!("output {} below minimum {}", expected_out, min_out)
+                }
+                SimulationIssue::BuyReverted(reason) => format!("buy reverted: {}", reason),
+                SimulationIssue::Honeypot(reason) => format!("sell reverted: {}", reason),
+                SimulationIssue::BuyTaxTooHigh { tax_pct } => format!("buy tax {:.2}%", tax_pct),
+                SimulationIssue::SellTaxTooHigh { tax_pct } => format!("sell tax {:.2}%", tax_pct),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Dry-runs plans before they are broadcast
+pub struct Simulator {
+    rpc: Arc<JsonRpcClient>,
+    sender: String,
+    config: SimulationConfig,
+}
+
+impl Simulator {
+    /// Create a simulator running plans as `sender` through `rpc`, a node or a fork
+    pub fn new(rpc: Arc<JsonRpcClient>, sender: &str, config: SimulationConfig) -> Self {
+        Self {
+            rpc,
+            sender: sender.to_lowercase(),
+            config,
+        }
+    }
+
+    /// Get the simulation settings
+    pub fn config(&self) -> &SimulationConfig {
+        &self.config
+    }
+
+    /// Simulate a plan
+    ///
+    /// A swap that reverts or misses its checks is reported in the issues;
+    /// errors are left for failures to simulate at all, such as an
+    /// unreachable node.
+    pub async fn simulate(&self, plan: &TradePlan) -> Result<SimulationReport> {
+        let report = match self.config.backend {
+            SimulationBackend::EthCall => self.simulate_call(plan).await?,
+            SimulationBackend::Fork => self.simulate_fork(plan).await?,
+        };
+        if !report.passed() {
+            tracing::warn!("simulation of {} failed: {}", plan.idem_key, report.summary());
+        }
+        Ok(report)
+    }
+
+    async fn simulate_call(&self, plan: &TradePlan) -> Result<SimulationReport> {
+        let path = self.path(plan)?;
+        let quoted_out = self.amounts_out(&plan.router, plan.amount_in, &path).await?;
+        let mut report = self.report(SimulationBackend::EthCall, quoted_out, plan);
+
+        let (data, value) = encode_swap(plan, &self.sender_address()?, self.config.weth.as_deref(), deadline())?;
+        // Native swaps need the value in the sender's balance
+        let overrides = (value > 0).then(|| {
+            let mut overrides = serde_json::Map::new();
+            overrides.insert(self.sender.clone(), json!({"balance": format_quantity(SIMULATION_BALANCE_WEI)}));
+            Value::Object(overrides)
+        });
+        let call = json!({
+            "from": self.sender,
+            "to": plan.router,
+            "value": format_quantity(value),
+            "data": format!("0x{}", hex::encode(&data)),
+        });
+        match self.rpc.call(call, &self.config.block, overrides).await {
+            Ok(output) => report.simulated_out = last_word(&output),
+            Err(e) if is_revert(&e) => report.add_issue(revert_issue(plan, quoted_out, &format!("{:#}", e))),
+            Err(e) => return Err(e),
+        }
+        Ok(report)
+    }
+
+    async fn simulate_fork(&self, plan: &TradePlan) -> Result<SimulationReport> {
+        let snapshot: String = self.rpc.request("evm_snapshot", json!([])).await?;
+        let result = self.run_on_fork(plan).await;
+        let reverted: Result<bool> = self.rpc.request("evm_revert", json!([snapshot])).await;
+        match reverted {
+            Ok(true) => {}
+            Ok(false) => tracing::warn!("fork did not revert to snapshot {}", snapshot),
+            Err(e) => tracing::warn!("fork did not revert to snapshot {}: {}", snapshot, e),
+        }
+        result
+    }
+
+    async fn run_on_fork(&self, plan: &TradePlan) -> Result<SimulationReport> {
+        let path = self.path(plan)?;
+        let token_out = &plan.token_out;
+        let sell_to = &path[0];
+        let quoted_out = self.amounts_out(&plan.router, plan.amount_in, &path).await?;
+        let mut report = self.report(SimulationBackend::Fork, quoted_out, plan);
+
+        let _: Value = self.rpc.request("anvil_impersonateAccount", json!([self.sender])).await?;
+        let (data, value) = encode_swap(plan, &self.sender_address()?, self.config.weth.as_deref(), deadline())?;
+        if value > 0 {
+            let balance = format_quantity(SIMULATION_BALANCE_WEI);
+            let _: Value = self.rpc.request("anvil_setBalance", json!([self.sender, balance])).await?;
+        }
+
+        let before = self.balance_of(token_out).await?;
+        if let Err(reason) = self.transact(&plan.router, value, &data).await? {
+            report.add_issue(revert_issue(plan, quoted_out, &reason));
+            return Ok(report);
+        }
+        let received = self.balance_of(token_out).await?.saturating_sub(before);
+        report.simulated_out = Some(received);
+        // Replaces the quote check: what arrives is what counts
+        report.issues.clear();
+        if received < plan.min_out {
+            report.issues.push(SimulationIssue::MinOutNotMet {
+                expected_out: received,
+                min_out: plan.min_out,
+            });
+        }
+        let buy_tax_pct = loss_pct(quoted_out, received);
+        report.buy_tax_pct = Some(buy_tax_pct);
+        if buy_tax_pct > self.config.max_buy_tax_pct {
+            report.issues.push(SimulationIssue::BuyTaxTooHigh { tax_pct: buy_tax_pct });
+        }
+        if received == 0 {
+            return Ok(report);
+        }
+
+        // Sell everything received back along the reversed path
+        let sell_path = vec![token_out.clone(), sell_to.clone()];
+        let sell_quote = self.amounts_out(&plan.router, received, &sell_path).await?;
+        let approve = encode_call(APPROVE, &[address_word(&parse_address(&plan.router)?), uint_word(received)]);
+        if let Err(reason) = self.transact(token_out, 0, &approve).await? {
+            report.issues.push(SimulationIssue::Honeypot(format!("approval reverted: {}", reason)));
+            return Ok(report);
+        }
+        let proceeds_before = self.balance_of(sell_to).await?;
+        let sell = encode_call(
+            SWAP_SUPPORTING_FEE,
+            &[
+                uint_word(received),
+                uint_word(0),
+                // Offset of the path array: five head words
+                uint_word(0xa0),
+                address_word(&self.sender_address()?),
+                uint_word(deadline() as u128),
+                uint_word(2),
+                address_word(&parse_address(token_out)?),
+                address_word(&parse_address(sell_to)?),
+            ],
+        );
+        if let Err(reason) = self.transact(&plan.router, 0, &sell).await? {
+            report.issues.push(SimulationIssue::Honeypot(reason));
+            return Ok(report);
+        }
+        let proceeds = self.balance_of(sell_to).await?.saturating_sub(proceeds_before);
+        let sell_tax_pct = loss_pct(sell_quote, proceeds);
+        report.sell_tax_pct = Some(sell_tax_pct);
+        if sell_tax_pct > self.config.max_sell_tax_pct {
+            report.issues.push(SimulationIssue::SellTaxTooHigh { tax_pct: sell_tax_pct });
+        }
+        Ok(report)
+    }
+
+    fn report(&self, backend: SimulationBackend, quoted_out: u128, plan: &TradePlan) -> SimulationReport {
+        let mut issues = Vec::new();
+        if quoted_out < plan.min_out {
+            issues.push(SimulationIssue::MinOutNotMet {
+                expected_out: quoted_out,
+                min_out: plan.min_out,
+            });
+        }
+        SimulationReport {
+            backend,
+            quoted_out,
+            simulated_out: None,
+            buy_tax_pct: None,
+            sell_tax_pct: None,
+            issues,
+        }
+    }
+
+    /// Swap path of a plan, with the native token replaced by WETH
+    fn path(&self, plan: &TradePlan) -> Result<Vec<String>> {
+        let token_in = if is_native(&plan.token_in) {
+            self.config
+                .weth
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("Simulating swaps from the native token needs a WETH address"))?
+        } else {
+            plan.token_in.clone()
+        };
+        Ok(vec![token_in, plan.token_out.clone()])
+    }
+
+    async fn amounts_out(&self, router: &str, amount_in: u128, path: &[String]) -> Result<u128> {
+        let mut words = vec![uint_word(amount_in), uint_word(0x40), uint_word(path.len() as u128)];
+        for token in path {
+            words.push(address_word(&parse_address(token)?));
+        }
+        let call = json!({"to": router, "data": format!("0x{}", hex::encode(encode_call(GET_AMOUNTS_OUT, &words)))});
+        let output = self.rpc.call(call, &self.config.block, None).await?;
+        last_word(&output).ok_or_else(|| anyhow::anyhow!("getAmountsOut on {} returned no amounts", router))
+    }
+
+    async fn balance_of(&self, token: &str) -> Result<u128> {
+        let data = encode_call(BALANCE_OF, &[address_word(&self.sender_address()?)]);
+        let call = json!({"to": token, "data": format!("0x{}", hex::encode(data))});
+        let output = self.rpc.call(call, &self.config.block, None).await?;
+        last_word(&output).ok_or_else(|| anyhow::anyhow!("balanceOf on {} returned nothing", token))
+    }
+
+    /// Send a transaction from the impersonated sender, returning the revert
+    /// reason if it fails
+    async fn transact(&self, to: &str, value: u128, data: &[u8]) -> Result<Result<(), String>> {
+        let tx = json!({
+            "from": self.sender,
+            "to": to,
+            "value": format_quantity(value),
+            "data": format!("0x{}", hex::encode(data)),
+        });
+        let hash: String = match self.rpc.request("eth_sendTransaction", json!([tx])).await {
+            Ok(hash) => hash,
+            // Forks reject transactions that revert during gas estimation
+            Err(e) if is_revert(&e) => return Ok(Err(format!("{:#}", e))),
+            Err(e) => return Err(e),
+        };
+        let timeout = Duration::from_millis(self.config.fork_receipt_timeout_ms);
+        let receipt = tokio::time::timeout(timeout, async {
+            loop {
+                if let Some(receipt) = self.rpc.transaction_receipt(&hash).await? {
+                    return Ok::<_, anyhow::Error>(receipt);
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .map_err(|_| anyhow::anyhow!("Fork did not mine {} within {:?}", hash, timeout))??;
+        Ok(if receipt.succeeded() { Ok(()) } else { Err(format!("{} reverted", hash)) })
+    }
+
+    fn sender_address(&self) -> Result<[u8; 20]> {
+        parse_address(&self.sender)
+    }
+}
+
+/// Issue for a reverted buy; the router's own slippage check means `min_out` was missed
+fn revert_issue(plan: &TradePlan, quoted_out: u128, reason: &str) -> SimulationIssue {
+    if reason.contains("INSUFFICIENT_OUTPUT_AMOUNT") {
+        SimulationIssue::MinOutNotMet {
+            expected_out: quoted_out,
+            min_out: plan.min_out,
+        }
+    } else {
+        SimulationIssue::BuyReverted(reason.to_string())
+    }
+}
+
+fn is_revert(error: &anyhow::Error) -> bool {
+    format!("{:#}", error).to_lowercase().contains("revert")
+}
+
+/// Last 32-byte word of ABI return data, e.g. the final amount of `uint256[]`
+fn last_word(output: &[u8]) -> Option<u128> {
+    if output.len() < 32 || output.len() % 32 != 0 {
+        return None;
+    }
+    let word = &output[output.len() - 32..];
+    if word[..16].iter().any(|byte| *byte != 0) {
+        return Some(u128::MAX);
+    }
+    Some(u128::from_be_bytes(word[16..].try_into().ok()?))
+}
+
+/// Share of `expected` missing from `actual`, in percent
+fn loss_pct(expected: u128, actual: u128) -> f64 {
+    if expected == 0 {
+        return 0.0;
+    }
+    expected.saturating_sub(actual) as f64 / expected as f64 * 100.0
+}
+
+fn deadline() -> u64 {
+    now_secs() + 300
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec_mempool::{MempoolConfig, MempoolExecutor};
+    use crate::{ExecError, Executor};
+    use axum::{routing::post, Extension, Json, Router};
+    use sniper_chain::{HedgeConfig, ProviderPool, RpcEndpoint};
+    use sniper_core::types::{ChainRef, ExecMode, ExitRules, GasPolicy};
+    use sniper_keys::LocalSigner;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    const KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+    const SENDER: &str = "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23";
+    const ROUTER: &str = "0x7a250d5630b4cf539739df2c5dacb4c659f2488d";
+    const TOKEN_IN: &str = "0x1111111111111111111111111111111111111111";
+    const TOKEN_OUT: &str = "0x2222222222222222222222222222222222222222";
+    const ETH: u128 = 1_000_000_000_000_000_000;
+
+    /// Pool pricing TOKEN_OUT at half a TOKEN_IN, with optional transfer taxes
+    #[derive(Default)]
+    struct MockChain {
+        buy_tax_pct: u128,
+        sell_tax_pct: u128,
+        /// Sells revert once mined
+        honeypot: bool,
+        /// Revert reason of swaps run with `eth_call`
+        call_revert: Option<String>,
+        balances: Mutex<HashMap<String, u128>>,
+        receipts: Mutex<Vec<bool>>,
+        reverted: AtomicBool,
+        raw_transactions: AtomicUsize,
+    }
+
+    fn word(data: &[u8], index: usize) -> u128 {
+        u128::from_be_bytes(data[4 + 32 * index + 16..4 + 32 * (index + 1)].try_into().unwrap())
+    }
+
+    fn address(data: &[u8], index: usize) -> String {
+        format!("0x{}", hex::encode(&data[4 + 32 * index + 12..4 + 32 * (index + 1)]))
+    }
+
+    fn amounts(amount_in: u128, amount_out: u128) -> Value {
+        let words = [uint_word(0x20), uint_word(2), uint_word(amount_in), uint_word(amount_out)];
+        json!(format!("0x{}", hex::encode(words.concat())))
+    }
+
+    fn credit(chain: &MockChain, token: &str, amount: u128) {
+        *chain.balances.lock().unwrap().entry(token.to_string()).or_default() += amount;
+    }
+
+    async fn handle(Extension(chain): Extension<Arc<MockChain>>, Json(request): Json<Value>) -> Json<Value> {
+        let params = &request["params"];
+        let error = |message: &str| Json(json!({"jsonrpc": "2.0", "id": request["id"], "error": {"code": 3, "message": message}}));
+        let data = |call: &Value| hex::decode(call["data"].as_str().unwrap().trim_start_matches("0x")).unwrap();
+        let result = match request["method"].as_str().unwrap() {
+            "eth_call" => {
+                let data = data(&params[0]);
+                match data[..4] {
+                    [0xd0, 0x6c, 0xa6, 0x1f] if address(&data, 3) == TOKEN_OUT => amounts(word(&data, 0), word(&data, 0) / 2),
+                    [0xd0, 0x6c, 0xa6, 0x1f] => amounts(word(&data, 0), word(&data, 0) * 2),
+                    [0x70, 0xa0, 0x82, 0x31] => {
+                        let balances = chain.balances.lock().unwrap();
+                        let balance = balances.get(params[0]["to"].as_str().unwrap()).copied().unwrap_or(0);
+                        json!(format!("0x{}", hex::encode(uint_word(balance))))
+                    }
+                    _ => match &chain.call_revert {
+                        Some(reason) => return error(&format!("execution reverted: {}", reason)),
+                        None => amounts(word(&data, 0), word(&data, 0) * 2),
+                    },
+                }
+            }
+            "eth_sendTransaction" => {
+                let data = data(&params[0]);
+                let mut success = true;
+                match data[..4] {
+                    [0x38, 0xed, 0x17, 0x39] => {
+                        credit(&chain, TOKEN_OUT, word(&data, 0) * 2 * (100 - chain.buy_tax_pct) / 100);
+                    }
+                    [0x5c, 0x11, 0xd7, 0x95] if chain.honeypot => success = false,
+                    [0x5c, 0x11, 0xd7, 0x95] => {
+                        credit(&chain, TOKEN_IN, word(&data, 0) * (100 - chain.sell_tax_pct) / 100 / 2);
+                    }
+                    _ => {}
+                }
+                let mut receipts = chain.receipts.lock().unwrap();
+                receipts.push(success);
+                json!(format!("0x{:x}", receipts.len() - 1))
+            }
+            "eth_getTransactionReceipt" => {
+                let index = parse_quantity_str(params[0].as_str().unwrap());
+                let status = if chain.receipts.lock().unwrap()[index] { "0x1" } else { "0x0" };
+                json!({"transactionHash": params[0], "blockNumber": "0x10", "status": status, "gasUsed": "0x30d40"})
+            }
+            "eth_sendRawTransaction" => {
+                chain.raw_transactions.fetch_add(1, Ordering::SeqCst);
+                json!("0xabc")
+            }
+            "eth_estimateGas" => json!("0x30d40"),
+            "evm_snapshot" => json!("0x1"),
+            "evm_revert" => {
+                chain.reverted.store(true, Ordering::SeqCst);
+                json!(true)
+            }
+            "anvil_impersonateAccount" | "anvil_setBalance" => Value::Null,
+            method => return error(method),
+        };
+        Json(json!({"jsonrpc": "2.0", "id": request["id"], "result": result}))
+    }
+
+    fn parse_quantity_str(quantity: &str) -> usize {
+        usize::from_str_radix(quantity.trim_start_matches("0x"), 16).unwrap()
+    }
+
+    async fn rpc(chain: Arc<MockChain>) -> Arc<JsonRpcClient> {
+        let app = Router::new().route("/", post(handle)).layer(Extension(chain));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let pool = ProviderPool::new(vec![RpcEndpoint { name: "mock".to_string(), url }], HedgeConfig::default());
+        Arc::new(JsonRpcClient::new(Arc::new(pool)).unwrap())
+    }
+
+    fn plan(min_out: u128) -> TradePlan {
+        TradePlan {
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            router: ROUTER.to_string(),
+            token_in: TOKEN_IN.to_string(),
+            token_out: TOKEN_OUT.to_string(),
+            amount_in: ETH,
+            min_out,
+            mode: ExecMode::Mempool,
+            gas: GasPolicy {
+                max_fee_gwei: 50,
+                max_priority_gwei: 2,
+            },
+            exits: ExitRules::default(),
+            idem_key: "simulation-test-key".to_string(),
+            quote: None,
+            correlation_id: None,
+        }
+    }
+
+    fn fork() -> SimulationConfig {
+        SimulationConfig {
+            backend: SimulationBackend::Fork,
+            ..SimulationConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_eth_call_checks_min_out_and_reverts() {
+        let simulator = Simulator::new(rpc(Arc::new(MockChain::default())).await, SENDER, SimulationConfig::default());
+        let report = simulator.simulate(&plan(ETH)).await.unwrap();
+        assert!(report.passed(), "{}", report.summary());
+        assert_eq!((report.quoted_out, report.simulated_out), (2 * ETH, Some(2 * ETH)));
+        assert_eq!(report.buy_tax_pct, None);
+
+        let report = simulator.simulate(&plan(3 * ETH)).await.unwrap();
+        assert_eq!(
+            report.issues,
+            vec![SimulationIssue::MinOutNotMet {
+                expected_out: 2 * ETH,
+                min_out: 3 * ETH
+            }]
+        );
+
+        let chain = MockChain {
+            call_revert: Some("TransferHelper: TRANSFER_FROM_FAILED".to_string()),
+            ..MockChain::default()
+        };
+        let simulator = Simulator::new(rpc(Arc::new(chain)).await, SENDER, SimulationConfig::default());
+        let report = simulator.simulate(&plan(ETH)).await.unwrap();
+        assert!(matches!(&report.issues[..], [SimulationIssue::BuyReverted(reason)] if reason.contains("TRANSFER_FROM_FAILED")));
+    }
+
+    #[tokio::test]
+    async fn test_fork_measures_taxes_and_honeypots() {
+        let chain = Arc::new(MockChain {
+            buy_tax_pct: 3,
+            sell_tax_pct: 20,
+            ..MockChain::default()
+        });
+        let simulator = Simulator::new(rpc(chain.clone()).await, SENDER, fork());
+        let report = simulator.simulate(&plan(ETH)).await.unwrap();
+        assert_eq!(report.simulated_out, Some(2 * ETH * 97 / 100));
+        assert!((report.buy_tax_pct.unwrap() - 3.0).abs() < 1e-9);
+        assert!((report.sell_tax_pct.unwrap() - 20.0).abs() < 1e-9);
+        assert!(matches!(&report.issues[..], [SimulationIssue::SellTaxTooHigh { .. }]));
+        assert!(chain.reverted.load(Ordering::SeqCst));
+
+        let honeypot = Arc::new(MockChain {
+            honeypot: true,
+            ..MockChain::default()
+        });
+        let simulator = Simulator::new(rpc(honeypot).await, SENDER, fork());
+        let report = simulator.simulate(&plan(ETH)).await.unwrap();
+        assert_eq!(report.buy_tax_pct, Some(0.0));
+        assert!(matches!(&report.issues[..], [SimulationIssue::Honeypot(_)]));
+    }
+
+    #[tokio::test]
+    async fn test_executor_refuses_plans_failing_simulation() {
+        let chain = Arc::new(MockChain {
+            call_revert: Some("TRADING_NOT_ENABLED".to_string()),
+            ..MockChain::default()
+        });
+        let rpc = rpc(chain.clone()).await;
+        let signer = Arc::new(LocalSigner::from_hex(KEY).unwrap());
+        let mempool = Arc::new(MempoolExecutor::new(rpc.clone(), signer, MempoolConfig::default()));
+        let executor = Executor::new()
+            .with_mempool(mempool)
+            .with_simulator(Simulator::new(rpc, SENDER, SimulationConfig::default()));
+
+        let error = executor.execute_trade(&plan(ETH)).await.unwrap_err();
+        assert!(matches!(error, ExecError::SimulationFailed { .. }));
+        assert!(error.to_string().contains("TRADING_NOT_ENABLED"));
+        assert_eq!(chain.raw_transactions.load(Ordering::SeqCst), 0);
+    }
+}
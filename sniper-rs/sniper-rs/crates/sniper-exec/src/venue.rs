@@ -0,0 +1,368 @@
+//! Execution venue cost model and selection policy
+//!
+//! This module learns per-venue costs (relay tips, failure probability and
+//! inclusion latency) from historical receipts, and picks the venue with the
+//! highest expected value for each trade plan.
+
+use serde::{Deserialize, Serialize};
+use sniper_core::types::{ExecMode, ExecReceipt, TradePlan};
+use std::collections::{HashMap, VecDeque};
+
+/// Weight of a venue's prior profile, in samples, when blending with history
+const PRIOR_WEIGHT: f64 = 5.0;
+
+/// Samples needed before the latency distribution replaces the prior
+const MIN_LATENCY_SAMPLES: usize = 5;
+
+/// Default number of outcomes kept per venue
+const DEFAULT_MAX_SAMPLES: usize = 500;
+
+/// Outcome of a submission, recorded after the receipt arrives
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VenueOutcome {
+    pub mode: ExecMode,
+    pub receipt: ExecReceipt,
+    /// Tip paid to the relay or block builder, zero if none
+    pub tip_wei: u128,
+    /// Time from submission until inclusion or failure
+    pub inclusion_latency_ms: u64,
+}
+
+/// Expected costs of a venue used until enough history is recorded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VenueProfile {
+    pub failure_probability: f64,
+    pub tip_wei: f64,
+    pub fee_wei: f64,
+    pub latency_p50_ms: u64,
+    pub latency_p90_ms: u64,
+}
+
+impl VenueProfile {
+    /// Default profile for a venue
+    pub fn for_mode(mode: &ExecMode) -> Self {
+        match mode {
+            ExecMode::Mempool => Self {
+                failure_probability: 0.10,
+                tip_wei: 0.0,
+                fee_wei: 2.1e15,
+                latency_p50_ms: 12_000,
+                latency_p90_ms: 36_000,
+            },
+            // Private relays protect from the public mempool but wait for a cooperating builder
+            ExecMode::Private => Self {
+                failure_probability: 0.05,
+                tip_wei: 0.0,
+                fee_wei: 2.1e15,
+                latency_p50_ms: 24_000,
+                latency_p90_ms: 60_000,
+            },
+            // Bundles land in the next block or not at all, and pay no gas when dropped
+            ExecMode::Bundle => Self {
+                failure_probability: 0.40,
+                tip_wei: 1e15,
+                fee_wei: 1.26e15,
+                latency_p50_ms: 12_000,
+                latency_p90_ms: 24_000,
+            },
+        }
+    }
+}
+
+/// Estimated costs of a venue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VenueEstimate {
+    pub mode: ExecMode,
+    pub samples: usize,
+    pub failure_probability: f64,
+    pub expected_tip_wei: f64,
+    pub expected_fee_wei: f64,
+    pub latency_p50_ms: u64,
+    pub latency_p90_ms: u64,
+}
+
+/// Per-venue cost model learned from historical receipts
+#[derive(Debug, Clone)]
+pub struct VenueCostModel {
+    outcomes: HashMap<ExecMode, VecDeque<VenueOutcome>>,
+    priors: HashMap<ExecMode, VenueProfile>,
+    max_samples: usize,
+}
+
+impl Default for VenueCostModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VenueCostModel {
+    /// Create a cost model with the default venue profiles
+    pub fn new() -> Self {
+        let priors = [ExecMode::Mempool, ExecMode::Private, ExecMode::Bundle]
+            .into_iter()
+            .map(|mode| {
+                let profile = VenueProfile::for_mode(&mode);
+                (mode, profile)
+            })
+            .collect();
+        Self {
+            outcomes: HashMap::new(),
+            priors,
+            max_samples: DEFAULT_MAX_SAMPLES,
+        }
+    }
+
+    /// Replace the prior profile of a venue
+    pub fn set_prior(&mut self, mode: ExecMode, profile: VenueProfile) {
+        self.priors.insert(mode, profile);
+    }
+
+    /// Set the number of outcomes kept per venue, dropping the oldest beyond it
+    pub fn set_max_samples(&mut self, max_samples: usize) {
+        self.max_samples = max_samples.max(1);
+        for outcomes in self.outcomes.values_mut() {
+            while outcomes.len() > self.max_samples {
+                outcomes.pop_front();
+            }
+        }
+    }
+
+    /// Record the outcome of a submission
+    pub fn record(&mut self, outcome: VenueOutcome) {
+        let outcomes = self.outcomes.entry(outcome.mode.clone()).or_default();
+        outcomes.push_back(outcome);
+        while outcomes.len() > self.max_samples {
+            outcomes.pop_front();
+        }
+    }
+
+    /// Number of outcomes recorded for a venue
+    pub fn samples(&self, mode: &ExecMode) -> usize {
+        self.outcomes.get(mode).map(|outcomes| outcomes.len()).unwrap_or(0)
+    }
+
+    /// Estimate the costs of a venue, blending its history with its prior profile
+    pub fn estimate(&self, mode: &ExecMode) -> VenueEstimate {
+        let prior = self
+            .priors
+            .get(mode)
+            .cloned()
+            .unwrap_or_else(|| VenueProfile::for_mode(mode));
+        let empty = VecDeque::new();
+        let outcomes = self.outcomes.get(mode).unwrap_or(&empty);
+        let n = outcomes.len() as f64;
+
+        let blend = |observed: f64, prior: f64| (observed + prior * PRIOR_WEIGHT) / (n + PRIOR_WEIGHT);
+        let failures = outcomes.iter().filter(|o| !o.receipt.success).count() as f64;
+        let tips: f64 = outcomes.iter().map(|o| o.tip_wei as f64).sum();
+        let fees: f64 = outcomes.iter().map(|o| o.receipt.fees_paid_wei as f64).sum();
+
+        let (latency_p50_ms, latency_p90_ms) = if outcomes.len() >= MIN_LATENCY_SAMPLES {
+            let mut latencies: Vec<u64> = outcomes.iter().map(|o| o.inclusion_latency_ms).collect();
+            latencies.sort_unstable();
+            (quantile(&latencies, 0.5), quantile(&latencies, 0.9))
+        } else {
+            (prior.latency_p50_ms, prior.latency_p90_ms)
+        };
+
+        VenueEstimate {
+            mode: mode.clone(),
+            samples: outcomes.len(),
+            failure_probability: blend(failures, prior.failure_probability),
+            expected_tip_wei: blend(tips, prior.tip_wei),
+            expected_fee_wei: blend(fees, prior.fee_wei),
+            latency_p50_ms,
+            latency_p90_ms,
+        }
+    }
+}
+
+/// Value of a sorted sample at a quantile
+fn quantile(sorted: &[u64], q: f64) -> u64 {
+    let index = ((sorted.len() - 1) as f64 * q).round() as usize;
+    sorted[index]
+}
+
+/// Policy scoring venues by the expected value of executing a plan through them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VenuePolicy {
+    /// Expected edge of a trade, in basis points of its input amount
+    pub edge_bps: f64,
+    /// Value lost to sandwiching in the public mempool, in basis points
+    pub mempool_mev_bps: f64,
+    /// Price drift cost while waiting for inclusion, in basis points per second
+    pub latency_cost_bps_per_sec: f64,
+    /// Venues the policy may pick from
+    pub venues: Vec<ExecMode>,
+}
+
+impl Default for VenuePolicy {
+    fn default() -> Self {
+        Self {
+            edge_bps: 100.0,
+            mempool_mev_bps: 50.0,
+            latency_cost_bps_per_sec: 0.5,
+            venues: vec![ExecMode::Mempool, ExecMode::Private, ExecMode::Bundle],
+        }
+    }
+}
+
+impl VenuePolicy {
+    /// Expected value in wei of executing a plan through a venue
+    pub fn expected_value_wei(&self, plan: &TradePlan, estimate: &VenueEstimate) -> f64 {
+        let notional = plan.amount_in as f64;
+        let edge = notional * self.edge_bps / 10_000.0;
+        let mev_loss = match estimate.mode {
+            ExecMode::Mempool => notional * self.mempool_mev_bps / 10_000.0,
+            ExecMode::Private | ExecMode::Bundle => 0.0,
+        };
+        let latency_cost =
+            notional * self.latency_cost_bps_per_sec / 10_000.0 * estimate.latency_p50_ms as f64 / 1000.0;
+
+        (1.0 - estimate.failure_probability) * (edge - mev_loss - latency_cost)
+            - estimate.expected_fee_wei
+            - estimate.expected_tip_wei
+    }
+
+    /// Pick the venue with the highest expected value for a plan
+    pub fn select(&self, plan: &TradePlan, model: &VenueCostModel) -> Option<VenueSelection> {
+        let candidates: Vec<VenueCandidate> = self
+            .venues
+            .iter()
+            .map(|mode| {
+                let estimate = model.estimate(mode);
+                let expected_value_wei = self.expected_value_wei(plan, &estimate);
+                VenueCandidate {
+                    estimate,
+                    expected_value_wei,
+                }
+            })
+            .collect();
+
+        let best = candidates
+            .iter()
+            .max_by(|a, b| a.expected_value_wei.total_cmp(&b.expected_value_wei))?;
+        Some(VenueSelection {
+            mode: best.estimate.mode.clone(),
+            expected_value_wei: best.expected_value_wei,
+            candidates: candidates.clone(),
+        })
+    }
+}
+
+/// A venue scored for a plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VenueCandidate {
+    pub estimate: VenueEstimate,
+    pub expected_value_wei: f64,
+}
+
+/// Venue picked for a plan, with the scores of every candidate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VenueSelection {
+    pub mode: ExecMode,
+    pub expected_value_wei: f64,
+    pub candidates: Vec<VenueCandidate>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sniper_core::types::{ChainRef, ExitRules, GasPolicy};
+
+    fn plan() -> TradePlan {
+        TradePlan {
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            router: "0xRouter".to_string(),
+            token_in: "0xTokenIn".to_string(),
+            token_out: "0xTokenOut".to_string(),
+            amount_in: 1000000000000000000, // 1 ETH
+            min_out: 900000000000000000,
+            mode: ExecMode::Mempool,
+            gas: GasPolicy {
+                max_fee_gwei: 50,
+                max_priority_gwei: 2,
+            },
+            exits: ExitRules::default(),
+            idem_key: "venue-test-key".to_string(),
+            quote: None,
+            correlation_id: None,
+        }
+    }
+
+    fn outcome(mode: ExecMode, success: bool, latency_ms: u64) -> VenueOutcome {
+        VenueOutcome {
+            mode,
+            receipt: ExecReceipt {
+                tx_hash: "0xtx".to_string(),
+                success,
+                block: 12345678,
+                gas_used: 100000,
+                fees_paid_wei: 2100000000000000,
+                failure_reason: None,
+                amount_out: None,
+                endpoint: None,
+            },
+            tip_wei: 0,
+            inclusion_latency_ms: latency_ms,
+        }
+    }
+
+    #[test]
+    fn test_estimate_learns_from_receipts() {
+        let mut model = VenueCostModel::new();
+        let prior = model.estimate(&ExecMode::Mempool);
+        assert_eq!(prior.samples, 0);
+        assert!((prior.failure_probability - 0.10).abs() < 1e-9);
+
+        for i in 0..10 {
+            model.record(outcome(ExecMode::Mempool, i < 5, 1000 * (i + 1)));
+        }
+        let estimate = model.estimate(&ExecMode::Mempool);
+        assert_eq!(estimate.samples, 10);
+        // 5 failures in 10 samples blended with 5 prior samples at 10%
+        assert!((estimate.failure_probability - 5.5 / 15.0).abs() < 1e-9);
+        assert_eq!(estimate.latency_p50_ms, 6000);
+        assert_eq!(estimate.latency_p90_ms, 9000);
+
+        model.set_max_samples(4);
+        assert_eq!(model.samples(&ExecMode::Mempool), 4);
+    }
+
+    #[test]
+    fn test_selection_follows_expected_value() {
+        let policy = VenuePolicy::default();
+        let mut model = VenueCostModel::new();
+
+        // Without history the private relay avoids sandwich losses at low failure odds
+        let selection = policy.select(&plan(), &model).unwrap();
+        assert_eq!(selection.mode, ExecMode::Private);
+        assert_eq!(selection.candidates.len(), 3);
+
+        // A relay that keeps failing loses its edge to bundles
+        for _ in 0..20 {
+            model.record(outcome(ExecMode::Private, false, 60_000));
+        }
+        let selection = policy.select(&plan(), &model).unwrap();
+        assert_eq!(selection.mode, ExecMode::Bundle);
+    }
+
+    #[test]
+    fn test_selection_restricted_to_allowed_venues() {
+        let policy = VenuePolicy {
+            venues: vec![ExecMode::Mempool],
+            ..VenuePolicy::default()
+        };
+        let selection = policy.select(&plan(), &VenueCostModel::new()).unwrap();
+        assert_eq!(selection.mode, ExecMode::Mempool);
+
+        let policy = VenuePolicy {
+            venues: Vec::new(),
+            ..VenuePolicy::default()
+        };
+        assert!(policy.select(&plan(), &VenueCostModel::new()).is_none());
+    }
+}
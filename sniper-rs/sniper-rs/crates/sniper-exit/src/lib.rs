@@ -1,3 +1,298 @@
+//! Exit rules for open positions.
+//!
+//! An [`ExitMonitor`] tracks open positions against the [`ExitRules`] of the
+//! plan that opened them. Every price update moves each position's best
+//! price for the trailing stop and checks its stop-loss, trailing stop and
+//! take-profit, in that order. A triggered position stops being tracked and
+//! comes back as an [`ExitSignal`] carrying the plan that closes it; once the
+//! close is confirmed, [`ExitMonitor::record_closed`] publishes a
+//! `PositionClosed` event on the bus, if one is attached. The monitor is a
+//! cloneable handle, so the executor and the portfolio can share one.
+
+pub mod stop_loss;
+pub mod take_profit;
+
+use serde::{Deserialize, Serialize};
+use sniper_core::bus::InMemoryBus;
+use sniper_core::events::TradingEvent;
+use sniper_core::types::{ChainRef, ExitRules, TradePlan};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+pub use stop_loss::{stop_loss_hit, trailing_stop_hit};
+pub use take_profit::take_profit_hit;
+
+/// Default slippage accepted by closing plans, in percent
+pub const DEFAULT_EXIT_SLIPPAGE_PCT: f64 = 5.0;
+
+/// Raw units per whole token assumed when valuing realized PnL
+const TOKEN_UNIT: f64 = 1e18;
+
+/// Which rule closed a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitKind {
+    StopLoss,
+    TrailingStop,
+    TakeProfit,
+}
+
+/// A position watched against its exit rules
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackedPosition {
+    pub position_id: String,
+    pub symbol: String,
+    pub chain: ChainRef,
+    pub side: String, // "long" or "short"
+    /// Entry price, in the units prices are reported in
+    pub entry_price: f64,
+    /// Size held, in raw units of the held token
+    pub amount: u128,
+    pub rules: ExitRules,
+    /// Best price since entry: highest for a long, lowest for a short
+    pub peak_price: f64,
+    /// Plan that opened the position, reversed to close it
+    pub entry: Option<TradePlan>,
+}
+
+impl TrackedPosition {
+    /// Track a position from its entry, with the best price starting at the entry price
+    pub fn new(position_id: &str, symbol: &str, chain: ChainRef, side: &str, entry_price: f64, amount: u128, rules: ExitRules) -> Self {
+        Self {
+            position_id: position_id.to_string(),
+            symbol: symbol.to_string(),
+            chain,
+            side: side.to_string(),
+            entry_price,
+            amount,
+            rules,
+            peak_price: entry_price,
+            entry: None,
+        }
+    }
+
+    /// Track a position opened by `plan`, holding `amount` of its output token
+    ///
+    /// Prices are in `token_in` per `token_out` raw units, so the entry
+    /// price is the plan's input over the amount received.
+    pub fn from_plan(plan: &TradePlan, amount: u128) -> Self {
+        let entry_price = if amount > 0 { plan.amount_in as f64 / amount as f64 } else { 0.0 };
+        Self {
+            entry: Some(plan.clone()),
+            ..Self::new(
+                &plan.idem_key,
+                &format!("{}/{}", plan.token_out, plan.token_in),
+                plan.chain.clone(),
+                "long",
+                entry_price,
+                amount,
+                plan.exits.clone(),
+            )
+        }
+    }
+
+    /// Return from entry to `price`, in percent, positive when in profit
+    pub fn return_pct(&self, price: f64) -> f64 {
+        if self.entry_price <= 0.0 {
+            return 0.0;
+        }
+        let direction = if self.side == "short" { -1.0 } else { 1.0 };
+        (price - self.entry_price) / self.entry_price * 100.0 * direction
+    }
+
+    /// Realized PnL of closing the whole position at `price`, in whole quote tokens
+    pub fn realized_pnl(&self, price: f64) -> f64 {
+        let direction = if self.side == "short" { -1.0 } else { 1.0 };
+        (price - self.entry_price) * self.amount as f64 / TOKEN_UNIT * direction
+    }
+
+    /// Move the best price, then check the rules against `price`
+    fn observe(&mut self, price: f64) -> Option<ExitKind> {
+        self.peak_price = if self.side == "short" { self.peak_price.min(price) } else { self.peak_price.max(price) };
+        let return_pct = self.return_pct(price);
+        if self.rules.stop_loss_pct.is_some_and(|pct| stop_loss_hit(return_pct, pct)) {
+            Some(ExitKind::StopLoss)
+        } else if self
+            .rules
+            .trailing_pct
+            .is_some_and(|pct| trailing_stop_hit(&self.side, self.peak_price, price, pct))
+        {
+            Some(ExitKind::TrailingStop)
+        } else if self.rules.take_profit_pct.is_some_and(|pct| take_profit_hit(return_pct, pct)) {
+            Some(ExitKind::TakeProfit)
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether any exit rule is set
+pub fn has_rules(rules: &ExitRules) -> bool {
+    rules.stop_loss_pct.is_some() || rules.trailing_pct.is_some() || rules.take_profit_pct.is_some()
+}
+
+/// A position whose exit rule triggered
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExitSignal {
+    pub kind: ExitKind,
+    /// Price the rule triggered at
+    pub price: f64,
+    pub return_pct: f64,
+    pub position: TrackedPosition,
+    /// Plan selling the position back into its entry token, when it was opened by a plan
+    pub plan: Option<TradePlan>,
+}
+
+/// Exit monitor shared by the executor and the portfolio
+#[derive(Clone)]
+pub struct ExitMonitor {
+    positions: Arc<RwLock<HashMap<String, TrackedPosition>>>,
+    slippage_pct: f64,
+    bus: Option<InMemoryBus>,
+}
+
+impl Default for ExitMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExitMonitor {
+    /// Create a monitor tracking nothing
+    pub fn new() -> Self {
+        Self {
+            positions: Arc::default(),
+            slippage_pct: DEFAULT_EXIT_SLIPPAGE_PCT,
+            bus: None,
+        }
+    }
+
+    /// Publish `PositionClosed` events on a bus
+    pub fn with_bus(mut self, bus: InMemoryBus) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Set the slippage closing plans accept, in percent
+    pub fn with_slippage(mut self, slippage_pct: f64) -> Self {
+        self.slippage_pct = slippage_pct;
+        self
+    }
+
+    /// Start watching a position, replacing any tracked under the same ID
+    pub fn track(&self, position: TrackedPosition) {
+        self.positions.write().unwrap().insert(position.position_id.clone(), position);
+    }
+
+    /// Update the entry and size of a tracked position, keeping its rules and best price
+    ///
+    /// Returns whether the position is tracked.
+    pub fn update(&self, position_id: &str, entry_price: f64, amount: u128) -> bool {
+        match self.positions.write().unwrap().get_mut(position_id) {
+            Some(position) => {
+                position.entry_price = entry_price;
+                position.amount = amount;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Replace the exit rules of a tracked position, returning whether it is tracked
+    pub fn set_rules(&self, position_id: &str, rules: ExitRules) -> bool {
+        match self.positions.write().unwrap().get_mut(position_id) {
+            Some(position) => {
+                position.rules = rules;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Stop watching a position
+    pub fn untrack(&self, position_id: &str) -> Option<TrackedPosition> {
+        self.positions.write().unwrap().remove(position_id)
+    }
+
+    /// Get a tracked position
+    pub fn tracked(&self, position_id: &str) -> Option<TrackedPosition> {
+        self.positions.read().unwrap().get(position_id).cloned()
+    }
+
+    /// List tracked positions
+    pub fn list(&self) -> Vec<TrackedPosition> {
+        self.positions.read().unwrap().values().cloned().collect()
+    }
+
+    /// Check every position in `symbol` against a new price
+    ///
+    /// Triggered positions stop being tracked; put one back with
+    /// [`track`](Self::track) if its closing plan fails.
+    pub fn on_price(&self, symbol: &str, price: f64) -> Vec<ExitSignal> {
+        if !(price.is_finite() && price > 0.0) {
+            return Vec::new();
+        }
+        let mut positions = self.positions.write().unwrap();
+        let mut triggered: Vec<(String, ExitKind)> = positions
+            .values_mut()
+            .filter(|position| position.symbol == symbol)
+            .filter_map(|position| Some((position.position_id.clone(), position.observe(price)?)))
+            .collect();
+        triggered.sort_by(|a, b| a.0.cmp(&b.0));
+
+        triggered
+            .into_iter()
+            .filter_map(|(position_id, kind)| positions.remove(&position_id).map(|position| (position, kind)))
+            .map(|(position, kind)| {
+                tracing::info!("{:?} triggered for position {} in {} at {}", kind, position.position_id, symbol, price);
+                ExitSignal {
+                    kind,
+                    price,
+                    return_pct: position.return_pct(price),
+                    plan: self.closing_plan(&position, kind, price),
+                    position,
+                }
+            })
+            .collect()
+    }
+
+    /// Plan selling a position back into its entry token at no worse than the slippage allows
+    fn closing_plan(&self, position: &TrackedPosition, kind: ExitKind, price: f64) -> Option<TradePlan> {
+        let entry = position.entry.as_ref()?;
+        let expected_out = position.amount as f64 * price;
+        Some(TradePlan {
+            token_in: entry.token_out.clone(),
+            token_out: entry.token_in.clone(),
+            amount_in: position.amount,
+            min_out: (expected_out * (1.0 - self.slippage_pct / 100.0)).max(0.0) as u128,
+            exits: ExitRules::default(),
+            idem_key: format!("exit_{:?}_{}", kind, entry.idem_key).to_lowercase(),
+            quote: None,
+            correlation_id: None,
+            ..entry.clone()
+        })
+    }
+
+    /// Publish that a triggered position was closed at `exit_price`
+    pub fn record_closed(&self, signal: &ExitSignal, exit_price: f64) {
+        let Some(bus) = &self.bus else {
+            return;
+        };
+        let event = TradingEvent::PositionClosed {
+            position_id: signal.position.position_id.clone(),
+            symbol: signal.position.symbol.clone(),
+            realized_pnl: signal.position.realized_pnl(exit_price),
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        };
+        if let Err(e) = bus.publish_now(event.subject(), &event) {
+            tracing::warn!("Failed to publish exit event: {}", e);
+        }
+    }
+}
+
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
 }
@@ -5,10 +300,98 @@ pub fn add(left: u64, right: u64) -> u64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sniper_core::events::POSITION_CLOSED_SUBJECT;
+    use sniper_core::types::{ExecMode, GasPolicy};
 
     #[test]
     fn it_works() {
         let result = add(2, 2);
         assert_eq!(result, 4);
     }
+
+    fn chain() -> ChainRef {
+        ChainRef {
+            name: "ethereum".to_string(),
+            id: 1,
+        }
+    }
+
+    fn rules(stop_loss_pct: Option<f64>, trailing_pct: Option<f64>, take_profit_pct: Option<f64>) -> ExitRules {
+        ExitRules {
+            take_profit_pct,
+            stop_loss_pct,
+            trailing_pct,
+        }
+    }
+
+    #[test]
+    fn test_rules_trigger_in_order() {
+        let monitor = ExitMonitor::new();
+        monitor.track(TrackedPosition::new("sl", "ETH/USDC", chain(), "long", 100.0, 1, rules(Some(5.0), None, None)));
+        monitor.track(TrackedPosition::new("tp", "ETH/USDC", chain(), "long", 100.0, 1, rules(None, None, Some(10.0))));
+        monitor.track(TrackedPosition::new("trail", "ETH/USDC", chain(), "long", 100.0, 1, rules(None, Some(3.0), None)));
+        monitor.track(TrackedPosition::new("short", "ETH/USDC", chain(), "short", 100.0, 1, rules(Some(5.0), None, None)));
+
+        // A rally takes profit and stops out the short; the trailing stop follows it up
+        let signals = monitor.on_price("ETH/USDC", 110.0);
+        let kinds: Vec<(&str, ExitKind)> = signals.iter().map(|s| (s.position.position_id.as_str(), s.kind)).collect();
+        assert_eq!(kinds, vec![("short", ExitKind::StopLoss), ("tp", ExitKind::TakeProfit)]);
+        assert!(signals.iter().all(|signal| signal.plan.is_none()));
+        assert!(monitor.on_price("BTC/USDC", 1.0).is_empty());
+        assert_eq!(monitor.tracked("trail").unwrap().peak_price, 110.0);
+
+        // Still up 6% on entry, but 3.6% off the best price
+        let signals = monitor.on_price("ETH/USDC", 106.0);
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].kind, ExitKind::TrailingStop);
+        assert!((signals[0].return_pct - 6.0).abs() < 1e-9);
+
+        let signals = monitor.on_price("ETH/USDC", 94.0);
+        assert_eq!(signals[0].kind, ExitKind::StopLoss);
+        assert!(monitor.list().is_empty());
+    }
+
+    #[test]
+    fn test_closing_plan_reverses_entry() {
+        let entry = TradePlan {
+            chain: chain(),
+            router: "0xRouter".to_string(),
+            token_in: "0xWETH".to_string(),
+            token_out: "0xPEPE".to_string(),
+            amount_in: 1_000_000_000_000_000_000,
+            min_out: 1_900_000_000_000_000_000_000,
+            mode: ExecMode::Bundle,
+            gas: GasPolicy {
+                max_fee_gwei: 50,
+                max_priority_gwei: 2,
+            },
+            exits: rules(Some(20.0), None, Some(100.0)),
+            idem_key: "snipe-1".to_string(),
+            quote: None,
+            correlation_id: None,
+        };
+        let bus = InMemoryBus::new(16);
+        let mut events = bus.subscribe(POSITION_CLOSED_SUBJECT);
+        let monitor = ExitMonitor::new().with_bus(bus).with_slippage(10.0);
+
+        // 1 WETH bought 2,000 PEPE: 0.0005 WETH per PEPE
+        let position = TrackedPosition::from_plan(&entry, 2_000_000_000_000_000_000_000);
+        assert_eq!(position.symbol, "0xPEPE/0xWETH");
+        assert_eq!(position.entry_price, 0.0005);
+        monitor.track(position);
+
+        let signals = monitor.on_price("0xPEPE/0xWETH", 0.001);
+        assert_eq!(signals[0].kind, ExitKind::TakeProfit);
+        let plan = signals[0].plan.as_ref().unwrap();
+        assert_eq!((plan.token_in.as_str(), plan.token_out.as_str()), ("0xPEPE", "0xWETH"));
+        assert_eq!(plan.amount_in, 2_000_000_000_000_000_000_000);
+        assert!((plan.min_out as f64 - 1.8e18).abs() < 1e6);
+        assert!(!has_rules(&plan.exits));
+        assert_eq!(plan.mode, ExecMode::Bundle);
+        assert_eq!(plan.idem_key, "exit_takeprofit_snipe-1");
+
+        monitor.record_closed(&signals[0], 0.001);
+        let event: TradingEvent = serde_json::from_slice(&events.try_recv().unwrap()).unwrap();
+        assert!(matches!(event, TradingEvent::PositionClosed { realized_pnl, .. } if (realized_pnl - 1.0).abs() < 1e-9));
+    }
 }
@@ -1 +1,21 @@
-pub fn placeholder() { /* TODO: implement */ }
+//! Stop-loss and trailing-stop triggers.
+
+/// Whether a position's return, in percent, has fallen to its stop-loss
+pub fn stop_loss_hit(return_pct: f64, stop_loss_pct: f64) -> bool {
+    stop_loss_pct > 0.0 && return_pct <= -stop_loss_pct
+}
+
+/// Whether the price has moved against a position from its best price by the trailing distance
+///
+/// `peak_price` is the highest price seen for a long and the lowest for a short.
+pub fn trailing_stop_hit(side: &str, peak_price: f64, price: f64, trailing_pct: f64) -> bool {
+    if trailing_pct <= 0.0 || peak_price <= 0.0 {
+        return false;
+    }
+    let retrace_pct = if side == "short" {
+        (price - peak_price) / peak_price * 100.0
+    } else {
+        (peak_price - price) / peak_price * 100.0
+    };
+    retrace_pct >= trailing_pct
+}
@@ -1 +1,6 @@
-pub fn placeholder() { /* TODO: implement */ }
+//! Take-profit triggers.
+
+/// Whether a position's return, in percent, has risen to its take-profit
+pub fn take_profit_hit(return_pct: f64, take_profit_pct: f64) -> bool {
+    take_profit_pct > 0.0 && return_pct >= take_profit_pct
+}
@@ -1,3 +1,46 @@
+//! Transaction signing keys for the sniper bot.
+//!
+//! Executors sign through the [`TxSigner`] trait so the key can live in
+//! process memory, a KMS, a vault or an MPC cluster without the executor
+//! caring which. [`local::LocalSigner`] holds a secp256k1 key in memory.
+
+pub mod local;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use sha3::{Digest, Keccak256};
+
+pub use local::LocalSigner;
+
+/// Recoverable secp256k1 signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signature {
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+    /// Parity of the signature point's y coordinate
+    pub y_parity: bool,
+}
+
+/// Signs transaction hashes for one account
+#[async_trait]
+pub trait TxSigner: Send + Sync {
+    /// Address of the signing account
+    fn address(&self) -> [u8; 20];
+
+    /// Sign a 32-byte hash
+    async fn sign_hash(&self, hash: [u8; 32]) -> Result<Signature>;
+}
+
+/// Keccak-256 hash
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+/// Checksum-free `0x` hex form of an address
+pub fn format_address(address: &[u8; 20]) -> String {
+    format!("0x{}", hex::encode(address))
+}
+
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
 }
@@ -11,4 +54,12 @@ mod tests {
         let result = add(2, 2);
         assert_eq!(result, 4);
     }
+
+    #[test]
+    fn test_keccak256() {
+        assert_eq!(
+            hex::encode(keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+    }
 }
@@ -1 +1,107 @@
-pub fn placeholder() { /* TODO: implement */ }
+//! In-memory secp256k1 signing key.
+//!
+//! For development and single-operator deployments; the key is read from a
+//! hex string, typically an environment variable, and held in memory.
+
+use crate::{keccak256, Signature, TxSigner};
+use anyhow::Result;
+use async_trait::async_trait;
+use k256::ecdsa::SigningKey;
+
+/// Signer holding its private key in memory
+pub struct LocalSigner {
+    key: SigningKey,
+    address: [u8; 20],
+}
+
+impl LocalSigner {
+    /// Create a signer from a hex private key, with or without `0x`
+    pub fn from_hex(private_key: &str) -> Result<Self> {
+        let bytes = hex::decode(private_key.trim().trim_start_matches("0x"))
+            .map_err(|e| anyhow::anyhow!("Invalid private key hex: {}", e))?;
+        let key = SigningKey::from_slice(&bytes).map_err(|_| anyhow::anyhow!("Invalid secp256k1 private key"))?;
+
+        // The address is the last 20 bytes of the hash of the uncompressed public key
+        let public_key = key.verifying_key().to_encoded_point(false);
+        let hash = keccak256(&public_key.as_bytes()[1..]);
+        let mut address = [0u8; 20];
+        address.copy_from_slice(&hash[12..]);
+        Ok(Self { key, address })
+    }
+
+    /// Create a signer from a hex private key in an environment variable
+    pub fn from_env(var: &str) -> Result<Self> {
+        let private_key = std::env::var(var).map_err(|_| anyhow::anyhow!("{} is not set", var))?;
+        Self::from_hex(&private_key)
+    }
+}
+
+impl std::fmt::Debug for LocalSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalSigner")
+            .field("address", &crate::format_address(&self.address))
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl TxSigner for LocalSigner {
+    fn address(&self) -> [u8; 20] {
+        self.address
+    }
+
+    async fn sign_hash(&self, hash: [u8; 32]) -> Result<Signature> {
+        let (signature, recovery_id) = self
+            .key
+            .sign_prehash_recoverable(&hash)
+            .map_err(|e| anyhow::anyhow!("Signing failed: {}", e))?;
+        let bytes = signature.to_bytes();
+        let mut r = [0u8; 32];
+        let mut s = [0u8; 32];
+        r.copy_from_slice(&bytes[..32]);
+        s.copy_from_slice(&bytes[32..]);
+        Ok(Signature {
+            r,
+            s,
+            y_parity: recovery_id.is_y_odd(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::ecdsa::{RecoveryId, VerifyingKey};
+
+    const PRIVATE_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    #[test]
+    fn test_address_from_private_key() {
+        let signer = LocalSigner::from_hex(PRIVATE_KEY).unwrap();
+        assert_eq!(
+            crate::format_address(&signer.address()),
+            "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23"
+        );
+        assert!(LocalSigner::from_hex("0x1234").is_err());
+        assert!(LocalSigner::from_hex("not hex").is_err());
+        assert!(!format!("{:?}", signer).contains("4c0883"));
+    }
+
+    #[tokio::test]
+    async fn test_signature_recovers_signer() {
+        let signer = LocalSigner::from_hex(PRIVATE_KEY).unwrap();
+        let hash = keccak256(b"transaction");
+        let signature = signer.sign_hash(hash).await.unwrap();
+
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&signature.r);
+        bytes[32..].copy_from_slice(&signature.s);
+        let recovered = VerifyingKey::recover_from_prehash(
+            &hash,
+            &k256::ecdsa::Signature::from_slice(&bytes).unwrap(),
+            RecoveryId::new(signature.y_parity, false),
+        )
+        .unwrap();
+        assert_eq!(&recovered, signer.key.verifying_key());
+    }
+}
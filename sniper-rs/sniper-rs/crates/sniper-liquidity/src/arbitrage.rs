@@ -0,0 +1,502 @@
+//! Arbitrage detection across liquidity sources.
+//!
+//! [`ArbDetector`] compares the prices the fresh sources of each tracked pair
+//! imply. Where one source sells the pair's token0 cheaper than another buys
+//! it back, buying on the first and selling on the second pays; the amount
+//! put in is sized to maximize the profit left after both pool fees, then
+//! the gas of both legs is taken off. Opportunities clearing `min_profit_bps`
+//! are published as `arb_opportunity` signals carrying the two trade plans.
+
+use crate::{LiquidityAggregator, LiquiditySource, TokenPair};
+use serde::{Deserialize, Serialize};
+use sniper_core::bus::InMemoryBus;
+use sniper_core::telemetry::{self, Context, KeyValue};
+use sniper_core::types::{ChainRef, ExecMode, ExitRules, GasPolicy, Signal, TradePlan};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+/// Kind of the signals opportunities are published as
+pub const ARB_OPPORTUNITY_KIND: &str = "arb_opportunity";
+
+/// Subject opportunities are published on
+pub const ARB_OPPORTUNITY_SUBJECT: &str = "signals.dex.arb_opportunity";
+
+/// Pair watched for price discrepancies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackedPair {
+    pub pair: TokenPair,
+    /// Gas of executing one leg, priced in the pair's token1
+    pub gas_cost_per_leg: f64,
+    /// Most token1 put into the buy leg; unbounded when absent
+    #[serde(default)]
+    pub max_amount_in: Option<f64>,
+}
+
+/// Arbitrage detection settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArbConfig {
+    /// Time between scans
+    pub interval_ms: u64,
+    /// Profit after fees and gas required, in basis points of the amount put in
+    pub min_profit_bps: f64,
+    /// Slippage allowed on each leg's minimum output
+    pub slippage_bps: u32,
+    /// Router of each protocol; sources of other protocols are not traded
+    pub routers: HashMap<String, String>,
+    pub max_fee_gwei: u64,
+    pub max_priority_gwei: u64,
+    pub pairs: Vec<TrackedPair>,
+}
+
+impl Default for ArbConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: 1_000,
+            min_profit_bps: 10.0,
+            slippage_bps: 50,
+            routers: HashMap::new(),
+            max_fee_gwei: 50,
+            max_priority_gwei: 2,
+            pairs: Vec::new(),
+        }
+    }
+}
+
+impl ArbConfig {
+    /// Load detection settings from a TOML file
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path, e))?;
+        Ok(toml::from_str(&text)?)
+    }
+}
+
+/// One swap of an opportunity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArbLeg {
+    pub protocol: String,
+    pub chain: ChainRef,
+    /// Pool traded through, when the source is backed by one
+    pub pool: Option<String>,
+    pub token_in: String,
+    pub token_out: String,
+    pub amount_in: u128,
+    pub expected_out: u128,
+}
+
+/// Price discrepancy between two sources of a pair
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArbOpportunity {
+    pub pair: TokenPair,
+    /// Buys token0 with token1 where it is cheap
+    pub buy: ArbLeg,
+    /// Sells the token0 bought back for token1 where it is dear
+    pub sell: ArbLeg,
+    /// Token1 gained after pool fees
+    pub gross_profit: u128,
+    /// Gas of both legs, in token1
+    pub gas_cost: u128,
+    pub net_profit: u128,
+    /// Net profit in basis points of the amount put in
+    pub profit_bps: f64,
+    /// Buy and sell plans, in execution order
+    pub plans: Vec<TradePlan>,
+    pub detected_at_ms: i64,
+}
+
+impl ArbOpportunity {
+    /// Signal announcing the opportunity
+    pub fn to_signal(&self) -> Signal {
+        Signal {
+            source: "dex".to_string(),
+            kind: ARB_OPPORTUNITY_KIND.to_string(),
+            chain: self.buy.chain.clone(),
+            token0: Some(self.pair.token0.clone()),
+            token1: Some(self.pair.token1.clone()),
+            extra: serde_json::to_value(self).unwrap_or_default(),
+            seen_at_ms: self.detected_at_ms,
+        }
+    }
+}
+
+/// Watches tracked pairs for arbitrage between their sources
+pub struct ArbDetector {
+    aggregator: Arc<RwLock<LiquidityAggregator>>,
+    bus: Option<InMemoryBus>,
+    config: ArbConfig,
+}
+
+impl ArbDetector {
+    /// Create a detector over the sources of `aggregator`
+    pub fn new(aggregator: Arc<RwLock<LiquidityAggregator>>, config: ArbConfig) -> Self {
+        Self {
+            aggregator,
+            bus: None,
+            config,
+        }
+    }
+
+    /// Publish the opportunities found on `bus`
+    pub fn with_bus(mut self, bus: InMemoryBus) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Best opportunity of each tracked pair clearing the profit threshold
+    pub fn detect(&self, aggregator: &LiquidityAggregator) -> Vec<ArbOpportunity> {
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64;
+        self.config
+            .pairs
+            .iter()
+            .filter_map(|tracked| {
+                let sources = oriented_sources(aggregator, &tracked.pair);
+                let mut best: Option<ArbOpportunity> = None;
+                for (buy_index, buy) in sources.iter().enumerate() {
+                    for (sell_index, sell) in sources.iter().enumerate() {
+                        if buy_index == sell_index {
+                            continue;
+                        }
+                        let Some(opportunity) = self.evaluate(tracked, buy, sell, now_ms) else {
+                            continue;
+                        };
+                        if best.as_ref().map(|best| opportunity.net_profit > best.net_profit).unwrap_or(true) {
+                            best = Some(opportunity);
+                        }
+                    }
+                }
+                best
+            })
+            .collect()
+    }
+
+    /// Scan every tracked pair once and publish what is found
+    pub async fn scan_once(&self) -> Vec<ArbOpportunity> {
+        let opportunities = self.detect(&*self.aggregator.read().await);
+        if let Some(bus) = &self.bus {
+            for opportunity in &opportunities {
+                publish(bus, opportunity);
+            }
+        }
+        opportunities
+    }
+
+    /// Scan on the configured interval until the task is aborted
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(self.config.interval_ms));
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                for opportunity in self.scan_once().await {
+                    tracing::info!(
+                        "Arbitrage on {}/{}: buy on {} and sell on {} for {} bps",
+                        opportunity.pair.token0,
+                        opportunity.pair.token1,
+                        opportunity.buy.protocol,
+                        opportunity.sell.protocol,
+                        opportunity.profit_bps
+                    );
+                }
+            }
+        })
+    }
+
+    /// Opportunity of buying token0 on `buy` and selling it on `sell`
+    fn evaluate(
+        &self,
+        tracked: &TrackedPair,
+        buy: &OrientedSource,
+        sell: &OrientedSource,
+        now_ms: i64,
+    ) -> Option<ArbOpportunity> {
+        let buy_router = self.config.routers.get(&buy.source.protocol)?;
+        let sell_router = self.config.routers.get(&sell.source.protocol)?;
+
+        // The two pools chained act as one pool taking token1 back to token1
+        let (buy_in, buy_out, buy_keep) = (buy.reserve1, buy.reserve0, 1.0 - buy.source.fee);
+        let (sell_in, sell_out, sell_keep) = (sell.reserve0, sell.reserve1, 1.0 - sell.source.fee);
+        let virtual_in = buy_in * sell_in / (sell_in + sell_keep * buy_out);
+        let virtual_out = sell_keep * buy_out * sell_out / (sell_in + sell_keep * buy_out);
+        if buy_keep * virtual_out <= virtual_in {
+            return None;
+        }
+        let mut amount_in = ((buy_keep * virtual_in * virtual_out).sqrt() - virtual_in) / buy_keep;
+        if let Some(max_amount_in) = tracked.max_amount_in {
+            amount_in = amount_in.min(max_amount_in);
+        }
+        let amount_in = amount_in.floor();
+        let bought = quote(amount_in, buy_in, buy_out, buy_keep).floor();
+        let sold = quote(bought, sell_in, sell_out, sell_keep).floor();
+        if amount_in < 1.0 || sold <= amount_in {
+            return None;
+        }
+
+        let (amount_in, bought, sold) = (amount_in as u128, bought as u128, sold as u128);
+        let gross_profit = sold - amount_in;
+        let gas_cost = (tracked.gas_cost_per_leg.max(0.0) * 2.0).ceil() as u128;
+        let net_profit = gross_profit.checked_sub(gas_cost).filter(|profit| *profit > 0)?;
+        let profit_bps = net_profit as f64 / amount_in as f64 * 10_000.0;
+        if profit_bps < self.config.min_profit_bps {
+            return None;
+        }
+
+        let pair = &tracked.pair;
+        let buy_leg = leg(buy, &pair.token1, &pair.token0, amount_in, bought);
+        let sell_leg = leg(sell, &pair.token0, &pair.token1, bought, sold);
+        // Legs on one chain can land together; across chains each goes on its own
+        let mode = if buy_leg.chain.id == sell_leg.chain.id {
+            ExecMode::Bundle
+        } else {
+            ExecMode::Private
+        };
+        let plans = vec![
+            self.plan(&buy_leg, buy_router, mode.clone(), format!("arb_{}_{}_buy_{}", pair.token0, pair.token1, now_ms)),
+            self.plan(&sell_leg, sell_router, mode, format!("arb_{}_{}_sell_{}", pair.token0, pair.token1, now_ms)),
+        ];
+        Some(ArbOpportunity {
+            pair: pair.clone(),
+            buy: buy_leg,
+            sell: sell_leg,
+            gross_profit,
+            gas_cost,
+            net_profit,
+            profit_bps,
+            plans,
+            detected_at_ms: now_ms,
+        })
+    }
+
+    fn plan(&self, leg: &ArbLeg, router: &str, mode: ExecMode, idem_key: String) -> TradePlan {
+        let keep_bps = 10_000 - self.config.slippage_bps.min(10_000) as u128;
+        TradePlan {
+            chain: leg.chain.clone(),
+            router: router.to_string(),
+            token_in: leg.token_in.clone(),
+            token_out: leg.token_out.clone(),
+            amount_in: leg.amount_in,
+            // Split so large amounts cannot overflow
+            min_out: leg.expected_out / 10_000 * keep_bps + leg.expected_out % 10_000 * keep_bps / 10_000,
+            mode,
+            gas: GasPolicy {
+                max_fee_gwei: self.config.max_fee_gwei,
+                max_priority_gwei: self.config.max_priority_gwei,
+            },
+            exits: ExitRules::default(),
+            idem_key,
+            quote: None,
+            correlation_id: None,
+        }
+    }
+}
+
+/// Fresh source with its reserves in the tracked pair's order
+struct OrientedSource<'a> {
+    source: &'a LiquiditySource,
+    reserve0: f64,
+    reserve1: f64,
+}
+
+/// Fresh sources of `pair`, whichever way round they were registered
+fn oriented_sources<'a>(aggregator: &'a LiquidityAggregator, pair: &TokenPair) -> Vec<OrientedSource<'a>> {
+    let reversed = TokenPair {
+        token0: pair.token1.clone(),
+        token1: pair.token0.clone(),
+    };
+    let straight = aggregator.get_liquidity_sources(pair).into_iter().map(|source| OrientedSource {
+        source,
+        reserve0: source.reserve0 as f64,
+        reserve1: source.reserve1 as f64,
+    });
+    let flipped = aggregator.get_liquidity_sources(&reversed).into_iter().map(|source| OrientedSource {
+        source,
+        reserve0: source.reserve1 as f64,
+        reserve1: source.reserve0 as f64,
+    });
+    straight
+        .chain(flipped)
+        .filter(|oriented| !oriented.source.stale && oriented.reserve0 > 0.0 && oriented.reserve1 > 0.0)
+        .collect()
+}
+
+/// Output of a constant-product swap keeping `keep` of the amount put in
+fn quote(amount_in: f64, reserve_in: f64, reserve_out: f64, keep: f64) -> f64 {
+    let effective_in = amount_in * keep;
+    effective_in * reserve_out / (reserve_in + effective_in)
+}
+
+fn leg(source: &OrientedSource, token_in: &str, token_out: &str, amount_in: u128, expected_out: u128) -> ArbLeg {
+    ArbLeg {
+        protocol: source.source.protocol.clone(),
+        chain: source.source.chain.clone(),
+        pool: source.source.pool.as_ref().map(|pool| pool.address.clone()),
+        token_in: token_in.to_string(),
+        token_out: token_out.to_string(),
+        amount_in,
+        expected_out,
+    }
+}
+
+fn publish(bus: &InMemoryBus, opportunity: &ArbOpportunity) {
+    let signal = opportunity.to_signal();
+    let cx = telemetry::start_span(
+        "signal.detect",
+        &Context::new(),
+        vec![
+            KeyValue::new("signal.kind", signal.kind.clone()),
+            KeyValue::new("chain.id", signal.chain.id as i64),
+        ],
+    );
+    if let Err(e) = bus.publish_in(ARB_OPPORTUNITY_SUBJECT, &signal, &cx) {
+        telemetry::record_error(&cx, &e);
+        tracing::warn!("Failed to publish {}: {}", ARB_OPPORTUNITY_SUBJECT, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LiquidityConfig;
+
+    fn source(protocol: &str, chain: u64, pair: (&str, &str), reserve0: u128, reserve1: u128) -> LiquiditySource {
+        LiquiditySource {
+            protocol: protocol.to_string(),
+            chain: ChainRef {
+                name: format!("chain-{}", chain),
+                id: chain,
+            },
+            pair: TokenPair {
+                token0: pair.0.to_string(),
+                token1: pair.1.to_string(),
+            },
+            reserve0,
+            reserve1,
+            fee: 0.003,
+            timestamp: 100,
+            pool: None,
+            stale: false,
+        }
+    }
+
+    fn config(min_profit_bps: f64) -> ArbConfig {
+        ArbConfig {
+            min_profit_bps,
+            routers: [("uniswap", "0xuniswap"), ("sushiswap", "0xsushiswap")]
+                .iter()
+                .map(|(protocol, router)| (protocol.to_string(), router.to_string()))
+                .collect(),
+            pairs: vec![TrackedPair {
+                pair: TokenPair {
+                    token0: "WETH".to_string(),
+                    token1: "USDC".to_string(),
+                },
+                gas_cost_per_leg: 1_000.0,
+                max_amount_in: None,
+            }],
+            ..ArbConfig::default()
+        }
+    }
+
+    fn aggregator(sources: Vec<LiquiditySource>) -> Arc<RwLock<LiquidityAggregator>> {
+        let mut aggregator = LiquidityAggregator::new(LiquidityConfig {
+            chains: vec!["ethereum".to_string()],
+            protocols: vec!["uniswap".to_string(), "sushiswap".to_string()],
+            min_liquidity: 0,
+            max_price_impact: 0.05,
+        });
+        for (index, source) in sources.into_iter().enumerate() {
+            aggregator.add_liquidity_source(format!("source-{}", index), source);
+        }
+        Arc::new(RwLock::new(aggregator))
+    }
+
+    #[tokio::test]
+    async fn test_detects_and_publishes_price_discrepancy() {
+        let aggregator = aggregator(vec![
+            // WETH at 2,000 USDC on one and 2,100 on the other, registered the other way round
+            source("uniswap", 1, ("WETH", "USDC"), 1_000_000, 2_000_000_000),
+            source("sushiswap", 1, ("USDC", "WETH"), 2_100_000_000, 1_000_000),
+        ]);
+        let bus = InMemoryBus::new(16);
+        let mut rx = bus.subscribe(ARB_OPPORTUNITY_SUBJECT);
+        let detector = ArbDetector::new(aggregator.clone(), config(10.0)).with_bus(bus);
+
+        let opportunities = detector.scan_once().await;
+        assert_eq!(opportunities.len(), 1);
+        let opportunity = &opportunities[0];
+        assert_eq!((opportunity.buy.protocol.as_str(), opportunity.sell.protocol.as_str()), ("uniswap", "sushiswap"));
+        assert_eq!((opportunity.buy.token_in.as_str(), opportunity.buy.token_out.as_str()), ("USDC", "WETH"));
+        assert_eq!(opportunity.sell.amount_in, opportunity.buy.expected_out);
+        assert_eq!(opportunity.gross_profit, opportunity.sell.expected_out - opportunity.buy.amount_in);
+        assert_eq!(opportunity.net_profit, opportunity.gross_profit - 2_000);
+        assert!(opportunity.profit_bps >= 10.0);
+
+        // Putting in a little more or less than the amount chosen earns less
+        let buy = |amount: f64| quote(amount, 2e9, 1e6, 0.997);
+        let profit = |amount: f64| quote(buy(amount), 1e6, 2.1e9, 0.997) - amount;
+        let amount_in = opportunity.buy.amount_in as f64;
+        assert!(profit(amount_in) > profit(amount_in * 0.9) && profit(amount_in) > profit(amount_in * 1.1));
+
+        let plans = &opportunity.plans;
+        assert_eq!((plans[0].router.as_str(), plans[1].router.as_str()), ("0xuniswap", "0xsushiswap"));
+        assert_eq!(plans[0].mode, ExecMode::Bundle);
+        assert_eq!(plans[1].amount_in, opportunity.buy.expected_out);
+        assert_eq!(plans[0].min_out, opportunity.buy.expected_out * 9_950 / 10_000);
+
+        let signal: Signal = serde_json::from_slice(&rx.try_recv().unwrap()).unwrap();
+        assert_eq!(signal.kind, ARB_OPPORTUNITY_KIND);
+        assert_eq!(signal.extra["net_profit"].as_u64(), Some(opportunity.net_profit as u64));
+
+        // Nothing clears a threshold above the spread
+        assert!(ArbDetector::new(aggregator, config(500.0)).scan_once().await.is_empty());
+    }
+
+    #[test]
+    fn test_ignores_aligned_stale_and_unroutable_sources() {
+        let detect = |sources: Vec<LiquiditySource>| {
+            let aggregator = aggregator(sources);
+            let detector = ArbDetector::new(aggregator.clone(), config(0.0));
+            let guard = aggregator.try_read().unwrap();
+            detector.detect(&guard)
+        };
+
+        // Fees eat a spread this thin
+        assert!(detect(vec![
+            source("uniswap", 1, ("WETH", "USDC"), 1_000_000, 2_000_000_000),
+            source("sushiswap", 1, ("WETH", "USDC"), 1_000_000, 2_004_000_000),
+        ])
+        .is_empty());
+
+        let mut stale = source("sushiswap", 1, ("WETH", "USDC"), 1_000_000, 2_100_000_000);
+        stale.stale = true;
+        assert!(detect(vec![source("uniswap", 1, ("WETH", "USDC"), 1_000_000, 2_000_000_000), stale]).is_empty());
+
+        // No router to trade through
+        assert!(detect(vec![
+            source("uniswap", 1, ("WETH", "USDC"), 1_000_000, 2_000_000_000),
+            source("curve", 1, ("WETH", "USDC"), 1_000_000, 2_100_000_000),
+        ])
+        .is_empty());
+
+        // Across chains each leg goes on its own
+        let opportunities = detect(vec![
+            source("uniswap", 1, ("WETH", "USDC"), 1_000_000, 2_000_000_000),
+            source("sushiswap", 56, ("WETH", "USDC"), 1_000_000, 2_100_000_000),
+        ]);
+        assert_eq!(opportunities[0].plans[0].mode, ExecMode::Private);
+        assert_eq!(opportunities[0].plans[1].chain.id, 56);
+    }
+
+    #[test]
+    fn test_example_config_parses() {
+        let config: ArbConfig = toml::from_str(include_str!("../../../configs/arbitrage.toml")).unwrap();
+        assert_eq!(config.routers.len(), 2);
+        assert_eq!(config.pairs[0].gas_cost_per_leg, 5e6);
+        assert_eq!(config.pairs[0].max_amount_in, Some(5e10));
+    }
+}
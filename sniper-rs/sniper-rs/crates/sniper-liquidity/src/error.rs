@@ -0,0 +1,25 @@
+//! Errors returned by the liquidity aggregator.
+
+use sniper_core::errors::{DomainError, ErrorKind};
+use thiserror::Error;
+
+/// Failure of a liquidity query or update
+#[derive(Debug, Clone, Error)]
+pub enum LiquidityError {
+    #[error("No liquidity sources found for pair")]
+    NoSources,
+    #[error("All liquidity sources for the pair are stale")]
+    AllStale,
+    #[error("Batch of {received} updates exceeds the limit of {limit}")]
+    BatchTooLarge { received: usize, limit: usize },
+}
+
+impl DomainError for LiquidityError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            LiquidityError::NoSources => ErrorKind::NotFound,
+            LiquidityError::AllStale => ErrorKind::Unavailable,
+            LiquidityError::BatchTooLarge { .. } => ErrorKind::Invalid,
+        }
+    }
+}
@@ -2,21 +2,56 @@
 //! 
 //! This module provides functionality to aggregate liquidity across multiple
 //! DeFi protocols and chains to find the best trading opportunities.
+//! Sources backed by an on-chain pool are kept current by the [`sync`]
+//! engine; sources it could not refresh in time are marked stale and left
+//! out of aggregation and routing. Trades are routed over every fresh source
+//! by the multi-hop [`routing`] pathfinder, and price discrepancies between
+//! the sources of a pair are watched for by the [`arbitrage`] detector.
+
+pub mod arbitrage;
+pub mod error;
+pub mod routing;
+pub mod sync;
 
 use anyhow::Result;
+pub use error::LiquidityError;
+use routing::{Pathfinder, PathfinderConfig};
 use serde::{Deserialize, Serialize};
 use sniper_core::types::ChainRef;
 use std::collections::HashMap;
+use utoipa::ToSchema;
 
 /// Token pair information
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
 pub struct TokenPair {
     pub token0: String,
     pub token1: String,
 }
 
+/// Pool interface reserves are read through
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PoolKind {
+    /// `getReserves()` of Uniswap V2 style pairs
+    #[default]
+    V2,
+    /// Virtual reserves from `slot0()` and `liquidity()` of Uniswap V3 pools
+    V3,
+}
+
+/// On-chain pool a source's reserves are synced from
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub struct PoolRef {
+    pub address: String,
+    #[serde(default)]
+    pub kind: PoolKind,
+    /// Whether the pair's token0 is the pool's token1
+    #[serde(default)]
+    pub inverted: bool,
+}
+
 /// Liquidity source information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct LiquiditySource {
     pub protocol: String,
     pub chain: ChainRef,
@@ -25,10 +60,17 @@ pub struct LiquiditySource {
     pub reserve1: u128,
     pub fee: f64,
     pub timestamp: u64,
+    /// Pool the reserves are synced from; sources without one keep the
+    /// reserves they were given
+    #[serde(default)]
+    pub pool: Option<PoolRef>,
+    /// Set when the reserves are too old to trade on
+    #[serde(default)]
+    pub stale: bool,
 }
 
 /// Aggregated liquidity information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct AggregatedLiquidity {
     pub pair: TokenPair,
     pub sources: Vec<LiquiditySource>,
@@ -47,10 +89,48 @@ pub struct LiquidityConfig {
     pub max_price_impact: f64,
 }
 
+/// Most source updates accepted in one bulk ingest
+pub const MAX_BULK_UPDATES: usize = 50_000;
+
+/// One source update in a bulk ingest
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SourceUpdate {
+    pub source_id: String,
+    pub source: LiquiditySource,
+}
+
+/// Update rejected during a bulk ingest
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct IngestError {
+    /// Position of the update in the batch
+    pub index: usize,
+    pub source_id: String,
+    pub error: String,
+}
+
+/// Outcome of a bulk ingest
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct BulkIngestReport {
+    pub received: usize,
+    /// Sources not known before the ingest
+    pub inserted: usize,
+    /// Known sources whose reserves were replaced
+    pub updated: usize,
+    pub errors: Vec<IngestError>,
+}
+
+impl BulkIngestReport {
+    /// Number of updates applied
+    pub fn ingested(&self) -> usize {
+        self.inserted + self.updated
+    }
+}
+
 /// Liquidity aggregator
 pub struct LiquidityAggregator {
     config: LiquidityConfig,
     liquidity_sources: HashMap<String, Vec<LiquiditySource>>,
+    pathfinder: Pathfinder,
 }
 
 impl LiquidityAggregator {
@@ -59,9 +139,16 @@ impl LiquidityAggregator {
         Self {
             config,
             liquidity_sources: HashMap::new(),
+            pathfinder: Pathfinder::default(),
         }
     }
     
+    /// Route trades with the given pathfinding settings
+    pub fn with_pathfinder(mut self, config: PathfinderConfig) -> Self {
+        self.pathfinder = Pathfinder::new(config);
+        self
+    }
+    
     /// Add liquidity source
     pub fn add_liquidity_source(&mut self, source_id: String, source: LiquiditySource) {
         self.liquidity_sources
@@ -75,6 +162,98 @@ impl LiquidityAggregator {
         self.liquidity_sources.remove(source_id);
     }
     
+    /// Add or replace the state of a source's pool
+    ///
+    /// A pool is identified by its protocol, chain and pair within the source;
+    /// updates older than the known state are rejected. Returns whether the
+    /// pool was new.
+    pub fn upsert_liquidity_source(&mut self, source_id: &str, source: LiquiditySource) -> Result<bool> {
+        validate_source(source_id, &source)?;
+        let pools = self.liquidity_sources.entry(source_id.to_string()).or_default();
+        match pools.iter_mut().find(|known| {
+            known.protocol == source.protocol && known.chain.id == source.chain.id && known.pair == source.pair
+        }) {
+            Some(known) if known.timestamp > source.timestamp => Err(anyhow::anyhow!(
+                "Update at {} is older than the known state at {}",
+                source.timestamp, known.timestamp
+            )),
+            Some(known) => {
+                *known = source;
+                Ok(false)
+            },
+            None => {
+                pools.push(source);
+                Ok(true)
+            },
+        }
+    }
+    
+    /// Ingest a batch of source updates, e.g. a full-chain pool snapshot
+    ///
+    /// Each update is applied on its own; invalid ones are reported by
+    /// position without stopping the rest of the batch.
+    pub fn ingest_sources(&mut self, updates: Vec<SourceUpdate>) -> Result<BulkIngestReport, LiquidityError> {
+        if updates.len() > MAX_BULK_UPDATES {
+            return Err(LiquidityError::BatchTooLarge {
+                received: updates.len(),
+                limit: MAX_BULK_UPDATES,
+            });
+        }
+        
+        let mut report = BulkIngestReport {
+            received: updates.len(),
+            ..Default::default()
+        };
+        for (index, update) in updates.into_iter().enumerate() {
+            match self.upsert_liquidity_source(&update.source_id, update.source) {
+                Ok(true) => report.inserted += 1,
+                Ok(false) => report.updated += 1,
+                Err(e) => report.errors.push(IngestError {
+                    index,
+                    source_id: update.source_id,
+                    error: e.to_string(),
+                }),
+            }
+        }
+        Ok(report)
+    }
+    
+    /// Number of pools known across all sources
+    pub fn source_count(&self) -> usize {
+        self.liquidity_sources.values().map(Vec::len).sum()
+    }
+    
+    /// Sources with an on-chain pool to sync, with their source IDs
+    pub fn pooled_sources(&self) -> Vec<(String, LiquiditySource)> {
+        self.liquidity_sources
+            .iter()
+            .flat_map(|(source_id, pools)| pools.iter().map(move |source| (source_id, source)))
+            .filter(|(_, source)| source.pool.is_some())
+            .map(|(source_id, source)| (source_id.clone(), source.clone()))
+            .collect()
+    }
+    
+    /// Mark sources last updated more than `max_age_secs` before `now` as stale
+    ///
+    /// Returns the pairs of the sources that turned stale.
+    pub fn mark_stale(&mut self, max_age_secs: u64, now: u64) -> Vec<TokenPair> {
+        let mut pairs = Vec::new();
+        for source in self.liquidity_sources.values_mut().flatten() {
+            if !source.stale && source.timestamp.saturating_add(max_age_secs) < now {
+                source.stale = true;
+                if !pairs.contains(&source.pair) {
+                    pairs.push(source.pair.clone());
+                }
+            }
+        }
+        pairs
+    }
+    
+    /// Number of sources marked stale
+    pub fn stale_count(&self) -> usize {
+        self.liquidity_sources.values().flatten().filter(|source| source.stale).count()
+    }
+    
     /// Get all liquidity sources for a token pair
     pub fn get_liquidity_sources(&self, pair: &TokenPair) -> Vec<&LiquiditySource> {
         self.liquidity_sources
@@ -86,12 +265,16 @@ impl LiquidityAggregator {
             .collect()
     }
     
-    /// Aggregate liquidity for a token pair across all sources
-    pub fn aggregate_liquidity(&self, pair: &TokenPair) -> Result<AggregatedLiquidity> {
+    /// Aggregate liquidity for a token pair across all fresh sources
+    pub fn aggregate_liquidity(&self, pair: &TokenPair) -> Result<AggregatedLiquidity, LiquidityError> {
         let sources = self.get_liquidity_sources(pair);
         
         if sources.is_empty() {
-            return Err(anyhow::anyhow!("No liquidity sources found for pair"));
+            return Err(LiquidityError::NoSources);
+        }
+        let sources: Vec<&LiquiditySource> = sources.into_iter().filter(|source| !source.stale).collect();
+        if sources.is_empty() {
+            return Err(LiquidityError::AllStale);
         }
         
         // Calculate total liquidity
@@ -122,6 +305,21 @@ impl LiquidityAggregator {
         })
     }
     
+    /// Routes for a trade over all fresh sources, best first
+    ///
+    /// Direct pairs and routes through hub tokens are ranked by expected
+    /// output net of fees, then by price impact; routes moving the price
+    /// more than `max_price_impact` are left out.
+    pub fn find_routes(&self, token_in: &str, token_out: &str, amount_in: u128) -> Vec<TradeRoute> {
+        self.pathfinder.find_routes(
+            self.liquidity_sources.values().flatten(),
+            token_in,
+            token_out,
+            amount_in,
+            self.config.max_price_impact,
+        )
+    }
+    
     /// Find the best route for a trade
     pub fn find_best_route(
         &self,
@@ -129,40 +327,41 @@ impl LiquidityAggregator {
         token_out: &str,
         amount_in: u128,
     ) -> Result<Option<TradeRoute>> {
-        // This is a simplified implementation
-        // In a real implementation, this would use pathfinding algorithms
-        
-        // For now, we'll just look for direct pairs
-        let pair = TokenPair {
-            token0: token_in.to_string(),
-            token1: token_out.to_string(),
-        };
-        
-        match self.aggregate_liquidity(&pair) {
-            Ok(liquidity) => {
-                // Check if liquidity is sufficient
-                if liquidity.total_liquidity > amount_in && liquidity.price_impact < self.config.max_price_impact {
-                    Ok(Some(TradeRoute {
-                        path: vec![pair],
-                        expected_output: (amount_in as f64 * liquidity.best_price) as u128,
-                        price_impact: liquidity.price_impact,
-                        sources: liquidity.sources,
-                    }))
-                } else {
-                    Ok(None)
-                }
-            },
-            Err(_) => Ok(None),
-        }
+        Ok(self.find_routes(token_in, token_out, amount_in).into_iter().next())
     }
 }
 
+/// Check a source update is usable for aggregation
+fn validate_source(source_id: &str, source: &LiquiditySource) -> Result<()> {
+    if source_id.is_empty() {
+        return Err(anyhow::anyhow!("Source id is empty"));
+    }
+    if source.pair.token0.is_empty() || source.pair.token1.is_empty() {
+        return Err(anyhow::anyhow!("Pair token is empty"));
+    }
+    if source.pair.token0 == source.pair.token1 {
+        return Err(anyhow::anyhow!("Pair trades {} against itself", source.pair.token0));
+    }
+    if source.reserve0 == 0 || source.reserve1 == 0 {
+        return Err(anyhow::anyhow!("Pool has no liquidity"));
+    }
+    if !(0.0..1.0).contains(&source.fee) {
+        return Err(anyhow::anyhow!("Fee {} is not a fraction below 1", source.fee));
+    }
+    Ok(())
+}
+
 /// Trade route information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TradeRoute {
+    /// Hops from the input token to the output token, each oriented as traded
     pub path: Vec<TokenPair>,
     pub expected_output: u128,
+    /// Combined price impact of all hops, as a fraction
     pub price_impact: f64,
+    /// Amount each hop pays out
+    pub amounts_out: Vec<u128>,
+    /// Source traded through at each hop
     pub sources: Vec<LiquiditySource>,
 }
 
@@ -213,6 +412,8 @@ mod tests {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
+            pool: None,
+            stale: false,
         };
         
         // Add liquidity source
@@ -262,6 +463,8 @@ mod tests {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
+            pool: None,
+            stale: false,
         };
         
         let pancakeswap_source = LiquiditySource {
@@ -278,6 +481,8 @@ mod tests {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
+            pool: None,
+            stale: false,
         };
         
         aggregator.add_liquidity_source("uniswap_ethereum".to_string(), uniswap_source);
@@ -295,4 +500,75 @@ mod tests {
         println!("Liquidity aggregation test passed!");
         Ok(())
     }
+    
+    #[test]
+    fn test_bulk_ingest() {
+        let config = LiquidityConfig {
+            chains: vec!["ethereum".to_string()],
+            protocols: vec!["uniswap".to_string()],
+            min_liquidity: 1000000,
+            max_price_impact: 0.05,
+        };
+        let mut aggregator = LiquidityAggregator::new(config);
+        
+        let source = |token1: &str, reserve1: u128, timestamp: u64| LiquiditySource {
+            protocol: "uniswap".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            pair: TokenPair {
+                token0: "WETH".to_string(),
+                token1: token1.to_string(),
+            },
+            reserve0: 1_000,
+            reserve1,
+            fee: 0.003,
+            timestamp,
+            pool: None,
+            stale: false,
+        };
+        let update = |source_id: &str, source: LiquiditySource| SourceUpdate {
+            source_id: source_id.to_string(),
+            source,
+        };
+        
+        // A snapshot of many pools, with a few bad entries among them
+        let mut updates: Vec<SourceUpdate> = (0..1_000)
+            .map(|i| update("indexer", source(&format!("TOKEN{}", i), 2_000, 100)))
+            .collect();
+        updates.push(update("indexer", source("WETH", 2_000, 100)));
+        updates.push(update("indexer", source("USDC", 0, 100)));
+        updates.push(update("", source("USDC", 2_000, 100)));
+        
+        let report = aggregator.ingest_sources(updates).unwrap();
+        assert_eq!(report.received, 1_003);
+        assert_eq!(report.inserted, 1_000);
+        assert_eq!(report.updated, 0);
+        assert_eq!(report.errors.iter().map(|e| e.index).collect::<Vec<_>>(), vec![1_000, 1_001, 1_002]);
+        assert_eq!(aggregator.source_count(), 1_000);
+        
+        // A later snapshot replaces reserves; a stale one is rejected
+        let report = aggregator
+            .ingest_sources(vec![
+                update("indexer", source("TOKEN0", 3_000, 200)),
+                update("indexer", source("TOKEN1", 3_000, 50)),
+            ])
+            .unwrap();
+        assert_eq!(report.updated, 1);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].index, 1);
+        assert_eq!(aggregator.source_count(), 1_000);
+        
+        let pair = TokenPair {
+            token0: "WETH".to_string(),
+            token1: "TOKEN0".to_string(),
+        };
+        assert_eq!(aggregator.get_liquidity_sources(&pair)[0].reserve1, 3_000);
+        
+        let oversized = (0..=MAX_BULK_UPDATES)
+            .map(|_| update("indexer", source("USDC", 2_000, 100)))
+            .collect();
+        assert!(aggregator.ingest_sources(oversized).is_err());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,309 @@
+//! Multi-hop pathfinding across liquidity sources.
+//!
+//! Every fresh source is an edge of a token graph, usable in both directions.
+//! [`Pathfinder`] walks the simple paths of up to `max_hops` edges from the
+//! input token with a depth-first search, quoting each hop on what the
+//! previous one pays out net of the pool fee. Routes stay on one chain, and
+//! only hub tokens such as WETH or USDC are traded through on the way, which
+//! keeps the search small and routes away from thin pools of unknown tokens.
+//! Every route found is returned, best first.
+
+use crate::{LiquiditySource, TokenPair, TradeRoute};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Longest route searched by default
+pub const DEFAULT_MAX_HOPS: usize = 3;
+
+/// Most routes returned by default, the best included
+pub const DEFAULT_MAX_ROUTES: usize = 5;
+
+/// Pathfinding settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PathfinderConfig {
+    /// Most pools a route trades through
+    pub max_hops: usize,
+    /// Tokens a route may pass through; when empty any token may be
+    pub hub_tokens: Vec<String>,
+    /// Most routes returned, the best included
+    pub max_routes: usize,
+}
+
+impl Default for PathfinderConfig {
+    fn default() -> Self {
+        Self {
+            max_hops: DEFAULT_MAX_HOPS,
+            hub_tokens: ["WETH", "USDC", "USDT", "DAI", "WBTC"].iter().map(|token| token.to_string()).collect(),
+            max_routes: DEFAULT_MAX_ROUTES,
+        }
+    }
+}
+
+/// Source an edge of the token graph trades through
+#[derive(Clone, Copy)]
+struct Edge<'a> {
+    token_out: &'a str,
+    source: &'a LiquiditySource,
+}
+
+impl Edge<'_> {
+    /// Amount out and price impact of swapping `amount_in` through the source
+    fn quote(&self, token_in: &str, amount_in: f64) -> Option<(f64, f64)> {
+        let pair = &self.source.pair;
+        let (reserve_in, reserve_out) = if pair.token0 == token_in {
+            (self.source.reserve0 as f64, self.source.reserve1 as f64)
+        } else {
+            (self.source.reserve1 as f64, self.source.reserve0 as f64)
+        };
+        if reserve_in <= 0.0 || reserve_out <= 0.0 {
+            return None;
+        }
+        let effective_in = amount_in * (1.0 - self.source.fee);
+        let amount_out = effective_in * reserve_out / (reserve_in + effective_in);
+        // Anything below one unit of the token is not received at all
+        (amount_out >= 1.0).then(|| (amount_out, effective_in / (reserve_in + effective_in)))
+    }
+}
+
+/// One hop of a route being searched
+struct Hop<'a> {
+    token_in: &'a str,
+    edge: Edge<'a>,
+    amount_out: f64,
+    price_impact: f64,
+}
+
+/// Bounded-depth route search over a set of sources
+#[derive(Debug, Clone, Default)]
+pub struct Pathfinder {
+    config: PathfinderConfig,
+}
+
+impl Pathfinder {
+    /// Create a pathfinder with the given settings
+    pub fn new(config: PathfinderConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &PathfinderConfig {
+        &self.config
+    }
+
+    /// Routes from `token_in` to `token_out` over `sources`, best first
+    ///
+    /// Routes are ranked by expected output, then by price impact, then by
+    /// number of hops. Stale sources are not traded through, and routes
+    /// whose price impact exceeds `max_price_impact` are left out.
+    pub fn find_routes<'a>(
+        &self,
+        sources: impl IntoIterator<Item = &'a LiquiditySource>,
+        token_in: &str,
+        token_out: &str,
+        amount_in: u128,
+        max_price_impact: f64,
+    ) -> Vec<TradeRoute> {
+        if token_in == token_out || amount_in == 0 {
+            return Vec::new();
+        }
+
+        // Edges are keyed by chain so routes never cross chains
+        let mut graph: HashMap<(u64, &str), Vec<Edge>> = HashMap::new();
+        for source in sources.into_iter().filter(|source| !source.stale) {
+            let TokenPair { token0, token1 } = &source.pair;
+            graph.entry((source.chain.id, token0.as_str())).or_default().push(Edge {
+                token_out: token1.as_str(),
+                source,
+            });
+            graph.entry((source.chain.id, token1.as_str())).or_default().push(Edge {
+                token_out: token0.as_str(),
+                source,
+            });
+        }
+        let mut chains: Vec<u64> = graph
+            .keys()
+            .filter(|(_, token)| *token == token_in)
+            .map(|(chain, _)| *chain)
+            .collect();
+        chains.sort_unstable();
+
+        let mut routes = Vec::new();
+        for chain in chains {
+            let mut visited = vec![token_in];
+            let mut hops = Vec::new();
+            self.search(&graph, chain, token_in, token_out, amount_in as f64, &mut visited, &mut hops, &mut routes);
+        }
+        routes.retain(|route: &TradeRoute| route.price_impact <= max_price_impact);
+        routes.sort_by(|a, b| {
+            b.expected_output
+                .cmp(&a.expected_output)
+                .then(a.price_impact.partial_cmp(&b.price_impact).unwrap_or(Ordering::Equal))
+                .then(a.path.len().cmp(&b.path.len()))
+        });
+        routes.truncate(self.config.max_routes.max(1));
+        routes
+    }
+
+    fn is_hub(&self, token: &str) -> bool {
+        self.config.hub_tokens.is_empty()
+            || self.config.hub_tokens.iter().any(|hub| hub.eq_ignore_ascii_case(token))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn search<'a>(
+        &self,
+        graph: &HashMap<(u64, &'a str), Vec<Edge<'a>>>,
+        chain: u64,
+        token: &'a str,
+        target: &str,
+        amount: f64,
+        visited: &mut Vec<&'a str>,
+        hops: &mut Vec<Hop<'a>>,
+        routes: &mut Vec<TradeRoute>,
+    ) {
+        if hops.len() >= self.config.max_hops {
+            return;
+        }
+
+        for edge in graph.get(&(chain, token)).into_iter().flatten() {
+            if visited.contains(&edge.token_out) {
+                continue;
+            }
+            let reaches_target = edge.token_out == target;
+            if !reaches_target && !self.is_hub(edge.token_out) {
+                continue;
+            }
+            // Sources too thin to pay anything out are simply not part of a route
+            let Some((amount_out, price_impact)) = edge.quote(token, amount) else {
+                continue;
+            };
+            hops.push(Hop {
+                token_in: token,
+                edge: *edge,
+                amount_out,
+                price_impact,
+            });
+
+            if reaches_target {
+                routes.push(to_route(hops));
+            } else {
+                visited.push(edge.token_out);
+                self.search(graph, chain, edge.token_out, target, amount_out, visited, hops, routes);
+                visited.pop();
+            }
+            hops.pop();
+        }
+    }
+}
+
+fn to_route(hops: &[Hop]) -> TradeRoute {
+    let retained: f64 = hops.iter().map(|hop| 1.0 - hop.price_impact).product();
+    TradeRoute {
+        path: hops
+            .iter()
+            .map(|hop| TokenPair {
+                token0: hop.token_in.to_string(),
+                token1: hop.edge.token_out.to_string(),
+            })
+            .collect(),
+        expected_output: hops.last().map(|hop| hop.amount_out as u128).unwrap_or(0),
+        price_impact: 1.0 - retained,
+        amounts_out: hops.iter().map(|hop| hop.amount_out as u128).collect(),
+        sources: hops.iter().map(|hop| hop.edge.source.clone()).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sniper_core::types::ChainRef;
+
+    fn source(chain: u64, token0: &str, token1: &str, reserve0: u128, reserve1: u128) -> LiquiditySource {
+        LiquiditySource {
+            protocol: "uniswap".to_string(),
+            chain: ChainRef {
+                name: format!("chain-{}", chain),
+                id: chain,
+            },
+            pair: TokenPair {
+                token0: token0.to_string(),
+                token1: token1.to_string(),
+            },
+            reserve0,
+            reserve1,
+            fee: 0.003,
+            timestamp: 100,
+            pool: None,
+            stale: false,
+        }
+    }
+
+    fn tokens(route: &TradeRoute) -> Vec<&str> {
+        let mut tokens: Vec<&str> = route.path.iter().map(|pair| pair.token0.as_str()).collect();
+        tokens.extend(route.path.last().map(|pair| pair.token1.as_str()));
+        tokens
+    }
+
+    #[test]
+    fn test_routes_through_hubs_ranked_by_output() {
+        let sources = vec![
+            // Thin direct pool; the liquidity sits behind WETH and USDC
+            source(1, "PEPE", "DAI", 1_000_000, 1_000),
+            source(1, "PEPE", "WETH", 1_000_000_000, 1_000_000),
+            source(1, "WETH", "USDC", 1_000_000, 2_000_000_000),
+            source(1, "DAI", "USDC", 2_000_000_000, 2_000_000_000),
+            // Not a hub, so never traded through
+            source(1, "PEPE", "SHIB", 1_000_000_000, 1_000_000_000_000),
+            source(1, "SHIB", "DAI", 1_000_000_000_000, 1_000_000_000_000),
+            // Another chain's pools cannot join a route on this one
+            source(56, "WETH", "DAI", 1_000_000, 2_000_000_000),
+        ];
+        let pathfinder = Pathfinder::default();
+        let routes = pathfinder.find_routes(&sources, "PEPE", "DAI", 10_000, 1.0);
+
+        assert_eq!(tokens(&routes[0]), vec!["PEPE", "WETH", "USDC", "DAI"]);
+        assert_eq!(routes[0].amounts_out.len(), 3);
+        assert_eq!(routes[0].expected_output, routes[0].amounts_out[2]);
+        assert!(routes.windows(2).all(|pair| pair[0].expected_output >= pair[1].expected_output));
+        assert!(routes.iter().all(|route| !tokens(route).contains(&"SHIB")));
+        assert!(routes.iter().all(|route| route.sources.iter().all(|source| source.chain.id == 1)));
+        assert_eq!(tokens(routes.last().unwrap()), vec!["PEPE", "DAI"]);
+
+        // Output is net of the fee of every hop
+        let hop = |amount: f64, reserve_in: f64, reserve_out: f64| {
+            amount * 0.997 * reserve_out / (reserve_in + amount * 0.997)
+        };
+        let expected = hop(hop(hop(10_000.0, 1e9, 1e6), 1e6, 2e9), 2e9, 2e9);
+        assert_eq!(routes[0].expected_output, expected as u128);
+
+        // A tight price impact limit leaves only the deep routes
+        let limit = routes[0].price_impact;
+        let deep = pathfinder.find_routes(&sources, "PEPE", "DAI", 10_000, limit);
+        assert!(deep.iter().all(|route| route.price_impact <= limit));
+        assert!(!deep.iter().any(|route| route.path.len() == 1));
+    }
+
+    #[test]
+    fn test_respects_hop_limit_and_staleness() {
+        let mut sources = vec![
+            source(1, "A", "B", 1_000_000, 1_000_000),
+            source(1, "B", "C", 1_000_000, 1_000_000),
+            source(1, "C", "D", 1_000_000, 1_000_000),
+        ];
+        let config = |max_hops| PathfinderConfig {
+            max_hops,
+            hub_tokens: Vec::new(),
+            max_routes: DEFAULT_MAX_ROUTES,
+        };
+
+        assert_eq!(Pathfinder::new(config(3)).find_routes(&sources, "A", "D", 1_000, 1.0).len(), 1);
+        assert!(Pathfinder::new(config(2)).find_routes(&sources, "A", "D", 1_000, 1.0).is_empty());
+        // Routes run both ways through a pool
+        assert_eq!(Pathfinder::new(config(3)).find_routes(&sources, "D", "A", 1_000, 1.0).len(), 1);
+        assert!(Pathfinder::new(config(3)).find_routes(&sources, "A", "A", 1_000, 1.0).is_empty());
+
+        sources[1].stale = true;
+        assert!(Pathfinder::new(config(3)).find_routes(&sources, "A", "D", 1_000, 1.0).is_empty());
+    }
+}
@@ -0,0 +1,413 @@
+//! Reserve synchronization from chain.
+//!
+//! [`ReserveSync`] periodically re-reads the reserves of every source backed
+//! by an on-chain pool: `getReserves()` for V2 style pairs, and the virtual
+//! reserves implied by `slot0()` and `liquidity()` for V3 pools. Sources not
+//! refreshed within `max_age_secs`, because their reads keep failing or
+//! because nothing syncs them, are marked stale and no longer priced or
+//! routed through. After each pass the aggregated liquidity of every pair
+//! that changed is published on [`LIQUIDITY_SNAPSHOT_SUBJECT`].
+
+use crate::{LiquidityAggregator, LiquiditySource, PoolKind, PoolRef, TokenPair};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sniper_chain::{HedgeConfig, JsonRpcClient, ProviderPool, RpcEndpoint};
+use sniper_core::bus::InMemoryBus;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+/// Subject aggregated liquidity snapshots are published on
+pub const LIQUIDITY_SNAPSHOT_SUBJECT: &str = "liquidity.snapshots";
+
+/// `getReserves()`
+const GET_RESERVES: &str = "0x0902f1ac";
+/// `slot0()`
+const SLOT0: &str = "0x3850c7bd";
+/// `liquidity()`
+const LIQUIDITY: &str = "0x1a686502";
+
+/// RPC endpoints of a chain whose pools are synced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainRpcConfig {
+    pub chain_id: u64,
+    pub rpc_urls: Vec<String>,
+}
+
+/// Reserve sync settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncConfig {
+    /// Time between sync passes
+    pub interval_ms: u64,
+    /// Age after which a source's reserves are stale
+    pub max_age_secs: u64,
+    /// Block reserves are read at
+    pub block: String,
+    pub chains: Vec<ChainRpcConfig>,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: 2_000,
+            max_age_secs: 30,
+            block: "latest".to_string(),
+            chains: Vec::new(),
+        }
+    }
+}
+
+impl SyncConfig {
+    /// Load sync settings from a TOML file
+    pub fn load(path: &str) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path, e))?;
+        Ok(toml::from_str(&text)?)
+    }
+}
+
+/// Source whose reserves could not be read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncFailure {
+    pub source_id: String,
+    pub pool: String,
+    pub error: String,
+}
+
+/// Outcome of a sync pass
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncReport {
+    /// Sources whose reserves were refreshed
+    pub refreshed: usize,
+    pub failures: Vec<SyncFailure>,
+    /// Sources that turned stale in this pass
+    pub newly_stale: usize,
+    /// Snapshots published on the bus
+    pub published: usize,
+}
+
+/// Keeps pool-backed sources in step with the chain
+pub struct ReserveSync {
+    aggregator: Arc<RwLock<LiquidityAggregator>>,
+    rpcs: HashMap<u64, Arc<JsonRpcClient>>,
+    bus: Option<InMemoryBus>,
+    config: SyncConfig,
+}
+
+impl ReserveSync {
+    /// Create a sync engine reading pools through the configured endpoints
+    pub fn new(aggregator: Arc<RwLock<LiquidityAggregator>>, config: SyncConfig) -> Result<Self> {
+        let mut rpcs = HashMap::new();
+        for chain in &config.chains {
+            if chain.rpc_urls.is_empty() {
+                return Err(anyhow::anyhow!("Chain {} has no RPC URLs to sync from", chain.chain_id));
+            }
+            let endpoints = chain
+                .rpc_urls
+                .iter()
+                .enumerate()
+                .map(|(index, url)| RpcEndpoint {
+                    name: format!("chain-{}-rpc-{}", chain.chain_id, index),
+                    url: url.clone(),
+                })
+                .collect();
+            let pool = ProviderPool::new(endpoints, HedgeConfig::default());
+            rpcs.insert(chain.chain_id, Arc::new(JsonRpcClient::new(Arc::new(pool))?));
+        }
+        Ok(Self {
+            aggregator,
+            rpcs,
+            bus: None,
+            config,
+        })
+    }
+
+    /// Read the pools of `chain_id` through `rpc`
+    pub fn with_rpc(mut self, chain_id: u64, rpc: Arc<JsonRpcClient>) -> Self {
+        self.rpcs.insert(chain_id, rpc);
+        self
+    }
+
+    /// Publish snapshots of the pairs each pass changes on `bus`
+    pub fn with_bus(mut self, bus: InMemoryBus) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Refresh every pool-backed source, mark stale sources and publish snapshots
+    pub async fn sync_once(&self) -> Result<SyncReport> {
+        let sources = self.aggregator.read().await.pooled_sources();
+        let reads = sources.iter().map(|(_, source)| async move {
+            let pool = source.pool.as_ref().expect("pooled sources have a pool");
+            let rpc = self
+                .rpcs
+                .get(&source.chain.id)
+                .ok_or_else(|| anyhow::anyhow!("No RPC configured for chain {}", source.chain.id))?;
+            read_reserves(rpc, pool, &self.config.block).await
+        });
+        let reserves = futures::future::join_all(reads).await;
+
+        let mut report = SyncReport::default();
+        let mut changed: Vec<TokenPair> = Vec::new();
+        let mut aggregator = self.aggregator.write().await;
+        let now = now_secs();
+        for ((source_id, source), reserves) in sources.into_iter().zip(reserves) {
+            let pool = source.pool.as_ref().map(|pool| pool.address.clone()).unwrap_or_default();
+            let result = reserves.and_then(|(reserve0, reserve1)| {
+                let pair = source.pair.clone();
+                let refreshed = LiquiditySource {
+                    reserve0,
+                    reserve1,
+                    timestamp: now.max(source.timestamp),
+                    stale: false,
+                    ..source
+                };
+                aggregator.upsert_liquidity_source(&source_id, refreshed).map(|_| pair)
+            });
+            match result {
+                Ok(pair) => {
+                    report.refreshed += 1;
+                    if !changed.contains(&pair) {
+                        changed.push(pair);
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to sync reserves of {} ({}): {}", pool, source_id, e);
+                    report.failures.push(SyncFailure {
+                        source_id,
+                        pool,
+                        error: e.to_string(),
+                    });
+                }
+            }
+        }
+
+        let stale_before = aggregator.stale_count();
+        for pair in aggregator.mark_stale(self.config.max_age_secs, now) {
+            if !changed.contains(&pair) {
+                changed.push(pair);
+            }
+        }
+        report.newly_stale = aggregator.stale_count() - stale_before;
+
+        if let Some(bus) = &self.bus {
+            for pair in &changed {
+                match aggregator.aggregate_liquidity(pair) {
+                    Ok(snapshot) => match bus.publish_now(LIQUIDITY_SNAPSHOT_SUBJECT, &snapshot) {
+                        Ok(()) => report.published += 1,
+                        Err(e) => tracing::warn!("Failed to publish liquidity snapshot: {}", e),
+                    },
+                    Err(e) => tracing::warn!("No snapshot of {}/{}: {}", pair.token0, pair.token1, e),
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Sync every `interval_ms` until the task is dropped
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(self.config.interval_ms));
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                match self.sync_once().await {
+                    Ok(report) if !report.failures.is_empty() || report.newly_stale > 0 => tracing::warn!(
+                        "Reserve sync refreshed {} sources, {} failed, {} turned stale",
+                        report.refreshed,
+                        report.failures.len(),
+                        report.newly_stale
+                    ),
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("Reserve sync failed: {}", e),
+                }
+            }
+        })
+    }
+}
+
+/// Reserves of a pool, oriented to the source's pair
+async fn read_reserves(rpc: &JsonRpcClient, pool: &PoolRef, block: &str) -> Result<(u128, u128)> {
+    let call = |data: &str| rpc.call(json!({"to": pool.address, "data": data}), block, None);
+    let (reserve0, reserve1) = match pool.kind {
+        PoolKind::V2 => {
+            let data = call(GET_RESERVES).await?;
+            (uint(word(&data, 0)?), uint(word(&data, 1)?))
+        }
+        PoolKind::V3 => {
+            let sqrt_price = word_f64(word(&call(SLOT0).await?, 0)?) / 2f64.powi(96);
+            let liquidity = word_f64(word(&call(LIQUIDITY).await?, 0)?);
+            if sqrt_price <= 0.0 {
+                return Err(anyhow::anyhow!("Pool {} is not initialized", pool.address));
+            }
+            ((liquidity / sqrt_price) as u128, (liquidity * sqrt_price) as u128)
+        }
+    };
+    Ok(if pool.inverted { (reserve1, reserve0) } else { (reserve0, reserve1) })
+}
+
+fn word(data: &[u8], index: usize) -> Result<&[u8]> {
+    data.get(32 * index..32 * (index + 1))
+        .ok_or_else(|| anyhow::anyhow!("Return data has no word {}", index))
+}
+
+/// Unsigned integer held by a word, saturating above `u128::MAX`
+fn uint(word: &[u8]) -> u128 {
+    if word[..16].iter().any(|byte| *byte != 0) {
+        return u128::MAX;
+    }
+    word[16..].iter().fold(0, |value, byte| (value << 8) | *byte as u128)
+}
+
+/// Approximate value of a full 256-bit word, for Q64.96 prices
+fn word_f64(word: &[u8]) -> f64 {
+    word.iter().fold(0.0, |value, byte| value * 256.0 + *byte as f64)
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LiquidityConfig;
+    use axum::{routing::post, Json, Router};
+    use serde_json::Value;
+    use sniper_core::types::ChainRef;
+
+    const V2_POOL: &str = "0x00000000000000000000000000000000000000a2";
+    const V3_POOL: &str = "0x00000000000000000000000000000000000000a3";
+    const BROKEN_POOL: &str = "0x00000000000000000000000000000000000000ff";
+
+    /// V2 pool holding 10,000 of its token0 against 30,000,000 of its token1,
+    /// V3 pool at a price of 4
+    async fn handle(Json(request): Json<Value>) -> Json<Value> {
+        let call = &request["params"][0];
+        let result = match (call["to"].as_str().unwrap(), call["data"].as_str().unwrap()) {
+            (V2_POOL, GET_RESERVES) => format!("0x{:064x}{:064x}{:064x}", 10_000u128, 30_000_000u128, 1),
+            // sqrtPriceX96 of 2 * 2^96
+            (V3_POOL, SLOT0) => format!("0x{:0>64}", format!("2{}", "0".repeat(24))),
+            (V3_POOL, LIQUIDITY) => format!("0x{:064x}", 1_000u128),
+            _ => return Json(json!({"jsonrpc": "2.0", "id": request["id"], "error": {"code": 3, "message": "execution reverted"}})),
+        };
+        Json(json!({"jsonrpc": "2.0", "id": request["id"], "result": result}))
+    }
+
+    async fn rpc() -> Arc<JsonRpcClient> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, Router::new().route("/", post(handle))).await });
+        let pool = ProviderPool::new(vec![RpcEndpoint { name: "mock".to_string(), url }], HedgeConfig::default());
+        Arc::new(JsonRpcClient::new(Arc::new(pool)).unwrap())
+    }
+
+    fn source(token1: &str, pool: Option<PoolRef>, timestamp: u64) -> LiquiditySource {
+        LiquiditySource {
+            protocol: "uniswap".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            pair: TokenPair {
+                token0: "WETH".to_string(),
+                token1: token1.to_string(),
+            },
+            reserve0: 1,
+            reserve1: 1,
+            fee: 0.003,
+            timestamp,
+            pool,
+            stale: false,
+        }
+    }
+
+    fn pool(address: &str, kind: PoolKind, inverted: bool) -> Option<PoolRef> {
+        Some(PoolRef {
+            address: address.to_string(),
+            kind,
+            inverted,
+        })
+    }
+
+    #[test]
+    fn test_word_decoding() {
+        let mut data = [0u8; 32];
+        data[31] = 0x10;
+        assert_eq!(uint(&data), 16);
+        assert_eq!(word_f64(&data), 16.0);
+        data[0] = 1;
+        assert_eq!(uint(&data), u128::MAX);
+        assert_eq!(word_f64(&data), 2f64.powi(248) + 16.0);
+        assert!(word(&data, 1).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sync_refreshes_reserves_and_marks_stale_sources() {
+        let now = now_secs();
+        let mut aggregator = LiquidityAggregator::new(LiquidityConfig {
+            chains: vec!["ethereum".to_string()],
+            protocols: vec!["uniswap".to_string()],
+            min_liquidity: 0,
+            max_price_impact: 0.05,
+        });
+        aggregator.add_liquidity_source("v2".to_string(), source("USDC", pool(V2_POOL, PoolKind::V2, true), now));
+        aggregator.add_liquidity_source("v3".to_string(), source("DAI", pool(V3_POOL, PoolKind::V3, false), now));
+        aggregator.add_liquidity_source("broken".to_string(), source("PEPE", pool(BROKEN_POOL, PoolKind::V2, false), now - 60));
+        aggregator.add_liquidity_source("posted".to_string(), source("USDT", None, now - 60));
+        let aggregator = Arc::new(RwLock::new(aggregator));
+
+        let bus = InMemoryBus::new(16);
+        let mut rx = bus.subscribe(LIQUIDITY_SNAPSHOT_SUBJECT);
+        let sync = ReserveSync::new(aggregator.clone(), SyncConfig::default())
+            .unwrap()
+            .with_rpc(1, rpc().await)
+            .with_bus(bus);
+        let report = sync.sync_once().await.unwrap();
+        assert_eq!(report.refreshed, 2);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].pool, BROKEN_POOL);
+        assert_eq!(report.newly_stale, 2);
+
+        let pair = |token1: &str| TokenPair {
+            token0: "WETH".to_string(),
+            token1: token1.to_string(),
+        };
+        {
+            let aggregator = aggregator.read().await;
+            // The V2 pool's token0 is the pair's token1
+            let usdc = aggregator.get_liquidity_sources(&pair("USDC"))[0].clone();
+            assert_eq!((usdc.reserve0, usdc.reserve1), (30_000_000, 10_000));
+            // Price 4 with liquidity 1000: 500 of token0 against 2000 of token1
+            let dai = aggregator.get_liquidity_sources(&pair("DAI"))[0].clone();
+            assert_eq!((dai.reserve0, dai.reserve1), (500, 2_000));
+            assert!(aggregator.aggregate_liquidity(&pair("PEPE")).is_err());
+            assert!(aggregator.aggregate_liquidity(&pair("USDT")).is_err());
+            assert!(aggregator.find_best_route("WETH", "USDT", 1).unwrap().is_none());
+        }
+
+        // Snapshots of the refreshed pairs; stale pairs have none to publish
+        assert_eq!(report.published, 2);
+        let mut published = Vec::new();
+        while let Ok(bytes) = rx.try_recv() {
+            let snapshot: crate::AggregatedLiquidity = serde_json::from_slice(&bytes).unwrap();
+            published.push(snapshot.pair.token1);
+        }
+        published.sort();
+        assert_eq!(published, ["DAI", "USDC"]);
+
+        // A source that refreshes again is fresh again
+        aggregator
+            .write()
+            .await
+            .upsert_liquidity_source("posted", source("USDT", None, now_secs()))
+            .unwrap();
+        assert!(aggregator.read().await.aggregate_liquidity(&pair("USDT")).is_ok());
+    }
+}
@@ -58,6 +58,8 @@ async fn test_liquidity_aggregation_functionality() {
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs(),
+        pool: None,
+        stale: false,
     };
     
     // Add liquidity source
@@ -105,6 +107,8 @@ async fn test_cross_protocol_liquidity_aggregation() {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
+            pool: None,
+            stale: false,
         }),
         ("pancakeswap_bsc", LiquiditySource {
             protocol: "pancakeswap".to_string(),
@@ -120,6 +124,8 @@ async fn test_cross_protocol_liquidity_aggregation() {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
+            pool: None,
+            stale: false,
         }),
         ("sushiswap_ethereum", LiquiditySource {
             protocol: "sushiswap".to_string(),
@@ -135,6 +141,8 @@ async fn test_cross_protocol_liquidity_aggregation() {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
+            pool: None,
+            stale: false,
         }),
     ];
     
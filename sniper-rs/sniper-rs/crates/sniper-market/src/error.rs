@@ -0,0 +1,37 @@
+//! Errors returned by the marketplace.
+
+use sniper_core::errors::{DomainError, ErrorKind};
+use thiserror::Error;
+
+/// Failure of a marketplace operation
+#[derive(Debug, Clone, Error)]
+pub enum MarketError {
+    #[error("Strategy {0} not found")]
+    StrategyNotFound(String),
+    #[error("Package of strategy {0} not found")]
+    PackageNotFound(String),
+    #[error("Review {0} not found")]
+    ReviewNotFound(String),
+    #[error("Rating must be between 1 and 5 stars, got {0}")]
+    InvalidRating(u8),
+    #[error("Review {0} already exists")]
+    DuplicateReview(String),
+    #[error("User {user_id} has already reviewed strategy {strategy_id}")]
+    AlreadyReviewed { user_id: String, strategy_id: String },
+    #[error("Review {0} has been removed")]
+    ReviewRemoved(String),
+}
+
+impl DomainError for MarketError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            MarketError::StrategyNotFound(_) | MarketError::PackageNotFound(_) | MarketError::ReviewNotFound(_) => {
+                ErrorKind::NotFound
+            }
+            MarketError::InvalidRating(_) => ErrorKind::Invalid,
+            MarketError::DuplicateReview(_) | MarketError::AlreadyReviewed { .. } | MarketError::ReviewRemoved(_) => {
+                ErrorKind::Conflict
+            }
+        }
+    }
+}
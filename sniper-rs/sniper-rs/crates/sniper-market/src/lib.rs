@@ -1,16 +1,31 @@
 //! Marketplace for community strategies in the sniper-rs ecosystem.
 //! 
 //! This module provides functionality for sharing, discovering, and rating
-//! community-created trading strategies and plugins.
+//! community-created trading strategies and plugins. Uploads go through the
+//! security scan in [`scan`] and are only published once it passes,
+//! published listings are discovered through the queries in [`search`], and
+//! their ratings are aggregated from the moderated reviews in [`reviews`].
+
+pub mod error;
+pub mod reviews;
+pub mod scan;
+pub mod search;
+
+pub use error::MarketError;
+pub use reviews::{RatingSummary, ReviewFlag, ReviewStatus, ReviewStore};
+
+pub use scan::{FindingSeverity, PackageScanner, ScanCheck, ScanFinding, ScanPolicy, ScanReport};
+pub use search::{SortOrder, StrategyPage, StrategyQuery, StrategySort, TagFacet};
 
-use anyhow::Result;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use std::collections::HashMap;
+use std::sync::RwLock;
 
 /// Strategy listing in the marketplace
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct StrategyListing {
     pub id: String,
     pub name: String,
@@ -19,16 +34,37 @@ pub struct StrategyListing {
     pub author: String,
     pub tags: Vec<String>,
     pub downloads: u64,
+    /// Average stars of the counted reviews
     pub rating: f64,
+    /// Number of reviews behind `rating`
+    #[serde(default)]
+    pub rating_count: u64,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub source_url: Option<String>,
     pub documentation_url: Option<String>,
     pub compatibility: Vec<String>, // List of compatible sniper-rs versions
+    /// SPDX license identifier
+    #[serde(default)]
+    pub license: Option<String>,
+    #[serde(default)]
+    pub status: ListingStatus,
+    /// Result of the latest security scan
+    #[serde(default)]
+    pub scan_report: Option<ScanReport>,
+}
+
+/// Publication status of a listing
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub enum ListingStatus {
+    #[default]
+    PendingScan,
+    Published,
+    Rejected,
 }
 
 /// Strategy rating/review
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct StrategyReview {
     pub id: String,
     pub strategy_id: String,
@@ -36,10 +72,18 @@ pub struct StrategyReview {
     pub rating: u8, // 1-5 stars
     pub comment: Option<String>,
     pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub status: ReviewStatus,
+    /// User reports awaiting moderation
+    #[serde(default)]
+    pub flags: Vec<ReviewFlag>,
+    /// Moderator's reason for removing the review
+    #[serde(default)]
+    pub moderation_note: Option<String>,
 }
 
 /// Marketplace statistics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct MarketStats {
     pub total_strategies: u64,
     pub total_downloads: u64,
@@ -51,49 +95,95 @@ pub struct MarketStats {
 #[async_trait]
 pub trait Marketplace: Send + Sync {
     /// List available strategies
-    async fn list_strategies(&self, filter: Option<&str>) -> Result<Vec<StrategyListing>>;
+    async fn list_strategies(&self, filter: Option<&str>) -> Result<Vec<StrategyListing>, MarketError>;
+    
+    /// Search published strategies, returning one page of results
+    async fn search_strategies(&self, query: &StrategyQuery) -> Result<StrategyPage, MarketError> {
+        Ok(query.apply(self.list_strategies(None).await?))
+    }
     
     /// Get a specific strategy by ID
-    async fn get_strategy(&self, id: &str) -> Result<Option<StrategyListing>>;
+    async fn get_strategy(&self, id: &str) -> Result<Option<StrategyListing>, MarketError>;
+    
+    /// Upload a new strategy package, scanning it before publication
+    async fn upload_strategy(&self, strategy: StrategyListing, package: Vec<u8>) -> Result<StrategyListing, MarketError>;
+    
+    /// Download strategy content of a published strategy
+    async fn download_strategy(&self, id: &str) -> Result<Vec<u8>, MarketError>;
     
-    /// Upload a new strategy
-    async fn upload_strategy(&self, strategy: StrategyListing) -> Result<()>;
+    /// Add a review for a published strategy, updating its rating
+    async fn add_review(&self, review: StrategyReview) -> Result<RatingSummary, MarketError>;
     
-    /// Download strategy content
-    async fn download_strategy(&self, id: &str) -> Result<Vec<u8>>;
+    /// Get reviews for a strategy, leaving out removed ones
+    async fn get_reviews(&self, strategy_id: &str) -> Result<Vec<StrategyReview>, MarketError>;
     
-    /// Add a review for a strategy
-    async fn add_review(&self, review: StrategyReview) -> Result<()>;
+    /// Report a review for moderation
+    async fn flag_review(&self, review_id: &str, flag: ReviewFlag) -> Result<StrategyReview, MarketError>;
     
-    /// Get reviews for a strategy
-    async fn get_reviews(&self, strategy_id: &str) -> Result<Vec<StrategyReview>>;
+    /// Reviews awaiting moderation
+    async fn flagged_reviews(&self) -> Result<Vec<StrategyReview>, MarketError>;
+    
+    /// Remove a review, taking it out of the strategy's rating
+    async fn remove_review(&self, review_id: &str, reason: Option<String>) -> Result<StrategyReview, MarketError>;
+    
+    /// Restore a removed or flagged review, dismissing its reports
+    async fn restore_review(&self, review_id: &str) -> Result<StrategyReview, MarketError>;
     
     /// Get marketplace statistics
-    async fn get_stats(&self) -> Result<MarketStats>;
+    async fn get_stats(&self) -> Result<MarketStats, MarketError>;
 }
 
 /// In-memory implementation of the marketplace for demonstration
 pub struct InMemoryMarketplace {
-    strategies: HashMap<String, StrategyListing>,
-    reviews: HashMap<String, Vec<StrategyReview>>,
-    downloads: HashMap<String, u64>,
+    strategies: RwLock<HashMap<String, StrategyListing>>,
+    packages: RwLock<HashMap<String, Vec<u8>>>,
+    reviews: RwLock<ReviewStore>,
+    downloads: RwLock<HashMap<String, u64>>,
+    scanner: PackageScanner,
 }
 
 impl InMemoryMarketplace {
     /// Create a new in-memory marketplace
     pub fn new() -> Self {
+        Self::with_scanner(PackageScanner::default())
+    }
+
+    /// Create a new in-memory marketplace with a custom upload scanner
+    pub fn with_scanner(scanner: PackageScanner) -> Self {
         Self {
-            strategies: HashMap::new(),
-            reviews: HashMap::new(),
-            downloads: HashMap::new(),
+            strategies: RwLock::new(HashMap::new()),
+            packages: RwLock::new(HashMap::new()),
+            reviews: RwLock::new(ReviewStore::new()),
+            downloads: RwLock::new(HashMap::new()),
+            scanner,
         }
     }
+
+    fn published(&self) -> Vec<StrategyListing> {
+        self.strategies
+            .read()
+            .unwrap()
+            .values()
+            .filter(|s| s.status == ListingStatus::Published)
+            .cloned()
+            .collect()
+    }
+
+    /// Recompute a strategy's rating from its reviews and copy it onto the listing
+    fn refresh_rating(&self, strategy_id: &str) -> RatingSummary {
+        let summary = self.reviews.read().unwrap().summary(strategy_id);
+        if let Some(strategy) = self.strategies.write().unwrap().get_mut(strategy_id) {
+            strategy.rating = summary.rating;
+            strategy.rating_count = summary.count;
+        }
+        summary
+    }
 }
 
 #[async_trait]
 impl Marketplace for InMemoryMarketplace {
-    async fn list_strategies(&self, filter: Option<&str>) -> Result<Vec<StrategyListing>> {
-        let mut strategies: Vec<StrategyListing> = self.strategies.values().cloned().collect();
+    async fn list_strategies(&self, filter: Option<&str>) -> Result<Vec<StrategyListing>, MarketError> {
+        let mut strategies = self.published();
         
         if let Some(filter_text) = filter {
             strategies.retain(|s| {
@@ -106,49 +196,108 @@ impl Marketplace for InMemoryMarketplace {
         Ok(strategies)
     }
     
-    async fn get_strategy(&self, id: &str) -> Result<Option<StrategyListing>> {
-        Ok(self.strategies.get(id).cloned())
+    async fn get_strategy(&self, id: &str) -> Result<Option<StrategyListing>, MarketError> {
+        Ok(self.strategies.read().unwrap().get(id).cloned())
     }
     
-    async fn upload_strategy(&self, strategy: StrategyListing) -> Result<()> {
-        // In a real implementation, this would store the strategy in a database
-        // For now, we'll just acknowledge the upload
-        println!("Uploaded strategy: {}", strategy.name);
-        Ok(())
+    async fn upload_strategy(&self, mut strategy: StrategyListing, package: Vec<u8>) -> Result<StrategyListing, MarketError> {
+        let report = self.scanner.scan(&strategy, &package);
+        strategy.status = if report.passed {
+            ListingStatus::Published
+        } else {
+            ListingStatus::Rejected
+        };
+        strategy.scan_report = Some(report);
+        strategy.updated_at = Utc::now();
+        // Ratings come from reviews, which carry over to new versions
+        let summary = self.reviews.read().unwrap().summary(&strategy.id);
+        strategy.rating = summary.rating;
+        strategy.rating_count = summary.count;
+        
+        self.packages.write().unwrap().insert(strategy.id.clone(), package);
+        self.strategies.write().unwrap().insert(strategy.id.clone(), strategy.clone());
+        Ok(strategy)
     }
     
-    async fn download_strategy(&self, id: &str) -> Result<Vec<u8>> {
+    async fn download_strategy(&self, id: &str) -> Result<Vec<u8>, MarketError> {
+        let published = self
+            .strategies
+            .read()
+            .unwrap()
+            .get(id)
+            .map(|s| s.status == ListingStatus::Published)
+            .unwrap_or(false);
+        if !published {
+            return Err(MarketError::StrategyNotFound(id.to_string()));
+        }
+        
         // Increment download count
-        let mut downloads = self.downloads.clone();
-        let count = downloads.entry(id.to_string()).or_insert(0);
-        *count += 1;
+        *self.downloads.write().unwrap().entry(id.to_string()).or_insert(0) += 1;
+        if let Some(strategy) = self.strategies.write().unwrap().get_mut(id) {
+            strategy.downloads += 1;
+        }
+        
+        self.packages
+            .read()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| MarketError::PackageNotFound(id.to_string()))
+    }
+    
+    async fn add_review(&self, review: StrategyReview) -> Result<RatingSummary, MarketError> {
+        let published = self
+            .strategies
+            .read()
+            .unwrap()
+            .get(&review.strategy_id)
+            .map(|s| s.status == ListingStatus::Published)
+            .unwrap_or(false);
+        if !published {
+            return Err(MarketError::StrategyNotFound(review.strategy_id));
+        }
         
-        // Return dummy strategy content
-        Ok(format!("Strategy content for {}", id).into_bytes())
+        let strategy_id = review.strategy_id.clone();
+        self.reviews.write().unwrap().add(review)?;
+        Ok(self.refresh_rating(&strategy_id))
+    }
+    
+    async fn get_reviews(&self, strategy_id: &str) -> Result<Vec<StrategyReview>, MarketError> {
+        Ok(self.reviews.read().unwrap().visible(strategy_id))
+    }
+    
+    async fn flag_review(&self, review_id: &str, flag: ReviewFlag) -> Result<StrategyReview, MarketError> {
+        self.reviews.write().unwrap().flag(review_id, flag)
+    }
+    
+    async fn flagged_reviews(&self) -> Result<Vec<StrategyReview>, MarketError> {
+        Ok(self.reviews.read().unwrap().flagged())
     }
     
-    async fn add_review(&self, review: StrategyReview) -> Result<()> {
-        // In a real implementation, this would store the review in a database
-        // For now, we'll just acknowledge the review
-        println!("Added review for strategy: {}", review.strategy_id);
-        Ok(())
+    async fn remove_review(&self, review_id: &str, reason: Option<String>) -> Result<StrategyReview, MarketError> {
+        let review = self.reviews.write().unwrap().remove(review_id, reason)?;
+        self.refresh_rating(&review.strategy_id);
+        Ok(review)
     }
     
-    async fn get_reviews(&self, strategy_id: &str) -> Result<Vec<StrategyReview>> {
-        Ok(self.reviews.get(strategy_id).cloned().unwrap_or_default())
+    async fn restore_review(&self, review_id: &str) -> Result<StrategyReview, MarketError> {
+        let review = self.reviews.write().unwrap().restore(review_id)?;
+        self.refresh_rating(&review.strategy_id);
+        Ok(review)
     }
     
-    async fn get_stats(&self) -> Result<MarketStats> {
-        let total_strategies = self.strategies.len() as u64;
-        let total_downloads: u64 = self.downloads.values().sum();
-        let total_reviews: u64 = self.reviews.values().map(|v| v.len() as u64).sum();
+    async fn get_stats(&self) -> Result<MarketStats, MarketError> {
+        let strategies = self.published();
+        let total_strategies = strategies.len() as u64;
+        let total_downloads: u64 = self.downloads.read().unwrap().values().sum();
+        let total_reviews = self.reviews.read().unwrap().total();
         
-        let average_rating = if total_strategies > 0 {
-            // Calculate average rating from all strategies
-            let sum: f64 = self.strategies.values().map(|s| s.rating).sum();
-            sum / total_strategies as f64
-        } else {
+        // Average over the strategies that have been rated
+        let rated: Vec<f64> = strategies.iter().filter(|s| s.rating_count > 0).map(|s| s.rating).collect();
+        let average_rating = if rated.is_empty() {
             0.0
+        } else {
+            rated.iter().sum::<f64>() / rated.len() as f64
         };
         
         Ok(MarketStats {
@@ -195,21 +344,126 @@ mod tests {
             tags: vec!["test".to_string(), "example".to_string()],
             downloads: 0,
             rating: 4.5,
+            rating_count: 0,
             created_at: Utc::now(),
             updated_at: Utc::now(),
             source_url: Some("https://github.com/example/test-strategy".to_string()),
             documentation_url: Some("https://docs.example.com/test-strategy".to_string()),
             compatibility: vec!["0.1.0".to_string(), "0.2.0".to_string()],
+            license: Some("MIT".to_string()),
+            status: ListingStatus::PendingScan,
+            scan_report: None,
         };
         
-        // Test uploading strategy
-        marketplace.upload_strategy(strategy.clone()).await.unwrap();
+        // Test uploading strategy with a minimal WebAssembly module
+        let package = b"\0asm\x01\0\0\0".to_vec();
+        let uploaded = marketplace.upload_strategy(strategy.clone(), package.clone()).await.unwrap();
+        assert_eq!(uploaded.status, ListingStatus::Published);
+        assert!(uploaded.scan_report.unwrap().passed);
         
-        // Test getting strategy
+        // Test getting and downloading strategy
         let retrieved = marketplace.get_strategy("test-strategy-1").await.unwrap();
-        // Note: In our in-memory implementation, we don't actually store the strategy
-        // This is just testing that the method doesn't error
+        assert!(retrieved.is_some());
+        assert_eq!(marketplace.list_strategies(Some("test")).await.unwrap().len(), 1);
+        assert_eq!(marketplace.search_strategies(&StrategyQuery::text("unit testing")).await.unwrap().total, 1);
+        assert_eq!(marketplace.download_strategy("test-strategy-1").await.unwrap(), package);
+        assert_eq!(marketplace.get_stats().await.unwrap().total_downloads, 1);
         
         println!("Strategy listing test passed!");
     }
+    
+    #[tokio::test]
+    async fn test_rejected_strategy_is_not_published() {
+        let marketplace = InMemoryMarketplace::new();
+        
+        let strategy = StrategyListing {
+            id: "test-strategy-2".to_string(),
+            name: "Unlicensed Strategy".to_string(),
+            version: "1.0.0".to_string(),
+            description: "A strategy without a license".to_string(),
+            author: "Test Author".to_string(),
+            tags: vec!["test".to_string()],
+            downloads: 0,
+            rating: 0.0,
+            rating_count: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            source_url: None,
+            documentation_url: None,
+            compatibility: vec!["0.1.0".to_string()],
+            license: None,
+            status: ListingStatus::PendingScan,
+            scan_report: None,
+        };
+        
+        let uploaded = marketplace.upload_strategy(strategy, b"not wasm".to_vec()).await.unwrap();
+        assert_eq!(uploaded.status, ListingStatus::Rejected);
+        let report = uploaded.scan_report.unwrap();
+        assert!(report.findings.iter().any(|f| f.check == ScanCheck::Format));
+        assert!(report.findings.iter().any(|f| f.check == ScanCheck::License));
+        
+        // Rejected listings keep their scan results but are not published
+        assert!(marketplace.get_strategy("test-strategy-2").await.unwrap().is_some());
+        assert!(marketplace.list_strategies(None).await.unwrap().is_empty());
+        assert!(marketplace.download_strategy("test-strategy-2").await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_reviews_drive_listing_rating() {
+        let marketplace = InMemoryMarketplace::new();
+        let strategy = StrategyListing {
+            id: "test-strategy-3".to_string(),
+            name: "Reviewed Strategy".to_string(),
+            version: "1.0.0".to_string(),
+            description: "A strategy to review".to_string(),
+            author: "Test Author".to_string(),
+            tags: vec!["test".to_string()],
+            downloads: 0,
+            rating: 5.0,
+            rating_count: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            source_url: None,
+            documentation_url: None,
+            compatibility: vec!["0.1.0".to_string()],
+            license: Some("MIT".to_string()),
+            status: ListingStatus::PendingScan,
+            scan_report: None,
+        };
+        // Self-declared ratings are replaced by the review aggregate
+        let uploaded = marketplace.upload_strategy(strategy, b"\0asm\x01\0\0\0".to_vec()).await.unwrap();
+        assert_eq!((uploaded.rating, uploaded.rating_count), (0.0, 0));
+        
+        let review = |id: &str, user_id: &str, rating: u8| StrategyReview {
+            id: id.to_string(),
+            strategy_id: "test-strategy-3".to_string(),
+            user_id: user_id.to_string(),
+            rating,
+            comment: None,
+            created_at: Utc::now(),
+            status: ReviewStatus::Visible,
+            flags: Vec::new(),
+            moderation_note: None,
+        };
+        marketplace.add_review(review("r1", "alice", 4)).await.unwrap();
+        marketplace.add_review(review("r2", "bob", 1)).await.unwrap();
+        assert!(marketplace.add_review(review("r3", "alice", 5)).await.is_err());
+        let listing = marketplace.get_strategy("test-strategy-3").await.unwrap().unwrap();
+        assert_eq!((listing.rating, listing.rating_count), (2.5, 2));
+        
+        marketplace
+            .flag_review("r2", ReviewFlag { user_id: "alice".to_string(), reason: "abusive".to_string(), created_at: Utc::now() })
+            .await
+            .unwrap();
+        assert_eq!(marketplace.flagged_reviews().await.unwrap().len(), 1);
+        marketplace.remove_review("r2", Some("abusive".to_string())).await.unwrap();
+        let listing = marketplace.get_strategy("test-strategy-3").await.unwrap().unwrap();
+        assert_eq!((listing.rating, listing.rating_count), (4.0, 1));
+        assert_eq!(marketplace.get_reviews("test-strategy-3").await.unwrap().len(), 1);
+        assert_eq!(marketplace.get_stats().await.unwrap().total_reviews, 1);
+        
+        let mut unknown = review("r4", "carol", 3);
+        unknown.strategy_id = "missing".to_string();
+        assert!(marketplace.add_review(unknown).await.is_err());
+    }
 }
\ No newline at end of file
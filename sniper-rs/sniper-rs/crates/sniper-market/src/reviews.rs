@@ -0,0 +1,244 @@
+//! Review storage, rating aggregation and moderation.
+//!
+//! Each user can review a strategy once. A listing's rating is the average
+//! of its counted reviews: flagged reviews keep counting until a moderator
+//! removes them, and removed reviews are hidden and stop counting. Removal
+//! does not free the user's slot, so a removed review can't simply be posted
+//! again; a moderator restores it instead if the removal was a mistake.
+
+use crate::{MarketError, StrategyReview};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::collections::HashMap;
+
+/// Moderation status of a review
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub enum ReviewStatus {
+    #[default]
+    Visible,
+    /// Reported by users and awaiting a moderator
+    Flagged,
+    /// Hidden by a moderator
+    Removed,
+}
+
+/// User report against a review
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct ReviewFlag {
+    pub user_id: String,
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Aggregated rating of a strategy
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct RatingSummary {
+    pub rating: f64,
+    pub count: u64,
+}
+
+/// Reviews of all strategies
+#[derive(Debug, Default)]
+pub struct ReviewStore {
+    reviews: HashMap<String, Vec<StrategyReview>>,
+}
+
+impl ReviewStore {
+    /// Create an empty review store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store a new review, returning the strategy's updated rating
+    pub fn add(&mut self, mut review: StrategyReview) -> Result<RatingSummary, MarketError> {
+        if !(1..=5).contains(&review.rating) {
+            return Err(MarketError::InvalidRating(review.rating));
+        }
+        if self.find(&review.id).is_some() {
+            return Err(MarketError::DuplicateReview(review.id));
+        }
+        let reviews = self.reviews.entry(review.strategy_id.clone()).or_default();
+        if reviews.iter().any(|existing| existing.user_id == review.user_id) {
+            return Err(MarketError::AlreadyReviewed {
+                user_id: review.user_id,
+                strategy_id: review.strategy_id,
+            });
+        }
+
+        // Moderation state is owned by the store, not the submitter
+        review.status = ReviewStatus::Visible;
+        review.flags.clear();
+        review.moderation_note = None;
+        let strategy_id = review.strategy_id.clone();
+        reviews.push(review);
+        Ok(self.summary(&strategy_id))
+    }
+
+    /// Reviews of a strategy that are not removed, newest first
+    pub fn visible(&self, strategy_id: &str) -> Vec<StrategyReview> {
+        let mut reviews: Vec<StrategyReview> = self
+            .reviews
+            .get(strategy_id)
+            .into_iter()
+            .flatten()
+            .filter(|review| review.status != ReviewStatus::Removed)
+            .cloned()
+            .collect();
+        reviews.sort_by_key(|review| std::cmp::Reverse(review.created_at));
+        reviews
+    }
+
+    /// Reviews awaiting a moderator, most reported first
+    pub fn flagged(&self) -> Vec<StrategyReview> {
+        let mut reviews: Vec<StrategyReview> = self
+            .reviews
+            .values()
+            .flatten()
+            .filter(|review| review.status == ReviewStatus::Flagged)
+            .cloned()
+            .collect();
+        reviews.sort_by(|a, b| b.flags.len().cmp(&a.flags.len()).then_with(|| a.id.cmp(&b.id)));
+        reviews
+    }
+
+    /// Report a review; each user's report is counted once
+    pub fn flag(&mut self, review_id: &str, flag: ReviewFlag) -> Result<StrategyReview, MarketError> {
+        let review = self.find_mut(review_id)?;
+        if review.status == ReviewStatus::Removed {
+            return Err(MarketError::ReviewRemoved(review_id.to_string()));
+        }
+        if !review.flags.iter().any(|existing| existing.user_id == flag.user_id) {
+            review.flags.push(flag);
+        }
+        review.status = ReviewStatus::Flagged;
+        Ok(review.clone())
+    }
+
+    /// Hide a review and stop counting it towards the rating
+    pub fn remove(&mut self, review_id: &str, reason: Option<String>) -> Result<StrategyReview, MarketError> {
+        let review = self.find_mut(review_id)?;
+        review.status = ReviewStatus::Removed;
+        review.moderation_note = reason;
+        Ok(review.clone())
+    }
+
+    /// Make a review visible again, dismissing its reports
+    pub fn restore(&mut self, review_id: &str) -> Result<StrategyReview, MarketError> {
+        let review = self.find_mut(review_id)?;
+        review.status = ReviewStatus::Visible;
+        review.flags.clear();
+        review.moderation_note = None;
+        Ok(review.clone())
+    }
+
+    /// Average and number of a strategy's counted reviews
+    pub fn summary(&self, strategy_id: &str) -> RatingSummary {
+        let ratings: Vec<f64> = self
+            .reviews
+            .get(strategy_id)
+            .into_iter()
+            .flatten()
+            .filter(|review| review.status != ReviewStatus::Removed)
+            .map(|review| review.rating as f64)
+            .collect();
+        if ratings.is_empty() {
+            return RatingSummary::default();
+        }
+        RatingSummary {
+            rating: ratings.iter().sum::<f64>() / ratings.len() as f64,
+            count: ratings.len() as u64,
+        }
+    }
+
+    /// Number of counted reviews across all strategies
+    pub fn total(&self) -> u64 {
+        self.reviews
+            .values()
+            .flatten()
+            .filter(|review| review.status != ReviewStatus::Removed)
+            .count() as u64
+    }
+
+    /// Find a review by ID
+    pub fn find(&self, review_id: &str) -> Option<&StrategyReview> {
+        self.reviews.values().flatten().find(|review| review.id == review_id)
+    }
+
+    fn find_mut(&mut self, review_id: &str) -> Result<&mut StrategyReview, MarketError> {
+        self.reviews
+            .values_mut()
+            .flatten()
+            .find(|review| review.id == review_id)
+            .ok_or_else(|| MarketError::ReviewNotFound(review_id.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn review(id: &str, user_id: &str, rating: u8) -> StrategyReview {
+        StrategyReview {
+            id: id.to_string(),
+            strategy_id: "strategy-1".to_string(),
+            user_id: user_id.to_string(),
+            rating,
+            comment: None,
+            created_at: Utc::now(),
+            status: ReviewStatus::Visible,
+            flags: Vec::new(),
+            moderation_note: None,
+        }
+    }
+
+    fn flag(user_id: &str) -> ReviewFlag {
+        ReviewFlag {
+            user_id: user_id.to_string(),
+            reason: "spam".to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_rating_is_recomputed_per_review() {
+        let mut store = ReviewStore::new();
+        assert_eq!(store.summary("strategy-1"), RatingSummary::default());
+        store.add(review("r1", "alice", 5)).unwrap();
+        let summary = store.add(review("r2", "bob", 2)).unwrap();
+        assert_eq!(summary, RatingSummary { rating: 3.5, count: 2 });
+
+        // One review per user, stars within range, unique ids
+        assert!(store.add(review("r3", "alice", 4)).is_err());
+        assert!(store.add(review("r4", "carol", 0)).is_err());
+        assert!(store.add(review("r1", "dave", 4)).is_err());
+        assert_eq!(store.total(), 2);
+    }
+
+    #[test]
+    fn test_moderation_flow() {
+        let mut store = ReviewStore::new();
+        store.add(review("r1", "alice", 5)).unwrap();
+        store.add(review("r2", "bob", 1)).unwrap();
+
+        store.flag("r2", flag("alice")).unwrap();
+        let flagged = store.flag("r2", flag("alice")).unwrap();
+        assert_eq!((flagged.status, flagged.flags.len()), (ReviewStatus::Flagged, 1));
+        assert_eq!(store.flagged().len(), 1);
+        // Flagged reviews count until a moderator acts
+        assert_eq!(store.summary("strategy-1").count, 2);
+
+        store.remove("r2", Some("abusive".to_string())).unwrap();
+        assert_eq!(store.summary("strategy-1"), RatingSummary { rating: 5.0, count: 1 });
+        assert_eq!(store.visible("strategy-1").len(), 1);
+        assert!(store.flagged().is_empty());
+        assert!(store.flag("r2", flag("carol")).is_err());
+        // The removed review still holds bob's slot
+        assert!(store.add(review("r3", "bob", 1)).is_err());
+
+        let restored = store.restore("r2").unwrap();
+        assert_eq!((restored.status, restored.flags.len()), (ReviewStatus::Visible, 0));
+        assert_eq!(store.summary("strategy-1").count, 2);
+        assert!(store.remove("missing", None).is_err());
+    }
+}
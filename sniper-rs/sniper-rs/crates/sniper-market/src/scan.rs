@@ -0,0 +1,451 @@
+//! Security scanning for uploaded strategy packages.
+//!
+//! Every upload is scanned before it can be published: the package must be a
+//! WebAssembly module within the size limit whose imports only reach allowed
+//! host modules and no forbidden host calls, the listing metadata must be
+//! complete, and the declared license must be on the allow list. Any error
+//! finding keeps the listing out of the marketplace.
+
+use crate::StrategyListing;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// WebAssembly binary magic number
+const WASM_MAGIC: &[u8] = b"\0asm";
+
+/// Supported WebAssembly binary version
+const WASM_VERSION: &[u8] = &[1, 0, 0, 0];
+
+/// Section ID of the import section
+const IMPORT_SECTION: u8 = 2;
+
+/// Scan check that produced a finding
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub enum ScanCheck {
+    Size,
+    Format,
+    HostCalls,
+    Metadata,
+    License,
+}
+
+/// Finding severity; errors block publication
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub enum FindingSeverity {
+    Warning,
+    Error,
+}
+
+/// Single scan finding
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ScanFinding {
+    pub check: ScanCheck,
+    pub severity: FindingSeverity,
+    pub message: String,
+}
+
+/// Result of scanning an upload
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ScanReport {
+    pub scanned_at: DateTime<Utc>,
+    pub package_size: usize,
+    /// Imports declared by the module, as `module::name`
+    pub imports: Vec<String>,
+    pub findings: Vec<ScanFinding>,
+    pub passed: bool,
+}
+
+/// Scanning rules
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanPolicy {
+    /// Largest accepted package in bytes
+    pub max_package_bytes: usize,
+    /// Host modules a package may import from
+    pub allowed_import_modules: Vec<String>,
+    /// Forbidden `module::name` imports; a trailing `*` matches any suffix
+    pub forbidden_imports: Vec<String>,
+    /// Accepted SPDX license identifiers
+    pub allowed_licenses: Vec<String>,
+}
+
+impl Default for ScanPolicy {
+    fn default() -> Self {
+        Self {
+            max_package_bytes: 5 * 1024 * 1024,
+            allowed_import_modules: vec![
+                "env".to_string(),
+                "sniper".to_string(),
+                "wasi_snapshot_preview1".to_string(),
+            ],
+            forbidden_imports: vec![
+                "wasi_snapshot_preview1::sock_*".to_string(),
+                "wasi_snapshot_preview1::path_*".to_string(),
+                "wasi_snapshot_preview1::proc_raise".to_string(),
+                "env::system".to_string(),
+                "env::exec*".to_string(),
+            ],
+            allowed_licenses: vec![
+                "MIT".to_string(),
+                "Apache-2.0".to_string(),
+                "BSD-2-Clause".to_string(),
+                "BSD-3-Clause".to_string(),
+                "MPL-2.0".to_string(),
+                "ISC".to_string(),
+            ],
+        }
+    }
+}
+
+/// Scanner run on every strategy upload
+#[derive(Debug, Clone, Default)]
+pub struct PackageScanner {
+    policy: ScanPolicy,
+}
+
+impl PackageScanner {
+    /// Create a scanner with a policy
+    pub fn new(policy: ScanPolicy) -> Self {
+        Self { policy }
+    }
+
+    /// Get the scanning policy
+    pub fn policy(&self) -> &ScanPolicy {
+        &self.policy
+    }
+
+    /// Scan a listing and its package
+    pub fn scan(&self, listing: &StrategyListing, package: &[u8]) -> ScanReport {
+        let mut findings = Vec::new();
+
+        if package.is_empty() {
+            findings.push(error(ScanCheck::Size, "Package is empty".to_string()));
+        } else if package.len() > self.policy.max_package_bytes {
+            findings.push(error(
+                ScanCheck::Size,
+                format!(
+                    "Package is {} bytes, above the {} byte limit",
+                    package.len(),
+                    self.policy.max_package_bytes
+                ),
+            ));
+        }
+
+        let imports = match wasm_imports(package) {
+            Ok(imports) => imports,
+            Err(e) => {
+                findings.push(error(ScanCheck::Format, format!("Invalid WebAssembly module: {}", e)));
+                Vec::new()
+            }
+        };
+        for (module, name) in &imports {
+            self.check_import(module, name, &mut findings);
+        }
+
+        check_metadata(listing, &mut findings);
+        self.check_license(listing, &mut findings);
+
+        let passed = findings.iter().all(|finding| finding.severity != FindingSeverity::Error);
+        ScanReport {
+            scanned_at: Utc::now(),
+            package_size: package.len(),
+            imports: imports
+                .iter()
+                .map(|(module, name)| format!("{}::{}", module, name))
+                .collect(),
+            findings,
+            passed,
+        }
+    }
+
+    /// Check an import against the allowed modules and forbidden host calls
+    fn check_import(&self, module: &str, name: &str, findings: &mut Vec<ScanFinding>) {
+        if !self.policy.allowed_import_modules.iter().any(|allowed| allowed == module) {
+            findings.push(error(
+                ScanCheck::HostCalls,
+                format!("Import from unknown host module {}::{}", module, name),
+            ));
+            return;
+        }
+
+        let import = format!("{}::{}", module, name);
+        let forbidden = self.policy.forbidden_imports.iter().any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => import.starts_with(prefix),
+            None => import == *pattern,
+        });
+        if forbidden {
+            findings.push(error(ScanCheck::HostCalls, format!("Forbidden host call {}", import)));
+        }
+    }
+
+    /// Check the declared license against the allow list
+    fn check_license(&self, listing: &StrategyListing, findings: &mut Vec<ScanFinding>) {
+        match listing.license.as_deref().map(str::trim) {
+            None | Some("") => findings.push(error(ScanCheck::License, "No license declared".to_string())),
+            Some(license) if !self.policy.allowed_licenses.iter().any(|allowed| allowed == license) => {
+                findings.push(error(ScanCheck::License, format!("License {} is not accepted", license)));
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+/// Check that the listing metadata is complete
+fn check_metadata(listing: &StrategyListing, findings: &mut Vec<ScanFinding>) {
+    let required = [
+        ("name", &listing.name),
+        ("version", &listing.version),
+        ("description", &listing.description),
+        ("author", &listing.author),
+    ];
+    for (field, value) in required {
+        if value.trim().is_empty() {
+            findings.push(error(ScanCheck::Metadata, format!("Missing {}", field)));
+        }
+    }
+
+    let semver = listing.version.split('.').count() == 3
+        && listing.version.split('.').all(|part| part.parse::<u64>().is_ok());
+    if !listing.version.trim().is_empty() && !semver {
+        findings.push(error(
+            ScanCheck::Metadata,
+            format!("Version {} is not MAJOR.MINOR.PATCH", listing.version),
+        ));
+    }
+
+    if listing.compatibility.is_empty() {
+        findings.push(error(ScanCheck::Metadata, "No compatible sniper-rs versions listed".to_string()));
+    }
+    if listing.documentation_url.is_none() && listing.source_url.is_none() {
+        findings.push(warning(ScanCheck::Metadata, "No source or documentation URL".to_string()));
+    }
+    if listing.tags.is_empty() {
+        findings.push(warning(ScanCheck::Metadata, "No tags".to_string()));
+    }
+}
+
+fn error(check: ScanCheck, message: String) -> ScanFinding {
+    ScanFinding {
+        check,
+        severity: FindingSeverity::Error,
+        message,
+    }
+}
+
+fn warning(check: ScanCheck, message: String) -> ScanFinding {
+    ScanFinding {
+        check,
+        severity: FindingSeverity::Warning,
+        message,
+    }
+}
+
+/// Cursor over a WebAssembly binary
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| anyhow::anyhow!("unexpected end of module"))?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let mut result: u32 = 0;
+        for shift in (0..35).step_by(7) {
+            let byte = self.byte()?;
+            result |= ((byte & 0x7f) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+        Err(anyhow::anyhow!("malformed LEB128 integer"))
+    }
+
+    fn name(&mut self) -> Result<String> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| anyhow::anyhow!("import name is not UTF-8"))
+    }
+
+    fn limits(&mut self) -> Result<()> {
+        let flags = self.byte()?;
+        self.u32()?;
+        if flags & 1 == 1 {
+            self.u32()?;
+        }
+        Ok(())
+    }
+}
+
+/// List the `(module, name)` imports of a WebAssembly module
+pub fn wasm_imports(bytes: &[u8]) -> Result<Vec<(String, String)>> {
+    let mut reader = Reader::new(bytes);
+    if reader.take(4)? != WASM_MAGIC {
+        return Err(anyhow::anyhow!("missing WebAssembly magic number"));
+    }
+    if reader.take(4)? != WASM_VERSION {
+        return Err(anyhow::anyhow!("unsupported WebAssembly version"));
+    }
+
+    let mut imports = Vec::new();
+    while !reader.is_empty() {
+        let id = reader.byte()?;
+        let size = reader.u32()? as usize;
+        let payload = reader.take(size)?;
+        if id != IMPORT_SECTION {
+            continue;
+        }
+
+        let mut section = Reader::new(payload);
+        let count = section.u32()?;
+        for _ in 0..count {
+            let module = section.name()?;
+            let name = section.name()?;
+            match section.byte()? {
+                // Function: type index
+                0x00 => {
+                    section.u32()?;
+                }
+                // Table: reference type and limits
+                0x01 => {
+                    section.byte()?;
+                    section.limits()?;
+                }
+                // Memory: limits
+                0x02 => section.limits()?,
+                // Global: value type and mutability
+                0x03 => {
+                    section.take(2)?;
+                }
+                // Tag: attribute and type index
+                0x04 => {
+                    section.byte()?;
+                    section.u32()?;
+                }
+                kind => return Err(anyhow::anyhow!("unknown import kind {}", kind)),
+            }
+            imports.push((module, name));
+        }
+    }
+
+    Ok(imports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encode a module whose import section holds the given function imports
+    fn module_with_imports(imports: &[(&str, &str)]) -> Vec<u8> {
+        let mut section = vec![imports.len() as u8];
+        for (module, name) in imports {
+            section.push(module.len() as u8);
+            section.extend_from_slice(module.as_bytes());
+            section.push(name.len() as u8);
+            section.extend_from_slice(name.as_bytes());
+            section.extend_from_slice(&[0x00, 0x00]);
+        }
+
+        let mut module = Vec::new();
+        module.extend_from_slice(WASM_MAGIC);
+        module.extend_from_slice(WASM_VERSION);
+        module.push(IMPORT_SECTION);
+        module.push(section.len() as u8);
+        module.extend(section);
+        module
+    }
+
+    fn listing() -> StrategyListing {
+        StrategyListing {
+            id: "strategy-1".to_string(),
+            name: "Momentum".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Momentum strategy".to_string(),
+            author: "Author".to_string(),
+            tags: vec!["momentum".to_string()],
+            downloads: 0,
+            rating: 0.0,
+            rating_count: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            source_url: Some("https://github.com/example/momentum".to_string()),
+            documentation_url: None,
+            compatibility: vec!["0.1.0".to_string()],
+            license: Some("MIT".to_string()),
+            status: Default::default(),
+            scan_report: None,
+        }
+    }
+
+    #[test]
+    fn test_wasm_imports() {
+        let module = module_with_imports(&[("env", "log"), ("sniper", "get_price")]);
+        let imports = wasm_imports(&module).unwrap();
+        assert_eq!(imports.len(), 2);
+        assert_eq!(imports[1], ("sniper".to_string(), "get_price".to_string()));
+
+        assert!(wasm_imports(b"not wasm").is_err());
+        assert!(wasm_imports(&module[..module.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_clean_package_passes() {
+        let scanner = PackageScanner::default();
+        let report = scanner.scan(&listing(), &module_with_imports(&[("sniper", "get_price")]));
+        assert!(report.passed);
+        assert!(report.findings.iter().all(|finding| finding.severity == FindingSeverity::Warning));
+    }
+
+    #[test]
+    fn test_forbidden_host_calls_and_license_fail() {
+        let scanner = PackageScanner::default();
+        let mut bad_listing = listing();
+        bad_listing.license = Some("Proprietary".to_string());
+        bad_listing.version = "1.0".to_string();
+
+        let package = module_with_imports(&[
+            ("wasi_snapshot_preview1", "sock_send"),
+            ("evil", "steal_keys"),
+        ]);
+        let report = scanner.scan(&bad_listing, &package);
+        assert!(!report.passed);
+
+        let checks: Vec<&ScanCheck> = report.findings.iter().map(|finding| &finding.check).collect();
+        assert_eq!(checks.iter().filter(|check| ***check == ScanCheck::HostCalls).count(), 2);
+        assert!(checks.contains(&&ScanCheck::License));
+        assert!(checks.contains(&&ScanCheck::Metadata));
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let scanner = PackageScanner::new(ScanPolicy {
+            max_package_bytes: 8,
+            ..ScanPolicy::default()
+        });
+        let report = scanner.scan(&listing(), &module_with_imports(&[("env", "log")]));
+        assert!(!report.passed);
+        assert_eq!(report.findings[0].check, ScanCheck::Size);
+    }
+}
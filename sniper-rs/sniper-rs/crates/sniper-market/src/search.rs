@@ -0,0 +1,318 @@
+//! Structured strategy search.
+//!
+//! A [`StrategyQuery`] narrows the published listings by free text, tags and
+//! compatible sniper-rs version, orders them and cuts out one page. Text
+//! search is case-insensitive: every term has to appear in the name,
+//! description or tags, and name matches rank above tag and description
+//! matches. Tag facets are counted over all matches, not just the page, so
+//! clients can show how many results each tag would narrow to.
+
+use crate::StrategyListing;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+/// Page size when the query sets none
+pub const DEFAULT_PAGE_SIZE: usize = 20;
+
+/// Largest page a query can ask for
+pub const MAX_PAGE_SIZE: usize = 100;
+
+/// Order of search results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategySort {
+    /// Best text match first; recency without search text
+    Relevance,
+    Rating,
+    Downloads,
+    /// Most recently updated first
+    Recent,
+    Name,
+}
+
+/// Direction of the sort
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Strategy search query
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StrategyQuery {
+    /// Free text matched against name, description and tags
+    pub text: Option<String>,
+    /// Tags a listing must all carry
+    pub tags: Vec<String>,
+    /// sniper-rs version a listing must be compatible with; `0.1.x` or `0.1.*` entries match any patch
+    pub compatible_with: Option<String>,
+    /// Relevance with search text, recency otherwise
+    pub sort: Option<StrategySort>,
+    /// Ascending or descending; names sort ascending when unset
+    pub order: Option<SortOrder>,
+    /// Page size, capped at [`MAX_PAGE_SIZE`]
+    pub limit: Option<usize>,
+    pub offset: usize,
+}
+
+/// Number of matching listings carrying a tag
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TagFacet {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// One page of search results
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StrategyPage {
+    pub items: Vec<StrategyListing>,
+    /// Matches across all pages
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    /// Offset of the next page, if there is one
+    pub next_offset: Option<usize>,
+    /// Tags of all matches, most common first
+    pub tag_facets: Vec<TagFacet>,
+}
+
+impl StrategyQuery {
+    /// Free-text query with default paging
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: Some(text.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Page size after defaults and the cap
+    pub fn page_size(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+    }
+
+    /// Filter, sort and page `listings`
+    pub fn apply(&self, listings: Vec<StrategyListing>) -> StrategyPage {
+        let terms = self.terms();
+        let mut matches: Vec<(u32, StrategyListing)> = listings
+            .into_iter()
+            .filter(|listing| self.matches_tags(listing) && self.matches_compatibility(listing))
+            .filter_map(|listing| relevance(&listing, &terms).map(|score| (score, listing)))
+            .collect();
+
+        let tag_facets = tag_facets(matches.iter().map(|(_, listing)| listing));
+
+        let sort = self.sort.unwrap_or(if terms.is_empty() {
+            StrategySort::Recent
+        } else {
+            StrategySort::Relevance
+        });
+        let order = self.order.unwrap_or(if sort == StrategySort::Name {
+            SortOrder::Asc
+        } else {
+            SortOrder::Desc
+        });
+        matches.sort_by(|(score_a, a), (score_b, b)| {
+            let ordering = match sort {
+                StrategySort::Relevance => score_a.cmp(score_b).then(a.updated_at.cmp(&b.updated_at)),
+                StrategySort::Rating => a.rating.partial_cmp(&b.rating).unwrap_or(Ordering::Equal),
+                StrategySort::Downloads => a.downloads.cmp(&b.downloads),
+                StrategySort::Recent => a.updated_at.cmp(&b.updated_at),
+                StrategySort::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            };
+            let ordering = match order {
+                SortOrder::Asc => ordering,
+                SortOrder::Desc => ordering.reverse(),
+            };
+            // Ids break ties so pages are stable between requests
+            ordering.then_with(|| a.id.cmp(&b.id))
+        });
+
+        let total = matches.len();
+        let limit = self.page_size();
+        let items: Vec<StrategyListing> = matches
+            .into_iter()
+            .skip(self.offset)
+            .take(limit)
+            .map(|(_, listing)| listing)
+            .collect();
+        let next_offset = Some(self.offset + items.len()).filter(|next| *next < total && !items.is_empty());
+
+        StrategyPage {
+            items,
+            total,
+            offset: self.offset,
+            limit,
+            next_offset,
+            tag_facets,
+        }
+    }
+
+    fn terms(&self) -> Vec<String> {
+        self.text
+            .as_deref()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect()
+    }
+
+    fn matches_tags(&self, listing: &StrategyListing) -> bool {
+        self.tags
+            .iter()
+            .all(|wanted| listing.tags.iter().any(|tag| tag.eq_ignore_ascii_case(wanted)))
+    }
+
+    fn matches_compatibility(&self, listing: &StrategyListing) -> bool {
+        let Some(version) = self.compatible_with.as_deref() else {
+            return true;
+        };
+        listing.compatibility.iter().any(|supported| {
+            match supported.strip_suffix(".x").or_else(|| supported.strip_suffix(".*")) {
+                Some(prefix) => version.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('.')),
+                None => supported == version,
+            }
+        })
+    }
+}
+
+/// Match score of `listing` against lowercase `terms`, or None if a term is missing
+fn relevance(listing: &StrategyListing, terms: &[String]) -> Option<u32> {
+    let name = listing.name.to_lowercase();
+    let description = listing.description.to_lowercase();
+    let tags: Vec<String> = listing.tags.iter().map(|tag| tag.to_lowercase()).collect();
+
+    terms.iter().try_fold(0, |score, term| {
+        let mut term_score = 0;
+        if name.contains(term.as_str()) {
+            term_score += 3;
+        }
+        if tags.iter().any(|tag| tag.contains(term.as_str())) {
+            term_score += 2;
+        }
+        if description.contains(term.as_str()) {
+            term_score += 1;
+        }
+        (term_score > 0).then_some(score + term_score)
+    })
+}
+
+fn tag_facets<'a>(listings: impl Iterator<Item = &'a StrategyListing>) -> Vec<TagFacet> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for listing in listings {
+        let mut tags: Vec<String> = listing.tags.iter().map(|tag| tag.to_lowercase()).collect();
+        tags.sort();
+        tags.dedup();
+        for tag in tags {
+            *counts.entry(tag).or_insert(0) += 1;
+        }
+    }
+    let mut facets: Vec<TagFacet> = counts.into_iter().map(|(tag, count)| TagFacet { tag, count }).collect();
+    facets.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+    facets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ListingStatus;
+    use chrono::{Duration, Utc};
+
+    fn listing(id: &str, name: &str, description: &str, tags: &[&str], rating: f64, downloads: u64, age_days: i64) -> StrategyListing {
+        let updated_at = Utc::now() - Duration::days(age_days);
+        StrategyListing {
+            id: id.to_string(),
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            description: description.to_string(),
+            author: "Test Author".to_string(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            downloads,
+            rating,
+            rating_count: 0,
+            created_at: updated_at,
+            updated_at,
+            source_url: None,
+            documentation_url: None,
+            compatibility: vec!["0.1.x".to_string()],
+            license: Some("MIT".to_string()),
+            status: ListingStatus::Published,
+            scan_report: None,
+        }
+    }
+
+    fn listings() -> Vec<StrategyListing> {
+        let mut pinned = listing("grid", "Grid Bot", "Range trading with a momentum filter", &["grid", "range"], 3.5, 900, 1);
+        pinned.compatibility = vec!["0.2.0".to_string()];
+        vec![
+            listing("momentum", "Momentum Sniper", "Buys breakouts on new pairs", &["momentum", "sniper"], 4.5, 100, 3),
+            listing("mev", "Backrun", "Backruns large swaps", &["mev", "sniper"], 4.8, 50, 2),
+            pinned,
+        ]
+    }
+
+    fn ids(page: &StrategyPage) -> Vec<&str> {
+        page.items.iter().map(|listing| listing.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_text_search_ranks_name_matches_first() {
+        let page = StrategyQuery::text("MOMENTUM").apply(listings());
+        assert_eq!(ids(&page), vec!["momentum", "grid"]);
+
+        // Every term has to match somewhere
+        assert_eq!(ids(&StrategyQuery::text("momentum breakouts").apply(listings())), vec!["momentum"]);
+        assert!(StrategyQuery::text("arbitrage").apply(listings()).items.is_empty());
+    }
+
+    #[test]
+    fn test_filters_sorting_and_facets() {
+        let query = StrategyQuery {
+            tags: vec!["Sniper".to_string()],
+            sort: Some(StrategySort::Rating),
+            ..StrategyQuery::default()
+        };
+        let page = query.apply(listings());
+        assert_eq!(ids(&page), vec!["mev", "momentum"]);
+        assert_eq!(page.tag_facets[0], TagFacet { tag: "sniper".to_string(), count: 2 });
+
+        let by_downloads = StrategyQuery {
+            sort: Some(StrategySort::Downloads),
+            order: Some(SortOrder::Asc),
+            ..StrategyQuery::default()
+        };
+        assert_eq!(ids(&by_downloads.apply(listings())), vec!["mev", "momentum", "grid"]);
+        assert_eq!(ids(&StrategyQuery::default().apply(listings())), vec!["grid", "mev", "momentum"]);
+
+        let compatible = |version: &str| StrategyQuery {
+            compatible_with: Some(version.to_string()),
+            sort: Some(StrategySort::Name),
+            ..StrategyQuery::default()
+        };
+        assert_eq!(ids(&compatible("0.1.3").apply(listings())), vec!["mev", "momentum"]);
+        assert_eq!(ids(&compatible("0.2.0").apply(listings())), vec!["grid"]);
+        assert!(compatible("0.10.0").apply(listings()).items.is_empty());
+    }
+
+    #[test]
+    fn test_pagination() {
+        let mut query = StrategyQuery {
+            sort: Some(StrategySort::Name),
+            limit: Some(2),
+            ..StrategyQuery::default()
+        };
+        let first = query.apply(listings());
+        assert_eq!((ids(&first), first.total, first.next_offset), (vec!["mev", "grid"], 3, Some(2)));
+
+        query.offset = 2;
+        let second = query.apply(listings());
+        assert_eq!((ids(&second), second.next_offset), (vec!["momentum"], None));
+
+        query.limit = Some(10_000);
+        assert_eq!(query.page_size(), MAX_PAGE_SIZE);
+    }
+}
@@ -0,0 +1,830 @@
+//! Alert rule expression language.
+//!
+//! Alert rules are boolean expressions over the metrics registry, such as
+//! `rate(orders_failed_total[5m]) > 0.1 && congestion == "high"`:
+//!
+//! ```text
+//! expr       := and ("||" and)*
+//! and        := unary ("&&" unary)*
+//! unary      := "!" unary | comparison
+//! comparison := operand ("==" | "!=" | "<" | "<=" | ">" | ">=") operand
+//!             | "(" expr ")"
+//! operand    := number | string | metric | function "(" metric "[" window "]" ")"
+//! window     := integer ("s" | "m" | "h" | "d")
+//! ```
+//!
+//! Expressions are parsed and type-checked when a rule is created, so typos,
+//! unknown functions and comparisons between numbers and strings are rejected
+//! up front. A bare metric reads its current value, either a number or the
+//! active state of a state metric; the range functions `rate`, `increase`,
+//! `avg_over_time`, `min_over_time` and `max_over_time` read the samples
+//! [`MetricHistory`] collects each time the rules are evaluated. Comparisons
+//! against a range with too few samples are false.
+//!
+//! Values come from a [`MetricSource`]: the local registry, metrics scraped
+//! from a Prometheus endpoint, or several of them consulted in order.
+
+use crate::MetricsRegistry;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+/// Current value of a metric
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetricValue {
+    Number(f64),
+    /// Active state of a state metric
+    Text(String),
+}
+
+/// Where alert rules read current metric values from
+pub trait MetricSource {
+    /// Current value of a metric, if the source has it
+    fn value(&self, name: &str) -> Option<MetricValue>;
+}
+
+impl MetricSource for MetricsRegistry {
+    fn value(&self, name: &str) -> Option<MetricValue> {
+        MetricsRegistry::value(self, name)
+    }
+}
+
+/// Sources consulted in order, the first holding a metric providing its value
+pub struct MetricSources<'a>(pub Vec<&'a dyn MetricSource>);
+
+impl MetricSource for MetricSources<'_> {
+    fn value(&self, name: &str) -> Option<MetricValue> {
+        self.0.iter().find_map(|source| source.value(name))
+    }
+}
+
+/// Function over a window of metric samples
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RangeFunction {
+    /// Per-second increase of a counter
+    Rate,
+    /// Total increase of a counter
+    Increase,
+    AvgOverTime,
+    MinOverTime,
+    MaxOverTime,
+}
+
+impl RangeFunction {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "rate" => Some(RangeFunction::Rate),
+            "increase" => Some(RangeFunction::Increase),
+            "avg_over_time" => Some(RangeFunction::AvgOverTime),
+            "min_over_time" => Some(RangeFunction::MinOverTime),
+            "max_over_time" => Some(RangeFunction::MaxOverTime),
+            _ => None,
+        }
+    }
+
+    /// Apply the function to samples in time order, if there are enough of them
+    fn apply(&self, samples: &[(DateTime<Utc>, f64)]) -> Option<f64> {
+        let values = samples.iter().map(|(_, value)| *value);
+        match self {
+            RangeFunction::Rate => {
+                let (first, last) = (samples.first()?.0, samples.last()?.0);
+                let elapsed = (last - first).num_milliseconds() as f64 / 1000.0;
+                if samples.len() < 2 || elapsed <= 0.0 {
+                    return None;
+                }
+                Some(counter_increase(samples) / elapsed)
+            }
+            RangeFunction::Increase => {
+                if samples.len() < 2 {
+                    return None;
+                }
+                Some(counter_increase(samples))
+            }
+            RangeFunction::AvgOverTime => {
+                if samples.is_empty() {
+                    return None;
+                }
+                Some(values.sum::<f64>() / samples.len() as f64)
+            }
+            RangeFunction::MinOverTime => values.reduce(f64::min),
+            RangeFunction::MaxOverTime => values.reduce(f64::max),
+        }
+    }
+}
+
+/// Increase of a counter over samples, treating any drop as a reset
+fn counter_increase(samples: &[(DateTime<Utc>, f64)]) -> f64 {
+    samples
+        .windows(2)
+        .map(|pair| {
+            let (previous, current) = (pair[0].1, pair[1].1);
+            if current >= previous {
+                current - previous
+            } else {
+                current
+            }
+        })
+        .sum()
+}
+
+/// Comparison operators
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    fn is_ordering(&self) -> bool {
+        !matches!(self, CompareOp::Eq | CompareOp::Ne)
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        }
+    }
+}
+
+/// Parsed alert expression tree
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Text(String),
+    Metric(String),
+    Range {
+        function: RangeFunction,
+        metric: String,
+        window: Duration,
+    },
+    Compare {
+        op: CompareOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+/// Alert rule condition, kept alongside the text it was parsed from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct AlertExpression {
+    source: String,
+    expr: Expr,
+}
+
+impl AlertExpression {
+    /// Parse and type-check an expression
+    pub fn parse(source: &str) -> Result<Self> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, position: 0 };
+        let expr = parser.parse_or()?;
+        if let Some((token, offset)) = parser.tokens.get(parser.position) {
+            return Err(anyhow::anyhow!("Unexpected {} at position {}", token, offset));
+        }
+        if check(&expr)? != Type::Bool {
+            return Err(anyhow::anyhow!("Alert expression must be a comparison, e.g. `{} > 0`", source.trim()));
+        }
+        Ok(Self {
+            source: source.trim().to_string(),
+            expr,
+        })
+    }
+
+    /// Text the expression was parsed from
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Parsed expression tree
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    /// Metrics read by range functions, with the longest window each is read over
+    pub fn range_selectors(&self) -> HashMap<String, Duration> {
+        let mut selectors = HashMap::new();
+        collect_ranges(&self.expr, &mut selectors);
+        selectors
+    }
+
+    /// Evaluate against current metric values and the sampled history
+    pub fn evaluate(&self, metrics: &dyn MetricSource, history: &MetricHistory, now: DateTime<Utc>) -> Result<bool> {
+        match eval(&self.expr, metrics, history, now)? {
+            Value::Bool(result) => Ok(result),
+            _ => Err(anyhow::anyhow!("Alert expression `{}` is not a condition", self.source)),
+        }
+    }
+}
+
+impl fmt::Display for AlertExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl TryFrom<String> for AlertExpression {
+    type Error = anyhow::Error;
+
+    fn try_from(source: String) -> Result<Self> {
+        Self::parse(&source)
+    }
+}
+
+impl From<AlertExpression> for String {
+    fn from(expression: AlertExpression) -> Self {
+        expression.source
+    }
+}
+
+/// Samples of the metrics alert rules read over a window
+#[derive(Debug, Clone, Default)]
+pub struct MetricHistory {
+    samples: HashMap<String, VecDeque<(DateTime<Utc>, f64)>>,
+}
+
+impl MetricHistory {
+    /// Create an empty history
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a sample, dropping those older than `retention`
+    pub fn record(&mut self, metric: &str, value: f64, at: DateTime<Utc>, retention: Duration) {
+        let samples = self.samples.entry(metric.to_string()).or_default();
+        if samples.back().map(|(last, _)| *last >= at).unwrap_or(false) {
+            return;
+        }
+        samples.push_back((at, value));
+        while samples.front().map(|(first, _)| *first < at - retention).unwrap_or(false) {
+            samples.pop_front();
+        }
+    }
+
+    /// Sample a numeric metric from a source
+    pub fn sample(&mut self, metrics: &dyn MetricSource, metric: &str, at: DateTime<Utc>, retention: Duration) -> Result<()> {
+        match metrics.value(metric) {
+            Some(MetricValue::Number(value)) => {
+                self.record(metric, value, at, retention);
+                Ok(())
+            }
+            Some(MetricValue::Text(_)) => Err(anyhow::anyhow!("Metric {} is a state, not a number", metric)),
+            None => Err(anyhow::anyhow!("Metric not found: {}", metric)),
+        }
+    }
+
+    /// Samples of a metric taken within `window` of `now`, oldest first
+    pub fn window(&self, metric: &str, window: Duration, now: DateTime<Utc>) -> Vec<(DateTime<Utc>, f64)> {
+        self.samples
+            .get(metric)
+            .map(|samples| {
+                samples
+                    .iter()
+                    .filter(|(at, _)| *at >= now - window && *at <= now)
+                    .copied()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+fn collect_ranges(expr: &Expr, selectors: &mut HashMap<String, Duration>) {
+    match expr {
+        Expr::Range { metric, window, .. } => {
+            let longest = selectors.entry(metric.clone()).or_insert(*window);
+            *longest = (*longest).max(*window);
+        }
+        Expr::Compare { left, right, .. } | Expr::And(left, right) | Expr::Or(left, right) => {
+            collect_ranges(left, selectors);
+            collect_ranges(right, selectors);
+        }
+        Expr::Not(inner) => collect_ranges(inner, selectors),
+        Expr::Number(_) | Expr::Text(_) | Expr::Metric(_) => {}
+    }
+}
+
+/// Static type of an expression; bare metrics may hold numbers or states
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Type {
+    Number,
+    Text,
+    Metric,
+    Bool,
+}
+
+fn check(expr: &Expr) -> Result<Type> {
+    match expr {
+        Expr::Number(_) | Expr::Range { .. } => Ok(Type::Number),
+        Expr::Text(_) => Ok(Type::Text),
+        Expr::Metric(_) => Ok(Type::Metric),
+        Expr::Compare { op, left, right } => {
+            let (left, right) = (check(left)?, check(right)?);
+            if left == Type::Bool || right == Type::Bool {
+                return Err(anyhow::anyhow!("Cannot compare conditions with `{}`", op.symbol()));
+            }
+            if (left == Type::Number && right == Type::Text) || (left == Type::Text && right == Type::Number) {
+                return Err(anyhow::anyhow!("Cannot compare a number with a string"));
+            }
+            if op.is_ordering() && (left == Type::Text || right == Type::Text) {
+                return Err(anyhow::anyhow!("Strings can only be compared with `==` or `!=`"));
+            }
+            Ok(Type::Bool)
+        }
+        Expr::And(left, right) | Expr::Or(left, right) => {
+            if check(left)? != Type::Bool || check(right)? != Type::Bool {
+                return Err(anyhow::anyhow!("`&&` and `||` combine comparisons"));
+            }
+            Ok(Type::Bool)
+        }
+        Expr::Not(inner) => {
+            if check(inner)? != Type::Bool {
+                return Err(anyhow::anyhow!("`!` negates a comparison"));
+            }
+            Ok(Type::Bool)
+        }
+    }
+}
+
+/// Runtime value; `Missing` stands for a range without enough samples
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+    Missing,
+}
+
+fn eval(expr: &Expr, metrics: &dyn MetricSource, history: &MetricHistory, now: DateTime<Utc>) -> Result<Value> {
+    Ok(match expr {
+        Expr::Number(value) => Value::Number(*value),
+        Expr::Text(value) => Value::Text(value.clone()),
+        Expr::Metric(name) => match metrics.value(name) {
+            Some(MetricValue::Number(value)) => Value::Number(value),
+            Some(MetricValue::Text(value)) => Value::Text(value),
+            None => return Err(anyhow::anyhow!("Metric not found: {}", name)),
+        },
+        Expr::Range { function, metric, window } => {
+            if metrics.value(metric).is_none() {
+                return Err(anyhow::anyhow!("Metric not found: {}", metric));
+            }
+            match function.apply(&history.window(metric, *window, now)) {
+                Some(value) => Value::Number(value),
+                None => Value::Missing,
+            }
+        }
+        Expr::Compare { op, left, right } => {
+            let (left, right) = (eval(left, metrics, history, now)?, eval(right, metrics, history, now)?);
+            Value::Bool(compare(*op, &left, &right)?)
+        }
+        Expr::And(left, right) => {
+            Value::Bool(eval_bool(left, metrics, history, now)? && eval_bool(right, metrics, history, now)?)
+        }
+        Expr::Or(left, right) => {
+            Value::Bool(eval_bool(left, metrics, history, now)? || eval_bool(right, metrics, history, now)?)
+        }
+        Expr::Not(inner) => Value::Bool(!eval_bool(inner, metrics, history, now)?),
+    })
+}
+
+fn eval_bool(expr: &Expr, metrics: &dyn MetricSource, history: &MetricHistory, now: DateTime<Utc>) -> Result<bool> {
+    match eval(expr, metrics, history, now)? {
+        Value::Bool(value) => Ok(value),
+        other => Err(anyhow::anyhow!("Expected a condition, found {:?}", other)),
+    }
+}
+
+fn compare(op: CompareOp, left: &Value, right: &Value) -> Result<bool> {
+    match (left, right) {
+        (Value::Missing, _) | (_, Value::Missing) => Ok(false),
+        (Value::Number(left), Value::Number(right)) => Ok(match op {
+            CompareOp::Eq => left == right,
+            CompareOp::Ne => left != right,
+            CompareOp::Lt => left < right,
+            CompareOp::Le => left <= right,
+            CompareOp::Gt => left > right,
+            CompareOp::Ge => left >= right,
+        }),
+        (Value::Text(left), Value::Text(right)) => match op {
+            CompareOp::Eq => Ok(left == right),
+            CompareOp::Ne => Ok(left != right),
+            _ => Err(anyhow::anyhow!("Strings can only be compared with `==` or `!=`")),
+        },
+        (left, right) => Err(anyhow::anyhow!("Cannot compare {:?} with {:?}", left, right)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Str(String),
+    Window(Duration),
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Op(CompareOp),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(name) => write!(f, "`{}`", name),
+            Token::Number(value) => write!(f, "number {}", value),
+            Token::Str(value) => write!(f, "string \"{}\"", value),
+            Token::Window(_) => f.write_str("range window"),
+            Token::LParen => f.write_str("`(`"),
+            Token::RParen => f.write_str("`)`"),
+            Token::And => f.write_str("`&&`"),
+            Token::Or => f.write_str("`||`"),
+            Token::Not => f.write_str("`!`"),
+            Token::Op(op) => write!(f, "`{}`", op.symbol()),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>> {
+    let chars: Vec<(usize, char)> = source.char_indices().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (offset, c) = chars[i];
+        let next = chars.get(i + 1).map(|(_, c)| *c);
+        let token = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '&' if next == Some('&') => Token::And,
+            '|' if next == Some('|') => Token::Or,
+            '=' if next == Some('=') => Token::Op(CompareOp::Eq),
+            '!' if next == Some('=') => Token::Op(CompareOp::Ne),
+            '<' if next == Some('=') => Token::Op(CompareOp::Le),
+            '>' if next == Some('=') => Token::Op(CompareOp::Ge),
+            '!' => Token::Not,
+            '<' => Token::Op(CompareOp::Lt),
+            '>' => Token::Op(CompareOp::Gt),
+            '"' => {
+                let mut value = String::new();
+                let mut j = i + 1;
+                loop {
+                    match chars.get(j).map(|(_, c)| *c) {
+                        Some('"') => break,
+                        Some('\\') => {
+                            match chars.get(j + 1).map(|(_, c)| *c) {
+                                Some(escaped @ ('"' | '\\')) => value.push(escaped),
+                                _ => return Err(anyhow::anyhow!("Invalid escape in string at position {}", chars[j].0)),
+                            }
+                            j += 2;
+                        }
+                        Some(c) => {
+                            value.push(c);
+                            j += 1;
+                        }
+                        None => return Err(anyhow::anyhow!("Unterminated string at position {}", offset)),
+                    }
+                }
+                tokens.push((Token::Str(value), offset));
+                i = j + 1;
+                continue;
+            }
+            '[' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|(_, c)| *c == ']')
+                    .map(|length| i + length)
+                    .ok_or_else(|| anyhow::anyhow!("Unterminated range window at position {}", offset))?;
+                let window: String = chars[i + 1..end].iter().map(|(_, c)| *c).collect();
+                tokens.push((Token::Window(parse_window(window.trim(), offset)?), offset));
+                i = end + 1;
+                continue;
+            }
+            c if c.is_ascii_digit() || (c == '-' && next.map(|c| c.is_ascii_digit()).unwrap_or(false)) => {
+                let mut j = i + 1;
+                while chars.get(j).map(|(_, c)| c.is_ascii_digit() || *c == '.').unwrap_or(false) {
+                    j += 1;
+                }
+                let literal: String = chars[i..j].iter().map(|(_, c)| *c).collect();
+                let value = literal
+                    .parse::<f64>()
+                    .map_err(|_| anyhow::anyhow!("Invalid number `{}` at position {}", literal, offset))?;
+                tokens.push((Token::Number(value), offset));
+                i = j;
+                continue;
+            }
+            c if c.is_ascii_alphabetic() || c == '_' || c == ':' => {
+                let mut j = i + 1;
+                while chars
+                    .get(j)
+                    .map(|(_, c)| c.is_ascii_alphanumeric() || *c == '_' || *c == ':')
+                    .unwrap_or(false)
+                {
+                    j += 1;
+                }
+                tokens.push((Token::Ident(chars[i..j].iter().map(|(_, c)| *c).collect()), offset));
+                i = j;
+                continue;
+            }
+            c => return Err(anyhow::anyhow!("Unexpected character `{}` at position {}", c, offset)),
+        };
+
+        // Two-character operators consume their second character too
+        i += match token {
+            Token::And | Token::Or => 2,
+            Token::Op(op) if op.symbol().len() == 2 => 2,
+            _ => 1,
+        };
+        tokens.push((token, offset));
+    }
+
+    Ok(tokens)
+}
+
+fn parse_window(window: &str, offset: usize) -> Result<Duration> {
+    let invalid = || anyhow::anyhow!("Invalid range window `{}` at position {}, expected e.g. `5m`", window, offset);
+    if window.len() < 2 {
+        return Err(invalid());
+    }
+    let (amount, unit) = window.split_at(window.len() - 1);
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    if amount <= 0 {
+        return Err(invalid());
+    }
+    match unit {
+        "s" => Ok(Duration::seconds(amount)),
+        "m" => Ok(Duration::minutes(amount)),
+        "h" => Ok(Duration::hours(amount)),
+        "d" => Ok(Duration::days(amount)),
+        _ => Err(invalid()),
+    }
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Result<(Token, usize)> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Unexpected end of expression"))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        let (token, offset) = self.next()?;
+        if token != expected {
+            return Err(anyhow::anyhow!("Expected {} at position {}, found {}", expected, offset, token));
+        }
+        Ok(())
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut expr = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut expr = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.position += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        if self.peek() == Some(&Token::Not) {
+            self.position += 1;
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr> {
+        let left = self.parse_operand()?;
+        if let Some(Token::Op(op)) = self.peek().cloned() {
+            self.position += 1;
+            let right = self.parse_operand()?;
+            return Ok(Expr::Compare {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            });
+        }
+        Ok(left)
+    }
+
+    fn parse_operand(&mut self) -> Result<Expr> {
+        let (token, offset) = self.next()?;
+        match token {
+            Token::Number(value) => Ok(Expr::Number(value)),
+            Token::Str(value) => Ok(Expr::Text(value)),
+            Token::LParen => {
+                let expr = self.parse_or()?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Token::Ident(name) if self.peek() == Some(&Token::LParen) => {
+                let function = RangeFunction::parse(&name)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown function `{}` at position {}", name, offset))?;
+                self.position += 1;
+                let metric = match self.next()? {
+                    (Token::Ident(metric), _) => metric,
+                    (token, offset) => {
+                        return Err(anyhow::anyhow!("Expected a metric at position {}, found {}", offset, token))
+                    }
+                };
+                let window = match self.next()? {
+                    (Token::Window(window), _) => window,
+                    (token, offset) => {
+                        return Err(anyhow::anyhow!(
+                            "Expected a range window such as `[5m]` at position {}, found {}",
+                            offset, token
+                        ))
+                    }
+                };
+                self.expect(Token::RParen)?;
+                Ok(Expr::Range { function, metric, window })
+            }
+            Token::Ident(name) => Ok(Expr::Metric(name)),
+            token => Err(anyhow::anyhow!("Unexpected {} at position {}", token, offset)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> MetricsRegistry {
+        let mut registry = MetricsRegistry::new();
+        registry.register_counter("orders_failed_total", "Failed orders").unwrap();
+        registry.register_gauge("queue_depth", "Queue depth").unwrap();
+        registry.register_state("congestion", "Network congestion").unwrap();
+        registry
+    }
+
+    #[test]
+    fn test_parses_precedence() {
+        let expression = AlertExpression::parse("a > 1 || b > 2 && !(c == \"x\")").unwrap();
+        match expression.expr() {
+            Expr::Or(left, right) => {
+                assert!(matches!(**left, Expr::Compare { op: CompareOp::Gt, .. }));
+                match &**right {
+                    Expr::And(_, negated) => assert!(matches!(**negated, Expr::Not(_))),
+                    other => panic!("expected `&&`, got {:?}", other),
+                }
+            }
+            other => panic!("expected `||`, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parses_range_functions() {
+        let expression = AlertExpression::parse("rate(orders_failed_total[5m]) > 0.1 && congestion == \"high\"").unwrap();
+        match expression.expr() {
+            Expr::And(left, _) => match &**left {
+                Expr::Compare { left, right, .. } => {
+                    assert_eq!(
+                        **left,
+                        Expr::Range {
+                            function: RangeFunction::Rate,
+                            metric: "orders_failed_total".to_string(),
+                            window: Duration::minutes(5),
+                        }
+                    );
+                    assert_eq!(**right, Expr::Number(0.1));
+                }
+                other => panic!("expected a comparison, got {:?}", other),
+            },
+            other => panic!("expected `&&`, got {:?}", other),
+        }
+
+        let selectors = AlertExpression::parse("increase(x[1h]) > 1 || max_over_time(x[90s]) > 2 || avg_over_time(y[2d]) < -1.5")
+            .unwrap()
+            .range_selectors();
+        assert_eq!(selectors["x"], Duration::hours(1));
+        assert_eq!(selectors["y"], Duration::days(2));
+    }
+
+    #[test]
+    fn test_rejects_invalid_expressions() {
+        for source in [
+            "",
+            "queue_depth",
+            "rate(orders_failed_total[5m])",
+            "queue_depth >",
+            "queue_depth > 1 &&",
+            "(queue_depth > 1",
+            "queue_depth > 1)",
+            "queue_depth = 1",
+            "queue_depth > 1 & x < 2",
+            "sum(queue_depth[5m]) > 1",
+            "rate(queue_depth) > 1",
+            "rate(queue_depth[5]) > 1",
+            "rate(queue_depth[5w]) > 1",
+            "rate(queue_depth[0m]) > 1",
+            "rate(queue_depth[5m] > 1",
+            "congestion > \"high\"",
+            "queue_depth == \"high",
+            "1 == \"high\"",
+            "(a > 1) == (b > 2)",
+            "!queue_depth",
+            "queue_depth > 1 && 5",
+            "queue_depth > 1.2.3",
+            "queue_depth > 1 $",
+        ] {
+            assert!(AlertExpression::parse(source).is_err(), "`{}` should not parse", source);
+        }
+    }
+
+    #[test]
+    fn test_serializes_as_source() {
+        let expression = AlertExpression::parse("  queue_depth >= 10 ").unwrap();
+        assert_eq!(expression.source(), "queue_depth >= 10");
+        assert_eq!(serde_json::to_string(&expression).unwrap(), "\"queue_depth >= 10\"");
+        let parsed: AlertExpression = serde_json::from_str("\"queue_depth >= 10\"").unwrap();
+        assert_eq!(parsed, expression);
+        assert!(serde_json::from_str::<AlertExpression>("\"queue_depth >=\"").is_err());
+    }
+
+    #[test]
+    fn test_evaluates_current_values() {
+        let registry = registry();
+        let history = MetricHistory::new();
+        let now = Utc::now();
+        registry.set_gauge("queue_depth", 12.0).unwrap();
+        registry.set_state("congestion", "high").unwrap();
+
+        let check = |source: &str| AlertExpression::parse(source).unwrap().evaluate(&registry, &history, now).unwrap();
+        assert!(check("queue_depth > 10"));
+        assert!(!check("queue_depth > 10 && congestion == \"low\""));
+        assert!(check("queue_depth > 100 || congestion != \"low\""));
+        assert!(check("!(queue_depth <= 10)"));
+
+        let missing = AlertExpression::parse("unknown_metric > 1").unwrap();
+        assert!(missing.evaluate(&registry, &history, now).is_err());
+        let mismatched = AlertExpression::parse("congestion > 1").unwrap();
+        assert!(mismatched.evaluate(&registry, &history, now).is_err());
+    }
+
+    #[test]
+    fn test_evaluates_ranges_over_history() {
+        let registry = registry();
+        let mut history = MetricHistory::new();
+        let now = Utc::now();
+        let retention = Duration::minutes(5);
+        let expression = AlertExpression::parse("rate(orders_failed_total[5m]) > 0.1").unwrap();
+
+        // A single sample is not enough to take a rate
+        history.record("orders_failed_total", 0.0, now - Duration::minutes(10), retention);
+        history.record("orders_failed_total", 10.0, now - Duration::seconds(60), retention);
+        assert!(!expression.evaluate(&registry, &history, now).unwrap());
+
+        // 12 failures in 60s, across a counter reset
+        history.record("orders_failed_total", 2.0, now - Duration::seconds(30), retention);
+        history.record("orders_failed_total", 12.0, now, retention);
+        assert!(expression.evaluate(&registry, &history, now).unwrap());
+        assert_eq!(
+            RangeFunction::Increase.apply(&history.window("orders_failed_total", retention, now)),
+            Some(12.0)
+        );
+        assert_eq!(
+            RangeFunction::MaxOverTime.apply(&history.window("orders_failed_total", retention, now)),
+            Some(12.0)
+        );
+
+        // Samples past the retention are dropped
+        assert_eq!(history.window("orders_failed_total", Duration::days(1), now).len(), 3);
+    }
+}
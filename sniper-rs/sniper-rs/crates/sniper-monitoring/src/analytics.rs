@@ -0,0 +1,360 @@
+//! Analytics over incident history.
+//!
+//! Summarises a tenant's incidents into mean time to acknowledge (MTTA) and
+//! mean time to resolve (MTTR) per severity, the alert rules raising the most
+//! incidents, and a ranking of noisy rules. An alert-raised incident counts
+//! as noise when it was resolved without ever being acknowledged, or within
+//! [`NOISY_RESOLUTION_SECS`] of being raised.
+
+use crate::{AlertRule, Incident, IncidentSeverity};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::collections::HashMap;
+
+/// Incidents resolved this quickly after being raised are treated as noise
+pub const NOISY_RESOLUTION_SECS: i64 = 300;
+
+/// Number of rules reported in the frequency and noise rankings
+pub const TOP_RULES: usize = 10;
+
+/// Response times for one severity
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SeverityStats {
+    pub severity: IncidentSeverity,
+    pub incidents: usize,
+    pub acknowledged: usize,
+    pub resolved: usize,
+    pub mtta_secs: Option<f64>,
+    pub mttr_secs: Option<f64>,
+}
+
+/// How often an alert rule raised incidents
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RuleFrequency {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub incidents: usize,
+}
+
+/// How much of an alert rule's output was noise
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NoisyRule {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub incidents: usize,
+    pub noisy: usize,
+    pub noise_ratio: f64,
+}
+
+/// Incident analytics for one tenant over a period
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct IncidentAnalytics {
+    pub tenant_id: String,
+    pub period_start: Option<DateTime<Utc>>,
+    pub period_end: DateTime<Utc>,
+    pub total_incidents: usize,
+    pub open_incidents: usize,
+    pub by_severity: Vec<SeverityStats>,
+    pub top_rules: Vec<RuleFrequency>,
+    pub noisy_rules: Vec<NoisyRule>,
+}
+
+impl IncidentAnalytics {
+    /// Compute analytics over the incidents raised in `[since, now]`
+    pub fn compute<'a>(
+        incidents: impl IntoIterator<Item = &'a Incident>,
+        rules: &HashMap<String, AlertRule>,
+        tenant_id: &str,
+        since: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let incidents: Vec<&Incident> = incidents
+            .into_iter()
+            .filter(|incident| incident.tenant_id == tenant_id)
+            .filter(|incident| incident.created_at <= now)
+            .filter(|incident| since.map(|since| incident.created_at >= since).unwrap_or(true))
+            .collect();
+
+        let by_severity = [
+            IncidentSeverity::Critical,
+            IncidentSeverity::High,
+            IncidentSeverity::Medium,
+            IncidentSeverity::Low,
+        ]
+        .into_iter()
+        .map(|severity| severity_stats(&incidents, severity))
+        .filter(|stats| stats.incidents > 0)
+        .collect();
+
+        let mut per_rule: HashMap<&str, (usize, usize)> = HashMap::new();
+        for incident in &incidents {
+            if let Some(rule_id) = incident.rule_id.as_deref() {
+                let entry = per_rule.entry(rule_id).or_insert((0, 0));
+                entry.0 += 1;
+                if is_noise(incident) {
+                    entry.1 += 1;
+                }
+            }
+        }
+        let rule_name = |rule_id: &str| {
+            rules
+                .get(rule_id)
+                .map(|rule| rule.name.clone())
+                .unwrap_or_else(|| rule_id.to_string())
+        };
+
+        let mut top_rules: Vec<RuleFrequency> = per_rule
+            .iter()
+            .map(|(rule_id, (count, _))| RuleFrequency {
+                rule_id: rule_id.to_string(),
+                rule_name: rule_name(*rule_id),
+                incidents: *count,
+            })
+            .collect();
+        top_rules.sort_by(|a, b| b.incidents.cmp(&a.incidents).then_with(|| a.rule_id.cmp(&b.rule_id)));
+        top_rules.truncate(TOP_RULES);
+
+        let mut noisy_rules: Vec<NoisyRule> = per_rule
+            .iter()
+            .filter(|(_, (_, noisy))| *noisy > 0)
+            .map(|(rule_id, (count, noisy))| NoisyRule {
+                rule_id: rule_id.to_string(),
+                rule_name: rule_name(*rule_id),
+                incidents: *count,
+                noisy: *noisy,
+                noise_ratio: *noisy as f64 / *count as f64,
+            })
+            .collect();
+        noisy_rules.sort_by(|a, b| {
+            b.noisy
+                .cmp(&a.noisy)
+                .then_with(|| b.noise_ratio.total_cmp(&a.noise_ratio))
+                .then_with(|| a.rule_id.cmp(&b.rule_id))
+        });
+        noisy_rules.truncate(TOP_RULES);
+
+        Self {
+            tenant_id: tenant_id.to_string(),
+            period_start: since,
+            period_end: now,
+            total_incidents: incidents.len(),
+            open_incidents: incidents.iter().filter(|incident| incident.resolved_at.is_none()).count(),
+            by_severity,
+            top_rules,
+            noisy_rules,
+        }
+    }
+
+    /// Plain-text summary suitable for a compliance report
+    pub fn summary(&self) -> String {
+        let mut lines = vec![
+            "Incident Analytics Report".to_string(),
+            format!(
+                "Period: {} to {}",
+                self.period_start
+                    .map(|start| start.to_string())
+                    .unwrap_or_else(|| "beginning".to_string()),
+                self.period_end
+            ),
+            format!("Tenant: {}", self.tenant_id),
+            String::new(),
+            format!("Incidents: {} ({} open)", self.total_incidents, self.open_incidents),
+        ];
+
+        for stats in &self.by_severity {
+            lines.push(format!(
+                "{:?}: {} incidents, MTTA {}, MTTR {}",
+                stats.severity,
+                stats.incidents,
+                format_secs(stats.mtta_secs),
+                format_secs(stats.mttr_secs)
+            ));
+        }
+
+        if !self.top_rules.is_empty() {
+            lines.push(String::new());
+            lines.push("Most frequent alert rules:".to_string());
+            for rule in &self.top_rules {
+                lines.push(format!("- {}: {} incidents", rule.rule_name, rule.incidents));
+            }
+        }
+
+        if !self.noisy_rules.is_empty() {
+            lines.push(String::new());
+            lines.push("Noisiest alert rules:".to_string());
+            for rule in &self.noisy_rules {
+                lines.push(format!(
+                    "- {}: {} of {} incidents were noise ({:.0}%)",
+                    rule.rule_name,
+                    rule.noisy,
+                    rule.incidents,
+                    rule.noise_ratio * 100.0
+                ));
+            }
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// Whether an incident was closed out without needing attention
+fn is_noise(incident: &Incident) -> bool {
+    match incident.resolved_at {
+        Some(resolved_at) => {
+            incident.acknowledged_at.is_none()
+                || (resolved_at - incident.created_at).num_seconds() < NOISY_RESOLUTION_SECS
+        }
+        None => false,
+    }
+}
+
+fn severity_stats(incidents: &[&Incident], severity: IncidentSeverity) -> SeverityStats {
+    let matching: Vec<&&Incident> = incidents
+        .iter()
+        .filter(|incident| incident.severity == severity)
+        .collect();
+
+    let ack_secs: Vec<f64> = matching
+        .iter()
+        .filter_map(|incident| incident.acknowledged_at.map(|at| elapsed_secs(incident.created_at, at)))
+        .collect();
+    let resolve_secs: Vec<f64> = matching
+        .iter()
+        .filter_map(|incident| incident.resolved_at.map(|at| elapsed_secs(incident.created_at, at)))
+        .collect();
+
+    SeverityStats {
+        severity,
+        incidents: matching.len(),
+        acknowledged: ack_secs.len(),
+        resolved: resolve_secs.len(),
+        mtta_secs: mean(&ack_secs),
+        mttr_secs: mean(&resolve_secs),
+    }
+}
+
+fn elapsed_secs(from: DateTime<Utc>, to: DateTime<Utc>) -> f64 {
+    (to - from).num_milliseconds().max(0) as f64 / 1000.0
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+}
+
+fn format_secs(secs: Option<f64>) -> String {
+    match secs {
+        Some(secs) => format!("{:.0}s", secs),
+        None => "n/a".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IncidentStatus;
+    use chrono::Duration;
+
+    fn incident(
+        severity: IncidentSeverity,
+        rule_id: Option<&str>,
+        created_at: DateTime<Utc>,
+        acknowledged_after: Option<i64>,
+        resolved_after: Option<i64>,
+    ) -> Incident {
+        Incident {
+            id: uuid::Uuid::new_v4().to_string(),
+            title: "incident".to_string(),
+            description: String::new(),
+            severity,
+            status: if resolved_after.is_some() {
+                IncidentStatus::Resolved
+            } else {
+                IncidentStatus::Open
+            },
+            created_at,
+            updated_at: created_at,
+            assigned_to: None,
+            resolution_notes: None,
+            tenant_id: "tenant-1".to_string(),
+            acknowledged_at: acknowledged_after.map(|secs| created_at + Duration::seconds(secs)),
+            resolved_at: resolved_after.map(|secs| created_at + Duration::seconds(secs)),
+            rule_id: rule_id.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_mtta_and_mttr_per_severity() {
+        let now = Utc::now();
+        let start = now - Duration::hours(2);
+        let incidents = vec![
+            incident(IncidentSeverity::High, None, start, Some(60), Some(1800)),
+            incident(IncidentSeverity::High, None, start, Some(180), Some(3600)),
+            incident(IncidentSeverity::Low, None, start, None, None),
+        ];
+
+        let analytics = IncidentAnalytics::compute(&incidents, &HashMap::new(), "tenant-1", None, now);
+        assert_eq!(analytics.total_incidents, 3);
+        assert_eq!(analytics.open_incidents, 1);
+        assert_eq!(analytics.by_severity.len(), 2);
+
+        let high = &analytics.by_severity[0];
+        assert_eq!(high.severity, IncidentSeverity::High);
+        assert_eq!(high.mtta_secs, Some(120.0));
+        assert_eq!(high.mttr_secs, Some(2700.0));
+
+        let low = &analytics.by_severity[1];
+        assert_eq!(low.mtta_secs, None);
+        assert_eq!(low.mttr_secs, None);
+    }
+
+    #[test]
+    fn test_rule_frequency_and_noise_ranking() {
+        let now = Utc::now();
+        let start = now - Duration::hours(1);
+        let incidents = vec![
+            // Flapping rule: auto-resolved without anyone looking
+            incident(IncidentSeverity::Medium, Some("flappy"), start, None, Some(30)),
+            incident(IncidentSeverity::Medium, Some("flappy"), start, None, Some(40)),
+            incident(IncidentSeverity::Medium, Some("flappy"), start, Some(10), Some(900)),
+            // Useful rule: acknowledged and worked
+            incident(IncidentSeverity::High, Some("useful"), start, Some(60), Some(1200)),
+            incident(IncidentSeverity::High, Some("useful"), start, Some(60), None),
+        ];
+
+        let analytics = IncidentAnalytics::compute(&incidents, &HashMap::new(), "tenant-1", None, now);
+        assert_eq!(analytics.top_rules[0].rule_id, "flappy");
+        assert_eq!(analytics.top_rules[0].incidents, 3);
+        assert_eq!(analytics.top_rules[1].rule_id, "useful");
+
+        assert_eq!(analytics.noisy_rules.len(), 1);
+        assert_eq!(analytics.noisy_rules[0].rule_id, "flappy");
+        assert_eq!(analytics.noisy_rules[0].noisy, 2);
+        assert!(analytics.summary().contains("Noisiest alert rules"));
+    }
+
+    #[test]
+    fn test_period_and_tenant_filtering() {
+        let now = Utc::now();
+        let mut other_tenant = incident(IncidentSeverity::Low, None, now, None, None);
+        other_tenant.tenant_id = "tenant-2".to_string();
+        let incidents = vec![
+            incident(IncidentSeverity::Low, None, now - Duration::days(2), None, None),
+            incident(IncidentSeverity::Low, None, now - Duration::hours(1), None, None),
+            other_tenant,
+        ];
+
+        let analytics = IncidentAnalytics::compute(
+            &incidents,
+            &HashMap::new(),
+            "tenant-1",
+            Some(now - Duration::days(1)),
+            now,
+        );
+        assert_eq!(analytics.total_incidents, 1);
+    }
+}
@@ -0,0 +1,440 @@
+//! Trading event subscription.
+//!
+//! The order and portfolio managers, the executor and the AMM router publish
+//! [`TradingEvent`]s on the core bus. The monitoring system records them in the
+//! standard trading metrics below, realized PnL under the listener's tenant,
+//! and opens an
+//! incident whenever the portfolio breaches its drawdown alert, a strategy
+//! is retired by its kill criteria, a plugin is disabled for failing or a
+//! scheduled backup fails. Backup failures are filed under the tenant whose
+//! backup failed rather than the listener's.
+
+use crate::{Incident, IncidentSeverity, MetricsRegistry, MonitoringSystem, LATENCY_BUCKETS_US};
+use anyhow::Result;
+use sniper_core::bus::InMemoryBus;
+use sniper_core::events::{TradingEvent, TRADING_EVENTS_SUBJECT};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+/// Counter of created orders
+pub const ORDERS_CREATED_METRIC: &str = "orders_created_total";
+
+/// Counter of order fills
+pub const ORDERS_FILLED_METRIC: &str = "orders_filled_total";
+
+/// Counter of cancelled orders
+pub const ORDERS_CANCELLED_METRIC: &str = "orders_cancelled_total";
+
+/// Counter of executed trades by `venue` and `outcome`
+pub const TRADES_EXECUTED_METRIC: &str = "trades_executed_total";
+
+/// Histogram of trade execution latency by `venue`, in microseconds
+pub const EXECUTION_LATENCY_METRIC: &str = "trade_execution_latency_us";
+
+/// Counter of fees paid by `chain`, in the chain's native token
+pub const GAS_PAID_METRIC: &str = "gas_paid_total";
+
+/// Histogram of slippage against the quoted minimum output by `venue`, in basis points
+pub const SLIPPAGE_METRIC: &str = "trade_slippage_bps";
+
+/// Histogram buckets for slippage in basis points, negative when the quote was beaten
+pub const SLIPPAGE_BUCKETS_BPS: &[f64] = &[-100.0, -25.0, -5.0, 0.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1_000.0];
+
+/// Gauge of realized PnL by `tenant`
+pub const REALIZED_PNL_METRIC: &str = "realized_pnl";
+
+/// Counter of route cache hits
+pub const ROUTE_CACHE_HITS_METRIC: &str = "route_cache_hits_total";
+
+/// Counter of route cache misses
+pub const ROUTE_CACHE_MISSES_METRIC: &str = "route_cache_misses_total";
+
+/// Gauge of the share of route cache lookups that hit
+pub const ROUTE_CACHE_HIT_RATE_METRIC: &str = "route_cache_hit_rate";
+
+/// Counter of opened positions
+pub const POSITIONS_OPENED_METRIC: &str = "positions_opened_total";
+
+/// Counter of closed positions
+pub const POSITIONS_CLOSED_METRIC: &str = "positions_closed_total";
+
+/// Counter of drawdown breaches
+pub const DRAWDOWN_BREACHES_METRIC: &str = "drawdown_breaches_total";
+
+/// Gauge of the drawdown at the latest breach, in percent
+pub const DRAWDOWN_METRIC: &str = "portfolio_drawdown_pct";
+
+/// Counter of strategies retired by their kill criteria
+pub const STRATEGIES_RETIRED_METRIC: &str = "strategies_retired_total";
+
+/// Counter of retired strategies re-enabled by an operator
+pub const STRATEGIES_REENABLED_METRIC: &str = "strategies_reenabled_total";
+
+/// Counter of plugins disabled after repeated failures
+pub const PLUGINS_DISABLED_METRIC: &str = "plugins_disabled_total";
+
+/// Counter of failed scheduled backups
+pub const BACKUPS_FAILED_METRIC: &str = "backups_failed_total";
+
+/// Register the metrics updated by trading events
+pub fn register_trading_metrics(registry: &mut MetricsRegistry) -> Result<()> {
+    registry.register_counter(ORDERS_CREATED_METRIC, "Total orders created")?;
+    registry.register_counter(ORDERS_FILLED_METRIC, "Total order fills")?;
+    registry.register_counter(ORDERS_CANCELLED_METRIC, "Total orders cancelled")?;
+    registry.register_counter_vec(TRADES_EXECUTED_METRIC, "Total trades executed", &["venue", "outcome"])?;
+    registry.register_histogram_vec(
+        EXECUTION_LATENCY_METRIC,
+        "Trade execution latency in microseconds",
+        &["venue"],
+        LATENCY_BUCKETS_US,
+    )?;
+    registry.register_counter_vec(GAS_PAID_METRIC, "Total fees paid in the chain's native token", &["chain"])?;
+    registry.register_histogram_vec(
+        SLIPPAGE_METRIC,
+        "Slippage against the quoted minimum output in basis points",
+        &["venue"],
+        SLIPPAGE_BUCKETS_BPS,
+    )?;
+    registry.register_gauge_vec(REALIZED_PNL_METRIC, "Realized PnL of closed positions", &["tenant"])?;
+    registry.register_counter(ROUTE_CACHE_HITS_METRIC, "Total route cache hits")?;
+    registry.register_counter(ROUTE_CACHE_MISSES_METRIC, "Total route cache misses")?;
+    registry.register_gauge(ROUTE_CACHE_HIT_RATE_METRIC, "Share of route cache lookups that hit")?;
+    registry.register_counter(POSITIONS_OPENED_METRIC, "Total positions opened")?;
+    registry.register_counter(POSITIONS_CLOSED_METRIC, "Total positions closed")?;
+    registry.register_counter(DRAWDOWN_BREACHES_METRIC, "Total portfolio drawdown breaches")?;
+    registry.register_gauge(DRAWDOWN_METRIC, "Portfolio drawdown at the latest breach in percent")?;
+    registry.register_counter(STRATEGIES_RETIRED_METRIC, "Total strategies retired by kill criteria")?;
+    registry.register_counter(STRATEGIES_REENABLED_METRIC, "Total retired strategies re-enabled")?;
+    registry.register_counter(PLUGINS_DISABLED_METRIC, "Total plugins disabled after repeated failures")?;
+    registry.register_counter(BACKUPS_FAILED_METRIC, "Total failed scheduled backups")?;
+    Ok(())
+}
+
+impl MonitoringSystem {
+    /// Record a trading event, returning the incident opened for a drawdown breach,
+    /// a strategy retirement, a disabled plugin or a failed backup
+    pub fn handle_trading_event(&mut self, event: &TradingEvent, tenant_id: &str) -> Result<Option<Incident>> {
+        {
+            let registry = self.metrics_registry.lock().unwrap();
+            match event {
+                TradingEvent::OrderCreated { .. } => registry.increment_counter(ORDERS_CREATED_METRIC)?,
+                TradingEvent::OrderFilled { .. } => registry.increment_counter(ORDERS_FILLED_METRIC)?,
+                TradingEvent::OrderCancelled { .. } => registry.increment_counter(ORDERS_CANCELLED_METRIC)?,
+                TradingEvent::TradeExecuted {
+                    chain,
+                    venue,
+                    success,
+                    latency_us,
+                    gas_paid,
+                    slippage_bps,
+                    ..
+                } => {
+                    let outcome = if *success { "success" } else { "failure" };
+                    registry.increment_counter_with(TRADES_EXECUTED_METRIC, &[venue, outcome])?;
+                    registry.observe_histogram_with(EXECUTION_LATENCY_METRIC, &[venue], *latency_us as f64)?;
+                    if *gas_paid > 0.0 {
+                        registry.counter_handle(GAS_PAID_METRIC, &[chain])?.inc_by(*gas_paid);
+                    }
+                    if let Some(slippage_bps) = slippage_bps {
+                        registry.observe_histogram_with(SLIPPAGE_METRIC, &[venue], *slippage_bps)?;
+                    }
+                }
+                TradingEvent::RouteCacheLookup { hit, .. } => {
+                    let hits = registry.counter_handle(ROUTE_CACHE_HITS_METRIC, &[])?;
+                    let misses = registry.counter_handle(ROUTE_CACHE_MISSES_METRIC, &[])?;
+                    if *hit { hits.inc() } else { misses.inc() }
+                    let rate = hits.get() / (hits.get() + misses.get());
+                    registry.set_gauge(ROUTE_CACHE_HIT_RATE_METRIC, rate)?;
+                }
+                TradingEvent::PositionOpened { .. } => registry.increment_counter(POSITIONS_OPENED_METRIC)?,
+                TradingEvent::PositionClosed { realized_pnl, .. } => {
+                    registry.increment_counter(POSITIONS_CLOSED_METRIC)?;
+                    registry.gauge_handle(REALIZED_PNL_METRIC, &[tenant_id])?.add(*realized_pnl);
+                }
+                TradingEvent::DrawdownBreached { drawdown_pct, .. } => {
+                    registry.increment_counter(DRAWDOWN_BREACHES_METRIC)?;
+                    registry.set_gauge(DRAWDOWN_METRIC, *drawdown_pct)?;
+                }
+                TradingEvent::StrategyRetired { .. } => registry.increment_counter(STRATEGIES_RETIRED_METRIC)?,
+                TradingEvent::StrategyReEnabled { .. } => registry.increment_counter(STRATEGIES_REENABLED_METRIC)?,
+                TradingEvent::PluginDisabled { .. } => registry.increment_counter(PLUGINS_DISABLED_METRIC)?,
+                TradingEvent::BackupFailed { .. } => registry.increment_counter(BACKUPS_FAILED_METRIC)?,
+            }
+        }
+
+        let incident = match event {
+            TradingEvent::DrawdownBreached {
+                drawdown_pct,
+                threshold_pct,
+                equity,
+                ..
+            } => {
+                // A drawdown twice past the alert threshold is critical
+                let severity = if *drawdown_pct >= threshold_pct * 2.0 {
+                    IncidentSeverity::Critical
+                } else {
+                    IncidentSeverity::High
+                };
+                self.incident_manager.create_incident(
+                    "Portfolio drawdown breached",
+                    &format!(
+                        "Portfolio drawdown of {:.2}% exceeded the {:.2}% alert threshold (equity {:.2})",
+                        drawdown_pct, threshold_pct, equity
+                    ),
+                    severity,
+                    tenant_id,
+                )
+            }
+            TradingEvent::StrategyRetired {
+                strategy_id,
+                reasons,
+                flattened_positions,
+                ..
+            } => self.incident_manager.create_incident(
+                &format!("Strategy {} retired", strategy_id),
+                &format!(
+                    "Strategy {} was disabled by its kill criteria ({}) and {} positions were flattened; \
+                     it stays disabled until re-enabled manually",
+                    strategy_id,
+                    reasons.join("; "),
+                    flattened_positions
+                ),
+                IncidentSeverity::High,
+                tenant_id,
+            ),
+            TradingEvent::PluginDisabled {
+                plugin_id,
+                failures,
+                last_error,
+                ..
+            } => self.incident_manager.create_incident(
+                &format!("Plugin {} disabled", plugin_id),
+                &format!(
+                    "Plugin {} was disabled after {} consecutive failed calls, the last with: {}; \
+                     it stays disabled until re-enabled manually",
+                    plugin_id, failures, last_error
+                ),
+                IncidentSeverity::Medium,
+                tenant_id,
+            ),
+            TradingEvent::BackupFailed {
+                schedule_id,
+                tenant_id: backup_tenant_id,
+                error,
+                ..
+            } => self.incident_manager.create_incident(
+                &format!("Scheduled backup {} failed", schedule_id),
+                &format!(
+                    "Scheduled backup {} of tenant {} failed: {}; the tenant has no fresh backup until it succeeds",
+                    schedule_id, backup_tenant_id, error
+                ),
+                IncidentSeverity::High,
+                backup_tenant_id,
+            ),
+            _ => return Ok(None),
+        };
+        Ok(Some(incident))
+    }
+}
+
+/// Feed trading events from a bus into the monitoring system until the bus closes
+pub fn spawn_trading_event_listener(
+    bus: &InMemoryBus,
+    monitoring: Arc<RwLock<MonitoringSystem>>,
+    tenant_id: String,
+) -> JoinHandle<()> {
+    let mut rx = bus.subscribe(TRADING_EVENTS_SUBJECT);
+    tokio::spawn(async move {
+        loop {
+            let bytes = match rx.recv().await {
+                Ok(bytes) => bytes,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Trading event listener lagged, skipped {} messages", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+            // Other messages share the bus; only trading events are of interest
+            let Some(event) = TradingEvent::decode(&bytes) else {
+                continue;
+            };
+            match monitoring.write().await.handle_trading_event(&event, &tenant_id) {
+                Ok(Some(incident)) => tracing::warn!("Opened incident {}: {}", incident.id, incident.description),
+                Ok(None) => {}
+                Err(e) => tracing::error!("Failed to record {} event: {}", event.subject(), e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drawdown_breach_opens_incident() {
+        let mut monitoring = MonitoringSystem::new().unwrap();
+
+        let fill = TradingEvent::OrderFilled {
+            order_id: "order-1".to_string(),
+            symbol: "ETH/USDT".to_string(),
+            quantity: 1.0,
+            price: 3000.0,
+            complete: true,
+            timestamp: 0,
+        };
+        assert!(monitoring.handle_trading_event(&fill, "tenant-1").unwrap().is_none());
+
+        let breach = TradingEvent::DrawdownBreached {
+            drawdown_pct: 12.0,
+            threshold_pct: 5.0,
+            equity: 8800.0,
+            timestamp: 0,
+        };
+        let incident = monitoring.handle_trading_event(&breach, "tenant-1").unwrap().unwrap();
+        assert_eq!(incident.severity, IncidentSeverity::Critical);
+        assert_eq!(monitoring.incident_manager_ref().list_tenant_incidents("tenant-1").len(), 1);
+
+        let metrics = monitoring.get_metrics_text().unwrap();
+        assert!(metrics.contains("orders_filled_total 1"));
+        assert!(metrics.contains("portfolio_drawdown_pct 12"));
+    }
+
+    #[test]
+    fn test_strategy_retirement_opens_incident() {
+        let mut monitoring = MonitoringSystem::new().unwrap();
+
+        let retired = TradingEvent::StrategyRetired {
+            strategy_id: "launch_snipe".to_string(),
+            reasons: vec!["3 consecutive losses".to_string()],
+            flattened_positions: 2,
+            timestamp: 0,
+        };
+        let incident = monitoring.handle_trading_event(&retired, "tenant-1").unwrap().unwrap();
+        assert_eq!(incident.severity, IncidentSeverity::High);
+        assert_eq!(incident.title, "Strategy launch_snipe retired");
+        assert!(incident.description.contains("3 consecutive losses"));
+
+        let reenabled = TradingEvent::StrategyReEnabled {
+            strategy_id: "launch_snipe".to_string(),
+            actor: "alice".to_string(),
+            reason: "fixed router".to_string(),
+            timestamp: 1,
+        };
+        assert!(monitoring.handle_trading_event(&reenabled, "tenant-1").unwrap().is_none());
+
+        let metrics = monitoring.get_metrics_text().unwrap();
+        assert!(metrics.contains("strategies_retired_total 1"));
+        assert!(metrics.contains("strategies_reenabled_total 1"));
+    }
+
+    #[test]
+    fn test_disabled_plugin_opens_incident() {
+        let mut monitoring = MonitoringSystem::new().unwrap();
+
+        let disabled = TradingEvent::PluginDisabled {
+            plugin_id: "simple-signal-processor".to_string(),
+            failures: 3,
+            last_error: "timed out after 100ms".to_string(),
+            timestamp: 0,
+        };
+        let incident = monitoring.handle_trading_event(&disabled, "tenant-1").unwrap().unwrap();
+        assert_eq!(incident.severity, IncidentSeverity::Medium);
+        assert_eq!(incident.title, "Plugin simple-signal-processor disabled");
+        assert!(incident.description.contains("timed out after 100ms"));
+        assert!(monitoring.get_metrics_text().unwrap().contains("plugins_disabled_total 1"));
+    }
+
+    #[test]
+    fn test_failed_backup_opens_incident_for_its_tenant() {
+        let mut monitoring = MonitoringSystem::new().unwrap();
+
+        let failed = TradingEvent::BackupFailed {
+            schedule_id: "nightly".to_string(),
+            tenant_id: "tenant-2".to_string(),
+            error: "S3 PUT failed with 403".to_string(),
+            timestamp: 0,
+        };
+        let incident = monitoring.handle_trading_event(&failed, "tenant-1").unwrap().unwrap();
+        assert_eq!(incident.severity, IncidentSeverity::High);
+        assert_eq!(incident.tenant_id, "tenant-2");
+        assert!(incident.description.contains("403"));
+        assert!(monitoring.get_metrics_text().unwrap().contains("backups_failed_total 1"));
+    }
+
+    #[test]
+    fn test_pipeline_events_update_trading_metrics() {
+        let mut monitoring = MonitoringSystem::new().unwrap();
+        let events = [
+            TradingEvent::OrderCancelled {
+                order_id: "order-1".to_string(),
+                symbol: "ETH/USDT".to_string(),
+                timestamp: 0,
+            },
+            TradingEvent::TradeExecuted {
+                idem_key: "plan-1".to_string(),
+                chain: "ethereum".to_string(),
+                venue: "mempool".to_string(),
+                success: true,
+                latency_us: 1_800,
+                gas_paid: 0.0021,
+                slippage_bps: Some(12.0),
+                timestamp: 0,
+            },
+            TradingEvent::PositionClosed {
+                position_id: "position-1".to_string(),
+                symbol: "ETH/USDT".to_string(),
+                realized_pnl: 40.0,
+                timestamp: 0,
+            },
+            TradingEvent::RouteCacheLookup {
+                chain: "ethereum".to_string(),
+                hit: false,
+                timestamp: 0,
+            },
+        ];
+        for event in &events {
+            assert!(monitoring.handle_trading_event(event, "tenant-1").unwrap().is_none());
+        }
+        for hit in [true, true, true] {
+            let lookup = TradingEvent::RouteCacheLookup {
+                chain: "ethereum".to_string(),
+                hit,
+                timestamp: 0,
+            };
+            monitoring.handle_trading_event(&lookup, "tenant-1").unwrap();
+        }
+
+        let metrics = monitoring.get_metrics_text().unwrap();
+        assert!(metrics.contains("orders_cancelled_total 1"));
+        assert!(metrics.contains("trades_executed_total{outcome=\"success\",venue=\"mempool\"} 1"));
+        assert!(metrics.contains("trade_execution_latency_us_bucket{venue=\"mempool\",le=\"2500\"} 1"));
+        assert!(metrics.contains("gas_paid_total{chain=\"ethereum\"} 0.0021"));
+        assert!(metrics.contains("trade_slippage_bps_sum{venue=\"mempool\"} 12"));
+        assert!(metrics.contains("realized_pnl{tenant=\"tenant-1\"} 40"));
+        assert!(metrics.contains("route_cache_hit_rate 0.75"));
+    }
+
+    #[tokio::test]
+    async fn test_listener_consumes_bus_events() {
+        let bus = InMemoryBus::new(16);
+        let monitoring = Arc::new(RwLock::new(MonitoringSystem::new().unwrap()));
+        let handle = spawn_trading_event_listener(&bus, monitoring.clone(), "tenant-1".to_string());
+
+        let created = TradingEvent::OrderCreated {
+            order_id: "order-1".to_string(),
+            symbol: "ETH/USDT".to_string(),
+            side: "buy".to_string(),
+            amount: 1.0,
+            timestamp: 0,
+        };
+        bus.publish(created.subject(), &created).await.unwrap();
+        drop(bus);
+        handle.await.unwrap();
+
+        let metrics = monitoring.read().await.get_metrics_text().unwrap();
+        assert!(metrics.contains("orders_created_total 1"));
+    }
+}
@@ -0,0 +1,188 @@
+//! HTTP metrics layer shared by the svc-* binaries.
+//!
+//! Each service wraps its router with [`instrument`], which records request
+//! counts and latencies per route and exposes them, together with any
+//! service-specific domain counters, in Prometheus format on `/metrics`.
+//! Processes without an HTTP API of their own serve a shared registry with
+//! [`registry_router`] instead.
+
+use crate::MetricsRegistry;
+use anyhow::Result;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::StatusCode,
+    middleware::{self, Next},
+    response::Response,
+    routing::get,
+    Router,
+};
+use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, Opts};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Request and domain metrics for one HTTP service
+pub struct ServiceMetrics {
+    service: String,
+    registry: MetricsRegistry,
+    requests_total: IntCounterVec,
+    request_duration: HistogramVec,
+}
+
+impl ServiceMetrics {
+    /// Create metrics for a service, registering the HTTP request metrics
+    pub fn new(service: &str) -> Result<Self> {
+        let registry = MetricsRegistry::new();
+
+        let requests_total = IntCounterVec::new(
+            Opts::new("http_requests_total", "Total HTTP requests")
+                .const_label("service", service),
+            &["method", "path", "status"],
+        )?;
+        let request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request duration")
+                .const_label("service", service),
+            &["method", "path"],
+        )?;
+        registry.registry().register(Box::new(requests_total.clone()))?;
+        registry.registry().register(Box::new(request_duration.clone()))?;
+
+        Ok(Self {
+            service: service.to_string(),
+            registry,
+            requests_total,
+            request_duration,
+        })
+    }
+
+    /// Get the service name
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    /// Prometheus registry the metrics are registered in
+    pub fn registry(&self) -> &prometheus::Registry {
+        self.registry.registry()
+    }
+
+    /// Register a domain counter
+    pub fn register_counter(&mut self, name: &str, help: &str) -> Result<()> {
+        self.registry.register_counter(name, help)
+    }
+
+    /// Register a domain gauge
+    pub fn register_gauge(&mut self, name: &str, help: &str) -> Result<()> {
+        self.registry.register_gauge(name, help)
+    }
+
+    /// Increment a domain counter, logging unknown names instead of failing
+    pub fn increment_counter(&self, name: &str) {
+        if let Err(e) = self.registry.increment_counter(name) {
+            tracing::warn!("{}: {}", self.service, e);
+        }
+    }
+
+    /// Set a domain gauge, logging unknown names instead of failing
+    pub fn set_gauge(&self, name: &str, value: f64) {
+        if let Err(e) = self.registry.set_gauge(name, value) {
+            tracing::warn!("{}: {}", self.service, e);
+        }
+    }
+
+    /// Record a completed HTTP request
+    pub fn observe_request(&self, method: &str, path: &str, status: u16, seconds: f64) {
+        self.requests_total
+            .with_label_values(&[method, path, &status.to_string()])
+            .inc();
+        self.request_duration
+            .with_label_values(&[method, path])
+            .observe(seconds);
+    }
+
+    /// Get metrics in Prometheus text format
+    pub fn get_metrics_text(&self) -> Result<String> {
+        self.registry.get_metrics_text()
+    }
+}
+
+/// Add request tracking and a `/metrics` route to a service router
+pub fn instrument(router: Router, metrics: Arc<ServiceMetrics>) -> Router {
+    router
+        .route("/metrics", get(metrics_handler).with_state(metrics.clone()))
+        .layer(middleware::from_fn_with_state(metrics, track_requests))
+}
+
+/// Router serving a shared metrics registry on `/metrics`
+pub fn registry_router(registry: Arc<Mutex<MetricsRegistry>>) -> Router {
+    Router::new().route("/metrics", get(registry_handler).with_state(registry))
+}
+
+/// Middleware recording request counts and latencies
+async fn track_requests(
+    State(metrics): State<Arc<ServiceMetrics>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let start = Instant::now();
+    let method = request.method().to_string();
+    // Use the route template to keep label cardinality bounded
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let response = next.run(request).await;
+
+    metrics.observe_request(
+        &method,
+        &path,
+        response.status().as_u16(),
+        start.elapsed().as_secs_f64(),
+    );
+    response
+}
+
+/// Serve metrics in Prometheus text format
+async fn metrics_handler(
+    State(metrics): State<Arc<ServiceMetrics>>,
+) -> Result<String, (StatusCode, String)> {
+    metrics
+        .get_metrics_text()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get metrics: {}", e)))
+}
+
+/// Serve a shared registry in Prometheus text format
+async fn registry_handler(
+    State(registry): State<Arc<Mutex<MetricsRegistry>>>,
+) -> Result<String, (StatusCode, String)> {
+    registry
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_metrics_text()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get metrics: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_metrics() {
+        let mut metrics = ServiceMetrics::new("svc-test").unwrap();
+        metrics.register_counter("orders_created_total", "Orders created").unwrap();
+
+        metrics.observe_request("GET", "/orders/:id", 200, 0.002);
+        metrics.observe_request("GET", "/orders/:id", 404, 0.001);
+        metrics.increment_counter("orders_created_total");
+        // Unknown counters are ignored
+        metrics.increment_counter("missing_total");
+
+        let text = metrics.get_metrics_text().unwrap();
+        assert!(text.contains("http_requests_total"));
+        assert!(text.contains("service=\"svc-test\""));
+        assert!(text.contains("path=\"/orders/:id\""));
+        assert!(text.contains("status=\"404\""));
+        assert!(text.contains("http_request_duration_seconds_bucket"));
+        assert!(text.contains("orders_created_total 1"));
+    }
+}
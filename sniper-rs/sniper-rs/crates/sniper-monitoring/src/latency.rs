@@ -0,0 +1,144 @@
+//! Tick-to-trade latency metrics.
+//!
+//! The pipeline records a [`LatencyTrace`](sniper_core::latency::LatencyTrace)
+//! for every signal it acts on into a [`LatencyRecorder`]. The reporter
+//! spawned here takes the recorder's report on an interval and publishes
+//! its p50/p99/p999 and max, per stage and for the whole tick-to-trade, as
+//! gauges; each interval's percentiles cover only the traces recorded in it.
+
+use crate::MetricsRegistry;
+use anyhow::Result;
+use sniper_core::latency::{LatencyRecorder, LatencyReport, LatencySummary};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Gauge of latency percentiles by `stage` and `quantile`, in microseconds
+///
+/// The `tick_to_trade` stage spans signal ingest to the venue's ack; every
+/// other stage is measured from the one before it.
+pub const PIPELINE_LATENCY_METRIC: &str = "pipeline_latency_us";
+
+/// Gauge of traces behind the latest percentiles by `stage`
+pub const PIPELINE_LATENCY_SAMPLES_METRIC: &str = "pipeline_latency_samples";
+
+/// Stage label of the end to end latency
+pub const TICK_TO_TRADE_STAGE: &str = "tick_to_trade";
+
+/// Register the latency gauges
+pub fn register_latency_metrics(registry: &mut MetricsRegistry) -> Result<()> {
+    registry.register_gauge_vec(
+        PIPELINE_LATENCY_METRIC,
+        "Pipeline latency percentiles in microseconds",
+        &["stage", "quantile"],
+    )?;
+    registry.register_gauge_vec(
+        PIPELINE_LATENCY_SAMPLES_METRIC,
+        "Traces behind the pipeline latency percentiles",
+        &["stage"],
+    )?;
+    Ok(())
+}
+
+/// Set the latency gauges from a report
+///
+/// Stages without samples keep their previous percentiles.
+pub fn publish_latency(registry: &MetricsRegistry, report: &LatencyReport) -> Result<()> {
+    publish_summary(registry, TICK_TO_TRADE_STAGE, &report.tick_to_trade)?;
+    for stage in &report.stages {
+        publish_summary(registry, stage.stage.as_str(), &stage.latency)?;
+    }
+    Ok(())
+}
+
+fn publish_summary(registry: &MetricsRegistry, stage: &str, summary: &LatencySummary) -> Result<()> {
+    registry.set_gauge_with(PIPELINE_LATENCY_SAMPLES_METRIC, &[stage], summary.count as f64)?;
+    if summary.count == 0 {
+        return Ok(());
+    }
+    for (quantile, value) in [
+        ("0.5", summary.p50_us),
+        ("0.99", summary.p99_us),
+        ("0.999", summary.p999_us),
+        ("1", summary.max_us),
+    ] {
+        registry.set_gauge_with(PIPELINE_LATENCY_METRIC, &[stage, quantile], value)?;
+    }
+    Ok(())
+}
+
+/// Publish the recorder's latencies every `interval`
+pub fn spawn_latency_reporter(
+    recorder: Arc<LatencyRecorder>,
+    registry: Arc<Mutex<MetricsRegistry>>,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            let report = recorder.take_report();
+            let registry = registry.lock().unwrap_or_else(|e| e.into_inner());
+            if let Err(e) = publish_latency(&registry, &report) {
+                tracing::warn!("failed to publish pipeline latency: {}", e);
+            } else if report.tick_to_trade.count > 0 {
+                tracing::info!(
+                    samples = report.tick_to_trade.count,
+                    p50_us = report.tick_to_trade.p50_us,
+                    p99_us = report.tick_to_trade.p99_us,
+                    p999_us = report.tick_to_trade.p999_us,
+                    "tick-to-trade latency"
+                );
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MetricValue;
+    use sniper_core::latency::{LatencyTrace, Stage};
+    use std::time::Instant;
+
+    fn trace(ack_after: Duration) -> LatencyTrace {
+        let ingest = Instant::now();
+        let mut trace = LatencyTrace::starting_at(ingest);
+        trace.mark_at(Stage::Decision, ingest + Duration::from_micros(20));
+        trace.mark_at(Stage::Ack, ingest + ack_after);
+        trace
+    }
+
+    #[tokio::test]
+    async fn test_reporter_publishes_percentiles() {
+        let mut registry = MetricsRegistry::new();
+        register_latency_metrics(&mut registry).unwrap();
+        let registry = Arc::new(Mutex::new(registry));
+        let recorder = Arc::new(LatencyRecorder::new());
+        for _ in 0..9 {
+            recorder.record(&trace(Duration::from_micros(100)));
+        }
+        recorder.record(&trace(Duration::from_micros(200)));
+
+        let reporter = spawn_latency_reporter(recorder.clone(), registry.clone(), Duration::from_millis(20));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        reporter.abort();
+
+        let registry = registry.lock().unwrap();
+        let gauge = |stage: &str, quantile: &str| {
+            registry.gauge_handle(PIPELINE_LATENCY_METRIC, &[stage, quantile]).unwrap().get()
+        };
+        // Percentiles are within the histogram's precision of the recorded values
+        assert!((gauge(TICK_TO_TRADE_STAGE, "0.5") - 100.0).abs() < 1.0);
+        assert_eq!(gauge(TICK_TO_TRADE_STAGE, "0.99"), 200.0);
+        assert_eq!(gauge(TICK_TO_TRADE_STAGE, "1"), 200.0);
+        assert!((gauge("decision", "0.5") - 20.0).abs() < 0.2);
+        // Later intervals had no traces, so the percentiles were kept
+        let samples = registry.gauge_handle(PIPELINE_LATENCY_SAMPLES_METRIC, &[TICK_TO_TRADE_STAGE]).unwrap();
+        assert_eq!(samples.get(), 0.0);
+        assert!(matches!(registry.value(PIPELINE_LATENCY_METRIC), Some(MetricValue::Number(_))));
+        let text = registry.get_metrics_text().unwrap();
+        assert!(text.contains("pipeline_latency_us{quantile=\"0.999\",stage=\"tick_to_trade\"} 200"));
+    }
+}
@@ -1,15 +1,36 @@
 //! Advanced monitoring system for the sniper-rs enterprise features.
 //! 
 //! This module provides functionality for advanced monitoring dashboards,
-//! automated incident response, and comprehensive system metrics.
+//! automated incident response, preference-aware incident notifications,
+//! comprehensive system metrics, metrics and incidents driven by trading
+//! events on the core bus, alert rules written as expressions over the
+//! metrics registry or a scraped Prometheus endpoint, analytics over
+//! incident history, and percentiles of the pipeline's tick-to-trade latency.
+
+pub mod alerting;
+pub mod analytics;
+pub mod events;
+pub mod http;
+pub mod latency;
+pub mod notifier;
+pub mod scrape;
+
+pub use alerting::{AlertExpression, MetricHistory, MetricSource, MetricSources, MetricValue};
+pub use analytics::{IncidentAnalytics, NoisyRule, RuleFrequency, SeverityStats};
+pub use events::spawn_trading_event_listener;
+pub use notifier::{Notification, NotificationDigest, Notifier};
+pub use scrape::ScrapedMetrics;
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::Mutex;
 use chrono::{DateTime, Utc};
-use prometheus::{Counter, Gauge, Histogram, HistogramOpts, Registry, TextEncoder, Encoder};
+use prometheus::core::Collector;
+use prometheus::{Counter, CounterVec, Gauge, GaugeVec, Histogram, HistogramOpts, HistogramVec, Opts, Registry, TextEncoder, Encoder};
+use sniper_users::NotificationSeverity;
 
 /// System metric types
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,7 +51,7 @@ pub struct SystemMetric {
 }
 
 /// Dashboard panel configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct DashboardPanel {
     pub id: String,
     pub title: String,
@@ -52,7 +73,7 @@ pub struct MonitoringDashboard {
 }
 
 /// Incident severity levels
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
 pub enum IncidentSeverity {
     Low,
     Medium,
@@ -60,6 +81,18 @@ pub enum IncidentSeverity {
     Critical,
 }
 
+impl IncidentSeverity {
+    /// Severity used when notifying users about the incident
+    pub fn notification_severity(&self) -> NotificationSeverity {
+        match self {
+            IncidentSeverity::Low => NotificationSeverity::Low,
+            IncidentSeverity::Medium => NotificationSeverity::Medium,
+            IncidentSeverity::High => NotificationSeverity::High,
+            IncidentSeverity::Critical => NotificationSeverity::Critical,
+        }
+    }
+}
+
 /// Incident status
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum IncidentStatus {
@@ -82,6 +115,15 @@ pub struct Incident {
     pub assigned_to: Option<String>,
     pub resolution_notes: Option<String>,
     pub tenant_id: String,
+    /// When the incident was first picked up or assigned
+    #[serde(default)]
+    pub acknowledged_at: Option<DateTime<Utc>>,
+    /// When the incident was resolved or closed
+    #[serde(default)]
+    pub resolved_at: Option<DateTime<Utc>>,
+    /// Alert rule that raised the incident
+    #[serde(default)]
+    pub rule_id: Option<String>,
 }
 
 /// Alert rule
@@ -90,20 +132,43 @@ pub struct AlertRule {
     pub id: String,
     pub name: String,
     pub description: String,
-    pub query: String,
-    pub threshold: f64,
+    /// Condition raising an incident, e.g. `rate(orders_failed_total[5m]) > 0.1`
+    pub expression: AlertExpression,
     pub severity: IncidentSeverity,
     pub enabled: bool,
     pub created_at: DateTime<Utc>,
     pub tenant_id: String,
 }
 
+/// Incidents one evaluation of the alert rules opened and resolved
+#[derive(Debug, Clone, Default)]
+pub struct AlertEvaluation {
+    pub opened: Vec<Incident>,
+    /// Incidents whose rule no longer fires
+    pub resolved: Vec<Incident>,
+}
+
+/// Histogram buckets for latencies in microseconds, from 1µs to 1s
+pub const LATENCY_BUCKETS_US: &[f64] = &[
+    1.0, 2.5, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1_000.0, 2_500.0, 5_000.0, 10_000.0, 25_000.0, 50_000.0,
+    100_000.0, 250_000.0, 500_000.0, 1_000_000.0,
+];
+
 /// Metrics registry wrapper
+///
+/// Labelled metrics take a fixed set of label names when registered and the
+/// values for each of them, in the same order, when updated. Hot paths should
+/// resolve a series once with the `*_handle` methods and update the returned
+/// Prometheus metric directly instead of looking it up by name every time.
 pub struct MetricsRegistry {
     registry: Registry,
     counters: HashMap<String, Counter>,
     gauges: HashMap<String, Gauge>,
     histograms: HashMap<String, Histogram>,
+    states: HashMap<String, GaugeVec>,
+    counter_vecs: HashMap<String, CounterVec>,
+    gauge_vecs: HashMap<String, GaugeVec>,
+    histogram_vecs: HashMap<String, HistogramVec>,
 }
 
 impl MetricsRegistry {
@@ -114,6 +179,10 @@ impl MetricsRegistry {
             counters: HashMap::new(),
             gauges: HashMap::new(),
             histograms: HashMap::new(),
+            states: HashMap::new(),
+            counter_vecs: HashMap::new(),
+            gauge_vecs: HashMap::new(),
+            histogram_vecs: HashMap::new(),
         }
     }
     
@@ -135,13 +204,51 @@ impl MetricsRegistry {
     
     /// Register a histogram metric
     pub fn register_histogram(&mut self, name: &str, help: &str) -> Result<()> {
-        let opts = HistogramOpts::new(name, help);
+        self.register_histogram_with_buckets(name, help, prometheus::DEFAULT_BUCKETS)
+    }
+    
+    /// Register a histogram metric with its own bucket upper bounds, e.g. [`LATENCY_BUCKETS_US`]
+    pub fn register_histogram_with_buckets(&mut self, name: &str, help: &str, buckets: &[f64]) -> Result<()> {
+        let opts = HistogramOpts::new(name, help).buckets(buckets.to_vec());
         let histogram = Histogram::with_opts(opts)?;
         self.registry.register(Box::new(histogram.clone()))?;
         self.histograms.insert(name.to_string(), histogram);
         Ok(())
     }
     
+    /// Register a counter with one series per combination of label values
+    pub fn register_counter_vec(&mut self, name: &str, help: &str, labels: &[&str]) -> Result<()> {
+        let counters = CounterVec::new(Opts::new(name, help), labels)?;
+        self.registry.register(Box::new(counters.clone()))?;
+        self.counter_vecs.insert(name.to_string(), counters);
+        Ok(())
+    }
+    
+    /// Register a gauge with one series per combination of label values
+    pub fn register_gauge_vec(&mut self, name: &str, help: &str, labels: &[&str]) -> Result<()> {
+        let gauges = GaugeVec::new(Opts::new(name, help), labels)?;
+        self.registry.register(Box::new(gauges.clone()))?;
+        self.gauge_vecs.insert(name.to_string(), gauges);
+        Ok(())
+    }
+    
+    /// Register a histogram with one series per combination of label values
+    pub fn register_histogram_vec(&mut self, name: &str, help: &str, labels: &[&str], buckets: &[f64]) -> Result<()> {
+        let opts = HistogramOpts::new(name, help).buckets(buckets.to_vec());
+        let histograms = HistogramVec::new(opts, labels)?;
+        self.registry.register(Box::new(histograms.clone()))?;
+        self.histogram_vecs.insert(name.to_string(), histograms);
+        Ok(())
+    }
+    
+    /// Register a state metric, exported as a gauge of 1 on its active `state` label
+    pub fn register_state(&mut self, name: &str, help: &str) -> Result<()> {
+        let state = GaugeVec::new(Opts::new(name, help), &["state"])?;
+        self.registry.register(Box::new(state.clone()))?;
+        self.states.insert(name.to_string(), state);
+        Ok(())
+    }
+    
     /// Increment a counter
     pub fn increment_counter(&self, name: &str) -> Result<()> {
         if let Some(counter) = self.counters.get(name) {
@@ -172,6 +279,115 @@ impl MetricsRegistry {
         }
     }
     
+    /// Increment the series of a labelled counter
+    pub fn increment_counter_with(&self, name: &str, label_values: &[&str]) -> Result<()> {
+        self.counter_handle(name, label_values)?.inc();
+        Ok(())
+    }
+    
+    /// Set the series of a labelled gauge
+    pub fn set_gauge_with(&self, name: &str, label_values: &[&str], value: f64) -> Result<()> {
+        self.gauge_handle(name, label_values)?.set(value);
+        Ok(())
+    }
+    
+    /// Observe a value in the series of a labelled histogram
+    pub fn observe_histogram_with(&self, name: &str, label_values: &[&str], value: f64) -> Result<()> {
+        self.histogram_handle(name, label_values)?.observe(value);
+        Ok(())
+    }
+    
+    /// Series of a counter, unlabelled if `label_values` is empty
+    pub fn counter_handle(&self, name: &str, label_values: &[&str]) -> Result<Counter> {
+        if label_values.is_empty() {
+            if let Some(counter) = self.counters.get(name) {
+                return Ok(counter.clone());
+            }
+        }
+        let counters = self.counter_vecs.get(name).ok_or_else(|| anyhow::anyhow!("Counter not found: {}", name))?;
+        Ok(counters.get_metric_with_label_values(label_values)?)
+    }
+    
+    /// Series of a gauge, unlabelled if `label_values` is empty
+    pub fn gauge_handle(&self, name: &str, label_values: &[&str]) -> Result<Gauge> {
+        if label_values.is_empty() {
+            if let Some(gauge) = self.gauges.get(name) {
+                return Ok(gauge.clone());
+            }
+        }
+        let gauges = self.gauge_vecs.get(name).ok_or_else(|| anyhow::anyhow!("Gauge not found: {}", name))?;
+        Ok(gauges.get_metric_with_label_values(label_values)?)
+    }
+    
+    /// Series of a histogram, unlabelled if `label_values` is empty
+    pub fn histogram_handle(&self, name: &str, label_values: &[&str]) -> Result<Histogram> {
+        if label_values.is_empty() {
+            if let Some(histogram) = self.histograms.get(name) {
+                return Ok(histogram.clone());
+            }
+        }
+        let histograms = self.histogram_vecs.get(name).ok_or_else(|| anyhow::anyhow!("Histogram not found: {}", name))?;
+        Ok(histograms.get_metric_with_label_values(label_values)?)
+    }
+    
+    /// Set the active state of a state metric
+    pub fn set_state(&self, name: &str, state: &str) -> Result<()> {
+        if let Some(states) = self.states.get(name) {
+            states.reset();
+            states.with_label_values(&[state]).set(1.0);
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("State metric not found: {}", name))
+        }
+    }
+    
+    /// Current value of a metric
+    ///
+    /// Histograms are read through their `_sum` and `_count` series, and
+    /// labelled metrics as the sum over all their series.
+    pub fn value(&self, name: &str) -> Option<MetricValue> {
+        if let Some(counter) = self.counters.get(name) {
+            return Some(MetricValue::Number(counter.get()));
+        }
+        if let Some(gauge) = self.gauges.get(name) {
+            return Some(MetricValue::Number(gauge.get()));
+        }
+        if let Some(states) = self.states.get(name) {
+            let active = states
+                .collect()
+                .iter()
+                .flat_map(|family| family.get_metric())
+                .find(|metric| metric.get_gauge().get_value() > 0.0)
+                .and_then(|metric| metric.get_label().first().map(|label| label.get_value().to_string()));
+            return Some(MetricValue::Text(active.unwrap_or_default()));
+        }
+        if let Some(histogram) = name.strip_suffix("_sum").and_then(|base| self.histograms.get(base)) {
+            return Some(MetricValue::Number(histogram.get_sample_sum()));
+        }
+        if let Some(histogram) = name.strip_suffix("_count").and_then(|base| self.histograms.get(base)) {
+            return Some(MetricValue::Number(histogram.get_sample_count() as f64));
+        }
+        if let Some(counters) = self.counter_vecs.get(name) {
+            return Some(MetricValue::Number(sum_series(counters, |metric| metric.get_counter().get_value())));
+        }
+        if let Some(gauges) = self.gauge_vecs.get(name) {
+            return Some(MetricValue::Number(sum_series(gauges, |metric| metric.get_gauge().get_value())));
+        }
+        if let Some(histograms) = name.strip_suffix("_sum").and_then(|base| self.histogram_vecs.get(base)) {
+            return Some(MetricValue::Number(sum_series(histograms, |metric| metric.get_histogram().get_sample_sum())));
+        }
+        if let Some(histograms) = name.strip_suffix("_count").and_then(|base| self.histogram_vecs.get(base)) {
+            let count = sum_series(histograms, |metric| metric.get_histogram().get_sample_count() as f64);
+            return Some(MetricValue::Number(count));
+        }
+        None
+    }
+    
+    /// Get the underlying Prometheus registry
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+    
     /// Get metrics in Prometheus text format
     pub fn get_metrics_text(&self) -> Result<String> {
         let mut buffer = Vec::new();
@@ -182,6 +398,16 @@ impl MetricsRegistry {
     }
 }
 
+/// Sum of one value over every series of a labelled metric
+fn sum_series(collector: &dyn Collector, value: impl Fn(&prometheus::proto::Metric) -> f64) -> f64 {
+    collector
+        .collect()
+        .iter()
+        .flat_map(|family| family.get_metric())
+        .map(value)
+        .sum()
+}
+
 /// Dashboard manager for advanced monitoring
 pub struct DashboardManager {
     dashboards: HashMap<String, MonitoringDashboard>,
@@ -254,6 +480,7 @@ impl DashboardManager {
 pub struct IncidentManager {
     incidents: HashMap<String, Incident>,
     alert_rules: HashMap<String, AlertRule>,
+    metric_history: MetricHistory,
 }
 
 impl IncidentManager {
@@ -262,6 +489,7 @@ impl IncidentManager {
         Self {
             incidents: HashMap::new(),
             alert_rules: HashMap::new(),
+            metric_history: MetricHistory::new(),
         }
     }
     
@@ -284,6 +512,9 @@ impl IncidentManager {
             assigned_to: None,
             resolution_notes: None,
             tenant_id: tenant_id.to_string(),
+            acknowledged_at: None,
+            resolved_at: None,
+            rule_id: None,
         };
         
         self.incidents.insert(incident.id.clone(), incident.clone());
@@ -311,8 +542,19 @@ impl IncidentManager {
         resolution_notes: Option<String>,
     ) -> Result<()> {
         if let Some(incident) = self.incidents.get_mut(incident_id) {
+            let now = Utc::now();
+            match status {
+                IncidentStatus::Open => incident.resolved_at = None,
+                IncidentStatus::InProgress => {
+                    incident.acknowledged_at.get_or_insert(now);
+                    incident.resolved_at = None;
+                }
+                IncidentStatus::Resolved | IncidentStatus::Closed => {
+                    incident.resolved_at.get_or_insert(now);
+                }
+            }
             incident.status = status;
-            incident.updated_at = Utc::now();
+            incident.updated_at = now;
             incident.resolution_notes = resolution_notes;
             Ok(())
         } else {
@@ -325,28 +567,27 @@ impl IncidentManager {
         if let Some(incident) = self.incidents.get_mut(incident_id) {
             incident.assigned_to = Some(user_id.to_string());
             incident.updated_at = Utc::now();
+            incident.acknowledged_at.get_or_insert(incident.updated_at);
             Ok(())
         } else {
             Err(anyhow::anyhow!("Incident not found"))
         }
     }
     
-    /// Create an alert rule
+    /// Create an alert rule, rejecting expressions that do not parse
     pub fn create_alert_rule(
         &mut self,
         name: &str,
         description: &str,
-        query: &str,
-        threshold: f64,
+        expression: &str,
         severity: IncidentSeverity,
         tenant_id: &str,
-    ) -> AlertRule {
+    ) -> Result<AlertRule> {
         let rule = AlertRule {
             id: uuid::Uuid::new_v4().to_string(),
             name: name.to_string(),
             description: description.to_string(),
-            query: query.to_string(),
-            threshold,
+            expression: AlertExpression::parse(expression)?,
             severity,
             enabled: true,
             created_at: Utc::now(),
@@ -354,40 +595,81 @@ impl IncidentManager {
         };
         
         self.alert_rules.insert(rule.id.clone(), rule.clone());
-        rule
+        Ok(rule)
+    }
+    
+    /// Evaluate alert rules, opening an incident for each rule that fires
+    /// without one already open and resolving those of rules that no longer fire
+    pub fn evaluate_alerts(&mut self, metrics: &dyn MetricSource) -> Result<AlertEvaluation> {
+        self.evaluate_alerts_at(metrics, Utc::now())
     }
     
-    /// Evaluate alert rules and create incidents when thresholds are exceeded
-    pub fn evaluate_alerts(&mut self) -> Result<Vec<Incident>> {
-        let mut new_incidents = Vec::new();
+    /// Evaluate alert rules as of `now`
+    pub fn evaluate_alerts_at(&mut self, metrics: &dyn MetricSource, now: DateTime<Utc>) -> Result<AlertEvaluation> {
+        let mut evaluation = AlertEvaluation::default();
+        let rules: Vec<AlertRule> = self.alert_rules.values().filter(|rule| rule.enabled).cloned().collect();
         
-        // Create a copy of alert rules to avoid borrowing conflicts
-        let rule_ids: Vec<String> = self.alert_rules.keys().cloned().collect();
+        // Sample every metric read over a window, keeping the longest window any rule needs
+        let mut retention: HashMap<String, chrono::Duration> = HashMap::new();
+        for rule in &rules {
+            for (metric, window) in rule.expression.range_selectors() {
+                let longest = retention.entry(metric).or_insert(window);
+                *longest = (*longest).max(window);
+            }
+        }
+        for (metric, window) in &retention {
+            if let Err(e) = self.metric_history.sample(metrics, metric, now, *window) {
+                tracing::warn!("Failed to sample {} for alert rules: {}", metric, e);
+            }
+        }
         
-        // In a real implementation, this would evaluate actual metrics against alert rules
-        // For now, we'll simulate this with a simple check
-        for rule_id in rule_ids {
-            if let Some(rule) = self.alert_rules.get(&rule_id).cloned() {
-                if rule.enabled {
-                    // Simulate metric evaluation
-                    let metric_value = 0.0; // Placeholder value
+        for rule in rules {
+            let open_incident = self
+                .incidents
+                .values()
+                .find(|incident| incident.rule_id.as_deref() == Some(rule.id.as_str()) && incident.resolved_at.is_none())
+                .map(|incident| incident.id.clone());
+            
+            match (rule.expression.evaluate(metrics, &self.metric_history, now), open_incident) {
+                // Still firing; the open incident covers it
+                (Ok(true), Some(_)) => {}
+                (Ok(false), Some(incident_id)) => {
+                    self.update_incident_status(
+                        &incident_id,
+                        IncidentStatus::Resolved,
+                        Some(format!("Auto-resolved: alert rule '{}' no longer fires", rule.name)),
+                    )?;
+                    evaluation.resolved.extend(self.incidents.get(&incident_id).cloned());
+                }
+                (Ok(true), None) => {
+                    let mut incident = self.create_incident(
+                        &format!("Alert: {}", rule.name),
+                        &format!("Alert rule '{}' triggered: {}", rule.name, rule.expression),
+                        rule.severity.clone(),
+                        &rule.tenant_id,
+                    );
+                    incident.rule_id = Some(rule.id.clone());
+                    self.incidents.insert(incident.id.clone(), incident.clone());
                     
-                    if metric_value > rule.threshold {
-                        let incident = self.create_incident(
-                            &format!("Alert: {}", rule.name),
-                            &format!("Alert rule '{}' triggered. Metric value {} exceeded threshold {}", 
-                                    rule.name, metric_value, rule.threshold),
-                            rule.severity.clone(),
-                            &rule.tenant_id,
-                        );
-                        
-                        new_incidents.push(incident);
-                    }
+                    evaluation.opened.push(incident);
                 }
+                (Ok(false), None) => {}
+                (Err(e), _) => tracing::warn!("Failed to evaluate alert rule '{}': {}", rule.name, e),
             }
         }
         
-        Ok(new_incidents)
+        Ok(evaluation)
+    }
+    
+    /// Analytics over a tenant's incidents raised since `since` (all history when `None`)
+    pub fn analytics(&self, tenant_id: &str, since: Option<DateTime<Utc>>) -> IncidentAnalytics {
+        IncidentAnalytics::compute(
+            self.incidents.values(),
+            &self.alert_rules,
+            tenant_id,
+            since,
+            Utc::now(),
+        )
     }
 }
 
@@ -396,6 +678,7 @@ pub struct MonitoringSystem {
     metrics_registry: Arc<Mutex<MetricsRegistry>>,
     dashboard_manager: DashboardManager,
     incident_manager: IncidentManager,
+    notifier: Notifier,
 }
 
 impl MonitoringSystem {
@@ -407,11 +690,14 @@ impl MonitoringSystem {
         metrics_registry.register_counter("http_requests_total", "Total HTTP requests")?;
         metrics_registry.register_gauge("active_users", "Number of active users")?;
         metrics_registry.register_histogram("request_duration_seconds", "HTTP request duration")?;
+        events::register_trading_metrics(&mut metrics_registry)?;
+        latency::register_latency_metrics(&mut metrics_registry)?;
         
         Ok(Self {
             metrics_registry: Arc::new(Mutex::new(metrics_registry)),
             dashboard_manager: DashboardManager::new(),
             incident_manager: IncidentManager::new(),
+            notifier: Notifier::new(),
         })
     }
     
@@ -440,6 +726,24 @@ impl MonitoringSystem {
         &self.incident_manager
     }
     
+    /// Get the incident notifier (mutable access)
+    pub fn notifier(&mut self) -> &mut Notifier {
+        &mut self.notifier
+    }
+    
+    /// Evaluate alert rules against the metrics registry
+    pub fn evaluate_alerts(&mut self) -> Result<AlertEvaluation> {
+        let registry = self.metrics_registry.lock().unwrap();
+        self.incident_manager.evaluate_alerts(&*registry)
+    }
+    
+    /// Evaluate alert rules against the metrics registry, falling back to
+    /// metrics scraped from another process for those it does not hold
+    pub fn evaluate_alerts_with(&mut self, scraped: &ScrapedMetrics) -> Result<AlertEvaluation> {
+        let registry = self.metrics_registry.lock().unwrap();
+        self.incident_manager.evaluate_alerts(&MetricSources(vec![&*registry, scraped]))
+    }
+    
     /// Get metrics in Prometheus text format
     pub fn get_metrics_text(&self) -> Result<String> {
         let registry = self.metrics_registry.lock().unwrap();
@@ -476,6 +780,41 @@ mod tests {
         assert!(metrics_text.contains("test_histogram"));
     }
 
+    #[test]
+    fn test_labelled_metrics() {
+        let mut registry = MetricsRegistry::new();
+        registry.register_counter_vec("fills_total", "Fills", &["chain", "venue"]).unwrap();
+        registry.register_gauge_vec("pnl", "PnL", &["tenant"]).unwrap();
+        registry
+            .register_histogram_vec("execution_latency_us", "Execution latency", &["venue"], LATENCY_BUCKETS_US)
+            .unwrap();
+        registry.register_histogram_with_buckets("tick_latency_us", "Tick latency", &[10.0, 100.0]).unwrap();
+        
+        registry.increment_counter_with("fills_total", &["ethereum", "uniswap"]).unwrap();
+        let binance = registry.counter_handle("fills_total", &["bsc", "binance"]).unwrap();
+        binance.inc_by(2.0);
+        registry.set_gauge_with("pnl", &["tenant-1"], 12.5).unwrap();
+        registry.set_gauge_with("pnl", &["tenant-2"], -2.5).unwrap();
+        let latency = registry.histogram_handle("execution_latency_us", &["uniswap"]).unwrap();
+        latency.observe(42.0);
+        latency.observe(800.0);
+        registry.observe_histogram("tick_latency_us", 50.0).unwrap();
+        
+        // Label values must match the registered label names
+        assert!(registry.increment_counter_with("fills_total", &["ethereum"]).is_err());
+        assert!(registry.counter_handle("missing_total", &[]).is_err());
+        
+        assert_eq!(registry.value("fills_total"), Some(MetricValue::Number(3.0)));
+        assert_eq!(registry.value("pnl"), Some(MetricValue::Number(10.0)));
+        assert_eq!(registry.value("execution_latency_us_count"), Some(MetricValue::Number(2.0)));
+        assert_eq!(registry.value("execution_latency_us_sum"), Some(MetricValue::Number(842.0)));
+        
+        let text = registry.get_metrics_text().unwrap();
+        assert!(text.contains("fills_total{chain=\"bsc\",venue=\"binance\"} 2"));
+        assert!(text.contains("execution_latency_us_bucket{venue=\"uniswap\",le=\"50\"} 1"));
+        assert!(text.contains("tick_latency_us_bucket{le=\"100\"} 1"));
+    }
+    
     #[test]
     fn test_dashboard_management() {
         let mut dashboard_manager = DashboardManager::new();
@@ -538,6 +877,40 @@ mod tests {
         assert_eq!(updated_incident.resolution_notes, Some("Issue fixed".to_string()));
     }
 
+    #[test]
+    fn test_incident_lifecycle_timestamps() {
+        let mut incident_manager = IncidentManager::new();
+        let incident = incident_manager.create_incident(
+            "Slow fills",
+            "Fill latency above target",
+            IncidentSeverity::Medium,
+            "tenant-1",
+        );
+        assert!(incident.acknowledged_at.is_none());
+        assert!(incident.resolved_at.is_none());
+        
+        incident_manager.assign_incident(&incident.id, "oncall").unwrap();
+        let acknowledged_at = incident_manager.get_incident(&incident.id).unwrap().acknowledged_at;
+        assert!(acknowledged_at.is_some());
+        
+        // Picking the incident up later keeps the first acknowledgement
+        incident_manager.update_incident_status(&incident.id, IncidentStatus::InProgress, None).unwrap();
+        assert_eq!(incident_manager.get_incident(&incident.id).unwrap().acknowledged_at, acknowledged_at);
+        
+        incident_manager.update_incident_status(&incident.id, IncidentStatus::Resolved, None).unwrap();
+        assert!(incident_manager.get_incident(&incident.id).unwrap().resolved_at.is_some());
+        
+        let analytics = incident_manager.analytics("tenant-1", None);
+        assert_eq!(analytics.total_incidents, 1);
+        assert_eq!(analytics.open_incidents, 0);
+        assert_eq!(analytics.by_severity[0].acknowledged, 1);
+        assert_eq!(analytics.by_severity[0].resolved, 1);
+        
+        // Reopening clears the resolution
+        incident_manager.update_incident_status(&incident.id, IncidentStatus::Open, None).unwrap();
+        assert!(incident_manager.get_incident(&incident.id).unwrap().resolved_at.is_none());
+    }
+
     #[test]
     fn test_alert_rules() {
         let mut incident_manager = IncidentManager::new();
@@ -546,18 +919,74 @@ mod tests {
             "High CPU Usage",
             "Alert when CPU usage exceeds 80%",
             "cpu_usage > 80",
-            80.0,
             IncidentSeverity::High,
             "tenant-1",
-        );
+        ).unwrap();
         
         assert_eq!(rule.name, "High CPU Usage");
-        assert_eq!(rule.threshold, 80.0);
+        assert_eq!(rule.expression.source(), "cpu_usage > 80");
         assert_eq!(rule.tenant_id, "tenant-1");
         assert!(rule.enabled);
         
         let rules = &incident_manager.alert_rules;
         assert!(rules.contains_key(&rule.id));
+        
+        // Expressions are validated when the rule is created
+        assert!(incident_manager.create_alert_rule(
+            "Broken",
+            "Missing right-hand side",
+            "cpu_usage >",
+            IncidentSeverity::High,
+            "tenant-1",
+        ).is_err());
+    }
+    
+    #[test]
+    fn test_alert_evaluation() {
+        let mut registry = MetricsRegistry::new();
+        registry.register_counter("orders_failed_total", "Failed orders").unwrap();
+        registry.register_state("congestion", "Network congestion").unwrap();
+        registry.set_state("congestion", "low").unwrap();
+        
+        let mut incident_manager = IncidentManager::new();
+        let rule = incident_manager.create_alert_rule(
+            "Order failures",
+            "Orders failing under congestion",
+            "rate(orders_failed_total[5m]) > 0.1 && congestion == \"high\"",
+            IncidentSeverity::High,
+            "tenant-1",
+        ).unwrap();
+        
+        let start = Utc::now();
+        assert!(incident_manager.evaluate_alerts_at(&registry, start).unwrap().opened.is_empty());
+        
+        // 30 failures over a minute while congested
+        for _ in 0..30 {
+            registry.increment_counter("orders_failed_total").unwrap();
+        }
+        registry.set_state("congestion", "high").unwrap();
+        let later = start + chrono::Duration::seconds(60);
+        let incidents = incident_manager.evaluate_alerts_at(&registry, later).unwrap().opened;
+        assert_eq!(incidents.len(), 1);
+        assert_eq!(incidents[0].rule_id.as_deref(), Some(rule.id.as_str()));
+        assert_eq!(incidents[0].severity, IncidentSeverity::High);
+        
+        // The rule stays quiet while its incident is open
+        let again = later + chrono::Duration::seconds(15);
+        let evaluation = incident_manager.evaluate_alerts_at(&registry, again).unwrap();
+        assert!(evaluation.opened.is_empty() && evaluation.resolved.is_empty());
+        
+        // Once the condition clears the incident resolves, and a new breach opens a new one
+        registry.set_state("congestion", "low").unwrap();
+        let cleared = again + chrono::Duration::seconds(15);
+        let evaluation = incident_manager.evaluate_alerts_at(&registry, cleared).unwrap();
+        assert_eq!(evaluation.resolved.len(), 1);
+        assert_eq!(evaluation.resolved[0].id, incidents[0].id);
+        assert_eq!(evaluation.resolved[0].status, IncidentStatus::Resolved);
+        registry.set_state("congestion", "high").unwrap();
+        let reopened = incident_manager.evaluate_alerts_at(&registry, cleared + chrono::Duration::seconds(15)).unwrap();
+        assert_eq!(reopened.opened.len(), 1);
+        assert_ne!(reopened.opened[0].id, incidents[0].id);
     }
 
     #[test]
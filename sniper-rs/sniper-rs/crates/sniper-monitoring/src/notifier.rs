@@ -0,0 +1,181 @@
+//! Incident notifications routed by user preferences.
+//!
+//! When an incident is raised, every user in its tenant whose preferences
+//! accept incidents at that severity is notified. Users on immediate delivery
+//! get the notification straight away; everyone else has it queued until
+//! their digest period elapses.
+
+use crate::Incident;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sniper_users::notifications::EVENT_INCIDENT;
+use sniper_users::{DigestFrequency, NotificationChannel, NotificationSeverity, UserManager};
+use std::collections::HashMap;
+
+/// A notification addressed to one user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub id: String,
+    pub user_id: String,
+    pub channels: Vec<NotificationChannel>,
+    pub event_type: String,
+    pub severity: NotificationSeverity,
+    pub title: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Batched notifications delivered at the end of a digest period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationDigest {
+    pub user_id: String,
+    pub channels: Vec<NotificationChannel>,
+    pub frequency: DigestFrequency,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub notifications: Vec<Notification>,
+}
+
+/// Notifier for monitoring incidents
+#[derive(Default)]
+pub struct Notifier {
+    queued: HashMap<String, Vec<Notification>>,
+    last_digest: HashMap<String, DateTime<Utc>>,
+}
+
+impl Notifier {
+    /// Create a new notifier
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Route an incident to its tenant's users, returning the notifications to deliver now
+    pub fn notify_incident(&mut self, incident: &Incident, users: &UserManager) -> Vec<Notification> {
+        let severity = incident.severity.notification_severity();
+        let mut immediate = Vec::new();
+
+        for user in users.get_tenant_users(&incident.tenant_id) {
+            let preferences = match users.get_notification_preferences(&user.id) {
+                Some(preferences) => preferences,
+                None => continue,
+            };
+            if !preferences.accepts(EVENT_INCIDENT, severity) {
+                continue;
+            }
+
+            let notification = Notification {
+                id: uuid::Uuid::new_v4().to_string(),
+                user_id: user.id.clone(),
+                channels: preferences.channels.clone(),
+                event_type: EVENT_INCIDENT.to_string(),
+                severity,
+                title: incident.title.clone(),
+                body: incident.description.clone(),
+                created_at: incident.created_at,
+            };
+
+            if preferences.is_immediate() {
+                immediate.push(notification);
+            } else {
+                // The first queued notification opens the user's digest period
+                self.last_digest.entry(user.id.clone()).or_insert(incident.created_at);
+                self.queued.entry(user.id.clone()).or_default().push(notification);
+            }
+        }
+
+        immediate
+    }
+
+    /// Notifications queued for a user's next digest
+    pub fn queued(&self, user_id: &str) -> &[Notification] {
+        self.queued.get(user_id).map(|queued| queued.as_slice()).unwrap_or(&[])
+    }
+
+    /// Build the digests whose period has elapsed and clear their queues
+    pub fn flush_digests(&mut self, users: &UserManager, now: DateTime<Utc>) -> Vec<NotificationDigest> {
+        let user_ids: Vec<String> = self.queued.keys().cloned().collect();
+        let mut digests = Vec::new();
+
+        for user_id in user_ids {
+            // Preferences may have changed since the notifications were queued
+            let preferences = users.get_notification_preferences(&user_id).unwrap_or_default();
+            let period_start = self.last_digest.get(&user_id).copied().unwrap_or(now);
+            if !preferences.digest_frequency.is_due(Some(period_start), now) {
+                continue;
+            }
+
+            let notifications = self.queued.remove(&user_id).unwrap_or_default();
+            if notifications.is_empty() {
+                continue;
+            }
+
+            self.last_digest.insert(user_id.clone(), now);
+            digests.push(NotificationDigest {
+                user_id,
+                channels: preferences.channels,
+                frequency: preferences.digest_frequency,
+                period_start,
+                period_end: now,
+                notifications,
+            });
+        }
+
+        digests
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IncidentManager, IncidentSeverity};
+    use chrono::Duration;
+    use sniper_users::{NotificationPreferences, UserRole};
+
+    #[test]
+    fn test_incident_routing() {
+        let mut users = UserManager::new();
+        let immediate = users.create_user("alice", "alice@example.com", vec![UserRole::Trader], "tenant-1").unwrap();
+        let digest = users.create_user("bob", "bob@example.com", vec![UserRole::Trader], "tenant-1").unwrap();
+        let picky = users.create_user("carol", "carol@example.com", vec![UserRole::Trader], "tenant-1").unwrap();
+        users.create_user("dave", "dave@example.com", vec![UserRole::Trader], "tenant-2").unwrap();
+
+        users
+            .set_notification_preferences(
+                &digest.id,
+                NotificationPreferences {
+                    digest_frequency: DigestFrequency::Hourly,
+                    ..NotificationPreferences::default()
+                },
+            )
+            .unwrap();
+        users
+            .set_notification_preferences(
+                &picky.id,
+                NotificationPreferences {
+                    min_severity: NotificationSeverity::Critical,
+                    ..NotificationPreferences::default()
+                },
+            )
+            .unwrap();
+
+        let mut incidents = IncidentManager::new();
+        let incident = incidents.create_incident("Feed down", "Price feed stalled", IncidentSeverity::High, "tenant-1");
+
+        let mut notifier = Notifier::new();
+        let delivered = notifier.notify_incident(&incident, &users);
+        assert_eq!(delivered.len(), 1);
+        assert_eq!(delivered[0].user_id, immediate.id);
+        assert_eq!(delivered[0].severity, NotificationSeverity::High);
+        assert_eq!(notifier.queued(&digest.id).len(), 1);
+        assert!(notifier.queued(&picky.id).is_empty());
+
+        // The digest goes out once the hourly period has elapsed
+        let now = incident.created_at;
+        assert!(notifier.flush_digests(&users, now + Duration::minutes(30)).is_empty());
+        let digests = notifier.flush_digests(&users, now + Duration::hours(1));
+        assert_eq!(digests.len(), 1);
+        assert_eq!(digests[0].notifications.len(), 1);
+        assert_eq!(digests[0].period_start, now);
+        assert!(notifier.queued(&digest.id).is_empty());
+    }
+}
@@ -0,0 +1,127 @@
+//! Metrics scraped from a Prometheus endpoint.
+//!
+//! Alert rules can read metrics other processes expose by scraping their
+//! `/metrics` endpoint in the Prometheus text format. A metric name reads
+//! its unlabelled series, or the sum of its labelled series when it has no
+//! unlabelled one, like `sum(metric)` in PromQL. Scraped values are numbers
+//! only; state metrics are read from the local registry.
+
+use crate::alerting::{MetricSource, MetricValue};
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// Values of one scrape of a Prometheus endpoint
+#[derive(Debug, Clone, Default)]
+pub struct ScrapedMetrics {
+    unlabelled: HashMap<String, f64>,
+    summed: HashMap<String, f64>,
+}
+
+impl ScrapedMetrics {
+    /// Parse metrics in the Prometheus text exposition format
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut metrics = Self::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || anyhow::anyhow!("Invalid sample on line {}: {}", number + 1, line);
+            let name_end = line.find(|c: char| c == '{' || c.is_whitespace()).ok_or_else(invalid)?;
+            let name = &line[..name_end];
+            let (labelled, rest) = if line[name_end..].starts_with('{') {
+                let labels_end = labels_end(&line[name_end..]).ok_or_else(invalid)?;
+                (true, &line[name_end + labels_end + 1..])
+            } else {
+                (false, &line[name_end..])
+            };
+            let value: f64 = rest.split_whitespace().next().ok_or_else(invalid)?.parse().map_err(|_| invalid())?;
+            if labelled {
+                *metrics.summed.entry(name.to_string()).or_insert(0.0) += value;
+            } else {
+                metrics.unlabelled.insert(name.to_string(), value);
+            }
+        }
+        Ok(metrics)
+    }
+
+    /// Scrape a Prometheus endpoint
+    pub async fn fetch(client: &reqwest::Client, url: &str) -> Result<Self> {
+        let response = client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to scrape {}: {}", url, e))?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Scraping {} returned {}", url, response.status()));
+        }
+        Self::parse(&response.text().await?)
+    }
+}
+
+/// Offset of the `}` closing a label set, skipping quoted values
+fn labels_end(labels: &str) -> Option<usize> {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (offset, c) in labels.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '}' if !quoted => return Some(offset),
+            _ => {}
+        }
+    }
+    None
+}
+
+impl MetricSource for ScrapedMetrics {
+    fn value(&self, name: &str) -> Option<MetricValue> {
+        self.unlabelled
+            .get(name)
+            .or_else(|| self.summed.get(name))
+            .map(|value| MetricValue::Number(*value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerting::{AlertExpression, MetricHistory, MetricSources};
+    use crate::MetricsRegistry;
+    use chrono::Utc;
+
+    const EXPOSITION: &str = r#"# HELP orders_failed_total Failed orders
+# TYPE orders_failed_total counter
+orders_failed_total 7
+# TYPE venue_latency_ms gauge
+venue_latency_ms{venue="binance",region="eu \"west\" }"} 12.5
+venue_latency_ms{venue="okx"} 7.5 1700000000000
+process_start_time_seconds 1.7e9
+up_ratio NaN
+"#;
+
+    #[test]
+    fn test_parse_exposition() {
+        let metrics = ScrapedMetrics::parse(EXPOSITION).unwrap();
+        assert_eq!(metrics.value("orders_failed_total"), Some(MetricValue::Number(7.0)));
+        assert_eq!(metrics.value("venue_latency_ms"), Some(MetricValue::Number(20.0)));
+        assert_eq!(metrics.value("process_start_time_seconds"), Some(MetricValue::Number(1.7e9)));
+        assert!(metrics.value("missing").is_none());
+        assert!(ScrapedMetrics::parse("broken_metric{venue=\"a\" 1").is_err());
+        assert!(ScrapedMetrics::parse("broken_metric one").is_err());
+    }
+
+    #[test]
+    fn test_rules_read_scraped_after_local_metrics() {
+        let mut registry = MetricsRegistry::new();
+        registry.register_gauge("orders_failed_total", "Failed orders").unwrap();
+        registry.set_gauge("orders_failed_total", 1.0).unwrap();
+        let scraped = ScrapedMetrics::parse(EXPOSITION).unwrap();
+        let sources = MetricSources(vec![&registry, &scraped]);
+
+        let check = |source: &str| AlertExpression::parse(source).unwrap().evaluate(&sources, &MetricHistory::new(), Utc::now()).unwrap();
+        assert!(check("orders_failed_total < 2"));
+        assert!(check("venue_latency_ms > 15"));
+    }
+}
@@ -0,0 +1,206 @@
+//! HTTP routes for feeding and querying the oracle from the svc-* binaries.
+//!
+//! Services merge [`routes`] into their router to accept DEX pool samples and
+//! Chainlink rounds from ingesters and to serve aggregated prices, and
+//! merge [`OracleApi`] into their OpenAPI document.
+
+use crate::sources::{ChainlinkRound, ChainlinkSource, TwapSource};
+use crate::{AggregatedPrice, OracleConfig, PriceOracle, PriceSource};
+use axum::{
+    extract::{Path, State},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sniper_core::errors::{ApiError, ErrorKind};
+use sniper_core::openapi::ErrorResponse;
+use std::sync::Arc;
+use utoipa::{OpenApi, ToSchema};
+
+/// Default TWAP window in seconds
+pub const DEFAULT_TWAP_WINDOW_SECS: i64 = 300;
+
+/// Oracle with its push-fed sources
+pub struct OracleFeeds {
+    pub oracle: Arc<PriceOracle>,
+    pub twap: Arc<TwapSource>,
+    pub chainlink: Arc<ChainlinkSource>,
+}
+
+impl OracleFeeds {
+    /// Create an oracle with a DEX TWAP and a Chainlink source registered
+    pub fn new(config: OracleConfig) -> Self {
+        Self::with_sources(config, Vec::new())
+    }
+
+    /// Create an oracle with the push-fed sources plus extra sources, such as CEX mid prices
+    pub fn with_sources(config: OracleConfig, sources: Vec<Arc<dyn PriceSource>>) -> Self {
+        let twap = Arc::new(TwapSource::new("dex-twap", DEFAULT_TWAP_WINDOW_SECS));
+        let chainlink = Arc::new(ChainlinkSource::new("chainlink"));
+
+        let mut oracle = PriceOracle::new(config);
+        oracle.add_source(twap.clone());
+        oracle.add_source(chainlink.clone());
+        for source in sources {
+            oracle.add_source(source);
+        }
+
+        Self {
+            oracle: Arc::new(oracle),
+            twap,
+            chainlink,
+        }
+    }
+}
+
+impl Default for OracleFeeds {
+    fn default() -> Self {
+        Self::new(OracleConfig::default())
+    }
+}
+
+/// Oracle API response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct OracleResponse<T> {
+    pub success: bool,
+    pub data: Option<T>,
+    pub message: Option<String>,
+}
+
+/// DEX pool price sample
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PoolSampleRequest {
+    pub asset: String,
+    pub price: f64,
+    #[serde(default)]
+    pub observed_at: Option<DateTime<Utc>>,
+}
+
+/// Chainlink round update
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ChainlinkRoundRequest {
+    pub asset: String,
+    pub round_id: u64,
+    pub answer: i128,
+    pub decimals: u8,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// OpenAPI document of the oracle routes
+#[derive(OpenApi)]
+#[openapi(paths(record_pool_sample, update_chainlink_round, get_price), tags((name = "oracle", description = "Price oracle feeds and aggregated prices")))]
+pub struct OracleApi;
+
+/// Oracle routes: `POST /oracle/twap`, `POST /oracle/chainlink`, `GET /oracle/prices/:asset`
+pub fn routes(feeds: Arc<OracleFeeds>) -> Router {
+    feed_routes(feeds.clone()).merge(query_routes(feeds))
+}
+
+/// Routes feeding the oracle: `POST /oracle/twap`, `POST /oracle/chainlink`
+pub fn feed_routes(feeds: Arc<OracleFeeds>) -> Router {
+    Router::new()
+        .route("/oracle/twap", post(record_pool_sample))
+        .route("/oracle/chainlink", post(update_chainlink_round))
+        .with_state(feeds)
+}
+
+/// Routes querying the oracle: `GET /oracle/prices/:asset`
+pub fn query_routes(feeds: Arc<OracleFeeds>) -> Router {
+    Router::new()
+        .route("/oracle/prices/:asset", get(get_price))
+        .with_state(feeds)
+}
+
+/// Record a DEX pool price sample
+#[utoipa::path(
+    post,
+    path = "/oracle/twap",
+    tag = "oracle",
+    request_body = PoolSampleRequest,
+    responses((status = 200, description = "Sample recorded", body = OracleResponse<bool>))
+)]
+async fn record_pool_sample(
+    State(feeds): State<Arc<OracleFeeds>>,
+    Json(payload): Json<PoolSampleRequest>,
+) -> Json<OracleResponse<bool>> {
+    let observed_at = payload.observed_at.unwrap_or_else(Utc::now);
+    feeds.twap.record(&payload.asset, payload.price, observed_at);
+
+    Json(OracleResponse {
+        success: true,
+        data: Some(true),
+        message: Some("Pool sample recorded successfully".to_string()),
+    })
+}
+
+/// Record a Chainlink round
+#[utoipa::path(
+    post,
+    path = "/oracle/chainlink",
+    tag = "oracle",
+    request_body = ChainlinkRoundRequest,
+    responses((status = 200, description = "Round recorded", body = OracleResponse<bool>))
+)]
+async fn update_chainlink_round(
+    State(feeds): State<Arc<OracleFeeds>>,
+    Json(payload): Json<ChainlinkRoundRequest>,
+) -> Json<OracleResponse<bool>> {
+    feeds.chainlink.update_round(&payload.asset, ChainlinkRound {
+        round_id: payload.round_id,
+        answer: payload.answer,
+        decimals: payload.decimals,
+        updated_at: payload.updated_at.unwrap_or_else(Utc::now),
+    });
+
+    Json(OracleResponse {
+        success: true,
+        data: Some(true),
+        message: Some("Chainlink round recorded successfully".to_string()),
+    })
+}
+
+/// Get the aggregated price of an asset
+#[utoipa::path(
+    get,
+    path = "/oracle/prices/{asset}",
+    tag = "oracle",
+    params(("asset" = String, Path, description = "Asset symbol")),
+    responses(
+        (status = 200, description = "Aggregated price", body = OracleResponse<AggregatedPrice>),
+        (status = 503, description = "Too few fresh sources agree on a price", body = ErrorResponse),
+    )
+)]
+async fn get_price(
+    State(feeds): State<Arc<OracleFeeds>>,
+    Path(asset): Path<String>,
+) -> Result<Json<OracleResponse<AggregatedPrice>>, ApiError> {
+    let price = feeds
+        .oracle
+        .aggregate(&asset)
+        .await
+        .map_err(|e| ApiError::new(ErrorKind::Unavailable, format!("Failed to get price: {}", e)))?;
+    Ok(Json(OracleResponse {
+        success: true,
+        data: Some(price),
+        message: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+
+    #[tokio::test]
+    async fn test_price_without_sources_is_unavailable() {
+        let feeds = Arc::new(OracleFeeds::default());
+        let error = get_price(State(feeds.clone()), Path("ETH".to_string())).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        feeds.twap.record("ETH", 3000.0, Utc::now());
+        let price = get_price(State(feeds), Path("ETH".to_string())).await.unwrap();
+        assert_eq!(price.0.data.unwrap().price, 3000.0);
+    }
+}
@@ -0,0 +1,313 @@
+//! Price oracle aggregation for the sniper bot.
+//!
+//! Prices for an asset are collected from pluggable sources (DEX TWAPs,
+//! Chainlink feeds, CEX mid prices). Stale observations are dropped, prices
+//! too far from the median are rejected as outliers, and the aggregate carries
+//! a confidence interval. The result is used for position marking, order
+//! trigger evaluation, and sanity checks against manipulated pool prices.
+
+pub mod sources;
+pub mod http;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::sync::Arc;
+
+/// Kind of price source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum PriceSourceKind {
+    DexTwap,
+    Chainlink,
+    CexMid,
+}
+
+/// A price reported by one source
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PriceObservation {
+    pub source: String,
+    pub kind: PriceSourceKind,
+    pub asset: String,
+    pub price: f64,
+    pub observed_at: DateTime<Utc>,
+}
+
+/// Pluggable price source
+#[async_trait]
+pub trait PriceSource: Send + Sync {
+    /// Source name, unique within an oracle
+    fn name(&self) -> &str;
+
+    /// Kind of source
+    fn kind(&self) -> PriceSourceKind;
+
+    /// Fetch the latest price for an asset, if the source covers it
+    async fn fetch_price(&self, asset: &str) -> Result<Option<PriceObservation>>;
+}
+
+/// Oracle aggregation settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OracleConfig {
+    /// Observations older than this are ignored
+    pub max_age_secs: i64,
+    /// Maximum relative distance from the median before a price is an outlier
+    pub max_deviation: f64,
+    /// Minimum number of agreeing sources for a valid price
+    pub min_sources: usize,
+    /// Maximum relative distance of a pool price from the oracle price
+    pub max_pool_deviation: f64,
+}
+
+impl Default for OracleConfig {
+    fn default() -> Self {
+        Self {
+            max_age_secs: 60,
+            max_deviation: 0.02,
+            min_sources: 1,
+            max_pool_deviation: 0.05,
+        }
+    }
+}
+
+/// Aggregated price with a 95% confidence interval
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AggregatedPrice {
+    pub asset: String,
+    pub price: f64,
+    pub lower: f64,
+    pub upper: f64,
+    pub sources: Vec<PriceObservation>,
+    pub rejected: Vec<PriceObservation>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl AggregatedPrice {
+    /// Width of the confidence interval relative to the price
+    pub fn confidence_width(&self) -> f64 {
+        if self.price == 0.0 {
+            return f64::INFINITY;
+        }
+        (self.upper - self.lower) / self.price
+    }
+}
+
+/// Price oracle aggregating multiple sources
+pub struct PriceOracle {
+    config: OracleConfig,
+    sources: Vec<Arc<dyn PriceSource>>,
+}
+
+impl PriceOracle {
+    /// Create a new price oracle
+    pub fn new(config: OracleConfig) -> Self {
+        Self {
+            config,
+            sources: Vec::new(),
+        }
+    }
+
+    /// Get the oracle configuration
+    pub fn config(&self) -> &OracleConfig {
+        &self.config
+    }
+
+    /// Add a price source
+    pub fn add_source(&mut self, source: Arc<dyn PriceSource>) {
+        self.sources.push(source);
+    }
+
+    /// List source names
+    pub fn source_names(&self) -> Vec<&str> {
+        self.sources.iter().map(|source| source.name()).collect()
+    }
+
+    /// Aggregate the current price of an asset across all sources
+    pub async fn aggregate(&self, asset: &str) -> Result<AggregatedPrice> {
+        let now = Utc::now();
+        let mut observations = Vec::new();
+
+        for source in &self.sources {
+            match source.fetch_price(asset).await {
+                Ok(Some(observation)) => {
+                    let age = (now - observation.observed_at).num_seconds();
+                    if age > self.config.max_age_secs {
+                        tracing::debug!("ignoring stale {} price for {} ({}s old)", source.name(), asset, age);
+                    } else if observation.price.is_finite() && observation.price > 0.0 {
+                        observations.push(observation);
+                    }
+                },
+                Ok(None) => {},
+                Err(e) => tracing::warn!("price source {} failed for {}: {}", source.name(), asset, e),
+            }
+        }
+
+        if observations.is_empty() {
+            return Err(anyhow::anyhow!("No prices available for {}", asset));
+        }
+
+        // Reject outliers relative to the median of all fresh prices
+        let mut prices: Vec<f64> = observations.iter().map(|o| o.price).collect();
+        let reference = median(&mut prices);
+        let (accepted, rejected): (Vec<_>, Vec<_>) = observations
+            .into_iter()
+            .partition(|o| (o.price - reference).abs() / reference <= self.config.max_deviation);
+
+        for observation in &rejected {
+            tracing::warn!(
+                "rejecting {} price {} for {} (median {})",
+                observation.source, observation.price, asset, reference
+            );
+        }
+
+        if accepted.len() < self.config.min_sources {
+            return Err(anyhow::anyhow!(
+                "Only {} sources agree on a price for {} (need {})",
+                accepted.len(), asset, self.config.min_sources
+            ));
+        }
+
+        let mut prices: Vec<f64> = accepted.iter().map(|o| o.price).collect();
+        let price = median(&mut prices);
+        let half_width = if prices.len() > 1 {
+            let n = prices.len() as f64;
+            let mean = prices.iter().sum::<f64>() / n;
+            let variance = prices.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / (n - 1.0);
+            1.96 * variance.sqrt() / n.sqrt()
+        } else {
+            // A single source gives no spread; fall back to the outlier tolerance
+            price * self.config.max_deviation
+        };
+
+        Ok(AggregatedPrice {
+            asset: asset.to_string(),
+            price,
+            lower: price - half_width,
+            upper: price + half_width,
+            sources: accepted,
+            rejected,
+            timestamp: now,
+        })
+    }
+
+    /// Check a pool price against the oracle, rejecting likely manipulation
+    pub async fn check_pool_price(&self, asset: &str, pool_price: f64) -> Result<AggregatedPrice> {
+        let aggregated = self.aggregate(asset).await?;
+        let deviation = (pool_price - aggregated.price).abs() / aggregated.price;
+
+        if deviation > self.config.max_pool_deviation {
+            return Err(anyhow::anyhow!(
+                "Pool price {} for {} deviates {:.2}% from oracle price {}",
+                pool_price, asset, deviation * 100.0, aggregated.price
+            ));
+        }
+
+        Ok(aggregated)
+    }
+}
+
+/// Median of a non-empty slice, sorting it in place
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len() % 2 == 0 {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sources::{ChainlinkRound, ChainlinkSource, TwapSource};
+
+    struct FixedSource {
+        name: String,
+        price: f64,
+    }
+
+    #[async_trait]
+    impl PriceSource for FixedSource {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn kind(&self) -> PriceSourceKind {
+            PriceSourceKind::CexMid
+        }
+
+        async fn fetch_price(&self, asset: &str) -> Result<Option<PriceObservation>> {
+            Ok(Some(PriceObservation {
+                source: self.name.clone(),
+                kind: self.kind(),
+                asset: asset.to_string(),
+                price: self.price,
+                observed_at: Utc::now(),
+            }))
+        }
+    }
+
+    fn fixed(name: &str, price: f64) -> Arc<dyn PriceSource> {
+        Arc::new(FixedSource {
+            name: name.to_string(),
+            price,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_outlier_rejection() -> Result<()> {
+        let mut oracle = PriceOracle::new(OracleConfig::default());
+        oracle.add_source(fixed("binance", 3000.0));
+        oracle.add_source(fixed("coinbase", 3003.0));
+        oracle.add_source(fixed("kraken", 2998.0));
+        oracle.add_source(fixed("manipulated-pool", 3600.0));
+
+        let aggregated = oracle.aggregate("ETH").await?;
+        assert_eq!(aggregated.sources.len(), 3);
+        assert_eq!(aggregated.rejected.len(), 1);
+        assert_eq!(aggregated.rejected[0].source, "manipulated-pool");
+        assert_eq!(aggregated.price, 3000.0);
+        assert!(aggregated.lower < 3000.0 && aggregated.upper > 3000.0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_min_sources() -> Result<()> {
+        let mut oracle = PriceOracle::new(OracleConfig {
+            min_sources: 2,
+            ..OracleConfig::default()
+        });
+        oracle.add_source(fixed("binance", 3000.0));
+
+        assert!(oracle.aggregate("ETH").await.is_err());
+        assert!(PriceOracle::new(OracleConfig::default()).aggregate("ETH").await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pool_price_sanity_check() -> Result<()> {
+        let twap = Arc::new(TwapSource::new("uniswap-twap", 300));
+        let chainlink = Arc::new(ChainlinkSource::new("chainlink"));
+        let now = Utc::now();
+        twap.record("ETH", 3000.0, now - chrono::Duration::seconds(30));
+        chainlink.update_round("ETH", ChainlinkRound {
+            round_id: 1,
+            answer: 301_000_000_000,
+            decimals: 8,
+            updated_at: now,
+        });
+
+        let mut oracle = PriceOracle::new(OracleConfig::default());
+        oracle.add_source(twap);
+        oracle.add_source(chainlink);
+
+        assert!(oracle.check_pool_price("ETH", 3020.0).await.is_ok());
+        assert!(oracle.check_pool_price("ETH", 3500.0).await.is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,262 @@
+//! Built-in price sources.
+//!
+//! `TwapSource` and `ChainlinkSource` are fed by whatever ingests chain data
+//! (pool price samples and aggregator rounds respectively); `CexMidSource`
+//! pulls order books from an exchange client on demand.
+
+use crate::{PriceObservation, PriceSource, PriceSourceKind};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sniper_cex::{CexClient, Symbol};
+use std::collections::{HashMap, VecDeque};
+use std::sync::RwLock;
+
+/// Time-weighted average of DEX pool prices over a rolling window
+pub struct TwapSource {
+    name: String,
+    window_secs: i64,
+    samples: RwLock<HashMap<String, VecDeque<(DateTime<Utc>, f64)>>>,
+}
+
+impl TwapSource {
+    /// Create a new TWAP source
+    pub fn new(name: &str, window_secs: i64) -> Self {
+        Self {
+            name: name.to_string(),
+            window_secs,
+            samples: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Record a pool price sample
+    ///
+    /// Samples older than the latest recorded one are ignored.
+    pub fn record(&self, asset: &str, price: f64, at: DateTime<Utc>) {
+        let mut samples = self.samples.write().unwrap();
+        let series = samples.entry(asset.to_string()).or_default();
+
+        if series.back().map(|(last, _)| at < *last).unwrap_or(false) {
+            return;
+        }
+        series.push_back((at, price));
+
+        // Keep the last sample before the window start, it prices the window's opening
+        let start = at - chrono::Duration::seconds(self.window_secs);
+        while series.len() > 1 && series[1].0 <= start {
+            series.pop_front();
+        }
+    }
+
+    /// Compute the TWAP for an asset as of `now`
+    pub fn twap(&self, asset: &str, now: DateTime<Utc>) -> Option<(f64, DateTime<Utc>)> {
+        let samples = self.samples.read().unwrap();
+        let series = samples.get(asset)?;
+        let (last_at, last_price) = *series.back()?;
+
+        let start = now - chrono::Duration::seconds(self.window_secs);
+        let mut weighted = 0.0;
+        let mut total = 0.0;
+        for (i, (at, price)) in series.iter().enumerate() {
+            let from = (*at).max(start);
+            let to = series.get(i + 1).map(|(next, _)| *next).unwrap_or(now);
+            let secs = (to - from).num_milliseconds().max(0) as f64 / 1000.0;
+            weighted += price * secs;
+            total += secs;
+        }
+
+        if total == 0.0 {
+            return Some((last_price, last_at));
+        }
+        Some((weighted / total, last_at))
+    }
+}
+
+#[async_trait]
+impl PriceSource for TwapSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn kind(&self) -> PriceSourceKind {
+        PriceSourceKind::DexTwap
+    }
+
+    async fn fetch_price(&self, asset: &str) -> Result<Option<PriceObservation>> {
+        Ok(self.twap(asset, Utc::now()).map(|(price, observed_at)| PriceObservation {
+            source: self.name.clone(),
+            kind: PriceSourceKind::DexTwap,
+            asset: asset.to_string(),
+            price,
+            observed_at,
+        }))
+    }
+}
+
+/// A Chainlink aggregator round
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainlinkRound {
+    pub round_id: u64,
+    pub answer: i128,
+    pub decimals: u8,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ChainlinkRound {
+    /// Answer scaled by the feed decimals
+    pub fn price(&self) -> f64 {
+        self.answer as f64 / 10f64.powi(self.decimals as i32)
+    }
+}
+
+/// Latest Chainlink answers per asset
+pub struct ChainlinkSource {
+    name: String,
+    rounds: RwLock<HashMap<String, ChainlinkRound>>,
+}
+
+impl ChainlinkSource {
+    /// Create a new Chainlink source
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            rounds: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Record a new round, ignoring rounds older than the current one
+    pub fn update_round(&self, asset: &str, round: ChainlinkRound) {
+        let mut rounds = self.rounds.write().unwrap();
+        match rounds.get(asset) {
+            Some(current) if current.round_id >= round.round_id => {},
+            _ => {
+                rounds.insert(asset.to_string(), round);
+            },
+        }
+    }
+
+    /// Get the latest round for an asset
+    pub fn latest_round(&self, asset: &str) -> Option<ChainlinkRound> {
+        self.rounds.read().unwrap().get(asset).cloned()
+    }
+}
+
+#[async_trait]
+impl PriceSource for ChainlinkSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn kind(&self) -> PriceSourceKind {
+        PriceSourceKind::Chainlink
+    }
+
+    async fn fetch_price(&self, asset: &str) -> Result<Option<PriceObservation>> {
+        // Non-positive answers indicate a broken feed
+        Ok(self
+            .latest_round(asset)
+            .filter(|round| round.answer > 0)
+            .map(|round| PriceObservation {
+                source: self.name.clone(),
+                kind: PriceSourceKind::Chainlink,
+                asset: asset.to_string(),
+                price: round.price(),
+                observed_at: round.updated_at,
+            }))
+    }
+}
+
+/// Mid price from a CEX order book
+pub struct CexMidSource<C> {
+    name: String,
+    client: C,
+    symbols: HashMap<String, Symbol>,
+}
+
+impl<C: CexClient + Send + Sync> CexMidSource<C> {
+    /// Create a new CEX mid-price source
+    pub fn new(name: &str, client: C) -> Self {
+        Self {
+            name: name.to_string(),
+            client,
+            symbols: HashMap::new(),
+        }
+    }
+
+    /// Map an asset to the exchange symbol quoting it
+    pub fn add_symbol(&mut self, asset: &str, symbol: Symbol) {
+        self.symbols.insert(asset.to_string(), symbol);
+    }
+}
+
+#[async_trait]
+impl<C: CexClient + Send + Sync> PriceSource for CexMidSource<C> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn kind(&self) -> PriceSourceKind {
+        PriceSourceKind::CexMid
+    }
+
+    async fn fetch_price(&self, asset: &str) -> Result<Option<PriceObservation>> {
+        let symbol = match self.symbols.get(asset) {
+            Some(symbol) => symbol,
+            None => return Ok(None),
+        };
+
+        let book = self.client.get_order_book(symbol).await?;
+        let best_bid = book.bids.iter().map(|level| level.price).fold(f64::NAN, f64::max);
+        let best_ask = book.asks.iter().map(|level| level.price).fold(f64::NAN, f64::min);
+        if !best_bid.is_finite() || !best_ask.is_finite() {
+            return Ok(None);
+        }
+
+        Ok(Some(PriceObservation {
+            source: self.name.clone(),
+            kind: PriceSourceKind::CexMid,
+            asset: asset.to_string(),
+            price: (best_bid + best_ask) / 2.0,
+            observed_at: Utc::now(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_twap_weights_by_time() {
+        let source = TwapSource::new("twap", 100);
+        let now = Utc::now();
+        source.record("ETH", 3000.0, now - chrono::Duration::seconds(100));
+        // A short spike barely moves the TWAP
+        source.record("ETH", 6000.0, now - chrono::Duration::seconds(1));
+
+        let (price, _) = source.twap("ETH", now).unwrap();
+        assert!((price - 3030.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_chainlink_rounds() {
+        let source = ChainlinkSource::new("chainlink");
+        let now = Utc::now();
+        source.update_round("ETH", ChainlinkRound {
+            round_id: 2,
+            answer: 300_000_000_000,
+            decimals: 8,
+            updated_at: now,
+        });
+        // Older rounds do not replace newer ones
+        source.update_round("ETH", ChainlinkRound {
+            round_id: 1,
+            answer: 100_000_000_000,
+            decimals: 8,
+            updated_at: now,
+        });
+
+        assert_eq!(source.latest_round("ETH").unwrap().price(), 3000.0);
+    }
+}
@@ -0,0 +1,116 @@
+//! Order manager sharding benchmarks
+//!
+//! This benchmark suite compares concurrent price-update dispatch through a
+//! single-lock order manager against the symbol-sharded order manager.
+
+use criterion::{black_box, criterion_group, criterion_main, Bencher, Criterion};
+use sniper_core::types::{ChainRef, Decimal};
+use sniper_orders::{AdvancedOrder, OrderManager, OrderStatus, OrderType, ShardedOrderManager, TimeInForce};
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+use tokio::sync::RwLock;
+
+const SYMBOLS: usize = 32;
+const ORDERS_PER_SYMBOL: usize = 100;
+
+/// Benchmark price updates across all symbols through one lock
+fn bench_single_lock_price_updates(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let manager = Arc::new(RwLock::new(OrderManager::new()));
+    rt.block_on(async {
+        let mut guard = manager.write().await;
+        for order in create_test_orders() {
+            guard.create_order(order).unwrap();
+        }
+    });
+
+    c.bench_function("single_lock_price_updates", |b: &mut Bencher| {
+        b.to_async(&rt).iter(|| {
+            let manager = manager.clone();
+            async move {
+                let mut tasks = Vec::new();
+                for i in 0..SYMBOLS {
+                    let manager = manager.clone();
+                    tasks.push(tokio::spawn(async move {
+                        // Each update holds the global write lock to move trailing stops
+                        let mut guard = manager.write().await;
+                        guard.update_market_price(&symbol(i), 100.0).len()
+                    }));
+                }
+                for task in tasks {
+                    black_box(task.await.unwrap());
+                }
+            }
+        })
+    });
+}
+
+/// Benchmark price updates across all symbols through per-symbol shards
+fn bench_sharded_price_updates(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let manager = Arc::new(ShardedOrderManager::new());
+    rt.block_on(async {
+        for order in create_test_orders() {
+            manager.create_order(order).await.unwrap();
+        }
+    });
+
+    c.bench_function("sharded_price_updates", |b: &mut Bencher| {
+        b.to_async(&rt).iter(|| {
+            let manager = manager.clone();
+            async move {
+                let mut tasks = Vec::new();
+                for i in 0..SYMBOLS {
+                    let manager = manager.clone();
+                    tasks.push(tokio::spawn(async move {
+                        manager.on_price_update(&symbol(i), 100.0).await.len()
+                    }));
+                }
+                for task in tasks {
+                    black_box(task.await.unwrap());
+                }
+            }
+        })
+    });
+}
+
+/// Symbol name for an index
+fn symbol(i: usize) -> String {
+    format!("TOKEN{}/USDT", i)
+}
+
+/// Create limit orders spread across the test symbols
+fn create_test_orders() -> Vec<AdvancedOrder> {
+    let mut orders = Vec::new();
+    for i in 0..SYMBOLS {
+        for j in 0..ORDERS_PER_SYMBOL {
+            orders.push(AdvancedOrder {
+                id: format!("order-{}-{}", i, j),
+                symbol: symbol(i),
+                chain: ChainRef {
+                    name: "ethereum".to_string(),
+                    id: 1,
+                },
+                order_type: OrderType::Limit { price: 90.0 + j as f64 * 0.2 },
+                side: "buy".to_string(),
+                amount: Decimal::from(1),
+                time_in_force: TimeInForce::GoodTillCancelled,
+                created_at: 1234567890,
+                updated_at: 1234567890,
+                status: OrderStatus::Pending,
+                fills: Vec::new(),
+                owner_id: None,
+                tenant_id: None,
+                trigger: None,
+            });
+        }
+    }
+    orders
+}
+
+criterion_group!(
+    benches,
+    bench_single_lock_price_updates,
+    bench_sharded_price_updates
+);
+criterion_main!(benches);
@@ -0,0 +1,362 @@
+//! Bracket orders.
+//!
+//! A bracket is an entry order with a take-profit and a stop-loss exit
+//! attached. The exits are placed once the entry fills, sized to the position
+//! it opened, and linked one-cancels-the-other: a fill in one exit shrinks the
+//! other to the position still open, and once the exits have closed the
+//! position the rest of the bracket is cancelled. The entry and its exits are
+//! cancelled together, under the lock of their shard.
+
+use crate::{AdvancedOrder, OrderError, OrderStatus, OrderType, ShardedOrderManager, TimeInForce};
+use serde::{Deserialize, Serialize};
+use sniper_core::audit::SYSTEM_ACTOR;
+use sniper_core::types::Decimal;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Stage of a bracket
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BracketStatus {
+    /// The entry has not filled yet
+    Pending,
+    /// The entry filled and its exits are working
+    Active,
+    /// An exit closed the whole position
+    Closed,
+    Cancelled,
+}
+
+/// Entry order with take-profit and stop-loss exits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BracketOrder {
+    /// Bracket ID, the same as its entry order's
+    pub id: String,
+    pub take_profit_price: f64,
+    pub stop_loss_price: f64,
+    /// Take-profit exit, once the entry has filled
+    pub take_profit_id: Option<String>,
+    /// Stop-loss exit, once the entry has filled
+    pub stop_loss_id: Option<String>,
+    pub status: BracketStatus,
+}
+
+impl BracketOrder {
+    /// ID of the entry order
+    pub fn entry_id(&self) -> &str {
+        &self.id
+    }
+
+    /// IDs of the entry and the exits placed so far
+    pub fn order_ids(&self) -> Vec<String> {
+        std::iter::once(&self.id)
+            .chain(self.take_profit_id.as_ref())
+            .chain(self.stop_loss_id.as_ref())
+            .cloned()
+            .collect()
+    }
+
+    /// Whether the exits can still be placed, resized or triggered
+    pub fn is_open(&self) -> bool {
+        matches!(self.status, BracketStatus::Pending | BracketStatus::Active)
+    }
+}
+
+/// Check the exit prices are positive and on either side of the entry
+///
+/// A long bracket takes profit above its stop, a short one below it. Entries
+/// with a price of their own must sit between the two.
+fn validate(entry: &AdvancedOrder, take_profit: f64, stop_loss: f64) -> Result<(), OrderError> {
+    if matches!(entry.order_type, OrderType::TWAP { .. } | OrderType::VWAP { .. }) {
+        return Err(OrderError::InvalidBracket("TWAP and VWAP orders cannot be bracketed".to_string()));
+    }
+    for (name, price) in [("take-profit", take_profit), ("stop-loss", stop_loss)] {
+        if !price.is_finite() || price <= 0.0 {
+            return Err(OrderError::InvalidBracket(format!("{} price {} is not positive", name, price)));
+        }
+    }
+    let (low, high) = if entry.side == "buy" {
+        (stop_loss, take_profit)
+    } else {
+        (take_profit, stop_loss)
+    };
+    let inside = match entry.reference_price() {
+        Some(price) => low < price && price < high,
+        None => low < high,
+    };
+    if !inside {
+        return Err(OrderError::InvalidBracket(format!(
+            "a {} entry needs its stop-loss {} and take-profit {} on either side of the entry",
+            entry.side, stop_loss, take_profit
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct Brackets {
+    brackets: HashMap<String, BracketOrder>,
+    /// Bracket of each entry and exit order
+    links: HashMap<String, String>,
+}
+
+/// Places brackets and keeps their exits in step with the fills
+pub struct BracketManager {
+    orders: Arc<ShardedOrderManager>,
+    brackets: Mutex<Brackets>,
+}
+
+impl BracketManager {
+    /// Create a bracket manager placing orders in `orders`
+    pub fn new(orders: Arc<ShardedOrderManager>) -> Self {
+        Self {
+            orders,
+            brackets: Mutex::new(Brackets::default()),
+        }
+    }
+
+    /// Place an entry order with take-profit and stop-loss exits at the given prices
+    pub async fn place(&self, entry: AdvancedOrder, take_profit: f64, stop_loss: f64) -> Result<BracketOrder, OrderError> {
+        validate(&entry, take_profit, stop_loss)?;
+        let mut brackets = self.brackets.lock().await;
+        if brackets.links.contains_key(&entry.id) {
+            return Err(OrderError::IdTaken(entry.id.clone()));
+        }
+        let bracket = BracketOrder {
+            id: entry.id.clone(),
+            take_profit_price: take_profit,
+            stop_loss_price: stop_loss,
+            take_profit_id: None,
+            stop_loss_id: None,
+            status: BracketStatus::Pending,
+        };
+        self.orders.create_order(entry).await?;
+        brackets.links.insert(bracket.id.clone(), bracket.id.clone());
+        brackets.brackets.insert(bracket.id.clone(), bracket.clone());
+        Ok(bracket)
+    }
+
+    /// Get a bracket by ID
+    pub async fn get(&self, bracket_id: &str) -> Option<BracketOrder> {
+        self.brackets.lock().await.brackets.get(bracket_id).cloned()
+    }
+
+    /// Get the bracket an entry or exit order belongs to
+    pub async fn bracket_of(&self, order_id: &str) -> Option<BracketOrder> {
+        let brackets = self.brackets.lock().await;
+        let bracket_id = brackets.links.get(order_id)?;
+        brackets.brackets.get(bracket_id).cloned()
+    }
+
+    /// Bring a bracket's exits in step with a fill of one of its orders
+    ///
+    /// Returns the bracket, or `None` if the order is not part of one.
+    pub async fn on_fill(&self, order_id: &str) -> Result<Option<BracketOrder>, OrderError> {
+        let mut brackets = self.brackets.lock().await;
+        let Some(bracket_id) = brackets.links.get(order_id).cloned() else {
+            return Ok(None);
+        };
+        let Some(mut bracket) = brackets.brackets.get(&bracket_id).cloned() else {
+            return Ok(None);
+        };
+        if !bracket.is_open() {
+            return Ok(Some(bracket));
+        }
+        // Keep the exits placed before a failure linked
+        let result = self.rebalance(&mut bracket).await;
+        for exit_id in [&bracket.take_profit_id, &bracket.stop_loss_id].into_iter().flatten() {
+            brackets.links.insert(exit_id.clone(), bracket.id.clone());
+        }
+        brackets.brackets.insert(bracket.id.clone(), bracket.clone());
+        result.map(|()| Some(bracket))
+    }
+
+    /// Cancel the entry and exits of a bracket together, returning the bracket
+    pub async fn cancel(&self, bracket_id: &str, actor: &str) -> Result<BracketOrder, OrderError> {
+        let mut brackets = self.brackets.lock().await;
+        let bracket = brackets
+            .brackets
+            .get_mut(bracket_id)
+            .ok_or_else(|| OrderError::NotFound(bracket_id.to_string()))?;
+        if !bracket.is_open() {
+            return Err(OrderError::BracketClosed {
+                id: bracket.id.clone(),
+                status: bracket.status,
+            });
+        }
+        self.orders.cancel_orders_by(&bracket.order_ids(), actor).await?;
+        bracket.status = BracketStatus::Cancelled;
+        Ok(bracket.clone())
+    }
+
+    /// Size the exits to the position the entry opened and the exits have not closed
+    async fn rebalance(&self, bracket: &mut BracketOrder) -> Result<(), OrderError> {
+        let entry = self
+            .orders
+            .get_order(bracket.entry_id())
+            .await
+            .ok_or_else(|| OrderError::NotFound(bracket.id.clone()))?;
+        let take_profit = match &bracket.take_profit_id {
+            Some(id) => self.orders.get_order(id).await,
+            None => None,
+        };
+        let stop_loss = match &bracket.stop_loss_id {
+            Some(id) => self.orders.get_order(id).await,
+            None => None,
+        };
+        let exited: Decimal = [&take_profit, &stop_loss]
+            .into_iter()
+            .flatten()
+            .map(AdvancedOrder::filled_amount)
+            .sum();
+        let position = entry.filled_amount() - exited;
+
+        if !position.is_positive() {
+            if exited.is_positive() {
+                self.orders.cancel_orders_by(&bracket.order_ids(), SYSTEM_ACTOR).await?;
+                bracket.status = BracketStatus::Closed;
+            }
+            return Ok(());
+        }
+
+        let take_profit_type = OrderType::Limit { price: bracket.take_profit_price };
+        let stop_loss_type = OrderType::StopLoss { price: bracket.stop_loss_price };
+        for (suffix, order_type, existing, slot) in [
+            ("tp", take_profit_type, take_profit, &mut bracket.take_profit_id),
+            ("sl", stop_loss_type, stop_loss, &mut bracket.stop_loss_id),
+        ] {
+            match existing {
+                None => {
+                    let exit = exit_order(&entry, suffix, order_type, position);
+                    *slot = Some(self.orders.create_order(exit).await?);
+                }
+                // Exits cancelled on their own are left alone
+                Some(exit) if exit.is_open() => {
+                    let amount = exit.filled_amount() + position;
+                    if exit.amount != amount {
+                        self.orders.amend_order(AdvancedOrder { amount, ..exit }, SYSTEM_ACTOR).await?;
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+        bracket.status = BracketStatus::Active;
+        Ok(())
+    }
+}
+
+/// Exit closing `amount` of the position an entry opened
+fn exit_order(entry: &AdvancedOrder, suffix: &str, order_type: OrderType, amount: Decimal) -> AdvancedOrder {
+    let now = chrono::Utc::now().timestamp() as u64;
+    AdvancedOrder {
+        id: format!("{}-{}", entry.id, suffix),
+        symbol: entry.symbol.clone(),
+        chain: entry.chain.clone(),
+        order_type,
+        side: if entry.side == "buy" { "sell" } else { "buy" }.to_string(),
+        amount,
+        time_in_force: TimeInForce::GoodTillCancelled,
+        created_at: now,
+        updated_at: now,
+        status: OrderStatus::Pending,
+        fills: Vec::new(),
+        owner_id: entry.owner_id.clone(),
+        tenant_id: entry.tenant_id.clone(),
+        trigger: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use sniper_core::types::ChainRef;
+
+    fn entry(side: &str, order_type: OrderType) -> AdvancedOrder {
+        AdvancedOrder {
+            id: "entry-1".to_string(),
+            symbol: "ETH/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type,
+            side: side.to_string(),
+            amount: Decimal::from(2),
+            time_in_force: TimeInForce::GoodTillCancelled,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: Some("user-1".to_string()),
+            tenant_id: None,
+            trigger: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bracket_exits_follow_fills() -> Result<()> {
+        let orders = Arc::new(ShardedOrderManager::new());
+        let brackets = BracketManager::new(orders.clone());
+        let bracket = brackets.place(entry("buy", OrderType::Limit { price: 3000.0 }), 3300.0, 2900.0).await?;
+        assert_eq!(bracket.status, BracketStatus::Pending);
+        assert_eq!(bracket.order_ids(), vec!["entry-1".to_string()]);
+
+        // A partial entry fill places both exits for what was filled
+        orders.record_fill("entry-1", Decimal::ONE, Decimal::from(3000)).await?;
+        let bracket = brackets.on_fill("entry-1").await?.unwrap();
+        assert_eq!(bracket.status, BracketStatus::Active);
+        let take_profit = orders.get_order("entry-1-tp").await.unwrap();
+        let stop_loss = orders.get_order("entry-1-sl").await.unwrap();
+        assert_eq!(take_profit.side, "sell");
+        assert_eq!(take_profit.amount, Decimal::ONE);
+        assert_eq!(take_profit.owner_id.as_deref(), Some("user-1"));
+        assert_eq!(stop_loss.order_type, OrderType::StopLoss { price: 2900.0 });
+
+        // The rest of the entry grows both exits
+        orders.record_fill("entry-1", Decimal::ONE, Decimal::from(3000)).await?;
+        brackets.on_fill("entry-1").await?;
+        assert_eq!(orders.get_order("entry-1-sl").await.unwrap().amount, Decimal::from(2));
+
+        // A partial take-profit shrinks the stop, a full one cancels it
+        orders.record_fill("entry-1-tp", Decimal::ONE, Decimal::from(3300)).await?;
+        brackets.on_fill("entry-1-tp").await?;
+        assert_eq!(orders.get_order("entry-1-sl").await.unwrap().remaining_amount(), Decimal::ONE);
+        orders.record_fill("entry-1-tp", Decimal::ONE, Decimal::from(3300)).await?;
+        let bracket = brackets.on_fill("entry-1-tp").await?.unwrap();
+        assert_eq!(bracket.status, BracketStatus::Closed);
+        assert_eq!(orders.get_order("entry-1-sl").await.unwrap().status, OrderStatus::Cancelled);
+
+        let closed = brackets.cancel("entry-1", "user-1").await.unwrap_err();
+        assert!(matches!(closed, OrderError::BracketClosed { .. }));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bracket_cancels_atomically() -> Result<()> {
+        let orders = Arc::new(ShardedOrderManager::new());
+        let brackets = BracketManager::new(orders.clone());
+        brackets.place(entry("sell", OrderType::Market), 2500.0, 3500.0).await?;
+        orders.record_fill("entry-1", Decimal::ONE, Decimal::from(3000)).await?;
+        brackets.on_fill("entry-1").await?;
+        assert_eq!(orders.get_order("entry-1-tp").await.unwrap().side, "buy");
+
+        let bracket = brackets.cancel("entry-1", "user-1").await?;
+        assert_eq!(bracket.status, BracketStatus::Cancelled);
+        for order_id in bracket.order_ids() {
+            assert_eq!(orders.get_order(&order_id).await.unwrap().status, OrderStatus::Cancelled);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bracket_prices_must_straddle_entry() {
+        let brackets = BracketManager::new(Arc::new(ShardedOrderManager::new()));
+        let inverted = brackets.place(entry("buy", OrderType::Market), 2900.0, 3300.0).await.unwrap_err();
+        assert!(matches!(inverted, OrderError::InvalidBracket(_)));
+        let above_entry = brackets.place(entry("buy", OrderType::Limit { price: 3400.0 }), 3300.0, 2900.0).await.unwrap_err();
+        assert!(matches!(above_entry, OrderError::InvalidBracket(_)));
+        let sliced = OrderType::TWAP { total_amount: 2.0, duration_minutes: 10 };
+        assert!(brackets.place(entry("buy", sliced), 3300.0, 2900.0).await.is_err());
+    }
+}
@@ -0,0 +1,71 @@
+//! Errors returned by the order managers.
+
+use crate::{BracketStatus, OrderStatus};
+use sniper_core::errors::{DomainError, ErrorKind};
+use sniper_core::kill_switch::KillSwitchTripped;
+use sniper_core::types::Decimal;
+use sniper_risk::RiskRejected;
+use thiserror::Error;
+
+/// Failure of an order operation
+#[derive(Debug, Clone, Error)]
+pub enum OrderError {
+    #[error("Order {0} not found")]
+    NotFound(String),
+    #[error("Order {id} is {status:?} and cannot be filled")]
+    NotOpen { id: String, status: OrderStatus },
+    #[error("Order {id} is {status:?} and cannot be amended")]
+    NotAmendable { id: String, status: OrderStatus },
+    #[error("Order {id} cannot change its {field}; cancel it and place a new order")]
+    Immutable { id: String, field: &'static str },
+    #[error("Fill quantity and price must be positive")]
+    InvalidFill,
+    #[error("Fill quantity {quantity} exceeds remaining quantity {remaining}")]
+    Overfill { quantity: Decimal, remaining: Decimal },
+    #[error("Fill-or-kill order cannot be partially filled ({quantity} of {remaining})")]
+    FillOrKill { quantity: Decimal, remaining: Decimal },
+    #[error("Order {0} conditions not met")]
+    ConditionsNotMet(String),
+    #[error("Invalid order amount: {0}")]
+    InvalidAmount(String),
+    #[error("Invalid order trigger: {0}")]
+    InvalidTrigger(String),
+    #[error("Invalid bracket: {0}")]
+    InvalidBracket(String),
+    #[error("Bracket {id} is {status:?}")]
+    BracketClosed { id: String, status: BracketStatus },
+    #[error("Order {id} is not a {expected} order")]
+    WrongType { id: String, expected: &'static str },
+    #[error("Order {id} does not belong to tenant {tenant_id}")]
+    WrongTenant { id: String, tenant_id: String },
+    #[error("Order ID {0} is taken by another tenant")]
+    IdTaken(String),
+    #[error(transparent)]
+    Risk(#[from] RiskRejected),
+    #[error(transparent)]
+    Halted(#[from] KillSwitchTripped),
+}
+
+impl DomainError for OrderError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            OrderError::NotFound(_) => ErrorKind::NotFound,
+            OrderError::InvalidFill
+            | OrderError::Overfill { .. }
+            | OrderError::InvalidAmount(_)
+            | OrderError::InvalidTrigger(_)
+            | OrderError::InvalidBracket(_)
+            | OrderError::Immutable { .. }
+            | OrderError::WrongType { .. } => ErrorKind::Invalid,
+            OrderError::NotOpen { .. }
+            | OrderError::NotAmendable { .. }
+            | OrderError::BracketClosed { .. }
+            | OrderError::FillOrKill { .. }
+            | OrderError::ConditionsNotMet(_)
+            | OrderError::IdTaken(_) => ErrorKind::Conflict,
+            OrderError::WrongTenant { .. } => ErrorKind::Forbidden,
+            OrderError::Risk(rejected) => rejected.kind(),
+            OrderError::Halted(tripped) => tripped.kind(),
+        }
+    }
+}
@@ -2,10 +2,39 @@
 //! 
 //! This module provides functionality for advanced order types including
 //! limit orders, stop-loss orders, take-profit orders, trailing stops, and more.
+//! Order creations, fills and cancellations are published as trading events on
+//! the core bus, and creations, amendments, cancellations and trade plans as
+//! audit events correlated by order ID. Open orders are amended in place,
+//! keeping their ID, fills and owner, and each amendment keeps the version it
+//! replaced.
+//! With a [`RiskEngine`] attached, every new order must pass its pre-trade checks,
+//! and no order is accepted while an attached [`KillSwitch`] is tripped.
+//! Orders armed with an [`OrderTrigger`] are only evaluated once a signal or a
+//! price of the watched symbol fires it. A [`BracketManager`] attaches
+//! take-profit and stop-loss exits to an entry order.
+
+pub mod bracket;
+pub mod error;
+pub mod sharded;
+pub mod slicing;
+pub mod trigger;
 
-use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use sniper_core::types::{TradePlan, ChainRef, ExecMode, GasPolicy, ExitRules};
+use sniper_core::audit::{self, AuditEvent, AUDIT_SUBJECT};
+use sniper_core::bus::InMemoryBus;
+use sniper_core::events::TradingEvent;
+use sniper_core::kill_switch::KillSwitch;
+use sniper_core::types::{TradePlan, ChainRef, Decimal, ExecMode, GasPolicy, ExitRules, Signal};
+use sniper_risk::{PreTradeRequest, RiskEngine};
+
+/// Share of the input a plan must receive, allowing 5% slippage
+const MIN_OUT_SHARE: Decimal = Decimal::from_raw(950_000_000_000_000_000);
+
+pub use bracket::{BracketManager, BracketOrder, BracketStatus};
+pub use error::OrderError;
+pub use sharded::ShardedOrderManager;
+pub use slicing::{ParkedSlices, SliceScheduler, SlicerConfig, VolumeProfile};
+pub use trigger::{CrossDirection, OrderTrigger};
 
 /// Order types
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -30,6 +59,21 @@ pub enum TimeInForce {
     GoodTillTime { expiry_timestamp: u64 }, // GTT
 }
 
+impl TimeInForce {
+    /// Whether the order gets a single chance to trigger
+    pub fn is_immediate(&self) -> bool {
+        matches!(self, TimeInForce::ImmediateOrCancel | TimeInForce::FillOrKill)
+    }
+
+    /// Timestamp after which the order expires, if any
+    pub fn expires_at(&self) -> Option<u64> {
+        match self {
+            TimeInForce::GoodTillTime { expiry_timestamp } => Some(*expiry_timestamp),
+            _ => None,
+        }
+    }
+}
+
 /// Advanced order
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AdvancedOrder {
@@ -38,11 +82,143 @@ pub struct AdvancedOrder {
     pub chain: ChainRef,
     pub order_type: OrderType,
     pub side: String, // "buy" or "sell"
-    pub amount: f64,
+    pub amount: Decimal,
     pub time_in_force: TimeInForce,
     pub created_at: u64,
     pub updated_at: u64,
     pub status: OrderStatus,
+    #[serde(default)]
+    pub fills: Vec<OrderFill>,
+    /// User who placed the order, if known
+    #[serde(default)]
+    pub owner_id: Option<String>,
+    /// Tenant the order was placed under, if known
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+    /// Event the order waits for while pending, if any
+    #[serde(default)]
+    pub trigger: Option<OrderTrigger>,
+}
+
+/// Execution report for part or all of an order
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OrderFill {
+    pub quantity: Decimal,
+    pub price: Decimal,
+    pub timestamp: u64,
+}
+
+/// Earlier version of an order, kept when the order is amended
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderAmendment {
+    /// The order as it was before the amendment
+    pub previous: AdvancedOrder,
+    /// User who amended the order
+    pub actor: String,
+    pub amended_at: u64,
+}
+
+impl AdvancedOrder {
+    /// Total filled quantity
+    pub fn filled_amount(&self) -> Decimal {
+        self.fills.iter().map(|fill| fill.quantity).sum()
+    }
+
+    /// Quantity still to be filled
+    pub fn remaining_amount(&self) -> Decimal {
+        self.amount
+            .checked_sub(self.filled_amount())
+            .map_or(Decimal::ZERO, |remaining| remaining.max(Decimal::ZERO))
+    }
+
+    /// Whether the order can still trigger or fill
+    pub fn is_open(&self) -> bool {
+        matches!(self.status, OrderStatus::Pending | OrderStatus::Active | OrderStatus::PartiallyFilled)
+    }
+
+    /// Whether the order is armed and still waiting for its trigger
+    pub fn awaiting_trigger(&self) -> bool {
+        self.trigger.is_some() && self.status == OrderStatus::Pending
+    }
+
+    /// Price the order is valued at before it triggers, if its type carries one
+    pub fn reference_price(&self) -> Option<f64> {
+        match self.order_type {
+            OrderType::Limit { price } | OrderType::StopLoss { price } | OrderType::TakeProfit { price } => Some(price),
+            OrderType::StopLimit { limit_price, .. } => Some(limit_price),
+            _ => None,
+        }
+    }
+
+    /// Quantity-weighted average fill price
+    pub fn avg_fill_price(&self) -> Option<Decimal> {
+        let filled = self.filled_amount();
+        if !filled.is_positive() {
+            return None;
+        }
+        self.filled_notional()?.checked_div(filled)
+    }
+
+    /// Sum of quantity times price over the fills, `None` if it overflows
+    fn filled_notional(&self) -> Option<Decimal> {
+        self.fills.iter().try_fold(Decimal::ZERO, |total, fill| {
+            total.checked_add(fill.quantity.checked_mul(fill.price)?)
+        })
+    }
+}
+
+/// Trailing stop state since activation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrailingStopState {
+    /// Highest price seen for sells, lowest for buys
+    pub extreme_price: f64,
+    /// Price at which the stop fires
+    pub trigger_price: f64,
+    pub activated_at: u64,
+    pub updated_at: u64,
+}
+
+impl TrailingStopState {
+    /// Start tracking from the first observed price
+    fn new(side: &str, trail_percent: f64, price: f64, now: u64) -> Self {
+        let mut state = Self {
+            extreme_price: price,
+            trigger_price: price,
+            activated_at: now,
+            updated_at: now,
+        };
+        state.reprice(side, trail_percent);
+        state
+    }
+
+    /// Move the extreme with the market and recalculate the trigger
+    fn observe(&mut self, side: &str, trail_percent: f64, price: f64, now: u64) {
+        self.extreme_price = if side == "buy" {
+            self.extreme_price.min(price)
+        } else {
+            self.extreme_price.max(price)
+        };
+        self.updated_at = now;
+        self.reprice(side, trail_percent);
+    }
+
+    fn reprice(&mut self, side: &str, trail_percent: f64) {
+        let trail = trail_percent / 100.0;
+        self.trigger_price = if side == "buy" {
+            self.extreme_price * (1.0 + trail)
+        } else {
+            self.extreme_price * (1.0 - trail)
+        };
+    }
+
+    /// Check if the price has retraced through the trigger
+    pub fn is_triggered(&self, side: &str, price: f64) -> bool {
+        if side == "buy" {
+            price >= self.trigger_price
+        } else {
+            price <= self.trigger_price
+        }
+    }
 }
 
 /// Order status
@@ -50,6 +226,7 @@ pub struct AdvancedOrder {
 pub enum OrderStatus {
     Pending,
     Active,
+    PartiallyFilled,
     Filled,
     Cancelled,
     Expired,
@@ -59,6 +236,13 @@ pub enum OrderStatus {
 /// Order manager for handling advanced order types
 pub struct OrderManager {
     orders: std::collections::HashMap<String, AdvancedOrder>,
+    trailing: std::collections::HashMap<String, TrailingStopState>,
+    amendments: std::collections::HashMap<String, Vec<OrderAmendment>>,
+    marks: std::collections::HashMap<String, f64>,
+    planned: std::collections::HashMap<String, usize>,
+    bus: Option<InMemoryBus>,
+    risk: Option<RiskEngine>,
+    kill_switch: Option<KillSwitch>,
 }
 
 impl OrderManager {
@@ -66,25 +250,314 @@ impl OrderManager {
     pub fn new() -> Self {
         Self {
             orders: std::collections::HashMap::new(),
+            trailing: std::collections::HashMap::new(),
+            amendments: std::collections::HashMap::new(),
+            marks: std::collections::HashMap::new(),
+            planned: std::collections::HashMap::new(),
+            bus: None,
+            risk: None,
+            kill_switch: None,
+        }
+    }
+
+    /// Publish order events on a bus
+    pub fn with_bus(mut self, bus: InMemoryBus) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Run every new order through a risk engine's pre-trade checks
+    pub fn with_risk(mut self, risk: RiskEngine) -> Self {
+        self.risk = Some(risk);
+        self
+    }
+
+    /// Refuse new orders while a kill switch is tripped
+    pub fn with_kill_switch(mut self, kill_switch: KillSwitch) -> Self {
+        self.kill_switch = Some(kill_switch);
+        self
+    }
+
+    /// Check an order against the kill switch and risk engine, if any
+    ///
+    /// Orders without a price of their own are valued at the symbol's last mark.
+    pub(crate) fn check_risk(&self, order: &AdvancedOrder) -> Result<(), OrderError> {
+        if let Some(kill_switch) = &self.kill_switch {
+            kill_switch.ensure_armed()?;
+        }
+        let Some(risk) = &self.risk else {
+            return Ok(());
+        };
+        let price = order.reference_price().or_else(|| risk.mark(&order.symbol));
+        risk.check(&PreTradeRequest {
+            symbol: order.symbol.clone(),
+            side: order.side.clone(),
+            notional: price.map(|price| price * order.amount.to_f64()),
+        })?;
+        Ok(())
+    }
+
+    /// Publish an event if a bus is attached
+    fn emit(&self, event: TradingEvent) {
+        if let Some(bus) = &self.bus {
+            if let Err(e) = bus.publish_now(event.subject(), &event) {
+                tracing::warn!("Failed to publish order event: {}", e);
+            }
+        }
+    }
+
+    /// Publish an audit event about an order if a bus is attached
+    fn audit(&self, action: &str, order: &AdvancedOrder, actor: Option<&str>, details: String) {
+        if let Some(bus) = &self.bus {
+            let event = AuditEvent::new(action, format!("orders/{}", order.id), order.id.clone())
+                .with_actor(actor)
+                .with_tenant(order.tenant_id.as_deref())
+                .with_details(details);
+            if let Err(e) = bus.publish_now(AUDIT_SUBJECT, &event) {
+                tracing::warn!("Failed to publish order audit event: {}", e);
+            }
         }
     }
 
-    /// Create a new advanced order
-    pub fn create_order(&mut self, order: AdvancedOrder) -> Result<String> {
+    /// Create a new advanced order, unless it fails the pre-trade risk checks or trading is halted
+    ///
+    /// TWAP and VWAP orders cannot carry a trigger, as they start slicing once accepted.
+    pub fn create_order(&mut self, order: AdvancedOrder) -> Result<String, OrderError> {
+        if let Some(trigger) = &order.trigger {
+            trigger.validate()?;
+            if matches!(order.order_type, OrderType::TWAP { .. } | OrderType::VWAP { .. }) {
+                return Err(OrderError::InvalidTrigger("TWAP and VWAP orders cannot be armed".to_string()));
+            }
+        }
+        self.check_risk(&order)?;
         let order_id = order.id.clone();
+        let event = TradingEvent::OrderCreated {
+            order_id: order_id.clone(),
+            symbol: order.symbol.clone(),
+            side: order.side.clone(),
+            amount: order.amount.to_f64(),
+            timestamp: order.created_at,
+        };
+        let details = format!("{} {} {} as {:?}", order.side, order.amount, order.symbol, order.order_type);
+        self.audit(audit::ORDER_CREATED, &order, order.owner_id.as_deref(), details);
+        // A replaced order starts trailing and planning afresh
+        self.trailing.remove(&order_id);
+        self.planned.remove(&order_id);
         self.orders.insert(order_id.clone(), order);
+        self.emit(event);
         Ok(order_id)
     }
 
+    /// Amend the terms of an open order on behalf of a user, returning the version it replaced
+    ///
+    /// The order keeps its ID, creation time, owner, tenant, status and fills;
+    /// its type, side, amount and time in force are taken from `amended`. The
+    /// symbol and chain cannot change, and the amount cannot drop to or below
+    /// what is already filled. The amended order must pass the pre-trade risk
+    /// checks again.
+    pub fn amend_order(&mut self, amended: AdvancedOrder, actor: &str) -> Result<AdvancedOrder, OrderError> {
+        let previous = self.orders.get(&amended.id).ok_or_else(|| OrderError::NotFound(amended.id.clone()))?;
+        if !previous.is_open() {
+            return Err(OrderError::NotAmendable {
+                id: previous.id.clone(),
+                status: previous.status.clone(),
+            });
+        }
+        if amended.symbol != previous.symbol {
+            return Err(OrderError::Immutable { id: previous.id.clone(), field: "symbol" });
+        }
+        if amended.chain.id != previous.chain.id || amended.chain.name != previous.chain.name {
+            return Err(OrderError::Immutable { id: previous.id.clone(), field: "chain" });
+        }
+        let filled = previous.filled_amount();
+        if amended.amount <= filled {
+            return Err(OrderError::InvalidAmount(format!(
+                "{} does not exceed the {} already filled",
+                amended.amount, filled
+            )));
+        }
+
+        let order = AdvancedOrder {
+            id: previous.id.clone(),
+            symbol: previous.symbol.clone(),
+            chain: previous.chain.clone(),
+            order_type: amended.order_type,
+            side: amended.side,
+            amount: amended.amount,
+            time_in_force: amended.time_in_force,
+            created_at: previous.created_at,
+            updated_at: chrono::Utc::now().timestamp() as u64,
+            status: previous.status.clone(),
+            fills: previous.fills.clone(),
+            owner_id: previous.owner_id.clone(),
+            tenant_id: previous.tenant_id.clone(),
+            trigger: previous.trigger.clone(),
+        };
+        self.check_risk(&order)?;
+
+        let previous = self.orders.insert(order.id.clone(), order.clone()).expect("order was looked up above");
+        if order.order_type != previous.order_type || order.side != previous.side {
+            // A trailing stop tracks afresh from its new terms
+            self.trailing.remove(&order.id);
+        }
+        let details = format!(
+            "{} {} {} as {:?}, was {} {} as {:?}",
+            order.side, order.amount, order.symbol, order.order_type, previous.side, previous.amount, previous.order_type
+        );
+        self.audit(audit::ORDER_AMENDED, &order, Some(actor), details);
+        self.amendments.entry(order.id.clone()).or_default().push(OrderAmendment {
+            previous: previous.clone(),
+            actor: actor.to_string(),
+            amended_at: order.updated_at,
+        });
+        Ok(previous)
+    }
+
+    /// Earlier versions of an order, oldest first
+    pub fn amendments(&self, order_id: &str) -> &[OrderAmendment] {
+        self.amendments.get(order_id).map(Vec::as_slice).unwrap_or_default()
+    }
+
     /// Cancel an order
-    pub fn cancel_order(&mut self, order_id: &str) -> Result<()> {
+    pub fn cancel_order(&mut self, order_id: &str) -> Result<(), OrderError> {
+        self.cancel_order_by(order_id, audit::SYSTEM_ACTOR)
+    }
+
+    /// Cancel an order on behalf of a user, recording them in the audit event
+    pub fn cancel_order_by(&mut self, order_id: &str, actor: &str) -> Result<(), OrderError> {
         if let Some(order) = self.orders.get_mut(order_id) {
             order.status = OrderStatus::Cancelled;
             order.updated_at = chrono::Utc::now().timestamp() as u64;
+            let order = order.clone();
+            self.trailing.remove(order_id);
+            let details = format!("cancelled with {} of {} filled", order.filled_amount(), order.amount);
+            self.audit(audit::ORDER_CANCELLED, &order, Some(actor), details);
+            self.emit(TradingEvent::OrderCancelled {
+                order_id: order.id.clone(),
+                symbol: order.symbol.clone(),
+                timestamp: order.updated_at,
+            });
             Ok(())
         } else {
-            Err(anyhow::anyhow!("Order not found"))
+            Err(OrderError::NotFound(order_id.to_string()))
+        }
+    }
+
+    /// Cancel several orders together on behalf of a user, returning the IDs of those that were open
+    ///
+    /// Nothing is cancelled unless every order exists.
+    pub fn cancel_orders_by(&mut self, order_ids: &[String], actor: &str) -> Result<Vec<String>, OrderError> {
+        if let Some(missing) = order_ids.iter().find(|order_id| !self.orders.contains_key(*order_id)) {
+            return Err(OrderError::NotFound(missing.clone()));
+        }
+        let open: Vec<String> = order_ids
+            .iter()
+            .filter(|order_id| self.orders.get(*order_id).map(AdvancedOrder::is_open).unwrap_or(false))
+            .cloned()
+            .collect();
+        for order_id in &open {
+            self.cancel_order_by(order_id, actor)?;
+        }
+        Ok(open)
+    }
+
+    /// Record an execution report, moving the order to PartiallyFilled or Filled
+    pub fn record_fill(&mut self, order_id: &str, quantity: Decimal, price: Decimal) -> Result<AdvancedOrder, OrderError> {
+        let order = self.orders.get_mut(order_id).ok_or_else(|| OrderError::NotFound(order_id.to_string()))?;
+        
+        if !order.is_open() {
+            return Err(OrderError::NotOpen {
+                id: order.id.clone(),
+                status: order.status.clone(),
+            });
+        }
+        if !quantity.is_positive() || !price.is_positive() {
+            return Err(OrderError::InvalidFill);
+        }
+        let remaining = order.remaining_amount();
+        if quantity > remaining {
+            return Err(OrderError::Overfill { quantity, remaining });
+        }
+        // The average fill price is taken over the fills' total notional
+        let notional = quantity.checked_mul(price).and_then(|fill| order.filled_notional()?.checked_add(fill));
+        if notional.is_none() {
+            return Err(OrderError::InvalidAmount(format!(
+                "fill of {} at {} is out of the decimal range",
+                quantity, price
+            )));
+        }
+        
+        let now = chrono::Utc::now().timestamp() as u64;
+        if order.time_in_force == TimeInForce::FillOrKill && quantity < remaining {
+            // Fill-or-kill orders are killed rather than partially filled
+            order.status = OrderStatus::Rejected;
+            order.updated_at = now;
+            return Err(OrderError::FillOrKill { quantity, remaining });
+        }
+        
+        order.fills.push(OrderFill {
+            quantity,
+            price,
+            timestamp: now,
+        });
+        order.status = if order.remaining_amount().is_zero() {
+            OrderStatus::Filled
+        } else if order.time_in_force == TimeInForce::ImmediateOrCancel {
+            // The unfilled remainder of an IOC order is cancelled
+            OrderStatus::Cancelled
+        } else {
+            OrderStatus::PartiallyFilled
+        };
+        order.updated_at = now;
+        let order = order.clone();
+        self.emit(TradingEvent::OrderFilled {
+            order_id: order.id.clone(),
+            symbol: order.symbol.clone(),
+            quantity: quantity.to_f64(),
+            price: price.to_f64(),
+            complete: order.status == OrderStatus::Filled,
+            timestamp: now,
+        });
+        Ok(order)
+    }
+
+    /// Remove an order
+    pub fn remove_order(&mut self, order_id: &str) -> Option<AdvancedOrder> {
+        self.trailing.remove(order_id);
+        self.planned.remove(order_id);
+        self.amendments.remove(order_id);
+        self.orders.remove(order_id)
+    }
+
+    /// Put back an order restored from a backup, skipping the risk checks and events of `create_order`
+    pub fn restore_order(&mut self, order: AdvancedOrder) {
+        self.trailing.remove(&order.id);
+        self.planned.remove(&order.id);
+        self.orders.insert(order.id.clone(), order);
+    }
+
+    /// Expire open Good-Till-Time orders whose expiry has passed, returning their IDs
+    pub fn tick(&mut self, now: u64) -> Vec<String> {
+        let mut expired = Vec::new();
+        for order in self.orders.values_mut() {
+            if !order.is_open() {
+                continue;
+            }
+            if order.time_in_force.expires_at().map(|expiry| expiry <= now).unwrap_or(false) {
+                order.status = OrderStatus::Expired;
+                order.updated_at = now;
+                expired.push(order.id.clone());
+            }
         }
+        for order_id in &expired {
+            self.trailing.remove(order_id);
+        }
+        expired
+    }
+
+    /// Get the trailing stop state of an order, once it has seen a price
+    pub fn trailing_state(&self, order_id: &str) -> Option<&TrailingStopState> {
+        self.trailing.get(order_id)
     }
 
     /// Get an order by ID
@@ -103,29 +576,31 @@ impl OrderManager {
     }
 
     /// Convert an advanced order to a trade plan
-    pub fn to_trade_plan(&self, order_id: &str, current_price: f64) -> Result<TradePlan> {
-        let order = self.get_order(order_id).ok_or_else(|| anyhow::anyhow!("Order not found"))?;
+    ///
+    /// The plan's idempotency key is derived from the order ID and its fill
+    /// count, so the same unfilled remainder is always planned under the same key.
+    pub fn to_trade_plan(&self, order_id: &str, current_price: f64) -> Result<TradePlan, OrderError> {
+        let order = self.get_order(order_id).ok_or_else(|| OrderError::NotFound(order_id.to_string()))?;
         
         // Check if order should be executed based on order type and current price
-        if !self.should_execute_order(order, current_price)? {
-            return Err(anyhow::anyhow!("Order conditions not met"));
+        if !self.should_execute_order(order, current_price) {
+            return Err(OrderError::ConditionsNotMet(order.id.clone()));
         }
         
-        // Convert to trade plan
-        let amount_in = (order.amount * 1e18) as u128; // Convert to wei
-        let min_out = match &order.order_type {
-            OrderType::Market => (order.amount * 0.95 * 1e18) as u128, // 5% slippage
-            OrderType::Limit { .. } => (order.amount * 0.95 * 1e18) as u128, // 5% slippage
-            OrderType::StopLoss { .. } => (order.amount * 0.95 * 1e18) as u128, // 5% slippage
-            OrderType::TakeProfit { .. } => (order.amount * 0.95 * 1e18) as u128, // 5% slippage
-            OrderType::StopLimit { .. } => (order.amount * 0.95 * 1e18) as u128, // 5% slippage
-            OrderType::TrailingStop { .. } => (order.amount * 0.95 * 1e18) as u128, // 5% slippage
-            OrderType::Iceberg { visible_amount, .. } => (visible_amount * 0.95 * 1e18) as u128, // 5% slippage
-            OrderType::TWAP { .. } => (order.amount * 0.95 * 1e18) as u128, // 5% slippage
-            OrderType::VWAP { .. } => (order.amount * 0.95 * 1e18) as u128, // 5% slippage
+        // Convert to trade plan for the unfilled remainder
+        let amount = order.remaining_amount();
+        let amount_in = amount
+            .to_wei()
+            .ok_or_else(|| OrderError::InvalidAmount(format!("{} cannot be converted to wei", amount)))?;
+        // Icebergs only expose their visible slice to the market
+        let out_amount = match &order.order_type {
+            OrderType::Iceberg { visible_amount, .. } => Decimal::from_f64(*visible_amount)
+                .ok_or_else(|| OrderError::InvalidAmount(format!("visible amount {}", visible_amount)))?,
+            _ => amount,
         };
+        let min_out = (out_amount * MIN_OUT_SHARE).to_wei().unwrap_or(0);
         
-        Ok(TradePlan {
+        let plan = TradePlan {
             chain: order.chain.clone(),
             router: "0xRouter".to_string(),
             token_in: "0xTokenIn".to_string(),
@@ -142,39 +617,189 @@ impl OrderManager {
                 stop_loss_pct: Some(5.0),
                 trailing_pct: Some(2.0),
             },
-            idem_key: format!("order-{}", uuid::Uuid::new_v4()),
-        })
+            idem_key: format!("order-{}-{}", order.id, order.fills.len()),
+            quote: None,
+            correlation_id: Some(order.id.clone()),
+        };
+        Ok(plan)
+    }
+
+    /// Plan a triggered order for execution, once per fill count
+    ///
+    /// Returns `None` if the order's unfilled remainder was already planned;
+    /// a fill lets the next remainder be planned. Each plan taken is audited.
+    pub fn take_trade_plan(&mut self, order_id: &str, current_price: f64) -> Result<Option<TradePlan>, OrderError> {
+        let order = self.get_order(order_id).ok_or_else(|| OrderError::NotFound(order_id.to_string()))?;
+        let fills = order.fills.len();
+        if self.planned.get(order_id) == Some(&fills) {
+            return Ok(None);
+        }
+        let plan = self.to_trade_plan(order_id, current_price)?;
+        let details = format!("plan {} for {} at price {}", plan.idem_key, order.remaining_amount(), current_price);
+        self.audit(audit::TRADE_PLANNED, order, None, details);
+        self.planned.insert(order_id.to_string(), fills);
+        Ok(Some(plan))
+    }
+
+    /// Evaluate open orders in a symbol against a price, returning plans for triggered orders
+    pub fn evaluate_price(&self, symbol: &str, current_price: f64) -> Vec<TradePlan> {
+        self.orders
+            .values()
+            .filter(|order| order.symbol == symbol)
+            .filter(|order| order.is_open())
+            .filter_map(|order| self.to_trade_plan(&order.id, current_price).ok())
+            .collect()
+    }
+
+    /// Record a market price, moving trailing stops with it, then evaluate the symbol's orders
+    ///
+    /// Orders armed on the price are activated first. IOC and FOK orders that
+    /// do not trigger at this price are rejected, unless still armed.
+    pub fn update_market_price(&mut self, symbol: &str, price: f64) -> Vec<TradePlan> {
+        let now = chrono::Utc::now().timestamp() as u64;
+        if let Some(risk) = &self.risk {
+            risk.record_price(symbol, price);
+        }
+        self.marks.insert(symbol.to_string(), price);
+        let activated = self.activate(|trigger| trigger.fired_by_price(symbol, price), &format!("{} at {}", symbol, price));
+        // Orders in the symbol itself are evaluated with the rest below
+        let elsewhere: Vec<String> = activated
+            .into_iter()
+            .filter(|order_id| self.orders.get(order_id).map(|order| order.symbol != symbol).unwrap_or(false))
+            .collect();
+        let mut plans = self.plan_activated(&elsewhere);
+        
+        for order in self.orders.values() {
+            if order.symbol != symbol || !order.is_open() || order.awaiting_trigger() {
+                continue;
+            }
+            if let OrderType::TrailingStop { trail_percent } = order.order_type {
+                self.trailing
+                    .entry(order.id.clone())
+                    .and_modify(|state| state.observe(&order.side, trail_percent, price, now))
+                    .or_insert_with(|| TrailingStopState::new(&order.side, trail_percent, price, now));
+            }
+        }
+
+        let open: Vec<(String, bool)> = self
+            .orders
+            .values()
+            .filter(|order| order.symbol == symbol && order.is_open() && !order.awaiting_trigger())
+            .map(|order| (order.id.clone(), order.time_in_force.is_immediate() && order.fills.is_empty()))
+            .collect();
+        let mut rejected = Vec::new();
+        for (order_id, immediate) in open {
+            match self.take_trade_plan(&order_id, price) {
+                Ok(plan) => plans.extend(plan),
+                // IOC and FOK orders that cannot trigger on the first price they see are rejected
+                Err(_) if immediate => rejected.push(order_id),
+                Err(_) => {}
+            }
+        }
+        for order_id in rejected {
+            if let Some(order) = self.orders.get_mut(&order_id) {
+                order.status = OrderStatus::Rejected;
+                order.updated_at = now;
+            }
+            self.trailing.remove(&order_id);
+        }
+
+        plans
+    }
+
+    /// Activate the armed orders whose trigger fires, returning their IDs
+    fn activate(&mut self, fires: impl Fn(&OrderTrigger) -> bool, cause: &str) -> Vec<String> {
+        let now = chrono::Utc::now().timestamp() as u64;
+        let mut activated = Vec::new();
+        for order in self.orders.values_mut() {
+            if order.awaiting_trigger() && order.trigger.as_ref().map(&fires).unwrap_or(false) {
+                order.status = OrderStatus::Active;
+                order.updated_at = now;
+                activated.push(order.id.clone());
+            }
+        }
+        for order_id in &activated {
+            if let Some(order) = self.orders.get(order_id) {
+                self.audit(audit::ORDER_TRIGGERED, order, None, cause.to_string());
+            }
+        }
+        activated
+    }
+
+    /// Evaluate just activated orders at the last price seen for their symbol
+    ///
+    /// Orders in a symbol without a price yet are evaluated on its first price update.
+    fn plan_activated(&mut self, activated: &[String]) -> Vec<TradePlan> {
+        let mut plans = Vec::new();
+        for order_id in activated {
+            let Some(order) = self.orders.get(order_id) else {
+                continue;
+            };
+            let Some(price) = self.marks.get(&order.symbol).copied() else {
+                continue;
+            };
+            if let Ok(Some(plan)) = self.take_trade_plan(order_id, price) {
+                plans.push(plan);
+            }
+        }
+        plans
+    }
+
+    /// Activate orders armed on a matching signal, returning plans for those that execute at once
+    pub fn on_signal(&mut self, signal: &Signal) -> Vec<TradePlan> {
+        let cause = format!("signal {}.{} on chain {}", signal.source, signal.kind, signal.chain.id);
+        let activated = self.activate(|trigger| trigger.fired_by_signal(signal), &cause);
+        self.plan_activated(&activated)
+    }
+
+    /// Activate orders armed on a price of another symbol than their own,
+    /// returning plans for those that execute at once
+    pub fn on_watched_price(&mut self, symbol: &str, price: f64) -> Vec<TradePlan> {
+        let cause = format!("{} at {}", symbol, price);
+        let activated = self.activate(|trigger| trigger.fired_by_price(symbol, price), &cause);
+        self.plan_activated(&activated)
     }
 
     /// Check if an order should be executed based on current price
-    fn should_execute_order(&self, order: &AdvancedOrder, current_price: f64) -> Result<bool> {
+    fn should_execute_order(&self, order: &AdvancedOrder, current_price: f64) -> bool {
+        // Armed orders wait for their trigger
+        if order.awaiting_trigger() {
+            return false;
+        }
+        
+        // Expired Good-Till-Time orders never trigger, even before the next sweep
+        let now = chrono::Utc::now().timestamp() as u64;
+        if order.time_in_force.expires_at().map(|expiry| expiry <= now).unwrap_or(false) {
+            return false;
+        }
+        
         match &order.order_type {
-            OrderType::Market => Ok(true), // Always execute market orders
+            OrderType::Market => true, // Always execute market orders
             OrderType::Limit { price } => {
                 // Buy limit: current price <= limit price
                 // Sell limit: current price >= limit price
                 if order.side == "buy" {
-                    Ok(current_price <= *price)
+                    current_price <= *price
                 } else {
-                    Ok(current_price >= *price)
+                    current_price >= *price
                 }
             }
             OrderType::StopLoss { price } => {
                 // Buy stop-loss: current price >= stop price
                 // Sell stop-loss: current price <= stop price
                 if order.side == "buy" {
-                    Ok(current_price >= *price)
+                    current_price >= *price
                 } else {
-                    Ok(current_price <= *price)
+                    current_price <= *price
                 }
             }
             OrderType::TakeProfit { price } => {
                 // Buy take-profit: current price >= take profit price
                 // Sell take-profit: current price <= take profit price
                 if order.side == "buy" {
-                    Ok(current_price >= *price)
+                    current_price >= *price
                 } else {
-                    Ok(current_price <= *price)
+                    current_price <= *price
                 }
             }
             OrderType::StopLimit { stop_price, limit_price } => {
@@ -187,20 +812,22 @@ impl OrderManager {
                 
                 if stop_hit {
                     if order.side == "buy" {
-                        Ok(current_price <= *limit_price)
+                        current_price <= *limit_price
                     } else {
-                        Ok(current_price >= *limit_price)
+                        current_price >= *limit_price
                     }
                 } else {
-                    Ok(false)
+                    false
                 }
             }
-            OrderType::TrailingStop { trail_percent } => {
-                // For trailing stops, we would normally track the highest/lowest price
-                // For simplicity, we'll execute if price moved by trail_percent
-                Ok(current_price.abs() >= *trail_percent)
+            OrderType::TrailingStop { .. } => {
+                // Trailing stops only fire once market prices have set a trigger level
+                self.trailing
+                    .get(&order.id)
+                    .map(|state| state.is_triggered(&order.side, current_price))
+                    .unwrap_or(false)
             }
-            _ => Ok(true), // For other order types, execute for now
+            _ => true, // For other order types, execute for now
         }
     }
 }
@@ -208,8 +835,13 @@ impl OrderManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sniper_core::errors::{DomainError, ErrorKind};
     use sniper_core::types::ChainRef;
 
+    fn dec(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
     #[test]
     fn test_order_manager_creation() {
         let order_manager = OrderManager::new();
@@ -229,11 +861,15 @@ mod tests {
             },
             order_type: OrderType::Market,
             side: "buy".to_string(),
-            amount: 1.0,
+            amount: Decimal::from(1),
             time_in_force: TimeInForce::GoodTillCancelled,
             created_at: 1234567890,
             updated_at: 1234567890,
             status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
         };
         
         let result = order_manager.create_order(order);
@@ -254,11 +890,15 @@ mod tests {
             },
             order_type: OrderType::Market,
             side: "buy".to_string(),
-            amount: 1.0,
+            amount: Decimal::from(1),
             time_in_force: TimeInForce::GoodTillCancelled,
             created_at: 1234567890,
             updated_at: 1234567890,
             status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
         };
         
         order_manager.create_order(order).unwrap();
@@ -284,11 +924,15 @@ mod tests {
             },
             order_type: OrderType::Market,
             side: "buy".to_string(),
-            amount: 1.0,
+            amount: Decimal::from(1),
             time_in_force: TimeInForce::GoodTillCancelled,
             created_at: 1234567890,
             updated_at: 1234567890,
             status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
         };
         
         order_manager.create_order(order).unwrap();
@@ -311,11 +955,15 @@ mod tests {
             },
             order_type: OrderType::Market,
             side: "buy".to_string(),
-            amount: 1.0,
+            amount: Decimal::from(1),
             time_in_force: TimeInForce::GoodTillCancelled,
             created_at: 1234567890,
             updated_at: 1234567890,
             status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
         };
         
         let order2 = AdvancedOrder {
@@ -327,11 +975,15 @@ mod tests {
             },
             order_type: OrderType::Limit { price: 3000.0 },
             side: "sell".to_string(),
-            amount: 2.0,
+            amount: Decimal::from(2),
             time_in_force: TimeInForce::GoodTillCancelled,
             created_at: 1234567890,
             updated_at: 1234567890,
             status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
         };
         
         order_manager.create_order(order1).unwrap();
@@ -354,11 +1006,15 @@ mod tests {
             },
             order_type: OrderType::Market,
             side: "buy".to_string(),
-            amount: 1.0,
+            amount: Decimal::from(1),
             time_in_force: TimeInForce::GoodTillCancelled,
             created_at: 1234567890,
             updated_at: 1234567890,
             status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
         };
         
         let order2 = AdvancedOrder {
@@ -370,11 +1026,15 @@ mod tests {
             },
             order_type: OrderType::Limit { price: 3000.0 },
             side: "sell".to_string(),
-            amount: 2.0,
+            amount: Decimal::from(2),
             time_in_force: TimeInForce::GoodTillCancelled,
             created_at: 1234567890,
             updated_at: 1234567890,
             status: OrderStatus::Active,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
         };
         
         order_manager.create_order(order1).unwrap();
@@ -403,14 +1063,18 @@ mod tests {
             },
             order_type: OrderType::Market,
             side: "buy".to_string(),
-            amount: 1.0,
+            amount: Decimal::from(1),
             time_in_force: TimeInForce::GoodTillCancelled,
             created_at: 1234567890,
             updated_at: 1234567890,
             status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
         };
         
-        let should_execute = order_manager.should_execute_order(&market_order, 50000.0).unwrap();
+        let should_execute = order_manager.should_execute_order(&market_order, 50000.0);
         assert!(should_execute);
         
         // Test buy limit order - should execute when current price <= limit price
@@ -423,19 +1087,23 @@ mod tests {
             },
             order_type: OrderType::Limit { price: 49000.0 },
             side: "buy".to_string(),
-            amount: 1.0,
+            amount: Decimal::from(1),
             time_in_force: TimeInForce::GoodTillCancelled,
             created_at: 1234567890,
             updated_at: 1234567890,
             status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
         };
         
         // Current price is higher than limit - should not execute
-        let should_execute = order_manager.should_execute_order(&limit_order, 50000.0).unwrap();
+        let should_execute = order_manager.should_execute_order(&limit_order, 50000.0);
         assert!(!should_execute);
         
         // Current price is lower than limit - should execute
-        let should_execute = order_manager.should_execute_order(&limit_order, 48000.0).unwrap();
+        let should_execute = order_manager.should_execute_order(&limit_order, 48000.0);
         assert!(should_execute);
         
         // Test sell limit order - should execute when current price >= limit price
@@ -448,19 +1116,23 @@ mod tests {
             },
             order_type: OrderType::Limit { price: 51000.0 },
             side: "sell".to_string(),
-            amount: 1.0,
+            amount: Decimal::from(1),
             time_in_force: TimeInForce::GoodTillCancelled,
             created_at: 1234567890,
             updated_at: 1234567890,
             status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
         };
         
         // Current price is lower than limit - should not execute
-        let should_execute = order_manager.should_execute_order(&sell_limit_order, 50000.0).unwrap();
+        let should_execute = order_manager.should_execute_order(&sell_limit_order, 50000.0);
         assert!(!should_execute);
         
         // Current price is higher than limit - should execute
-        let should_execute = order_manager.should_execute_order(&sell_limit_order, 52000.0).unwrap();
+        let should_execute = order_manager.should_execute_order(&sell_limit_order, 52000.0);
         assert!(should_execute);
     }
 
@@ -477,11 +1149,15 @@ mod tests {
             },
             order_type: OrderType::Market,
             side: "buy".to_string(),
-            amount: 1.0,
+            amount: Decimal::from(1),
             time_in_force: TimeInForce::GoodTillCancelled,
             created_at: 1234567890,
             updated_at: 1234567890,
             status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
         };
         
         order_manager.create_order(order).unwrap();
@@ -496,4 +1172,736 @@ mod tests {
         // But we're dealing with token_out amount, so it should be 1 * 0.95 * 1e18 = 950000000000000000
         assert_eq!(plan.min_out, 950000000000000000); // 1 * 0.95 * 1e18
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_record_fill() {
+        let mut order_manager = OrderManager::new();
+        
+        let order = AdvancedOrder {
+            id: "order-1".to_string(),
+            symbol: "BTC/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type: OrderType::Limit { price: 50000.0 },
+            side: "buy".to_string(),
+            amount: Decimal::from(2),
+            time_in_force: TimeInForce::GoodTillCancelled,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            status: OrderStatus::Active,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
+        };
+        
+        order_manager.create_order(order).unwrap();
+        
+        let order = order_manager.record_fill("order-1", dec("0.5"), Decimal::from(49000)).unwrap();
+        assert_eq!(order.status, OrderStatus::PartiallyFilled);
+        assert_eq!(order.remaining_amount(), dec("1.5"));
+        
+        // The trade plan only covers the unfilled remainder
+        let plan = order_manager.to_trade_plan("order-1", 48000.0).unwrap();
+        assert_eq!(plan.amount_in, 1500000000000000000);
+        
+        let err = order_manager.record_fill("order-1", Decimal::from(2), Decimal::from(49000)).unwrap_err();
+        assert!(matches!(err, OrderError::Overfill { .. }));
+        assert_eq!(err.kind(), ErrorKind::Invalid);
+        
+        // A price whose notional overflows is rejected rather than panicking
+        let err = order_manager.record_fill("order-1", dec("1.5"), Decimal::MAX).unwrap_err();
+        assert!(matches!(err, OrderError::InvalidAmount(_)));
+        
+        let order = order_manager.record_fill("order-1", dec("1.5"), Decimal::from(49400)).unwrap();
+        assert_eq!(order.status, OrderStatus::Filled);
+        assert_eq!(order.filled_amount(), Decimal::from(2));
+        assert_eq!(order.avg_fill_price(), Some(Decimal::from(49300)));
+        
+        // Filled orders accept no further fills
+        let err = order_manager.record_fill("order-1", dec("0.1"), Decimal::from(49000)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Conflict);
+        let err = order_manager.record_fill("order-2", dec("0.1"), Decimal::from(49000)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_amend_order() {
+        let mut order_manager = OrderManager::new();
+        
+        let order = AdvancedOrder {
+            id: "order-1".to_string(),
+            symbol: "BTC/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type: OrderType::Limit { price: 50000.0 },
+            side: "buy".to_string(),
+            amount: Decimal::from(2),
+            time_in_force: TimeInForce::GoodTillCancelled,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            status: OrderStatus::Active,
+            fills: Vec::new(),
+            owner_id: Some("alice".to_string()),
+            tenant_id: Some("tenant-1".to_string()),
+            trigger: None,
+        };
+        order_manager.create_order(order.clone()).unwrap();
+        order_manager.record_fill("order-1", dec("0.5"), Decimal::from(49000)).unwrap();
+        
+        // Identity, status and fills survive the amendment
+        let amended = AdvancedOrder {
+            order_type: OrderType::Limit { price: 48000.0 },
+            amount: Decimal::from(3),
+            created_at: 0,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            ..order.clone()
+        };
+        let previous = order_manager.amend_order(amended, "alice").unwrap();
+        assert_eq!(previous.order_type, OrderType::Limit { price: 50000.0 });
+        assert_eq!(previous.amount, Decimal::from(2));
+        let current = order_manager.get_order("order-1").unwrap();
+        assert_eq!(current.order_type, OrderType::Limit { price: 48000.0 });
+        assert_eq!(current.created_at, 1234567890);
+        assert_eq!(current.status, OrderStatus::PartiallyFilled);
+        assert_eq!(current.filled_amount(), dec("0.5"));
+        assert_eq!(current.owner_id.as_deref(), Some("alice"));
+        
+        let history = order_manager.amendments("order-1");
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].actor, "alice");
+        assert_eq!(history[0].previous.amount, Decimal::from(2));
+        
+        let err = order_manager.amend_order(AdvancedOrder { symbol: "ETH/USDT".to_string(), ..order.clone() }, "alice").unwrap_err();
+        assert!(matches!(err, OrderError::Immutable { field: "symbol", .. }));
+        let err = order_manager.amend_order(AdvancedOrder { amount: dec("0.5"), ..order.clone() }, "alice").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Invalid);
+        
+        // Filled orders are final
+        order_manager.record_fill("order-1", dec("2.5"), Decimal::from(48000)).unwrap();
+        let err = order_manager.amend_order(AdvancedOrder { amount: Decimal::from(4), ..order.clone() }, "alice").unwrap_err();
+        assert!(matches!(err, OrderError::NotAmendable { status: OrderStatus::Filled, .. }));
+        assert_eq!(err.kind(), ErrorKind::Conflict);
+        assert_eq!(order_manager.amendments("order-1").len(), 1);
+        
+        let err = order_manager.amend_order(AdvancedOrder { id: "order-2".to_string(), ..order }, "alice").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_trailing_stop_high_water_mark() {
+        let mut order_manager = OrderManager::new();
+        
+        let order = AdvancedOrder {
+            id: "order-1".to_string(),
+            symbol: "ETH/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type: OrderType::TrailingStop { trail_percent: 5.0 },
+            side: "sell".to_string(),
+            amount: Decimal::from(1),
+            time_in_force: TimeInForce::GoodTillCancelled,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
+        };
+        
+        order_manager.create_order(order).unwrap();
+        
+        // Not triggered before any price has been observed
+        assert!(order_manager.to_trade_plan("order-1", 1.0).is_err());
+        
+        // The high-water mark rises with the market
+        assert!(order_manager.update_market_price("ETH/USDT", 2000.0).is_empty());
+        assert!(order_manager.update_market_price("ETH/USDT", 2200.0).is_empty());
+        let state = order_manager.trailing_state("order-1").unwrap();
+        assert_eq!(state.extreme_price, 2200.0);
+        assert!((state.trigger_price - 2090.0).abs() < 1e-9);
+        
+        // Falling back does not lower the trigger
+        assert!(order_manager.update_market_price("ETH/USDT", 2100.0).is_empty());
+        assert_eq!(order_manager.trailing_state("order-1").unwrap().extreme_price, 2200.0);
+        
+        // Retracing 5% from the high fires the stop
+        assert_eq!(order_manager.update_market_price("ETH/USDT", 2085.0).len(), 1);
+    }
+
+    #[test]
+    fn test_triggered_order_planned_once_per_fill() {
+        let mut order_manager = OrderManager::new();
+        
+        let order = AdvancedOrder {
+            id: "order-1".to_string(),
+            symbol: "ETH/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type: OrderType::Limit { price: 3000.0 },
+            side: "buy".to_string(),
+            amount: Decimal::from(2),
+            time_in_force: TimeInForce::GoodTillCancelled,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
+        };
+        
+        order_manager.create_order(order).unwrap();
+        
+        // Planned on the tick it triggers, and not again while the plan is in flight
+        let plans = order_manager.update_market_price("ETH/USDT", 2900.0);
+        assert_eq!(plans.len(), 1);
+        assert_eq!(plans[0].idem_key, "order-order-1-0");
+        assert!(order_manager.update_market_price("ETH/USDT", 2800.0).is_empty());
+        assert_eq!(order_manager.to_trade_plan("order-1", 2800.0).unwrap().idem_key, "order-order-1-0");
+        
+        // A partial fill lets the remainder be planned under a new key
+        order_manager.record_fill("order-1", Decimal::from(1), Decimal::from(2900)).unwrap();
+        let plans = order_manager.update_market_price("ETH/USDT", 2800.0);
+        assert_eq!(plans.len(), 1);
+        assert_eq!(plans[0].idem_key, "order-order-1-1");
+        assert!(order_manager.update_market_price("ETH/USDT", 2700.0).is_empty());
+    }
+
+    #[test]
+    fn test_trailing_stop_buy_tracks_low() {
+        let mut order_manager = OrderManager::new();
+        
+        let order = AdvancedOrder {
+            id: "order-1".to_string(),
+            symbol: "ETH/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type: OrderType::TrailingStop { trail_percent: 10.0 },
+            side: "buy".to_string(),
+            amount: Decimal::from(1),
+            time_in_force: TimeInForce::GoodTillCancelled,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
+        };
+        
+        order_manager.create_order(order).unwrap();
+        
+        assert!(order_manager.update_market_price("ETH/USDT", 2000.0).is_empty());
+        assert!(order_manager.update_market_price("ETH/USDT", 1500.0).is_empty());
+        assert!(order_manager.update_market_price("ETH/USDT", 1600.0).is_empty());
+        assert!((order_manager.trailing_state("order-1").unwrap().trigger_price - 1650.0).abs() < 1e-9);
+        assert_eq!(order_manager.update_market_price("ETH/USDT", 1660.0).len(), 1);
+        
+        // Cancelling drops the tracked state
+        order_manager.cancel_order("order-1").unwrap();
+        assert!(order_manager.trailing_state("order-1").is_none());
+    }
+
+    #[test]
+    fn test_good_till_time_expiry() {
+        let mut order_manager = OrderManager::new();
+        
+        let gtt_order = AdvancedOrder {
+            id: "order-1".to_string(),
+            symbol: "BTC/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type: OrderType::Limit { price: 49000.0 },
+            side: "buy".to_string(),
+            amount: Decimal::from(1),
+            time_in_force: TimeInForce::GoodTillTime { expiry_timestamp: 1234568000 },
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
+        };
+        let gtc_order = AdvancedOrder {
+            id: "order-2".to_string(),
+            symbol: "BTC/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type: OrderType::Limit { price: 49000.0 },
+            side: "buy".to_string(),
+            amount: Decimal::from(1),
+            time_in_force: TimeInForce::GoodTillCancelled,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
+        };
+        
+        order_manager.create_order(gtt_order).unwrap();
+        order_manager.create_order(gtc_order).unwrap();
+        
+        // Nothing expires before the expiry timestamp
+        assert!(order_manager.tick(1234567999).is_empty());
+        
+        // GTT orders expire, GTC orders stay open
+        assert_eq!(order_manager.tick(1234568000), vec!["order-1".to_string()]);
+        assert_eq!(order_manager.get_order("order-1").unwrap().status, OrderStatus::Expired);
+        assert_eq!(order_manager.get_order("order-2").unwrap().status, OrderStatus::Pending);
+        assert!(order_manager.tick(1234569000).is_empty());
+        
+        // Expired orders no longer trigger
+        assert_eq!(order_manager.update_market_price("BTC/USDT", 48000.0).len(), 1);
+    }
+
+    #[test]
+    fn test_immediate_or_cancel() {
+        let mut order_manager = OrderManager::new();
+        
+        let ioc_order = AdvancedOrder {
+            id: "order-1".to_string(),
+            symbol: "BTC/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type: OrderType::Limit { price: 49000.0 },
+            side: "buy".to_string(),
+            amount: Decimal::from(1),
+            time_in_force: TimeInForce::ImmediateOrCancel,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
+        };
+        let unfilled_ioc_order = AdvancedOrder {
+            id: "order-2".to_string(),
+            symbol: "BTC/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type: OrderType::Limit { price: 47000.0 },
+            side: "buy".to_string(),
+            amount: Decimal::from(1),
+            time_in_force: TimeInForce::ImmediateOrCancel,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
+        };
+        
+        order_manager.create_order(ioc_order).unwrap();
+        order_manager.create_order(unfilled_ioc_order).unwrap();
+        
+        // Only the marketable order triggers; the other is rejected
+        assert_eq!(order_manager.update_market_price("BTC/USDT", 48000.0).len(), 1);
+        assert_eq!(order_manager.get_order("order-1").unwrap().status, OrderStatus::Pending);
+        assert_eq!(order_manager.get_order("order-2").unwrap().status, OrderStatus::Rejected);
+        
+        // A partial fill cancels the remainder
+        let order = order_manager.record_fill("order-1", dec("0.4"), Decimal::from(48000)).unwrap();
+        assert_eq!(order.status, OrderStatus::Cancelled);
+        assert_eq!(order.filled_amount(), dec("0.4"));
+    }
+
+    #[test]
+    fn test_fill_or_kill() {
+        let mut order_manager = OrderManager::new();
+        
+        let fok_order = AdvancedOrder {
+            id: "order-1".to_string(),
+            symbol: "BTC/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type: OrderType::Market,
+            side: "buy".to_string(),
+            amount: Decimal::from(1),
+            time_in_force: TimeInForce::FillOrKill,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
+        };
+        let partial_fok_order = AdvancedOrder {
+            id: "order-2".to_string(),
+            symbol: "BTC/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type: OrderType::Market,
+            side: "buy".to_string(),
+            amount: Decimal::from(1),
+            time_in_force: TimeInForce::FillOrKill,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
+        };
+        
+        order_manager.create_order(fok_order).unwrap();
+        order_manager.create_order(partial_fok_order).unwrap();
+        
+        // A complete fill is accepted
+        let order = order_manager.record_fill("order-1", Decimal::from(1), Decimal::from(50000)).unwrap();
+        assert_eq!(order.status, OrderStatus::Filled);
+        
+        // A partial fill kills the order without recording the fill
+        assert!(order_manager.record_fill("order-2", dec("0.5"), Decimal::from(50000)).is_err());
+        let order = order_manager.get_order("order-2").unwrap();
+        assert_eq!(order.status, OrderStatus::Rejected);
+        assert!(order.fills.is_empty());
+    }
+
+    #[test]
+    fn test_fill_or_kill_rejected_when_not_triggered() {
+        let mut order_manager = OrderManager::new();
+        
+        let fok_order = AdvancedOrder {
+            id: "order-1".to_string(),
+            symbol: "BTC/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type: OrderType::Limit { price: 51000.0 },
+            side: "sell".to_string(),
+            amount: Decimal::from(1),
+            time_in_force: TimeInForce::FillOrKill,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
+        };
+        
+        order_manager.create_order(fok_order).unwrap();
+        
+        assert!(order_manager.update_market_price("BTC/USDT", 50000.0).is_empty());
+        assert_eq!(order_manager.get_order("order-1").unwrap().status, OrderStatus::Rejected);
+    }
+
+    #[tokio::test]
+    async fn test_order_events_published() {
+        let bus = InMemoryBus::new(16);
+        let mut rx = bus.subscribe(sniper_core::events::ORDER_CREATED_SUBJECT);
+        let mut order_manager = OrderManager::new().with_bus(bus);
+        
+        let order = AdvancedOrder {
+            id: "order-1".to_string(),
+            symbol: "BTC/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type: OrderType::Market,
+            side: "buy".to_string(),
+            amount: Decimal::from(1),
+            time_in_force: TimeInForce::GoodTillCancelled,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            status: OrderStatus::Active,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
+        };
+        
+        order_manager.create_order(order).unwrap();
+        order_manager.record_fill("order-1", Decimal::from(1), Decimal::from(50000)).unwrap();
+        
+        // Audit events share the bus and are skipped here
+        let mut events = Vec::new();
+        while let Ok(bytes) = rx.try_recv() {
+            events.extend(TradingEvent::decode(&bytes));
+        }
+        let mut events = events.into_iter();
+        let created = events.next().unwrap();
+        assert_eq!(created.subject(), sniper_core::events::ORDER_CREATED_SUBJECT);
+        match events.next().unwrap() {
+            TradingEvent::OrderFilled { order_id, quantity, complete, .. } => {
+                assert_eq!(order_id, "order-1");
+                assert_eq!(quantity, 1.0);
+                assert!(complete);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        
+        order_manager.cancel_order("order-1").unwrap();
+        let cancelled = std::iter::from_fn(|| rx.try_recv().ok()).find_map(|bytes| TradingEvent::decode(&bytes)).unwrap();
+        assert!(matches!(cancelled, TradingEvent::OrderCancelled { order_id, .. } if order_id == "order-1"));
+    }
+
+    #[tokio::test]
+    async fn test_order_audit_events_are_correlated() {
+        let bus = InMemoryBus::new(16);
+        let mut rx = bus.subscribe(AUDIT_SUBJECT);
+        let mut order_manager = OrderManager::new().with_bus(bus);
+        
+        let order = AdvancedOrder {
+            id: "order-1".to_string(),
+            symbol: "BTC/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type: OrderType::Market,
+            side: "buy".to_string(),
+            amount: Decimal::from(1),
+            time_in_force: TimeInForce::GoodTillCancelled,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            status: OrderStatus::Active,
+            fills: Vec::new(),
+            owner_id: Some("alice".to_string()),
+            tenant_id: Some("tenant-1".to_string()),
+            trigger: None,
+        };
+        
+        order_manager.create_order(order).unwrap();
+        // Previewing a plan is not audited, only taking it for execution
+        order_manager.to_trade_plan("order-1", 50000.0).unwrap();
+        let plan = order_manager.take_trade_plan("order-1", 50000.0).unwrap().unwrap();
+        assert_eq!(plan.correlation_id.as_deref(), Some("order-1"));
+        assert!(order_manager.take_trade_plan("order-1", 50000.0).unwrap().is_none());
+        order_manager.cancel_order_by("order-1", "bob").unwrap();
+        
+        let mut events = Vec::new();
+        while let Ok(bytes) = rx.try_recv() {
+            events.extend(AuditEvent::decode(&bytes));
+        }
+        let actions: Vec<&str> = events.iter().map(|event| event.action.as_str()).collect();
+        assert_eq!(actions, vec![audit::ORDER_CREATED, audit::TRADE_PLANNED, audit::ORDER_CANCELLED]);
+        assert!(events.iter().all(|event| event.correlation_id == "order-1"));
+        assert!(events.iter().all(|event| event.tenant_id.as_deref() == Some("tenant-1")));
+        assert_eq!(events[0].actor, "alice");
+        assert_eq!(events[1].actor, audit::SYSTEM_ACTOR);
+        assert_eq!(events[2].actor, "bob");
+        assert!(events[1].details.as_deref().unwrap().contains(&plan.idem_key));
+    }
+
+    #[test]
+    fn test_create_order_runs_risk_checks() {
+        let risk = RiskEngine::new(sniper_risk::RiskLimits {
+            max_order_notional: Some(10_000.0),
+            banned_tokens: vec!["SCAM".to_string()],
+            ..Default::default()
+        });
+        let mut order_manager = OrderManager::new().with_risk(risk);
+        let order = |id: &str, symbol: &str, order_type: OrderType, amount: Decimal| AdvancedOrder {
+            id: id.to_string(),
+            symbol: symbol.to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type,
+            side: "buy".to_string(),
+            amount,
+            time_in_force: TimeInForce::GoodTillCancelled,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
+        };
+
+        assert!(order_manager.create_order(order("order-1", "ETH/USDT", OrderType::Limit { price: 3000.0 }, Decimal::from(3))).is_ok());
+
+        let err = order_manager
+            .create_order(order("order-2", "ETH/USDT", OrderType::Limit { price: 3000.0 }, Decimal::from(4)))
+            .unwrap_err();
+        let OrderError::Risk(rejected) = err else {
+            panic!("unexpected error {:?}", err);
+        };
+        assert_eq!(rejected.rejections, vec![sniper_risk::RiskRejection::NotionalTooLarge {
+            notional: 12_000.0,
+            limit: 10_000.0,
+        }]);
+
+        // Market orders are valued at the last price seen
+        assert!(order_manager.create_order(order("order-3", "ETH/USDT", OrderType::Market, Decimal::from(4))).is_err());
+        order_manager.update_market_price("ETH/USDT", 2000.0);
+        assert!(order_manager.create_order(order("order-3", "ETH/USDT", OrderType::Market, Decimal::from(4))).is_ok());
+
+        assert!(order_manager.create_order(order("order-4", "SCAM/WETH", OrderType::Limit { price: 1.0 }, Decimal::from(1))).is_err());
+        assert_eq!(order_manager.orders.len(), 2);
+    }
+
+    #[test]
+    fn test_create_order_refused_while_kill_switch_tripped() {
+        let kill_switch = KillSwitch::default();
+        let mut order_manager = OrderManager::new().with_kill_switch(kill_switch.clone());
+        let order = AdvancedOrder {
+            id: "order-1".to_string(),
+            symbol: "BTC/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type: OrderType::Market,
+            side: "buy".to_string(),
+            amount: Decimal::from(1),
+            time_in_force: TimeInForce::GoodTillCancelled,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
+        };
+
+        kill_switch.trip(sniper_core::kill_switch::TripReason::Manual {
+            by: "ops".to_string(),
+            reason: "venue outage".to_string(),
+        });
+        let err = order_manager.create_order(order.clone()).unwrap_err();
+        assert!(err.to_string().contains("venue outage"));
+        assert!(order_manager.orders.is_empty());
+
+        kill_switch.reset("ops");
+        assert!(order_manager.create_order(order).is_ok());
+    }
+
+    #[test]
+    fn test_signal_trigger_arms_order() {
+        let mut order_manager = OrderManager::new();
+        let order = AdvancedOrder {
+            id: "order-1".to_string(),
+            symbol: "PEPE/WETH".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type: OrderType::Market,
+            side: "buy".to_string(),
+            amount: Decimal::from(1),
+            time_in_force: TimeInForce::ImmediateOrCancel,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: Some(OrderTrigger::Signal {
+                kind: "liquidity_added".to_string(),
+                source: None,
+                token: Some("0xPEPE".to_string()),
+                chain_id: Some(1),
+            }),
+        };
+        order_manager.create_order(order.clone()).unwrap();
+        
+        // Armed orders neither execute nor get rejected as IOC on prices
+        assert!(order_manager.update_market_price("PEPE/WETH", 0.001).is_empty());
+        assert!(order_manager.get_order("order-1").unwrap().awaiting_trigger());
+        
+        let mut signal = Signal {
+            source: "dex".to_string(),
+            kind: "pair_created".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            token0: Some("0xpepe".to_string()),
+            token1: Some("0xWETH".to_string()),
+            extra: serde_json::Value::Null,
+            seen_at_ms: 0,
+        };
+        assert!(order_manager.on_signal(&signal).is_empty());
+        assert_eq!(order_manager.get_order("order-1").unwrap().status, OrderStatus::Pending);
+        
+        // Activated orders execute at once at the symbol's last price
+        signal.kind = "liquidity_added".to_string();
+        let plans = order_manager.on_signal(&signal);
+        assert_eq!(plans.len(), 1);
+        assert_eq!(plans[0].correlation_id.as_deref(), Some("order-1"));
+        assert_eq!(order_manager.get_order("order-1").unwrap().status, OrderStatus::Active);
+        assert!(order_manager.on_signal(&signal).is_empty());
+        
+        let twap = AdvancedOrder {
+            id: "order-2".to_string(),
+            order_type: OrderType::TWAP { total_amount: 1.0, duration_minutes: 10 },
+            ..order
+        };
+        assert!(matches!(order_manager.create_order(twap), Err(OrderError::InvalidTrigger(_))));
+    }
+
+    #[test]
+    fn test_price_trigger_on_another_symbol() {
+        let mut order_manager = OrderManager::new();
+        let order = AdvancedOrder {
+            id: "order-1".to_string(),
+            symbol: "ETH/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type: OrderType::Limit { price: 3000.0 },
+            side: "buy".to_string(),
+            amount: Decimal::from(1),
+            time_in_force: TimeInForce::GoodTillCancelled,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: Some(OrderTrigger::PriceCross {
+                symbol: "BTC/USDT".to_string(),
+                level: 60000.0,
+                direction: CrossDirection::Above,
+            }),
+        };
+        order_manager.create_order(order).unwrap();
+        
+        assert!(order_manager.update_market_price("ETH/USDT", 2900.0).is_empty());
+        assert!(order_manager.update_market_price("BTC/USDT", 59000.0).is_empty());
+        assert!(order_manager.get_order("order-1").unwrap().awaiting_trigger());
+        
+        // The limit is evaluated at the last ETH price once BTC crosses the level
+        assert_eq!(order_manager.update_market_price("BTC/USDT", 60500.0).len(), 1);
+        assert_eq!(order_manager.get_order("order-1").unwrap().status, OrderStatus::Active);
+    }
+}
@@ -0,0 +1,478 @@
+//! Symbol-sharded order manager.
+//!
+//! Orders are partitioned by symbol, each shard an `OrderManager` behind its
+//! own lock. Price updates are dispatched to the shard for their symbol, so a
+//! burst of ticks for one market never blocks order flow on another. Every
+//! shard shares the same risk engine and kill switch, if attached. A price is
+//! also passed to the shards holding orders armed on that symbol, while
+//! signals go to every shard.
+
+use crate::{AdvancedOrder, OrderAmendment, OrderError, OrderManager, OrderStatus, TrailingStopState};
+use sniper_core::bus::InMemoryBus;
+use sniper_core::kill_switch::KillSwitch;
+use sniper_core::types::{Decimal, Signal, TradePlan};
+use sniper_risk::RiskEngine;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Order manager sharded by symbol
+#[derive(Default)]
+pub struct ShardedOrderManager {
+    shards: std::sync::RwLock<HashMap<String, Arc<RwLock<OrderManager>>>>,
+    order_symbols: std::sync::RwLock<HashMap<String, String>>,
+    /// Shards with orders armed on the prices of another symbol, by watched symbol
+    watchers: std::sync::RwLock<HashMap<String, HashSet<String>>>,
+    bus: Option<InMemoryBus>,
+    risk: Option<RiskEngine>,
+    kill_switch: Option<KillSwitch>,
+}
+
+impl ShardedOrderManager {
+    /// Create a new sharded order manager
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Publish order events from every shard on a bus
+    pub fn with_bus(mut self, bus: InMemoryBus) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Run new orders in every shard through a risk engine's pre-trade checks
+    pub fn with_risk(mut self, risk: RiskEngine) -> Self {
+        self.risk = Some(risk);
+        self
+    }
+
+    /// Refuse new orders in every shard while a kill switch is tripped
+    pub fn with_kill_switch(mut self, kill_switch: KillSwitch) -> Self {
+        self.kill_switch = Some(kill_switch);
+        self
+    }
+
+    /// New shard, attached to the bus, risk engine and kill switch if any
+    fn new_shard(&self) -> OrderManager {
+        let shard = match &self.bus {
+            Some(bus) => OrderManager::new().with_bus(bus.clone()),
+            None => OrderManager::new(),
+        };
+        let shard = match &self.risk {
+            Some(risk) => shard.with_risk(risk.clone()),
+            None => shard,
+        };
+        match &self.kill_switch {
+            Some(kill_switch) => shard.with_kill_switch(kill_switch.clone()),
+            None => shard,
+        }
+    }
+
+    /// Get the shard for a symbol, if any orders were ever placed in it
+    fn shard(&self, symbol: &str) -> Option<Arc<RwLock<OrderManager>>> {
+        self.shards.read().unwrap().get(symbol).cloned()
+    }
+
+    /// Get or create the shard for a symbol
+    fn shard_or_create(&self, symbol: &str) -> Arc<RwLock<OrderManager>> {
+        if let Some(shard) = self.shard(symbol) {
+            return shard;
+        }
+        self.shards
+            .write()
+            .unwrap()
+            .entry(symbol.to_string())
+            .or_insert_with(|| Arc::new(RwLock::new(self.new_shard())))
+            .clone()
+    }
+
+    /// Get the shard holding an order
+    fn order_shard(&self, order_id: &str) -> Option<Arc<RwLock<OrderManager>>> {
+        let symbol = self.order_symbols.read().unwrap().get(order_id).cloned()?;
+        self.shard(&symbol)
+    }
+
+    /// Pass the prices of the symbol an order is armed on to the order's shard
+    fn watch(&self, order: &AdvancedOrder) {
+        if let Some(watched) = order.trigger.as_ref().and_then(|trigger| trigger.watched_symbol()) {
+            if watched != order.symbol {
+                self.watchers
+                    .write()
+                    .unwrap()
+                    .entry(watched.to_string())
+                    .or_default()
+                    .insert(order.symbol.clone());
+            }
+        }
+    }
+
+    /// Symbols with a shard
+    pub fn symbols(&self) -> Vec<String> {
+        self.shards.read().unwrap().keys().cloned().collect()
+    }
+
+    /// Create or replace an order, moving it between shards if its symbol changed
+    pub async fn create_order(&self, order: AdvancedOrder) -> Result<String, OrderError> {
+        // A rejected replacement must not evict the order from its old shard
+        self.shard_or_create(&order.symbol).read().await.check_risk(&order)?;
+        let previous = self
+            .order_symbols
+            .read()
+            .unwrap()
+            .get(&order.id)
+            .filter(|symbol| **symbol != order.symbol)
+            .cloned();
+        if let Some(shard) = previous.and_then(|symbol| self.shard(&symbol)) {
+            shard.write().await.remove_order(&order.id);
+        }
+
+        let order_id = order.id.clone();
+        let symbol = order.symbol.clone();
+        self.watch(&order);
+        self.shard_or_create(&symbol).write().await.create_order(order)?;
+        self.order_symbols.write().unwrap().insert(order_id.clone(), symbol);
+        Ok(order_id)
+    }
+
+    /// Amend the terms of an open order on behalf of a user, returning the version it replaced
+    pub async fn amend_order(&self, order: AdvancedOrder, actor: &str) -> Result<AdvancedOrder, OrderError> {
+        let shard = self
+            .order_shard(&order.id)
+            .ok_or_else(|| OrderError::NotFound(order.id.clone()))?;
+        let result = shard.write().await.amend_order(order, actor);
+        result
+    }
+
+    /// Earlier versions of an order, oldest first
+    pub async fn amendments(&self, order_id: &str) -> Vec<OrderAmendment> {
+        let Some(shard) = self.order_shard(order_id) else {
+            return Vec::new();
+        };
+        let amendments = shard.read().await.amendments(order_id).to_vec();
+        amendments
+    }
+
+    /// Cancel an order
+    pub async fn cancel_order(&self, order_id: &str) -> Result<(), OrderError> {
+        self.cancel_order_by(order_id, sniper_core::audit::SYSTEM_ACTOR).await
+    }
+
+    /// Cancel an order on behalf of a user, recording them in the audit event
+    pub async fn cancel_order_by(&self, order_id: &str, actor: &str) -> Result<(), OrderError> {
+        let shard = self
+            .order_shard(order_id)
+            .ok_or_else(|| OrderError::NotFound(order_id.to_string()))?;
+        let result = shard.write().await.cancel_order_by(order_id, actor);
+        result
+    }
+
+    /// Cancel several orders together on behalf of a user, returning the IDs of those that were open
+    ///
+    /// The shards involved are all locked first, in symbol order, so no fill or
+    /// trigger lands between the cancellations. Nothing is cancelled unless
+    /// every order exists.
+    pub async fn cancel_orders_by(&self, order_ids: &[String], actor: &str) -> Result<Vec<String>, OrderError> {
+        let mut by_symbol: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        {
+            let order_symbols = self.order_symbols.read().unwrap();
+            for order_id in order_ids {
+                let symbol = order_symbols
+                    .get(order_id)
+                    .ok_or_else(|| OrderError::NotFound(order_id.clone()))?;
+                by_symbol.entry(symbol.clone()).or_default().push(order_id.clone());
+            }
+        }
+        let shards = by_symbol
+            .keys()
+            .map(|symbol| self.shard(symbol).ok_or_else(|| OrderError::NotFound(symbol.clone())))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut guards = Vec::with_capacity(shards.len());
+        for shard in &shards {
+            guards.push(shard.write().await);
+        }
+        for (guard, order_ids) in guards.iter().zip(by_symbol.values()) {
+            if let Some(missing) = order_ids.iter().find(|order_id| guard.get_order(order_id).is_none()) {
+                return Err(OrderError::NotFound(missing.clone()));
+            }
+        }
+        let mut cancelled = Vec::new();
+        for (guard, order_ids) in guards.iter_mut().zip(by_symbol.values()) {
+            cancelled.extend(guard.cancel_orders_by(order_ids, actor)?);
+        }
+        Ok(cancelled)
+    }
+
+    /// List the orders placed under a tenant
+    pub async fn list_tenant_orders(&self, tenant_id: &str) -> Vec<AdvancedOrder> {
+        let mut orders = self.list_orders().await;
+        orders.retain(|order| order.tenant_id.as_deref() == Some(tenant_id));
+        orders
+    }
+
+    /// Replace all orders of a tenant, e.g. with ones restored from a backup
+    pub async fn restore_orders(&self, tenant_id: &str, orders: Vec<AdvancedOrder>) -> Result<(), OrderError> {
+        for order in &orders {
+            if order.tenant_id.as_deref() != Some(tenant_id) {
+                return Err(OrderError::WrongTenant {
+                    id: order.id.clone(),
+                    tenant_id: tenant_id.to_string(),
+                });
+            }
+            if let Some(existing) = self.get_order(&order.id).await {
+                if existing.tenant_id != order.tenant_id {
+                    return Err(OrderError::IdTaken(order.id.clone()));
+                }
+            }
+        }
+
+        for order in self.list_tenant_orders(tenant_id).await {
+            if let Some(shard) = self.order_shard(&order.id) {
+                shard.write().await.remove_order(&order.id);
+            }
+            self.order_symbols.write().unwrap().remove(&order.id);
+        }
+        for order in orders {
+            let order_id = order.id.clone();
+            let symbol = order.symbol.clone();
+            self.watch(&order);
+            self.shard_or_create(&symbol).write().await.restore_order(order);
+            self.order_symbols.write().unwrap().insert(order_id, symbol);
+        }
+        Ok(())
+    }
+
+    /// Record an execution report for an order
+    pub async fn record_fill(&self, order_id: &str, quantity: Decimal, price: Decimal) -> Result<AdvancedOrder, OrderError> {
+        let shard = self
+            .order_shard(order_id)
+            .ok_or_else(|| OrderError::NotFound(order_id.to_string()))?;
+        let result = shard.write().await.record_fill(order_id, quantity, price);
+        result
+    }
+
+    /// Get an order by ID
+    pub async fn get_order(&self, order_id: &str) -> Option<AdvancedOrder> {
+        let shard = self.order_shard(order_id)?;
+        let order = shard.read().await.get_order(order_id).cloned();
+        order
+    }
+
+    /// List all orders
+    pub async fn list_orders(&self) -> Vec<AdvancedOrder> {
+        let shards: Vec<_> = self.shards.read().unwrap().values().cloned().collect();
+        let mut orders = Vec::new();
+        for shard in shards {
+            orders.extend(shard.read().await.list_orders().into_iter().cloned());
+        }
+        orders
+    }
+
+    /// List orders in one symbol
+    pub async fn list_orders_for_symbol(&self, symbol: &str) -> Vec<AdvancedOrder> {
+        match self.shard(symbol) {
+            Some(shard) => shard.read().await.list_orders().into_iter().cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// List orders by status
+    pub async fn list_orders_by_status(&self, status: OrderStatus) -> Vec<AdvancedOrder> {
+        let shards: Vec<_> = self.shards.read().unwrap().values().cloned().collect();
+        let mut orders = Vec::new();
+        for shard in shards {
+            orders.extend(shard.read().await.list_orders_by_status(status.clone()).into_iter().cloned());
+        }
+        orders
+    }
+
+    /// Convert an order to a trade plan
+    pub async fn to_trade_plan(&self, order_id: &str, current_price: f64) -> Result<TradePlan, OrderError> {
+        let shard = self
+            .order_shard(order_id)
+            .ok_or_else(|| OrderError::NotFound(order_id.to_string()))?;
+        let result = shard.read().await.to_trade_plan(order_id, current_price);
+        result
+    }
+
+    /// Plan a triggered order for execution, once per fill count
+    pub async fn take_trade_plan(&self, order_id: &str, current_price: f64) -> Result<Option<TradePlan>, OrderError> {
+        let shard = self
+            .order_shard(order_id)
+            .ok_or_else(|| OrderError::NotFound(order_id.to_string()))?;
+        let result = shard.write().await.take_trade_plan(order_id, current_price);
+        result
+    }
+
+    /// Dispatch a price update to the symbol's shard, returning plans for triggered orders
+    pub async fn on_price_update(&self, symbol: &str, price: f64) -> Vec<TradePlan> {
+        let mut plans = match self.shard(symbol) {
+            Some(shard) => shard.write().await.update_market_price(symbol, price),
+            None => {
+                // Keep the mark fresh so the first order in the symbol can be valued
+                if let Some(risk) = &self.risk {
+                    risk.record_price(symbol, price);
+                }
+                Vec::new()
+            },
+        };
+        let watchers: Vec<String> = self
+            .watchers
+            .read()
+            .unwrap()
+            .get(symbol)
+            .map(|shards| shards.iter().cloned().collect())
+            .unwrap_or_default();
+        for watcher in watchers {
+            if let Some(shard) = self.shard(&watcher) {
+                plans.extend(shard.write().await.on_watched_price(symbol, price));
+            }
+        }
+        plans
+    }
+
+    /// Activate orders armed on a signal across all shards, returning plans for those that execute at once
+    pub async fn on_signal(&self, signal: &Signal) -> Vec<TradePlan> {
+        let shards: Vec<_> = self.shards.read().unwrap().values().cloned().collect();
+        let mut plans = Vec::new();
+        for shard in shards {
+            plans.extend(shard.write().await.on_signal(signal));
+        }
+        plans
+    }
+
+    /// Expire Good-Till-Time orders across all shards, returning their IDs
+    pub async fn tick(&self, now: u64) -> Vec<String> {
+        let shards: Vec<_> = self.shards.read().unwrap().values().cloned().collect();
+        let mut expired = Vec::new();
+        for shard in shards {
+            expired.extend(shard.write().await.tick(now));
+        }
+        expired
+    }
+
+    /// Get the trailing stop state of an order
+    pub async fn trailing_state(&self, order_id: &str) -> Option<TrailingStopState> {
+        let shard = self.order_shard(order_id)?;
+        let state = shard.read().await.trailing_state(order_id).cloned();
+        state
+    }
+
+    /// Total number of orders
+    pub fn len(&self) -> usize {
+        self.order_symbols.read().unwrap().len()
+    }
+
+    /// Whether there are no orders
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use crate::{OrderType, TimeInForce};
+    use sniper_core::types::ChainRef;
+
+    fn order(id: &str, symbol: &str, order_type: OrderType) -> AdvancedOrder {
+        AdvancedOrder {
+            id: id.to_string(),
+            symbol: symbol.to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type,
+            side: "buy".to_string(),
+            amount: Decimal::ONE,
+            time_in_force: TimeInForce::GoodTillCancelled,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sharded_orders() -> Result<()> {
+        let manager = ShardedOrderManager::new();
+        manager.create_order(order("order-1", "BTC/USDT", OrderType::Limit { price: 49000.0 })).await?;
+        manager.create_order(order("order-2", "ETH/USDT", OrderType::Limit { price: 2900.0 })).await?;
+
+        assert_eq!(manager.len(), 2);
+        assert_eq!(manager.symbols().len(), 2);
+        assert_eq!(manager.list_orders().await.len(), 2);
+
+        // Only the ticked symbol's orders are evaluated
+        assert_eq!(manager.on_price_update("BTC/USDT", 48000.0).await.len(), 1);
+        assert!(manager.on_price_update("ETH/USDT", 3000.0).await.is_empty());
+        assert!(manager.on_price_update("SOL/USDT", 1.0).await.is_empty());
+
+        manager.cancel_order("order-1").await?;
+        assert_eq!(manager.get_order("order-1").await.unwrap().status, OrderStatus::Cancelled);
+        assert!(manager.on_price_update("BTC/USDT", 48000.0).await.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_symbol_change_moves_shard() -> Result<()> {
+        let manager = ShardedOrderManager::new();
+        manager.create_order(order("order-1", "BTC/USDT", OrderType::Market)).await?;
+        manager.create_order(order("order-1", "ETH/USDT", OrderType::Market)).await?;
+
+        assert_eq!(manager.len(), 1);
+        assert!(manager.list_orders_for_symbol("BTC/USDT").await.is_empty());
+        assert_eq!(manager.list_orders_for_symbol("ETH/USDT").await.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_triggers_reach_other_shards() -> Result<()> {
+        let manager = ShardedOrderManager::new();
+        let mut armed = order("order-1", "ETH/USDT", OrderType::Market);
+        armed.trigger = Some(crate::OrderTrigger::PriceCross {
+            symbol: "BTC/USDT".to_string(),
+            level: 50000.0,
+            direction: crate::CrossDirection::Below,
+        });
+        manager.create_order(armed).await?;
+        let mut on_signal = order("order-2", "SOL/USDT", OrderType::Market);
+        on_signal.trigger = Some(crate::OrderTrigger::Signal {
+            kind: "trading_enabled".to_string(),
+            source: Some("cex".to_string()),
+            token: None,
+            chain_id: None,
+        });
+        manager.create_order(on_signal).await?;
+        manager.on_price_update("ETH/USDT", 3000.0).await;
+
+        assert!(manager.on_price_update("BTC/USDT", 51000.0).await.is_empty());
+        assert_eq!(manager.on_price_update("BTC/USDT", 49000.0).await.len(), 1);
+        assert_eq!(manager.get_order("order-1").await.unwrap().status, OrderStatus::Active);
+
+        let signal = Signal {
+            source: "cex".to_string(),
+            kind: "trading_enabled".to_string(),
+            chain: ChainRef {
+                name: "solana".to_string(),
+                id: 101,
+            },
+            token0: None,
+            token1: None,
+            extra: serde_json::Value::Null,
+            seen_at_ms: 0,
+        };
+        // No SOL price seen yet, so the order waits for the first one
+        assert!(manager.on_signal(&signal).await.is_empty());
+        assert_eq!(manager.get_order("order-2").await.unwrap().status, OrderStatus::Active);
+        assert_eq!(manager.on_price_update("SOL/USDT", 150.0).await.len(), 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,522 @@
+//! TWAP and VWAP order slicing.
+//!
+//! The scheduler splits a TWAP order into equal child market orders spread
+//! over its duration, and a VWAP order into children sized by a pluggable
+//! volume profile. A background task releases children as they come due and
+//! emits their trade plans through the regular `take_trade_plan` path.
+
+use crate::{AdvancedOrder, OrderError, OrderStatus, OrderType, ShardedOrderManager, TimeInForce};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sniper_core::types::{Decimal, TradePlan};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+
+/// Expected volume distribution used to size VWAP slices
+pub trait VolumeProfile: Send + Sync {
+    /// Relative volume expected in each of the next `buckets` intervals
+    fn weights(&self, symbol: &str, buckets: usize) -> Vec<f64>;
+}
+
+/// Flat volume profile
+pub struct UniformVolumeProfile;
+
+impl VolumeProfile for UniformVolumeProfile {
+    fn weights(&self, _symbol: &str, buckets: usize) -> Vec<f64> {
+        vec![1.0; buckets]
+    }
+}
+
+/// Volume profile built from historical per-interval volumes
+#[derive(Default)]
+pub struct HistoricalVolumeProfile {
+    profiles: HashMap<String, Vec<f64>>,
+}
+
+impl HistoricalVolumeProfile {
+    /// Create an empty historical profile
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the historical volumes for a symbol, one entry per interval
+    pub fn set_profile(&mut self, symbol: &str, volumes: Vec<f64>) {
+        self.profiles.insert(symbol.to_string(), volumes);
+    }
+}
+
+impl VolumeProfile for HistoricalVolumeProfile {
+    fn weights(&self, symbol: &str, buckets: usize) -> Vec<f64> {
+        let volumes = match self.profiles.get(symbol) {
+            Some(volumes) if !volumes.is_empty() => volumes,
+            _ => return vec![1.0; buckets],
+        };
+
+        // Resample the stored profile onto the requested number of buckets
+        (0..buckets)
+            .map(|i| volumes[i * volumes.len() / buckets].max(0.0))
+            .collect()
+    }
+}
+
+/// Slicing settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlicerConfig {
+    /// Interval between TWAP child orders in seconds
+    pub twap_slice_secs: i64,
+    /// Number of VWAP buckets
+    pub vwap_buckets: usize,
+    /// Length of a VWAP bucket in seconds
+    pub vwap_bucket_secs: i64,
+}
+
+impl Default for SlicerConfig {
+    fn default() -> Self {
+        Self {
+            twap_slice_secs: 60,
+            vwap_buckets: 12,
+            vwap_bucket_secs: 300,
+        }
+    }
+}
+
+/// A child order scheduled for release
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChildSlice {
+    pub parent_id: String,
+    pub index: usize,
+    pub amount: Decimal,
+    pub release_at: DateTime<Utc>,
+}
+
+impl ChildSlice {
+    /// ID of the child order created for this slice
+    pub fn child_id(&self) -> String {
+        format!("{}-slice-{}", self.parent_id, self.index)
+    }
+}
+
+/// Split a TWAP order into equal slices over its duration
+pub fn twap_slices(order: &AdvancedOrder, config: &SlicerConfig, start: DateTime<Utc>) -> Result<Vec<ChildSlice>, OrderError> {
+    let (total_amount, duration_minutes) = match order.order_type {
+        OrderType::TWAP { total_amount, duration_minutes } => (total_amount, duration_minutes),
+        _ => {
+            return Err(OrderError::WrongType {
+                id: order.id.clone(),
+                expected: "TWAP",
+            })
+        }
+    };
+
+    let interval = config.twap_slice_secs.max(1);
+    let count = ((duration_minutes as i64 * 60) / interval).max(1) as usize;
+    let total_amount = Decimal::from_f64(total_amount)
+        .ok_or_else(|| OrderError::InvalidAmount(format!("TWAP amount {}", total_amount)))?;
+    let amount = total_amount / Decimal::from(count as u64);
+
+    Ok((0..count)
+        .map(|index| ChildSlice {
+            parent_id: order.id.clone(),
+            index,
+            amount,
+            release_at: start + Duration::seconds(index as i64 * interval),
+        })
+        .collect())
+}
+
+/// Split a VWAP order into slices sized by the volume profile
+pub fn vwap_slices(
+    order: &AdvancedOrder,
+    config: &SlicerConfig,
+    profile: &dyn VolumeProfile,
+    start: DateTime<Utc>,
+) -> Result<Vec<ChildSlice>, OrderError> {
+    let total_amount = match order.order_type {
+        OrderType::VWAP { total_amount } => total_amount,
+        _ => {
+            return Err(OrderError::WrongType {
+                id: order.id.clone(),
+                expected: "VWAP",
+            })
+        }
+    };
+
+    let buckets = config.vwap_buckets.max(1);
+    let mut weights = profile.weights(&order.symbol, buckets);
+    weights.resize(buckets, 0.0);
+    let total_weight: f64 = weights.iter().sum();
+    if total_weight <= 0.0 {
+        weights = vec![1.0; buckets];
+    }
+    let total_weight: f64 = weights.iter().sum();
+
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0.0)
+        .map(|(index, weight)| {
+            let amount = total_amount * weight / total_weight;
+            Ok(ChildSlice {
+                parent_id: order.id.clone(),
+                index,
+                amount: Decimal::from_f64(amount)
+                    .ok_or_else(|| OrderError::InvalidAmount(format!("VWAP slice amount {}", amount)))?,
+                release_at: start + Duration::seconds(index as i64 * config.vwap_bucket_secs),
+            })
+        })
+        .collect()
+}
+
+/// Pending slices held back on shutdown, with the parent orders they belong to
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParkedSlices {
+    pub parents: Vec<AdvancedOrder>,
+    pub slices: Vec<ChildSlice>,
+}
+
+/// Background scheduler releasing TWAP and VWAP child orders
+pub struct SliceScheduler {
+    config: SlicerConfig,
+    profile: Arc<dyn VolumeProfile>,
+    orders: Arc<ShardedOrderManager>,
+    pending: Mutex<Vec<ChildSlice>>,
+}
+
+impl SliceScheduler {
+    /// Create a new slice scheduler
+    pub fn new(config: SlicerConfig, profile: Arc<dyn VolumeProfile>, orders: Arc<ShardedOrderManager>) -> Self {
+        Self {
+            config,
+            profile,
+            orders,
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Slice a TWAP or VWAP order and queue its children
+    pub async fn schedule(&self, order_id: &str, start: DateTime<Utc>) -> Result<usize, OrderError> {
+        let mut parent = self
+            .orders
+            .get_order(order_id)
+            .await
+            .ok_or_else(|| OrderError::NotFound(order_id.to_string()))?;
+
+        let slices = match parent.order_type {
+            OrderType::TWAP { .. } => twap_slices(&parent, &self.config, start)?,
+            OrderType::VWAP { .. } => vwap_slices(&parent, &self.config, self.profile.as_ref(), start)?,
+            _ => {
+                return Err(OrderError::WrongType {
+                    id: parent.id.clone(),
+                    expected: "TWAP or VWAP",
+                })
+            }
+        };
+
+        parent.status = OrderStatus::Active;
+        parent.updated_at = start.timestamp() as u64;
+        self.orders.create_order(parent).await?;
+
+        let count = slices.len();
+        self.pending.lock().await.extend(slices);
+        Ok(count)
+    }
+
+    /// Slices not yet released, optionally for one parent order
+    pub async fn pending_slices(&self, parent_id: Option<&str>) -> Vec<ChildSlice> {
+        self.pending
+            .lock()
+            .await
+            .iter()
+            .filter(|slice| parent_id.map(|id| slice.parent_id == id).unwrap_or(true))
+            .cloned()
+            .collect()
+    }
+
+    /// Drop the pending slices of a parent order
+    pub async fn cancel(&self, parent_id: &str) -> usize {
+        let mut pending = self.pending.lock().await;
+        let before = pending.len();
+        pending.retain(|slice| slice.parent_id != parent_id);
+        before - pending.len()
+    }
+
+    /// Release due slices as child market orders and return their trade plans
+    pub async fn release_due(&self, now: DateTime<Utc>) -> Vec<TradePlan> {
+        let due: Vec<ChildSlice> = {
+            let mut pending = self.pending.lock().await;
+            let (due, later): (Vec<_>, Vec<_>) = pending.drain(..).partition(|slice| slice.release_at <= now);
+            *pending = later;
+            due
+        };
+
+        let mut plans = Vec::new();
+        for slice in due {
+            let parent = match self.orders.get_order(&slice.parent_id).await {
+                Some(parent) => parent,
+                None => continue,
+            };
+            // Children of cancelled or finished parents are dropped
+            if !matches!(parent.status, OrderStatus::Active | OrderStatus::PartiallyFilled) {
+                continue;
+            }
+
+            let child = AdvancedOrder {
+                id: slice.child_id(),
+                symbol: parent.symbol.clone(),
+                chain: parent.chain.clone(),
+                order_type: OrderType::Market,
+                side: parent.side.clone(),
+                amount: slice.amount,
+                time_in_force: TimeInForce::ImmediateOrCancel,
+                created_at: now.timestamp() as u64,
+                updated_at: now.timestamp() as u64,
+                status: OrderStatus::Pending,
+                fills: Vec::new(),
+                owner_id: parent.owner_id.clone(),
+                tenant_id: parent.tenant_id.clone(),
+                trigger: None,
+            };
+            if let Err(e) = self.orders.create_order(child.clone()).await {
+                tracing::warn!("failed to create child order {}: {}", child.id, e);
+                continue;
+            }
+
+            // Market children trigger at any price
+            match self.orders.take_trade_plan(&child.id, 0.0).await {
+                Ok(plan) => plans.extend(plan),
+                Err(e) => tracing::warn!("failed to plan child order {}: {}", child.id, e),
+            }
+        }
+        plans
+    }
+
+    /// Stop releasing slices and hand back the pending ones with their parents
+    ///
+    /// Slices of parents that are no longer active are dropped. Children
+    /// already released but still open are cancelled, since nothing tracks
+    /// them once the service is gone.
+    pub async fn park(&self) -> ParkedSlices {
+        let pending: Vec<ChildSlice> = self.pending.lock().await.drain(..).collect();
+        let mut parked = ParkedSlices::default();
+        for slice in pending {
+            if !parked.parents.iter().any(|parent| parent.id == slice.parent_id) {
+                match self.orders.get_order(&slice.parent_id).await {
+                    Some(parent) if is_open(&parent.status) => parked.parents.push(parent),
+                    _ => continue,
+                }
+            }
+            parked.slices.push(slice);
+        }
+        self.cancel_open_children().await;
+        parked
+    }
+
+    /// Stop releasing slices and cancel their parents, returning the cancelled parent IDs
+    ///
+    /// Children already released but still open are cancelled too.
+    pub async fn cancel_all(&self) -> Vec<String> {
+        let mut cancelled = Vec::new();
+        for parent in self.park().await.parents {
+            match self.orders.cancel_order(&parent.id).await {
+                Ok(()) => cancelled.push(parent.id),
+                Err(e) => tracing::warn!("failed to cancel parent order {}: {}", parent.id, e),
+            }
+        }
+        cancelled
+    }
+
+    /// Restore parked parents and queue their slices again, returning how many were queued
+    ///
+    /// Slices that came due while parked are released on the next tick. A
+    /// parent that can't be restored, e.g. because risk now rejects it, is
+    /// dropped with its slices.
+    pub async fn unpark(&self, parked: ParkedSlices) -> usize {
+        let mut restored = Vec::new();
+        for parent in parked.parents {
+            let parent_id = parent.id.clone();
+            match self.orders.create_order(parent).await {
+                Ok(_) => restored.push(parent_id),
+                Err(e) => tracing::warn!("dropping parked slices of {}: {}", parent_id, e),
+            }
+        }
+
+        let slices: Vec<ChildSlice> = parked
+            .slices
+            .into_iter()
+            .filter(|slice| restored.contains(&slice.parent_id))
+            .collect();
+        let count = slices.len();
+        self.pending.lock().await.extend(slices);
+        count
+    }
+
+    /// Cancel released children of TWAP and VWAP orders that are still open
+    async fn cancel_open_children(&self) {
+        let orders = self.orders.list_orders().await;
+        let prefixes: Vec<String> = orders
+            .iter()
+            .filter(|order| matches!(order.order_type, OrderType::TWAP { .. } | OrderType::VWAP { .. }))
+            .map(|order| format!("{}-slice-", order.id))
+            .collect();
+        for order in orders {
+            if !is_open(&order.status) || !prefixes.iter().any(|prefix| order.id.starts_with(prefix)) {
+                continue;
+            }
+            match self.orders.cancel_order(&order.id).await {
+                Ok(()) => tracing::info!("cancelled open child order {}", order.id),
+                Err(e) => tracing::warn!("failed to cancel child order {}: {}", order.id, e),
+            }
+        }
+    }
+
+    /// Release due slices every `tick`, sending their plans to `sink` until it closes
+    pub async fn run(self: Arc<Self>, tick: std::time::Duration, sink: mpsc::Sender<TradePlan>) {
+        let mut interval = tokio::time::interval(tick);
+        loop {
+            interval.tick().await;
+            for plan in self.release_due(Utc::now()).await {
+                if sink.send(plan).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Run the scheduler in the background, see [`SliceScheduler::run`]
+    pub fn spawn(self: Arc<Self>, tick: std::time::Duration, sink: mpsc::Sender<TradePlan>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(self.run(tick, sink))
+    }
+}
+
+/// Whether an order can still be filled
+fn is_open(status: &OrderStatus) -> bool {
+    matches!(status, OrderStatus::Pending | OrderStatus::Active | OrderStatus::PartiallyFilled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use sniper_core::types::ChainRef;
+
+    fn order(id: &str, order_type: OrderType) -> AdvancedOrder {
+        AdvancedOrder {
+            id: id.to_string(),
+            symbol: "ETH/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            order_type,
+            side: "buy".to_string(),
+            amount: Decimal::from(10),
+            time_in_force: TimeInForce::GoodTillCancelled,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: None,
+            trigger: None,
+        }
+    }
+
+    #[test]
+    fn test_twap_slices() {
+        let start = Utc::now();
+        let twap = order("twap-1", OrderType::TWAP { total_amount: 10.0, duration_minutes: 5 });
+        let slices = twap_slices(&twap, &SlicerConfig::default(), start).unwrap();
+
+        assert_eq!(slices.len(), 5);
+        assert!(slices.iter().all(|slice| slice.amount == Decimal::from(2)));
+        assert_eq!(slices[4].release_at, start + Duration::minutes(4));
+    }
+
+    #[test]
+    fn test_vwap_slices_follow_profile() {
+        let mut profile = HistoricalVolumeProfile::new();
+        profile.set_profile("ETH/USDT", vec![1.0, 3.0]);
+        let config = SlicerConfig {
+            vwap_buckets: 2,
+            ..SlicerConfig::default()
+        };
+        let vwap = order("vwap-1", OrderType::VWAP { total_amount: 8.0 });
+        let slices = vwap_slices(&vwap, &config, &profile, Utc::now()).unwrap();
+
+        assert_eq!(slices.len(), 2);
+        assert_eq!(slices[0].amount, Decimal::from(2));
+        assert_eq!(slices[1].amount, Decimal::from(6));
+    }
+
+    #[test]
+    fn test_vwap_slices_refuse_unrepresentable_amount() {
+        let vwap = order("vwap-2", OrderType::VWAP { total_amount: f64::INFINITY });
+        let err = vwap_slices(&vwap, &SlicerConfig::default(), &UniformVolumeProfile, Utc::now()).unwrap_err();
+
+        assert!(matches!(err, OrderError::InvalidAmount(_)));
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_releases_due_children() -> Result<()> {
+        let orders = Arc::new(ShardedOrderManager::new());
+        orders
+            .create_order(order("twap-1", OrderType::TWAP { total_amount: 3.0, duration_minutes: 3 }))
+            .await?;
+        let scheduler = SliceScheduler::new(SlicerConfig::default(), Arc::new(UniformVolumeProfile), orders.clone());
+
+        let start = Utc::now();
+        assert_eq!(scheduler.schedule("twap-1", start).await?, 3);
+
+        let plans = scheduler.release_due(start + Duration::seconds(61)).await;
+        assert_eq!(plans.len(), 2);
+        assert_eq!(plans[0].amount_in, 1000000000000000000);
+        assert!(orders.get_order("twap-1-slice-1").await.is_some());
+        assert_eq!(scheduler.pending_slices(Some("twap-1")).await.len(), 1);
+
+        // Cancelling the parent drops the remaining children
+        orders.cancel_order("twap-1").await?;
+        assert!(scheduler.release_due(start + Duration::minutes(5)).await.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_park_and_unpark_slices() -> Result<()> {
+        let orders = Arc::new(ShardedOrderManager::new());
+        orders
+            .create_order(order("twap-1", OrderType::TWAP { total_amount: 3.0, duration_minutes: 3 }))
+            .await?;
+        let scheduler = SliceScheduler::new(SlicerConfig::default(), Arc::new(UniformVolumeProfile), orders.clone());
+        let start = Utc::now();
+        scheduler.schedule("twap-1", start).await?;
+        scheduler.release_due(start).await;
+
+        // Parking holds back the pending slices and cancels the open child
+        let parked = scheduler.park().await;
+        assert_eq!(parked.parents.len(), 1);
+        assert_eq!(parked.slices.len(), 2);
+        assert!(scheduler.pending_slices(None).await.is_empty());
+        assert_eq!(orders.get_order("twap-1-slice-0").await.unwrap().status, OrderStatus::Cancelled);
+        assert_eq!(orders.get_order("twap-1").await.unwrap().status, OrderStatus::Active);
+
+        // A restarted service picks the parked slices up again
+        let parked: ParkedSlices = serde_json::from_str(&serde_json::to_string(&parked)?)?;
+        let restarted = Arc::new(ShardedOrderManager::new());
+        let scheduler = SliceScheduler::new(SlicerConfig::default(), Arc::new(UniformVolumeProfile), restarted.clone());
+        assert_eq!(scheduler.unpark(parked).await, 2);
+        assert_eq!(restarted.get_order("twap-1").await.unwrap().status, OrderStatus::Active);
+        assert_eq!(scheduler.release_due(start + Duration::minutes(5)).await.len(), 2);
+
+        // Cancelling instead stops the parent
+        restarted
+            .create_order(order("twap-2", OrderType::TWAP { total_amount: 2.0, duration_minutes: 2 }))
+            .await?;
+        scheduler.schedule("twap-2", start).await?;
+        assert_eq!(scheduler.cancel_all().await, vec!["twap-2".to_string()]);
+        assert_eq!(restarted.get_order("twap-2").await.unwrap().status, OrderStatus::Cancelled);
+        assert!(scheduler.pending_slices(None).await.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+//! Conditions that arm an order until an external event fires.
+//!
+//! An order carrying a trigger waits in `Pending` without being evaluated
+//! against prices. It is activated when a matching signal arrives on the
+//! signal bus, or when a price feed reports its watched symbol at or beyond a
+//! level, and from then on is evaluated like any other order.
+
+use crate::OrderError;
+use serde::{Deserialize, Serialize};
+use sniper_core::types::Signal;
+
+/// Side of a price level a trigger waits for
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CrossDirection {
+    /// Fires at or above the level
+    Above,
+    /// Fires at or below the level
+    Below,
+}
+
+/// Event that activates an armed order
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OrderTrigger {
+    /// A signal of a kind, e.g. `liquidity_added`, optionally narrowed to a
+    /// source, a token on either side of the pair and a chain
+    Signal {
+        kind: String,
+        #[serde(default)]
+        source: Option<String>,
+        #[serde(default)]
+        token: Option<String>,
+        #[serde(default)]
+        chain_id: Option<u64>,
+    },
+    /// A symbol's price reaching a level, which may be in another market than the order's
+    PriceCross {
+        symbol: String,
+        level: f64,
+        direction: CrossDirection,
+    },
+}
+
+impl OrderTrigger {
+    /// Check the trigger can ever fire
+    pub fn validate(&self) -> Result<(), OrderError> {
+        match self {
+            OrderTrigger::Signal { kind, .. } if kind.is_empty() => {
+                Err(OrderError::InvalidTrigger("signal kind is empty".to_string()))
+            }
+            OrderTrigger::PriceCross { symbol, .. } if symbol.is_empty() => {
+                Err(OrderError::InvalidTrigger("price symbol is empty".to_string()))
+            }
+            OrderTrigger::PriceCross { level, .. } if !level.is_finite() || *level <= 0.0 => {
+                Err(OrderError::InvalidTrigger(format!("price level {} is not positive", level)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Symbol whose prices the trigger watches, if it is a price trigger
+    pub fn watched_symbol(&self) -> Option<&str> {
+        match self {
+            OrderTrigger::PriceCross { symbol, .. } => Some(symbol),
+            OrderTrigger::Signal { .. } => None,
+        }
+    }
+
+    /// Whether a signal fires the trigger
+    ///
+    /// Tokens are compared case-insensitively, as addresses may be checksummed.
+    pub fn fired_by_signal(&self, signal: &Signal) -> bool {
+        let OrderTrigger::Signal { kind, source, token, chain_id } = self else {
+            return false;
+        };
+        let token_matches = |wanted: &str| {
+            [&signal.token0, &signal.token1]
+                .into_iter()
+                .flatten()
+                .any(|token| token.eq_ignore_ascii_case(wanted))
+        };
+        *kind == signal.kind
+            && source.as_ref().map(|source| *source == signal.source).unwrap_or(true)
+            && chain_id.map(|id| id == signal.chain.id).unwrap_or(true)
+            && token.as_deref().map(token_matches).unwrap_or(true)
+    }
+
+    /// Whether a price of a symbol fires the trigger
+    pub fn fired_by_price(&self, price_symbol: &str, price: f64) -> bool {
+        let OrderTrigger::PriceCross { symbol, level, direction } = self else {
+            return false;
+        };
+        symbol == price_symbol
+            && match direction {
+                CrossDirection::Above => price >= *level,
+                CrossDirection::Below => price <= *level,
+            }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sniper_core::types::ChainRef;
+
+    fn signal(kind: &str, token0: &str, chain_id: u64) -> Signal {
+        Signal {
+            source: "dex".to_string(),
+            kind: kind.to_string(),
+            chain: ChainRef { name: "ethereum".to_string(), id: chain_id },
+            token0: Some(token0.to_string()),
+            token1: Some("0xWETH".to_string()),
+            extra: serde_json::Value::Null,
+            seen_at_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_signal_trigger() {
+        let trigger = OrderTrigger::Signal {
+            kind: "liquidity_added".to_string(),
+            source: None,
+            token: Some("0xabc".to_string()),
+            chain_id: Some(1),
+        };
+        assert!(trigger.fired_by_signal(&signal("liquidity_added", "0xABC", 1)));
+        assert!(!trigger.fired_by_signal(&signal("liquidity_added", "0xdef", 1)));
+        assert!(!trigger.fired_by_signal(&signal("liquidity_added", "0xabc", 56)));
+        assert!(!trigger.fired_by_signal(&signal("pair_created", "0xabc", 1)));
+        assert!(!trigger.fired_by_price("0xabc", 1.0));
+    }
+
+    #[test]
+    fn test_price_trigger() {
+        let trigger: OrderTrigger = serde_json::from_value(serde_json::json!({
+            "type": "price_cross",
+            "symbol": "BTC/USDT",
+            "level": 50000.0,
+            "direction": "below",
+        }))
+        .unwrap();
+        assert_eq!(trigger.watched_symbol(), Some("BTC/USDT"));
+        assert!(!trigger.fired_by_price("BTC/USDT", 50001.0));
+        assert!(trigger.fired_by_price("BTC/USDT", 50000.0));
+        assert!(!trigger.fired_by_price("ETH/USDT", 100.0));
+
+        let invalid = OrderTrigger::PriceCross {
+            symbol: "BTC/USDT".to_string(),
+            level: 0.0,
+            direction: CrossDirection::Above,
+        };
+        assert!(matches!(invalid.validate(), Err(OrderError::InvalidTrigger(_))));
+        assert!(trigger.validate().is_ok());
+    }
+}
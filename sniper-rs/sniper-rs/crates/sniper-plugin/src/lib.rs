@@ -3,12 +3,31 @@
 //! This module provides a flexible plugin system that allows third-party integrations
 //! with the sniper bot framework. Plugins can extend functionality in various areas
 //! including signal processing, strategy execution, risk management, and more.
+//! Plugin calls are [`sandbox`]ed: a plugin that errors, panics or times out
+//! is skipped without affecting the others, and is disabled once it keeps failing.
+//! Plugins can be [`reload`]ed at runtime from a plugins directory, with the
+//! replaced version restored if the new one fails on its first calls.
+
+pub mod reload;
+pub mod sandbox;
 
 use anyhow::Result;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use serde_json::Value;
+use sniper_core::bus::InMemoryBus;
+use sniper_core::events::TradingEvent;
 use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use reload::PluginSlot;
+pub use reload::{
+    parse_version, validate_config, validate_manifest, LoadedPlugin, PluginFactory, PluginInstance, PluginLoader,
+    PluginManifest, PluginRevision, DEFAULT_PROBATION_CALLS,
+};
+pub use sandbox::{run_guarded, PluginFailure, PluginHealth, PluginLimits};
 
 /// Plugin metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,7 +42,7 @@ pub struct PluginMetadata {
 }
 
 /// Plugin configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct PluginConfig {
     pub enabled: bool,
     pub settings: HashMap<String, Value>,
@@ -69,13 +88,78 @@ pub trait Executor: Send + Sync {
     fn metadata(&self) -> &PluginMetadata;
 }
 
+/// Plugin kinds the manager holds in slots
+trait Plugin {
+    fn plugin_metadata(&self) -> &PluginMetadata;
+}
+
+impl Plugin for dyn SignalProcessor {
+    fn plugin_metadata(&self) -> &PluginMetadata {
+        self.metadata()
+    }
+}
+
+impl Plugin for dyn Strategy {
+    fn plugin_metadata(&self) -> &PluginMetadata {
+        self.metadata()
+    }
+}
+
+impl Plugin for dyn RiskAssessor {
+    fn plugin_metadata(&self) -> &PluginMetadata {
+        self.metadata()
+    }
+}
+
+impl Plugin for dyn Executor {
+    fn plugin_metadata(&self) -> &PluginMetadata {
+        self.metadata()
+    }
+}
+
+fn contains<P: ?Sized + Plugin>(slots: &[PluginSlot<P>], plugin_id: &str) -> bool {
+    slots.iter().any(|slot| slot.current().plugin_metadata().id == plugin_id)
+}
+
+/// Register `plugin`, or swap it in for the running version with the same id
+fn install_into<P: ?Sized + Plugin>(
+    slots: &mut Vec<PluginSlot<P>>,
+    plugin: Arc<P>,
+    config: Option<PluginConfig>,
+    probation: u32,
+) -> Result<PluginRevision> {
+    let metadata = plugin.plugin_metadata();
+    let Some(slot) = slots.iter().find(|slot| slot.current().plugin_metadata().id == metadata.id) else {
+        let revision = PluginRevision::new(1, &metadata.version, config);
+        slots.push(PluginSlot::new(plugin, revision.clone()));
+        return Ok(revision);
+    };
+    let current = slot.revision();
+    if parse_version(&metadata.version)? < parse_version(&current.version).unwrap_or_default() {
+        return Err(anyhow::anyhow!(
+            "Plugin {} {} would downgrade the running {}",
+            metadata.id,
+            metadata.version,
+            current.version
+        ));
+    }
+    let revision = PluginRevision::new(slot.next_revision(), &metadata.version, config);
+    slot.replace(plugin, revision.clone(), probation);
+    Ok(revision)
+}
+
 /// Plugin manager for loading and managing plugins
 pub struct PluginManager {
-    signal_processors: Vec<Box<dyn SignalProcessor>>,
-    strategies: Vec<Box<dyn Strategy>>,
-    risk_assessors: Vec<Box<dyn RiskAssessor>>,
-    executors: Vec<Box<dyn Executor>>,
+    signal_processors: Vec<PluginSlot<dyn SignalProcessor>>,
+    strategies: Vec<PluginSlot<dyn Strategy>>,
+    risk_assessors: Vec<PluginSlot<dyn RiskAssessor>>,
+    executors: Vec<PluginSlot<dyn Executor>>,
     config: HashMap<String, PluginConfig>,
+    probation_calls: u32,
+    default_limits: PluginLimits,
+    limits: HashMap<String, PluginLimits>,
+    health: Mutex<HashMap<String, PluginHealth>>,
+    bus: Option<InMemoryBus>,
 }
 
 impl PluginManager {
@@ -87,27 +171,215 @@ impl PluginManager {
             risk_assessors: Vec::new(),
             executors: Vec::new(),
             config: HashMap::new(),
+            probation_calls: DEFAULT_PROBATION_CALLS,
+            default_limits: PluginLimits::default(),
+            limits: HashMap::new(),
+            health: Mutex::new(HashMap::new()),
+            bus: None,
         }
     }
     
+    /// Publish plugin events on a bus
+    pub fn with_bus(mut self, bus: InMemoryBus) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+    
+    /// Calls a reloaded plugin must serve before the version it replaced is dropped
+    pub fn with_probation_calls(mut self, calls: u32) -> Self {
+        self.probation_calls = calls;
+        self
+    }
+    
+    /// Limits applied to plugins without their own
+    pub fn with_default_limits(mut self, limits: PluginLimits) -> Self {
+        self.default_limits = limits;
+        self
+    }
+    
+    /// Set the execution limits of a plugin
+    pub fn set_plugin_limits(&mut self, plugin_id: &str, limits: PluginLimits) {
+        self.limits.insert(plugin_id.to_string(), limits);
+    }
+    
+    /// Get the execution limits of a plugin
+    pub fn plugin_limits(&self, plugin_id: &str) -> &PluginLimits {
+        self.limits.get(plugin_id).unwrap_or(&self.default_limits)
+    }
+    
+    /// Get the call counters of a plugin
+    pub fn plugin_health(&self, plugin_id: &str) -> PluginHealth {
+        self.health.lock().unwrap_or_else(|e| e.into_inner()).get(plugin_id).cloned().unwrap_or_default()
+    }
+    
+    /// Whether a plugin was disabled for failing
+    pub fn is_disabled(&self, plugin_id: &str) -> bool {
+        self.plugin_health(plugin_id).disabled
+    }
+    
+    /// Re-enable a disabled plugin, returning false if it was not disabled
+    pub fn enable_plugin(&self, plugin_id: &str) -> bool {
+        let mut health = self.health.lock().unwrap_or_else(|e| e.into_inner());
+        match health.get_mut(plugin_id).filter(|health| health.disabled) {
+            Some(health) => {
+                health.reset();
+                tracing::info!("Plugin {} re-enabled", plugin_id);
+                true
+            },
+            None => false,
+        }
+    }
+    
+    /// Call a plugin under its limits, recording the outcome
+    ///
+    /// Returns `None` if the plugin is disabled or the call failed. A failure
+    /// of a reloaded version on probation rolls the slot back to the version
+    /// it replaced; any other failure that crosses the plugin's threshold
+    /// disables it and publishes a [`TradingEvent::PluginDisabled`].
+    async fn guarded<P: ?Sized + Plugin, T>(
+        &self,
+        slot: &PluginSlot<P>,
+        plugin: &Arc<P>,
+        call: impl Future<Output = Result<T>>,
+    ) -> Option<T> {
+        let plugin_id = plugin.plugin_metadata().id.as_str();
+        if self.is_disabled(plugin_id) {
+            return None;
+        }
+        let limits = self.plugin_limits(plugin_id);
+        let outcome = run_guarded(limits, call).await;
+        
+        let mut health = self.health.lock().unwrap_or_else(|e| e.into_inner());
+        let health = health.entry(plugin_id.to_string()).or_default();
+        let failure = match outcome {
+            Ok(value) => {
+                health.record_success();
+                slot.succeeded(plugin);
+                return Some(value);
+            },
+            Err(failure) => failure,
+        };
+        tracing::warn!("Plugin {} failed: {}", plugin_id, failure);
+        if let Some(failed) = slot.failed(plugin) {
+            tracing::error!(
+                "Plugin {} {} failed on probation, rolled back to {}",
+                plugin_id,
+                failed.version,
+                slot.revision().version
+            );
+            health.record_rollback(failure);
+            return None;
+        }
+        let last_error = failure.to_string();
+        if health.record_failure(failure, limits) {
+            tracing::error!("Plugin {} disabled after {} consecutive failures", plugin_id, health.consecutive_failures);
+            let event = TradingEvent::PluginDisabled {
+                plugin_id: plugin_id.to_string(),
+                failures: health.consecutive_failures,
+                last_error,
+                timestamp: chrono::Utc::now().timestamp() as u64,
+            };
+            if let Some(bus) = &self.bus {
+                if let Err(e) = bus.publish_now(event.subject(), &event) {
+                    tracing::warn!("Failed to publish plugin event: {}", e);
+                }
+            }
+        }
+        None
+    }
+    
     /// Register a signal processor plugin
     pub fn register_signal_processor(&mut self, processor: Box<dyn SignalProcessor>) {
-        self.signal_processors.push(processor);
+        let revision = PluginRevision::new(1, &processor.metadata().version, None);
+        self.signal_processors.push(PluginSlot::new(Arc::from(processor), revision));
     }
     
     /// Register a strategy plugin
     pub fn register_strategy(&mut self, strategy: Box<dyn Strategy>) {
-        self.strategies.push(strategy);
+        let revision = PluginRevision::new(1, &strategy.metadata().version, None);
+        self.strategies.push(PluginSlot::new(Arc::from(strategy), revision));
     }
     
     /// Register a risk assessor plugin
     pub fn register_risk_assessor(&mut self, assessor: Box<dyn RiskAssessor>) {
-        self.risk_assessors.push(assessor);
+        let revision = PluginRevision::new(1, &assessor.metadata().version, None);
+        self.risk_assessors.push(PluginSlot::new(Arc::from(assessor), revision));
     }
     
     /// Register an executor plugin
     pub fn register_executor(&mut self, executor: Box<dyn Executor>) {
-        self.executors.push(executor);
+        let revision = PluginRevision::new(1, &executor.metadata().version, None);
+        self.executors.push(PluginSlot::new(Arc::from(executor), revision));
+    }
+    
+    /// Install a plugin, atomically swapping out the running version with the same id
+    ///
+    /// The replaced version is kept for rollback until the new one has served
+    /// the probation calls. Installing also clears a disabled plugin, and
+    /// fails if the id belongs to a plugin of another kind or the version is
+    /// older than the running one.
+    pub fn install(&mut self, instance: PluginInstance, config: Option<PluginConfig>) -> Result<PluginRevision> {
+        let plugin_id = instance.metadata().id.clone();
+        let kinds = [
+            contains(&self.signal_processors, &plugin_id),
+            contains(&self.strategies, &plugin_id),
+            contains(&self.risk_assessors, &plugin_id),
+            contains(&self.executors, &plugin_id),
+        ];
+        let kind = match &instance {
+            PluginInstance::SignalProcessor(_) => 0,
+            PluginInstance::Strategy(_) => 1,
+            PluginInstance::RiskAssessor(_) => 2,
+            PluginInstance::Executor(_) => 3,
+        };
+        if kinds.iter().enumerate().any(|(other, found)| *found && other != kind) {
+            return Err(anyhow::anyhow!("Plugin {} is already registered as another kind", plugin_id));
+        }
+        
+        let probation = self.probation_calls;
+        let revision = match instance {
+            PluginInstance::SignalProcessor(plugin) => install_into(&mut self.signal_processors, Arc::from(plugin), config.clone(), probation),
+            PluginInstance::Strategy(plugin) => install_into(&mut self.strategies, Arc::from(plugin), config.clone(), probation),
+            PluginInstance::RiskAssessor(plugin) => install_into(&mut self.risk_assessors, Arc::from(plugin), config.clone(), probation),
+            PluginInstance::Executor(plugin) => install_into(&mut self.executors, Arc::from(plugin), config.clone(), probation),
+        }?;
+        if let Some(config) = config {
+            self.config.insert(plugin_id.clone(), config);
+        }
+        if let Some(health) = self.health.lock().unwrap_or_else(|e| e.into_inner()).get_mut(&plugin_id) {
+            health.reset();
+        }
+        tracing::info!("Plugin {} {} installed as revision {}", plugin_id, revision.version, revision.revision);
+        Ok(revision)
+    }
+    
+    /// Install every plugin a loader finds changed, returning the outcome per manifest
+    pub fn reload_from(&mut self, loader: &mut PluginLoader) -> Result<Vec<(std::path::PathBuf, Result<PluginRevision>)>> {
+        Ok(loader
+            .scan()?
+            .into_iter()
+            .map(|(path, loaded)| {
+                let installed = loaded.and_then(|loaded| self.install(loaded.instance, loaded.manifest.config));
+                if let Err(e) = &installed {
+                    tracing::warn!("Plugin manifest {} not installed: {}", path.display(), e);
+                }
+                (path, installed)
+            })
+            .collect())
+    }
+    
+    /// Get the running revision of a plugin
+    pub fn plugin_revision(&self, plugin_id: &str) -> Option<PluginRevision> {
+        fn find<P: ?Sized + Plugin>(slots: &[PluginSlot<P>], plugin_id: &str) -> Option<PluginRevision> {
+            slots
+                .iter()
+                .find(|slot| slot.current().plugin_metadata().id == plugin_id)
+                .map(PluginSlot::revision)
+        }
+        find(&self.signal_processors, plugin_id)
+            .or_else(|| find(&self.strategies, plugin_id))
+            .or_else(|| find(&self.risk_assessors, plugin_id))
+            .or_else(|| find(&self.executors, plugin_id))
     }
     
     /// Configure a plugin
@@ -121,11 +393,14 @@ impl PluginManager {
     }
     
     /// Process signals through all registered signal processors
+    ///
+    /// Disabled and failing processors are skipped, see [`sandbox`].
     pub async fn process_signals(&self, signal: &Value) -> Result<Vec<Value>> {
         let mut results = Vec::new();
         
-        for processor in &self.signal_processors {
-            if let Some(result) = processor.process_signal(signal).await? {
+        for slot in &self.signal_processors {
+            let processor = slot.current();
+            if let Some(Some(result)) = self.guarded(slot, &processor, processor.process_signal(signal)).await {
                 results.push(result);
             }
         }
@@ -134,11 +409,14 @@ impl PluginManager {
     }
     
     /// Generate plans through all registered strategies
+    ///
+    /// Disabled and failing strategies are skipped, see [`sandbox`].
     pub async fn generate_plans(&self, signal: &Value) -> Result<Vec<Value>> {
         let mut plans = Vec::new();
         
-        for strategy in &self.strategies {
-            if let Some(plan) = strategy.generate_plan(signal).await? {
+        for slot in &self.strategies {
+            let strategy = slot.current();
+            if let Some(Some(plan)) = self.guarded(slot, &strategy, strategy.generate_plan(signal)).await {
                 plans.push(plan);
             }
         }
@@ -150,9 +428,11 @@ impl PluginManager {
     pub async fn assess_risks(&self, plan: &Value) -> Result<Vec<Value>> {
         let mut assessments = Vec::new();
         
-        for assessor in &self.risk_assessors {
-            let assessment = assessor.assess_risk(plan).await?;
-            assessments.push(assessment);
+        for slot in &self.risk_assessors {
+            let assessor = slot.current();
+            if let Some(assessment) = self.guarded(slot, &assessor, assessor.assess_risk(plan)).await {
+                assessments.push(assessment);
+            }
         }
         
         Ok(assessments)
@@ -162,32 +442,34 @@ impl PluginManager {
     pub async fn execute_plans(&self, plan: &Value) -> Result<Vec<Value>> {
         let mut results = Vec::new();
         
-        for executor in &self.executors {
-            let result = executor.execute(plan).await?;
-            results.push(result);
+        for slot in &self.executors {
+            let executor = slot.current();
+            if let Some(result) = self.guarded(slot, &executor, executor.execute(plan)).await {
+                results.push(result);
+            }
         }
         
         Ok(results)
     }
     
-    /// Get all registered plugin metadata
-    pub fn list_plugins(&self) -> Vec<&PluginMetadata> {
+    /// Get the metadata of every registered plugin's running version
+    pub fn list_plugins(&self) -> Vec<PluginMetadata> {
         let mut metadata = Vec::new();
         
         for processor in &self.signal_processors {
-            metadata.push(processor.metadata());
+            metadata.push(processor.current().metadata().clone());
         }
         
         for strategy in &self.strategies {
-            metadata.push(strategy.metadata());
+            metadata.push(strategy.current().metadata().clone());
         }
         
         for assessor in &self.risk_assessors {
-            metadata.push(assessor.metadata());
+            metadata.push(assessor.current().metadata().clone());
         }
         
         for executor in &self.executors {
-            metadata.push(executor.metadata());
+            metadata.push(executor.current().metadata().clone());
         }
         
         metadata
@@ -315,6 +597,176 @@ mod tests {
         println!("Plugin manager tests passed!");
     }
     
+    // Strategy that panics, stalls or errors depending on the signal
+    struct FaultyStrategy {
+        metadata: PluginMetadata,
+    }
+    
+    #[async_trait]
+    impl Strategy for FaultyStrategy {
+        async fn generate_plan(&self, signal: &Value) -> Result<Option<Value>> {
+            match signal["fault"].as_str() {
+                Some("panic") => panic!("faulty strategy"),
+                Some("stall") => {
+                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                    Ok(None)
+                },
+                Some(_) => Err(anyhow::anyhow!("bad signal")),
+                None => Ok(Some(json!({"strategy": self.metadata.name}))),
+            }
+        }
+        
+        fn metadata(&self) -> &PluginMetadata {
+            &self.metadata
+        }
+    }
+    
+    fn plugin_metadata(id: &str) -> PluginMetadata {
+        PluginMetadata {
+            id: id.to_string(),
+            name: id.to_string(),
+            version: "1.0.0".to_string(),
+            description: String::new(),
+            author: "Test".to_string(),
+            capabilities: vec!["strategy".to_string()],
+            config_schema: None,
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_failing_plugin_is_isolated_and_disabled() {
+        let bus = InMemoryBus::new(16);
+        let mut rx = bus.subscribe(sniper_core::events::PLUGIN_DISABLED_SUBJECT);
+        let mut plugin_manager = PluginManager::new().with_bus(bus);
+        plugin_manager.register_strategy(Box::new(FaultyStrategy {
+            metadata: plugin_metadata("faulty"),
+        }));
+        plugin_manager.register_strategy(Box::new(MockStrategy {
+            metadata: plugin_metadata("mock"),
+        }));
+        plugin_manager.set_plugin_limits("faulty", PluginLimits {
+            call_timeout_ms: 20,
+            max_consecutive_failures: 3,
+            max_memory_bytes: None,
+        });
+        
+        // Each fault is contained and the healthy strategy still plans
+        for fault in ["panic", "stall", "error"] {
+            let plans = plugin_manager.generate_plans(&json!({"fault": fault})).await.unwrap();
+            assert_eq!(plans.len(), 1);
+            assert_eq!(plans[0]["strategy"], "mock");
+        }
+        let health = plugin_manager.plugin_health("faulty");
+        assert!(health.disabled);
+        assert_eq!((health.failures, health.timeouts, health.panics), (3, 1, 1));
+        assert_eq!(plugin_manager.plugin_health("mock").failures, 0);
+        match TradingEvent::decode(&rx.recv().await.unwrap()).unwrap() {
+            TradingEvent::PluginDisabled { plugin_id, failures, last_error, .. } => {
+                assert_eq!((plugin_id.as_str(), failures, last_error.as_str()), ("faulty", 3, "bad signal"));
+            },
+            other => panic!("unexpected event {:?}", other),
+        }
+        
+        // Disabled plugins are no longer called until re-enabled
+        assert_eq!(plugin_manager.generate_plans(&json!({})).await.unwrap().len(), 1);
+        assert_eq!(plugin_manager.plugin_health("faulty").calls, 3);
+        assert!(plugin_manager.enable_plugin("faulty"));
+        assert!(!plugin_manager.enable_plugin("mock"));
+        assert_eq!(plugin_manager.generate_plans(&json!({})).await.unwrap().len(), 2);
+    }
+    
+    // Strategy built from a manifest, failing when its config says so
+    struct ConfiguredStrategy {
+        metadata: PluginMetadata,
+        fail: bool,
+    }
+    
+    #[async_trait]
+    impl Strategy for ConfiguredStrategy {
+        async fn generate_plan(&self, _signal: &Value) -> Result<Option<Value>> {
+            if self.fail {
+                return Err(anyhow::anyhow!("broken release"));
+            }
+            Ok(Some(json!({"version": self.metadata.version})))
+        }
+        
+        fn metadata(&self) -> &PluginMetadata {
+            &self.metadata
+        }
+    }
+    
+    fn write_manifest(path: &std::path::Path, version: &str, settings: Value, generation: u64) {
+        let manifest = json!({
+            "factory": "configured",
+            "metadata": {
+                "id": "configured",
+                "name": "Configured Strategy",
+                "version": version,
+                "description": "",
+                "author": "Test",
+                "capabilities": ["strategy"],
+                "config_schema": {
+                    "type": "object",
+                    "properties": {"fail": {"type": "boolean"}},
+                    "additionalProperties": false
+                }
+            },
+            "config": {"enabled": true, "settings": settings}
+        });
+        std::fs::write(path, serde_json::to_vec(&manifest).unwrap()).unwrap();
+        // Distinct modification times regardless of filesystem granularity
+        let modified = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000 + generation);
+        std::fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_hot_reload_with_rollback() {
+        let dir = std::env::temp_dir().join(format!("sniper-plugins-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("configured.json");
+        let mut loader = PluginLoader::new(&dir);
+        loader.register_factory("configured", |manifest| {
+            let fail = manifest.config.as_ref().and_then(|config| config.settings.get("fail")).and_then(Value::as_bool);
+            Ok(PluginInstance::Strategy(Box::new(ConfiguredStrategy {
+                metadata: manifest.metadata.clone(),
+                fail: fail.unwrap_or(false),
+            })))
+        });
+        let mut plugin_manager = PluginManager::new().with_probation_calls(2);
+        let version = |plans: Vec<Value>| plans[0]["version"].as_str().unwrap().to_string();
+        
+        write_manifest(&path, "1.0.0", json!({"fail": false}), 1);
+        let installed = plugin_manager.reload_from(&mut loader).unwrap();
+        assert_eq!(installed[0].1.as_ref().unwrap().revision, 1);
+        assert!(plugin_manager.reload_from(&mut loader).unwrap().is_empty());
+        assert_eq!(version(plugin_manager.generate_plans(&json!({})).await.unwrap()), "1.0.0");
+        
+        // A release failing on probation is rolled back to the running version
+        write_manifest(&path, "1.1.0", json!({"fail": true}), 2);
+        assert_eq!(plugin_manager.reload_from(&mut loader).unwrap()[0].1.as_ref().unwrap().revision, 2);
+        assert!(plugin_manager.generate_plans(&json!({})).await.unwrap().is_empty());
+        assert_eq!(plugin_manager.plugin_revision("configured").unwrap().version, "1.0.0");
+        assert_eq!(version(plugin_manager.generate_plans(&json!({})).await.unwrap()), "1.0.0");
+        assert_eq!(plugin_manager.plugin_health("configured").rollbacks, 1);
+        
+        // Invalid config and downgrades are rejected, leaving the plugin as it was
+        write_manifest(&path, "1.2.0", json!({"fail": "no"}), 3);
+        assert!(plugin_manager.reload_from(&mut loader).unwrap()[0].1.is_err());
+        write_manifest(&path, "0.9.0", json!({"fail": false}), 4);
+        assert!(plugin_manager.reload_from(&mut loader).unwrap()[0].1.is_err());
+        
+        // A healthy release serves its probation and replaces the old one for good
+        write_manifest(&path, "1.2.0", json!({"fail": false}), 5);
+        assert_eq!(plugin_manager.reload_from(&mut loader).unwrap()[0].1.as_ref().unwrap().revision, 3);
+        for _ in 0..3 {
+            assert_eq!(version(plugin_manager.generate_plans(&json!({})).await.unwrap()), "1.2.0");
+        }
+        assert_eq!(plugin_manager.list_plugins()[0].version, "1.2.0");
+        assert_eq!(plugin_manager.get_plugin_config("configured").unwrap().settings["fail"], json!(false));
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
     #[tokio::test]
     async fn test_plugin_configuration() {
         let mut plugin_manager = PluginManager::new();
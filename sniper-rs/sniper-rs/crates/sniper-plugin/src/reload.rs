@@ -0,0 +1,380 @@
+//! Plugin hot-reload.
+//!
+//! A plugins directory holds one JSON [`PluginManifest`] per plugin, naming
+//! the factory that builds its implementation along with its metadata and
+//! configuration. [`PluginLoader::scan`] picks up manifests added or modified
+//! since the last scan, validates them and builds their implementations, and
+//! [`PluginManager::install`](crate::PluginManager::install) swaps each one in
+//! for the running version with the same id. The replaced version is kept
+//! until the new one has served its probation calls, and restored if any of
+//! them fails.
+
+use crate::{Executor, PluginConfig, PluginMetadata, RiskAssessor, SignalProcessor, Strategy};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Calls a new plugin version serves before the version it replaced is dropped
+pub const DEFAULT_PROBATION_CALLS: u32 = 3;
+
+/// A plugin implementation of any kind
+pub enum PluginInstance {
+    SignalProcessor(Box<dyn SignalProcessor>),
+    Strategy(Box<dyn Strategy>),
+    RiskAssessor(Box<dyn RiskAssessor>),
+    Executor(Box<dyn Executor>),
+}
+
+impl PluginInstance {
+    pub fn metadata(&self) -> &PluginMetadata {
+        match self {
+            PluginInstance::SignalProcessor(plugin) => plugin.metadata(),
+            PluginInstance::Strategy(plugin) => plugin.metadata(),
+            PluginInstance::RiskAssessor(plugin) => plugin.metadata(),
+            PluginInstance::Executor(plugin) => plugin.metadata(),
+        }
+    }
+}
+
+/// Plugin description read from the plugins directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginManifest {
+    /// Name of the registered factory that builds the implementation
+    pub factory: String,
+    pub metadata: PluginMetadata,
+    #[serde(default)]
+    pub config: Option<PluginConfig>,
+}
+
+/// Builds a plugin implementation from its manifest
+pub type PluginFactory = Box<dyn Fn(&PluginManifest) -> Result<PluginInstance> + Send + Sync>;
+
+/// A validated manifest and the implementation built from it
+pub struct LoadedPlugin {
+    pub path: PathBuf,
+    pub manifest: PluginManifest,
+    pub instance: PluginInstance,
+}
+
+/// Installed version of a plugin
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PluginRevision {
+    /// Incremented every time a plugin with this id is installed
+    pub revision: u32,
+    pub version: String,
+    pub config: Option<PluginConfig>,
+    pub installed_at: u64,
+}
+
+impl PluginRevision {
+    pub(crate) fn new(revision: u32, version: &str, config: Option<PluginConfig>) -> Self {
+        Self {
+            revision,
+            version: version.to_string(),
+            config,
+            installed_at: chrono::Utc::now().timestamp() as u64,
+        }
+    }
+}
+
+/// Parse a `major.minor.patch` version
+pub fn parse_version(version: &str) -> Result<(u64, u64, u64)> {
+    let parts: Vec<u64> = version
+        .split('.')
+        .map(|part| part.parse::<u64>())
+        .collect::<std::result::Result<_, _>>()
+        .map_err(|_| anyhow::anyhow!("Invalid plugin version {}", version))?;
+    match parts[..] {
+        [major, minor, patch] => Ok((major, minor, patch)),
+        _ => Err(anyhow::anyhow!("Plugin version {} is not major.minor.patch", version)),
+    }
+}
+
+/// Check a manifest's metadata and that its settings satisfy its config schema
+pub fn validate_manifest(manifest: &PluginManifest) -> Result<()> {
+    let metadata = &manifest.metadata;
+    if metadata.id.trim().is_empty() {
+        return Err(anyhow::anyhow!("Plugin id is required"));
+    }
+    parse_version(&metadata.version)?;
+    if let (Some(schema), Some(config)) = (&metadata.config_schema, &manifest.config) {
+        validate_config(schema, &config.settings)
+            .map_err(|e| anyhow::anyhow!("Plugin {} config is invalid: {}", metadata.id, e))?;
+    }
+    Ok(())
+}
+
+/// Validate settings against an object schema
+///
+/// Supports the JSON Schema keywords plugins use: `properties` with `type`,
+/// `enum`, `minimum` and `maximum`, `required`, and `additionalProperties: false`.
+pub fn validate_config(schema: &Value, settings: &HashMap<String, Value>) -> Result<()> {
+    let properties = schema.get("properties").and_then(Value::as_object);
+    for required in schema.get("required").and_then(Value::as_array).into_iter().flatten() {
+        if let Some(name) = required.as_str().filter(|name| !settings.contains_key(*name)) {
+            return Err(anyhow::anyhow!("missing required setting {}", name));
+        }
+    }
+    for (name, value) in settings {
+        let Some(property) = properties.and_then(|properties| properties.get(name)) else {
+            if schema.get("additionalProperties") == Some(&Value::Bool(false)) {
+                return Err(anyhow::anyhow!("unknown setting {}", name));
+            }
+            continue;
+        };
+        if let Some(expected) = property.get("type").and_then(Value::as_str) {
+            let matches = match expected {
+                "number" => value.is_number(),
+                "integer" => value.is_i64() || value.is_u64(),
+                "string" => value.is_string(),
+                "boolean" => value.is_boolean(),
+                "array" => value.is_array(),
+                "object" => value.is_object(),
+                _ => true,
+            };
+            if !matches {
+                return Err(anyhow::anyhow!("setting {} must be of type {}", name, expected));
+            }
+        }
+        if let Some(allowed) = property.get("enum").and_then(Value::as_array) {
+            if !allowed.contains(value) {
+                return Err(anyhow::anyhow!("setting {} must be one of {:?}", name, allowed));
+            }
+        }
+        if let Some(number) = value.as_f64() {
+            if property.get("minimum").and_then(Value::as_f64).is_some_and(|minimum| number < minimum) {
+                return Err(anyhow::anyhow!("setting {} is below its minimum", name));
+            }
+            if property.get("maximum").and_then(Value::as_f64).is_some_and(|maximum| number > maximum) {
+                return Err(anyhow::anyhow!("setting {} is above its maximum", name));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Watches a plugins directory and builds plugins from changed manifests
+pub struct PluginLoader {
+    dir: PathBuf,
+    factories: HashMap<String, PluginFactory>,
+    modified: HashMap<PathBuf, SystemTime>,
+}
+
+impl PluginLoader {
+    /// Create a loader for the `*.json` manifests in `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            factories: HashMap::new(),
+            modified: HashMap::new(),
+        }
+    }
+
+    /// Get the watched directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Register a factory manifests can name
+    pub fn register_factory(
+        &mut self,
+        name: &str,
+        factory: impl Fn(&PluginManifest) -> Result<PluginInstance> + Send + Sync + 'static,
+    ) {
+        self.factories.insert(name.to_string(), Box::new(factory));
+    }
+
+    /// Manifests added or modified since the last scan, in path order
+    pub fn changed_manifests(&mut self) -> Result<Vec<PathBuf>> {
+        let mut changed = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+                continue;
+            }
+            let modified = std::fs::metadata(&path)?.modified()?;
+            if self.modified.insert(path.clone(), modified) != Some(modified) {
+                changed.push(path);
+            }
+        }
+        changed.sort();
+        Ok(changed)
+    }
+
+    /// Read, validate and build the plugin described by a manifest
+    pub fn load(&self, path: &Path) -> Result<LoadedPlugin> {
+        let manifest: PluginManifest = serde_json::from_slice(&std::fs::read(path)?)
+            .map_err(|e| anyhow::anyhow!("Invalid plugin manifest {}: {}", path.display(), e))?;
+        validate_manifest(&manifest)?;
+        let factory = self
+            .factories
+            .get(&manifest.factory)
+            .ok_or_else(|| anyhow::anyhow!("Unknown plugin factory {} in {}", manifest.factory, path.display()))?;
+        let instance = factory(&manifest)?;
+        if instance.metadata().id != manifest.metadata.id {
+            return Err(anyhow::anyhow!(
+                "Factory {} built plugin {} for manifest of {}",
+                manifest.factory,
+                instance.metadata().id,
+                manifest.metadata.id
+            ));
+        }
+        Ok(LoadedPlugin {
+            path: path.to_path_buf(),
+            manifest,
+            instance,
+        })
+    }
+
+    /// Load every manifest changed since the last scan
+    pub fn scan(&mut self) -> Result<Vec<(PathBuf, Result<LoadedPlugin>)>> {
+        Ok(self
+            .changed_manifests()?
+            .into_iter()
+            .map(|path| {
+                let loaded = self.load(&path);
+                (path, loaded)
+            })
+            .collect())
+    }
+}
+
+/// A registered plugin and the version it replaced, while on probation
+pub(crate) struct PluginSlot<P: ?Sized> {
+    state: Mutex<SlotState<P>>,
+}
+
+struct SlotState<P: ?Sized> {
+    current: Arc<P>,
+    revision: PluginRevision,
+    previous: Option<(Arc<P>, PluginRevision)>,
+    /// Highest revision installed, including rolled back ones
+    latest: u32,
+    /// Successful calls left before the previous version is dropped
+    probation: u32,
+}
+
+impl<P: ?Sized> PluginSlot<P> {
+    pub(crate) fn new(plugin: Arc<P>, revision: PluginRevision) -> Self {
+        Self {
+            state: Mutex::new(SlotState {
+                current: plugin,
+                latest: revision.revision,
+                revision,
+                previous: None,
+                probation: 0,
+            }),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, SlotState<P>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn current(&self) -> Arc<P> {
+        self.state().current.clone()
+    }
+
+    pub(crate) fn revision(&self) -> PluginRevision {
+        self.state().revision.clone()
+    }
+
+    /// Revision number of the next install
+    pub(crate) fn next_revision(&self) -> u32 {
+        self.state().latest + 1
+    }
+
+    /// Swap in a new version, keeping the current one until `probation` calls succeed
+    pub(crate) fn replace(&self, plugin: Arc<P>, revision: PluginRevision, probation: u32) {
+        let mut state = self.state();
+        state.latest = state.latest.max(revision.revision);
+        let current = std::mem::replace(&mut state.current, plugin);
+        let replaced = std::mem::replace(&mut state.revision, revision);
+        state.previous = (probation > 0).then_some((current, replaced));
+        state.probation = probation;
+    }
+
+    /// Count a successful call of `plugin` towards its probation
+    pub(crate) fn succeeded(&self, plugin: &Arc<P>) {
+        let mut state = self.state();
+        if state.previous.is_some() && Arc::ptr_eq(&state.current, plugin) {
+            state.probation = state.probation.saturating_sub(1);
+            if state.probation == 0 {
+                state.previous = None;
+            }
+        }
+    }
+
+    /// Restore the previous version if `plugin` failed on probation, returning the failed revision
+    pub(crate) fn failed(&self, plugin: &Arc<P>) -> Option<PluginRevision> {
+        let mut state = self.state();
+        if !Arc::ptr_eq(&state.current, plugin) {
+            return None;
+        }
+        let (previous, revision) = state.previous.take()?;
+        state.current = previous;
+        state.probation = 0;
+        Some(std::mem::replace(&mut state.revision, revision))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_config_against_schema() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "min_liquidity": {"type": "number", "minimum": 0},
+                "mode": {"type": "string", "enum": ["fast", "safe"]},
+                "max_hops": {"type": "integer", "maximum": 4}
+            },
+            "required": ["min_liquidity"],
+            "additionalProperties": false
+        });
+        let settings = |pairs: Value| -> HashMap<String, Value> { serde_json::from_value(pairs).unwrap() };
+
+        assert!(validate_config(&schema, &settings(json!({"min_liquidity": 1000, "mode": "safe", "max_hops": 2}))).is_ok());
+        for invalid in [
+            json!({}),
+            json!({"min_liquidity": "1000"}),
+            json!({"min_liquidity": -1}),
+            json!({"min_liquidity": 1, "mode": "yolo"}),
+            json!({"min_liquidity": 1, "max_hops": 2.5}),
+            json!({"min_liquidity": 1, "max_hops": 5}),
+            json!({"min_liquidity": 1, "slippage": 0.5}),
+        ] {
+            assert!(validate_config(&schema, &settings(invalid.clone())).is_err(), "{}", invalid);
+        }
+
+        assert_eq!(parse_version("1.10.0").unwrap(), (1, 10, 0));
+        assert!(parse_version("1.0").is_err());
+        assert!(parse_version("v1.0.0").is_err());
+    }
+
+    #[test]
+    fn test_slot_rolls_back_on_probation_failure() {
+        let slot = PluginSlot::new(Arc::new(1), PluginRevision::new(1, "1.0.0", None));
+        let v2 = Arc::new(2);
+        slot.replace(v2.clone(), PluginRevision::new(2, "1.1.0", None), 2);
+        slot.succeeded(&v2);
+        assert_eq!(slot.failed(&v2).unwrap().version, "1.1.0");
+        assert_eq!(*slot.current(), 1);
+        assert_eq!(slot.revision().revision, 1);
+
+        // Once probation is served the previous version is dropped
+        let v3 = Arc::new(3);
+        slot.replace(v3.clone(), PluginRevision::new(3, "1.2.0", None), 1);
+        slot.succeeded(&v3);
+        assert!(slot.failed(&v3).is_none());
+        assert_eq!(*slot.current(), 3);
+    }
+}
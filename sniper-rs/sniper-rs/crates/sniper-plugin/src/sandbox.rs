@@ -0,0 +1,203 @@
+//! Plugin sandboxing and resource limits.
+//!
+//! Every call into a plugin runs under its [`PluginLimits`]: it is cut off
+//! once it exceeds the wall-clock timeout, and a panic is caught instead of
+//! unwinding into the manager. The timeout is checked whenever the call
+//! yields, so a plugin that blocks its thread without awaiting can only be
+//! stopped by the runtime hosting it, which is also where the memory cap of
+//! WASM instances is enforced. Outcomes are counted in the plugin's
+//! [`PluginHealth`], and a plugin that fails `max_consecutive_failures` calls
+//! in a row is disabled until re-enabled.
+
+use anyhow::Result;
+use futures::FutureExt;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::any::Any;
+use std::fmt;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::time::Duration;
+
+/// Default wall-clock budget of a plugin call
+pub const DEFAULT_CALL_TIMEOUT_MS: u64 = 1_000;
+
+/// Default consecutive failed calls before a plugin is disabled
+pub const DEFAULT_MAX_CONSECUTIVE_FAILURES: u32 = 5;
+
+/// Execution limits of a plugin
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct PluginLimits {
+    /// Wall-clock budget of each call
+    pub call_timeout_ms: u64,
+    /// Consecutive failed calls before the plugin is disabled, zero to never disable it
+    pub max_consecutive_failures: u32,
+    /// Linear memory cap of a WASM instance; native plugins share the process and are not capped
+    pub max_memory_bytes: Option<u64>,
+}
+
+impl Default for PluginLimits {
+    fn default() -> Self {
+        Self {
+            call_timeout_ms: DEFAULT_CALL_TIMEOUT_MS,
+            max_consecutive_failures: DEFAULT_MAX_CONSECUTIVE_FAILURES,
+            max_memory_bytes: None,
+        }
+    }
+}
+
+/// Why a plugin call failed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "kind", content = "detail", rename_all = "snake_case")]
+pub enum PluginFailure {
+    /// The plugin returned an error
+    Error(String),
+    /// The call exceeded its timeout, in milliseconds
+    Timeout(u64),
+    /// The plugin panicked with this message
+    Panic(String),
+}
+
+impl fmt::Display for PluginFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PluginFailure::Error(error) => write!(f, "{}", error),
+            PluginFailure::Timeout(ms) => write!(f, "timed out after {}ms", ms),
+            PluginFailure::Panic(message) => write!(f, "panicked: {}", message),
+        }
+    }
+}
+
+/// Call counters of a plugin
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PluginHealth {
+    pub calls: u64,
+    pub failures: u64,
+    pub timeouts: u64,
+    pub panics: u64,
+    /// New versions rolled back after failing on probation
+    pub rollbacks: u64,
+    pub consecutive_failures: u32,
+    pub last_failure: Option<PluginFailure>,
+    /// Set once the failure threshold is crossed; disabled plugins are not called
+    pub disabled: bool,
+}
+
+impl PluginHealth {
+    /// Record a successful call
+    pub fn record_success(&mut self) {
+        self.calls += 1;
+        self.consecutive_failures = 0;
+    }
+
+    fn count_failure(&mut self, failure: PluginFailure) {
+        self.calls += 1;
+        self.failures += 1;
+        match failure {
+            PluginFailure::Timeout(_) => self.timeouts += 1,
+            PluginFailure::Panic(_) => self.panics += 1,
+            PluginFailure::Error(_) => {},
+        }
+        self.last_failure = Some(failure);
+    }
+
+    /// Record a failed call, returning true if it disables the plugin
+    pub fn record_failure(&mut self, failure: PluginFailure, limits: &PluginLimits) -> bool {
+        self.count_failure(failure);
+        self.consecutive_failures += 1;
+
+        let crossed = limits.max_consecutive_failures > 0 && self.consecutive_failures >= limits.max_consecutive_failures;
+        if crossed && !self.disabled {
+            self.disabled = true;
+            return true;
+        }
+        false
+    }
+
+    /// Record a failed call of a new version that was rolled back
+    ///
+    /// The failure is not held against the restored version's streak.
+    pub fn record_rollback(&mut self, failure: PluginFailure) {
+        self.count_failure(failure);
+        self.rollbacks += 1;
+        self.consecutive_failures = 0;
+    }
+
+    /// Clear the disabled flag and the failure streak
+    pub fn reset(&mut self) {
+        self.disabled = false;
+        self.consecutive_failures = 0;
+    }
+}
+
+/// Run a plugin call under `limits`, turning errors, timeouts and panics into a [`PluginFailure`]
+pub async fn run_guarded<T>(limits: &PluginLimits, call: impl Future<Output = Result<T>>) -> Result<T, PluginFailure> {
+    let timeout = Duration::from_millis(limits.call_timeout_ms);
+    match tokio::time::timeout(timeout, AssertUnwindSafe(call).catch_unwind()).await {
+        Err(_) => Err(PluginFailure::Timeout(limits.call_timeout_ms)),
+        Ok(Err(panic)) => Err(PluginFailure::Panic(panic_message(panic.as_ref()))),
+        Ok(Ok(Err(e))) => Err(PluginFailure::Error(e.to_string())),
+        Ok(Ok(Ok(value))) => Ok(value),
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_guarded_contains_failures() {
+        let limits = PluginLimits {
+            call_timeout_ms: 20,
+            ..PluginLimits::default()
+        };
+        assert_eq!(run_guarded(&limits, async { Ok(1) }).await, Ok(1));
+        assert_eq!(
+            run_guarded::<()>(&limits, async { Err(anyhow::anyhow!("bad signal")) }).await,
+            Err(PluginFailure::Error("bad signal".to_string()))
+        );
+        assert_eq!(
+            run_guarded::<()>(&limits, async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(())
+            })
+            .await,
+            Err(PluginFailure::Timeout(20))
+        );
+        assert_eq!(
+            run_guarded::<()>(&limits, async { panic!("index out of bounds") }).await,
+            Err(PluginFailure::Panic("index out of bounds".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_consecutive_failures_disable_once() {
+        let limits = PluginLimits {
+            max_consecutive_failures: 2,
+            ..PluginLimits::default()
+        };
+        let mut health = PluginHealth::default();
+        assert!(!health.record_failure(PluginFailure::Timeout(20), &limits));
+        health.record_success();
+        assert!(!health.record_failure(PluginFailure::Error("a".to_string()), &limits));
+        assert!(health.record_failure(PluginFailure::Panic("b".to_string()), &limits));
+        assert!(!health.record_failure(PluginFailure::Panic("c".to_string()), &limits));
+        assert!(health.disabled);
+        assert_eq!((health.calls, health.failures, health.timeouts, health.panics), (5, 4, 1, 2));
+
+        health.reset();
+        assert!(!health.disabled);
+        assert_eq!(health.consecutive_failures, 0);
+    }
+}
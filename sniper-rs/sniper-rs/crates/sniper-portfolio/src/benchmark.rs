@@ -0,0 +1,261 @@
+//! Benchmark-relative performance: beta, alpha, tracking error and
+//! information ratio.
+//!
+//! A benchmark is a weighted basket of symbols, e.g. ETH alone or an
+//! ETH/BTC mix. Its level is sampled alongside the equity curve from the
+//! symbols' marked prices, each normalized to its price at the first sample,
+//! so weights are shares of the basket rather than of raw prices. Series
+//! computed elsewhere can be recorded directly instead. The metrics pair the
+//! portfolio and benchmark returns between consecutive equity samples taken
+//! at the same timestamps.
+
+use crate::equity::{EquityPoint, DEFAULT_MAX_POINTS, SECS_PER_YEAR};
+use crate::PortfolioError;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use utoipa::ToSchema;
+
+/// Symbols making up a benchmark and their weights
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BenchmarkConfig {
+    /// Name reported with the metrics, e.g. "ETH"
+    pub name: String,
+    /// Weight per marked symbol, e.g. {"ETH/USDT": 1.0}
+    pub weights: BTreeMap<String, f64>,
+}
+
+impl BenchmarkConfig {
+    /// Check the basket is non-empty with positive, finite weights
+    pub fn validate(&self) -> Result<(), PortfolioError> {
+        if self.weights.is_empty() {
+            return Err(PortfolioError::InvalidSettings(format!("benchmark {} has no symbols", self.name)));
+        }
+        if let Some((symbol, weight)) = self.weights.iter().find(|(_, weight)| !(weight.is_finite() && **weight > 0.0)) {
+            return Err(PortfolioError::InvalidSettings(format!("benchmark weight of {} must be positive, got {}", symbol, weight)));
+        }
+        Ok(())
+    }
+}
+
+/// Benchmark level at a point in time
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BenchmarkPoint {
+    pub timestamp: u64,
+    pub level: f64,
+}
+
+/// Portfolio performance relative to a benchmark
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct BenchmarkMetrics {
+    pub benchmark: String,
+    /// Paired portfolio and benchmark returns the metrics are taken over
+    pub samples: usize,
+    pub benchmark_return_pct: f64,
+    pub beta: f64,
+    /// Annualized return not explained by beta, with a zero risk-free rate
+    pub alpha_pct: f64,
+    pub correlation: f64,
+    /// Annualized volatility of the returns in excess of the benchmark
+    pub tracking_error_pct: f64,
+    /// Annualized excess return per unit of tracking error
+    pub information_ratio: f64,
+}
+
+/// Sampled benchmark series
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Benchmark {
+    config: BenchmarkConfig,
+    /// Prices of the symbols at the first sample
+    base_prices: BTreeMap<String, f64>,
+    points: Vec<BenchmarkPoint>,
+}
+
+impl Benchmark {
+    /// Create an empty series for a basket
+    pub fn new(config: BenchmarkConfig) -> Self {
+        Self {
+            config,
+            base_prices: BTreeMap::new(),
+            points: Vec::new(),
+        }
+    }
+
+    /// Get the basket the series tracks
+    pub fn config(&self) -> &BenchmarkConfig {
+        &self.config
+    }
+
+    /// Recorded levels, oldest first
+    pub fn points(&self) -> &[BenchmarkPoint] {
+        &self.points
+    }
+
+    /// Record a level computed outside the portfolio
+    pub fn record(&mut self, timestamp: u64, level: f64) {
+        self.points.push(BenchmarkPoint { timestamp, level });
+        if self.points.len() > DEFAULT_MAX_POINTS {
+            let excess = self.points.len() - DEFAULT_MAX_POINTS;
+            self.points.drain(..excess);
+        }
+    }
+
+    /// Sample the basket level from the symbols' prices
+    ///
+    /// Returns false, recording nothing, while a symbol has no positive price.
+    pub fn sample(&mut self, timestamp: u64, price: impl Fn(&str) -> Option<f64>) -> bool {
+        let mut prices = BTreeMap::new();
+        for symbol in self.config.weights.keys() {
+            match price(symbol) {
+                Some(price) if price > 0.0 => prices.insert(symbol.clone(), price),
+                _ => return false,
+            };
+        }
+        if self.base_prices.is_empty() {
+            self.base_prices = prices.clone();
+        }
+
+        let total_weight: f64 = self.config.weights.values().sum();
+        let level = self
+            .config
+            .weights
+            .iter()
+            .map(|(symbol, weight)| weight * prices[symbol] / self.base_prices[symbol])
+            .sum::<f64>()
+            / total_weight;
+        self.record(timestamp, level);
+        true
+    }
+
+    /// Compare the returns of an equity curve with the benchmark's over the same periods
+    pub fn metrics(&self, equity: &[EquityPoint]) -> BenchmarkMetrics {
+        let levels: HashMap<u64, f64> = self.points.iter().map(|point| (point.timestamp, point.level)).collect();
+        // (period length, portfolio return, benchmark return)
+        let periods: Vec<(u64, f64, f64)> = equity
+            .windows(2)
+            .filter_map(|pair| {
+                let (start, end) = (levels.get(&pair[0].timestamp)?, levels.get(&pair[1].timestamp)?);
+                (pair[0].value > 0.0 && *start > 0.0).then(|| {
+                    let elapsed = pair[1].timestamp.saturating_sub(pair[0].timestamp);
+                    (elapsed, pair[1].value / pair[0].value - 1.0, end / start - 1.0)
+                })
+            })
+            .collect();
+
+        let mut metrics = BenchmarkMetrics {
+            benchmark: self.config.name.clone(),
+            samples: periods.len(),
+            benchmark_return_pct: (periods.iter().fold(1.0, |acc, (_, _, b)| acc * (1.0 + b)) - 1.0) * 100.0,
+            ..BenchmarkMetrics::default()
+        };
+        let elapsed_secs: u64 = periods.iter().map(|(elapsed, _, _)| elapsed).sum();
+        if periods.len() < 2 || elapsed_secs == 0 {
+            return metrics;
+        }
+
+        let n = periods.len() as f64;
+        let periods_per_year = SECS_PER_YEAR / (elapsed_secs as f64 / n);
+        let mean_portfolio = periods.iter().map(|(_, p, _)| p).sum::<f64>() / n;
+        let mean_benchmark = periods.iter().map(|(_, _, b)| b).sum::<f64>() / n;
+        let (mut covariance, mut var_portfolio, mut var_benchmark) = (0.0, 0.0, 0.0);
+        for (_, p, b) in &periods {
+            covariance += (p - mean_portfolio) * (b - mean_benchmark);
+            var_portfolio += (p - mean_portfolio).powi(2);
+            var_benchmark += (b - mean_benchmark).powi(2);
+        }
+        covariance /= n - 1.0;
+        var_portfolio /= n - 1.0;
+        var_benchmark /= n - 1.0;
+
+        let mean_active = mean_portfolio - mean_benchmark;
+        let tracking_error = (periods
+            .iter()
+            .map(|(_, p, b)| (p - b - mean_active).powi(2))
+            .sum::<f64>()
+            / (n - 1.0))
+            .sqrt();
+
+        if var_benchmark > 0.0 {
+            metrics.beta = covariance / var_benchmark;
+        }
+        if var_portfolio > 0.0 && var_benchmark > 0.0 {
+            metrics.correlation = covariance / (var_portfolio * var_benchmark).sqrt();
+        }
+        metrics.alpha_pct = (mean_portfolio - metrics.beta * mean_benchmark) * periods_per_year * 100.0;
+        metrics.tracking_error_pct = tracking_error * periods_per_year.sqrt() * 100.0;
+        if tracking_error > 0.0 {
+            metrics.information_ratio = mean_active / tracking_error * periods_per_year.sqrt();
+        }
+        metrics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(weights: &[(&str, f64)]) -> BenchmarkConfig {
+        BenchmarkConfig {
+            name: "basket".to_string(),
+            weights: weights.iter().map(|(symbol, weight)| (symbol.to_string(), *weight)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_basket_level() {
+        let mut benchmark = Benchmark::new(config(&[("ETH/USDT", 1.0), ("BTC/USDT", 3.0)]));
+        assert!(!benchmark.sample(0, |symbol| (symbol == "ETH/USDT").then_some(3000.0)));
+
+        assert!(benchmark.sample(0, |symbol| Some(if symbol == "ETH/USDT" { 3000.0 } else { 60_000.0 })));
+        // ETH up 20%, BTC down 4%, weighted 1:3
+        assert!(benchmark.sample(60, |symbol| Some(if symbol == "ETH/USDT" { 3600.0 } else { 57_600.0 })));
+        let levels: Vec<f64> = benchmark.points().iter().map(|point| point.level).collect();
+        assert_eq!(levels[0], 1.0);
+        assert!((levels[1] - 1.02).abs() < 1e-12);
+
+        assert!(config(&[]).validate().is_err());
+        assert!(config(&[("ETH/USDT", -1.0)]).validate().is_err());
+        assert!(config(&[("ETH/USDT", 1.0)]).validate().is_ok());
+    }
+
+    #[test]
+    fn test_beta_and_alpha() {
+        let mut benchmark = Benchmark::new(config(&[("ETH/USDT", 1.0)]));
+        let benchmark_returns = [0.01, -0.02, 0.03, -0.01, 0.02];
+        let mut level = 100.0;
+        let mut value = 10_000.0;
+        let mut equity = Vec::new();
+        for (i, benchmark_return) in std::iter::once(&0.0).chain(&benchmark_returns).enumerate() {
+            // Twice the benchmark's moves plus 0.1% per period
+            if i > 0 {
+                level *= 1.0 + benchmark_return;
+                value *= 1.0 + 2.0 * benchmark_return + 0.001;
+            }
+            let timestamp = i as u64 * 3600;
+            benchmark.record(timestamp, level);
+            equity.push(EquityPoint {
+                timestamp,
+                value,
+                realized_pnl: 0.0,
+                unrealized_pnl: value - 10_000.0,
+            });
+        }
+        // An equity sample without a benchmark level is left out
+        equity.push(EquityPoint {
+            timestamp: 6 * 3600,
+            value: 20_000.0,
+            realized_pnl: 0.0,
+            unrealized_pnl: 10_000.0,
+        });
+
+        let metrics = benchmark.metrics(&equity);
+        assert_eq!(metrics.samples, 5);
+        assert!((metrics.beta - 2.0).abs() < 1e-9);
+        assert!((metrics.correlation - 1.0).abs() < 1e-9);
+        assert!((metrics.alpha_pct - 0.1 * 24.0 * 365.0).abs() < 1e-6);
+        assert!(metrics.tracking_error_pct > 0.0);
+        assert!(metrics.information_ratio > 0.0);
+
+        let metrics = Benchmark::new(config(&[("ETH/USDT", 1.0)])).metrics(&equity);
+        assert_eq!((metrics.samples, metrics.beta), (0, 0.0));
+    }
+}
@@ -0,0 +1,273 @@
+//! Historical equity curve and time-weighted performance metrics.
+//!
+//! The curve samples portfolio value at a configurable interval. Drawdown,
+//! risk-adjusted ratios and time-weighted returns are computed from the
+//! per-period returns between consecutive samples.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Seconds in a year, used to annualize returns
+pub(crate) const SECS_PER_YEAR: f64 = 365.0 * 24.0 * 3600.0;
+
+/// Default interval between equity samples in seconds
+pub const DEFAULT_SAMPLE_INTERVAL_SECS: u64 = 60;
+
+/// Default number of samples kept
+pub const DEFAULT_MAX_POINTS: usize = 10_000;
+
+/// Portfolio value at a point in time
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EquityPoint {
+    pub timestamp: u64,
+    pub value: f64,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+}
+
+/// Performance metrics computed from the equity curve
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct HistoricalMetrics {
+    pub start: u64,
+    pub end: u64,
+    pub samples: usize,
+    pub total_return_pct: f64,
+    pub time_weighted_return_pct: f64,
+    pub annualized_return_pct: f64,
+    pub volatility_pct: f64,
+    pub max_drawdown_pct: f64,
+    pub sharpe_ratio: f64,
+    pub sortino_ratio: f64,
+    pub calmar_ratio: f64,
+}
+
+/// Sampled portfolio value over time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EquityCurve {
+    sample_interval_secs: u64,
+    max_points: usize,
+    points: Vec<EquityPoint>,
+}
+
+impl Default for EquityCurve {
+    fn default() -> Self {
+        Self::new(DEFAULT_SAMPLE_INTERVAL_SECS)
+    }
+}
+
+impl EquityCurve {
+    /// Create a new equity curve sampling at most once per interval
+    pub fn new(sample_interval_secs: u64) -> Self {
+        Self {
+            sample_interval_secs,
+            max_points: DEFAULT_MAX_POINTS,
+            points: Vec::new(),
+        }
+    }
+
+    /// Get the sampling interval in seconds
+    pub fn sample_interval_secs(&self) -> u64 {
+        self.sample_interval_secs
+    }
+
+    /// Set the sampling interval in seconds
+    pub fn set_sample_interval(&mut self, sample_interval_secs: u64) {
+        self.sample_interval_secs = sample_interval_secs;
+    }
+
+    /// Set the number of samples kept, dropping the oldest beyond it
+    pub fn set_max_points(&mut self, max_points: usize) {
+        self.max_points = max_points.max(2);
+        self.truncate();
+    }
+
+    /// Record a sample if the interval has elapsed since the last one
+    pub fn record(&mut self, point: EquityPoint) -> bool {
+        if let Some(last) = self.points.last() {
+            if point.timestamp < last.timestamp + self.sample_interval_secs {
+                return false;
+            }
+        }
+
+        self.points.push(point);
+        self.truncate();
+        true
+    }
+
+    /// Recorded samples, oldest first
+    pub fn points(&self) -> &[EquityPoint] {
+        &self.points
+    }
+
+    /// Samples recorded at or after `since`
+    pub fn points_since(&self, since: u64) -> &[EquityPoint] {
+        let start = self.points.partition_point(|point| point.timestamp < since);
+        &self.points[start..]
+    }
+
+    /// Returns between consecutive samples
+    pub fn returns(&self) -> Vec<f64> {
+        self.points
+            .windows(2)
+            .filter(|pair| pair[0].value > 0.0)
+            .map(|pair| pair[1].value / pair[0].value - 1.0)
+            .collect()
+    }
+
+    /// Time-weighted return, chaining the per-period returns
+    pub fn time_weighted_return(&self) -> f64 {
+        self.returns().iter().fold(1.0, |acc, r| acc * (1.0 + r)) - 1.0
+    }
+
+    /// Largest peak-to-trough decline as a fraction of the peak
+    pub fn max_drawdown(&self) -> f64 {
+        let mut peak = f64::MIN;
+        let mut max_drawdown: f64 = 0.0;
+        for point in &self.points {
+            peak = peak.max(point.value);
+            if peak > 0.0 {
+                max_drawdown = max_drawdown.max((peak - point.value) / peak);
+            }
+        }
+        max_drawdown
+    }
+
+    /// Compute performance metrics over the whole curve
+    pub fn metrics(&self) -> HistoricalMetrics {
+        let (first, last) = match (self.points.first(), self.points.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return HistoricalMetrics::default(),
+        };
+
+        let returns = self.returns();
+        let elapsed_secs = last.timestamp.saturating_sub(first.timestamp) as f64;
+        let twr = self.time_weighted_return();
+        let max_drawdown = self.max_drawdown();
+
+        let total_return = if first.value > 0.0 {
+            last.value / first.value - 1.0
+        } else {
+            0.0
+        };
+
+        let annualized_return = if elapsed_secs > 0.0 && twr > -1.0 {
+            (1.0 + twr).powf(SECS_PER_YEAR / elapsed_secs) - 1.0
+        } else {
+            0.0
+        };
+
+        // Annualize per-period statistics by the average spacing between samples
+        let periods_per_year = if returns.is_empty() || elapsed_secs == 0.0 {
+            0.0
+        } else {
+            SECS_PER_YEAR / (elapsed_secs / returns.len() as f64)
+        };
+
+        let mean = if returns.is_empty() {
+            0.0
+        } else {
+            returns.iter().sum::<f64>() / returns.len() as f64
+        };
+        let volatility = if returns.len() > 1 {
+            let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+            variance.sqrt()
+        } else {
+            0.0
+        };
+        let downside_deviation = if returns.is_empty() {
+            0.0
+        } else {
+            (returns.iter().map(|r| r.min(0.0).powi(2)).sum::<f64>() / returns.len() as f64).sqrt()
+        };
+
+        let sharpe_ratio = if volatility > 0.0 {
+            mean / volatility * periods_per_year.sqrt()
+        } else {
+            0.0
+        };
+        let sortino_ratio = if downside_deviation > 0.0 {
+            mean / downside_deviation * periods_per_year.sqrt()
+        } else {
+            0.0
+        };
+        let calmar_ratio = if max_drawdown > 0.0 {
+            annualized_return / max_drawdown
+        } else {
+            0.0
+        };
+
+        HistoricalMetrics {
+            start: first.timestamp,
+            end: last.timestamp,
+            samples: self.points.len(),
+            total_return_pct: total_return * 100.0,
+            time_weighted_return_pct: twr * 100.0,
+            annualized_return_pct: annualized_return * 100.0,
+            volatility_pct: volatility * periods_per_year.sqrt() * 100.0,
+            max_drawdown_pct: max_drawdown * 100.0,
+            sharpe_ratio,
+            sortino_ratio,
+            calmar_ratio,
+        }
+    }
+
+    /// Drop the oldest samples beyond the cap
+    fn truncate(&mut self) {
+        if self.points.len() > self.max_points {
+            let excess = self.points.len() - self.max_points;
+            self.points.drain(..excess);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(timestamp: u64, value: f64) -> EquityPoint {
+        EquityPoint {
+            timestamp,
+            value,
+            realized_pnl: 0.0,
+            unrealized_pnl: value - 10000.0,
+        }
+    }
+
+    #[test]
+    fn test_sampling_interval() {
+        let mut curve = EquityCurve::new(60);
+        assert!(curve.record(point(1000, 10000.0)));
+        assert!(!curve.record(point(1030, 10100.0)));
+        assert!(curve.record(point(1060, 10100.0)));
+        assert_eq!(curve.points().len(), 2);
+        assert_eq!(curve.points_since(1001).len(), 1);
+    }
+
+    #[test]
+    fn test_drawdown_and_returns() {
+        let mut curve = EquityCurve::new(60);
+        for (i, value) in [10000.0, 11000.0, 9900.0, 10450.0, 12000.0].iter().enumerate() {
+            curve.record(point(i as u64 * 60, *value));
+        }
+
+        // Peak 11000 to trough 9900
+        assert!((curve.max_drawdown() - 0.1).abs() < 1e-9);
+        assert!((curve.time_weighted_return() - 0.2).abs() < 1e-9);
+
+        let metrics = curve.metrics();
+        assert_eq!(metrics.samples, 5);
+        assert!((metrics.total_return_pct - 20.0).abs() < 1e-9);
+        assert!((metrics.max_drawdown_pct - 10.0).abs() < 1e-9);
+        assert!(metrics.sharpe_ratio > 0.0);
+        assert!(metrics.sortino_ratio > 0.0);
+        assert!(metrics.calmar_ratio > 0.0);
+    }
+
+    #[test]
+    fn test_empty_curve() {
+        let curve = EquityCurve::default();
+        let metrics = curve.metrics();
+        assert_eq!(metrics.samples, 0);
+        assert_eq!(curve.max_drawdown(), 0.0);
+    }
+}
@@ -0,0 +1,68 @@
+//! Errors returned by the portfolio manager.
+
+use sniper_core::errors::{DomainError, ErrorKind};
+use sniper_core::kill_switch::KillSwitchTripped;
+use sniper_core::types::Decimal;
+use sniper_risk::RiskRejected;
+use thiserror::Error;
+
+/// Failure of a portfolio operation
+#[derive(Debug, Clone, Error)]
+pub enum PortfolioError {
+    #[error("Position {0} not found")]
+    NotFound(String),
+    #[error("Position belongs to tenant {owner}, not {tenant_id}")]
+    WrongTenant { owner: String, tenant_id: String },
+    #[error("Position {id} does not belong to tenant {tenant_id}")]
+    NotOwned { id: String, tenant_id: String },
+    #[error("Position ID {0} is taken by another tenant")]
+    IdTaken(String),
+    #[error("Position size exceeds allocation limits")]
+    ExceedsAllocation,
+    #[error("Fill amount and price must be positive")]
+    InvalidFill,
+    #[error("Invalid fee: {0}")]
+    InvalidFee(String),
+    #[error("Invalid funding rate {0}")]
+    InvalidFundingRate(f64),
+    #[error("Close amount must be positive")]
+    InvalidCloseAmount,
+    #[error("Exit price must be positive")]
+    InvalidPrice,
+    #[error("Close amount {amount} exceeds position size {held}")]
+    Overclose { amount: Decimal, held: Decimal },
+    #[error("Invalid trade amount {0}")]
+    InvalidAmount(Decimal),
+    #[error("Cannot apply a {0} trade without a position")]
+    NoPosition(String),
+    #[error("Invalid allocation settings: {0}")]
+    InvalidSettings(String),
+    #[error("{0} is out of the decimal range")]
+    Overflow(&'static str),
+    #[error(transparent)]
+    Risk(#[from] RiskRejected),
+    #[error(transparent)]
+    Halted(#[from] KillSwitchTripped),
+}
+
+impl DomainError for PortfolioError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            PortfolioError::NotFound(_) => ErrorKind::NotFound,
+            PortfolioError::InvalidFill
+            | PortfolioError::InvalidFee(_)
+            | PortfolioError::InvalidFundingRate(_)
+            | PortfolioError::InvalidCloseAmount
+            | PortfolioError::InvalidPrice
+            | PortfolioError::Overclose { .. }
+            | PortfolioError::InvalidAmount(_)
+            | PortfolioError::NoPosition(_)
+            | PortfolioError::InvalidSettings(_)
+            | PortfolioError::Overflow(_) => ErrorKind::Invalid,
+            PortfolioError::ExceedsAllocation | PortfolioError::IdTaken(_) => ErrorKind::Conflict,
+            PortfolioError::WrongTenant { .. } | PortfolioError::NotOwned { .. } => ErrorKind::Forbidden,
+            PortfolioError::Risk(rejected) => rejected.kind(),
+            PortfolioError::Halted(tripped) => tripped.kind(),
+        }
+    }
+}
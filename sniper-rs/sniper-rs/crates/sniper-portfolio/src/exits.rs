@@ -0,0 +1,197 @@
+//! Stop-loss, trailing-stop and take-profit exits.
+//!
+//! Every marked position is watched by the portfolio's [`ExitMonitor`]
+//! against its exit rules, which default to the `stop_loss_pct` and
+//! `take_profit_pct` of the allocation settings and can be overridden per
+//! position. A triggered exit is first planned on the opposite side of the
+//! position; only once the kill switch and risk checks let the plan through
+//! is the position closed at the triggering price, booking the realized PnL
+//! like any other close, and handed back with the plan so a caller can
+//! execute the exit. Refused exits leave the position open and are retried
+//! at the next evaluation.
+//!
+//! [`ExitMonitor`]: sniper_exit::ExitMonitor
+
+use crate::{PortfolioError, PortfolioManager, RealizedPnlEntry};
+use serde::{Deserialize, Serialize};
+use sniper_core::types::{Decimal, TradePlan};
+use sniper_exit::ExitKind;
+
+/// A position closed by one of its exit rules
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExitTrigger {
+    pub reason: ExitKind,
+    /// PnL percentage the exit triggered at
+    pub pnl_percentage: f64,
+    pub realized: RealizedPnlEntry,
+    /// Plan unwinding the closed amount
+    pub plan: TradePlan,
+}
+
+impl PortfolioManager {
+    /// Plan and close every position whose exit rules triggered since the last call
+    ///
+    /// Exits whose plan is refused keep their position open and stay pending.
+    pub fn evaluate_exits(&mut self) -> Vec<ExitTrigger> {
+        let mut due: Vec<_> = self.pending_exits.drain().map(|(_, signal)| signal).collect();
+        due.sort_by(|a, b| a.position.position_id.cmp(&b.position.position_id));
+
+        let mut triggered = Vec::with_capacity(due.len());
+        for signal in due {
+            let position_id = signal.position.position_id.clone();
+            let Some(position) = self.positions.get(&position_id) else {
+                continue;
+            };
+            let Some(exit_price) = Decimal::from_f64(signal.price) else {
+                tracing::warn!("{:?} exit of position {} at invalid price {}", signal.kind, position_id, signal.price);
+                continue;
+            };
+            let (symbol, chain) = (position.symbol.clone(), position.chain.clone());
+            let side = if position.side == "short" { "buy" } else { "sell" };
+            let plan = position
+                .amount
+                .checked_mul(exit_price)
+                .ok_or(PortfolioError::Overflow("Exit notional"))
+                .and_then(|notional| self.generate_trade_plan(&symbol, chain, notional, side));
+            let plan = match plan {
+                Ok(plan) => plan,
+                Err(e) => {
+                    tracing::warn!("{:?} exit of position {} held open, no plan: {}", signal.kind, position_id, e);
+                    self.pending_exits.insert(position_id, signal);
+                    continue;
+                },
+            };
+            match self.close_position(&position_id, exit_price) {
+                Ok(realized) => {
+                    tracing::info!(
+                        "{:?} exit of position {} at {} ({:.2}%)",
+                        signal.kind, position_id, signal.price, signal.return_pct
+                    );
+                    triggered.push(ExitTrigger {
+                        reason: signal.kind,
+                        pnl_percentage: signal.return_pct,
+                        realized,
+                        plan,
+                    });
+                },
+                Err(e) => tracing::warn!("{:?} exit of position {} failed: {}", signal.kind, position_id, e),
+            }
+        }
+        triggered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AllocationSettings, NettingMode, PositionFill};
+    use sniper_core::kill_switch::{CircuitBreakerConfig, KillSwitch, TripReason};
+    use sniper_core::types::{ChainRef, ExitRules};
+    use std::collections::HashMap;
+
+    fn long(symbol: &str, price: f64) -> PositionFill {
+        PositionFill {
+            symbol: symbol.to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            side: "long".to_string(),
+            amount: Decimal::ONE,
+            price: Decimal::from_f64(price).unwrap(),
+            leverage: 1.0,
+            fee: Decimal::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_stop_loss_and_take_profit() {
+        let mut portfolio = PortfolioManager::new(Decimal::from(100_000), AllocationSettings {
+            max_position_size_pct: 100.0,
+            max_portfolio_risk_pct: 2.0,
+            diversification_targets: HashMap::new(),
+            stop_loss_pct: 5.0,
+            take_profit_pct: 10.0,
+            netting: NettingMode::SeparateLots,
+        });
+        portfolio.apply_fill(long("ETH/USDT", 3000.0)).unwrap();
+        portfolio.apply_fill(long("BTC/USDT", 60_000.0)).unwrap();
+        portfolio.apply_fill(long("SOL/USDT", 100.0)).unwrap();
+
+        portfolio.mark_to_market("ETH/USDT", Decimal::from(2800)).unwrap(); // -6.7%
+        portfolio.mark_to_market("BTC/USDT", Decimal::from(66_000)).unwrap(); // +10%
+        portfolio.mark_to_market("SOL/USDT", Decimal::from(104)).unwrap(); // +4%
+
+        let mut triggered = portfolio.evaluate_exits();
+        triggered.sort_by(|a, b| a.realized.symbol.cmp(&b.realized.symbol));
+        assert_eq!(triggered.len(), 2);
+        assert_eq!(triggered[0].reason, ExitKind::TakeProfit);
+        assert_eq!(triggered[0].realized.realized_pnl, Decimal::from(6000));
+        assert_eq!(triggered[1].reason, ExitKind::StopLoss);
+        assert_eq!(triggered[1].realized.realized_pnl, Decimal::from(-200));
+
+        assert!(triggered[0].plan.amount_in > 0);
+
+        assert_eq!(portfolio.list_positions().len(), 1);
+        assert!(portfolio.evaluate_exits().is_empty());
+    }
+
+    #[test]
+    fn test_trailing_stop_override() {
+        let mut portfolio = PortfolioManager::new(Decimal::from(100_000), AllocationSettings {
+            max_position_size_pct: 100.0,
+            max_portfolio_risk_pct: 2.0,
+            diversification_targets: HashMap::new(),
+            stop_loss_pct: 5.0,
+            take_profit_pct: 50.0,
+            netting: NettingMode::SeparateLots,
+        });
+        let position_id = portfolio.apply_fill(long("ETH/USDT", 3000.0)).unwrap().position.unwrap().id;
+        assert!(portfolio.set_exit_rules("missing", ExitRules::default()).is_err());
+        portfolio
+            .set_exit_rules(&position_id, ExitRules {
+                take_profit_pct: None,
+                stop_loss_pct: Some(5.0),
+                trailing_pct: Some(3.0),
+            })
+            .unwrap();
+
+        portfolio.mark_to_market("ETH/USDT", Decimal::from(3300)).unwrap();
+        assert!(portfolio.evaluate_exits().is_empty());
+        portfolio.mark_to_market("ETH/USDT", Decimal::from(3190)).unwrap(); // 3.3% off the peak, still +6.3%
+
+        let triggered = portfolio.evaluate_exits();
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(triggered[0].reason, ExitKind::TrailingStop);
+        assert_eq!(triggered[0].realized.realized_pnl, Decimal::from(190));
+        assert!(portfolio.list_positions().is_empty());
+        assert!(portfolio.exit_monitor().list().is_empty());
+    }
+
+    #[test]
+    fn test_refused_exit_keeps_position() {
+        let kill_switch = KillSwitch::new(CircuitBreakerConfig::default());
+        let mut portfolio = PortfolioManager::new(Decimal::from(100_000), AllocationSettings {
+            max_position_size_pct: 100.0,
+            max_portfolio_risk_pct: 2.0,
+            diversification_targets: HashMap::new(),
+            stop_loss_pct: 5.0,
+            take_profit_pct: 10.0,
+            netting: NettingMode::SeparateLots,
+        })
+        .with_kill_switch(kill_switch.clone());
+        portfolio.apply_fill(long("ETH/USDT", 3000.0)).unwrap();
+        portfolio.mark_to_market("ETH/USDT", Decimal::from(2800)).unwrap();
+
+        kill_switch.trip(TripReason::Manual { by: "ops".to_string(), reason: "test".to_string() });
+        assert!(portfolio.evaluate_exits().is_empty());
+        assert_eq!(portfolio.list_positions().len(), 1);
+        assert!(portfolio.realized_pnl().unwrap().is_zero());
+
+        assert!(kill_switch.reset("ops"));
+        let triggered = portfolio.evaluate_exits();
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(triggered[0].reason, ExitKind::StopLoss);
+        assert!(portfolio.list_positions().is_empty());
+    }
+}
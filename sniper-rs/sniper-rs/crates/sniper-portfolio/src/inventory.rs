@@ -0,0 +1,50 @@
+//! Net inventory per symbol, shared with quoting engines.
+//!
+//! An [`InventoryBook`] is a cloneable handle that a [`PortfolioManager`]
+//! keeps in sync as positions open, change and close: longs count positive,
+//! shorts negative. Market makers read it on every quote to skew toward a
+//! flat book. With the `hft` feature the book implements hft-common's
+//! `InventorySource`, so an `EnhancedMarketMaking` engine can quote directly
+//! off the portfolio.
+//!
+//! [`PortfolioManager`]: crate::PortfolioManager
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Shared net inventory per symbol
+#[derive(Debug, Clone, Default)]
+pub struct InventoryBook {
+    net: Arc<RwLock<HashMap<String, f64>>>,
+}
+
+impl InventoryBook {
+    /// Create an empty inventory book
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Net inventory of a symbol, `None` if the portfolio never held it
+    pub fn net(&self, symbol: &str) -> Option<f64> {
+        self.net.read().ok()?.get(symbol).copied()
+    }
+
+    /// Net inventory of every symbol the portfolio has held
+    pub fn snapshot(&self) -> HashMap<String, f64> {
+        self.net.read().map(|net| net.clone()).unwrap_or_default()
+    }
+
+    /// Record the net inventory of a symbol
+    pub(crate) fn set(&self, symbol: &str, net: f64) {
+        if let Ok(mut book) = self.net.write() {
+            book.insert(symbol.to_string(), net);
+        }
+    }
+}
+
+#[cfg(feature = "hft")]
+impl hft_common::enhanced_mm::InventorySource for InventoryBook {
+    fn net_inventory(&self, symbol: &str) -> Option<f64> {
+        self.net(symbol)
+    }
+}
@@ -2,27 +2,152 @@
 //! 
 //! This module provides functionality for managing trading portfolios,
 //! including position tracking, risk allocation, and performance analytics.
+//! Position opens and closes and drawdown breaches are published as trading
+//! events on the core bus, position opens and closes also as audit events
+//! correlated by position ID, and net inventory per symbol can be shared with
+//! quoting engines through an [`InventoryBook`]. With a [`RiskEngine`]
+//! attached, the portfolio publishes its exposure into it and every trade plan
+//! must pass its pre-trade checks. An attached [`KillSwitch`] trips on the
+//! portfolio drawdown and halts trade plans. Value-at-Risk, exposure and
+//! concentration are reported through [`RiskReport`], and a [`Rebalancer`]
+//! plans the trades restoring the diversification targets. Marked positions
+//! are watched by an [`ExitMonitor`] against their exit rules, and those past
+//! their stop-loss, trailing stop or take-profit are closed by
+//! [`PortfolioManager::evaluate_exits`]. Whatever the [`NettingMode`], the
+//! positions can also be read netted per symbol and chain as [`NetPosition`]s.
+//! Fill fees, including gas from execution receipts, and periodic funding
+//! charges are carried by the positions and booked with their closes, so
+//! performance is reported both gross and net of trading costs. Against a
+//! configured [`Benchmark`] basket, sampled with the equity curve, the
+//! performance also reports beta, alpha, tracking error and information ratio.
+
+pub mod benchmark;
+pub mod equity;
+pub mod error;
+pub mod exits;
+pub mod inventory;
+pub mod net;
+pub mod rebalance;
+pub mod risk;
 
-use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use sniper_core::types::{ChainRef, TradePlan};
+use utoipa::ToSchema;
+use sniper_core::audit::{self, AuditEvent, AUDIT_SUBJECT};
+use sniper_core::bus::InMemoryBus;
+use sniper_core::events::TradingEvent;
+use sniper_core::kill_switch::KillSwitch;
+use sniper_core::types::{ChainRef, Decimal, ExecReceipt, ExitRules, TradePlan};
+use sniper_exit::{ExitMonitor, ExitSignal, TrackedPosition};
+use sniper_risk::{Exposure, PreTradeRequest, RiskEngine};
 use std::collections::HashMap;
 
+pub use benchmark::{Benchmark, BenchmarkConfig, BenchmarkMetrics, BenchmarkPoint};
+pub use equity::{EquityCurve, EquityPoint, HistoricalMetrics};
+pub use error::PortfolioError;
+pub use exits::ExitTrigger;
+pub use inventory::InventoryBook;
+pub use net::{net_positions, NetPosition};
+pub use rebalance::{ClassAllocation, RebalanceConfig, RebalancePreview, RebalanceTrade, Rebalancer};
+pub use risk::{Concentration, ExposureLine, ExposureReport, RiskReport, ValueAtRisk};
+
+/// Remaining size below which a position counts as fully closed
+const POSITION_DUST: Decimal = Decimal::from_raw(1_000_000); // 1e-12
+
+/// Slippage tolerated by generated trade plans, as the share of output kept
+const MIN_OUT_SHARE: Decimal = Decimal::from_raw(950_000_000_000_000_000); // 0.95
+
+/// Marked prices kept per symbol for correlation estimates
+const PRICE_HISTORY_LEN: usize = 1_000;
+
 /// Portfolio position
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Position {
     pub id: String,
     pub symbol: String,
     pub chain: ChainRef,
-    pub amount: f64,
-    pub entry_price: f64,
-    pub current_price: f64,
+    pub amount: Decimal,
+    pub entry_price: Decimal,
+    pub current_price: Decimal,
     pub side: String, // "long" or "short"
     pub leverage: f64,
-    pub pnl: f64,
+    pub pnl: Decimal, // Unrealized, marked to current_price
     pub pnl_percentage: f64,
     pub created_at: u64,
     pub updated_at: u64,
+    /// Tenant owning the position, if known
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+    /// Fees and gas paid opening the remaining size, in the quote currency
+    #[serde(default)]
+    pub fees: Decimal,
+    /// Funding charged while open, negative when received
+    #[serde(default)]
+    pub funding: Decimal,
+}
+
+impl Position {
+    /// Value of the position at its current price
+    pub fn notional(&self) -> Decimal {
+        self.amount * self.current_price
+    }
+
+    /// 1 for long positions, -1 for short ones
+    fn direction(&self) -> Decimal {
+        direction(&self.side)
+    }
+}
+
+/// 1 for the long side, -1 for the short one
+fn direction(side: &str) -> Decimal {
+    if side == "short" {
+        -Decimal::ONE
+    } else {
+        Decimal::ONE
+    }
+}
+
+/// Result of a checked decimal operation, or an error naming the value that overflowed
+fn in_range(value: Option<Decimal>, what: &'static str) -> Result<Decimal, PortfolioError> {
+    value.ok_or(PortfolioError::Overflow(what))
+}
+
+/// Sum of `values`, or an error naming the total that overflowed
+fn total(values: impl IntoIterator<Item = Decimal>, what: &'static str) -> Result<Decimal, PortfolioError> {
+    in_range(Decimal::checked_sum(values), what)
+}
+
+/// PnL of `amount` entered at `entry_price` and marked at `price`
+fn pnl_at(entry_price: Decimal, price: Decimal, amount: Decimal, direction: Decimal) -> Result<Decimal, PortfolioError> {
+    let pnl = price
+        .checked_sub(entry_price)
+        .and_then(|change| change.checked_mul(amount))
+        .and_then(|pnl| pnl.checked_mul(direction));
+    in_range(pnl, "position PnL")
+}
+
+/// Move from `entry_price` to `price` in percent, positive when it is a gain
+fn pnl_pct_at(entry_price: Decimal, price: Decimal, direction: Decimal) -> Result<f64, PortfolioError> {
+    if !entry_price.is_positive() {
+        return Ok(0.0);
+    }
+    let share = price
+        .checked_sub(entry_price)
+        .and_then(|change| change.checked_div(entry_price))
+        .and_then(|share| share.checked_mul(direction));
+    Ok(in_range(share, "position PnL percentage")?.to_f64() * 100.0)
+}
+
+/// `part` as a percentage of a positive `whole`, in `f64` if the share is out of the decimal range
+fn share_pct(part: Decimal, whole: Decimal) -> f64 {
+    match part.checked_div(whole) {
+        Some(share) => share.to_f64() * 100.0,
+        None => part.to_f64() / whole.to_f64() * 100.0,
+    }
+}
+
+/// `value` times `part / whole`, e.g. the fees carried by part of a position
+fn pro_rata(value: Decimal, part: Decimal, whole: Decimal, what: &'static str) -> Result<Decimal, PortfolioError> {
+    in_range(value.checked_mul(part).and_then(|value| value.checked_div(whole)), what)
 }
 
 /// Portfolio allocation settings
@@ -33,70 +158,544 @@ pub struct AllocationSettings {
     pub diversification_targets: HashMap<String, f64>, // Target allocation by asset class
     pub stop_loss_pct: f64, // Default stop loss percentage
     pub take_profit_pct: f64, // Default take profit percentage
+    #[serde(default)]
+    pub netting: NettingMode, // How fills combine with existing positions
+}
+
+impl AllocationSettings {
+    /// Check that percentages are in range and the targets fit in the portfolio
+    pub fn validate(&self) -> Result<(), PortfolioError> {
+        let limits = [
+            ("max_position_size_pct", self.max_position_size_pct),
+            ("max_portfolio_risk_pct", self.max_portfolio_risk_pct),
+            ("stop_loss_pct", self.stop_loss_pct),
+            ("take_profit_pct", self.take_profit_pct),
+        ];
+        for (name, pct) in limits {
+            if !(pct > 0.0 && pct <= 100.0) {
+                return Err(PortfolioError::InvalidSettings(format!("{} must be in (0, 100], got {}", name, pct)));
+            }
+        }
+        if let Some((class, target)) = self.diversification_targets.iter().find(|(_, target)| **target < 0.0) {
+            return Err(PortfolioError::InvalidSettings(format!("target of {} is negative: {}", class, target)));
+        }
+        let total: f64 = self.diversification_targets.values().sum();
+        if total > 100.0 {
+            return Err(PortfolioError::InvalidSettings(format!("diversification targets add up to {}%", total)));
+        }
+        Ok(())
+    }
+}
+
+/// How a fill combines with an existing position in the same symbol and chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum NettingMode {
+    /// Every fill opens its own lot
+    #[default]
+    SeparateLots,
+    /// Fills net into the existing position, averaging entry or booking realized PnL
+    Net,
+}
+
+/// A fill to apply to the portfolio
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionFill {
+    pub symbol: String,
+    pub chain: ChainRef,
+    pub side: String, // "long" or "short"
+    pub amount: Decimal,
+    pub price: Decimal,
+    pub leverage: f64,
+    /// Fees and gas paid for the fill, in the quote currency
+    #[serde(default)]
+    pub fee: Decimal,
+}
+
+impl PositionFill {
+    /// Add the gas of an execution receipt to the fee, converted at the native token's price
+    pub fn with_receipt_fees(mut self, receipt: &ExecReceipt, native_price: Decimal) -> Result<Self, PortfolioError> {
+        let fee = Decimal::from_wei(receipt.fees_paid_wei)
+            .and_then(|fee| fee.checked_mul(native_price))
+            .and_then(|fee| fee.checked_add(self.fee))
+            .ok_or_else(|| PortfolioError::InvalidFee(format!("{} wei at {} is out of range", receipt.fees_paid_wei, native_price)))?;
+        self.fee = fee;
+        Ok(self)
+    }
+}
+
+/// Result of applying a fill
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FillOutcome {
+    /// Position holding the fill, if any size remains
+    pub position: Option<Position>,
+    /// Realized PnL booked by netting against an opposite position
+    pub realized: Option<RealizedPnlEntry>,
+    /// Whether the fill was merged into an existing position
+    pub netted: bool,
+}
+
+/// Realized PnL booked when (part of) a position is closed
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RealizedPnlEntry {
+    pub id: String,
+    pub position_id: String,
+    pub symbol: String,
+    pub side: String,
+    pub amount: Decimal,
+    pub entry_price: Decimal,
+    pub exit_price: Decimal,
+    pub realized_pnl: Decimal,
+    pub remaining_amount: Decimal,
+    pub closed_at: u64,
+    /// Fees of the closed size, from opening it and from the closing fill
+    #[serde(default)]
+    pub fees: Decimal,
+    /// Funding charged to the closed size while it was open
+    #[serde(default)]
+    pub funding: Decimal,
+}
+
+impl RealizedPnlEntry {
+    /// Realized PnL after fees and funding
+    pub fn net_pnl(&self) -> Decimal {
+        self.realized_pnl - self.fees - self.funding
+    }
+}
+
+/// Funding charged to (or, when negative, received by) a position
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FundingCharge {
+    pub position_id: String,
+    pub symbol: String,
+    /// Funding rate of the period, if charged from one
+    pub rate: Option<f64>,
+    pub amount: Decimal,
+    pub charged_at: u64,
+    #[serde(default)]
+    pub tenant_id: Option<String>,
 }
 
 /// Portfolio performance metrics
+///
+/// PnL figures are gross; `net_pnl` deducts the fees and funding, and
+/// `total_value` is net of them.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PerformanceMetrics {
-    pub total_value: f64,
-    pub total_pnl: f64,
+    pub total_value: Decimal,
+    pub total_pnl: Decimal,
+    pub realized_pnl: Decimal,
+    pub unrealized_pnl: Decimal,
+    /// Fees and gas paid on open and closed positions
+    #[serde(default)]
+    pub fees: Decimal,
+    /// Funding charged to open and closed positions
+    #[serde(default)]
+    pub funding: Decimal,
+    /// Total PnL after fees and funding
+    #[serde(default)]
+    pub net_pnl: Decimal,
     pub total_pnl_percentage: f64,
     pub win_rate: f64,
     pub profit_factor: f64,
     pub sharpe_ratio: f64,
     pub max_drawdown: f64,
     pub positions_count: usize,
+    /// VaR, exposure and concentration at the default confidence
+    #[serde(default)]
+    pub risk: RiskReport,
+    /// Performance relative to the benchmark, if one is configured
+    #[serde(default)]
+    pub benchmark: Option<BenchmarkMetrics>,
 }
 
 /// Portfolio manager
 pub struct PortfolioManager {
     positions: HashMap<String, Position>,
+    realized_ledger: Vec<RealizedPnlEntry>,
+    funding_ledger: Vec<FundingCharge>,
+    equity_curve: EquityCurve,
+    benchmark: Option<Benchmark>,
     allocation_settings: AllocationSettings,
-    initial_capital: f64,
+    initial_capital: Decimal,
+    bus: Option<InMemoryBus>,
+    drawdown_alert_pct: Option<f64>,
+    drawdown_breached: bool,
+    inventory: Option<InventoryBook>,
+    risk: Option<RiskEngine>,
+    kill_switch: Option<KillSwitch>,
+    price_history: HashMap<String, Vec<f64>>,
+    exit_monitor: ExitMonitor,
+    exit_rules: HashMap<String, ExitRules>,
+    pending_exits: HashMap<String, ExitSignal>,
+    tenant_id: Option<String>,
 }
 
 impl PortfolioManager {
     /// Create a new portfolio manager
-    pub fn new(initial_capital: f64, allocation_settings: AllocationSettings) -> Self {
+    pub fn new(initial_capital: Decimal, allocation_settings: AllocationSettings) -> Self {
         Self {
             positions: HashMap::new(),
+            realized_ledger: Vec::new(),
+            funding_ledger: Vec::new(),
+            equity_curve: EquityCurve::default(),
+            benchmark: None,
             allocation_settings,
             initial_capital,
+            bus: None,
+            drawdown_alert_pct: None,
+            drawdown_breached: false,
+            inventory: None,
+            risk: None,
+            kill_switch: None,
+            price_history: HashMap::new(),
+            exit_monitor: ExitMonitor::new(),
+            exit_rules: HashMap::new(),
+            pending_exits: HashMap::new(),
+            tenant_id: None,
+        }
+    }
+
+    /// Restrict the portfolio to one tenant's positions
+    ///
+    /// Positions added without a tenant are stamped with it, and positions
+    /// of other tenants are rejected.
+    pub fn with_tenant(mut self, tenant_id: impl Into<String>) -> Self {
+        self.tenant_id = Some(tenant_id.into());
+        self
+    }
+
+    /// Tenant the portfolio belongs to, if restricted to one
+    pub fn tenant_id(&self) -> Option<&str> {
+        self.tenant_id.as_deref()
+    }
+
+    /// Stamp a position with the portfolio's tenant, rejecting other tenants' positions
+    fn claim(&self, position: &mut Position) -> Result<(), PortfolioError> {
+        let Some(tenant_id) = &self.tenant_id else {
+            return Ok(());
+        };
+        match &position.tenant_id {
+            Some(owner) if owner != tenant_id => {
+                Err(PortfolioError::WrongTenant {
+                owner: owner.clone(),
+                tenant_id: tenant_id.clone(),
+            })
+            },
+            Some(_) => Ok(()),
+            None => {
+                position.tenant_id = Some(tenant_id.clone());
+                Ok(())
+            },
+        }
+    }
+
+    /// Publish portfolio events on a bus
+    pub fn with_bus(mut self, bus: InMemoryBus) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Keep net inventory per symbol up to date in a shared book
+    pub fn with_inventory_book(mut self, book: InventoryBook) -> Self {
+        self.inventory = Some(book);
+        let symbols: Vec<String> = self.positions.values().map(|p| p.symbol.clone()).collect();
+        for symbol in symbols {
+            self.refresh_inventory(&symbol);
+        }
+        self
+    }
+
+    /// Publish exposure to a risk engine and check trade plans against it
+    pub fn with_risk(mut self, risk: RiskEngine) -> Self {
+        self.risk = Some(risk);
+        self.refresh_risk();
+        self
+    }
+
+    /// Feed the drawdown to a kill switch and refuse trade plans while it is tripped
+    pub fn with_kill_switch(mut self, kill_switch: KillSwitch) -> Self {
+        self.kill_switch = Some(kill_switch);
+        self
+    }
+
+    /// Compare performance with a benchmark basket, sampled with the equity curve
+    pub fn with_benchmark(mut self, config: BenchmarkConfig) -> Self {
+        self.benchmark = Some(Benchmark::new(config));
+        self
+    }
+
+    /// Net inventory of a symbol: long amounts minus short amounts
+    pub fn net_inventory(&self, symbol: &str) -> Decimal {
+        self.positions
+            .values()
+            .filter(|position| position.symbol == symbol)
+            .map(|position| position.amount * position.direction())
+            .sum()
+    }
+
+    /// Publish a symbol's net inventory to the shared book, if any
+    fn refresh_inventory(&self, symbol: &str) {
+        if let Some(book) = &self.inventory {
+            book.set(symbol, self.net_inventory(symbol).to_f64());
+        }
+    }
+
+    /// PnL since the start of the UTC day containing `now`: realized today plus open unrealized
+    pub fn daily_pnl(&self, now: u64) -> Result<Decimal, PortfolioError> {
+        let day_start = now - now % 86_400;
+        let realized = total(
+            self.realized_ledger
+                .iter()
+                .filter(|entry| entry.closed_at >= day_start)
+                .map(|entry| entry.realized_pnl),
+            "daily realized PnL",
+        )?;
+        in_range(realized.checked_add(self.unrealized_pnl()?), "daily PnL")
+    }
+
+    /// Exposure the pre-trade risk limits are checked against
+    pub fn exposure(&self) -> Result<Exposure, PortfolioError> {
+        let mut open_positions = HashMap::new();
+        for position in self.positions.values() {
+            *open_positions.entry(position.symbol.clone()).or_insert(0) += 1;
+        }
+        Ok(Exposure {
+            equity: self.calculate_portfolio_value()?.to_f64(),
+            gross_exposure: total(self.positions.values().map(Position::notional), "gross exposure")?.to_f64(),
+            daily_pnl: self.daily_pnl(Self::now())?.to_f64(),
+            open_positions,
+        })
+    }
+
+    /// Publish the portfolio exposure to the risk engine, if any
+    fn refresh_risk(&self) {
+        if let Some(risk) = &self.risk {
+            match self.exposure() {
+                Ok(exposure) => risk.publish_exposure(exposure),
+                Err(e) => tracing::warn!("Failed to publish portfolio exposure: {}", e),
+            }
+        }
+    }
+
+    /// Set the drawdown from the equity peak, in percent, that raises a breach event
+    pub fn set_drawdown_alert(&mut self, threshold_pct: Option<f64>) {
+        self.drawdown_alert_pct = threshold_pct;
+        self.drawdown_breached = false;
+    }
+
+    /// Publish an event if a bus is attached
+    fn emit(&self, event: TradingEvent) {
+        if let Some(bus) = &self.bus {
+            if let Err(e) = bus.publish_now(event.subject(), &event) {
+                tracing::warn!("Failed to publish portfolio event: {}", e);
+            }
+        }
+    }
+
+    /// Publish an audit event about a position if a bus is attached
+    fn audit(&self, action: &str, position_id: &str, tenant_id: Option<&str>, details: String) {
+        if let Some(bus) = &self.bus {
+            let event = AuditEvent::new(action, format!("positions/{}", position_id), position_id)
+                .with_tenant(tenant_id)
+                .with_details(details);
+            if let Err(e) = bus.publish_now(AUDIT_SUBJECT, &event) {
+                tracing::warn!("Failed to publish portfolio audit event: {}", e);
+            }
         }
     }
 
     /// Add a new position to the portfolio
-    pub fn add_position(&mut self, position: Position) -> Result<()> {
+    pub fn add_position(&mut self, mut position: Position) -> Result<(), PortfolioError> {
+        self.claim(&mut position)?;
+        
         // Validate position size against allocation settings
         if !self.validate_position_size(&position)? {
-            return Err(anyhow::anyhow!("Position size exceeds allocation limits"));
+            return Err(PortfolioError::ExceedsAllocation);
         }
         
+        let event = TradingEvent::PositionOpened {
+            position_id: position.id.clone(),
+            symbol: position.symbol.clone(),
+            side: position.side.clone(),
+            amount: position.amount.to_f64(),
+            entry_price: position.entry_price.to_f64(),
+            timestamp: position.created_at,
+        };
+        let details = format!(
+            "{} {} {} at {} with {}x leverage",
+            position.side, position.amount, position.symbol, position.entry_price, position.leverage
+        );
+        self.audit(audit::POSITION_OPENED, &position.id, position.tenant_id.as_deref(), details);
+        let symbol = position.symbol.clone();
         self.positions.insert(position.id.clone(), position);
+        self.refresh_inventory(&symbol);
+        self.refresh_risk();
+        self.emit(event);
         Ok(())
     }
 
+    /// Apply a fill according to the netting mode
+    ///
+    /// In `Net` mode a same-side fill grows the existing position at the
+    /// size-weighted average entry price; an opposite-side fill reduces it,
+    /// booking realized PnL, and any excess opens a position on the fill side.
+    pub fn apply_fill(&mut self, fill: PositionFill) -> Result<FillOutcome, PortfolioError> {
+        if !fill.amount.is_positive() || !fill.price.is_positive() {
+            return Err(PortfolioError::InvalidFill);
+        }
+        if fill.fee.is_negative() {
+            return Err(PortfolioError::InvalidFee(format!("{} is negative", fill.fee)));
+        }
+
+        let existing = match self.allocation_settings.netting {
+            NettingMode::SeparateLots => None,
+            NettingMode::Net => self
+                .positions
+                .values()
+                .filter(|position| position.symbol == fill.symbol && position.chain.id == fill.chain.id)
+                .min_by_key(|position| position.created_at)
+                .cloned(),
+        };
+
+        let existing = match existing {
+            Some(existing) => existing,
+            None => {
+                let position = self.position_from_fill(&fill, fill.amount)?;
+                self.add_position(position.clone())?;
+                return Ok(FillOutcome {
+                    position: Some(position),
+                    realized: None,
+                    netted: false,
+                });
+            },
+        };
+
+        if existing.side == fill.side {
+            let amount = in_range(existing.amount.checked_add(fill.amount), "position amount")?;
+            let cost = existing
+                .entry_price
+                .checked_mul(existing.amount)
+                .zip(fill.price.checked_mul(fill.amount))
+                .and_then(|(held, filled)| held.checked_add(filled));
+            let entry_price = in_range(cost.and_then(|cost| cost.checked_div(amount)), "entry price")?;
+            let direction = direction(&fill.side);
+            let mut merged = existing.clone();
+            merged.amount = amount;
+            merged.entry_price = entry_price;
+            merged.current_price = fill.price;
+            merged.pnl = pnl_at(entry_price, fill.price, amount, direction)?;
+            merged.pnl_percentage = pnl_pct_at(entry_price, fill.price, direction)?;
+            merged.updated_at = Self::now();
+            merged.fees = in_range(merged.fees.checked_add(fill.fee), "position fees")?;
+            self.update_position(&existing.id, merged.clone())?;
+            return Ok(FillOutcome {
+                position: Some(merged),
+                realized: None,
+                netted: true,
+            });
+        }
+
+        // Opposite side: reduce the existing position, flipping with any excess
+        let closed = fill.amount.min(existing.amount);
+        let closing_fee = pro_rata(fill.fee, closed, fill.amount, "closing fee")?;
+        let realized = self.close_position_partial_with_fee(&existing.id, closed, fill.price, closing_fee)?;
+        let excess = fill.amount - closed;
+        let position = if excess > POSITION_DUST {
+            let position = self.position_from_fill(&fill, excess)?;
+            self.add_position(position.clone())?;
+            Some(position)
+        } else {
+            self.positions.get(&existing.id).cloned()
+        };
+
+        Ok(FillOutcome {
+            position,
+            realized: Some(realized),
+            netted: true,
+        })
+    }
+
+    /// New position opened by (part of) a fill, with its share of the fill's fee
+    fn position_from_fill(&self, fill: &PositionFill, amount: Decimal) -> Result<Position, PortfolioError> {
+        let now = Self::now();
+        Ok(Position {
+            id: uuid::Uuid::new_v4().to_string(),
+            symbol: fill.symbol.clone(),
+            chain: fill.chain.clone(),
+            amount,
+            entry_price: fill.price,
+            current_price: fill.price,
+            side: fill.side.clone(),
+            leverage: fill.leverage,
+            pnl: Decimal::ZERO,
+            pnl_percentage: 0.0,
+            created_at: now,
+            updated_at: now,
+            tenant_id: self.tenant_id.clone(),
+            fees: pro_rata(fill.fee, amount, fill.amount, "position fees")?,
+            funding: Decimal::ZERO,
+        })
+    }
+
+    /// Current Unix time in seconds
+    fn now() -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
     /// Update an existing position
-    pub fn update_position(&mut self, position_id: &str, updated_position: Position) -> Result<()> {
+    pub fn update_position(&mut self, position_id: &str, mut updated_position: Position) -> Result<(), PortfolioError> {
         if self.positions.contains_key(position_id) {
+            self.claim(&mut updated_position)?;
+            
             // Validate position size for updated position
             if !self.validate_position_size(&updated_position)? {
-                return Err(anyhow::anyhow!("Updated position size exceeds allocation limits"));
+                return Err(PortfolioError::ExceedsAllocation);
             }
             
-            self.positions.insert(position_id.to_string(), updated_position);
+            let symbol = updated_position.symbol.clone();
+            let previous = self.positions.insert(position_id.to_string(), updated_position);
+            if let Some(previous) = previous.filter(|previous| previous.symbol != symbol) {
+                self.refresh_inventory(&previous.symbol);
+            }
+            self.refresh_inventory(&symbol);
+            self.refresh_risk();
             Ok(())
         } else {
-            Err(anyhow::anyhow!("Position not found"))
+            Err(PortfolioError::NotFound(position_id.to_string()))
         }
     }
 
-    /// Remove a position from the portfolio
-    pub fn remove_position(&mut self, position_id: &str) -> Result<()> {
-        if self.positions.remove(position_id).is_some() {
+    /// Remove a position from the portfolio without realizing its PnL
+    ///
+    /// Fees and funding the position carried are still booked to the realized
+    /// ledger, as a zero-amount entry, so they stay in the portfolio's costs.
+    pub fn remove_position(&mut self, position_id: &str) -> Result<(), PortfolioError> {
+        if let Some(position) = self.positions.remove(position_id) {
+            self.forget_exits(position_id);
+            if !(position.fees.is_zero() && position.funding.is_zero()) {
+                self.realized_ledger.push(RealizedPnlEntry {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    position_id: position_id.to_string(),
+                    symbol: position.symbol.clone(),
+                    side: position.side.clone(),
+                    amount: Decimal::ZERO,
+                    entry_price: position.entry_price,
+                    exit_price: position.current_price,
+                    realized_pnl: Decimal::ZERO,
+                    remaining_amount: Decimal::ZERO,
+                    closed_at: Self::now(),
+                    fees: position.fees,
+                    funding: position.funding,
+                });
+            }
+            self.refresh_inventory(&position.symbol);
+            self.refresh_risk();
             Ok(())
         } else {
-            Err(anyhow::anyhow!("Position not found"))
+            Err(PortfolioError::NotFound(position_id.to_string()))
         }
     }
 
@@ -110,106 +709,624 @@ impl PortfolioManager {
         self.positions.values().collect()
     }
 
+    /// Positions netted into one per symbol and chain
+    pub fn net_positions(&self) -> Vec<NetPosition> {
+        net_positions(self.positions.values())
+    }
+
+    /// Positions belonging to a tenant, counting untagged positions as the portfolio's own
+    pub fn tenant_positions(&self, tenant_id: &str) -> Vec<&Position> {
+        self.positions
+            .values()
+            .filter(|position| self.owner(position) == Some(tenant_id))
+            .collect()
+    }
+
+    fn owner<'a>(&'a self, position: &'a Position) -> Option<&'a str> {
+        position.tenant_id.as_deref().or(self.tenant_id())
+    }
+
+    /// Replace a tenant's positions, e.g. with ones restored from a backup
+    ///
+    /// Unlike `add_position` this skips the allocation checks and events, since
+    /// the positions already existed; their exit rules start from the defaults.
+    pub fn restore_positions(&mut self, tenant_id: &str, positions: Vec<Position>) -> Result<(), PortfolioError> {
+        let mut restored = Vec::with_capacity(positions.len());
+        for mut position in positions {
+            position.tenant_id.get_or_insert_with(|| tenant_id.to_string());
+            self.claim(&mut position)?;
+            if position.tenant_id.as_deref() != Some(tenant_id) {
+                return Err(PortfolioError::NotOwned {
+                    id: position.id,
+                    tenant_id: tenant_id.to_string(),
+                });
+            }
+            if self.positions.get(&position.id).is_some_and(|existing| self.owner(existing) != Some(tenant_id)) {
+                return Err(PortfolioError::IdTaken(position.id));
+            }
+            in_range(position.amount.checked_mul(position.current_price), "position notional")?;
+            restored.push(position);
+        }
+
+        let replaced: Vec<String> = self.tenant_positions(tenant_id).iter().map(|position| position.id.clone()).collect();
+        let mut symbols: Vec<String> = Vec::new();
+        for position_id in replaced {
+            if let Some(position) = self.positions.remove(&position_id) {
+                self.forget_exits(&position_id);
+                symbols.push(position.symbol);
+            }
+        }
+        for position in restored {
+            symbols.push(position.symbol.clone());
+            self.positions.insert(position.id.clone(), position);
+        }
+        symbols.sort();
+        symbols.dedup();
+        for symbol in &symbols {
+            self.refresh_inventory(symbol);
+        }
+        self.refresh_risk();
+        Ok(())
+    }
+
+    /// Last price a symbol was marked at
+    pub fn last_price(&self, symbol: &str) -> Option<f64> {
+        self.price_history.get(symbol)?.last().copied()
+    }
+
+    /// Reprice all positions in a symbol and recompute their PnL
+    ///
+    /// Nothing is repriced if any position's value or PnL at `price` is out
+    /// of the decimal range.
+    pub fn mark_to_market(&mut self, symbol: &str, price: Decimal) -> Result<Vec<Position>, PortfolioError> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        
+        let mut marks = HashMap::new();
+        for position in self.positions.values().filter(|p| p.symbol == symbol) {
+            let direction = position.direction();
+            in_range(position.amount.checked_mul(price), "position notional")?;
+            let pnl = pnl_at(position.entry_price, price, position.amount, direction)?;
+            let pnl_percentage = pnl_pct_at(position.entry_price, price, direction)?;
+            marks.insert(position.id.clone(), (pnl, pnl_percentage));
+        }
+
+        let history = self.price_history.entry(symbol.to_string()).or_default();
+        history.push(price.to_f64());
+        if history.len() > PRICE_HISTORY_LEN {
+            history.drain(..history.len() - PRICE_HISTORY_LEN);
+        }
+
+        let mut updated = Vec::new();
+        for position in self.positions.values_mut().filter(|p| p.symbol == symbol) {
+            (position.pnl, position.pnl_percentage) = marks[&position.id];
+            position.current_price = price;
+            position.updated_at = now;
+            updated.push(position.clone());
+        }
+        if !updated.is_empty() {
+            self.refresh_risk();
+        }
+        for position in &updated {
+            self.watch_exits(position);
+        }
+        for signal in self.exit_monitor.on_price(symbol, price.to_f64()) {
+            self.pending_exits.insert(signal.position.position_id.clone(), signal);
+        }
+        Ok(updated)
+    }
+
+    /// Get the exit monitor watching marked positions
+    pub fn exit_monitor(&self) -> &ExitMonitor {
+        &self.exit_monitor
+    }
+
+    /// Exit rules of a position, defaulting to the allocation stop-loss and take-profit
+    pub fn exit_rules(&self, position_id: &str) -> ExitRules {
+        self.exit_rules.get(position_id).cloned().unwrap_or_else(|| ExitRules {
+            take_profit_pct: Some(self.allocation_settings.take_profit_pct).filter(|pct| *pct > 0.0),
+            stop_loss_pct: Some(self.allocation_settings.stop_loss_pct).filter(|pct| *pct > 0.0),
+            trailing_pct: None,
+        })
+    }
+
+    /// Override the exit rules of an open position
+    pub fn set_exit_rules(&mut self, position_id: &str, rules: ExitRules) -> Result<(), PortfolioError> {
+        if !self.positions.contains_key(position_id) {
+            return Err(PortfolioError::NotFound(position_id.to_string()));
+        }
+        self.exit_monitor.set_rules(position_id, rules.clone());
+        self.exit_rules.insert(position_id.to_string(), rules);
+        Ok(())
+    }
+
+    /// Track a marked position in the exit monitor, or update its size and entry
+    fn watch_exits(&self, position: &Position) {
+        let amount = position.amount.to_wei().unwrap_or(0);
+        let entry_price = position.entry_price.to_f64();
+        if !self.exit_monitor.update(&position.id, entry_price, amount) {
+            self.exit_monitor.track(TrackedPosition::new(
+                &position.id,
+                &position.symbol,
+                position.chain.clone(),
+                &position.side,
+                entry_price,
+                amount,
+                self.exit_rules(&position.id),
+            ));
+        }
+    }
+
+    /// Stop watching a position that is no longer open
+    fn forget_exits(&mut self, position_id: &str) {
+        self.exit_monitor.untrack(position_id);
+        self.exit_rules.remove(position_id);
+        self.pending_exits.remove(position_id);
+    }
+
+    /// Close part of a position at `exit_price`, booking the realized PnL
+    ///
+    /// The remaining size keeps its entry price and is re-marked at its
+    /// current price; a position closed down to zero is removed.
+    pub fn close_position_partial(
+        &mut self,
+        position_id: &str,
+        amount: Decimal,
+        exit_price: Decimal,
+    ) -> Result<RealizedPnlEntry, PortfolioError> {
+        self.close_position_partial_with_fee(position_id, amount, exit_price, Decimal::ZERO)
+    }
+
+    /// Close part of a position, paying `fee` for the closing fill
+    ///
+    /// The closed size takes its share of the fees and funding the position
+    /// carries, which are booked with the realized PnL.
+    pub fn close_position_partial_with_fee(
+        &mut self,
+        position_id: &str,
+        amount: Decimal,
+        exit_price: Decimal,
+        fee: Decimal,
+    ) -> Result<RealizedPnlEntry, PortfolioError> {
+        if !amount.is_positive() {
+            return Err(PortfolioError::InvalidCloseAmount);
+        }
+        if !exit_price.is_positive() {
+            return Err(PortfolioError::InvalidPrice);
+        }
+        if fee.is_negative() {
+            return Err(PortfolioError::InvalidFee(format!("{} is negative", fee)));
+        }
+
+        let position = self
+            .positions
+            .get(position_id)
+            .ok_or_else(|| PortfolioError::NotFound(position_id.to_string()))?;
+        if amount > position.amount.checked_add(POSITION_DUST).unwrap_or(Decimal::MAX) {
+            return Err(PortfolioError::Overclose {
+                amount,
+                held: position.amount,
+            });
+        }
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let direction = position.direction();
+        let amount = amount.min(position.amount);
+        let closed_fees = pro_rata(position.fees, amount, position.amount, "closed fees")?;
+        let closed_funding = pro_rata(position.funding, amount, position.amount, "closed funding")?;
+        let remaining = position.amount - amount;
+        let pnl = pnl_at(position.entry_price, position.current_price, remaining, direction)?;
+        let realized_pnl = pnl_at(position.entry_price, exit_price, amount, direction)?;
+        let fees = in_range(closed_fees.checked_add(fee), "closing fees")?;
+        let position_realized_pnl = in_range(
+            self.position_realized_pnl(position_id)?.checked_add(realized_pnl),
+            "position realized PnL",
+        )?;
+
+        let position = self.positions.get_mut(position_id).expect("position checked above");
+        position.amount = remaining;
+        position.fees -= closed_fees;
+        position.funding -= closed_funding;
+        position.pnl = pnl;
+        position.updated_at = now;
+
+        let entry = RealizedPnlEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            position_id: position_id.to_string(),
+            symbol: position.symbol.clone(),
+            side: position.side.clone(),
+            amount,
+            entry_price: position.entry_price,
+            exit_price,
+            realized_pnl,
+            remaining_amount: position.amount,
+            closed_at: now,
+            fees,
+            funding: closed_funding,
+        };
+
+        let closed = position.amount <= POSITION_DUST;
+        let tenant_id = position.tenant_id.clone();
+        if closed {
+            self.positions.remove(position_id);
+            self.forget_exits(position_id);
+        }
+        self.realized_ledger.push(entry.clone());
+        self.refresh_inventory(&entry.symbol);
+        self.refresh_risk();
+        if closed {
+            let details = format!("{} closed at {} with realized PnL {}", entry.symbol, exit_price, position_realized_pnl);
+            self.audit(audit::POSITION_CLOSED, position_id, tenant_id.as_deref(), details);
+            self.emit(TradingEvent::PositionClosed {
+                position_id: position_id.to_string(),
+                symbol: entry.symbol.clone(),
+                realized_pnl: position_realized_pnl.to_f64(),
+                timestamp: now,
+            });
+        }
+        Ok(entry)
+    }
+
+    /// Close a whole position at `exit_price`, booking the realized PnL
+    pub fn close_position(&mut self, position_id: &str, exit_price: Decimal) -> Result<RealizedPnlEntry, PortfolioError> {
+        let amount = self
+            .positions
+            .get(position_id)
+            .map(|position| position.amount)
+            .ok_or_else(|| PortfolioError::NotFound(position_id.to_string()))?;
+        self.close_position_partial(position_id, amount, exit_price)
+    }
+
+    /// Close a whole position at its marked price, or its entry price if it was never marked
+    pub fn close_position_at_mark(&mut self, position_id: &str) -> Result<RealizedPnlEntry, PortfolioError> {
+        let exit_price = self
+            .positions
+            .get(position_id)
+            .map(|position| if position.current_price.is_positive() { position.current_price } else { position.entry_price })
+            .ok_or_else(|| PortfolioError::NotFound(position_id.to_string()))?;
+        self.close_position(position_id, exit_price)
+    }
+
+    /// Realized PnL ledger, oldest first
+    pub fn realized_ledger(&self) -> &[RealizedPnlEntry] {
+        &self.realized_ledger
+    }
+
+    /// Total realized PnL across all closes
+    pub fn realized_pnl(&self) -> Result<Decimal, PortfolioError> {
+        total(self.realized_ledger.iter().map(|entry| entry.realized_pnl), "realized PnL")
+    }
+
+    /// Realized PnL booked against one position
+    pub fn position_realized_pnl(&self, position_id: &str) -> Result<Decimal, PortfolioError> {
+        total(
+            self.realized_ledger
+                .iter()
+                .filter(|entry| entry.position_id == position_id)
+                .map(|entry| entry.realized_pnl),
+            "position realized PnL",
+        )
+    }
+
+    /// Total unrealized (mark-to-market) PnL of open positions
+    pub fn unrealized_pnl(&self) -> Result<Decimal, PortfolioError> {
+        total(self.positions.values().map(|position| position.pnl), "unrealized PnL")
+    }
+
+    /// Charge funding to an open position, or credit it when negative
+    pub fn charge_funding(&mut self, position_id: &str, amount: Decimal) -> Result<FundingCharge, PortfolioError> {
+        self.book_funding(position_id, amount, None)
+    }
+
+    /// Charge a period's funding rate to the leveraged positions in a symbol
+    ///
+    /// Longs pay `rate` times their notional and shorts receive it, so a
+    /// negative rate runs the other way. Unleveraged (spot) positions pay no
+    /// funding.
+    pub fn apply_funding_rate(&mut self, symbol: &str, rate: f64) -> Result<Vec<FundingCharge>, PortfolioError> {
+        let rate_decimal = Decimal::from_f64(rate).ok_or(PortfolioError::InvalidFundingRate(rate))?;
+        let due: Vec<(String, Decimal)> = self
+            .positions
+            .values()
+            .filter(|position| position.symbol == symbol && position.leverage > 1.0)
+            .map(|position| {
+                let amount = position
+                    .notional()
+                    .checked_mul(rate_decimal)
+                    .and_then(|amount| amount.checked_mul(position.direction()));
+                Ok((position.id.clone(), in_range(amount, "funding charge")?))
+            })
+            .collect::<Result<_, PortfolioError>>()?;
+        due.into_iter()
+            .map(|(position_id, amount)| self.book_funding(&position_id, amount, Some(rate)))
+            .collect()
+    }
+
+    fn book_funding(&mut self, position_id: &str, amount: Decimal, rate: Option<f64>) -> Result<FundingCharge, PortfolioError> {
+        let position = self
+            .positions
+            .get_mut(position_id)
+            .ok_or_else(|| PortfolioError::NotFound(position_id.to_string()))?;
+        position.funding = in_range(position.funding.checked_add(amount), "position funding")?;
+        let charge = FundingCharge {
+            position_id: position_id.to_string(),
+            symbol: position.symbol.clone(),
+            rate,
+            amount,
+            charged_at: Self::now(),
+            tenant_id: position.tenant_id.clone(),
+        };
+        self.funding_ledger.push(charge.clone());
+        Ok(charge)
+    }
+
+    /// Funding charges, oldest first
+    pub fn funding_ledger(&self) -> &[FundingCharge] {
+        &self.funding_ledger
+    }
+
+    /// Fees paid on open positions and booked with closes
+    pub fn fees_paid(&self) -> Result<Decimal, PortfolioError> {
+        let open = self.positions.values().map(|position| position.fees);
+        total(open.chain(self.realized_ledger.iter().map(|entry| entry.fees)), "fees paid")
+    }
+
+    /// Net funding charged across all positions
+    pub fn funding_paid(&self) -> Result<Decimal, PortfolioError> {
+        total(self.funding_ledger.iter().map(|charge| charge.amount), "funding paid")
+    }
+
+    /// Fees and funding paid
+    fn trading_costs(&self) -> Result<Decimal, PortfolioError> {
+        total([self.fees_paid()?, self.funding_paid()?], "trading costs")
+    }
+
+    /// Get the equity curve
+    pub fn equity_curve(&self) -> &EquityCurve {
+        &self.equity_curve
+    }
+
+    /// Set the minimum interval between equity samples
+    pub fn set_equity_sample_interval(&mut self, sample_interval_secs: u64) {
+        self.equity_curve.set_sample_interval(sample_interval_secs);
+    }
+
+    /// Sample the current portfolio value into the equity curve
+    ///
+    /// Returns false if the sampling interval has not elapsed yet, or the
+    /// portfolio value is out of the decimal range. A sample that first
+    /// crosses the drawdown alert threshold raises a breach event; the alert
+    /// re-arms once the drawdown recovers below the threshold.
+    pub fn record_equity(&mut self, timestamp: u64) -> bool {
+        let (realized_pnl, unrealized_pnl, value) = match self.equity_sample() {
+            Ok((realized_pnl, unrealized_pnl, value)) => (realized_pnl, unrealized_pnl, value.to_f64()),
+            Err(e) => {
+                tracing::warn!("Failed to sample portfolio equity: {}", e);
+                return false;
+            },
+        };
+        let recorded = self.equity_curve.record(EquityPoint {
+            timestamp,
+            value,
+            realized_pnl: realized_pnl.to_f64(),
+            unrealized_pnl: unrealized_pnl.to_f64(),
+        });
+        if recorded {
+            if let Some(benchmark) = &mut self.benchmark {
+                let price_history = &self.price_history;
+                benchmark.sample(timestamp, |symbol| price_history.get(symbol)?.last().copied());
+            }
+            self.check_drawdown(value, timestamp);
+            if let Some(kill_switch) = &self.kill_switch {
+                kill_switch.record_drawdown(self.current_drawdown_pct());
+            }
+        }
+        recorded
+    }
+
+    /// Realized and unrealized PnL, and the portfolio value they add up to
+    fn equity_sample(&self) -> Result<(Decimal, Decimal, Decimal), PortfolioError> {
+        Ok((self.realized_pnl()?, self.unrealized_pnl()?, self.calculate_portfolio_value()?))
+    }
+
+    /// Current drawdown from the equity peak, in percent
+    pub fn current_drawdown_pct(&self) -> f64 {
+        let points = self.equity_curve.points();
+        let peak = points.iter().map(|point| point.value).fold(f64::MIN, f64::max);
+        match points.last() {
+            Some(last) if peak > 0.0 => ((peak - last.value) / peak * 100.0).max(0.0),
+            _ => 0.0,
+        }
+    }
+
+    /// Raise a breach event when the drawdown first crosses the alert threshold
+    fn check_drawdown(&mut self, equity: f64, timestamp: u64) {
+        let Some(threshold_pct) = self.drawdown_alert_pct else {
+            return;
+        };
+        let drawdown_pct = self.current_drawdown_pct();
+        if drawdown_pct < threshold_pct {
+            self.drawdown_breached = false;
+            return;
+        }
+        if !self.drawdown_breached {
+            self.drawdown_breached = true;
+            self.emit(TradingEvent::DrawdownBreached {
+                drawdown_pct,
+                threshold_pct,
+                equity,
+                timestamp,
+            });
+        }
+    }
+
+    /// Compute time-weighted performance metrics from the equity curve
+    pub fn historical_metrics(&self) -> HistoricalMetrics {
+        self.equity_curve.metrics()
+    }
+
+    /// Get the benchmark series, if one is configured
+    pub fn benchmark(&self) -> Option<&Benchmark> {
+        self.benchmark.as_ref()
+    }
+
+    /// Beta, alpha, tracking error and information ratio of the equity curve against the benchmark
+    pub fn benchmark_metrics(&self) -> Option<BenchmarkMetrics> {
+        self.benchmark.as_ref().map(|benchmark| benchmark.metrics(self.equity_curve.points()))
+    }
+
     /// Calculate portfolio performance metrics
-    pub fn calculate_performance(&self) -> PerformanceMetrics {
-        let mut total_value = self.initial_capital;
-        let mut total_pnl = 0.0;
+    pub fn calculate_performance(&self) -> Result<PerformanceMetrics, PortfolioError> {
+        let realized_pnl = self.realized_pnl()?;
+        let unrealized_pnl = self.unrealized_pnl()?;
+        let total_pnl = total([realized_pnl, unrealized_pnl], "total PnL")?;
+        let fees = self.fees_paid()?;
+        let funding = self.funding_paid()?;
+        let net_pnl = in_range(total_pnl.checked_sub(self.trading_costs()?), "net PnL")?;
+        let total_value = total([self.initial_capital, net_pnl], "portfolio value")?;
         let mut winning_trades = 0;
-        let mut total_wins = 0.0;
-        let mut total_losses = 0.0;
+        let mut total_wins = Decimal::ZERO;
+        let mut total_losses = Decimal::ZERO;
         
-        for position in self.positions.values() {
-            total_value += position.pnl;
-            total_pnl += position.pnl;
-            
-            if position.pnl > 0.0 {
+        // Open positions and booked closes both count as trades
+        let trade_pnls: Vec<Decimal> = self
+            .positions
+            .values()
+            .map(|position| position.pnl)
+            .chain(self.realized_ledger.iter().map(|entry| entry.realized_pnl))
+            .collect();
+        for pnl in &trade_pnls {
+            if pnl.is_positive() {
                 winning_trades += 1;
-                total_wins += position.pnl;
+                total_wins = total([total_wins, *pnl], "winning PnL")?;
             } else {
-                total_losses += position.pnl.abs();
+                total_losses = in_range(total_losses.checked_sub(*pnl), "losing PnL")?;
             }
         }
         
-        let win_rate = if self.positions.is_empty() {
+        let win_rate = if trade_pnls.is_empty() {
             0.0
         } else {
-            winning_trades as f64 / self.positions.len() as f64
+            winning_trades as f64 / trade_pnls.len() as f64
         };
         
-        let profit_factor = if total_losses > 0.0 {
-            total_wins / total_losses
-        } else if total_wins > 0.0 {
+        let profit_factor = if total_losses.is_positive() {
+            total_wins.checked_div(total_losses).map_or(f64::INFINITY, Decimal::to_f64)
+        } else if total_wins.is_positive() {
             f64::INFINITY
         } else {
             0.0
         };
         
-        let total_pnl_percentage = if self.initial_capital > 0.0 {
-            (total_pnl / self.initial_capital) * 100.0
+        let total_pnl_percentage = if self.initial_capital.is_positive() {
+            share_pct(total_pnl, self.initial_capital)
         } else {
             0.0
         };
         
-        // Simplified Sharpe ratio and max drawdown calculations
-        let sharpe_ratio = if total_pnl > 0.0 && total_losses > 0.0 {
-            total_pnl / total_losses
+        // Use the equity curve once it has history, else fall back to a snapshot estimate
+        let (sharpe_ratio, max_drawdown) = if self.equity_curve.points().len() > 1 {
+            let history = self.equity_curve.metrics();
+            (history.sharpe_ratio, history.max_drawdown_pct)
         } else {
-            0.0
-        };
-        
-        let max_drawdown = if total_losses > 0.0 {
-            (total_losses / self.initial_capital) * 100.0
-        } else {
-            0.0
+            let sharpe_ratio = if total_pnl.is_positive() && total_losses.is_positive() {
+                total_pnl.checked_div(total_losses).map_or(f64::INFINITY, Decimal::to_f64)
+            } else {
+                0.0
+            };
+            let max_drawdown = if total_losses.is_positive() && self.initial_capital.is_positive() {
+                share_pct(total_losses, self.initial_capital)
+            } else {
+                0.0
+            };
+            (sharpe_ratio, max_drawdown)
         };
         
-        PerformanceMetrics {
+        Ok(PerformanceMetrics {
             total_value,
             total_pnl,
+            realized_pnl,
+            unrealized_pnl,
+            fees,
+            funding,
+            net_pnl,
             total_pnl_percentage,
             win_rate,
             profit_factor,
             sharpe_ratio,
             max_drawdown,
             positions_count: self.positions.len(),
-        }
+            risk: self.risk_report(risk::DEFAULT_VAR_CONFIDENCE)?,
+            benchmark: self.benchmark_metrics(),
+        })
+    }
+
+    /// VaR at `confidence` over the equity curve, with exposure and concentration of open positions
+    pub fn risk_report(&self, confidence: f64) -> Result<RiskReport, PortfolioError> {
+        let equity = self.calculate_portfolio_value()?.to_f64();
+        Ok(RiskReport {
+            equity,
+            value_at_risk: ValueAtRisk::from_returns(&self.equity_curve.returns(), confidence, equity),
+            exposure: ExposureReport::from_positions(self.positions.values(), equity),
+            concentration: Concentration::from_exposures(
+                &risk::net_exposure_by_symbol(self.positions.values()),
+                &self.price_history,
+            ),
+        })
     }
 
     /// Validate that a position size is within allocation limits
-    fn validate_position_size(&self, position: &Position) -> Result<bool> {
-        let position_value = position.amount * position.current_price;
-        let portfolio_value = self.calculate_portfolio_value();
+    fn validate_position_size(&self, position: &Position) -> Result<bool, PortfolioError> {
+        let position_value = in_range(position.amount.checked_mul(position.current_price), "position notional")?;
+        let portfolio_value = self.calculate_portfolio_value()?;
         
         // If portfolio is empty, allow the position
-        if portfolio_value == 0.0 && self.initial_capital == 0.0 {
+        if portfolio_value.is_zero() && self.initial_capital.is_zero() {
             return Ok(true);
         }
         
         let total_portfolio_value = portfolio_value;
-        if total_portfolio_value > 0.0 {
-            let position_pct = (position_value / total_portfolio_value) * 100.0;
+        if total_portfolio_value.is_positive() {
+            let position_pct = share_pct(position_value, total_portfolio_value);
             Ok(position_pct <= self.allocation_settings.max_position_size_pct)
         } else {
             Ok(true)
         }
     }
 
-    /// Calculate total portfolio value
-    fn calculate_portfolio_value(&self) -> f64 {
-        let mut value = self.initial_capital;
-        for position in self.positions.values() {
-            value += position.pnl;
-        }
-        value
+    /// Calculate total portfolio value, net of fees and funding
+    fn calculate_portfolio_value(&self) -> Result<Decimal, PortfolioError> {
+        let value = total([self.initial_capital, self.realized_pnl()?, self.unrealized_pnl()?], "portfolio value")?;
+        in_range(value.checked_sub(self.trading_costs()?), "portfolio value")
     }
 
     /// Generate a trade plan based on portfolio allocation
-    pub fn generate_trade_plan(&self, _symbol: &str, chain: ChainRef, amount: f64, _side: &str) -> Result<TradePlan> {
-        // In a real implementation, this would:
-        // 1. Check risk allocation
-        // 2. Validate against portfolio constraints
-        // 3. Generate appropriate trade parameters
-        // 4. Apply position sizing algorithms
+    ///
+    /// `amount` is spent in the quote token and is the notional checked by the
+    /// risk engine, if one is attached.
+    pub fn generate_trade_plan(&self, symbol: &str, chain: ChainRef, amount: Decimal, side: &str) -> Result<TradePlan, PortfolioError> {
+        if let Some(kill_switch) = &self.kill_switch {
+            kill_switch.ensure_armed()?;
+        }
+        if let Some(risk) = &self.risk {
+            risk.check(&PreTradeRequest {
+                symbol: symbol.to_string(),
+                side: side.to_string(),
+                notional: Some(amount.to_f64()),
+            })?;
+        }
+        let amount_in = amount.to_wei().ok_or(PortfolioError::InvalidAmount(amount))?;
+        let min_out = (amount * MIN_OUT_SHARE).to_wei().unwrap_or(0);
+
+        // In a real implementation, this would also:
+        // 1. Validate against portfolio constraints
+        // 2. Generate appropriate trade parameters
+        // 3. Apply position sizing algorithms
         
         // For now, return a placeholder
         Ok(TradePlan {
@@ -217,8 +1334,8 @@ impl PortfolioManager {
             router: "0xRouter".to_string(),
             token_in: "0xTokenIn".to_string(),
             token_out: "0xTokenOut".to_string(),
-            amount_in: (amount * 1e18) as u128, // Convert to wei
-            min_out: ((amount * 0.95) * 1e18) as u128, // 5% slippage
+            amount_in,
+            min_out,
             mode: sniper_core::types::ExecMode::Mempool,
             gas: sniper_core::types::GasPolicy {
                 max_fee_gwei: 50,
@@ -230,6 +1347,8 @@ impl PortfolioManager {
                 trailing_pct: Some(2.0),
             },
             idem_key: format!("portfolio-trade-{}", uuid::Uuid::new_v4()),
+            quote: None,
+            correlation_id: None,
         })
     }
 }
@@ -237,8 +1356,13 @@ impl PortfolioManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sniper_core::errors::{DomainError, ErrorKind};
     use sniper_core::types::{ChainRef, ExecMode, GasPolicy, ExitRules};
 
+    fn dec(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
     #[test]
     fn test_portfolio_manager_creation() {
         let settings = AllocationSettings {
@@ -247,10 +1371,19 @@ mod tests {
             diversification_targets: HashMap::new(),
             stop_loss_pct: 5.0,
             take_profit_pct: 10.0,
+            netting: NettingMode::SeparateLots,
         };
         
-        let portfolio = PortfolioManager::new(10000.0, settings);
-        assert_eq!(portfolio.initial_capital, 10000.0);
+        assert!(settings.validate().is_ok());
+        let mut invalid = settings.clone();
+        invalid.max_position_size_pct = 0.0;
+        assert!(invalid.validate().is_err());
+        let mut invalid = settings.clone();
+        invalid.diversification_targets = HashMap::from([("ETH".to_string(), 60.0), ("BTC".to_string(), 50.0)]);
+        assert_eq!(invalid.validate().unwrap_err().kind(), ErrorKind::Invalid);
+        
+        let portfolio = PortfolioManager::new(dec("10000"), settings);
+        assert_eq!(portfolio.initial_capital, dec("10000"));
         assert_eq!(portfolio.positions.len(), 0);
     }
 
@@ -262,9 +1395,10 @@ mod tests {
             diversification_targets: HashMap::new(),
             stop_loss_pct: 5.0,
             take_profit_pct: 10.0,
+            netting: NettingMode::SeparateLots,
         };
         
-        let mut portfolio = PortfolioManager::new(10000.0, settings);
+        let mut portfolio = PortfolioManager::new(dec("10000"), settings);
         
         let position = Position {
             id: "pos-1".to_string(),
@@ -273,15 +1407,18 @@ mod tests {
                 name: "ethereum".to_string(),
                 id: 1,
             },
-            amount: 0.05, // Smaller amount to stay within limits (0.05 * 51000 = 2550, 2550/10000 = 25.5%)
-            entry_price: 50000.0,
-            current_price: 51000.0,
+            amount: dec("0.05"), // Smaller amount to stay within limits (0.05 * 51000 = 2550, 2550/10000 = 25.5%)
+            entry_price: dec("50000"),
+            current_price: dec("51000"),
             side: "long".to_string(),
             leverage: 1.0,
-            pnl: 500.0,
+            pnl: dec("500"),
             pnl_percentage: 1.0,
             created_at: 1234567890,
             updated_at: 1234567890,
+            tenant_id: None,
+            fees: Decimal::ZERO,
+            funding: Decimal::ZERO,
         };
         
         let result = portfolio.add_position(position);
@@ -297,9 +1434,10 @@ mod tests {
             diversification_targets: HashMap::new(),
             stop_loss_pct: 5.0,
             take_profit_pct: 10.0,
+            netting: NettingMode::SeparateLots,
         };
         
-        let mut portfolio = PortfolioManager::new(10000.0, settings);
+        let mut portfolio = PortfolioManager::new(dec("10000"), settings);
         
         let position = Position {
             id: "pos-1".to_string(),
@@ -308,29 +1446,32 @@ mod tests {
                 name: "ethereum".to_string(),
                 id: 1,
             },
-            amount: 0.05, // Smaller amount to stay within limits
-            entry_price: 50000.0,
-            current_price: 51000.0,
+            amount: dec("0.05"), // Smaller amount to stay within limits
+            entry_price: dec("50000"),
+            current_price: dec("51000"),
             side: "long".to_string(),
             leverage: 1.0,
-            pnl: 500.0,
+            pnl: dec("500"),
             pnl_percentage: 1.0,
             created_at: 1234567890,
             updated_at: 1234567890,
+            tenant_id: None,
+            fees: Decimal::ZERO,
+            funding: Decimal::ZERO,
         };
         
         portfolio.add_position(position.clone()).unwrap();
         
         let mut updated_position = position;
-        updated_position.current_price = 52000.0;
-        updated_position.pnl = 1000.0;
+        updated_position.current_price = dec("52000");
+        updated_position.pnl = dec("1000");
         
         let result = portfolio.update_position("pos-1", updated_position);
         assert!(result.is_ok());
         
         let retrieved = portfolio.get_position("pos-1").unwrap();
-        assert_eq!(retrieved.current_price, 52000.0);
-        assert_eq!(retrieved.pnl, 1000.0);
+        assert_eq!(retrieved.current_price, dec("52000"));
+        assert_eq!(retrieved.pnl, dec("1000"));
     }
 
     #[test]
@@ -341,9 +1482,10 @@ mod tests {
             diversification_targets: HashMap::new(),
             stop_loss_pct: 5.0,
             take_profit_pct: 10.0,
+            netting: NettingMode::SeparateLots,
         };
         
-        let mut portfolio = PortfolioManager::new(10000.0, settings);
+        let mut portfolio = PortfolioManager::new(dec("10000"), settings);
         
         let position = Position {
             id: "pos-1".to_string(),
@@ -352,15 +1494,18 @@ mod tests {
                 name: "ethereum".to_string(),
                 id: 1,
             },
-            amount: 0.05, // Smaller amount to stay within limits
-            entry_price: 50000.0,
-            current_price: 51000.0,
+            amount: dec("0.05"), // Smaller amount to stay within limits
+            entry_price: dec("50000"),
+            current_price: dec("51000"),
             side: "long".to_string(),
             leverage: 1.0,
-            pnl: 500.0,
+            pnl: dec("500"),
             pnl_percentage: 1.0,
             created_at: 1234567890,
             updated_at: 1234567890,
+            tenant_id: None,
+            fees: Decimal::ZERO,
+            funding: Decimal::ZERO,
         };
         
         portfolio.add_position(position).unwrap();
@@ -379,9 +1524,10 @@ mod tests {
             diversification_targets: HashMap::new(),
             stop_loss_pct: 5.0,
             take_profit_pct: 10.0,
+            netting: NettingMode::SeparateLots,
         };
         
-        let mut portfolio = PortfolioManager::new(10000.0, settings);
+        let mut portfolio = PortfolioManager::new(dec("10000"), settings);
         
         let position1 = Position {
             id: "pos-1".to_string(),
@@ -390,15 +1536,18 @@ mod tests {
                 name: "ethereum".to_string(),
                 id: 1,
             },
-            amount: 0.05, // Smaller amount to stay within limits (0.05 * 51000 = 2550)
-            entry_price: 50000.0,
-            current_price: 51000.0,
+            amount: dec("0.05"), // Smaller amount to stay within limits (0.05 * 51000 = 2550)
+            entry_price: dec("50000"),
+            current_price: dec("51000"),
             side: "long".to_string(),
             leverage: 1.0,
-            pnl: 500.0,
+            pnl: dec("500"),
             pnl_percentage: 1.0,
             created_at: 1234567890,
             updated_at: 1234567890,
+            tenant_id: None,
+            fees: Decimal::ZERO,
+            funding: Decimal::ZERO,
         };
         
         let position2 = Position {
@@ -408,25 +1557,30 @@ mod tests {
                 name: "ethereum".to_string(),
                 id: 1,
             },
-            amount: 0.5, // Smaller amount to stay within limits (0.5 * 3100 = 1550)
-            entry_price: 3000.0,
-            current_price: 3100.0,
+            amount: dec("0.5"), // Smaller amount to stay within limits (0.5 * 3100 = 1550)
+            entry_price: dec("3000"),
+            current_price: dec("3100"),
             side: "long".to_string(),
             leverage: 1.0,
-            pnl: 500.0,
+            pnl: dec("500"),
             pnl_percentage: 1.67,
             created_at: 1234567890,
             updated_at: 1234567890,
+            tenant_id: None,
+            fees: Decimal::ZERO,
+            funding: Decimal::ZERO,
         };
         
         portfolio.add_position(position1).unwrap();
         portfolio.add_position(position2).unwrap();
         
-        let performance = portfolio.calculate_performance();
-        assert_eq!(performance.total_value, 11000.0); // 10000 + 500 + 500
-        assert_eq!(performance.total_pnl, 1000.0);
+        let performance = portfolio.calculate_performance().unwrap();
+        assert_eq!(performance.total_value, dec("11000")); // 10000 + 500 + 500
+        assert_eq!(performance.total_pnl, dec("1000"));
         assert_eq!(performance.positions_count, 2);
         assert_eq!(performance.win_rate, 1.0); // All winning positions
+        assert_eq!(performance.risk.exposure.total.positions, 2);
+        assert_eq!(performance.risk.value_at_risk.samples, 0);
     }
 
     #[test]
@@ -437,15 +1591,16 @@ mod tests {
             diversification_targets: HashMap::new(),
             stop_loss_pct: 5.0,
             take_profit_pct: 10.0,
+            netting: NettingMode::SeparateLots,
         };
         
-        let portfolio = PortfolioManager::new(10000.0, settings);
+        let portfolio = PortfolioManager::new(dec("10000"), settings);
         let chain = ChainRef {
             name: "ethereum".to_string(),
             id: 1,
         };
         
-        let trade_plan = portfolio.generate_trade_plan("BTC/USDT", chain, 1.0, "long");
+        let trade_plan = portfolio.generate_trade_plan("BTC/USDT", chain, dec("1"), "long");
         assert!(trade_plan.is_ok());
         
         let plan = trade_plan.unwrap();
@@ -455,4 +1610,602 @@ mod tests {
         assert_eq!(plan.exits.take_profit_pct, Some(10.0));
         assert_eq!(plan.exits.stop_loss_pct, Some(5.0));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_mark_to_market() {
+        let settings = AllocationSettings {
+            max_position_size_pct: 50.0,
+            max_portfolio_risk_pct: 2.0,
+            diversification_targets: HashMap::new(),
+            stop_loss_pct: 5.0,
+            take_profit_pct: 10.0,
+            netting: NettingMode::SeparateLots,
+        };
+        
+        let mut portfolio = PortfolioManager::new(dec("10000"), settings);
+        
+        let long = Position {
+            id: "pos-1".to_string(),
+            symbol: "ETH/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            amount: dec("0.5"),
+            entry_price: dec("3000"),
+            current_price: dec("3000"),
+            side: "long".to_string(),
+            leverage: 1.0,
+            pnl: dec("0"),
+            pnl_percentage: 0.0,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            tenant_id: None,
+            fees: Decimal::ZERO,
+            funding: Decimal::ZERO,
+        };
+        let mut short = long.clone();
+        short.id = "pos-2".to_string();
+        short.side = "short".to_string();
+        let mut other = long.clone();
+        other.id = "pos-3".to_string();
+        other.symbol = "BTC/USDT".to_string();
+        
+        portfolio.add_position(long).unwrap();
+        portfolio.add_position(short).unwrap();
+        portfolio.add_position(other).unwrap();
+        
+        let updated = portfolio.mark_to_market("ETH/USDT", dec("3300")).unwrap();
+        assert_eq!(updated.len(), 2);
+        
+        let long = portfolio.get_position("pos-1").unwrap();
+        assert_eq!(long.current_price, dec("3300"));
+        assert_eq!(long.pnl, dec("150"));
+        assert!((long.pnl_percentage - 10.0).abs() < 1e-9);
+        
+        let short = portfolio.get_position("pos-2").unwrap();
+        assert_eq!(short.pnl, dec("-150"));
+        
+        // Other symbols are untouched
+        assert_eq!(portfolio.get_position("pos-3").unwrap().current_price, dec("3000"));
+        
+        // A price whose value is out of the decimal range is rejected and nothing is repriced
+        let mut doge = portfolio.get_position("pos-3").unwrap().clone();
+        doge.id = "pos-4".to_string();
+        doge.symbol = "DOGE/USDT".to_string();
+        (doge.amount, doge.entry_price, doge.current_price) = (dec("2"), dec("0.5"), dec("0.5"));
+        portfolio.add_position(doge).unwrap();
+        let err = portfolio.mark_to_market("DOGE/USDT", Decimal::MAX).unwrap_err();
+        assert!(matches!(err, PortfolioError::Overflow(_)));
+        assert_eq!(err.kind(), ErrorKind::Invalid);
+        assert_eq!(portfolio.get_position("pos-4").unwrap().current_price, dec("0.5"));
+        assert!(portfolio.calculate_performance().unwrap().unrealized_pnl.is_zero());
+    }
+
+    #[test]
+    fn test_close_position_partial() {
+        let settings = AllocationSettings {
+            max_position_size_pct: 50.0,
+            max_portfolio_risk_pct: 2.0,
+            diversification_targets: HashMap::new(),
+            stop_loss_pct: 5.0,
+            take_profit_pct: 10.0,
+            netting: NettingMode::SeparateLots,
+        };
+        
+        let mut portfolio = PortfolioManager::new(dec("10000"), settings);
+        
+        let position = Position {
+            id: "pos-1".to_string(),
+            symbol: "ETH/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            amount: dec("1"),
+            entry_price: dec("3000"),
+            current_price: dec("3000"),
+            side: "long".to_string(),
+            leverage: 1.0,
+            pnl: dec("0"),
+            pnl_percentage: 0.0,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            tenant_id: None,
+            fees: Decimal::ZERO,
+            funding: Decimal::ZERO,
+        };
+        portfolio.add_position(position).unwrap();
+        portfolio.mark_to_market("ETH/USDT", dec("3200")).unwrap();
+        
+        let entry = portfolio.close_position_partial("pos-1", dec("0.25"), dec("3400")).unwrap();
+        assert_eq!(entry.realized_pnl, dec("100"));
+        assert_eq!(entry.remaining_amount, dec("0.75"));
+        
+        // Remaining size is still marked at the last price
+        let remaining = portfolio.get_position("pos-1").unwrap();
+        assert_eq!(remaining.amount, dec("0.75"));
+        assert_eq!(remaining.pnl, dec("150"));
+        
+        let err = portfolio.close_position_partial("pos-1", dec("1"), dec("3400")).unwrap_err();
+        assert!(matches!(err, PortfolioError::Overclose { .. }));
+        assert_eq!(err.kind(), ErrorKind::Invalid);
+        let err = portfolio.close_position("pos-9", dec("3400")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        
+        portfolio.close_position("pos-1", dec("2900")).unwrap();
+        assert!(portfolio.get_position("pos-1").is_none());
+        assert_eq!(portfolio.realized_ledger().len(), 2);
+        assert_eq!(portfolio.realized_pnl().unwrap(), dec("25")); // 100 - 75
+        assert!(portfolio.unrealized_pnl().unwrap().is_zero());
+        
+        let performance = portfolio.calculate_performance().unwrap();
+        assert_eq!(performance.realized_pnl, dec("25"));
+        assert_eq!(performance.total_value, dec("10025"));
+        assert_eq!(performance.positions_count, 0);
+    }
+
+    #[test]
+    fn test_equity_curve_metrics() {
+        let settings = AllocationSettings {
+            max_position_size_pct: 50.0,
+            max_portfolio_risk_pct: 2.0,
+            diversification_targets: HashMap::new(),
+            stop_loss_pct: 5.0,
+            take_profit_pct: 10.0,
+            netting: NettingMode::SeparateLots,
+        };
+        
+        let mut portfolio = PortfolioManager::new(dec("10000"), settings);
+        portfolio.set_equity_sample_interval(60);
+        
+        let position = Position {
+            id: "pos-1".to_string(),
+            symbol: "ETH/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            amount: dec("1"),
+            entry_price: dec("3000"),
+            current_price: dec("3000"),
+            side: "long".to_string(),
+            leverage: 1.0,
+            pnl: dec("0"),
+            pnl_percentage: 0.0,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            tenant_id: None,
+            fees: Decimal::ZERO,
+            funding: Decimal::ZERO,
+        };
+        portfolio.add_position(position).unwrap();
+        
+        assert!(portfolio.record_equity(0));
+        portfolio.mark_to_market("ETH/USDT", dec("4000")).unwrap();
+        assert!(!portfolio.record_equity(30));
+        assert!(portfolio.record_equity(60));
+        portfolio.mark_to_market("ETH/USDT", dec("2900")).unwrap();
+        assert!(portfolio.record_equity(120));
+        
+        // Peak 11000 to 9900 is a 10% drawdown, realized on the curve only
+        let performance = portfolio.calculate_performance().unwrap();
+        assert!((performance.max_drawdown - 10.0).abs() < 1e-9);
+        
+        let history = portfolio.historical_metrics();
+        assert_eq!(history.samples, 3);
+        assert!((history.time_weighted_return_pct + 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fill_netting() {
+        let settings = AllocationSettings {
+            max_position_size_pct: 50.0,
+            max_portfolio_risk_pct: 2.0,
+            diversification_targets: HashMap::new(),
+            stop_loss_pct: 5.0,
+            take_profit_pct: 10.0,
+            netting: NettingMode::Net,
+        };
+        
+        let mut portfolio = PortfolioManager::new(dec("10000"), settings);
+        let fill = |side: &str, amount: Decimal, price: Decimal| PositionFill {
+            symbol: "ETH/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            side: side.to_string(),
+            amount,
+            price,
+            leverage: 1.0,
+            fee: Decimal::ZERO,
+        };
+        
+        let first = portfolio.apply_fill(fill("long", dec("0.5"), dec("3000"))).unwrap();
+        assert!(!first.netted);
+        let id = first.position.unwrap().id;
+        
+        // Same side averages the entry into the same position
+        let merged = portfolio.apply_fill(fill("long", dec("0.5"), dec("3200"))).unwrap();
+        let position = merged.position.unwrap();
+        assert_eq!(position.id, id);
+        assert_eq!(position.amount, dec("1"));
+        assert_eq!(position.entry_price, dec("3100"));
+        assert_eq!(portfolio.list_positions().len(), 1);
+        
+        // Opposite side reduces and books realized PnL, flipping with the excess
+        let flipped = portfolio.apply_fill(fill("short", dec("1.5"), dec("3300"))).unwrap();
+        assert_eq!(flipped.realized.unwrap().realized_pnl, dec("200"));
+        let short = flipped.position.unwrap();
+        assert_ne!(short.id, id);
+        assert_eq!(short.side, "short");
+        assert_eq!(short.amount, dec("0.5"));
+        assert_eq!(portfolio.list_positions().len(), 1);
+    }
+
+    #[test]
+    fn test_fees_and_funding() {
+        let settings = AllocationSettings {
+            max_position_size_pct: 50.0,
+            max_portfolio_risk_pct: 2.0,
+            diversification_targets: HashMap::new(),
+            stop_loss_pct: 5.0,
+            take_profit_pct: 10.0,
+            netting: NettingMode::Net,
+        };
+        let mut portfolio = PortfolioManager::new(dec("10000"), settings);
+        let fill = |side: &str, amount: Decimal, price: Decimal, fee: Decimal| PositionFill {
+            symbol: "ETH/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            side: side.to_string(),
+            amount,
+            price,
+            leverage: 2.0,
+            fee,
+        };
+        let receipt = ExecReceipt {
+            tx_hash: "0xabc".to_string(),
+            success: true,
+            block: 1,
+            gas_used: 100_000,
+            fees_paid_wei: 1_000_000_000_000_000, // 0.001 ETH
+            failure_reason: None,
+            amount_out: None,
+            endpoint: None,
+        };
+        
+        // Gas from the receipt is added to the fill fee at the native price
+        let open = fill("long", dec("1"), dec("3000"), dec("3")).with_receipt_fees(&receipt, dec("3000")).unwrap();
+        assert_eq!(open.fee, dec("6"));
+        let id = portfolio.apply_fill(open).unwrap().position.unwrap().id;
+        
+        // Longs pay a positive funding rate on their notional
+        let charges = portfolio.apply_funding_rate("ETH/USDT", 0.001).unwrap();
+        assert_eq!(charges.len(), 1);
+        assert_eq!(charges[0].amount, dec("3"));
+        assert_eq!(portfolio.get_position(&id).unwrap().funding, dec("3"));
+        
+        // Closing half books half the costs carried plus the closing fee
+        let realized = portfolio.apply_fill(fill("short", dec("0.5"), dec("3100"), dec("1"))).unwrap().realized.unwrap();
+        assert_eq!(realized.realized_pnl, dec("50"));
+        assert_eq!(realized.fees, dec("4"));
+        assert_eq!(realized.funding, dec("1.5"));
+        assert_eq!(realized.net_pnl(), dec("44.5"));
+        let position = portfolio.get_position(&id).unwrap();
+        assert_eq!((position.fees, position.funding), (dec("3"), dec("1.5")));
+        
+        let metrics = portfolio.calculate_performance().unwrap();
+        assert_eq!(metrics.total_pnl, dec("50"));
+        assert_eq!((metrics.fees, metrics.funding), (dec("7"), dec("3")));
+        assert_eq!(metrics.net_pnl, dec("40"));
+        assert_eq!(metrics.total_value, dec("10040"));
+        
+        assert!(matches!(
+            portfolio.apply_fill(fill("long", dec("1"), dec("3000"), dec("-1"))),
+            Err(PortfolioError::InvalidFee(_))
+        ));
+        assert!(matches!(
+            portfolio.apply_funding_rate("ETH/USDT", f64::NAN),
+            Err(PortfolioError::InvalidFundingRate(_))
+        ));
+
+        // Closing or removing a position keeps the costs it carried
+        let second = portfolio.apply_fill(fill("long", dec("0.1"), dec("3000"), dec("2"))).unwrap().position.unwrap().id;
+        assert_eq!(second, id);
+        let fees = portfolio.fees_paid().unwrap();
+        let realized = portfolio.close_position_at_mark(&id).unwrap();
+        assert_eq!(realized.exit_price, dec("3000"));
+        assert_eq!(portfolio.fees_paid().unwrap(), fees);
+        let lot = portfolio.apply_fill(fill("long", dec("0.1"), dec("3000"), dec("2"))).unwrap().position.unwrap().id;
+        portfolio.apply_funding_rate("ETH/USDT", 0.001).unwrap();
+        let (fees, funding) = (portfolio.fees_paid().unwrap(), portfolio.funding_paid().unwrap());
+        portfolio.remove_position(&lot).unwrap();
+        assert_eq!((portfolio.fees_paid().unwrap(), portfolio.funding_paid().unwrap()), (fees, funding));
+        assert_eq!(portfolio.calculate_performance().unwrap().funding, funding);
+    }
+
+    #[test]
+    fn test_fill_separate_lots() {
+        let settings = AllocationSettings {
+            max_position_size_pct: 50.0,
+            max_portfolio_risk_pct: 2.0,
+            diversification_targets: HashMap::new(),
+            stop_loss_pct: 5.0,
+            take_profit_pct: 10.0,
+            netting: NettingMode::SeparateLots,
+        };
+        
+        let mut portfolio = PortfolioManager::new(dec("10000"), settings);
+        for _ in 0..2 {
+            let outcome = portfolio.apply_fill(PositionFill {
+                symbol: "ETH/USDT".to_string(),
+                chain: ChainRef {
+                    name: "ethereum".to_string(),
+                    id: 1,
+                },
+                side: "long".to_string(),
+                amount: dec("0.5"),
+                price: dec("3000"),
+                leverage: 1.0,
+                fee: Decimal::ZERO,
+            }).unwrap();
+            assert!(!outcome.netted);
+        }
+        assert_eq!(portfolio.list_positions().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_portfolio_events_published() {
+        let settings = AllocationSettings {
+            max_position_size_pct: 50.0,
+            max_portfolio_risk_pct: 2.0,
+            diversification_targets: HashMap::new(),
+            stop_loss_pct: 5.0,
+            take_profit_pct: 10.0,
+            netting: NettingMode::SeparateLots,
+        };
+        
+        let bus = InMemoryBus::new(16);
+        let mut rx = bus.subscribe(sniper_core::events::POSITION_OPENED_SUBJECT);
+        let mut portfolio = PortfolioManager::new(dec("10000"), settings).with_bus(bus);
+        portfolio.set_drawdown_alert(Some(5.0));
+        
+        let position = Position {
+            id: "pos-1".to_string(),
+            symbol: "ETH/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            amount: dec("1"),
+            entry_price: dec("3000"),
+            current_price: dec("3000"),
+            side: "long".to_string(),
+            leverage: 1.0,
+            pnl: dec("0"),
+            pnl_percentage: 0.0,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            tenant_id: None,
+            fees: Decimal::ZERO,
+            funding: Decimal::ZERO,
+        };
+        portfolio.add_position(position).unwrap();
+        
+        // Peak 11000 to 9900 breaches the 5% alert once; deeper samples do not repeat it
+        portfolio.record_equity(0);
+        portfolio.mark_to_market("ETH/USDT", dec("4000")).unwrap();
+        portfolio.record_equity(60);
+        portfolio.mark_to_market("ETH/USDT", dec("2900")).unwrap();
+        portfolio.record_equity(120);
+        portfolio.mark_to_market("ETH/USDT", dec("2800")).unwrap();
+        portfolio.record_equity(180);
+        portfolio.close_position("pos-1", dec("2800")).unwrap();
+        
+        let mut events = Vec::new();
+        let mut audits = Vec::new();
+        while let Ok(bytes) = rx.try_recv() {
+            events.extend(TradingEvent::decode(&bytes));
+            audits.extend(AuditEvent::decode(&bytes));
+        }
+        let mut events = events.into_iter();
+        let opened = events.next().unwrap();
+        assert_eq!(opened.subject(), sniper_core::events::POSITION_OPENED_SUBJECT);
+        match events.next().unwrap() {
+            TradingEvent::DrawdownBreached { drawdown_pct, threshold_pct, .. } => {
+                assert!((drawdown_pct - 10.0).abs() < 1e-9);
+                assert_eq!(threshold_pct, 5.0);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        match events.next().unwrap() {
+            TradingEvent::PositionClosed { position_id, realized_pnl, .. } => {
+                assert_eq!(position_id, "pos-1");
+                assert!((realized_pnl + 200.0).abs() < 1e-9);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        assert!(events.next().is_none());
+        
+        // Opens and closes are audited under the position ID
+        let actions: Vec<&str> = audits.iter().map(|event| event.action.as_str()).collect();
+        assert_eq!(actions, vec![audit::POSITION_OPENED, audit::POSITION_CLOSED]);
+        assert!(audits.iter().all(|event| event.correlation_id == "pos-1" && event.resource == "positions/pos-1"));
+    }
+    #[test]
+    fn test_inventory_book_tracks_net_inventory() {
+        let settings = AllocationSettings {
+            max_position_size_pct: 50.0,
+            max_portfolio_risk_pct: 2.0,
+            diversification_targets: HashMap::new(),
+            stop_loss_pct: 5.0,
+            take_profit_pct: 10.0,
+            netting: NettingMode::SeparateLots,
+        };
+        
+        let book = InventoryBook::new();
+        let mut portfolio = PortfolioManager::new(dec("10000"), settings).with_inventory_book(book.clone());
+        let fill = |side: &str, amount: Decimal| PositionFill {
+            symbol: "ETH/USDT".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            side: side.to_string(),
+            amount,
+            price: dec("3000"),
+            leverage: 1.0,
+            fee: Decimal::ZERO,
+        };
+        assert_eq!(book.net("ETH/USDT"), None);
+        
+        let long = portfolio.apply_fill(fill("long", dec("1"))).unwrap().position.unwrap();
+        portfolio.apply_fill(fill("short", dec("0.25"))).unwrap();
+        assert!((book.net("ETH/USDT").unwrap() - 0.75).abs() < 1e-9);
+        assert_eq!(portfolio.net_inventory("ETH/USDT"), dec("0.75"));
+        
+        // Closing the long leaves only the short
+        portfolio.close_position_partial(&long.id, dec("1"), dec("3100")).unwrap();
+        assert!((book.net("ETH/USDT").unwrap() + 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_trade_plan_runs_risk_checks() {
+        let settings = AllocationSettings {
+            max_position_size_pct: 50.0,
+            max_portfolio_risk_pct: 2.0,
+            diversification_targets: HashMap::new(),
+            stop_loss_pct: 5.0,
+            take_profit_pct: 10.0,
+            netting: NettingMode::SeparateLots,
+        };
+        let risk = RiskEngine::new(sniper_risk::RiskLimits {
+            max_open_positions_per_symbol: Some(1),
+            daily_loss_limit: Some(100.0),
+            ..Default::default()
+        });
+        let mut portfolio = PortfolioManager::new(dec("10000"), settings).with_risk(risk.clone());
+        let chain = ChainRef {
+            name: "ethereum".to_string(),
+            id: 1,
+        };
+        assert!(portfolio.generate_trade_plan("ETH/USDT", chain.clone(), dec("1"), "long").is_ok());
+
+        portfolio
+            .apply_fill(PositionFill {
+                symbol: "ETH/USDT".to_string(),
+                chain: chain.clone(),
+                side: "long".to_string(),
+                amount: dec("1"),
+                price: dec("3000"),
+                leverage: 1.0,
+                fee: Decimal::ZERO,
+            })
+            .unwrap();
+        assert_eq!(risk.exposure().unwrap().open_positions["ETH/USDT"], 1);
+        assert_eq!(risk.exposure().unwrap().gross_exposure, 3000.0);
+
+        let err = portfolio.generate_trade_plan("ETH/USDT", chain.clone(), dec("1"), "long").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Invalid);
+        let PortfolioError::Risk(rejected) = err else {
+            panic!("expected a risk rejection, got {}", err);
+        };
+        assert!(matches!(rejected.rejections[..], [sniper_risk::RiskRejection::TooManyOpenPositions { open: 1, .. }]));
+        assert!(portfolio.generate_trade_plan("BTC/USDT", chain.clone(), dec("1"), "long").is_ok());
+
+        // Marking the position down past the daily loss limit blocks every new position
+        portfolio.mark_to_market("ETH/USDT", dec("2850")).unwrap();
+        assert!(portfolio.generate_trade_plan("BTC/USDT", chain.clone(), dec("1"), "long").is_err());
+        assert!(portfolio.generate_trade_plan("ETH/USDT", chain.clone(), dec("1"), "short").is_err());
+        assert!(portfolio.generate_trade_plan("ETH/USDT", chain, dec("1"), "sell").is_ok());
+    }
+
+    #[test]
+    fn test_drawdown_trips_kill_switch() {
+        let settings = AllocationSettings {
+            max_position_size_pct: 50.0,
+            max_portfolio_risk_pct: 2.0,
+            diversification_targets: HashMap::new(),
+            stop_loss_pct: 5.0,
+            take_profit_pct: 10.0,
+            netting: NettingMode::SeparateLots,
+        };
+        let kill_switch = KillSwitch::new(sniper_core::kill_switch::CircuitBreakerConfig {
+            max_drawdown_pct: Some(5.0),
+            cooldown_secs: None,
+            ..Default::default()
+        });
+        let mut portfolio = PortfolioManager::new(dec("10000"), settings).with_kill_switch(kill_switch.clone());
+        let chain = ChainRef {
+            name: "ethereum".to_string(),
+            id: 1,
+        };
+        portfolio
+            .apply_fill(PositionFill {
+                symbol: "ETH/USDT".to_string(),
+                chain: chain.clone(),
+                side: "long".to_string(),
+                amount: dec("1"),
+                price: dec("3000"),
+                leverage: 1.0,
+                fee: Decimal::ZERO,
+            })
+            .unwrap();
+
+        assert!(portfolio.record_equity(0));
+        portfolio.mark_to_market("ETH/USDT", dec("2400")).unwrap();
+        assert!(portfolio.record_equity(60));
+
+        // 10000 to 9400 is a 6% drawdown
+        assert!(kill_switch.is_tripped());
+        let err = portfolio.generate_trade_plan("ETH/USDT", chain.clone(), dec("1"), "long").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Unavailable);
+        kill_switch.reset("ops");
+        assert!(portfolio.generate_trade_plan("ETH/USDT", chain, dec("1"), "long").is_ok());
+    }
+
+    #[test]
+    fn test_tenant_portfolio_claims_positions() {
+        let settings = AllocationSettings {
+            max_position_size_pct: 50.0,
+            max_portfolio_risk_pct: 2.0,
+            diversification_targets: HashMap::new(),
+            stop_loss_pct: 5.0,
+            take_profit_pct: 10.0,
+            netting: NettingMode::SeparateLots,
+        };
+        let mut portfolio = PortfolioManager::new(dec("10000"), settings).with_tenant("tenant-1");
+        let chain = ChainRef {
+            name: "ethereum".to_string(),
+            id: 1,
+        };
+        
+        let outcome = portfolio
+            .apply_fill(PositionFill {
+                symbol: "ETH/USDT".to_string(),
+                chain: chain.clone(),
+                side: "long".to_string(),
+                amount: dec("0.5"),
+                price: dec("3000"),
+                leverage: 1.0,
+                fee: Decimal::ZERO,
+            })
+            .unwrap();
+        let position = outcome.position.unwrap();
+        assert_eq!(position.tenant_id.as_deref(), Some("tenant-1"));
+        
+        // Positions of other tenants are rejected, also when updating
+        let mut foreign = position.clone();
+        foreign.id = "pos-2".to_string();
+        foreign.tenant_id = Some("tenant-2".to_string());
+        let err = portfolio.add_position(foreign.clone()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Forbidden);
+        foreign.id = position.id.clone();
+        assert!(portfolio.update_position(&position.id, foreign).is_err());
+        assert_eq!(portfolio.get_position(&position.id).unwrap().tenant_id.as_deref(), Some("tenant-1"));
+    }
+}
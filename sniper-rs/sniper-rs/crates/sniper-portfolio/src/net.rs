@@ -0,0 +1,149 @@
+//! Net view of positions per symbol and chain.
+//!
+//! With `NettingMode::SeparateLots` a symbol may be held as several long and
+//! short lots. The net view folds them into one line per symbol and chain,
+//! whichever netting mode booked them, so the gross lots and the net exposure
+//! can be read side by side.
+
+use crate::Position;
+use serde::{Deserialize, Serialize};
+use sniper_core::types::{ChainRef, Decimal};
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+/// Lots of one symbol and chain folded into a single position
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NetPosition {
+    pub symbol: String,
+    pub chain: ChainRef,
+    /// "long", "short" or "flat"
+    pub side: String,
+    /// Long minus short amount, negative when net short
+    pub net_amount: Decimal,
+    pub long_amount: Decimal,
+    pub short_amount: Decimal,
+    /// Size-weighted entry price of the lots on the net side, unless flat
+    pub avg_entry_price: Option<Decimal>,
+    /// Price the most recently updated lot is marked at
+    pub current_price: Decimal,
+    /// Unrealized PnL of all lots
+    pub pnl: Decimal,
+    /// Number of lots folded in
+    pub lots: usize,
+}
+
+/// Fold positions into one net position per symbol and chain, ordered by symbol then chain ID
+pub fn net_positions<'a>(positions: impl IntoIterator<Item = &'a Position>) -> Vec<NetPosition> {
+    let mut groups: BTreeMap<(String, u64), Vec<&Position>> = BTreeMap::new();
+    for position in positions {
+        groups
+            .entry((position.symbol.clone(), position.chain.id))
+            .or_default()
+            .push(position);
+    }
+    groups.into_values().map(|lots| net(&lots)).collect()
+}
+
+/// Net a non-empty group of lots in the same symbol and chain
+fn net(lots: &[&Position]) -> NetPosition {
+    let side_amount = |side: &str| -> Decimal {
+        lots.iter()
+            .filter(|lot| lot.side == side)
+            .map(|lot| lot.amount)
+            .sum()
+    };
+    let long_amount = side_amount("long");
+    let short_amount = side_amount("short");
+    let net_amount = long_amount - short_amount;
+    let side = if net_amount.is_positive() {
+        "long"
+    } else if net_amount.is_negative() {
+        "short"
+    } else {
+        "flat"
+    };
+
+    let net_side: Vec<&&Position> = lots.iter().filter(|lot| lot.side == side).collect();
+    let net_side_amount: Decimal = net_side.iter().map(|lot| lot.amount).sum();
+    let avg_entry_price = net_side
+        .iter()
+        .map(|lot| lot.entry_price * lot.amount)
+        .sum::<Decimal>()
+        .checked_div(net_side_amount);
+    let latest = lots
+        .iter()
+        .max_by_key(|lot| lot.updated_at)
+        .expect("net positions are built from at least one lot");
+
+    NetPosition {
+        symbol: latest.symbol.clone(),
+        chain: latest.chain.clone(),
+        side: side.to_string(),
+        net_amount,
+        long_amount,
+        short_amount,
+        avg_entry_price,
+        current_price: latest.current_price,
+        pnl: lots.iter().map(|lot| lot.pnl).sum(),
+        lots: lots.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lot(symbol: &str, chain_id: u64, side: &str, amount: i64, entry_price: i64, updated_at: u64) -> Position {
+        Position {
+            id: format!("{}-{}-{}", symbol, side, updated_at),
+            symbol: symbol.to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: chain_id,
+            },
+            amount: Decimal::from(amount),
+            entry_price: Decimal::from(entry_price),
+            current_price: Decimal::from(entry_price),
+            side: side.to_string(),
+            leverage: 1.0,
+            pnl: Decimal::from(amount),
+            pnl_percentage: 0.0,
+            created_at: updated_at,
+            updated_at,
+            tenant_id: None,
+            fees: Decimal::ZERO,
+            funding: Decimal::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_net_positions() {
+        let lots = [
+            lot("ETH", 1, "long", 2, 100, 1),
+            lot("ETH", 1, "long", 2, 200, 2),
+            lot("ETH", 1, "short", 1, 180, 3),
+            lot("ETH", 56, "short", 3, 150, 1),
+            lot("BTC", 1, "long", 1, 50, 1),
+            lot("BTC", 1, "short", 1, 60, 2),
+        ];
+        let net = net_positions(&lots);
+        assert_eq!(net.len(), 3);
+
+        assert_eq!(net[0].symbol, "BTC");
+        assert_eq!(net[0].side, "flat");
+        assert!(net[0].net_amount.is_zero());
+        assert_eq!(net[0].avg_entry_price, None);
+
+        // Lots on the same symbol but another chain are kept apart
+        assert_eq!((net[1].chain.id, net[2].chain.id), (1, 56));
+        assert_eq!(net[1].side, "long");
+        assert_eq!(net[1].net_amount, Decimal::from(3));
+        assert_eq!(net[1].long_amount, Decimal::from(4));
+        assert_eq!(net[1].avg_entry_price, Some(Decimal::from(150)));
+        assert_eq!(net[1].current_price, Decimal::from(180));
+        assert_eq!(net[1].pnl, Decimal::from(5));
+        assert_eq!(net[1].lots, 3);
+        assert_eq!(net[2].side, "short");
+        assert_eq!(net[2].net_amount, Decimal::from(-3));
+    }
+}
@@ -0,0 +1,359 @@
+//! Rebalancing towards the diversification targets.
+//!
+//! Each long position belongs to an asset class: its base asset mapped
+//! through `asset_classes`, or the base asset itself when unmapped. A class
+//! whose share of equity drifts outside the tolerance band around its target
+//! in `AllocationSettings::diversification_targets` (percent of equity) is
+//! traded back to the target: overweight classes sell down every position
+//! pro rata, underweight classes buy their largest position, or the class's
+//! configured symbol when nothing is held. Classes without a target are left
+//! alone. Every trade is planned through
+//! [`PortfolioManager::generate_trade_plan`], so the risk engine and kill
+//! switch apply.
+
+use crate::{FillOutcome, PortfolioError, PortfolioManager, PositionFill};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use sniper_core::types::{ChainRef, Decimal, TradePlan};
+use std::collections::{BTreeMap, HashMap};
+
+/// Rebalancing settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RebalanceConfig {
+    /// Drift from a target, in percentage points, tolerated before rebalancing
+    pub tolerance_pct: f64,
+    /// Smallest trade worth placing, in quote units
+    pub min_trade_notional: Decimal,
+    /// Asset class of each base asset, e.g. `WBTC` to `BTC`
+    pub asset_classes: HashMap<String, String>,
+    /// Symbol bought for a class the portfolio holds nothing of
+    pub class_symbols: HashMap<String, String>,
+    /// Chain class symbols are bought on
+    pub chain: ChainRef,
+}
+
+impl Default for RebalanceConfig {
+    fn default() -> Self {
+        Self {
+            tolerance_pct: 5.0,
+            min_trade_notional: Decimal::from(10),
+            asset_classes: HashMap::new(),
+            class_symbols: HashMap::new(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+        }
+    }
+}
+
+/// Current and target share of equity of an asset class
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ClassAllocation {
+    pub class: String,
+    pub value: Decimal,
+    pub current_pct: f64,
+    pub target_pct: f64,
+    /// Current minus target, in percentage points
+    pub drift_pct: f64,
+    pub within_band: bool,
+}
+
+/// Trade bringing a class back to its target
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RebalanceTrade {
+    pub class: String,
+    pub symbol: String,
+    pub chain: ChainRef,
+    pub side: String, // "buy" or "sell"
+    /// Position sold down, for sells
+    pub position_id: Option<String>,
+    pub notional: Decimal,
+    /// Size in base units at `price`
+    pub amount: Decimal,
+    pub price: Decimal,
+    pub plan: TradePlan,
+}
+
+/// Allocations and the trades that would restore the targets
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RebalancePreview {
+    pub equity: Decimal,
+    pub tolerance_pct: f64,
+    pub allocations: Vec<ClassAllocation>,
+    pub trades: Vec<RebalanceTrade>,
+    /// Rebalancing that could not be planned, and why
+    pub skipped: Vec<String>,
+}
+
+/// Plans trades restoring the diversification targets
+#[derive(Debug, Clone, Default)]
+pub struct Rebalancer {
+    config: RebalanceConfig,
+}
+
+impl Rebalancer {
+    /// Create a new rebalancer
+    pub fn new(config: RebalanceConfig) -> Self {
+        Self { config }
+    }
+
+    /// Get the rebalancing settings
+    pub fn config(&self) -> &RebalanceConfig {
+        &self.config
+    }
+
+    /// Asset class of a symbol
+    pub fn asset_class(&self, symbol: &str) -> String {
+        let base = symbol.split(['/', '-']).next().unwrap_or(symbol);
+        self.config.asset_classes.get(base).cloned().unwrap_or_else(|| base.to_string())
+    }
+
+    /// Compare allocations to the targets and plan the trades restoring them
+    pub fn preview(&self, portfolio: &PortfolioManager) -> RebalancePreview {
+        let value = portfolio.calculate_portfolio_value();
+        let equity = value.clone().unwrap_or_default();
+        let targets = &portfolio.allocation_settings.diversification_targets;
+        let mut preview = RebalancePreview {
+            equity,
+            tolerance_pct: self.config.tolerance_pct,
+            allocations: Vec::new(),
+            trades: Vec::new(),
+            skipped: Vec::new(),
+        };
+        if let Err(e) = value {
+            preview.skipped.push(e.to_string());
+            return preview;
+        }
+        if !equity.is_positive() {
+            preview.skipped.push(format!("Equity {} leaves nothing to allocate", equity));
+            return preview;
+        }
+
+        let mut holdings: BTreeMap<String, Vec<_>> = BTreeMap::new();
+        for position in portfolio.list_positions().into_iter().filter(|p| p.side != "short") {
+            holdings.entry(self.asset_class(&position.symbol)).or_default().push(position);
+        }
+        let mut classes: Vec<&String> = targets.keys().collect();
+        classes.sort();
+
+        for class in classes {
+            let target_pct = targets[class];
+            let positions = holdings.get(class).map(Vec::as_slice).unwrap_or_default();
+            let Some(value) = Decimal::checked_sum(positions.iter().map(|p| p.notional())) else {
+                preview.skipped.push(format!("Value held in {} is out of the decimal range", class));
+                continue;
+            };
+            let current_pct = match value.checked_div(equity) {
+                Some(share) => share.to_f64() * 100.0,
+                None => value.to_f64() / equity.to_f64() * 100.0,
+            };
+            let drift_pct = current_pct - target_pct;
+            let within_band = drift_pct.abs() <= self.config.tolerance_pct;
+            preview.allocations.push(ClassAllocation {
+                class: class.clone(),
+                value,
+                current_pct,
+                target_pct,
+                drift_pct,
+                within_band,
+            });
+            if within_band {
+                continue;
+            }
+
+            let Some(notional) = Decimal::from_f64(drift_pct.abs() / 100.0).and_then(|share| share.checked_mul(equity)) else {
+                continue;
+            };
+            if drift_pct > 0.0 {
+                for position in positions {
+                    // Each position's share of the class value is at most one, so this stays in range
+                    let share = notional * (position.notional() / value);
+                    self.plan_trade(portfolio, &mut preview, TradeRequest {
+                        class,
+                        symbol: &position.symbol,
+                        chain: &position.chain,
+                        side: "sell",
+                        position_id: Some(&position.id),
+                        notional: share,
+                        price: position.current_price,
+                    });
+                }
+                continue;
+            }
+
+            let largest = positions
+                .iter()
+                .max_by_key(|position| position.notional());
+            let target = match largest {
+                Some(position) => Some((position.symbol.clone(), position.chain.clone(), position.current_price)),
+                None => self.config.class_symbols.get(class).and_then(|symbol| {
+                    let price = Decimal::from_f64(portfolio.last_price(symbol)?)?;
+                    Some((symbol.clone(), self.config.chain.clone(), price))
+                }),
+            };
+            match target {
+                Some((symbol, chain, price)) => self.plan_trade(portfolio, &mut preview, TradeRequest {
+                    class,
+                    symbol: &symbol,
+                    chain: &chain,
+                    side: "buy",
+                    position_id: None,
+                    notional,
+                    price,
+                }),
+                None => preview
+                    .skipped
+                    .push(format!("{} is underweight but has no held or priced symbol to buy", class)),
+            }
+        }
+        preview
+    }
+
+    /// Plan one trade, recording why it was skipped if it cannot be planned
+    fn plan_trade(&self, portfolio: &PortfolioManager, preview: &mut RebalancePreview, request: TradeRequest<'_>) {
+        if request.notional < self.config.min_trade_notional {
+            preview.skipped.push(format!(
+                "{} {} of {} is below the minimum trade of {}",
+                request.side, request.notional, request.symbol, self.config.min_trade_notional
+            ));
+            return;
+        }
+        match portfolio.generate_trade_plan(request.symbol, request.chain.clone(), request.notional, request.side) {
+            Ok(plan) => preview.trades.push(RebalanceTrade {
+                class: request.class.clone(),
+                symbol: request.symbol.to_string(),
+                chain: request.chain.clone(),
+                side: request.side.to_string(),
+                position_id: request.position_id.map(str::to_string),
+                notional: request.notional,
+                amount: request.notional.checked_div(request.price).unwrap_or_default(),
+                price: request.price,
+                plan,
+            }),
+            Err(e) => preview.skipped.push(format!("{} {}: {}", request.side, request.symbol, e)),
+        }
+    }
+}
+
+/// Trade to plan for a class
+struct TradeRequest<'a> {
+    class: &'a String,
+    symbol: &'a str,
+    chain: &'a ChainRef,
+    side: &'static str,
+    position_id: Option<&'a str>,
+    notional: Decimal,
+    price: Decimal,
+}
+
+impl PortfolioManager {
+    /// Book an executed rebalancing trade at its planned price, paying `fee` for it
+    ///
+    /// Buys open or grow a long position; sells close part of the position
+    /// they were planned against, reported as a netted fill.
+    pub fn apply_rebalance_trade(&mut self, trade: &RebalanceTrade, fee: Decimal) -> Result<FillOutcome, PortfolioError> {
+        match (trade.side.as_str(), &trade.position_id) {
+            ("sell", Some(position_id)) => {
+                let held = self
+                    .get_position(position_id)
+                    .map(|position| position.amount)
+                    .ok_or_else(|| PortfolioError::NotFound(position_id.clone()))?;
+                let realized = self.close_position_partial_with_fee(position_id, trade.amount.min(held), trade.price, fee)?;
+                Ok(FillOutcome {
+                    position: self.get_position(position_id).cloned(),
+                    realized: Some(realized),
+                    netted: true,
+                })
+            },
+            ("buy", _) => self.apply_fill(PositionFill {
+                symbol: trade.symbol.clone(),
+                chain: trade.chain.clone(),
+                side: "long".to_string(),
+                amount: trade.amount,
+                price: trade.price,
+                leverage: 1.0,
+                fee,
+            }),
+            _ => Err(PortfolioError::NoPosition(trade.side.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AllocationSettings, NettingMode};
+
+    fn portfolio(targets: &[(&str, f64)]) -> PortfolioManager {
+        let settings = AllocationSettings {
+            max_position_size_pct: 100.0,
+            max_portfolio_risk_pct: 2.0,
+            diversification_targets: targets.iter().map(|(class, pct)| (class.to_string(), *pct)).collect(),
+            stop_loss_pct: 5.0,
+            take_profit_pct: 10.0,
+            netting: NettingMode::Net,
+        };
+        PortfolioManager::new(Decimal::from(10_000), settings)
+    }
+
+    fn fill(symbol: &str, amount: f64, price: f64) -> PositionFill {
+        PositionFill {
+            symbol: symbol.to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            side: "long".to_string(),
+            amount: Decimal::from_f64(amount).unwrap(),
+            price: Decimal::from_f64(price).unwrap(),
+            leverage: 1.0,
+            fee: Decimal::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_preview_and_apply() {
+        let mut portfolio = portfolio(&[("ETH", 30.0), ("BTC", 30.0), ("SOL", 10.0)]);
+        portfolio.apply_fill(fill("ETH/USDT", 2.0, 3000.0)).unwrap(); // 60%
+        portfolio.apply_fill(fill("WBTC/USDT", 0.05, 50_000.0)).unwrap(); // 25%, within 5 points
+        portfolio.mark_to_market("SOL/USDT", Decimal::from(100)).unwrap();
+
+        let mut config = RebalanceConfig::default();
+        config.asset_classes.insert("WBTC".to_string(), "BTC".to_string());
+        config.class_symbols.insert("SOL".to_string(), "SOL/USDT".to_string());
+        let rebalancer = Rebalancer::new(config);
+        let preview = rebalancer.preview(&portfolio);
+
+        assert_eq!(preview.allocations.len(), 3);
+        let btc = preview.allocations.iter().find(|a| a.class == "BTC").unwrap();
+        assert!(btc.within_band);
+        assert!((btc.drift_pct + 5.0).abs() < 1e-9);
+
+        assert_eq!(preview.trades.len(), 2);
+        let sell = preview.trades.iter().find(|t| t.side == "sell").unwrap();
+        assert_eq!(sell.symbol, "ETH/USDT");
+        assert_eq!(sell.notional, Decimal::from(3000));
+        assert_eq!(sell.amount, Decimal::ONE);
+        let buy = preview.trades.iter().find(|t| t.side == "buy").unwrap();
+        assert_eq!(buy.symbol, "SOL/USDT");
+        assert_eq!(buy.amount, Decimal::from(10));
+
+        for trade in &preview.trades {
+            portfolio.apply_rebalance_trade(trade, Decimal::ZERO).unwrap();
+        }
+        let after = rebalancer.preview(&portfolio);
+        assert!(after.allocations.iter().all(|a| a.within_band));
+        assert!(after.trades.is_empty());
+    }
+
+    #[test]
+    fn test_unpriced_class_is_skipped() {
+        let portfolio = portfolio(&[("DOGE", 10.0)]);
+        let preview = Rebalancer::default().preview(&portfolio);
+        assert!(preview.trades.is_empty());
+        assert_eq!(preview.skipped.len(), 1);
+        assert!(preview.skipped[0].contains("DOGE"));
+    }
+}
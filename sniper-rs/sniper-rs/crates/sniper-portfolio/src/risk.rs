@@ -0,0 +1,391 @@
+//! Portfolio risk analytics: Value-at-Risk, exposure and concentration.
+//!
+//! VaR is taken over the per-period returns of the equity curve, both
+//! historically (the empirical loss quantile) and parametrically (a normal
+//! fit). Exposure is broken down by chain and by asset, long positions
+//! counting positive and shorts negative. Concentration weighs each symbol's
+//! net exposure and adjusts the Herfindahl index by the correlation between
+//! the symbols' marked prices, so two highly correlated positions count as
+//! one bet rather than two.
+
+use crate::Position;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::collections::{BTreeMap, HashMap};
+
+/// Default VaR confidence level
+pub const DEFAULT_VAR_CONFIDENCE: f64 = 0.95;
+
+/// Value-at-Risk over one equity sampling period, as a positive loss
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct ValueAtRisk {
+    pub confidence: f64,
+    /// Returns the estimates are taken over
+    pub samples: usize,
+    pub historical_pct: f64,
+    pub parametric_pct: f64,
+    /// Historical VaR in quote units at the current equity
+    pub historical: f64,
+    /// Parametric VaR in quote units at the current equity
+    pub parametric: f64,
+}
+
+impl ValueAtRisk {
+    /// Estimate VaR from per-period returns, zero with fewer than two returns
+    pub fn from_returns(returns: &[f64], confidence: f64, equity: f64) -> Self {
+        let historical_pct = historical_var(returns, confidence).unwrap_or(0.0) * 100.0;
+        let parametric_pct = parametric_var(returns, confidence).unwrap_or(0.0) * 100.0;
+        Self {
+            confidence,
+            samples: returns.len(),
+            historical_pct,
+            parametric_pct,
+            historical: equity.max(0.0) * historical_pct / 100.0,
+            parametric: equity.max(0.0) * parametric_pct / 100.0,
+        }
+    }
+}
+
+/// Long, short, gross and net exposure of a group of positions
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ExposureLine {
+    pub long: f64,
+    pub short: f64,
+    pub gross: f64,
+    pub net: f64,
+    pub positions: usize,
+}
+
+impl ExposureLine {
+    /// Add a position's notional, negative for shorts
+    fn add(&mut self, notional: f64) {
+        if notional < 0.0 {
+            self.short -= notional;
+        } else {
+            self.long += notional;
+        }
+        self.gross += notional.abs();
+        self.net += notional;
+        self.positions += 1;
+    }
+}
+
+/// Exposure of the whole portfolio and by chain and asset
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct ExposureReport {
+    pub total: ExposureLine,
+    /// Gross exposure as a multiple of equity
+    pub gross_leverage: f64,
+    /// Net exposure as a multiple of equity
+    pub net_leverage: f64,
+    pub by_chain: BTreeMap<String, ExposureLine>,
+    /// Keyed by the base asset of each symbol, e.g. `ETH` for `ETH/USDT`
+    pub by_asset: BTreeMap<String, ExposureLine>,
+}
+
+impl ExposureReport {
+    /// Break down the exposure of open positions
+    pub fn from_positions<'a>(positions: impl IntoIterator<Item = &'a Position>, equity: f64) -> Self {
+        let mut report = Self::default();
+        for position in positions {
+            let notional = signed_notional(position);
+            report.total.add(notional);
+            report.by_chain.entry(position.chain.name.clone()).or_default().add(notional);
+            report.by_asset.entry(base_asset(&position.symbol).to_string()).or_default().add(notional);
+        }
+        if equity > 0.0 {
+            report.gross_leverage = report.total.gross / equity;
+            report.net_leverage = report.total.net / equity;
+        }
+        report
+    }
+}
+
+/// Concentration of the portfolio across symbols
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct Concentration {
+    /// Sum of squared exposure weights, 1.0 when everything is in one symbol
+    pub herfindahl: f64,
+    /// Number of equally sized symbols with the same Herfindahl index
+    pub effective_positions: f64,
+    /// Herfindahl index with cross terms weighted by price correlation
+    pub correlation_adjusted_herfindahl: f64,
+    /// Number of uncorrelated equal bets with the same adjusted index
+    pub effective_independent_bets: f64,
+    pub largest_symbol: Option<String>,
+    pub largest_weight_pct: f64,
+}
+
+impl Concentration {
+    /// Measure concentration from net exposure per symbol and each symbol's price history
+    ///
+    /// Symbols without enough shared history are treated as uncorrelated.
+    pub fn from_exposures(exposures: &BTreeMap<String, f64>, prices: &HashMap<String, Vec<f64>>) -> Self {
+        let gross: f64 = exposures.values().map(|exposure| exposure.abs()).sum();
+        if gross <= 0.0 {
+            return Self::default();
+        }
+        let weights: Vec<(&String, f64)> = exposures.iter().map(|(symbol, exposure)| (symbol, exposure / gross)).collect();
+        let returns: Vec<Vec<f64>> = weights
+            .iter()
+            .map(|(symbol, _)| prices.get(*symbol).map(|history| price_returns(history)).unwrap_or_default())
+            .collect();
+
+        let herfindahl: f64 = weights.iter().map(|(_, weight)| weight * weight).sum();
+        let mut adjusted = 0.0;
+        for (i, (_, wi)) in weights.iter().enumerate() {
+            for (j, (_, wj)) in weights.iter().enumerate() {
+                let rho = if i == j {
+                    1.0
+                } else {
+                    correlation(&returns[i], &returns[j]).unwrap_or(0.0)
+                };
+                adjusted += wi * wj * rho;
+            }
+        }
+        // Offsetting correlated positions can cancel out entirely
+        let adjusted = adjusted.max(0.0);
+
+        let (largest_symbol, largest_weight) = weights
+            .iter()
+            .map(|(symbol, weight)| (*symbol, weight.abs()))
+            .fold((None, 0.0), |best, (symbol, weight)| if weight > best.1 { (Some(symbol.clone()), weight) } else { best });
+
+        Self {
+            herfindahl,
+            effective_positions: 1.0 / herfindahl,
+            correlation_adjusted_herfindahl: adjusted,
+            effective_independent_bets: if adjusted > 0.0 { 1.0 / adjusted } else { 0.0 },
+            largest_symbol,
+            largest_weight_pct: largest_weight * 100.0,
+        }
+    }
+}
+
+/// VaR, exposure and concentration of the portfolio
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct RiskReport {
+    pub equity: f64,
+    pub value_at_risk: ValueAtRisk,
+    pub exposure: ExposureReport,
+    pub concentration: Concentration,
+}
+
+/// Position notional at its current price, negative for shorts
+fn signed_notional(position: &Position) -> f64 {
+    let notional = position.notional().to_f64();
+    if position.side == "short" {
+        -notional
+    } else {
+        notional
+    }
+}
+
+/// Net exposure per symbol of open positions
+pub fn net_exposure_by_symbol<'a>(positions: impl IntoIterator<Item = &'a Position>) -> BTreeMap<String, f64> {
+    let mut exposures = BTreeMap::new();
+    for position in positions {
+        *exposures.entry(position.symbol.clone()).or_insert(0.0) += signed_notional(position);
+    }
+    exposures
+}
+
+/// Base asset of a symbol such as `ETH/USDT` or `ETH-USDT`
+fn base_asset(symbol: &str) -> &str {
+    symbol.split(['/', '-']).next().unwrap_or(symbol)
+}
+
+/// Returns between consecutive prices
+fn price_returns(prices: &[f64]) -> Vec<f64> {
+    prices
+        .windows(2)
+        .filter(|pair| pair[0] > 0.0)
+        .map(|pair| pair[1] / pair[0] - 1.0)
+        .collect()
+}
+
+/// Empirical loss quantile of the returns, as a positive fraction
+pub fn historical_var(returns: &[f64], confidence: f64) -> Option<f64> {
+    if returns.len() < 2 {
+        return None;
+    }
+    let mut sorted = returns.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let index = (((1.0 - confidence) * sorted.len() as f64).floor() as usize).min(sorted.len() - 1);
+    Some((-sorted[index]).max(0.0))
+}
+
+/// Loss quantile of a normal fit to the returns, as a positive fraction
+pub fn parametric_var(returns: &[f64], confidence: f64) -> Option<f64> {
+    if returns.len() < 2 {
+        return None;
+    }
+    let n = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / n;
+    let std_dev = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+    Some((normal_quantile(confidence) * std_dev - mean).max(0.0))
+}
+
+/// Pearson correlation over the most recent returns both series share
+pub fn correlation(a: &[f64], b: &[f64]) -> Option<f64> {
+    let n = a.len().min(b.len());
+    if n < 2 {
+        return None;
+    }
+    let (a, b) = (&a[a.len() - n..], &b[b.len() - n..]);
+    let mean_a = a.iter().sum::<f64>() / n as f64;
+    let mean_b = b.iter().sum::<f64>() / n as f64;
+    let mut covariance = 0.0;
+    let mut var_a = 0.0;
+    let mut var_b = 0.0;
+    for (x, y) in a.iter().zip(b) {
+        covariance += (x - mean_a) * (y - mean_b);
+        var_a += (x - mean_a).powi(2);
+        var_b += (y - mean_b).powi(2);
+    }
+    if var_a <= 0.0 || var_b <= 0.0 {
+        return None;
+    }
+    Some((covariance / (var_a.sqrt() * var_b.sqrt())).clamp(-1.0, 1.0))
+}
+
+/// Inverse of the standard normal CDF (Acklam's rational approximation)
+pub fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.383577518672690e2,
+        -3.066479806614716e1,
+        2.506628277459239e0,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838e0,
+        -2.549732539343734e0,
+        4.374664141464968e0,
+        2.938163982698783e0,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996e0,
+        3.754408661907416e0,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    let p = p.clamp(f64::MIN_POSITIVE, 1.0 - f64::EPSILON);
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < P_LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - P_LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sniper_core::types::{ChainRef, Decimal};
+
+    fn position(symbol: &str, chain: &str, side: &str, amount: f64, price: f64) -> Position {
+        Position {
+            id: format!("{}-{}-{}", symbol, chain, side),
+            symbol: symbol.to_string(),
+            chain: ChainRef {
+                name: chain.to_string(),
+                id: 1,
+            },
+            amount: Decimal::from_f64(amount).unwrap(),
+            entry_price: Decimal::from_f64(price).unwrap(),
+            current_price: Decimal::from_f64(price).unwrap(),
+            side: side.to_string(),
+            leverage: 1.0,
+            pnl: Decimal::ZERO,
+            pnl_percentage: 0.0,
+            created_at: 0,
+            updated_at: 0,
+            tenant_id: None,
+            fees: Decimal::ZERO,
+            funding: Decimal::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_value_at_risk() {
+        assert!((normal_quantile(0.95) - 1.644854).abs() < 1e-5);
+        assert!((normal_quantile(0.99) - 2.326348).abs() < 1e-5);
+        assert!((normal_quantile(0.001) + 3.090232).abs() < 1e-5);
+
+        // 1..=100 basis point losses: the 5% tail starts at the sixth worst
+        let returns: Vec<f64> = (1..=100).map(|i| -(i as f64) / 10_000.0).collect();
+        let var = ValueAtRisk::from_returns(&returns, 0.95, 10_000.0);
+        assert_eq!(var.samples, 100);
+        assert!((var.historical_pct - 0.95).abs() < 1e-9);
+        assert!((var.historical - 95.0).abs() < 1e-6);
+        assert!(var.parametric_pct > 0.5);
+
+        let flat = ValueAtRisk::from_returns(&[0.01], 0.95, 10_000.0);
+        assert_eq!(flat.historical, 0.0);
+        assert_eq!(flat.parametric, 0.0);
+    }
+
+    #[test]
+    fn test_exposure_by_chain_and_asset() {
+        let positions = [
+            position("ETH/USDT", "ethereum", "long", 2.0, 3000.0),
+            position("ETH/USDC", "arbitrum", "short", 1.0, 3000.0),
+            position("BTC/USDT", "ethereum", "long", 0.1, 60000.0),
+        ];
+        let report = ExposureReport::from_positions(&positions, 10_000.0);
+        assert_eq!(report.total.gross, 15_000.0);
+        assert_eq!(report.total.net, 9_000.0);
+        assert!((report.gross_leverage - 1.5).abs() < 1e-12);
+        assert_eq!(report.by_asset["ETH"], ExposureLine {
+            long: 6000.0,
+            short: 3000.0,
+            gross: 9000.0,
+            net: 3000.0,
+            positions: 2,
+        });
+        assert_eq!(report.by_chain["ethereum"].gross, 12_000.0);
+        assert_eq!(report.by_chain["arbitrum"].net, -3000.0);
+    }
+
+    #[test]
+    fn test_correlation_adjusts_concentration() {
+        let mut exposures = BTreeMap::new();
+        exposures.insert("ETH/USDT".to_string(), 5000.0);
+        exposures.insert("STETH/USDT".to_string(), 5000.0);
+
+        // Without price history the two positions count as independent
+        let independent = Concentration::from_exposures(&exposures, &HashMap::new());
+        assert!((independent.herfindahl - 0.5).abs() < 1e-12);
+        assert!((independent.effective_independent_bets - 2.0).abs() < 1e-12);
+
+        // Prices moving in lockstep make them a single bet
+        let mut prices = HashMap::new();
+        prices.insert("ETH/USDT".to_string(), vec![3000.0, 3030.0, 2990.0, 3100.0]);
+        prices.insert("STETH/USDT".to_string(), vec![2990.0, 3019.9, 2980.0, 3089.6]);
+        let correlated = Concentration::from_exposures(&exposures, &prices);
+        assert!(correlated.correlation_adjusted_herfindahl > 0.99);
+        assert!(correlated.effective_independent_bets < 1.01);
+        assert_eq!(correlated.largest_weight_pct, 50.0);
+    }
+}
@@ -0,0 +1,280 @@
+//! Price cache over the feeds.
+//!
+//! Feeds are queried in the order they were added and the first one with a
+//! price wins, so a cache built from config prefers Chainlink, then the DEX
+//! TWAP, then REST tickers. A quote fetched within `max_age_ms` is served
+//! from the cache. Every fetched quote is broadcast to subscribers, which is
+//! how the services mark positions and evaluate orders as prices move.
+
+use crate::chainlink::ChainlinkFeed;
+use crate::dex::{DexTwapFeed, PoolConfig};
+use crate::rest::{RestFeed, RestFeedConfig};
+use crate::{PriceFeed, PriceQuote};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sniper_chain::{HedgeConfig, JsonRpcClient, ProviderPool, RpcEndpoint};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Capacity of the quote broadcast channel
+const QUOTE_CHANNEL_CAPACITY: usize = 1024;
+
+/// Price cache and feed settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PriceCacheConfig {
+    /// Symbols refreshed in the background
+    pub symbols: Vec<String>,
+    /// Interval between background refreshes
+    pub poll_interval_ms: u64,
+    /// Age after which a cached quote is fetched again
+    pub max_age_ms: i64,
+    /// RPC endpoints the on-chain feeds read through
+    pub rpc_urls: Vec<String>,
+    /// Chainlink aggregator proxy address per symbol
+    pub chainlink: HashMap<String, String>,
+    /// DEX pool per symbol
+    pub dex_pools: HashMap<String, PoolConfig>,
+    /// Window of the DEX TWAP
+    pub twap_window_secs: i64,
+    /// REST ticker endpoints
+    pub rest: Vec<RestFeedConfig>,
+}
+
+impl Default for PriceCacheConfig {
+    fn default() -> Self {
+        Self {
+            symbols: Vec::new(),
+            poll_interval_ms: 1_000,
+            max_age_ms: 5_000,
+            rpc_urls: Vec::new(),
+            chainlink: HashMap::new(),
+            dex_pools: HashMap::new(),
+            twap_window_secs: 300,
+            rest: Vec::new(),
+        }
+    }
+}
+
+/// A quote and when it was fetched
+#[derive(Debug, Clone)]
+struct CachedQuote {
+    quote: PriceQuote,
+    fetched_at_ms: i64,
+}
+
+/// Latest prices per symbol from prioritised feeds
+pub struct PriceCache {
+    config: PriceCacheConfig,
+    feeds: Vec<Arc<dyn PriceFeed>>,
+    quotes: RwLock<HashMap<String, CachedQuote>>,
+    updates: broadcast::Sender<PriceQuote>,
+}
+
+impl PriceCache {
+    /// Create a cache without any feeds
+    pub fn new(config: PriceCacheConfig) -> Self {
+        let (updates, _) = broadcast::channel(QUOTE_CHANNEL_CAPACITY);
+        Self {
+            config,
+            feeds: Vec::new(),
+            quotes: RwLock::new(HashMap::new()),
+            updates,
+        }
+    }
+
+    /// Create a cache with the Chainlink, DEX TWAP and REST feeds configured
+    pub fn from_config(config: PriceCacheConfig) -> Result<Self> {
+        let mut feeds: Vec<Arc<dyn PriceFeed>> = Vec::new();
+        let on_chain = !config.chainlink.is_empty() || !config.dex_pools.is_empty();
+        if on_chain {
+            if config.rpc_urls.is_empty() {
+                return Err(anyhow::anyhow!("Chainlink and DEX feeds need at least one RPC URL"));
+            }
+            let endpoints = config
+                .rpc_urls
+                .iter()
+                .enumerate()
+                .map(|(index, url)| RpcEndpoint {
+                    name: format!("rpc-{}", index),
+                    url: url.clone(),
+                })
+                .collect();
+            let rpc = Arc::new(JsonRpcClient::new(Arc::new(ProviderPool::new(endpoints, HedgeConfig::default())))?);
+
+            if !config.chainlink.is_empty() {
+                let feed = config
+                    .chainlink
+                    .iter()
+                    .fold(ChainlinkFeed::new("chainlink", rpc.clone()), |feed, (symbol, address)| {
+                        feed.with_aggregator(symbol, address)
+                    });
+                feeds.push(Arc::new(feed));
+            }
+            if !config.dex_pools.is_empty() {
+                let feed = config.dex_pools.iter().fold(
+                    DexTwapFeed::new("dex-twap", rpc, config.twap_window_secs),
+                    |feed, (symbol, pool)| feed.with_pool(symbol, pool.clone()),
+                );
+                feeds.push(Arc::new(feed));
+            }
+        }
+        for rest in &config.rest {
+            feeds.push(Arc::new(RestFeed::new(rest.clone())?));
+        }
+
+        let mut cache = Self::new(config);
+        cache.feeds = feeds;
+        Ok(cache)
+    }
+
+    /// Add a feed, queried after those already added
+    pub fn with_feed(mut self, feed: Arc<dyn PriceFeed>) -> Self {
+        self.feeds.push(feed);
+        self
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &PriceCacheConfig {
+        &self.config
+    }
+
+    /// List feed names in priority order
+    pub fn feed_names(&self) -> Vec<&str> {
+        self.feeds.iter().map(|feed| feed.name()).collect()
+    }
+
+    /// Subscribe to every quote fetched
+    pub fn subscribe(&self) -> broadcast::Receiver<PriceQuote> {
+        self.updates.subscribe()
+    }
+
+    /// Get the latest cached quote of a symbol, however old
+    pub fn cached(&self, symbol: &str) -> Option<PriceQuote> {
+        self.quotes.read().unwrap().get(symbol).map(|cached| cached.quote.clone())
+    }
+
+    /// Get the price of a symbol, from the cache if fresh and the feeds otherwise
+    pub async fn get(&self, symbol: &str) -> Result<PriceQuote> {
+        let fresh = self
+            .quotes
+            .read()
+            .unwrap()
+            .get(symbol)
+            .filter(|cached| now_ms() - cached.fetched_at_ms <= self.config.max_age_ms)
+            .map(|cached| cached.quote.clone());
+        match fresh {
+            Some(quote) => Ok(quote),
+            None => self.refresh(symbol).await,
+        }
+    }
+
+    /// Fetch a symbol from the first feed with a price, caching and broadcasting it
+    pub async fn refresh(&self, symbol: &str) -> Result<PriceQuote> {
+        for feed in &self.feeds {
+            match feed.latest_price(symbol).await {
+                Ok(Some(quote)) => {
+                    self.quotes.write().unwrap().insert(symbol.to_string(), CachedQuote {
+                        quote: quote.clone(),
+                        fetched_at_ms: now_ms(),
+                    });
+                    let _ = self.updates.send(quote.clone());
+                    return Ok(quote);
+                },
+                Ok(None) => {},
+                Err(e) => tracing::warn!("price feed {} failed for {}: {}", feed.name(), symbol, e),
+            }
+        }
+        Err(anyhow::anyhow!("No feed has a price for {}", symbol))
+    }
+
+    /// Fetch every configured symbol, keeping the previous quote of symbols that fail
+    pub async fn refresh_all(&self) {
+        let refreshes = self.config.symbols.iter().map(|symbol| self.refresh(symbol));
+        for result in futures::future::join_all(refreshes).await {
+            if let Err(e) = result {
+                tracing::debug!("price not refreshed: {}", e);
+            }
+        }
+    }
+
+    /// Fetch every configured symbol at the configured interval, forever
+    pub async fn run(&self) {
+        let mut interval = tokio::time::interval(Duration::from_millis(self.config.poll_interval_ms.max(1)));
+        loop {
+            interval.tick().await;
+            self.refresh_all().await;
+        }
+    }
+}
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::StreamFeed;
+    use async_trait::async_trait;
+    use chrono::Utc;
+
+    struct FailingFeed;
+
+    #[async_trait]
+    impl PriceFeed for FailingFeed {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        async fn latest_price(&self, _symbol: &str) -> Result<Option<PriceQuote>> {
+            Err(anyhow::anyhow!("provider down"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_and_broadcasts() {
+        let primary = Arc::new(StreamFeed::new("primary", "/s", "/p").with_symbol("ETH/USDT", "ETH"));
+        let fallback = Arc::new(StreamFeed::new("fallback", "/s", "/p").with_symbol("ETH/USDT", "ETH"));
+        let cache = PriceCache::new(PriceCacheConfig {
+            symbols: vec!["ETH/USDT".to_string()],
+            max_age_ms: 60_000,
+            ..PriceCacheConfig::default()
+        })
+        .with_feed(Arc::new(FailingFeed))
+        .with_feed(primary.clone())
+        .with_feed(fallback.clone());
+        assert_eq!(cache.feed_names(), vec!["failing", "primary", "fallback"]);
+        let mut updates = cache.subscribe();
+
+        assert!(cache.get("ETH/USDT").await.is_err());
+
+        fallback.ingest(r#"{"s": "ETH", "p": 2990}"#);
+        cache.refresh_all().await;
+        assert_eq!(cache.cached("ETH/USDT").unwrap().source, "fallback");
+        assert_eq!(updates.recv().await.unwrap().price, 2990.0);
+
+        // A fresh quote is served from the cache until it ages out
+        primary.ingest(r#"{"s": "ETH", "p": 3000}"#);
+        assert_eq!(cache.get("ETH/USDT").await.unwrap().price, 2990.0);
+        let quote = cache.refresh("ETH/USDT").await.unwrap();
+        assert_eq!((quote.source.as_str(), quote.price), ("primary", 3000.0));
+        assert!(quote.observed_at <= Utc::now());
+    }
+
+    #[test]
+    fn test_on_chain_feeds_need_rpc() {
+        let mut config = PriceCacheConfig::default();
+        config.chainlink.insert("ETH/USD".to_string(), "0xaggregator".to_string());
+        assert!(PriceCache::from_config(config.clone()).is_err());
+
+        config.rpc_urls.push("http://127.0.0.1:8545".to_string());
+        let cache = PriceCache::from_config(config).unwrap();
+        assert_eq!(cache.feed_names(), vec!["chainlink"]);
+    }
+}
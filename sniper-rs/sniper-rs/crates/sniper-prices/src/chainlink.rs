@@ -0,0 +1,159 @@
+//! Chainlink aggregator reads.
+//!
+//! Prices come from `latestRoundData()` on each symbol's aggregator proxy,
+//! scaled by the feed's `decimals()`, which is read once and remembered.
+//! Rounds older than the maximum age, or with a non-positive answer, are
+//! treated as a broken feed rather than a price.
+
+use crate::{abi_int, abi_uint, abi_word, PriceFeed, PriceQuote};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use sniper_chain::rpc::JsonRpcClient;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// `latestRoundData()` selector
+const LATEST_ROUND_DATA: &str = "0xfeaf968c";
+
+/// `decimals()` selector
+const DECIMALS: &str = "0x313ce567";
+
+/// Default age after which a round is stale, above the usual one-hour heartbeat
+pub const DEFAULT_MAX_ROUND_AGE_SECS: i64 = 3_900;
+
+/// Chainlink price feed over aggregator proxies
+pub struct ChainlinkFeed {
+    name: String,
+    rpc: Arc<JsonRpcClient>,
+    aggregators: HashMap<String, String>,
+    max_round_age_secs: i64,
+    decimals: RwLock<HashMap<String, u8>>,
+}
+
+impl ChainlinkFeed {
+    /// Create a feed reading aggregators through `rpc`
+    pub fn new(name: &str, rpc: Arc<JsonRpcClient>) -> Self {
+        Self {
+            name: name.to_string(),
+            rpc,
+            aggregators: HashMap::new(),
+            max_round_age_secs: DEFAULT_MAX_ROUND_AGE_SECS,
+            decimals: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Price `symbol` from the aggregator proxy at `address`
+    pub fn with_aggregator(mut self, symbol: &str, address: &str) -> Self {
+        self.aggregators.insert(symbol.to_string(), address.to_string());
+        self
+    }
+
+    /// Set the age after which a round is stale
+    pub fn with_max_round_age(mut self, max_round_age_secs: i64) -> Self {
+        self.max_round_age_secs = max_round_age_secs;
+        self
+    }
+
+    /// Decimals of an aggregator, read on first use
+    async fn decimals(&self, address: &str) -> Result<u8> {
+        if let Some(decimals) = self.decimals.read().unwrap().get(address) {
+            return Ok(*decimals);
+        }
+        let data = self.rpc.call(json!({"to": address, "data": DECIMALS}), "latest", None).await?;
+        let decimals = u8::try_from(abi_uint(abi_word(&data, 0)?)?)?;
+        self.decimals.write().unwrap().insert(address.to_string(), decimals);
+        Ok(decimals)
+    }
+}
+
+#[async_trait]
+impl PriceFeed for ChainlinkFeed {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn latest_price(&self, symbol: &str) -> Result<Option<PriceQuote>> {
+        let Some(address) = self.aggregators.get(symbol) else {
+            return Ok(None);
+        };
+        let decimals = self.decimals(address).await?;
+        let data = self
+            .rpc
+            .call(json!({"to": address, "data": LATEST_ROUND_DATA}), "latest", None)
+            .await?;
+
+        // (roundId, answer, startedAt, updatedAt, answeredInRound)
+        let answer = abi_int(abi_word(&data, 1)?)?;
+        let updated_at = i64::try_from(abi_uint(abi_word(&data, 3)?)?)?;
+        let observed_at = DateTime::<Utc>::from_timestamp(updated_at, 0)
+            .ok_or_else(|| anyhow::anyhow!("Invalid round timestamp {} for {}", updated_at, symbol))?;
+        if answer <= 0 {
+            return Err(anyhow::anyhow!("Aggregator {} answered {} for {}", address, answer, symbol));
+        }
+        let age = (Utc::now() - observed_at).num_seconds();
+        if age > self.max_round_age_secs {
+            return Err(anyhow::anyhow!("Latest {} round from {} is {}s old", symbol, address, age));
+        }
+
+        Ok(Some(PriceQuote {
+            symbol: symbol.to_string(),
+            price: answer as f64 / 10f64.powi(decimals as i32),
+            source: self.name.clone(),
+            observed_at,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Json, Router};
+    use serde_json::Value;
+    use sniper_chain::{HedgeConfig, ProviderPool, RpcEndpoint};
+
+    fn words(values: &[u128]) -> String {
+        let mut data = String::from("0x");
+        for value in values {
+            data.push_str(&format!("{:064x}", value));
+        }
+        data
+    }
+
+    async fn handle(Json(request): Json<Value>) -> Json<Value> {
+        assert_eq!(request["method"], "eth_call");
+        let call = &request["params"][0];
+        let result = match (call["to"].as_str().unwrap(), call["data"].as_str().unwrap()) {
+            (_, DECIMALS) => words(&[8]),
+            ("0xfresh", LATEST_ROUND_DATA) => {
+                let now = Utc::now().timestamp() as u128;
+                words(&[7, 300_012_345_678, now, now, 7])
+            },
+            (_, LATEST_ROUND_DATA) => words(&[7, 300_012_345_678, 1_000, 1_000, 7]),
+            other => panic!("unexpected call {:?}", other),
+        };
+        Json(json!({"jsonrpc": "2.0", "id": request["id"], "result": result}))
+    }
+
+    #[tokio::test]
+    async fn test_reads_latest_round() {
+        let app = Router::new().route("/", post(handle));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let pool = ProviderPool::new(vec![RpcEndpoint { name: "mock".to_string(), url }], HedgeConfig::default());
+        let rpc = Arc::new(JsonRpcClient::new(Arc::new(pool)).unwrap());
+        let feed = ChainlinkFeed::new("chainlink", rpc)
+            .with_aggregator("ETH/USD", "0xfresh")
+            .with_aggregator("BTC/USD", "0xstale");
+
+        let quote = feed.latest_price("ETH/USD").await.unwrap().unwrap();
+        assert!((quote.price - 3000.12345678).abs() < 1e-9);
+        assert_eq!(quote.source, "chainlink");
+
+        assert!(feed.latest_price("BTC/USD").await.is_err());
+        assert!(feed.latest_price("SOL/USD").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,150 @@
+//! DEX TWAP from pool reserves.
+//!
+//! Each read samples `getReserves()` of a Uniswap V2 style pool, turns the
+//! reserves into a spot price of the base token in the quote token and
+//! records it into a rolling [`TwapSource`]. The feed answers with the
+//! time-weighted average, so a single manipulated block moves it little.
+
+use crate::{abi_uint, abi_word, PriceFeed, PriceQuote};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sniper_chain::rpc::JsonRpcClient;
+use sniper_oracle::sources::TwapSource;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// `getReserves()` selector
+const GET_RESERVES: &str = "0x0902f1ac";
+
+/// A pool a symbol is priced from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolConfig {
+    pub address: String,
+    /// Whether the symbol's base token is the pool's token0
+    pub base_is_token0: bool,
+    pub decimals0: u8,
+    pub decimals1: u8,
+}
+
+impl PoolConfig {
+    /// Price of the base token in the quote token from the pool reserves
+    pub fn spot_price(&self, reserve0: u128, reserve1: u128) -> Option<f64> {
+        let amount0 = reserve0 as f64 / 10f64.powi(self.decimals0 as i32);
+        let amount1 = reserve1 as f64 / 10f64.powi(self.decimals1 as i32);
+        let price = if self.base_is_token0 { amount1 / amount0 } else { amount0 / amount1 };
+        (price.is_finite() && price > 0.0).then_some(price)
+    }
+}
+
+/// TWAP price feed over DEX pool reserves
+pub struct DexTwapFeed {
+    name: String,
+    rpc: Arc<JsonRpcClient>,
+    pools: HashMap<String, PoolConfig>,
+    twap: TwapSource,
+}
+
+impl DexTwapFeed {
+    /// Create a feed averaging pool prices over `window_secs`
+    pub fn new(name: &str, rpc: Arc<JsonRpcClient>, window_secs: i64) -> Self {
+        Self {
+            name: name.to_string(),
+            rpc,
+            pools: HashMap::new(),
+            twap: TwapSource::new(name, window_secs),
+        }
+    }
+
+    /// Price `symbol` from a pool
+    pub fn with_pool(mut self, symbol: &str, pool: PoolConfig) -> Self {
+        self.pools.insert(symbol.to_string(), pool);
+        self
+    }
+}
+
+#[async_trait]
+impl PriceFeed for DexTwapFeed {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn latest_price(&self, symbol: &str) -> Result<Option<PriceQuote>> {
+        let Some(pool) = self.pools.get(symbol) else {
+            return Ok(None);
+        };
+        let data = self
+            .rpc
+            .call(json!({"to": pool.address, "data": GET_RESERVES}), "latest", None)
+            .await?;
+        let reserve0 = abi_uint(abi_word(&data, 0)?)?;
+        let reserve1 = abi_uint(abi_word(&data, 1)?)?;
+        let spot = pool
+            .spot_price(reserve0, reserve1)
+            .ok_or_else(|| anyhow::anyhow!("Pool {} for {} has empty reserves", pool.address, symbol))?;
+
+        let now = Utc::now();
+        self.twap.record(symbol, spot, now);
+        Ok(self.twap.twap(symbol, now).map(|(price, observed_at)| PriceQuote {
+            symbol: symbol.to_string(),
+            price,
+            source: self.name.clone(),
+            observed_at,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Json, Router};
+    use serde_json::Value;
+    use sniper_chain::{HedgeConfig, ProviderPool, RpcEndpoint};
+
+    #[test]
+    fn test_spot_price_orientation() {
+        // 10 WETH (18 decimals) against 30,000 USDC (6 decimals)
+        let mut pool = PoolConfig {
+            address: "0xpool".to_string(),
+            base_is_token0: false,
+            decimals0: 6,
+            decimals1: 18,
+        };
+        let (usdc, weth) = (30_000_000_000u128, 10_000_000_000_000_000_000u128);
+        assert!((pool.spot_price(usdc, weth).unwrap() - 3000.0).abs() < 1e-9);
+
+        pool.base_is_token0 = true;
+        assert!((pool.spot_price(usdc, weth).unwrap() - 1.0 / 3000.0).abs() < 1e-12);
+        assert!(pool.spot_price(0, weth).is_none());
+    }
+
+    async fn handle(Json(request): Json<Value>) -> Json<Value> {
+        assert_eq!(request["params"][0]["data"], GET_RESERVES);
+        // 30,000 USDC / 10 WETH, last updated at block timestamp 1
+        let result = format!("0x{:064x}{:064x}{:064x}", 30_000_000_000u128, 10_000_000_000_000_000_000u128, 1);
+        Json(json!({"jsonrpc": "2.0", "id": request["id"], "result": result}))
+    }
+
+    #[tokio::test]
+    async fn test_prices_from_reserves() {
+        let app = Router::new().route("/", post(handle));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let pool = ProviderPool::new(vec![RpcEndpoint { name: "mock".to_string(), url }], HedgeConfig::default());
+        let rpc = Arc::new(JsonRpcClient::new(Arc::new(pool)).unwrap());
+        let feed = DexTwapFeed::new("uniswap-twap", rpc, 300).with_pool("WETH/USDC", PoolConfig {
+            address: "0xpool".to_string(),
+            base_is_token0: false,
+            decimals0: 6,
+            decimals1: 18,
+        });
+
+        let quote = feed.latest_price("WETH/USDC").await.unwrap().unwrap();
+        assert!((quote.price - 3000.0).abs() < 1e-6);
+        assert!(feed.latest_price("WBTC/USDC").await.unwrap().is_none());
+    }
+}
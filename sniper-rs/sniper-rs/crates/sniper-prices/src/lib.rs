@@ -0,0 +1,92 @@
+//! Live price feeds for the sniper bot.
+//!
+//! A [`PriceFeed`] returns the latest price of a symbol from one provider:
+//! Chainlink aggregators read on chain, a TWAP over DEX pool reserves, a REST
+//! ticker endpoint, or a WebSocket stream fed by whoever owns the socket. A
+//! [`PriceCache`] queries its feeds in priority order, keeps the latest quote
+//! per symbol and broadcasts every new one, so order and portfolio managers
+//! can mark to market without prices being posted to them by hand.
+
+pub mod cache;
+pub mod chainlink;
+pub mod dex;
+pub mod rest;
+pub mod stream;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub use cache::{PriceCache, PriceCacheConfig};
+pub use chainlink::ChainlinkFeed;
+pub use dex::{DexTwapFeed, PoolConfig};
+pub use rest::{RestFeed, RestFeedConfig};
+pub use stream::StreamFeed;
+
+/// A price reported by one feed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceQuote {
+    pub symbol: String,
+    pub price: f64,
+    /// Feed the price came from
+    pub source: String,
+    /// When the provider observed the price
+    pub observed_at: DateTime<Utc>,
+}
+
+/// Pluggable live price provider
+#[async_trait]
+pub trait PriceFeed: Send + Sync {
+    /// Feed name, unique within a cache
+    fn name(&self) -> &str;
+
+    /// Fetch the latest price of a symbol, `None` if the feed does not cover it
+    async fn latest_price(&self, symbol: &str) -> Result<Option<PriceQuote>>;
+}
+
+/// 32-byte ABI word at `index` of call return data
+pub(crate) fn abi_word(data: &[u8], index: usize) -> Result<&[u8]> {
+    data.get(index * 32..(index + 1) * 32)
+        .ok_or_else(|| anyhow::anyhow!("Return data of {} bytes has no word {}", data.len(), index))
+}
+
+/// Unsigned ABI word, failing if it does not fit in 128 bits
+pub(crate) fn abi_uint(word: &[u8]) -> Result<u128> {
+    let (high, low) = word.split_at(16);
+    if high.iter().any(|byte| *byte != 0) {
+        return Err(anyhow::anyhow!("ABI word 0x{} overflows 128 bits", hex::encode(word)));
+    }
+    Ok(u128::from_be_bytes(low.try_into()?))
+}
+
+/// Signed ABI word, failing if it does not fit in 128 bits
+pub(crate) fn abi_int(word: &[u8]) -> Result<i128> {
+    let (high, low) = word.split_at(16);
+    let value = i128::from_be_bytes(low.try_into()?);
+    let extension = if value < 0 { 0xff } else { 0 };
+    if high.iter().any(|byte| *byte != extension) {
+        return Err(anyhow::anyhow!("ABI word 0x{} overflows 128 bits", hex::encode(word)));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_abi_words() {
+        let mut data = vec![0u8; 64];
+        data[31] = 0x2a;
+        data[32..].fill(0xff);
+        assert_eq!(abi_uint(abi_word(&data, 0).unwrap()).unwrap(), 42);
+        assert_eq!(abi_int(abi_word(&data, 1).unwrap()).unwrap(), -1);
+        assert!(abi_uint(abi_word(&data, 1).unwrap()).is_err());
+        assert!(abi_word(&data, 2).is_err());
+
+        // A positive value with stray high bytes is not sign-extended
+        data[32..48].fill(0);
+        assert!(abi_int(abi_word(&data, 1).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+//! REST ticker polling.
+//!
+//! The feed requests a URL template with `{symbol}` replaced by the
+//! provider's name for the symbol and reads the price at a JSON pointer of
+//! the response. Prices may be JSON numbers or numeric strings, as most
+//! exchanges send them.
+
+use crate::{PriceFeed, PriceQuote};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Timeout of a single ticker request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// REST feed settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestFeedConfig {
+    pub name: String,
+    /// Ticker URL with a `{symbol}` placeholder
+    pub url: String,
+    /// JSON pointer to the price in the response, e.g. `/price`
+    pub price_pointer: String,
+    /// Provider symbol of each symbol covered, e.g. `ETH/USDT` to `ETHUSDT`
+    pub symbols: HashMap<String, String>,
+}
+
+/// Price feed polling a REST ticker endpoint
+pub struct RestFeed {
+    config: RestFeedConfig,
+    http: reqwest::Client,
+}
+
+impl RestFeed {
+    /// Create a feed polling the configured endpoint
+    pub fn new(config: RestFeedConfig) -> Result<Self> {
+        let http = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+        Ok(Self { config, http })
+    }
+}
+
+/// Price at `pointer` of a JSON value, from a number or numeric string
+pub(crate) fn price_at(value: &Value, pointer: &str) -> Option<f64> {
+    let price = match value.pointer(pointer)? {
+        Value::Number(number) => number.as_f64()?,
+        Value::String(text) => text.parse().ok()?,
+        _ => return None,
+    };
+    (price.is_finite() && price > 0.0).then_some(price)
+}
+
+#[async_trait]
+impl PriceFeed for RestFeed {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    async fn latest_price(&self, symbol: &str) -> Result<Option<PriceQuote>> {
+        let Some(remote) = self.config.symbols.get(symbol) else {
+            return Ok(None);
+        };
+        let url = self.config.url.replace("{symbol}", remote);
+        let response: Value = self.http.get(&url).send().await?.error_for_status()?.json().await?;
+        let price = price_at(&response, &self.config.price_pointer).ok_or_else(|| {
+            anyhow::anyhow!("No price at {} in {} response for {}", self.config.price_pointer, self.config.name, symbol)
+        })?;
+
+        Ok(Some(PriceQuote {
+            symbol: symbol.to_string(),
+            price,
+            source: self.config.name.clone(),
+            observed_at: Utc::now(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::Query, routing::get, Json, Router};
+    use serde_json::json;
+
+    async fn ticker(Query(params): Query<HashMap<String, String>>) -> Json<Value> {
+        Json(json!({"symbol": params["symbol"], "price": "3000.50"}))
+    }
+
+    #[tokio::test]
+    async fn test_polls_ticker() {
+        let app = Router::new().route("/ticker", get(ticker));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/ticker?symbol={{symbol}}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let mut symbols = HashMap::new();
+        symbols.insert("ETH/USDT".to_string(), "ETHUSDT".to_string());
+        let feed = RestFeed::new(RestFeedConfig {
+            name: "binance".to_string(),
+            url,
+            price_pointer: "/price".to_string(),
+            symbols,
+        })
+        .unwrap();
+
+        let quote = feed.latest_price("ETH/USDT").await.unwrap().unwrap();
+        assert_eq!(quote.price, 3000.5);
+        assert!(feed.latest_price("BTC/USDT").await.unwrap().is_none());
+
+        assert_eq!(price_at(&json!({"data": {"p": 1.5}}), "/data/p"), Some(1.5));
+        assert_eq!(price_at(&json!({"price": "-1"}), "/price"), None);
+    }
+}
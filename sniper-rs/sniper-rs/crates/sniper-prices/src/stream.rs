@@ -0,0 +1,99 @@
+//! Push-fed prices from a WebSocket stream.
+//!
+//! The feed does not own a socket: whoever holds the WebSocket connection
+//! passes its text frames to [`StreamFeed::ingest`] or [`StreamFeed::run`],
+//! and the feed keeps the latest price per symbol parsed from them. Frames
+//! that are not price updates, such as subscription acks, are ignored.
+
+use crate::rest::price_at;
+use crate::{PriceFeed, PriceQuote};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use futures::{Stream, StreamExt};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// Price feed fed by WebSocket messages
+pub struct StreamFeed {
+    name: String,
+    symbol_pointer: String,
+    price_pointer: String,
+    symbols: HashMap<String, String>,
+    quotes: RwLock<HashMap<String, PriceQuote>>,
+}
+
+impl StreamFeed {
+    /// Create a feed reading the symbol and price of each message at JSON pointers
+    pub fn new(name: &str, symbol_pointer: &str, price_pointer: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            symbol_pointer: symbol_pointer.to_string(),
+            price_pointer: price_pointer.to_string(),
+            symbols: HashMap::new(),
+            quotes: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Cover `symbol`, which the stream calls `remote`
+    pub fn with_symbol(mut self, symbol: &str, remote: &str) -> Self {
+        self.symbols.insert(remote.to_string(), symbol.to_string());
+        self
+    }
+
+    /// Parse a message, recording the price it carries for a covered symbol
+    pub fn ingest(&self, message: &str) -> Option<PriceQuote> {
+        let value: Value = serde_json::from_str(message).ok()?;
+        let remote = value.pointer(&self.symbol_pointer)?.as_str()?;
+        let symbol = self.symbols.get(remote)?;
+        let quote = PriceQuote {
+            symbol: symbol.clone(),
+            price: price_at(&value, &self.price_pointer)?,
+            source: self.name.clone(),
+            observed_at: Utc::now(),
+        };
+        self.quotes.write().unwrap().insert(symbol.clone(), quote.clone());
+        Some(quote)
+    }
+
+    /// Ingest messages until the stream ends
+    pub async fn run<S: Stream<Item = String> + Unpin>(&self, mut messages: S) {
+        while let Some(message) = messages.next().await {
+            self.ingest(&message);
+        }
+        tracing::warn!("price stream {} ended", self.name);
+    }
+}
+
+#[async_trait]
+impl PriceFeed for StreamFeed {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn latest_price(&self, symbol: &str) -> Result<Option<PriceQuote>> {
+        Ok(self.quotes.read().unwrap().get(symbol).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_ingests_stream_messages() {
+        let feed = StreamFeed::new("binance-ws", "/s", "/c").with_symbol("ETH/USDT", "ETHUSDT");
+        let messages = futures::stream::iter(vec![
+            r#"{"result": null, "id": 1}"#.to_string(),
+            r#"{"e": "24hrMiniTicker", "s": "ETHUSDT", "c": "3001.25"}"#.to_string(),
+            r#"{"e": "24hrMiniTicker", "s": "BTCUSDT", "c": "60000"}"#.to_string(),
+            "not json".to_string(),
+        ]);
+        feed.run(messages).await;
+
+        let quote = feed.latest_price("ETH/USDT").await.unwrap().unwrap();
+        assert_eq!(quote.price, 3001.25);
+        assert!(feed.latest_price("BTC/USDT").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,295 @@
+//! Depeg and oracle-divergence guard.
+//!
+//! Compares pool-implied prices against the aggregated oracle and blocks
+//! execution when the pool diverges too far, or when a pegged asset has lost
+//! its peg. Operators can grant a time-limited override per asset, and with a
+//! bus attached every grant and revocation is published as an audit event.
+//! With a [`RiskEngine`] attached, every verdict is recorded into it, so the
+//! pre-trade checks of orders and plans refuse trades in blocked assets.
+
+use crate::engine::RiskEngine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sniper_core::audit::{self, AuditEvent, AUDIT_SUBJECT};
+use sniper_core::bus::InMemoryBus;
+use sniper_core::types::Decision;
+use sniper_oracle::PriceOracle;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Divergence guard settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DivergenceGuardConfig {
+    /// Maximum relative divergence of a pool price from the oracle price
+    pub max_divergence: f64,
+    /// Maximum relative distance of a pegged asset from its peg
+    pub max_depeg: f64,
+    /// Peg targets per asset, e.g. 1.0 for USD stablecoins
+    pub pegs: HashMap<String, f64>,
+    /// Block assets the oracle has no price for
+    pub require_oracle: bool,
+}
+
+impl Default for DivergenceGuardConfig {
+    fn default() -> Self {
+        Self {
+            max_divergence: 0.03,
+            max_depeg: 0.01,
+            pegs: HashMap::new(),
+            require_oracle: false,
+        }
+    }
+}
+
+/// Operator override allowing execution despite divergence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuardOverride {
+    pub asset: String,
+    pub approved_by: String,
+    pub reason: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Guard blocking execution into manipulated or depegged pools
+pub struct DivergenceGuard {
+    config: DivergenceGuardConfig,
+    oracle: Arc<PriceOracle>,
+    overrides: HashMap<String, GuardOverride>,
+    bus: Option<InMemoryBus>,
+    risk: Option<RiskEngine>,
+}
+
+impl DivergenceGuard {
+    /// Create a new divergence guard
+    pub fn new(config: DivergenceGuardConfig, oracle: Arc<PriceOracle>) -> Self {
+        Self {
+            config,
+            oracle,
+            overrides: HashMap::new(),
+            bus: None,
+            risk: None,
+        }
+    }
+
+    /// Publish override grants and revocations as audit events on a bus
+    pub fn with_bus(mut self, bus: InMemoryBus) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Record every verdict into a risk engine's pre-trade checks
+    pub fn with_risk(mut self, risk: RiskEngine) -> Self {
+        self.risk = Some(risk);
+        self
+    }
+
+    /// Publish an audit event about an asset's override if a bus is attached
+    fn audit(&self, action: &str, asset: &str, actor: Option<&str>, details: String) {
+        if let Some(bus) = &self.bus {
+            let resource = format!("risk/divergence/{}", asset);
+            let event = AuditEvent::new(action, resource.clone(), resource)
+                .with_actor(actor)
+                .with_details(details);
+            if let Err(e) = bus.publish_now(AUDIT_SUBJECT, &event) {
+                tracing::warn!("Failed to publish risk override audit event: {}", e);
+            }
+        }
+    }
+
+    /// Grant an override for an asset until it expires
+    pub fn grant_override(&mut self, guard_override: GuardOverride) {
+        tracing::warn!(
+            "divergence guard override for {} granted by {}: {}",
+            guard_override.asset, guard_override.approved_by, guard_override.reason
+        );
+        let details = format!("{} until {}", guard_override.reason, guard_override.expires_at.to_rfc3339());
+        self.audit(
+            audit::RISK_OVERRIDE_GRANTED,
+            &guard_override.asset,
+            Some(&guard_override.approved_by),
+            details,
+        );
+        self.overrides.insert(guard_override.asset.clone(), guard_override);
+    }
+
+    /// Revoke the override for an asset
+    pub fn revoke_override(&mut self, asset: &str) -> bool {
+        let Some(revoked) = self.overrides.remove(asset) else {
+            return false;
+        };
+        let details = format!("override granted by {} revoked", revoked.approved_by);
+        self.audit(audit::RISK_OVERRIDE_REVOKED, asset, None, details);
+        true
+    }
+
+    /// Get the active override for an asset
+    pub fn active_override(&self, asset: &str) -> Option<&GuardOverride> {
+        self.overrides
+            .get(asset)
+            .filter(|guard_override| guard_override.expires_at > Utc::now())
+    }
+
+    /// Check a pool-implied price for an asset against the oracle
+    pub async fn check(&self, asset: &str, pool_price: f64) -> Decision {
+        let decision = self.evaluate(asset, pool_price).await;
+        if let Some(risk) = &self.risk {
+            risk.record_divergence(asset, &decision);
+        }
+        decision
+    }
+
+    async fn evaluate(&self, asset: &str, pool_price: f64) -> Decision {
+        let mut violations = Vec::new();
+
+        match self.oracle.aggregate(asset).await {
+            Ok(aggregated) => {
+                let divergence = (pool_price - aggregated.price).abs() / aggregated.price;
+                if divergence > self.config.max_divergence {
+                    violations.push(format!(
+                        "Pool price {} for {} diverges {:.2}% from oracle price {}",
+                        pool_price, asset, divergence * 100.0, aggregated.price
+                    ));
+                }
+
+                if let Some(peg) = self.config.pegs.get(asset) {
+                    let depeg = (aggregated.price - peg).abs() / peg;
+                    if depeg > self.config.max_depeg {
+                        violations.push(format!(
+                            "{} is depegged: oracle price {} is {:.2}% from peg {}",
+                            asset, aggregated.price, depeg * 100.0, peg
+                        ));
+                    }
+                }
+            },
+            Err(e) if self.config.require_oracle => {
+                violations.push(format!("No oracle price for {}: {}", asset, e));
+            },
+            Err(_) => {
+                return Decision {
+                    allow: true,
+                    reasons: vec![format!("No oracle coverage for {}, divergence not checked", asset)],
+                };
+            },
+        }
+
+        if violations.is_empty() {
+            return Decision {
+                allow: true,
+                reasons: vec![format!("Pool price for {} is consistent with oracle", asset)],
+            };
+        }
+
+        if let Some(guard_override) = self.active_override(asset) {
+            violations.push(format!(
+                "Overridden by {}: {}",
+                guard_override.approved_by, guard_override.reason
+            ));
+            return Decision {
+                allow: true,
+                reasons: violations,
+            };
+        }
+
+        Decision {
+            allow: false,
+            reasons: violations,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sniper_oracle::sources::{ChainlinkRound, ChainlinkSource};
+    use sniper_oracle::OracleConfig;
+
+    fn guard_with_price(asset: &str, price: f64, config: DivergenceGuardConfig) -> DivergenceGuard {
+        let chainlink = Arc::new(ChainlinkSource::new("chainlink"));
+        chainlink.update_round(asset, ChainlinkRound {
+            round_id: 1,
+            answer: (price * 1e8) as i128,
+            decimals: 8,
+            updated_at: Utc::now(),
+        });
+        let mut oracle = PriceOracle::new(OracleConfig::default());
+        oracle.add_source(chainlink);
+        DivergenceGuard::new(config, Arc::new(oracle))
+    }
+
+    #[tokio::test]
+    async fn test_divergence_blocks_and_override() {
+        let bus = InMemoryBus::new(16);
+        let mut rx = bus.subscribe(AUDIT_SUBJECT);
+        let mut guard = guard_with_price("WETH", 3000.0, DivergenceGuardConfig::default()).with_bus(bus);
+
+        assert!(guard.check("WETH", 3030.0).await.allow);
+        assert!(!guard.check("WETH", 3300.0).await.allow);
+
+        guard.grant_override(GuardOverride {
+            asset: "WETH".to_string(),
+            approved_by: "risk-desk".to_string(),
+            reason: "known CEX outage".to_string(),
+            expires_at: Utc::now() + chrono::Duration::minutes(5),
+        });
+        assert!(guard.check("WETH", 3300.0).await.allow);
+
+        guard.revoke_override("WETH");
+        assert!(!guard.check("WETH", 3300.0).await.allow);
+        assert!(!guard.revoke_override("WETH"));
+
+        let granted = AuditEvent::decode(&rx.recv().await.unwrap()).unwrap();
+        assert_eq!((granted.action.as_str(), granted.actor.as_str()), (audit::RISK_OVERRIDE_GRANTED, "risk-desk"));
+        let revoked = AuditEvent::decode(&rx.recv().await.unwrap()).unwrap();
+        assert_eq!(revoked.action, audit::RISK_OVERRIDE_REVOKED);
+        assert_eq!(revoked.correlation_id, granted.correlation_id);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_depeg_blocks() {
+        let mut pegs = HashMap::new();
+        pegs.insert("USDC".to_string(), 1.0);
+        let config = DivergenceGuardConfig {
+            pegs,
+            ..DivergenceGuardConfig::default()
+        };
+        let guard = guard_with_price("USDC", 0.95, config);
+
+        // Pool agrees with the oracle, but the asset itself is off peg
+        let decision = guard.check("USDC", 0.95).await;
+        assert!(!decision.allow);
+        assert!(decision.reasons[0].contains("depegged"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_oracle_price() {
+        let oracle = Arc::new(PriceOracle::new(OracleConfig::default()));
+        let guard = DivergenceGuard::new(DivergenceGuardConfig::default(), oracle.clone());
+        assert!(guard.check("NEWTOKEN", 1.0).await.allow);
+
+        let strict = DivergenceGuard::new(
+            DivergenceGuardConfig {
+                require_oracle: true,
+                ..DivergenceGuardConfig::default()
+            },
+            oracle,
+        );
+        assert!(!strict.check("NEWTOKEN", 1.0).await.allow);
+    }
+
+    #[tokio::test]
+    async fn test_verdicts_gate_pre_trade_checks() {
+        let risk = RiskEngine::default();
+        let guard = guard_with_price("WETH", 3000.0, DivergenceGuardConfig::default()).with_risk(risk.clone());
+        let request = crate::PreTradeRequest {
+            symbol: "PEPE/WETH".to_string(),
+            side: "buy".to_string(),
+            notional: None,
+        };
+
+        guard.check("WETH", 3300.0).await;
+        assert!(risk.check(&request).unwrap_err().to_string().contains("divergence guard"));
+        guard.check("WETH", 3030.0).await;
+        assert!(risk.check(&request).is_ok());
+    }
+}
@@ -0,0 +1,442 @@
+//! Pre-trade risk gate shared by the order and portfolio managers.
+//!
+//! A [`RiskEngine`] is a cloneable handle. The portfolio publishes its
+//! exposure into it as positions change, the order manager publishes last
+//! prices, and both call [`RiskEngine::check`] before accepting an order or
+//! producing a trade plan. Safety scans of tokens are recorded into it too,
+//! so buys of tokens scoring too high are refused, and so are the verdicts of
+//! a [`DivergenceGuard`], so trades in assets it blocks are refused until a
+//! later check clears them. A rejection lists every limit the trade breaches,
+//! not just the first.
+//!
+//! [`DivergenceGuard`]: crate::divergence::DivergenceGuard
+
+use crate::limits::RiskLimits;
+use crate::token_scanner::TokenScanReport;
+use serde::{Deserialize, Serialize};
+use sniper_core::errors::{DomainError, ErrorKind};
+use sniper_core::types::Decision;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+/// Trade submitted to the pre-trade checks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreTradeRequest {
+    pub symbol: String,
+    pub side: String, // "sell" reduces exposure, "buy", "long" and "short" add to it
+    /// Size in quote units, `None` when no price is known to value it
+    pub notional: Option<f64>,
+}
+
+impl PreTradeRequest {
+    /// Whether the trade adds exposure rather than reducing it
+    pub fn opens_exposure(&self) -> bool {
+        self.side != "sell"
+    }
+}
+
+/// Portfolio exposure the position and loss limits are checked against
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Exposure {
+    /// Capital plus realized and unrealized PnL
+    pub equity: f64,
+    /// Sum of open position notionals
+    pub gross_exposure: f64,
+    /// PnL since the start of the UTC day
+    pub daily_pnl: f64,
+    /// Open positions per symbol
+    pub open_positions: HashMap<String, usize>,
+}
+
+/// Why a trade was rejected
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum RiskRejection {
+    BannedToken { token: String },
+    NotionalTooLarge { notional: f64, limit: f64 },
+    NoReferencePrice { symbol: String },
+    TooManyOpenPositions { symbol: String, open: usize, limit: usize },
+    LeverageTooHigh { leverage: f64, limit: f64 },
+    NoEquity { equity: f64 },
+    DailyLossLimit { loss: f64, limit: f64 },
+    UnsafeToken { token: String, risk_score: u8, limit: u8 },
+    PriceDivergence { asset: String, reasons: Vec<String> },
+}
+
+impl fmt::Display for RiskRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RiskRejection::BannedToken { token } => write!(f, "{} is banned", token),
+            RiskRejection::NotionalTooLarge { notional, limit } => {
+                write!(f, "notional {} exceeds the per-order limit of {}", notional, limit)
+            },
+            RiskRejection::NoReferencePrice { symbol } => {
+                write!(f, "no price to value the order in {}", symbol)
+            },
+            RiskRejection::TooManyOpenPositions { symbol, open, limit } => {
+                write!(f, "{} open positions in {} reach the limit of {}", open, symbol, limit)
+            },
+            RiskRejection::LeverageTooHigh { leverage, limit } => {
+                write!(f, "leverage {:.2}x would exceed the limit of {:.2}x", leverage, limit)
+            },
+            RiskRejection::NoEquity { equity } => {
+                write!(f, "equity {} leaves no room for new exposure", equity)
+            },
+            RiskRejection::DailyLossLimit { loss, limit } => {
+                write!(f, "daily loss {} reached the limit of {}", loss, limit)
+            },
+            RiskRejection::UnsafeToken { token, risk_score, limit } => {
+                write!(f, "{} scored {} in its safety scan, above the limit of {}", token, risk_score, limit)
+            },
+            RiskRejection::PriceDivergence { asset, reasons } => {
+                write!(f, "{} is blocked by the divergence guard: {}", asset, reasons.join(", "))
+            },
+        }
+    }
+}
+
+/// Error returned for a trade that failed the pre-trade checks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RiskRejected {
+    pub symbol: String,
+    pub rejections: Vec<RiskRejection>,
+}
+
+impl fmt::Display for RiskRejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Pre-trade risk check rejected {}: ", self.symbol)?;
+        for (i, rejection) in self.rejections.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", rejection)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RiskRejected {}
+
+impl DomainError for RiskRejected {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Invalid
+    }
+}
+
+/// Exposure and prices published by the managers
+#[derive(Debug, Default)]
+struct EngineState {
+    exposure: Option<Exposure>,
+    marks: HashMap<String, f64>,
+    /// Risk scores of scanned tokens, by lowercase address
+    token_scores: HashMap<String, u8>,
+    /// Reasons the divergence guard last blocked an asset for, by lowercase asset
+    divergences: HashMap<String, Vec<String>>,
+}
+
+/// Pre-trade risk engine
+#[derive(Debug, Clone, Default)]
+pub struct RiskEngine {
+    limits: Arc<RiskLimits>,
+    state: Arc<RwLock<EngineState>>,
+}
+
+impl RiskEngine {
+    /// Create a new risk engine
+    pub fn new(limits: RiskLimits) -> Self {
+        Self {
+            limits: Arc::new(limits),
+            state: Arc::default(),
+        }
+    }
+
+    /// Get the configured limits
+    pub fn limits(&self) -> &RiskLimits {
+        &self.limits
+    }
+
+    /// Replace the portfolio exposure the limits are checked against
+    pub fn publish_exposure(&self, exposure: Exposure) {
+        if let Ok(mut state) = self.state.write() {
+            state.exposure = Some(exposure);
+        }
+    }
+
+    /// Get the last published exposure, `None` if no portfolio publishes one
+    pub fn exposure(&self) -> Option<Exposure> {
+        self.state.read().ok()?.exposure.clone()
+    }
+
+    /// Record the last price of a symbol, used to value orders without one
+    pub fn record_price(&self, symbol: &str, price: f64) {
+        if let Ok(mut state) = self.state.write() {
+            state.marks.insert(symbol.to_string(), price);
+        }
+    }
+
+    /// Get the last recorded price of a symbol
+    pub fn mark(&self, symbol: &str) -> Option<f64> {
+        self.state.read().ok()?.marks.get(symbol).copied()
+    }
+
+    /// Record the safety scan of a token, checked against `max_token_risk_score`
+    pub fn record_token_scan(&self, report: &TokenScanReport) {
+        if let Ok(mut state) = self.state.write() {
+            state.token_scores.insert(report.token.to_lowercase(), report.risk_score);
+        }
+    }
+
+    /// Get the risk score of the last scan of a token
+    pub fn token_risk_score(&self, token: &str) -> Option<u8> {
+        self.state.read().ok()?.token_scores.get(&token.to_lowercase()).copied()
+    }
+
+    /// Record the divergence guard's latest verdict on an asset
+    ///
+    /// A blocking verdict refuses trades in the asset until an allowing one,
+    /// such as after an override, replaces it.
+    pub fn record_divergence(&self, asset: &str, decision: &Decision) {
+        if let Ok(mut state) = self.state.write() {
+            if decision.allow {
+                state.divergences.remove(&asset.to_lowercase());
+            } else {
+                state.divergences.insert(asset.to_lowercase(), decision.reasons.clone());
+            }
+        }
+    }
+
+    /// Get the reasons an asset is blocked by the divergence guard, if it is
+    pub fn divergence(&self, asset: &str) -> Option<Vec<String>> {
+        self.state.read().ok()?.divergences.get(&asset.to_lowercase()).cloned()
+    }
+
+    /// Run every pre-trade check, rejecting with all limits the trade breaches
+    ///
+    /// Position, leverage and loss limits only apply to trades that open
+    /// exposure, and are skipped until a portfolio publishes its exposure.
+    /// Divergence blocks apply to the symbol and each of its tokens, on both
+    /// sides, as the guard blocks execution into the pool either way.
+    pub fn check(&self, request: &PreTradeRequest) -> Result<(), RiskRejected> {
+        let limits = &self.limits;
+        let mut rejections = Vec::new();
+
+        if let Some(token) = limits.banned_token(&request.symbol) {
+            rejections.push(RiskRejection::BannedToken { token: token.to_string() });
+        }
+
+        let tokens = request.symbol.split(['/', '-']).map(str::trim).filter(|token| *token != request.symbol);
+        for asset in std::iter::once(request.symbol.as_str()).chain(tokens) {
+            if let Some(reasons) = self.divergence(asset) {
+                rejections.push(RiskRejection::PriceDivergence {
+                    asset: asset.to_string(),
+                    reasons,
+                });
+            }
+        }
+
+        if let Some(limit) = limits.max_order_notional {
+            match request.notional {
+                Some(notional) if notional > limit => {
+                    rejections.push(RiskRejection::NotionalTooLarge { notional, limit });
+                },
+                Some(_) => {},
+                None => rejections.push(RiskRejection::NoReferencePrice { symbol: request.symbol.clone() }),
+            }
+        }
+
+        if let Some(limit) = limits.max_token_risk_score.filter(|_| request.opens_exposure()) {
+            for token in request.symbol.split(['/', '-']).map(str::trim) {
+                match self.token_risk_score(token) {
+                    Some(risk_score) if risk_score > limit => rejections.push(RiskRejection::UnsafeToken {
+                        token: token.to_string(),
+                        risk_score,
+                        limit,
+                    }),
+                    _ => {},
+                }
+            }
+        }
+
+        if let Some(exposure) = self.exposure().filter(|_| request.opens_exposure()) {
+            if let Some(limit) = limits.max_open_positions_per_symbol {
+                let open = exposure.open_positions.get(&request.symbol).copied().unwrap_or(0);
+                if open >= limit {
+                    rejections.push(RiskRejection::TooManyOpenPositions {
+                        symbol: request.symbol.clone(),
+                        open,
+                        limit,
+                    });
+                }
+            }
+
+            if let Some(limit) = limits.max_leverage {
+                if exposure.equity <= 0.0 {
+                    rejections.push(RiskRejection::NoEquity { equity: exposure.equity });
+                } else {
+                    let leverage = (exposure.gross_exposure + request.notional.unwrap_or(0.0)) / exposure.equity;
+                    if leverage > limit {
+                        rejections.push(RiskRejection::LeverageTooHigh { leverage, limit });
+                    }
+                }
+            }
+
+            if let Some(limit) = limits.daily_loss_limit {
+                let loss = -exposure.daily_pnl;
+                if loss >= limit {
+                    rejections.push(RiskRejection::DailyLossLimit { loss, limit });
+                }
+            }
+        }
+
+        if rejections.is_empty() {
+            Ok(())
+        } else {
+            tracing::warn!("pre-trade risk check rejected {} {}: {:?}", request.side, request.symbol, rejections);
+            Err(RiskRejected {
+                symbol: request.symbol.clone(),
+                rejections,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(symbol: &str, side: &str, notional: Option<f64>) -> PreTradeRequest {
+        PreTradeRequest {
+            symbol: symbol.to_string(),
+            side: side.to_string(),
+            notional,
+        }
+    }
+
+    #[test]
+    fn test_default_limits_accept_everything() {
+        let engine = RiskEngine::default();
+        assert!(engine.check(&request("PEPE/WETH", "buy", None)).is_ok());
+        assert!(engine.check(&request("PEPE/WETH", "buy", Some(1e12))).is_ok());
+    }
+
+    #[test]
+    fn test_order_limits() {
+        let engine = RiskEngine::new(RiskLimits {
+            max_order_notional: Some(1000.0),
+            banned_tokens: vec!["scam".to_string()],
+            ..RiskLimits::default()
+        });
+
+        assert!(engine.check(&request("PEPE/WETH", "buy", Some(999.0))).is_ok());
+
+        let rejected = engine.check(&request("SCAM/WETH", "sell", Some(1500.0))).unwrap_err();
+        assert_eq!(rejected.rejections, vec![
+            RiskRejection::BannedToken { token: "scam".to_string() },
+            RiskRejection::NotionalTooLarge { notional: 1500.0, limit: 1000.0 },
+        ]);
+        assert!(rejected.to_string().contains("scam is banned"));
+
+        let rejected = engine.check(&request("PEPE/WETH", "buy", None)).unwrap_err();
+        assert_eq!(rejected.rejections, vec![RiskRejection::NoReferencePrice { symbol: "PEPE/WETH".to_string() }]);
+    }
+
+    #[test]
+    fn test_exposure_limits_apply_to_opening_trades() {
+        let engine = RiskEngine::new(RiskLimits {
+            max_open_positions_per_symbol: Some(2),
+            max_leverage: Some(2.0),
+            daily_loss_limit: Some(500.0),
+            ..RiskLimits::default()
+        });
+
+        // Nothing to check against until a portfolio publishes its exposure
+        assert!(engine.check(&request("ETH/USDC", "buy", Some(50_000.0))).is_ok());
+
+        let mut open_positions = HashMap::new();
+        open_positions.insert("ETH/USDC".to_string(), 2);
+        engine.publish_exposure(Exposure {
+            equity: 10_000.0,
+            gross_exposure: 15_000.0,
+            daily_pnl: -600.0,
+            open_positions,
+        });
+
+        let rejected = engine.check(&request("ETH/USDC", "buy", Some(6000.0))).unwrap_err();
+        assert_eq!(rejected.rejections.len(), 3);
+        assert!(matches!(rejected.rejections[0], RiskRejection::TooManyOpenPositions { open: 2, limit: 2, .. }));
+        assert!(matches!(rejected.rejections[1], RiskRejection::LeverageTooHigh { leverage, .. } if (leverage - 2.1).abs() < 1e-9));
+        assert!(matches!(rejected.rejections[2], RiskRejection::DailyLossLimit { loss, .. } if loss == 600.0));
+
+        // Reducing exposure is always allowed
+        assert!(engine.check(&request("ETH/USDC", "sell", Some(6000.0))).is_ok());
+
+        // Clones share the published state
+        let shared = engine.clone();
+        shared.publish_exposure(Exposure {
+            equity: 10_000.0,
+            ..Exposure::default()
+        });
+        assert!(engine.check(&request("ETH/USDC", "buy", Some(6000.0))).is_ok());
+    }
+
+    #[test]
+    fn test_token_scan_scores_gate_buys() {
+        use crate::honeypot::RoundTrip;
+        use crate::owner_powers::{ContractPowers, Ownership};
+
+        let engine = RiskEngine::new(RiskLimits {
+            max_token_risk_score: Some(30),
+            ..RiskLimits::default()
+        });
+        let token = "0x2222222222222222222222222222222222222222";
+        let symbol = format!("{}/WETH", token.to_uppercase().replacen("0X", "0x", 1));
+        // Unscanned tokens are not checked
+        assert!(engine.check(&request(&symbol, "buy", None)).is_ok());
+
+        let honeypot = RoundTrip {
+            verified: true,
+            sell_reverted: Some("TRANSFER_FAILED".to_string()),
+            ..RoundTrip::default()
+        };
+        let powers = ContractPowers {
+            ownership: Ownership::Renounced,
+            total_supply: None,
+            max_tx_amount: None,
+            max_wallet_amount: None,
+        };
+        engine.record_token_scan(&TokenScanReport::new(token, honeypot, powers, 1.0));
+        assert_eq!(engine.token_risk_score(token), Some(100));
+
+        let rejected = engine.check(&request(&symbol, "buy", None)).unwrap_err();
+        assert_eq!(rejected.rejections, vec![RiskRejection::UnsafeToken {
+            token: symbol.split('/').next().unwrap().to_string(),
+            risk_score: 100,
+            limit: 30,
+        }]);
+        // Positions already held can still be sold
+        assert!(engine.check(&request(&symbol, "sell", None)).is_ok());
+    }
+
+    #[test]
+    fn test_divergence_verdicts_gate_trades() {
+        let engine = RiskEngine::default();
+        let blocked = Decision {
+            allow: false,
+            reasons: vec!["Pool price 3300 for WETH diverges 10.00% from oracle price 3000".to_string()],
+        };
+        engine.record_divergence("WETH", &blocked);
+
+        let rejected = engine.check(&request("PEPE/WETH", "buy", None)).unwrap_err();
+        assert_eq!(rejected.rejections, vec![RiskRejection::PriceDivergence {
+            asset: "WETH".to_string(),
+            reasons: blocked.reasons.clone(),
+        }]);
+        assert!(engine.check(&request("PEPE/WETH", "sell", None)).is_err());
+        assert!(engine.check(&request("PEPE/USDC", "buy", None)).is_ok());
+
+        // A later allowing verdict, e.g. under an override, lifts the block
+        engine.record_divergence("weth", &Decision { allow: true, reasons: Vec::new() });
+        assert!(engine.check(&request("PEPE/WETH", "buy", None)).is_ok());
+    }
+}
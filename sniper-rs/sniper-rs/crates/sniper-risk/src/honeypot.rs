@@ -1 +1,90 @@
-pub fn placeholder() { /* TODO: implement */ }
+//! Honeypot and transfer tax detection.
+//!
+//! A token is probed by simulating a small buy and selling everything
+//! received straight back. A token that can be bought but not sold is a
+//! honeypot; what the two legs lose against the router's quotes are the buy
+//! and sell taxes. Only a fork runs the sell leg, so round trips simulated
+//! with `eth_call` are reported as unverified.
+
+use serde::{Deserialize, Serialize};
+use sniper_exec::simulator::{SimulationBackend, SimulationIssue, SimulationReport};
+
+/// Outcome of a simulated buy+sell round trip
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RoundTrip {
+    /// Whether the sell leg was run, so the absence of a honeypot is known
+    pub verified: bool,
+    /// Revert reason of the buy, e.g. while trading is not yet enabled
+    pub buy_reverted: Option<String>,
+    /// Revert reason of the approval or sell of the tokens bought
+    pub sell_reverted: Option<String>,
+    /// Share of the quote lost on buying, in percent
+    pub buy_tax_pct: Option<f64>,
+    /// Share of the sell-back quote lost on selling, in percent
+    pub sell_tax_pct: Option<f64>,
+}
+
+impl RoundTrip {
+    /// Read the round trip out of the simulation of a probe buy
+    pub fn from_simulation(report: &SimulationReport) -> Self {
+        let mut round_trip = Self {
+            buy_tax_pct: report.buy_tax_pct,
+            sell_tax_pct: report.sell_tax_pct,
+            ..Self::default()
+        };
+        for issue in &report.issues {
+            match issue {
+                SimulationIssue::BuyReverted(reason) => round_trip.buy_reverted = Some(reason.clone()),
+                SimulationIssue::Honeypot(reason) => round_trip.sell_reverted = Some(reason.clone()),
+                _ => {}
+            }
+        }
+        round_trip.verified = report.backend == SimulationBackend::Fork
+            && (round_trip.sell_tax_pct.is_some() || round_trip.sell_reverted.is_some());
+        round_trip
+    }
+
+    /// Whether the token can be bought but not sold
+    pub fn is_honeypot(&self) -> bool {
+        self.sell_reverted.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(backend: SimulationBackend, issues: Vec<SimulationIssue>) -> SimulationReport {
+        SimulationReport {
+            backend,
+            quoted_out: 100,
+            simulated_out: Some(97),
+            buy_tax_pct: None,
+            sell_tax_pct: None,
+            issues,
+        }
+    }
+
+    #[test]
+    fn test_round_trip_from_simulation() {
+        let mut taxed = report(SimulationBackend::Fork, vec![SimulationIssue::SellTaxTooHigh { tax_pct: 20.0 }]);
+        taxed.buy_tax_pct = Some(3.0);
+        taxed.sell_tax_pct = Some(20.0);
+        let round_trip = RoundTrip::from_simulation(&taxed);
+        assert!(round_trip.verified && !round_trip.is_honeypot());
+        assert_eq!((round_trip.buy_tax_pct, round_trip.sell_tax_pct), (Some(3.0), Some(20.0)));
+
+        let honeypot = report(SimulationBackend::Fork, vec![SimulationIssue::Honeypot("reverted".to_string())]);
+        let round_trip = RoundTrip::from_simulation(&honeypot);
+        assert!(round_trip.verified && round_trip.is_honeypot());
+
+        // A buy checked with eth_call says nothing about selling
+        let round_trip = RoundTrip::from_simulation(&report(SimulationBackend::EthCall, Vec::new()));
+        assert!(!round_trip.verified && !round_trip.is_honeypot());
+
+        let closed = report(SimulationBackend::Fork, vec![SimulationIssue::BuyReverted("TRADING_NOT_ENABLED".to_string())]);
+        let round_trip = RoundTrip::from_simulation(&closed);
+        assert!(!round_trip.verified);
+        assert_eq!(round_trip.buy_reverted.as_deref(), Some("TRADING_NOT_ENABLED"));
+    }
+}
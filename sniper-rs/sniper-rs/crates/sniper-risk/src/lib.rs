@@ -1,16 +1,25 @@
 //! Risk management module for the sniper bot.
 //! 
 //! This module provides functionality for evaluating trades and determining if they
-//! meet the configured risk criteria.
+//! meet the configured risk criteria. The [`RiskEngine`] is the pre-trade gate
+//! shared by the order and portfolio managers, and the [`TokenScanner`]
+//! checks tokens for honeypots, taxes and owner powers before they are sniped.
 
+pub mod engine;
 pub mod honeypot;
 pub mod owner_powers;
 pub mod lp_quality;
 pub mod limits;
 pub mod decide;
+pub mod divergence;
+pub mod token_scanner;
 
 use sniper_core::types::{Decision, TradePlan};
 
+pub use engine::{Exposure, PreTradeRequest, RiskEngine, RiskRejected, RiskRejection};
+pub use limits::RiskLimits;
+pub use token_scanner::{ScannerConfig, TokenFinding, TokenScanAssessor, TokenScanReport, TokenScanner};
+
 /// Main risk evaluation function
 /// 
 /// Evaluates a trade plan against all configured risk criteria
@@ -52,6 +61,8 @@ mod tests {
                 trailing_pct: Some(2.0),
             },
             idem_key: "test-key".to_string(),
+            quote: None,
+            correlation_id: None,
         };
 
         let decision = evaluate_trade(&plan);
@@ -1 +1,40 @@
-pub fn placeholder() { /* TODO: implement */ }
+//! Pre-trade risk limits.
+//!
+//! Every numeric limit is optional and left unchecked when unset, so the
+//! default limits accept every trade. Values are in quote units unless noted.
+
+use serde::{Deserialize, Serialize};
+
+/// Limits enforced by the [`RiskEngine`](crate::engine::RiskEngine)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RiskLimits {
+    /// Largest notional a single order may carry
+    pub max_order_notional: Option<f64>,
+    /// Most open positions allowed in one symbol
+    pub max_open_positions_per_symbol: Option<usize>,
+    /// Largest gross exposure as a multiple of portfolio equity
+    pub max_leverage: Option<f64>,
+    /// Largest loss allowed since the start of the UTC day
+    pub daily_loss_limit: Option<f64>,
+    /// Tokens that may not be traded, by symbol or address
+    pub banned_tokens: Vec<String>,
+    /// Highest token scan risk score, from 0 to 100, of tokens that may be
+    /// bought; tokens without a scan are not checked
+    pub max_token_risk_score: Option<u8>,
+}
+
+impl RiskLimits {
+    /// Banned token traded by `symbol`, matching either leg of a pair case-insensitively
+    pub fn banned_token(&self, symbol: &str) -> Option<&str> {
+        symbol
+            .split(['/', '-'])
+            .chain(std::iter::once(symbol))
+            .find_map(|leg| {
+                self.banned_tokens
+                    .iter()
+                    .find(|banned| banned.eq_ignore_ascii_case(leg.trim()))
+            })
+            .map(String::as_str)
+    }
+}
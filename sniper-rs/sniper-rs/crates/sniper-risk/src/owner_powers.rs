@@ -1 +1,148 @@
-pub fn placeholder() { /* TODO: implement */ }
+//! Ownership and trading limits of a token contract.
+//!
+//! Read with `eth_call` through the getters common to ERC-20 tokens launched
+//! for sniping. A contract without one of them simply has no such power, so
+//! getters that revert are not errors.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sniper_chain::JsonRpcClient;
+
+/// `owner()` and `getOwner()`
+const OWNER_GETTERS: [[u8; 4]; 2] = [[0x8d, 0xa5, 0xcb, 0x5b], [0x89, 0x3d, 0x20, 0xe8]];
+/// `totalSupply()`
+const TOTAL_SUPPLY: [u8; 4] = [0x18, 0x16, 0x0d, 0xdd];
+/// `_maxTxAmount()`, `maxTransactionAmount()` and `maxTxAmount()`
+const MAX_TX_GETTERS: [[u8; 4]; 3] = [[0x7d, 0x1d, 0xb4, 0xa5], [0xc8, 0xc8, 0xeb, 0xe4], [0x8c, 0x0b, 0x5e, 0x22]];
+/// `_maxWalletSize()`, `maxWallet()`, `maxWalletAmount()` and `_maxWalletAmount()`
+const MAX_WALLET_GETTERS: [[u8; 4]; 4] = [
+    [0x8f, 0x9a, 0x55, 0xc0],
+    [0xf8, 0xb4, 0x5b, 0x05],
+    [0xaa, 0x4b, 0xde, 0x28],
+    [0x6c, 0x0a, 0x24, 0xeb],
+];
+/// Owners ownership is renounced to
+const RENOUNCED_OWNERS: [&str; 2] = [
+    "0x0000000000000000000000000000000000000000",
+    "0x000000000000000000000000000000000000dead",
+];
+
+/// Who controls the token contract
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Ownership {
+    /// Owned by the zero or dead address
+    Renounced,
+    Owned { owner: String },
+    /// The contract exposes no owner getter
+    Unknown,
+}
+
+impl Ownership {
+    /// Ownership given the address an owner getter returned
+    pub fn of(owner: &str) -> Self {
+        if RENOUNCED_OWNERS.iter().any(|renounced| renounced.eq_ignore_ascii_case(owner)) {
+            Ownership::Renounced
+        } else {
+            Ownership::Owned {
+                owner: owner.to_lowercase(),
+            }
+        }
+    }
+}
+
+/// Powers the contract keeps over holders
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContractPowers {
+    pub ownership: Ownership,
+    pub total_supply: Option<u128>,
+    /// Most tokens a single transfer may move
+    pub max_tx_amount: Option<u128>,
+    /// Most tokens a single wallet may hold
+    pub max_wallet_amount: Option<u128>,
+}
+
+impl ContractPowers {
+    /// Max transaction as a share of the supply, in percent
+    pub fn max_tx_pct(&self) -> Option<f64> {
+        share_pct(self.max_tx_amount?, self.total_supply?)
+    }
+
+    /// Max wallet as a share of the supply, in percent
+    pub fn max_wallet_pct(&self) -> Option<f64> {
+        share_pct(self.max_wallet_amount?, self.total_supply?)
+    }
+}
+
+fn share_pct(amount: u128, supply: u128) -> Option<f64> {
+    (supply > 0).then(|| (amount as f64 / supply as f64 * 100.0).min(100.0))
+}
+
+/// Read the ownership and limits of `token` at `block`
+pub async fn read_powers(rpc: &JsonRpcClient, token: &str, block: &str) -> Result<ContractPowers> {
+    let ownership = match first_word(rpc, token, block, &OWNER_GETTERS).await? {
+        Some(word) => Ownership::of(&format!("0x{}", hex::encode(&word[12..]))),
+        None => Ownership::Unknown,
+    };
+    Ok(ContractPowers {
+        ownership,
+        total_supply: first_word(rpc, token, block, &[TOTAL_SUPPLY]).await?.map(|word| uint(&word)),
+        max_tx_amount: first_word(rpc, token, block, &MAX_TX_GETTERS).await?.map(|word| uint(&word)),
+        max_wallet_amount: first_word(rpc, token, block, &MAX_WALLET_GETTERS).await?.map(|word| uint(&word)),
+    })
+}
+
+/// Return word of the first getter the contract implements
+async fn first_word(rpc: &JsonRpcClient, token: &str, block: &str, getters: &[[u8; 4]]) -> Result<Option<[u8; 32]>> {
+    for selector in getters {
+        let call = json!({"to": token, "data": format!("0x{}", hex::encode(selector))});
+        match rpc.call(call, block, None).await {
+            // Calls to missing functions of contracts without a fallback return nothing
+            Ok(output) if output.len() >= 32 => return Ok(output[..32].try_into().ok()),
+            Ok(_) => {}
+            Err(e) if format!("{:#}", e).to_lowercase().contains("revert") => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(None)
+}
+
+/// Unsigned integer held by a word, saturating above `u128::MAX`
+fn uint(word: &[u8; 32]) -> u128 {
+    if word[..16].iter().any(|byte| *byte != 0) {
+        return u128::MAX;
+    }
+    u128::from_be_bytes(word[16..].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ownership_and_limit_shares() {
+        assert_eq!(Ownership::of(RENOUNCED_OWNERS[0]), Ownership::Renounced);
+        assert_eq!(Ownership::of("0x000000000000000000000000000000000000dEaD"), Ownership::Renounced);
+        assert_eq!(
+            Ownership::of("0xAB00000000000000000000000000000000000001"),
+            Ownership::Owned {
+                owner: "0xab00000000000000000000000000000000000001".to_string()
+            }
+        );
+
+        let powers = ContractPowers {
+            ownership: Ownership::Unknown,
+            total_supply: Some(1_000_000),
+            max_tx_amount: Some(5_000),
+            max_wallet_amount: Some(u128::MAX),
+        };
+        assert_eq!(powers.max_tx_pct(), Some(0.5));
+        assert_eq!(powers.max_wallet_pct(), Some(100.0));
+        let unknown_supply = ContractPowers {
+            total_supply: None,
+            ..powers
+        };
+        assert_eq!(unknown_supply.max_tx_pct(), None);
+    }
+}
@@ -0,0 +1,416 @@
+//! Safety scan of tokens before they are sniped.
+//!
+//! A [`TokenScanner`] probes a token with a simulated buy+sell round trip
+//! (see [`honeypot`](crate::honeypot)) and reads the powers its contract
+//! keeps (see [`owner_powers`](crate::owner_powers)). Every problem found is
+//! a [`TokenFinding`] adding to a risk score from 0, nothing found, to 100,
+//! untradeable. The score is consumed by the [`RiskEngine`], which rejects
+//! trades in tokens scoring above `max_token_risk_score`, and by risk
+//! assessor plugins through [`TokenScanAssessor`].
+//!
+//! [`RiskEngine`]: crate::engine::RiskEngine
+
+use crate::honeypot::RoundTrip;
+use crate::owner_powers::{read_powers, ContractPowers, Ownership};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sniper_chain::JsonRpcClient;
+use sniper_core::types::{ChainRef, Decision, ExecMode, ExitRules, GasPolicy, TradePlan};
+use sniper_exec::simulator::{SimulationConfig, Simulator};
+use sniper_plugin::{PluginMetadata, RiskAssessor};
+use std::fmt;
+use std::sync::Arc;
+
+/// Highest risk score, given to tokens that cannot be traded
+pub const MAX_RISK_SCORE: u8 = 100;
+/// Score added when the sell leg could not be simulated
+const UNVERIFIED_POINTS: f64 = 25.0;
+/// Score added while the contract keeps an owner
+const OWNED_POINTS: f64 = 15.0;
+/// Score added per restrictive transfer limit
+const LIMIT_POINTS: f64 = 10.0;
+
+/// Token scan settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScannerConfig {
+    /// V2 router the probe round trip is swapped through
+    pub router: String,
+    /// Native amount the probe buys with, in wei
+    pub probe_amount_wei: u128,
+    /// Max transaction or wallet limits below this share of the supply, in
+    /// percent, are findings
+    pub min_limit_pct: f64,
+    /// Simulation of the probe; a fork backend is needed to verify the sell
+    /// leg, and the wrapped native token is required
+    pub simulation: SimulationConfig,
+}
+
+impl Default for ScannerConfig {
+    fn default() -> Self {
+        Self {
+            router: String::new(),
+            probe_amount_wei: 10_000_000_000_000_000,
+            min_limit_pct: 1.0,
+            simulation: SimulationConfig::default(),
+        }
+    }
+}
+
+/// Problem found in a token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "finding", rename_all = "snake_case")]
+pub enum TokenFinding {
+    /// Bought tokens cannot be sold
+    Honeypot { reason: String },
+    /// The token cannot be bought, e.g. before trading is enabled
+    BuyReverted { reason: String },
+    /// Only the buy could be simulated, so selling is unchecked
+    RoundTripUnverified,
+    BuyTax { tax_pct: f64 },
+    SellTax { tax_pct: f64 },
+    /// The owner can still change fees, limits or blacklists
+    OwnerNotRenounced { owner: String },
+    MaxTxLimit { pct_of_supply: f64 },
+    MaxWalletLimit { pct_of_supply: f64 },
+}
+
+impl TokenFinding {
+    /// Risk score the finding adds
+    pub fn points(&self) -> f64 {
+        match self {
+            TokenFinding::Honeypot { .. } | TokenFinding::BuyReverted { .. } => MAX_RISK_SCORE as f64,
+            TokenFinding::RoundTripUnverified => UNVERIFIED_POINTS,
+            TokenFinding::BuyTax { tax_pct } | TokenFinding::SellTax { tax_pct } => *tax_pct,
+            TokenFinding::OwnerNotRenounced { .. } => OWNED_POINTS,
+            TokenFinding::MaxTxLimit { .. } | TokenFinding::MaxWalletLimit { .. } => LIMIT_POINTS,
+        }
+    }
+}
+
+impl fmt::Display for TokenFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenFinding::Honeypot { reason } => write!(f, "honeypot: sell reverted: {}", reason),
+            TokenFinding::BuyReverted { reason } => write!(f, "buy reverted: {}", reason),
+            TokenFinding::RoundTripUnverified => write!(f, "sell leg not simulated"),
+            TokenFinding::BuyTax { tax_pct } => write!(f, "buy tax {:.2}%", tax_pct),
+            TokenFinding::SellTax { tax_pct } => write!(f, "sell tax {:.2}%", tax_pct),
+            TokenFinding::OwnerNotRenounced { owner } => write!(f, "owned by {}", owner),
+            TokenFinding::MaxTxLimit { pct_of_supply } => {
+                write!(f, "max transaction {:.2}% of supply", pct_of_supply)
+            }
+            TokenFinding::MaxWalletLimit { pct_of_supply } => {
+                write!(f, "max wallet {:.2}% of supply", pct_of_supply)
+            }
+        }
+    }
+}
+
+/// Result of scanning a token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenScanReport {
+    pub token: String,
+    pub round_trip: RoundTrip,
+    pub powers: ContractPowers,
+    pub findings: Vec<TokenFinding>,
+    /// 0 when nothing was found, up to [`MAX_RISK_SCORE`]
+    pub risk_score: u8,
+    pub scanned_at_ms: i64,
+}
+
+impl TokenScanReport {
+    /// Assemble a report, listing the findings and scoring them
+    pub fn new(token: &str, round_trip: RoundTrip, powers: ContractPowers, min_limit_pct: f64) -> Self {
+        let mut findings = Vec::new();
+        if let Some(reason) = &round_trip.buy_reverted {
+            findings.push(TokenFinding::BuyReverted { reason: reason.clone() });
+        } else if let Some(reason) = &round_trip.sell_reverted {
+            findings.push(TokenFinding::Honeypot { reason: reason.clone() });
+        } else if !round_trip.verified {
+            findings.push(TokenFinding::RoundTripUnverified);
+        }
+        if let Some(tax_pct) = round_trip.buy_tax_pct.filter(|tax| *tax > 0.0) {
+            findings.push(TokenFinding::BuyTax { tax_pct });
+        }
+        if let Some(tax_pct) = round_trip.sell_tax_pct.filter(|tax| *tax > 0.0) {
+            findings.push(TokenFinding::SellTax { tax_pct });
+        }
+        if let Ownership::Owned { owner } = &powers.ownership {
+            findings.push(TokenFinding::OwnerNotRenounced { owner: owner.clone() });
+        }
+        if let Some(pct_of_supply) = powers.max_tx_pct().filter(|pct| *pct < min_limit_pct) {
+            findings.push(TokenFinding::MaxTxLimit { pct_of_supply });
+        }
+        if let Some(pct_of_supply) = powers.max_wallet_pct().filter(|pct| *pct < min_limit_pct) {
+            findings.push(TokenFinding::MaxWalletLimit { pct_of_supply });
+        }
+        let points: f64 = findings.iter().map(TokenFinding::points).sum();
+        Self {
+            token: token.to_lowercase(),
+            round_trip,
+            powers,
+            findings,
+            risk_score: points.round().clamp(0.0, MAX_RISK_SCORE as f64) as u8,
+            scanned_at_ms: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+
+    /// Whether the token is a honeypot or cannot be bought at all
+    pub fn is_untradeable(&self) -> bool {
+        self.round_trip.is_honeypot() || self.round_trip.buy_reverted.is_some()
+    }
+
+    /// Decision allowing the token up to `max_risk_score`, giving the findings as reasons
+    pub fn decision(&self, max_risk_score: u8) -> Decision {
+        let mut reasons = vec![format!("risk score {}", self.risk_score)];
+        reasons.extend(self.findings.iter().map(ToString::to_string));
+        Decision {
+            allow: self.risk_score <= max_risk_score,
+            reasons,
+        }
+    }
+}
+
+/// Scans tokens on one chain
+pub struct TokenScanner {
+    chain: ChainRef,
+    rpc: Arc<JsonRpcClient>,
+    simulator: Simulator,
+    config: ScannerConfig,
+}
+
+impl TokenScanner {
+    /// Create a scanner probing tokens as `sender` through `rpc`, a node or a fork
+    pub fn new(chain: ChainRef, rpc: Arc<JsonRpcClient>, sender: &str, config: ScannerConfig) -> Self {
+        Self {
+            chain,
+            simulator: Simulator::new(rpc.clone(), sender, config.simulation.clone()),
+            rpc,
+            config,
+        }
+    }
+
+    /// Get the scan settings
+    pub fn config(&self) -> &ScannerConfig {
+        &self.config
+    }
+
+    /// Scan a token
+    ///
+    /// Problems with the token are findings of the report; errors are left
+    /// for failures to scan at all, such as an unreachable node.
+    pub async fn scan(&self, token: &str) -> Result<TokenScanReport> {
+        let simulation = self.simulator.simulate(&self.probe(token)).await?;
+        let powers = read_powers(&self.rpc, token, &self.config.simulation.block).await?;
+        let report = TokenScanReport::new(token, RoundTrip::from_simulation(&simulation), powers, self.config.min_limit_pct);
+        tracing::info!(
+            "scanned {} on {}: risk score {} ({} findings)",
+            report.token,
+            self.chain.name,
+            report.risk_score,
+            report.findings.len()
+        );
+        Ok(report)
+    }
+
+    /// Plan buying the token with the probe amount of the native token
+    fn probe(&self, token: &str) -> TradePlan {
+        TradePlan {
+            chain: self.chain.clone(),
+            router: self.config.router.clone(),
+            token_in: "ETH".to_string(),
+            token_out: token.to_string(),
+            amount_in: self.config.probe_amount_wei,
+            min_out: 0,
+            mode: ExecMode::Mempool,
+            gas: GasPolicy {
+                max_fee_gwei: 0,
+                max_priority_gwei: 0,
+            },
+            exits: ExitRules::default(),
+            idem_key: format!("token-scan-{}", token.to_lowercase()),
+            quote: None,
+            correlation_id: None,
+        }
+    }
+}
+
+/// Risk assessor plugin scanning the token a plan buys
+///
+/// Assessments are the plan's [`Decision`] with the scan report under `report`.
+pub struct TokenScanAssessor {
+    metadata: PluginMetadata,
+    scanner: Arc<TokenScanner>,
+    max_risk_score: u8,
+}
+
+impl TokenScanAssessor {
+    pub fn new(scanner: Arc<TokenScanner>, max_risk_score: u8) -> Self {
+        Self {
+            metadata: PluginMetadata {
+                id: "token-scanner".to_string(),
+                name: "Token Scanner".to_string(),
+                version: "1.0.0".to_string(),
+                description: "Honeypot, tax, ownership and limit checks of the token bought".to_string(),
+                author: "Sniper-RS Team".to_string(),
+                capabilities: vec!["risk".to_string()],
+                config_schema: None,
+            },
+            scanner,
+            max_risk_score,
+        }
+    }
+}
+
+#[async_trait]
+impl RiskAssessor for TokenScanAssessor {
+    async fn assess_risk(&self, plan: &Value) -> Result<Value> {
+        let plan: TradePlan = serde_json::from_value(plan.clone())?;
+        let report = self.scanner.scan(&plan.token_out).await?;
+        let mut assessment = serde_json::to_value(report.decision(self.max_risk_score))?;
+        assessment["risk_score"] = report.risk_score.into();
+        assessment["report"] = serde_json::to_value(&report)?;
+        Ok(assessment)
+    }
+
+    fn metadata(&self) -> &PluginMetadata {
+        &self.metadata
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Json, Router};
+    use serde_json::json;
+    use sniper_chain::{HedgeConfig, ProviderPool, RpcEndpoint};
+
+    const SENDER: &str = "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23";
+    const ROUTER: &str = "0x7a250d5630b4cf539739df2c5dacb4c659f2488d";
+    const WETH: &str = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+    const TOKEN: &str = "0x2222222222222222222222222222222222222222";
+    const OWNER: &str = "0x00000000000000000000000000000000000000aa";
+
+    fn word(value: u128) -> String {
+        format!("{:064x}", value)
+    }
+
+    /// Token with an owner, a supply of 1e6 and a max transaction of 0.5%
+    async fn handle(Json(request): Json<Value>) -> Json<Value> {
+        let data = request["params"][0]["data"].as_str().unwrap_or_default();
+        let result = match &data[2..10] {
+            // getAmountsOut
+            "d06ca61f" => format!("0x{}{}{}{}", word(0x20), word(2), word(1), word(2)),
+            "8da5cb5b" => format!("0x{}", word(u128::from_str_radix(&OWNER[2..], 16).unwrap())),
+            "18160ddd" => format!("0x{}", word(1_000_000)),
+            "7d1db4a5" => format!("0x{}", word(5_000)),
+            // Swaps succeed, other getters are missing
+            "7ff36ab5" => format!("0x{}{}{}{}", word(0x20), word(2), word(1), word(2)),
+            _ => {
+                return Json(json!({"jsonrpc": "2.0", "id": request["id"], "error": {"code": 3, "message": "execution reverted"}}))
+            }
+        };
+        Json(json!({"jsonrpc": "2.0", "id": request["id"], "result": result}))
+    }
+
+    async fn scanner() -> Arc<TokenScanner> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, Router::new().route("/", post(handle))).await });
+        let pool = ProviderPool::new(vec![RpcEndpoint { name: "mock".to_string(), url }], HedgeConfig::default());
+        let rpc = Arc::new(JsonRpcClient::new(Arc::new(pool)).unwrap());
+        let config = ScannerConfig {
+            router: ROUTER.to_string(),
+            simulation: SimulationConfig {
+                weth: Some(WETH.to_string()),
+                ..SimulationConfig::default()
+            },
+            ..ScannerConfig::default()
+        };
+        let chain = ChainRef {
+            name: "ethereum".to_string(),
+            id: 1,
+        };
+        Arc::new(TokenScanner::new(chain, rpc, SENDER, config))
+    }
+
+    fn powers(ownership: Ownership) -> ContractPowers {
+        ContractPowers {
+            ownership,
+            total_supply: Some(1_000_000),
+            max_tx_amount: None,
+            max_wallet_amount: Some(5_000),
+        }
+    }
+
+    #[test]
+    fn test_findings_and_score() {
+        let safe = RoundTrip {
+            verified: true,
+            buy_tax_pct: Some(0.0),
+            sell_tax_pct: Some(0.0),
+            ..RoundTrip::default()
+        };
+        let report = TokenScanReport::new(TOKEN, safe.clone(), powers(Ownership::Renounced), 0.1);
+        assert!(report.findings.is_empty());
+        assert_eq!(report.risk_score, 0);
+        assert!(report.decision(0).allow);
+
+        let taxed = RoundTrip {
+            buy_tax_pct: Some(3.0),
+            sell_tax_pct: Some(12.0),
+            ..safe.clone()
+        };
+        let owner = Ownership::Owned { owner: OWNER.to_string() };
+        let report = TokenScanReport::new(TOKEN, taxed, powers(owner), 1.0);
+        assert_eq!(
+            report.findings,
+            vec![
+                TokenFinding::BuyTax { tax_pct: 3.0 },
+                TokenFinding::SellTax { tax_pct: 12.0 },
+                TokenFinding::OwnerNotRenounced { owner: OWNER.to_string() },
+                TokenFinding::MaxWalletLimit { pct_of_supply: 0.5 },
+            ]
+        );
+        assert_eq!(report.risk_score, 40);
+        let decision = report.decision(30);
+        assert!(!decision.allow);
+        assert!(decision.reasons.contains(&"sell tax 12.00%".to_string()));
+
+        let honeypot = RoundTrip {
+            sell_reverted: Some("TRANSFER_FAILED".to_string()),
+            ..safe
+        };
+        let report = TokenScanReport::new(TOKEN, honeypot, powers(Ownership::Unknown), 0.1);
+        assert_eq!(report.risk_score, MAX_RISK_SCORE);
+        assert!(report.is_untradeable());
+    }
+
+    #[tokio::test]
+    async fn test_scan_and_assess_plan() {
+        let scanner = scanner().await;
+        let report = scanner.scan(TOKEN).await.unwrap();
+        assert!(!report.round_trip.verified && !report.is_untradeable());
+        assert_eq!(report.powers.ownership, Ownership::Owned { owner: OWNER.to_string() });
+        assert_eq!((report.powers.max_tx_amount, report.powers.max_wallet_amount), (Some(5_000), None));
+        assert_eq!(
+            report.findings,
+            vec![
+                TokenFinding::RoundTripUnverified,
+                TokenFinding::OwnerNotRenounced { owner: OWNER.to_string() },
+                TokenFinding::MaxTxLimit { pct_of_supply: 0.5 },
+            ]
+        );
+        assert_eq!(report.risk_score, 50);
+
+        let assessor = TokenScanAssessor::new(scanner.clone(), 30);
+        let plan = serde_json::to_value(scanner.probe(TOKEN)).unwrap();
+        let assessment = assessor.assess_risk(&plan).await.unwrap();
+        assert_eq!(assessment["allow"], false);
+        assert_eq!(assessment["risk_score"], 50);
+        assert_eq!(assessment["report"]["token"], TOKEN);
+    }
+}
@@ -0,0 +1,14 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Use the vendored protoc so builds need no system protobuf compiler
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    tonic_build::configure().compile_protos(
+        &[
+            "proto/sniper/v1/types.proto",
+            "proto/sniper/v1/orders.proto",
+            "proto/sniper/v1/portfolio.proto",
+            "proto/sniper/v1/execution.proto",
+        ],
+        &["proto"],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,245 @@
+//! Clients of the gRPC services for internal callers.
+//!
+//! Each client wraps a channel to one service, attaches the caller's
+//! access token to every call and converts the replies to the core types.
+//! Replies that do not convert are reported as `INTERNAL`.
+
+use crate::convert::InvalidMessage;
+use crate::proto;
+use crate::proto::execution_client::ExecutionClient as ExecutionStub;
+use crate::proto::orders_client::OrdersClient as OrdersStub;
+use crate::proto::portfolio_client::PortfolioClient as PortfolioStub;
+use sniper_core::types::{ChainRef, Decimal, ExecReceipt, TradePlan};
+use sniper_orders::{AdvancedOrder, OrderType, TimeInForce};
+use sniper_portfolio::Position;
+use tonic::metadata::MetadataValue;
+use tonic::service::interceptor::InterceptedService;
+use tonic::service::Interceptor;
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Request, Status};
+
+/// Interceptor attaching an access token to outgoing calls
+#[derive(Clone, Default)]
+pub struct BearerToken {
+    authorization: Option<MetadataValue<tonic::metadata::Ascii>>,
+}
+
+impl BearerToken {
+    /// Attach `token` to every call
+    pub fn new(token: &str) -> Result<Self, Status> {
+        let authorization = format!("Bearer {}", token)
+            .parse()
+            .map_err(|_| Status::invalid_argument("Access token is not valid metadata"))?;
+        Ok(Self {
+            authorization: Some(authorization),
+        })
+    }
+}
+
+impl Interceptor for BearerToken {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(authorization) = &self.authorization {
+            request.metadata_mut().insert("authorization", authorization.clone());
+        }
+        Ok(request)
+    }
+}
+
+type AuthChannel = InterceptedService<Channel, BearerToken>;
+
+async fn connect(endpoint: &str) -> anyhow::Result<Channel> {
+    Ok(Endpoint::from_shared(endpoint.to_string())?.connect().await?)
+}
+
+fn reply<T, M>(message: M) -> Result<T, Status>
+where
+    T: TryFrom<M, Error = InvalidMessage>,
+{
+    T::try_from(message).map_err(|e| Status::internal(format!("Malformed reply: {}", e)))
+}
+
+/// Order to place through [`OrdersClient::place_order`]
+#[derive(Debug, Clone)]
+pub struct NewOrder {
+    pub symbol: String,
+    pub chain: ChainRef,
+    pub order_type: OrderType,
+    pub side: String,
+    pub amount: Decimal,
+    pub time_in_force: TimeInForce,
+    /// Tenant to place the order under, defaulting to the caller's
+    pub tenant_id: Option<String>,
+}
+
+/// Client of svc-orders
+#[derive(Clone)]
+pub struct OrdersClient {
+    channel: Channel,
+    inner: OrdersStub<AuthChannel>,
+}
+
+impl OrdersClient {
+    /// Connect to svc-orders at `endpoint`, like `http://localhost:9081`
+    pub async fn connect(endpoint: &str) -> anyhow::Result<Self> {
+        Ok(Self::new(connect(endpoint).await?))
+    }
+
+    /// Create a client over an established channel
+    pub fn new(channel: Channel) -> Self {
+        Self {
+            inner: OrdersStub::with_interceptor(channel.clone(), BearerToken::default()),
+            channel,
+        }
+    }
+
+    /// Authenticate calls with `token`
+    pub fn with_token(self, token: &str) -> Result<Self, Status> {
+        Ok(Self {
+            inner: OrdersStub::with_interceptor(self.channel.clone(), BearerToken::new(token)?),
+            channel: self.channel,
+        })
+    }
+
+    /// Place an order
+    pub async fn place_order(&mut self, order: NewOrder) -> Result<AdvancedOrder, Status> {
+        let request = proto::PlaceOrderRequest {
+            symbol: order.symbol,
+            chain: Some(order.chain.into()),
+            order_type: Some(order.order_type.into()),
+            side: order.side,
+            amount: order.amount.to_string(),
+            time_in_force: Some(order.time_in_force.into()),
+            tenant_id: order.tenant_id,
+        };
+        reply(self.inner.place_order(request).await?.into_inner())
+    }
+
+    /// Get an order
+    pub async fn get_order(&mut self, id: &str) -> Result<AdvancedOrder, Status> {
+        let request = proto::GetOrderRequest { id: id.to_string() };
+        reply(self.inner.get_order(request).await?.into_inner())
+    }
+
+    /// List the orders of the caller's tenants
+    pub async fn list_orders(&mut self) -> Result<Vec<AdvancedOrder>, Status> {
+        let response = self.inner.list_orders(proto::ListOrdersRequest {}).await?.into_inner();
+        response.orders.into_iter().map(reply).collect()
+    }
+
+    /// Cancel an order, returning it as cancelled
+    pub async fn cancel_order(&mut self, id: &str) -> Result<AdvancedOrder, Status> {
+        let request = proto::CancelOrderRequest { id: id.to_string() };
+        reply(self.inner.cancel_order(request).await?.into_inner())
+    }
+
+    /// Record a fill against an order
+    pub async fn record_fill(&mut self, id: &str, quantity: Decimal, price: Decimal) -> Result<AdvancedOrder, Status> {
+        let request = proto::RecordFillRequest {
+            id: id.to_string(),
+            quantity: quantity.to_string(),
+            price: price.to_string(),
+        };
+        reply(self.inner.record_fill(request).await?.into_inner())
+    }
+
+    /// Trade plan of an order at the current oracle price
+    pub async fn get_trade_plan(&mut self, id: &str) -> Result<TradePlan, Status> {
+        let request = proto::GetTradePlanRequest { id: id.to_string() };
+        reply(self.inner.get_trade_plan(request).await?.into_inner())
+    }
+}
+
+/// Client of svc-portfolio
+#[derive(Clone)]
+pub struct PortfolioClient {
+    channel: Channel,
+    inner: PortfolioStub<AuthChannel>,
+}
+
+impl PortfolioClient {
+    /// Connect to svc-portfolio at `endpoint`, like `http://localhost:9080`
+    pub async fn connect(endpoint: &str) -> anyhow::Result<Self> {
+        Ok(Self::new(connect(endpoint).await?))
+    }
+
+    /// Create a client over an established channel
+    pub fn new(channel: Channel) -> Self {
+        Self {
+            inner: PortfolioStub::with_interceptor(channel.clone(), BearerToken::default()),
+            channel,
+        }
+    }
+
+    /// Authenticate calls with `token`
+    pub fn with_token(self, token: &str) -> Result<Self, Status> {
+        Ok(Self {
+            inner: PortfolioStub::with_interceptor(self.channel.clone(), BearerToken::new(token)?),
+            channel: self.channel,
+        })
+    }
+
+    /// List the open positions
+    pub async fn list_positions(&mut self) -> Result<Vec<Position>, Status> {
+        let response = self.inner.list_positions(proto::ListPositionsRequest {}).await?.into_inner();
+        response.positions.into_iter().map(reply).collect()
+    }
+
+    /// Get an open position
+    pub async fn get_position(&mut self, id: &str) -> Result<Position, Status> {
+        let request = proto::GetPositionRequest { id: id.to_string() };
+        reply(self.inner.get_position(request).await?.into_inner())
+    }
+
+    /// Trade plan sized by the portfolio's allocation settings
+    pub async fn generate_trade_plan(
+        &mut self,
+        symbol: &str,
+        chain: ChainRef,
+        amount: Decimal,
+        side: &str,
+    ) -> Result<TradePlan, Status> {
+        let request = proto::GenerateTradePlanRequest {
+            symbol: symbol.to_string(),
+            chain: Some(chain.into()),
+            amount: amount.to_string(),
+            side: side.to_string(),
+        };
+        reply(self.inner.generate_trade_plan(request).await?.into_inner())
+    }
+}
+
+/// Client of svc-executor
+#[derive(Clone)]
+pub struct ExecutionClient {
+    channel: Channel,
+    inner: ExecutionStub<AuthChannel>,
+}
+
+impl ExecutionClient {
+    /// Connect to svc-executor at `endpoint`, like `http://localhost:9090`
+    pub async fn connect(endpoint: &str) -> anyhow::Result<Self> {
+        Ok(Self::new(connect(endpoint).await?))
+    }
+
+    /// Create a client over an established channel
+    pub fn new(channel: Channel) -> Self {
+        Self {
+            inner: ExecutionStub::with_interceptor(channel.clone(), BearerToken::default()),
+            channel,
+        }
+    }
+
+    /// Authenticate calls with `token`
+    pub fn with_token(self, token: &str) -> Result<Self, Status> {
+        Ok(Self {
+            inner: ExecutionStub::with_interceptor(self.channel.clone(), BearerToken::new(token)?),
+            channel: self.channel,
+        })
+    }
+
+    /// Submit a trade plan and wait for its receipt
+    pub async fn execute_plan(&mut self, plan: TradePlan) -> Result<ExecReceipt, Status> {
+        let request = proto::TradePlan::from(plan);
+        reply(self.inner.execute_plan(request).await?.into_inner())
+    }
+}
@@ -0,0 +1,539 @@
+//! Conversions between the protobuf messages and the core types.
+//!
+//! Core types convert into messages infallibly. Messages convert back with
+//! `TryFrom`, failing with [`InvalidMessage`] when a required field is
+//! missing or an amount does not parse; services answer those with
+//! `INVALID_ARGUMENT`.
+
+use crate::proto;
+use sniper_core::types::{ChainRef, Decimal, ExecMode, ExecReceipt, ExitRules, GasPolicy, QuoteStamp, TradePlan};
+use sniper_orders::{AdvancedOrder, OrderFill, OrderStatus, OrderType, TimeInForce};
+use sniper_portfolio::Position;
+use thiserror::Error;
+use tonic::Status;
+
+/// Message that does not convert to its core type
+#[derive(Debug, Clone, Error)]
+pub enum InvalidMessage {
+    #[error("Missing field {0}")]
+    Missing(&'static str),
+    #[error("Invalid {field}: {value}")]
+    Value { field: &'static str, value: String },
+}
+
+impl From<InvalidMessage> for Status {
+    fn from(error: InvalidMessage) -> Self {
+        Status::invalid_argument(error.to_string())
+    }
+}
+
+fn required<T>(value: Option<T>, field: &'static str) -> Result<T, InvalidMessage> {
+    value.ok_or(InvalidMessage::Missing(field))
+}
+
+fn parse<T: std::str::FromStr>(value: &str, field: &'static str) -> Result<T, InvalidMessage> {
+    value.parse().map_err(|_| InvalidMessage::Value {
+        field,
+        value: value.to_string(),
+    })
+}
+
+/// Parse a decimal carried as a string
+pub fn decimal(value: &str, field: &'static str) -> Result<Decimal, InvalidMessage> {
+    parse(value, field)
+}
+
+/// Parse a decimal added to a message later, which older peers leave empty
+fn decimal_or_zero(value: &str, field: &'static str) -> Result<Decimal, InvalidMessage> {
+    if value.is_empty() {
+        Ok(Decimal::ZERO)
+    } else {
+        decimal(value, field)
+    }
+}
+
+impl From<ChainRef> for proto::ChainRef {
+    fn from(chain: ChainRef) -> Self {
+        Self {
+            name: chain.name,
+            id: chain.id,
+        }
+    }
+}
+
+impl From<proto::ChainRef> for ChainRef {
+    fn from(chain: proto::ChainRef) -> Self {
+        Self {
+            name: chain.name,
+            id: chain.id,
+        }
+    }
+}
+
+impl From<ExecMode> for proto::ExecMode {
+    fn from(mode: ExecMode) -> Self {
+        match mode {
+            ExecMode::Bundle => proto::ExecMode::Bundle,
+            ExecMode::Private => proto::ExecMode::Private,
+            ExecMode::Mempool => proto::ExecMode::Mempool,
+        }
+    }
+}
+
+impl TryFrom<proto::ExecMode> for ExecMode {
+    type Error = InvalidMessage;
+
+    fn try_from(mode: proto::ExecMode) -> Result<Self, Self::Error> {
+        match mode {
+            proto::ExecMode::Bundle => Ok(ExecMode::Bundle),
+            proto::ExecMode::Private => Ok(ExecMode::Private),
+            proto::ExecMode::Mempool => Ok(ExecMode::Mempool),
+            proto::ExecMode::Unspecified => Err(InvalidMessage::Missing("mode")),
+        }
+    }
+}
+
+impl From<TradePlan> for proto::TradePlan {
+    fn from(plan: TradePlan) -> Self {
+        Self {
+            chain: Some(plan.chain.into()),
+            router: plan.router,
+            token_in: plan.token_in,
+            token_out: plan.token_out,
+            amount_in: plan.amount_in.to_string(),
+            min_out: plan.min_out.to_string(),
+            mode: proto::ExecMode::from(plan.mode).into(),
+            gas: Some(proto::GasPolicy {
+                max_fee_gwei: plan.gas.max_fee_gwei,
+                max_priority_gwei: plan.gas.max_priority_gwei,
+            }),
+            exits: Some(proto::ExitRules {
+                take_profit_pct: plan.exits.take_profit_pct,
+                stop_loss_pct: plan.exits.stop_loss_pct,
+                trailing_pct: plan.exits.trailing_pct,
+            }),
+            idem_key: plan.idem_key,
+            quote: plan.quote.map(|quote| proto::QuoteStamp {
+                quoted_at_ms: quote.quoted_at_ms,
+                block: quote.block,
+                expected_out: quote.expected_out.map(|amount| amount.to_string()),
+            }),
+            correlation_id: plan.correlation_id,
+        }
+    }
+}
+
+impl TryFrom<proto::TradePlan> for TradePlan {
+    type Error = InvalidMessage;
+
+    fn try_from(plan: proto::TradePlan) -> Result<Self, Self::Error> {
+        let mode = proto::ExecMode::try_from(plan.mode).map_err(|_| InvalidMessage::Value {
+            field: "mode",
+            value: plan.mode.to_string(),
+        })?;
+        let gas = required(plan.gas, "gas")?;
+        let exits = plan.exits.unwrap_or_default();
+        Ok(Self {
+            chain: required(plan.chain, "chain")?.into(),
+            router: plan.router,
+            token_in: plan.token_in,
+            token_out: plan.token_out,
+            amount_in: parse(&plan.amount_in, "amount_in")?,
+            min_out: parse(&plan.min_out, "min_out")?,
+            mode: mode.try_into()?,
+            gas: GasPolicy {
+                max_fee_gwei: gas.max_fee_gwei,
+                max_priority_gwei: gas.max_priority_gwei,
+            },
+            exits: ExitRules {
+                take_profit_pct: exits.take_profit_pct,
+                stop_loss_pct: exits.stop_loss_pct,
+                trailing_pct: exits.trailing_pct,
+            },
+            idem_key: plan.idem_key,
+            quote: plan
+                .quote
+                .map(|quote| -> Result<QuoteStamp, InvalidMessage> {
+                    Ok(QuoteStamp {
+                        quoted_at_ms: quote.quoted_at_ms,
+                        block: quote.block,
+                        expected_out: quote.expected_out.as_deref().map(|amount| parse(amount, "quote.expected_out")).transpose()?,
+                    })
+                })
+                .transpose()?,
+            correlation_id: plan.correlation_id,
+        })
+    }
+}
+
+impl From<ExecReceipt> for proto::ExecReceipt {
+    fn from(receipt: ExecReceipt) -> Self {
+        Self {
+            tx_hash: receipt.tx_hash,
+            success: receipt.success,
+            block: receipt.block,
+            gas_used: receipt.gas_used,
+            fees_paid_wei: receipt.fees_paid_wei.to_string(),
+            failure_reason: receipt.failure_reason,
+            amount_out: receipt.amount_out.map(|amount| amount.to_string()),
+            endpoint: receipt.endpoint,
+        }
+    }
+}
+
+impl TryFrom<proto::ExecReceipt> for ExecReceipt {
+    type Error = InvalidMessage;
+
+    fn try_from(receipt: proto::ExecReceipt) -> Result<Self, Self::Error> {
+        Ok(Self {
+            tx_hash: receipt.tx_hash,
+            success: receipt.success,
+            block: receipt.block,
+            gas_used: receipt.gas_used,
+            fees_paid_wei: parse(&receipt.fees_paid_wei, "fees_paid_wei")?,
+            failure_reason: receipt.failure_reason,
+            amount_out: receipt.amount_out.map(|amount| parse(&amount, "amount_out")).transpose()?,
+            endpoint: receipt.endpoint,
+        })
+    }
+}
+
+impl From<OrderType> for proto::OrderType {
+    fn from(order_type: OrderType) -> Self {
+        use proto::order_type::{Kind, *};
+
+        let kind = match order_type {
+            OrderType::Market => Kind::Market(Market {}),
+            OrderType::Limit { price } => Kind::Limit(Limit { price }),
+            OrderType::StopLoss { price } => Kind::StopLoss(StopLoss { price }),
+            OrderType::TakeProfit { price } => Kind::TakeProfit(TakeProfit { price }),
+            OrderType::StopLimit { stop_price, limit_price } => Kind::StopLimit(StopLimit { stop_price, limit_price }),
+            OrderType::TrailingStop { trail_percent } => Kind::TrailingStop(TrailingStop { trail_percent }),
+            OrderType::Iceberg { visible_amount, total_amount } => Kind::Iceberg(Iceberg { visible_amount, total_amount }),
+            OrderType::TWAP { total_amount, duration_minutes } => Kind::Twap(Twap { total_amount, duration_minutes }),
+            OrderType::VWAP { total_amount } => Kind::Vwap(Vwap { total_amount }),
+        };
+        Self { kind: Some(kind) }
+    }
+}
+
+impl TryFrom<proto::OrderType> for OrderType {
+    type Error = InvalidMessage;
+
+    fn try_from(order_type: proto::OrderType) -> Result<Self, Self::Error> {
+        use proto::order_type::Kind;
+
+        Ok(match required(order_type.kind, "order_type")? {
+            Kind::Market(_) => OrderType::Market,
+            Kind::Limit(limit) => OrderType::Limit { price: limit.price },
+            Kind::StopLoss(stop) => OrderType::StopLoss { price: stop.price },
+            Kind::TakeProfit(take) => OrderType::TakeProfit { price: take.price },
+            Kind::StopLimit(stop) => OrderType::StopLimit {
+                stop_price: stop.stop_price,
+                limit_price: stop.limit_price,
+            },
+            Kind::TrailingStop(trail) => OrderType::TrailingStop { trail_percent: trail.trail_percent },
+            Kind::Iceberg(iceberg) => OrderType::Iceberg {
+                visible_amount: iceberg.visible_amount,
+                total_amount: iceberg.total_amount,
+            },
+            Kind::Twap(twap) => OrderType::TWAP {
+                total_amount: twap.total_amount,
+                duration_minutes: twap.duration_minutes,
+            },
+            Kind::Vwap(vwap) => OrderType::VWAP { total_amount: vwap.total_amount },
+        })
+    }
+}
+
+impl From<TimeInForce> for proto::TimeInForce {
+    fn from(time_in_force: TimeInForce) -> Self {
+        use proto::time_in_force::Kind;
+
+        let (kind, expiry_timestamp) = match time_in_force {
+            TimeInForce::GoodTillCancelled => (Kind::GoodTillCancelled, 0),
+            TimeInForce::ImmediateOrCancel => (Kind::ImmediateOrCancel, 0),
+            TimeInForce::FillOrKill => (Kind::FillOrKill, 0),
+            TimeInForce::GoodTillTime { expiry_timestamp } => (Kind::GoodTillTime, expiry_timestamp),
+        };
+        Self {
+            kind: kind.into(),
+            expiry_timestamp,
+        }
+    }
+}
+
+impl TryFrom<proto::TimeInForce> for TimeInForce {
+    type Error = InvalidMessage;
+
+    fn try_from(time_in_force: proto::TimeInForce) -> Result<Self, Self::Error> {
+        use proto::time_in_force::Kind;
+
+        let kind = Kind::try_from(time_in_force.kind).map_err(|_| InvalidMessage::Value {
+            field: "time_in_force",
+            value: time_in_force.kind.to_string(),
+        })?;
+        Ok(match kind {
+            Kind::GoodTillCancelled => TimeInForce::GoodTillCancelled,
+            Kind::ImmediateOrCancel => TimeInForce::ImmediateOrCancel,
+            Kind::FillOrKill => TimeInForce::FillOrKill,
+            Kind::GoodTillTime if time_in_force.expiry_timestamp == 0 => {
+                return Err(InvalidMessage::Missing("expiry_timestamp"));
+            },
+            Kind::GoodTillTime => TimeInForce::GoodTillTime {
+                expiry_timestamp: time_in_force.expiry_timestamp,
+            },
+        })
+    }
+}
+
+impl From<OrderStatus> for proto::OrderStatus {
+    fn from(status: OrderStatus) -> Self {
+        match status {
+            OrderStatus::Pending => proto::OrderStatus::Pending,
+            OrderStatus::Active => proto::OrderStatus::Active,
+            OrderStatus::PartiallyFilled => proto::OrderStatus::PartiallyFilled,
+            OrderStatus::Filled => proto::OrderStatus::Filled,
+            OrderStatus::Cancelled => proto::OrderStatus::Cancelled,
+            OrderStatus::Expired => proto::OrderStatus::Expired,
+            OrderStatus::Rejected => proto::OrderStatus::Rejected,
+        }
+    }
+}
+
+impl From<proto::OrderStatus> for OrderStatus {
+    fn from(status: proto::OrderStatus) -> Self {
+        match status {
+            proto::OrderStatus::Pending => OrderStatus::Pending,
+            proto::OrderStatus::Active => OrderStatus::Active,
+            proto::OrderStatus::PartiallyFilled => OrderStatus::PartiallyFilled,
+            proto::OrderStatus::Filled => OrderStatus::Filled,
+            proto::OrderStatus::Cancelled => OrderStatus::Cancelled,
+            proto::OrderStatus::Expired => OrderStatus::Expired,
+            proto::OrderStatus::Rejected => OrderStatus::Rejected,
+        }
+    }
+}
+
+impl From<AdvancedOrder> for proto::Order {
+    fn from(order: AdvancedOrder) -> Self {
+        Self {
+            id: order.id,
+            symbol: order.symbol,
+            chain: Some(order.chain.into()),
+            order_type: Some(order.order_type.into()),
+            side: order.side,
+            amount: order.amount.to_string(),
+            time_in_force: Some(order.time_in_force.into()),
+            created_at: order.created_at,
+            updated_at: order.updated_at,
+            status: proto::OrderStatus::from(order.status).into(),
+            fills: order.fills.into_iter()
+                .map(|fill| proto::OrderFill {
+                    quantity: fill.quantity.to_string(),
+                    price: fill.price.to_string(),
+                    timestamp: fill.timestamp,
+                })
+                .collect(),
+            owner_id: order.owner_id,
+            tenant_id: order.tenant_id,
+        }
+    }
+}
+
+impl TryFrom<proto::Order> for AdvancedOrder {
+    type Error = InvalidMessage;
+
+    fn try_from(order: proto::Order) -> Result<Self, Self::Error> {
+        let status = proto::OrderStatus::try_from(order.status).map_err(|_| InvalidMessage::Value {
+            field: "status",
+            value: order.status.to_string(),
+        })?;
+        Ok(Self {
+            id: order.id,
+            symbol: order.symbol,
+            chain: required(order.chain, "chain")?.into(),
+            order_type: required(order.order_type, "order_type")?.try_into()?,
+            side: order.side,
+            amount: decimal(&order.amount, "amount")?,
+            time_in_force: order.time_in_force.unwrap_or_default().try_into()?,
+            created_at: order.created_at,
+            updated_at: order.updated_at,
+            status: status.into(),
+            fills: order.fills.into_iter()
+                .map(|fill| Ok(OrderFill {
+                    quantity: decimal(&fill.quantity, "fill quantity")?,
+                    price: decimal(&fill.price, "fill price")?,
+                    timestamp: fill.timestamp,
+                }))
+                .collect::<Result<_, InvalidMessage>>()?,
+            owner_id: order.owner_id,
+            tenant_id: order.tenant_id,
+            // Orders are armed over REST; the wire format carries no trigger
+            trigger: None,
+        })
+    }
+}
+
+impl From<Position> for proto::Position {
+    fn from(position: Position) -> Self {
+        Self {
+            id: position.id,
+            symbol: position.symbol,
+            chain: Some(position.chain.into()),
+            amount: position.amount.to_string(),
+            entry_price: position.entry_price.to_string(),
+            current_price: position.current_price.to_string(),
+            side: position.side,
+            leverage: position.leverage,
+            pnl: position.pnl.to_string(),
+            pnl_percentage: position.pnl_percentage,
+            created_at: position.created_at,
+            updated_at: position.updated_at,
+            tenant_id: position.tenant_id,
+            fees: position.fees.to_string(),
+            funding: position.funding.to_string(),
+        }
+    }
+}
+
+impl TryFrom<proto::Position> for Position {
+    type Error = InvalidMessage;
+
+    fn try_from(position: proto::Position) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: position.id,
+            symbol: position.symbol,
+            chain: required(position.chain, "chain")?.into(),
+            amount: decimal(&position.amount, "amount")?,
+            entry_price: decimal(&position.entry_price, "entry_price")?,
+            current_price: decimal(&position.current_price, "current_price")?,
+            side: position.side,
+            leverage: position.leverage,
+            pnl: decimal(&position.pnl, "pnl")?,
+            pnl_percentage: position.pnl_percentage,
+            created_at: position.created_at,
+            updated_at: position.updated_at,
+            tenant_id: position.tenant_id,
+            fees: decimal_or_zero(&position.fees, "fees")?,
+            funding: decimal_or_zero(&position.funding, "funding")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan() -> TradePlan {
+        TradePlan {
+            chain: ChainRef { name: "base".to_string(), id: 8453 },
+            router: "0xrouter".to_string(),
+            token_in: "0xweth".to_string(),
+            token_out: "0xusdc".to_string(),
+            amount_in: u128::MAX,
+            min_out: 1_000_000,
+            mode: ExecMode::Private,
+            gas: GasPolicy { max_fee_gwei: 30, max_priority_gwei: 2 },
+            exits: ExitRules { take_profit_pct: Some(5.0), stop_loss_pct: None, trailing_pct: Some(1.5) },
+            idem_key: "plan-1".to_string(),
+            quote: Some(QuoteStamp { quoted_at_ms: 1_700_000_000_000, block: 42, expected_out: Some(950) }),
+            correlation_id: Some("order-1".to_string()),
+        }
+    }
+
+    fn order(order_type: OrderType, time_in_force: TimeInForce) -> AdvancedOrder {
+        AdvancedOrder {
+            id: "order-1".to_string(),
+            symbol: "ETH".to_string(),
+            chain: ChainRef { name: "ethereum".to_string(), id: 1 },
+            order_type,
+            side: "buy".to_string(),
+            amount: "1.5".parse().unwrap(),
+            time_in_force,
+            created_at: 1,
+            updated_at: 2,
+            status: OrderStatus::PartiallyFilled,
+            fills: vec![OrderFill { quantity: "0.5".parse().unwrap(), price: "2000.25".parse().unwrap(), timestamp: 2 }],
+            owner_id: Some("user-1".to_string()),
+            tenant_id: None,
+            trigger: None,
+        }
+    }
+
+    #[test]
+    fn test_trade_plan_round_trip() {
+        let message = proto::TradePlan::from(plan());
+        assert_eq!(message.amount_in, u128::MAX.to_string());
+
+        let plan = TradePlan::try_from(message).unwrap();
+        assert_eq!(plan.amount_in, u128::MAX);
+        assert_eq!(plan.mode, ExecMode::Private);
+        assert_eq!(plan.exits.trailing_pct, Some(1.5));
+        assert_eq!(plan.quote, Some(QuoteStamp { quoted_at_ms: 1_700_000_000_000, block: 42, expected_out: Some(950) }));
+        assert_eq!(plan.correlation_id.as_deref(), Some("order-1"));
+    }
+
+    #[test]
+    fn test_trade_plan_rejects_bad_messages() {
+        let mut message = proto::TradePlan::from(plan());
+        message.amount_in = "1e18".to_string();
+        assert!(matches!(TradePlan::try_from(message), Err(InvalidMessage::Value { field: "amount_in", .. })));
+
+        let mut message = proto::TradePlan::from(plan());
+        message.mode = proto::ExecMode::Unspecified.into();
+        assert!(matches!(TradePlan::try_from(message), Err(InvalidMessage::Missing("mode"))));
+
+        let mut message = proto::TradePlan::from(plan());
+        message.chain = None;
+        let status = Status::from(TradePlan::try_from(message).unwrap_err());
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[test]
+    fn test_exec_receipt_round_trip() {
+        let receipt = ExecReceipt {
+            tx_hash: "0xabc".to_string(),
+            success: true,
+            block: 7,
+            gas_used: 21_000,
+            fees_paid_wei: 420_000_000_000_000,
+            failure_reason: None,
+            amount_out: Some(999),
+            endpoint: Some("flashbots".to_string()),
+        };
+        let receipt = ExecReceipt::try_from(proto::ExecReceipt::from(receipt)).unwrap();
+        assert_eq!(receipt.fees_paid_wei, 420_000_000_000_000);
+        assert_eq!(receipt.amount_out, Some(999));
+        assert_eq!(receipt.endpoint.as_deref(), Some("flashbots"));
+    }
+
+    #[test]
+    fn test_order_round_trip() {
+        let order_types = [
+            OrderType::Market,
+            OrderType::StopLimit { stop_price: 1900.0, limit_price: 1890.0 },
+            OrderType::TWAP { total_amount: 10.0, duration_minutes: 30 },
+        ];
+        for order_type in order_types {
+            let original = order(order_type, TimeInForce::GoodTillTime { expiry_timestamp: 1_800_000_000 });
+            let order = AdvancedOrder::try_from(proto::Order::from(original.clone())).unwrap();
+            assert_eq!(format!("{:?}", order), format!("{:?}", original));
+        }
+    }
+
+    #[test]
+    fn test_good_till_time_requires_expiry() {
+        let time_in_force = proto::TimeInForce {
+            kind: proto::time_in_force::Kind::GoodTillTime.into(),
+            expiry_timestamp: 0,
+        };
+        assert!(matches!(TimeInForce::try_from(time_in_force), Err(InvalidMessage::Missing("expiry_timestamp"))));
+
+        // An absent time in force is good till cancelled
+        let mut message = proto::Order::from(order(OrderType::Market, TimeInForce::FillOrKill));
+        message.time_in_force = None;
+        let order = AdvancedOrder::try_from(message).unwrap();
+        assert!(matches!(order.time_in_force, TimeInForce::GoodTillCancelled));
+    }
+}
@@ -0,0 +1,23 @@
+//! gRPC interface of the trading services.
+//!
+//! svc-orders, svc-portfolio and svc-executor serve the [`proto`] services
+//! next to their REST routes for low-latency internal calls. The messages
+//! mirror the core types and [`convert`] maps between the two; [`server`]
+//! authenticates calls with the same access tokens as the REST routes, and
+//! [`client`] wraps the generated stubs for services calling each other.
+
+// Calls fail with tonic's `Status`, which is large but what every handler returns
+#![allow(clippy::result_large_err)]
+
+pub mod client;
+pub mod convert;
+pub mod server;
+
+/// Generated messages, service traits and stubs of `sniper.v1`
+pub mod proto {
+    tonic::include_proto!("sniper.v1");
+}
+
+pub use client::{ExecutionClient, NewOrder, OrdersClient, PortfolioClient};
+pub use convert::InvalidMessage;
+pub use server::{caller, require_permission, status, Authenticate};
@@ -0,0 +1,170 @@
+//! Serving the gRPC services.
+//!
+//! Calls are authenticated like the REST routes: [`Authenticate`] verifies
+//! the bearer token in the `authorization` metadata and attaches the
+//! caller's [`UserContext`], which handlers read back with [`caller`].
+
+use anyhow::Result;
+use sniper_core::errors::{ApiError, ErrorKind};
+use sniper_core::shutdown::Shutdown;
+use sniper_users::{JwtAuth, UserContext};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tonic::service::Interceptor;
+use tonic::transport::server::{Router, TcpIncoming};
+use tonic::{Request, Status};
+
+/// Interceptor admitting only calls carrying a valid access token
+#[derive(Clone)]
+pub struct Authenticate {
+    auth: Arc<JwtAuth>,
+}
+
+impl Authenticate {
+    /// Create an interceptor verifying tokens with `auth`
+    pub fn new(auth: Arc<JwtAuth>) -> Self {
+        Self { auth }
+    }
+}
+
+impl Interceptor for Authenticate {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let token = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| Status::unauthenticated("Authentication required"))?;
+        let claims = self
+            .auth
+            .verify(token)
+            .map_err(|e| Status::unauthenticated(format!("Invalid access token: {}", e)))?;
+        request.extensions_mut().insert(UserContext::from(claims));
+        Ok(request)
+    }
+}
+
+/// Authenticated caller of a call, who must be acting within a tenant
+pub fn caller<T>(request: &Request<T>) -> Result<UserContext, Status> {
+    let caller = request
+        .extensions()
+        .get::<UserContext>()
+        .filter(|caller| !caller.is_anonymous())
+        .ok_or_else(|| Status::unauthenticated("Authentication required"))?;
+    if caller.tenant_id.is_empty() {
+        return Err(Status::permission_denied("Tenant context required"));
+    }
+    Ok(caller.clone())
+}
+
+/// Check that the caller holds `permission`
+pub fn require_permission(caller: &UserContext, permission: &str) -> Result<(), Status> {
+    if caller.permissions.iter().any(|p| p == permission) {
+        Ok(())
+    } else {
+        Err(Status::permission_denied(format!("Missing permission {}", permission)))
+    }
+}
+
+/// gRPC status reporting a handler error, by its kind
+pub fn status(error: ApiError) -> Status {
+    let code = match error.kind {
+        ErrorKind::NotFound => tonic::Code::NotFound,
+        ErrorKind::Invalid => tonic::Code::InvalidArgument,
+        ErrorKind::Conflict => tonic::Code::FailedPrecondition,
+        ErrorKind::Unauthenticated => tonic::Code::Unauthenticated,
+        ErrorKind::Forbidden => tonic::Code::PermissionDenied,
+        ErrorKind::Unavailable => tonic::Code::Unavailable,
+        ErrorKind::Upstream => tonic::Code::Unknown,
+        ErrorKind::Internal => tonic::Code::Internal,
+    };
+    Status::new(code, error.message)
+}
+
+/// Serve `router` until shutdown is triggered, then let in-flight calls finish
+///
+/// Calls still running after the drain timeout are abandoned.
+pub async fn serve(listener: TcpListener, router: Router, shutdown: &Shutdown) -> Result<()> {
+    let incoming = TcpIncoming::from_listener(listener, true, None).map_err(|e| anyhow::anyhow!(e))?;
+    let signal = shutdown.clone();
+    let server = router.serve_with_incoming_shutdown(incoming, async move { signal.wait().await });
+    tokio::pin!(server);
+
+    tokio::select! {
+        result = &mut server => return Ok(result?),
+        _ = shutdown.wait() => {},
+    }
+    tracing::info!("draining in-flight calls");
+    match tokio::time::timeout(shutdown.drain_timeout(), server).await {
+        Ok(result) => Ok(result?),
+        Err(_) => {
+            tracing::warn!("calls still in flight after {:?} were abandoned", shutdown.drain_timeout());
+            Ok(())
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sniper_users::UserRole;
+
+    const SECRET: &str = "an-unguessable-test-secret-of-32-bytes";
+
+    fn trader() -> UserContext {
+        UserContext {
+            user_id: "user-1".to_string(),
+            tenant_id: "tenant-1".to_string(),
+            roles: vec![UserRole::Trader],
+            permissions: vec!["execute_trades".to_string()],
+        }
+    }
+
+    fn incoming(authorization: Option<&str>) -> Request<()> {
+        let mut request = Request::new(());
+        if let Some(authorization) = authorization {
+            request.metadata_mut().insert("authorization", authorization.parse().unwrap());
+        }
+        request
+    }
+
+    #[test]
+    fn test_authenticate() {
+        let auth = Arc::new(JwtAuth::new(SECRET).unwrap());
+        let token = auth.issue(&trader()).unwrap().token;
+        let mut interceptor = Authenticate::new(auth);
+
+        let request = interceptor.call(incoming(Some(&format!("Bearer {}", token)))).unwrap();
+        let caller = caller(&request).unwrap();
+        assert_eq!(caller.user_id, "user-1");
+        assert!(require_permission(&caller, "execute_trades").is_ok());
+        assert_eq!(require_permission(&caller, "configure_system").unwrap_err().code(), tonic::Code::PermissionDenied);
+
+        for authorization in [None, Some("Bearer "), Some("Bearer not-a-token"), Some(token.as_str())] {
+            let status = interceptor.call(incoming(authorization)).unwrap_err();
+            assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        }
+    }
+
+    #[test]
+    fn test_caller_requires_tenant() {
+        assert_eq!(caller(&incoming(None)).unwrap_err().code(), tonic::Code::Unauthenticated);
+
+        let mut request = incoming(None);
+        request.extensions_mut().insert(UserContext {
+            tenant_id: String::new(),
+            ..trader()
+        });
+        assert_eq!(caller(&request).unwrap_err().code(), tonic::Code::PermissionDenied);
+    }
+
+    #[test]
+    fn test_status() {
+        let status = status(ApiError::not_found("Order not found"));
+        assert_eq!(status.code(), tonic::Code::NotFound);
+        assert_eq!(status.message(), "Order not found");
+        assert_eq!(super::status(ApiError::new(ErrorKind::Conflict, "closed")).code(), tonic::Code::FailedPrecondition);
+    }
+}
@@ -0,0 +1,307 @@
+//! Runner configuration.
+//!
+//! A single TOML file lists the signal feeds to run, the strategies to enable
+//! (built-in or plugin), their parameters, the feeds and execution venues each
+//! strategy uses, the risk limits applied before anything is submitted, the
+//! warm-up thresholds met before the first submission, the kill criteria
+//! retiring a strategy, the order signals are handled in when they back up,
+//! how failed submissions are retried, and the circuit breaker halting all
+//! execution.
+
+use crate::retirement::RetirementRules;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sniper_core::bus_priority::PriorityConfig;
+use sniper_core::kill_switch::CircuitBreakerConfig;
+use sniper_core::types::{ChainRef, ExecMode};
+use sniper_core::warmup::WarmupConfig;
+use sniper_exec::retry::RetryConfig;
+use std::collections::HashSet;
+
+/// Runner configuration loaded from `configs/runner.toml`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunnerConfig {
+    #[serde(default)]
+    pub runner: RunnerSettings,
+    #[serde(default)]
+    pub risk: RiskLimits,
+    #[serde(default)]
+    pub warmup: WarmupConfig,
+    #[serde(default)]
+    pub feeds: Vec<FeedConfig>,
+    #[serde(default)]
+    pub strategies: Vec<StrategyConfig>,
+    /// Signal classes drained by priority when strategies fall behind
+    #[serde(default)]
+    pub signal_priority: PriorityConfig,
+    /// Retries of transient submission errors and the idempotency ledger
+    #[serde(default)]
+    pub execution_retry: RetryConfig,
+    /// Error-rate threshold of the kill switch halting all execution
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+}
+
+/// Process-wide settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunnerSettings {
+    pub tenant_id: String,
+    /// Capacity of the in-process bus
+    pub bus_buffer: usize,
+}
+
+impl Default for RunnerSettings {
+    fn default() -> Self {
+        Self {
+            tenant_id: "default".to_string(),
+            bus_buffer: 1024,
+        }
+    }
+}
+
+/// Risk limits applied to every plan before execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskLimits {
+    /// Largest input amount of a single trade, in ETH
+    pub max_trade_eth: f64,
+    /// Trades allowed across all strategies in a rolling hour
+    pub max_trades_per_hour: usize,
+    /// Minimum time between two trades of the same token
+    pub cooldown_secs: u64,
+}
+
+impl Default for RiskLimits {
+    fn default() -> Self {
+        Self {
+            max_trade_eth: 1.0,
+            max_trades_per_hour: 20,
+            cooldown_secs: 30,
+        }
+    }
+}
+
+/// Kind of signal feed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedKind {
+    /// Synthetic signals published on an interval
+    Demo,
+}
+
+/// Signal feed publishing onto the bus
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedConfig {
+    pub id: String,
+    pub kind: FeedKind,
+    /// Signal source reported by the feed (dex, nft, cex, social)
+    pub source: String,
+    pub chain: ChainRef,
+    /// Signal kinds the feed emits
+    pub signal_kinds: Vec<String>,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+}
+
+fn default_interval_secs() -> u64 {
+    5
+}
+
+/// Where a strategy's logic comes from
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StrategyKind {
+    /// Built-in snipe strategy driven by its parameters
+    Builtin,
+    /// Strategy plugin registered with the runner under `plugin`
+    Plugin,
+}
+
+/// Strategy enabled in the runner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyConfig {
+    pub id: String,
+    pub kind: StrategyKind,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Plugin ID, for plugin strategies
+    #[serde(default)]
+    pub plugin: Option<String>,
+    /// Feeds whose signals the strategy receives
+    pub feeds: Vec<String>,
+    /// Signal kinds the strategy handles; empty handles all
+    #[serde(default)]
+    pub signals: Vec<String>,
+    /// Venues the strategy may execute through, picked by expected value
+    #[serde(default = "default_venues")]
+    pub venues: Vec<ExecMode>,
+    /// Overrides the global trade size limit, in ETH
+    #[serde(default)]
+    pub max_trade_eth: Option<f64>,
+    /// Strategy parameters; plugins receive them as their settings
+    #[serde(default)]
+    pub params: serde_json::Value,
+    /// Kill criteria retiring the strategy
+    #[serde(default)]
+    pub retirement: RetirementRules,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_venues() -> Vec<ExecMode> {
+    vec![ExecMode::Private, ExecMode::Bundle, ExecMode::Mempool]
+}
+
+impl StrategyConfig {
+    /// Whether the strategy handles a signal kind from a feed
+    pub fn handles(&self, feed_id: &str, signal_kind: &str) -> bool {
+        self.feeds.iter().any(|feed| feed == feed_id)
+            && (self.signals.is_empty() || self.signals.iter().any(|kind| kind == signal_kind))
+    }
+}
+
+impl RunnerConfig {
+    /// Load and validate the runner configuration from a TOML file
+    pub fn load(path: &str) -> Result<Self> {
+        let txt = std::fs::read_to_string(path)?;
+        Self::parse(&txt)
+    }
+
+    /// Load the runner configuration from the default location
+    pub fn load_default() -> Result<Self> {
+        Self::load("configs/runner.toml")
+    }
+
+    /// Parse and validate a runner configuration
+    pub fn parse(txt: &str) -> Result<Self> {
+        let config: Self = toml::from_str(txt)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Enabled strategies
+    pub fn enabled_strategies(&self) -> impl Iterator<Item = &StrategyConfig> {
+        self.strategies.iter().filter(|strategy| strategy.enabled)
+    }
+
+    /// Check that IDs are unique and every strategy references known feeds
+    pub fn validate(&self) -> Result<()> {
+        let mut feed_ids = HashSet::new();
+        for feed in &self.feeds {
+            if !feed_ids.insert(feed.id.as_str()) {
+                return Err(anyhow::anyhow!("Duplicate feed {}", feed.id));
+            }
+            if feed.interval_secs == 0 {
+                return Err(anyhow::anyhow!("Feed {} must have a positive interval", feed.id));
+            }
+        }
+
+        let mut strategy_ids = HashSet::new();
+        for strategy in &self.strategies {
+            if !strategy_ids.insert(strategy.id.as_str()) {
+                return Err(anyhow::anyhow!("Duplicate strategy {}", strategy.id));
+            }
+            if let Some(feed) = strategy.feeds.iter().find(|feed| !feed_ids.contains(feed.as_str())) {
+                return Err(anyhow::anyhow!("Strategy {} uses unknown feed {}", strategy.id, feed));
+            }
+            if strategy.venues.is_empty() {
+                return Err(anyhow::anyhow!("Strategy {} has no execution venues", strategy.id));
+            }
+            if strategy.kind == StrategyKind::Plugin && strategy.plugin.is_none() {
+                return Err(anyhow::anyhow!("Plugin strategy {} does not name its plugin", strategy.id));
+            }
+            strategy
+                .retirement
+                .validate()
+                .map_err(|e| anyhow::anyhow!("Strategy {}: {}", strategy.id, e))?;
+        }
+
+        if self.signal_priority.classes.is_empty() {
+            return Err(anyhow::anyhow!("No signal priority classes"));
+        }
+        if let Some(class) = self.signal_priority.classes.iter().find(|class| class.capacity == 0) {
+            return Err(anyhow::anyhow!("Signal priority class {} must have a positive capacity", class.name));
+        }
+
+        if self.execution_retry.max_attempts == 0 {
+            return Err(anyhow::anyhow!("Execution retries need at least one attempt"));
+        }
+
+        if self.enabled_strategies().next().is_none() {
+            return Err(anyhow::anyhow!("No strategies enabled"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        [[feeds]]
+        id = "dex-demo"
+        kind = "demo"
+        source = "dex"
+        chain = { name = "ethereum", id = 1 }
+        signal_kinds = ["pair_created"]
+
+        [[strategies]]
+        id = "launch_snipe"
+        kind = "builtin"
+        feeds = ["dex-demo"]
+        signals = ["pair_created"]
+        venues = ["Private", "Mempool"]
+
+        [strategies.params]
+        amount_in_eth = 0.5
+    "#;
+
+    #[test]
+    fn test_parse_config() {
+        let config = RunnerConfig::parse(CONFIG).unwrap();
+        assert_eq!(config.runner.tenant_id, "default");
+        assert_eq!(config.feeds[0].interval_secs, 5);
+        assert_eq!(config.warmup.min_quote_samples, 5);
+        assert_eq!(config.signal_priority.classes[0].name, "launch");
+
+        let strategy = &config.strategies[0];
+        assert!(strategy.enabled);
+        assert_eq!(strategy.venues, vec![ExecMode::Private, ExecMode::Mempool]);
+        assert_eq!(strategy.params["amount_in_eth"], 0.5);
+        assert!(strategy.handles("dex-demo", "pair_created"));
+        assert!(!strategy.handles("dex-demo", "trading_enabled"));
+        assert!(!strategy.handles("nft-demo", "pair_created"));
+    }
+
+    #[test]
+    fn test_validation() {
+        let unknown_feed = CONFIG.replace(r#"feeds = ["dex-demo"]"#, r#"feeds = ["cex"]"#);
+        assert!(RunnerConfig::parse(&unknown_feed).is_err());
+
+        let unnamed_plugin = CONFIG.replace(r#"kind = "builtin""#, r#"kind = "plugin""#);
+        assert!(RunnerConfig::parse(&unnamed_plugin).is_err());
+
+        let disabled = CONFIG.replace(r#"kind = "builtin""#, "kind = \"builtin\"\nenabled = false");
+        assert!(RunnerConfig::parse(&disabled).is_err());
+
+        let bad_fill_rate = CONFIG.replace(r#"kind = "builtin""#, "kind = \"builtin\"\nretirement = { min_fill_rate = 1.5 }");
+        assert!(RunnerConfig::parse(&bad_fill_rate).is_err());
+
+        let no_backlog = format!(
+            "{}\n[[signal_priority.classes]]\nname = \"all\"\npriority = 0\ntopics = [\">\"]\ncapacity = 0\ndrop_policy = \"drop_oldest\"\n",
+            CONFIG
+        );
+        assert!(RunnerConfig::parse(&no_backlog).is_err());
+    }
+
+    #[test]
+    fn test_default_config_parses() {
+        let txt = include_str!("../../../configs/runner.toml");
+        let config = RunnerConfig::parse(txt).unwrap();
+        assert_eq!(config.execution_retry.max_attempts, 3);
+        assert!(config.execution_retry.ledger_path.is_some());
+        assert_eq!(config.circuit_breaker.max_error_rate_pct, Some(50.0));
+    }
+}
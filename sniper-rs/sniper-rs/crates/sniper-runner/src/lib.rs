@@ -0,0 +1,19 @@
+//! Single-process runner for the sniper-rs pipeline.
+//!
+//! Reads a config listing the enabled strategies (built-in and plugins), their
+//! parameters, feeds, venues and risk limits, wires them over the in-process
+//! bus and runs the whole pipeline as one deployable process, for users who
+//! don't want to operate the svc-* microservices. Builds that ship strategy
+//! plugins register them with [`Runner::register_plugin`] before starting.
+//! Plans are only executed once the pipeline has warmed up, see
+//! [`Runner::warmup`], and strategies breaching their kill criteria are
+//! retired until re-enabled with [`Runner::reenable_strategy`].
+
+pub mod config;
+pub mod pipeline;
+pub mod retirement;
+pub mod risk;
+pub mod strategy;
+
+pub use config::RunnerConfig;
+pub use pipeline::Runner;
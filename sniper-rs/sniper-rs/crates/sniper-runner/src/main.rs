@@ -0,0 +1,93 @@
+//! sniper-runner binary: loads the runner configuration and runs the pipeline.
+
+use clap::Parser;
+use sniper_monitoring::http::registry_router;
+use sniper_monitoring::latency::{register_latency_metrics, spawn_latency_reporter};
+use sniper_monitoring::MetricsRegistry;
+use sniper_runner::{Runner, RunnerConfig};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// CLI arguments for the runner
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Path to the runner configuration
+    #[clap(short, long, default_value = "configs/runner.toml")]
+    config: String,
+
+    /// Validate the configuration and exit
+    #[clap(long)]
+    check: bool,
+
+    /// OTLP endpoint spans are exported to, defaulting to OTEL_EXPORTER_OTLP_ENDPOINT
+    #[clap(long)]
+    otlp_endpoint: Option<String>,
+
+    /// Address serving tick-to-trade latency metrics on /metrics, e.g. 0.0.0.0:9100
+    #[clap(long)]
+    metrics_addr: Option<String>,
+
+    /// Interval latency percentiles are computed over
+    #[clap(long, default_value_t = 10)]
+    latency_report_secs: u64,
+}
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter("info")
+        .json()
+        .init();
+    dotenvy::dotenv().ok();
+
+    let args = Args::parse();
+    let _telemetry = sniper_core::telemetry::init_telemetry("sniper-runner", args.otlp_endpoint.as_deref())
+        .map_err(|e| eyre::eyre!("{}", e))?;
+    let config = RunnerConfig::load(&args.config).map_err(|e| eyre::eyre!("{}: {}", args.config, e))?;
+    if args.check {
+        tracing::info!(config = %args.config, "configuration is valid");
+        return Ok(());
+    }
+
+    // Builds embedding plugin strategies register them here before starting
+    let runner = Runner::new(config);
+    let mut handles = runner.start().map_err(|e| eyre::eyre!("{}", e))?;
+
+    if let Some(addr) = &args.metrics_addr {
+        let mut registry = MetricsRegistry::new();
+        register_latency_metrics(&mut registry).map_err(|e| eyre::eyre!("{}", e))?;
+        let registry = Arc::new(Mutex::new(registry));
+        handles.push(spawn_latency_reporter(
+            runner.latency(),
+            registry.clone(),
+            Duration::from_secs(args.latency_report_secs),
+        ));
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        tracing::info!(addr = %addr, "serving metrics");
+        handles.push(tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, registry_router(registry)).await {
+                tracing::error!("metrics server failed: {}", e);
+            }
+        }));
+    }
+
+    for handle in handles {
+        handle.await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args_parsing() {
+        let args = Args::parse_from(["sniper-runner", "--config", "runner.toml", "--check"]);
+        assert_eq!(args.config, "runner.toml");
+        assert!(args.check);
+        assert!(args.metrics_addr.is_none());
+        assert_eq!(args.latency_report_secs, 10);
+    }
+}
@@ -0,0 +1,1012 @@
+//! Single-process trading pipeline.
+//!
+//! Feeds publish signals on the in-process bus, enabled strategies turn the
+//! signals they handle into plans, and the execution stage checks each plan
+//! against the risk gate, picks its venue by expected value and submits it.
+//! Submission goes through an idempotency ledger, so a plan is executed at
+//! most once per `idem_key` and transient RPC errors are retried. Nothing
+//! is submitted while the kill switch is tripped, and a burst of failed
+//! submissions trips it. Runners deployed in several regions attach a
+//! [`FailoverCoordinator`]; only the region holding the execution lease then
+//! submits, and every send is fenced by the lease's token.
+//! Subjects match the ones used between the svc-* services.
+//!
+//! Signals are taken off the bus as they arrive and queued by priority, so
+//! when the strategies fall behind launch signals are handled ahead of bulk
+//! data, and the bulk backlog is what gets dropped.
+//!
+//! Until the warm-up completes, plans are held back rather than executed:
+//! signal arrival keeps data freshness, and plan generation time stands in
+//! for quote latency.
+//!
+//! The execution stage also keeps each strategy's open positions and track
+//! record. Fills come from the receipts, realized PnL from the
+//! [`StrategyPositionClosed`] messages published by whatever runs the exits;
+//! a strategy breaching its kill criteria is retired and flattened.
+//!
+//! Every signal starts a trace, carried on the bus from the feed through
+//! strategy planning, the risk check and execution, so a trade can be
+//! followed end to end. Alongside, each signal is timestamped at ingest,
+//! decision, risk check, order send and ack, and the latencies of executed
+//! plans are aggregated in the runner's [`LatencyRecorder`].
+
+use crate::config::{FeedConfig, FeedKind, RunnerConfig};
+use crate::retirement::{flatten_plan, Retirement, RetirementBook};
+use crate::risk::RiskGate;
+use crate::strategy::ActiveStrategy;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sniper_core::bus::InMemoryBus;
+use sniper_core::bus_priority::{ClassStats, PriorityQueue};
+use sniper_core::events::TradingEvent;
+use sniper_core::kill_switch::KillSwitch;
+use sniper_core::latency::{LatencyRecorder, LatencyTrace, Stage};
+use sniper_core::telemetry::{self, Context, FutureExt, KeyValue};
+use sniper_core::types::{ExecMode, ExecReceipt, Signal, TradePlan};
+use sniper_core::warmup::{WarmupCoordinator, WarmupStatus};
+use sniper_exec::retry::ExecutionLedger;
+use sniper_exec::venue::{VenueOutcome, VenuePolicy};
+use sniper_exec::Executor;
+use sniper_storage::failover::FailoverCoordinator;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+
+/// Subject signals are published under
+pub const SIGNALS_SUBJECT: &str = "signals.>";
+
+/// Subject plans are published under
+pub const PLAN_SUBJECT: &str = "plan.created";
+
+/// Subject execution results are published under
+pub const EXEC_RESULT_SUBJECT: &str = "exec.result";
+
+/// Subject closed strategy positions are published under
+pub const POSITION_CLOSED_SUBJECT: &str = "strategy.position_closed";
+
+/// Key in a signal's `extra` naming the feed that produced it
+const FEED_KEY: &str = "feed";
+
+/// Signal waiting for the strategies, with its trace context and timestamps
+type QueuedSignal = (Signal, Context, LatencyTrace);
+
+/// Plan generated by a strategy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyPlan {
+    pub strategy_id: String,
+    pub plan: TradePlan,
+}
+
+/// Result of executing a strategy's plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyExecution {
+    pub strategy_id: String,
+    pub mode: ExecMode,
+    pub receipt: ExecReceipt,
+}
+
+/// Realized result of a strategy's position, counted towards its kill criteria
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyPositionClosed {
+    pub strategy_id: String,
+    /// Token the position held
+    pub token: String,
+    pub realized_pnl_eth: f64,
+}
+
+/// Position opened by a strategy's filled plan
+struct OpenPosition {
+    entry: TradePlan,
+    amount: u128,
+}
+
+/// Runner wiring feeds, strategies, risk and execution over one bus
+pub struct Runner {
+    config: RunnerConfig,
+    bus: InMemoryBus,
+    plugins: HashMap<String, Arc<dyn sniper_plugin::Strategy>>,
+    warmup: Arc<Mutex<WarmupCoordinator>>,
+    retirements: Arc<Mutex<RetirementBook>>,
+    signals: Arc<PriorityQueue<QueuedSignal>>,
+    kill_switch: KillSwitch,
+    latency: Arc<LatencyRecorder>,
+    failover: Option<Arc<tokio::sync::RwLock<FailoverCoordinator>>>,
+}
+
+impl Runner {
+    /// Create a runner from a validated configuration
+    pub fn new(config: RunnerConfig) -> Self {
+        let bus = InMemoryBus::with_history(
+            config.runner.bus_buffer,
+            Duration::from_millis(config.warmup.replay_window_ms),
+        );
+        let warmup = Arc::new(Mutex::new(WarmupCoordinator::new(config.warmup.clone())));
+        let retirements = Arc::new(Mutex::new(RetirementBook::new(&config.strategies)));
+        let signals = Arc::new(PriorityQueue::new(config.signal_priority.clone()));
+        let kill_switch = KillSwitch::new(config.circuit_breaker.clone());
+        Self {
+            config,
+            bus,
+            plugins: HashMap::new(),
+            warmup,
+            retirements,
+            signals,
+            kill_switch,
+            latency: Arc::new(LatencyRecorder::new()),
+            failover: None,
+        }
+    }
+
+    /// Only execute while holding the coordinator's execution lease
+    ///
+    /// The runner drives the coordinator's heartbeats once started.
+    pub fn with_failover(mut self, failover: FailoverCoordinator) -> Self {
+        self.failover = Some(Arc::new(tokio::sync::RwLock::new(failover)));
+        self
+    }
+
+    /// Register a strategy plugin, referenced from the configuration by its ID
+    pub fn register_plugin(&mut self, plugin: Arc<dyn sniper_plugin::Strategy>) {
+        self.plugins.insert(plugin.metadata().id.clone(), plugin);
+    }
+
+    /// Get the bus the pipeline runs on
+    pub fn bus(&self) -> &InMemoryBus {
+        &self.bus
+    }
+
+    /// Get the warm-up coordinator
+    ///
+    /// Builds that preload their own caches call `expect_preload` before
+    /// starting and record the outcome once loaded.
+    pub fn warmup(&self) -> Arc<Mutex<WarmupCoordinator>> {
+        self.warmup.clone()
+    }
+
+    /// Warm-up progress
+    pub fn warmup_status(&self) -> WarmupStatus {
+        lock(&self.warmup).status(now_ms())
+    }
+
+    /// Get the kill switch halting execution
+    pub fn kill_switch(&self) -> &KillSwitch {
+        &self.kill_switch
+    }
+
+    /// Get the strategies' track records, retirements and audit trail
+    pub fn retirements(&self) -> Arc<Mutex<RetirementBook>> {
+        self.retirements.clone()
+    }
+
+    /// Get the recorder of the pipeline's stage and tick-to-trade latencies
+    pub fn latency(&self) -> Arc<LatencyRecorder> {
+        self.latency.clone()
+    }
+
+    /// Get the queued, handled and dropped signals of each priority class
+    pub fn signal_queue_stats(&self) -> Vec<ClassStats> {
+        self.signals.stats()
+    }
+
+    /// Re-enable a retired strategy, recording who did it and why
+    pub async fn reenable_strategy(&self, strategy_id: &str, actor: &str, reason: &str) -> Result<()> {
+        let now = now_ms();
+        lock(&self.retirements).reenable(strategy_id, actor, reason, now)?;
+        tracing::info!(strategy = %strategy_id, actor = %actor, "strategy re-enabled: {}", reason);
+
+        let event = TradingEvent::StrategyReEnabled {
+            strategy_id: strategy_id.to_string(),
+            actor: actor.to_string(),
+            reason: reason.to_string(),
+            timestamp: (now / 1000) as u64,
+        };
+        self.bus.publish(event.subject(), &event).await?;
+        Ok(())
+    }
+
+    /// Start the pipeline tasks
+    ///
+    /// Fails without starting anything if a strategy cannot be built.
+    pub fn start(&self) -> Result<Vec<JoinHandle<()>>> {
+        let strategies = self
+            .config
+            .enabled_strategies()
+            .map(|config| ActiveStrategy::new(config.clone(), &self.plugins))
+            .collect::<Result<Vec<_>>>()?;
+        let ledger = Arc::new(ExecutionLedger::from_config(&self.config.execution_retry)?);
+
+        {
+            let mut warmup = lock(&self.warmup);
+            warmup.record_preload("strategies", Ok(strategies.len()));
+            // Nothing is re-executed; replayed signals only count towards data freshness
+            let now = now_ms();
+            for event in warmup.replay(&self.bus, now) {
+                if event.subject.starts_with("signals.") {
+                    warmup.record_data(event.published_at_ms);
+                }
+            }
+        }
+
+        let mut executor = Executor::new()
+            .with_idempotency(ledger, self.config.execution_retry.clone())
+            .with_kill_switch(self.kill_switch.clone())
+            .with_bus(self.bus.clone());
+        if let Some(failover) = &self.failover {
+            executor = executor.with_failover(failover.clone());
+        }
+
+        // Subscribe before any feed can publish
+        let mut handles = vec![
+            spawn_execution(
+                &self.bus,
+                strategies.clone(),
+                RiskGate::new(self.config.risk.clone()),
+                self.warmup.clone(),
+                self.retirements.clone(),
+                self.latency.clone(),
+                executor,
+            ),
+            spawn_signal_intake(&self.bus, self.signals.clone()),
+            spawn_strategies(
+                &self.bus,
+                self.signals.clone(),
+                strategies,
+                self.warmup.clone(),
+                self.latency.clone(),
+            ),
+        ];
+        if let Some(failover) = &self.failover {
+            handles.push(spawn_heartbeat(failover.clone()));
+        }
+        for feed in &self.config.feeds {
+            handles.push(spawn_feed(&self.bus, feed.clone()));
+        }
+        tracing::info!(
+            tenant = %self.config.runner.tenant_id,
+            feeds = self.config.feeds.len(),
+            strategies = self.config.enabled_strategies().count(),
+            "runner started"
+        );
+        Ok(handles)
+    }
+}
+
+/// Renew the execution lease as primary, or take it over as standby
+fn spawn_heartbeat(failover: Arc<tokio::sync::RwLock<FailoverCoordinator>>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let interval = {
+                let mut failover = failover.write().await;
+                if let Err(e) = failover.heartbeat().await {
+                    tracing::error!("failover heartbeat failed: {}", e);
+                }
+                failover.config().heartbeat_interval
+            };
+            sleep(interval).await;
+        }
+    })
+}
+
+/// Lock shared pipeline state, recovering it if a holder panicked
+fn lock<T>(state: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+/// Receive the next message, skipping over lag; `None` once the bus closes
+async fn next_message(rx: &mut tokio::sync::broadcast::Receiver<Vec<u8>>) -> Option<Vec<u8>> {
+    loop {
+        match rx.recv().await {
+            Ok(bytes) => return Some(bytes),
+            Err(RecvError::Lagged(skipped)) => tracing::warn!("runner lagged, skipped {} messages", skipped),
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// Publish a feed's signals on its interval
+fn spawn_feed(bus: &InMemoryBus, feed: FeedConfig) -> JoinHandle<()> {
+    let bus = bus.clone();
+    tokio::spawn(async move {
+        match feed.kind {
+            FeedKind::Demo => {
+                let mut seq: i64 = 0;
+                loop {
+                    for kind in &feed.signal_kinds {
+                        seq += 1;
+                        let signal = Signal {
+                            source: feed.source.clone(),
+                            kind: kind.clone(),
+                            chain: feed.chain.clone(),
+                            token0: Some(format!("0xToken{}", seq)),
+                            token1: Some("0xWETH".to_string()),
+                            extra: serde_json::json!({ FEED_KEY: feed.id }),
+                            seen_at_ms: seq,
+                        };
+                        let cx = telemetry::start_span(
+                            "signal.publish",
+                            &Context::new(),
+                            vec![
+                                KeyValue::new("feed.id", feed.id.clone()),
+                                KeyValue::new("signal.kind", kind.clone()),
+                            ],
+                        );
+                        if let Err(e) = bus.publish_in(&signal_subject(&signal), &signal, &cx) {
+                            tracing::warn!(feed = %feed.id, "failed to publish signal: {}", e);
+                        }
+                    }
+                    sleep(Duration::from_secs(feed.interval_secs)).await;
+                }
+            }
+        }
+    })
+}
+
+/// Subject a signal is published under
+fn signal_subject(signal: &Signal) -> String {
+    format!("signals.{}.{}", signal.source, signal.kind)
+}
+
+/// Move signals off the bus into the priority queue as they arrive
+fn spawn_signal_intake(bus: &InMemoryBus, signals: Arc<PriorityQueue<QueuedSignal>>) -> JoinHandle<()> {
+    let mut rx = bus.subscribe(SIGNALS_SUBJECT);
+    tokio::spawn(async move {
+        while let Some(bytes) = next_message(&mut rx).await {
+            let trace = LatencyTrace::start();
+            if let Ok(signal) = serde_json::from_slice::<Signal>(&bytes) {
+                signals.push(&signal_subject(&signal), (signal, telemetry::message_context(&bytes), trace));
+            }
+        }
+        signals.close();
+    })
+}
+
+/// Turn queued signals into plans for every strategy handling them
+fn spawn_strategies(
+    bus: &InMemoryBus,
+    signals: Arc<PriorityQueue<QueuedSignal>>,
+    strategies: Vec<ActiveStrategy>,
+    warmup: Arc<Mutex<WarmupCoordinator>>,
+    latency: Arc<LatencyRecorder>,
+) -> JoinHandle<()> {
+    let bus = bus.clone();
+    tokio::spawn(async move {
+        while let Some((signal, signal_cx, trace)) = signals.pop().await {
+            lock(&warmup).record_data(now_ms());
+            let feed_id = signal.extra.get(FEED_KEY).and_then(|feed| feed.as_str()).unwrap_or_default();
+            for strategy in strategies.iter().filter(|s| s.config.handles(feed_id, &signal.kind)) {
+                let cx = telemetry::start_span(
+                    "strategy.plan",
+                    &signal_cx,
+                    vec![KeyValue::new("strategy.id", strategy.config.id.clone())],
+                );
+                let started = std::time::Instant::now();
+                let generated = strategy.generate_plan(&signal).await;
+                lock(&warmup).record_quote_latency(started.elapsed().as_millis() as u64);
+                match generated {
+                    Ok(Some(plan)) => {
+                        // Execution picks the trace back up from the plan's key
+                        let mut trace = trace.clone();
+                        trace.mark(Stage::Decision);
+                        latency.park(plan.idem_key.clone(), trace);
+                        let planned = StrategyPlan {
+                            strategy_id: strategy.config.id.clone(),
+                            plan,
+                        };
+                        if let Err(e) = bus.publish_in(PLAN_SUBJECT, &planned, &cx) {
+                            tracing::warn!(strategy = %planned.strategy_id, "failed to publish plan: {}", e);
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        telemetry::record_error(&cx, &e);
+                        tracing::error!(strategy = %strategy.config.id, "strategy failed: {}", e);
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Check, route and execute plans, publishing their results
+fn spawn_execution(
+    bus: &InMemoryBus,
+    strategies: Vec<ActiveStrategy>,
+    mut risk: RiskGate,
+    warmup: Arc<Mutex<WarmupCoordinator>>,
+    retirements: Arc<Mutex<RetirementBook>>,
+    latency: Arc<LatencyRecorder>,
+    mut executor: Executor,
+) -> JoinHandle<()> {
+    let bus = bus.clone();
+    let mut rx = bus.subscribe(PLAN_SUBJECT);
+    let policies: HashMap<String, (ActiveStrategy, VenuePolicy)> = strategies
+        .into_iter()
+        .map(|strategy| {
+            let policy = VenuePolicy {
+                venues: strategy.config.venues.clone(),
+                ..VenuePolicy::default()
+            };
+            (strategy.config.id.clone(), (strategy, policy))
+        })
+        .collect();
+    tokio::spawn(async move {
+        let mut positions: HashMap<String, Vec<OpenPosition>> = HashMap::new();
+        while let Some(bytes) = next_message(&mut rx).await {
+            if let Ok(closed) = serde_json::from_slice::<StrategyPositionClosed>(&bytes) {
+                let open = positions.entry(closed.strategy_id.clone()).or_default();
+                if let Some(index) = open.iter().position(|position| position.entry.token_out == closed.token) {
+                    open.remove(index);
+                }
+                let retirement = lock(&retirements).record_close(&closed.strategy_id, closed.realized_pnl_eth, now_ms());
+                if let Some(retirement) = retirement {
+                    retire(&bus, &executor, retirement, open).await;
+                }
+                continue;
+            }
+            let Ok(StrategyPlan { strategy_id, mut plan }) = serde_json::from_slice::<StrategyPlan>(&bytes) else {
+                continue;
+            };
+            let Some((strategy, policy)) = policies.get(&strategy_id) else {
+                continue;
+            };
+            let mut trace = latency.resume(&plan.idem_key);
+            let cx = telemetry::start_span(
+                "plan.execute",
+                &telemetry::message_context(&bytes),
+                vec![
+                    KeyValue::new("strategy.id", strategy_id.clone()),
+                    KeyValue::new("plan.idem_key", plan.idem_key.clone()),
+                ],
+            );
+
+            let blockers = {
+                let mut warmup = lock(&warmup);
+                let now = now_ms();
+                if warmup.check(now) {
+                    None
+                } else {
+                    warmup.record_held();
+                    Some(warmup.blockers(now))
+                }
+            };
+            if let Some(blockers) = blockers {
+                tracing::info!(strategy = %strategy_id, "plan {} held during warm-up: {:?}", plan.idem_key, blockers);
+                continue;
+            }
+            if lock(&retirements).is_retired(&strategy_id) {
+                tracing::info!(strategy = %strategy_id, "plan {} dropped, strategy is retired", plan.idem_key);
+                continue;
+            }
+
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            let decision = {
+                let risk_cx = telemetry::start_span("risk.check", &cx, Vec::new());
+                let decision = risk.check(&strategy.config, &plan, now);
+                if !decision.allow {
+                    telemetry::record_error(&risk_cx, decision.reasons.join("; "));
+                }
+                decision
+            };
+            if !decision.allow {
+                tracing::warn!(strategy = %strategy_id, "plan {} rejected: {:?}", plan.idem_key, decision.reasons);
+                continue;
+            }
+            mark(&mut trace, Stage::RiskCheck);
+
+            if let Some(selection) = policy.select(&plan, executor.cost_model()) {
+                plan.mode = selection.mode;
+            }
+            let exec_cx = telemetry::start_span(
+                "trade.execute",
+                &cx,
+                vec![KeyValue::new("exec.mode", format!("{:?}", plan.mode))],
+            );
+            let started = std::time::Instant::now();
+            mark(&mut trace, Stage::OrderSend);
+            let receipt = match executor.execute_trade(&plan).with_context(exec_cx.clone()).await {
+                Ok(receipt) => {
+                    if let Some(mut trace) = trace {
+                        trace.mark(Stage::Ack);
+                        latency.record(&trace);
+                    }
+                    receipt
+                }
+                Err(e) => {
+                    telemetry::record_error(&exec_cx, &e);
+                    tracing::error!(strategy = %strategy_id, "execution of {} failed: {}", plan.idem_key, e);
+                    continue;
+                }
+            };
+            if !receipt.success {
+                telemetry::record_error(&exec_cx, receipt.failure_reason.as_deref().unwrap_or("execution failed"));
+            }
+            drop(exec_cx);
+            executor.record_outcome(VenueOutcome {
+                mode: plan.mode.clone(),
+                receipt: receipt.clone(),
+                tip_wei: 0,
+                inclusion_latency_ms: started.elapsed().as_millis() as u64,
+            });
+
+            tracing::info!(strategy = %strategy_id, mode = ?plan.mode, "executed {}", receipt.tx_hash);
+            let open = positions.entry(strategy_id.clone()).or_default();
+            if receipt.success {
+                open.push(OpenPosition {
+                    amount: receipt.amount_out.unwrap_or(plan.min_out),
+                    entry: plan.clone(),
+                });
+            }
+            let retirement = lock(&retirements).record_submission(&strategy_id, receipt.success, now_ms());
+            let execution = StrategyExecution {
+                strategy_id,
+                mode: plan.mode,
+                receipt,
+            };
+            if let Err(e) = bus.publish_in(EXEC_RESULT_SUBJECT, &execution, &cx) {
+                tracing::warn!("failed to publish execution result: {}", e);
+            }
+            if let Some(retirement) = retirement {
+                retire(&bus, &executor, retirement, open).await;
+            }
+        }
+    })
+}
+
+/// Timestamp `stage` of a plan's trace, if it has one
+fn mark(trace: &mut Option<LatencyTrace>, stage: Stage) {
+    if let Some(trace) = trace {
+        trace.mark(stage);
+    }
+}
+
+/// Flatten a retired strategy's open positions and announce the retirement
+///
+/// Positions that fail to flatten stay open and are retried at the next
+/// retirement.
+async fn retire(bus: &InMemoryBus, executor: &Executor, retirement: Retirement, positions: &mut Vec<OpenPosition>) {
+    tracing::warn!(strategy = %retirement.strategy_id, "strategy retired: {:?}", retirement.reasons);
+    let mut flattened = 0;
+    for position in std::mem::take(positions) {
+        let plan = flatten_plan(&position.entry, position.amount);
+        match executor.execute_trade(&plan).await {
+            Ok(receipt) if receipt.success => {
+                flattened += 1;
+                let execution = StrategyExecution {
+                    strategy_id: retirement.strategy_id.clone(),
+                    mode: plan.mode,
+                    receipt,
+                };
+                if let Err(e) = bus.publish(EXEC_RESULT_SUBJECT, &execution).await {
+                    tracing::warn!("failed to publish execution result: {}", e);
+                }
+            }
+            Ok(receipt) => {
+                tracing::error!(strategy = %retirement.strategy_id, "flattening {} failed: {:?}", plan.idem_key, receipt.failure_reason);
+                positions.push(position);
+            }
+            Err(e) => {
+                tracing::error!(strategy = %retirement.strategy_id, "flattening {} failed: {}", plan.idem_key, e);
+                positions.push(position);
+            }
+        }
+    }
+
+    let event = TradingEvent::StrategyRetired {
+        strategy_id: retirement.strategy_id,
+        reasons: retirement.reasons,
+        flattened_positions: flattened,
+        timestamp: (retirement.retired_at_ms / 1000) as u64,
+    };
+    if let Err(e) = bus.publish(event.subject(), &event).await {
+        tracing::warn!("failed to publish strategy retirement: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_pipeline_executes_configured_strategy() {
+        let config = RunnerConfig::parse(
+            r#"
+            [[feeds]]
+            id = "dex-demo"
+            kind = "demo"
+            source = "dex"
+            chain = { name = "ethereum", id = 1 }
+            signal_kinds = ["pair_created", "trading_enabled"]
+            interval_secs = 3600
+
+            [[strategies]]
+            id = "launch_snipe"
+            kind = "builtin"
+            feeds = ["dex-demo"]
+            signals = ["pair_created"]
+            venues = ["Bundle"]
+
+            [warmup]
+            min_quote_samples = 1
+            "#,
+        )
+        .unwrap();
+
+        let _telemetry = telemetry::init_telemetry("sniper-runner", Some("")).unwrap();
+        let runner = Runner::new(config);
+        let mut rx = runner.bus().subscribe(EXEC_RESULT_SUBJECT);
+        let handles = runner.start().unwrap();
+
+        let (execution, trace) = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let bytes = rx.recv().await.unwrap();
+                if let Ok(execution) = serde_json::from_slice::<StrategyExecution>(&bytes) {
+                    return (execution, telemetry::trace_id(&telemetry::message_context(&bytes)));
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(execution.strategy_id, "launch_snipe");
+        assert_eq!(execution.mode, ExecMode::Bundle);
+        assert!(execution.receipt.success);
+        // The result continues the trace started by the feed's signal
+        assert!(trace.is_some());
+        // and the plan was timed at every stage from ingest to ack
+        let report = runner.latency().report();
+        assert_eq!(report.tick_to_trade.count, 1);
+        assert!(report.stages.iter().all(|stage| stage.latency.count == 1));
+
+        for handle in handles {
+            handle.abort();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_only_lease_holder_executes() {
+        use sniper_storage::failover::{FailoverConfig, InMemoryLeaseStore, LeaseStore};
+
+        let config = RunnerConfig::parse(
+            r#"
+            [[feeds]]
+            id = "dex-demo"
+            kind = "demo"
+            source = "dex"
+            chain = { name = "ethereum", id = 1 }
+            signal_kinds = ["pair_created"]
+            interval_secs = 3600
+
+            [[strategies]]
+            id = "launch_snipe"
+            kind = "builtin"
+            feeds = ["dex-demo"]
+            signals = ["pair_created"]
+            venues = ["Bundle"]
+
+            [warmup]
+            min_quote_samples = 1
+            "#,
+        )
+        .unwrap();
+        let execution = |mut rx: tokio::sync::broadcast::Receiver<Vec<u8>>, wait: Duration| {
+            tokio::time::timeout(wait, async move {
+                loop {
+                    let bytes = rx.recv().await.unwrap();
+                    if let Ok(execution) = serde_json::from_slice::<StrategyExecution>(&bytes) {
+                        return execution;
+                    }
+                }
+            })
+        };
+        let store: Arc<dyn LeaseStore> = Arc::new(InMemoryLeaseStore::new());
+        let failover = |region: &str| {
+            FailoverCoordinator::new(FailoverConfig::new(region, "main", Duration::from_secs(10)), store.clone())
+        };
+        let mut primary = failover("us-east-1");
+        primary.heartbeat().await.unwrap();
+
+        // Another region holds the lease, so the standby's plans are refused
+        let standby = Runner::new(config.clone()).with_failover(failover("eu-west-1"));
+        let rx = standby.bus().subscribe(EXEC_RESULT_SUBJECT);
+        let handles = standby.start().unwrap();
+        assert!(execution(rx, Duration::from_secs(1)).await.is_err());
+        for handle in handles {
+            handle.abort();
+        }
+
+        primary.step_down().await.unwrap();
+        let runner = Runner::new(config).with_failover(failover("eu-west-1"));
+        let rx = runner.bus().subscribe(EXEC_RESULT_SUBJECT);
+        let handles = runner.start().unwrap();
+        assert!(execution(rx, Duration::from_secs(5)).await.unwrap().receipt.success);
+        for handle in handles {
+            handle.abort();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plans_held_until_warm() {
+        let config = RunnerConfig::parse(
+            r#"
+            [[feeds]]
+            id = "dex-demo"
+            kind = "demo"
+            source = "dex"
+            chain = { name = "ethereum", id = 1 }
+            signal_kinds = ["pair_created"]
+            interval_secs = 3600
+
+            [[strategies]]
+            id = "launch_snipe"
+            kind = "builtin"
+            feeds = ["dex-demo"]
+            venues = ["Bundle"]
+
+            [warmup]
+            min_quote_samples = 2
+            "#,
+        )
+        .unwrap();
+
+        let runner = Runner::new(config);
+        runner.warmup().lock().unwrap().expect_preload("pools");
+        let mut rx = runner.bus().subscribe(EXEC_RESULT_SUBJECT);
+        let handles = runner.start().unwrap();
+
+        // The feed's first plan comes before enough quotes were measured
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while runner.warmup_status().held == 0 {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        let status = runner.warmup_status();
+        assert!(!status.ready);
+        assert!(status.blockers.contains(&"preload pools pending".to_string()));
+        while let Ok(bytes) = rx.try_recv() {
+            assert!(serde_json::from_slice::<StrategyExecution>(&bytes).is_err());
+        }
+
+        // Once the caches are loaded the next plan goes out
+        runner.warmup().lock().unwrap().record_preload("pools", Ok(10));
+        let signal = Signal {
+            source: "dex".to_string(),
+            kind: "pair_created".to_string(),
+            chain: sniper_core::types::ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            token0: Some("0xLate".to_string()),
+            token1: Some("0xWETH".to_string()),
+            extra: serde_json::json!({ FEED_KEY: "dex-demo" }),
+            seen_at_ms: 2,
+        };
+        runner.bus().publish("signals.dex.pair_created", &signal).await.unwrap();
+
+        let execution = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let bytes = rx.recv().await.unwrap();
+                if let Ok(execution) = serde_json::from_slice::<StrategyExecution>(&bytes) {
+                    return execution;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(execution.strategy_id, "launch_snipe");
+        assert!(runner.warmup_status().ready);
+        assert_eq!(runner.warmup_status().held, 1);
+
+        for handle in handles {
+            handle.abort();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_losing_strategy_retired_and_flattened() {
+        let config = RunnerConfig::parse(
+            r#"
+            [[feeds]]
+            id = "dex-demo"
+            kind = "demo"
+            source = "dex"
+            chain = { name = "ethereum", id = 1 }
+            signal_kinds = ["pair_created"]
+            interval_secs = 3600
+
+            [[strategies]]
+            id = "launch_snipe"
+            kind = "builtin"
+            feeds = ["dex-demo"]
+            venues = ["Bundle"]
+            retirement = { max_consecutive_losses = 1 }
+
+            [warmup]
+            min_quote_samples = 1
+            "#,
+        )
+        .unwrap();
+
+        let runner = Runner::new(config);
+        let mut rx = runner.bus().subscribe(EXEC_RESULT_SUBJECT);
+        let handles = runner.start().unwrap();
+        async fn next_execution(rx: &mut tokio::sync::broadcast::Receiver<Vec<u8>>) -> StrategyExecution {
+            loop {
+                let bytes = rx.recv().await.unwrap();
+                if let Ok(execution) = serde_json::from_slice::<StrategyExecution>(&bytes) {
+                    return execution;
+                }
+            }
+        }
+
+        // The feed's first signal opens a position in 0xToken1
+        tokio::time::timeout(Duration::from_secs(5), next_execution(&mut rx)).await.unwrap();
+        let signal = Signal {
+            source: "dex".to_string(),
+            kind: "pair_created".to_string(),
+            chain: sniper_core::types::ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            token0: Some("0xSecond".to_string()),
+            token1: Some("0xWETH".to_string()),
+            extra: serde_json::json!({ FEED_KEY: "dex-demo" }),
+            seen_at_ms: 2,
+        };
+        runner.bus().publish("signals.dex.pair_created", &signal).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), next_execution(&mut rx)).await.unwrap();
+
+        // Closing the first position at a loss retires the strategy and flattens the second
+        let closed = StrategyPositionClosed {
+            strategy_id: "launch_snipe".to_string(),
+            token: "0xToken1".to_string(),
+            realized_pnl_eth: -0.05,
+        };
+        runner.bus().publish(POSITION_CLOSED_SUBJECT, &closed).await.unwrap();
+        let (flattening, retired) = tokio::time::timeout(Duration::from_secs(5), async {
+            let mut flattening = None;
+            loop {
+                let bytes = rx.recv().await.unwrap();
+                if let Ok(execution) = serde_json::from_slice::<StrategyExecution>(&bytes) {
+                    flattening = Some(execution);
+                } else if let Some(event @ TradingEvent::StrategyRetired { .. }) = TradingEvent::decode(&bytes) {
+                    return (flattening, event);
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(flattening.unwrap().strategy_id, "launch_snipe");
+        match retired {
+            TradingEvent::StrategyRetired {
+                strategy_id,
+                flattened_positions,
+                ..
+            } => {
+                assert_eq!(strategy_id, "launch_snipe");
+                assert_eq!(flattened_positions, 1);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        assert!(runner.retirements().lock().unwrap().is_retired("launch_snipe"));
+
+        assert!(runner.reenable_strategy("launch_snipe", "", "").await.is_err());
+        runner.reenable_strategy("launch_snipe", "alice", "bad pool filter fixed").await.unwrap();
+        let retirements = runner.retirements();
+        let book = retirements.lock().unwrap();
+        assert!(!book.is_retired("launch_snipe"));
+        assert_eq!(book.audit_log().len(), 2);
+
+        for handle in handles {
+            handle.abort();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_launch_signals_drained_ahead_of_backlog() {
+        let config = RunnerConfig::parse(
+            r#"
+            [[feeds]]
+            id = "dex-demo"
+            kind = "demo"
+            source = "dex"
+            chain = { name = "ethereum", id = 1 }
+            signal_kinds = ["pair_created"]
+            interval_secs = 3600
+
+            [[strategies]]
+            id = "launch_snipe"
+            kind = "builtin"
+            feeds = ["dex-demo"]
+            signals = ["pair_created"]
+            venues = ["Bundle"]
+
+            [warmup]
+            min_quote_samples = 1
+            "#,
+        )
+        .unwrap();
+
+        let runner = Runner::new(config);
+        let signal = |kind: &str, seq: i64| Signal {
+            source: "dex".to_string(),
+            kind: kind.to_string(),
+            chain: sniper_core::types::ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            token0: Some(format!("0xToken{}", seq)),
+            token1: Some("0xWETH".to_string()),
+            extra: serde_json::json!({ FEED_KEY: "dex-demo" }),
+            seen_at_ms: seq,
+        };
+        // A backlog of ticks built up before the launch signal arrived
+        for seq in 0..300 {
+            let tick = signal("tick", seq);
+            runner.signals.push(&signal_subject(&tick), (tick, Context::new(), LatencyTrace::start()));
+        }
+        let launch = signal("pair_created", 300);
+        runner.signals.push(&signal_subject(&launch), (launch, Context::new(), LatencyTrace::start()));
+
+        let mut rx = runner.bus().subscribe(EXEC_RESULT_SUBJECT);
+        let handles = runner.start().unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let bytes = rx.recv().await.unwrap();
+                if serde_json::from_slice::<StrategyExecution>(&bytes).is_ok() {
+                    return;
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        let stats = runner.signal_queue_stats();
+        assert_eq!(stats[0].name, "launch");
+        assert!(stats[0].processed >= 1);
+        assert_eq!(stats[1].name, "bulk");
+        assert_eq!(stats[1].enqueued, 300);
+        assert_eq!(stats[1].dropped, 44);
+
+        for handle in handles {
+            handle.abort();
+        }
+    }
+
+    #[test]
+    fn test_unregistered_plugin_fails_start() {
+        let config = RunnerConfig::parse(
+            r#"
+            [[feeds]]
+            id = "dex-demo"
+            kind = "demo"
+            source = "dex"
+            chain = { name = "ethereum", id = 1 }
+            signal_kinds = ["pair_created"]
+
+            [[strategies]]
+            id = "custom"
+            kind = "plugin"
+            plugin = "missing"
+            feeds = ["dex-demo"]
+            "#,
+        )
+        .unwrap();
+
+        assert!(Runner::new(config).start().is_err());
+    }
+}
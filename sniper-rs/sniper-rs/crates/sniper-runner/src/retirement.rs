@@ -0,0 +1,391 @@
+//! Kill criteria and automated strategy retirement.
+//!
+//! Each strategy can carry retirement rules: a run of consecutive losing
+//! trades, a drawdown of its realized PnL from its peak, or a collapse of its
+//! fill rate over the last submissions. A strategy breaching any of them is
+//! retired: it stops trading, its open positions are flattened and an
+//! incident is raised. Only an operator re-enables it, and every retirement
+//! and re-enable is kept in the audit trail.
+
+use crate::config::StrategyConfig;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sniper_core::types::TradePlan;
+use std::collections::{HashMap, VecDeque};
+
+/// Actor recorded for automatic retirements
+pub const SYSTEM_ACTOR: &str = "system";
+
+/// Retirement rules of a strategy; unset rules never fire
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetirementRules {
+    /// Losing trades in a row before the strategy is retired
+    pub max_consecutive_losses: Option<u32>,
+    /// Drawdown of realized PnL from its peak, in percent of capital plus peak
+    pub max_drawdown_pct: Option<f64>,
+    /// Capital the drawdown is measured against, in ETH
+    pub capital_eth: f64,
+    /// Share of submissions that must fill, between 0 and 1
+    pub min_fill_rate: Option<f64>,
+    /// Submissions the fill rate is measured over; checked once this many are seen
+    pub fill_rate_window: usize,
+}
+
+impl Default for RetirementRules {
+    fn default() -> Self {
+        Self {
+            max_consecutive_losses: None,
+            max_drawdown_pct: None,
+            capital_eth: 1.0,
+            min_fill_rate: None,
+            fill_rate_window: 20,
+        }
+    }
+}
+
+impl RetirementRules {
+    /// Check that the thresholds are in range
+    pub fn validate(&self) -> Result<()> {
+        if self.capital_eth <= 0.0 {
+            return Err(anyhow::anyhow!("Retirement capital must be positive"));
+        }
+        if self.max_drawdown_pct.is_some_and(|pct| pct <= 0.0 || pct > 100.0) {
+            return Err(anyhow::anyhow!("Retirement drawdown must be within (0, 100]"));
+        }
+        if self.min_fill_rate.is_some_and(|rate| !(0.0..=1.0).contains(&rate)) {
+            return Err(anyhow::anyhow!("Retirement fill rate must be within [0, 1]"));
+        }
+        if self.fill_rate_window == 0 {
+            return Err(anyhow::anyhow!("Retirement fill rate window must be positive"));
+        }
+        Ok(())
+    }
+}
+
+/// Recent track record of a strategy, checked against its rules
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StrategyHealth {
+    pub consecutive_losses: u32,
+    /// Realized PnL since the strategy was last (re-)enabled, in ETH
+    pub realized_pnl_eth: f64,
+    pub peak_pnl_eth: f64,
+    /// Whether each of the last submissions filled, oldest first
+    fills: VecDeque<bool>,
+}
+
+impl StrategyHealth {
+    /// Drawdown of realized PnL from its peak, in percent
+    pub fn drawdown_pct(&self, capital_eth: f64) -> f64 {
+        (self.peak_pnl_eth - self.realized_pnl_eth) / (capital_eth + self.peak_pnl_eth) * 100.0
+    }
+
+    /// Share of the recent submissions that filled, `None` before any
+    pub fn fill_rate(&self) -> Option<f64> {
+        if self.fills.is_empty() {
+            return None;
+        }
+        Some(self.fills.iter().filter(|filled| **filled).count() as f64 / self.fills.len() as f64)
+    }
+
+    /// Kill criteria currently breached
+    pub fn breaches(&self, rules: &RetirementRules) -> Vec<String> {
+        let mut reasons = Vec::new();
+        if let Some(max) = rules.max_consecutive_losses {
+            if self.consecutive_losses >= max {
+                reasons.push(format!("{} consecutive losses (limit {})", self.consecutive_losses, max));
+            }
+        }
+        if let Some(max) = rules.max_drawdown_pct {
+            let drawdown = self.drawdown_pct(rules.capital_eth);
+            if drawdown >= max {
+                reasons.push(format!("drawdown {:.2}% (limit {:.2}%)", drawdown, max));
+            }
+        }
+        if let (Some(min), Some(rate)) = (rules.min_fill_rate, self.fill_rate()) {
+            if self.fills.len() >= rules.fill_rate_window && rate < min {
+                reasons.push(format!(
+                    "fill rate {:.0}% over {} submissions (minimum {:.0}%)",
+                    rate * 100.0,
+                    self.fills.len(),
+                    min * 100.0
+                ));
+            }
+        }
+        reasons
+    }
+
+    fn record_submission(&mut self, filled: bool, window: usize) {
+        self.fills.push_back(filled);
+        while self.fills.len() > window {
+            self.fills.pop_front();
+        }
+    }
+
+    fn record_close(&mut self, pnl_eth: f64) {
+        if pnl_eth < 0.0 {
+            self.consecutive_losses += 1;
+        } else {
+            self.consecutive_losses = 0;
+        }
+        self.realized_pnl_eth += pnl_eth;
+        self.peak_pnl_eth = self.peak_pnl_eth.max(self.realized_pnl_eth);
+    }
+}
+
+/// Why and when a strategy was retired
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Retirement {
+    pub strategy_id: String,
+    pub reasons: Vec<String>,
+    pub retired_at_ms: i64,
+}
+
+/// Change to a strategy's enablement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AuditAction {
+    Retired,
+    ReEnabled,
+}
+
+/// Entry of the retirement audit trail
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub strategy_id: String,
+    pub action: AuditAction,
+    pub actor: String,
+    pub reason: String,
+    pub at_ms: i64,
+}
+
+/// Track record, retirements and audit trail of every strategy
+#[derive(Debug, Default)]
+pub struct RetirementBook {
+    rules: HashMap<String, RetirementRules>,
+    health: HashMap<String, StrategyHealth>,
+    retired: HashMap<String, Retirement>,
+    audit: Vec<AuditEntry>,
+}
+
+impl RetirementBook {
+    /// Create a book applying each strategy's configured rules
+    pub fn new<'a>(strategies: impl IntoIterator<Item = &'a StrategyConfig>) -> Self {
+        Self {
+            rules: strategies
+                .into_iter()
+                .map(|strategy| (strategy.id.clone(), strategy.retirement.clone()))
+                .collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Whether a strategy is retired
+    pub fn is_retired(&self, strategy_id: &str) -> bool {
+        self.retired.contains_key(strategy_id)
+    }
+
+    /// Retirement of a strategy, if retired
+    pub fn retirement(&self, strategy_id: &str) -> Option<&Retirement> {
+        self.retired.get(strategy_id)
+    }
+
+    /// Track record of a strategy
+    pub fn health(&self, strategy_id: &str) -> Option<&StrategyHealth> {
+        self.health.get(strategy_id)
+    }
+
+    /// Retirements and re-enables, oldest first
+    pub fn audit_log(&self) -> &[AuditEntry] {
+        &self.audit
+    }
+
+    /// Record whether a submission filled, returning the retirement it triggers
+    pub fn record_submission(&mut self, strategy_id: &str, filled: bool, now_ms: i64) -> Option<Retirement> {
+        let window = self.rules.get(strategy_id)?.fill_rate_window;
+        self.health
+            .entry(strategy_id.to_string())
+            .or_default()
+            .record_submission(filled, window);
+        self.check(strategy_id, now_ms)
+    }
+
+    /// Record the realized PnL of a closed trade, returning the retirement it triggers
+    pub fn record_close(&mut self, strategy_id: &str, pnl_eth: f64, now_ms: i64) -> Option<Retirement> {
+        if !self.rules.contains_key(strategy_id) {
+            return None;
+        }
+        self.health.entry(strategy_id.to_string()).or_default().record_close(pnl_eth);
+        self.check(strategy_id, now_ms)
+    }
+
+    /// Re-enable a retired strategy with a fresh track record
+    pub fn reenable(&mut self, strategy_id: &str, actor: &str, reason: &str, now_ms: i64) -> Result<()> {
+        if actor.trim().is_empty() || reason.trim().is_empty() {
+            return Err(anyhow::anyhow!("Re-enabling a strategy needs an actor and a reason"));
+        }
+        if self.retired.remove(strategy_id).is_none() {
+            return Err(anyhow::anyhow!("Strategy {} is not retired", strategy_id));
+        }
+        self.health.remove(strategy_id);
+        self.audit.push(AuditEntry {
+            strategy_id: strategy_id.to_string(),
+            action: AuditAction::ReEnabled,
+            actor: actor.to_string(),
+            reason: reason.to_string(),
+            at_ms: now_ms,
+        });
+        Ok(())
+    }
+
+    /// Retire the strategy if it breaches its rules and is not retired yet
+    fn check(&mut self, strategy_id: &str, now_ms: i64) -> Option<Retirement> {
+        if self.is_retired(strategy_id) {
+            return None;
+        }
+        let reasons = self.health.get(strategy_id)?.breaches(self.rules.get(strategy_id)?);
+        if reasons.is_empty() {
+            return None;
+        }
+
+        let retirement = Retirement {
+            strategy_id: strategy_id.to_string(),
+            reasons,
+            retired_at_ms: now_ms,
+        };
+        self.audit.push(AuditEntry {
+            strategy_id: strategy_id.to_string(),
+            action: AuditAction::Retired,
+            actor: SYSTEM_ACTOR.to_string(),
+            reason: retirement.reasons.join("; "),
+            at_ms: now_ms,
+        });
+        self.retired.insert(strategy_id.to_string(), retirement.clone());
+        Some(retirement)
+    }
+}
+
+/// Plan selling a position opened by `entry` back into its input token
+///
+/// Flattening exits at any price, so the plan accepts any output.
+pub fn flatten_plan(entry: &TradePlan, amount: u128) -> TradePlan {
+    TradePlan {
+        token_in: entry.token_out.clone(),
+        token_out: entry.token_in.clone(),
+        amount_in: amount,
+        min_out: 0,
+        idem_key: format!("flatten_{}", entry.idem_key),
+        ..entry.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RunnerConfig;
+    use crate::strategy::SnipeParams;
+    use sniper_core::types::{ChainRef, Signal};
+
+    fn book(rules: &str) -> RetirementBook {
+        let txt = format!(
+            r#"
+            [[feeds]]
+            id = "dex-demo"
+            kind = "demo"
+            source = "dex"
+            chain = {{ name = "ethereum", id = 1 }}
+            signal_kinds = ["pair_created"]
+
+            [[strategies]]
+            id = "snipe"
+            kind = "builtin"
+            feeds = ["dex-demo"]
+            retirement = {{ {} }}
+            "#,
+            rules
+        );
+        RetirementBook::new(&RunnerConfig::parse(&txt).unwrap().strategies)
+    }
+
+    #[test]
+    fn test_consecutive_losses_retire() {
+        let mut book = book("max_consecutive_losses = 3");
+        assert!(book.record_close("snipe", -0.1, 0).is_none());
+        assert!(book.record_close("snipe", -0.1, 1).is_none());
+        // A win resets the run
+        assert!(book.record_close("snipe", 0.05, 2).is_none());
+        assert!(book.record_close("snipe", -0.1, 3).is_none());
+        assert!(book.record_close("snipe", -0.1, 4).is_none());
+
+        let retirement = book.record_close("snipe", -0.1, 5).unwrap();
+        assert_eq!(retirement.reasons, vec!["3 consecutive losses (limit 3)".to_string()]);
+        assert!(book.is_retired("snipe"));
+        // Already retired strategies are not retired again
+        assert!(book.record_close("snipe", -0.1, 6).is_none());
+        assert!(book.record_close("unknown", -0.1, 6).is_none());
+    }
+
+    #[test]
+    fn test_drawdown_and_fill_rate_retire() {
+        let mut drawdown = book("max_drawdown_pct = 20.0, capital_eth = 1.0");
+        drawdown.record_close("snipe", 0.5, 0);
+        // 0.25 off a peak of 1.5 ETH (capital plus peak PnL) is under 20%
+        assert!(drawdown.record_close("snipe", -0.25, 1).is_none());
+        let retirement = drawdown.record_close("snipe", -0.1, 2).unwrap();
+        assert!(retirement.reasons[0].starts_with("drawdown 23.33%"));
+
+        let mut fills = book("min_fill_rate = 0.5, fill_rate_window = 4");
+        for (at, filled) in [true, false, false].into_iter().enumerate() {
+            // Not judged before the window is full
+            assert!(fills.record_submission("snipe", filled, at as i64).is_none());
+        }
+        assert!(fills.record_submission("snipe", true, 3).is_none());
+        let retirement = fills.record_submission("snipe", false, 4).unwrap();
+        assert_eq!(retirement.reasons, vec!["fill rate 25% over 4 submissions (minimum 50%)".to_string()]);
+    }
+
+    #[test]
+    fn test_reenable_needs_actor_and_is_audited() {
+        let mut book = book("max_consecutive_losses = 1");
+        assert!(book.reenable("snipe", "alice", "not retired", 0).is_err());
+        book.record_close("snipe", -0.1, 1).unwrap();
+
+        assert!(book.reenable("snipe", "", "fixed", 2).is_err());
+        assert!(book.reenable("snipe", "alice", " ", 2).is_err());
+        book.reenable("snipe", "alice", "router fixed", 2).unwrap();
+        assert!(!book.is_retired("snipe"));
+        assert_eq!(book.health("snipe").map(|health| health.consecutive_losses), None);
+
+        let audit = book.audit_log();
+        assert_eq!(audit.len(), 2);
+        assert_eq!(audit[0].action, AuditAction::Retired);
+        assert_eq!(audit[0].actor, SYSTEM_ACTOR);
+        assert_eq!(audit[1].action, AuditAction::ReEnabled);
+        assert_eq!(audit[1].actor, "alice");
+        assert_eq!(audit[1].reason, "router fixed");
+    }
+
+    #[test]
+    fn test_flatten_plan_reverses_entry() {
+        let signal = Signal {
+            source: "dex".to_string(),
+            kind: "pair_created".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            token0: Some("0xToken".to_string()),
+            token1: Some("0xWETH".to_string()),
+            extra: serde_json::json!({}),
+            seen_at_ms: 7,
+        };
+        let entry = SnipeParams::default().plan("snipe", &signal);
+
+        let exit = flatten_plan(&entry, 1234);
+        assert_eq!(exit.token_in, "0xToken");
+        assert_eq!(exit.token_out, "0xWETH");
+        assert_eq!(exit.amount_in, 1234);
+        assert_eq!(exit.min_out, 0);
+        assert_eq!(exit.idem_key, "flatten_snipe_0xToken_7");
+        assert_eq!(exit.router, entry.router);
+    }
+}
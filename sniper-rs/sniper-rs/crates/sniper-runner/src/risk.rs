@@ -0,0 +1,151 @@
+//! Risk gate between strategies and execution.
+//!
+//! Every plan passes the core risk evaluation and then the runner's own
+//! limits: trade size (global or per strategy), a rolling hourly trade budget,
+//! and a cooldown per token.
+
+use crate::config::{RiskLimits, StrategyConfig};
+use sniper_core::types::{Decision, TradePlan};
+use std::collections::{HashMap, VecDeque};
+
+/// Seconds in the rolling trade budget window
+const TRADE_WINDOW_SECS: u64 = 3600;
+
+/// Wei per ETH
+const WEI_PER_ETH: f64 = 1e18;
+
+/// Stateful risk gate applied before execution
+pub struct RiskGate {
+    limits: RiskLimits,
+    recent_trades: VecDeque<u64>,
+    last_trade: HashMap<String, u64>,
+}
+
+impl RiskGate {
+    /// Create a risk gate enforcing the given limits
+    pub fn new(limits: RiskLimits) -> Self {
+        Self {
+            limits,
+            recent_trades: VecDeque::new(),
+            last_trade: HashMap::new(),
+        }
+    }
+
+    /// Decide whether a strategy's plan may execute at `now` (Unix seconds)
+    ///
+    /// An allowed plan counts against the trade budget and starts its token's cooldown.
+    pub fn check(&mut self, strategy: &StrategyConfig, plan: &TradePlan, now: u64) -> Decision {
+        let decision = sniper_risk::evaluate_trade(plan);
+        if !decision.allow {
+            return decision;
+        }
+
+        let mut reasons = Vec::new();
+        let max_trade_eth = strategy.max_trade_eth.unwrap_or(self.limits.max_trade_eth);
+        let trade_eth = plan.amount_in as f64 / WEI_PER_ETH;
+        if trade_eth > max_trade_eth {
+            reasons.push(format!("trade size {} ETH exceeds limit {} ETH", trade_eth, max_trade_eth));
+        }
+
+        while self
+            .recent_trades
+            .front()
+            .map(|at| *at + TRADE_WINDOW_SECS <= now)
+            .unwrap_or(false)
+        {
+            self.recent_trades.pop_front();
+        }
+        if self.recent_trades.len() >= self.limits.max_trades_per_hour {
+            reasons.push(format!("hourly trade budget of {} used", self.limits.max_trades_per_hour));
+        }
+
+        if let Some(last) = self.last_trade.get(&plan.token_out) {
+            if now < last + self.limits.cooldown_secs {
+                reasons.push(format!("{} is cooling down", plan.token_out));
+            }
+        }
+
+        if !reasons.is_empty() {
+            return Decision { allow: false, reasons };
+        }
+
+        self.recent_trades.push_back(now);
+        self.last_trade.insert(plan.token_out.clone(), now);
+        Decision {
+            allow: true,
+            reasons: vec!["within runner risk limits".to_string()],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RunnerConfig;
+    use crate::strategy::SnipeParams;
+    use sniper_core::types::{ChainRef, Signal};
+
+    fn plan(token: &str, amount_in_eth: f64) -> TradePlan {
+        let params = SnipeParams {
+            amount_in_eth,
+            ..SnipeParams::default()
+        };
+        let signal = Signal {
+            source: "dex".to_string(),
+            kind: "pair_created".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            token0: Some(token.to_string()),
+            token1: None,
+            extra: serde_json::json!({}),
+            seen_at_ms: 0,
+        };
+        params.plan("snipe", &signal)
+    }
+
+    fn strategy(max_trade_eth: Option<f64>) -> StrategyConfig {
+        let txt = r#"
+            [[feeds]]
+            id = "dex-demo"
+            kind = "demo"
+            source = "dex"
+            chain = { name = "ethereum", id = 1 }
+            signal_kinds = ["pair_created"]
+
+            [[strategies]]
+            id = "snipe"
+            kind = "builtin"
+            feeds = ["dex-demo"]
+        "#;
+        let mut strategy = RunnerConfig::parse(txt).unwrap().strategies.remove(0);
+        strategy.max_trade_eth = max_trade_eth;
+        strategy
+    }
+
+    #[test]
+    fn test_trade_size_limit() {
+        let mut gate = RiskGate::new(RiskLimits::default());
+        assert!(!gate.check(&strategy(None), &plan("0xA", 2.0), 0).allow);
+        assert!(gate.check(&strategy(Some(5.0)), &plan("0xA", 2.0), 0).allow);
+    }
+
+    #[test]
+    fn test_cooldown_and_hourly_budget() {
+        let mut gate = RiskGate::new(RiskLimits {
+            max_trade_eth: 1.0,
+            max_trades_per_hour: 2,
+            cooldown_secs: 30,
+        });
+        let strategy = strategy(None);
+
+        assert!(gate.check(&strategy, &plan("0xA", 0.1), 0).allow);
+        assert!(!gate.check(&strategy, &plan("0xA", 0.1), 10).allow);
+        assert!(gate.check(&strategy, &plan("0xB", 0.1), 10).allow);
+
+        // Budget of two trades per hour is used until the first one ages out
+        assert!(!gate.check(&strategy, &plan("0xC", 0.1), 60).allow);
+        assert!(gate.check(&strategy, &plan("0xC", 0.1), 3600).allow);
+    }
+}
@@ -0,0 +1,237 @@
+//! Strategies run by the runner.
+//!
+//! Built-in strategies turn a signal into a snipe plan sized by their
+//! parameters. Plugin strategies implement [`sniper_plugin::Strategy`], are
+//! registered with the runner by ID, and exchange signals and plans as JSON.
+
+use crate::config::{StrategyConfig, StrategyKind};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sniper_core::types::{ExecMode, ExitRules, GasPolicy, Signal, TradePlan};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Wei per ETH
+const WEI_PER_ETH: f64 = 1e18;
+
+/// Parameters of the built-in snipe strategy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnipeParams {
+    pub router: String,
+    /// Input amount per trade, in ETH
+    pub amount_in_eth: f64,
+    /// Accepted slippage, in basis points
+    pub slippage_bps: u64,
+    pub max_fee_gwei: u64,
+    pub max_priority_gwei: u64,
+    pub take_profit_pct: Option<f64>,
+    pub stop_loss_pct: Option<f64>,
+    pub trailing_pct: Option<f64>,
+}
+
+impl Default for SnipeParams {
+    fn default() -> Self {
+        Self {
+            router: "0xRouterAddress".to_string(),
+            amount_in_eth: 0.1,
+            slippage_bps: 1000,
+            max_fee_gwei: 50,
+            max_priority_gwei: 2,
+            take_profit_pct: Some(20.0),
+            stop_loss_pct: Some(10.0),
+            trailing_pct: Some(5.0),
+        }
+    }
+}
+
+impl SnipeParams {
+    /// Build the plan buying the signal's token
+    pub fn plan(&self, strategy_id: &str, signal: &Signal) -> TradePlan {
+        let amount_in = (self.amount_in_eth * WEI_PER_ETH) as u128;
+        let min_out = amount_in * (10_000 - self.slippage_bps.min(10_000) as u128) / 10_000;
+        let token_out = signal.token0.clone().unwrap_or("0xToken".to_string());
+        TradePlan {
+            chain: signal.chain.clone(),
+            router: self.router.clone(),
+            token_in: signal.token1.clone().unwrap_or("0xWETH".to_string()),
+            idem_key: format!("{}_{}_{}", strategy_id, token_out, signal.seen_at_ms),
+            token_out,
+            amount_in,
+            min_out,
+            // Replaced by the venue picked at execution time
+            mode: ExecMode::Mempool,
+            gas: GasPolicy {
+                max_fee_gwei: self.max_fee_gwei,
+                max_priority_gwei: self.max_priority_gwei,
+            },
+            exits: ExitRules {
+                take_profit_pct: self.take_profit_pct,
+                stop_loss_pct: self.stop_loss_pct,
+                trailing_pct: self.trailing_pct,
+            },
+            quote: None,
+            correlation_id: None,
+        }
+    }
+}
+
+/// Logic behind an enabled strategy
+#[derive(Clone)]
+enum StrategyLogic {
+    Builtin(SnipeParams),
+    Plugin(Arc<dyn sniper_plugin::Strategy>),
+}
+
+/// Strategy ready to receive signals
+#[derive(Clone)]
+pub struct ActiveStrategy {
+    pub config: StrategyConfig,
+    logic: StrategyLogic,
+}
+
+impl ActiveStrategy {
+    /// Build an enabled strategy from its configuration and the registered plugins
+    pub fn new(config: StrategyConfig, plugins: &HashMap<String, Arc<dyn sniper_plugin::Strategy>>) -> Result<Self> {
+        let logic = match config.kind {
+            StrategyKind::Builtin => {
+                let params = if config.params.is_null() {
+                    SnipeParams::default()
+                } else {
+                    serde_json::from_value(config.params.clone())
+                        .map_err(|e| anyhow::anyhow!("Invalid parameters for strategy {}: {}", config.id, e))?
+                };
+                StrategyLogic::Builtin(params)
+            }
+            StrategyKind::Plugin => {
+                let plugin_id = config.plugin.as_deref().unwrap_or_default();
+                let plugin = plugins
+                    .get(plugin_id)
+                    .ok_or_else(|| anyhow::anyhow!("Strategy plugin {} not registered", plugin_id))?;
+                StrategyLogic::Plugin(plugin.clone())
+            }
+        };
+        Ok(Self { config, logic })
+    }
+
+    /// Generate a plan for a signal
+    pub async fn generate_plan(&self, signal: &Signal) -> Result<Option<TradePlan>> {
+        match &self.logic {
+            StrategyLogic::Builtin(params) => Ok(Some(params.plan(&self.config.id, signal))),
+            StrategyLogic::Plugin(plugin) => {
+                // Plugins see the strategy parameters alongside the signal
+                let input = serde_json::json!({
+                    "signal": signal,
+                    "params": self.config.params,
+                });
+                match plugin.generate_plan(&input).await? {
+                    Some(plan) => Ok(Some(serde_json::from_value(plan)?)),
+                    None => Ok(None),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RunnerConfig;
+    use async_trait::async_trait;
+    use sniper_core::types::ChainRef;
+    use sniper_plugin::PluginMetadata;
+
+    struct FixedPlanPlugin {
+        metadata: PluginMetadata,
+        plan: TradePlan,
+    }
+
+    #[async_trait]
+    impl sniper_plugin::Strategy for FixedPlanPlugin {
+        async fn generate_plan(&self, input: &serde_json::Value) -> Result<Option<serde_json::Value>> {
+            assert_eq!(input["params"]["threshold"], 3);
+            Ok(Some(serde_json::to_value(&self.plan)?))
+        }
+
+        fn metadata(&self) -> &PluginMetadata {
+            &self.metadata
+        }
+    }
+
+    fn signal() -> Signal {
+        Signal {
+            source: "dex".to_string(),
+            kind: "pair_created".to_string(),
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            token0: Some("0xTokenA".to_string()),
+            token1: Some("0xWETH".to_string()),
+            extra: serde_json::json!({}),
+            seen_at_ms: 42,
+        }
+    }
+
+    fn config(kind: &str, extra: &str) -> StrategyConfig {
+        let txt = format!(
+            r#"
+            [[feeds]]
+            id = "dex-demo"
+            kind = "demo"
+            source = "dex"
+            chain = {{ name = "ethereum", id = 1 }}
+            signal_kinds = ["pair_created"]
+
+            [[strategies]]
+            id = "snipe"
+            kind = "{}"
+            feeds = ["dex-demo"]
+            {}
+            "#,
+            kind, extra
+        );
+        RunnerConfig::parse(&txt).unwrap().strategies.remove(0)
+    }
+
+    #[tokio::test]
+    async fn test_builtin_strategy_plan() {
+        let strategy = ActiveStrategy::new(
+            config("builtin", "params = { amount_in_eth = 0.5, slippage_bps = 500 }"),
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        let plan = strategy.generate_plan(&signal()).await.unwrap().unwrap();
+        assert_eq!(plan.amount_in, 500000000000000000);
+        assert_eq!(plan.min_out, 475000000000000000);
+        assert_eq!(plan.token_out, "0xTokenA");
+        assert_eq!(plan.idem_key, "snipe_0xTokenA_42");
+        assert_eq!(plan.gas.max_fee_gwei, 50);
+    }
+
+    #[tokio::test]
+    async fn test_plugin_strategy() {
+        let config = config("plugin", "plugin = \"fixed\"\nparams = { threshold = 3 }");
+        assert!(ActiveStrategy::new(config.clone(), &HashMap::new()).is_err());
+
+        let plan = SnipeParams::default().plan("fixed", &signal());
+        let plugin: Arc<dyn sniper_plugin::Strategy> = Arc::new(FixedPlanPlugin {
+            metadata: PluginMetadata {
+                id: "fixed".to_string(),
+                name: "Fixed plan".to_string(),
+                version: "1.0.0".to_string(),
+                description: "Returns the same plan for every signal".to_string(),
+                author: "Test Author".to_string(),
+                capabilities: vec!["strategy".to_string()],
+                config_schema: None,
+            },
+            plan: plan.clone(),
+        });
+        let plugins = HashMap::from([("fixed".to_string(), plugin)]);
+
+        let strategy = ActiveStrategy::new(config, &plugins).unwrap();
+        let generated = strategy.generate_plan(&signal()).await.unwrap().unwrap();
+        assert_eq!(generated.idem_key, plan.idem_key);
+    }
+}
@@ -0,0 +1,404 @@
+//! Trading-session scheduling for the sniper bot.
+//!
+//! This module decides when strategies may run and when orders are accepted,
+//! based on configurable calendars: weekends off, UTC trading windows and
+//! blackout periods around major events. Tenants can override the default
+//! calendar, and session open/close transitions are published on the bus.
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use sniper_core::bus::InMemoryBus;
+use std::collections::HashMap;
+
+/// Tenant used when a request carries no tenant
+pub const DEFAULT_TENANT: &str = "default";
+
+/// Bus subject for session open events
+pub const SESSION_OPENED_SUBJECT: &str = "session.opened";
+
+/// Bus subject for session close events
+pub const SESSION_CLOSED_SUBJECT: &str = "session.closed";
+
+/// Daily UTC window during which trading is allowed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradingWindow {
+    /// Days the window applies to; empty means every day
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    pub start: NaiveTime,
+    /// End of the window; an end before the start wraps past midnight
+    pub end: NaiveTime,
+}
+
+impl TradingWindow {
+    /// Check if a point in time falls inside the window
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        if !self.days.is_empty() && !self.days.contains(&at.weekday()) {
+            return false;
+        }
+        let time = at.time();
+        if self.start <= self.end {
+            time >= self.start && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// Period during which trading is halted, e.g. around a major event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlackoutPeriod {
+    pub name: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl BlackoutPeriod {
+    /// Check if a point in time falls inside the blackout
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        at >= self.start && at < self.end
+    }
+}
+
+/// Calendar describing when a trading session is open
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionCalendar {
+    #[serde(default)]
+    pub weekends_off: bool,
+    /// Allowed windows; empty means open all day
+    #[serde(default)]
+    pub windows: Vec<TradingWindow>,
+    #[serde(default)]
+    pub blackouts: Vec<BlackoutPeriod>,
+}
+
+impl SessionCalendar {
+    /// Create a calendar that is always open
+    pub fn always_open() -> Self {
+        Self::default()
+    }
+
+    /// Check if the session is open at a point in time
+    pub fn is_open(&self, at: DateTime<Utc>) -> bool {
+        if self.weekends_off && matches!(at.weekday(), Weekday::Sat | Weekday::Sun) {
+            return false;
+        }
+        if self.blackouts.iter().any(|blackout| blackout.contains(at)) {
+            return false;
+        }
+        self.windows.is_empty() || self.windows.iter().any(|window| window.contains(at))
+    }
+}
+
+/// Session configuration loaded from `configs/sessions.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionConfig {
+    #[serde(default)]
+    pub default: SessionCalendar,
+    /// Per-tenant calendars replacing the default
+    #[serde(default)]
+    pub tenants: HashMap<String, SessionCalendar>,
+    /// Per-strategy calendars applied on top of the tenant calendar
+    #[serde(default)]
+    pub strategies: HashMap<String, SessionCalendar>,
+}
+
+impl SessionConfig {
+    /// Load the session configuration from a TOML file
+    pub fn load(path: &str) -> Result<Self> {
+        let txt = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&txt)?)
+    }
+
+    /// Load the session configuration from the default location
+    pub fn load_default() -> Result<Self> {
+        Self::load("configs/sessions.toml")
+    }
+}
+
+/// Kind of session transition
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum SessionEventKind {
+    Opened,
+    Closed,
+}
+
+/// Session transition published on the bus
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionEvent {
+    pub tenant_id: String,
+    pub kind: SessionEventKind,
+    pub at: DateTime<Utc>,
+}
+
+/// Scheduler gating strategies and order acceptance by trading session
+pub struct SessionScheduler {
+    default_calendar: SessionCalendar,
+    tenant_overrides: HashMap<String, SessionCalendar>,
+    strategy_calendars: HashMap<String, SessionCalendar>,
+    session_states: HashMap<String, bool>,
+    bus: Option<InMemoryBus>,
+}
+
+impl SessionScheduler {
+    /// Create a new session scheduler
+    pub fn new(default_calendar: SessionCalendar) -> Self {
+        let mut session_states = HashMap::new();
+        // Track the default tenant so its transitions are always reported
+        session_states.insert(DEFAULT_TENANT.to_string(), false);
+        Self {
+            default_calendar,
+            tenant_overrides: HashMap::new(),
+            strategy_calendars: HashMap::new(),
+            session_states,
+            bus: None,
+        }
+    }
+
+    /// Create a scheduler from a session configuration
+    pub fn from_config(config: SessionConfig) -> Self {
+        let mut scheduler = Self::new(config.default);
+        for (tenant_id, calendar) in config.tenants {
+            scheduler.set_tenant_override(&tenant_id, calendar);
+        }
+        for (strategy_id, calendar) in config.strategies {
+            scheduler.set_strategy_calendar(&strategy_id, calendar);
+        }
+        scheduler
+    }
+
+    /// Publish session events on a bus
+    pub fn with_bus(mut self, bus: InMemoryBus) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Override the calendar for a tenant
+    pub fn set_tenant_override(&mut self, tenant_id: &str, calendar: SessionCalendar) {
+        self.tenant_overrides.insert(tenant_id.to_string(), calendar);
+        self.track_tenant(tenant_id);
+    }
+
+    /// Remove a tenant override, falling back to the default calendar
+    pub fn remove_tenant_override(&mut self, tenant_id: &str) -> Option<SessionCalendar> {
+        self.tenant_overrides.remove(tenant_id)
+    }
+
+    /// Set a calendar restricting when a strategy may run
+    pub fn set_strategy_calendar(&mut self, strategy_id: &str, calendar: SessionCalendar) {
+        self.strategy_calendars.insert(strategy_id.to_string(), calendar);
+    }
+
+    /// Add a blackout to the default calendar
+    pub fn add_blackout(&mut self, blackout: BlackoutPeriod) {
+        self.default_calendar.blackouts.push(blackout);
+    }
+
+    /// Start reporting session transitions for a tenant
+    pub fn track_tenant(&mut self, tenant_id: &str) {
+        self.session_states.entry(tenant_id.to_string()).or_insert(false);
+    }
+
+    /// Get the calendar in effect for a tenant
+    pub fn calendar_for(&self, tenant_id: &str) -> &SessionCalendar {
+        self.tenant_overrides.get(tenant_id).unwrap_or(&self.default_calendar)
+    }
+
+    /// Check if a tenant's session is open
+    pub fn is_session_open(&self, tenant_id: &str, at: DateTime<Utc>) -> bool {
+        self.calendar_for(tenant_id).is_open(at)
+    }
+
+    /// Check if orders are accepted for a tenant
+    pub fn accepts_orders(&self, tenant_id: &str, at: DateTime<Utc>) -> bool {
+        self.is_session_open(tenant_id, at)
+    }
+
+    /// Check if a strategy may run for a tenant
+    pub fn strategy_enabled(&self, tenant_id: &str, strategy_id: &str, at: DateTime<Utc>) -> bool {
+        self.is_session_open(tenant_id, at)
+            && self.strategy_calendars
+                .get(strategy_id)
+                .map(|calendar| calendar.is_open(at))
+                .unwrap_or(true)
+    }
+
+    /// Evaluate all tracked tenants and publish open/close transitions
+    pub async fn tick(&mut self, at: DateTime<Utc>) -> Vec<SessionEvent> {
+        let mut events = Vec::new();
+        let tenants: Vec<String> = self.session_states.keys().cloned().collect();
+
+        for tenant_id in tenants {
+            let open = self.is_session_open(&tenant_id, at);
+            let was_open = self.session_states.insert(tenant_id.clone(), open).unwrap_or(false);
+            if open == was_open {
+                continue;
+            }
+
+            let kind = if open { SessionEventKind::Opened } else { SessionEventKind::Closed };
+            events.push(SessionEvent { tenant_id, kind, at });
+        }
+
+        if let Some(bus) = &self.bus {
+            for event in &events {
+                let subject = match event.kind {
+                    SessionEventKind::Opened => SESSION_OPENED_SUBJECT,
+                    SessionEventKind::Closed => SESSION_CLOSED_SUBJECT,
+                };
+                if let Err(e) = bus.publish(subject, event).await {
+                    tracing::warn!("Failed to publish session event for {}: {}", event.tenant_id, e);
+                }
+            }
+        }
+
+        events
+    }
+}
+
+impl Default for SessionScheduler {
+    fn default() -> Self {
+        Self::new(SessionCalendar::always_open())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    fn time(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn test_weekends_off() {
+        let calendar = SessionCalendar {
+            weekends_off: true,
+            ..Default::default()
+        };
+
+        // 2024-06-07 is a Friday
+        assert!(calendar.is_open(at(2024, 6, 7, 12, 0)));
+        assert!(!calendar.is_open(at(2024, 6, 8, 12, 0)));
+        assert!(!calendar.is_open(at(2024, 6, 9, 12, 0)));
+        assert!(calendar.is_open(at(2024, 6, 10, 0, 0)));
+    }
+
+    #[test]
+    fn test_trading_windows() {
+        let calendar = SessionCalendar {
+            weekends_off: false,
+            windows: vec![
+                TradingWindow { days: vec![Weekday::Mon], start: time(13, 30), end: time(20, 0) },
+                TradingWindow { days: vec![], start: time(22, 0), end: time(2, 0) },
+            ],
+            blackouts: vec![],
+        };
+
+        // 2024-06-10 is a Monday
+        assert!(calendar.is_open(at(2024, 6, 10, 14, 0)));
+        assert!(!calendar.is_open(at(2024, 6, 11, 14, 0)));
+        assert!(!calendar.is_open(at(2024, 6, 10, 20, 0)));
+
+        // Overnight window wraps past midnight
+        assert!(calendar.is_open(at(2024, 6, 11, 23, 0)));
+        assert!(calendar.is_open(at(2024, 6, 12, 1, 30)));
+        assert!(!calendar.is_open(at(2024, 6, 12, 2, 30)));
+    }
+
+    #[test]
+    fn test_blackouts_and_tenant_overrides() {
+        let mut scheduler = SessionScheduler::default();
+        scheduler.add_blackout(BlackoutPeriod {
+            name: "FOMC".to_string(),
+            start: at(2024, 6, 12, 17, 30),
+            end: at(2024, 6, 12, 19, 0),
+        });
+        scheduler.set_tenant_override("tenant-24x7", SessionCalendar::always_open());
+
+        assert!(scheduler.accepts_orders(DEFAULT_TENANT, at(2024, 6, 12, 17, 0)));
+        assert!(!scheduler.accepts_orders(DEFAULT_TENANT, at(2024, 6, 12, 18, 0)));
+        assert!(!scheduler.accepts_orders("tenant-unknown", at(2024, 6, 12, 18, 0)));
+        assert!(scheduler.accepts_orders("tenant-24x7", at(2024, 6, 12, 18, 0)));
+
+        scheduler.remove_tenant_override("tenant-24x7");
+        assert!(!scheduler.accepts_orders("tenant-24x7", at(2024, 6, 12, 18, 0)));
+    }
+
+    #[test]
+    fn test_strategy_calendars() {
+        let mut scheduler = SessionScheduler::default();
+        scheduler.set_strategy_calendar("launch_snipe", SessionCalendar {
+            weekends_off: true,
+            ..Default::default()
+        });
+
+        // Saturday
+        let saturday = at(2024, 6, 8, 12, 0);
+        assert!(scheduler.accepts_orders(DEFAULT_TENANT, saturday));
+        assert!(!scheduler.strategy_enabled(DEFAULT_TENANT, "launch_snipe", saturday));
+        assert!(scheduler.strategy_enabled(DEFAULT_TENANT, "price_threshold", saturday));
+    }
+
+    #[tokio::test]
+    async fn test_session_events_published() {
+        let bus = InMemoryBus::new(16);
+        let mut rx = bus.subscribe(SESSION_OPENED_SUBJECT);
+        let mut scheduler = SessionScheduler::new(SessionCalendar {
+            weekends_off: true,
+            ..Default::default()
+        })
+        .with_bus(bus);
+
+        let events = scheduler.tick(at(2024, 6, 7, 12, 0)).await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, SessionEventKind::Opened);
+
+        // No transition, no event
+        assert!(scheduler.tick(at(2024, 6, 7, 13, 0)).await.is_empty());
+
+        let events = scheduler.tick(at(2024, 6, 8, 0, 0)).await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, SessionEventKind::Closed);
+
+        let bytes = rx.recv().await.unwrap();
+        let published: SessionEvent = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(published.tenant_id, DEFAULT_TENANT);
+        assert_eq!(published.kind, SessionEventKind::Opened);
+    }
+
+    #[test]
+    fn test_config_parsing() {
+        let config: SessionConfig = toml::from_str(r#"
+            [default]
+            weekends_off = true
+
+            [[default.windows]]
+            start = "08:00:00"
+            end = "20:00:00"
+
+            [[default.blackouts]]
+            name = "CPI release"
+            start = "2024-06-12T12:00:00Z"
+            end = "2024-06-12T13:00:00Z"
+
+            [tenants.tenant-asia]
+            weekends_off = false
+
+            [strategies.launch_snipe]
+            windows = [{ days = ["Mon", "Tue"], start = "00:00:00", end = "12:00:00" }]
+        "#).unwrap();
+
+        let scheduler = SessionScheduler::from_config(config);
+        assert!(scheduler.accepts_orders(DEFAULT_TENANT, at(2024, 6, 11, 9, 0)));
+        assert!(!scheduler.accepts_orders(DEFAULT_TENANT, at(2024, 6, 12, 12, 30)));
+        assert!(scheduler.accepts_orders("tenant-asia", at(2024, 6, 8, 3, 0)));
+        assert!(scheduler.strategy_enabled(DEFAULT_TENANT, "launch_snipe", at(2024, 6, 11, 9, 0)));
+        assert!(!scheduler.strategy_enabled(DEFAULT_TENANT, "launch_snipe", at(2024, 6, 12, 9, 0)));
+    }
+}
@@ -0,0 +1,157 @@
+//! Signal listener configuration.
+//!
+//! A TOML file lists, per chain, the WebSocket RPC endpoint to subscribe to,
+//! the DEX factories whose new pairs are watched, and the routers whose
+//! pending liquidity adds are decoded from the mempool.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sniper_core::types::ChainRef;
+use std::collections::HashSet;
+
+/// Listener configuration loaded from `configs/signals.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SignalsConfig {
+    #[serde(default)]
+    pub chains: Vec<ChainWatchConfig>,
+}
+
+/// Protocol a factory deploys pools of
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum DexKind {
+    UniswapV2,
+    UniswapV3,
+}
+
+impl DexKind {
+    /// Name the DEX is reported under in signals
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DexKind::UniswapV2 => "uniswap_v2",
+            DexKind::UniswapV3 => "uniswap_v3",
+        }
+    }
+}
+
+/// Factory whose pair creations are watched
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FactoryConfig {
+    pub address: String,
+    pub dex: DexKind,
+}
+
+/// What to watch on one chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainWatchConfig {
+    /// Feed ID stamped on the signals, which strategies select feeds by
+    pub id: String,
+    pub chain: ChainRef,
+    /// WebSocket JSON-RPC endpoint
+    pub ws_url: String,
+    #[serde(default)]
+    pub factories: Vec<FactoryConfig>,
+    /// Routers whose pending `addLiquidity` calls are decoded; none disables
+    /// the mempool subscription
+    #[serde(default)]
+    pub routers: Vec<String>,
+    /// Wrapped native token paired by `addLiquidityETH`
+    #[serde(default)]
+    pub wrapped_native: Option<String>,
+    /// Most newly created pools tracked for their first liquidity
+    #[serde(default = "default_max_tracked_pools")]
+    pub max_tracked_pools: usize,
+}
+
+fn default_max_tracked_pools() -> usize {
+    10_000
+}
+
+impl SignalsConfig {
+    /// Parse and validate a configuration
+    pub fn parse(text: &str) -> Result<Self> {
+        let config: Self = toml::from_str(text)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Load and validate a configuration file
+    pub fn load(path: &str) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path, e))?;
+        Self::parse(&text)
+    }
+
+    /// Check feed IDs are unique and every chain has something to watch
+    pub fn validate(&self) -> Result<()> {
+        let mut ids = HashSet::new();
+        for chain in &self.chains {
+            if !ids.insert(chain.id.as_str()) {
+                return Err(anyhow::anyhow!("Duplicate signal feed {}", chain.id));
+            }
+            if !chain.ws_url.starts_with("ws://") && !chain.ws_url.starts_with("wss://") {
+                return Err(anyhow::anyhow!("Feed {} needs a ws:// or wss:// endpoint", chain.id));
+            }
+            if chain.factories.is_empty() && chain.routers.is_empty() {
+                return Err(anyhow::anyhow!("Feed {} watches no factories or routers", chain.id));
+            }
+            for address in chain.factories.iter().map(|f| &f.address).chain(&chain.routers) {
+                if !is_address(address) {
+                    return Err(anyhow::anyhow!("Feed {} has an invalid address {}", chain.id, address));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_address(address: &str) -> bool {
+    address
+        .strip_prefix("0x")
+        .is_some_and(|hex| hex.len() == 40 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = SignalsConfig::parse(
+            r#"
+            [[chains]]
+            id = "ethereum-dex"
+            chain = { name = "ethereum", id = 1 }
+            ws_url = "wss://ethereum.example/ws"
+            routers = ["0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D"]
+            factories = [
+                { address = "0x5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f", dex = "uniswap_v2" },
+                { address = "0x1F98431c8aD98523631AE4a59f267346ea31F984", dex = "uniswap_v3" },
+            ]
+            "#,
+        )
+        .unwrap();
+        let chain = &config.chains[0];
+        assert_eq!(chain.factories[1].dex, DexKind::UniswapV3);
+        assert_eq!(chain.max_tracked_pools, 10_000);
+
+        let invalid = [
+            r#"[[chains]]
+            id = "a"
+            chain = { name = "ethereum", id = 1 }
+            ws_url = "https://ethereum.example"
+            routers = ["0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D"]"#,
+            r#"[[chains]]
+            id = "a"
+            chain = { name = "ethereum", id = 1 }
+            ws_url = "wss://ethereum.example/ws""#,
+            r#"[[chains]]
+            id = "a"
+            chain = { name = "ethereum", id = 1 }
+            ws_url = "wss://ethereum.example/ws"
+            routers = ["0x7a25"]"#,
+        ];
+        for text in invalid {
+            assert!(SignalsConfig::parse(text).is_err());
+        }
+    }
+}
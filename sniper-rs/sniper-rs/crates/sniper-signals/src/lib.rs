@@ -1,14 +1,17 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
+//! On-chain signal sources.
+//!
+//! Listens to chains over WebSocket RPC for new Uniswap V2/V3 pools and the
+//! liquidity added to them, and publishes them on the bus as [`Signal`]s on
+//! `signals.dex.pair_created` and `signals.dex.liquidity_added`. Which
+//! factories and routers are watched is configured per chain.
+//!
+//! [`Signal`]: sniper_core::types::Signal
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+pub mod config;
+pub mod listener;
+pub mod onchain;
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub use config::{ChainWatchConfig, DexKind, FactoryConfig, SignalsConfig};
+pub use listener::{spawn_listeners, ChainListener};
+pub use onchain::liquidity_events::LiquidityAdded;
+pub use onchain::pair_created::PairCreated;
@@ -0,0 +1,413 @@
+//! WebSocket RPC listener turning chain activity into signals.
+//!
+//! Each configured chain gets one connection with up to three `eth_subscribe`
+//! subscriptions: pair creation logs of the watched factories, `Mint` logs,
+//! and full pending transactions when routers are watched. Mints are only
+//! reported for pools created since the listener started, the first time
+//! they receive liquidity. Dropped connections are re-established with
+//! exponential backoff.
+
+use crate::config::{ChainWatchConfig, SignalsConfig};
+use crate::onchain::liquidity_events::{LiquidityAdded, V2_MINT, V3_MINT};
+use crate::onchain::pair_created::{PairCreated, V2_PAIR_CREATED, V3_POOL_CREATED};
+use crate::onchain::{event_topic, Log, PendingTransaction};
+use anyhow::Result;
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use sniper_core::bus::InMemoryBus;
+use sniper_core::telemetry::{self, Context, KeyValue};
+use sniper_core::types::Signal;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Delay before the first reconnection attempt
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest delay between reconnection attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// What a subscription delivers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Subscription {
+    PairCreations,
+    Mints,
+    PendingTransactions,
+}
+
+impl Subscription {
+    /// JSON-RPC request ID the subscription is requested under
+    fn request_id(self) -> u64 {
+        match self {
+            Subscription::PairCreations => 1,
+            Subscription::Mints => 2,
+            Subscription::PendingTransactions => 3,
+        }
+    }
+
+    fn from_request_id(id: u64) -> Option<Self> {
+        [Self::PairCreations, Self::Mints, Self::PendingTransactions]
+            .into_iter()
+            .find(|subscription| subscription.request_id() == id)
+    }
+}
+
+#[derive(Deserialize)]
+struct RpcMessage {
+    #[serde(default)]
+    id: Option<u64>,
+    #[serde(default)]
+    result: Option<serde_json::Value>,
+    #[serde(default)]
+    error: Option<serde_json::Value>,
+    #[serde(default)]
+    params: Option<Notification>,
+}
+
+#[derive(Deserialize)]
+struct Notification {
+    subscription: String,
+    result: serde_json::Value,
+}
+
+/// Listener for one chain
+pub struct ChainListener {
+    config: ChainWatchConfig,
+    factories: HashSet<String>,
+    routers: HashSet<String>,
+    /// Subscription IDs assigned by the node
+    subscriptions: HashMap<String, Subscription>,
+    /// Tokens of pools awaiting their first liquidity
+    pools: HashMap<String, (String, String)>,
+    /// Tracked pools, oldest first
+    pool_order: VecDeque<String>,
+}
+
+impl ChainListener {
+    pub fn new(config: ChainWatchConfig) -> Self {
+        Self {
+            factories: config.factories.iter().map(|f| f.address.to_lowercase()).collect(),
+            routers: config.routers.iter().map(|r| r.to_lowercase()).collect(),
+            config,
+            subscriptions: HashMap::new(),
+            pools: HashMap::new(),
+            pool_order: VecDeque::new(),
+        }
+    }
+
+    /// Number of new pools awaiting their first liquidity
+    pub fn tracked_pools(&self) -> usize {
+        self.pools.len()
+    }
+
+    /// `eth_subscribe` requests to send once connected
+    pub fn subscribe_requests(&self) -> Vec<String> {
+        let mut requests = Vec::new();
+        let mut request = |subscription: Subscription, params: serde_json::Value| {
+            requests.push(
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": subscription.request_id(),
+                    "method": "eth_subscribe",
+                    "params": params,
+                })
+                .to_string(),
+            );
+        };
+        if !self.config.factories.is_empty() {
+            let factories: Vec<&String> = self.config.factories.iter().map(|f| &f.address).collect();
+            request(
+                Subscription::PairCreations,
+                serde_json::json!(["logs", {
+                    "address": factories,
+                    "topics": [[event_topic(V2_PAIR_CREATED), event_topic(V3_POOL_CREATED)]],
+                }]),
+            );
+            request(
+                Subscription::Mints,
+                serde_json::json!(["logs", { "topics": [[event_topic(V2_MINT), event_topic(V3_MINT)]] }]),
+            );
+        }
+        if !self.config.routers.is_empty() {
+            request(Subscription::PendingTransactions, serde_json::json!(["newPendingTransactions", true]));
+        }
+        requests
+    }
+
+    /// Handle a message from the node, returning the signals it produced
+    pub fn handle_message(&mut self, text: &str) -> Vec<Signal> {
+        let message: RpcMessage = match serde_json::from_str(text) {
+            Ok(message) => message,
+            Err(e) => {
+                tracing::warn!("Feed {} sent an undecodable message: {}", self.config.id, e);
+                return Vec::new();
+            }
+        };
+        if let Some(subscription) = message.id.and_then(Subscription::from_request_id) {
+            match (message.result.as_ref().and_then(|r| r.as_str()), message.error) {
+                (Some(id), _) => {
+                    self.subscriptions.insert(id.to_string(), subscription);
+                }
+                (None, error) => {
+                    tracing::warn!("Feed {} failed to subscribe to {:?}: {:?}", self.config.id, subscription, error);
+                }
+            }
+            return Vec::new();
+        }
+        let Some(notification) = message.params else {
+            return Vec::new();
+        };
+        let signal = match self.subscriptions.get(&notification.subscription) {
+            Some(Subscription::PairCreations) => serde_json::from_value::<Log>(notification.result)
+                .ok()
+                .and_then(|log| self.on_pair_created(&log)),
+            Some(Subscription::Mints) => serde_json::from_value::<Log>(notification.result)
+                .ok()
+                .and_then(|log| self.on_mint(&log)),
+            // Nodes that ignore the full transaction flag send only hashes
+            Some(Subscription::PendingTransactions) => {
+                serde_json::from_value::<PendingTransaction>(notification.result)
+                    .ok()
+                    .and_then(|tx| self.on_pending_transaction(&tx))
+            }
+            None => None,
+        };
+        signal.into_iter().collect()
+    }
+
+    fn on_pair_created(&mut self, log: &Log) -> Option<Signal> {
+        if log.removed || !self.factories.contains(&log.address.to_lowercase()) {
+            return None;
+        }
+        let created = PairCreated::decode(log)?;
+        if !self.pools.contains_key(&created.pool) {
+            if self.pool_order.len() >= self.config.max_tracked_pools {
+                if let Some(oldest) = self.pool_order.pop_front() {
+                    self.pools.remove(&oldest);
+                }
+            }
+            self.pool_order.push_back(created.pool.clone());
+        }
+        self.pools
+            .insert(created.pool.clone(), (created.token0.clone(), created.token1.clone()));
+        Some(created.to_signal(&self.config.chain, &self.config.id, now_ms()))
+    }
+
+    fn on_mint(&mut self, log: &Log) -> Option<Signal> {
+        if log.removed {
+            return None;
+        }
+        let added = LiquidityAdded::from_log(log)?;
+        let (token0, token1) = self.pools.remove(&added.target)?;
+        self.pool_order.retain(|tracked| *tracked != added.target);
+        Some(added.to_signal(&self.config.chain, &self.config.id, Some((&token0, &token1)), now_ms()))
+    }
+
+    fn on_pending_transaction(&self, tx: &PendingTransaction) -> Option<Signal> {
+        let to = tx.to.as_deref()?.to_lowercase();
+        if !self.routers.contains(&to) {
+            return None;
+        }
+        let added = LiquidityAdded::from_transaction(tx, self.config.wrapped_native.as_deref())?;
+        Some(added.to_signal(&self.config.chain, &self.config.id, None, now_ms()))
+    }
+
+    /// Listen until the task is dropped, reconnecting whenever the
+    /// connection fails
+    pub async fn run(mut self, bus: InMemoryBus) {
+        let mut backoff = MIN_BACKOFF;
+        loop {
+            let connected_at = Instant::now();
+            match self.listen(&bus).await {
+                Ok(()) => tracing::warn!("Feed {} connection closed", self.config.id),
+                Err(e) => tracing::warn!("Feed {} connection failed: {}", self.config.id, e),
+            }
+            if connected_at.elapsed() > MAX_BACKOFF {
+                backoff = MIN_BACKOFF;
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    async fn listen(&mut self, bus: &InMemoryBus) -> Result<()> {
+        let (mut socket, _) = tokio_tungstenite::connect_async(self.config.ws_url.as_str()).await?;
+        tracing::info!("Feed {} connected to {}", self.config.id, self.config.ws_url);
+        self.subscriptions.clear();
+        for request in self.subscribe_requests() {
+            socket.send(Message::Text(request)).await?;
+        }
+        while let Some(message) = socket.next().await {
+            match message? {
+                Message::Text(text) => {
+                    for signal in self.handle_message(&text) {
+                        publish(bus, &signal);
+                    }
+                }
+                Message::Close(_) => break,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Publish a signal on `signals.dex.<kind>`, starting its trace
+fn publish(bus: &InMemoryBus, signal: &Signal) {
+    let feed = signal.extra["feed"].as_str().unwrap_or_default().to_string();
+    let cx = telemetry::start_span(
+        "signal.detect",
+        &Context::new(),
+        vec![
+            KeyValue::new("signal.feed", feed),
+            KeyValue::new("signal.kind", signal.kind.clone()),
+            KeyValue::new("chain.id", signal.chain.id as i64),
+        ],
+    );
+    let subject = format!("signals.{}.{}", signal.source, signal.kind);
+    if let Err(e) = bus.publish_in(&subject, signal, &cx) {
+        telemetry::record_error(&cx, &e);
+        tracing::warn!("Failed to publish {}: {}", subject, e);
+    }
+}
+
+/// Start a listener per configured chain
+pub fn spawn_listeners(config: &SignalsConfig, bus: &InMemoryBus) -> Vec<JoinHandle<()>> {
+    config
+        .chains
+        .iter()
+        .map(|chain| tokio::spawn(ChainListener::new(chain.clone()).run(bus.clone())))
+        .collect()
+}
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::onchain::pair_created::tests::{topic_word, v2_pair_created_log, POOL, TOKEN0, TOKEN1};
+    use tokio::net::TcpListener;
+
+    fn log_json(log: &Log) -> serde_json::Value {
+        serde_json::json!({
+            "address": log.address,
+            "topics": log.topics,
+            "data": log.data,
+            "blockNumber": log.block_number,
+            "transactionHash": log.transaction_hash,
+            "removed": log.removed,
+        })
+    }
+
+    fn mint_log(pool: &str) -> serde_json::Value {
+        serde_json::json!({
+            "address": pool,
+            "topics": [event_topic(V2_MINT), topic_word(TOKEN0)],
+            "data": format!("0x{:064x}{:064x}", 1_000u64, 5_000u64),
+        })
+    }
+
+    fn notification(subscription: &str, result: serde_json::Value) -> String {
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "eth_subscription",
+            "params": { "subscription": subscription, "result": result },
+        })
+        .to_string()
+    }
+
+    fn config(ws_url: &str, max_tracked_pools: usize) -> ChainWatchConfig {
+        SignalsConfig::parse(&format!(
+            r#"
+            [[chains]]
+            id = "ethereum-dex"
+            chain = {{ name = "ethereum", id = 1 }}
+            ws_url = "{}"
+            max_tracked_pools = {}
+            factories = [{{ address = "0x5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f", dex = "uniswap_v2" }}]
+            "#,
+            ws_url, max_tracked_pools
+        ))
+        .unwrap()
+        .chains
+        .remove(0)
+    }
+
+    #[test]
+    fn test_first_liquidity_of_new_pools() {
+        let mut listener = ChainListener::new(config("ws://localhost", 1));
+        assert_eq!(listener.subscribe_requests().len(), 2);
+        listener.handle_message(r#"{"jsonrpc":"2.0","id":1,"result":"0xa"}"#);
+        listener.handle_message(r#"{"jsonrpc":"2.0","id":2,"result":"0xb"}"#);
+
+        // Mints into pools created before the listener started are ignored
+        assert!(listener.handle_message(&notification("0xb", mint_log(POOL))).is_empty());
+
+        let signals = listener.handle_message(&notification("0xa", log_json(&v2_pair_created_log())));
+        assert_eq!(signals[0].kind, "pair_created");
+        assert_eq!(listener.tracked_pools(), 1);
+
+        let signals = listener.handle_message(&notification("0xb", mint_log(POOL)));
+        assert_eq!(signals[0].kind, "liquidity_added");
+        assert_eq!(signals[0].token0.as_deref(), Some(TOKEN0));
+        assert_eq!(signals[0].token1.as_deref(), Some(TOKEN1));
+        assert_eq!(listener.tracked_pools(), 0);
+        assert!(listener.handle_message(&notification("0xb", mint_log(POOL))).is_empty());
+
+        // The oldest pool is evicted past the limit
+        let mut other = v2_pair_created_log();
+        other.data = format!("{}{:064x}", topic_word("0x3333333333333333333333333333333333333333"), 43);
+        listener.handle_message(&notification("0xa", log_json(&v2_pair_created_log())));
+        listener.handle_message(&notification("0xa", log_json(&other)));
+        assert_eq!(listener.tracked_pools(), 1);
+        assert!(listener.handle_message(&notification("0xb", mint_log(POOL))).is_empty());
+
+        // Unknown subscriptions and factories are ignored
+        assert!(listener
+            .handle_message(&notification("0xc", log_json(&v2_pair_created_log())))
+            .is_empty());
+        let mut foreign = v2_pair_created_log();
+        foreign.address = POOL.to_string();
+        assert!(listener.handle_message(&notification("0xa", log_json(&foreign))).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_listener_publishes_signals() {
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = server.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            for (id, subscription) in [(1, "0xa"), (2, "0xb")] {
+                let request = socket.next().await.unwrap().unwrap().into_text().unwrap();
+                assert!(request.contains("eth_subscribe"));
+                let reply = serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": subscription });
+                socket.send(Message::Text(reply.to_string())).await.unwrap();
+            }
+            let created = notification("0xa", log_json(&v2_pair_created_log()));
+            socket.send(Message::Text(created)).await.unwrap();
+            socket.send(Message::Text(notification("0xb", mint_log(POOL)))).await.unwrap();
+            // Keep the connection open until the test ends
+            while socket.next().await.is_some() {}
+        });
+
+        let bus = InMemoryBus::new(16);
+        let mut rx = bus.subscribe("signals.dex.>");
+        let handle = tokio::spawn(ChainListener::new(config(&format!("ws://{}", addr), 10)).run(bus.clone()));
+        let mut kinds = Vec::new();
+        for _ in 0..2 {
+            let bytes = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await.unwrap().unwrap();
+            let signal: Signal = serde_json::from_slice(&bytes).unwrap();
+            assert_eq!(signal.extra["feed"], "ethereum-dex");
+            kinds.push(signal.kind);
+        }
+        assert_eq!(kinds, ["pair_created", "liquidity_added"]);
+        handle.abort();
+    }
+}
@@ -1 +1,227 @@
-pub fn placeholder() { /* TODO: implement */ }
+//! Liquidity additions, confirmed and pending.
+//!
+//! Confirmed additions are the `Mint` events of pools; pending ones are the
+//! `addLiquidity` and `addLiquidityETH` calls of V2 routers seen in the
+//! mempool, which land a block before the first `Mint` can be observed.
+
+use super::{decode_hex, event_topic, function_selector, parse_quantity, word_address, word_uint, words};
+use super::{Log, PendingTransaction};
+use crate::config::DexKind;
+use serde::{Deserialize, Serialize};
+use sniper_core::types::{ChainRef, Signal};
+
+/// Signature of the Uniswap V2 pair mint event
+pub const V2_MINT: &str = "Mint(address,uint256,uint256)";
+
+/// Signature of the Uniswap V3 pool mint event
+pub const V3_MINT: &str = "Mint(address,address,int24,int24,uint128,uint256,uint256)";
+
+/// Signature of the V2 router's token/token liquidity add
+pub const ADD_LIQUIDITY: &str = "addLiquidity(address,address,uint256,uint256,uint256,uint256,address,uint256)";
+
+/// Signature of the V2 router's token/native liquidity add
+pub const ADD_LIQUIDITY_ETH: &str = "addLiquidityETH(address,uint256,uint256,uint256,address,uint256)";
+
+/// Kind of the signals emitted for liquidity additions
+pub const LIQUIDITY_ADDED_KIND: &str = "liquidity_added";
+
+/// Liquidity added to a pool, or about to be
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LiquidityAdded {
+    pub dex: DexKind,
+    /// Pool minted into, or router called for pending additions
+    pub target: String,
+    /// Tokens, known only for pending additions; minted pools are resolved
+    /// from the pairs they were created with
+    pub token0: Option<String>,
+    pub token1: Option<String>,
+    pub amount0: u128,
+    pub amount1: u128,
+    pub block: Option<u64>,
+    pub tx_hash: Option<String>,
+    /// Whether the addition is still in the mempool
+    pub pending: bool,
+}
+
+impl LiquidityAdded {
+    /// Decode a pool `Mint` log
+    pub fn from_log(log: &Log) -> Option<Self> {
+        let words = log.words()?;
+        let (dex, amount0, amount1) = if log.is_event(&event_topic(V2_MINT)) {
+            (DexKind::UniswapV2, words.first()?, words.get(1)?)
+        } else if log.is_event(&event_topic(V3_MINT)) {
+            // Data holds sender, liquidity, amount0 and amount1
+            (DexKind::UniswapV3, words.get(2)?, words.get(3)?)
+        } else {
+            return None;
+        };
+        Some(Self {
+            dex,
+            target: log.address.to_lowercase(),
+            token0: None,
+            token1: None,
+            amount0: word_uint(amount0),
+            amount1: word_uint(amount1),
+            block: log.block(),
+            tx_hash: log.transaction_hash.clone(),
+            pending: false,
+        })
+    }
+
+    /// Decode a pending router call, pairing `addLiquidityETH` with the
+    /// wrapped native token
+    pub fn from_transaction(tx: &PendingTransaction, wrapped_native: Option<&str>) -> Option<Self> {
+        let input = decode_hex(&tx.input)?;
+        let (selector, args) = (input.get(..4)?, words(input.get(4..)?));
+        let (token0, token1, amount0, amount1) = if selector == function_selector(ADD_LIQUIDITY) {
+            (
+                word_address(args.first()?)?,
+                word_address(args.get(1)?)?,
+                word_uint(args.get(2)?),
+                word_uint(args.get(3)?),
+            )
+        } else if selector == function_selector(ADD_LIQUIDITY_ETH) {
+            let value = tx.value.as_deref().and_then(parse_quantity).unwrap_or_default();
+            (
+                word_address(args.first()?)?,
+                wrapped_native?.to_lowercase(),
+                word_uint(args.get(1)?),
+                value,
+            )
+        } else {
+            return None;
+        };
+        Some(Self {
+            dex: DexKind::UniswapV2,
+            target: tx.to.as_deref()?.to_lowercase(),
+            token0: Some(token0),
+            token1: Some(token1),
+            amount0,
+            amount1,
+            block: None,
+            tx_hash: Some(tx.hash.clone()),
+            pending: true,
+        })
+    }
+
+    /// Signal announcing the addition, with the tokens of the pool if known
+    ///
+    /// Amounts are decimal strings as they may exceed JSON's safe integers.
+    pub fn to_signal(&self, chain: &ChainRef, feed: &str, tokens: Option<(&str, &str)>, seen_at_ms: i64) -> Signal {
+        let (token0, token1) = match tokens {
+            Some((token0, token1)) => (Some(token0.to_string()), Some(token1.to_string())),
+            None => (self.token0.clone(), self.token1.clone()),
+        };
+        Signal {
+            source: "dex".to_string(),
+            kind: LIQUIDITY_ADDED_KIND.to_string(),
+            chain: chain.clone(),
+            token0,
+            token1,
+            extra: serde_json::json!({
+                "feed": feed,
+                "dex": self.dex.as_str(),
+                "target": self.target,
+                "amount0": self.amount0.to_string(),
+                "amount1": self.amount1.to_string(),
+                "pending": self.pending,
+                "block": self.block,
+                "tx_hash": self.tx_hash,
+            }),
+            seen_at_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::onchain::pair_created::tests::{topic_word, POOL, TOKEN0, TOKEN1};
+
+    fn v2_mint_log() -> Log {
+        Log {
+            address: POOL.to_string(),
+            topics: vec![event_topic(V2_MINT), topic_word(TOKEN0)],
+            data: format!("0x{:064x}{:064x}", 1_000u64, 5_000u64),
+            block_number: Some("0x11".to_string()),
+            transaction_hash: None,
+            removed: false,
+        }
+    }
+
+    #[test]
+    fn test_decode_mints() {
+        let added = LiquidityAdded::from_log(&v2_mint_log()).unwrap();
+        assert_eq!(added.dex, DexKind::UniswapV2);
+        assert_eq!(added.target, POOL);
+        assert_eq!((added.amount0, added.amount1), (1_000, 5_000));
+        assert!(!added.pending);
+
+        let v3 = Log {
+            topics: vec![event_topic(V3_MINT), topic_word(TOKEN0)],
+            data: format!("{}{:064x}{:064x}{:064x}", topic_word(TOKEN0), 9u64, 7u64, 8u64),
+            ..v2_mint_log()
+        };
+        let added = LiquidityAdded::from_log(&v3).unwrap();
+        assert_eq!(added.dex, DexKind::UniswapV3);
+        assert_eq!((added.amount0, added.amount1), (7, 8));
+
+        let chain = ChainRef {
+            name: "ethereum".to_string(),
+            id: 1,
+        };
+        let signal = added.to_signal(&chain, "ethereum-dex", Some((TOKEN0, TOKEN1)), 0);
+        assert_eq!(signal.kind, LIQUIDITY_ADDED_KIND);
+        assert_eq!(signal.token1.as_deref(), Some(TOKEN1));
+        assert_eq!(signal.extra["amount0"], "7");
+    }
+
+    #[test]
+    fn test_decode_pending_router_calls() {
+        let router = "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D";
+        let tx = PendingTransaction {
+            hash: "0xfeed".to_string(),
+            to: Some(router.to_string()),
+            value: Some("0xde0b6b3a7640000".to_string()),
+            input: format!(
+                "0x{}{}{:064x}{:064x}{:064x}{}{:064x}",
+                hex::encode(function_selector(ADD_LIQUIDITY_ETH)),
+                topic_word(TOKEN0).trim_start_matches("0x"),
+                1_000_000u64,
+                0,
+                0,
+                topic_word(TOKEN0).trim_start_matches("0x"),
+                u64::MAX
+            ),
+        };
+        let added = LiquidityAdded::from_transaction(&tx, Some(TOKEN1)).unwrap();
+        assert!(added.pending);
+        assert_eq!(added.target, router.to_lowercase());
+        assert_eq!(added.token0.as_deref(), Some(TOKEN0));
+        assert_eq!(added.token1.as_deref(), Some(TOKEN1));
+        assert_eq!((added.amount0, added.amount1), (1_000_000, 1_000_000_000_000_000_000));
+
+        // Without a wrapped native token the pair is unknown
+        assert!(LiquidityAdded::from_transaction(&tx, None).is_none());
+
+        let tx = PendingTransaction {
+            input: format!(
+                "0x{}{}{}{:064x}{:064x}",
+                hex::encode(function_selector(ADD_LIQUIDITY)),
+                topic_word(TOKEN0).trim_start_matches("0x"),
+                topic_word(TOKEN1).trim_start_matches("0x"),
+                10u64,
+                20u64
+            ),
+            ..tx
+        };
+        let added = LiquidityAdded::from_transaction(&tx, None).unwrap();
+        assert_eq!((added.amount0, added.amount1), (10, 20));
+
+        let swap = PendingTransaction {
+            input: "0x38ed1739".to_string(),
+            ..tx
+        };
+        assert!(LiquidityAdded::from_transaction(&swap, None).is_none());
+    }
+}
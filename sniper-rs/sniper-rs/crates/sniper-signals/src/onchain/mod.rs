@@ -0,0 +1,134 @@
+//! Decoding of on-chain events and pending transactions.
+//!
+//! Logs and transactions arrive as the JSON objects of `eth_subscribe`
+//! notifications. Event topics are the Keccak-256 hashes of the event
+//! signatures; ABI words are 32 bytes, with addresses in the low 20.
+
+pub mod liquidity_events;
+pub mod pair_created;
+
+use serde::Deserialize;
+use sha3::{Digest, Keccak256};
+
+/// Log delivered by a `logs` subscription
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Log {
+    pub address: String,
+    pub topics: Vec<String>,
+    pub data: String,
+    #[serde(default)]
+    pub block_number: Option<String>,
+    #[serde(default)]
+    pub transaction_hash: Option<String>,
+    /// Set when a reorg dropped the log
+    #[serde(default)]
+    pub removed: bool,
+}
+
+impl Log {
+    /// Block the log was emitted in
+    pub fn block(&self) -> Option<u64> {
+        let number = self.block_number.as_deref()?.strip_prefix("0x")?;
+        u64::from_str_radix(number, 16).ok()
+    }
+
+    /// Whether the first topic is the given event's
+    pub fn is_event(&self, topic: &str) -> bool {
+        self.topics.first().is_some_and(|first| first.eq_ignore_ascii_case(topic))
+    }
+
+    /// Address held by a topic
+    pub(crate) fn topic_address(&self, index: usize) -> Option<String> {
+        word_address(&decode_hex(self.topics.get(index)?)?)
+    }
+
+    /// ABI words of the data
+    pub(crate) fn words(&self) -> Option<Vec<[u8; 32]>> {
+        Some(words(&decode_hex(&self.data)?))
+    }
+}
+
+/// Transaction delivered by a full `newPendingTransactions` subscription
+#[derive(Debug, Clone, Deserialize)]
+pub struct PendingTransaction {
+    pub hash: String,
+    #[serde(default)]
+    pub to: Option<String>,
+    #[serde(default)]
+    pub value: Option<String>,
+    #[serde(default)]
+    pub input: String,
+}
+
+/// Topic of an event, the hash of its signature
+pub fn event_topic(signature: &str) -> String {
+    format!("0x{}", hex::encode(Keccak256::digest(signature.as_bytes())))
+}
+
+/// Selector of a function, the first four bytes of the hash of its signature
+pub fn function_selector(signature: &str) -> [u8; 4] {
+    let hash = Keccak256::digest(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+pub(crate) fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    hex::decode(value.strip_prefix("0x").unwrap_or(value)).ok()
+}
+
+/// Split ABI-encoded data into words, ignoring a trailing partial word
+pub(crate) fn words(data: &[u8]) -> Vec<[u8; 32]> {
+    data.chunks_exact(32).map(|chunk| chunk.try_into().unwrap()).collect()
+}
+
+/// Checksum-free, lowercase address held in the low 20 bytes of a word
+pub(crate) fn word_address(word: &[u8]) -> Option<String> {
+    (word.len() == 32).then(|| format!("0x{}", hex::encode(&word[12..])))
+}
+
+/// Unsigned integer held by a word, saturating above `u128::MAX`
+pub(crate) fn word_uint(word: &[u8; 32]) -> u128 {
+    if word[..16].iter().any(|byte| *byte != 0) {
+        return u128::MAX;
+    }
+    u128::from_be_bytes(word[16..].try_into().unwrap())
+}
+
+/// Parse a hex quantity such as a transaction value
+pub(crate) fn parse_quantity(quantity: &str) -> Option<u128> {
+    u128::from_str_radix(quantity.strip_prefix("0x")?, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_hashes() {
+        assert_eq!(
+            event_topic("PairCreated(address,address,address,uint256)"),
+            "0x0d3648bd0f6ba80134a33ba9275ac585d9d315f0ad8355cddefde31afa28d0e9"
+        );
+        assert_eq!(
+            event_topic("Mint(address,uint256,uint256)"),
+            "0x4c209b5fc8ad50758f13e2e1088ba56a560dff690a1c6fef26394f4c03821c4f"
+        );
+        assert_eq!(
+            function_selector("addLiquidityETH(address,uint256,uint256,uint256,address,uint256)"),
+            [0xf3, 0x05, 0xd7, 0x19]
+        );
+    }
+
+    #[test]
+    fn test_words() {
+        let mut word = [0u8; 32];
+        word[12..].copy_from_slice(&[0xab; 20]);
+        assert_eq!(word_address(&word).unwrap(), format!("0x{}", "ab".repeat(20)));
+        word = [0u8; 32];
+        word[31] = 7;
+        assert_eq!(word_uint(&word), 7);
+        word[0] = 1;
+        assert_eq!(word_uint(&word), u128::MAX);
+        assert_eq!(parse_quantity("0x10"), Some(16));
+    }
+}
@@ -1 +1,163 @@
-pub fn placeholder() { /* TODO: implement */ }
+//! Pair creation events of Uniswap V2 and V3 factories.
+//!
+//! V2 factories emit `PairCreated(token0, token1, pair, index)` and V3
+//! factories `PoolCreated(token0, token1, fee, tickSpacing, pool)`, with the
+//! tokens (and the V3 fee tier) indexed.
+
+use super::{event_topic, word_address, Log};
+use crate::config::DexKind;
+use serde::{Deserialize, Serialize};
+use sniper_core::types::{ChainRef, Signal};
+
+/// Signature of the Uniswap V2 pair creation event
+pub const V2_PAIR_CREATED: &str = "PairCreated(address,address,address,uint256)";
+
+/// Signature of the Uniswap V3 pool creation event
+pub const V3_POOL_CREATED: &str = "PoolCreated(address,address,uint24,int24,address)";
+
+/// Kind of the signals emitted for new pairs
+pub const PAIR_CREATED_KIND: &str = "pair_created";
+
+/// Pool deployed by a factory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PairCreated {
+    pub dex: DexKind,
+    pub factory: String,
+    pub token0: String,
+    pub token1: String,
+    pub pool: String,
+    /// Fee tier of V3 pools, in hundredths of a basis point
+    pub fee: Option<u32>,
+    pub block: Option<u64>,
+    pub tx_hash: Option<String>,
+}
+
+impl PairCreated {
+    /// Decode a factory log, `None` if it is not a pair creation
+    pub fn decode(log: &Log) -> Option<Self> {
+        let dex = if log.is_event(&event_topic(V2_PAIR_CREATED)) {
+            DexKind::UniswapV2
+        } else if log.is_event(&event_topic(V3_POOL_CREATED)) {
+            DexKind::UniswapV3
+        } else {
+            return None;
+        };
+        let words = log.words()?;
+        let (pool, fee) = match dex {
+            DexKind::UniswapV2 => (word_address(words.first()?)?, None),
+            DexKind::UniswapV3 => {
+                let fee = super::decode_hex(log.topics.get(3)?)?;
+                let fee = u32::from_be_bytes(fee.get(28..32)?.try_into().ok()?);
+                (word_address(words.get(1)?)?, Some(fee))
+            }
+        };
+        Some(Self {
+            dex,
+            factory: log.address.to_lowercase(),
+            token0: log.topic_address(1)?,
+            token1: log.topic_address(2)?,
+            pool,
+            fee,
+            block: log.block(),
+            tx_hash: log.transaction_hash.clone(),
+        })
+    }
+
+    /// Signal announcing the pool
+    pub fn to_signal(&self, chain: &ChainRef, feed: &str, seen_at_ms: i64) -> Signal {
+        Signal {
+            source: "dex".to_string(),
+            kind: PAIR_CREATED_KIND.to_string(),
+            chain: chain.clone(),
+            token0: Some(self.token0.clone()),
+            token1: Some(self.token1.clone()),
+            extra: serde_json::json!({
+                "feed": feed,
+                "dex": self.dex.as_str(),
+                "factory": self.factory,
+                "pool": self.pool,
+                "fee": self.fee,
+                "block": self.block,
+                "tx_hash": self.tx_hash,
+            }),
+            seen_at_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const TOKEN0: &str = "0x1111111111111111111111111111111111111111";
+    pub(crate) const TOKEN1: &str = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+    pub(crate) const POOL: &str = "0x2222222222222222222222222222222222222222";
+
+    pub(crate) fn topic_word(address: &str) -> String {
+        format!("0x{:0>64}", address.trim_start_matches("0x"))
+    }
+
+    pub(crate) fn v2_pair_created_log() -> Log {
+        Log {
+            address: "0x5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f".to_string(),
+            topics: vec![event_topic(V2_PAIR_CREATED), topic_word(TOKEN0), topic_word(TOKEN1)],
+            data: format!("{}{:064x}", topic_word(POOL), 42),
+            block_number: Some("0x10".to_string()),
+            transaction_hash: Some("0xabc".to_string()),
+            removed: false,
+        }
+    }
+
+    #[test]
+    fn test_decode_v2_pair_created() {
+        let created = PairCreated::decode(&v2_pair_created_log()).unwrap();
+        assert_eq!(created.dex, DexKind::UniswapV2);
+        assert_eq!(created.factory, "0x5c69bee701ef814a2b6a3edd4b1652cb9cc5aa6f");
+        assert_eq!((created.token0.as_str(), created.token1.as_str()), (TOKEN0, TOKEN1));
+        assert_eq!(created.pool, POOL);
+        assert_eq!(created.fee, None);
+        assert_eq!(created.block, Some(16));
+
+        let chain = ChainRef {
+            name: "ethereum".to_string(),
+            id: 1,
+        };
+        let signal = created.to_signal(&chain, "ethereum-dex", 7);
+        assert_eq!(signal.kind, PAIR_CREATED_KIND);
+        assert_eq!(signal.extra["feed"], "ethereum-dex");
+        assert_eq!(signal.extra["pool"], POOL);
+    }
+
+    #[test]
+    fn test_decode_v3_pool_created() {
+        let log = Log {
+            address: "0x1F98431c8aD98523631AE4a59f267346ea31F984".to_string(),
+            topics: vec![
+                event_topic(V3_POOL_CREATED),
+                topic_word(TOKEN0),
+                topic_word(TOKEN1),
+                format!("0x{:064x}", 3000),
+            ],
+            data: format!("0x{:064x}{}", 60, topic_word(POOL).trim_start_matches("0x")),
+            block_number: None,
+            transaction_hash: None,
+            removed: false,
+        };
+        let created = PairCreated::decode(&log).unwrap();
+        assert_eq!(created.dex, DexKind::UniswapV3);
+        assert_eq!(created.pool, POOL);
+        assert_eq!(created.fee, Some(3000));
+
+        // Other events and truncated logs are not pair creations
+        let other = Log {
+            topics: vec![event_topic("Transfer(address,address,uint256)")],
+            ..log.clone()
+        };
+        assert!(PairCreated::decode(&other).is_none());
+        let truncated = Log {
+            data: "0x".to_string(),
+            ..log
+        };
+        assert!(PairCreated::decode(&truncated).is_none());
+    }
+}
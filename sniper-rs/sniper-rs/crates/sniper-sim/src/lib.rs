@@ -1,3 +1,12 @@
+//! Simulation tooling for running the stack without chain access.
+//!
+//! [`scenario`] generates deterministic synthetic demo data, and [`session`]
+//! seeds it into the running services and drives a scripted trading session.
+//! The `sniper-sim` binary wraps both for demos and integration tests.
+
+pub mod scenario;
+pub mod session;
+
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
 }
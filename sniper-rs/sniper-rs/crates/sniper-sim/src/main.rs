@@ -0,0 +1,151 @@
+//! sniper-sim binary: generates demo scenarios and drives them against a running stack.
+
+use clap::{Parser, Subcommand};
+use sniper_sim::scenario::{Scenario, ScenarioConfig};
+use sniper_sim::session::{ServiceUrls, SessionDriver};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// CLI arguments for the scenario tool
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+/// Settings of a generated scenario
+#[derive(clap::Args, Debug)]
+struct GenerateArgs {
+    #[clap(long, default_value_t = 42)]
+    seed: u64,
+    #[clap(long, default_value_t = 2)]
+    tenants: usize,
+    #[clap(long, default_value_t = 3)]
+    users_per_tenant: usize,
+    #[clap(long, default_value_t = 4)]
+    tokens: usize,
+    #[clap(long, default_value_t = 3)]
+    orders_per_user: usize,
+    #[clap(long, default_value_t = 120)]
+    ticks: usize,
+    /// Standard deviation of a tick's price move, in percent
+    #[clap(long, default_value_t = 1.5)]
+    volatility_pct: f64,
+}
+
+impl GenerateArgs {
+    fn scenario(&self) -> Scenario {
+        Scenario::generate(ScenarioConfig {
+            seed: self.seed,
+            tenants: self.tenants,
+            users_per_tenant: self.users_per_tenant,
+            tokens: self.tokens,
+            orders_per_user: self.orders_per_user,
+            ticks: self.ticks,
+            volatility_pct: self.volatility_pct,
+            ..ScenarioConfig::default()
+        })
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Write a generated scenario as JSON
+    Generate {
+        #[clap(flatten)]
+        scenario: GenerateArgs,
+        #[clap(long, default_value = "target/sim/scenario.json")]
+        out: PathBuf,
+    },
+    /// Seed the services with a scenario and drive its trading session
+    Run {
+        /// Scenario written by `generate`; generated from the flags if omitted
+        #[clap(long)]
+        scenario_file: Option<PathBuf>,
+        #[clap(flatten)]
+        scenario: GenerateArgs,
+        /// Pause between ticks
+        #[clap(long, default_value_t = 250)]
+        tick_interval_ms: u64,
+        /// Service base URLs as JSON, e.g. '{"orders": "http://orders:8081"}'
+        #[clap(long)]
+        urls: Option<String>,
+        /// Write the session report as JSON
+        #[clap(long)]
+        report: Option<PathBuf>,
+        /// Access token issued by svc-users, sent as the bearer token
+        #[clap(long)]
+        access_token: Option<String>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    tracing_subscriber::fmt::init();
+    let args = Args::parse();
+
+    match args.command {
+        Command::Generate { scenario, out } => {
+            let scenario = scenario.scenario();
+            scenario.save(&out).map_err(|e| eyre::eyre!("{}", e))?;
+            println!(
+                "wrote {} users, {} pools, {} orders and {} ticks to {}",
+                scenario.users.len(),
+                scenario.pools.len(),
+                scenario.orders.len(),
+                scenario.ticks.len(),
+                out.display()
+            );
+        }
+        Command::Run {
+            scenario_file,
+            scenario,
+            tick_interval_ms,
+            urls,
+            report,
+            access_token,
+        } => {
+            let scenario = match scenario_file {
+                Some(path) => Scenario::load(&path).map_err(|e| eyre::eyre!("{}", e))?,
+                None => scenario.scenario(),
+            };
+            let urls: ServiceUrls = match urls {
+                Some(urls) => serde_json::from_str(&urls)?,
+                None => ServiceUrls::default(),
+            };
+            let mut driver =
+                SessionDriver::new(urls, Duration::from_millis(tick_interval_ms)).map_err(|e| eyre::eyre!("{}", e))?;
+            if let Some(token) = access_token {
+                driver = driver.with_access_token(token);
+            }
+            let session = driver.run(&scenario).await;
+
+            println!("services up: {}", session.services_up.join(", "));
+            if !session.services_skipped.is_empty() {
+                println!("skipped: {}", session.services_skipped.join(", "));
+            }
+            println!(
+                "{} users, {} accounts, {} pools, {} orders, {} ticks, {} fills, {} positions, {} signals",
+                session.users_created,
+                session.accounts_funded,
+                session.pools_added,
+                session.orders_created,
+                session.ticks_sent,
+                session.fills_recorded,
+                session.positions_opened,
+                session.signals_sent
+            );
+            for failure in &session.failures {
+                println!("FAILED {}", failure);
+            }
+            if let Some(path) = report {
+                std::fs::write(&path, serde_json::to_string_pretty(&session)?)?;
+            }
+            if session.services_up.is_empty() {
+                return Err(eyre::eyre!("no service answered its health check"));
+            }
+        }
+    }
+    Ok(())
+}
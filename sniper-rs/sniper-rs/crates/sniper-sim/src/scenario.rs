@@ -0,0 +1,463 @@
+//! Synthetic demo scenarios
+//!
+//! A scenario holds everything a demo stack needs without chain access: the
+//! tenants with their users and treasury accounts, tokens and the pools they
+//! trade in, resting orders, and a scripted price path for every token. One
+//! token launches part way through the session, pumps and then dumps, so the
+//! path crosses the trigger of every kind of generated order. Generation is
+//! deterministic in the seed, so the same scenario can be regenerated from
+//! the seed alone or shared as a JSON file.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Quote token of every generated pool
+pub const QUOTE_SYMBOL: &str = "WETH";
+const QUOTE_ADDRESS: &str = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+
+/// Protocols generated pools are spread over
+const PROTOCOLS: [&str; 2] = ["uniswap_v2", "sushiswap"];
+const ROLES: [&str; 3] = ["Trader", "Analyst", "Auditor"];
+const NAMES: [&str; 8] = ["PEPE", "WOJAK", "MOON", "TURBO", "BOBO", "FROG", "DOGE", "KITTY"];
+
+/// Settings of a generated scenario
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScenarioConfig {
+    pub seed: u64,
+    pub tenants: usize,
+    /// Users per tenant; the first is the tenant admin
+    pub users_per_tenant: usize,
+    pub tokens: usize,
+    /// Resting orders per trader
+    pub orders_per_user: usize,
+    /// Price ticks per token
+    pub ticks: usize,
+    /// Standard deviation of a tick's price move, in percent
+    pub volatility_pct: f64,
+    pub chain_name: String,
+    pub chain_id: u64,
+}
+
+impl Default for ScenarioConfig {
+    fn default() -> Self {
+        Self {
+            seed: 42,
+            tenants: 2,
+            users_per_tenant: 3,
+            tokens: 4,
+            orders_per_user: 3,
+            ticks: 120,
+            volatility_pct: 1.5,
+            chain_name: "ethereum".to_string(),
+            chain_id: 1,
+        }
+    }
+}
+
+/// Generated tenant and the treasury account funding it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TenantSeed {
+    pub id: String,
+    pub name: String,
+    pub treasury_address: String,
+    /// Quote token deposited into the treasury account
+    pub funding: f64,
+}
+
+/// Generated user of a tenant
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserSeed {
+    pub username: String,
+    pub email: String,
+    pub tenant_id: String,
+    pub roles: Vec<String>,
+}
+
+/// Generated token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenSeed {
+    pub symbol: String,
+    pub address: String,
+    /// Price in the quote token at the start of the session
+    pub initial_price: f64,
+    /// Tick at which the token's pool is created; zero for tokens trading from the start
+    pub launch_tick: usize,
+}
+
+impl TokenSeed {
+    /// Get the trading pair symbol, e.g. `PEPE/WETH`
+    pub fn pair(&self) -> String {
+        format!("{}/{}", self.symbol, QUOTE_SYMBOL)
+    }
+}
+
+/// Generated pool of a token against the quote token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PoolSeed {
+    pub id: String,
+    pub protocol: String,
+    pub symbol: String,
+    pub token0: String,
+    pub token1: String,
+    pub reserve0: u128,
+    pub reserve1: u128,
+    pub fee: f64,
+}
+
+/// Generated resting order of a trader
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderSeed {
+    pub username: String,
+    pub tenant_id: String,
+    /// Trading pair symbol
+    pub symbol: String,
+    /// `market`, `limit`, `stop_loss` or `take_profit`
+    pub order_type: String,
+    /// `buy` or `sell`
+    pub side: String,
+    pub amount: f64,
+    /// Limit, stop or target price, depending on the order type
+    pub price: Option<f64>,
+}
+
+impl OrderSeed {
+    /// Whether the order triggers at `price`, following the rules of svc-orders
+    pub fn triggers(&self, price: f64) -> bool {
+        let Some(level) = self.price else {
+            return true;
+        };
+        let buy = self.side == "buy";
+        match self.order_type.as_str() {
+            "limit" => (buy && price <= level) || (!buy && price >= level),
+            "stop_loss" | "take_profit" => (buy && price >= level) || (!buy && price <= level),
+            _ => true,
+        }
+    }
+}
+
+/// One step of a token's price path
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceTick {
+    pub tick: usize,
+    pub symbol: String,
+    pub price: f64,
+    /// Quote token traded during the tick
+    pub volume: f64,
+    /// Price change since the previous tick, in percent
+    pub momentum_pct: f64,
+    /// Relative strength index over the last 14 ticks
+    pub rsi: f64,
+}
+
+/// Generated demo scenario
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scenario {
+    pub config: ScenarioConfig,
+    pub tenants: Vec<TenantSeed>,
+    pub users: Vec<UserSeed>,
+    pub tokens: Vec<TokenSeed>,
+    pub pools: Vec<PoolSeed>,
+    pub orders: Vec<OrderSeed>,
+    /// Ticks of all tokens, ordered by tick
+    pub ticks: Vec<PriceTick>,
+}
+
+impl Scenario {
+    /// Generate a scenario from its settings
+    pub fn generate(config: ScenarioConfig) -> Self {
+        let mut rng = SplitMix64::new(config.seed);
+
+        let tenants: Vec<TenantSeed> = (0..config.tenants)
+            .map(|index| TenantSeed {
+                id: format!("tenant-{}", index + 1),
+                name: format!("Demo Desk {}", index + 1),
+                treasury_address: rng.address(),
+                funding: (rng.range(50.0, 250.0) * 100.0).round() / 100.0,
+            })
+            .collect();
+
+        let mut users = Vec::new();
+        for tenant in &tenants {
+            for index in 0..config.users_per_tenant {
+                let username = format!("{}-user{}", tenant.id, index + 1);
+                let role = if index == 0 {
+                    "Admin"
+                } else {
+                    ROLES[(index - 1) % ROLES.len()]
+                };
+                users.push(UserSeed {
+                    email: format!("{}@demo.local", username),
+                    username,
+                    tenant_id: tenant.id.clone(),
+                    roles: vec![role.to_string()],
+                });
+            }
+        }
+
+        // The last token launches a third of the way through the session
+        let launch_tick = config.ticks / 3;
+        let tokens: Vec<TokenSeed> = (0..config.tokens)
+            .map(|index| TokenSeed {
+                symbol: match index / NAMES.len() {
+                    0 => NAMES[index].to_string(),
+                    round => format!("{}{}", NAMES[index % NAMES.len()], round + 1),
+                },
+                address: rng.address(),
+                initial_price: 10f64.powf(rng.range(-6.0, -2.0)),
+                launch_tick: if index + 1 == config.tokens { launch_tick } else { 0 },
+            })
+            .collect();
+
+        let mut pools = Vec::new();
+        for token in &tokens {
+            // New tokens start with a single pool
+            let count = if token.launch_tick > 0 { 1 } else { PROTOCOLS.len() };
+            for protocol in &PROTOCOLS[..count] {
+                let quote_reserve = rng.range(20.0, 500.0);
+                pools.push(PoolSeed {
+                    id: format!("{}-{}", protocol, token.symbol.to_lowercase()),
+                    protocol: protocol.to_string(),
+                    symbol: token.pair(),
+                    token0: token.address.clone(),
+                    token1: QUOTE_ADDRESS.to_string(),
+                    reserve0: to_wei(quote_reserve / token.initial_price),
+                    reserve1: to_wei(quote_reserve),
+                    fee: 0.003,
+                });
+            }
+        }
+
+        let mut orders = Vec::new();
+        let traders = users
+            .iter()
+            .filter(|user| !tokens.is_empty() && user.roles.iter().any(|role| role == "Trader"));
+        for user in traders {
+            for index in 0..config.orders_per_user {
+                let token = &tokens[rng.below(tokens.len())];
+                let (order_type, side, price) = match index % 4 {
+                    0 => ("limit", "buy", Some(token.initial_price * 0.97)),
+                    1 => ("take_profit", "buy", Some(token.initial_price * 1.15)),
+                    2 => ("stop_loss", "sell", Some(token.initial_price * 0.9)),
+                    _ => ("market", "buy", None),
+                };
+                orders.push(OrderSeed {
+                    username: user.username.clone(),
+                    tenant_id: user.tenant_id.clone(),
+                    symbol: token.pair(),
+                    order_type: order_type.to_string(),
+                    side: side.to_string(),
+                    amount: (rng.range(0.05, 0.5) * 1000.0).round() / 1000.0,
+                    price,
+                });
+            }
+        }
+
+        let paths: Vec<Vec<PriceTick>> = tokens
+            .iter()
+            .map(|token| price_path(&config, token, &mut rng))
+            .collect();
+        let mut ticks: Vec<PriceTick> = paths.into_iter().flatten().collect();
+        ticks.sort_by_key(|tick| tick.tick);
+
+        Self {
+            config,
+            tenants,
+            users,
+            tokens,
+            pools,
+            orders,
+            ticks,
+        }
+    }
+
+    /// Load a scenario saved with `save`
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Save the scenario as JSON
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Random walk of a token from its launch tick, with a scripted pump and
+/// dump for tokens launching during the session
+fn price_path(config: &ScenarioConfig, token: &TokenSeed, rng: &mut SplitMix64) -> Vec<PriceTick> {
+    let mut ticks = Vec::new();
+    let mut price = token.initial_price;
+    let mut moves: Vec<f64> = Vec::new();
+    for tick in token.launch_tick..config.ticks {
+        let since_launch = tick - token.launch_tick;
+        let mut change_pct = rng.normal() * config.volatility_pct;
+        if token.launch_tick > 0 {
+            change_pct += match since_launch {
+                1..=5 => 8.0,
+                6..=10 => -7.0,
+                _ => 0.0,
+            };
+        }
+        if tick > token.launch_tick {
+            let previous = price;
+            price *= 1.0 + change_pct / 100.0;
+            moves.push(price - previous);
+        }
+        let recent = &moves[moves.len().saturating_sub(14)..];
+        ticks.push(PriceTick {
+            tick,
+            symbol: token.pair(),
+            price,
+            volume: rng.range(0.5, 5.0) * (1.0 + change_pct.abs() / 2.0),
+            momentum_pct: if tick > token.launch_tick { change_pct } else { 0.0 },
+            rsi: rsi(recent),
+        });
+    }
+    ticks
+}
+
+fn rsi(moves: &[f64]) -> f64 {
+    let gains: f64 = moves.iter().filter(|change| **change > 0.0).sum();
+    let losses: f64 = -moves.iter().filter(|change| **change < 0.0).sum::<f64>();
+    if gains + losses == 0.0 {
+        return 50.0;
+    }
+    100.0 * gains / (gains + losses)
+}
+
+fn to_wei(amount: f64) -> u128 {
+    (amount * 1e18) as u128
+}
+
+/// SplitMix64, so scenarios don't change with a random number crate's version
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1)
+    fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn range(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.unit()
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound.max(1) as u64) as usize
+    }
+
+    /// Standard normal, by Box-Muller
+    fn normal(&mut self) -> f64 {
+        let u1 = self.unit().max(f64::MIN_POSITIVE);
+        let u2 = self.unit();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+
+    fn address(&mut self) -> String {
+        format!(
+            "0x{:016x}{:016x}{:08x}",
+            self.next_u64(),
+            self.next_u64(),
+            self.next_u64() as u32
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generation_is_deterministic() {
+        let first = Scenario::generate(ScenarioConfig::default());
+        let second = Scenario::generate(ScenarioConfig::default());
+        assert_eq!(first.tenants, second.tenants);
+        assert_eq!(first.orders, second.orders);
+        assert_eq!(first.ticks, second.ticks);
+
+        let other = Scenario::generate(ScenarioConfig {
+            seed: 7,
+            ..ScenarioConfig::default()
+        });
+        assert_ne!(first.ticks, other.ticks);
+    }
+
+    #[test]
+    fn test_scenario_shape() {
+        let config = ScenarioConfig::default();
+        let scenario = Scenario::generate(config.clone());
+        assert_eq!(scenario.tenants.len(), 2);
+        assert_eq!(scenario.users.len(), 6);
+        assert_eq!(scenario.users[0].roles, vec!["Admin"]);
+        // One trader per tenant with the default roles
+        assert_eq!(scenario.orders.len(), 2 * config.orders_per_user);
+        assert!(scenario.ticks.windows(2).all(|pair| pair[0].tick <= pair[1].tick));
+
+        let launched = scenario.tokens.last().unwrap();
+        assert_eq!(launched.launch_tick, 40);
+        assert_eq!(
+            scenario
+                .pools
+                .iter()
+                .filter(|pool| pool.symbol == launched.pair())
+                .count(),
+            1
+        );
+        let path: Vec<&PriceTick> = scenario
+            .ticks
+            .iter()
+            .filter(|tick| tick.symbol == launched.pair())
+            .collect();
+        assert_eq!(path.len(), 80);
+        let peak = path.iter().map(|tick| tick.price).fold(0.0, f64::max);
+        assert!(peak > launched.initial_price * 1.15);
+        assert!(path[10].price < peak * 0.9);
+
+        for pool in &scenario.pools {
+            let token = scenario
+                .tokens
+                .iter()
+                .find(|token| token.pair() == pool.symbol)
+                .unwrap();
+            let price = pool.reserve1 as f64 / pool.reserve0 as f64;
+            assert!((price / token.initial_price - 1.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_order_triggers() {
+        let order = |order_type: &str, side: &str, price: Option<f64>| OrderSeed {
+            username: "u".to_string(),
+            tenant_id: "t".to_string(),
+            symbol: "PEPE/WETH".to_string(),
+            order_type: order_type.to_string(),
+            side: side.to_string(),
+            amount: 1.0,
+            price,
+        };
+        assert!(order("limit", "buy", Some(1.0)).triggers(0.9));
+        assert!(!order("limit", "buy", Some(1.0)).triggers(1.1));
+        assert!(order("stop_loss", "sell", Some(1.0)).triggers(0.9));
+        assert!(!order("stop_loss", "sell", Some(1.0)).triggers(1.1));
+        assert!(order("take_profit", "buy", Some(1.0)).triggers(1.1));
+        assert!(order("market", "buy", None).triggers(5.0));
+    }
+}
@@ -0,0 +1,542 @@
+//! Scripted trading session against a running stack
+//!
+//! `SessionDriver` seeds the services over their HTTP APIs with a generated
+//! scenario and then replays its price path: users, treasury accounts and
+//! deposits, pools, resting orders, and per tick the prices sent to the order,
+//! portfolio and AI services. When a resting order's trigger is crossed, the
+//! driver records its fill and opens the matching portfolio position, standing
+//! in for an executor without chain access. Services that don't answer their
+//! health check are skipped, so the session also runs against a partial stack.
+
+use crate::scenario::{OrderSeed, PriceTick, Scenario, UserSeed, QUOTE_SYMBOL};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Base URLs of the services, defaulting to their local ports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServiceUrls {
+    pub users: String,
+    pub orders: String,
+    pub liquidity: String,
+    pub portfolio: String,
+    pub treasury: String,
+    pub ai: String,
+    pub gateway: String,
+}
+
+impl Default for ServiceUrls {
+    fn default() -> Self {
+        Self {
+            users: "http://127.0.0.1:8084".to_string(),
+            orders: "http://127.0.0.1:8081".to_string(),
+            liquidity: "http://127.0.0.1:8097".to_string(),
+            portfolio: "http://127.0.0.1:8080".to_string(),
+            treasury: "http://127.0.0.1:8098".to_string(),
+            ai: "http://127.0.0.1:8096".to_string(),
+            gateway: "http://127.0.0.1:3000".to_string(),
+        }
+    }
+}
+
+impl ServiceUrls {
+    /// Point every service at the same base URL, e.g. a gateway proxying all of them
+    pub fn all(url: &str) -> Self {
+        Self {
+            users: url.to_string(),
+            orders: url.to_string(),
+            liquidity: url.to_string(),
+            portfolio: url.to_string(),
+            treasury: url.to_string(),
+            ai: url.to_string(),
+            gateway: url.to_string(),
+        }
+    }
+
+    fn named(&self) -> [(&'static str, &str); 7] {
+        [
+            ("users", &self.users),
+            ("orders", &self.orders),
+            ("liquidity", &self.liquidity),
+            ("portfolio", &self.portfolio),
+            ("treasury", &self.treasury),
+            ("ai", &self.ai),
+            ("gateway", &self.gateway),
+        ]
+    }
+}
+
+/// Outcome of a session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionReport {
+    /// Services that answered their health check
+    pub services_up: Vec<String>,
+    /// Services skipped because they were down
+    pub services_skipped: Vec<String>,
+    pub users_created: usize,
+    pub accounts_funded: usize,
+    pub pools_added: usize,
+    pub orders_created: usize,
+    pub ticks_sent: usize,
+    /// Trade plans svc-orders returned for price updates
+    pub plans_triggered: usize,
+    pub fills_recorded: usize,
+    pub positions_opened: usize,
+    pub signals_sent: usize,
+    /// Requests that failed, with the reason
+    pub failures: Vec<String>,
+}
+
+/// Drives a scenario against the services
+pub struct SessionDriver {
+    http: reqwest::Client,
+    urls: ServiceUrls,
+    tick_interval: Duration,
+    access_token: Option<String>,
+}
+
+struct OpenOrder<'a> {
+    id: String,
+    seed: &'a OrderSeed,
+}
+
+impl SessionDriver {
+    /// Create a driver pausing `tick_interval` between ticks
+    pub fn new(urls: ServiceUrls, tick_interval: Duration) -> Result<Self> {
+        let http = reqwest::Client::builder().timeout(Duration::from_secs(5)).build()?;
+        Ok(Self {
+            http,
+            urls,
+            tick_interval,
+            access_token: None,
+        })
+    }
+
+    /// Send `token` as the bearer token, for services that require authentication
+    pub fn with_access_token(mut self, token: impl Into<String>) -> Self {
+        self.access_token = Some(token.into());
+        self
+    }
+
+    /// Seed the services with the scenario and replay its price path
+    pub async fn run(&self, scenario: &Scenario) -> SessionReport {
+        let mut report = SessionReport::default();
+        for (name, url) in self.urls.named() {
+            if self.healthy(url).await {
+                report.services_up.push(name.to_string());
+            } else {
+                tracing::warn!("{} at {} is down, skipping it", name, url);
+                report.services_skipped.push(name.to_string());
+            }
+        }
+        let up = |name: &str| report.services_up.iter().any(|service| service == name);
+        let (users_up, treasury_up, liquidity_up, orders_up) =
+            (up("users"), up("treasury"), up("liquidity"), up("orders"));
+        let (portfolio_up, ai_up, gateway_up) = (up("portfolio"), up("ai"), up("gateway"));
+
+        let mut user_ids: HashMap<&str, String> = HashMap::new();
+        for user in &scenario.users {
+            // Without svc-users, usernames stand in for user IDs
+            let mut id = user.username.clone();
+            if users_up {
+                match self.create_user(user).await {
+                    Ok(created) => {
+                        id = created;
+                        report.users_created += 1;
+                    }
+                    Err(e) => report.failures.push(format!("user {}: {}", user.username, e)),
+                }
+            }
+            user_ids.insert(&user.username, id);
+        }
+
+        if treasury_up {
+            for tenant in &scenario.tenants {
+                match self.fund_tenant(scenario, tenant).await {
+                    Ok(()) => report.accounts_funded += 1,
+                    Err(e) => report.failures.push(format!("treasury of {}: {}", tenant.id, e)),
+                }
+            }
+        }
+
+        // Pools of tokens launching later are added at their launch tick
+        if liquidity_up {
+            for token in scenario.tokens.iter().filter(|token| token.launch_tick == 0) {
+                self.add_pools(scenario, &token.pair(), &mut report).await;
+            }
+        }
+
+        let mut open = Vec::new();
+        if orders_up {
+            for order in &scenario.orders {
+                let user_id = &user_ids[order.username.as_str()];
+                match self.create_order(scenario, order, user_id).await {
+                    Ok(id) => {
+                        report.orders_created += 1;
+                        open.push(OpenOrder { id, seed: order });
+                    }
+                    Err(e) => report.failures.push(format!("order of {}: {}", order.username, e)),
+                }
+            }
+        }
+
+        let mut current = None;
+        for tick in &scenario.ticks {
+            if current != Some(tick.tick) {
+                if current.is_some() && !self.tick_interval.is_zero() {
+                    tokio::time::sleep(self.tick_interval).await;
+                }
+                current = Some(tick.tick);
+            }
+
+            if let Some(token) = scenario
+                .tokens
+                .iter()
+                .find(|token| token.launch_tick > 0 && token.launch_tick == tick.tick && token.pair() == tick.symbol)
+            {
+                if liquidity_up {
+                    self.add_pools(scenario, &tick.symbol, &mut report).await;
+                }
+                if gateway_up {
+                    match self.send_launch_signal(scenario, &token.address).await {
+                        Ok(()) => report.signals_sent += 1,
+                        Err(e) => report
+                            .failures
+                            .push(format!("launch signal of {}: {}", token.symbol, e)),
+                    }
+                }
+            }
+
+            self.send_tick(tick, orders_up, portfolio_up, ai_up, &mut report).await;
+
+            let mut index = 0;
+            while index < open.len() {
+                if open[index].seed.symbol != tick.symbol || !open[index].seed.triggers(tick.price) {
+                    index += 1;
+                    continue;
+                }
+                let order = open.swap_remove(index);
+                match self.fill(scenario, &order, tick.price, portfolio_up).await {
+                    Ok(position) => {
+                        report.fills_recorded += 1;
+                        report.positions_opened += position as usize;
+                    }
+                    Err(e) => report.failures.push(format!("fill of {}: {}", order.id, e)),
+                }
+            }
+        }
+        report.ticks_sent = scenario.ticks.len();
+        report
+    }
+
+    async fn healthy(&self, url: &str) -> bool {
+        match self.http.get(format!("{}/health", url)).send().await {
+            Ok(response) => response.status().is_success(),
+            Err(_) => false,
+        }
+    }
+
+    async fn create_user(&self, user: &UserSeed) -> Result<String> {
+        let body = json!({
+            "username": user.username,
+            "email": user.email,
+            "roles": user.roles,
+            "tenant_id": user.tenant_id,
+        });
+        let response = self.post(&format!("{}/users", self.urls.users), &body, None).await?;
+        created_id(&response)
+    }
+
+    async fn fund_tenant(&self, scenario: &Scenario, tenant: &crate::scenario::TenantSeed) -> Result<()> {
+        let body = json!({
+            "name": format!("{} hot wallet", tenant.name),
+            "kind": "HotWallet",
+            "chain_id": scenario.config.chain_id,
+            "chain_name": scenario.config.chain_name,
+            "venue": "onchain",
+            "address": tenant.treasury_address,
+            "tenant_id": tenant.id,
+            "actor": "sniper-sim",
+        });
+        let response = self
+            .post(&format!("{}/accounts", self.urls.treasury), &body, None)
+            .await?;
+        let id = created_id(&response)?;
+        let deposit = json!({"asset": QUOTE_SYMBOL, "amount": tenant.funding, "actor": "sniper-sim"});
+        self.post(
+            &format!("{}/accounts/{}/deposit", self.urls.treasury, id),
+            &deposit,
+            None,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn add_pools(&self, scenario: &Scenario, symbol: &str, report: &mut SessionReport) {
+        for pool in scenario.pools.iter().filter(|pool| pool.symbol == symbol) {
+            let body = json!({
+                "source_id": pool.id,
+                "source": {
+                    "protocol": pool.protocol,
+                    "chain": {"name": scenario.config.chain_name, "id": scenario.config.chain_id},
+                    "pair": {"token0": pool.token0, "token1": pool.token1},
+                    "reserve0": pool.reserve0,
+                    "reserve1": pool.reserve1,
+                    "fee": pool.fee,
+                    "timestamp": now_ms() / 1000,
+                },
+            });
+            match self
+                .post(&format!("{}/liquidity/sources", self.urls.liquidity), &body, None)
+                .await
+            {
+                Ok(_) => report.pools_added += 1,
+                Err(e) => report.failures.push(format!("pool {}: {}", pool.id, e)),
+            }
+        }
+    }
+
+    async fn create_order(&self, scenario: &Scenario, order: &OrderSeed, user_id: &str) -> Result<String> {
+        let mut body = json!({
+            "symbol": order.symbol,
+            "chain_id": scenario.config.chain_id,
+            "chain_name": scenario.config.chain_name,
+            "order_type": order.order_type,
+            "side": order.side,
+            "amount": order.amount,
+            "tenant_id": order.tenant_id,
+        });
+        let field = match order.order_type.as_str() {
+            "stop_loss" => "stop_price",
+            "take_profit" => "limit_price",
+            _ => "price",
+        };
+        if let Some(price) = order.price {
+            body[field] = json!(price);
+        }
+        let response = self
+            .post(
+                &format!("{}/orders", self.urls.orders),
+                &body,
+                Some((user_id, &order.tenant_id)),
+            )
+            .await?;
+        created_id(&response)
+    }
+
+    async fn send_launch_signal(&self, scenario: &Scenario, token: &str) -> Result<()> {
+        let pool = scenario.pools.iter().find(|pool| pool.token0 == token);
+        let body = json!({
+            "source": "dex",
+            "kind": "pair_created",
+            "chain_name": scenario.config.chain_name,
+            "chain_id": scenario.config.chain_id,
+            "token0": token,
+            "token1": pool.map(|pool| pool.token1.clone()),
+            "extra": {"pool": pool.map(|pool| pool.id.clone()), "scenario_seed": scenario.config.seed},
+        });
+        self.post(&format!("{}/signals", self.urls.gateway), &body, None)
+            .await?;
+        Ok(())
+    }
+
+    async fn send_tick(
+        &self,
+        tick: &PriceTick,
+        orders_up: bool,
+        portfolio_up: bool,
+        ai_up: bool,
+        report: &mut SessionReport,
+    ) {
+        let price = json!({"symbol": tick.symbol, "price": tick.price});
+        if orders_up {
+            match self.post(&format!("{}/prices", self.urls.orders), &price, None).await {
+                Ok(response) => report.plans_triggered += plans(&response),
+                Err(e) => report
+                    .failures
+                    .push(format!("price of {} to orders: {}", tick.symbol, e)),
+            }
+        }
+        if portfolio_up {
+            if let Err(e) = self
+                .post(&format!("{}/prices", self.urls.portfolio), &price, None)
+                .await
+            {
+                report
+                    .failures
+                    .push(format!("price of {} to portfolio: {}", tick.symbol, e));
+            }
+        }
+        if ai_up {
+            let point = json!({
+                "timestamp": now_ms() / 1000,
+                "price": tick.price,
+                "volume": tick.volume,
+                "liquidity": tick.volume * 100.0,
+                "volatility": tick.momentum_pct.abs(),
+                "momentum": tick.momentum_pct,
+                "rsi": tick.rsi,
+                "macd": 0.0,
+                "signal": null,
+            });
+            if let Err(e) = self
+                .post(
+                    &format!("{}/data", self.urls.ai),
+                    &json!({"data_points": [point]}),
+                    None,
+                )
+                .await
+            {
+                report
+                    .failures
+                    .push(format!("market data of {} to ai: {}", tick.symbol, e));
+            }
+        }
+    }
+
+    /// Record the fill of a triggered order, returning whether a position was opened
+    async fn fill(&self, scenario: &Scenario, order: &OpenOrder<'_>, price: f64, portfolio_up: bool) -> Result<bool> {
+        let body = json!({"quantity": order.seed.amount, "price": price});
+        self.post(&format!("{}/orders/{}/fills", self.urls.orders, order.id), &body, None)
+            .await?;
+        if !portfolio_up {
+            return Ok(false);
+        }
+        let position = json!({
+            "symbol": order.seed.symbol,
+            "chain_id": scenario.config.chain_id,
+            "chain_name": scenario.config.chain_name,
+            "amount": order.seed.amount,
+            "entry_price": price,
+            "current_price": price,
+            "side": order.seed.side,
+            "leverage": 1.0,
+        });
+        self.post(&format!("{}/positions", self.urls.portfolio), &position, None)
+            .await?;
+        Ok(true)
+    }
+
+    /// POST a JSON body, failing on error statuses and on `"success": false`
+    async fn post(&self, url: &str, body: &Value, viewer: Option<(&str, &str)>) -> Result<Value> {
+        let mut request = self.http.post(url).json(body);
+        if let Some(token) = &self.access_token {
+            request = request.bearer_auth(token);
+        }
+        if let Some((user_id, tenant_id)) = viewer {
+            request = request.header("x-user-id", user_id).header("x-tenant-id", tenant_id);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        let value: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            return Err(anyhow::anyhow!("{} returned {}", url, status));
+        }
+        if value.get("success") == Some(&Value::Bool(false)) {
+            let message = value.get("message").and_then(Value::as_str).unwrap_or("no message");
+            return Err(anyhow::anyhow!("{} rejected the request: {}", url, message));
+        }
+        Ok(value)
+    }
+}
+
+fn created_id(response: &Value) -> Result<String> {
+    response["data"]["id"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow::anyhow!("Response has no data.id"))
+}
+
+fn plans(response: &Value) -> usize {
+    response
+        .as_array()
+        .or_else(|| response["data"].as_array())
+        .map(Vec::len)
+        .unwrap_or(0)
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::ScenarioConfig;
+    use axum::{extract::Path, routing::get, routing::post, Extension, Json, Router};
+    use std::sync::{Arc, Mutex};
+
+    type Calls = Arc<Mutex<Vec<String>>>;
+
+    async fn created(Extension(calls): Extension<Calls>, Json(body): Json<Value>) -> Json<Value> {
+        let mut calls = calls.lock().unwrap();
+        calls.push(format!(
+            "create {}",
+            body["username"].as_str().or(body["symbol"].as_str()).unwrap_or("")
+        ));
+        Json(json!({"success": true, "data": {"id": format!("id-{}", calls.len())}}))
+    }
+
+    async fn fill(Extension(calls): Extension<Calls>, Path(id): Path<String>) -> Json<Value> {
+        calls.lock().unwrap().push(format!("fill {}", id));
+        Json(json!({"success": true, "data": {"id": id}}))
+    }
+
+    #[tokio::test]
+    async fn test_session_against_orders_and_users() {
+        let calls: Calls = Arc::new(Mutex::new(Vec::new()));
+        let app = Router::new()
+            .route("/health", get(|| async { "OK" }))
+            .route("/users", post(created))
+            .route("/orders", post(created))
+            .route("/orders/:id/fills", post(fill))
+            .route("/prices", post(|| async { Json(json!([])) }))
+            .layer(Extension(calls.clone()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let scenario = Scenario::generate(ScenarioConfig {
+            orders_per_user: 4,
+            ticks: 30,
+            ..ScenarioConfig::default()
+        });
+        let urls = ServiceUrls {
+            users: url.clone(),
+            orders: url,
+            ..ServiceUrls::all("http://127.0.0.1:1")
+        };
+        let report = SessionDriver::new(urls, Duration::ZERO).unwrap().run(&scenario).await;
+
+        assert_eq!(report.services_up, vec!["users", "orders"]);
+        assert_eq!(report.services_skipped.len(), 5);
+        assert_eq!(report.users_created, scenario.users.len());
+        assert_eq!(report.orders_created, scenario.orders.len());
+        assert_eq!(report.ticks_sent, scenario.ticks.len());
+        assert_eq!(
+            (report.pools_added, report.positions_opened, report.signals_sent),
+            (0, 0, 0)
+        );
+        assert!(report.failures.is_empty(), "{:?}", report.failures);
+
+        // Market orders fill on the first tick of their symbol
+        let markets = scenario
+            .orders
+            .iter()
+            .filter(|order| order.order_type == "market")
+            .count();
+        assert_eq!(markets, 2);
+        assert!(report.fills_recorded >= markets);
+        let calls = calls.lock().unwrap();
+        assert_eq!(
+            calls.iter().filter(|call| call.starts_with("fill")).count(),
+            report.fills_recorded
+        );
+    }
+}
@@ -0,0 +1,513 @@
+//! Lease-based leadership for multi-region active/standby failover.
+//!
+//! Each region runs a `FailoverCoordinator` against the shared lease store.
+//! The primary renews its lease on every heartbeat; when it misses enough
+//! heartbeats for the lease to expire, a standby acquires it and takes over
+//! execution. Every change of holder bumps a fencing token, and executors
+//! validate their token against the store before submitting a transaction,
+//! so a primary that lost its lease can never submit for the same wallets.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use redis::RedisResult;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+
+/// An execution lease held by one region
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lease {
+    pub name: String,
+    pub holder: String,
+    pub fencing_token: u64,
+    pub expires_at_ms: u64, // Unix timestamp in milliseconds
+}
+
+impl Lease {
+    /// Check whether the lease is still valid at the given time
+    pub fn is_live(&self, now_ms: u64) -> bool {
+        now_ms < self.expires_at_ms
+    }
+}
+
+/// Shared backend storing execution leases
+#[async_trait]
+pub trait LeaseStore: Send + Sync {
+    /// Acquire or renew a lease
+    ///
+    /// Returns the lease if `holder` now owns it, or None if another holder's
+    /// lease is still live. The fencing token only changes when the holder does.
+    async fn try_acquire(&self, name: &str, holder: &str, ttl: Duration) -> Result<Option<Lease>>;
+
+    /// Release a lease, only if it is held by `holder`
+    async fn release(&self, name: &str, holder: &str) -> Result<bool>;
+
+    /// Get the current live lease, if any
+    async fn current(&self, name: &str) -> Result<Option<Lease>>;
+
+    /// Check that a fencing token belongs to the current live lease
+    async fn validate_fence(&self, name: &str, fencing_token: u64) -> Result<bool>;
+}
+
+/// Current time as a Unix timestamp in milliseconds
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// In-memory lease store for single-process deployments and tests
+pub struct InMemoryLeaseStore {
+    leases: Arc<RwLock<HashMap<String, Lease>>>,
+    // Last issued token per lease, kept across releases so tokens never go backwards
+    tokens: Arc<RwLock<HashMap<String, u64>>>,
+}
+
+impl InMemoryLeaseStore {
+    /// Create a new in-memory lease store
+    pub fn new() -> Self {
+        Self {
+            leases: Arc::new(RwLock::new(HashMap::new())),
+            tokens: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
+
+impl Default for InMemoryLeaseStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl LeaseStore for InMemoryLeaseStore {
+    async fn try_acquire(&self, name: &str, holder: &str, ttl: Duration) -> Result<Option<Lease>> {
+        let mut leases = self.leases.write().await;
+        let mut tokens = self.tokens.write().await;
+        let now = now_ms();
+        let expires_at_ms = now + ttl.as_millis() as u64;
+
+        if let Some(lease) = leases.get_mut(name) {
+            if lease.is_live(now) {
+                if lease.holder != holder {
+                    return Ok(None);
+                }
+                // Renewal keeps the same fencing token
+                lease.expires_at_ms = expires_at_ms;
+                return Ok(Some(lease.clone()));
+            }
+        }
+
+        let token = tokens.entry(name.to_string()).or_insert(0);
+        *token += 1;
+
+        let lease = Lease {
+            name: name.to_string(),
+            holder: holder.to_string(),
+            fencing_token: *token,
+            expires_at_ms,
+        };
+        leases.insert(name.to_string(), lease.clone());
+
+        Ok(Some(lease))
+    }
+
+    async fn release(&self, name: &str, holder: &str) -> Result<bool> {
+        let mut leases = self.leases.write().await;
+        match leases.get(name) {
+            Some(lease) if lease.holder == holder => {
+                leases.remove(name);
+                Ok(true)
+            },
+            _ => Ok(false),
+        }
+    }
+
+    async fn current(&self, name: &str) -> Result<Option<Lease>> {
+        let leases = self.leases.read().await;
+        Ok(leases.get(name).filter(|lease| lease.is_live(now_ms())).cloned())
+    }
+
+    async fn validate_fence(&self, name: &str, fencing_token: u64) -> Result<bool> {
+        Ok(self
+            .current(name)
+            .await?
+            .map(|lease| lease.fencing_token == fencing_token)
+            .unwrap_or(false))
+    }
+}
+
+/// Redis-backed lease store shared by all regions
+pub struct RedisLeaseStore {
+    client: redis::Client,
+}
+
+impl RedisLeaseStore {
+    /// Create a new Redis lease store
+    pub fn new(client: redis::Client) -> Self {
+        Self { client }
+    }
+
+    fn holder_key(name: &str) -> String {
+        format!("lease:{}", name)
+    }
+
+    fn fence_key(name: &str) -> String {
+        format!("lease:{}:fence", name)
+    }
+}
+
+#[async_trait]
+impl LeaseStore for RedisLeaseStore {
+    async fn try_acquire(&self, name: &str, holder: &str, ttl: Duration) -> Result<Option<Lease>> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+
+        // Atomically renew our own lease or take over a missing one, bumping the
+        // fencing token only on a change of holder
+        let script = redis::Script::new(
+            r#"
+            local current = redis.call("GET", KEYS[1])
+            if current == false then
+                local token = redis.call("INCR", KEYS[2])
+                redis.call("SET", KEYS[1], ARGV[1], "PX", ARGV[2])
+                return token
+            elseif current == ARGV[1] then
+                redis.call("PEXPIRE", KEYS[1], ARGV[2])
+                return tonumber(redis.call("GET", KEYS[2]))
+            else
+                return -1
+            end
+            "#
+        );
+
+        let ttl_ms = ttl.as_millis() as u64;
+        let token: i64 = script
+            .key(Self::holder_key(name))
+            .key(Self::fence_key(name))
+            .arg(holder)
+            .arg(ttl_ms)
+            .invoke_async(&mut conn)
+            .await?;
+
+        if token < 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(Lease {
+            name: name.to_string(),
+            holder: holder.to_string(),
+            fencing_token: token as u64,
+            expires_at_ms: now_ms() + ttl_ms,
+        }))
+    }
+
+    async fn release(&self, name: &str, holder: &str) -> Result<bool> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+
+        let script = redis::Script::new(
+            r#"
+            if redis.call("GET", KEYS[1]) == ARGV[1] then
+                return redis.call("DEL", KEYS[1])
+            else
+                return 0
+            end
+            "#
+        );
+
+        let result: RedisResult<i32> = script
+            .key(Self::holder_key(name))
+            .arg(holder)
+            .invoke_async(&mut conn)
+            .await;
+
+        match result {
+            Ok(1) => Ok(true),
+            Ok(_) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn current(&self, name: &str) -> Result<Option<Lease>> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+
+        let (holder, token, ttl_ms): (Option<String>, Option<u64>, i64) = redis::pipe()
+            .atomic()
+            .get(Self::holder_key(name))
+            .get(Self::fence_key(name))
+            .cmd("PTTL")
+            .arg(Self::holder_key(name))
+            .query_async(&mut conn)
+            .await?;
+
+        match (holder, token) {
+            (Some(holder), Some(fencing_token)) if ttl_ms > 0 => Ok(Some(Lease {
+                name: name.to_string(),
+                holder,
+                fencing_token,
+                expires_at_ms: now_ms() + ttl_ms as u64,
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    async fn validate_fence(&self, name: &str, fencing_token: u64) -> Result<bool> {
+        Ok(self
+            .current(name)
+            .await?
+            .map(|lease| lease.fencing_token == fencing_token)
+            .unwrap_or(false))
+    }
+}
+
+/// Role of a region in the active/standby pair
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Role {
+    Primary,
+    Standby,
+}
+
+/// A fencing token handed to a submission
+///
+/// Carries the lease it was issued for, so the submission checks it against
+/// the store at the point of the write rather than when it was fetched.
+#[derive(Clone)]
+pub struct FencingToken {
+    store: Arc<dyn LeaseStore>,
+    lease_name: String,
+    token: u64,
+}
+
+impl FencingToken {
+    /// Get the token value
+    pub fn token(&self) -> u64 {
+        self.token
+    }
+
+    /// Fail if the token no longer belongs to the live lease
+    pub async fn check(&self) -> Result<()> {
+        if !self.store.validate_fence(&self.lease_name, self.token).await? {
+            return Err(anyhow::anyhow!("Fencing token {} is stale", self.token));
+        }
+        Ok(())
+    }
+}
+
+/// Failover coordinator configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailoverConfig {
+    pub region: String,
+    pub lease_name: String,
+    pub lease_ttl: Duration,
+    pub heartbeat_interval: Duration,
+}
+
+impl FailoverConfig {
+    /// Create a configuration for a region guarding a wallet group
+    ///
+    /// The lease expires after three missed heartbeats.
+    pub fn new(region: &str, wallet_group: &str, heartbeat_interval: Duration) -> Self {
+        Self {
+            region: region.to_string(),
+            lease_name: format!("execution:{}", wallet_group),
+            lease_ttl: heartbeat_interval * 3,
+            heartbeat_interval,
+        }
+    }
+}
+
+/// Coordinates leadership of one region over an execution lease
+pub struct FailoverCoordinator {
+    config: FailoverConfig,
+    store: Arc<dyn LeaseStore>,
+    role: Role,
+    lease: Option<Lease>,
+}
+
+impl FailoverCoordinator {
+    /// Create a new coordinator, starting as standby
+    pub fn new(config: FailoverConfig, store: Arc<dyn LeaseStore>) -> Self {
+        Self {
+            config,
+            store,
+            role: Role::Standby,
+            lease: None,
+        }
+    }
+
+    /// Get the coordinator configuration
+    pub fn config(&self) -> &FailoverConfig {
+        &self.config
+    }
+
+    /// Get the current role
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    /// Check whether this region is the primary
+    pub fn is_primary(&self) -> bool {
+        self.role == Role::Primary
+    }
+
+    /// Get the fencing token of the held lease
+    pub fn fencing_token(&self) -> Option<u64> {
+        self.lease.as_ref().map(|lease| lease.fencing_token)
+    }
+
+    /// Send a heartbeat, renewing the lease as primary or trying to take it over as standby
+    ///
+    /// If the store is unreachable, a primary keeps its role only until its
+    /// last known lease expires.
+    pub async fn heartbeat(&mut self) -> Result<Role> {
+        let result = self
+            .store
+            .try_acquire(&self.config.lease_name, &self.config.region, self.config.lease_ttl)
+            .await;
+
+        match result {
+            Ok(Some(lease)) => {
+                if self.role == Role::Standby {
+                    tracing::info!(
+                        "region {} promoted to primary for {} with fencing token {}",
+                        self.config.region, self.config.lease_name, lease.fencing_token
+                    );
+                }
+                self.role = Role::Primary;
+                self.lease = Some(lease);
+            },
+            Ok(None) => {
+                if self.role == Role::Primary {
+                    tracing::warn!(
+                        "region {} lost lease {} to another region",
+                        self.config.region, self.config.lease_name
+                    );
+                }
+                self.role = Role::Standby;
+                self.lease = None;
+            },
+            Err(e) => {
+                let expired = self
+                    .lease
+                    .as_ref()
+                    .map(|lease| !lease.is_live(now_ms()))
+                    .unwrap_or(true);
+                if self.role == Role::Primary && expired {
+                    tracing::warn!(
+                        "region {} demoted to standby, lease {} expired while store unreachable",
+                        self.config.region, self.config.lease_name
+                    );
+                    self.role = Role::Standby;
+                    self.lease = None;
+                }
+                return Err(e);
+            },
+        }
+
+        Ok(self.role)
+    }
+
+    /// Check the fencing token against the store before submitting a transaction
+    ///
+    /// Returns the token to attach to the submission.
+    pub async fn fence(&self) -> Result<u64> {
+        let token = match (&self.lease, self.role) {
+            (Some(lease), Role::Primary) => lease.fencing_token,
+            _ => return Err(anyhow::anyhow!("Region {} is not the primary", self.config.region)),
+        };
+
+        if !self.store.validate_fence(&self.config.lease_name, token).await? {
+            return Err(anyhow::anyhow!("Fencing token {} is stale", token));
+        }
+
+        Ok(token)
+    }
+
+    /// Check the fencing token and hand it out for a submission to check again before it writes
+    pub async fn submission_fence(&self) -> Result<FencingToken> {
+        Ok(FencingToken {
+            store: self.store.clone(),
+            lease_name: self.config.lease_name.clone(),
+            token: self.fence().await?,
+        })
+    }
+
+    /// Release the lease so a standby can take over immediately
+    pub async fn step_down(&mut self) -> Result<()> {
+        self.store.release(&self.config.lease_name, &self.config.region).await?;
+        self.role = Role::Standby;
+        self.lease = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coordinator(region: &str, store: Arc<dyn LeaseStore>) -> FailoverCoordinator {
+        FailoverCoordinator::new(
+            FailoverConfig::new(region, "main", Duration::from_millis(20)),
+            store,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_single_primary() -> Result<()> {
+        let store: Arc<dyn LeaseStore> = Arc::new(InMemoryLeaseStore::new());
+        let mut primary = coordinator("us-east-1", store.clone());
+        let mut standby = coordinator("eu-west-1", store.clone());
+
+        assert_eq!(primary.heartbeat().await?, Role::Primary);
+        assert_eq!(standby.heartbeat().await?, Role::Standby);
+
+        // Renewals keep the same token
+        let token = primary.fence().await?;
+        primary.heartbeat().await?;
+        assert_eq!(primary.fence().await?, token);
+        assert!(standby.fence().await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_standby_takes_over_after_missed_heartbeats() -> Result<()> {
+        let store: Arc<dyn LeaseStore> = Arc::new(InMemoryLeaseStore::new());
+        let mut primary = coordinator("us-east-1", store.clone());
+        let mut standby = coordinator("eu-west-1", store.clone());
+
+        primary.heartbeat().await?;
+        let old_token = primary.fencing_token().unwrap();
+
+        // Primary stops heartbeating until its lease expires
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert_eq!(standby.heartbeat().await?, Role::Primary);
+        let new_token = standby.fence().await?;
+        assert!(new_token > old_token);
+
+        // The old primary is fenced off before it notices the takeover
+        assert!(primary.is_primary());
+        assert!(primary.fence().await.is_err());
+        assert!(primary.submission_fence().await.is_err());
+        assert_eq!(primary.heartbeat().await?, Role::Standby);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_step_down() -> Result<()> {
+        let store: Arc<dyn LeaseStore> = Arc::new(InMemoryLeaseStore::new());
+        let mut primary = coordinator("us-east-1", store.clone());
+        let mut standby = coordinator("eu-west-1", store.clone());
+
+        primary.heartbeat().await?;
+        primary.step_down().await?;
+        assert_eq!(primary.role(), Role::Standby);
+        assert_eq!(standby.heartbeat().await?, Role::Primary);
+        assert_eq!(standby.fencing_token(), Some(2));
+
+        Ok(())
+    }
+}
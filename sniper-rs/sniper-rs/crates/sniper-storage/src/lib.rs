@@ -1,13 +1,17 @@
 //! Storage module for the sniper bot.
 //! 
 //! This module provides functionality for database storage, position tracking,
-//! distributed locks, and idempotency mechanisms.
+//! distributed locks, idempotency mechanisms, and per-tenant encryption of
+//! data at rest.
 
 pub mod repo_trades;
 pub mod repo_positions;
 pub mod repo_runs;
 pub mod redis_locks;
 pub mod outbox;
+pub mod failover;
+pub mod tenant_keys;
+pub mod repo_encrypted;
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
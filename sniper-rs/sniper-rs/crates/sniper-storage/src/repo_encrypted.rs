@@ -0,0 +1,217 @@
+//! Tenant-partitioned repository of encrypted records.
+//!
+//! Records are serialized to JSON and encrypted with their tenant's key
+//! before they are stored, and partitioned by tenant so a lookup only ever
+//! sees the caller's tenant. One repository holds one kind of record, such as
+//! orders, positions or reports, and the kind is bound into each ciphertext
+//! so records cannot be moved between repositories.
+
+use crate::tenant_keys::{EncryptedRecord, TenantKeyManager};
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// In-memory repository of records encrypted per tenant
+/// In a real implementation, this would use a database
+pub struct EncryptedRepo<T> {
+    keys: Arc<TenantKeyManager>,
+    kind: String,
+    // Service identity recorded in the key audit log
+    actor: String,
+    records: Arc<RwLock<HashMap<String, HashMap<String, EncryptedRecord>>>>,
+    _record: PhantomData<fn() -> T>,
+}
+
+impl<T: Serialize + DeserializeOwned> EncryptedRepo<T> {
+    /// Create a repository for one kind of record, accessed as `actor`
+    pub fn new(keys: Arc<TenantKeyManager>, kind: &str, actor: &str) -> Self {
+        Self {
+            keys,
+            kind: kind.to_string(),
+            actor: actor.to_string(),
+            records: Arc::new(RwLock::new(HashMap::new())),
+            _record: PhantomData,
+        }
+    }
+
+    /// Kind of record held
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// Encrypt and store a record of a tenant
+    pub async fn put(&self, tenant_id: &str, id: &str, record: &T) -> Result<()> {
+        let plaintext = serde_json::to_vec(record)?;
+        let encrypted = self.keys.encrypt(tenant_id, &self.kind, &plaintext, &self.actor).await?;
+        self.records
+            .write()
+            .await
+            .entry(tenant_id.to_string())
+            .or_default()
+            .insert(id.to_string(), encrypted);
+        Ok(())
+    }
+
+    /// Get and decrypt a record of a tenant
+    pub async fn get(&self, tenant_id: &str, id: &str) -> Result<Option<T>> {
+        let encrypted = self.raw(tenant_id, id).await;
+        match encrypted {
+            Some(encrypted) => Ok(Some(self.open(&encrypted).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// List and decrypt every record of a tenant
+    pub async fn list(&self, tenant_id: &str) -> Result<Vec<T>> {
+        let encrypted: Vec<EncryptedRecord> = self
+            .records
+            .read()
+            .await
+            .get(tenant_id)
+            .map(|records| records.values().cloned().collect())
+            .unwrap_or_default();
+
+        let mut result = Vec::with_capacity(encrypted.len());
+        for record in &encrypted {
+            result.push(self.open(record).await?);
+        }
+        Ok(result)
+    }
+
+    /// Delete a record of a tenant
+    pub async fn delete(&self, tenant_id: &str, id: &str) -> Result<bool> {
+        let mut records = self.records.write().await;
+        Ok(records
+            .get_mut(tenant_id)
+            .map(|records| records.remove(id).is_some())
+            .unwrap_or(false))
+    }
+
+    /// Encrypted form of a record, as stored
+    pub async fn raw(&self, tenant_id: &str, id: &str) -> Option<EncryptedRecord> {
+        self.records.read().await.get(tenant_id).and_then(|records| records.get(id)).cloned()
+    }
+
+    /// Re-encrypt every record of a tenant under its active key
+    ///
+    /// Run after rotating a tenant's key; returns the number of records rewritten.
+    pub async fn reencrypt_tenant(&self, tenant_id: &str) -> Result<usize> {
+        let active = self.keys.active_version(tenant_id).await;
+        let mut records = self.records.write().await;
+        let Some(records) = records.get_mut(tenant_id) else {
+            return Ok(0);
+        };
+
+        let mut rewritten = 0;
+        for record in records.values_mut() {
+            if Some(record.key_version) != active {
+                *record = self.keys.reencrypt(record, &self.actor).await?;
+                rewritten += 1;
+            }
+        }
+        Ok(rewritten)
+    }
+
+    async fn open(&self, encrypted: &EncryptedRecord) -> Result<T> {
+        let plaintext = self.keys.decrypt(encrypted, &self.actor).await?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo_positions::{Position, PositionStatus};
+    use crate::tenant_keys::KeyAction;
+    use uuid::Uuid;
+
+    fn position(user_id: &str, entry_price: f64) -> Position {
+        Position {
+            id: Uuid::new_v4(),
+            user_id: user_id.to_string(),
+            symbol: "BTC/USDT".to_string(),
+            side: "buy".to_string(),
+            entry_price,
+            exit_price: None,
+            amount: 1.0,
+            leverage: 10.0,
+            status: PositionStatus::Open,
+            created_at: 1234567890,
+            updated_at: 1234567890,
+            closed_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tenant_partitioning() -> Result<()> {
+        let keys = Arc::new(TenantKeyManager::new(TenantKeyManager::generate_master_key()));
+        keys.create_tenant_key("tenant-a", "admin").await?;
+        keys.create_tenant_key("tenant-b", "admin").await?;
+        let repo: EncryptedRepo<Position> = EncryptedRepo::new(keys.clone(), "positions", "svc-portfolio");
+
+        let a = position("alice", 50000.0);
+        let b = position("bob", 51000.0);
+        repo.put("tenant-a", &a.id.to_string(), &a).await?;
+        repo.put("tenant-b", &b.id.to_string(), &b).await?;
+        assert!(repo.put("tenant-c", "p", &a).await.is_err());
+
+        // Stored records are ciphertext, each under its tenant's key
+        let raw = repo.raw("tenant-a", &a.id.to_string()).await.unwrap();
+        assert_eq!(raw.tenant_id, "tenant-a");
+        assert_eq!(raw.kind, "positions");
+        assert!(!raw.ciphertext.windows(5).any(|window| window == b"alice"));
+
+        let got = repo.get("tenant-a", &a.id.to_string()).await?.unwrap();
+        assert_eq!(got.user_id, "alice");
+        assert!(repo.get("tenant-b", &a.id.to_string()).await?.is_none());
+        let listed = repo.list("tenant-b").await?;
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].user_id, "bob");
+
+        // The record cannot be opened as another kind
+        let moved = EncryptedRecord {
+            kind: "reports".to_string(),
+            ..raw
+        };
+        assert!(keys.decrypt(&moved, "svc-compliance").await.is_err());
+
+        assert!(repo.delete("tenant-a", &a.id.to_string()).await?);
+        assert!(!repo.delete("tenant-a", &a.id.to_string()).await?);
+        assert!(repo.list("tenant-a").await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reencrypt_after_rotation() -> Result<()> {
+        let keys = Arc::new(TenantKeyManager::new(TenantKeyManager::generate_master_key()));
+        keys.create_tenant_key("tenant-a", "admin").await?;
+        keys.create_tenant_key("tenant-b", "admin").await?;
+        let repo: EncryptedRepo<Position> = EncryptedRepo::new(keys.clone(), "positions", "svc-portfolio");
+        for i in 0..3 {
+            repo.put("tenant-a", &i.to_string(), &position("alice", 50000.0 + i as f64)).await?;
+        }
+        repo.put("tenant-b", "0", &position("bob", 1.0)).await?;
+
+        keys.rotate_key("tenant-a", "admin").await?;
+        assert_eq!(repo.reencrypt_tenant("tenant-a").await?, 3);
+        assert_eq!(repo.reencrypt_tenant("tenant-a").await?, 0);
+        assert_eq!(repo.reencrypt_tenant("tenant-c").await?, 0);
+
+        assert_eq!(repo.raw("tenant-a", "1").await.unwrap().key_version, 2);
+        assert_eq!(repo.raw("tenant-b", "0").await.unwrap().key_version, 1);
+        assert_eq!(repo.get("tenant-a", "2").await?.unwrap().entry_price, 50002.0);
+
+        // Key usage is audited under the repository's service identity
+        let audit = keys.audit_for("tenant-a").await;
+        assert!(audit
+            .iter()
+            .filter(|entry| matches!(entry.action, KeyAction::Encrypted | KeyAction::Decrypted))
+            .all(|entry| entry.actor == "svc-portfolio" && entry.kind.as_deref() == Some("positions")));
+        assert!(keys.audit_for("tenant-b").await.iter().all(|entry| entry.key_version == 1));
+        Ok(())
+    }
+}
@@ -0,0 +1,409 @@
+//! Per-tenant encryption keys for data at rest.
+//!
+//! Every tenant's records are encrypted with the tenant's own AES-256-GCM
+//! data key, so one tenant's orders, positions and reports can never be read
+//! with another tenant's key. Data keys are held wrapped by a master key and
+//! versioned: rotating a tenant's key makes a new version active for
+//! encryption while older versions keep decrypting existing records until
+//! they are re-encrypted. Every use of a key is recorded in an audit log.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+
+/// Length of an AES-GCM nonce
+const NONCE_LEN: usize = 12;
+
+/// Operation a tenant key was used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyAction {
+    Created,
+    Rotated,
+    Encrypted,
+    Decrypted,
+    /// A decryption failed, e.g. a record presented under another tenant
+    Denied,
+}
+
+/// Audit record of one use of a tenant key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyAuditEntry {
+    pub tenant_id: String,
+    pub key_version: u32,
+    pub action: KeyAction,
+    pub actor: String,
+    /// Kind of record the key was used on, e.g. `positions`
+    pub kind: Option<String>,
+    pub timestamp: u64, // Unix timestamp
+}
+
+/// Version of a tenant data key, encrypted under the master key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WrappedKey {
+    pub version: u32,
+    pub nonce: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+    pub created_at: u64, // Unix timestamp
+}
+
+/// Record encrypted under a tenant key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedRecord {
+    pub tenant_id: String,
+    pub kind: String,
+    pub key_version: u32,
+    pub nonce: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
+
+/// Key versions of one tenant
+struct TenantKeys {
+    active: u32,
+    versions: BTreeMap<u32, WrappedKey>,
+}
+
+/// Manager of the per-tenant data keys
+pub struct TenantKeyManager {
+    master: Aes256Gcm,
+    tenants: RwLock<HashMap<String, TenantKeys>>,
+    audit: RwLock<Vec<KeyAuditEntry>>,
+}
+
+impl TenantKeyManager {
+    /// Create a key manager wrapping tenant keys with a 256-bit master key
+    pub fn new(master_key: [u8; 32]) -> Self {
+        Self {
+            master: Aes256Gcm::new(&master_key.into()),
+            tenants: RwLock::new(HashMap::new()),
+            audit: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Create a key manager from a hex master key in an environment variable
+    pub fn from_env(var: &str) -> Result<Self> {
+        let master_key = std::env::var(var).map_err(|_| anyhow::anyhow!("{} is not set", var))?;
+        let bytes = hex::decode(master_key.trim().trim_start_matches("0x"))
+            .map_err(|e| anyhow::anyhow!("Invalid master key hex: {}", e))?;
+        let master_key: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Master key must be 32 bytes"))?;
+        Ok(Self::new(master_key))
+    }
+
+    /// Generate a random master key
+    pub fn generate_master_key() -> [u8; 32] {
+        let mut master_key = [0u8; 32];
+        master_key.copy_from_slice(&Aes256Gcm::generate_key(OsRng));
+        master_key
+    }
+
+    /// Create the first data key of a tenant
+    pub async fn create_tenant_key(&self, tenant_id: &str, actor: &str) -> Result<u32> {
+        let mut tenants = self.tenants.write().await;
+        if tenants.contains_key(tenant_id) {
+            return Err(anyhow::anyhow!("Tenant {} already has an encryption key", tenant_id));
+        }
+        let key = self.new_wrapped_key(tenant_id, 1)?;
+        tenants.insert(
+            tenant_id.to_string(),
+            TenantKeys {
+                active: 1,
+                versions: BTreeMap::from([(1, key)]),
+            },
+        );
+        drop(tenants);
+
+        self.record(tenant_id, 1, KeyAction::Created, actor, None).await;
+        Ok(1)
+    }
+
+    /// Rotate a tenant's data key, returning the new active version
+    ///
+    /// Older versions stay available for decryption until the records
+    /// encrypted under them have been re-encrypted.
+    pub async fn rotate_key(&self, tenant_id: &str, actor: &str) -> Result<u32> {
+        let mut tenants = self.tenants.write().await;
+        let keys = tenants
+            .get_mut(tenant_id)
+            .ok_or_else(|| anyhow::anyhow!("No encryption key for tenant {}", tenant_id))?;
+        let version = keys.versions.keys().next_back().copied().unwrap_or(0) + 1;
+        let key = self.new_wrapped_key(tenant_id, version)?;
+        keys.versions.insert(version, key);
+        keys.active = version;
+        drop(tenants);
+
+        tracing::info!("rotated encryption key of tenant {} to version {}", tenant_id, version);
+        self.record(tenant_id, version, KeyAction::Rotated, actor, None).await;
+        Ok(version)
+    }
+
+    /// Active key version of a tenant
+    pub async fn active_version(&self, tenant_id: &str) -> Option<u32> {
+        self.tenants.read().await.get(tenant_id).map(|keys| keys.active)
+    }
+
+    /// Wrapped key versions of a tenant, for persisting the keyring
+    pub async fn wrapped_keys(&self, tenant_id: &str) -> Vec<WrappedKey> {
+        self.tenants
+            .read()
+            .await
+            .get(tenant_id)
+            .map(|keys| keys.versions.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Encrypt a record under the tenant's active key
+    pub async fn encrypt(&self, tenant_id: &str, kind: &str, plaintext: &[u8], actor: &str) -> Result<EncryptedRecord> {
+        let (version, cipher) = {
+            let tenants = self.tenants.read().await;
+            let keys = tenants
+                .get(tenant_id)
+                .ok_or_else(|| anyhow::anyhow!("No encryption key for tenant {}", tenant_id))?;
+            (keys.active, self.data_key(tenant_id, &keys.versions[&keys.active])?)
+        };
+
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let aad = record_aad(tenant_id, kind, version);
+        let ciphertext = cipher
+            .encrypt(&nonce, Payload { msg: plaintext, aad: aad.as_bytes() })
+            .map_err(|_| anyhow::anyhow!("Encryption failed for tenant {}", tenant_id))?;
+
+        self.record(tenant_id, version, KeyAction::Encrypted, actor, Some(kind)).await;
+        Ok(EncryptedRecord {
+            tenant_id: tenant_id.to_string(),
+            kind: kind.to_string(),
+            key_version: version,
+            nonce: nonce.to_vec(),
+            ciphertext,
+        })
+    }
+
+    /// Decrypt a record with the key version it was encrypted under
+    ///
+    /// The tenant and kind are authenticated along with the ciphertext, so a
+    /// record relabelled with another tenant or kind fails to decrypt.
+    pub async fn decrypt(&self, record: &EncryptedRecord, actor: &str) -> Result<Vec<u8>> {
+        let cipher = {
+            let tenants = self.tenants.read().await;
+            let key = tenants
+                .get(&record.tenant_id)
+                .and_then(|keys| keys.versions.get(&record.key_version))
+                .ok_or_else(|| {
+                    anyhow::anyhow!("No key version {} for tenant {}", record.key_version, record.tenant_id)
+                })?;
+            self.data_key(&record.tenant_id, key)?
+        };
+
+        let aad = record_aad(&record.tenant_id, &record.kind, record.key_version);
+        let plaintext = if record.nonce.len() == NONCE_LEN {
+            cipher
+                .decrypt(
+                    Nonce::from_slice(&record.nonce),
+                    Payload { msg: &record.ciphertext, aad: aad.as_bytes() },
+                )
+                .ok()
+        } else {
+            None
+        };
+
+        match plaintext {
+            Some(plaintext) => {
+                self.record(&record.tenant_id, record.key_version, KeyAction::Decrypted, actor, Some(&record.kind))
+                    .await;
+                Ok(plaintext)
+            }
+            None => {
+                tracing::warn!("{} failed to decrypt a {} record of tenant {}", actor, record.kind, record.tenant_id);
+                self.record(&record.tenant_id, record.key_version, KeyAction::Denied, actor, Some(&record.kind))
+                    .await;
+                Err(anyhow::anyhow!("Cannot decrypt {} record of tenant {}", record.kind, record.tenant_id))
+            }
+        }
+    }
+
+    /// Re-encrypt a record under the tenant's active key
+    pub async fn reencrypt(&self, record: &EncryptedRecord, actor: &str) -> Result<EncryptedRecord> {
+        if self.active_version(&record.tenant_id).await == Some(record.key_version) {
+            return Ok(record.clone());
+        }
+        let plaintext = self.decrypt(record, actor).await?;
+        self.encrypt(&record.tenant_id, &record.kind, &plaintext, actor).await
+    }
+
+    /// Get the whole audit log
+    pub async fn audit_log(&self) -> Vec<KeyAuditEntry> {
+        self.audit.read().await.clone()
+    }
+
+    /// Get the audit log of one tenant
+    pub async fn audit_for(&self, tenant_id: &str) -> Vec<KeyAuditEntry> {
+        self.audit
+            .read()
+            .await
+            .iter()
+            .filter(|entry| entry.tenant_id == tenant_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Generate a data key and wrap it under the master key
+    fn new_wrapped_key(&self, tenant_id: &str, version: u32) -> Result<WrappedKey> {
+        let data_key = Aes256Gcm::generate_key(OsRng);
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let aad = key_aad(tenant_id, version);
+        let ciphertext = self
+            .master
+            .encrypt(&nonce, Payload { msg: data_key.as_slice(), aad: aad.as_bytes() })
+            .map_err(|_| anyhow::anyhow!("Wrapping key of tenant {} failed", tenant_id))?;
+        Ok(WrappedKey {
+            version,
+            nonce: nonce.to_vec(),
+            ciphertext,
+            created_at: now_secs(),
+        })
+    }
+
+    /// Unwrap a tenant data key
+    fn data_key(&self, tenant_id: &str, key: &WrappedKey) -> Result<Aes256Gcm> {
+        let aad = key_aad(tenant_id, key.version);
+        let data_key = self
+            .master
+            .decrypt(Nonce::from_slice(&key.nonce), Payload { msg: &key.ciphertext, aad: aad.as_bytes() })
+            .map_err(|_| anyhow::anyhow!("Unwrapping key of tenant {} failed", tenant_id))?;
+        Aes256Gcm::new_from_slice(&data_key).map_err(|_| anyhow::anyhow!("Invalid data key of tenant {}", tenant_id))
+    }
+
+    async fn record(&self, tenant_id: &str, key_version: u32, action: KeyAction, actor: &str, kind: Option<&str>) {
+        self.audit.write().await.push(KeyAuditEntry {
+            tenant_id: tenant_id.to_string(),
+            key_version,
+            action,
+            actor: actor.to_string(),
+            kind: kind.map(str::to_string),
+            timestamp: now_secs(),
+        });
+    }
+}
+
+/// Data authenticated with a record: its tenant, kind and key version
+fn record_aad(tenant_id: &str, kind: &str, version: u32) -> String {
+    format!("record:{}:{}:{}", tenant_id, kind, version)
+}
+
+/// Data authenticated with a wrapped key: its tenant and version
+fn key_aad(tenant_id: &str, version: u32) -> String {
+    format!("tenant-key:{}:{}", tenant_id, version)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn manager() -> TenantKeyManager {
+        let keys = TenantKeyManager::new(TenantKeyManager::generate_master_key());
+        keys.create_tenant_key("tenant-a", "admin").await.unwrap();
+        keys.create_tenant_key("tenant-b", "admin").await.unwrap();
+        keys
+    }
+
+    #[tokio::test]
+    async fn test_tenants_use_distinct_keys() -> Result<()> {
+        let keys = manager().await;
+        assert!(keys.create_tenant_key("tenant-a", "admin").await.is_err());
+        assert!(keys.encrypt("tenant-c", "orders", b"{}", "svc-orders").await.is_err());
+
+        let a = keys.encrypt("tenant-a", "orders", b"order-1", "svc-orders").await?;
+        let b = keys.encrypt("tenant-b", "orders", b"order-1", "svc-orders").await?;
+        assert_ne!(a.ciphertext, b.ciphertext);
+        assert_eq!(keys.decrypt(&a, "svc-orders").await?, b"order-1");
+
+        // A record relabelled as another tenant's, or moved to another kind, is refused
+        let relabelled = EncryptedRecord {
+            tenant_id: "tenant-b".to_string(),
+            ..a.clone()
+        };
+        assert!(keys.decrypt(&relabelled, "svc-orders").await.is_err());
+        let moved = EncryptedRecord {
+            kind: "reports".to_string(),
+            ..a.clone()
+        };
+        assert!(keys.decrypt(&moved, "svc-orders").await.is_err());
+
+        // A manager with another master key cannot read the keyring's records
+        let other = manager().await;
+        assert!(other.decrypt(&a, "svc-orders").await.is_err());
+
+        let denied: Vec<_> = keys
+            .audit_for("tenant-b")
+            .await
+            .into_iter()
+            .filter(|entry| entry.action == KeyAction::Denied)
+            .collect();
+        assert_eq!(denied.len(), 1);
+        assert_eq!(denied[0].actor, "svc-orders");
+        assert_eq!(denied[0].kind.as_deref(), Some("orders"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rotation_keeps_old_records_readable() -> Result<()> {
+        let keys = manager().await;
+        let old = keys.encrypt("tenant-a", "positions", b"position-1", "svc-portfolio").await?;
+        assert!(keys.rotate_key("tenant-x", "admin").await.is_err());
+
+        assert_eq!(keys.rotate_key("tenant-a", "admin").await?, 2);
+        assert_eq!(keys.active_version("tenant-a").await, Some(2));
+        assert_eq!(keys.active_version("tenant-b").await, Some(1));
+        assert_eq!(keys.wrapped_keys("tenant-a").await.len(), 2);
+
+        let new = keys.encrypt("tenant-a", "positions", b"position-2", "svc-portfolio").await?;
+        assert_eq!(new.key_version, 2);
+        assert_eq!(keys.decrypt(&old, "svc-portfolio").await?, b"position-1");
+
+        let migrated = keys.reencrypt(&old, "admin").await?;
+        assert_eq!(migrated.key_version, 2);
+        assert_eq!(keys.decrypt(&migrated, "svc-portfolio").await?, b"position-1");
+        assert_eq!(keys.reencrypt(&migrated, "admin").await?, migrated);
+
+        let actions: Vec<_> = keys
+            .audit_for("tenant-a")
+            .await
+            .into_iter()
+            .map(|entry| (entry.action, entry.key_version))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                (KeyAction::Created, 1),
+                (KeyAction::Encrypted, 1),
+                (KeyAction::Rotated, 2),
+                (KeyAction::Encrypted, 2),
+                (KeyAction::Decrypted, 1),
+                (KeyAction::Decrypted, 1),
+                (KeyAction::Encrypted, 2),
+                (KeyAction::Decrypted, 2),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_master_key_from_env() {
+        std::env::set_var("TEST_TENANT_MASTER_KEY", hex::encode([7u8; 32]));
+        assert!(TenantKeyManager::from_env("TEST_TENANT_MASTER_KEY").is_ok());
+        std::env::set_var("TEST_TENANT_MASTER_KEY_SHORT", "abcd");
+        assert!(TenantKeyManager::from_env("TEST_TENANT_MASTER_KEY_SHORT").is_err());
+        assert!(TenantKeyManager::from_env("TEST_TENANT_MASTER_KEY_UNSET").is_err());
+    }
+}
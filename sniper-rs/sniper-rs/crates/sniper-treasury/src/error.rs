@@ -0,0 +1,49 @@
+//! Errors returned by the treasury and funding managers.
+
+use crate::funding::FundingStatus;
+use sniper_core::errors::{DomainError, ErrorKind};
+use thiserror::Error;
+
+/// Failure of a treasury or funding operation
+#[derive(Debug, Clone, Error)]
+pub enum TreasuryError {
+    #[error("Account {0} not found")]
+    AccountNotFound(String),
+    #[error("Sweep rule {0} not found")]
+    SweepRuleNotFound(String),
+    #[error("Funding request {0} not found")]
+    RequestNotFound(String),
+    #[error("{0} amount must be positive")]
+    NonPositiveAmount(&'static str),
+    #[error("Insufficient balance")]
+    InsufficientBalance,
+    #[error("Cannot transfer to the same account")]
+    SameAccount,
+    #[error("Invalid sweep rule: {0}")]
+    InvalidSweepRule(&'static str),
+    #[error("Account {0} belongs to a different tenant")]
+    CrossTenant(String),
+    #[error("Funding request {id} is {status:?} and cannot be {action}")]
+    RequestClosed { id: String, status: FundingStatus, action: &'static str },
+    #[error("User is not allowed to {action} funding request {id}")]
+    NotApprover { id: String, action: &'static str },
+    #[error("User has already approved funding request {0}")]
+    AlreadyApproved(String),
+}
+
+impl DomainError for TreasuryError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            TreasuryError::AccountNotFound(_)
+            | TreasuryError::SweepRuleNotFound(_)
+            | TreasuryError::RequestNotFound(_) => ErrorKind::NotFound,
+            TreasuryError::NonPositiveAmount(_)
+            | TreasuryError::SameAccount
+            | TreasuryError::InvalidSweepRule(_) => ErrorKind::Invalid,
+            TreasuryError::InsufficientBalance
+            | TreasuryError::RequestClosed { .. }
+            | TreasuryError::AlreadyApproved(_) => ErrorKind::Conflict,
+            TreasuryError::CrossTenant(_) | TreasuryError::NotApprover { .. } => ErrorKind::Forbidden,
+        }
+    }
+}
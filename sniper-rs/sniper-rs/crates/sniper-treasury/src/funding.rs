@@ -0,0 +1,483 @@
+//! Deposit and withdrawal workflow for the treasury.
+//!
+//! Funding requests move through requested, approved, broadcast and confirmed
+//! states. Withdrawals above the approval policy limit need sign-off from
+//! users holding the approver permission, and broadcast requests are
+//! reconciled against transfers observed on-chain before balances change.
+
+use crate::{TreasuryError, TreasuryManager};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sniper_users::redaction::mask_address;
+use sniper_users::{ApprovalPolicy, Redact, UserContext};
+use std::collections::HashMap;
+
+/// Direction of a funding request
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum FundingDirection {
+    Deposit,
+    Withdrawal,
+}
+
+/// Funding request status
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum FundingStatus {
+    Requested,
+    Approved,
+    Broadcast,
+    Confirmed,
+    Rejected,
+}
+
+/// A deposit into or withdrawal from a treasury account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundingRequest {
+    pub id: String,
+    pub direction: FundingDirection,
+    pub account_id: String,
+    pub asset: String,
+    pub amount: f64,
+    /// Counterparty address funds come from or go to
+    pub external_address: String,
+    pub status: FundingStatus,
+    pub requested_by: String,
+    pub approvals: Vec<String>,
+    pub approvals_required: usize,
+    pub tx_hash: Option<String>,
+    pub rejection_reason: Option<String>,
+    pub tenant_id: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Transfer observed on-chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnChainTransfer {
+    pub tx_hash: String,
+    pub from_address: String,
+    pub to_address: String,
+    pub asset: String,
+    pub amount: f64,
+    pub block: u64,
+}
+
+/// Outcome of reconciling funding requests against on-chain transfers
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReconciliationReport {
+    /// Requests confirmed by a matching transfer
+    pub confirmed: Vec<String>,
+    /// Broadcast requests with no matching transfer yet
+    pub pending: Vec<String>,
+    /// Requests whose transfer amount or asset differs from the request
+    pub mismatched: Vec<String>,
+    /// Transfers touching treasury accounts with no matching request
+    pub unmatched_transfers: Vec<OnChainTransfer>,
+}
+
+/// Tolerance when comparing requested and transferred amounts
+const AMOUNT_TOLERANCE: f64 = 1e-9;
+
+/// Funding manager driving deposit and withdrawal requests
+pub struct FundingManager {
+    requests: HashMap<String, FundingRequest>,
+    policy: ApprovalPolicy,
+}
+
+impl FundingManager {
+    /// Create a new funding manager
+    pub fn new(policy: ApprovalPolicy) -> Self {
+        Self {
+            requests: HashMap::new(),
+            policy,
+        }
+    }
+
+    /// Get the approval policy
+    pub fn policy(&self) -> &ApprovalPolicy {
+        &self.policy
+    }
+
+    /// Request a deposit into a treasury account
+    pub fn request_deposit(
+        &mut self,
+        treasury: &TreasuryManager,
+        account_id: &str,
+        asset: &str,
+        amount: f64,
+        from_address: &str,
+        requester: &UserContext,
+    ) -> Result<FundingRequest, TreasuryError> {
+        // Deposits bring funds in and never need approval
+        self.create_request(treasury, FundingDirection::Deposit, account_id, asset, amount, from_address, requester, 0)
+    }
+
+    /// Request a withdrawal from a treasury account
+    pub fn request_withdrawal(
+        &mut self,
+        treasury: &TreasuryManager,
+        account_id: &str,
+        asset: &str,
+        amount: f64,
+        to_address: &str,
+        requester: &UserContext,
+    ) -> Result<FundingRequest, TreasuryError> {
+        if treasury.get_balance(account_id, asset) < amount {
+            return Err(TreasuryError::InsufficientBalance);
+        }
+        let approvals_required = self.policy.approvals_required(amount);
+        self.create_request(treasury, FundingDirection::Withdrawal, account_id, asset, amount, to_address, requester, approvals_required)
+    }
+
+    /// Approve a funding request
+    pub fn approve(&mut self, request_id: &str, approver: &UserContext) -> Result<FundingRequest, TreasuryError> {
+        let request = self.requests.get_mut(request_id)
+            .ok_or_else(|| TreasuryError::RequestNotFound(request_id.to_string()))?;
+
+        if request.status != FundingStatus::Requested {
+            return Err(TreasuryError::RequestClosed {
+                id: request.id.clone(),
+                status: request.status.clone(),
+                action: "approved",
+            });
+        }
+        if !self.policy.can_approve(approver, &request.requested_by, &request.tenant_id) {
+            return Err(TreasuryError::NotApprover {
+                id: request.id.clone(),
+                action: "approve",
+            });
+        }
+        if request.approvals.contains(&approver.user_id) {
+            return Err(TreasuryError::AlreadyApproved(request.id.clone()));
+        }
+
+        request.approvals.push(approver.user_id.clone());
+        if request.approvals.len() >= request.approvals_required {
+            request.status = FundingStatus::Approved;
+        }
+        request.updated_at = Utc::now();
+        Ok(request.clone())
+    }
+
+    /// Reject a funding request
+    pub fn reject(&mut self, request_id: &str, approver: &UserContext, reason: &str) -> Result<FundingRequest, TreasuryError> {
+        let request = self.requests.get_mut(request_id)
+            .ok_or_else(|| TreasuryError::RequestNotFound(request_id.to_string()))?;
+
+        if !matches!(request.status, FundingStatus::Requested | FundingStatus::Approved) {
+            return Err(TreasuryError::RequestClosed {
+                id: request.id.clone(),
+                status: request.status.clone(),
+                action: "rejected",
+            });
+        }
+        if !self.policy.can_approve(approver, &request.requested_by, &request.tenant_id) {
+            return Err(TreasuryError::NotApprover {
+                id: request.id.clone(),
+                action: "reject",
+            });
+        }
+
+        request.status = FundingStatus::Rejected;
+        request.rejection_reason = Some(reason.to_string());
+        request.updated_at = Utc::now();
+        Ok(request.clone())
+    }
+
+    /// Record the transaction hash of an approved request
+    pub fn mark_broadcast(&mut self, request_id: &str, tx_hash: &str) -> Result<FundingRequest, TreasuryError> {
+        let request = self.requests.get_mut(request_id)
+            .ok_or_else(|| TreasuryError::RequestNotFound(request_id.to_string()))?;
+
+        if request.status != FundingStatus::Approved {
+            return Err(TreasuryError::RequestClosed {
+                id: request.id.clone(),
+                status: request.status.clone(),
+                action: "broadcast",
+            });
+        }
+
+        request.status = FundingStatus::Broadcast;
+        request.tx_hash = Some(tx_hash.to_string());
+        request.updated_at = Utc::now();
+        Ok(request.clone())
+    }
+
+    /// Reconcile broadcast requests against observed on-chain transfers
+    ///
+    /// Matching requests are confirmed and applied to treasury balances.
+    pub fn reconcile(
+        &mut self,
+        treasury: &mut TreasuryManager,
+        transfers: &[OnChainTransfer],
+    ) -> ReconciliationReport {
+        let mut report = ReconciliationReport::default();
+        let by_hash: HashMap<&str, &OnChainTransfer> = transfers
+            .iter()
+            .map(|transfer| (transfer.tx_hash.as_str(), transfer))
+            .collect();
+
+        let mut matched_hashes = Vec::new();
+        for request in self.requests.values_mut() {
+            if request.status != FundingStatus::Broadcast {
+                continue;
+            }
+            let tx_hash = match &request.tx_hash {
+                Some(tx_hash) => tx_hash.clone(),
+                None => continue,
+            };
+
+            let transfer = match by_hash.get(tx_hash.as_str()) {
+                Some(transfer) => *transfer,
+                None => {
+                    report.pending.push(request.id.clone());
+                    continue;
+                }
+            };
+            matched_hashes.push(tx_hash);
+
+            if transfer.asset != request.asset || (transfer.amount - request.amount).abs() > AMOUNT_TOLERANCE {
+                report.mismatched.push(request.id.clone());
+                continue;
+            }
+
+            let applied = match request.direction {
+                FundingDirection::Deposit => treasury.deposit(&request.account_id, &request.asset, request.amount, &request.requested_by),
+                FundingDirection::Withdrawal => treasury.withdraw(&request.account_id, &request.asset, request.amount, &request.requested_by),
+            };
+            match applied {
+                Ok(_) => {
+                    request.status = FundingStatus::Confirmed;
+                    request.updated_at = Utc::now();
+                    report.confirmed.push(request.id.clone());
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to apply funding request {}: {}", request.id, e);
+                    report.mismatched.push(request.id.clone());
+                }
+            }
+        }
+
+        // Flag transfers touching our accounts that no request accounts for
+        let treasury_addresses: Vec<&str> = self.requests
+            .values()
+            .filter_map(|request| treasury.get_account(&request.account_id))
+            .map(|account| account.address.as_str())
+            .collect();
+        report.unmatched_transfers = transfers
+            .iter()
+            .filter(|transfer| !matched_hashes.contains(&transfer.tx_hash))
+            .filter(|transfer| {
+                treasury_addresses.contains(&transfer.from_address.as_str())
+                    || treasury_addresses.contains(&transfer.to_address.as_str())
+            })
+            .cloned()
+            .collect();
+
+        report
+    }
+
+    /// Get a funding request by ID
+    pub fn get_request(&self, request_id: &str) -> Option<&FundingRequest> {
+        self.requests.get(request_id)
+    }
+
+    /// List funding requests for a tenant
+    pub fn list_tenant_requests(&self, tenant_id: &str) -> Vec<&FundingRequest> {
+        self.requests
+            .values()
+            .filter(|request| request.tenant_id == tenant_id)
+            .collect()
+    }
+
+    /// Build and store a new funding request
+    #[allow(clippy::too_many_arguments)]
+    fn create_request(
+        &mut self,
+        treasury: &TreasuryManager,
+        direction: FundingDirection,
+        account_id: &str,
+        asset: &str,
+        amount: f64,
+        external_address: &str,
+        requester: &UserContext,
+        approvals_required: usize,
+    ) -> Result<FundingRequest, TreasuryError> {
+        if amount <= 0.0 {
+            return Err(TreasuryError::NonPositiveAmount("Funding"));
+        }
+        let account = treasury.get_account(account_id)
+            .ok_or_else(|| TreasuryError::AccountNotFound(account_id.to_string()))?;
+        if account.tenant_id != requester.tenant_id {
+            return Err(TreasuryError::CrossTenant(account_id.to_string()));
+        }
+
+        let status = if approvals_required == 0 {
+            FundingStatus::Approved
+        } else {
+            FundingStatus::Requested
+        };
+
+        let request = FundingRequest {
+            id: uuid::Uuid::new_v4().to_string(),
+            direction,
+            account_id: account_id.to_string(),
+            asset: asset.to_string(),
+            amount,
+            external_address: external_address.to_string(),
+            status,
+            requested_by: requester.user_id.clone(),
+            approvals: Vec::new(),
+            approvals_required,
+            tx_hash: None,
+            rejection_reason: None,
+            tenant_id: account.tenant_id.clone(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        self.requests.insert(request.id.clone(), request.clone());
+        Ok(request)
+    }
+}
+
+impl Redact for FundingRequest {
+    fn redact_fields(&mut self, _viewer: &UserContext) {
+        self.external_address = mask_address(&self.external_address);
+    }
+}
+
+impl Default for FundingManager {
+    fn default() -> Self {
+        Self::new(ApprovalPolicy::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AccountKind;
+    use sniper_core::types::ChainRef;
+    use sniper_users::UserRole;
+
+    fn context(user_id: &str, roles: Vec<UserRole>, permissions: Vec<&str>) -> UserContext {
+        UserContext {
+            user_id: user_id.to_string(),
+            tenant_id: "tenant-1".to_string(),
+            roles,
+            permissions: permissions.into_iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    fn setup() -> (TreasuryManager, String) {
+        let mut treasury = TreasuryManager::new();
+        let account = treasury.create_account(
+            "hot",
+            AccountKind::HotWallet,
+            ChainRef { name: "ethereum".to_string(), id: 1 },
+            "onchain",
+            "0xHot",
+            "tenant-1",
+            "ops",
+        );
+        treasury.deposit(&account.id, "USDC", 50000.0, "ops").unwrap();
+        (treasury, account.id)
+    }
+
+    #[test]
+    fn test_small_withdrawal_skips_approval() {
+        let (treasury, account_id) = setup();
+        let mut funding = FundingManager::new(ApprovalPolicy::new(1000.0, 2));
+        let trader = context("trader", vec![UserRole::Trader], vec!["execute_trades"]);
+
+        let request = funding.request_withdrawal(&treasury, &account_id, "USDC", 500.0, "0xExternal", &trader).unwrap();
+        assert_eq!(request.status, FundingStatus::Approved);
+        assert_eq!(request.approvals_required, 0);
+    }
+
+    #[test]
+    fn test_large_withdrawal_requires_approvals() {
+        let (treasury, account_id) = setup();
+        let mut funding = FundingManager::new(ApprovalPolicy::new(1000.0, 2));
+        let trader = context("trader", vec![UserRole::Trader], vec!["execute_trades"]);
+        let admin1 = context("admin1", vec![UserRole::Admin], vec!["approve_withdrawals"]);
+        let admin2 = context("admin2", vec![UserRole::Admin], vec!["approve_withdrawals"]);
+        let analyst = context("analyst", vec![UserRole::Analyst], vec!["view_reports"]);
+
+        let request = funding.request_withdrawal(&treasury, &account_id, "USDC", 5000.0, "0xExternal", &trader).unwrap();
+        assert_eq!(request.status, FundingStatus::Requested);
+
+        // Can't broadcast before approval
+        assert!(funding.mark_broadcast(&request.id, "0xabc").is_err());
+        assert!(funding.approve(&request.id, &analyst).is_err());
+
+        let request = funding.approve(&request.id, &admin1).unwrap();
+        assert_eq!(request.status, FundingStatus::Requested);
+        assert!(funding.approve(&request.id, &admin1).is_err());
+
+        let request = funding.approve(&request.id, &admin2).unwrap();
+        assert_eq!(request.status, FundingStatus::Approved);
+    }
+
+    #[test]
+    fn test_withdrawal_rejection() {
+        let (treasury, account_id) = setup();
+        let mut funding = FundingManager::new(ApprovalPolicy::new(1000.0, 1));
+        let trader = context("trader", vec![UserRole::Trader], vec!["execute_trades"]);
+        let admin = context("admin", vec![UserRole::Admin], vec!["approve_withdrawals"]);
+
+        let request = funding.request_withdrawal(&treasury, &account_id, "USDC", 5000.0, "0xExternal", &trader).unwrap();
+        let request = funding.reject(&request.id, &admin, "unknown destination").unwrap();
+        assert_eq!(request.status, FundingStatus::Rejected);
+        assert!(funding.approve(&request.id, &admin).is_err());
+    }
+
+    #[test]
+    fn test_reconciliation() {
+        let (mut treasury, account_id) = setup();
+        let mut funding = FundingManager::new(ApprovalPolicy::new(1000.0, 2));
+        let trader = context("trader", vec![UserRole::Trader], vec!["execute_trades"]);
+
+        let withdrawal = funding.request_withdrawal(&treasury, &account_id, "USDC", 500.0, "0xExternal", &trader).unwrap();
+        let deposit = funding.request_deposit(&treasury, &account_id, "USDC", 200.0, "0xFunder", &trader).unwrap();
+        let pending = funding.request_withdrawal(&treasury, &account_id, "USDC", 100.0, "0xExternal", &trader).unwrap();
+        funding.mark_broadcast(&withdrawal.id, "0x01").unwrap();
+        funding.mark_broadcast(&deposit.id, "0x02").unwrap();
+        funding.mark_broadcast(&pending.id, "0x03").unwrap();
+
+        let transfers = vec![
+            OnChainTransfer {
+                tx_hash: "0x01".to_string(),
+                from_address: "0xHot".to_string(),
+                to_address: "0xExternal".to_string(),
+                asset: "USDC".to_string(),
+                amount: 500.0,
+                block: 100,
+            },
+            OnChainTransfer {
+                tx_hash: "0x02".to_string(),
+                from_address: "0xFunder".to_string(),
+                to_address: "0xHot".to_string(),
+                asset: "USDC".to_string(),
+                amount: 200.0,
+                block: 101,
+            },
+            OnChainTransfer {
+                tx_hash: "0x99".to_string(),
+                from_address: "0xHot".to_string(),
+                to_address: "0xUnknown".to_string(),
+                asset: "USDC".to_string(),
+                amount: 42.0,
+                block: 102,
+            },
+        ];
+
+        let report = funding.reconcile(&mut treasury, &transfers);
+        assert_eq!(report.confirmed.len(), 2);
+        assert_eq!(report.pending, vec![pending.id.clone()]);
+        assert_eq!(report.unmatched_transfers.len(), 1);
+        assert_eq!(report.unmatched_transfers[0].tx_hash, "0x99");
+
+        assert_eq!(funding.get_request(&withdrawal.id).unwrap().status, FundingStatus::Confirmed);
+        assert_eq!(treasury.get_balance(&account_id, "USDC"), 49700.0);
+    }
+}
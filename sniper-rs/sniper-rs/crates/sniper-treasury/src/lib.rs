@@ -0,0 +1,685 @@
+//! Treasury and balance management for the sniper-rs enterprise features.
+//!
+//! This module tracks balances across wallets, chains and venues, and provides
+//! internal transfers, sweep rules for consolidating profits into cold storage,
+//! low-balance alerts and an audit trail of every treasury action.
+
+pub mod error;
+pub mod funding;
+
+pub use error::TreasuryError;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sniper_core::types::ChainRef;
+use std::collections::HashMap;
+
+/// Kind of treasury account
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum AccountKind {
+    HotWallet,
+    ColdWallet,
+    Exchange,
+}
+
+/// A wallet or venue account holding funds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreasuryAccount {
+    pub id: String,
+    pub name: String,
+    pub kind: AccountKind,
+    pub chain: ChainRef,
+    pub venue: String,
+    pub address: String,
+    pub tenant_id: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Balance of a single asset in an account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Balance {
+    pub account_id: String,
+    pub asset: String,
+    pub amount: f64,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Reason an internal transfer was made
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum TransferReason {
+    Manual,
+    Sweep { rule_id: String },
+}
+
+/// Internal transfer between two treasury accounts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InternalTransfer {
+    pub id: String,
+    pub from_account: String,
+    pub to_account: String,
+    pub asset: String,
+    pub amount: f64,
+    pub reason: TransferReason,
+    pub initiated_by: String,
+    pub tenant_id: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Rule consolidating funds above a threshold into a cold wallet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepRule {
+    pub id: String,
+    pub source_account: String,
+    pub destination_account: String,
+    pub asset: String,
+    /// Balance above which the sweep triggers
+    pub threshold: f64,
+    /// Balance left in the source account after a sweep
+    pub retain_amount: f64,
+    pub enabled: bool,
+    pub tenant_id: String,
+}
+
+/// Minimum balance an account is expected to hold for an asset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LowBalanceThreshold {
+    pub account_id: String,
+    pub asset: String,
+    pub min_amount: f64,
+}
+
+/// Alert raised when a balance falls below its threshold
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LowBalanceAlert {
+    pub account_id: String,
+    pub asset: String,
+    pub balance: f64,
+    pub min_amount: f64,
+    pub triggered_at: DateTime<Utc>,
+}
+
+/// Treasury actions recorded in the audit trail
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum TreasuryAction {
+    AccountCreated,
+    Deposit,
+    Withdrawal,
+    Transfer,
+    Sweep,
+    SweepRuleCreated,
+    SweepRuleUpdated,
+    ThresholdSet,
+}
+
+/// Audit trail entry for a treasury action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreasuryAuditEntry {
+    pub id: String,
+    pub tenant_id: String,
+    pub actor: String,
+    pub action: TreasuryAction,
+    pub account_id: Option<String>,
+    pub details: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Treasury manager tracking accounts, balances and movements
+pub struct TreasuryManager {
+    accounts: HashMap<String, TreasuryAccount>,
+    balances: HashMap<String, HashMap<String, Balance>>,
+    transfers: Vec<InternalTransfer>,
+    sweep_rules: HashMap<String, SweepRule>,
+    thresholds: HashMap<String, LowBalanceThreshold>,
+    audit_trail: Vec<TreasuryAuditEntry>,
+}
+
+impl TreasuryManager {
+    /// Create a new treasury manager
+    pub fn new() -> Self {
+        Self {
+            accounts: HashMap::new(),
+            balances: HashMap::new(),
+            transfers: Vec::new(),
+            sweep_rules: HashMap::new(),
+            thresholds: HashMap::new(),
+            audit_trail: Vec::new(),
+        }
+    }
+
+    /// Register a new treasury account
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_account(
+        &mut self,
+        name: &str,
+        kind: AccountKind,
+        chain: ChainRef,
+        venue: &str,
+        address: &str,
+        tenant_id: &str,
+        actor: &str,
+    ) -> TreasuryAccount {
+        let account = TreasuryAccount {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            kind,
+            chain,
+            venue: venue.to_string(),
+            address: address.to_string(),
+            tenant_id: tenant_id.to_string(),
+            created_at: Utc::now(),
+        };
+
+        self.accounts.insert(account.id.clone(), account.clone());
+        self.record_audit(
+            tenant_id,
+            actor,
+            TreasuryAction::AccountCreated,
+            Some(&account.id),
+            format!("Created {:?} account {} on {}/{}", account.kind, account.name, account.chain.name, account.venue),
+        );
+        account
+    }
+
+    /// Get an account by ID
+    pub fn get_account(&self, account_id: &str) -> Option<&TreasuryAccount> {
+        self.accounts.get(account_id)
+    }
+
+    /// List accounts for a tenant
+    pub fn list_tenant_accounts(&self, tenant_id: &str) -> Vec<&TreasuryAccount> {
+        self.accounts
+            .values()
+            .filter(|account| account.tenant_id == tenant_id)
+            .collect()
+    }
+
+    /// Get the balance of an asset in an account
+    pub fn get_balance(&self, account_id: &str, asset: &str) -> f64 {
+        self.balances
+            .get(account_id)
+            .and_then(|assets| assets.get(asset))
+            .map(|balance| balance.amount)
+            .unwrap_or(0.0)
+    }
+
+    /// List all asset balances held by an account
+    pub fn list_account_balances(&self, account_id: &str) -> Vec<&Balance> {
+        self.balances
+            .get(account_id)
+            .map(|assets| assets.values().collect())
+            .unwrap_or_default()
+    }
+
+    /// Total balance of an asset across all accounts of a tenant
+    pub fn total_tenant_balance(&self, tenant_id: &str, asset: &str) -> f64 {
+        self.list_tenant_accounts(tenant_id)
+            .iter()
+            .map(|account| self.get_balance(&account.id, asset))
+            .sum()
+    }
+
+    /// Record funds arriving in an account
+    pub fn deposit(&mut self, account_id: &str, asset: &str, amount: f64, actor: &str) -> Result<Balance, TreasuryError> {
+        if amount <= 0.0 {
+            return Err(TreasuryError::NonPositiveAmount("Deposit"));
+        }
+        let tenant_id = self.account_tenant(account_id)?;
+
+        let balance = self.credit(account_id, asset, amount);
+        self.record_audit(
+            &tenant_id,
+            actor,
+            TreasuryAction::Deposit,
+            Some(account_id),
+            format!("Deposited {} {}", amount, asset),
+        );
+        Ok(balance)
+    }
+
+    /// Record funds leaving an account
+    pub fn withdraw(&mut self, account_id: &str, asset: &str, amount: f64, actor: &str) -> Result<Balance, TreasuryError> {
+        if amount <= 0.0 {
+            return Err(TreasuryError::NonPositiveAmount("Withdrawal"));
+        }
+        let tenant_id = self.account_tenant(account_id)?;
+
+        let balance = self.debit(account_id, asset, amount)?;
+        self.record_audit(
+            &tenant_id,
+            actor,
+            TreasuryAction::Withdrawal,
+            Some(account_id),
+            format!("Withdrew {} {}", amount, asset),
+        );
+        Ok(balance)
+    }
+
+    /// Move funds between two accounts of the same tenant
+    pub fn transfer(
+        &mut self,
+        from_account: &str,
+        to_account: &str,
+        asset: &str,
+        amount: f64,
+        actor: &str,
+    ) -> Result<InternalTransfer, TreasuryError> {
+        self.execute_transfer(from_account, to_account, asset, amount, TransferReason::Manual, actor)
+    }
+
+    /// List internal transfers for a tenant
+    pub fn list_tenant_transfers(&self, tenant_id: &str) -> Vec<&InternalTransfer> {
+        self.transfers
+            .iter()
+            .filter(|transfer| transfer.tenant_id == tenant_id)
+            .collect()
+    }
+
+    /// Create a sweep rule consolidating funds into a cold wallet
+    pub fn create_sweep_rule(
+        &mut self,
+        source_account: &str,
+        destination_account: &str,
+        asset: &str,
+        threshold: f64,
+        retain_amount: f64,
+        actor: &str,
+    ) -> Result<SweepRule, TreasuryError> {
+        let source = self.get_account(source_account)
+            .ok_or_else(|| TreasuryError::AccountNotFound(source_account.to_string()))?;
+        let destination = self.get_account(destination_account)
+            .ok_or_else(|| TreasuryError::AccountNotFound(destination_account.to_string()))?;
+
+        if destination.kind != AccountKind::ColdWallet {
+            return Err(TreasuryError::InvalidSweepRule("destination must be a cold wallet"));
+        }
+        if source.tenant_id != destination.tenant_id {
+            return Err(TreasuryError::CrossTenant(destination_account.to_string()));
+        }
+        if retain_amount < 0.0 || retain_amount > threshold {
+            return Err(TreasuryError::InvalidSweepRule("retain amount must be between zero and the threshold"));
+        }
+
+        let rule = SweepRule {
+            id: uuid::Uuid::new_v4().to_string(),
+            source_account: source_account.to_string(),
+            destination_account: destination_account.to_string(),
+            asset: asset.to_string(),
+            threshold,
+            retain_amount,
+            enabled: true,
+            tenant_id: source.tenant_id.clone(),
+        };
+
+        self.sweep_rules.insert(rule.id.clone(), rule.clone());
+        self.record_audit(
+            &rule.tenant_id,
+            actor,
+            TreasuryAction::SweepRuleCreated,
+            Some(source_account),
+            format!("Sweep rule {} created: {} above {} retaining {}", rule.id, rule.asset, threshold, retain_amount),
+        );
+        Ok(rule)
+    }
+
+    /// Get a sweep rule by ID
+    pub fn get_sweep_rule(&self, rule_id: &str) -> Option<&SweepRule> {
+        self.sweep_rules.get(rule_id)
+    }
+
+    /// List sweep rules for a tenant
+    pub fn list_tenant_sweep_rules(&self, tenant_id: &str) -> Vec<&SweepRule> {
+        self.sweep_rules
+            .values()
+            .filter(|rule| rule.tenant_id == tenant_id)
+            .collect()
+    }
+
+    /// Enable or disable a sweep rule
+    pub fn set_sweep_rule_enabled(&mut self, rule_id: &str, enabled: bool, actor: &str) -> Result<(), TreasuryError> {
+        let rule = self.sweep_rules.get_mut(rule_id)
+            .ok_or_else(|| TreasuryError::SweepRuleNotFound(rule_id.to_string()))?;
+        rule.enabled = enabled;
+        let tenant_id = rule.tenant_id.clone();
+        let source_account = rule.source_account.clone();
+
+        self.record_audit(
+            &tenant_id,
+            actor,
+            TreasuryAction::SweepRuleUpdated,
+            Some(&source_account),
+            format!("Sweep rule {} enabled: {}", rule_id, enabled),
+        );
+        Ok(())
+    }
+
+    /// Run all enabled sweep rules, returning the transfers made
+    pub fn run_sweeps(&mut self, actor: &str) -> Vec<InternalTransfer> {
+        let due: Vec<(SweepRule, f64)> = self.sweep_rules
+            .values()
+            .filter(|rule| rule.enabled)
+            .filter_map(|rule| {
+                let balance = self.get_balance(&rule.source_account, &rule.asset);
+                if balance > rule.threshold {
+                    Some((rule.clone(), balance - rule.retain_amount))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let mut swept = Vec::new();
+        for (rule, amount) in due {
+            let reason = TransferReason::Sweep { rule_id: rule.id.clone() };
+            match self.execute_transfer(&rule.source_account, &rule.destination_account, &rule.asset, amount, reason, actor) {
+                Ok(transfer) => swept.push(transfer),
+                Err(e) => tracing::warn!("Sweep rule {} failed: {}", rule.id, e),
+            }
+        }
+        swept
+    }
+
+    /// Set the minimum balance expected for an asset in an account
+    pub fn set_low_balance_threshold(
+        &mut self,
+        account_id: &str,
+        asset: &str,
+        min_amount: f64,
+        actor: &str,
+    ) -> Result<(), TreasuryError> {
+        let tenant_id = self.account_tenant(account_id)?;
+
+        self.thresholds.insert(
+            Self::threshold_key(account_id, asset),
+            LowBalanceThreshold {
+                account_id: account_id.to_string(),
+                asset: asset.to_string(),
+                min_amount,
+            },
+        );
+        self.record_audit(
+            &tenant_id,
+            actor,
+            TreasuryAction::ThresholdSet,
+            Some(account_id),
+            format!("Low balance threshold for {} set to {}", asset, min_amount),
+        );
+        Ok(())
+    }
+
+    /// Check balances of a tenant against their low-balance thresholds
+    pub fn check_low_balances(&self, tenant_id: &str) -> Vec<LowBalanceAlert> {
+        self.thresholds
+            .values()
+            .filter(|threshold| {
+                self.get_account(&threshold.account_id)
+                    .map(|account| account.tenant_id == tenant_id)
+                    .unwrap_or(false)
+            })
+            .filter_map(|threshold| {
+                let balance = self.get_balance(&threshold.account_id, &threshold.asset);
+                if balance < threshold.min_amount {
+                    Some(LowBalanceAlert {
+                        account_id: threshold.account_id.clone(),
+                        asset: threshold.asset.clone(),
+                        balance,
+                        min_amount: threshold.min_amount,
+                        triggered_at: Utc::now(),
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Get the audit trail for a tenant
+    pub fn get_tenant_audit_trail(&self, tenant_id: &str) -> Vec<&TreasuryAuditEntry> {
+        self.audit_trail
+            .iter()
+            .filter(|entry| entry.tenant_id == tenant_id)
+            .collect()
+    }
+
+    /// Get the audit trail for an account
+    pub fn get_account_audit_trail(&self, account_id: &str) -> Vec<&TreasuryAuditEntry> {
+        self.audit_trail
+            .iter()
+            .filter(|entry| entry.account_id.as_deref() == Some(account_id))
+            .collect()
+    }
+
+    /// Validate and apply a transfer between two accounts
+    fn execute_transfer(
+        &mut self,
+        from_account: &str,
+        to_account: &str,
+        asset: &str,
+        amount: f64,
+        reason: TransferReason,
+        actor: &str,
+    ) -> Result<InternalTransfer, TreasuryError> {
+        if amount <= 0.0 {
+            return Err(TreasuryError::NonPositiveAmount("Transfer"));
+        }
+        if from_account == to_account {
+            return Err(TreasuryError::SameAccount);
+        }
+        let from_tenant = self.account_tenant(from_account)?;
+        let to_tenant = self.account_tenant(to_account)?;
+        if from_tenant != to_tenant {
+            return Err(TreasuryError::CrossTenant(to_account.to_string()));
+        }
+
+        self.debit(from_account, asset, amount)?;
+        self.credit(to_account, asset, amount);
+
+        let transfer = InternalTransfer {
+            id: uuid::Uuid::new_v4().to_string(),
+            from_account: from_account.to_string(),
+            to_account: to_account.to_string(),
+            asset: asset.to_string(),
+            amount,
+            reason,
+            initiated_by: actor.to_string(),
+            tenant_id: from_tenant.clone(),
+            created_at: Utc::now(),
+        };
+        self.transfers.push(transfer.clone());
+
+        let action = match transfer.reason {
+            TransferReason::Manual => TreasuryAction::Transfer,
+            TransferReason::Sweep { .. } => TreasuryAction::Sweep,
+        };
+        self.record_audit(
+            &from_tenant,
+            actor,
+            action,
+            Some(from_account),
+            format!("Moved {} {} from {} to {}", amount, asset, from_account, to_account),
+        );
+        Ok(transfer)
+    }
+
+    /// Look up the tenant owning an account
+    fn account_tenant(&self, account_id: &str) -> Result<String, TreasuryError> {
+        self.get_account(account_id)
+            .map(|account| account.tenant_id.clone())
+            .ok_or_else(|| TreasuryError::AccountNotFound(account_id.to_string()))
+    }
+
+    /// Add funds to an account balance
+    fn credit(&mut self, account_id: &str, asset: &str, amount: f64) -> Balance {
+        let balance = self.balances
+            .entry(account_id.to_string())
+            .or_default()
+            .entry(asset.to_string())
+            .or_insert_with(|| Balance {
+                account_id: account_id.to_string(),
+                asset: asset.to_string(),
+                amount: 0.0,
+                updated_at: Utc::now(),
+            });
+        balance.amount += amount;
+        balance.updated_at = Utc::now();
+        balance.clone()
+    }
+
+    /// Remove funds from an account balance
+    fn debit(&mut self, account_id: &str, asset: &str, amount: f64) -> Result<Balance, TreasuryError> {
+        let balance = self.balances
+            .get_mut(account_id)
+            .and_then(|assets| assets.get_mut(asset))
+            .ok_or(TreasuryError::InsufficientBalance)?;
+        if balance.amount < amount {
+            return Err(TreasuryError::InsufficientBalance);
+        }
+        balance.amount -= amount;
+        balance.updated_at = Utc::now();
+        Ok(balance.clone())
+    }
+
+    /// Append an entry to the audit trail
+    fn record_audit(
+        &mut self,
+        tenant_id: &str,
+        actor: &str,
+        action: TreasuryAction,
+        account_id: Option<&str>,
+        details: String,
+    ) {
+        self.audit_trail.push(TreasuryAuditEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            tenant_id: tenant_id.to_string(),
+            actor: actor.to_string(),
+            action,
+            account_id: account_id.map(|id| id.to_string()),
+            details,
+            timestamp: Utc::now(),
+        });
+    }
+
+    fn threshold_key(account_id: &str, asset: &str) -> String {
+        format!("{}:{}", account_id, asset)
+    }
+}
+
+impl Default for TreasuryManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ethereum() -> ChainRef {
+        ChainRef {
+            name: "ethereum".to_string(),
+            id: 1,
+        }
+    }
+
+    fn setup(manager: &mut TreasuryManager, tenant_id: &str) -> (TreasuryAccount, TreasuryAccount) {
+        let hot = manager.create_account("hot", AccountKind::HotWallet, ethereum(), "onchain", "0xHot", tenant_id, "ops");
+        let cold = manager.create_account("cold", AccountKind::ColdWallet, ethereum(), "onchain", "0xCold", tenant_id, "ops");
+        (hot, cold)
+    }
+
+    #[test]
+    fn test_deposit_and_withdraw() {
+        let mut manager = TreasuryManager::new();
+        let (hot, _) = setup(&mut manager, "tenant-1");
+
+        manager.deposit(&hot.id, "USDC", 1000.0, "ops").unwrap();
+        manager.withdraw(&hot.id, "USDC", 250.0, "ops").unwrap();
+        assert_eq!(manager.get_balance(&hot.id, "USDC"), 750.0);
+
+        assert!(manager.withdraw(&hot.id, "USDC", 1000.0, "ops").is_err());
+        assert!(manager.withdraw(&hot.id, "WETH", 1.0, "ops").is_err());
+        assert!(manager.deposit(&hot.id, "USDC", -1.0, "ops").is_err());
+        assert!(manager.deposit("missing", "USDC", 1.0, "ops").is_err());
+    }
+
+    #[test]
+    fn test_internal_transfer() {
+        let mut manager = TreasuryManager::new();
+        let (hot, cold) = setup(&mut manager, "tenant-1");
+        let (other_hot, _) = setup(&mut manager, "tenant-2");
+
+        manager.deposit(&hot.id, "USDC", 1000.0, "ops").unwrap();
+        let transfer = manager.transfer(&hot.id, &cold.id, "USDC", 400.0, "ops").unwrap();
+
+        assert_eq!(transfer.reason, TransferReason::Manual);
+        assert_eq!(manager.get_balance(&hot.id, "USDC"), 600.0);
+        assert_eq!(manager.get_balance(&cold.id, "USDC"), 400.0);
+        assert_eq!(manager.total_tenant_balance("tenant-1", "USDC"), 1000.0);
+
+        // Cross-tenant transfers are rejected
+        assert!(manager.transfer(&hot.id, &other_hot.id, "USDC", 100.0, "ops").is_err());
+        assert_eq!(manager.list_tenant_transfers("tenant-1").len(), 1);
+    }
+
+    #[test]
+    fn test_sweep_rules() {
+        let mut manager = TreasuryManager::new();
+        let (hot, cold) = setup(&mut manager, "tenant-1");
+
+        // Sweeps must target a cold wallet
+        assert!(manager.create_sweep_rule(&cold.id, &hot.id, "USDC", 1000.0, 200.0, "ops").is_err());
+
+        let rule = manager.create_sweep_rule(&hot.id, &cold.id, "USDC", 1000.0, 200.0, "ops").unwrap();
+
+        manager.deposit(&hot.id, "USDC", 900.0, "ops").unwrap();
+        assert!(manager.run_sweeps("scheduler").is_empty());
+
+        manager.deposit(&hot.id, "USDC", 600.0, "ops").unwrap();
+        let swept = manager.run_sweeps("scheduler");
+        assert_eq!(swept.len(), 1);
+        assert_eq!(swept[0].amount, 1300.0);
+        assert_eq!(swept[0].reason, TransferReason::Sweep { rule_id: rule.id.clone() });
+        assert_eq!(manager.get_balance(&hot.id, "USDC"), 200.0);
+        assert_eq!(manager.get_balance(&cold.id, "USDC"), 1300.0);
+
+        // Disabled rules don't sweep
+        manager.set_sweep_rule_enabled(&rule.id, false, "ops").unwrap();
+        manager.deposit(&hot.id, "USDC", 5000.0, "ops").unwrap();
+        assert!(manager.run_sweeps("scheduler").is_empty());
+    }
+
+    #[test]
+    fn test_low_balance_alerts() {
+        let mut manager = TreasuryManager::new();
+        let (hot, _) = setup(&mut manager, "tenant-1");
+
+        manager.set_low_balance_threshold(&hot.id, "ETH", 0.5, "ops").unwrap();
+        manager.deposit(&hot.id, "ETH", 0.2, "ops").unwrap();
+
+        let alerts = manager.check_low_balances("tenant-1");
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].account_id, hot.id);
+        assert_eq!(alerts[0].balance, 0.2);
+        assert!(manager.check_low_balances("tenant-2").is_empty());
+
+        manager.deposit(&hot.id, "ETH", 1.0, "ops").unwrap();
+        assert!(manager.check_low_balances("tenant-1").is_empty());
+    }
+
+    #[test]
+    fn test_audit_trail() {
+        let mut manager = TreasuryManager::new();
+        let (hot, cold) = setup(&mut manager, "tenant-1");
+
+        manager.deposit(&hot.id, "USDC", 100.0, "alice").unwrap();
+        manager.transfer(&hot.id, &cold.id, "USDC", 50.0, "bob").unwrap();
+
+        let trail = manager.get_tenant_audit_trail("tenant-1");
+        assert_eq!(trail.len(), 4);
+        assert_eq!(trail[2].action, TreasuryAction::Deposit);
+        assert_eq!(trail[2].actor, "alice");
+        assert_eq!(trail[3].action, TreasuryAction::Transfer);
+
+        assert_eq!(manager.get_account_audit_trail(&hot.id).len(), 3);
+        assert!(manager.get_tenant_audit_trail("tenant-2").is_empty());
+    }
+}
@@ -0,0 +1,449 @@
+//! Persistent, queryable audit log.
+//!
+//! An [`AuditStore`] keeps every entry in memory for querying and, when
+//! opened on a path, appends each entry to a JSON-lines file so the log
+//! survives restarts. The file is shared: trading services append the audit
+//! events of their managers to it through an [`AuditStore::append_only`]
+//! writer fed by [`spawn_audit_listener`], and a store reading the file picks
+//! their entries up with [`AuditStore::refresh`]. The file is only ever
+//! appended to, except when a [`RetentionPolicy`] drops expired entries: the
+//! survivors are then written to a temporary file that atomically replaces the
+//! log, so retention should be applied by a single service. Other services
+//! read the same file to build reports, e.g. svc-compliance for trade audits.
+
+use crate::error::UserError;
+use crate::AuditLog;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+use sniper_core::audit::{AuditEvent, AUDIT_SUBJECT};
+use sniper_core::bus::InMemoryBus;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+
+/// How long audit entries are kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// Entries older than this many days are dropped
+    pub max_age_days: Option<i64>,
+    /// Only the newest entries up to this count are kept
+    pub max_entries: Option<usize>,
+}
+
+/// Filter over audit entries; unset fields match everything
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    pub user_id: Option<String>,
+    pub tenant_id: Option<String>,
+    pub action: Option<String>,
+    pub resource: Option<String>,
+    pub correlation_id: Option<String>,
+    /// Inclusive start of the time range
+    pub from: Option<DateTime<Utc>>,
+    /// Exclusive end of the time range
+    pub to: Option<DateTime<Utc>>,
+    /// Most entries to return, newest first
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    /// Entries of one tenant within `[from, to)`
+    pub fn tenant_range(tenant_id: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+        Self {
+            tenant_id: Some(tenant_id.to_string()),
+            from: Some(from),
+            to: Some(to),
+            ..Self::default()
+        }
+    }
+
+    /// Whether an entry passes the filter
+    pub fn matches(&self, entry: &AuditLog) -> bool {
+        let equals = |wanted: &Option<String>, value: &str| wanted.iter().all(|wanted| wanted == value);
+        equals(&self.user_id, &entry.user_id)
+            && equals(&self.tenant_id, &entry.tenant_id)
+            && equals(&self.action, &entry.action)
+            && equals(&self.resource, &entry.resource)
+            && self.correlation_id.iter().all(|wanted| entry.correlation_id.as_ref() == Some(wanted))
+            && self.from.iter().all(|&from| entry.timestamp >= from)
+            && self.to.iter().all(|&to| entry.timestamp < to)
+    }
+}
+
+impl From<AuditEvent> for AuditLog {
+    fn from(event: AuditEvent) -> Self {
+        AuditLog {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: event.actor,
+            tenant_id: event.tenant_id.unwrap_or_default(),
+            action: event.action,
+            resource: event.resource,
+            timestamp: DateTime::from_timestamp_millis(event.timestamp_ms).unwrap_or_else(Utc::now),
+            details: event.details,
+            correlation_id: Some(event.correlation_id),
+        }
+    }
+}
+
+/// Audit entries, optionally backed by an append-only file
+#[derive(Debug, Default)]
+pub struct AuditStore {
+    path: Option<PathBuf>,
+    entries: Vec<AuditLog>,
+    retention: RetentionPolicy,
+    /// Bytes of the file already loaded into `entries`
+    loaded: u64,
+    /// Whether entries are only written to the file, not kept
+    append_only: bool,
+}
+
+impl AuditStore {
+    /// Create a store that keeps entries in memory only
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Open the log at `path`, loading the entries already written to it
+    ///
+    /// Lines that don't parse are skipped.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let mut store = Self::append_only(path)?;
+        store.append_only = false;
+        store.refresh()?;
+        Ok(store)
+    }
+
+    /// Open the log at `path` for writing only, without loading or keeping entries
+    pub fn append_only(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(Self {
+            path: Some(path),
+            append_only: true,
+            ..Self::default()
+        })
+    }
+
+    /// Load the entries appended to the file since the last load, returning how many
+    ///
+    /// A last line without its newline may still be being written and is left
+    /// for the next load. If the file shrank, because another store applied
+    /// retention, it is loaded again from the start.
+    pub fn refresh(&mut self) -> Result<usize> {
+        let Some(path) = self.path.as_ref().filter(|_| !self.append_only) else {
+            return Ok(0);
+        };
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e).with_context(|| format!("Failed to open audit log {}", path.display())),
+        };
+        if file.metadata()?.len() < self.loaded {
+            self.entries.clear();
+            self.loaded = 0;
+        }
+        file.seek(SeekFrom::Start(self.loaded))?;
+
+        let mut reader = BufReader::new(file);
+        let mut line = String::new();
+        let mut loaded = 0;
+        loop {
+            line.clear();
+            let read = reader.read_line(&mut line)?;
+            if read == 0 || !line.ends_with('\n') {
+                break;
+            }
+            self.loaded += read as u64;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<AuditLog>(&line) {
+                Ok(entry) => {
+                    self.entries.push(entry);
+                    loaded += 1;
+                },
+                Err(e) => tracing::warn!("skipping entry of audit log {} at byte {}: {}", path.display(), self.loaded, e),
+            }
+        }
+        Ok(loaded)
+    }
+
+    /// Drop entries according to `retention` whenever it is applied
+    pub fn with_retention(mut self, retention: RetentionPolicy) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// File backing the store, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Append an entry, writing it through to the file
+    ///
+    /// A file-backed store then loads it back along with any entries other
+    /// writers appended in the meantime, so entries stay in file order.
+    pub fn append(&mut self, entry: AuditLog) -> Result<()> {
+        let Some(path) = &self.path else {
+            self.entries.push(entry);
+            return Ok(());
+        };
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        // One write per entry keeps concurrent appends from interleaving
+        OpenOptions::new().create(true).append(true).open(path)?.write_all(&line)?;
+        self.refresh()?;
+        Ok(())
+    }
+
+    /// All entries in the order they were logged
+    pub fn entries(&self) -> &Vec<AuditLog> {
+        &self.entries
+    }
+
+    /// Entries matching a query, newest first
+    pub fn query(&self, query: &AuditQuery) -> Vec<AuditLog> {
+        let mut matches: Vec<AuditLog> = self.entries.iter().filter(|entry| query.matches(entry)).cloned().collect();
+        matches.sort_by_key(|entry| std::cmp::Reverse(entry.timestamp));
+        if let Some(limit) = query.limit {
+            matches.truncate(limit);
+        }
+        matches
+    }
+
+    /// Drop entries outside the retention policy, returning how many were dropped
+    pub fn apply_retention(&mut self, now: DateTime<Utc>) -> Result<usize> {
+        self.refresh()?;
+        let before = self.entries.len();
+        if let Some(days) = self.retention.max_age_days {
+            let cutoff = now - Duration::days(days);
+            self.entries.retain(|entry| entry.timestamp >= cutoff);
+        }
+        if let Some(max_entries) = self.retention.max_entries {
+            let excess = self.entries.len().saturating_sub(max_entries);
+            self.entries.drain(..excess);
+        }
+
+        let dropped = before - self.entries.len();
+        if dropped > 0 {
+            self.rewrite()?;
+        }
+        Ok(dropped)
+    }
+
+    /// Export the entries matching a query as `json` or `csv`
+    pub fn export(&self, query: &AuditQuery, format: &str) -> Result<Vec<u8>> {
+        let entries = self.query(query);
+        match format {
+            "json" => Ok(serde_json::to_vec(&entries)?),
+            "csv" => {
+                let mut csv = String::from("id,timestamp,tenant_id,user_id,action,resource,details\n");
+                for entry in &entries {
+                    let fields = [
+                        entry.id.as_str(),
+                        &entry.timestamp.to_rfc3339(),
+                        &entry.tenant_id,
+                        &entry.user_id,
+                        &entry.action,
+                        &entry.resource,
+                        entry.details.as_deref().unwrap_or_default(),
+                    ];
+                    let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+                    csv.push_str(&row.join(","));
+                    csv.push('\n');
+                }
+                Ok(csv.into_bytes())
+            },
+            _ => Err(UserError::UnsupportedFormat(format.to_string()).into()),
+        }
+    }
+
+    /// Replace the file with the retained entries
+    fn rewrite(&mut self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let temp = path.with_extension("tmp");
+        {
+            let mut writer = BufWriter::new(File::create(&temp)?);
+            for entry in &self.entries {
+                serde_json::to_writer(&mut writer, entry)?;
+                writer.write_all(b"\n")?;
+            }
+            writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        }
+        fs::rename(&temp, path)?;
+        self.loaded = fs::metadata(path)?.len();
+        Ok(())
+    }
+}
+
+/// Append the audit events published on a bus to a store until the bus closes
+///
+/// Trading services pass an [`AuditStore::append_only`] writer on the shared
+/// audit log, so their managers' events become queryable next to user actions.
+pub fn spawn_audit_listener(bus: &InMemoryBus, mut store: AuditStore) -> JoinHandle<()> {
+    let mut rx = bus.subscribe(AUDIT_SUBJECT);
+    tokio::spawn(async move {
+        loop {
+            let bytes = match rx.recv().await {
+                Ok(bytes) => bytes,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::error!("Audit listener lagged, {} messages were not audited", skipped);
+                    continue;
+                },
+                Err(RecvError::Closed) => break,
+            };
+            // Other messages share the bus; only audit events are of interest
+            let Some(event) = AuditEvent::decode(&bytes) else {
+                continue;
+            };
+            let action = event.action.clone();
+            if let Err(e) = store.append(AuditLog::from(event)) {
+                tracing::error!("Failed to persist {} audit event: {}", action, e);
+            }
+        }
+    })
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(tenant_id: &str, action: &str, age_days: i64) -> AuditLog {
+        AuditLog {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: "user-1".to_string(),
+            tenant_id: tenant_id.to_string(),
+            action: action.to_string(),
+            resource: "orders".to_string(),
+            timestamp: Utc::now() - Duration::days(age_days),
+            details: Some("size 1,5 \"ETH\"".to_string()),
+            correlation_id: None,
+        }
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("sniper-audit-{}-{}.jsonl", name, uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let mut store = AuditStore::in_memory();
+        store.append(entry("tenant-1", "PLACE_ORDER", 3)).unwrap();
+        store.append(entry("tenant-1", "CANCEL_ORDER", 1)).unwrap();
+        store.append(entry("tenant-2", "PLACE_ORDER", 1)).unwrap();
+
+        let query = AuditQuery {
+            tenant_id: Some("tenant-1".to_string()),
+            ..AuditQuery::default()
+        };
+        let results = store.query(&query);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].action, "CANCEL_ORDER");
+
+        let recent = AuditQuery::tenant_range("tenant-1", Utc::now() - Duration::days(2), Utc::now());
+        assert_eq!(store.query(&recent).len(), 1);
+        let placed = AuditQuery {
+            action: Some("PLACE_ORDER".to_string()),
+            limit: Some(1),
+            ..AuditQuery::default()
+        };
+        assert_eq!(store.query(&placed)[0].tenant_id, "tenant-2");
+    }
+
+    #[test]
+    fn test_file_survives_reopen_and_retention() {
+        let path = temp_path("retention");
+        let mut store = AuditStore::open(&path).unwrap();
+        store.append(entry("tenant-1", "OLD", 40)).unwrap();
+        store.append(entry("tenant-1", "RECENT", 1)).unwrap();
+        drop(store);
+
+        // A torn last line is skipped rather than failing the load
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"id\":").unwrap();
+        let mut store = AuditStore::open(&path)
+            .unwrap()
+            .with_retention(RetentionPolicy {
+                max_age_days: Some(30),
+                max_entries: None,
+            });
+        assert_eq!(store.entries().len(), 2);
+
+        assert_eq!(store.apply_retention(Utc::now()).unwrap(), 1);
+        let reopened = AuditStore::open(&path).unwrap();
+        assert_eq!(reopened.entries().len(), 1);
+        assert_eq!(reopened.entries()[0].action, "RECENT");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_trading_events_reach_shared_file() {
+        let path = temp_path("shared");
+        let mut reader = AuditStore::open(&path).unwrap();
+        reader.append(entry("tenant-1", "LOGIN", 0)).unwrap();
+
+        let bus = InMemoryBus::new(16);
+        let listener = spawn_audit_listener(&bus, AuditStore::append_only(&path).unwrap());
+        for action in [sniper_core::audit::ORDER_CREATED, sniper_core::audit::TRADE_EXECUTED] {
+            let event = AuditEvent::new(action, "orders/order-1", "order-1").with_tenant(Some("tenant-1"));
+            bus.publish_now(AUDIT_SUBJECT, &event).unwrap();
+        }
+        drop(bus);
+        listener.await.unwrap();
+
+        assert_eq!(reader.refresh().unwrap(), 2);
+        let chain = AuditQuery {
+            correlation_id: Some("order-1".to_string()),
+            ..AuditQuery::default()
+        };
+        let results = reader.query(&chain);
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|entry| entry.tenant_id == "tenant-1" && entry.user_id == "system"));
+
+        // Entries appended by other writers survive retention passes
+        let mut writer = AuditStore::append_only(&path).unwrap();
+        writer.append(entry("tenant-1", "POSITION_OPENED", 0)).unwrap();
+        assert!(writer.entries().is_empty());
+        let mut retained = reader.with_retention(RetentionPolicy {
+            max_age_days: None,
+            max_entries: Some(3),
+        });
+        assert_eq!(retained.apply_retention(Utc::now()).unwrap(), 1);
+        assert_eq!(AuditStore::open(&path).unwrap().entries().len(), 3);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_export() {
+        let mut store = AuditStore::in_memory();
+        store.append(entry("tenant-1", "PLACE_ORDER", 0)).unwrap();
+
+        let csv = String::from_utf8(store.export(&AuditQuery::default(), "csv").unwrap()).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].ends_with(",PLACE_ORDER,orders,\"size 1,5 \"\"ETH\"\"\""));
+
+        let json: Vec<AuditLog> = serde_json::from_slice(&store.export(&AuditQuery::default(), "json").unwrap()).unwrap();
+        assert_eq!(json.len(), 1);
+        assert!(store.export(&AuditQuery::default(), "xml").is_err());
+    }
+}
@@ -0,0 +1,503 @@
+//! Token authentication for the svc-* binaries.
+//!
+//! svc-users issues HS256 JSON Web Tokens when a user authenticates; the
+//! claims carry the user's tenant, roles and the permissions the
+//! [`RBACManager`](crate::RBACManager) granted them at login. Services share
+//! the signing secret through [`JWT_SECRET_ENV`] and wrap their router in an
+//! [`AuthLayer`], which verifies the `Authorization: Bearer` token and
+//! injects the caller's [`UserContext`] into the request for the
+//! [`Viewer`](crate::http::Viewer) extractor. Requests without a token go
+//! through as anonymous; a token that fails verification is rejected with
+//! 401. Identity headers are dropped at the layer, so behind it the token is
+//! the only way to claim an identity. Mutating endpoints additionally sit
+//! behind [`require_permission`], answering 401 to anonymous callers and 403
+//! to callers without the permission. Tenant-scoped services put their
+//! routes behind [`require_tenant`], which admits only callers carrying a
+//! tenant. Verified callers are rate limited by tenant through the
+//! [`RateLimitKey`] the layer adds.
+
+use crate::{UserContext, UserRole};
+use crate::http::{PERMISSIONS_HEADER, TENANT_ID_HEADER, USER_ID_HEADER};
+use anyhow::Result;
+use sniper_core::config::AuthConfig;
+use sniper_core::rate_limit::RateLimitKey;
+use axum::http::{header::AUTHORIZATION, HeaderMap, Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use futures::future::{ready, Either, Ready};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Environment variable holding the token signing secret
+pub const JWT_SECRET_ENV: &str = "SNIPER_JWT_SECRET";
+
+/// Environment variable overriding the token lifetime, in seconds
+pub const JWT_TTL_ENV: &str = "SNIPER_JWT_TTL_SECS";
+
+/// Issuer written into and required of every token
+pub const JWT_ISSUER: &str = "sniper-users";
+
+/// Token lifetime when none is configured
+pub const DEFAULT_TOKEN_TTL_SECS: i64 = 3_600;
+
+/// Shortest signing secret accepted, in bytes
+pub const MIN_SECRET_LEN: usize = 32;
+
+/// Claims of an access token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenClaims {
+    /// User ID
+    pub sub: String,
+    pub tenant_id: String,
+    #[serde(default)]
+    pub roles: Vec<UserRole>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    pub iss: String,
+    /// Issue time, in seconds since the epoch
+    pub iat: i64,
+    /// Expiry, in seconds since the epoch
+    pub exp: i64,
+}
+
+impl From<TokenClaims> for UserContext {
+    fn from(claims: TokenClaims) -> Self {
+        Self {
+            user_id: claims.sub,
+            tenant_id: claims.tenant_id,
+            roles: claims.roles,
+            permissions: claims.permissions,
+        }
+    }
+}
+
+/// Access token handed out on authentication
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssuedToken {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TokenHeader {
+    alg: String,
+    #[serde(default)]
+    typ: Option<String>,
+}
+
+/// Issues and verifies HS256 access tokens
+pub struct JwtAuth {
+    secret: Vec<u8>,
+    issuer: String,
+    ttl_secs: i64,
+}
+
+impl JwtAuth {
+    /// Create a token authority signing with `secret`
+    pub fn new(secret: impl Into<Vec<u8>>) -> Result<Self> {
+        let secret = secret.into();
+        if secret.len() < MIN_SECRET_LEN {
+            return Err(anyhow::anyhow!(
+                "Token signing secret must be at least {} bytes, got {}",
+                MIN_SECRET_LEN,
+                secret.len()
+            ));
+        }
+        Ok(Self {
+            secret,
+            issuer: JWT_ISSUER.to_string(),
+            ttl_secs: DEFAULT_TOKEN_TTL_SECS,
+        })
+    }
+
+    /// Create a token authority from [`JWT_SECRET_ENV`] and [`JWT_TTL_ENV`]
+    pub fn from_env() -> Result<Self> {
+        let secret = std::env::var(JWT_SECRET_ENV)
+            .map_err(|_| anyhow::anyhow!("{} must be set to the token signing secret", JWT_SECRET_ENV))?;
+        let auth = Self::new(secret)?;
+        match std::env::var(JWT_TTL_ENV) {
+            Ok(ttl) => {
+                let ttl_secs = ttl
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("{} must be a number of seconds, got {}", JWT_TTL_ENV, ttl))?;
+                auth.with_ttl_secs(ttl_secs)
+            },
+            Err(_) => Ok(auth),
+        }
+    }
+
+    /// Create a token authority from a service's auth settings
+    ///
+    /// Without a secret in the configuration, the authority is created from
+    /// the environment as in [`JwtAuth::from_env`].
+    pub fn from_config(config: &AuthConfig) -> Result<Self> {
+        let Some(secret) = &config.jwt_secret else {
+            return Self::from_env();
+        };
+        let auth = Self::new(secret.as_str())?;
+        match config.jwt_ttl_secs {
+            Some(ttl_secs) => auth.with_ttl_secs(ttl_secs as i64),
+            None => Ok(auth),
+        }
+    }
+
+    /// Set the lifetime of issued tokens
+    pub fn with_ttl_secs(mut self, ttl_secs: i64) -> Result<Self> {
+        if ttl_secs <= 0 {
+            return Err(anyhow::anyhow!("Token lifetime must be positive"));
+        }
+        self.ttl_secs = ttl_secs;
+        Ok(self)
+    }
+
+    /// Issue a token for an authenticated user
+    pub fn issue(&self, context: &UserContext) -> Result<IssuedToken> {
+        self.issue_at(context, Utc::now())
+    }
+
+    fn issue_at(&self, context: &UserContext, now: DateTime<Utc>) -> Result<IssuedToken> {
+        if context.is_anonymous() {
+            return Err(anyhow::anyhow!("Cannot issue a token without a user"));
+        }
+        let expires_at = now + chrono::Duration::seconds(self.ttl_secs);
+        let claims = TokenClaims {
+            sub: context.user_id.clone(),
+            tenant_id: context.tenant_id.clone(),
+            roles: context.roles.clone(),
+            permissions: context.permissions.clone(),
+            iss: self.issuer.clone(),
+            iat: now.timestamp(),
+            exp: expires_at.timestamp(),
+        };
+        let header = TokenHeader {
+            alg: "HS256".to_string(),
+            typ: Some("JWT".to_string()),
+        };
+
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(&header)?),
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims)?)
+        );
+        let signature = URL_SAFE_NO_PAD.encode(self.mac(&signing_input).finalize().into_bytes());
+        Ok(IssuedToken {
+            token: format!("{}.{}", signing_input, signature),
+            expires_at,
+        })
+    }
+
+    /// Verify a token's signature, issuer and expiry, returning its claims
+    pub fn verify(&self, token: &str) -> Result<TokenClaims> {
+        self.verify_at(token, Utc::now())
+    }
+
+    fn verify_at(&self, token: &str, now: DateTime<Utc>) -> Result<TokenClaims> {
+        let mut parts = token.split('.');
+        let (Some(header), Some(claims), Some(signature), None) = (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(anyhow::anyhow!("Malformed token"));
+        };
+
+        let header: TokenHeader = serde_json::from_slice(&decode(header)?)?;
+        if header.alg != "HS256" {
+            return Err(anyhow::anyhow!("Unsupported token algorithm {}", header.alg));
+        }
+        let signing_input = &token[..token.len() - signature.len() - 1];
+        self.mac(signing_input)
+            .verify_slice(&decode(signature)?)
+            .map_err(|_| anyhow::anyhow!("Bad token signature"))?;
+
+        let claims: TokenClaims = serde_json::from_slice(&decode(claims)?)?;
+        if claims.iss != self.issuer {
+            return Err(anyhow::anyhow!("Token issued by {}", claims.iss));
+        }
+        if claims.exp <= now.timestamp() {
+            return Err(anyhow::anyhow!("Token expired"));
+        }
+        Ok(claims)
+    }
+
+    fn mac(&self, signing_input: &str) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(signing_input.as_bytes());
+        mac
+    }
+}
+
+fn decode(part: &str) -> Result<Vec<u8>> {
+    URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(|_| anyhow::anyhow!("Malformed token"))
+}
+
+/// Bearer token of a request, if it carries one
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
+
+/// Error response in the services' `ApiResponse` shape
+pub fn reject(status: StatusCode, message: String) -> Response {
+    let body = serde_json::json!({
+        "success": false,
+        "data": null,
+        "message": message,
+    });
+    (status, Json(body)).into_response()
+}
+
+/// Layer verifying bearer tokens and injecting the caller's [`UserContext`]
+#[derive(Clone)]
+pub struct AuthLayer {
+    auth: Arc<JwtAuth>,
+}
+
+impl AuthLayer {
+    /// Create a layer verifying tokens with `auth`
+    pub fn new(auth: Arc<JwtAuth>) -> Self {
+        Self { auth }
+    }
+}
+
+impl<S> Layer<S> for AuthLayer {
+    type Service = AuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthService {
+            inner,
+            auth: self.auth.clone(),
+        }
+    }
+}
+
+/// Service produced by [`AuthLayer`]
+#[derive(Clone)]
+pub struct AuthService<S> {
+    inner: S,
+    auth: Arc<JwtAuth>,
+}
+
+impl<S, B> Service<Request<B>> for AuthService<S>
+where
+    S: Service<Request<B>, Response = Response>,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Either<Ready<Result<Response, S::Error>>, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<B>) -> Self::Future {
+        for header in [USER_ID_HEADER, TENANT_ID_HEADER, PERMISSIONS_HEADER] {
+            request.headers_mut().remove(header);
+        }
+
+        if let Some(token) = bearer_token(request.headers()) {
+            match self.auth.verify(token) {
+                Ok(claims) => {
+                    // Tenants share one rate limit across their users
+                    let key = if claims.tenant_id.is_empty() {
+                        RateLimitKey::user(&claims.sub)
+                    } else {
+                        RateLimitKey::tenant(&claims.tenant_id)
+                    };
+                    request.extensions_mut().insert(key);
+                    request.extensions_mut().insert(UserContext::from(claims));
+                },
+                Err(e) => {
+                    let response = reject(StatusCode::UNAUTHORIZED, format!("Invalid access token: {}", e));
+                    return Either::Left(ready(Ok(response)));
+                },
+            }
+        }
+        Either::Right(self.inner.call(request))
+    }
+}
+
+/// Layer admitting only callers holding `permission`
+pub fn require_permission(permission: &'static str) -> RequirePermissionLayer {
+    RequirePermissionLayer {
+        permission: Some(permission),
+    }
+}
+
+/// Layer admitting only authenticated callers acting within a tenant
+pub fn require_tenant() -> RequirePermissionLayer {
+    RequirePermissionLayer { permission: None }
+}
+
+/// Layer returned by [`require_permission`] and [`require_tenant`]
+#[derive(Debug, Clone, Copy)]
+pub struct RequirePermissionLayer {
+    permission: Option<&'static str>,
+}
+
+impl<S> Layer<S> for RequirePermissionLayer {
+    type Service = RequirePermission<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequirePermission {
+            inner,
+            permission: self.permission,
+        }
+    }
+}
+
+/// Service produced by [`RequirePermissionLayer`]
+#[derive(Clone)]
+pub struct RequirePermission<S> {
+    inner: S,
+    permission: Option<&'static str>,
+}
+
+impl<S, B> Service<Request<B>> for RequirePermission<S>
+where
+    S: Service<Request<B>, Response = Response>,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Either<Ready<Result<Response, S::Error>>, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let rejection = match request.extensions().get::<UserContext>() {
+            Some(context) if !context.is_anonymous() => match self.permission {
+                Some(permission) => (!context.permissions.iter().any(|p| p == permission))
+                    .then(|| reject(StatusCode::FORBIDDEN, format!("Missing permission {}", permission))),
+                None => context
+                    .tenant_id
+                    .is_empty()
+                    .then(|| reject(StatusCode::FORBIDDEN, "Tenant context required".to_string())),
+            },
+            _ => Some(reject(StatusCode::UNAUTHORIZED, "Authentication required".to_string())),
+        };
+        match rejection {
+            Some(response) => Either::Left(ready(Ok(response))),
+            None => Either::Right(self.inner.call(request)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::Viewer;
+    use axum::body::Body;
+    use axum::handler::Handler;
+    use axum::routing::{get, post};
+    use axum::Router;
+    use tower::ServiceExt;
+
+    const SECRET: &str = "an-unguessable-test-secret-of-32-bytes";
+
+    fn trader() -> UserContext {
+        UserContext {
+            user_id: "user-1".to_string(),
+            tenant_id: "tenant-1".to_string(),
+            roles: vec![UserRole::Trader],
+            permissions: vec!["execute_trades".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_token_round_trip_and_tampering() {
+        let auth = JwtAuth::new(SECRET).unwrap();
+        let issued = auth.issue(&trader()).unwrap();
+        let context = UserContext::from(auth.verify(&issued.token).unwrap());
+        assert_eq!((context.user_id.as_str(), context.tenant_id.as_str()), ("user-1", "tenant-1"));
+        assert_eq!(context.permissions, vec!["execute_trades".to_string()]);
+
+        // Another secret, an edited payload or a stripped signature don't verify
+        let other = JwtAuth::new("another-unguessable-secret-of-32-bytes").unwrap();
+        assert!(other.verify(&issued.token).is_err());
+        let mut parts: Vec<&str> = issued.token.split('.').collect();
+        let forged = URL_SAFE_NO_PAD.encode(br#"{"sub":"admin","tenant_id":"tenant-1","iss":"sniper-users","iat":0,"exp":9999999999,"permissions":["manage_users"]}"#);
+        parts[1] = &forged;
+        assert!(auth.verify(&parts.join(".")).is_err());
+        let unsigned = URL_SAFE_NO_PAD.encode(br#"{"alg":"none"}"#);
+        assert!(auth.verify(&format!("{}.{}.", unsigned, parts[1])).is_err());
+
+        assert!(JwtAuth::new("short").is_err());
+        assert!(auth.issue(&UserContext::anonymous()).is_err());
+    }
+
+    #[test]
+    fn test_token_expiry() {
+        let auth = JwtAuth::new(SECRET).unwrap().with_ttl_secs(60).unwrap();
+        let issued_at = Utc::now();
+        let issued = auth.issue_at(&trader(), issued_at).unwrap();
+        assert!(auth.verify_at(&issued.token, issued_at + chrono::Duration::seconds(59)).is_ok());
+        assert!(auth.verify_at(&issued.token, issued_at + chrono::Duration::seconds(60)).is_err());
+
+        let config = AuthConfig {
+            jwt_secret: Some(SECRET.to_string()),
+            jwt_ttl_secs: Some(60),
+        };
+        let configured = JwtAuth::from_config(&config).unwrap();
+        let issued = configured.issue_at(&trader(), issued_at).unwrap();
+        assert!(auth.verify_at(&issued.token, issued_at + chrono::Duration::seconds(59)).is_ok());
+        assert!(auth.verify_at(&issued.token, issued_at + chrono::Duration::seconds(60)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_layers_guard_routes() {
+        let auth = Arc::new(JwtAuth::new(SECRET).unwrap());
+        let whoami = |Viewer(viewer): Viewer| async move { viewer.user_id };
+        let app = Router::new()
+            .route("/whoami", get(whoami))
+            .route("/orders", post((|| async { "created" }).layer(require_permission("execute_trades"))))
+            .route("/users", post((|| async { "created" }).layer(require_permission("manage_users"))))
+            .route("/positions", get((|| async { "listed" }).layer(require_tenant())))
+            .layer(AuthLayer::new(auth.clone()));
+        let token = auth.issue(&trader()).unwrap().token;
+        let tenantless = auth
+            .issue(&UserContext {
+                tenant_id: String::new(),
+                ..trader()
+            })
+            .unwrap()
+            .token;
+
+        let send = |method: &str, uri: &str, token: Option<&str>| {
+            let mut request = Request::builder().method(method).uri(uri).header(USER_ID_HEADER, "spoofed");
+            if let Some(token) = token {
+                request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+            }
+            app.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+
+        // Identity comes from the token, never from the headers
+        let response = send("GET", "/whoami", Some(&token)).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"user-1");
+        let response = send("GET", "/whoami", None).await.unwrap();
+        assert!(axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap().is_empty());
+
+        assert_eq!(send("GET", "/whoami", Some("garbage")).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(send("POST", "/orders", None).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(send("POST", "/orders", Some(&token)).await.unwrap().status(), StatusCode::OK);
+        assert_eq!(send("POST", "/users", Some(&token)).await.unwrap().status(), StatusCode::FORBIDDEN);
+        assert_eq!(send("GET", "/positions", None).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(send("GET", "/positions", Some(&tenantless)).await.unwrap().status(), StatusCode::FORBIDDEN);
+        assert_eq!(send("GET", "/positions", Some(&token)).await.unwrap().status(), StatusCode::OK);
+    }
+}
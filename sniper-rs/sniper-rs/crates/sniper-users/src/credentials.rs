@@ -0,0 +1,303 @@
+//! Password and API key credentials.
+//!
+//! Passwords are stored as Argon2id hashes in PHC string format, so each
+//! hash carries its own salt and parameters. API keys look like
+//! `sk_<key id>_<secret>`: the ID locates the key and only a SHA-256 digest
+//! of the 256-bit random secret is stored. The plaintext key is returned once,
+//! when it is created or rotated. A key is limited to its scopes, which are
+//! permission names, and stops working once it expires or is revoked.
+
+use crate::error::UserError;
+use anyhow::Result;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Shortest password accepted
+pub const MIN_PASSWORD_LEN: usize = 12;
+
+/// Prefix of every API key
+pub const API_KEY_PREFIX: &str = "sk";
+
+/// API key metadata; the secret itself is never stored
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiKey {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    /// Permissions the key grants, a subset of its owner's
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked: bool,
+    #[serde(skip)]
+    secret_hash: Vec<u8>,
+}
+
+impl ApiKey {
+    /// Whether the key can still be used at `now`
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        !self.revoked && !self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// A freshly created API key along with its plaintext, shown only once
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NewApiKey {
+    #[serde(flatten)]
+    pub key: ApiKey,
+    /// The full key to present in requests
+    pub secret: String,
+}
+
+/// Stored password hash
+#[derive(Debug, Clone)]
+struct PasswordCredential {
+    hash: String,
+    updated_at: DateTime<Utc>,
+}
+
+/// Password hashes and API keys of all users
+#[derive(Default)]
+pub struct CredentialStore {
+    hasher: Argon2<'static>,
+    passwords: HashMap<String, PasswordCredential>,
+    api_keys: HashMap<String, ApiKey>,
+}
+
+impl CredentialStore {
+    /// Create an empty store hashing with the default Argon2id parameters
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty store hashing with custom Argon2id parameters
+    pub fn with_params(params: argon2::Params) -> Self {
+        Self {
+            hasher: Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params),
+            ..Self::default()
+        }
+    }
+
+    /// Set a user's password, replacing any previous one
+    pub fn set_password(&mut self, user_id: &str, password: &str) -> Result<()> {
+        if password.chars().count() < MIN_PASSWORD_LEN {
+            return Err(UserError::PasswordTooShort.into());
+        }
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = self
+            .hasher
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|e| anyhow::anyhow!("Failed to hash password: {}", e))?
+            .to_string();
+        self.passwords.insert(
+            user_id.to_string(),
+            PasswordCredential {
+                hash,
+                updated_at: Utc::now(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Whether a user has a password set
+    pub fn has_password(&self, user_id: &str) -> bool {
+        self.passwords.contains_key(user_id)
+    }
+
+    /// When a user's password was last set
+    pub fn password_updated_at(&self, user_id: &str) -> Option<DateTime<Utc>> {
+        self.passwords.get(user_id).map(|credential| credential.updated_at)
+    }
+
+    /// Check a password against the user's stored hash
+    pub fn verify_password(&self, user_id: &str, password: &str) -> bool {
+        let Some(credential) = self.passwords.get(user_id) else {
+            return false;
+        };
+        PasswordHash::new(&credential.hash)
+            .map(|hash| self.hasher.verify_password(password.as_bytes(), &hash).is_ok())
+            .unwrap_or(false)
+    }
+
+    /// Replace a user's password after checking the current one
+    pub fn rotate_password(&mut self, user_id: &str, current: &str, new: &str) -> Result<()> {
+        if !self.verify_password(user_id, current) {
+            return Err(UserError::WrongPassword.into());
+        }
+        if current == new {
+            return Err(UserError::PasswordUnchanged.into());
+        }
+        self.set_password(user_id, new)
+    }
+
+    /// Create an API key for a user, valid for `ttl` if given
+    pub fn create_api_key(&mut self, user_id: &str, name: &str, scopes: Vec<String>, ttl: Option<Duration>) -> Result<NewApiKey> {
+        if scopes.is_empty() {
+            return Err(UserError::InvalidApiKey("it needs at least one scope").into());
+        }
+        if ttl.is_some_and(|ttl| ttl <= Duration::zero()) {
+            return Err(UserError::InvalidApiKey("its lifetime must be positive").into());
+        }
+
+        let id = Uuid::new_v4().simple().to_string();
+        let mut secret = [0u8; 32];
+        OsRng.fill_bytes(&mut secret);
+        let secret = URL_SAFE_NO_PAD.encode(secret);
+        let now = Utc::now();
+        let key = ApiKey {
+            id: id.clone(),
+            user_id: user_id.to_string(),
+            name: name.to_string(),
+            scopes,
+            created_at: now,
+            expires_at: ttl.map(|ttl| now + ttl),
+            last_used_at: None,
+            revoked: false,
+            secret_hash: Sha256::digest(secret.as_bytes()).to_vec(),
+        };
+        self.api_keys.insert(id.clone(), key.clone());
+        Ok(NewApiKey {
+            key,
+            secret: format!("{}_{}_{}", API_KEY_PREFIX, id, secret),
+        })
+    }
+
+    /// Check a presented API key, recording its use
+    pub fn verify_api_key(&mut self, presented: &str) -> Result<ApiKey> {
+        let invalid = || anyhow::anyhow!("Invalid API key");
+        let (id, secret) = presented
+            .strip_prefix(API_KEY_PREFIX)
+            .and_then(|rest| rest.strip_prefix('_'))
+            .and_then(|rest| rest.split_once('_'))
+            .ok_or_else(invalid)?;
+        let key = self.api_keys.get_mut(id).ok_or_else(invalid)?;
+        if !constant_time_eq(&Sha256::digest(secret.as_bytes()), &key.secret_hash) {
+            return Err(invalid());
+        }
+
+        let now = Utc::now();
+        if key.revoked {
+            return Err(anyhow::anyhow!("API key {} has been revoked", key.id));
+        }
+        if !key.is_active(now) {
+            return Err(anyhow::anyhow!("API key {} has expired", key.id));
+        }
+        key.last_used_at = Some(now);
+        Ok(key.clone())
+    }
+
+    /// Get an API key by ID
+    pub fn api_key(&self, key_id: &str) -> Option<&ApiKey> {
+        self.api_keys.get(key_id)
+    }
+
+    /// API keys of a user, newest first
+    pub fn list_api_keys(&self, user_id: &str) -> Vec<ApiKey> {
+        let mut keys: Vec<ApiKey> = self.api_keys.values().filter(|key| key.user_id == user_id).cloned().collect();
+        keys.sort_by_key(|key| std::cmp::Reverse(key.created_at));
+        keys
+    }
+
+    /// Revoke an API key
+    pub fn revoke_api_key(&mut self, key_id: &str) -> Result<ApiKey> {
+        let key = self
+            .api_keys
+            .get_mut(key_id)
+            .ok_or_else(|| UserError::ApiKeyNotFound(key_id.to_string()))?;
+        key.revoked = true;
+        Ok(key.clone())
+    }
+
+    /// Replace an active API key with a new one of the same name, scopes and lifetime
+    pub fn rotate_api_key(&mut self, key_id: &str) -> Result<NewApiKey> {
+        let key = self
+            .api_keys
+            .get(key_id)
+            .ok_or_else(|| UserError::ApiKeyNotFound(key_id.to_string()))?
+            .clone();
+        if !key.is_active(Utc::now()) {
+            return Err(UserError::InactiveApiKey(key_id.to_string()).into());
+        }
+        let ttl = key.expires_at.map(|expires_at| expires_at - key.created_at);
+        let rotated = self.create_api_key(&key.user_id, &key.name, key.scopes, ttl)?;
+        self.revoke_api_key(key_id)?;
+        Ok(rotated)
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> CredentialStore {
+        // Cheap parameters keep the tests fast
+        CredentialStore::with_params(argon2::Params::new(1024, 1, 1, None).unwrap())
+    }
+
+    #[test]
+    fn test_password_hashing_and_rotation() {
+        let mut store = store();
+        assert!(store.set_password("user-1", "short").is_err());
+        store.set_password("user-1", "correct horse battery").unwrap();
+        assert!(store.passwords["user-1"].hash.starts_with("$argon2id$"));
+        assert!(store.verify_password("user-1", "correct horse battery"));
+        assert!(!store.verify_password("user-1", "wrong horse battery"));
+        assert!(!store.verify_password("user-2", "correct horse battery"));
+
+        assert!(store.rotate_password("user-1", "wrong horse battery", "staple battery horse").is_err());
+        store.rotate_password("user-1", "correct horse battery", "staple battery horse").unwrap();
+        assert!(!store.verify_password("user-1", "correct horse battery"));
+        assert!(store.verify_password("user-1", "staple battery horse"));
+    }
+
+    #[test]
+    fn test_api_key_lifecycle() {
+        let mut store = store();
+        let scopes = vec!["view_orders".to_string()];
+        assert!(store.create_api_key("user-1", "bot", Vec::new(), None).is_err());
+        let created = store.create_api_key("user-1", "bot", scopes.clone(), Some(Duration::days(30))).unwrap();
+        assert!(created.secret.starts_with(&format!("sk_{}_", created.key.id)));
+
+        let verified = store.verify_api_key(&created.secret).unwrap();
+        assert_eq!(verified.scopes, scopes);
+        assert!(verified.last_used_at.is_some());
+        assert!(store.verify_api_key(&format!("{}x", created.secret)).is_err());
+        assert!(store.verify_api_key("sk_missing_secret").is_err());
+
+        // Rotation keeps the scopes and lifetime and retires the old key
+        let rotated = store.rotate_api_key(&created.key.id).unwrap();
+        assert_eq!(rotated.key.scopes, scopes);
+        assert_eq!(rotated.key.expires_at.unwrap() - rotated.key.created_at, Duration::days(30));
+        assert!(store.verify_api_key(&created.secret).is_err());
+        assert!(store.verify_api_key(&rotated.secret).is_ok());
+        assert!(store.rotate_api_key(&created.key.id).is_err());
+
+        store.revoke_api_key(&rotated.key.id).unwrap();
+        assert!(store.verify_api_key(&rotated.secret).is_err());
+        assert_eq!(store.list_api_keys("user-1").len(), 2);
+    }
+
+    #[test]
+    fn test_expired_api_key() {
+        let mut store = store();
+        let created = store
+            .create_api_key("user-1", "bot", vec!["view_orders".to_string()], Some(Duration::days(1)))
+            .unwrap();
+        store.api_keys.get_mut(&created.key.id).unwrap().expires_at = Some(Utc::now() - Duration::seconds(1));
+        assert!(store.verify_api_key(&created.secret).unwrap_err().to_string().contains("expired"));
+    }
+}
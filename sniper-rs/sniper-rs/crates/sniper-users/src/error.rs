@@ -0,0 +1,72 @@
+//! Errors returned by the user manager and credential store.
+
+use crate::credentials::MIN_PASSWORD_LEN;
+use sniper_core::errors::{DomainError, ErrorKind};
+use thiserror::Error;
+
+/// Failure of a user, role or credential operation
+#[derive(Debug, Error)]
+pub enum UserError {
+    #[error("User {0} not found")]
+    UserNotFound(String),
+    #[error("Role {0} not found")]
+    RoleNotFound(String),
+    #[error("API key {0} not found")]
+    ApiKeyNotFound(String),
+    #[error("Role {0} is not defined")]
+    UndefinedRole(String),
+    #[error("Invalid role name {0:?}")]
+    InvalidRoleName(String),
+    #[error("{0}")]
+    InvalidPermission(String),
+    #[error("Password must be at least {} characters", MIN_PASSWORD_LEN)]
+    PasswordTooShort,
+    #[error("New password must differ from the current one")]
+    PasswordUnchanged,
+    #[error("Invalid API key: {0}")]
+    InvalidApiKey(&'static str),
+    #[error("Unsupported export format {0}")]
+    UnsupportedFormat(String),
+    #[error("Current password is incorrect")]
+    WrongPassword,
+    #[error("User does not hold the {0} permission")]
+    ScopeNotHeld(String),
+    #[error("Built-in role {name} cannot be {action}")]
+    BuiltInRole { name: String, action: &'static str },
+    #[error("Role {name} is still assigned to {holders} users")]
+    RoleInUse { name: String, holders: usize },
+    #[error("API key {0} is no longer active")]
+    InactiveApiKey(String),
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+/// Errors carrying a [`UserError`], such as a missing user raised inside
+/// the managers, keep their variant
+impl From<anyhow::Error> for UserError {
+    fn from(error: anyhow::Error) -> Self {
+        error.downcast::<UserError>().unwrap_or_else(UserError::Other)
+    }
+}
+
+impl DomainError for UserError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            UserError::UserNotFound(_) | UserError::RoleNotFound(_) | UserError::ApiKeyNotFound(_) => {
+                ErrorKind::NotFound
+            }
+            UserError::UndefinedRole(_)
+            | UserError::InvalidRoleName(_)
+            | UserError::InvalidPermission(_)
+            | UserError::PasswordTooShort
+            | UserError::PasswordUnchanged
+            | UserError::InvalidApiKey(_)
+            | UserError::UnsupportedFormat(_) => ErrorKind::Invalid,
+            UserError::WrongPassword | UserError::ScopeNotHeld(_) | UserError::BuiltInRole { .. } => {
+                ErrorKind::Forbidden
+            }
+            UserError::RoleInUse { .. } | UserError::InactiveApiKey(_) => ErrorKind::Conflict,
+            UserError::Other(_) => ErrorKind::Internal,
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! Request identity for the svc-* binaries.
+//!
+//! Services behind an [`AuthLayer`](crate::auth::AuthLayer) learn the
+//! caller's context from their verified access token; otherwise the gateway
+//! forwards the authenticated user's context in request headers. Handlers
+//! take a [`Viewer`] to learn who is asking, which drives the redaction
+//! applied to their responses. Requests without an identity are treated as
+//! anonymous and see only redacted data.
+
+use crate::UserContext;
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+use std::convert::Infallible;
+
+/// Header carrying the user ID
+pub const USER_ID_HEADER: &str = "x-user-id";
+
+/// Header carrying the tenant ID
+pub const TENANT_ID_HEADER: &str = "x-tenant-id";
+
+/// Header carrying the comma-separated permissions
+pub const PERMISSIONS_HEADER: &str = "x-user-permissions";
+
+/// Context of the user making a request
+#[derive(Debug, Clone)]
+pub struct Viewer(pub UserContext);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Viewer {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(context) = parts.extensions.get::<UserContext>() {
+            return Ok(Viewer(context.clone()));
+        }
+
+        let header = |name: &str| {
+            parts
+                .headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim().to_string())
+                .unwrap_or_default()
+        };
+
+        let user_id = header(USER_ID_HEADER);
+        if user_id.is_empty() {
+            return Ok(Viewer(UserContext::anonymous()));
+        }
+
+        Ok(Viewer(UserContext {
+            user_id,
+            tenant_id: header(TENANT_ID_HEADER),
+            roles: Vec::new(),
+            permissions: header(PERMISSIONS_HEADER)
+                .split(',')
+                .map(|permission| permission.trim().to_string())
+                .filter(|permission| !permission.is_empty())
+                .collect(),
+        }))
+    }
+}
@@ -0,0 +1,384 @@
+//! Idempotent mutations for the svc-* binaries.
+//!
+//! Clients retrying a POST after a network error send the same
+//! `Idempotency-Key` header with every attempt. The [`idempotent`] middleware
+//! runs the first attempt and caches its response for the store's TTL;
+//! replays get the cached response, marked with `Idempotent-Replayed: true`,
+//! instead of creating the resource again. Keys are scoped to the caller's
+//! tenant and user, so it sits behind the [`AuthLayer`](crate::AuthLayer).
+//! Reusing a key for a different request is rejected with 422, and a replay
+//! arriving while the first attempt still runs with 409. Server errors are
+//! not cached, so the client can retry them under the same key.
+
+use crate::auth::reject;
+use crate::UserContext;
+use axum::body::{to_bytes, Body, Bytes};
+use axum::extract::{Request, State};
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Header carrying the client's idempotency key
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Header set on responses replayed from the cache
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// Longest accepted idempotency key
+pub const MAX_KEY_LEN: usize = 255;
+
+/// Largest request or response body the middleware buffers
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Idempotency settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IdempotencyConfig {
+    /// How long a response is replayed for its key
+    pub ttl_secs: u64,
+    /// Keys remembered at most; the oldest are forgotten first
+    pub max_entries: usize,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: 24 * 60 * 60,
+            max_entries: 10_000,
+        }
+    }
+}
+
+/// Caller, key and request a cached response belongs to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Scope {
+    tenant_id: String,
+    user_id: String,
+    key: String,
+}
+
+/// Response recorded for a key
+#[derive(Debug, Clone)]
+struct CachedResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    fingerprint: [u8; 32],
+    stored_at: Instant,
+    /// `None` while the first attempt is still running
+    response: Option<CachedResponse>,
+}
+
+/// Outcome of looking a key up
+enum Claim {
+    /// The key is new; the caller runs the request
+    Run,
+    Replay(CachedResponse),
+    InProgress,
+    Mismatch,
+}
+
+/// Responses cached by idempotency key, shared by a service's routes
+#[derive(Clone)]
+pub struct IdempotencyStore {
+    config: IdempotencyConfig,
+    entries: Arc<Mutex<HashMap<Scope, Entry>>>,
+}
+
+impl Default for IdempotencyStore {
+    fn default() -> Self {
+        Self::new(IdempotencyConfig::default())
+    }
+}
+
+impl IdempotencyStore {
+    /// Create an empty store
+    pub fn new(config: IdempotencyConfig) -> Self {
+        Self {
+            config,
+            entries: Arc::default(),
+        }
+    }
+
+    /// Number of keys remembered, including expired ones not yet dropped
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether no keys are remembered
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Scope, Entry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn ttl(&self) -> Duration {
+        Duration::from_secs(self.config.ttl_secs)
+    }
+
+    /// Look a key up, reserving it for the caller if it is new
+    fn claim(&self, scope: &Scope, fingerprint: [u8; 32]) -> Claim {
+        let ttl = self.ttl();
+        let mut entries = self.lock();
+        if let Some(entry) = entries.get(scope).filter(|entry| entry.stored_at.elapsed() < ttl) {
+            if entry.fingerprint != fingerprint {
+                return Claim::Mismatch;
+            }
+            return match &entry.response {
+                Some(response) => Claim::Replay(response.clone()),
+                None => Claim::InProgress,
+            };
+        }
+
+        entries.retain(|_, entry| entry.stored_at.elapsed() < ttl);
+        if entries.len() >= self.config.max_entries {
+            let excess = entries.len() + 1 - self.config.max_entries.max(1);
+            let mut oldest: Vec<(Scope, Instant)> =
+                entries.iter().map(|(scope, entry)| (scope.clone(), entry.stored_at)).collect();
+            oldest.sort_by_key(|(_, stored_at)| *stored_at);
+            for (scope, _) in oldest.into_iter().take(excess) {
+                entries.remove(&scope);
+            }
+        }
+        entries.insert(
+            scope.clone(),
+            Entry {
+                fingerprint,
+                stored_at: Instant::now(),
+                response: None,
+            },
+        );
+        Claim::Run
+    }
+
+    /// Record the response of a claimed key, or forget the key so it can be retried
+    fn complete(&self, scope: &Scope, response: Option<CachedResponse>) {
+        let mut entries = self.lock();
+        match response {
+            Some(response) => {
+                if let Some(entry) = entries.get_mut(scope) {
+                    entry.response = Some(response);
+                    entry.stored_at = Instant::now();
+                }
+            },
+            None => {
+                entries.remove(scope);
+            },
+        }
+    }
+}
+
+/// Replay the cached response of a repeated `Idempotency-Key`
+///
+/// Applies to POST and PATCH requests carrying the header; other requests
+/// pass straight through. Install with
+/// `axum::middleware::from_fn_with_state(store, idempotent)`.
+pub async fn idempotent(State(store): State<IdempotencyStore>, request: Request, next: Next) -> Response {
+    if !matches!(*request.method(), Method::POST | Method::PATCH) {
+        return next.run(request).await;
+    }
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return next.run(request).await;
+    };
+    let key = match key.to_str().map(str::trim) {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LEN => key.to_string(),
+        _ => {
+            let message = format!("Idempotency-Key must be 1 to {} visible ASCII characters", MAX_KEY_LEN);
+            return reject(StatusCode::BAD_REQUEST, message);
+        },
+    };
+    let caller = request.extensions().get::<UserContext>();
+    let scope = Scope {
+        tenant_id: caller.map(|caller| caller.tenant_id.clone()).unwrap_or_default(),
+        user_id: caller.map(|caller| caller.user_id.clone()).unwrap_or_default(),
+        key,
+    };
+
+    let (parts, body) = request.into_parts();
+    let body = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(_) => return reject(StatusCode::PAYLOAD_TOO_LARGE, "Request body too large".to_string()),
+    };
+    let mut hasher = Sha256::new();
+    hasher.update(parts.method.as_str());
+    hasher.update([0]);
+    hasher.update(parts.uri.path());
+    hasher.update([0]);
+    hasher.update(&body);
+    let fingerprint: [u8; 32] = hasher.finalize().into();
+
+    match store.claim(&scope, fingerprint) {
+        Claim::Run => {},
+        Claim::Replay(cached) => return replay(cached),
+        Claim::InProgress => {
+            return reject(StatusCode::CONFLICT, "A request with this Idempotency-Key is still in progress".to_string())
+        },
+        Claim::Mismatch => {
+            return reject(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Idempotency-Key was already used for a different request".to_string(),
+            )
+        },
+    }
+
+    // Run the request to completion even if the client goes away, so its retry finds the response
+    let request = Request::from_parts(parts, Body::from(body));
+    match tokio::spawn(run_and_record(store.clone(), scope.clone(), request, next)).await {
+        Ok(response) => response,
+        Err(e) => {
+            store.complete(&scope, None);
+            tracing::error!("Idempotent request failed: {}", e);
+            reject(StatusCode::INTERNAL_SERVER_ERROR, "Request failed".to_string())
+        },
+    }
+}
+
+/// Run a claimed request and record its response under the key
+async fn run_and_record(store: IdempotencyStore, scope: Scope, request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    if response.status().is_server_error() {
+        store.complete(&scope, None);
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(e) => {
+            // The handler ran but its response is lost; keep the key so a retry doesn't run it again
+            tracing::error!("Failed to buffer idempotent response: {}", e);
+            return reject(StatusCode::INTERNAL_SERVER_ERROR, "Failed to read response".to_string());
+        },
+    };
+    store.complete(
+        &scope,
+        Some(CachedResponse {
+            status: parts.status,
+            headers: parts.headers.clone(),
+            body: body.clone(),
+        }),
+    );
+    Response::from_parts(parts, Body::from(body))
+}
+
+fn replay(cached: CachedResponse) -> Response {
+    let mut response = (cached.status, cached.body).into_response();
+    *response.headers_mut() = cached.headers;
+    response.headers_mut().insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::post;
+    use axum::Router;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::ServiceExt;
+
+    fn caller(user_id: &str) -> UserContext {
+        UserContext {
+            user_id: user_id.to_string(),
+            tenant_id: "tenant-1".to_string(),
+            roles: Vec::new(),
+            permissions: Vec::new(),
+        }
+    }
+
+    fn orders_app(store: IdempotencyStore, created: Arc<AtomicUsize>) -> Router {
+        Router::new()
+            .route(
+                "/orders",
+                post(move |body: String| async move {
+                    let id = created.fetch_add(1, Ordering::SeqCst);
+                    if body == "fail" {
+                        return (StatusCode::SERVICE_UNAVAILABLE, "try again".to_string());
+                    }
+                    (StatusCode::CREATED, format!("order-{}", id))
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(store, idempotent))
+    }
+
+    async fn send(app: &Router, user_id: &str, key: Option<&str>, body: &str) -> (StatusCode, bool, String) {
+        let mut request = Request::post("/orders");
+        if let Some(key) = key {
+            request = request.header(IDEMPOTENCY_KEY_HEADER, key);
+        }
+        let mut request = request.body(Body::from(body.to_string())).unwrap();
+        request.extensions_mut().insert(caller(user_id));
+        let response = app.clone().oneshot(request).await.unwrap();
+        let replayed = response.headers().contains_key(REPLAYED_HEADER);
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, replayed, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_replays_first_response() {
+        let created = Arc::new(AtomicUsize::new(0));
+        let store = IdempotencyStore::default();
+        let app = orders_app(store.clone(), created.clone());
+
+        assert_eq!(send(&app, "user-1", Some("key-1"), "buy").await, (StatusCode::CREATED, false, "order-0".to_string()));
+        assert_eq!(send(&app, "user-1", Some("key-1"), "buy").await, (StatusCode::CREATED, true, "order-0".to_string()));
+        assert_eq!(created.load(Ordering::SeqCst), 1);
+
+        // The same key for another request, another caller's key, no key at all
+        assert_eq!(send(&app, "user-1", Some("key-1"), "sell").await.0, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(send(&app, "user-2", Some("key-1"), "buy").await.2, "order-1");
+        assert_eq!(send(&app, "user-1", None, "buy").await.2, "order-2");
+        assert_eq!(send(&app, "user-1", None, "buy").await.2, "order-3");
+        assert_eq!(store.len(), 2);
+
+        // Server errors are retried under the same key
+        assert_eq!(send(&app, "user-1", Some("key-2"), "fail").await.0, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(!send(&app, "user-1", Some("key-2"), "fail").await.1);
+        assert_eq!(created.load(Ordering::SeqCst), 6);
+
+        let long_key = "k".repeat(MAX_KEY_LEN + 1);
+        assert_eq!(send(&app, "user-1", Some(&long_key), "buy").await.0, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_expired_and_evicted_keys_run_again() {
+        let created = Arc::new(AtomicUsize::new(0));
+        let store = IdempotencyStore::new(IdempotencyConfig {
+            ttl_secs: 0,
+            max_entries: 10,
+        });
+        let app = orders_app(store, created.clone());
+        send(&app, "user-1", Some("key-1"), "buy").await;
+        assert_eq!(send(&app, "user-1", Some("key-1"), "buy").await, (StatusCode::CREATED, false, "order-1".to_string()));
+
+        let store = IdempotencyStore::new(IdempotencyConfig {
+            ttl_secs: 60,
+            max_entries: 1,
+        });
+        let app = orders_app(store.clone(), created.clone());
+        send(&app, "user-1", Some("key-1"), "buy").await;
+        send(&app, "user-1", Some("key-2"), "buy").await;
+        assert_eq!(store.len(), 1);
+        assert!(!send(&app, "user-1", Some("key-1"), "buy").await.1);
+
+        // A replay while the first attempt runs is a conflict
+        let scope = Scope {
+            tenant_id: "tenant-1".to_string(),
+            user_id: "user-1".to_string(),
+            key: "key-3".to_string(),
+        };
+        let fingerprint = [0; 32];
+        assert!(matches!(store.claim(&scope, fingerprint), Claim::Run));
+        assert!(matches!(store.claim(&scope, fingerprint), Claim::InProgress));
+    }
+}
@@ -1,13 +1,39 @@
 //! User management system for the sniper-rs enterprise features.
 //! 
 //! This module provides functionality for multi-user support with isolated contexts,
-//! advanced RBAC (Role-Based Access Control), and audit logging.
+//! advanced RBAC (Role-Based Access Control), persistent audit logging, per-user
+//! notification preferences, permission-aware response redaction, password
+//! and API key credentials, and token authentication for the services.
+//! Besides the five built-in roles, custom roles can be defined at runtime
+//! with resource-scoped grants evaluated by the [`policy`] engine.
+
+pub mod audit;
+pub mod auth;
+pub mod credentials;
+pub mod error;
+pub mod http;
+pub mod idempotency;
+pub mod notifications;
+pub mod policy;
+pub mod redaction;
+
+pub use audit::{spawn_audit_listener, AuditQuery, AuditStore, RetentionPolicy};
+pub use auth::{require_permission, require_tenant, AuthLayer, IssuedToken, JwtAuth, TokenClaims};
+pub use credentials::{ApiKey, CredentialStore, NewApiKey};
+pub use error::UserError;
+pub use idempotency::{idempotent, IdempotencyConfig, IdempotencyStore};
+pub use notifications::{
+    DigestFrequency, NotificationChannel, NotificationPreferences, NotificationSeverity,
+};
+pub use policy::{AccessRequest, Decision, Effect, PolicyRule};
+pub use redaction::{Redact, can_view_tenant, redact, redact_all};
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use std::collections::HashMap;
 use uuid::Uuid;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 
 /// User roles for RBAC
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -17,6 +43,36 @@ pub enum UserRole {
     Analyst,
     Auditor,
     Guest,
+    /// Role defined at runtime through [`RBACManager::define_role`]
+    Custom(String),
+}
+
+impl UserRole {
+    /// Roles every RBAC manager starts with
+    pub const BUILT_IN: [UserRole; 5] = [
+        UserRole::Admin,
+        UserRole::Trader,
+        UserRole::Analyst,
+        UserRole::Auditor,
+        UserRole::Guest,
+    ];
+
+    /// Name of the role
+    pub fn name(&self) -> &str {
+        match self {
+            UserRole::Admin => "Admin",
+            UserRole::Trader => "Trader",
+            UserRole::Analyst => "Analyst",
+            UserRole::Auditor => "Auditor",
+            UserRole::Guest => "Guest",
+            UserRole::Custom(name) => name,
+        }
+    }
+
+    /// Whether the role is one of the built-in five
+    pub fn is_built_in(&self) -> bool {
+        !matches!(self, UserRole::Custom(_))
+    }
 }
 
 /// User context for isolated environments
@@ -28,6 +84,13 @@ pub struct UserContext {
     pub permissions: Vec<String>,
 }
 
+impl UserContext {
+    /// Check if the context's resource grants allow a request
+    pub fn can(&self, request: &AccessRequest) -> bool {
+        policy::authorize(self, request)
+    }
+}
+
 /// User information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
@@ -45,10 +108,24 @@ pub struct User {
 pub struct AuditLog {
     pub id: String,
     pub user_id: String,
+    /// Tenant of the acting user; empty if the user is unknown
+    #[serde(default)]
+    pub tenant_id: String,
     pub action: String,
     pub resource: String,
     pub timestamp: DateTime<Utc>,
     pub details: Option<String>,
+    /// Links the entries of one order, its trade plans and their receipts
+    #[serde(default)]
+    pub correlation_id: Option<String>,
+}
+
+/// A role and the permissions it grants
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RoleDefinition {
+    pub name: String,
+    pub built_in: bool,
+    pub permissions: Vec<String>,
 }
 
 /// Role-Based Access Control manager
@@ -69,23 +146,33 @@ impl RBACManager {
             "execute_trades".to_string(),
             "view_reports".to_string(),
             "configure_system".to_string(),
+            "request_withdrawals".to_string(),
+            "approve_withdrawals".to_string(),
+            "*:*:tenant".to_string(),
         ]);
         
         roles_permissions.insert(UserRole::Trader, vec![
             "execute_trades".to_string(),
             "view_portfolio".to_string(),
             "view_orders".to_string(),
+            "orders:*:own".to_string(),
+            "portfolio:*:own".to_string(),
         ]);
         
         roles_permissions.insert(UserRole::Analyst, vec![
             "view_portfolio".to_string(),
             "view_orders".to_string(),
             "view_reports".to_string(),
+            "orders:read:tenant".to_string(),
+            "portfolio:read:tenant".to_string(),
+            "reports:read:tenant".to_string(),
         ]);
         
         roles_permissions.insert(UserRole::Auditor, vec![
             "view_audit_logs".to_string(),
             "view_reports".to_string(),
+            "audit:read:tenant".to_string(),
+            "reports:read:tenant".to_string(),
         ]);
         
         roles_permissions.insert(UserRole::Guest, vec![
@@ -118,13 +205,113 @@ impl RBACManager {
         permissions.dedup();
         permissions
     }
+    
+    /// Look up a built-in or custom role by name
+    pub fn role(&self, name: &str) -> Option<UserRole> {
+        UserRole::BUILT_IN
+            .into_iter()
+            .find(|role| role.name() == name)
+            .or_else(|| {
+                let custom = UserRole::Custom(name.to_string());
+                self.roles_permissions.contains_key(&custom).then_some(custom)
+            })
+    }
+    
+    /// All roles, built-in ones first, then custom ones by name
+    pub fn roles(&self) -> Vec<RoleDefinition> {
+        let mut roles: Vec<RoleDefinition> = self
+            .roles_permissions
+            .iter()
+            .map(|(role, permissions)| RoleDefinition {
+                name: role.name().to_string(),
+                built_in: role.is_built_in(),
+                permissions: permissions.clone(),
+            })
+            .collect();
+        roles.sort_by(|a, b| b.built_in.cmp(&a.built_in).then_with(|| a.name.cmp(&b.name)));
+        roles
+    }
+    
+    /// Define or replace a custom role
+    pub fn define_role(&mut self, name: &str, permissions: Vec<String>) -> Result<UserRole> {
+        if name.is_empty() || name.len() > 64 || !name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-')) {
+            return Err(UserError::InvalidRoleName(name.to_string()).into());
+        }
+        if UserRole::BUILT_IN.iter().any(|role| role.name().eq_ignore_ascii_case(name)) {
+            return Err(UserError::BuiltInRole { name: name.to_string(), action: "redefined" }.into());
+        }
+        for permission in &permissions {
+            policy::validate_grant(permission).map_err(|e| UserError::InvalidPermission(e.to_string()))?;
+        }
+        let role = UserRole::Custom(name.to_string());
+        self.roles_permissions.insert(role.clone(), permissions);
+        Ok(role)
+    }
+    
+    /// Remove a custom role
+    pub fn remove_role(&mut self, name: &str) -> Result<()> {
+        match self.role(name) {
+            Some(role) if role.is_built_in() => Err(UserError::BuiltInRole { name: name.to_string(), action: "removed" }.into()),
+            Some(role) => {
+                self.roles_permissions.remove(&role);
+                Ok(())
+            },
+            None => Err(UserError::RoleNotFound(name.to_string()).into()),
+        }
+    }
+}
+
+/// Approval policy for withdrawals and other sensitive fund movements
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalPolicy {
+    /// Amount above which approvals are required
+    pub approval_threshold: f64,
+    /// Number of distinct approvers required above the threshold
+    pub required_approvals: usize,
+    /// Permission an approver must hold
+    pub approver_permission: String,
+}
+
+impl ApprovalPolicy {
+    /// Create a new approval policy
+    pub fn new(approval_threshold: f64, required_approvals: usize) -> Self {
+        Self {
+            approval_threshold,
+            required_approvals,
+            approver_permission: "approve_withdrawals".to_string(),
+        }
+    }
+    
+    /// Number of approvals required for an amount
+    pub fn approvals_required(&self, amount: f64) -> usize {
+        if amount > self.approval_threshold {
+            self.required_approvals
+        } else {
+            0
+        }
+    }
+    
+    /// Check if a user may approve on behalf of a requester
+    pub fn can_approve(&self, approver: &UserContext, requester_id: &str, tenant_id: &str) -> bool {
+        approver.user_id != requester_id
+            && approver.tenant_id == tenant_id
+            && approver.permissions.contains(&self.approver_permission)
+    }
+}
+
+impl Default for ApprovalPolicy {
+    fn default() -> Self {
+        Self::new(10000.0, 2)
+    }
 }
 
 /// User manager for multi-user support
 pub struct UserManager {
     users: HashMap<String, User>,
     rbac: RBACManager,
-    audit_logs: Vec<AuditLog>,
+    audit: AuditStore,
+    notification_preferences: HashMap<String, NotificationPreferences>,
+    credentials: CredentialStore,
 }
 
 impl UserManager {
@@ -133,12 +320,29 @@ impl UserManager {
         Self {
             users: HashMap::new(),
             rbac: RBACManager::new(),
-            audit_logs: Vec::new(),
+            audit: AuditStore::in_memory(),
+            notification_preferences: HashMap::new(),
+            credentials: CredentialStore::new(),
         }
     }
     
+    /// Keep audit logs in `audit`, e.g. a store persisted to a file
+    pub fn with_audit_store(mut self, audit: AuditStore) -> Self {
+        self.audit = audit;
+        self
+    }
+    
+    /// Use a custom credential store, e.g. one with different hashing parameters
+    pub fn with_credential_store(mut self, credentials: CredentialStore) -> Self {
+        self.credentials = credentials;
+        self
+    }
+    
     /// Create a new user
     pub fn create_user(&mut self, username: &str, email: &str, roles: Vec<UserRole>, tenant_id: &str) -> Result<User> {
+        if let Some(role) = roles.iter().find(|role| self.rbac.role(role.name()).as_ref() != Some(*role)) {
+            return Err(UserError::UndefinedRole(role.name().to_string()).into());
+        }
         let user = User {
             id: Uuid::new_v4().to_string(),
             username: username.to_string(),
@@ -167,27 +371,131 @@ impl UserManager {
         self.users.values().find(|user| user.username == username)
     }
     
-    /// Authenticate a user (simplified for demo)
-    pub fn authenticate_user(&mut self, username: &str) -> Option<UserContext> {
-        if let Some(user) = self.get_user_by_username(username) {
-            let mut user = user.clone();
-            user.last_login = Some(Utc::now());
-            
-            // Update the user with the new login time
-            self.users.insert(user.id.clone(), user.clone());
-            
-            // Log the authentication
-            self.log_audit(&user.id, "LOGIN", "auth", Some(format!("User {} logged in", username)));
-            
-            Some(UserContext {
-                user_id: user.id.clone(),
-                tenant_id: user.tenant_id.clone(),
-                roles: user.roles.clone(),
-                permissions: self.rbac.get_user_permissions(&user),
-            })
-        } else {
-            None
+    /// Get all users in a tenant
+    pub fn get_tenant_users(&self, tenant_id: &str) -> Vec<&User> {
+        self.users
+            .values()
+            .filter(|user| user.tenant_id == tenant_id)
+            .collect()
+    }
+
+    /// Replace all users of a tenant, e.g. with ones restored from a backup
+    ///
+    /// Passwords and API keys are not part of user records, so restored users
+    /// keep whatever credentials this manager still holds for them.
+    pub fn restore_tenant_users(&mut self, tenant_id: &str, users: Vec<User>) -> Result<()> {
+        for user in &users {
+            if user.tenant_id != tenant_id {
+                return Err(anyhow::anyhow!("User {} belongs to tenant {}, not {}", user.id, user.tenant_id, tenant_id));
+            }
+            if self.users.get(&user.id).is_some_and(|existing| existing.tenant_id != tenant_id) {
+                return Err(anyhow::anyhow!("User ID {} is taken by another tenant", user.id));
+            }
+            if let Some(role) = user.roles.iter().find(|role| self.rbac.role(role.name()).as_ref() != Some(*role)) {
+                return Err(anyhow::anyhow!("Role {} is not defined", role.name()));
+            }
+        }
+
+        let count = users.len();
+        self.users.retain(|_, user| user.tenant_id != tenant_id);
+        self.users.extend(users.into_iter().map(|user| (user.id.clone(), user)));
+        self.log_audit("system", "RESTORE_USERS", "users", Some(format!("Restored {} users of tenant {}", count, tenant_id)));
+        Ok(())
+    }
+
+    /// Authenticate a user by username and password
+    pub fn authenticate_user(&mut self, username: &str, password: &str) -> Option<UserContext> {
+        let user_id = self.get_user_by_username(username)?.id.clone();
+        if !self.credentials.verify_password(&user_id, password) {
+            self.log_audit(&user_id, "LOGIN_FAILED", "auth", Some(format!("Bad password for user {}", username)));
+            return None;
+        }
+        
+        let context = self.record_login(&user_id)?;
+        self.log_audit(&user_id, "LOGIN", "auth", Some(format!("User {} logged in", username)));
+        Some(context)
+    }
+    
+    /// Authenticate with an API key, limiting the context to the key's scopes
+    pub fn authenticate_api_key(&mut self, presented: &str) -> Option<UserContext> {
+        let key = match self.credentials.verify_api_key(presented) {
+            Ok(key) => key,
+            Err(e) => {
+                tracing::debug!("API key rejected: {}", e);
+                return None;
+            },
+        };
+        
+        let mut context = self.record_login(&key.user_id)?;
+        // Scopes can't outlive the roles that granted them
+        context.permissions.retain(|permission| key.scopes.contains(permission));
+        self.log_audit(&key.user_id, "LOGIN", "auth", Some(format!("API key {} used", key.id)));
+        Some(context)
+    }
+    
+    fn record_login(&mut self, user_id: &str) -> Option<UserContext> {
+        let user = self.users.get_mut(user_id)?;
+        user.last_login = Some(Utc::now());
+        self.get_user_context(user_id)
+    }
+    
+    /// Set a user's password
+    pub fn set_password(&mut self, user_id: &str, password: &str) -> Result<()> {
+        if !self.users.contains_key(user_id) {
+            return Err(UserError::UserNotFound(user_id.to_string()).into());
         }
+        self.credentials.set_password(user_id, password)?;
+        self.log_audit(user_id, "SET_PASSWORD", "credentials", None);
+        Ok(())
+    }
+    
+    /// Change a user's password, checking the current one
+    pub fn change_password(&mut self, user_id: &str, current: &str, new: &str) -> Result<()> {
+        self.credentials.rotate_password(user_id, current, new)?;
+        self.log_audit(user_id, "ROTATE_PASSWORD", "credentials", None);
+        Ok(())
+    }
+    
+    /// Create an API key whose scopes are a subset of the user's permissions
+    pub fn create_api_key(&mut self, user_id: &str, name: &str, scopes: Vec<String>, ttl: Option<Duration>) -> Result<NewApiKey> {
+        let user = self.get_user(user_id).ok_or_else(|| UserError::UserNotFound(user_id.to_string()))?;
+        let permissions = self.rbac.get_user_permissions(user);
+        if let Some(scope) = scopes.iter().find(|scope| !permissions.contains(scope)) {
+            return Err(UserError::ScopeNotHeld(scope.clone()).into());
+        }
+        
+        let created = self.credentials.create_api_key(user_id, name, scopes, ttl)?;
+        self.log_audit(user_id, "CREATE_API_KEY", "credentials", Some(format!("Created API key {}", created.key.id)));
+        Ok(created)
+    }
+    
+    /// Get an API key by ID
+    pub fn get_api_key(&self, key_id: &str) -> Option<&ApiKey> {
+        self.credentials.api_key(key_id)
+    }
+    
+    /// List a user's API keys
+    pub fn list_api_keys(&self, user_id: &str) -> Vec<ApiKey> {
+        self.credentials.list_api_keys(user_id)
+    }
+    
+    /// Revoke an API key
+    pub fn revoke_api_key(&mut self, key_id: &str) -> Result<ApiKey> {
+        let key = self.credentials.revoke_api_key(key_id)?;
+        self.log_audit(&key.user_id, "REVOKE_API_KEY", "credentials", Some(format!("Revoked API key {}", key.id)));
+        Ok(key)
+    }
+    
+    /// Replace an API key with a new secret, revoking the old one
+    pub fn rotate_api_key(&mut self, key_id: &str) -> Result<NewApiKey> {
+        let rotated = self.credentials.rotate_api_key(key_id)?;
+        self.log_audit(
+            &rotated.key.user_id,
+            "ROTATE_API_KEY",
+            "credentials",
+            Some(format!("Replaced API key {} with {}", key_id, rotated.key.id)),
+        );
+        Ok(rotated)
     }
     
     /// Check if a user has a specific permission
@@ -199,36 +507,108 @@ impl UserManager {
         }
     }
     
+    /// Check if a user's resource grants allow a request
+    pub fn authorize(&self, user_id: &str, request: &AccessRequest) -> bool {
+        self.get_user_context(user_id)
+            .map(|context| context.can(request))
+            .unwrap_or(false)
+    }
+    
+    /// Look up a built-in or custom role by name
+    pub fn role(&self, name: &str) -> Option<UserRole> {
+        self.rbac.role(name)
+    }
+    
+    /// All roles and their permissions
+    pub fn list_roles(&self) -> Vec<RoleDefinition> {
+        self.rbac.roles()
+    }
+    
+    /// Define or replace a custom role on behalf of `actor_id`
+    pub fn define_role(&mut self, actor_id: &str, name: &str, permissions: Vec<String>) -> Result<UserRole> {
+        let details = format!("Defined role {} with permissions {}", name, permissions.join(", "));
+        let role = self.rbac.define_role(name, permissions)?;
+        self.log_audit(actor_id, "DEFINE_ROLE", "roles", Some(details));
+        Ok(role)
+    }
+    
+    /// Remove a custom role no user holds any more
+    pub fn remove_role(&mut self, actor_id: &str, name: &str) -> Result<()> {
+        if let Some(role) = self.rbac.role(name) {
+            let holders = self.users.values().filter(|user| user.roles.contains(&role)).count();
+            if holders > 0 {
+                return Err(UserError::RoleInUse { name: name.to_string(), holders }.into());
+            }
+        }
+        self.rbac.remove_role(name)?;
+        self.log_audit(actor_id, "REMOVE_ROLE", "roles", Some(format!("Removed role {}", name)));
+        Ok(())
+    }
+    
     /// Add a role to a user
     pub fn add_user_role(&mut self, user_id: &str, role: UserRole) -> Result<()> {
+        if self.rbac.role(role.name()).as_ref() != Some(&role) {
+            return Err(UserError::UndefinedRole(role.name().to_string()).into());
+        }
         if let Some(user) = self.users.get_mut(user_id) {
             if !user.roles.contains(&role) {
                 user.roles.push(role.clone());
-                self.log_audit(user_id, "ADD_ROLE", "users", Some(format!("Added role {:?} to user", role)));
+                self.log_audit(user_id, "ADD_ROLE", "users", Some(format!("Added role {} to user", role.name())));
             }
             Ok(())
         } else {
-            Err(anyhow::anyhow!("User not found"))
+            Err(UserError::UserNotFound(user_id.to_string()).into())
         }
     }
     
+    /// Set a user's notification preferences
+    pub fn set_notification_preferences(&mut self, user_id: &str, preferences: NotificationPreferences) -> Result<()> {
+        if !self.users.contains_key(user_id) {
+            return Err(UserError::UserNotFound(user_id.to_string()).into());
+        }
+        
+        self.log_audit(
+            user_id,
+            "UPDATE_NOTIFICATION_PREFERENCES",
+            "users",
+            Some(format!("Digest frequency {:?}", preferences.digest_frequency)),
+        );
+        self.notification_preferences.insert(user_id.to_string(), preferences);
+        Ok(())
+    }
+    
+    /// Get a user's notification preferences, falling back to the defaults
+    pub fn get_notification_preferences(&self, user_id: &str) -> Option<NotificationPreferences> {
+        if !self.users.contains_key(user_id) {
+            return None;
+        }
+        
+        Some(self.notification_preferences.get(user_id).cloned().unwrap_or_default())
+    }
+    
     /// Log an audit entry
     pub fn log_audit(&mut self, user_id: &str, action: &str, resource: &str, details: Option<String>) {
         let log_entry = AuditLog {
             id: Uuid::new_v4().to_string(),
             user_id: user_id.to_string(),
+            tenant_id: self.users.get(user_id).map(|user| user.tenant_id.clone()).unwrap_or_default(),
             action: action.to_string(),
             resource: resource.to_string(),
             timestamp: Utc::now(),
             details,
+            correlation_id: None,
         };
         
-        self.audit_logs.push(log_entry);
+        // A failed write must not fail the audited action
+        if let Err(e) = self.audit.append(log_entry) {
+            tracing::error!("failed to persist audit entry: {}", e);
+        }
     }
     
     /// Get audit logs for a user
     pub fn get_user_audit_logs(&self, user_id: &str) -> Vec<&AuditLog> {
-        self.audit_logs
+        self.audit
+            .entries()
             .iter()
             .filter(|log| log.user_id == user_id)
             .collect()
@@ -236,7 +616,27 @@ impl UserManager {
     
     /// Get all audit logs
     pub fn get_all_audit_logs(&self) -> &Vec<AuditLog> {
-        &self.audit_logs
+        self.audit.entries()
+    }
+    
+    /// Load audit logs other services appended to the shared file since the last load
+    pub fn refresh_audit_logs(&mut self) -> Result<usize> {
+        self.audit.refresh()
+    }
+    
+    /// Audit logs matching a query, newest first
+    pub fn query_audit_logs(&self, query: &AuditQuery) -> Vec<AuditLog> {
+        self.audit.query(query)
+    }
+    
+    /// Export the audit logs matching a query as `json` or `csv`
+    pub fn export_audit_logs(&self, query: &AuditQuery, format: &str) -> Result<Vec<u8>> {
+        self.audit.export(query, format)
+    }
+    
+    /// Drop audit logs outside the store's retention policy
+    pub fn apply_audit_retention(&mut self) -> Result<usize> {
+        self.audit.apply_retention(Utc::now())
     }
     
     /// Get user context for isolated environments
@@ -284,7 +684,12 @@ mod tests {
             "tenant-1"
         ).unwrap();
         
-        let context = user_manager.authenticate_user("testuser");
+        assert!(user_manager.authenticate_user("testuser", "correct horse battery").is_none());
+        user_manager.set_password(&user.id, "correct horse battery").unwrap();
+        assert!(user_manager.authenticate_user("testuser", "wrong horse battery").is_none());
+        assert!(user_manager.authenticate_user("nobody", "correct horse battery").is_none());
+        
+        let context = user_manager.authenticate_user("testuser", "correct horse battery");
         assert!(context.is_some());
         let context = context.unwrap();
         assert_eq!(context.user_id, user.id);
@@ -292,6 +697,33 @@ mod tests {
         assert!(context.permissions.contains(&"execute_trades".to_string()));
     }
 
+    #[test]
+    fn test_api_key_authentication() {
+        let mut user_manager = UserManager::new();
+        let user = user_manager.create_user(
+            "botuser", 
+            "bot@example.com", 
+            vec![UserRole::Trader], 
+            "tenant-1"
+        ).unwrap();
+        
+        // Keys can only carry permissions the user holds
+        assert!(user_manager.create_api_key(&user.id, "bot", vec!["manage_users".to_string()], None).is_err());
+        let created = user_manager
+            .create_api_key(&user.id, "bot", vec!["view_orders".to_string()], Some(Duration::days(7)))
+            .unwrap();
+        
+        let context = user_manager.authenticate_api_key(&created.secret).unwrap();
+        assert_eq!(context.user_id, user.id);
+        assert_eq!(context.permissions, vec!["view_orders".to_string()]);
+        
+        let rotated = user_manager.rotate_api_key(&created.key.id).unwrap();
+        assert!(user_manager.authenticate_api_key(&created.secret).is_none());
+        user_manager.revoke_api_key(&rotated.key.id).unwrap();
+        assert!(user_manager.authenticate_api_key(&rotated.secret).is_none());
+        assert_eq!(user_manager.list_api_keys(&user.id).len(), 2);
+    }
+
     #[test]
     fn test_rbac_permissions() {
         let mut user_manager = UserManager::new();
@@ -324,6 +756,14 @@ mod tests {
         
         let all_logs = user_manager.get_all_audit_logs();
         assert_eq!(all_logs.len(), 2);
+        
+        // Entries carry the acting user's tenant
+        let query = AuditQuery {
+            tenant_id: Some("tenant-1".to_string()),
+            action: Some("TEST_ACTION".to_string()),
+            ..AuditQuery::default()
+        };
+        assert_eq!(user_manager.query_audit_logs(&query).len(), 1);
     }
 
     #[test]
@@ -356,4 +796,104 @@ mod tests {
         assert_eq!(context2.tenant_id, "tenant-2");
         assert_ne!(context1.user_id, context2.user_id);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_approval_policy() {
+        let mut user_manager = UserManager::new();
+        let trader = user_manager.create_user(
+            "trader", 
+            "trader@example.com", 
+            vec![UserRole::Trader], 
+            "tenant-1"
+        ).unwrap();
+        let admin = user_manager.create_user(
+            "admin", 
+            "admin@example.com", 
+            vec![UserRole::Admin], 
+            "tenant-1"
+        ).unwrap();
+        
+        let policy = ApprovalPolicy::new(1000.0, 2);
+        assert_eq!(policy.approvals_required(500.0), 0);
+        assert_eq!(policy.approvals_required(5000.0), 2);
+        
+        let trader_context = user_manager.get_user_context(&trader.id).unwrap();
+        let admin_context = user_manager.get_user_context(&admin.id).unwrap();
+        
+        // Only users holding the approver permission may approve
+        assert!(policy.can_approve(&admin_context, &trader.id, "tenant-1"));
+        assert!(!policy.can_approve(&trader_context, &admin.id, "tenant-1"));
+        
+        // Approvers can't approve their own requests or other tenants' requests
+        assert!(!policy.can_approve(&admin_context, &admin.id, "tenant-1"));
+        assert!(!policy.can_approve(&admin_context, &trader.id, "tenant-2"));
+    }
+
+    #[test]
+    fn test_notification_preferences() {
+        let mut user_manager = UserManager::new();
+        let user = user_manager.create_user(
+            "testuser", 
+            "test@example.com", 
+            vec![UserRole::Trader], 
+            "tenant-1"
+        ).unwrap();
+        
+        // Users start with the default preferences
+        let preferences = user_manager.get_notification_preferences(&user.id).unwrap();
+        assert_eq!(preferences.digest_frequency, DigestFrequency::Immediate);
+        
+        let preferences = NotificationPreferences {
+            channels: vec![NotificationChannel::Slack],
+            min_severity: NotificationSeverity::High,
+            event_types: Vec::new(),
+            digest_frequency: DigestFrequency::Daily,
+        };
+        user_manager.set_notification_preferences(&user.id, preferences).unwrap();
+        
+        let stored = user_manager.get_notification_preferences(&user.id).unwrap();
+        assert_eq!(stored.digest_frequency, DigestFrequency::Daily);
+        assert_eq!(stored.channels, vec![NotificationChannel::Slack]);
+        
+        assert!(user_manager.get_notification_preferences("missing").is_none());
+        assert!(user_manager
+            .set_notification_preferences("missing", NotificationPreferences::default())
+            .is_err());
+        assert_eq!(user_manager.get_tenant_users("tenant-1").len(), 1);
+    }
+
+    #[test]
+    fn test_custom_roles_and_resource_grants() {
+        let mut user_manager = UserManager::new();
+        let admin = user_manager.create_user("admin", "admin@example.com", vec![UserRole::Admin], "tenant-1").unwrap();
+        let desk = UserRole::Custom("desk-lead".to_string());
+        assert!(user_manager.create_user("early", "early@example.com", vec![desk.clone()], "tenant-1").is_err());
+
+        assert!(user_manager.define_role(&admin.id, "Admin", vec!["execute_trades".to_string()]).is_err());
+        assert!(user_manager.define_role(&admin.id, "desk-lead", vec!["orders:write".to_string()]).is_err());
+        let role = user_manager
+            .define_role(
+                &admin.id,
+                "desk-lead",
+                vec!["execute_trades".to_string(), "orders:*:tenant".to_string(), "!orders:cancel:tenant".to_string()],
+            )
+            .unwrap();
+        assert_eq!(role, desk);
+        assert_eq!(user_manager.role("desk-lead"), Some(desk.clone()));
+        assert_eq!(user_manager.list_roles().len(), 6);
+
+        let lead = user_manager.create_user("lead", "lead@example.com", vec![desk], "tenant-1").unwrap();
+        assert!(user_manager.user_has_permission(&lead.id, "execute_trades"));
+        assert!(user_manager.authorize(&lead.id, &AccessRequest::new("orders", "write", "tenant-1")));
+        assert!(!user_manager.authorize(&lead.id, &AccessRequest::new("orders", "cancel", "tenant-1")));
+        assert!(!user_manager.authorize(&lead.id, &AccessRequest::new("orders", "write", "tenant-2")));
+
+        // Built-in roles carry resource grants too
+        assert!(user_manager.authorize(&admin.id, &AccessRequest::new("portfolio", "write", "tenant-1")));
+        assert!(!user_manager.authorize(&admin.id, &AccessRequest::new("portfolio", "write", "tenant-2")));
+
+        assert!(user_manager.remove_role(&admin.id, "desk-lead").is_err());
+        assert!(user_manager.remove_role(&admin.id, "Trader").is_err());
+        assert_eq!(user_manager.get_user_audit_logs(&admin.id).iter().filter(|log| log.resource == "roles").count(), 1);
+    }
+}
@@ -0,0 +1,138 @@
+//! Per-user notification preferences.
+//!
+//! Preferences decide which events reach a user, at what minimum severity,
+//! over which channels, and whether they are delivered immediately or batched
+//! into a periodic digest. Producers such as the monitoring notifier and the
+//! compliance report scheduler consult them before delivering anything.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Event type for monitoring incidents
+pub const EVENT_INCIDENT: &str = "incident";
+
+/// Event type for generated compliance reports
+pub const EVENT_COMPLIANCE_REPORT: &str = "compliance_report";
+
+/// Delivery channels for notifications
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
+pub enum NotificationChannel {
+    Email,
+    Slack,
+    Webhook,
+    InApp,
+}
+
+/// Notification severity, ordered from least to most severe
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, ToSchema)]
+pub enum NotificationSeverity {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+/// How often queued notifications are delivered
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub enum DigestFrequency {
+    Immediate,
+    Hourly,
+    Daily,
+    Weekly,
+}
+
+impl DigestFrequency {
+    /// Length of a digest period, or `None` for immediate delivery
+    pub fn period(&self) -> Option<Duration> {
+        match self {
+            DigestFrequency::Immediate => None,
+            DigestFrequency::Hourly => Some(Duration::hours(1)),
+            DigestFrequency::Daily => Some(Duration::days(1)),
+            DigestFrequency::Weekly => Some(Duration::weeks(1)),
+        }
+    }
+
+    /// Check if a digest last sent at `last_sent` is due at `now`
+    pub fn is_due(&self, last_sent: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+        match (self.period(), last_sent) {
+            (None, _) | (_, None) => true,
+            (Some(period), Some(last_sent)) => now >= last_sent + period,
+        }
+    }
+}
+
+/// Notification preferences for a user
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NotificationPreferences {
+    /// Channels notifications are delivered on; empty mutes the user
+    pub channels: Vec<NotificationChannel>,
+    /// Minimum severity delivered
+    pub min_severity: NotificationSeverity,
+    /// Event types delivered; empty means all
+    #[serde(default)]
+    pub event_types: Vec<String>,
+    /// Delivery cadence
+    pub digest_frequency: DigestFrequency,
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        Self {
+            channels: vec![NotificationChannel::Email, NotificationChannel::InApp],
+            min_severity: NotificationSeverity::Info,
+            event_types: Vec::new(),
+            digest_frequency: DigestFrequency::Immediate,
+        }
+    }
+}
+
+impl NotificationPreferences {
+    /// Check if an event of this type and severity should reach the user
+    pub fn accepts(&self, event_type: &str, severity: NotificationSeverity) -> bool {
+        !self.channels.is_empty()
+            && severity >= self.min_severity
+            && (self.event_types.is_empty() || self.event_types.iter().any(|t| t == event_type))
+    }
+
+    /// Whether accepted events are delivered right away rather than batched
+    pub fn is_immediate(&self) -> bool {
+        self.digest_frequency == DigestFrequency::Immediate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preferences_filter_events() {
+        let preferences = NotificationPreferences {
+            channels: vec![NotificationChannel::Slack],
+            min_severity: NotificationSeverity::High,
+            event_types: vec![EVENT_INCIDENT.to_string()],
+            digest_frequency: DigestFrequency::Daily,
+        };
+
+        assert!(preferences.accepts(EVENT_INCIDENT, NotificationSeverity::Critical));
+        assert!(!preferences.accepts(EVENT_INCIDENT, NotificationSeverity::Medium));
+        assert!(!preferences.accepts(EVENT_COMPLIANCE_REPORT, NotificationSeverity::Critical));
+        assert!(!preferences.is_immediate());
+
+        let muted = NotificationPreferences {
+            channels: Vec::new(),
+            ..NotificationPreferences::default()
+        };
+        assert!(!muted.accepts(EVENT_INCIDENT, NotificationSeverity::Critical));
+    }
+
+    #[test]
+    fn test_digest_due() {
+        let now = Utc::now();
+        assert!(DigestFrequency::Daily.is_due(None, now));
+        assert!(!DigestFrequency::Daily.is_due(Some(now - Duration::hours(23)), now));
+        assert!(DigestFrequency::Daily.is_due(Some(now - Duration::hours(24)), now));
+        assert!(DigestFrequency::Immediate.is_due(Some(now), now));
+    }
+}
@@ -0,0 +1,246 @@
+//! Resource-scoped permissions and policy evaluation.
+//!
+//! Besides flat permission names like `execute_trades`, a role can hold
+//! resource grants of the form `resource:action:scope`, e.g.
+//! `orders:write:tenant-1` or `portfolio:read:own`. Any segment may be `*`,
+//! or end in `*` to match by prefix. The scope is matched against the tenant
+//! owning the resource: `tenant` stands for the caller's own tenant and `own`
+//! for resources the caller owns. A grant starting with `!` is a deny rule,
+//! and a matching deny overrides every allow.
+
+use crate::UserContext;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Prefix turning a resource grant into a deny rule
+pub const DENY_PREFIX: char = '!';
+
+/// Scope matching resources owned by the caller
+pub const SCOPE_OWN: &str = "own";
+
+/// Scope matching resources of the caller's tenant
+pub const SCOPE_TENANT: &str = "tenant";
+
+/// Whether a rule grants or withholds access
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Effect {
+    Allow,
+    Deny,
+}
+
+/// A parsed `resource:action:scope` grant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyRule {
+    pub effect: Effect,
+    pub resource: String,
+    pub action: String,
+    pub scope: String,
+}
+
+/// An action on a resource owned by a tenant and optionally a user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessRequest<'a> {
+    pub resource: &'a str,
+    pub action: &'a str,
+    pub tenant_id: &'a str,
+    pub owner_id: Option<&'a str>,
+}
+
+/// Outcome of evaluating grants against a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Decision {
+    Allow,
+    Deny,
+    /// No rule matched, which denies by default
+    NoMatch,
+}
+
+impl Decision {
+    /// Whether access is granted
+    pub fn is_allowed(self) -> bool {
+        self == Decision::Allow
+    }
+}
+
+impl PolicyRule {
+    /// Whether a grant is a resource grant rather than a flat permission name
+    pub fn is_resource_grant(grant: &str) -> bool {
+        grant.contains(':')
+    }
+
+    /// Whether the rule applies to a request made by `subject`
+    pub fn matches(&self, subject: &UserContext, request: &AccessRequest) -> bool {
+        glob_match(&self.resource, request.resource)
+            && glob_match(&self.action, request.action)
+            && self.matches_scope(subject, request)
+    }
+
+    fn matches_scope(&self, subject: &UserContext, request: &AccessRequest) -> bool {
+        match self.scope.as_str() {
+            SCOPE_OWN => {
+                request.tenant_id == subject.tenant_id && request.owner_id == Some(subject.user_id.as_str())
+            },
+            SCOPE_TENANT => request.tenant_id == subject.tenant_id,
+            scope => glob_match(scope, request.tenant_id),
+        }
+    }
+}
+
+impl FromStr for PolicyRule {
+    type Err = anyhow::Error;
+
+    fn from_str(grant: &str) -> Result<Self> {
+        let (effect, body) = match grant.strip_prefix(DENY_PREFIX) {
+            Some(body) => (Effect::Deny, body),
+            None => (Effect::Allow, grant),
+        };
+        let segments: Vec<&str> = body.split(':').collect();
+        let [resource, action, scope] = segments[..] else {
+            return Err(anyhow::anyhow!("Grant {} must have the form resource:action:scope", grant));
+        };
+        for segment in [resource, action, scope] {
+            let literal = segment.strip_suffix('*').unwrap_or(segment);
+            if (segment.is_empty() || literal.contains('*'))
+                || !literal.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+            {
+                return Err(anyhow::anyhow!("Invalid segment {:?} in grant {}", segment, grant));
+            }
+        }
+        Ok(Self {
+            effect,
+            resource: resource.to_string(),
+            action: action.to_string(),
+            scope: scope.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for PolicyRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.effect == Effect::Deny {
+            write!(f, "{}", DENY_PREFIX)?;
+        }
+        write!(f, "{}:{}:{}", self.resource, self.action, self.scope)
+    }
+}
+
+impl<'a> AccessRequest<'a> {
+    /// Request for an action on a tenant's resource
+    pub fn new(resource: &'a str, action: &'a str, tenant_id: &'a str) -> Self {
+        Self {
+            resource,
+            action,
+            tenant_id,
+            owner_id: None,
+        }
+    }
+
+    /// Set the user owning the resource, so `own` grants can apply
+    pub fn owned_by(mut self, owner_id: &'a str) -> Self {
+        self.owner_id = Some(owner_id);
+        self
+    }
+}
+
+/// Check that a grant is a valid flat permission name or resource grant
+pub fn validate_grant(grant: &str) -> Result<()> {
+    if PolicyRule::is_resource_grant(grant) {
+        return grant.parse::<PolicyRule>().map(|_| ());
+    }
+    if grant.is_empty() || !grant.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(anyhow::anyhow!("Invalid permission name {:?}", grant));
+    }
+    Ok(())
+}
+
+/// Evaluate the resource grants among `grants`; flat permission names are ignored
+pub fn evaluate<'g>(grants: impl IntoIterator<Item = &'g String>, subject: &UserContext, request: &AccessRequest) -> Decision {
+    let mut decision = Decision::NoMatch;
+    for grant in grants.into_iter().filter(|grant| PolicyRule::is_resource_grant(grant)) {
+        let Ok(rule) = grant.parse::<PolicyRule>() else {
+            continue;
+        };
+        if rule.matches(subject, request) {
+            match rule.effect {
+                Effect::Deny => return Decision::Deny,
+                Effect::Allow => decision = Decision::Allow,
+            }
+        }
+    }
+    decision
+}
+
+/// Check if a caller's grants allow a request
+pub fn authorize(subject: &UserContext, request: &AccessRequest) -> bool {
+    !subject.is_anonymous() && evaluate(&subject.permissions, subject, request).is_allowed()
+}
+
+fn glob_match(pattern: &str, value: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => value.starts_with(prefix),
+        None => pattern == value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subject(grants: &[&str]) -> UserContext {
+        UserContext {
+            user_id: "alice".to_string(),
+            tenant_id: "tenant-1".to_string(),
+            roles: Vec::new(),
+            permissions: grants.iter().map(|grant| grant.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse_grants() {
+        let rule: PolicyRule = "!orders:write:tenant-*".parse().unwrap();
+        assert_eq!(rule.effect, Effect::Deny);
+        assert_eq!((rule.resource.as_str(), rule.scope.as_str()), ("orders", "tenant-*"));
+        assert_eq!(rule.to_string(), "!orders:write:tenant-*");
+
+        assert!("orders:write".parse::<PolicyRule>().is_err());
+        assert!("orders:w*te:own".parse::<PolicyRule>().is_err());
+        assert!("orders::own".parse::<PolicyRule>().is_err());
+        assert!(validate_grant("execute_trades").is_ok());
+        assert!(validate_grant("!execute_trades").is_err());
+        assert!(validate_grant("portfolio:read:own").is_ok());
+    }
+
+    #[test]
+    fn test_scopes_and_wildcards() {
+        let trader = subject(&["orders:*:own", "portfolio:read:tenant", "reports:read:tenant-2"]);
+        let own_order = AccessRequest::new("orders", "write", "tenant-1").owned_by("alice");
+        assert!(authorize(&trader, &own_order));
+        assert!(!authorize(&trader, &own_order.owned_by("bob")));
+        assert!(!authorize(&trader, &AccessRequest::new("orders", "write", "tenant-1")));
+
+        assert!(authorize(&trader, &AccessRequest::new("portfolio", "read", "tenant-1")));
+        assert!(!authorize(&trader, &AccessRequest::new("portfolio", "read", "tenant-2")));
+        assert!(!authorize(&trader, &AccessRequest::new("portfolio", "write", "tenant-1")));
+        assert!(authorize(&trader, &AccessRequest::new("reports", "read", "tenant-2")));
+
+        let mut anonymous = subject(&["*:*:*"]);
+        anonymous.user_id.clear();
+        assert!(!authorize(&anonymous, &AccessRequest::new("orders", "read", "tenant-1")));
+    }
+
+    #[test]
+    fn test_deny_overrides_allow() {
+        let operator = subject(&["*:*:*", "!orders:write:tenant-2", "execute_trades"]);
+        assert!(authorize(&operator, &AccessRequest::new("orders", "write", "tenant-3")));
+        assert_eq!(
+            evaluate(&operator.permissions, &operator, &AccessRequest::new("orders", "write", "tenant-2")),
+            Decision::Deny
+        );
+        assert_eq!(
+            evaluate(&subject(&["execute_trades"]).permissions, &operator, &AccessRequest::new("orders", "write", "tenant-1")),
+            Decision::NoMatch
+        );
+    }
+}
@@ -0,0 +1,157 @@
+//! Permission-aware response redaction.
+//!
+//! Viewers holding `view_all_data` see records as they are. Everyone else
+//! gets a shaped view: records they do not own are dropped and sensitive
+//! fields such as wallet addresses are masked. Types opt in by implementing
+//! [`Redact`]; services pass their response data through [`redact`] or
+//! [`redact_all`] before serializing it.
+
+use crate::UserContext;
+
+/// Permission that bypasses redaction
+pub const VIEW_ALL_DATA: &str = "view_all_data";
+
+/// Characters kept at each end of a masked address
+const MASK_KEEP: usize = 6;
+
+/// A record whose visibility and fields depend on who is viewing it
+pub trait Redact {
+    /// Whether a viewer without `view_all_data` may see the record at all
+    fn visible_to(&self, _viewer: &UserContext) -> bool {
+        true
+    }
+
+    /// Mask the fields a viewer without `view_all_data` may not see
+    fn redact_fields(&mut self, _viewer: &UserContext) {}
+}
+
+/// Check if a viewer sees unredacted data
+pub fn can_view_all(viewer: &UserContext) -> bool {
+    viewer.permissions.iter().any(|permission| permission == VIEW_ALL_DATA)
+}
+
+/// Check if a viewer may see data belonging to a tenant
+pub fn can_view_tenant(viewer: &UserContext, tenant_id: &str) -> bool {
+    can_view_all(viewer) || (!viewer.is_anonymous() && viewer.tenant_id == tenant_id)
+}
+
+/// Shape a record for a viewer, returning `None` if it must be hidden
+pub fn redact<T: Redact>(mut value: T, viewer: &UserContext) -> Option<T> {
+    if can_view_all(viewer) {
+        return Some(value);
+    }
+    if !value.visible_to(viewer) {
+        return None;
+    }
+    value.redact_fields(viewer);
+    Some(value)
+}
+
+/// Shape a list of records for a viewer, dropping hidden ones
+pub fn redact_all<T: Redact>(values: Vec<T>, viewer: &UserContext) -> Vec<T> {
+    values
+        .into_iter()
+        .filter_map(|value| redact(value, viewer))
+        .collect()
+}
+
+/// Mask the middle of an address, keeping its first and last characters
+pub fn mask_address(address: &str) -> String {
+    let chars: Vec<char> = address.chars().collect();
+    if chars.len() <= MASK_KEEP * 2 {
+        return "*".repeat(chars.len());
+    }
+    let head: String = chars[..MASK_KEEP].iter().collect();
+    let tail: String = chars[chars.len() - MASK_KEEP..].iter().collect();
+    format!("{}...{}", head, tail)
+}
+
+impl UserContext {
+    /// Context for requests that carry no user identity
+    pub fn anonymous() -> Self {
+        Self {
+            user_id: String::new(),
+            tenant_id: String::new(),
+            roles: Vec::new(),
+            permissions: Vec::new(),
+        }
+    }
+
+    /// Whether the context identifies a user
+    pub fn is_anonymous(&self) -> bool {
+        self.user_id.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UserRole;
+
+    struct Wallet {
+        owner: String,
+        address: String,
+    }
+
+    impl Redact for Wallet {
+        fn visible_to(&self, viewer: &UserContext) -> bool {
+            self.owner == viewer.user_id
+        }
+
+        fn redact_fields(&mut self, _viewer: &UserContext) {
+            self.address = mask_address(&self.address);
+        }
+    }
+
+    fn context(user_id: &str, permissions: &[&str]) -> UserContext {
+        UserContext {
+            user_id: user_id.to_string(),
+            tenant_id: "tenant-1".to_string(),
+            roles: vec![UserRole::Trader],
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    fn wallets() -> Vec<Wallet> {
+        vec![
+            Wallet {
+                owner: "user-1".to_string(),
+                address: "0x1234567890abcdef1234567890abcdef12345678".to_string(),
+            },
+            Wallet {
+                owner: "user-2".to_string(),
+                address: "0xabcdef".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_redaction_without_view_all_data() {
+        let visible = redact_all(wallets(), &context("user-1", &["view_orders"]));
+        assert_eq!(visible.len(), 1);
+        assert_eq!(visible[0].address, "0x1234...345678");
+
+        assert!(redact_all(wallets(), &UserContext::anonymous()).is_empty());
+    }
+
+    #[test]
+    fn test_view_all_data_sees_everything() {
+        let visible = redact_all(wallets(), &context("admin", &[VIEW_ALL_DATA]));
+        assert_eq!(visible.len(), 2);
+        assert_eq!(visible[1].address, "0xabcdef");
+    }
+
+    #[test]
+    fn test_tenant_visibility() {
+        assert!(can_view_tenant(&context("user-1", &[]), "tenant-1"));
+        assert!(!can_view_tenant(&context("user-1", &[]), "tenant-2"));
+        assert!(can_view_tenant(&context("admin", &[VIEW_ALL_DATA]), "tenant-2"));
+        assert!(!can_view_tenant(&UserContext::anonymous(), ""));
+    }
+
+    #[test]
+    fn test_mask_short_address() {
+        assert_eq!(mask_address("0xabcdef"), "********");
+        assert_eq!(mask_address(""), "");
+    }
+}
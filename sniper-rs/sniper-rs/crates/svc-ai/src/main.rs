@@ -1,30 +1,52 @@
 //! AI service for the sniper-rs ecosystem.
 //! 
 //! This service provides REST APIs for AI-driven trading strategies and market prediction.
+//! With `--model-path`, a saved model is loaded on startup and every trained
+//! model version is written back to it.
 
 use anyhow::Result;
 use clap::Parser;
+use sniper_core::config::ConfigArgs;
+use sniper_core::errors::{ApiError, ErrorKind};
+use sniper_core::rate_limit::{RateLimitConfig, RateLimiter};
+use sniper_core::shutdown::Shutdown;
 use serde::{Deserialize, Serialize};
 use axum::{
     routing::{get, post},
     Json, Router, Extension,
 };
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use sniper_ai::{AiTradingStrategy, AiModelConfig, MarketDataPoint, MarketPrediction};
+use sniper_ai::{AiTradingStrategy, AiModelConfig, MarketDataPoint, MarketPrediction, TrainedModel};
+
+/// Port used when neither the configuration nor --port sets one
+const DEFAULT_PORT: u16 = 8096;
 
 /// CLI arguments for the AI service
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    /// Port to listen on
-    #[clap(short, long, default_value = "8096")]
-    port: u16,
+    /// Port to listen on, overriding the configured port (default 8096)
+    #[clap(short, long)]
+    port: Option<u16>,
+    
+    /// File the trained model is loaded from and saved to
+    #[clap(long)]
+    model_path: Option<PathBuf>,
+
+    /// OTLP endpoint spans are exported to, defaulting to OTEL_EXPORTER_OTLP_ENDPOINT
+    #[clap(long)]
+    otlp_endpoint: Option<String>,
+
+    #[clap(flatten)]
+    config: ConfigArgs,
 }
 
 /// AI service state
 struct AppState {
     ai_strategy: RwLock<AiTradingStrategy>,
+    model_path: Option<PathBuf>,
 }
 
 /// Health check response
@@ -49,7 +71,7 @@ struct MarketDataResponse {
 }
 
 /// Prediction response
-#[derive(Serialize)]
+#[derive(Debug, Serialize)]
 struct PredictionResponse {
     success: bool,
     data: Option<MarketPrediction>,
@@ -57,17 +79,35 @@ struct PredictionResponse {
 }
 
 /// Training response
-#[derive(Serialize)]
+#[derive(Debug, Serialize)]
 struct TrainingResponse {
     success: bool,
     message: String,
 }
 
+/// Model response
+#[derive(Debug, Serialize)]
+struct ModelResponse {
+    success: bool,
+    data: Option<TrainedModel>,
+    message: Option<String>,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
     
     let args = Args::parse();
+    let service_config = args.config.loader("svc-ai")
+        .with_default("port", DEFAULT_PORT)
+        .with_override("port", args.port)
+        .load_service()?;
+    if args.config.print_config {
+        println!("{}", service_config.render()?);
+        return Ok(());
+    }
+    let _telemetry = sniper_core::telemetry::init_telemetry("svc-ai", args.otlp_endpoint.as_deref())?;
+    let shutdown = Shutdown::on_signal();
     
     // Create AI strategy with default config
     let config = AiModelConfig {
@@ -78,27 +118,37 @@ async fn main() -> Result<()> {
         confidence_threshold: 0.7,
     };
     
-    let ai_strategy = AiTradingStrategy::new(config);
+    let mut ai_strategy = AiTradingStrategy::new(config);
+    if let Some(path) = args.model_path.as_ref().filter(|path| path.exists()) {
+        ai_strategy.load_model(path)?;
+    }
     
     // Create app state
     let app_state = Arc::new(AppState {
         ai_strategy: RwLock::new(ai_strategy),
+        model_path: args.model_path,
     });
     
+    // Throttle callers per tenant, separately for reads and trades
+    let rate_limiter = RateLimiter::new(service_config.section::<RateLimitConfig>("rate_limit")?.unwrap_or_default());
+
     // Create router
     let app = Router::new()
         .route("/health", get(health_check))
         .route("/data", post(add_market_data))
         .route("/predict", get(get_prediction))
         .route("/train", post(train_model))
-        .layer(Extension(app_state));
+        .route("/model", get(get_model))
+        .layer(Extension(app_state))
+        .layer(rate_limiter.layer())
+        .layer(axum::middleware::from_fn(sniper_core::telemetry::trace_http));
     
     // Run server
-    let addr = format!("0.0.0.0:{}", args.port);
+    let addr = format!("0.0.0.0:{}", service_config.port.unwrap_or(DEFAULT_PORT));
     tracing::info!("AI service listening on http://{}", addr);
     
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    sniper_core::shutdown::serve(listener, app, &shutdown).await?;
         
     Ok(())
 }
@@ -136,43 +186,57 @@ async fn add_market_data(
 /// Get market prediction
 async fn get_prediction(
     Extension(state): Extension<Arc<AppState>>,
-) -> Json<PredictionResponse> {
-    match state.ai_strategy.read().await.predict() {
-        Ok(prediction) => {
-            Json(PredictionResponse {
-                success: true,
-                data: Some(prediction),
-                message: None,
-            })
-        },
-        Err(e) => {
-            Json(PredictionResponse {
-                success: false,
-                data: None,
-                message: Some(format!("Error generating prediction: {}", e)),
-            })
-        }
-    }
+) -> Result<Json<PredictionResponse>, ApiError> {
+    let prediction = state
+        .ai_strategy
+        .read()
+        .await
+        .predict()
+        .map_err(|e| ApiError::from(e).context("Error generating prediction"))?;
+    Ok(Json(PredictionResponse {
+        success: true,
+        data: Some(prediction),
+        message: None,
+    }))
 }
 
 /// Train the AI model
+///
+/// Responds 409 while there is not enough market data to train on.
 async fn train_model(
     Extension(state): Extension<Arc<AppState>>,
-) -> Json<TrainingResponse> {
-    match state.ai_strategy.write().await.train() {
-        Ok(_) => {
-            Json(TrainingResponse {
-                success: true,
-                message: "Model trained successfully".to_string(),
-            })
-        },
-        Err(e) => {
-            Json(TrainingResponse {
-                success: false,
-                message: format!("Error training model: {}", e),
-            })
-        }
+) -> Result<Json<TrainingResponse>, ApiError> {
+    let mut ai_strategy = state.ai_strategy.write().await;
+    let model = ai_strategy
+        .train()
+        .map_err(|e| ApiError::from(e).context("Error training model"))?
+        .ok_or_else(|| ApiError::new(ErrorKind::Conflict, "Not enough market data to train the model"))?;
+    let message = format!(
+        "Model v{} trained on {} samples, walk-forward accuracy {:.3}",
+        model.version, model.samples, model.validation.mean_accuracy
+    );
+    if let Some(path) = &state.model_path {
+        ai_strategy
+            .save_model(path)
+            .map_err(|e| ApiError::from(e).context(&format!("{}, but saving it failed", message)))?;
     }
+    Ok(Json(TrainingResponse {
+        success: true,
+        message,
+    }))
+}
+
+/// Get the trained model
+async fn get_model(
+    Extension(state): Extension<Arc<AppState>>,
+) -> Result<Json<ModelResponse>, ApiError> {
+    let ai_strategy = state.ai_strategy.read().await;
+    let model = ai_strategy.model().ok_or_else(|| ApiError::not_found("No model trained yet"))?;
+    Ok(Json(ModelResponse {
+        success: true,
+        data: Some(model.clone()),
+        message: None,
+    }))
 }
 
 #[cfg(test)]
@@ -182,7 +246,8 @@ mod tests {
     #[test]
     fn test_args_parsing() {
         let args = Args::parse_from(["svc-ai", "--port", "8097"]);
-        assert_eq!(args.port, 8097);
+        assert_eq!(args.port, Some(8097));
+        assert!(args.model_path.is_none());
     }
 
     #[tokio::test]
@@ -198,8 +263,29 @@ mod tests {
         let ai_strategy = AiTradingStrategy::new(config);
         let _app_state = Arc::new(AppState {
             ai_strategy: RwLock::new(ai_strategy),
+            model_path: None,
         });
         
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_handler_errors_map_to_status() {
+        let config = AiModelConfig {
+            model_type: "lstm".to_string(),
+            features: vec!["price".to_string(), "volume".to_string()],
+            lookback_period: 100,
+            prediction_horizon: 10,
+            confidence_threshold: 0.7,
+        };
+        let state = Arc::new(AppState {
+            ai_strategy: RwLock::new(AiTradingStrategy::new(config)),
+            model_path: None,
+        });
+
+        let error = train_model(Extension(state.clone())).await.unwrap_err();
+        assert_eq!(error.status(), axum::http::StatusCode::CONFLICT);
+        let error = get_model(Extension(state)).await.unwrap_err();
+        assert_eq!(error.status(), axum::http::StatusCode::NOT_FOUND);
+    }
+}
@@ -46,6 +46,8 @@ async fn test_ai_service_data_handling() {
             rsi: 50.0,
             macd: 0.0,
             signal: None,
+            buy_volume: 0.0,
+            sell_volume: 0.0,
         };
         
         ai_strategy.add_data_point(data_point);
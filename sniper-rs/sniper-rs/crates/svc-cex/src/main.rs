@@ -1,6 +1,39 @@
+use clap::Parser;
+use sniper_core::config::ConfigArgs;
+use sniper_core::shutdown::Shutdown;
+use sniper_cex::binance::{TESTNET_REST_ENDPOINT, TESTNET_WS_ENDPOINT};
+use sniper_cex::{Client, ExchangeId, Symbol};
 use sniper_core::{bus::InMemoryBus, prelude::*};
 use tokio::time::{sleep, Duration};
 
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Exchange connected to
+    #[clap(long, default_value = "binance")]
+    exchange: String,
+
+    /// REST endpoint of the exchange, the Binance spot testnet by default
+    #[clap(long, default_value = TESTNET_REST_ENDPOINT)]
+    rest_endpoint: String,
+
+    /// WebSocket endpoint of the exchange, the Binance spot testnet by default
+    #[clap(long, default_value = TESTNET_WS_ENDPOINT)]
+    ws_endpoint: String,
+
+    /// Symbol whose books and trades are published, e.g. BTC/USDT, repeated per symbol;
+    /// demo signals are published without any
+    #[clap(long = "symbol")]
+    symbols: Vec<String>,
+
+    /// OTLP endpoint spans are exported to, defaulting to OTEL_EXPORTER_OTLP_ENDPOINT
+    #[clap(long)]
+    otlp_endpoint: Option<String>,
+
+    #[clap(flatten)]
+    config: ConfigArgs,
+}
+
 #[tokio::main]
 async fn main() -> eyre::Result<()> {
     tracing_subscriber::fmt()
@@ -8,33 +41,66 @@ async fn main() -> eyre::Result<()> {
         .json()
         .init();
     dotenvy::dotenv().ok();
+    let args = Args::parse();
+    let config = args.config.loader("svc-cex").load_service()?;
+    if args.config.print_config {
+        println!("{}", config.render()?);
+        return Ok(());
+    }
+    let _telemetry = sniper_core::telemetry::init_telemetry("svc-cex", args.otlp_endpoint.as_deref())
+        .map_err(|e| eyre::eyre!("{}", e))?;
+    let shutdown = Shutdown::on_signal();
 
     let bus = InMemoryBus::new(1024);
 
-    // Demo: publisher task
-    let tx_bus = bus.clone();
-    tokio::spawn(async move {
-        loop {
-            let sig = Signal {
-                source: "dex".into(),
-                kind: "pair_created".into(),
-                chain: ChainRef {
-                    name: "ethereum".into(),
-                    id: 1,
-                },
-                token0: None,
-                token1: None,
-                extra: serde_json::json!({"demo":true}),
-                seen_at_ms: 0,
-            };
-            let _ = tx_bus.publish("signals.dex.pair_created", &sig).await;
-            sleep(Duration::from_secs(5)).await;
-        }
-    });
+    if !args.symbols.is_empty() {
+        // Trading keys are optional; market data needs none
+        let client = Client::new(
+            ExchangeId(args.exchange.clone()),
+            std::env::var("CEX_API_KEY").unwrap_or_default(),
+            std::env::var("CEX_API_SECRET").unwrap_or_default(),
+            args.rest_endpoint.clone(),
+            args.ws_endpoint.clone(),
+        );
+        let adapter = client.adapter().map_err(|e| eyre::eyre!("{}", e))?;
+        let symbols: Vec<Symbol> = args.symbols.iter().map(|symbol| Symbol(symbol.clone())).collect();
+        let mut stream = adapter.subscribe_market(&symbols).await.map_err(|e| eyre::eyre!("{}", e))?;
+        tracing::info!("Streaming {} symbols from {}", symbols.len(), args.exchange);
+        let tx_bus = bus.clone();
+        shutdown.spawn(async move {
+            while let Some(event) = stream.recv().await {
+                let subject = event.subject(adapter.exchange_id());
+                if let Err(e) = tx_bus.publish_now(&subject, &event) {
+                    tracing::warn!("Failed to publish {}: {}", subject, e);
+                }
+            }
+        });
+    } else {
+        // Demo: publisher task
+        let tx_bus = bus.clone();
+        shutdown.spawn(async move {
+            loop {
+                let sig = Signal {
+                    source: "dex".into(),
+                    kind: "pair_created".into(),
+                    chain: ChainRef {
+                        name: "ethereum".into(),
+                        id: 1,
+                    },
+                    token0: None,
+                    token1: None,
+                    extra: serde_json::json!({"demo":true}),
+                    seen_at_ms: 0,
+                };
+                let _ = tx_bus.publish("signals.dex.pair_created", &sig).await;
+                sleep(Duration::from_secs(5)).await;
+            }
+        });
+    }
 
     // Demo: subscriber task
     let mut rx = bus.subscribe("signals.>");
-    tokio::spawn(async move {
+    shutdown.spawn(async move {
         loop {
             if let Ok(bytes) = rx.recv().await {
                 if let Ok(sig) = serde_json::from_slice::<Signal>(&bytes) {
@@ -44,8 +110,8 @@ async fn main() -> eyre::Result<()> {
         }
     });
 
-    // Keep running; a real service would expose HTTP/gRPC, healthz, etc.
-    loop {
-        sleep(Duration::from_secs(3600)).await;
-    }
+    // Run until SIGINT or SIGTERM, then let the background tasks stop
+    shutdown.wait().await;
+    shutdown.join().await;
+    Ok(())
 }
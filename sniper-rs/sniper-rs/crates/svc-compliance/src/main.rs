@@ -1,47 +1,148 @@
 //! Compliance service for the sniper-rs enterprise features.
 //! 
 //! This service provides REST APIs for compliance reporting, disaster recovery,
-//! and backup/restore capabilities.
+//! and backup/restore capabilities. Given the audit log svc-users writes,
+//! trade audit reports list the tenant's audit entries for the period.
+//! Trade audits and financial summaries tabulate the orders, fills and
+//! positions exported to `--report-data`.
+//! Backups cover the service's reports and recovery plans plus any config
+//! files passed with `--config-file`, and are written to `--backup-dir` or
+//! an S3-compatible bucket. Backup schedules loaded from `--backup-schedules`
+//! or created over the API run in the background; failed scheduled backups
+//! open monitoring incidents. Recovery plans execute in the background and
+//! their progress streams as server-sent events from `/dr-runs/:id/events`.
+//! The OpenAPI document is served on `/openapi.json`, with a Swagger UI on
+//! `/docs`.
 
 use anyhow::Result;
 use clap::Parser;
+use sniper_core::config::ConfigArgs;
+use sniper_core::errors::ApiError;
+use sniper_core::openapi::{self, BearerAuth, ErrorResponse};
+use sniper_core::rate_limit::{RateLimitConfig, RateLimiter};
+use sniper_core::shutdown::Shutdown;
 use serde::{Deserialize, Serialize};
 use axum::{
+    handler::Handler,
+    http::StatusCode,
+    response::{sse::{Event, KeepAlive, Sse}, IntoResponse, Response},
     routing::{get, post},
     Json, Router, Extension,
 };
+use futures::StreamExt;
+use std::collections::HashMap;
 use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::RwLock;
+use utoipa::{OpenApi, ToSchema};
+use sniper_compliance::components::{CONFIGS, RECOVERY_PLANS, REPORTS};
 use sniper_compliance::{
+    ComplianceError,
     ComplianceManager, 
     BackupManager, 
+    ConfigFiles,
+    BackupSchedule,
+    BackupScheduler,
+    LocalDirDestination,
+    S3Config,
+    S3Destination,
     DisasterRecoveryManager, 
+    ReportDataSet,
     ReportType, 
     ComplianceReport, 
     BackupMetadata, 
     DisasterRecoveryPlan,
-    RecoveryStep
+    RecoveryHandle,
+    RecoveryProgress,
+    RecoveryRun,
+    RecoveryStep,
+    RunStatus,
 };
+use sniper_core::bus::InMemoryBus;
+use sniper_monitoring::events::spawn_trading_event_listener;
+use sniper_monitoring::http::{instrument, ServiceMetrics};
+use sniper_monitoring::MonitoringSystem;
+use sniper_users::{require_permission, AuthLayer, JwtAuth};
 use chrono::{DateTime, Utc};
 
+/// Port used when neither the configuration nor --port sets one
+const DEFAULT_PORT: u16 = 8085;
+
 /// CLI arguments for the compliance service
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    /// Port to listen on
-    #[clap(short, long, default_value = "8085")]
-    port: u16,
+    /// Port to listen on, overriding the configured port (default 8085)
+    #[clap(short, long)]
+    port: Option<u16>,
+
+    /// Audit log file written by svc-users, read for trade audit reports
+    #[clap(long)]
+    audit_log: Option<std::path::PathBuf>,
+
+    /// JSON file of orders, fills, funding charges and positions that trade audit and financial summary reports cover
+    #[clap(long)]
+    report_data: Option<std::path::PathBuf>,
+
+    /// Directory to store backup archives in; kept in memory if neither this nor an S3 bucket is set
+    #[clap(long, conflicts_with = "s3_bucket")]
+    backup_dir: Option<std::path::PathBuf>,
+
+    /// S3-compatible bucket to store backup archives in, using AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY
+    #[clap(long, requires = "s3_endpoint")]
+    s3_bucket: Option<String>,
+
+    /// Endpoint of the S3-compatible service, e.g. https://s3.eu-west-1.amazonaws.com
+    #[clap(long)]
+    s3_endpoint: Option<String>,
+
+    /// Region of the S3 bucket
+    #[clap(long, default_value = "us-east-1")]
+    s3_region: String,
+
+    /// Key prefix of backup archives in the S3 bucket
+    #[clap(long, default_value = "")]
+    s3_prefix: String,
+
+    /// Config file to include in backups; may be repeated
+    #[clap(long = "config-file")]
+    config_files: Vec<std::path::PathBuf>,
+
+    /// JSON file with a list of backup schedules to run
+    #[clap(long)]
+    backup_schedules: Option<std::path::PathBuf>,
+
+    /// Seconds between checks for due backup schedules
+    #[clap(long, default_value = "60")]
+    backup_schedule_interval_secs: u64,
+
+    /// Tenant of incidents opened for events without their own tenant
+    #[clap(long, default_value = "default")]
+    tenant_id: String,
+
+    /// OTLP endpoint spans are exported to, defaulting to OTEL_EXPORTER_OTLP_ENDPOINT
+    #[clap(long)]
+    otlp_endpoint: Option<String>,
+
+    #[clap(flatten)]
+    config: ConfigArgs,
 }
 
 /// Compliance service state
 struct AppState {
-    compliance_manager: RwLock<ComplianceManager>,
-    backup_manager: RwLock<BackupManager>,
-    dr_manager: RwLock<DisasterRecoveryManager>,
+    compliance_manager: Arc<RwLock<ComplianceManager>>,
+    backup_manager: Arc<RwLock<BackupManager>>,
+    backup_scheduler: Arc<RwLock<BackupScheduler>>,
+    dr_manager: Arc<RwLock<DisasterRecoveryManager>>,
+    /// Recovery plan runs by ID, including finished ones
+    dr_runs: RwLock<HashMap<String, RecoveryHandle>>,
+    metrics: Arc<ServiceMetrics>,
+    /// Shutdown waiting for recovery runs in progress
+    shutdown: Shutdown,
 }
 
 /// Report generation request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct GenerateReportRequest {
     pub report_type: String, // Will be parsed into ReportType
     pub period_start: String, // ISO 8601 format
@@ -51,14 +152,14 @@ struct GenerateReportRequest {
 }
 
 /// Backup creation request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct CreateBackupRequest {
     pub components: Vec<String>,
     pub tenant_id: String,
 }
 
 /// Disaster recovery plan creation request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct CreateDRPlanRequest {
     pub name: String,
     pub description: String,
@@ -67,7 +168,7 @@ struct CreateDRPlanRequest {
 }
 
 /// Standard response format
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
@@ -75,7 +176,7 @@ struct ApiResponse<T> {
 }
 
 /// Compliance report response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct ReportResponse {
     pub id: String,
     pub report_type: String,
@@ -103,7 +204,7 @@ impl From<ComplianceReport> for ReportResponse {
 }
 
 /// Backup metadata response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct BackupResponse {
     pub id: String,
     pub created_at: String,
@@ -111,6 +212,8 @@ struct BackupResponse {
     pub checksum: String,
     pub components: Vec<String>,
     pub tenant_id: String,
+    pub location: String,
+    pub schedule_id: Option<String>,
 }
 
 impl From<BackupMetadata> for BackupResponse {
@@ -122,12 +225,14 @@ impl From<BackupMetadata> for BackupResponse {
             checksum: backup.checksum,
             components: backup.components,
             tenant_id: backup.tenant_id,
+            location: backup.location,
+            schedule_id: backup.schedule_id,
         }
     }
 }
 
 /// Disaster recovery plan response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct DRPlanResponse {
     pub id: String,
     pub name: String,
@@ -152,52 +257,185 @@ impl From<DisasterRecoveryPlan> for DRPlanResponse {
     }
 }
 
+/// OpenAPI document of the service, served on `/openapi.json`
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        health_check, generate_report, get_report, list_tenant_reports, export_report, create_backup, get_backup,
+        list_tenant_backups, restore_backup, verify_backup, create_backup_schedule, get_backup_schedule,
+        delete_backup_schedule, list_tenant_backup_schedules, create_dr_plan, get_dr_plan, list_tenant_dr_plans,
+        execute_dr_plan, get_dr_run, stream_dr_run,
+    ),
+    modifiers(&BearerAuth),
+    security(("bearer_auth" = [])),
+    tags(
+        (name = "reports", description = "Compliance reports"),
+        (name = "backups", description = "Backups, restores and backup schedules"),
+        (name = "recovery", description = "Disaster recovery plans and their runs"),
+    )
+)]
+struct ApiDoc;
+
+/// Back up the service's reports and recovery plans, and any config files, to the configured destination
+fn build_backup_manager(
+    args: &Args,
+    compliance_manager: Arc<RwLock<ComplianceManager>>,
+    dr_manager: Arc<RwLock<DisasterRecoveryManager>>,
+) -> Result<BackupManager> {
+    let mut backup_manager = BackupManager::new()
+        .with_component(REPORTS, compliance_manager)
+        .with_component(RECOVERY_PLANS, dr_manager);
+    if !args.config_files.is_empty() {
+        backup_manager = backup_manager.with_component(CONFIGS, Arc::new(ConfigFiles::new(args.config_files.clone())?));
+    }
+    match (&args.backup_dir, &args.s3_bucket, &args.s3_endpoint) {
+        (Some(dir), _, _) => Ok(backup_manager.with_destination(Arc::new(LocalDirDestination::new(dir)))),
+        (None, Some(bucket), Some(endpoint)) => {
+            let config = S3Config::from_env(endpoint, bucket, &args.s3_region)?.with_prefix(args.s3_prefix.clone());
+            Ok(backup_manager.with_destination(Arc::new(S3Destination::new(config))))
+        },
+        _ => {
+            tracing::warn!("No backup destination configured, backups are kept in memory");
+            Ok(backup_manager)
+        },
+    }
+}
+
+/// Load backup schedules from a JSON file holding a list of them
+fn load_backup_schedules(path: &std::path::Path, bus: InMemoryBus) -> Result<BackupScheduler> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read backup schedules {}: {}", path.display(), e))?;
+    let schedules: Vec<BackupSchedule> = serde_json::from_str(&contents)
+        .map_err(|e| anyhow::anyhow!("Invalid backup schedules {}: {}", path.display(), e))?;
+    let mut scheduler = BackupScheduler::new().with_bus(bus);
+    for schedule in schedules {
+        scheduler.add_schedule(schedule)?;
+    }
+    Ok(scheduler)
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
     
     let args = Args::parse();
+    let config = args.config.loader("svc-compliance")
+        .with_default("port", DEFAULT_PORT)
+        .with_override("port", args.port)
+        .load_service()?;
+    if args.config.print_config {
+        println!("{}", config.render()?);
+        return Ok(());
+    }
+    let _telemetry = sniper_core::telemetry::init_telemetry("svc-compliance", args.otlp_endpoint.as_deref())?;
+    let shutdown = Shutdown::on_signal();
     
     // Create managers
-    let compliance_manager = ComplianceManager::new();
-    let backup_manager = BackupManager::new();
-    let dr_manager = DisasterRecoveryManager::new();
+    let mut compliance_manager = match &args.audit_log {
+        Some(path) => ComplianceManager::new().with_audit_log(path),
+        None => ComplianceManager::new(),
+    };
+    if let Some(path) = &args.report_data {
+        compliance_manager = compliance_manager.with_data_provider(Arc::new(ReportDataSet::load(path)?));
+    }
+    let compliance_manager = Arc::new(RwLock::new(compliance_manager));
+    let dr_manager = Arc::new(RwLock::new(DisasterRecoveryManager::new()));
+    let backup_manager = Arc::new(RwLock::new(build_backup_manager(&args, compliance_manager.clone(), dr_manager.clone())?));
+    
+    // Open incidents for failed scheduled backups
+    let bus = InMemoryBus::new(1024);
+    let monitoring_system = Arc::new(RwLock::new(MonitoringSystem::new()?));
+    spawn_trading_event_listener(&bus, monitoring_system, args.tenant_id.clone());
+    
+    // Run backup schedules in the background
+    let backup_scheduler = match &args.backup_schedules {
+        Some(path) => load_backup_schedules(path, bus.clone())?,
+        None => BackupScheduler::new().with_bus(bus.clone()),
+    };
+    let backup_scheduler = Arc::new(RwLock::new(backup_scheduler));
+    BackupScheduler::spawn(
+        backup_scheduler.clone(),
+        backup_manager.clone(),
+        std::time::Duration::from_secs(args.backup_schedule_interval_secs.max(1)),
+        &shutdown,
+    );
+    
+    // Create service metrics
+    let mut metrics = ServiceMetrics::new("svc-compliance")?;
+    metrics.register_counter("reports_generated_total", "Total compliance reports generated")?;
+    metrics.register_counter("backups_created_total", "Total backups created")?;
+    let metrics = Arc::new(metrics);
     
     // Create app state
     let app_state = Arc::new(AppState {
-        compliance_manager: RwLock::new(compliance_manager),
-        backup_manager: RwLock::new(backup_manager),
-        dr_manager: RwLock::new(dr_manager),
+        compliance_manager,
+        backup_manager,
+        backup_scheduler,
+        dr_manager,
+        dr_runs: RwLock::new(HashMap::new()),
+        metrics: metrics.clone(),
+        shutdown: shutdown.clone(),
     });
     
+    // Verify access tokens issued by svc-users
+    let auth = Arc::new(JwtAuth::from_config(&config.auth)?);
+    let reporting = require_permission("view_reports");
+    let operations = require_permission("configure_system");
+    
+    // Throttle callers per tenant, separately for reads and trades
+    let rate_limiter = RateLimiter::new(config.section::<RateLimitConfig>("rate_limit")?.unwrap_or_default())
+        .with_registry(metrics.registry(), "svc-compliance")?;
+
     // Create router
     let app = Router::new()
         .route("/health", get(health_check))
-        .route("/reports", post(generate_report))
+        .route("/reports", post(generate_report.layer(reporting)))
         .route("/reports/:id", get(get_report))
         .route("/reports/tenant/:tenant_id", get(list_tenant_reports))
-        .route("/reports/:id/export", post(export_report))
-        .route("/backups", post(create_backup))
+        .route("/reports/:id/export", post(export_report.layer(reporting)))
+        .route("/backups", post(create_backup.layer(operations)))
         .route("/backups/:id", get(get_backup))
         .route("/backups/tenant/:tenant_id", get(list_tenant_backups))
-        .route("/backups/:id/restore", post(restore_backup))
-        .route("/dr-plans", post(create_dr_plan))
+        .route("/backups/:id/restore", post(restore_backup.layer(operations)))
+        .route("/backups/:id/verify", post(verify_backup.layer(operations)))
+        .route("/backup-schedules", post(create_backup_schedule.layer(operations)))
+        .route("/backup-schedules/:id", get(get_backup_schedule).delete(delete_backup_schedule.layer(operations)))
+        .route("/backup-schedules/tenant/:tenant_id", get(list_tenant_backup_schedules))
+        .route("/dr-plans", post(create_dr_plan.layer(operations)))
         .route("/dr-plans/:id", get(get_dr_plan))
         .route("/dr-plans/tenant/:tenant_id", get(list_tenant_dr_plans))
-        .route("/dr-plans/:id/execute", post(execute_dr_plan))
-        .layer(Extension(app_state));
+        .route("/dr-plans/:id/execute", post(execute_dr_plan.layer(operations)))
+        .route("/dr-runs/:id", get(get_dr_run))
+        .route("/dr-runs/:id/events", get(stream_dr_run))
+        .layer(Extension(app_state))
+        .layer(rate_limiter.layer())
+        .layer(AuthLayer::new(auth))
+        .layer(axum::middleware::from_fn(sniper_core::telemetry::trace_http));
+    let app = instrument(app, metrics).merge(openapi::router(ApiDoc::openapi()));
     
     // Run server
-    let addr = format!("0.0.0.0:{}", args.port);
+    let addr = format!("0.0.0.0:{}", config.port.unwrap_or(DEFAULT_PORT));
     tracing::info!("Compliance service listening on http://{}", addr);
     
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    sniper_core::shutdown::serve(listener, app, &shutdown).await?;
+    
+    // Let scheduled backups and recovery runs in progress complete
+    shutdown.join().await;
         
     Ok(())
 }
 
 /// Health check endpoint
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    security(()),
+    responses(
+        (status = 200, description = "Service is healthy", body = ApiResponse<String>),
+    )
+)]
 async fn health_check() -> Json<ApiResponse<String>> {
     let response = ApiResponse {
         success: true,
@@ -208,10 +446,20 @@ async fn health_check() -> Json<ApiResponse<String>> {
 }
 
 /// Generate a compliance report
+#[utoipa::path(
+    post,
+    path = "/reports",
+    tag = "reports",
+    request_body = GenerateReportRequest,
+    responses(
+        (status = 200, description = "Report generated", body = ApiResponse<ReportResponse>),
+        (status = 403, description = "Missing `view_reports`"),
+    )
+)]
 async fn generate_report(
     Extension(state): Extension<Arc<AppState>>,
     Json(payload): Json<GenerateReportRequest>,
-) -> Json<ApiResponse<ReportResponse>> {
+) -> Result<Json<ApiResponse<ReportResponse>>, ApiError> {
     // Parse report type from string
     let report_type = match payload.report_type.as_str() {
         "DailyActivity" => ReportType::DailyActivity,
@@ -219,6 +467,7 @@ async fn generate_report(
         "RiskAssessment" => ReportType::RiskAssessment,
         "RegulatoryCompliance" => ReportType::RegulatoryCompliance,
         "FinancialSummary" => ReportType::FinancialSummary,
+        "IncidentAnalytics" => ReportType::IncidentAnalytics,
         _ => ReportType::DailyActivity,
     };
     
@@ -228,62 +477,59 @@ async fn generate_report(
     let period_end = payload.period_end.parse::<DateTime<Utc>>()
         .unwrap_or_else(|_| Utc::now());
     
-    let result = state.compliance_manager.write().await.generate_report(
+    let report = state.compliance_manager.write().await.generate_report(
         report_type,
         period_start,
         period_end,
         &payload.generated_by,
         &payload.tenant_id,
-    );
+    ).map_err(|e| ApiError::from(ComplianceError::from(e)).context("Failed to generate report"))?;
     
-    match result {
-        Ok(report) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(ReportResponse::from(report)),
-                message: Some("Report generated successfully".to_string()),
-            };
-            Json(response)
-        },
-        Err(e) => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some(format!("Failed to generate report: {}", e)),
-            };
-            Json(response)
-        },
-    }
+    state.metrics.increment_counter("reports_generated_total");
+    let response = ApiResponse {
+        success: true,
+        data: Some(ReportResponse::from(report)),
+        message: Some("Report generated successfully".to_string()),
+    };
+    Ok(Json(response))
 }
 
 /// Get a report by ID
+#[utoipa::path(
+    get,
+    path = "/reports/{id}",
+    tag = "reports",
+    params(("id" = String, Path, description = "Report ID")),
+    responses(
+        (status = 200, description = "The report", body = ApiResponse<ReportResponse>),
+        (status = 404, description = "No such report", body = ErrorResponse),
+    )
+)]
 async fn get_report(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<ApiResponse<ReportResponse>> {
-    let report_opt = state.compliance_manager.read().await.get_report(&id).cloned();
-    
-    match report_opt {
-        Some(report) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(ReportResponse::from(report)),
-                message: None,
-            };
-            Json(response)
-        },
-        None => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some("Report not found".to_string()),
-            };
-            Json(response)
-        },
-    }
+) -> Result<Json<ApiResponse<ReportResponse>>, ApiError> {
+    let report = state.compliance_manager.read().await.get_report(&id).cloned()
+        .ok_or(ComplianceError::ReportNotFound(id))?;
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(ReportResponse::from(report)),
+        message: None,
+    };
+    Ok(Json(response))
 }
 
 /// List reports for a tenant
+#[utoipa::path(
+    get,
+    path = "/reports/tenant/{tenant_id}",
+    tag = "reports",
+    params(("tenant_id" = String, Path, description = "Tenant ID")),
+    responses(
+        (status = 200, description = "Reports of the tenant", body = ApiResponse<Vec<ReportResponse>>),
+    )
+)]
 async fn list_tenant_reports(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(tenant_id): axum::extract::Path<String>,
@@ -302,95 +548,105 @@ async fn list_tenant_reports(
 }
 
 /// Export a report
+#[utoipa::path(
+    post,
+    path = "/reports/{id}/export",
+    tag = "reports",
+    params(("id" = String, Path, description = "Report ID")),
+    request_body(content = Object, description = "`format`: `json` (default) or `text`", example = json!({"format": "text"})),
+    responses(
+        (status = 200, description = "Base64-encoded export", body = ApiResponse<String>),
+        (status = 403, description = "Missing `view_reports`"),
+        (status = 404, description = "No such report", body = ErrorResponse),
+        (status = 422, description = "Unsupported export format", body = ErrorResponse),
+    )
+)]
 async fn export_report(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(id): axum::extract::Path<String>,
     Json(payload): Json<serde_json::Value>,
-) -> Json<ApiResponse<String>> {
+) -> Result<Json<ApiResponse<String>>, ApiError> {
     let format = payload.get("format")
         .and_then(|v| v.as_str())
         .unwrap_or("json");
     
-    let result = state.compliance_manager.read().await.export_report(&id, format);
+    let data = state.compliance_manager.read().await.export_report(&id, format)
+        .map_err(|e| ApiError::from(ComplianceError::from(e)).context("Failed to export report"))?;
     
-    match result {
-        Ok(data) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(base64::encode(data)),
-                message: Some("Report exported successfully".to_string()),
-            };
-            Json(response)
-        },
-        Err(e) => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some(format!("Failed to export report: {}", e)),
-            };
-            Json(response)
-        },
-    }
+    let response = ApiResponse {
+        success: true,
+        data: Some(base64::encode(data)),
+        message: Some("Report exported successfully".to_string()),
+    };
+    Ok(Json(response))
 }
 
 /// Create a backup
+#[utoipa::path(
+    post,
+    path = "/backups",
+    tag = "backups",
+    request_body = CreateBackupRequest,
+    responses(
+        (status = 200, description = "Backup created", body = ApiResponse<BackupResponse>),
+        (status = 403, description = "Missing `configure_system`"),
+        (status = 422, description = "No, duplicate or unknown components", body = ErrorResponse),
+    )
+)]
 async fn create_backup(
     Extension(state): Extension<Arc<AppState>>,
     Json(payload): Json<CreateBackupRequest>,
-) -> Json<ApiResponse<BackupResponse>> {
-    let result = state.backup_manager.write().await.create_backup(
+) -> Result<Json<ApiResponse<BackupResponse>>, ApiError> {
+    let backup = state.backup_manager.write().await.create_backup(
         payload.components,
         &payload.tenant_id,
-    );
+    ).await.map_err(|e| ApiError::from(ComplianceError::from(e)).context("Failed to create backup"))?;
     
-    match result {
-        Ok(backup) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(BackupResponse::from(backup)),
-                message: Some("Backup created successfully".to_string()),
-            };
-            Json(response)
-        },
-        Err(e) => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some(format!("Failed to create backup: {}", e)),
-            };
-            Json(response)
-        },
-    }
+    state.metrics.increment_counter("backups_created_total");
+    let response = ApiResponse {
+        success: true,
+        data: Some(BackupResponse::from(backup)),
+        message: Some("Backup created successfully".to_string()),
+    };
+    Ok(Json(response))
 }
 
 /// Get a backup by ID
+#[utoipa::path(
+    get,
+    path = "/backups/{id}",
+    tag = "backups",
+    params(("id" = String, Path, description = "Backup ID")),
+    responses(
+        (status = 200, description = "Backup metadata", body = ApiResponse<BackupResponse>),
+        (status = 404, description = "No such backup", body = ErrorResponse),
+    )
+)]
 async fn get_backup(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<ApiResponse<BackupResponse>> {
-    let backup_opt = state.backup_manager.read().await.get_backup(&id).cloned();
-    
-    match backup_opt {
-        Some(backup) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(BackupResponse::from(backup)),
-                message: None,
-            };
-            Json(response)
-        },
-        None => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some("Backup not found".to_string()),
-            };
-            Json(response)
-        },
-    }
+) -> Result<Json<ApiResponse<BackupResponse>>, ApiError> {
+    let backup = state.backup_manager.read().await.get_backup(&id).cloned()
+        .ok_or(ComplianceError::BackupNotFound(id))?;
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(BackupResponse::from(backup)),
+        message: None,
+    };
+    Ok(Json(response))
 }
 
 /// List backups for a tenant
+#[utoipa::path(
+    get,
+    path = "/backups/tenant/{tenant_id}",
+    tag = "backups",
+    params(("tenant_id" = String, Path, description = "Tenant ID")),
+    responses(
+        (status = 200, description = "Backups of the tenant", body = ApiResponse<Vec<BackupResponse>>),
+    )
+)]
 async fn list_tenant_backups(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(tenant_id): axum::extract::Path<String>,
@@ -409,33 +665,195 @@ async fn list_tenant_backups(
 }
 
 /// Restore from a backup
+#[utoipa::path(
+    post,
+    path = "/backups/{id}/restore",
+    tag = "backups",
+    params(("id" = String, Path, description = "Backup ID")),
+    responses(
+        (status = 200, description = "Backup restored", body = ApiResponse<bool>),
+        (status = 403, description = "Missing `configure_system`"),
+        (status = 404, description = "No such backup", body = ErrorResponse),
+        (status = 409, description = "The backup's archive is corrupt", body = ErrorResponse),
+    )
+)]
 async fn restore_backup(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<ApiResponse<bool>> {
-    let result = state.backup_manager.read().await.restore_from_backup(&id);
-    
-    match result {
-        Ok(_) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(true),
-                message: Some("Backup restored successfully".to_string()),
-            };
-            Json(response)
+) -> Result<Json<ApiResponse<bool>>, ApiError> {
+    state.backup_manager.read().await.restore_from_backup(&id).await
+        .map_err(|e| ApiError::from(ComplianceError::from(e)).context("Failed to restore backup"))?;
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(true),
+        message: Some("Backup restored successfully".to_string()),
+    };
+    Ok(Json(response))
+}
+
+/// Verify a backup's archive against its checksum without restoring it
+#[utoipa::path(
+    post,
+    path = "/backups/{id}/verify",
+    tag = "backups",
+    params(("id" = String, Path, description = "Backup ID")),
+    responses(
+        (status = 200, description = "Whether the archive is intact, with the reason when it is not", body = ApiResponse<bool>),
+        (status = 403, description = "Missing `configure_system`"),
+        (status = 404, description = "No such backup", body = ErrorResponse),
+    )
+)]
+async fn verify_backup(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<ApiResponse<bool>>, ApiError> {
+    let result = state.backup_manager.read().await.verify_backup(&id).await.map_err(ComplianceError::from);
+    
+    // A corrupt archive is the answer to the check, not a failure of it
+    let response = match result {
+        Ok(()) => ApiResponse {
+            success: true,
+            data: Some(true),
+            message: Some("Backup verified successfully".to_string()),
         },
-        Err(e) => {
-            let response = ApiResponse {
-                success: false,
-                data: Some(false),
-                message: Some(format!("Failed to restore backup: {}", e)),
-            };
-            Json(response)
+        Err(e @ ComplianceError::CorruptBackup { .. }) => ApiResponse {
+            success: true,
+            data: Some(false),
+            message: Some(format!("Backup verification failed: {}", e)),
         },
+        Err(e) => return Err(ApiError::from(e).context("Failed to verify backup")),
+    };
+    Ok(Json(response))
+}
+
+/// Create or replace a backup schedule
+#[utoipa::path(
+    post,
+    path = "/backup-schedules",
+    tag = "backups",
+    request_body = BackupSchedule,
+    responses(
+        (status = 200, description = "Schedule created", body = ApiResponse<BackupSchedule>),
+        (status = 403, description = "Missing `configure_system`"),
+        (status = 422, description = "Unknown components or a retention keeping no backups", body = ErrorResponse),
+    )
+)]
+async fn create_backup_schedule(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(payload): Json<BackupSchedule>,
+) -> Result<Json<ApiResponse<BackupSchedule>>, ApiError> {
+    let unknown: Vec<String> = {
+        let known = state.backup_manager.read().await.component_names();
+        payload.components.iter().filter(|component| !known.contains(component)).cloned().collect()
+    };
+    if !unknown.is_empty() {
+        return Err(ApiError::invalid(format!("Unknown backup components: {}", unknown.join(", ")))
+            .context("Failed to create backup schedule"));
     }
+    state.backup_scheduler.write().await.add_schedule(payload.clone())
+        .map_err(|e| ApiError::from(ComplianceError::from(e)).context("Failed to create backup schedule"))?;
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(payload),
+        message: Some("Backup schedule created successfully".to_string()),
+    };
+    Ok(Json(response))
+}
+
+/// Get a backup schedule by ID
+#[utoipa::path(
+    get,
+    path = "/backup-schedules/{id}",
+    tag = "backups",
+    params(("id" = String, Path, description = "Schedule ID")),
+    responses(
+        (status = 200, description = "The schedule", body = ApiResponse<BackupSchedule>),
+        (status = 404, description = "No such schedule", body = ErrorResponse),
+    )
+)]
+async fn get_backup_schedule(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<ApiResponse<BackupSchedule>>, ApiError> {
+    let schedule = state.backup_scheduler.read().await.get_schedule(&id).cloned()
+        .ok_or_else(|| ApiError::not_found("Backup schedule not found"))?;
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(schedule),
+        message: None,
+    };
+    Ok(Json(response))
+}
+
+/// Delete a backup schedule, keeping the backups it made
+#[utoipa::path(
+    delete,
+    path = "/backup-schedules/{id}",
+    tag = "backups",
+    params(("id" = String, Path, description = "Schedule ID")),
+    responses(
+        (status = 200, description = "Schedule deleted", body = ApiResponse<bool>),
+        (status = 403, description = "Missing `configure_system`"),
+        (status = 404, description = "No such schedule", body = ErrorResponse),
+    )
+)]
+async fn delete_backup_schedule(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<ApiResponse<bool>>, ApiError> {
+    state.backup_scheduler.write().await.remove_schedule(&id)
+        .ok_or_else(|| ApiError::not_found("Backup schedule not found"))?;
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(true),
+        message: Some("Backup schedule deleted successfully".to_string()),
+    };
+    Ok(Json(response))
+}
+
+/// List backup schedules for a tenant
+#[utoipa::path(
+    get,
+    path = "/backup-schedules/tenant/{tenant_id}",
+    tag = "backups",
+    params(("tenant_id" = String, Path, description = "Tenant ID")),
+    responses(
+        (status = 200, description = "Schedules of the tenant", body = ApiResponse<Vec<BackupSchedule>>),
+    )
+)]
+async fn list_tenant_backup_schedules(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(tenant_id): axum::extract::Path<String>,
+) -> Json<ApiResponse<Vec<BackupSchedule>>> {
+    let mut schedules: Vec<BackupSchedule> = state.backup_scheduler.read().await.list_tenant_schedules(&tenant_id)
+        .into_iter()
+        .cloned()
+        .collect();
+    schedules.sort_by(|a, b| a.id.cmp(&b.id));
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(schedules),
+        message: None,
+    };
+    Json(response)
 }
 
 /// Create a disaster recovery plan
+#[utoipa::path(
+    post,
+    path = "/dr-plans",
+    tag = "recovery",
+    request_body = CreateDRPlanRequest,
+    responses(
+        (status = 200, description = "Plan created", body = ApiResponse<DRPlanResponse>),
+        (status = 403, description = "Missing `configure_system`"),
+    )
+)]
 async fn create_dr_plan(
     Extension(state): Extension<Arc<AppState>>,
     Json(payload): Json<CreateDRPlanRequest>,
@@ -456,33 +874,41 @@ async fn create_dr_plan(
 }
 
 /// Get a disaster recovery plan by ID
+#[utoipa::path(
+    get,
+    path = "/dr-plans/{id}",
+    tag = "recovery",
+    params(("id" = String, Path, description = "Plan ID")),
+    responses(
+        (status = 200, description = "The plan", body = ApiResponse<DRPlanResponse>),
+        (status = 404, description = "No such plan", body = ErrorResponse),
+    )
+)]
 async fn get_dr_plan(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<ApiResponse<DRPlanResponse>> {
-    let plan_opt = state.dr_manager.read().await.get_plan(&id).cloned();
-    
-    match plan_opt {
-        Some(plan) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(DRPlanResponse::from(plan)),
-                message: None,
-            };
-            Json(response)
-        },
-        None => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some("Disaster recovery plan not found".to_string()),
-            };
-            Json(response)
-        },
-    }
+) -> Result<Json<ApiResponse<DRPlanResponse>>, ApiError> {
+    let plan = state.dr_manager.read().await.get_plan(&id).cloned()
+        .ok_or(ComplianceError::PlanNotFound(id))?;
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(DRPlanResponse::from(plan)),
+        message: None,
+    };
+    Ok(Json(response))
 }
 
 /// List disaster recovery plans for a tenant
+#[utoipa::path(
+    get,
+    path = "/dr-plans/tenant/{tenant_id}",
+    tag = "recovery",
+    params(("tenant_id" = String, Path, description = "Tenant ID")),
+    responses(
+        (status = 200, description = "Plans of the tenant", body = ApiResponse<Vec<DRPlanResponse>>),
+    )
+)]
 async fn list_tenant_dr_plans(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(tenant_id): axum::extract::Path<String>,
@@ -500,30 +926,116 @@ async fn list_tenant_dr_plans(
     Json(response)
 }
 
-/// Execute a disaster recovery plan
+/// Start executing a disaster recovery plan in the background
+#[utoipa::path(
+    post,
+    path = "/dr-plans/{id}/execute",
+    tag = "recovery",
+    params(("id" = String, Path, description = "Plan ID")),
+    responses(
+        (status = 200, description = "Run started in the background", body = ApiResponse<RecoveryRun>),
+        (status = 403, description = "Missing `configure_system`"),
+        (status = 404, description = "No such plan", body = ErrorResponse),
+        (status = 422, description = "The plan's steps are not a valid dependency graph of known actions", body = ErrorResponse),
+    )
+)]
 async fn execute_dr_plan(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<ApiResponse<bool>> {
-    let result = state.dr_manager.read().await.execute_plan(&id);
-    
-    match result {
-        Ok(_) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(true),
-                message: Some("Disaster recovery plan executed successfully".to_string()),
-            };
-            Json(response)
-        },
-        Err(e) => {
-            let response = ApiResponse {
-                success: false,
-                data: Some(false),
-                message: Some(format!("Failed to execute disaster recovery plan: {}", e)),
-            };
-            Json(response)
-        },
+) -> Result<Json<ApiResponse<RecoveryRun>>, ApiError> {
+    let execution = state.dr_manager.read().await.prepare_execution(&id)
+        .map_err(|e| ApiError::from(ComplianceError::from(e)).context("Failed to execute disaster recovery plan"))?;
+    
+    let handle = execution.handle();
+    let run = handle.snapshot().await;
+    state.dr_runs.write().await.insert(run.id.clone(), handle);
+    state.shutdown.track(tokio::spawn(async move {
+        execution.run().await;
+    }));
+    let response = ApiResponse {
+        success: true,
+        data: Some(run),
+        message: Some("Disaster recovery plan execution started".to_string()),
+    };
+    Ok(Json(response))
+}
+
+/// Get the state of a disaster recovery plan run
+#[utoipa::path(
+    get,
+    path = "/dr-runs/{id}",
+    tag = "recovery",
+    params(("id" = String, Path, description = "Run ID")),
+    responses(
+        (status = 200, description = "The run", body = ApiResponse<RecoveryRun>),
+        (status = 404, description = "No such run", body = ErrorResponse),
+    )
+)]
+async fn get_dr_run(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<ApiResponse<RecoveryRun>>, ApiError> {
+    let handle = state.dr_runs.read().await.get(&id).cloned()
+        .ok_or_else(|| ApiError::not_found("Disaster recovery run not found"))?;
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(handle.snapshot().await),
+        message: None,
+    };
+    Ok(Json(response))
+}
+
+/// Stream a run's state as a `run` event, then its progress as `step` events until a `finished` event
+#[utoipa::path(
+    get,
+    path = "/dr-runs/{id}/events",
+    tag = "recovery",
+    params(("id" = String, Path, description = "Run ID")),
+    responses(
+        (status = 200, description = "Server-sent `run`, `step` and `finished` events"),
+        (status = 404, description = "No such run"),
+    )
+)]
+async fn stream_dr_run(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Response {
+    let Some(handle) = state.dr_runs.read().await.get(&id).cloned() else {
+        return (StatusCode::NOT_FOUND, "Disaster recovery run not found").into_response();
+    };
+    // Subscribe before the snapshot so no progress falls between them
+    let progress = handle.subscribe();
+    let run = handle.snapshot().await;
+    let finished = run.status != RunStatus::Running;
+    
+    let updates = futures::stream::unfold((progress, finished), |(mut progress, finished)| async move {
+        if finished {
+            return None;
+        }
+        loop {
+            match progress.recv().await {
+                Ok(update) => {
+                    let finished = update.step.is_none();
+                    return Some((progress_event(&update), (progress, finished)));
+                },
+                Err(RecvError::Lagged(skipped)) => tracing::warn!("Recovery progress stream skipped {} updates", skipped),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    let mut initial = vec![Event::default().event("run").json_data(&run)];
+    if finished {
+        initial.push(progress_event(&RecoveryProgress { run_id: run.id, status: run.status, step: None }));
+    }
+    let events = futures::stream::iter(initial).chain(updates);
+    Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+}
+
+fn progress_event(update: &RecoveryProgress) -> Result<Event, axum::Error> {
+    match &update.step {
+        Some(step) => Event::default().event("step").json_data(step),
+        None => Event::default().event("finished").json_data(update),
     }
 }
 
@@ -531,24 +1043,130 @@ async fn execute_dr_plan(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_openapi_document() {
+        let document = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        for path in ["/reports", "/backups/{id}/restore", "/backup-schedules/{id}", "/dr-runs/{id}/events"] {
+            assert!(document["paths"][path].is_object(), "{} is not documented", path);
+        }
+        assert_eq!(document["components"]["schemas"]["BackupSchedule"]["properties"]["cron"]["type"], "string");
+    }
+
     #[test]
     fn test_args_parsing() {
         let args = Args::parse_from(["svc-compliance", "--port", "8086"]);
-        assert_eq!(args.port, 8086);
+        assert_eq!(args.port, Some(8086));
+        
+        let args = Args::parse_from(["svc-compliance", "--backup-dir", "/var/backups", "--config-file", "a.toml", "--config-file", "b.toml"]);
+        assert_eq!(args.config_files.len(), 2);
+        assert!(Args::try_parse_from(["svc-compliance", "--s3-bucket", "backups"]).is_err());
+        assert!(Args::try_parse_from(["svc-compliance", "--backup-dir", "/var/backups", "--s3-bucket", "backups", "--s3-endpoint", "http://minio:9000"]).is_err());
     }
 
     #[tokio::test]
     async fn test_compliance_service_creation() -> Result<()> {
-        let compliance_manager = ComplianceManager::new();
-        let backup_manager = BackupManager::new();
-        let dr_manager = DisasterRecoveryManager::new();
+        let compliance_manager = Arc::new(RwLock::new(ComplianceManager::new()));
+        let dr_manager = Arc::new(RwLock::new(DisasterRecoveryManager::new()));
+        let args = Args::parse_from(["svc-compliance"]);
+        let backup_manager = build_backup_manager(&args, compliance_manager.clone(), dr_manager.clone())?;
+        assert_eq!(backup_manager.component_names(), [RECOVERY_PLANS, REPORTS]);
         
         let _app_state = Arc::new(AppState {
-            compliance_manager: RwLock::new(compliance_manager),
-            backup_manager: RwLock::new(backup_manager),
-            dr_manager: RwLock::new(dr_manager),
+            compliance_manager,
+            backup_manager: Arc::new(RwLock::new(backup_manager)),
+            backup_scheduler: Arc::new(RwLock::new(BackupScheduler::new())),
+            dr_manager,
+            dr_runs: RwLock::new(HashMap::new()),
+            metrics: Arc::new(ServiceMetrics::new("svc-compliance")?),
+            shutdown: Shutdown::new(),
+        });
+        
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_dr_run_streams_progress() -> Result<()> {
+        let mut dr_manager = DisasterRecoveryManager::new();
+        let step = |id: &str, dependencies: Vec<String>| RecoveryStep {
+            id: id.to_string(),
+            order: 1,
+            description: format!("Step {}", id),
+            expected_duration_minutes: 1,
+            dependencies,
+            action: None,
+            timeout_secs: None,
+        };
+        let plan = dr_manager.create_plan("Restore", "", vec![step("stop", vec![]), step("start", vec!["stop".to_string()])], "tenant-1");
+        let state = Arc::new(AppState {
+            compliance_manager: Arc::new(RwLock::new(ComplianceManager::new())),
+            backup_manager: Arc::new(RwLock::new(BackupManager::new())),
+            backup_scheduler: Arc::new(RwLock::new(BackupScheduler::new())),
+            dr_manager: Arc::new(RwLock::new(dr_manager)),
+            dr_runs: RwLock::new(HashMap::new()),
+            metrics: Arc::new(ServiceMetrics::new("svc-compliance")?),
+            shutdown: Shutdown::new(),
+        });
+        
+        let Json(started) = execute_dr_plan(Extension(state.clone()), axum::extract::Path(plan.id)).await.unwrap();
+        let run_id = started.data.unwrap().id;
+        let response = stream_dr_run(Extension(state.clone()), axum::extract::Path(run_id.clone())).await;
+        let body = String::from_utf8(axum::body::to_bytes(response.into_body(), usize::MAX).await?.to_vec())?;
+        assert!(body.starts_with("event: run\n"));
+        assert!(body.contains("event: finished\n"));
+        
+        let Json(finished) = get_dr_run(Extension(state.clone()), axum::extract::Path(run_id)).await.unwrap();
+        assert_eq!(finished.data.unwrap().status, RunStatus::Succeeded);
+        let missing = stream_dr_run(Extension(state), axum::extract::Path("missing".to_string())).await;
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_handler_errors_map_to_status() -> Result<()> {
+        let state = Arc::new(AppState {
+            compliance_manager: Arc::new(RwLock::new(ComplianceManager::new())),
+            backup_manager: Arc::new(RwLock::new(BackupManager::new())),
+            backup_scheduler: Arc::new(RwLock::new(BackupScheduler::new())),
+            dr_manager: Arc::new(RwLock::new(DisasterRecoveryManager::new())),
+            dr_runs: RwLock::new(HashMap::new()),
+            metrics: Arc::new(ServiceMetrics::new("svc-compliance")?),
+            shutdown: Shutdown::new(),
         });
+        let missing = || axum::extract::Path("missing".to_string());
+
+        let error = get_report(Extension(state.clone()), missing()).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        let error = export_report(Extension(state.clone()), missing(), Json(serde_json::json!({}))).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        let error = restore_backup(Extension(state.clone()), missing()).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        let error = verify_backup(Extension(state.clone()), missing()).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        let error = delete_backup_schedule(Extension(state.clone()), missing()).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        let error = execute_dr_plan(Extension(state.clone()), missing()).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+
+        let request = CreateBackupRequest { components: vec![REPORTS.to_string()], tenant_id: "tenant-1".to_string() };
+        let error = create_backup(Extension(state), Json(request)).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_backup_schedules() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("svc-compliance-schedules-{}.json", Utc::now().timestamp_nanos_opt().unwrap()));
+        std::fs::write(&path, r#"[
+            {"id": "nightly", "tenant_id": "tenant-1", "components": ["reports"], "cron": "30 2 * * *", "retention": {"keep_daily": 7, "keep_weekly": 4}},
+            {"id": "hourly", "tenant_id": "tenant-1", "components": ["recovery_plans"], "cron": "@hourly"}
+        ]"#)?;
+        let scheduler = load_backup_schedules(&path, InMemoryBus::new(16))?;
+        assert_eq!(scheduler.list_tenant_schedules("tenant-1").len(), 2);
+        assert_eq!(scheduler.get_schedule("nightly").unwrap().retention.unwrap().keep_weekly, 4);
         
+        std::fs::write(&path, r#"[{"id": "bad", "tenant_id": "tenant-1", "components": ["reports"], "cron": "61 * * * *"}]"#)?;
+        assert!(load_backup_schedules(&path, InMemoryBus::new(16)).is_err());
+        std::fs::remove_file(path)?;
         Ok(())
     }
-}
\ No newline at end of file
+}
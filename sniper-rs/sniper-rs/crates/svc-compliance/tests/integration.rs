@@ -1,13 +1,21 @@
 //! Integration tests for the compliance service and enterprise features
 
+use sniper_compliance::components::{CONFIGS, RECOVERY_PLANS, REPORTS, USERS};
 use sniper_compliance::{
     ComplianceManager, 
     BackupManager, 
+    ConfigFiles,
     DisasterRecoveryManager, 
+    LocalDirDestination,
     ReportType,
-    RecoveryStep
+    RecoveryStep,
+    RunStatus,
+    StepStatus,
 };
+use sniper_users::{UserManager, UserRole};
 use chrono::Utc;
+use std::sync::Arc;
+use tokio::sync::RwLock;
 
 #[test]
 fn test_compliance_reporting_enterprise_features() {
@@ -47,26 +55,49 @@ fn test_compliance_reporting_enterprise_features() {
     assert_eq!(retrieved_audit.unwrap().id, audit_report.id);
 }
 
-#[test]
-fn test_backup_restore_enterprise_features() {
-    let mut backup_manager = BackupManager::new();
+#[tokio::test]
+async fn test_backup_restore_enterprise_features() {
+    let root = std::env::temp_dir().join(format!("svc-compliance-backups-{}", Utc::now().timestamp_nanos_opt().unwrap()));
+    std::fs::create_dir_all(&root).unwrap();
+    let config = root.join("compliance.toml");
+    std::fs::write(&config, "retention_days = 365\n").unwrap();
+    
+    let users = Arc::new(RwLock::new(UserManager::new()));
+    let officer = users.write().await
+        .create_user("officer", "officer@example.com", vec![UserRole::Auditor], "backup-tenant-1")
+        .expect("Failed to create user");
+    let compliance_manager = Arc::new(RwLock::new(ComplianceManager::new()));
+    let report = compliance_manager.write().await.generate_report(
+        ReportType::DailyActivity,
+        Utc::now() - chrono::Duration::days(1),
+        Utc::now(),
+        "officer",
+        "backup-tenant-1",
+    ).expect("Failed to generate report");
+    
+    let mut backup_manager = BackupManager::new()
+        .with_component(USERS, users.clone())
+        .with_component(REPORTS, compliance_manager.clone())
+        .with_component(CONFIGS, Arc::new(ConfigFiles::new(vec![config.clone()]).unwrap()))
+        .with_destination(Arc::new(LocalDirDestination::new(root.join("backups"))));
     
     // Test creating backups with different components
     let core_components = vec![
-        "users".to_string(), 
-        "reports".to_string(), 
-        "configurations".to_string()
+        USERS.to_string(), 
+        REPORTS.to_string(), 
+        CONFIGS.to_string(),
     ];
     
     let full_backup = backup_manager.create_backup(
         core_components,
         "backup-tenant-1",
-    ).expect("Failed to create full backup");
+    ).await.expect("Failed to create full backup");
     
     assert_eq!(full_backup.tenant_id, "backup-tenant-1");
     assert_eq!(full_backup.components.len(), 3);
-    assert!(!full_backup.checksum.is_empty());
+    assert_eq!(full_backup.checksum.len(), 64);
     assert!(full_backup.size_bytes > 0);
+    assert!(std::path::Path::new(&full_backup.location).exists());
     
     // Test retrieving backup
     let retrieved_backup = backup_manager.get_backup(&full_backup.id);
@@ -78,13 +109,21 @@ fn test_backup_restore_enterprise_features() {
     assert_eq!(tenant_backups.len(), 1);
     assert_eq!(tenant_backups[0].id, full_backup.id);
     
-    // Test restore functionality
-    backup_manager.restore_from_backup(&full_backup.id)
+    // Test restore functionality after losing the tenant's data
+    users.write().await.restore_tenant_users("backup-tenant-1", Vec::new()).unwrap();
+    compliance_manager.write().await.restore_tenant_reports("backup-tenant-1", Vec::new()).unwrap();
+    std::fs::write(&config, "retention_days = 1\n").unwrap();
+    backup_manager.restore_from_backup(&full_backup.id).await
         .expect("Failed to restore from backup");
+    
+    assert!(users.read().await.get_user(&officer.id).is_some());
+    assert!(compliance_manager.read().await.get_report(&report.id).is_some());
+    assert_eq!(std::fs::read_to_string(&config).unwrap(), "retention_days = 365\n");
+    std::fs::remove_dir_all(root).unwrap();
 }
 
-#[test]
-fn test_disaster_recovery_enterprise_features() {
+#[tokio::test]
+async fn test_disaster_recovery_enterprise_features() {
     let mut dr_manager = DisasterRecoveryManager::new();
     
     // Test creating a comprehensive disaster recovery plan
@@ -95,6 +134,8 @@ fn test_disaster_recovery_enterprise_features() {
             description: "Assess system status and identify failure points".to_string(),
             expected_duration_minutes: 1,
             dependencies: vec![],
+            action: None,
+            timeout_secs: None,
         },
         RecoveryStep {
             id: "step-2".to_string(),
@@ -102,6 +143,8 @@ fn test_disaster_recovery_enterprise_features() {
             description: "Safely shutdown all trading services".to_string(),
             expected_duration_minutes: 2,
             dependencies: vec!["step-1".to_string()],
+            action: None,
+            timeout_secs: None,
         },
         RecoveryStep {
             id: "step-3".to_string(),
@@ -109,6 +152,8 @@ fn test_disaster_recovery_enterprise_features() {
             description: "Restore system from latest verified backup".to_string(),
             expected_duration_minutes: 30,
             dependencies: vec!["step-2".to_string()],
+            action: None,
+            timeout_secs: None,
         },
         RecoveryStep {
             id: "step-4".to_string(),
@@ -116,6 +161,8 @@ fn test_disaster_recovery_enterprise_features() {
             description: "Restart all services in correct order".to_string(),
             expected_duration_minutes: 5,
             dependencies: vec!["step-3".to_string()],
+            action: None,
+            timeout_secs: None,
         },
         RecoveryStep {
             id: "step-5".to_string(),
@@ -123,6 +170,8 @@ fn test_disaster_recovery_enterprise_features() {
             description: "Validate system functionality and data integrity".to_string(),
             expected_duration_minutes: 10,
             dependencies: vec!["step-4".to_string()],
+            action: None,
+            timeout_secs: None,
         }
     ];
     
@@ -148,15 +197,18 @@ fn test_disaster_recovery_enterprise_features() {
     assert_eq!(tenant_plans[0].id, dr_plan.id);
     
     // Test plan execution
-    dr_manager.execute_plan(&dr_plan.id)
+    let run = dr_manager.execute_plan(&dr_plan.id).await
         .expect("Failed to execute disaster recovery plan");
+    assert_eq!(run.status, RunStatus::Succeeded);
+    assert_eq!(run.steps.len(), 5);
+    assert!(run.steps.iter().all(|step| step.status == StepStatus::Succeeded));
 }
 
-#[test]
-fn test_multi_tenant_compliance_isolation() {
+#[tokio::test]
+async fn test_multi_tenant_compliance_isolation() {
     let mut compliance_manager = ComplianceManager::new();
-    let mut backup_manager = BackupManager::new();
-    let mut dr_manager = DisasterRecoveryManager::new();
+    let dr_manager = Arc::new(RwLock::new(DisasterRecoveryManager::new()));
+    let mut backup_manager = BackupManager::new().with_component(RECOVERY_PLANS, dr_manager.clone());
     
     // Create resources for tenant 1
     let tenant1_report = compliance_manager.generate_report(
@@ -167,18 +219,18 @@ fn test_multi_tenant_compliance_isolation() {
         "compliance-tenant-1",
     ).expect("Failed to generate tenant1 report");
     
-    let tenant1_backup = backup_manager.create_backup(
-        vec!["compliance_data".to_string()],
-        "compliance-tenant-1",
-    ).expect("Failed to create tenant1 backup");
-    
-    let tenant1_plan = dr_manager.create_plan(
+    let tenant1_plan = dr_manager.write().await.create_plan(
         "Tenant 1 DR Plan",
         "DR plan for tenant 1",
         vec![],
         "compliance-tenant-1",
     );
     
+    let tenant1_backup = backup_manager.create_backup(
+        vec![RECOVERY_PLANS.to_string()],
+        "compliance-tenant-1",
+    ).await.expect("Failed to create tenant1 backup");
+    
     // Create resources for tenant 2
     let tenant2_report = compliance_manager.generate_report(
         ReportType::FinancialSummary,
@@ -188,18 +240,18 @@ fn test_multi_tenant_compliance_isolation() {
         "compliance-tenant-2",
     ).expect("Failed to generate tenant2 report");
     
-    let tenant2_backup = backup_manager.create_backup(
-        vec!["financial_data".to_string()],
-        "compliance-tenant-2",
-    ).expect("Failed to create tenant2 backup");
-    
-    let tenant2_plan = dr_manager.create_plan(
+    let tenant2_plan = dr_manager.write().await.create_plan(
         "Tenant 2 DR Plan",
         "DR plan for tenant 2",
         vec![],
         "compliance-tenant-2",
     );
     
+    let tenant2_backup = backup_manager.create_backup(
+        vec![RECOVERY_PLANS.to_string()],
+        "compliance-tenant-2",
+    ).await.expect("Failed to create tenant2 backup");
+    
     // Verify tenant isolation for reports
     let tenant1_reports = compliance_manager.get_tenant_reports("compliance-tenant-1");
     let tenant2_reports = compliance_manager.get_tenant_reports("compliance-tenant-2");
@@ -219,6 +271,7 @@ fn test_multi_tenant_compliance_isolation() {
     assert_eq!(tenant2_backups[0].id, tenant2_backup.id);
     
     // Verify tenant isolation for DR plans
+    let dr_manager = dr_manager.read().await;
     let tenant1_plans = dr_manager.list_tenant_plans("compliance-tenant-1");
     let tenant2_plans = dr_manager.list_tenant_plans("compliance-tenant-2");
     
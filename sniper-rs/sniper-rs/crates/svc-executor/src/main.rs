@@ -1,7 +1,29 @@
+use clap::Parser;
 use sniper_core::{bus::InMemoryBus, prelude::*};
+use sniper_core::config::ConfigArgs;
+use sniper_core::shutdown::Shutdown;
 use sniper_core::types::{Signal, TradePlan, Decision, ExecReceipt};
+use sniper_exec::Executor;
+use sniper_rpc::{proto, server as rpc};
+use sniper_storage::failover::{
+    FailoverConfig, FailoverCoordinator, InMemoryLeaseStore, LeaseStore, RedisLeaseStore,
+};
+use sniper_users::JwtAuth;
+use std::sync::Arc;
+use tokio::sync::RwLock;
 use tokio::time::{sleep, Duration};
 
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Port to execute plans submitted over gRPC on; without one, plans only arrive over the bus
+    #[clap(long)]
+    grpc_port: Option<u16>,
+
+    #[clap(flatten)]
+    config: ConfigArgs,
+}
+
 #[tokio::main]
 async fn main() -> eyre::Result<()> {
     tracing_subscriber::fmt()
@@ -9,15 +31,64 @@ async fn main() -> eyre::Result<()> {
         .json()
         .init();
     dotenvy::dotenv().ok();
+    let args = Args::parse();
+    let config = args.config.loader("svc-executor")
+        .with_override("grpc_port", args.grpc_port)
+        .load_service()?;
+    if args.config.print_config {
+        println!("{}", config.render()?);
+        return Ok(());
+    }
+    let _telemetry = sniper_core::telemetry::init_telemetry("svc-executor", None)
+        .map_err(|e| eyre::eyre!("{}", e))?;
+    let shutdown = Shutdown::on_signal();
 
     let bus = InMemoryBus::new(1024);
 
+    // Active/standby failover: only the region holding the execution lease submits
+    let region = std::env::var("EXEC_REGION").unwrap_or_else(|_| "local".to_string());
+    let wallet_group = std::env::var("EXEC_WALLET_GROUP").unwrap_or_else(|_| "default".to_string());
+    let heartbeat_ms = std::env::var("FAILOVER_HEARTBEAT_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1000);
+    let store: Arc<dyn LeaseStore> = match std::env::var("FAILOVER_REDIS_URL").ok().filter(|url| !url.is_empty()) {
+        Some(url) => Arc::new(RedisLeaseStore::new(redis::Client::open(url)?)),
+        None => {
+            tracing::warn!("FAILOVER_REDIS_URL not set, using in-memory lease store (single region only)");
+            Arc::new(InMemoryLeaseStore::new())
+        }
+    };
+    let failover = FailoverConfig::new(&region, &wallet_group, Duration::from_millis(heartbeat_ms));
+    let heartbeat_interval = failover.heartbeat_interval;
+    let coordinator = Arc::new(RwLock::new(FailoverCoordinator::new(failover, store)));
+    // Every submission is fenced by the lease, checked again right before sending
+    let executor = Arc::new(Executor::new().with_failover(coordinator.clone()));
+
+    // Heartbeat task - renews the lease as primary or takes over as standby
+    let hb_coordinator = coordinator.clone();
+    shutdown.spawn(async move {
+        loop {
+            if let Err(e) = hb_coordinator.write().await.heartbeat().await {
+                tracing::error!("failover heartbeat failed: {}", e);
+            }
+            sleep(heartbeat_interval).await;
+        }
+    });
+
     // Trade plan subscriber task - listens for trade plans and executes them
     let rx_bus = bus.clone();
-    tokio::spawn(async move {
+    let exec_executor = executor.clone();
+    let stop = shutdown.clone();
+    shutdown.track(tokio::spawn(async move {
         let mut rx = rx_bus.subscribe("plan.created");
         loop {
-            if let Ok(bytes) = rx.recv().await {
+            // Stop between plans so a trade being submitted is never cut short
+            let received = tokio::select! {
+                received = rx.recv() => received,
+                _ = stop.wait() => break,
+            };
+            if let Ok(bytes) = received {
                 if let Ok(plan) = serde_json::from_slice::<TradePlan>(&bytes) {
                     tracing::info!("received trade plan for {} on {}", plan.token_out, plan.chain.name);
                     
@@ -33,51 +104,136 @@ async fn main() -> eyre::Result<()> {
                     };
                     
                     if decision.allow {
-                        // Execute the trade
-                        let receipt = execute_trade(&plan).await;
-                        
-                        // Publish the execution result
-                        let _ = rx_bus.publish("exec.result", &receipt).await;
-                        tracing::info!("executed trade: {}", receipt.tx_hash);
+                        if let Err(e) = submit_plan(&exec_executor, &rx_bus, &plan).await {
+                            tracing::info!("skipping trade plan {}: {}", plan.idem_key, e);
+                        }
                     } else {
                         tracing::warn!("trade rejected by risk checks: {:?}", decision.reasons);
                     }
                 }
             }
         }
-    });
+    }));
+
+    // Execute plans submitted over gRPC by callers holding `execute_trades`, if enabled
+    if let Some(port) = config.grpc_port {
+        let auth = Arc::new(JwtAuth::from_config(&config.auth).map_err(|e| eyre::eyre!("{}", e))?);
+        let grpc = tonic::transport::Server::builder().add_service(proto::execution_server::ExecutionServer::with_interceptor(
+            ExecutionRpc { executor: executor.clone(), bus: bus.clone() },
+            rpc::Authenticate::new(auth),
+        ));
+        let addr = format!("0.0.0.0:{}", port);
+        let listener = tokio::net::TcpListener::bind(&addr).await?;
+        tracing::info!("Execution gRPC service listening on {}", addr);
+        let stop = shutdown.clone();
+        shutdown.track(tokio::spawn(async move {
+            if let Err(e) = rpc::serve(listener, grpc, &stop).await {
+                tracing::error!("gRPC server failed: {}", e);
+            }
+        }));
+    }
 
     // Demo: publisher task - simulates trade plan generation
     let tx_bus = bus.clone();
-    tokio::spawn(async move {
+    shutdown.spawn(async move {
         // In a real system, these would come from the strategy service
         sleep(Duration::from_secs(1)).await; // Wait a bit for subscriber to start
     });
 
-    // Keep running
-    loop {
-        sleep(Duration::from_secs(3600)).await;
+    // Run until SIGINT or SIGTERM, then let the background tasks stop
+    shutdown.wait().await;
+    shutdown.join().await;
+    
+    // Release the lease so a standby takes over without waiting for it to expire
+    if let Err(e) = coordinator.write().await.step_down().await {
+        tracing::warn!("failed to release the execution lease: {}", e);
     }
+    Ok(())
 }
 
-/// Execute a trade and return the receipt
-async fn execute_trade(plan: &TradePlan) -> ExecReceipt {
-    tracing::info!("executing trade on {} chain", plan.chain.name);
-    
-    // In a real implementation, this would:
-    // 1. Connect to the appropriate blockchain
-    // 2. Build the transaction
-    // 3. Sign the transaction (using the keys service)
-    // 4. Submit the transaction via the selected execution mode
-    // 5. Wait for confirmation and return the receipt
-    
-    // Simulate execution
-    ExecReceipt {
-        tx_hash: format!("0x{}", hex::encode(plan.idem_key.as_bytes())),
-        success: true,
-        block: 12345678,
-        gas_used: 150000,
-        fees_paid_wei: 3150000000000000, // 0.00315 ETH
-        failure_reason: None,
+/// Submit a plan if this region holds the execution lease, publishing its receipt
+async fn submit_plan(executor: &Executor, bus: &InMemoryBus, plan: &TradePlan) -> anyhow::Result<ExecReceipt> {
+    // The executor takes the fencing token so a demoted region never submits
+    let receipt = executor.execute_trade(plan).await?;
+
+    // Publish the execution result
+    let _ = bus.publish("exec.result", &receipt).await;
+    tracing::info!("executed trade: {}", receipt.tx_hash);
+    Ok(receipt)
+}
+
+/// gRPC interface executing plans like those received over the bus
+struct ExecutionRpc {
+    executor: Arc<Executor>,
+    bus: InMemoryBus,
+}
+
+#[tonic::async_trait]
+impl proto::execution_server::Execution for ExecutionRpc {
+    async fn execute_plan(
+        &self,
+        request: tonic::Request<proto::TradePlan>,
+    ) -> Result<tonic::Response<proto::ExecReceipt>, tonic::Status> {
+        let caller = rpc::caller(&request)?;
+        rpc::require_permission(&caller, "execute_trades")?;
+        let plan = TradePlan::try_from(request.into_inner())?;
+        tracing::info!("received trade plan {} from {} over gRPC", plan.idem_key, caller.user_id);
+
+        // A standby region turns plans away for the caller to retry against the primary
+        let receipt = submit_plan(&self.executor, &self.bus, &plan)
+            .await
+            .map_err(|e| tonic::Status::unavailable(e.to_string()))?;
+        Ok(tonic::Response::new(receipt.into()))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sniper_core::types::{ChainRef, ExecMode, ExitRules, GasPolicy};
+
+    fn plan() -> TradePlan {
+        TradePlan {
+            chain: ChainRef {
+                name: "ethereum".to_string(),
+                id: 1,
+            },
+            router: "0xRouter".to_string(),
+            token_in: "0xTokenIn".to_string(),
+            token_out: "0xTokenOut".to_string(),
+            amount_in: 1000000000000000000, // 1 ETH
+            min_out: 900000000000000000,    // 0.9 ETH worth of tokens
+            mode: ExecMode::Mempool,
+            gas: GasPolicy {
+                max_fee_gwei: 50,
+                max_priority_gwei: 2,
+            },
+            exits: ExitRules::default(),
+            idem_key: "test-key".to_string(),
+            quote: None,
+            correlation_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_superseded_fencing_token_is_refused() -> anyhow::Result<()> {
+        let store: Arc<dyn LeaseStore> = Arc::new(InMemoryLeaseStore::new());
+        let coordinator = |region: &str| {
+            let config = FailoverConfig::new(region, "main", Duration::from_millis(20));
+            Arc::new(RwLock::new(FailoverCoordinator::new(config, store.clone())))
+        };
+        let (primary, standby) = (coordinator("us-east-1"), coordinator("eu-west-1"));
+        let executor = |coordinator: &Arc<RwLock<FailoverCoordinator>>| Executor::new().with_failover(coordinator.clone());
+        let bus = InMemoryBus::new(16);
+
+        primary.write().await.heartbeat().await?;
+        assert!(submit_plan(&executor(&primary), &bus, &plan()).await.is_ok());
+
+        // The standby takes over from the old primary
+        sleep(Duration::from_millis(80)).await;
+        standby.write().await.heartbeat().await?;
+        assert!(submit_plan(&executor(&primary), &bus, &plan()).await.unwrap_err().to_string().contains("lease"));
+        assert!(submit_plan(&executor(&standby), &bus, &plan()).await.is_ok());
+        Ok(())
+    }
+}
@@ -7,15 +7,29 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use clap::Parser;
+use sniper_core::config::ConfigArgs;
+use sniper_core::errors::ApiError;
+use sniper_core::rate_limit::{RateLimitConfig, RateLimiter};
+use sniper_core::shutdown::Shutdown;
 use std::collections::HashMap;
 
+/// Port used when neither the configuration nor --port sets one
+const DEFAULT_PORT: u16 = 3000;
+
 /// CLI arguments for the gateway service
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    /// Port to listen on
-    #[clap(short, long, default_value = "3000")]
-    port: u16,
+    /// Port to listen on, overriding the configured port (default 3000)
+    #[clap(short, long)]
+    port: Option<u16>,
+
+    /// OTLP endpoint spans are exported to, defaulting to OTEL_EXPORTER_OTLP_ENDPOINT
+    #[clap(long)]
+    otlp_endpoint: Option<String>,
+
+    #[clap(flatten)]
+    config: ConfigArgs,
 }
 
 /// Health check response
@@ -77,6 +91,17 @@ async fn main() -> eyre::Result<()> {
     dotenvy::dotenv().ok();
 
     let args = Args::parse();
+    let config = args.config.loader("svc-gateway")
+        .with_default("port", DEFAULT_PORT)
+        .with_override("port", args.port)
+        .load_service()?;
+    if args.config.print_config {
+        println!("{}", config.render()?);
+        return Ok(());
+    }
+    let _telemetry = sniper_core::telemetry::init_telemetry("svc-gateway", args.otlp_endpoint.as_deref())
+        .map_err(|e| eyre::eyre!("{}", e))?;
+    let shutdown = Shutdown::on_signal();
     
     let bus = InMemoryBus::new(1024);
     
@@ -88,7 +113,7 @@ async fn main() -> eyre::Result<()> {
 
     // Demo: publisher task
     let tx_bus = bus.clone();
-    tokio::spawn(async move {
+    shutdown.spawn(async move {
         loop {
             let sig = Signal {
                 source: "dex".into(),
@@ -109,7 +134,7 @@ async fn main() -> eyre::Result<()> {
 
     // Demo: subscriber task
     let mut rx = bus.subscribe("signals.>");
-    tokio::spawn(async move {
+    shutdown.spawn(async move {
         loop {
             if let Ok(bytes) = rx.recv().await {
                 if let Ok(sig) = serde_json::from_slice::<Signal>(&bytes) {
@@ -119,6 +144,9 @@ async fn main() -> eyre::Result<()> {
         }
     });
 
+    // Throttle callers per tenant, separately for reads and trades
+    let rate_limiter = RateLimiter::new(config.section::<RateLimitConfig>("rate_limit")?.unwrap_or_default());
+
     // Create router
     let app = Router::new()
         .route("/health", get(health_check))
@@ -127,14 +155,17 @@ async fn main() -> eyre::Result<()> {
         .route("/external-apis", post(add_external_api))
         .route("/external-apis/:id", put(update_external_api))
         .route("/external-apis/:id", delete(remove_external_api))
-        .layer(Extension(app_state));
+        .layer(Extension(app_state))
+        .layer(rate_limiter.layer())
+        .layer(axum::middleware::from_fn(sniper_core::telemetry::trace_http));
 
     // Run server
-    let addr = format!("0.0.0.0:{}", args.port);
+    let addr = format!("0.0.0.0:{}", config.port.unwrap_or(DEFAULT_PORT));
     tracing::info!("Gateway service listening on http://{}", addr);
     
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    sniper_core::shutdown::serve(listener, app, &shutdown).await?;
+    shutdown.join().await;
 
     Ok(())
 }
@@ -161,7 +192,7 @@ async fn health_check() -> Json<HealthResponse> {
 async fn create_signal(
     Extension(state): Extension<Arc<AppState>>,
     Json(payload): Json<SignalRequest>,
-) -> Json<SignalResponse> {
+) -> Result<Json<SignalResponse>, ApiError> {
     let signal = Signal {
         source: payload.source,
         kind: payload.kind,
@@ -178,16 +209,16 @@ async fn create_signal(
             .as_millis() as i64, // Changed to i64 to match Signal struct
     };
 
-    match state.bus.publish("signals.api.created", &signal).await {
-        Ok(_) => Json(SignalResponse {
-            success: true,
-            message: "Signal created successfully".to_string(),
-        }),
-        Err(_) => Json(SignalResponse {
-            success: false,
-            message: "Failed to create signal".to_string(),
-        }),
-    }
+    // The signal continues the trace of the request that created it
+    let cx = sniper_core::telemetry::Context::current();
+    state
+        .bus
+        .publish_in("signals.api.created", &signal, &cx)
+        .map_err(|e| ApiError::from(e).context("Failed to create signal"))?;
+    Ok(Json(SignalResponse {
+        success: true,
+        message: "Signal created successfully".to_string(),
+    }))
 }
 
 /// List all external API integrations
@@ -221,39 +252,29 @@ async fn update_external_api(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(id): axum::extract::Path<String>,
     Json(payload): Json<ExternalApiConfig>,
-) -> Json<ExternalApiResponse> {
+) -> Result<Json<ExternalApiResponse>, ApiError> {
     let mut apis = state.external_apis.write().await;
     
-    if apis.contains_key(&id) {
-        apis.insert(id, payload);
-        Json(ExternalApiResponse {
-            success: true,
-            message: "External API updated successfully".to_string(),
-        })
-    } else {
-        Json(ExternalApiResponse {
-            success: false,
-            message: "External API not found".to_string(),
-        })
+    if !apis.contains_key(&id) {
+        return Err(ApiError::not_found("External API not found"));
     }
+    apis.insert(id, payload);
+    Ok(Json(ExternalApiResponse {
+        success: true,
+        message: "External API updated successfully".to_string(),
+    }))
 }
 
 /// Remove an external API integration
 async fn remove_external_api(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<ExternalApiResponse> {
+) -> Result<Json<ExternalApiResponse>, ApiError> {
     let mut apis = state.external_apis.write().await;
     
-    if apis.remove(&id).is_some() {
-        Json(ExternalApiResponse {
-            success: true,
-            message: "External API removed successfully".to_string(),
-        })
-    } else {
-        Json(ExternalApiResponse {
-            success: false,
-            message: "External API not found".to_string(),
-        })
-    }
+    apis.remove(&id).ok_or_else(|| ApiError::not_found("External API not found"))?;
+    Ok(Json(ExternalApiResponse {
+        success: true,
+        message: "External API removed successfully".to_string(),
+    }))
 }
\ No newline at end of file
@@ -2,34 +2,82 @@
 //! 
 //! This service provides REST APIs for cross-protocol liquidity aggregation
 //! and optimal trade routing.
+//! The OpenAPI document is served on `/openapi.json`, with a Swagger UI on
+//! `/docs`.
 
 use anyhow::Result;
 use clap::Parser;
+use sniper_core::config::ConfigArgs;
+use sniper_core::errors::{ApiError, ErrorKind};
+use sniper_core::openapi::{self, ErrorResponse};
+use sniper_core::rate_limit::{RateLimitConfig, RateLimiter};
+use sniper_core::shutdown::Shutdown;
 use serde::{Deserialize, Serialize};
 use axum::{
+    extract::DefaultBodyLimit,
     routing::{get, post, delete},
     Json, Router, Extension,
 };
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use sniper_liquidity::{LiquidityAggregator, LiquidityConfig, LiquiditySource, TokenPair, AggregatedLiquidity, TradeRoute};
+use utoipa::{OpenApi, ToSchema};
+use sniper_core::bus::InMemoryBus;
+use sniper_liquidity::arbitrage::{ArbConfig, ArbDetector};
+use sniper_liquidity::routing::PathfinderConfig;
+use sniper_liquidity::sync::{ReserveSync, SyncConfig};
+use sniper_liquidity::{LiquidityAggregator, LiquidityConfig, LiquiditySource, TokenPair, AggregatedLiquidity, TradeRoute, SourceUpdate, BulkIngestReport};
+use sniper_monitoring::http::{instrument, ServiceMetrics};
+use sniper_oracle::http::OracleFeeds;
+use sniper_risk::divergence::{DivergenceGuard, DivergenceGuardConfig, GuardOverride};
+
+/// Port used when neither the configuration nor --port sets one
+const DEFAULT_PORT: u16 = 8097;
 
 /// CLI arguments for the liquidity service
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    /// Port to listen on
-    #[clap(short, long, default_value = "8097")]
-    port: u16,
+    /// Port to listen on, overriding the configured port (default 8097)
+    #[clap(short, long)]
+    port: Option<u16>,
+    
+    /// Largest bulk source ingest body accepted, in megabytes
+    #[clap(long, default_value = "64")]
+    max_bulk_body_mb: usize,
+
+    /// Most pools a trade route passes through
+    #[clap(long, default_value = "3")]
+    max_hops: usize,
+
+    /// Token routes may pass through, repeated per token; defaults to WETH, USDC, USDT, DAI and WBTC
+    #[clap(long = "hub-token")]
+    hub_tokens: Vec<String>,
+
+    /// Reserve sync settings; without them sources keep the reserves they were posted with
+    #[clap(long)]
+    sync_config: Option<String>,
+
+    /// Arbitrage detection settings; without them no opportunities are looked for
+    #[clap(long)]
+    arb_config: Option<String>,
+
+    /// OTLP endpoint spans are exported to, defaulting to OTEL_EXPORTER_OTLP_ENDPOINT
+    #[clap(long)]
+    otlp_endpoint: Option<String>,
+
+    #[clap(flatten)]
+    config: ConfigArgs,
 }
 
 /// Liquidity service state
 struct AppState {
-    liquidity_aggregator: RwLock<LiquidityAggregator>,
+    liquidity_aggregator: Arc<RwLock<LiquidityAggregator>>,
+    metrics: Arc<ServiceMetrics>,
+    divergence_guard: RwLock<DivergenceGuard>,
 }
 
 /// Health check response
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct HealthResponse {
     status: String,
     service: String,
@@ -37,19 +85,33 @@ struct HealthResponse {
 }
 
 /// Liquidity source request
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct AddLiquiditySourceRequest {
     source_id: String,
     source: LiquiditySource,
 }
 
 /// Liquidity source response
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct AddLiquiditySourceResponse {
     success: bool,
     message: String,
 }
 
+/// Bulk liquidity source ingest request
+#[derive(Deserialize, ToSchema)]
+struct BulkIngestRequest {
+    updates: Vec<SourceUpdate>,
+}
+
+/// Bulk liquidity source ingest response
+#[derive(Serialize, ToSchema)]
+struct BulkIngestResponse {
+    success: bool,
+    data: Option<BulkIngestReport>,
+    message: Option<String>,
+}
+
 /// Remove liquidity source request
 #[derive(Deserialize)]
 struct RemoveLiquiditySourceRequest {
@@ -57,20 +119,20 @@ struct RemoveLiquiditySourceRequest {
 }
 
 /// Remove liquidity source response
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct RemoveLiquiditySourceResponse {
     success: bool,
     message: String,
 }
 
 /// Aggregate liquidity request
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct AggregateLiquidityRequest {
     pair: TokenPair,
 }
 
 /// Aggregate liquidity response
-#[derive(Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct AggregateLiquidityResponse {
     success: bool,
     data: Option<AggregatedLiquidity>,
@@ -78,26 +140,69 @@ struct AggregateLiquidityResponse {
 }
 
 /// Find route request
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct FindRouteRequest {
     token_in: String,
     token_out: String,
+    #[schema(example = "1000000000000000000")]
     amount_in: String, // Using string to avoid parsing issues
 }
 
 /// Find route response
-#[derive(Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct FindRouteResponse {
     success: bool,
     data: Option<TradeRoute>,
+    /// Other usable routes, best first
+    alternatives: Vec<TradeRoute>,
     message: Option<String>,
 }
 
+/// Divergence guard override request
+#[derive(Deserialize, ToSchema)]
+struct GrantOverrideRequest {
+    asset: String,
+    approved_by: String,
+    reason: String,
+    duration_minutes: i64,
+}
+
+/// Divergence guard override response
+#[derive(Debug, Serialize, ToSchema)]
+struct GuardOverrideResponse {
+    success: bool,
+    message: String,
+}
+
+/// OpenAPI document of the service, served on `/openapi.json`
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        health_check, add_liquidity_source, ingest_liquidity_sources, remove_liquidity_source, aggregate_liquidity,
+        find_best_route, grant_guard_override, revoke_guard_override,
+    ),
+    tags(
+        (name = "liquidity", description = "Liquidity sources and their aggregation"),
+        (name = "routing", description = "Trade routing and the divergence guard"),
+    )
+)]
+struct ApiDoc;
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
     
     let args = Args::parse();
+    let service_config = args.config.loader("svc-liquidity")
+        .with_default("port", DEFAULT_PORT)
+        .with_override("port", args.port)
+        .load_service()?;
+    if args.config.print_config {
+        println!("{}", service_config.render()?);
+        return Ok(());
+    }
+    let _telemetry = sniper_core::telemetry::init_telemetry("svc-liquidity", args.otlp_endpoint.as_deref())?;
+    let shutdown = Shutdown::on_signal();
     
     // Create liquidity aggregator with default config
     let config = LiquidityConfig {
@@ -107,33 +212,93 @@ async fn main() -> Result<()> {
         max_price_impact: 0.05,
     };
     
-    let liquidity_aggregator = LiquidityAggregator::new(config);
+    let mut pathfinder = PathfinderConfig {
+        max_hops: args.max_hops,
+        ..PathfinderConfig::default()
+    };
+    if !args.hub_tokens.is_empty() {
+        pathfinder.hub_tokens = args.hub_tokens.clone();
+    }
+    let liquidity_aggregator = Arc::new(RwLock::new(LiquidityAggregator::new(config).with_pathfinder(pathfinder)));
+    
+    // Keep pool-backed sources in step with the chain
+    let bus = InMemoryBus::new(1024);
+    if let Some(path) = &args.sync_config {
+        let sync = ReserveSync::new(liquidity_aggregator.clone(), SyncConfig::load(path)?)?.with_bus(bus.clone());
+        Arc::new(sync).spawn();
+        tracing::info!("Syncing liquidity source reserves with settings from {}", path);
+    }
+    
+    // Watch tracked pairs for price discrepancies between their sources
+    if let Some(path) = &args.arb_config {
+        let detector = ArbDetector::new(liquidity_aggregator.clone(), ArbConfig::load(path)?).with_bus(bus.clone());
+        Arc::new(detector).spawn();
+        tracing::info!("Detecting arbitrage with settings from {}", path);
+    }
+    
+    // Create service metrics
+    let mut metrics = ServiceMetrics::new("svc-liquidity")?;
+    metrics.register_counter("routes_found_total", "Total trade routes found")?;
+    metrics.register_counter("routes_not_found_total", "Total route requests with no suitable route")?;
+    metrics.register_counter("routes_blocked_total", "Total routes blocked by the divergence guard")?;
+    metrics.register_counter("bulk_ingests_total", "Total bulk liquidity source ingests")?;
+    metrics.register_gauge("liquidity_sources", "Pools known across all liquidity sources")?;
+    let metrics = Arc::new(metrics);
+    
+    // Create price oracle and the divergence guard checking pools against it
+    let oracle = Arc::new(OracleFeeds::default());
+    let divergence_guard = DivergenceGuard::new(DivergenceGuardConfig::default(), oracle.oracle.clone());
     
     // Create app state
     let app_state = Arc::new(AppState {
-        liquidity_aggregator: RwLock::new(liquidity_aggregator),
+        liquidity_aggregator,
+        metrics: metrics.clone(),
+        divergence_guard: RwLock::new(divergence_guard),
     });
     
+    // Throttle callers per tenant, separately for reads and trades
+    let rate_limiter = RateLimiter::new(service_config.section::<RateLimitConfig>("rate_limit")?.unwrap_or_default())
+        .with_registry(metrics.registry(), "svc-liquidity")?;
+
     // Create router
     let app = Router::new()
         .route("/health", get(health_check))
         .route("/liquidity/sources", post(add_liquidity_source))
+        .route(
+            "/liquidity/sources/bulk",
+            post(ingest_liquidity_sources).layer(DefaultBodyLimit::max(args.max_bulk_body_mb * 1024 * 1024)),
+        )
         .route("/liquidity/sources/:id", delete(remove_liquidity_source))
         .route("/liquidity/aggregate", post(aggregate_liquidity))
         .route("/liquidity/route", post(find_best_route))
-        .layer(Extension(app_state));
+        .route("/guard/overrides", post(grant_guard_override))
+        .route("/guard/overrides/:asset", delete(revoke_guard_override))
+        .merge(sniper_oracle::http::routes(oracle))
+        .layer(Extension(app_state))
+        .layer(rate_limiter.layer())
+        .layer(axum::middleware::from_fn(sniper_core::telemetry::trace_http));
+    let app = instrument(app, metrics)
+        .merge(openapi::router(ApiDoc::openapi().merge_from(sniper_oracle::http::OracleApi::openapi())));
     
     // Run server
-    let addr = format!("0.0.0.0:{}", args.port);
+    let addr = format!("0.0.0.0:{}", service_config.port.unwrap_or(DEFAULT_PORT));
     tracing::info!("Liquidity service listening on http://{}", addr);
     
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    sniper_core::shutdown::serve(listener, app, &shutdown).await?;
         
     Ok(())
 }
 
 /// Health check endpoint
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses(
+        (status = 200, description = "Service is healthy", body = HealthResponse),
+    )
+)]
 async fn health_check() -> Json<HealthResponse> {
     Json(HealthResponse {
         status: "ok".to_string(),
@@ -146,6 +311,15 @@ async fn health_check() -> Json<HealthResponse> {
 }
 
 /// Add liquidity source
+#[utoipa::path(
+    post,
+    path = "/liquidity/sources",
+    tag = "liquidity",
+    request_body = AddLiquiditySourceRequest,
+    responses(
+        (status = 200, description = "Liquidity source added", body = AddLiquiditySourceResponse),
+    )
+)]
 async fn add_liquidity_source(
     Extension(state): Extension<Arc<AppState>>,
     Json(payload): Json<AddLiquiditySourceRequest>,
@@ -161,7 +335,53 @@ async fn add_liquidity_source(
     })
 }
 
+/// Ingest a batch of liquidity source updates, reporting rejected items
+#[utoipa::path(
+    post,
+    path = "/liquidity/sources/bulk",
+    tag = "liquidity",
+    request_body = BulkIngestRequest,
+    responses(
+        (status = 200, description = "Sources inserted or updated, with the updates rejected; `success: false` if any were", body = BulkIngestResponse),
+        (status = 413, description = "Body larger than `--max-bulk-body-mb`"),
+        (status = 422, description = "Batch larger than the update limit", body = ErrorResponse),
+    )
+)]
+async fn ingest_liquidity_sources(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(payload): Json<BulkIngestRequest>,
+) -> Result<Json<BulkIngestResponse>, ApiError> {
+    let (report, source_count) = {
+        let mut aggregator = state.liquidity_aggregator.write().await;
+        let report = aggregator
+            .ingest_sources(payload.updates)
+            .map_err(|e| ApiError::from(e).context("Error ingesting liquidity sources"))?;
+        (report, aggregator.source_count())
+    };
+    
+    state.metrics.increment_counter("bulk_ingests_total");
+    state.metrics.set_gauge("liquidity_sources", source_count as f64);
+    let message = format!(
+        "Ingested {} of {} updates, {} rejected",
+        report.ingested(), report.received, report.errors.len()
+    );
+    Ok(Json(BulkIngestResponse {
+        success: report.errors.is_empty(),
+        data: Some(report),
+        message: Some(message),
+    }))
+}
+
 /// Remove liquidity source
+#[utoipa::path(
+    delete,
+    path = "/liquidity/sources/{id}",
+    tag = "liquidity",
+    params(("id" = String, Path, description = "Liquidity source ID")),
+    responses(
+        (status = 200, description = "Liquidity source removed", body = RemoveLiquiditySourceResponse),
+    )
+)]
 async fn remove_liquidity_source(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(id): axum::extract::Path<String>,
@@ -178,77 +398,188 @@ async fn remove_liquidity_source(
 }
 
 /// Aggregate liquidity for a token pair
+#[utoipa::path(
+    post,
+    path = "/liquidity/aggregate",
+    tag = "liquidity",
+    request_body = AggregateLiquidityRequest,
+    responses(
+        (status = 200, description = "Liquidity of the pair across all sources", body = AggregateLiquidityResponse),
+        (status = 404, description = "No sources for the pair", body = ErrorResponse),
+        (status = 503, description = "Every source for the pair is stale", body = ErrorResponse),
+    )
+)]
 async fn aggregate_liquidity(
     Extension(state): Extension<Arc<AppState>>,
     Json(payload): Json<AggregateLiquidityRequest>,
-) -> Json<AggregateLiquidityResponse> {
-    match state.liquidity_aggregator.read().await.aggregate_liquidity(&payload.pair) {
-        Ok(aggregated) => {
-            Json(AggregateLiquidityResponse {
-                success: true,
-                data: Some(aggregated),
-                message: None,
-            })
-        },
-        Err(e) => {
-            Json(AggregateLiquidityResponse {
-                success: false,
-                data: None,
-                message: Some(format!("Error aggregating liquidity: {}", e)),
-            })
-        }
-    }
+) -> Result<Json<AggregateLiquidityResponse>, ApiError> {
+    let aggregated = state
+        .liquidity_aggregator
+        .read()
+        .await
+        .aggregate_liquidity(&payload.pair)
+        .map_err(|e| ApiError::from(e).context("Error aggregating liquidity"))?;
+    Ok(Json(AggregateLiquidityResponse {
+        success: true,
+        data: Some(aggregated),
+        message: None,
+    }))
 }
 
 /// Find the best route for a trade
+#[utoipa::path(
+    post,
+    path = "/liquidity/route",
+    tag = "routing",
+    request_body = FindRouteRequest,
+    responses(
+        (status = 200, description = "Best route and its alternatives", body = FindRouteResponse),
+        (status = 404, description = "No route between the tokens", body = ErrorResponse),
+        (status = 409, description = "Every route is blocked by the divergence guard", body = ErrorResponse),
+        (status = 422, description = "Amount is not a positive integer", body = ErrorResponse),
+    )
+)]
 async fn find_best_route(
     Extension(state): Extension<Arc<AppState>>,
     Json(payload): Json<FindRouteRequest>,
-) -> Json<FindRouteResponse> {
-    // Parse amount_in
-    let amount_in = payload.amount_in.parse::<u128>().unwrap_or(0);
+) -> Result<Json<FindRouteResponse>, ApiError> {
+    let amount_in = payload
+        .amount_in
+        .parse::<u128>()
+        .ok()
+        .filter(|amount| *amount > 0)
+        .ok_or_else(|| ApiError::invalid(format!("Invalid amount_in {}", payload.amount_in)))?;
     
-    match state.liquidity_aggregator.read().await.find_best_route(
+    let routes = state.liquidity_aggregator.read().await.find_routes(
         &payload.token_in,
         &payload.token_out,
         amount_in,
-    ) {
-        Ok(Some(route)) => {
-            Json(FindRouteResponse {
-                success: true,
-                data: Some(route),
-                message: None,
-            })
-        },
-        Ok(None) => {
-            Json(FindRouteResponse {
-                success: false,
-                data: None,
-                message: Some("No suitable route found".to_string()),
-            })
-        },
-        Err(e) => {
-            Json(FindRouteResponse {
-                success: false,
-                data: None,
-                message: Some(format!("Error finding route: {}", e)),
-            })
+    );
+    if routes.is_empty() {
+        state.metrics.increment_counter("routes_not_found_total");
+        return Err(ApiError::not_found("No suitable route found"));
+    }
+    
+    // Refuse routes through pools diverging from the oracle
+    let guard = state.divergence_guard.read().await;
+    let mut allowed = Vec::new();
+    let mut blocked = Vec::new();
+    for route in routes {
+        let mut reasons = Vec::new();
+        for source in route.sources.iter().filter(|s| s.reserve0 > 0) {
+            let pool_price = source.reserve1 as f64 / source.reserve0 as f64;
+            let decision = guard.check(&source.pair.token0, pool_price).await;
+            if !decision.allow {
+                reasons.extend(decision.reasons);
+            }
+        }
+        if reasons.is_empty() {
+            allowed.push(route);
+        } else {
+            blocked.extend(reasons);
         }
     }
+    if allowed.is_empty() {
+        state.metrics.increment_counter("routes_blocked_total");
+        blocked.dedup();
+        return Err(ApiError::new(
+            ErrorKind::Conflict,
+            format!("Route blocked by divergence guard: {}", blocked.join("; ")),
+        ));
+    }
+    
+    state.metrics.increment_counter("routes_found_total");
+    let best = allowed.remove(0);
+    Ok(Json(FindRouteResponse {
+        success: true,
+        data: Some(best),
+        alternatives: allowed,
+        message: None,
+    }))
+}
+
+/// Grant a divergence guard override for an asset
+#[utoipa::path(
+    post,
+    path = "/guard/overrides",
+    tag = "routing",
+    request_body = GrantOverrideRequest,
+    responses(
+        (status = 200, description = "Divergence guard override granted", body = GuardOverrideResponse),
+        (status = 422, description = "Duration is not positive", body = ErrorResponse),
+    )
+)]
+async fn grant_guard_override(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(payload): Json<GrantOverrideRequest>,
+) -> Result<Json<GuardOverrideResponse>, ApiError> {
+    if payload.duration_minutes <= 0 {
+        return Err(ApiError::invalid(format!("Override duration must be positive, got {} minutes", payload.duration_minutes)));
+    }
+    state.divergence_guard.write().await.grant_override(GuardOverride {
+        asset: payload.asset,
+        approved_by: payload.approved_by,
+        reason: payload.reason,
+        expires_at: chrono::Utc::now() + chrono::Duration::minutes(payload.duration_minutes),
+    });
+    
+    Ok(Json(GuardOverrideResponse {
+        success: true,
+        message: "Guard override granted successfully".to_string(),
+    }))
+}
+
+/// Revoke the divergence guard override for an asset
+#[utoipa::path(
+    delete,
+    path = "/guard/overrides/{asset}",
+    tag = "routing",
+    params(("asset" = String, Path, description = "Asset the override applies to")),
+    responses(
+        (status = 200, description = "Divergence guard override revoked", body = GuardOverrideResponse),
+        (status = 404, description = "No override for the asset", body = ErrorResponse),
+    )
+)]
+async fn revoke_guard_override(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(asset): axum::extract::Path<String>,
+) -> Result<Json<GuardOverrideResponse>, ApiError> {
+    if !state.divergence_guard.write().await.revoke_override(&asset) {
+        return Err(ApiError::not_found("Guard override not found"));
+    }
+    Ok(Json(GuardOverrideResponse {
+        success: true,
+        message: "Guard override revoked successfully".to_string(),
+    }))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_openapi_document() {
+        let document = serde_json::to_value(ApiDoc::openapi().merge_from(sniper_oracle::http::OracleApi::openapi())).unwrap();
+        for path in ["/liquidity/sources/bulk", "/liquidity/route", "/guard/overrides/{asset}", "/oracle/prices/{asset}"] {
+            assert!(document["paths"][path].is_object(), "{} is not documented", path);
+        }
+        assert!(document["components"]["schemas"]["TradeRoute"]["properties"]["amounts_out"].is_object());
+    }
+
     #[test]
     fn test_args_parsing() {
         let args = Args::parse_from(["svc-liquidity", "--port", "8098"]);
-        assert_eq!(args.port, 8098);
+        assert_eq!(args.port, Some(8098));
+        assert_eq!(args.max_bulk_body_mb, 64);
+        assert_eq!(args.max_hops, 3);
+        assert!(args.hub_tokens.is_empty());
+        
+        let args = Args::parse_from(["svc-liquidity", "--max-hops", "2", "--hub-token", "WETH", "--hub-token", "USDC"]);
+        assert_eq!(args.max_hops, 2);
+        assert_eq!(args.hub_tokens, vec!["WETH", "USDC"]);
     }
 
-    #[tokio::test]
-    async fn test_liquidity_service_creation() -> Result<()> {
+    fn app_state() -> Result<Arc<AppState>> {
         let config = LiquidityConfig {
             chains: vec!["ethereum".to_string()],
             protocols: vec!["uniswap".to_string()],
@@ -257,10 +588,46 @@ mod tests {
         };
         
         let liquidity_aggregator = LiquidityAggregator::new(config);
-        let _app_state = Arc::new(AppState {
-            liquidity_aggregator: RwLock::new(liquidity_aggregator),
-        });
-        
+        Ok(Arc::new(AppState {
+            liquidity_aggregator: Arc::new(RwLock::new(liquidity_aggregator)),
+            metrics: Arc::new(ServiceMetrics::new("svc-liquidity")?),
+            divergence_guard: RwLock::new(DivergenceGuard::new(
+                DivergenceGuardConfig::default(),
+                Arc::new(sniper_oracle::PriceOracle::new(sniper_oracle::OracleConfig::default())),
+            )),
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_liquidity_service_creation() -> Result<()> {
+        let _app_state = app_state()?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_handler_errors_map_to_status() -> Result<()> {
+        use axum::http::StatusCode;
+
+        let state = app_state()?;
+        let route = |amount_in: &str| FindRouteRequest {
+            token_in: "WETH".to_string(),
+            token_out: "USDC".to_string(),
+            amount_in: amount_in.to_string(),
+        };
+        let status = find_best_route(Extension(state.clone()), Json(route("1000"))).await.unwrap_err().status();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let status = find_best_route(Extension(state.clone()), Json(route("lots"))).await.unwrap_err().status();
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let pair = TokenPair {
+            token0: "WETH".to_string(),
+            token1: "USDC".to_string(),
+        };
+        let status = aggregate_liquidity(Extension(state.clone()), Json(AggregateLiquidityRequest { pair })).await.unwrap_err().status();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let revoked = revoke_guard_override(Extension(state.clone()), axum::extract::Path("WETH".to_string())).await;
+        assert_eq!(revoked.unwrap_err().status(), StatusCode::NOT_FOUND);
         Ok(())
     }
 }
\ No newline at end of file
@@ -57,6 +57,8 @@ async fn test_liquidity_service_data_handling() {
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs(),
+        pool: None,
+        stale: false,
     };
     
     // Add liquidity source
@@ -104,6 +106,8 @@ async fn test_liquidity_service_cross_protocol() {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
+            pool: None,
+            stale: false,
         }),
         ("pancakeswap_bsc", LiquiditySource {
             protocol: "pancakeswap".to_string(),
@@ -119,6 +123,8 @@ async fn test_liquidity_service_cross_protocol() {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
+            pool: None,
+            stale: false,
         }),
         ("sushiswap_ethereum", LiquiditySource {
             protocol: "sushiswap".to_string(),
@@ -134,6 +140,8 @@ async fn test_liquidity_service_cross_protocol() {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
+            pool: None,
+            stale: false,
         }),
     ];
     
@@ -1,25 +1,47 @@
 //! Marketplace service for the sniper-rs ecosystem.
 //! 
 //! This service provides REST APIs for community strategy sharing and discovery.
+//! The OpenAPI document is served on `/openapi.json`, with a Swagger UI on
+//! `/docs`.
 
 use anyhow::Result;
 use clap::Parser;
+use sniper_core::config::ConfigArgs;
+use sniper_core::errors::ApiError;
+use sniper_core::openapi::{self, ErrorResponse};
+use sniper_core::rate_limit::{RateLimitConfig, RateLimiter};
+use sniper_core::shutdown::Shutdown;
 use serde::{Deserialize, Serialize};
 use axum::{
+    extract::Query,
     routing::{get, post},
     Json, Router, Extension,
 };
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use sniper_market::{InMemoryMarketplace, Marketplace, StrategyListing, StrategyReview, MarketStats};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use sniper_market::{
+    InMemoryMarketplace, ListingStatus, MarketError, Marketplace, MarketStats, RatingSummary, ReviewFlag, SortOrder, StrategyListing,
+    StrategyPage, StrategyQuery, StrategyReview, StrategySort,
+};
+
+/// Port used when neither the configuration nor --port sets one
+const DEFAULT_PORT: u16 = 8095;
 
 /// CLI arguments for the marketplace service
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    /// Port to listen on
-    #[clap(short, long, default_value = "8095")]
-    port: u16,
+    /// Port to listen on, overriding the configured port (default 8095)
+    #[clap(short, long)]
+    port: Option<u16>,
+
+    /// OTLP endpoint spans are exported to, defaulting to OTEL_EXPORTER_OTLP_ENDPOINT
+    #[clap(long)]
+    otlp_endpoint: Option<String>,
+
+    #[clap(flatten)]
+    config: ConfigArgs,
 }
 
 /// Marketplace service state
@@ -28,18 +50,98 @@ struct AppState {
 }
 
 /// Standard response format
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
     pub message: Option<String>,
 }
 
+/// Strategy upload request
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+struct UploadStrategyRequest {
+    #[serde(flatten)]
+    pub listing: StrategyListing,
+    /// WebAssembly package bytes
+    pub package: Vec<u8>,
+}
+
+/// Report of an abusive review
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+struct FlagReviewRequest {
+    pub user_id: String,
+    pub reason: String,
+}
+
+/// Moderator's removal of a review
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+struct RemoveReviewRequest {
+    pub reason: Option<String>,
+}
+
+/// Query parameters of the strategy listing
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ListStrategiesParams {
+    /// Free text matched against name, description and tags
+    pub q: Option<String>,
+    /// Comma-separated tags a strategy must all carry
+    pub tags: Option<String>,
+    /// sniper-rs version a strategy must be compatible with
+    pub compatible_with: Option<String>,
+    pub sort: Option<StrategySort>,
+    pub order: Option<SortOrder>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+impl From<ListStrategiesParams> for StrategyQuery {
+    fn from(params: ListStrategiesParams) -> Self {
+        Self {
+            text: params.q.filter(|q| !q.trim().is_empty()),
+            tags: params
+                .tags
+                .map(|tags| tags.split(',').map(str::trim).filter(|tag| !tag.is_empty()).map(String::from).collect())
+                .unwrap_or_default(),
+            compatible_with: params.compatible_with,
+            sort: params.sort,
+            order: params.order,
+            limit: params.limit,
+            offset: params.offset.unwrap_or(0),
+        }
+    }
+}
+
+/// OpenAPI document of the service, served on `/openapi.json`
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        health_check, list_strategies, get_strategy, upload_strategy, download_strategy, get_reviews, add_review,
+        flagged_reviews, flag_review, remove_review, restore_review, get_stats,
+    ),
+    tags(
+        (name = "strategies", description = "Strategy search, upload and download"),
+        (name = "reviews", description = "Ratings and reviews of strategies"),
+        (name = "moderation", description = "Moderation of reported reviews"),
+    )
+)]
+struct ApiDoc;
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
     
     let args = Args::parse();
+    let config = args.config.loader("svc-market")
+        .with_default("port", DEFAULT_PORT)
+        .with_override("port", args.port)
+        .load_service()?;
+    if args.config.print_config {
+        println!("{}", config.render()?);
+        return Ok(());
+    }
+    let _telemetry = sniper_core::telemetry::init_telemetry("svc-market", args.otlp_endpoint.as_deref())?;
+    let shutdown = Shutdown::on_signal();
     
     // Create marketplace
     let marketplace = InMemoryMarketplace::new();
@@ -49,6 +151,9 @@ async fn main() -> Result<()> {
         marketplace: RwLock::new(marketplace),
     });
     
+    // Throttle callers per tenant, separately for reads and trades
+    let rate_limiter = RateLimiter::new(config.section::<RateLimitConfig>("rate_limit")?.unwrap_or_default());
+
     // Create router
     let app = Router::new()
         .route("/health", get(health_check))
@@ -58,20 +163,35 @@ async fn main() -> Result<()> {
         .route("/strategies/:id/download", get(download_strategy))
         .route("/strategies/:id/reviews", get(get_reviews))
         .route("/reviews", post(add_review))
+        .route("/reviews/flagged", get(flagged_reviews))
+        .route("/reviews/:id/flag", post(flag_review))
+        .route("/reviews/:id/remove", post(remove_review))
+        .route("/reviews/:id/restore", post(restore_review))
         .route("/stats", get(get_stats))
-        .layer(Extension(app_state));
+        .layer(Extension(app_state))
+        .layer(rate_limiter.layer())
+        .layer(axum::middleware::from_fn(sniper_core::telemetry::trace_http))
+        .merge(openapi::router(ApiDoc::openapi()));
     
     // Run server
-    let addr = format!("0.0.0.0:{}", args.port);
+    let addr = format!("0.0.0.0:{}", config.port.unwrap_or(DEFAULT_PORT));
     tracing::info!("Marketplace service listening on http://{}", addr);
     
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    sniper_core::shutdown::serve(listener, app, &shutdown).await?;
         
     Ok(())
 }
 
 /// Health check endpoint
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses(
+        (status = 200, description = "Service is healthy", body = ApiResponse<String>),
+    )
+)]
 async fn health_check() -> Json<ApiResponse<String>> {
     let response = ApiResponse {
         success: true,
@@ -81,189 +201,331 @@ async fn health_check() -> Json<ApiResponse<String>> {
     Json(response)
 }
 
-/// List strategies
+/// Search strategies, one page at a time
+#[utoipa::path(
+    get,
+    path = "/strategies",
+    tag = "strategies",
+    params(ListStrategiesParams),
+    responses(
+        (status = 200, description = "One page of matching published strategies", body = ApiResponse<StrategyPage>),
+    )
+)]
 async fn list_strategies(
     Extension(state): Extension<Arc<AppState>>,
-) -> Json<ApiResponse<Vec<StrategyListing>>> {
-    let strategies = state.marketplace.read().await.list_strategies(None).await
-        .unwrap_or_else(|e| {
-            tracing::error!("Error listing strategies: {}", e);
-            Vec::new()
-        });
-    
+    Query(params): Query<ListStrategiesParams>,
+) -> Result<Json<ApiResponse<StrategyPage>>, ApiError> {
+    let query = StrategyQuery::from(params);
+    let page = state.marketplace.read().await.search_strategies(&query).await
+        .map_err(|e| ApiError::from(e).context("Error listing strategies"))?;
     let response = ApiResponse {
         success: true,
-        data: Some(strategies),
+        data: Some(page),
         message: None,
     };
-    Json(response)
+    Ok(Json(response))
 }
 
 /// Get a strategy by ID
+#[utoipa::path(
+    get,
+    path = "/strategies/{id}",
+    tag = "strategies",
+    params(("id" = String, Path, description = "Strategy ID")),
+    responses(
+        (status = 200, description = "The strategy", body = ApiResponse<StrategyListing>),
+        (status = 404, description = "Strategy not found", body = ErrorResponse),
+    )
+)]
 async fn get_strategy(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<ApiResponse<StrategyListing>> {
-    match state.marketplace.read().await.get_strategy(&id).await {
-        Ok(Some(strategy)) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(strategy),
-                message: None,
-            };
-            Json(response)
-        },
-        Ok(None) => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some("Strategy not found".to_string()),
-            };
-            Json(response)
-        },
-        Err(e) => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some(format!("Error retrieving strategy: {}", e)),
-            };
-            Json(response)
-        }
-    }
+) -> Result<Json<ApiResponse<StrategyListing>>, ApiError> {
+    let strategy = state
+        .marketplace
+        .read()
+        .await
+        .get_strategy(&id)
+        .await
+        .map_err(|e| ApiError::from(e).context("Error retrieving strategy"))?
+        .ok_or_else(|| ApiError::from(MarketError::StrategyNotFound(id)))?;
+    let response = ApiResponse {
+        success: true,
+        data: Some(strategy),
+        message: None,
+    };
+    Ok(Json(response))
 }
 
-/// Upload a new strategy
+/// Upload a new strategy; the listing is published only if its security scan passes
+#[utoipa::path(
+    post,
+    path = "/strategies",
+    tag = "strategies",
+    request_body = UploadStrategyRequest,
+    responses(
+        (status = 200, description = "Strategy published", body = ApiResponse<StrategyListing>),
+        (status = 422, description = "Rejected by the security scan; the listing and its scan report stay readable by ID", body = ErrorResponse),
+    )
+)]
 async fn upload_strategy(
     Extension(state): Extension<Arc<AppState>>,
-    Json(payload): Json<StrategyListing>,
-) -> Json<ApiResponse<bool>> {
-    match state.marketplace.read().await.upload_strategy(payload).await {
-        Ok(_) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(true),
-                message: Some("Strategy uploaded successfully".to_string()),
-            };
-            Json(response)
-        },
-        Err(e) => {
-            let response = ApiResponse {
-                success: false,
-                data: Some(false),
-                message: Some(format!("Error uploading strategy: {}", e)),
-            };
-            Json(response)
-        }
+    Json(payload): Json<UploadStrategyRequest>,
+) -> Result<Json<ApiResponse<StrategyListing>>, ApiError> {
+    let listing = state
+        .marketplace
+        .read()
+        .await
+        .upload_strategy(payload.listing, payload.package)
+        .await
+        .map_err(|e| ApiError::from(e).context("Error uploading strategy"))?;
+    if listing.status != ListingStatus::Published {
+        let findings: Vec<&str> = listing
+            .scan_report
+            .iter()
+            .flat_map(|report| &report.findings)
+            .map(|finding| finding.message.as_str())
+            .collect();
+        return Err(ApiError::invalid(format!("Strategy rejected by security scan: {}", findings.join("; "))));
     }
+    let response = ApiResponse {
+        success: true,
+        data: Some(listing),
+        message: Some("Strategy published successfully".to_string()),
+    };
+    Ok(Json(response))
 }
 
 /// Download a strategy
+#[utoipa::path(
+    get,
+    path = "/strategies/{id}/download",
+    tag = "strategies",
+    params(("id" = String, Path, description = "Strategy ID")),
+    responses(
+        (status = 200, description = "WebAssembly package bytes of the strategy", body = ApiResponse<Vec<u8>>),
+        (status = 404, description = "No published strategy with the ID", body = ErrorResponse),
+    )
+)]
 async fn download_strategy(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<ApiResponse<Vec<u8>>> {
-    match state.marketplace.read().await.download_strategy(&id).await {
-        Ok(content) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(content),
-                message: None,
-            };
-            Json(response)
-        },
-        Err(e) => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some(format!("Error downloading strategy: {}", e)),
-            };
-            Json(response)
-        }
-    }
+) -> Result<Json<ApiResponse<Vec<u8>>>, ApiError> {
+    let content = state.marketplace.read().await.download_strategy(&id).await
+        .map_err(|e| ApiError::from(e).context("Error downloading strategy"))?;
+    let response = ApiResponse {
+        success: true,
+        data: Some(content),
+        message: None,
+    };
+    Ok(Json(response))
 }
 
 /// Get reviews for a strategy
+#[utoipa::path(
+    get,
+    path = "/strategies/{id}/reviews",
+    tag = "reviews",
+    params(("id" = String, Path, description = "Strategy ID")),
+    responses(
+        (status = 200, description = "Visible reviews of the strategy", body = ApiResponse<Vec<StrategyReview>>),
+    )
+)]
 async fn get_reviews(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<ApiResponse<Vec<StrategyReview>>> {
-    match state.marketplace.read().await.get_reviews(&id).await {
-        Ok(reviews) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(reviews),
-                message: None,
-            };
-            Json(response)
-        },
-        Err(e) => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some(format!("Error retrieving reviews: {}", e)),
-            };
-            Json(response)
-        }
-    }
+) -> Result<Json<ApiResponse<Vec<StrategyReview>>>, ApiError> {
+    let reviews = state.marketplace.read().await.get_reviews(&id).await
+        .map_err(|e| ApiError::from(e).context("Error retrieving reviews"))?;
+    let response = ApiResponse {
+        success: true,
+        data: Some(reviews),
+        message: None,
+    };
+    Ok(Json(response))
 }
 
-/// Add a review
+/// Add a review, returning the strategy's updated rating
+#[utoipa::path(
+    post,
+    path = "/reviews",
+    tag = "reviews",
+    request_body = StrategyReview,
+    responses(
+        (status = 200, description = "Review added, with the updated rating of the strategy", body = ApiResponse<RatingSummary>),
+        (status = 404, description = "No published strategy with the ID", body = ErrorResponse),
+        (status = 409, description = "The review or the user's review of the strategy already exists", body = ErrorResponse),
+        (status = 422, description = "Rating is not between 1 and 5 stars", body = ErrorResponse),
+    )
+)]
 async fn add_review(
     Extension(state): Extension<Arc<AppState>>,
     Json(payload): Json<StrategyReview>,
-) -> Json<ApiResponse<bool>> {
-    match state.marketplace.read().await.add_review(payload).await {
-        Ok(_) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(true),
-                message: Some("Review added successfully".to_string()),
-            };
-            Json(response)
-        },
-        Err(e) => {
-            let response = ApiResponse {
-                success: false,
-                data: Some(false),
-                message: Some(format!("Error adding review: {}", e)),
-            };
-            Json(response)
-        }
-    }
+) -> Result<Json<ApiResponse<RatingSummary>>, ApiError> {
+    let summary = state.marketplace.read().await.add_review(payload).await
+        .map_err(|e| ApiError::from(e).context("Error adding review"))?;
+    let response = ApiResponse {
+        success: true,
+        data: Some(summary),
+        message: Some("Review added successfully".to_string()),
+    };
+    Ok(Json(response))
+}
+
+/// Reviews awaiting moderation
+#[utoipa::path(
+    get,
+    path = "/reviews/flagged",
+    tag = "moderation",
+    responses(
+        (status = 200, description = "Reviews awaiting moderation", body = ApiResponse<Vec<StrategyReview>>),
+    )
+)]
+async fn flagged_reviews(
+    Extension(state): Extension<Arc<AppState>>,
+) -> Result<Json<ApiResponse<Vec<StrategyReview>>>, ApiError> {
+    let reviews = state.marketplace.read().await.flagged_reviews().await
+        .map_err(|e| ApiError::from(e).context("Error retrieving flagged reviews"))?;
+    let response = ApiResponse {
+        success: true,
+        data: Some(reviews),
+        message: None,
+    };
+    Ok(Json(response))
+}
+
+/// Report a review for moderation
+#[utoipa::path(
+    post,
+    path = "/reviews/{id}/flag",
+    tag = "moderation",
+    params(("id" = String, Path, description = "Review ID")),
+    request_body = FlagReviewRequest,
+    responses(
+        (status = 200, description = "Review flagged", body = ApiResponse<StrategyReview>),
+        (status = 404, description = "Review not found", body = ErrorResponse),
+        (status = 409, description = "Review has been removed", body = ErrorResponse),
+    )
+)]
+async fn flag_review(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(payload): Json<FlagReviewRequest>,
+) -> Result<Json<ApiResponse<StrategyReview>>, ApiError> {
+    let flag = ReviewFlag {
+        user_id: payload.user_id,
+        reason: payload.reason,
+        created_at: chrono::Utc::now(),
+    };
+    moderation_response(state.marketplace.read().await.flag_review(&id, flag).await, "Review flagged for moderation")
+}
+
+/// Remove a review and recompute its strategy's rating
+#[utoipa::path(
+    post,
+    path = "/reviews/{id}/remove",
+    tag = "moderation",
+    params(("id" = String, Path, description = "Review ID")),
+    request_body = RemoveReviewRequest,
+    responses(
+        (status = 200, description = "Review removed", body = ApiResponse<StrategyReview>),
+        (status = 404, description = "Review not found", body = ErrorResponse),
+    )
+)]
+async fn remove_review(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(payload): Json<RemoveReviewRequest>,
+) -> Result<Json<ApiResponse<StrategyReview>>, ApiError> {
+    moderation_response(state.marketplace.read().await.remove_review(&id, payload.reason).await, "Review removed")
+}
+
+/// Restore a review, dismissing its reports
+#[utoipa::path(
+    post,
+    path = "/reviews/{id}/restore",
+    tag = "moderation",
+    params(("id" = String, Path, description = "Review ID")),
+    responses(
+        (status = 200, description = "Review restored", body = ApiResponse<StrategyReview>),
+        (status = 404, description = "Review not found", body = ErrorResponse),
+    )
+)]
+async fn restore_review(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<ApiResponse<StrategyReview>>, ApiError> {
+    moderation_response(state.marketplace.read().await.restore_review(&id).await, "Review restored")
+}
+
+fn moderation_response(
+    result: Result<StrategyReview, MarketError>,
+    message: &str,
+) -> Result<Json<ApiResponse<StrategyReview>>, ApiError> {
+    let review = result.map_err(|e| ApiError::from(e).context("Error moderating review"))?;
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(review),
+        message: Some(message.to_string()),
+    }))
 }
 
 /// Get marketplace statistics
+#[utoipa::path(
+    get,
+    path = "/stats",
+    tag = "strategies",
+    responses(
+        (status = 200, description = "Marketplace statistics", body = ApiResponse<MarketStats>),
+    )
+)]
 async fn get_stats(
     Extension(state): Extension<Arc<AppState>>,
-) -> Json<ApiResponse<MarketStats>> {
-    match state.marketplace.read().await.get_stats().await {
-        Ok(stats) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(stats),
-                message: None,
-            };
-            Json(response)
-        },
-        Err(e) => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some(format!("Error retrieving stats: {}", e)),
-            };
-            Json(response)
-        }
-    }
+) -> Result<Json<ApiResponse<MarketStats>>, ApiError> {
+    let stats = state.marketplace.read().await.get_stats().await
+        .map_err(|e| ApiError::from(e).context("Error retrieving stats"))?;
+    let response = ApiResponse {
+        success: true,
+        data: Some(stats),
+        message: None,
+    };
+    Ok(Json(response))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_openapi_document() {
+        let document = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        for path in ["/strategies", "/strategies/{id}/download", "/reviews/{id}/flag", "/stats"] {
+            assert!(document["paths"][path].is_object(), "{} is not documented", path);
+        }
+        assert!(document["components"]["schemas"]["StrategyPage"]["properties"]["tag_facets"].is_object());
+    }
+
     #[test]
     fn test_args_parsing() {
         let args = Args::parse_from(["svc-market", "--port", "8096"]);
-        assert_eq!(args.port, 8096);
+        assert_eq!(args.port, Some(8096));
+    }
+
+    #[test]
+    fn test_list_params_to_query() {
+        let params = ListStrategiesParams {
+            q: Some("momentum".to_string()),
+            tags: Some("sniper, mev,".to_string()),
+            sort: Some(StrategySort::Downloads),
+            limit: Some(10),
+            offset: Some(20),
+            ..ListStrategiesParams::default()
+        };
+        let query = StrategyQuery::from(params);
+        assert_eq!(query.text.as_deref(), Some("momentum"));
+        assert_eq!(query.tags, vec!["sniper".to_string(), "mev".to_string()]);
+        assert_eq!((query.sort, query.limit, query.offset), (Some(StrategySort::Downloads), Some(10), 20));
+        assert_eq!(StrategyQuery::from(ListStrategiesParams::default()), StrategyQuery::default());
     }
 
     #[tokio::test]
@@ -275,4 +537,36 @@ mod tests {
         
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_handler_errors_map_to_status() {
+        use axum::extract::Path;
+        use axum::http::StatusCode;
+
+        let state = Arc::new(AppState {
+            marketplace: RwLock::new(InMemoryMarketplace::new()),
+        });
+
+        let error = get_strategy(Extension(state.clone()), Path("missing".to_string())).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        let error = download_strategy(Extension(state.clone()), Path("missing".to_string())).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+
+        let review = StrategyReview {
+            id: "r1".to_string(),
+            strategy_id: "missing".to_string(),
+            user_id: "alice".to_string(),
+            rating: 5,
+            comment: None,
+            created_at: chrono::Utc::now(),
+            status: Default::default(),
+            flags: Vec::new(),
+            moderation_note: None,
+        };
+        let error = add_review(Extension(state.clone()), Json(review)).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        let error = restore_review(Extension(state), Path("r1".to_string())).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        assert_eq!(error.message, "Error moderating review: Review r1 not found");
+    }
+}
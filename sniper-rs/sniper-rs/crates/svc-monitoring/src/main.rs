@@ -1,41 +1,98 @@
 //! Monitoring service for the sniper-rs enterprise features.
 //! 
-//! This service provides REST APIs for advanced monitoring dashboards
-//! and automated incident response.
+//! This service provides REST APIs for advanced monitoring dashboards,
+//! automated incident response and incident analytics. Analytics summaries
+//! are recorded periodically as compliance reports. Alert rules are evaluated
+//! on a timer against the local metrics and, with `--prometheus-url`, those
+//! scraped from another service; their incidents resolve once they stop firing.
+//! The OpenAPI document is served on `/openapi.json`, with a Swagger UI on
+//! `/docs`.
 
 use anyhow::Result;
 use clap::Parser;
+use sniper_core::config::ConfigArgs;
+use sniper_core::errors::ApiError;
+use sniper_core::openapi::{self, BearerAuth, ErrorResponse};
+use sniper_core::rate_limit::{RateLimitConfig, RateLimiter};
+use sniper_core::shutdown::Shutdown;
 use serde::{Deserialize, Serialize};
 use axum::{
+    extract::Query,
+    handler::Handler,
     routing::{get, post},
     Json, Router, Extension,
 };
+use chrono::{DateTime, Duration, Utc};
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use sniper_compliance::{ComplianceManager, ComplianceReport, ReportType};
+use sniper_core::bus::InMemoryBus;
+use sniper_users::{require_permission, AuthLayer, JwtAuth};
 use sniper_monitoring::{
+    spawn_trading_event_listener,
     MonitoringSystem,
+    ScrapedMetrics,
     DashboardPanel,
     Incident,
+    IncidentAnalytics,
     IncidentSeverity,
     AlertRule,
 };
 
+/// Port used when neither the configuration nor --port sets one
+const DEFAULT_PORT: u16 = 8086;
+
 /// CLI arguments for the monitoring service
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    /// Port to listen on
-    #[clap(short, long, default_value = "8086")]
-    port: u16,
+    /// Port to listen on, overriding the configured port (default 8086)
+    #[clap(short, long)]
+    port: Option<u16>,
+
+    /// Tenant that incidents raised from trading events belong to
+    #[clap(long, default_value = "default")]
+    tenant_id: String,
+
+    /// How often alert rules are evaluated against the metrics registry (seconds)
+    #[clap(long, default_value = "15")]
+    alert_eval_interval_secs: u64,
+
+    /// Prometheus endpoint scraped before each evaluation, for metrics alert rules read from other services
+    #[clap(long)]
+    prometheus_url: Option<String>,
+
+    /// How often incident analytics are recorded as compliance reports (seconds)
+    #[clap(long, default_value = "3600")]
+    analytics_report_interval_secs: u64,
+
+    /// OTLP endpoint spans are exported to, defaulting to OTEL_EXPORTER_OTLP_ENDPOINT
+    #[clap(long)]
+    otlp_endpoint: Option<String>,
+
+    #[clap(flatten)]
+    config: ConfigArgs,
 }
 
 /// Monitoring service state
 struct AppState {
     monitoring_system: Arc<RwLock<MonitoringSystem>>,
+    compliance_manager: Arc<RwLock<ComplianceManager>>,
+    tenant_id: String,
+}
+
+/// Query parameters for incident analytics
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+struct AnalyticsParams {
+    /// Tenant to report on, defaulting to the service's tenant
+    pub tenant_id: Option<String>,
+    /// Only consider incidents raised in the last `window_hours`
+    pub window_hours: Option<i64>,
 }
 
 /// Dashboard creation request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct CreateDashboardRequest {
     pub name: String,
     pub description: String,
@@ -44,7 +101,7 @@ struct CreateDashboardRequest {
 }
 
 /// Incident creation request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct CreateIncidentRequest {
     pub title: String,
     pub description: String,
@@ -53,18 +110,17 @@ struct CreateIncidentRequest {
 }
 
 /// Alert rule creation request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct CreateAlertRuleRequest {
     pub name: String,
     pub description: String,
-    pub query: String,
-    pub threshold: f64,
+    pub expression: String,
     pub severity: String, // Will be parsed into IncidentSeverity
     pub tenant_id: String,
 }
 
 /// Standard response format
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
@@ -72,7 +128,7 @@ struct ApiResponse<T> {
 }
 
 /// Dashboard response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct DashboardResponse {
     pub id: String,
     pub name: String,
@@ -83,7 +139,7 @@ struct DashboardResponse {
 }
 
 /// Incident response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct IncidentResponse {
     pub id: String,
     pub title: String,
@@ -115,13 +171,12 @@ impl From<Incident> for IncidentResponse {
 }
 
 /// Alert rule response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct AlertRuleResponse {
     pub id: String,
     pub name: String,
     pub description: String,
-    pub query: String,
-    pub threshold: f64,
+    pub expression: String,
     pub severity: String,
     pub enabled: bool,
     pub created_at: String,
@@ -134,8 +189,7 @@ impl From<AlertRule> for AlertRuleResponse {
             id: rule.id,
             name: rule.name,
             description: rule.description,
-            query: rule.query,
-            threshold: rule.threshold,
+            expression: rule.expression.to_string(),
             severity: format!("{:?}", rule.severity),
             enabled: rule.enabled,
             created_at: rule.created_at.to_rfc3339(),
@@ -144,44 +198,122 @@ impl From<AlertRule> for AlertRuleResponse {
     }
 }
 
+/// OpenAPI document of the service, served on `/openapi.json`
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        health_check, get_metrics, create_dashboard, get_dashboard, list_tenant_dashboards, create_incident,
+        get_incident, list_tenant_incidents, create_alert_rule, get_analytics, list_analytics_reports,
+    ),
+    modifiers(&BearerAuth),
+    security(("bearer_auth" = [])),
+    tags(
+        (name = "dashboards", description = "Monitoring dashboards"),
+        (name = "incidents", description = "Incidents and the alert rules raising them"),
+        (name = "analytics", description = "Incident analytics"),
+    )
+)]
+struct ApiDoc;
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
     
     let args = Args::parse();
+    let config = args.config.loader("svc-monitoring")
+        .with_default("port", DEFAULT_PORT)
+        .with_override("port", args.port)
+        .load_service()?;
+    if args.config.print_config {
+        println!("{}", config.render()?);
+        return Ok(());
+    }
+    let _telemetry = sniper_core::telemetry::init_telemetry("svc-monitoring", args.otlp_endpoint.as_deref())?;
+    let shutdown = Shutdown::on_signal();
     
     // Create monitoring system
     let monitoring_system = MonitoringSystem::new()?;
     
+    // Throttle callers per tenant, separately for reads and trades
+    let rate_limiter = RateLimiter::new(config.section::<RateLimitConfig>("rate_limit")?.unwrap_or_default())
+        .with_registry(monitoring_system.metrics_registry().lock().unwrap().registry(), "svc-monitoring")?;
+    
+    let monitoring_system = Arc::new(RwLock::new(monitoring_system));
+    
+    // Update metrics and incidents from order and portfolio events on the bus
+    let bus = InMemoryBus::new(1024);
+    spawn_trading_event_listener(&bus, monitoring_system.clone(), args.tenant_id.clone());
+    
+    // Evaluate alert rules against the metrics registry
+    spawn_alert_evaluation(
+        &shutdown,
+        monitoring_system.clone(),
+        args.prometheus_url.clone(),
+        std::time::Duration::from_secs(args.alert_eval_interval_secs.max(1)),
+    );
+    
+    // Record incident analytics summaries for compliance
+    let compliance_manager = Arc::new(RwLock::new(ComplianceManager::new()));
+    spawn_analytics_reports(
+        &shutdown,
+        monitoring_system.clone(),
+        compliance_manager.clone(),
+        args.tenant_id.clone(),
+        std::time::Duration::from_secs(args.analytics_report_interval_secs.max(1)),
+    );
+    
     // Create app state
     let app_state = Arc::new(AppState {
-        monitoring_system: Arc::new(RwLock::new(monitoring_system)),
+        monitoring_system,
+        compliance_manager,
+        tenant_id: args.tenant_id.clone(),
     });
     
+    // Verify access tokens issued by svc-users
+    let auth = Arc::new(JwtAuth::from_config(&config.auth)?);
+    let reporting = require_permission("view_reports");
+    let operations = require_permission("configure_system");
+    
     // Create router
     let app = Router::new()
         .route("/health", get(health_check))
         .route("/metrics", get(get_metrics))
-        .route("/dashboards", post(create_dashboard))
+        .route("/dashboards", post(create_dashboard.layer(reporting)))
         .route("/dashboards/:id", get(get_dashboard))
         .route("/dashboards/tenant/:tenant_id", get(list_tenant_dashboards))
-        .route("/incidents", post(create_incident))
+        .route("/incidents", post(create_incident.layer(operations)))
         .route("/incidents/:id", get(get_incident))
         .route("/incidents/tenant/:tenant_id", get(list_tenant_incidents))
-        .route("/alerts", post(create_alert_rule))
-        .layer(Extension(app_state));
+        .route("/alerts", post(create_alert_rule.layer(operations)))
+        .route("/analytics", get(get_analytics))
+        .route("/analytics/reports", get(list_analytics_reports))
+        .layer(Extension(app_state))
+        .layer(rate_limiter.layer())
+        .layer(AuthLayer::new(auth))
+        .layer(axum::middleware::from_fn(sniper_core::telemetry::trace_http))
+        .merge(openapi::router(ApiDoc::openapi()));
     
     // Run server
-    let addr = format!("0.0.0.0:{}", args.port);
+    let addr = format!("0.0.0.0:{}", config.port.unwrap_or(DEFAULT_PORT));
     tracing::info!("Monitoring service listening on http://{}", addr);
     
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    sniper_core::shutdown::serve(listener, app, &shutdown).await?;
+    shutdown.join().await;
         
     Ok(())
 }
 
 /// Health check endpoint
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    security(()),
+    responses(
+        (status = 200, description = "Service is healthy", body = ApiResponse<String>),
+    )
+)]
 async fn health_check() -> Json<ApiResponse<String>> {
     let response = ApiResponse {
         success: true,
@@ -192,6 +324,15 @@ async fn health_check() -> Json<ApiResponse<String>> {
 }
 
 /// Get metrics in Prometheus format
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Metrics in Prometheus text format", body = String),
+        (status = 500, description = "Metrics could not be encoded", body = String),
+    )
+)]
 async fn get_metrics(
     Extension(state): Extension<Arc<AppState>>,
 ) -> Result<String, (axum::http::StatusCode, String)> {
@@ -203,6 +344,16 @@ async fn get_metrics(
 }
 
 /// Create a dashboard
+#[utoipa::path(
+    post,
+    path = "/dashboards",
+    tag = "dashboards",
+    request_body = CreateDashboardRequest,
+    responses(
+        (status = 200, description = "Dashboard created", body = ApiResponse<DashboardResponse>),
+        (status = 403, description = "Missing `view_reports`"),
+    )
+)]
 async fn create_dashboard(
     Extension(state): Extension<Arc<AppState>>,
     Json(payload): Json<CreateDashboardRequest>,
@@ -236,45 +387,53 @@ async fn create_dashboard(
 }
 
 /// Get a dashboard by ID
+#[utoipa::path(
+    get,
+    path = "/dashboards/{id}",
+    tag = "dashboards",
+    params(("id" = String, Path, description = "Dashboard ID")),
+    responses(
+        (status = 200, description = "The dashboard", body = ApiResponse<DashboardResponse>),
+        (status = 404, description = "Dashboard not found", body = ErrorResponse),
+    )
+)]
 async fn get_dashboard(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<ApiResponse<DashboardResponse>> {
-    let dashboard_opt = {
+) -> Result<Json<ApiResponse<DashboardResponse>>, ApiError> {
+    let dashboard = {
         let monitoring_system = state.monitoring_system.read().await;
         monitoring_system.dashboard_manager_ref().get_dashboard(&id).cloned()
+    }
+    .ok_or_else(|| ApiError::not_found("Dashboard not found"))?;
+    
+    let response = DashboardResponse {
+        id: dashboard.id,
+        name: dashboard.name,
+        description: dashboard.description,
+        created_at: dashboard.created_at.to_rfc3339(),
+        panels: dashboard.panels,
+        tenant_id: dashboard.tenant_id,
     };
     
-    match dashboard_opt {
-        Some(dashboard) => {
-            let response = DashboardResponse {
-                id: dashboard.id,
-                name: dashboard.name,
-                description: dashboard.description,
-                created_at: dashboard.created_at.to_rfc3339(),
-                panels: dashboard.panels,
-                tenant_id: dashboard.tenant_id,
-            };
-            
-            let api_response = ApiResponse {
-                success: true,
-                data: Some(response),
-                message: None,
-            };
-            Json(api_response)
-        },
-        None => {
-            let api_response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some("Dashboard not found".to_string()),
-            };
-            Json(api_response)
-        },
-    }
+    let api_response = ApiResponse {
+        success: true,
+        data: Some(response),
+        message: None,
+    };
+    Ok(Json(api_response))
 }
 
 /// List dashboards for a tenant
+#[utoipa::path(
+    get,
+    path = "/dashboards/tenant/{tenant_id}",
+    tag = "dashboards",
+    params(("tenant_id" = String, Path, description = "Tenant ID")),
+    responses(
+        (status = 200, description = "Dashboards of the tenant", body = ApiResponse<Vec<DashboardResponse>>),
+    )
+)]
 async fn list_tenant_dashboards(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(tenant_id): axum::extract::Path<String>,
@@ -303,6 +462,16 @@ async fn list_tenant_dashboards(
 }
 
 /// Create an incident
+#[utoipa::path(
+    post,
+    path = "/incidents",
+    tag = "incidents",
+    request_body = CreateIncidentRequest,
+    responses(
+        (status = 200, description = "Incident opened", body = ApiResponse<IncidentResponse>),
+        (status = 403, description = "Missing `configure_system`"),
+    )
+)]
 async fn create_incident(
     Extension(state): Extension<Arc<AppState>>,
     Json(payload): Json<CreateIncidentRequest>,
@@ -338,38 +507,44 @@ async fn create_incident(
 }
 
 /// Get an incident by ID
+#[utoipa::path(
+    get,
+    path = "/incidents/{id}",
+    tag = "incidents",
+    params(("id" = String, Path, description = "Incident ID")),
+    responses(
+        (status = 200, description = "The incident", body = ApiResponse<IncidentResponse>),
+        (status = 404, description = "Incident not found", body = ErrorResponse),
+    )
+)]
 async fn get_incident(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<ApiResponse<IncidentResponse>> {
-    let incident_opt = {
+) -> Result<Json<ApiResponse<IncidentResponse>>, ApiError> {
+    let incident = {
         let monitoring_system = state.monitoring_system.read().await;
         monitoring_system.incident_manager_ref().get_incident(&id).cloned()
-    };
-    
-    match incident_opt {
-        Some(incident) => {
-            let response = IncidentResponse::from(incident);
-            
-            let api_response = ApiResponse {
-                success: true,
-                data: Some(response),
-                message: None,
-            };
-            Json(api_response)
-        },
-        None => {
-            let api_response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some("Incident not found".to_string()),
-            };
-            Json(api_response)
-        },
     }
+    .ok_or_else(|| ApiError::not_found("Incident not found"))?;
+    
+    let api_response = ApiResponse {
+        success: true,
+        data: Some(IncidentResponse::from(incident)),
+        message: None,
+    };
+    Ok(Json(api_response))
 }
 
 /// List incidents for a tenant
+#[utoipa::path(
+    get,
+    path = "/incidents/tenant/{tenant_id}",
+    tag = "incidents",
+    params(("tenant_id" = String, Path, description = "Tenant ID")),
+    responses(
+        (status = 200, description = "Incidents of the tenant", body = ApiResponse<Vec<IncidentResponse>>),
+    )
+)]
 async fn list_tenant_incidents(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(tenant_id): axum::extract::Path<String>,
@@ -391,10 +566,21 @@ async fn list_tenant_incidents(
 }
 
 /// Create an alert rule
+#[utoipa::path(
+    post,
+    path = "/alerts",
+    tag = "incidents",
+    request_body = CreateAlertRuleRequest,
+    responses(
+        (status = 200, description = "Alert rule created", body = ApiResponse<AlertRuleResponse>),
+        (status = 403, description = "Missing `configure_system`"),
+        (status = 422, description = "Alert expression does not parse", body = ErrorResponse),
+    )
+)]
 async fn create_alert_rule(
     Extension(state): Extension<Arc<AppState>>,
     Json(payload): Json<CreateAlertRuleRequest>,
-) -> Json<ApiResponse<AlertRuleResponse>> {
+) -> Result<Json<ApiResponse<AlertRuleResponse>>, ApiError> {
     // Parse severity from string
     let severity = match payload.severity.as_str() {
         "Low" => IncidentSeverity::Low,
@@ -410,31 +596,187 @@ async fn create_alert_rule(
         incident_manager.create_alert_rule(
             &payload.name,
             &payload.description,
-            &payload.query,
-            payload.threshold,
+            &payload.expression,
             severity,
             &payload.tenant_id,
         )
+    }
+    .map_err(|e| ApiError::invalid(format!("Invalid alert expression: {}", e)))?;
+    
+    let api_response = ApiResponse {
+        success: true,
+        data: Some(AlertRuleResponse::from(rule)),
+        message: Some("Alert rule created successfully".to_string()),
     };
+    Ok(Json(api_response))
+}
+
+/// Periodically evaluate alert rules, opening incidents for those that fire and resolving those that stopped
+fn spawn_alert_evaluation(
+    shutdown: &Shutdown,
+    monitoring_system: Arc<RwLock<MonitoringSystem>>,
+    prometheus_url: Option<String>,
+    interval: std::time::Duration,
+) {
+    shutdown.spawn(async move {
+        let client = reqwest::Client::new();
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            // Without a fresh scrape, rules reading scraped metrics fail to evaluate and keep their incidents as they are
+            let scraped = match &prometheus_url {
+                Some(url) => ScrapedMetrics::fetch(&client, url).await.unwrap_or_else(|e| {
+                    tracing::warn!("{}", e);
+                    ScrapedMetrics::default()
+                }),
+                None => ScrapedMetrics::default(),
+            };
+            match monitoring_system.write().await.evaluate_alerts_with(&scraped) {
+                Ok(evaluation) => {
+                    for incident in evaluation.opened {
+                        tracing::warn!("Opened incident {}: {}", incident.id, incident.description);
+                    }
+                    for incident in evaluation.resolved {
+                        tracing::info!("Resolved incident {}: {}", incident.id, incident.title);
+                    }
+                }
+                Err(e) => tracing::error!("Failed to evaluate alert rules: {}", e),
+            }
+        }
+    })
+}
+
+/// Get incident analytics for a tenant
+#[utoipa::path(
+    get,
+    path = "/analytics",
+    tag = "analytics",
+    params(AnalyticsParams),
+    responses(
+        (status = 200, description = "MTTA, MTTR and noisy alert rules", body = ApiResponse<IncidentAnalytics>),
+    )
+)]
+async fn get_analytics(
+    Extension(state): Extension<Arc<AppState>>,
+    Query(params): Query<AnalyticsParams>,
+) -> Json<ApiResponse<IncidentAnalytics>> {
+    let tenant_id = params.tenant_id.unwrap_or_else(|| state.tenant_id.clone());
+    let since = params.window_hours.map(|hours| Utc::now() - Duration::hours(hours));
     
-    let response = AlertRuleResponse::from(rule);
+    let analytics = {
+        let monitoring_system = state.monitoring_system.read().await;
+        monitoring_system.incident_manager_ref().analytics(&tenant_id, since)
+    };
     
     let api_response = ApiResponse {
         success: true,
-        data: Some(response),
-        message: Some("Alert rule created successfully".to_string()),
+        data: Some(analytics),
+        message: None,
     };
     Json(api_response)
 }
 
+/// List the incident analytics reports recorded for compliance
+#[utoipa::path(
+    get,
+    path = "/analytics/reports",
+    tag = "analytics",
+    params(AnalyticsParams),
+    responses(
+        (status = 200, description = "Recorded analytics summaries", body = ApiResponse<Vec<ComplianceReport>>),
+    )
+)]
+async fn list_analytics_reports(
+    Extension(state): Extension<Arc<AppState>>,
+    Query(params): Query<AnalyticsParams>,
+) -> Json<ApiResponse<Vec<ComplianceReport>>> {
+    let tenant_id = params.tenant_id.unwrap_or_else(|| state.tenant_id.clone());
+    
+    let mut reports = {
+        let compliance_manager = state.compliance_manager.read().await;
+        compliance_manager.get_tenant_reports(&tenant_id)
+            .into_iter()
+            .filter(|report| report.report_type == ReportType::IncidentAnalytics)
+            .cloned()
+            .collect::<Vec<ComplianceReport>>()
+    };
+    reports.sort_by_key(|report| report.generated_at);
+    
+    let api_response = ApiResponse {
+        success: true,
+        data: Some(reports),
+        message: None,
+    };
+    Json(api_response)
+}
+
+/// Record the analytics for incidents raised since `since` as a compliance report
+async fn record_analytics_report(
+    monitoring_system: &RwLock<MonitoringSystem>,
+    compliance_manager: &RwLock<ComplianceManager>,
+    tenant_id: &str,
+    since: DateTime<Utc>,
+) -> ComplianceReport {
+    let analytics = {
+        let monitoring_system = monitoring_system.read().await;
+        monitoring_system.incident_manager_ref().analytics(tenant_id, Some(since))
+    };
+    
+    let mut compliance_manager = compliance_manager.write().await;
+    compliance_manager.record_report(
+        ReportType::IncidentAnalytics,
+        since,
+        analytics.period_end,
+        analytics.summary(),
+        "svc-monitoring",
+        tenant_id,
+    )
+}
+
+/// Periodically summarise incident analytics into compliance reports
+fn spawn_analytics_reports(
+    shutdown: &Shutdown,
+    monitoring_system: Arc<RwLock<MonitoringSystem>>,
+    compliance_manager: Arc<RwLock<ComplianceManager>>,
+    tenant_id: String,
+    interval: std::time::Duration,
+) {
+    shutdown.spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // The first tick completes immediately; there is nothing to report yet
+        ticker.tick().await;
+        let mut since = Utc::now();
+        loop {
+            ticker.tick().await;
+            let report = record_analytics_report(
+                &monitoring_system,
+                &compliance_manager,
+                &tenant_id,
+                since,
+            ).await;
+            tracing::info!("Recorded incident analytics report {} for tenant {}", report.id, tenant_id);
+            since = report.period_end;
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_openapi_document() {
+        let document = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        for path in ["/dashboards", "/incidents/{id}", "/alerts", "/analytics"] {
+            assert!(document["paths"][path].is_object(), "{} is not documented", path);
+        }
+        assert!(document["components"]["schemas"]["IncidentAnalytics"]["properties"]["noisy_rules"].is_object());
+    }
+
     #[test]
     fn test_args_parsing() {
         let args = Args::parse_from(["svc-monitoring", "--port", "8087"]);
-        assert_eq!(args.port, 8087);
+        assert_eq!(args.port, Some(8087));
     }
 
     #[tokio::test]
@@ -442,8 +784,59 @@ mod tests {
         let monitoring_system = MonitoringSystem::new()?;
         let _app_state = Arc::new(AppState {
             monitoring_system: Arc::new(RwLock::new(monitoring_system)),
+            compliance_manager: Arc::new(RwLock::new(ComplianceManager::new())),
+            tenant_id: "default".to_string(),
+        });
+        
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_record_analytics_report() -> Result<()> {
+        let monitoring_system = RwLock::new(MonitoringSystem::new()?);
+        let compliance_manager = RwLock::new(ComplianceManager::new());
+        let since = Utc::now() - Duration::hours(1);
+        
+        monitoring_system.write().await.incident_manager().create_incident(
+            "Order failures",
+            "Orders failing to submit",
+            IncidentSeverity::High,
+            "tenant-1",
+        );
+        
+        let report = record_analytics_report(&monitoring_system, &compliance_manager, "tenant-1", since).await;
+        assert_eq!(report.report_type, ReportType::IncidentAnalytics);
+        assert_eq!(report.period_start, since);
+        assert!(report.content.contains("Incidents: 1 (1 open)"));
+        assert_eq!(compliance_manager.read().await.get_tenant_reports("tenant-1").len(), 1);
+        
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_handler_errors_map_to_status() -> Result<()> {
+        let state = Arc::new(AppState {
+            monitoring_system: Arc::new(RwLock::new(MonitoringSystem::new()?)),
+            compliance_manager: Arc::new(RwLock::new(ComplianceManager::new())),
+            tenant_id: "default".to_string(),
         });
+        let missing = || axum::extract::Path("missing".to_string());
+        
+        let error = get_dashboard(Extension(state.clone()), missing()).await.unwrap_err();
+        assert_eq!(error.status(), axum::http::StatusCode::NOT_FOUND);
+        let error = get_incident(Extension(state.clone()), missing()).await.unwrap_err();
+        assert_eq!(error.status(), axum::http::StatusCode::NOT_FOUND);
+        
+        let request = CreateAlertRuleRequest {
+            name: "broken".to_string(),
+            description: "Does not parse".to_string(),
+            expression: "error_rate >".to_string(),
+            severity: "High".to_string(),
+            tenant_id: "tenant-1".to_string(),
+        };
+        let error = create_alert_rule(Extension(state), Json(request)).await.unwrap_err();
+        assert_eq!(error.status(), axum::http::StatusCode::UNPROCESSABLE_ENTITY);
         
         Ok(())
     }
-}
\ No newline at end of file
+}
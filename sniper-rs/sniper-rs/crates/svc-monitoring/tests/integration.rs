@@ -154,48 +154,53 @@ fn test_alert_rules_enterprise_features() {
     let cpu_alert = monitoring_system.incident_manager().create_alert_rule(
         "High CPU Usage Alert",
         "Trigger alert when CPU usage exceeds 90%",
-        "avg(cpu_usage_percent) > 90",
-        90.0,
+        "avg_over_time(cpu_usage_percent[5m]) > 90",
         IncidentSeverity::High,
         "monitoring-tenant-1",
-    );
+    ).expect("Failed to create CPU alert");
     
     let memory_alert = monitoring_system.incident_manager().create_alert_rule(
         "Low Memory Alert",
         "Trigger alert when available memory drops below 10%",
         "memory_available_percent < 10",
-        10.0,
         IncidentSeverity::Medium,
         "monitoring-tenant-1",
-    );
+    ).expect("Failed to create memory alert");
     
     let latency_alert = monitoring_system.incident_manager().create_alert_rule(
         "High Latency Alert",
+        "Trigger alert when request latency grows by more than 200s in 5 minutes",
+        "increase(request_duration_seconds_sum[5m]) > 200",
+        IncidentSeverity::Low,
+        "monitoring-tenant-1",
+    ).expect("Failed to create latency alert");
+    
+    // Expressions outside the grammar are rejected when the rule is created
+    let unsupported = monitoring_system.incident_manager().create_alert_rule(
+        "Latency Quantile Alert",
         "Trigger alert when 95th percentile latency exceeds 200ms",
         "histogram_quantile(0.95, request_latency_ms_bucket) > 200",
-        200.0,
         IncidentSeverity::Low,
         "monitoring-tenant-1",
     );
+    assert!(unsupported.is_err());
     
     // Verify alert rule properties
     assert_eq!(cpu_alert.name, "High CPU Usage Alert");
-    assert_eq!(cpu_alert.threshold, 90.0);
+    assert_eq!(cpu_alert.expression.source(), "avg_over_time(cpu_usage_percent[5m]) > 90");
     assert_eq!(cpu_alert.severity, IncidentSeverity::High);
     assert_eq!(cpu_alert.tenant_id, "monitoring-tenant-1");
     assert!(cpu_alert.enabled);
     
-    assert_eq!(memory_alert.threshold, 10.0);
+    assert_eq!(memory_alert.expression.source(), "memory_available_percent < 10");
     assert_eq!(memory_alert.severity, IncidentSeverity::Medium);
     
-    assert_eq!(latency_alert.threshold, 200.0);
+    assert_eq!(latency_alert.expression.source(), "increase(request_duration_seconds_sum[5m]) > 200");
     assert_eq!(latency_alert.severity, IncidentSeverity::Low);
     
-    // Test alert evaluation (mock implementation)
-    let _incidents = monitoring_system.incident_manager().evaluate_alerts()
+    // Rules over unregistered metrics are skipped rather than failing the evaluation
+    let _incidents = monitoring_system.evaluate_alerts()
         .expect("Failed to evaluate alerts");
-    // In a real implementation, this might create incidents based on metric values
-    // For now, we're just testing that it doesn't error
 }
 
 #[test]
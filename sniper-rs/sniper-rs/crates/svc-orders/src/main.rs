@@ -1,44 +1,147 @@
 //! Advanced order types service for the sniper bot.
 //! 
 //! This service provides a REST API for managing advanced order types including
-//! limit orders, stop-loss orders, take-profit orders, trailing stops, and more.
+//! limit orders, stop-loss orders, take-profit orders, trailing stops, and more,
+//! plus a WebSocket stream of order status changes filtered by tenant. Every
+//! call except the health check needs an access token; orders are placed
+//! under the caller's tenant, and other tenants' orders are neither listed
+//! nor reachable by ID unless the caller holds `view_all_data`. A retried
+//! POST carrying the same `Idempotency-Key` header gets the first attempt's
+//! response instead of placing the order twice. The OpenAPI document is
+//! served on `/openapi.json`, with a Swagger UI on `/docs`.
+//!
+//! Orders may be armed with a trigger, waiting until a signal on the signal bus
+//! (or posted to `/signals`) or a price of a watched symbol fires it. The
+//! plans of triggered orders are handed off to the executor on `plan.created`.
+//! Bracket
+//! orders (`/orders/bracket`) attach take-profit and stop-loss exits to an
+//! entry, placed as it fills and cancelled together with it.
+//!
+//! The same orders are served over gRPC (`sniper.v1.Orders`, port 9081 by
+//! default) for low-latency internal calls, authenticated with the same
+//! tokens and sharing the REST handlers' checks.
+//!
+//! On SIGTERM the service drains in-flight requests, stops releasing TWAP and
+//! VWAP slices and either parks them in `--park-file`, to be resumed on the
+//! next start, or cancels their parent orders.
 
 use anyhow::Result;
 use clap::Parser;
+use sniper_core::config::ConfigArgs;
 use serde::{Deserialize, Serialize};
-use sniper_orders::{OrderManager, AdvancedOrder, OrderType, TimeInForce, OrderStatus};
-use sniper_core::types::{ChainRef, TradePlan};
+use sniper_orders::{BracketManager, BracketOrder, ShardedOrderManager, SliceScheduler, SlicerConfig, AdvancedOrder, OrderType, TimeInForce, OrderStatus, OrderError, OrderTrigger, ParkedSlices};
+use sniper_orders::slicing::UniformVolumeProfile;
+use sniper_core::bus::InMemoryBus;
+use sniper_core::errors::{ApiError, ErrorKind};
+use sniper_core::kill_switch::{KillSwitch, KillSwitchStatus, TripReason};
+use sniper_core::openapi::{self, BearerAuth, ErrorResponse};
+use sniper_core::rate_limit::{RateLimitConfig, RateLimiter};
+use sniper_core::shutdown::Shutdown;
+use sniper_core::types::{ChainRef, Decimal, Signal, TradePlan};
+use sniper_schedule::{SessionConfig, SessionScheduler, DEFAULT_TENANT};
+use sniper_monitoring::http::{instrument, ServiceMetrics};
+use sniper_oracle::http::OracleFeeds;
+use sniper_prices::{PriceCache, PriceCacheConfig};
+use sniper_risk::divergence::{DivergenceGuard, DivergenceGuardConfig};
+use sniper_risk::{RiskEngine, RiskLimits};
+use sniper_rpc::convert::{self, InvalidMessage};
+use sniper_rpc::{proto, server as rpc};
+use sniper_users::http::Viewer;
+use sniper_users::{can_view_tenant, idempotent, redact, redact_all, require_permission, require_tenant, spawn_audit_listener, AuditStore, AuthLayer, IdempotencyConfig, IdempotencyStore, JwtAuth, Redact, UserContext};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, mpsc, RwLock};
 use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    extract::Query,
+    handler::Handler,
+    http::StatusCode,
+    response::{IntoResponse, Response},
     routing::{get, post, put, delete},
     Json, Router, Extension,
 };
+use utoipa::{IntoParams, OpenApi, ToSchema};
 use uuid::Uuid;
 
+/// Port used when neither the configuration nor --port sets one
+const DEFAULT_PORT: u16 = 8081;
+
+/// gRPC port used when neither the configuration nor --grpc-port sets one
+const DEFAULT_GRPC_PORT: u16 = 9081;
+
 /// CLI arguments for the orders service
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    /// Port to listen on
-    #[clap(short, long, default_value = "8081")]
-    port: u16,
+    /// Port to listen on, overriding the configured port (default 8081)
+    #[clap(short, long)]
+    port: Option<u16>,
+
+    /// Port to serve gRPC on, overriding the configured port (default 9081)
+    #[clap(long)]
+    grpc_port: Option<u16>,
+
+    /// Pre-trade risk limits as JSON, e.g. '{"max_order_notional": 10000}'
+    #[clap(long)]
+    risk_limits: Option<String>,
+
+    /// Live price feeds triggering orders as JSON, e.g. '{"symbols": ["ETH/USDT"], "rest": [...]}'
+    #[clap(long)]
+    price_feeds: Option<String>,
+
+    /// Shared audit log file to append order audit events to
+    #[clap(long)]
+    audit_log: Option<std::path::PathBuf>,
+
+    /// OTLP endpoint spans are exported to, defaulting to OTEL_EXPORTER_OTLP_ENDPOINT
+    #[clap(long)]
+    otlp_endpoint: Option<String>,
+
+    /// File pending TWAP/VWAP slices are parked in on shutdown and resumed from on start;
+    /// without it their parent orders are cancelled on shutdown
+    #[clap(long)]
+    park_file: Option<std::path::PathBuf>,
+
+    #[clap(flatten)]
+    config: ConfigArgs,
 }
 
+/// Interval at which due TWAP/VWAP slices are released
+const SLICE_TICK: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Interval at which expired Good-Till-Time orders are swept
+const EXPIRY_TICK: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Subject the executor takes trade plans from
+const PLAN_SUBJECT: &str = "plan.created";
+
+/// Capacity of the child trade plan channel
+const CHILD_PLAN_CHANNEL_CAPACITY: usize = 1024;
+
+/// Capacity of the order update channel
+const ORDER_UPDATE_CHANNEL_CAPACITY: usize = 1024;
+
 /// Order service state
 struct AppState {
-    order_manager: RwLock<OrderManager>,
+    order_manager: Arc<ShardedOrderManager>,
+    slice_scheduler: Arc<SliceScheduler>,
+    brackets: Arc<BracketManager>,
+    session_scheduler: RwLock<SessionScheduler>,
+    order_updates: broadcast::Sender<OrderUpdate>,
+    metrics: Arc<ServiceMetrics>,
+    oracle: Arc<OracleFeeds>,
+    kill_switch: KillSwitch,
+    bus: InMemoryBus,
 }
 
 /// Order creation request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct CreateOrderRequest {
     pub symbol: String,
     pub chain_id: u64,
     pub chain_name: String,
     pub order_type: String, // Will be parsed into OrderType
     pub side: String,
-    pub amount: f64,
+    pub amount: Decimal,
     pub price: Option<f64>, // For limit, stop-loss, take-profit orders
     pub stop_price: Option<f64>, // For stop-limit orders
     pub limit_price: Option<f64>, // For stop-limit orders
@@ -46,10 +149,55 @@ struct CreateOrderRequest {
     pub visible_amount: Option<f64>, // For iceberg orders
     pub total_amount: Option<f64>, // For iceberg, TWAP, VWAP orders
     pub duration_minutes: Option<u64>, // For TWAP orders
+    #[serde(default)]
+    pub time_in_force: Option<String>, // "gtc", "ioc", "fok" or "gtt"
+    #[serde(default)]
+    pub expiry_timestamp: Option<u64>, // For GTT orders
+    /// Tenant to place the order under, defaulting to the caller's
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+    /// Signal or price the order waits for before it is evaluated, e.g.
+    /// `{"type": "price_cross", "symbol": "BTC/USDT", "level": 60000, "direction": "above"}`
+    /// or `{"type": "signal", "kind": "liquidity_added", "token": "0x...", "chain_id": 1}`
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub trigger: Option<OrderTrigger>,
+}
+
+/// Fill (execution report) request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct RecordFillRequest {
+    pub quantity: Decimal,
+    pub price: Decimal,
+}
+
+/// Price update request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct PriceUpdateRequest {
+    pub symbol: String,
+    pub price: f64,
+}
+
+/// Query parameters for the order stream
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+struct OrderStreamParams {
+    /// Tenant to stream; the viewer's own tenant when absent
+    pub tenant_id: Option<String>,
+    /// Comma-separated symbols to stream; all symbols when absent
+    pub symbols: Option<String>,
+}
+
+/// Order status change streamed to clients
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct OrderUpdate {
+    pub event: String, // "created", "updated", "filled", "cancelled" or "expired"
+    pub tenant_id: String,
+    pub order: OrderResponse,
+    pub timestamp: u64,
 }
 
 /// Standard response format
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
@@ -57,7 +205,7 @@ struct ApiResponse<T> {
 }
 
 /// Order response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct OrderResponse {
     pub id: String,
     pub symbol: String,
@@ -65,13 +213,30 @@ struct OrderResponse {
     pub chain_name: String,
     pub order_type: String,
     pub side: String,
-    pub amount: f64,
+    pub amount: Decimal,
     pub price: Option<f64>,
+    pub time_in_force: String,
     pub status: String,
+    pub filled_amount: Decimal,
+    pub remaining_amount: Decimal,
+    pub avg_fill_price: Option<Decimal>,
+    pub owner_id: Option<String>,
+    pub tenant_id: Option<String>,
+    #[schema(value_type = Option<Object>)]
+    pub trigger: Option<OrderTrigger>,
+    /// Whether the order is still waiting for its trigger
+    pub armed: bool,
     pub created_at: u64,
     pub updated_at: u64,
 }
 
+impl Redact for OrderResponse {
+    fn visible_to(&self, viewer: &UserContext) -> bool {
+        // Orders without an owner predate ownership tracking and stay visible
+        self.owner_id.as_ref().map(|owner| *owner == viewer.user_id).unwrap_or(true)
+    }
+}
+
 impl From<&AdvancedOrder> for OrderResponse {
     fn from(order: &AdvancedOrder) -> Self {
         OrderResponse {
@@ -87,47 +252,301 @@ impl From<&AdvancedOrder> for OrderResponse {
                 OrderType::StopLoss { price } => Some(*price),
                 _ => None,
             },
+            time_in_force: format!("{:?}", order.time_in_force),
             status: format!("{:?}", order.status),
+            filled_amount: order.filled_amount(),
+            remaining_amount: order.remaining_amount(),
+            avg_fill_price: order.avg_fill_price(),
+            owner_id: order.owner_id.clone(),
+            tenant_id: order.tenant_id.clone(),
+            trigger: order.trigger.clone(),
+            armed: order.awaiting_trigger(),
             created_at: order.created_at,
             updated_at: order.updated_at,
         }
     }
 }
 
+/// Bracket order request: an entry with take-profit and stop-loss exits
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct CreateBracketRequest {
+    #[serde(flatten)]
+    pub entry: CreateOrderRequest,
+    /// Price the take-profit exit closes the position at
+    pub take_profit: f64,
+    /// Price the stop-loss exit fires at
+    pub stop_loss: f64,
+}
+
+/// Bracket with its entry and the exits placed so far
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct BracketResponse {
+    pub id: String,
+    pub status: String,
+    pub take_profit_price: f64,
+    pub stop_loss_price: f64,
+    pub entry: OrderResponse,
+    pub take_profit: Option<OrderResponse>,
+    pub stop_loss: Option<OrderResponse>,
+}
+
+/// Amended order with the version it replaced
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct AmendOrderResponse {
+    pub order: OrderResponse,
+    pub previous: OrderResponse,
+}
+
+/// Earlier version of an order, replaced by an amendment
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct OrderAmendmentResponse {
+    pub previous: OrderResponse,
+    /// User who amended the order
+    pub actor: String,
+    pub amended_at: u64,
+}
+
+/// OpenAPI document of the service, served on `/openapi.json`
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        health_check, get_orders, create_order, create_bracket, get_bracket, cancel_bracket, get_order, update_order, get_order_amendments, cancel_order, get_order_status,
+        get_trade_plan, record_fill, ingest_price_update, ingest_signal, order_stream, get_kill_switch, trip_kill_switch,
+        reset_kill_switch,
+    ),
+    components(schemas(OrderUpdate)),
+    modifiers(&BearerAuth),
+    security(("bearer_auth" = [])),
+    tags(
+        (name = "orders", description = "Advanced orders of the caller's tenant"),
+        (name = "admin", description = "Trading kill switch"),
+    )
+)]
+struct ApiDoc;
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
     
     let args = Args::parse();
+    let config = args.config.loader("svc-orders")
+        .with_default("port", DEFAULT_PORT)
+        .with_default("grpc_port", DEFAULT_GRPC_PORT)
+        .with_override("port", args.port)
+        .with_override("grpc_port", args.grpc_port)
+        .load_service()?;
+    if args.config.print_config {
+        println!("{}", config.render()?);
+        return Ok(());
+    }
+    let _telemetry = sniper_core::telemetry::init_telemetry("svc-orders", args.otlp_endpoint.as_deref())?;
+    let shutdown = Shutdown::on_signal();
     
-    // Create order manager
-    let order_manager = OrderManager::new();
+    // Create order manager, sharded by symbol, gating new orders on pre-trade risk
+    let risk_limits: RiskLimits = match &args.risk_limits {
+        Some(limits) => serde_json::from_str(limits)?,
+        None => RiskLimits::default(),
+    };
+    let risk = RiskEngine::new(risk_limits);
+    let kill_switch = KillSwitch::global().clone();
+    let bus = InMemoryBus::new(1024);
+    let order_manager = Arc::new(
+        ShardedOrderManager::new()
+            .with_bus(bus.clone())
+            .with_risk(risk.clone())
+            .with_kill_switch(kill_switch.clone()),
+    );
+    
+    // Append order audit events to the shared audit log
+    if let Some(path) = &args.audit_log {
+        spawn_audit_listener(&bus, AuditStore::append_only(path)?);
+    }
+    
+    // Activate armed orders as signals arrive
+    shutdown.spawn(trigger_from_signals(order_manager.clone(), bus.clone()));
+    
+    // Slice TWAP and VWAP orders into child orders in the background
+    let slice_scheduler = Arc::new(SliceScheduler::new(
+        SlicerConfig::default(),
+        Arc::new(UniformVolumeProfile),
+        order_manager.clone(),
+    ));
+    if let Some(path) = args.park_file.as_ref().filter(|path| path.exists()) {
+        let parked: ParkedSlices = serde_json::from_slice(&std::fs::read(path)?)?;
+        let resumed = slice_scheduler.unpark(parked).await;
+        std::fs::remove_file(path)?;
+        tracing::info!("resumed {} parked slices from {}", resumed, path.display());
+    }
+    let (child_plans, mut child_plan_rx) = mpsc::channel(CHILD_PLAN_CHANNEL_CAPACITY);
+    shutdown.spawn(slice_scheduler.clone().run(SLICE_TICK, child_plans));
+    
+    // Load trading-session calendars
+    let session_config = SessionConfig::load_default().unwrap_or_else(|e| {
+        tracing::warn!("Failed to load session config, orders accepted at all times: {}", e);
+        SessionConfig::default()
+    });
+    
+    // Create service metrics
+    let mut metrics = ServiceMetrics::new("svc-orders")?;
+    metrics.register_counter("orders_created_total", "Total orders created")?;
+    metrics.register_counter("orders_cancelled_total", "Total orders cancelled")?;
+    metrics.register_counter("order_fills_total", "Total order fills recorded")?;
+    metrics.register_counter("child_plans_emitted_total", "Total TWAP/VWAP child trade plans emitted")?;
+    metrics.register_counter("orders_expired_total", "Total Good-Till-Time orders expired")?;
+    metrics.register_counter("orders_risk_rejected_total", "Total orders rejected by pre-trade risk checks")?;
+    let metrics = Arc::new(metrics);
+    
+    // Broadcast order status changes to stream subscribers
+    let (order_updates, _) = broadcast::channel(ORDER_UPDATE_CHANNEL_CAPACITY);
+    
+    // Expire Good-Till-Time orders in the background
+    let expiry_orders = order_manager.clone();
+    let expiry_metrics = metrics.clone();
+    let expiry_updates = order_updates.clone();
+    shutdown.spawn(async move {
+        let mut interval = tokio::time::interval(EXPIRY_TICK);
+        loop {
+            interval.tick().await;
+            for order_id in expiry_orders.tick(chrono::Utc::now().timestamp() as u64).await {
+                expiry_metrics.increment_counter("orders_expired_total");
+                tracing::info!("order {} expired", order_id);
+                if let Some(order) = expiry_orders.get_order(&order_id).await {
+                    publish_order_update(&expiry_updates, "expired", &order);
+                }
+            }
+        }
+    });
+    
+    // Hand child trade plans off to the executor as slices come due
+    let child_metrics = metrics.clone();
+    let child_bus = bus.clone();
+    shutdown.spawn(async move {
+        while let Some(plan) = child_plan_rx.recv().await {
+            child_metrics.increment_counter("child_plans_emitted_total");
+            tracing::info!("emitting child trade plan {}", plan.idem_key);
+            hand_off(&child_bus, std::slice::from_ref(&plan));
+        }
+    });
+    
+    // Create price oracle used to evaluate order triggers
+    let oracle = Arc::new(OracleFeeds::default());
+    
+    // Check venue prices against the oracle, refusing orders in assets whose prices diverge
+    let divergence_guard = DivergenceGuard::new(
+        config.section::<DivergenceGuardConfig>("divergence_guard")?.unwrap_or_default(),
+        oracle.oracle.clone(),
+    )
+    .with_risk(risk);
+    
+    // Evaluate orders against the live price feeds in the background, if configured
+    if let Some(config) = &args.price_feeds {
+        let prices = Arc::new(PriceCache::from_config(serde_json::from_str::<PriceCacheConfig>(config)?)?);
+        shutdown.spawn(trigger_from_feeds(order_manager.clone(), prices, divergence_guard, bus.clone()));
+    }
+    
+    // Attach take-profit and stop-loss exits to bracket entries as they fill
+    let brackets = Arc::new(BracketManager::new(order_manager.clone()));
     
     // Create app state
     let app_state = Arc::new(AppState {
-        order_manager: RwLock::new(order_manager),
+        order_manager,
+        slice_scheduler: slice_scheduler.clone(),
+        brackets,
+        session_scheduler: RwLock::new(SessionScheduler::from_config(session_config)),
+        order_updates,
+        metrics: metrics.clone(),
+        oracle: oracle.clone(),
+        kill_switch,
+        bus,
     });
     
-    // Create router
+    // Verify access tokens issued by svc-users
+    let auth = Arc::new(JwtAuth::from_config(&config.auth)?);
+    let trading = require_permission("execute_trades");
+    let feeds = require_permission("configure_system");
+    
+    // Replay responses of retried POSTs carrying an Idempotency-Key
+    let idempotency = IdempotencyStore::new(config.section::<IdempotencyConfig>("idempotency")?.unwrap_or_default());
+    
+    // Throttle callers per tenant, separately for reads and trades
+    let rate_limiter = RateLimiter::new(config.section::<RateLimitConfig>("rate_limit")?.unwrap_or_default())
+        .with_registry(metrics.registry(), "svc-orders")?;
+    
+    // Serve the same orders over gRPC for internal callers
+    let grpc = tonic::transport::Server::builder().add_service(proto::orders_server::OrdersServer::with_interceptor(
+        OrdersRpc { state: app_state.clone() },
+        rpc::Authenticate::new(auth.clone()),
+    ));
+
+    // Create router; everything but the health check needs a tenant
     let app = Router::new()
-        .route("/health", get(health_check))
-        .route("/orders", get(get_orders).post(create_order))
-        .route("/orders/:id", get(get_order).put(update_order).delete(cancel_order))
+        .route("/orders", get(get_orders).post(create_order.layer(trading)))
+        .route("/orders/bracket", post(create_bracket.layer(trading)))
+        .route("/orders/bracket/:id", get(get_bracket).delete(cancel_bracket.layer(trading)))
+        .route("/orders/:id", get(get_order).put(update_order.layer(trading)).delete(cancel_order.layer(trading)))
         .route("/orders/:id/status", get(get_order_status))
+        .route("/orders/:id/amendments", get(get_order_amendments))
         .route("/orders/:id/plan", get(get_trade_plan))
-        .layer(Extension(app_state));
+        .route("/orders/:id/fills", post(record_fill.layer(trading)))
+        .route("/prices", post(ingest_price_update.layer(feeds)))
+        .route("/signals", post(ingest_signal.layer(feeds)))
+        .route("/ws", get(order_stream))
+        .route("/admin/kill-switch", get(get_kill_switch))
+        .route("/admin/kill-switch/trip", post(trip_kill_switch))
+        .route("/admin/kill-switch/reset", post(reset_kill_switch))
+        .merge(sniper_oracle::http::feed_routes(oracle.clone()).route_layer(feeds))
+        .merge(sniper_oracle::http::query_routes(oracle))
+        .route_layer(require_tenant())
+        .route("/health", get(health_check))
+        .layer(Extension(app_state))
+        .layer(axum::middleware::from_fn_with_state(idempotency, idempotent))
+        .layer(rate_limiter.layer())
+        .layer(AuthLayer::new(auth))
+        .layer(axum::middleware::from_fn(sniper_core::telemetry::trace_http));
+    let app = instrument(app, metrics)
+        .merge(openapi::router(ApiDoc::openapi().merge_from(sniper_oracle::http::OracleApi::openapi())));
     
     // Run server
-    let addr = format!("0.0.0.0:{}", args.port);
+    let addr = format!("0.0.0.0:{}", config.port.unwrap_or(DEFAULT_PORT));
     tracing::info!("Orders service listening on http://{}", addr);
     
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
-        
+    let grpc_addr = format!("0.0.0.0:{}", config.grpc_port.unwrap_or(DEFAULT_GRPC_PORT));
+    tracing::info!("Orders gRPC service listening on {}", grpc_addr);
+    
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let grpc_listener = tokio::net::TcpListener::bind(grpc_addr).await?;
+    tokio::try_join!(
+        async { Ok::<_, anyhow::Error>(sniper_core::shutdown::serve(listener, app, &shutdown).await?) },
+        rpc::serve(grpc_listener, grpc, &shutdown),
+    )?;
+    
+    // Nothing releases slices any more; park or cancel what is still open
+    shutdown.join().await;
+    match &args.park_file {
+        Some(path) => {
+            let parked = slice_scheduler.park().await;
+            std::fs::write(path, serde_json::to_vec(&parked)?)?;
+            tracing::info!("parked {} slices of {} orders in {}", parked.slices.len(), parked.parents.len(), path.display());
+        },
+        None => {
+            let cancelled = slice_scheduler.cancel_all().await;
+            tracing::info!("cancelled {} sliced orders on shutdown", cancelled.len());
+        },
+    }
+    
     Ok(())
 }
 
 /// Health check endpoint
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    security(()),
+    responses(
+        (status = 200, description = "Service is healthy", body = ApiResponse<String>),
+    )
+)]
 async fn health_check() -> Json<ApiResponse<String>> {
     let response = ApiResponse {
         success: true,
@@ -137,68 +556,76 @@ async fn health_check() -> Json<ApiResponse<String>> {
     Json(response)
 }
 
-/// Get all orders
+/// Tenant an order belongs to; orders predating tenant tracking belong to the default tenant
+fn order_tenant(order: &AdvancedOrder) -> &str {
+    order.tenant_id.as_deref().unwrap_or(DEFAULT_TENANT)
+}
+
+/// Look up an order in a tenant the viewer may access
+async fn tenant_order(state: &AppState, id: &str, viewer: &UserContext) -> Option<AdvancedOrder> {
+    state.order_manager.get_order(id).await
+        .filter(|order| can_view_tenant(viewer, order_tenant(order)))
+}
+
+/// Get all orders visible to the viewer
+#[utoipa::path(
+    get,
+    path = "/orders",
+    tag = "orders",
+    responses(
+        (status = 200, description = "Orders of the viewer's tenants", body = ApiResponse<Vec<OrderResponse>>),
+    )
+)]
 async fn get_orders(
     Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
 ) -> Json<ApiResponse<Vec<OrderResponse>>> {
-    let orders = {
-        let manager = state.order_manager.read().await;
-        manager.list_orders()
-            .iter()
-            .map(|&order| OrderResponse::from(order))
-            .collect::<Vec<OrderResponse>>()
-    };
+    let orders = state.order_manager.list_orders().await
+        .iter()
+        .filter(|order| can_view_tenant(&viewer, order_tenant(order)))
+        .map(OrderResponse::from)
+        .collect::<Vec<OrderResponse>>();
     
     let response = ApiResponse {
         success: true,
-        data: Some(orders),
+        data: Some(redact_all(orders, &viewer)),
         message: None,
     };
     Json(response)
 }
 
 /// Get a specific order
+#[utoipa::path(
+    get,
+    path = "/orders/{id}",
+    tag = "orders",
+    params(("id" = String, Path, description = "Order ID")),
+    responses(
+        (status = 200, description = "The order", body = ApiResponse<OrderResponse>),
+        (status = 404, description = "No such order in the viewer's tenants", body = ErrorResponse),
+    )
+)]
 async fn get_order(
     Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<ApiResponse<OrderResponse>> {
-    let order_result = {
-        let manager = state.order_manager.read().await;
-        manager.get_order(&id).cloned()
-    };
+) -> Result<Json<ApiResponse<OrderResponse>>, ApiError> {
+    // Other users' orders are reported as missing rather than forbidden
+    let order = tenant_order(&state, &id, &viewer).await
+        .and_then(|order| redact(OrderResponse::from(&order), &viewer))
+        .ok_or_else(|| ApiError::not_found("Order not found"))?;
     
-    match order_result {
-        Some(order) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(OrderResponse::from(&order)),
-                message: None,
-            };
-            Json(response)
-        },
-        None => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some("Order not found".to_string()),
-            };
-            Json(response)
-        },
-    }
+    let response = ApiResponse {
+        success: true,
+        data: Some(order),
+        message: None,
+    };
+    Ok(Json(response))
 }
 
-/// Create a new order
-async fn create_order(
-    Extension(state): Extension<Arc<AppState>>,
-    Json(payload): Json<CreateOrderRequest>,
-) -> Json<ApiResponse<OrderResponse>> {
-    let chain_ref = ChainRef {
-        name: payload.chain_name,
-        id: payload.chain_id,
-    };
-    
-    // Parse order type from string
-    let order_type = match payload.order_type.as_str() {
+/// Order type a create or update request asks for
+fn requested_order_type(payload: &CreateOrderRequest) -> OrderType {
+    match payload.order_type.as_str() {
         "market" => OrderType::Market,
         "limit" => OrderType::Limit { price: payload.price.unwrap_or(0.0) },
         "stop_loss" => OrderType::StopLoss { price: payload.price.unwrap_or(0.0) },
@@ -218,11 +645,62 @@ async fn create_order(
         },
         "vwap" => OrderType::VWAP { total_amount: payload.total_amount.unwrap_or(0.0) },
         _ => OrderType::Market, // Default to market order
-    };
+    }
+}
+
+/// Time in force a create or update request asks for
+fn requested_time_in_force(payload: &CreateOrderRequest) -> Result<TimeInForce, ApiError> {
+    match payload.time_in_force.as_deref() {
+        Some("ioc") => Ok(TimeInForce::ImmediateOrCancel),
+        Some("fok") => Ok(TimeInForce::FillOrKill),
+        Some("gtt") => match payload.expiry_timestamp {
+            Some(expiry_timestamp) => Ok(TimeInForce::GoodTillTime { expiry_timestamp }),
+            None => Err(ApiError::invalid("Good-Till-Time orders require an expiry_timestamp")),
+        },
+        _ => Ok(TimeInForce::GoodTillCancelled), // Default to Good Till Cancelled
+    }
+}
+
+/// Create a new order
+#[utoipa::path(
+    post,
+    path = "/orders",
+    tag = "orders",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Replays the first response of a retried request")),
+    request_body = CreateOrderRequest,
+    responses(
+        (status = 200, description = "Order created", body = ApiResponse<OrderResponse>),
+        (status = 403, description = "Tenant not accessible or missing `execute_trades`", body = ErrorResponse),
+        (status = 409, description = "Trading session closed or order ID taken", body = ErrorResponse),
+        (status = 422, description = "Invalid order or rejected by pre-trade risk checks", body = ErrorResponse),
+        (status = 503, description = "Kill switch tripped", body = ErrorResponse),
+    )
+)]
+async fn create_order(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    Json(payload): Json<CreateOrderRequest>,
+) -> Result<Json<ApiResponse<OrderResponse>>, ApiError> {
+    let order = place_order(&state, &viewer, new_order(payload)?).await?;
     
-    let time_in_force = TimeInForce::GoodTillCancelled; // Default to Good Till Cancelled
+    let response = ApiResponse {
+        success: true,
+        data: Some(OrderResponse::from(&order)),
+        message: Some("Order created successfully".to_string()),
+    };
+    Ok(Json(response))
+}
+
+/// Build a new order from a creation request
+fn new_order(payload: CreateOrderRequest) -> Result<AdvancedOrder, ApiError> {
+    let order_type = requested_order_type(&payload);
+    let time_in_force = requested_time_in_force(&payload)?;
+    let chain_ref = ChainRef {
+        name: payload.chain_name,
+        id: payload.chain_id,
+    };
     
-    let order = AdvancedOrder {
+    Ok(AdvancedOrder {
         id: Uuid::new_v4().to_string(),
         symbol: payload.symbol,
         chain: chain_ref,
@@ -230,206 +708,871 @@ async fn create_order(
         side: payload.side,
         amount: payload.amount,
         time_in_force,
-        created_at: std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs(),
-        updated_at: std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs(),
+        created_at: 0,
+        updated_at: 0,
         status: OrderStatus::Pending,
-    };
+        fills: Vec::new(),
+        owner_id: None,
+        tenant_id: payload.tenant_id,
+        trigger: payload.trigger,
+    })
+}
+
+/// Place a new order for the viewer, over REST or gRPC
+///
+/// The order goes to the viewer's tenant unless it names another the viewer
+/// may act for. Its owner and timestamps are set here.
+async fn place_order(state: &AppState, viewer: &UserContext, order: AdvancedOrder) -> Result<AdvancedOrder, ApiError> {
+    let order = prepare_order(state, viewer, order).await?;
+    if let Err(e) = state.order_manager.create_order(order.clone()).await {
+        return Err(rejected(state, e).context("Failed to create order"));
+    }
     
-    let result = state.order_manager.write().await.create_order(order.clone());
-    match result {
-        Ok(_) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(OrderResponse::from(&order)),
-                message: Some("Order created successfully".to_string()),
-            };
-            Json(response)
-        },
-        Err(e) => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some(format!("Failed to create order: {}", e)),
-            };
-            Json(response)
-        },
+    state.metrics.increment_counter("orders_created_total");
+    publish_order_update(&state.order_updates, "created", &order);
+    if matches!(order.order_type, OrderType::TWAP { .. } | OrderType::VWAP { .. }) {
+        if let Err(e) = state.slice_scheduler.schedule(&order.id, chrono::Utc::now()).await {
+            tracing::warn!("failed to slice order {}: {}", order.id, e);
+        }
     }
+    Ok(order)
 }
 
-/// Update an existing order
+/// Stamp a new order with its tenant, owner and timestamps
+///
+/// Fails if the viewer may not trade for the tenant or its trading session is closed.
+async fn prepare_order(state: &AppState, viewer: &UserContext, mut order: AdvancedOrder) -> Result<AdvancedOrder, ApiError> {
+    // Orders go to the caller's tenant unless they may act for others
+    let tenant_id = order.tenant_id.take().unwrap_or_else(|| viewer.tenant_id.clone());
+    if !can_view_tenant(viewer, &tenant_id) {
+        return Err(ApiError::new(
+            ErrorKind::Forbidden,
+            format!("Cannot place orders for tenant {}", tenant_id),
+        ));
+    }
+    
+    // Reject orders outside the tenant's trading session
+    if !state.session_scheduler.read().await.accepts_orders(&tenant_id, chrono::Utc::now()) {
+        return Err(ApiError::new(ErrorKind::Conflict, "Trading session is closed"));
+    }
+    
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    order.created_at = now;
+    order.updated_at = now;
+    order.owner_id = (!viewer.is_anonymous()).then(|| viewer.user_id.clone());
+    order.tenant_id = Some(tenant_id);
+    Ok(order)
+}
+
+/// Count an order the manager refused, if the risk checks rejected it
+fn rejected(state: &AppState, e: OrderError) -> ApiError {
+    if matches!(e, OrderError::Risk(_)) {
+        state.metrics.increment_counter("orders_risk_rejected_total");
+    }
+    ApiError::from(e)
+}
+
+/// Amend an open order in place, keeping its ID, fills and owner
+///
+/// The symbol and chain cannot change, and the time in force is kept when the
+/// request leaves it out. The response carries the version the amendment replaced.
+#[utoipa::path(
+    put,
+    path = "/orders/{id}",
+    tag = "orders",
+    params(("id" = String, Path, description = "Order ID")),
+    request_body = CreateOrderRequest,
+    responses(
+        (status = 200, description = "Order amended", body = ApiResponse<AmendOrderResponse>),
+        (status = 404, description = "No such order in the viewer's tenants", body = ErrorResponse),
+        (status = 409, description = "Order no longer open", body = ErrorResponse),
+        (status = 422, description = "Invalid amendment or rejected by pre-trade risk checks", body = ErrorResponse),
+        (status = 503, description = "Kill switch tripped", body = ErrorResponse),
+    )
+)]
 async fn update_order(
     Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
     axum::extract::Path(id): axum::extract::Path<String>,
     Json(payload): Json<CreateOrderRequest>,
-) -> Json<ApiResponse<OrderResponse>> {
-    let order_result = {
-        let manager = state.order_manager.read().await;
-        manager.get_order(&id).cloned()
+) -> Result<Json<ApiResponse<AmendOrderResponse>>, ApiError> {
+    let existing = tenant_order(&state, &id, &viewer).await
+        .ok_or_else(|| ApiError::not_found("Order not found"))?;
+    let time_in_force = match payload.time_in_force {
+        Some(_) => requested_time_in_force(&payload)?,
+        None => existing.time_in_force.clone(),
     };
-    
-    match order_result {
-        Some(mut existing_order) => {
-            let chain_ref = ChainRef {
-                name: payload.chain_name,
-                id: payload.chain_id,
-            };
-            
-            // Parse order type from string
-            let order_type = match payload.order_type.as_str() {
-                "market" => OrderType::Market,
-                "limit" => OrderType::Limit { price: payload.price.unwrap_or(0.0) },
-                "stop_loss" => OrderType::StopLoss { price: payload.price.unwrap_or(0.0) },
-                "take_profit" => OrderType::TakeProfit { price: payload.price.unwrap_or(0.0) },
-                "stop_limit" => OrderType::StopLimit { 
-                    stop_price: payload.stop_price.unwrap_or(0.0), 
-                    limit_price: payload.limit_price.unwrap_or(0.0) 
-                },
-                "trailing_stop" => OrderType::TrailingStop { trail_percent: payload.trail_percent.unwrap_or(1.0) },
-                "iceberg" => OrderType::Iceberg { 
-                    visible_amount: payload.visible_amount.unwrap_or(0.0), 
-                    total_amount: payload.total_amount.unwrap_or(0.0) 
-                },
-                "twap" => OrderType::TWAP { 
-                    total_amount: payload.total_amount.unwrap_or(0.0), 
-                    duration_minutes: payload.duration_minutes.unwrap_or(60) 
-                },
-                "vwap" => OrderType::VWAP { total_amount: payload.total_amount.unwrap_or(0.0) },
-                _ => OrderType::Market, // Default to market order
-            };
-            
-            existing_order.symbol = payload.symbol;
-            existing_order.chain = chain_ref;
-            existing_order.order_type = order_type;
-            existing_order.side = payload.side;
-            existing_order.amount = payload.amount;
-            existing_order.updated_at = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
-            
-            let result = state.order_manager.write().await.create_order(existing_order.clone());
-            match result {
-                Ok(_) => {
-                    let response = ApiResponse {
-                        success: true,
-                        data: Some(OrderResponse::from(&existing_order)),
-                        message: Some("Order updated successfully".to_string()),
-                    };
-                    Json(response)
-                },
-                Err(e) => {
-                    let response = ApiResponse {
-                        success: false,
-                        data: None,
-                        message: Some(format!("Failed to update order: {}", e)),
-                    };
-                    Json(response)
-                },
-            }
-        },
-        None => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some("Order not found".to_string()),
-            };
-            Json(response)
+    let amended = AdvancedOrder {
+        symbol: payload.symbol.clone(),
+        chain: ChainRef {
+            name: payload.chain_name.clone(),
+            id: payload.chain_id,
         },
-    }
+        order_type: requested_order_type(&payload),
+        side: payload.side.clone(),
+        amount: payload.amount,
+        time_in_force,
+        ..existing
+    };
+    
+    let previous = state.order_manager.amend_order(amended, &viewer.user_id).await
+        .map_err(|e| ApiError::from(e).context("Failed to amend order"))?;
+    let order = state.order_manager.get_order(&id).await
+        .ok_or_else(|| ApiError::not_found("Order not found"))?;
+    publish_order_update(&state.order_updates, "updated", &order);
+    let response = ApiResponse {
+        success: true,
+        data: Some(AmendOrderResponse {
+            order: OrderResponse::from(&order),
+            previous: OrderResponse::from(&previous),
+        }),
+        message: Some("Order amended successfully".to_string()),
+    };
+    Ok(Json(response))
+}
+
+/// Get the earlier versions of an order, oldest first
+#[utoipa::path(
+    get,
+    path = "/orders/{id}/amendments",
+    tag = "orders",
+    params(("id" = String, Path, description = "Order ID")),
+    responses(
+        (status = 200, description = "Versions replaced by amendments", body = ApiResponse<Vec<OrderAmendmentResponse>>),
+        (status = 404, description = "No such order in the viewer's tenants", body = ErrorResponse),
+    )
+)]
+async fn get_order_amendments(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<ApiResponse<Vec<OrderAmendmentResponse>>>, ApiError> {
+    tenant_order(&state, &id, &viewer).await
+        .and_then(|order| redact(OrderResponse::from(&order), &viewer))
+        .ok_or_else(|| ApiError::not_found("Order not found"))?;
+    
+    let amendments = state.order_manager.amendments(&id).await
+        .iter()
+        .map(|amendment| OrderAmendmentResponse {
+            previous: OrderResponse::from(&amendment.previous),
+            actor: amendment.actor.clone(),
+            amended_at: amendment.amended_at,
+        })
+        .collect();
+    let response = ApiResponse {
+        success: true,
+        data: Some(amendments),
+        message: None,
+    };
+    Ok(Json(response))
 }
 
 /// Cancel an order
+#[utoipa::path(
+    delete,
+    path = "/orders/{id}",
+    tag = "orders",
+    params(("id" = String, Path, description = "Order ID")),
+    responses(
+        (status = 200, description = "Order cancelled", body = ApiResponse<bool>),
+        (status = 404, description = "No such order in the viewer's tenants", body = ErrorResponse),
+    )
+)]
 async fn cancel_order(
     Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<ApiResponse<bool>> {
-    let result = state.order_manager.write().await.cancel_order(&id);
-    match result {
-        Ok(_) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(true),
-                message: Some("Order cancelled successfully".to_string()),
-            };
-            Json(response)
-        },
-        Err(e) => {
-            let response = ApiResponse {
-                success: false,
-                data: Some(false),
-                message: Some(format!("Failed to cancel order: {}", e)),
-            };
-            Json(response)
-        },
+) -> Result<Json<ApiResponse<bool>>, ApiError> {
+    cancel(&state, &viewer, &id).await?;
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(true),
+        message: Some("Order cancelled successfully".to_string()),
+    };
+    Ok(Json(response))
+}
+
+/// Cancel one of the viewer's orders, along with its pending slices
+async fn cancel(state: &AppState, viewer: &UserContext, id: &str) -> Result<Option<AdvancedOrder>, ApiError> {
+    let result = match tenant_order(state, id, viewer).await {
+        Some(_) => state.order_manager.cancel_order_by(id, &viewer.user_id).await,
+        None => Err(OrderError::NotFound(id.to_string())),
+    };
+    result.map_err(|e| ApiError::from(e).context("Failed to cancel order"))?;
+    
+    state.slice_scheduler.cancel(id).await;
+    state.metrics.increment_counter("orders_cancelled_total");
+    let order = state.order_manager.get_order(id).await;
+    if let Some(order) = &order {
+        publish_order_update(&state.order_updates, "cancelled", order);
     }
+    Ok(order)
 }
 
-/// Get order status
-async fn get_order_status(
+/// Place an entry order with take-profit and stop-loss exits
+///
+/// The exits are placed once the entry fills, sized to the filled amount, and
+/// cancel each other out: once one has closed the position, the other is cancelled.
+#[utoipa::path(
+    post,
+    path = "/orders/bracket",
+    tag = "orders",
+    request_body = CreateBracketRequest,
+    responses(
+        (status = 200, description = "Bracket placed", body = ApiResponse<BracketResponse>),
+        (status = 403, description = "Tenant not accessible", body = ErrorResponse),
+        (status = 409, description = "Trading session closed", body = ErrorResponse),
+        (status = 422, description = "Exit prices not on either side of the entry, or rejected by pre-trade risk checks", body = ErrorResponse),
+        (status = 503, description = "Kill switch tripped", body = ErrorResponse),
+    )
+)]
+async fn create_bracket(
     Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    Json(payload): Json<CreateBracketRequest>,
+) -> Result<Json<ApiResponse<BracketResponse>>, ApiError> {
+    let entry = prepare_order(&state, &viewer, new_order(payload.entry)?).await?;
+    let bracket = state.brackets.place(entry.clone(), payload.take_profit, payload.stop_loss).await
+        .map_err(|e| rejected(&state, e).context("Failed to place bracket"))?;
+    
+    state.metrics.increment_counter("orders_created_total");
+    publish_order_update(&state.order_updates, "created", &entry);
+    let response = ApiResponse {
+        success: true,
+        data: Some(bracket_response(&state, &bracket).await?),
+        message: Some("Bracket placed successfully".to_string()),
+    };
+    Ok(Json(response))
+}
+
+/// Get a bracket by the ID of its entry order
+#[utoipa::path(
+    get,
+    path = "/orders/bracket/{id}",
+    tag = "orders",
+    params(("id" = String, Path, description = "Bracket ID, the entry order's")),
+    responses(
+        (status = 200, description = "Bracket found", body = ApiResponse<BracketResponse>),
+        (status = 404, description = "No such bracket in the viewer's tenants", body = ErrorResponse),
+    )
+)]
+async fn get_bracket(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<ApiResponse<String>> {
-    let status_result = {
-        let manager = state.order_manager.read().await;
-        manager.get_order(&id).map(|order| order.status.clone())
+) -> Result<Json<ApiResponse<BracketResponse>>, ApiError> {
+    let bracket = visible_bracket(&state, &viewer, &id).await?;
+    let response = ApiResponse {
+        success: true,
+        data: Some(bracket_response(&state, &bracket).await?),
+        message: None,
     };
+    Ok(Json(response))
+}
+
+/// Cancel a bracket's entry and exits together
+#[utoipa::path(
+    delete,
+    path = "/orders/bracket/{id}",
+    tag = "orders",
+    params(("id" = String, Path, description = "Bracket ID, the entry order's")),
+    responses(
+        (status = 200, description = "Bracket cancelled", body = ApiResponse<BracketResponse>),
+        (status = 404, description = "No such bracket in the viewer's tenants", body = ErrorResponse),
+        (status = 409, description = "Bracket already closed or cancelled", body = ErrorResponse),
+    )
+)]
+async fn cancel_bracket(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<ApiResponse<BracketResponse>>, ApiError> {
+    visible_bracket(&state, &viewer, &id).await?;
+    let bracket = state.brackets.cancel(&id, &viewer.user_id).await
+        .map_err(|e| ApiError::from(e).context("Failed to cancel bracket"))?;
     
-    match status_result {
-        Some(status) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(format!("{:?}", status)),
-                message: None,
-            };
-            Json(response)
-        },
-        None => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some("Order not found".to_string()),
-            };
-            Json(response)
-        },
+    for order_id in bracket.order_ids() {
+        if let Some(order) = state.order_manager.get_order(&order_id).await {
+            if order.status == OrderStatus::Cancelled {
+                state.metrics.increment_counter("orders_cancelled_total");
+                publish_order_update(&state.order_updates, "cancelled", &order);
+            }
+        }
+    }
+    let response = ApiResponse {
+        success: true,
+        data: Some(bracket_response(&state, &bracket).await?),
+        message: Some("Bracket cancelled successfully".to_string()),
+    };
+    Ok(Json(response))
+}
+
+/// Get a bracket whose entry the viewer can see
+async fn visible_bracket(state: &AppState, viewer: &UserContext, id: &str) -> Result<BracketOrder, ApiError> {
+    let entry_visible = tenant_order(state, id, viewer).await
+        .map(|entry| order_visible(&entry, viewer))
+        .unwrap_or(false);
+    match state.brackets.get(id).await {
+        Some(bracket) if entry_visible => Ok(bracket),
+        _ => Err(ApiError::not_found("Bracket not found")),
+    }
+}
+
+/// Describe a bracket with the current state of its orders
+async fn bracket_response(state: &AppState, bracket: &BracketOrder) -> Result<BracketResponse, ApiError> {
+    let order = |id: Option<String>| async move {
+        match id {
+            Some(id) => state.order_manager.get_order(&id).await.map(|order| OrderResponse::from(&order)),
+            None => None,
+        }
+    };
+    let entry = order(Some(bracket.id.clone())).await
+        .ok_or_else(|| ApiError::not_found("Bracket entry not found"))?;
+    Ok(BracketResponse {
+        id: bracket.id.clone(),
+        status: format!("{:?}", bracket.status),
+        take_profit_price: bracket.take_profit_price,
+        stop_loss_price: bracket.stop_loss_price,
+        entry,
+        take_profit: order(bracket.take_profit_id.clone()).await,
+        stop_loss: order(bracket.stop_loss_id.clone()).await,
+    })
+}
+
+/// Record a fill against an order
+#[utoipa::path(
+    post,
+    path = "/orders/{id}/fills",
+    tag = "orders",
+    params(("id" = String, Path, description = "Order ID")),
+    request_body = RecordFillRequest,
+    responses(
+        (status = 200, description = "Fill recorded", body = ApiResponse<OrderResponse>),
+        (status = 409, description = "Order not open or fill-or-kill partially filled", body = ErrorResponse),
+        (status = 422, description = "Invalid fill", body = ErrorResponse),
+        (status = 404, description = "No such order in the viewer's tenants", body = ErrorResponse),
+    )
+)]
+async fn record_fill(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(payload): Json<RecordFillRequest>,
+) -> Result<Json<ApiResponse<OrderResponse>>, ApiError> {
+    let order = fill(&state, &viewer, &id, payload.quantity, payload.price).await?;
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(OrderResponse::from(&order)),
+        message: Some("Fill recorded successfully".to_string()),
+    };
+    Ok(Json(response))
+}
+
+/// Record a fill against one of the viewer's orders
+async fn fill(state: &AppState, viewer: &UserContext, id: &str, quantity: Decimal, price: Decimal) -> Result<AdvancedOrder, ApiError> {
+    let result = match tenant_order(state, id, viewer).await {
+        Some(_) => state.order_manager.record_fill(id, quantity, price).await,
+        None => Err(OrderError::NotFound(id.to_string())),
+    };
+    let order = result.map_err(|e| ApiError::from(e).context("Failed to record fill"))?;
+    
+    state.metrics.increment_counter("order_fills_total");
+    publish_order_update(&state.order_updates, "filled", &order);
+    // Place or resize the exits of the bracket the order belongs to
+    if let Err(e) = state.brackets.on_fill(id).await {
+        tracing::warn!("failed to update the bracket of order {}: {}", id, e);
     }
+    Ok(order)
+}
+
+/// Get order status
+#[utoipa::path(
+    get,
+    path = "/orders/{id}/status",
+    tag = "orders",
+    params(("id" = String, Path, description = "Order ID")),
+    responses(
+        (status = 200, description = "Order status", body = ApiResponse<String>),
+        (status = 404, description = "No such order in the viewer's tenants", body = ErrorResponse),
+    )
+)]
+async fn get_order_status(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<ApiResponse<String>>, ApiError> {
+    let status = tenant_order(&state, &id, &viewer).await
+        .map(|order| order.status)
+        .ok_or_else(|| ApiError::not_found("Order not found"))?;
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(format!("{:?}", status)),
+        message: None,
+    };
+    Ok(Json(response))
 }
 
 /// Get trade plan for an order
+#[utoipa::path(
+    get,
+    path = "/orders/{id}/plan",
+    tag = "orders",
+    params(("id" = String, Path, description = "Order ID")),
+    responses(
+        (status = 200, description = "Trade plan at the current oracle price", body = ApiResponse<TradePlan>),
+        (status = 404, description = "No such order in the viewer's tenants", body = ErrorResponse),
+        (status = 502, description = "Oracle price unavailable", body = ErrorResponse),
+    )
+)]
 async fn get_trade_plan(
     Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<ApiResponse<TradePlan>> {
-    // For demonstration, we'll use a default price
-    let current_price = 3000.0;
+) -> Result<Json<ApiResponse<TradePlan>>, ApiError> {
+    let trade_plan = trade_plan(&state, &viewer, &id).await?;
     
-    let plan_result = {
-        let manager = state.order_manager.read().await;
-        manager.to_trade_plan(&id, current_price)
+    let response = ApiResponse {
+        success: true,
+        data: Some(trade_plan),
+        message: Some("Trade plan generated successfully".to_string()),
     };
+    Ok(Json(response))
+}
+
+/// Trade plan of one of the viewer's orders at the current oracle price
+async fn trade_plan(state: &AppState, viewer: &UserContext, id: &str) -> Result<TradePlan, ApiError> {
+    let symbol = tenant_order(state, id, viewer).await
+        .map(|order| order.symbol)
+        .ok_or_else(|| ApiError::not_found("Order not found"))?;
     
-    match plan_result {
-        Ok(trade_plan) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(trade_plan),
-                message: Some("Trade plan generated successfully".to_string()),
-            };
-            Json(response)
-        },
+    // Evaluate triggers against the aggregated oracle price
+    let current_price = match state.oracle.oracle.aggregate(&symbol).await {
+        Ok(aggregated) => aggregated.price,
         Err(e) => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some(format!("Failed to generate trade plan: {}", e)),
-            };
-            Json(response)
+            return Err(ApiError::new(ErrorKind::Upstream, format!("Failed to get oracle price: {}", e)));
         },
+    };
+    
+    state.order_manager.to_trade_plan(id, current_price).await
+        .map_err(|e| ApiError::from(e).context("Failed to generate trade plan"))
+}
+
+/// Evaluate a symbol's orders against a price update
+#[utoipa::path(
+    post,
+    path = "/prices",
+    tag = "orders",
+    request_body = PriceUpdateRequest,
+    responses(
+        (status = 200, description = "Trade plans of the orders the price triggered, handed off for execution", body = ApiResponse<Vec<TradePlan>>),
+    )
+)]
+async fn ingest_price_update(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(payload): Json<PriceUpdateRequest>,
+) -> Json<ApiResponse<Vec<TradePlan>>> {
+    let plans = state.order_manager.on_price_update(&payload.symbol, payload.price).await;
+    hand_off(&state.bus, &plans);
+    let message = format!("{} orders triggered", plans.len());
+    let response = ApiResponse {
+        success: true,
+        data: Some(plans),
+        message: Some(message),
+    };
+    Json(response)
+}
+
+/// Activate the orders armed on a signal
+#[utoipa::path(
+    post,
+    path = "/signals",
+    tag = "orders",
+    request_body = Signal,
+    responses(
+        (status = 200, description = "Trade plans of the activated orders that executed at once, handed off for execution", body = ApiResponse<Vec<TradePlan>>),
+    )
+)]
+async fn ingest_signal(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(signal): Json<Signal>,
+) -> Json<ApiResponse<Vec<TradePlan>>> {
+    let plans = state.order_manager.on_signal(&signal).await;
+    hand_off(&state.bus, &plans);
+    let message = format!("{} orders triggered", plans.len());
+    let response = ApiResponse {
+        success: true,
+        data: Some(plans),
+        message: Some(message),
+    };
+    Json(response)
+}
+
+/// Activate orders armed on the signals published on the bus
+async fn trigger_from_signals(order_manager: Arc<ShardedOrderManager>, bus: InMemoryBus) {
+    let mut rx = bus.subscribe("signals.>");
+    loop {
+        match rx.recv().await {
+            Ok(bytes) => {
+                // Other messages on the bus do not decode as signals
+                let Ok(signal) = serde_json::from_slice::<Signal>(&bytes) else {
+                    continue;
+                };
+                let plans = order_manager.on_signal(&signal).await;
+                if !plans.is_empty() {
+                    tracing::info!("{} orders triggered by {}.{} on chain {}", plans.len(), signal.source, signal.kind, signal.chain.id);
+                }
+                hand_off(&bus, &plans);
+            },
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!("order triggers fell behind the signal bus, {} messages skipped", skipped);
+            },
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Poll the live price feeds, evaluating each symbol's orders against every quote
+///
+/// Quotes diverging from the oracle trigger nothing, and block new orders in
+/// the symbol until a later quote agrees with the oracle again.
+async fn trigger_from_feeds(order_manager: Arc<ShardedOrderManager>, prices: Arc<PriceCache>, guard: DivergenceGuard, bus: InMemoryBus) {
+    let mut quotes = prices.subscribe();
+    tokio::spawn(async move { prices.run().await });
+    loop {
+        match quotes.recv().await {
+            Ok(quote) => {
+                // The verdict also gates new orders through the risk engine
+                let decision = guard.check(&quote.symbol, quote.price).await;
+                if !decision.allow {
+                    tracing::warn!("not triggering orders in {} at {}: {}", quote.symbol, quote.price, decision.reasons.join("; "));
+                    continue;
+                }
+                let plans = order_manager.on_price_update(&quote.symbol, quote.price).await;
+                if !plans.is_empty() {
+                    tracing::info!("{} orders in {} triggered at {} ({})", plans.len(), quote.symbol, quote.price, quote.source);
+                }
+                hand_off(&bus, &plans);
+            },
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!("order triggers fell behind the price feeds, {} quotes skipped", skipped);
+            },
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Hand trade plans off to the executor
+fn hand_off(bus: &InMemoryBus, plans: &[TradePlan]) {
+    for plan in plans {
+        if let Err(e) = bus.publish_now(PLAN_SUBJECT, plan) {
+            tracing::error!("failed to hand off trade plan {}: {}", plan.idem_key, e);
+        }
+    }
+}
+
+/// Publish an order status change to stream subscribers
+fn publish_order_update(updates: &broadcast::Sender<OrderUpdate>, event: &str, order: &AdvancedOrder) {
+    // Nobody is listening, skip building the update
+    if updates.receiver_count() == 0 {
+        return;
+    }
+    
+    let update = OrderUpdate {
+        event: event.to_string(),
+        tenant_id: order_tenant(order).to_string(),
+        order: OrderResponse::from(order),
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    };
+    let _ = updates.send(update);
+}
+
+/// Stream order status changes of one tenant over a WebSocket
+///
+/// Viewers may only subscribe to their own tenant unless they hold `view_all_data`.
+#[utoipa::path(
+    get,
+    path = "/ws",
+    tag = "orders",
+    params(OrderStreamParams),
+    responses(
+        (status = 101, description = "WebSocket of `OrderUpdate` JSON messages"),
+        (status = 403, description = "Tenant not accessible"),
+    )
+)]
+async fn order_stream(
+    ws: WebSocketUpgrade,
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    Query(params): Query<OrderStreamParams>,
+) -> Response {
+    let tenant_id = params.tenant_id.clone().unwrap_or_else(|| viewer.tenant_id.clone());
+    if !can_view_tenant(&viewer, &tenant_id) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    
+    let filter = OrderStreamFilter {
+        tenant_id,
+        symbols: params.symbols
+            .map(|s| s.split(',').map(|symbol| symbol.trim().to_string()).collect()),
+        viewer,
+    };
+    let updates = state.order_updates.subscribe();
+    ws.on_upgrade(move |socket| stream_order_updates(socket, updates, filter))
+}
+
+/// Forward order updates matching a subscription to a WebSocket client
+async fn stream_order_updates(
+    mut socket: WebSocket,
+    mut updates: broadcast::Receiver<OrderUpdate>,
+    filter: OrderStreamFilter,
+) {
+    loop {
+        tokio::select! {
+            update = updates.recv() => {
+                match update {
+                    Ok(update) => {
+                        if let Some(update) = filter.apply(update) {
+                            if send_order_update(&mut socket, &update).await.is_err() {
+                                break;
+                            }
+                        }
+                    },
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Order stream lagged, skipped {} updates", skipped);
+                    },
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            },
+            message = socket.recv() => {
+                match message {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    _ => {},
+                }
+            },
+        }
+    }
+}
+
+/// Send an order update as a JSON text frame
+async fn send_order_update(socket: &mut WebSocket, update: &OrderUpdate) -> Result<()> {
+    let text = serde_json::to_string(update)?;
+    socket.send(Message::Text(text)).await?;
+    Ok(())
+}
+
+/// Subscription of one WebSocket client to order updates
+struct OrderStreamFilter {
+    tenant_id: String,
+    symbols: Option<Vec<String>>,
+    viewer: UserContext,
+}
+
+impl OrderStreamFilter {
+    /// Shape an update for the subscriber, returning `None` if it must not be sent
+    fn apply(&self, mut update: OrderUpdate) -> Option<OrderUpdate> {
+        if update.tenant_id != self.tenant_id {
+            return None;
+        }
+        if let Some(symbols) = &self.symbols {
+            if !symbols.contains(&update.order.symbol) {
+                return None;
+            }
+        }
+        update.order = redact(update.order, &self.viewer)?;
+        Some(update)
+    }
+}
+
+/// Kill switch trip request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct TripKillSwitchRequest {
+    pub reason: String,
+}
+
+/// Whether the viewer may trip and reset the kill switch
+fn can_operate_kill_switch(viewer: &UserContext) -> bool {
+    !viewer.is_anonymous() && viewer.permissions.iter().any(|permission| permission == "configure_system")
+}
+
+/// Get the kill switch state
+#[utoipa::path(
+    get,
+    path = "/admin/kill-switch",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Kill switch state", body = ApiResponse<KillSwitchStatus>),
+    )
+)]
+async fn get_kill_switch(Extension(state): Extension<Arc<AppState>>) -> Json<ApiResponse<KillSwitchStatus>> {
+    Json(ApiResponse {
+        success: true,
+        data: Some(state.kill_switch.status()),
+        message: None,
+    })
+}
+
+/// Halt trading by hand; requires `configure_system`
+#[utoipa::path(
+    post,
+    path = "/admin/kill-switch/trip",
+    tag = "admin",
+    request_body = TripKillSwitchRequest,
+    responses(
+        (status = 200, description = "Kill switch tripped", body = ApiResponse<KillSwitchStatus>),
+        (status = 403, description = "Missing `configure_system`"),
+    )
+)]
+async fn trip_kill_switch(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    Json(payload): Json<TripKillSwitchRequest>,
+) -> Response {
+    if !can_operate_kill_switch(&viewer) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    state.kill_switch.trip(TripReason::Manual {
+        by: viewer.user_id.clone(),
+        reason: payload.reason,
+    });
+    Json(ApiResponse {
+        success: true,
+        data: Some(state.kill_switch.status()),
+        message: Some("Kill switch tripped".to_string()),
+    })
+    .into_response()
+}
+
+/// Resume trading; requires `configure_system`
+#[utoipa::path(
+    post,
+    path = "/admin/kill-switch/reset",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Kill switch reset", body = ApiResponse<KillSwitchStatus>),
+        (status = 403, description = "Missing `configure_system`"),
+    )
+)]
+async fn reset_kill_switch(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+) -> Response {
+    if !can_operate_kill_switch(&viewer) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let was_tripped = state.kill_switch.reset(&viewer.user_id);
+    Json(ApiResponse {
+        success: true,
+        data: Some(state.kill_switch.status()),
+        message: Some(if was_tripped { "Kill switch reset" } else { "Kill switch was not tripped" }.to_string()),
+    })
+    .into_response()
+}
+
+/// Whether the viewer may see an order, as `GET /orders/:id` decides
+fn order_visible(order: &AdvancedOrder, viewer: &UserContext) -> bool {
+    redact(OrderResponse::from(order), viewer).is_some()
+}
+
+/// gRPC interface of the service, sharing the REST handlers' logic
+struct OrdersRpc {
+    state: Arc<AppState>,
+}
+
+#[tonic::async_trait]
+impl proto::orders_server::Orders for OrdersRpc {
+    async fn place_order(
+        &self,
+        request: tonic::Request<proto::PlaceOrderRequest>,
+    ) -> Result<tonic::Response<proto::Order>, tonic::Status> {
+        let caller = rpc::caller(&request)?;
+        rpc::require_permission(&caller, "execute_trades")?;
+        let request = request.into_inner();
+        let order = AdvancedOrder {
+            id: Uuid::new_v4().to_string(),
+            symbol: request.symbol,
+            chain: request.chain.ok_or(InvalidMessage::Missing("chain"))?.into(),
+            order_type: request.order_type.ok_or(InvalidMessage::Missing("order_type"))?.try_into()?,
+            side: request.side,
+            amount: convert::decimal(&request.amount, "amount")?,
+            time_in_force: request.time_in_force.unwrap_or_default().try_into()?,
+            created_at: 0,
+            updated_at: 0,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: request.tenant_id,
+            trigger: None,
+        };
+        let order = place_order(&self.state, &caller, order).await.map_err(rpc::status)?;
+        Ok(tonic::Response::new(order.into()))
+    }
+
+    async fn get_order(
+        &self,
+        request: tonic::Request<proto::GetOrderRequest>,
+    ) -> Result<tonic::Response<proto::Order>, tonic::Status> {
+        let caller = rpc::caller(&request)?;
+        // Other users' orders are reported as missing rather than forbidden
+        let order = tenant_order(&self.state, &request.get_ref().id, &caller).await
+            .filter(|order| order_visible(order, &caller))
+            .ok_or_else(|| tonic::Status::not_found("Order not found"))?;
+        Ok(tonic::Response::new(order.into()))
+    }
+
+    async fn list_orders(
+        &self,
+        request: tonic::Request<proto::ListOrdersRequest>,
+    ) -> Result<tonic::Response<proto::ListOrdersResponse>, tonic::Status> {
+        let caller = rpc::caller(&request)?;
+        let orders = self.state.order_manager.list_orders().await
+            .into_iter()
+            .filter(|order| can_view_tenant(&caller, order_tenant(order)) && order_visible(order, &caller))
+            .map(proto::Order::from)
+            .collect();
+        Ok(tonic::Response::new(proto::ListOrdersResponse { orders }))
+    }
+
+    async fn cancel_order(
+        &self,
+        request: tonic::Request<proto::CancelOrderRequest>,
+    ) -> Result<tonic::Response<proto::Order>, tonic::Status> {
+        let caller = rpc::caller(&request)?;
+        rpc::require_permission(&caller, "execute_trades")?;
+        let order = cancel(&self.state, &caller, &request.get_ref().id).await
+            .map_err(rpc::status)?
+            .ok_or_else(|| tonic::Status::not_found("Order not found"))?;
+        Ok(tonic::Response::new(order.into()))
+    }
+
+    async fn record_fill(
+        &self,
+        request: tonic::Request<proto::RecordFillRequest>,
+    ) -> Result<tonic::Response<proto::Order>, tonic::Status> {
+        let caller = rpc::caller(&request)?;
+        rpc::require_permission(&caller, "execute_trades")?;
+        let request = request.into_inner();
+        let quantity = convert::decimal(&request.quantity, "quantity")?;
+        let price = convert::decimal(&request.price, "price")?;
+        let order = fill(&self.state, &caller, &request.id, quantity, price).await.map_err(rpc::status)?;
+        Ok(tonic::Response::new(order.into()))
+    }
+
+    async fn get_trade_plan(
+        &self,
+        request: tonic::Request<proto::GetTradePlanRequest>,
+    ) -> Result<tonic::Response<proto::TradePlan>, tonic::Status> {
+        let caller = rpc::caller(&request)?;
+        let plan = trade_plan(&self.state, &caller, &request.get_ref().id).await.map_err(rpc::status)?;
+        Ok(tonic::Response::new(plan.into()))
     }
 }
 
@@ -437,19 +1580,481 @@ async fn get_trade_plan(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_openapi_document() {
+        let document = serde_json::to_value(ApiDoc::openapi().merge_from(sniper_oracle::http::OracleApi::openapi())).unwrap();
+        for path in ["/orders", "/orders/{id}", "/orders/{id}/fills", "/orders/{id}/amendments", "/orders/bracket", "/orders/bracket/{id}", "/signals", "/ws", "/admin/kill-switch/trip", "/oracle/prices/{asset}"] {
+            assert!(document["paths"][path].is_object(), "{} is not documented", path);
+        }
+        assert!(document["components"]["schemas"]["CreateOrderRequest"]["properties"]["amount"].is_object());
+        assert_eq!(document["paths"]["/health"]["get"]["security"], serde_json::json!([{}]));
+    }
+
     #[test]
     fn test_args_parsing() {
         let args = Args::parse_from(["svc-orders", "--port", "8082"]);
-        assert_eq!(args.port, 8082);
+        assert_eq!(args.port, Some(8082));
     }
 
     #[tokio::test]
     async fn test_orders_service_creation() -> Result<()> {
-        let order_manager = OrderManager::new();
+        let order_manager = Arc::new(ShardedOrderManager::new());
+        let slice_scheduler = Arc::new(SliceScheduler::new(
+            SlicerConfig::default(),
+            Arc::new(UniformVolumeProfile),
+            order_manager.clone(),
+        ));
+        let brackets = Arc::new(BracketManager::new(order_manager.clone()));
         let _app_state = Arc::new(AppState {
-            order_manager: RwLock::new(order_manager),
+            order_manager,
+            slice_scheduler,
+            brackets,
+            session_scheduler: RwLock::new(SessionScheduler::default()),
+            order_updates: broadcast::channel(ORDER_UPDATE_CHANNEL_CAPACITY).0,
+            metrics: Arc::new(ServiceMetrics::new("svc-orders")?),
+            oracle: Arc::new(OracleFeeds::default()),
+            kill_switch: KillSwitch::default(),
+            bus: InMemoryBus::new(16),
         });
         
         Ok(())
     }
+    
+    #[tokio::test]
+    async fn test_handler_errors_map_to_status() -> Result<()> {
+        let order_manager = Arc::new(ShardedOrderManager::new());
+        let slice_scheduler = Arc::new(SliceScheduler::new(
+            SlicerConfig::default(),
+            Arc::new(UniformVolumeProfile),
+            order_manager.clone(),
+        ));
+        let brackets = Arc::new(BracketManager::new(order_manager.clone()));
+        let state = Arc::new(AppState {
+            order_manager: order_manager.clone(),
+            slice_scheduler,
+            brackets,
+            session_scheduler: RwLock::new(SessionScheduler::default()),
+            order_updates: broadcast::channel(ORDER_UPDATE_CHANNEL_CAPACITY).0,
+            metrics: Arc::new(ServiceMetrics::new("svc-orders")?),
+            oracle: Arc::new(OracleFeeds::default()),
+            kill_switch: KillSwitch::default(),
+            bus: InMemoryBus::new(16),
+        });
+        let viewer = UserContext {
+            user_id: "user-1".to_string(),
+            tenant_id: DEFAULT_TENANT.to_string(),
+            roles: Vec::new(),
+            permissions: Vec::new(),
+        };
+        let fill = |quantity: &str| RecordFillRequest {
+            quantity: quantity.parse().unwrap(),
+            price: Decimal::ONE,
+        };
+        
+        let missing = record_fill(
+            Extension(state.clone()),
+            Viewer(viewer.clone()),
+            axum::extract::Path("missing".to_string()),
+            Json(fill("1")),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+        
+        order_manager.create_order(AdvancedOrder {
+            id: "order-1".to_string(),
+            symbol: "ETH".to_string(),
+            chain: ChainRef { name: "ethereum".to_string(), id: 1 },
+            order_type: OrderType::Market,
+            side: "buy".to_string(),
+            amount: Decimal::ONE,
+            time_in_force: TimeInForce::GoodTillCancelled,
+            created_at: 0,
+            updated_at: 0,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: Some(DEFAULT_TENANT.to_string()),
+            trigger: None,
+        }).await?;
+        let overfill = record_fill(
+            Extension(state.clone()),
+            Viewer(viewer.clone()),
+            axum::extract::Path("order-1".to_string()),
+            Json(fill("2")),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(overfill.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(overfill.message.starts_with("Failed to record fill"));
+        
+        let Json(response) = record_fill(Extension(state.clone()), Viewer(viewer.clone()), axum::extract::Path("order-1".to_string()), Json(fill("1")))
+            .await
+            .unwrap();
+        assert_eq!(response.data.unwrap().status, "Filled");
+        let filled = record_fill(Extension(state), Viewer(viewer), axum::extract::Path("order-1".to_string()), Json(fill("1")))
+            .await
+            .unwrap_err();
+        assert_eq!(filled.status(), StatusCode::CONFLICT);
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_triggered_plans_handed_off() -> Result<()> {
+        let order_manager = Arc::new(ShardedOrderManager::new());
+        let slice_scheduler = Arc::new(SliceScheduler::new(
+            SlicerConfig::default(),
+            Arc::new(UniformVolumeProfile),
+            order_manager.clone(),
+        ));
+        let brackets = Arc::new(BracketManager::new(order_manager.clone()));
+        let bus = InMemoryBus::new(16);
+        let mut plans = bus.subscribe(PLAN_SUBJECT);
+        let state = Arc::new(AppState {
+            order_manager: order_manager.clone(),
+            slice_scheduler,
+            brackets,
+            session_scheduler: RwLock::new(SessionScheduler::default()),
+            order_updates: broadcast::channel(ORDER_UPDATE_CHANNEL_CAPACITY).0,
+            metrics: Arc::new(ServiceMetrics::new("svc-orders")?),
+            oracle: Arc::new(OracleFeeds::default()),
+            kill_switch: KillSwitch::default(),
+            bus: bus.clone(),
+        });
+        order_manager.create_order(AdvancedOrder {
+            id: "order-1".to_string(),
+            symbol: "ETH".to_string(),
+            chain: ChainRef { name: "ethereum".to_string(), id: 1 },
+            order_type: OrderType::Limit { price: 3000.0 },
+            side: "buy".to_string(),
+            amount: Decimal::ONE,
+            time_in_force: TimeInForce::GoodTillCancelled,
+            created_at: 0,
+            updated_at: 0,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: Some(DEFAULT_TENANT.to_string()),
+            trigger: None,
+        }).await?;
+        
+        let price = |price| Json(PriceUpdateRequest { symbol: "ETH".to_string(), price });
+        let Json(response) = ingest_price_update(Extension(state.clone()), price(3100.0)).await;
+        assert!(response.data.unwrap().is_empty());
+        assert!(plans.try_recv().is_err());
+        
+        let Json(response) = ingest_price_update(Extension(state), price(2900.0)).await;
+        let plan: TradePlan = serde_json::from_slice(&plans.try_recv()?)?;
+        assert_eq!(plan.correlation_id.as_deref(), Some("order-1"));
+        assert_eq!(response.data.unwrap()[0].idem_key, plan.idem_key);
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_update_order_amends_in_place() -> Result<()> {
+        let order_manager = Arc::new(ShardedOrderManager::new());
+        let slice_scheduler = Arc::new(SliceScheduler::new(
+            SlicerConfig::default(),
+            Arc::new(UniformVolumeProfile),
+            order_manager.clone(),
+        ));
+        let brackets = Arc::new(BracketManager::new(order_manager.clone()));
+        let state = Arc::new(AppState {
+            order_manager: order_manager.clone(),
+            slice_scheduler,
+            brackets,
+            session_scheduler: RwLock::new(SessionScheduler::default()),
+            order_updates: broadcast::channel(ORDER_UPDATE_CHANNEL_CAPACITY).0,
+            metrics: Arc::new(ServiceMetrics::new("svc-orders")?),
+            oracle: Arc::new(OracleFeeds::default()),
+            kill_switch: KillSwitch::default(),
+            bus: InMemoryBus::new(16),
+        });
+        let viewer = UserContext {
+            user_id: "user-1".to_string(),
+            tenant_id: DEFAULT_TENANT.to_string(),
+            roles: Vec::new(),
+            permissions: Vec::new(),
+        };
+        order_manager.create_order(AdvancedOrder {
+            id: "order-1".to_string(),
+            symbol: "ETH".to_string(),
+            chain: ChainRef { name: "ethereum".to_string(), id: 1 },
+            order_type: OrderType::Limit { price: 100.0 },
+            side: "buy".to_string(),
+            amount: Decimal::ONE,
+            time_in_force: TimeInForce::GoodTillTime { expiry_timestamp: u64::MAX },
+            created_at: 0,
+            updated_at: 0,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: None,
+            tenant_id: Some(DEFAULT_TENANT.to_string()),
+            trigger: None,
+        }).await?;
+        let update = |symbol: &str, amount: Decimal| CreateOrderRequest {
+            symbol: symbol.to_string(),
+            chain_id: 1,
+            chain_name: "ethereum".to_string(),
+            order_type: "limit".to_string(),
+            side: "buy".to_string(),
+            amount,
+            price: Some(95.0),
+            stop_price: None,
+            limit_price: None,
+            trail_percent: None,
+            visible_amount: None,
+            total_amount: None,
+            duration_minutes: None,
+            time_in_force: None,
+            expiry_timestamp: None,
+            tenant_id: None,
+            trigger: None,
+        };
+        
+        let Json(response) = update_order(
+            Extension(state.clone()),
+            Viewer(viewer.clone()),
+            axum::extract::Path("order-1".to_string()),
+            Json(update("ETH", Decimal::from(2))),
+        )
+        .await
+        .unwrap();
+        let amended = response.data.unwrap();
+        assert_eq!(amended.order.id, "order-1");
+        assert_eq!(amended.order.amount, Decimal::from(2));
+        assert_eq!(amended.order.price, Some(95.0));
+        assert_eq!(amended.order.time_in_force, amended.previous.time_in_force);
+        assert_eq!(amended.previous.price, Some(100.0));
+        
+        let Json(history) = get_order_amendments(Extension(state.clone()), Viewer(viewer.clone()), axum::extract::Path("order-1".to_string()))
+            .await
+            .unwrap();
+        let history = history.data.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].actor, "user-1");
+        assert_eq!(history[0].previous.amount, Decimal::ONE);
+        
+        let moved = update_order(Extension(state.clone()), Viewer(viewer.clone()), axum::extract::Path("order-1".to_string()), Json(update("BTC", Decimal::from(2))))
+            .await
+            .unwrap_err();
+        assert_eq!(moved.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        
+        order_manager.cancel_order("order-1").await?;
+        let cancelled = update_order(Extension(state), Viewer(viewer), axum::extract::Path("order-1".to_string()), Json(update("ETH", Decimal::ONE)))
+            .await
+            .unwrap_err();
+        assert_eq!(cancelled.status(), StatusCode::CONFLICT);
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_bracket_endpoints() -> Result<()> {
+        let order_manager = Arc::new(ShardedOrderManager::new());
+        let slice_scheduler = Arc::new(SliceScheduler::new(
+            SlicerConfig::default(),
+            Arc::new(UniformVolumeProfile),
+            order_manager.clone(),
+        ));
+        let brackets = Arc::new(BracketManager::new(order_manager.clone()));
+        let state = Arc::new(AppState {
+            order_manager: order_manager.clone(),
+            slice_scheduler,
+            brackets,
+            session_scheduler: RwLock::new(SessionScheduler::default()),
+            order_updates: broadcast::channel(ORDER_UPDATE_CHANNEL_CAPACITY).0,
+            metrics: Arc::new(ServiceMetrics::new("svc-orders")?),
+            oracle: Arc::new(OracleFeeds::default()),
+            kill_switch: KillSwitch::default(),
+            bus: InMemoryBus::new(16),
+        });
+        let viewer = UserContext {
+            user_id: "user-1".to_string(),
+            tenant_id: DEFAULT_TENANT.to_string(),
+            roles: Vec::new(),
+            permissions: Vec::new(),
+        };
+        let request = |take_profit: f64, stop_loss: f64| CreateBracketRequest {
+            entry: CreateOrderRequest {
+                symbol: "ETH".to_string(),
+                chain_id: 1,
+                chain_name: "ethereum".to_string(),
+                order_type: "limit".to_string(),
+                side: "buy".to_string(),
+                amount: Decimal::ONE,
+                price: Some(100.0),
+                stop_price: None,
+                limit_price: None,
+                trail_percent: None,
+                visible_amount: None,
+                total_amount: None,
+                duration_minutes: None,
+                time_in_force: None,
+                expiry_timestamp: None,
+                tenant_id: None,
+                trigger: None,
+            },
+            take_profit,
+            stop_loss,
+        };
+        
+        let inverted = create_bracket(Extension(state.clone()), Viewer(viewer.clone()), Json(request(90.0, 110.0)))
+            .await
+            .unwrap_err();
+        assert_eq!(inverted.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        
+        let Json(placed) = create_bracket(Extension(state.clone()), Viewer(viewer.clone()), Json(request(110.0, 90.0)))
+            .await
+            .unwrap();
+        let placed = placed.data.unwrap();
+        assert_eq!(placed.status, "Pending");
+        assert!(placed.take_profit.is_none());
+        
+        fill(&state, &viewer, &placed.id, Decimal::ONE, Decimal::from(100)).await.unwrap();
+        let Json(active) = get_bracket(Extension(state.clone()), Viewer(viewer.clone()), axum::extract::Path(placed.id.clone()))
+            .await
+            .unwrap();
+        let active = active.data.unwrap();
+        assert_eq!(active.status, "Active");
+        assert_eq!(active.take_profit.unwrap().price, Some(110.0));
+        assert_eq!(active.stop_loss.unwrap().amount, Decimal::ONE);
+        
+        let Json(cancelled) = cancel_bracket(Extension(state.clone()), Viewer(viewer.clone()), axum::extract::Path(placed.id.clone()))
+            .await
+            .unwrap();
+        let cancelled = cancelled.data.unwrap();
+        assert_eq!(cancelled.status, "Cancelled");
+        assert_eq!(cancelled.stop_loss.unwrap().status, "Cancelled");
+        let again = cancel_bracket(Extension(state), Viewer(viewer), axum::extract::Path(placed.id))
+            .await
+            .unwrap_err();
+        assert_eq!(again.status(), StatusCode::CONFLICT);
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_grpc_orders() -> Result<()> {
+        use sniper_rpc::{NewOrder, OrdersClient};
+        
+        let order_manager = Arc::new(ShardedOrderManager::new());
+        let slice_scheduler = Arc::new(SliceScheduler::new(
+            SlicerConfig::default(),
+            Arc::new(UniformVolumeProfile),
+            order_manager.clone(),
+        ));
+        let brackets = Arc::new(BracketManager::new(order_manager.clone()));
+        let state = Arc::new(AppState {
+            order_manager,
+            slice_scheduler,
+            brackets,
+            session_scheduler: RwLock::new(SessionScheduler::default()),
+            order_updates: broadcast::channel(ORDER_UPDATE_CHANNEL_CAPACITY).0,
+            metrics: Arc::new(ServiceMetrics::new("svc-orders")?),
+            oracle: Arc::new(OracleFeeds::default()),
+            kill_switch: KillSwitch::default(),
+            bus: InMemoryBus::new(16),
+        });
+        let auth = Arc::new(JwtAuth::new("an-unguessable-test-secret-of-32-bytes")?);
+        let token = |user_id: &str, tenant_id: &str, permissions: &[&str]| {
+            let context = UserContext {
+                user_id: user_id.to_string(),
+                tenant_id: tenant_id.to_string(),
+                roles: Vec::new(),
+                permissions: permissions.iter().map(|p| p.to_string()).collect(),
+            };
+            auth.issue(&context).unwrap().token
+        };
+        
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let endpoint = format!("http://{}", listener.local_addr()?);
+        let shutdown = Shutdown::new();
+        let grpc = tonic::transport::Server::builder().add_service(proto::orders_server::OrdersServer::with_interceptor(
+            OrdersRpc { state },
+            rpc::Authenticate::new(auth.clone()),
+        ));
+        let server = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { rpc::serve(listener, grpc, &shutdown).await }
+        });
+        
+        let mut trader = OrdersClient::connect(&endpoint).await?.with_token(&token("user-1", "tenant-1", &["execute_trades"]))?;
+        let order = trader.place_order(NewOrder {
+            symbol: "ETH".to_string(),
+            chain: ChainRef { name: "ethereum".to_string(), id: 1 },
+            order_type: OrderType::Limit { price: 2000.0 },
+            side: "buy".to_string(),
+            amount: Decimal::from(2u64),
+            time_in_force: TimeInForce::GoodTillCancelled,
+            tenant_id: None,
+        }).await?;
+        assert_eq!(order.tenant_id.as_deref(), Some("tenant-1"));
+        assert_eq!(order.owner_id.as_deref(), Some("user-1"));
+        
+        let order = trader.record_fill(&order.id, Decimal::ONE, Decimal::from(2000u64)).await?;
+        assert!(matches!(order.status, OrderStatus::PartiallyFilled));
+        assert_eq!(order.fills.len(), 1);
+        assert_eq!(trader.list_orders().await?.len(), 1);
+        let overfill = trader.record_fill(&order.id, Decimal::from(5u64), Decimal::ONE).await.unwrap_err();
+        assert_eq!(overfill.code(), tonic::Code::InvalidArgument);
+        
+        // Other tenants neither see the order nor trade without `execute_trades`
+        let mut other = OrdersClient::connect(&endpoint).await?.with_token(&token("user-2", "tenant-2", &[]))?;
+        assert_eq!(other.get_order(&order.id).await.unwrap_err().code(), tonic::Code::NotFound);
+        assert!(other.list_orders().await?.is_empty());
+        assert_eq!(other.cancel_order(&order.id).await.unwrap_err().code(), tonic::Code::PermissionDenied);
+        let mut anonymous = OrdersClient::connect(&endpoint).await?;
+        assert_eq!(anonymous.list_orders().await.unwrap_err().code(), tonic::Code::Unauthenticated);
+        
+        let order = trader.cancel_order(&order.id).await?;
+        assert!(matches!(order.status, OrderStatus::Cancelled));
+        
+        shutdown.trigger();
+        server.await??;
+        Ok(())
+    }
+    
+    fn update(tenant_id: &str, symbol: &str, owner_id: Option<&str>) -> OrderUpdate {
+        let order = AdvancedOrder {
+            id: "order-1".to_string(),
+            symbol: symbol.to_string(),
+            chain: ChainRef { name: "ethereum".to_string(), id: 1 },
+            order_type: OrderType::Market,
+            side: "buy".to_string(),
+            amount: Decimal::ONE,
+            time_in_force: TimeInForce::GoodTillCancelled,
+            created_at: 0,
+            updated_at: 0,
+            status: OrderStatus::Pending,
+            fills: Vec::new(),
+            owner_id: owner_id.map(str::to_string),
+            tenant_id: Some(tenant_id.to_string()),
+            trigger: None,
+        };
+        OrderUpdate {
+            event: "created".to_string(),
+            tenant_id: tenant_id.to_string(),
+            order: OrderResponse::from(&order),
+            timestamp: 0,
+        }
+    }
+    
+    #[test]
+    fn test_order_stream_filter() {
+        let filter = OrderStreamFilter {
+            tenant_id: "tenant-1".to_string(),
+            symbols: Some(vec!["ETH".to_string()]),
+            viewer: UserContext {
+                user_id: "user-1".to_string(),
+                tenant_id: "tenant-1".to_string(),
+                roles: Vec::new(),
+                permissions: Vec::new(),
+            },
+        };
+        
+        assert!(filter.apply(update("tenant-1", "ETH", Some("user-1"))).is_some());
+        assert!(filter.apply(update("tenant-2", "ETH", Some("user-1"))).is_none());
+        assert!(filter.apply(update("tenant-1", "BTC", Some("user-1"))).is_none());
+        // Other users' orders are redacted away
+        assert!(filter.apply(update("tenant-1", "ETH", Some("user-2"))).is_none());
+    }
 }
\ No newline at end of file
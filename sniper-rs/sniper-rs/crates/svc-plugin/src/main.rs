@@ -2,47 +2,89 @@
 //! 
 //! This service provides REST APIs for managing third-party plugins,
 //! including loading, configuring, and monitoring plugin performance.
+//! Plugins run under per-plugin execution limits; disabled plugins are
+//! counted in the service metrics and can be re-enabled over the API.
+//! With `--plugins-dir`, manifests in that directory are reloaded as they
+//! change, without restarting the service.
+//! The OpenAPI document is served on `/openapi.json`, with a Swagger UI on
+//! `/docs`.
 
 use anyhow::Result;
 use clap::Parser;
+use sniper_core::config::ConfigArgs;
+use sniper_core::errors::{ApiError, ErrorKind};
+use sniper_core::openapi::{self, ErrorResponse};
+use sniper_core::rate_limit::{RateLimitConfig, RateLimiter};
+use sniper_core::shutdown::Shutdown;
 use serde::{Deserialize, Serialize};
 use axum::{
     routing::{get, post, put, delete},
     Json, Router, Extension,
 };
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::RwLock;
-use sniper_plugin::{PluginManager, PluginConfig, PluginMetadata};
+use tokio::task::JoinHandle;
+use utoipa::{OpenApi, ToSchema};
+use sniper_core::bus::InMemoryBus;
+use sniper_core::events::{TradingEvent, PLUGIN_DISABLED_SUBJECT};
+use sniper_plugin::{PluginManager, PluginConfig, PluginHealth, PluginLimits, PluginLoader, PluginMetadata, PluginRevision};
+use sniper_monitoring::http::{instrument, ServiceMetrics};
+
+/// Counter of plugins disabled after repeated failures
+const PLUGINS_DISABLED_METRIC: &str = "plugins_disabled_total";
+
+/// Port used when neither the configuration nor --port sets one
+const DEFAULT_PORT: u16 = 8094;
 
 /// CLI arguments for the plugin service
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    /// Port to listen on
-    #[clap(short, long, default_value = "8094")]
-    port: u16,
+    /// Port to listen on, overriding the configured port (default 8094)
+    #[clap(short, long)]
+    port: Option<u16>,
+    
+    /// Directory of plugin manifests to watch and hot-reload
+    #[clap(long)]
+    plugins_dir: Option<PathBuf>,
+    
+    /// Seconds between scans of the plugins directory
+    #[clap(long, default_value = "5")]
+    reload_interval_secs: u64,
+
+    /// OTLP endpoint spans are exported to, defaulting to OTEL_EXPORTER_OTLP_ENDPOINT
+    #[clap(long)]
+    otlp_endpoint: Option<String>,
+
+    #[clap(flatten)]
+    config: ConfigArgs,
 }
 
 /// Plugin service state
 struct AppState {
     plugin_manager: RwLock<PluginManager>,
+    metrics: Arc<ServiceMetrics>,
 }
 
 /// Plugin registration request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct RegisterPluginRequest {
     pub plugin_type: String, // "signal_processor", "strategy", "risk_assessor", "executor"
+    #[schema(value_type = Object)]
     pub plugin_data: serde_json::Value,
 }
 
 /// Plugin configuration request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct ConfigurePluginRequest {
     pub config: PluginConfig,
 }
 
 /// Standard response format
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
@@ -50,7 +92,7 @@ struct ApiResponse<T> {
 }
 
 /// Plugin metadata response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct PluginMetadataResponse {
     pub id: String,
     pub name: String,
@@ -73,20 +115,61 @@ impl From<&PluginMetadata> for PluginMetadataResponse {
     }
 }
 
+/// OpenAPI document of the service, served on `/openapi.json`
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        health_check, list_plugins, get_plugin, register_plugin, configure_plugin, set_plugin_limits,
+        get_plugin_health, enable_plugin, get_plugin_revision, unregister_plugin, process_signals, generate_plans,
+    ),
+    tags(
+        (name = "plugins", description = "Plugin registration, configuration and health"),
+        (name = "pipeline", description = "Signals and plans run through the registered plugins"),
+    )
+)]
+struct ApiDoc;
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
     
     let args = Args::parse();
+    let config = args.config.loader("svc-plugin")
+        .with_default("port", DEFAULT_PORT)
+        .with_override("port", args.port)
+        .load_service()?;
+    if args.config.print_config {
+        println!("{}", config.render()?);
+        return Ok(());
+    }
+    let _telemetry = sniper_core::telemetry::init_telemetry("svc-plugin", args.otlp_endpoint.as_deref())?;
+    let shutdown = Shutdown::on_signal();
+    
+    // Create plugin manager, publishing when it disables a plugin
+    let bus = InMemoryBus::new(1024);
+    let plugin_manager = PluginManager::new().with_bus(bus.clone());
     
-    // Create plugin manager
-    let plugin_manager = PluginManager::new();
+    // Create service metrics
+    let mut metrics = ServiceMetrics::new("svc-plugin")?;
+    metrics.register_counter("signal_batches_processed_total", "Total signal batches processed by plugins")?;
+    metrics.register_counter("plan_batches_generated_total", "Total plan batches generated by plugins")?;
+    metrics.register_counter(PLUGINS_DISABLED_METRIC, "Total plugins disabled after repeated failures")?;
+    let metrics = Arc::new(metrics);
+    spawn_plugin_event_listener(&bus, metrics.clone());
     
     // Create app state
     let app_state = Arc::new(AppState {
         plugin_manager: RwLock::new(plugin_manager),
+        metrics: metrics.clone(),
     });
+    if let Some(dir) = args.plugins_dir {
+        spawn_plugin_reloader(&shutdown, PluginLoader::new(dir), app_state.clone(), Duration::from_secs(args.reload_interval_secs.max(1)));
+    }
     
+    // Throttle callers per tenant, separately for reads and trades
+    let rate_limiter = RateLimiter::new(config.section::<RateLimitConfig>("rate_limit")?.unwrap_or_default())
+        .with_registry(metrics.registry(), "svc-plugin")?;
+
     // Create router
     let app = Router::new()
         .route("/health", get(health_check))
@@ -94,22 +177,38 @@ async fn main() -> Result<()> {
         .route("/plugins/:id", get(get_plugin))
         .route("/plugins", post(register_plugin))
         .route("/plugins/:id/config", put(configure_plugin))
+        .route("/plugins/:id/limits", put(set_plugin_limits))
+        .route("/plugins/:id/health", get(get_plugin_health))
+        .route("/plugins/:id/enable", post(enable_plugin))
+        .route("/plugins/:id/revision", get(get_plugin_revision))
         .route("/plugins/:id", delete(unregister_plugin))
         .route("/process/signals", post(process_signals))
         .route("/generate/plans", post(generate_plans))
-        .layer(Extension(app_state));
+        .layer(Extension(app_state))
+        .layer(rate_limiter.layer())
+        .layer(axum::middleware::from_fn(sniper_core::telemetry::trace_http));
+    let app = instrument(app, metrics).merge(openapi::router(ApiDoc::openapi()));
     
     // Run server
-    let addr = format!("0.0.0.0:{}", args.port);
+    let addr = format!("0.0.0.0:{}", config.port.unwrap_or(DEFAULT_PORT));
     tracing::info!("Plugin service listening on http://{}", addr);
     
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    sniper_core::shutdown::serve(listener, app, &shutdown).await?;
+    shutdown.join().await;
         
     Ok(())
 }
 
 /// Health check endpoint
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses(
+        (status = 200, description = "Service is healthy", body = ApiResponse<String>),
+    )
+)]
 async fn health_check() -> Json<ApiResponse<String>> {
     let response = ApiResponse {
         success: true,
@@ -120,6 +219,14 @@ async fn health_check() -> Json<ApiResponse<String>> {
 }
 
 /// List all registered plugins
+#[utoipa::path(
+    get,
+    path = "/plugins",
+    tag = "plugins",
+    responses(
+        (status = 200, description = "Registered plugins", body = ApiResponse<Vec<PluginMetadataResponse>>),
+    )
+)]
 async fn list_plugins(
     Extension(state): Extension<Arc<AppState>>,
 ) -> Json<ApiResponse<Vec<PluginMetadataResponse>>> {
@@ -127,7 +234,7 @@ async fn list_plugins(
         let plugin_manager = state.plugin_manager.read().await;
         plugin_manager.list_plugins()
             .iter()
-            .map(|&metadata| PluginMetadataResponse::from(metadata))
+            .map(PluginMetadataResponse::from)
             .collect::<Vec<PluginMetadataResponse>>()
     };
     
@@ -140,39 +247,47 @@ async fn list_plugins(
 }
 
 /// Get a plugin by ID
+#[utoipa::path(
+    get,
+    path = "/plugins/{id}",
+    tag = "plugins",
+    params(("id" = String, Path, description = "Plugin ID")),
+    responses(
+        (status = 200, description = "The plugin", body = ApiResponse<PluginMetadataResponse>),
+        (status = 404, description = "Plugin not found", body = ErrorResponse),
+    )
+)]
 async fn get_plugin(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<ApiResponse<PluginMetadataResponse>> {
-    let plugin_data = {
+) -> Result<Json<ApiResponse<PluginMetadataResponse>>, ApiError> {
+    let plugin_response = {
         let plugin_manager = state.plugin_manager.read().await;
         plugin_manager.list_plugins()
             .iter()
             .find(|metadata| metadata.id == id)
-            .map(|&metadata| PluginMetadataResponse::from(metadata))
-    };
-    
-    match plugin_data {
-        Some(plugin_response) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(plugin_response),
-                message: None,
-            };
-            Json(response)
-        },
-        None => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some("Plugin not found".to_string()),
-            };
-            Json(response)
-        },
+            .map(PluginMetadataResponse::from)
     }
+    .ok_or_else(|| ApiError::not_found("Plugin not found"))?;
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(plugin_response),
+        message: None,
+    };
+    Ok(Json(response))
 }
 
 /// Register a new plugin
+#[utoipa::path(
+    post,
+    path = "/plugins",
+    tag = "plugins",
+    request_body = RegisterPluginRequest,
+    responses(
+        (status = 200, description = "Plugin registered", body = ApiResponse<bool>),
+    )
+)]
 async fn register_plugin(
     Extension(state): Extension<Arc<AppState>>,
     Json(payload): Json<RegisterPluginRequest>,
@@ -190,6 +305,16 @@ async fn register_plugin(
 }
 
 /// Configure a plugin
+#[utoipa::path(
+    put,
+    path = "/plugins/{id}/config",
+    tag = "plugins",
+    params(("id" = String, Path, description = "Plugin ID")),
+    request_body = ConfigurePluginRequest,
+    responses(
+        (status = 200, description = "Plugin configured", body = ApiResponse<bool>),
+    )
+)]
 async fn configure_plugin(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(id): axum::extract::Path<String>,
@@ -205,7 +330,174 @@ async fn configure_plugin(
     Json(response)
 }
 
+/// Set a plugin's execution limits
+#[utoipa::path(
+    put,
+    path = "/plugins/{id}/limits",
+    tag = "plugins",
+    params(("id" = String, Path, description = "Plugin ID")),
+    request_body = PluginLimits,
+    responses(
+        (status = 200, description = "Execution limits set", body = ApiResponse<bool>),
+    )
+)]
+async fn set_plugin_limits(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(limits): Json<PluginLimits>,
+) -> Json<ApiResponse<bool>> {
+    state.plugin_manager.write().await.set_plugin_limits(&id, limits);
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(true),
+        message: Some("Plugin limits updated".to_string()),
+    };
+    Json(response)
+}
+
+/// Get a plugin's call counters and whether it is disabled
+#[utoipa::path(
+    get,
+    path = "/plugins/{id}/health",
+    tag = "plugins",
+    params(("id" = String, Path, description = "Plugin ID")),
+    responses(
+        (status = 200, description = "Call counters of the plugin", body = ApiResponse<PluginHealth>),
+    )
+)]
+async fn get_plugin_health(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Json<ApiResponse<PluginHealth>> {
+    let health = state.plugin_manager.read().await.plugin_health(&id);
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(health),
+        message: None,
+    };
+    Json(response)
+}
+
+/// Re-enable a plugin disabled for failing
+#[utoipa::path(
+    post,
+    path = "/plugins/{id}/enable",
+    tag = "plugins",
+    params(("id" = String, Path, description = "Plugin ID")),
+    responses(
+        (status = 200, description = "Plugin re-enabled", body = ApiResponse<bool>),
+        (status = 409, description = "Plugin is not disabled", body = ErrorResponse),
+    )
+)]
+async fn enable_plugin(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<ApiResponse<bool>>, ApiError> {
+    if !state.plugin_manager.read().await.enable_plugin(&id) {
+        return Err(ApiError::new(ErrorKind::Conflict, "Plugin is not disabled"));
+    }
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(true),
+        message: Some("Plugin re-enabled".to_string()),
+    };
+    Ok(Json(response))
+}
+
+/// Get the running revision of a plugin
+#[utoipa::path(
+    get,
+    path = "/plugins/{id}/revision",
+    tag = "plugins",
+    params(("id" = String, Path, description = "Plugin ID")),
+    responses(
+        (status = 200, description = "Installed version of the plugin", body = ApiResponse<PluginRevision>),
+        (status = 404, description = "Plugin not found", body = ErrorResponse),
+    )
+)]
+async fn get_plugin_revision(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<ApiResponse<PluginRevision>>, ApiError> {
+    let revision = state
+        .plugin_manager
+        .read()
+        .await
+        .plugin_revision(&id)
+        .ok_or_else(|| ApiError::not_found("Plugin not found"))?;
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(revision),
+        message: None,
+    };
+    Ok(Json(response))
+}
+
+/// Install plugins from manifests in the loader's directory as they change
+///
+/// Manifests are read and built outside the manager lock, which is only
+/// held to swap the new versions in.
+fn spawn_plugin_reloader(shutdown: &Shutdown, mut loader: PluginLoader, state: Arc<AppState>, interval: Duration) {
+    shutdown.spawn(async move {
+        tracing::info!("Watching {} for plugin manifests", loader.dir().display());
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let loaded = match loader.scan() {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    tracing::warn!("Failed to scan {}: {}", loader.dir().display(), e);
+                    continue;
+                },
+            };
+            if loaded.is_empty() {
+                continue;
+            }
+            let mut plugin_manager = state.plugin_manager.write().await;
+            for (path, plugin) in loaded {
+                let installed = plugin.and_then(|plugin| plugin_manager.install(plugin.instance, plugin.manifest.config));
+                if let Err(e) = installed {
+                    tracing::warn!("Plugin manifest {} not installed: {}", path.display(), e);
+                }
+            }
+        }
+    })
+}
+
+/// Count plugins disabled by the manager until the bus closes
+fn spawn_plugin_event_listener(bus: &InMemoryBus, metrics: Arc<ServiceMetrics>) -> JoinHandle<()> {
+    let mut rx = bus.subscribe(PLUGIN_DISABLED_SUBJECT);
+    tokio::spawn(async move {
+        loop {
+            let bytes = match rx.recv().await {
+                Ok(bytes) => bytes,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Plugin event listener lagged, skipped {} messages", skipped);
+                    continue;
+                },
+                Err(RecvError::Closed) => break,
+            };
+            if let Some(TradingEvent::PluginDisabled { .. }) = TradingEvent::decode(&bytes) {
+                metrics.increment_counter(PLUGINS_DISABLED_METRIC);
+            }
+        }
+    })
+}
+
 /// Unregister a plugin
+#[utoipa::path(
+    delete,
+    path = "/plugins/{id}",
+    tag = "plugins",
+    params(("id" = String, Path, description = "Plugin ID")),
+    responses(
+        (status = 200, description = "Plugin unregistered", body = ApiResponse<bool>),
+    )
+)]
 async fn unregister_plugin(
     Extension(_state): Extension<Arc<AppState>>,
     axum::extract::Path(id): axum::extract::Path<String>,
@@ -223,6 +515,15 @@ async fn unregister_plugin(
 }
 
 /// Process signals through registered signal processors
+#[utoipa::path(
+    post,
+    path = "/process/signals",
+    tag = "pipeline",
+    request_body(content = Object, description = "Signal passed to every signal processor"),
+    responses(
+        (status = 200, description = "Outputs of the signal processors", body = ApiResponse<Vec<Object>>),
+    )
+)]
 async fn process_signals(
     Extension(state): Extension<Arc<AppState>>,
     Json(payload): Json<serde_json::Value>,
@@ -233,6 +534,7 @@ async fn process_signals(
             tracing::error!("Error processing signals: {}", e);
             Vec::new()
         });
+    state.metrics.increment_counter("signal_batches_processed_total");
     
     let response = ApiResponse {
         success: true,
@@ -243,6 +545,15 @@ async fn process_signals(
 }
 
 /// Generate plans through registered strategies
+#[utoipa::path(
+    post,
+    path = "/generate/plans",
+    tag = "pipeline",
+    request_body(content = Object, description = "Signal passed to every strategy"),
+    responses(
+        (status = 200, description = "Plans generated by the strategies", body = ApiResponse<Vec<Object>>),
+    )
+)]
 async fn generate_plans(
     Extension(state): Extension<Arc<AppState>>,
     Json(payload): Json<serde_json::Value>,
@@ -253,6 +564,7 @@ async fn generate_plans(
             tracing::error!("Error generating plans: {}", e);
             Vec::new()
         });
+    state.metrics.increment_counter("plan_batches_generated_total");
     
     let response = ApiResponse {
         success: true,
@@ -266,10 +578,19 @@ async fn generate_plans(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_openapi_document() {
+        let document = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        for path in ["/plugins", "/plugins/{id}/limits", "/plugins/{id}/health", "/process/signals"] {
+            assert!(document["paths"][path].is_object(), "{} is not documented", path);
+        }
+        assert!(document["components"]["schemas"]["PluginLimits"]["properties"]["call_timeout_ms"].is_object());
+    }
+
     #[test]
     fn test_args_parsing() {
         let args = Args::parse_from(["svc-plugin", "--port", "8095"]);
-        assert_eq!(args.port, 8095);
+        assert_eq!(args.port, Some(8095));
     }
 
     #[tokio::test]
@@ -277,8 +598,27 @@ mod tests {
         let plugin_manager = PluginManager::new();
         let _app_state = Arc::new(AppState {
             plugin_manager: RwLock::new(plugin_manager),
+            metrics: Arc::new(ServiceMetrics::new("svc-plugin")?),
+        });
+        
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_handler_errors_map_to_status() -> Result<()> {
+        let state = Arc::new(AppState {
+            plugin_manager: RwLock::new(PluginManager::new()),
+            metrics: Arc::new(ServiceMetrics::new("svc-plugin")?),
         });
+        let missing = || axum::extract::Path("missing".to_string());
+        
+        let error = get_plugin(Extension(state.clone()), missing()).await.unwrap_err();
+        assert_eq!(error.status(), axum::http::StatusCode::NOT_FOUND);
+        let error = get_plugin_revision(Extension(state.clone()), missing()).await.unwrap_err();
+        assert_eq!(error.status(), axum::http::StatusCode::NOT_FOUND);
+        let error = enable_plugin(Extension(state), missing()).await.unwrap_err();
+        assert_eq!(error.status(), axum::http::StatusCode::CONFLICT);
         
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -1,71 +1,270 @@
 //! Portfolio management service for the sniper bot.
 //! 
 //! This service provides a REST API for managing trading portfolios,
-//! including position tracking, risk allocation, and performance analytics.
+//! including position tracking, risk allocation, and performance analytics,
+//! plus WebSocket streams of PnL updates as prices tick and of position
+//! changes for the portfolio's tenant. The portfolio belongs to one tenant:
+//! every call except the health check needs an access token, and callers
+//! from other tenants are turned away unless they hold `view_all_data`.
+//! A retried POST carrying the same `Idempotency-Key` header gets the first
+//! attempt's response instead of opening or closing a position twice. The
+//! OpenAPI document is served on `/openapi.json`, with a Swagger UI on `/docs`.
+//!
+//! With a `benchmark` basket configured, performance is also compared with
+//! it: beta, alpha, tracking error and information ratio are reported by
+//! `/performance` and `/metrics/history` and exported as gauges on `/metrics`.
+//!
+//! Positions and trade plans are also served over gRPC
+//! (`sniper.v1.Portfolio`, port 9080 by default) for internal callers, under
+//! the same tokens and tenant rules.
 
 use anyhow::Result;
 use clap::Parser;
+use sniper_core::config::ConfigArgs;
+use sniper_core::rate_limit::{RateLimitConfig, RateLimiter};
+use sniper_core::shutdown::Shutdown;
 use serde::{Deserialize, Serialize};
-use sniper_portfolio::{PortfolioManager, AllocationSettings, Position, PerformanceMetrics};
-use sniper_core::types::{ChainRef, TradePlan};
+use sniper_portfolio::{PortfolioManager, AllocationSettings, InventoryBook, NetPosition, NettingMode, Position, PositionFill, FundingCharge, PerformanceMetrics, RealizedPnlEntry, EquityPoint, HistoricalMetrics, RiskReport, RebalanceConfig, RebalancePreview, Rebalancer, FillOutcome, BenchmarkConfig, BenchmarkMetrics};
+use sniper_core::bus::InMemoryBus;
+use sniper_core::errors::ApiError;
+use sniper_core::kill_switch::{KillSwitch, KillSwitchStatus, TripReason};
+use sniper_core::openapi::{self, BearerAuth, ErrorResponse};
+use sniper_core::types::{ChainRef, Decimal, ExecReceipt, TradePlan};
+use sniper_exec::{ExecError, Executor};
+use sniper_monitoring::http::{instrument, ServiceMetrics};
+use sniper_oracle::http::OracleFeeds;
+use sniper_prices::{PriceCache, PriceCacheConfig};
+use sniper_risk::{RiskEngine, RiskLimits};
+use sniper_rpc::convert::{self, InvalidMessage};
+use sniper_rpc::{proto, server as rpc};
+use sniper_users::{can_view_tenant, idempotent, require_permission, require_tenant, spawn_audit_listener, AuditStore, AuthLayer, IdempotencyConfig, IdempotencyStore, JwtAuth, UserContext};
+use sniper_users::auth::reject;
+use sniper_users::http::Viewer;
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, RwLock};
+use utoipa::{IntoParams, OpenApi, ToSchema};
 use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    extract::{Query, Request, State},
+    handler::Handler,
+    http::StatusCode,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
     routing::{get, post, put, delete},
     Json, Router, Extension,
 };
-use uuid::Uuid;
+
+/// Port used when neither the configuration nor --port sets one
+const DEFAULT_PORT: u16 = 8080;
+
+/// gRPC port used when neither the configuration nor --grpc-port sets one
+const DEFAULT_GRPC_PORT: u16 = 9080;
 
 /// CLI arguments for the portfolio service
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    /// Port to listen on
-    #[clap(short, long, default_value = "8080")]
-    port: u16,
+    /// Port to listen on, overriding the configured port (default 8080)
+    #[clap(short, long)]
+    port: Option<u16>,
+
+    /// Port to serve gRPC on, overriding the configured port (default 9080)
+    #[clap(long)]
+    grpc_port: Option<u16>,
 
     /// Initial capital for the portfolio
     #[clap(long, default_value = "10000.0")]
     initial_capital: f64,
+
+    /// Interval between equity curve samples in seconds
+    #[clap(long, default_value = "60")]
+    equity_sample_secs: u64,
+
+    /// Net fills into existing positions instead of opening separate lots
+    #[clap(long)]
+    net_positions: bool,
+
+    /// Tenant the portfolio belongs to
+    #[clap(long, default_value = "default")]
+    tenant_id: String,
+
+    /// Pre-trade risk limits as JSON, e.g. '{"max_leverage": 3.0}'
+    #[clap(long)]
+    risk_limits: Option<String>,
+
+    /// Target allocation by asset class in percent of equity as JSON, e.g. '{"ETH": 40.0}'
+    #[clap(long)]
+    diversification_targets: Option<String>,
+
+    /// Rebalancing settings as JSON, e.g. '{"tolerance_pct": 5.0}'
+    #[clap(long)]
+    rebalance: Option<String>,
+
+    /// Live price feeds marking positions as JSON, e.g. '{"symbols": ["ETH/USDT"], "rest": [...]}'
+    #[clap(long)]
+    price_feeds: Option<String>,
+
+    /// Interval between mark-to-market passes over the price feeds in milliseconds
+    #[clap(long, default_value = "1000")]
+    mark_interval_ms: u64,
+
+    /// Shared audit log file to append position and execution audit events to
+    #[clap(long)]
+    audit_log: Option<std::path::PathBuf>,
+
+    /// OTLP endpoint spans are exported to, defaulting to OTEL_EXPORTER_OTLP_ENDPOINT
+    #[clap(long)]
+    otlp_endpoint: Option<String>,
+
+    #[clap(flatten)]
+    config: ConfigArgs,
 }
 
+/// Default minimum interval between streamed updates for one symbol
+const DEFAULT_THROTTLE_MS: u64 = 250;
+
+/// Capacity of the PnL update channel
+const PNL_CHANNEL_CAPACITY: usize = 1024;
+
+/// Capacity of the position update channel
+const POSITION_CHANNEL_CAPACITY: usize = 1024;
+
 /// Portfolio service state
 struct AppState {
     portfolio_manager: RwLock<PortfolioManager>,
+    inventory: InventoryBook,
+    tenant_id: String,
+    pnl_updates: broadcast::Sender<PnlUpdate>,
+    position_updates: broadcast::Sender<PositionUpdate>,
+    metrics: Arc<ServiceMetrics>,
+    oracle: Arc<OracleFeeds>,
+    kill_switch: KillSwitch,
+    rebalancer: Rebalancer,
+    executor: Executor,
+    /// Oracle asset of each chain's gas token, by chain ID
+    native_assets: HashMap<String, String>,
+}
+
+/// Price tick request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct PriceTickRequest {
+    pub symbol: String,
+    pub price: f64,
+}
+
+/// Funding rate of a period for a symbol
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct FundingRateRequest {
+    pub symbol: String,
+    /// Share of the notional longs pay to shorts, e.g. 0.0001
+    pub rate: f64,
+}
+
+/// Query parameters for the PnL stream
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+struct PnlStreamParams {
+    /// Minimum milliseconds between updates for the same symbol
+    pub throttle_ms: Option<u64>,
+    /// Comma-separated symbols to stream; all symbols when absent
+    pub symbols: Option<String>,
+}
+
+/// Incremental PnL update streamed to dashboards
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct PnlUpdate {
+    pub symbol: String,
+    pub price: Decimal,
+    pub positions: Vec<PositionResponse>,
+    pub symbol_pnl: Decimal,
+    pub total_pnl: Decimal,
+    pub timestamp: u64,
+}
+
+/// Position change streamed to clients
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct PositionUpdate {
+    pub event: String, // "opened", "netted", "updated", "reduced" or "closed"
+    pub position_id: String,
+    pub symbol: String,
+    pub position: Option<PositionResponse>,
+    pub realized: Option<RealizedPnlEntry>,
+    pub timestamp: u64,
+}
+
+/// Message sent on the portfolio stream
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamUpdate {
+    Position(PositionUpdate),
+    Pnl(PnlUpdate),
+}
+
+impl StreamUpdate {
+    fn symbol(&self) -> &str {
+        match self {
+            StreamUpdate::Position(update) => &update.symbol,
+            StreamUpdate::Pnl(update) => &update.symbol,
+        }
+    }
+}
+
+/// Portfolio stream message tagged with the tenant it belongs to
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct StreamMessage {
+    pub tenant_id: String,
+    #[serde(flatten)]
+    pub update: StreamUpdate,
 }
 
 /// Position creation request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct CreatePositionRequest {
     pub symbol: String,
     pub chain_id: u64,
     pub chain_name: String,
-    pub amount: f64,
-    pub entry_price: f64,
-    pub current_price: f64,
+    pub amount: Decimal,
+    pub entry_price: Decimal,
+    pub current_price: Decimal,
     pub side: String,
     pub leverage: f64,
+    /// Fee paid for the fill, in the quote currency
+    #[serde(default)]
+    pub fee: Decimal,
+    /// Execution receipt of the fill, whose gas is added to the fee
+    #[serde(default)]
+    pub receipt: Option<ExecReceipt>,
 }
 
 /// Position update request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct UpdatePositionRequest {
-    pub current_price: f64,
+    pub current_price: Decimal,
+}
+
+/// Partial close request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct ClosePositionRequest {
+    pub amount: Decimal,
+    pub exit_price: Decimal,
+    /// Fee paid for the closing fill, in the quote currency
+    #[serde(default)]
+    pub fee: Decimal,
 }
 
 /// Trade plan request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct GenerateTradePlanRequest {
     pub symbol: String,
     pub chain_id: u64,
     pub chain_name: String,
-    pub amount: f64,
+    pub amount: Decimal,
     pub side: String,
 }
 
 /// Standard response format
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
@@ -73,32 +272,75 @@ struct ApiResponse<T> {
 }
 
 /// Portfolio metrics response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct PortfolioMetricsResponse {
-    pub total_value: f64,
-    pub total_pnl: f64,
+    pub total_value: Decimal,
+    pub total_pnl: Decimal,
+    pub realized_pnl: Decimal,
+    pub unrealized_pnl: Decimal,
+    pub fees: Decimal,
+    pub funding: Decimal,
+    pub net_pnl: Decimal,
     pub total_pnl_percentage: f64,
     pub win_rate: f64,
     pub profit_factor: f64,
     pub sharpe_ratio: f64,
     pub max_drawdown: f64,
     pub positions_count: usize,
+    pub risk: RiskReport,
+    pub benchmark: Option<BenchmarkMetrics>,
+}
+
+/// Query parameters for the risk report
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+struct RiskParams {
+    /// VaR confidence level, e.g. 0.99
+    pub confidence: Option<f64>,
+}
+
+/// Outcome of executing one rebalancing trade
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct RebalanceTradeResult {
+    pub class: String,
+    pub symbol: String,
+    pub side: String,
+    pub notional: Decimal,
+    pub receipt: Option<ExecReceipt>,
+    pub error: Option<String>,
+}
+
+/// Rebalancing execution response
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct RebalanceExecution {
+    pub preview: RebalancePreview,
+    pub results: Vec<RebalanceTradeResult>,
+}
+
+/// Equity curve history response
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct EquityHistoryResponse {
+    pub metrics: HistoricalMetrics,
+    pub benchmark: Option<BenchmarkMetrics>,
+    pub points: Vec<EquityPoint>,
 }
 
 /// Position response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct PositionResponse {
     pub id: String,
     pub symbol: String,
     pub chain_id: u64,
     pub chain_name: String,
-    pub amount: f64,
-    pub entry_price: f64,
-    pub current_price: f64,
+    pub amount: Decimal,
+    pub entry_price: Decimal,
+    pub current_price: Decimal,
     pub side: String,
     pub leverage: f64,
-    pub pnl: f64,
+    pub pnl: Decimal,
     pub pnl_percentage: f64,
+    pub fees: Decimal,
+    pub funding: Decimal,
+    pub tenant_id: Option<String>,
     pub created_at: u64,
     pub updated_at: u64,
 }
@@ -117,55 +359,249 @@ impl From<Position> for PositionResponse {
             leverage: position.leverage,
             pnl: position.pnl,
             pnl_percentage: position.pnl_percentage,
+            fees: position.fees,
+            funding: position.funding,
+            tenant_id: position.tenant_id,
             created_at: position.created_at,
             updated_at: position.updated_at,
         }
     }
 }
 
+/// OpenAPI document of the service, served on `/openapi.json`
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        health_check, get_positions, get_net_positions, create_position, get_position, update_position, close_position,
+        close_position_partial, get_realized_pnl, get_funding_ledger, charge_funding_rate, get_inventory, get_portfolio_metrics, get_metrics_history,
+        get_risk_report, generate_trade_plan, preview_rebalance, execute_rebalance, ingest_price_tick,
+        portfolio_stream, pnl_stream, get_kill_switch, trip_kill_switch, reset_kill_switch,
+    ),
+    components(schemas(StreamMessage, PnlUpdate)),
+    modifiers(&BearerAuth),
+    security(("bearer_auth" = [])),
+    tags(
+        (name = "portfolio", description = "Positions and performance of the portfolio; callers from other tenants get 403"),
+        (name = "rebalance", description = "Rebalancing towards the diversification targets"),
+        (name = "streams", description = "WebSocket streams of position and PnL changes"),
+        (name = "admin", description = "Trading kill switch"),
+    )
+)]
+struct ApiDoc;
+
+/// Allocation settings used where the configuration leaves them out
+fn default_allocation_settings() -> AllocationSettings {
+    AllocationSettings {
+        max_position_size_pct: 5.0,
+        max_portfolio_risk_pct: 2.0,
+        diversification_targets: HashMap::new(),
+        stop_loss_pct: 5.0,
+        take_profit_pct: 10.0,
+        netting: NettingMode::SeparateLots,
+    }
+}
+
+/// Gas tokens of the supported chains, used where the configuration leaves them out
+fn default_native_assets() -> HashMap<String, String> {
+    [("1", "ETH"), ("10", "ETH"), ("56", "BNB"), ("137", "MATIC"), ("8453", "ETH"), ("42161", "ETH")]
+        .into_iter()
+        .map(|(chain_id, asset)| (chain_id.to_string(), asset.to_string()))
+        .collect()
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
     
     let args = Args::parse();
+    // Allocation settings come from the `allocation` section, with flags overriding it
+    let diversification_targets: Option<HashMap<String, f64>> = match &args.diversification_targets {
+        Some(targets) => Some(serde_json::from_str(targets)?),
+        None => None,
+    };
+    let config = args.config.loader("svc-portfolio")
+        .with_default("port", DEFAULT_PORT)
+        .with_default("grpc_port", DEFAULT_GRPC_PORT)
+        .with_default("allocation", default_allocation_settings())
+        .with_default("native_assets", default_native_assets())
+        .with_override("port", args.port)
+        .with_override("grpc_port", args.grpc_port)
+        .with_override("allocation.diversification_targets", diversification_targets)
+        .with_override("allocation.netting", args.net_positions.then_some(NettingMode::Net))
+        .load_service()?;
+    let allocation_settings: AllocationSettings = config.section("allocation")?
+        .ok_or_else(|| anyhow::anyhow!("Missing allocation settings"))?;
+    allocation_settings.validate()?;
+    let native_assets: HashMap<String, String> = config.section("native_assets")?.unwrap_or_default();
+    let benchmark: Option<BenchmarkConfig> = config.section("benchmark")?;
+    if let Some(benchmark) = &benchmark {
+        benchmark.validate()?;
+    }
+    if args.config.print_config {
+        println!("{}", config.render()?);
+        return Ok(());
+    }
+    let _telemetry = sniper_core::telemetry::init_telemetry("svc-portfolio", args.otlp_endpoint.as_deref())?;
+    let shutdown = Shutdown::on_signal();
     
-    // Create default allocation settings
-    let allocation_settings = AllocationSettings {
-        max_position_size_pct: 5.0,
-        max_portfolio_risk_pct: 2.0,
-        diversification_targets: HashMap::new(),
-        stop_loss_pct: 5.0,
-        take_profit_pct: 10.0,
+    let rebalance_config: RebalanceConfig = match &args.rebalance {
+        Some(config) => serde_json::from_str(config)?,
+        None => RebalanceConfig::default(),
     };
     
-    // Create portfolio manager
-    let portfolio_manager = PortfolioManager::new(args.initial_capital, allocation_settings);
+    // Create portfolio manager, sharing net inventory with quoting engines,
+    // gating trade plans on pre-trade risk and tripping the kill switch on drawdown
+    let risk_limits: RiskLimits = match &args.risk_limits {
+        Some(limits) => serde_json::from_str(limits)?,
+        None => RiskLimits::default(),
+    };
+    let inventory = InventoryBook::new();
+    let kill_switch = KillSwitch::global().clone();
+    let bus = InMemoryBus::new(1024);
+    let initial_capital = Decimal::from_f64(args.initial_capital)
+        .ok_or_else(|| anyhow::anyhow!("Invalid initial capital {}", args.initial_capital))?;
+    let mut portfolio_manager = PortfolioManager::new(initial_capital, allocation_settings)
+        .with_bus(bus.clone())
+        .with_inventory_book(inventory.clone())
+        .with_risk(RiskEngine::new(risk_limits))
+        .with_kill_switch(kill_switch.clone())
+        .with_tenant(args.tenant_id.clone());
+    portfolio_manager.set_equity_sample_interval(args.equity_sample_secs);
+    if let Some(benchmark) = benchmark {
+        portfolio_manager = portfolio_manager.with_benchmark(benchmark);
+    }
+    
+    // Append position and execution audit events to the shared audit log
+    if let Some(path) = &args.audit_log {
+        spawn_audit_listener(&bus, AuditStore::append_only(path)?);
+    }
+    
+    // Create service metrics
+    let mut metrics = ServiceMetrics::new("svc-portfolio")?;
+    metrics.register_counter("positions_opened_total", "Total positions opened")?;
+    metrics.register_counter("positions_closed_total", "Total positions closed")?;
+    metrics.register_counter("price_ticks_total", "Total price ticks ingested")?;
+    metrics.register_counter("exits_triggered_total", "Total stop-loss and take-profit exits")?;
+    for (name, help) in BENCHMARK_GAUGES {
+        metrics.register_gauge(name, help)?;
+    }
+    let metrics = Arc::new(metrics);
+    
+    // Create price oracle used to mark positions
+    let oracle = Arc::new(OracleFeeds::default());
+    
+    // Create live price feeds, if configured
+    let prices = match &args.price_feeds {
+        Some(config) => Some(Arc::new(PriceCache::from_config(serde_json::from_str::<PriceCacheConfig>(config)?)?)),
+        None => None,
+    };
     
     // Create app state
+    let (pnl_updates, _) = broadcast::channel(PNL_CHANNEL_CAPACITY);
+    let (position_updates, _) = broadcast::channel(POSITION_CHANNEL_CAPACITY);
     let app_state = Arc::new(AppState {
         portfolio_manager: RwLock::new(portfolio_manager),
+        inventory,
+        tenant_id: args.tenant_id,
+        pnl_updates,
+        position_updates,
+        metrics: metrics.clone(),
+        oracle: oracle.clone(),
+        executor: Executor::new().with_kill_switch(kill_switch.clone()).with_bus(bus.clone()),
+        kill_switch,
+        rebalancer: Rebalancer::new(rebalance_config),
+        native_assets,
     });
     
-    // Create router
+    // Sample the equity curve in the background
+    shutdown.spawn(sample_equity(app_state.clone(), args.equity_sample_secs));
+    
+    // Mark positions from the live price feeds in the background
+    if let Some(prices) = prices {
+        shutdown.spawn(mark_to_market_loop(app_state.clone(), prices, args.mark_interval_ms));
+    }
+    
+    // Verify access tokens issued by svc-users
+    let auth = Arc::new(JwtAuth::from_config(&config.auth)?);
+    let trading = require_permission("execute_trades");
+    let feeds = require_permission("configure_system");
+    
+    // Replay responses of retried POSTs carrying an Idempotency-Key
+    let idempotency = IdempotencyStore::new(config.section::<IdempotencyConfig>("idempotency")?.unwrap_or_default());
+    
+    // Throttle callers per tenant, separately for reads and trades
+    let rate_limiter = RateLimiter::new(config.section::<RateLimitConfig>("rate_limit")?.unwrap_or_default())
+        .with_registry(metrics.registry(), "svc-portfolio")?;
+    
+    // Serve positions and trade plans over gRPC for internal callers
+    let grpc = tonic::transport::Server::builder().add_service(proto::portfolio_server::PortfolioServer::with_interceptor(
+        PortfolioRpc { state: app_state.clone() },
+        rpc::Authenticate::new(auth.clone()),
+    ));
+
+    // Create router; everything but the health check is scoped to the portfolio's tenant
     let app = Router::new()
+        .route("/positions", get(get_positions).post(create_position.layer(trading)))
+        .route("/positions/net", get(get_net_positions))
+        .route("/positions/:id", get(get_position).put(update_position.layer(trading)).delete(close_position.layer(trading)))
+        .route("/positions/:id/close", post(close_position_partial.layer(trading)))
+        .route("/pnl/realized", get(get_realized_pnl))
+        .route("/pnl/funding", get(get_funding_ledger))
+        .route("/funding", post(charge_funding_rate.layer(feeds)))
+        .route("/inventory", get(get_inventory))
+        .route("/performance", get(get_portfolio_metrics))
+        .route("/metrics/history", get(get_metrics_history))
+        .route("/risk", get(get_risk_report))
+        .route("/plan", post(generate_trade_plan.layer(trading)))
+        .route("/rebalance/preview", get(preview_rebalance))
+        .route("/rebalance/execute", post(execute_rebalance.layer(trading)))
+        .route("/prices", post(ingest_price_tick.layer(feeds)))
+        .route("/ws", get(portfolio_stream))
+        .route("/ws/pnl", get(pnl_stream))
+        .route("/admin/kill-switch", get(get_kill_switch))
+        .route("/admin/kill-switch/trip", post(trip_kill_switch))
+        .route("/admin/kill-switch/reset", post(reset_kill_switch))
+        .merge(sniper_oracle::http::feed_routes(oracle.clone()).route_layer(feeds))
+        .merge(sniper_oracle::http::query_routes(oracle))
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), tenant_guard))
+        .route_layer(require_tenant())
         .route("/health", get(health_check))
-        .route("/positions", get(get_positions).post(create_position))
-        .route("/positions/:id", get(get_position).put(update_position).delete(close_position))
-        .route("/metrics", get(get_portfolio_metrics))
-        .route("/plan", post(generate_trade_plan))
-        .layer(Extension(app_state));
+        .layer(Extension(app_state))
+        .layer(middleware::from_fn_with_state(idempotency, idempotent))
+        .layer(rate_limiter.layer())
+        .layer(AuthLayer::new(auth))
+        .layer(axum::middleware::from_fn(sniper_core::telemetry::trace_http));
+    let app = instrument(app, metrics)
+        .merge(openapi::router(ApiDoc::openapi().merge_from(sniper_oracle::http::OracleApi::openapi())));
     
     // Run server
-    let addr = format!("0.0.0.0:{}", args.port);
+    let addr = format!("0.0.0.0:{}", config.port.unwrap_or(DEFAULT_PORT));
     tracing::info!("Portfolio service listening on http://{}", addr);
     
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let grpc_addr = format!("0.0.0.0:{}", config.grpc_port.unwrap_or(DEFAULT_GRPC_PORT));
+    tracing::info!("Portfolio gRPC service listening on {}", grpc_addr);
+    
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let grpc_listener = tokio::net::TcpListener::bind(grpc_addr).await?;
+    tokio::try_join!(
+        async { Ok::<_, anyhow::Error>(sniper_core::shutdown::serve(listener, app, &shutdown).await?) },
+        rpc::serve(grpc_listener, grpc, &shutdown),
+    )?;
+    shutdown.join().await;
         
     Ok(())
 }
 
 /// Health check endpoint
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    security(()),
+    responses(
+        (status = 200, description = "Service is healthy", body = ApiResponse<String>),
+    )
+)]
 async fn health_check() -> Json<ApiResponse<String>> {
     let response = ApiResponse {
         success: true,
@@ -175,14 +611,46 @@ async fn health_check() -> Json<ApiResponse<String>> {
     Json(response)
 }
 
-/// Get all positions
+/// Reject callers acting for another tenant than the portfolio's
+async fn tenant_guard(
+    State(state): State<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !can_view_tenant(&viewer, &state.tenant_id) {
+        return reject(StatusCode::FORBIDDEN, "Portfolio belongs to another tenant".to_string());
+    }
+    next.run(request).await
+}
+
+/// Whether a position belongs to a tenant the viewer may see
+fn position_visible(position: &Position, viewer: &UserContext) -> bool {
+    position
+        .tenant_id
+        .as_deref()
+        .map(|tenant_id| can_view_tenant(viewer, tenant_id))
+        .unwrap_or(true)
+}
+
+/// Get the viewer's tenant's positions
+#[utoipa::path(
+    get,
+    path = "/positions",
+    tag = "portfolio",
+    responses(
+        (status = 200, description = "Open positions", body = ApiResponse<Vec<PositionResponse>>),
+    )
+)]
 async fn get_positions(
     Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
 ) -> Json<ApiResponse<Vec<PositionResponse>>> {
     let positions = {
         let manager = state.portfolio_manager.read().await;
         manager.list_positions()
             .iter()
+            .filter(|p| position_visible(p, &viewer))
             .map(|&p| PositionResponse::from((*p).clone()))
             .collect::<Vec<PositionResponse>>()
     };
@@ -195,102 +663,160 @@ async fn get_positions(
     Json(api_response)
 }
 
+/// Get the viewer's tenant's positions netted per symbol and chain
+///
+/// Lots opened separately are folded together, so this is the net exposure
+/// next to the gross lots of `/positions`, whichever netting mode is set.
+#[utoipa::path(
+    get,
+    path = "/positions/net",
+    tag = "portfolio",
+    responses(
+        (status = 200, description = "Net position per symbol and chain", body = ApiResponse<Vec<NetPosition>>),
+    )
+)]
+async fn get_net_positions(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+) -> Json<ApiResponse<Vec<NetPosition>>> {
+    let positions = {
+        let manager = state.portfolio_manager.read().await;
+        sniper_portfolio::net_positions(manager.list_positions().into_iter().filter(|p| position_visible(p, &viewer)))
+    };
+    
+    let api_response = ApiResponse {
+        success: true,
+        data: Some(positions),
+        message: None,
+    };
+    Json(api_response)
+}
+
 /// Get a specific position
+#[utoipa::path(
+    get,
+    path = "/positions/{id}",
+    tag = "portfolio",
+    params(("id" = String, Path, description = "Position ID")),
+    responses(
+        (status = 200, description = "The position", body = ApiResponse<PositionResponse>),
+        (status = 404, description = "No such position", body = ErrorResponse),
+    )
+)]
 async fn get_position(
     Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<ApiResponse<PositionResponse>> {
-    let position_result = {
+) -> Result<Json<ApiResponse<PositionResponse>>, ApiError> {
+    // Other tenants' positions are reported as missing rather than forbidden
+    let position = {
         let manager = state.portfolio_manager.read().await;
-        manager.get_position(&id).cloned()
-    };
-    
-    match position_result {
-        Some(position) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(PositionResponse::from(position)),
-                message: None,
-            };
-            Json(response)
-        },
-        None => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some("Position not found".to_string()),
-            };
-            Json(response)
-        },
+        manager.get_position(&id).filter(|position| position_visible(position, &viewer)).cloned()
     }
+    .ok_or_else(|| ApiError::not_found("Position not found"))?;
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(PositionResponse::from(position)),
+        message: None,
+    };
+    Ok(Json(response))
 }
 
 /// Create a new position
+#[utoipa::path(
+    post,
+    path = "/positions",
+    tag = "portfolio",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Replays the first response of a retried request")),
+    request_body = CreatePositionRequest,
+    responses(
+        (status = 200, description = "Position opened or netted", body = ApiResponse<PositionResponse>),
+        (status = 409, description = "Exceeds the allocation limits", body = ErrorResponse),
+        (status = 422, description = "Invalid position or rejected by pre-trade risk checks", body = ErrorResponse),
+        (status = 503, description = "Kill switch tripped", body = ErrorResponse),
+    )
+)]
 async fn create_position(
     Extension(state): Extension<Arc<AppState>>,
     Json(payload): Json<CreatePositionRequest>,
-) -> Json<ApiResponse<PositionResponse>> {
+) -> Result<Json<ApiResponse<PositionResponse>>, ApiError> {
     let chain_ref = ChainRef {
         name: payload.chain_name,
         id: payload.chain_id,
     };
     
-    // Calculate initial PnL
-    let pnl = (payload.current_price - payload.entry_price) * payload.amount;
-    let pnl_percentage = if payload.entry_price > 0.0 {
-        ((payload.current_price - payload.entry_price) / payload.entry_price) * 100.0
-    } else {
-        0.0
-    };
-    
-    let position = Position {
-        id: Uuid::new_v4().to_string(),
-        symbol: payload.symbol,
+    let mut fill = PositionFill {
+        symbol: payload.symbol.clone(),
         chain: chain_ref,
-        amount: payload.amount,
-        entry_price: payload.entry_price,
-        current_price: payload.current_price,
         side: payload.side,
+        amount: payload.amount,
+        price: payload.entry_price,
         leverage: payload.leverage,
-        pnl,
-        pnl_percentage,
-        created_at: std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs(),
-        updated_at: std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs(),
+        fee: payload.fee,
     };
+    if let Some(receipt) = &payload.receipt {
+        let native_price = native_price(&state, payload.chain_id)
+            .await
+            .map_err(|e| ApiError::invalid(format!("Cannot price the receipt's gas: {}", e)))?;
+        fill = fill
+            .with_receipt_fees(receipt, native_price)
+            .map_err(|e| ApiError::from(e).context("Failed to create position"))?;
+    }
     
-    let result = state.portfolio_manager.write().await.add_position(position.clone());
-    match result {
-        Ok(_) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(PositionResponse::from(position)),
-                message: Some("Position created successfully".to_string()),
-            };
-            Json(response)
-        },
-        Err(e) => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some(format!("Failed to create position: {}", e)),
-            };
-            Json(response)
-        },
+    // Apply the fill per the netting mode, then mark the symbol at the current price
+    let (netted, position_id, position) = {
+        let mut manager = state.portfolio_manager.write().await;
+        let outcome = manager
+            .apply_fill(fill)
+            .map_err(|e| ApiError::from(e).context("Failed to create position"))?;
+        manager
+            .mark_to_market(&payload.symbol, payload.current_price)
+            .map_err(|e| ApiError::from(e).context("Failed to mark position"))?;
+        let position_id = outcome.position.as_ref().map(|position| position.id.clone());
+        let position = outcome
+            .position
+            .and_then(|position| manager.get_position(&position.id).cloned());
+        (outcome.netted, position_id, position)
+    };
+    if !netted {
+        state.metrics.increment_counter("positions_opened_total");
     }
+    if let Some(position_id) = position_id {
+        let event = if netted { "netted" } else { "opened" };
+        publish_position_update(&state, event, position_id, &payload.symbol, position.clone(), None);
+    }
+    let message = if netted {
+        "Fill netted into existing position"
+    } else {
+        "Position created successfully"
+    };
+    let response = ApiResponse {
+        success: true,
+        data: position.map(PositionResponse::from),
+        message: Some(message.to_string()),
+    };
+    Ok(Json(response))
 }
 
 /// Update an existing position
+#[utoipa::path(
+    put,
+    path = "/positions/{id}",
+    tag = "portfolio",
+    params(("id" = String, Path, description = "Position ID")),
+    request_body = UpdatePositionRequest,
+    responses(
+        (status = 200, description = "Position marked at the new price", body = ApiResponse<PositionResponse>),
+        (status = 404, description = "No such position", body = ErrorResponse),
+        (status = 422, description = "Invalid price", body = ErrorResponse),
+    )
+)]
 async fn update_position(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(id): axum::extract::Path<String>,
     Json(payload): Json<UpdatePositionRequest>,
-) -> Json<ApiResponse<PositionResponse>> {
+) -> Result<Json<ApiResponse<PositionResponse>>, ApiError> {
     let position_result = {
         let manager = state.portfolio_manager.read().await;
         manager.get_position(&id).cloned()
@@ -301,9 +827,14 @@ async fn update_position(
             existing_position.current_price = payload.current_price;
             
             // Recalculate PnL
-            existing_position.pnl = (payload.current_price - existing_position.entry_price) * existing_position.amount;
-            existing_position.pnl_percentage = if existing_position.entry_price > 0.0 {
-                ((payload.current_price - existing_position.entry_price) / existing_position.entry_price) * 100.0
+            let out_of_range = || ApiError::invalid(format!("PnL at {} is out of the decimal range", payload.current_price));
+            let change = payload
+                .current_price
+                .checked_sub(existing_position.entry_price)
+                .ok_or_else(out_of_range)?;
+            existing_position.pnl = change.checked_mul(existing_position.amount).ok_or_else(out_of_range)?;
+            existing_position.pnl_percentage = if existing_position.entry_price.is_positive() {
+                change.checked_div(existing_position.entry_price).ok_or_else(out_of_range)?.to_f64() * 100.0
             } else {
                 0.0
             };
@@ -313,81 +844,254 @@ async fn update_position(
                 .unwrap()
                 .as_secs();
             
-            let result = state.portfolio_manager.write().await.update_position(&id, existing_position.clone());
-            match result {
-                Ok(_) => {
-                    let response = ApiResponse {
-                        success: true,
-                        data: Some(PositionResponse::from(existing_position)),
-                        message: Some("Position updated successfully".to_string()),
-                    };
-                    Json(response)
-                },
-                Err(e) => {
-                    let response = ApiResponse {
-                        success: false,
-                        data: None,
-                        message: Some(format!("Failed to update position: {}", e)),
-                    };
-                    Json(response)
-                },
-            }
-        },
-        None => {
+            state.portfolio_manager.write().await
+                .update_position(&id, existing_position.clone())
+                .map_err(|e| ApiError::from(e).context("Failed to update position"))?;
+            publish_position_update(
+                &state,
+                "updated",
+                id.clone(),
+                &existing_position.symbol,
+                Some(existing_position.clone()),
+                None,
+            );
+            publish_pnl_update(&state, &existing_position.symbol, existing_position.current_price).await;
             let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some("Position not found".to_string()),
+                success: true,
+                data: Some(PositionResponse::from(existing_position)),
+                message: Some("Position updated successfully".to_string()),
             };
-            Json(response)
+            Ok(Json(response))
         },
+        None => Err(ApiError::not_found("Position not found")),
     }
 }
 
-/// Close a position
+/// Close a position at its marked price, booking realized PnL, fees and funding
+#[utoipa::path(
+    delete,
+    path = "/positions/{id}",
+    tag = "portfolio",
+    params(("id" = String, Path, description = "Position ID")),
+    responses(
+        (status = 200, description = "Position closed", body = ApiResponse<bool>),
+        (status = 404, description = "No such position", body = ErrorResponse),
+    )
+)]
 async fn close_position(
     Extension(state): Extension<Arc<AppState>>,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<ApiResponse<bool>> {
-    let result = state.portfolio_manager.write().await.remove_position(&id);
-    match result {
-        Ok(_) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(true),
-                message: Some("Position closed successfully".to_string()),
-            };
-            Json(response)
-        },
-        Err(e) => {
-            let response = ApiResponse {
-                success: false,
-                data: Some(false),
-                message: Some(format!("Failed to close position: {}", e)),
-            };
-            Json(response)
-        },
-    }
+) -> Result<Json<ApiResponse<bool>>, ApiError> {
+    let entry = state
+        .portfolio_manager
+        .write()
+        .await
+        .close_position_at_mark(&id)
+        .map_err(|e| ApiError::from(e).context("Failed to close position"))?;
+    state.metrics.increment_counter("positions_closed_total");
+    publish_position_update(&state, "closed", id.clone(), &entry.symbol, None, Some(entry.clone()));
+    let response = ApiResponse {
+        success: true,
+        data: Some(true),
+        message: Some("Position closed successfully".to_string()),
+    };
+    Ok(Json(response))
+}
+
+/// Close part of a position, booking realized PnL
+#[utoipa::path(
+    post,
+    path = "/positions/{id}/close",
+    tag = "portfolio",
+    params(("id" = String, Path, description = "Position ID"), ("Idempotency-Key" = Option<String>, Header, description = "Replays the first response of a retried request")),
+    request_body = ClosePositionRequest,
+    responses(
+        (status = 200, description = "Realized PnL of the closed amount", body = ApiResponse<RealizedPnlEntry>),
+        (status = 404, description = "No such position", body = ErrorResponse),
+        (status = 422, description = "Invalid amount or price", body = ErrorResponse),
+    )
+)]
+async fn close_position_partial(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(payload): Json<ClosePositionRequest>,
+) -> Result<Json<ApiResponse<RealizedPnlEntry>>, ApiError> {
+    let (entry, remaining) = {
+        let mut manager = state.portfolio_manager.write().await;
+        let entry = manager
+            .close_position_partial_with_fee(&id, payload.amount, payload.exit_price, payload.fee)
+            .map_err(|e| ApiError::from(e).context("Failed to close position"))?;
+        (entry, manager.get_position(&id).cloned())
+    };
+    let event = if remaining.is_none() {
+        state.metrics.increment_counter("positions_closed_total");
+        "closed"
+    } else {
+        "reduced"
+    };
+    publish_position_update(&state, event, id.clone(), &entry.symbol, remaining, Some(entry.clone()));
+    let response = ApiResponse {
+        success: true,
+        data: Some(entry),
+        message: Some("Position closed successfully".to_string()),
+    };
+    Ok(Json(response))
+}
+
+/// Get the realized PnL ledger
+#[utoipa::path(
+    get,
+    path = "/pnl/realized",
+    tag = "portfolio",
+    responses(
+        (status = 200, description = "Realized PnL entries", body = ApiResponse<Vec<RealizedPnlEntry>>),
+    )
+)]
+async fn get_realized_pnl(
+    Extension(state): Extension<Arc<AppState>>,
+) -> Json<ApiResponse<Vec<RealizedPnlEntry>>> {
+    let ledger = state.portfolio_manager.read().await.realized_ledger().to_vec();
+    let response = ApiResponse {
+        success: true,
+        data: Some(ledger),
+        message: None,
+    };
+    Json(response)
+}
+
+/// Get the funding charged to positions
+#[utoipa::path(
+    get,
+    path = "/pnl/funding",
+    tag = "portfolio",
+    responses(
+        (status = 200, description = "Funding charges, oldest first", body = ApiResponse<Vec<FundingCharge>>),
+    )
+)]
+async fn get_funding_ledger(
+    Extension(state): Extension<Arc<AppState>>,
+) -> Json<ApiResponse<Vec<FundingCharge>>> {
+    let ledger = state.portfolio_manager.read().await.funding_ledger().to_vec();
+    let response = ApiResponse {
+        success: true,
+        data: Some(ledger),
+        message: None,
+    };
+    Json(response)
+}
+
+/// Charge a period's funding rate to the leveraged positions in a symbol
+#[utoipa::path(
+    post,
+    path = "/funding",
+    tag = "portfolio",
+    request_body = FundingRateRequest,
+    responses(
+        (status = 200, description = "Funding charged per position", body = ApiResponse<Vec<FundingCharge>>),
+        (status = 422, description = "Invalid funding rate", body = ErrorResponse),
+    )
+)]
+async fn charge_funding_rate(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(payload): Json<FundingRateRequest>,
+) -> Result<Json<ApiResponse<Vec<FundingCharge>>>, ApiError> {
+    let charges = state
+        .portfolio_manager
+        .write()
+        .await
+        .apply_funding_rate(&payload.symbol, payload.rate)
+        .map_err(|e| ApiError::from(e).context("Failed to charge funding"))?;
+    let response = ApiResponse {
+        success: true,
+        data: Some(charges),
+        message: None,
+    };
+    Ok(Json(response))
+}
+
+/// Get net inventory per symbol
+#[utoipa::path(
+    get,
+    path = "/inventory",
+    tag = "portfolio",
+    responses(
+        (status = 200, description = "Net inventory per symbol", body = ApiResponse<HashMap<String, f64>>),
+    )
+)]
+async fn get_inventory(
+    Extension(state): Extension<Arc<AppState>>,
+) -> Json<ApiResponse<HashMap<String, f64>>> {
+    let response = ApiResponse {
+        success: true,
+        data: Some(state.inventory.snapshot()),
+        message: None,
+    };
+    Json(response)
 }
 
 /// Get portfolio metrics
+#[utoipa::path(
+    get,
+    path = "/performance",
+    tag = "portfolio",
+    responses(
+        (status = 200, description = "Performance and risk metrics", body = ApiResponse<PortfolioMetricsResponse>),
+        (status = 422, description = "A PnL total is out of the decimal range", body = ErrorResponse),
+    )
+)]
 async fn get_portfolio_metrics(
     Extension(state): Extension<Arc<AppState>>,
-) -> Json<ApiResponse<PortfolioMetricsResponse>> {
+) -> Result<Json<ApiResponse<PortfolioMetricsResponse>>, ApiError> {
     let metrics = {
         let manager = state.portfolio_manager.read().await;
-        manager.calculate_performance()
+        manager
+            .calculate_performance()
+            .map_err(|e| ApiError::from(e).context("Failed to calculate performance"))?
     };
     
     let response = PortfolioMetricsResponse {
         total_value: metrics.total_value,
         total_pnl: metrics.total_pnl,
+        realized_pnl: metrics.realized_pnl,
+        unrealized_pnl: metrics.unrealized_pnl,
+        fees: metrics.fees,
+        funding: metrics.funding,
+        net_pnl: metrics.net_pnl,
         total_pnl_percentage: metrics.total_pnl_percentage,
         win_rate: metrics.win_rate,
         profit_factor: metrics.profit_factor,
         sharpe_ratio: metrics.sharpe_ratio,
         max_drawdown: metrics.max_drawdown,
         positions_count: metrics.positions_count,
+        risk: metrics.risk,
+        benchmark: metrics.benchmark,
+    };
+    
+    let api_response = ApiResponse {
+        success: true,
+        data: Some(response),
+        message: None,
+    };
+    Ok(Json(api_response))
+}
+
+/// Get the equity curve and time-weighted performance metrics
+#[utoipa::path(
+    get,
+    path = "/metrics/history",
+    tag = "portfolio",
+    responses(
+        (status = 200, description = "Equity curve and time-weighted metrics", body = ApiResponse<EquityHistoryResponse>),
+    )
+)]
+async fn get_metrics_history(
+    Extension(state): Extension<Arc<AppState>>,
+) -> Json<ApiResponse<EquityHistoryResponse>> {
+    let manager = state.portfolio_manager.read().await;
+    let response = EquityHistoryResponse {
+        metrics: manager.historical_metrics(),
+        benchmark: manager.benchmark_metrics(),
+        points: manager.equity_curve().points().to_vec(),
     };
     
     let api_response = ApiResponse {
@@ -398,17 +1102,163 @@ async fn get_portfolio_metrics(
     Json(api_response)
 }
 
+/// Get Value-at-Risk, exposure by chain and asset, and concentration
+#[utoipa::path(
+    get,
+    path = "/risk",
+    tag = "portfolio",
+    params(RiskParams),
+    responses(
+        (status = 200, description = "VaR, exposure and concentration", body = ApiResponse<RiskReport>),
+        (status = 422, description = "Confidence out of range", body = ErrorResponse),
+    )
+)]
+async fn get_risk_report(
+    Extension(state): Extension<Arc<AppState>>,
+    Query(params): Query<RiskParams>,
+) -> Result<Json<ApiResponse<RiskReport>>, ApiError> {
+    let confidence = params.confidence.unwrap_or(sniper_portfolio::risk::DEFAULT_VAR_CONFIDENCE);
+    if !(confidence > 0.5 && confidence < 1.0) {
+        return Err(ApiError::invalid(format!("Confidence must be between 0.5 and 1, got {}", confidence)));
+    }
+    
+    let report = state
+        .portfolio_manager
+        .read()
+        .await
+        .risk_report(confidence)
+        .map_err(|e| ApiError::from(e).context("Failed to build risk report"))?;
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(report),
+        message: None,
+    }))
+}
+
+/// Periodically record portfolio value into the equity curve
+async fn sample_equity(state: Arc<AppState>, sample_interval_secs: u64) {
+    let mut interval = tokio::time::interval(Duration::from_secs(sample_interval_secs.max(1)));
+    loop {
+        interval.tick().await;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let benchmark = {
+            let mut manager = state.portfolio_manager.write().await;
+            manager.record_equity(now);
+            manager.benchmark_metrics()
+        };
+        set_benchmark_gauges(&state, benchmark);
+    }
+}
+
+/// Prometheus gauges of the benchmark-relative metrics
+const BENCHMARK_GAUGES: [(&str, &str); 4] = [
+    ("portfolio_benchmark_beta", "Beta of the equity curve against the benchmark"),
+    ("portfolio_benchmark_alpha_pct", "Annualized alpha against the benchmark, in percent"),
+    ("portfolio_benchmark_tracking_error_pct", "Annualized tracking error against the benchmark, in percent"),
+    ("portfolio_benchmark_information_ratio", "Information ratio against the benchmark"),
+];
+
+/// Export the benchmark-relative metrics on `/metrics`, if a benchmark is configured
+fn set_benchmark_gauges(state: &AppState, benchmark: Option<BenchmarkMetrics>) {
+    let Some(benchmark) = benchmark else {
+        return;
+    };
+    let values = [benchmark.beta, benchmark.alpha_pct, benchmark.tracking_error_pct, benchmark.information_ratio];
+    for ((name, _), value) in BENCHMARK_GAUGES.iter().zip(values) {
+        state.metrics.set_gauge(name, value);
+    }
+}
+
+/// Mark every position from the live price feeds on an interval
+///
+/// Each pass prices the symbols of open positions, of the benchmark and the
+/// feeds' configured symbols, marks them all under one lock, samples the
+/// equity curve and closes positions past their stop-loss or take-profit.
+async fn mark_to_market_loop(state: Arc<AppState>, prices: Arc<PriceCache>, interval_ms: u64) {
+    let mut interval = tokio::time::interval(Duration::from_millis(interval_ms.max(1)));
+    loop {
+        interval.tick().await;
+        let mut symbols: Vec<String> = {
+            let manager = state.portfolio_manager.read().await;
+            let benchmark = manager.benchmark().into_iter().flat_map(|benchmark| benchmark.config().weights.keys());
+            manager.list_positions().into_iter().map(|position| position.symbol.clone()).chain(benchmark.cloned()).collect()
+        };
+        symbols.extend(prices.config().symbols.iter().cloned());
+        symbols.sort();
+        symbols.dedup();
+        
+        let mut quotes = Vec::with_capacity(symbols.len());
+        for symbol in &symbols {
+            match prices.get(symbol).await {
+                Ok(quote) => match Decimal::from_f64(quote.price) {
+                    Some(price) => quotes.push((quote, price)),
+                    None => tracing::debug!("{} not marked: invalid price {}", symbol, quote.price),
+                },
+                Err(e) => tracing::debug!("{} not marked: {}", symbol, e),
+            }
+        }
+        if quotes.is_empty() {
+            continue;
+        }
+        
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let (exits, benchmark) = {
+            let mut manager = state.portfolio_manager.write().await;
+            for (quote, price) in &quotes {
+                if let Err(e) = manager.mark_to_market(&quote.symbol, *price) {
+                    tracing::warn!("{} not marked: {}", quote.symbol, e);
+                }
+            }
+            manager.record_equity(now);
+            (manager.evaluate_exits(), manager.benchmark_metrics())
+        };
+        set_benchmark_gauges(&state, benchmark);
+        
+        for (quote, price) in &quotes {
+            state.metrics.increment_counter("price_ticks_total");
+            publish_pnl_update(&state, &quote.symbol, *price).await;
+        }
+        for exit in exits {
+            state.metrics.increment_counter("exits_triggered_total");
+            if let Err(e) = state.executor.execute_trade(&exit.plan).await.and_then(ExecError::ensure_success) {
+                tracing::warn!("Exit plan for position {} failed: {}", exit.realized.position_id, e);
+            }
+            state.metrics.increment_counter("positions_closed_total");
+            let (position_id, symbol) = (exit.realized.position_id.clone(), exit.realized.symbol.clone());
+            publish_position_update(&state, "closed", position_id, &symbol, None, Some(exit.realized));
+        }
+    }
+}
+
 /// Generate a trade plan
+#[utoipa::path(
+    post,
+    path = "/plan",
+    tag = "portfolio",
+    request_body = GenerateTradePlanRequest,
+    responses(
+        (status = 200, description = "Trade plan within the allocation limits", body = ApiResponse<TradePlan>),
+        (status = 409, description = "Exceeds the allocation limits", body = ErrorResponse),
+        (status = 422, description = "Invalid request or rejected by pre-trade risk checks", body = ErrorResponse),
+        (status = 503, description = "Kill switch tripped", body = ErrorResponse),
+    )
+)]
 async fn generate_trade_plan(
     Extension(state): Extension<Arc<AppState>>,
     Json(payload): Json<GenerateTradePlanRequest>,
-) -> Json<ApiResponse<TradePlan>> {
+) -> Result<Json<ApiResponse<TradePlan>>, ApiError> {
     let chain_ref = ChainRef {
         name: payload.chain_name,
         id: payload.chain_id,
     };
     
-    let plan_result = {
+    let trade_plan = {
         let manager = state.portfolio_manager.read().await;
         manager.generate_trade_plan(
             &payload.symbol,
@@ -416,25 +1266,629 @@ async fn generate_trade_plan(
             payload.amount,
             &payload.side,
         )
+    }
+    .map_err(|e| ApiError::from(e).context("Failed to generate trade plan"))?;
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(trade_plan),
+        message: Some("Trade plan generated successfully".to_string()),
     };
+    Ok(Json(response))
+}
+
+/// Compare allocations to the diversification targets and plan the trades restoring them
+#[utoipa::path(
+    get,
+    path = "/rebalance/preview",
+    tag = "rebalance",
+    responses(
+        (status = 200, description = "Allocations and planned trades", body = ApiResponse<RebalancePreview>),
+    )
+)]
+async fn preview_rebalance(
+    Extension(state): Extension<Arc<AppState>>,
+) -> Json<ApiResponse<RebalancePreview>> {
+    let preview = state.rebalancer.preview(&*state.portfolio_manager.read().await);
+    Json(ApiResponse {
+        success: true,
+        data: Some(preview),
+        message: None,
+    })
+}
+
+/// Execute the rebalancing trades, booking each one that fills into the portfolio
+///
+/// Trades are executed in order and independently; a failed trade is
+/// reported and the rest still run.
+#[utoipa::path(
+    post,
+    path = "/rebalance/execute",
+    tag = "rebalance",
+    responses(
+        (status = 200, description = "Planned trades and their outcomes", body = ApiResponse<RebalanceExecution>),
+    )
+)]
+async fn execute_rebalance(
+    Extension(state): Extension<Arc<AppState>>,
+) -> Json<ApiResponse<RebalanceExecution>> {
+    let preview = state.rebalancer.preview(&*state.portfolio_manager.read().await);
     
-    match plan_result {
-        Ok(trade_plan) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(trade_plan),
-                message: Some("Trade plan generated successfully".to_string()),
-            };
-            Json(response)
+    let mut results = Vec::with_capacity(preview.trades.len());
+    for trade in &preview.trades {
+        let mut result = RebalanceTradeResult {
+            class: trade.class.clone(),
+            symbol: trade.symbol.clone(),
+            side: trade.side.clone(),
+            notional: trade.notional,
+            receipt: None,
+            error: None,
+        };
+        match state.executor.execute_trade(&trade.plan).await {
+            Ok(receipt) if receipt.success => {
+                let fee = gas_fee(&state, &receipt, trade.plan.chain.id).await;
+                let applied = state.portfolio_manager.write().await.apply_rebalance_trade(trade, fee);
+                match applied {
+                    Ok(outcome) => publish_rebalance_update(&state, trade.side == "sell", &trade.symbol, outcome),
+                    Err(e) => result.error = Some(format!("Executed but not booked: {}", e)),
+                }
+                result.receipt = Some(receipt);
+            },
+            Ok(receipt) => {
+                result.error = receipt.failure_reason.clone().or_else(|| Some("Trade failed".to_string()));
+                result.receipt = Some(receipt);
+            },
+            Err(e) => result.error = Some(e.to_string()),
+        }
+        results.push(result);
+    }
+    
+    let failed = results.iter().filter(|result| result.error.is_some()).count();
+    let message = format!("Executed {} of {} rebalancing trades", results.len() - failed, results.len());
+    Json(ApiResponse {
+        success: failed == 0,
+        data: Some(RebalanceExecution { preview, results }),
+        message: Some(message),
+    })
+}
+
+/// Oracle price of a chain's gas token
+async fn native_price(state: &AppState, chain_id: u64) -> Result<Decimal> {
+    let asset = state
+        .native_assets
+        .get(&chain_id.to_string())
+        .ok_or_else(|| anyhow::anyhow!("no gas token configured for chain {}", chain_id))?;
+    let price = state.oracle.oracle.aggregate(asset).await?.price;
+    Decimal::from_f64(price).ok_or_else(|| anyhow::anyhow!("invalid {} price {}", asset, price))
+}
+
+/// Gas paid by a receipt in the quote currency, or nothing if it cannot be priced
+async fn gas_fee(state: &AppState, receipt: &ExecReceipt, chain_id: u64) -> Decimal {
+    if receipt.fees_paid_wei == 0 {
+        return Decimal::ZERO;
+    }
+    let fee = native_price(state, chain_id).await.and_then(|native_price| {
+        Decimal::from_wei(receipt.fees_paid_wei)
+            .and_then(|gas| gas.checked_mul(native_price))
+            .ok_or_else(|| anyhow::anyhow!("{} wei is out of range", receipt.fees_paid_wei))
+    });
+    fee.unwrap_or_else(|e| {
+        tracing::warn!("Trade {} booked without its gas fee: {}", receipt.tx_hash, e);
+        Decimal::ZERO
+    })
+}
+
+/// Publish the position change made by a booked rebalancing trade
+fn publish_rebalance_update(state: &AppState, sell: bool, symbol: &str, outcome: FillOutcome) {
+    let position_id = match (&outcome.position, &outcome.realized) {
+        (Some(position), _) => position.id.clone(),
+        (None, Some(realized)) => realized.position_id.clone(),
+        (None, None) => return,
+    };
+    let event = match (sell, &outcome.position) {
+        (true, None) => {
+            state.metrics.increment_counter("positions_closed_total");
+            "closed"
         },
-        Err(e) => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some(format!("Failed to generate trade plan: {}", e)),
-            };
-            Json(response)
+        (true, Some(_)) => "reduced",
+        (false, _) if outcome.netted => "netted",
+        (false, _) => {
+            state.metrics.increment_counter("positions_opened_total");
+            "opened"
         },
+    };
+    publish_position_update(state, event, position_id, symbol, outcome.position, outcome.realized);
+}
+
+/// Ingest a pool price tick, repricing positions at the oracle price and streaming the PnL change
+///
+/// Ticks too far from the oracle price are rejected as likely manipulation.
+#[utoipa::path(
+    post,
+    path = "/prices",
+    tag = "portfolio",
+    request_body = PriceTickRequest,
+    responses(
+        (status = 200, description = "Positions marked at the price", body = ApiResponse<Vec<PositionResponse>>),
+        (status = 422, description = "Price rejected by the oracle check", body = ErrorResponse),
+    )
+)]
+async fn ingest_price_tick(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(payload): Json<PriceTickRequest>,
+) -> Result<Json<ApiResponse<Vec<PositionResponse>>>, ApiError> {
+    state.metrics.increment_counter("price_ticks_total");
+    state.oracle.twap.record(&payload.symbol, payload.price, chrono::Utc::now());
+    
+    let checked = state.oracle.oracle.check_pool_price(&payload.symbol, payload.price).await.and_then(|aggregated| {
+        Decimal::from_f64(aggregated.price).ok_or_else(|| anyhow::anyhow!("invalid oracle price {}", aggregated.price))
+    });
+    let mark_price = checked.map_err(|e| ApiError::invalid(format!("Price tick rejected: {}", e)))?;
+    
+    let updated = state
+        .portfolio_manager
+        .write()
+        .await
+        .mark_to_market(&payload.symbol, mark_price)
+        .map_err(|e| ApiError::from(e).context("Price tick rejected"))?;
+    publish_pnl_update(&state, &payload.symbol, mark_price).await;
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(updated.into_iter().map(PositionResponse::from).collect()),
+        message: None,
+    };
+    Ok(Json(response))
+}
+
+/// Publish the current PnL of a symbol to stream subscribers
+async fn publish_pnl_update(state: &AppState, symbol: &str, price: Decimal) {
+    // Nobody is listening, skip building the update
+    if state.pnl_updates.receiver_count() == 0 {
+        return;
+    }
+    
+    let update = {
+        let manager = state.portfolio_manager.read().await;
+        let positions: Vec<PositionResponse> = manager.list_positions()
+            .into_iter()
+            .filter(|p| p.symbol == symbol)
+            .map(|p| PositionResponse::from(p.clone()))
+            .collect();
+        let Some(symbol_pnl) = Decimal::checked_sum(positions.iter().map(|p| p.pnl)) else {
+            tracing::warn!("Failed to publish {} PnL: it is out of the decimal range", symbol);
+            return;
+        };
+        let total_pnl = match manager.calculate_performance() {
+            Ok(metrics) => metrics.total_pnl,
+            Err(e) => {
+                tracing::warn!("Failed to publish {} PnL: {}", symbol, e);
+                return;
+            },
+        };
+        PnlUpdate {
+            symbol: symbol.to_string(),
+            price,
+            symbol_pnl,
+            positions,
+            total_pnl,
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        }
+    };
+    let _ = state.pnl_updates.send(update);
+}
+
+/// Publish a position change to stream subscribers
+fn publish_position_update(
+    state: &AppState,
+    event: &str,
+    position_id: String,
+    symbol: &str,
+    position: Option<Position>,
+    realized: Option<RealizedPnlEntry>,
+) {
+    // Nobody is listening, skip building the update
+    if state.position_updates.receiver_count() == 0 {
+        return;
+    }
+    
+    let update = PositionUpdate {
+        event: event.to_string(),
+        position_id,
+        symbol: symbol.to_string(),
+        position: position.map(PositionResponse::from),
+        realized,
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    };
+    let _ = state.position_updates.send(update);
+}
+
+/// Stream position changes and PnL updates over a WebSocket
+///
+/// Viewers may only subscribe to the portfolio's tenant unless they hold `view_all_data`.
+#[utoipa::path(
+    get,
+    path = "/ws",
+    tag = "streams",
+    params(PnlStreamParams),
+    responses(
+        (status = 101, description = "WebSocket of `StreamMessage` JSON messages"),
+        (status = 403, description = "Tenant not accessible"),
+    )
+)]
+async fn portfolio_stream(
+    ws: WebSocketUpgrade,
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    Query(params): Query<PnlStreamParams>,
+) -> Response {
+    if !can_view_tenant(&viewer, &state.tenant_id) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    
+    let positions = state.position_updates.subscribe();
+    let pnl = state.pnl_updates.subscribe();
+    let tenant_id = state.tenant_id.clone();
+    ws.on_upgrade(move |socket| stream_portfolio_updates(socket, tenant_id, positions, pnl, params))
+}
+
+/// Forward position changes and throttled PnL updates to a WebSocket client
+async fn stream_portfolio_updates(
+    mut socket: WebSocket,
+    tenant_id: String,
+    mut positions: broadcast::Receiver<PositionUpdate>,
+    mut pnl: broadcast::Receiver<PnlUpdate>,
+    params: PnlStreamParams,
+) {
+    let min_interval = Duration::from_millis(params.throttle_ms.unwrap_or(DEFAULT_THROTTLE_MS).max(1));
+    let symbols: Option<Vec<String>> = params.symbols
+        .map(|s| s.split(',').map(|symbol| symbol.trim().to_string()).collect());
+    let wanted = |symbol: &str| {
+        symbols.as_ref().map(|symbols| symbols.iter().any(|s| s == symbol)).unwrap_or(true)
+    };
+    let mut throttle = SymbolThrottle::new(min_interval);
+    let mut flush = tokio::time::interval(min_interval);
+    
+    loop {
+        // Position changes are sent as they happen, PnL updates are throttled per symbol
+        let outgoing: Vec<StreamUpdate> = tokio::select! {
+            update = positions.recv() => {
+                match update {
+                    Ok(update) => vec![StreamUpdate::Position(update)],
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Portfolio stream lagged, skipped {} position updates", skipped);
+                        continue;
+                    },
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            },
+            update = pnl.recv() => {
+                match update {
+                    Ok(update) => {
+                        if !wanted(&update.symbol) {
+                            continue;
+                        }
+                        throttle.offer(update, Instant::now()).map(StreamUpdate::Pnl).into_iter().collect()
+                    },
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Portfolio stream lagged, skipped {} PnL updates", skipped);
+                        continue;
+                    },
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            },
+            _ = flush.tick() => {
+                throttle.drain_due(Instant::now()).into_iter().map(StreamUpdate::Pnl).collect()
+            },
+            message = socket.recv() => {
+                match message {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    _ => continue,
+                }
+            },
+        };
+        
+        for update in outgoing.into_iter().filter(|update| wanted(update.symbol())) {
+            let message = StreamMessage {
+                tenant_id: tenant_id.clone(),
+                update,
+            };
+            if send_stream_message(&mut socket, &message).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Send a portfolio stream message as a JSON text frame
+async fn send_stream_message(socket: &mut WebSocket, message: &StreamMessage) -> Result<()> {
+    let text = serde_json::to_string(message)?;
+    socket.send(Message::Text(text)).await?;
+    Ok(())
+}
+
+/// Stream PnL updates over a WebSocket
+#[utoipa::path(
+    get,
+    path = "/ws/pnl",
+    tag = "streams",
+    params(PnlStreamParams),
+    responses(
+        (status = 101, description = "WebSocket of `PnlUpdate` JSON messages"),
+    )
+)]
+async fn pnl_stream(
+    ws: WebSocketUpgrade,
+    Extension(state): Extension<Arc<AppState>>,
+    Query(params): Query<PnlStreamParams>,
+) -> Response {
+    let updates = state.pnl_updates.subscribe();
+    ws.on_upgrade(move |socket| stream_pnl_updates(socket, updates, params))
+}
+
+/// Forward PnL updates to a WebSocket client, throttled per symbol
+async fn stream_pnl_updates(
+    mut socket: WebSocket,
+    mut updates: broadcast::Receiver<PnlUpdate>,
+    params: PnlStreamParams,
+) {
+    let min_interval = Duration::from_millis(params.throttle_ms.unwrap_or(DEFAULT_THROTTLE_MS).max(1));
+    let symbols: Option<Vec<String>> = params.symbols
+        .map(|s| s.split(',').map(|symbol| symbol.trim().to_string()).collect());
+    let mut throttle = SymbolThrottle::new(min_interval);
+    let mut flush = tokio::time::interval(min_interval);
+    
+    loop {
+        tokio::select! {
+            update = updates.recv() => {
+                match update {
+                    Ok(update) => {
+                        if let Some(symbols) = &symbols {
+                            if !symbols.contains(&update.symbol) {
+                                continue;
+                            }
+                        }
+                        if let Some(update) = throttle.offer(update, Instant::now()) {
+                            if send_pnl_update(&mut socket, &update).await.is_err() {
+                                break;
+                            }
+                        }
+                    },
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("PnL stream lagged, skipped {} updates", skipped);
+                    },
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            },
+            _ = flush.tick() => {
+                for update in throttle.drain_due(Instant::now()) {
+                    if send_pnl_update(&mut socket, &update).await.is_err() {
+                        return;
+                    }
+                }
+            },
+            message = socket.recv() => {
+                match message {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    _ => {},
+                }
+            },
+        }
+    }
+}
+
+/// Send a PnL update as a JSON text frame
+async fn send_pnl_update(socket: &mut WebSocket, update: &PnlUpdate) -> Result<()> {
+    let text = serde_json::to_string(update)?;
+    socket.send(Message::Text(text)).await?;
+    Ok(())
+}
+
+/// Per-symbol throttle that keeps only the latest update while rate limited
+struct SymbolThrottle {
+    min_interval: Duration,
+    last_sent: HashMap<String, Instant>,
+    pending: HashMap<String, PnlUpdate>,
+}
+
+impl SymbolThrottle {
+    /// Create a new throttle
+    fn new(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            last_sent: HashMap::new(),
+            pending: HashMap::new(),
+        }
+    }
+    
+    /// Offer an update, returning it if it may be sent now
+    fn offer(&mut self, update: PnlUpdate, now: Instant) -> Option<PnlUpdate> {
+        if self.is_due(&update.symbol, now) {
+            self.pending.remove(&update.symbol);
+            self.last_sent.insert(update.symbol.clone(), now);
+            Some(update)
+        } else {
+            // Coalesce: only the latest update per symbol is kept
+            self.pending.insert(update.symbol.clone(), update);
+            None
+        }
+    }
+    
+    /// Take pending updates whose symbol is no longer rate limited
+    fn drain_due(&mut self, now: Instant) -> Vec<PnlUpdate> {
+        let due: Vec<String> = self.pending
+            .keys()
+            .filter(|symbol| self.is_due(symbol, now))
+            .cloned()
+            .collect();
+        
+        due.into_iter()
+            .filter_map(|symbol| {
+                self.last_sent.insert(symbol.clone(), now);
+                self.pending.remove(&symbol)
+            })
+            .collect()
+    }
+    
+    fn is_due(&self, symbol: &str, now: Instant) -> bool {
+        self.last_sent
+            .get(symbol)
+            .map(|last| now.duration_since(*last) >= self.min_interval)
+            .unwrap_or(true)
+    }
+}
+
+/// Kill switch trip request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct TripKillSwitchRequest {
+    pub reason: String,
+}
+
+/// Whether the viewer may trip and reset the kill switch
+fn can_operate_kill_switch(viewer: &UserContext) -> bool {
+    !viewer.is_anonymous() && viewer.permissions.iter().any(|permission| permission == "configure_system")
+}
+
+/// Get the kill switch state
+#[utoipa::path(
+    get,
+    path = "/admin/kill-switch",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Kill switch state", body = ApiResponse<KillSwitchStatus>),
+    )
+)]
+async fn get_kill_switch(Extension(state): Extension<Arc<AppState>>) -> Json<ApiResponse<KillSwitchStatus>> {
+    Json(ApiResponse {
+        success: true,
+        data: Some(state.kill_switch.status()),
+        message: None,
+    })
+}
+
+/// Halt trading by hand; requires `configure_system`
+#[utoipa::path(
+    post,
+    path = "/admin/kill-switch/trip",
+    tag = "admin",
+    request_body = TripKillSwitchRequest,
+    responses(
+        (status = 200, description = "Kill switch tripped", body = ApiResponse<KillSwitchStatus>),
+        (status = 403, description = "Missing `configure_system`"),
+    )
+)]
+async fn trip_kill_switch(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    Json(payload): Json<TripKillSwitchRequest>,
+) -> Response {
+    if !can_operate_kill_switch(&viewer) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    state.kill_switch.trip(TripReason::Manual {
+        by: viewer.user_id.clone(),
+        reason: payload.reason,
+    });
+    Json(ApiResponse {
+        success: true,
+        data: Some(state.kill_switch.status()),
+        message: Some("Kill switch tripped".to_string()),
+    })
+    .into_response()
+}
+
+/// Resume trading; requires `configure_system`
+#[utoipa::path(
+    post,
+    path = "/admin/kill-switch/reset",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Kill switch reset", body = ApiResponse<KillSwitchStatus>),
+        (status = 403, description = "Missing `configure_system`"),
+    )
+)]
+async fn reset_kill_switch(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+) -> Response {
+    if !can_operate_kill_switch(&viewer) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let was_tripped = state.kill_switch.reset(&viewer.user_id);
+    Json(ApiResponse {
+        success: true,
+        data: Some(state.kill_switch.status()),
+        message: Some(if was_tripped { "Kill switch reset" } else { "Kill switch was not tripped" }.to_string()),
+    })
+    .into_response()
+}
+
+/// gRPC interface of the service, applying the REST routes' tenant rules
+struct PortfolioRpc {
+    state: Arc<AppState>,
+}
+
+impl PortfolioRpc {
+    /// Caller of a call, who must be able to view the portfolio's tenant
+    #[allow(clippy::result_large_err)]
+    fn caller<T>(&self, request: &tonic::Request<T>) -> Result<UserContext, tonic::Status> {
+        let caller = rpc::caller(request)?;
+        if !can_view_tenant(&caller, &self.state.tenant_id) {
+            return Err(tonic::Status::permission_denied("Portfolio belongs to another tenant"));
+        }
+        Ok(caller)
+    }
+}
+
+#[tonic::async_trait]
+impl proto::portfolio_server::Portfolio for PortfolioRpc {
+    async fn list_positions(
+        &self,
+        request: tonic::Request<proto::ListPositionsRequest>,
+    ) -> Result<tonic::Response<proto::ListPositionsResponse>, tonic::Status> {
+        let caller = self.caller(&request)?;
+        let positions = self.state.portfolio_manager.read().await
+            .list_positions()
+            .into_iter()
+            .filter(|position| position_visible(position, &caller))
+            .map(|position| proto::Position::from(position.clone()))
+            .collect();
+        Ok(tonic::Response::new(proto::ListPositionsResponse { positions }))
+    }
+
+    async fn get_position(
+        &self,
+        request: tonic::Request<proto::GetPositionRequest>,
+    ) -> Result<tonic::Response<proto::Position>, tonic::Status> {
+        let caller = self.caller(&request)?;
+        // Other tenants' positions are reported as missing rather than forbidden
+        let position = self.state.portfolio_manager.read().await
+            .get_position(&request.get_ref().id)
+            .filter(|position| position_visible(position, &caller))
+            .cloned()
+            .ok_or_else(|| tonic::Status::not_found("Position not found"))?;
+        Ok(tonic::Response::new(position.into()))
+    }
+
+    async fn generate_trade_plan(
+        &self,
+        request: tonic::Request<proto::GenerateTradePlanRequest>,
+    ) -> Result<tonic::Response<proto::TradePlan>, tonic::Status> {
+        let caller = self.caller(&request)?;
+        rpc::require_permission(&caller, "execute_trades")?;
+        let request = request.into_inner();
+        let chain = request.chain.ok_or(InvalidMessage::Missing("chain"))?;
+        let amount = convert::decimal(&request.amount, "amount")?;
+        let plan = self.state.portfolio_manager.read().await
+            .generate_trade_plan(&request.symbol, chain.into(), amount, &request.side)
+            .map_err(|e| rpc::status(ApiError::from(e).context("Failed to generate trade plan")))?;
+        Ok(tonic::Response::new(plan.into()))
     }
 }
 
@@ -442,28 +1896,195 @@ async fn generate_trade_plan(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_openapi_document() {
+        let document = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        for path in ["/positions", "/positions/{id}/close", "/risk", "/rebalance/execute", "/ws/pnl"] {
+            assert!(document["paths"][path].is_object(), "{} is not documented", path);
+        }
+        assert!(document["components"]["schemas"]["RiskReport"]["properties"]["value_at_risk"].is_object());
+    }
+
     #[test]
     fn test_args_parsing() {
         let args = Args::parse_from(["svc-portfolio", "--port", "8081", "--initial-capital", "50000.0"]);
-        assert_eq!(args.port, 8081);
+        assert_eq!(args.port, Some(8081));
         assert_eq!(args.initial_capital, 50000.0);
+        assert_eq!(args.equity_sample_secs, 60);
+        assert!(!args.net_positions);
+        assert_eq!(args.tenant_id, "default");
+    }
+
+    fn app_state(tenant_id: &str) -> Result<Arc<AppState>> {
+        let portfolio_manager = PortfolioManager::new(Decimal::from(10000), default_allocation_settings()).with_tenant(tenant_id);
+        let (pnl_updates, _) = broadcast::channel(PNL_CHANNEL_CAPACITY);
+        let (position_updates, _) = broadcast::channel(POSITION_CHANNEL_CAPACITY);
+        Ok(Arc::new(AppState {
+            portfolio_manager: RwLock::new(portfolio_manager),
+            inventory: InventoryBook::new(),
+            tenant_id: tenant_id.to_string(),
+            pnl_updates,
+            position_updates,
+            metrics: Arc::new(ServiceMetrics::new("svc-portfolio")?),
+            oracle: Arc::new(OracleFeeds::default()),
+            kill_switch: KillSwitch::default(),
+            rebalancer: Rebalancer::default(),
+            executor: Executor::new(),
+            native_assets: default_native_assets(),
+        }))
     }
 
     #[tokio::test]
     async fn test_portfolio_service_creation() -> Result<()> {
-        let allocation_settings = AllocationSettings {
-            max_position_size_pct: 5.0,
-            max_portfolio_risk_pct: 2.0,
-            diversification_targets: HashMap::new(),
-            stop_loss_pct: 5.0,
-            take_profit_pct: 10.0,
+        let _app_state = app_state("default")?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grpc_positions() -> Result<()> {
+        use proto::portfolio_server::Portfolio;
+        
+        fn call<T>(message: T, tenant_id: &str) -> tonic::Request<T> {
+            let mut request = tonic::Request::new(message);
+            request.extensions_mut().insert(UserContext {
+                user_id: "user-1".to_string(),
+                tenant_id: tenant_id.to_string(),
+                roles: Vec::new(),
+                permissions: Vec::new(),
+            });
+            request
+        }
+        let get = |id: &str| proto::GetPositionRequest { id: id.to_string() };
+        
+        let rpc = PortfolioRpc { state: app_state("tenant-1")? };
+        rpc.state.portfolio_manager.write().await.apply_fill(PositionFill {
+            symbol: "ETH".to_string(),
+            chain: ChainRef { name: "ethereum".to_string(), id: 1 },
+            side: "long".to_string(),
+            amount: Decimal::ONE,
+            price: Decimal::from(100),
+            leverage: 1.0,
+            fee: Decimal::ZERO,
+        })?;
+        let id = rpc.state.portfolio_manager.read().await.list_positions()[0].id.clone();
+        
+        let positions = rpc.list_positions(call(proto::ListPositionsRequest {}, "tenant-1")).await?.into_inner().positions;
+        assert_eq!(positions.len(), 1);
+        let position = rpc.get_position(call(get(&id), "tenant-1")).await?.into_inner();
+        assert_eq!(position.symbol, "ETH");
+        assert_eq!(position.entry_price, "100");
+        let missing = rpc.get_position(call(get("missing"), "tenant-1")).await.unwrap_err();
+        assert_eq!(missing.code(), tonic::Code::NotFound);
+        let forbidden = rpc.get_position(call(get(&id), "tenant-2")).await.unwrap_err();
+        assert_eq!(forbidden.code(), tonic::Code::PermissionDenied);
+        
+        // Trade plans need `execute_trades`
+        let plan = rpc.generate_trade_plan(call(proto::GenerateTradePlanRequest::default(), "tenant-1")).await.unwrap_err();
+        assert_eq!(plan.code(), tonic::Code::PermissionDenied);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tenant_guard() -> Result<()> {
+        use axum::body::Body;
+        use tower::ServiceExt;
+
+        let state = app_state("tenant-1")?;
+        let app = Router::new()
+            .route("/inventory", get(get_inventory))
+            .route_layer(middleware::from_fn_with_state(state.clone(), tenant_guard))
+            .layer(Extension(state));
+        let viewer = |tenant_id: &str, permissions: &[&str]| UserContext {
+            user_id: "user-1".to_string(),
+            tenant_id: tenant_id.to_string(),
+            roles: Vec::new(),
+            permissions: permissions.iter().map(|permission| permission.to_string()).collect(),
+        };
+        let status = |viewer: UserContext| {
+            let request = axum::http::Request::builder().uri("/inventory").extension(viewer).body(Body::empty()).unwrap();
+            let app = app.clone();
+            async move { app.oneshot(request).await.unwrap().status() }
         };
         
-        let portfolio_manager = PortfolioManager::new(10000.0, allocation_settings);
-        let _app_state = Arc::new(AppState {
-            portfolio_manager: RwLock::new(portfolio_manager),
-        });
+        assert_eq!(status(viewer("tenant-1", &[])).await, StatusCode::OK);
+        assert_eq!(status(viewer("tenant-2", &[])).await, StatusCode::FORBIDDEN);
+        assert_eq!(status(viewer("tenant-2", &["view_all_data"])).await, StatusCode::OK);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_keeps_fees() -> Result<()> {
+        use axum::body::Body;
+        use tower::ServiceExt;
+
+        let state = app_state("default")?;
+        let id = state.portfolio_manager.write().await.apply_fill(PositionFill {
+            symbol: "ETH".to_string(),
+            chain: ChainRef { name: "ethereum".to_string(), id: 1 },
+            side: "long".to_string(),
+            amount: Decimal::ONE,
+            price: Decimal::from(100),
+            leverage: 1.0,
+            fee: Decimal::from(2),
+        })?.position.unwrap().id;
+        let fees = state.portfolio_manager.read().await.fees_paid().unwrap();
+        
+        let app = Router::new()
+            .route("/positions/:id", delete(close_position))
+            .layer(Extension(state.clone()));
+        let request = axum::http::Request::builder().method("DELETE").uri(format!("/positions/{}", id)).body(Body::empty()).unwrap();
+        assert_eq!(app.oneshot(request).await.unwrap().status(), StatusCode::OK);
         
+        let manager = state.portfolio_manager.read().await;
+        assert!(manager.get_position(&id).is_none());
+        assert_eq!(manager.fees_paid().unwrap(), fees);
+        assert_eq!(manager.realized_ledger()[0].fees, Decimal::from(2));
         Ok(())
     }
+
+    fn pnl_update(symbol: &str, price: u64) -> PnlUpdate {
+        PnlUpdate {
+            symbol: symbol.to_string(),
+            price: Decimal::from(price),
+            positions: vec![],
+            symbol_pnl: Decimal::ZERO,
+            total_pnl: Decimal::ZERO,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_symbol_throttle() {
+        let mut throttle = SymbolThrottle::new(Duration::from_millis(100));
+        let start = Instant::now();
+        
+        // First update per symbol goes straight through
+        assert!(throttle.offer(pnl_update("ETH", 3000), start).is_some());
+        assert!(throttle.offer(pnl_update("BTC", 60000), start).is_some());
+        
+        // Rapid updates are coalesced to the latest one
+        assert!(throttle.offer(pnl_update("ETH", 3001), start + Duration::from_millis(10)).is_none());
+        assert!(throttle.offer(pnl_update("ETH", 3002), start + Duration::from_millis(20)).is_none());
+        assert!(throttle.drain_due(start + Duration::from_millis(50)).is_empty());
+        
+        let flushed = throttle.drain_due(start + Duration::from_millis(100));
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(flushed[0].price, Decimal::from(3002));
+        assert!(throttle.drain_due(start + Duration::from_millis(300)).is_empty());
+        
+        // A flush counts as a send for throttling purposes
+        assert!(throttle.offer(pnl_update("ETH", 3003), start + Duration::from_millis(150)).is_none());
+        assert!(throttle.offer(pnl_update("ETH", 3004), start + Duration::from_millis(200)).is_some());
+    }
+    #[test]
+    fn test_stream_message_is_tagged() {
+        let message = StreamMessage {
+            tenant_id: "tenant-1".to_string(),
+            update: StreamUpdate::Pnl(pnl_update("ETH", 3000)),
+        };
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(json["type"], "pnl");
+        assert_eq!(json["tenant_id"], "tenant-1");
+        assert_eq!(json["symbol"], "ETH");
+    }
 }
\ No newline at end of file
@@ -1,6 +1,16 @@
+use clap::Parser;
 use sniper_core::{bus::InMemoryBus, prelude::*};
+use sniper_core::config::ConfigArgs;
+use sniper_core::shutdown::Shutdown;
 use tokio::time::{sleep, Duration};
 
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[clap(flatten)]
+    config: ConfigArgs,
+}
+
 #[tokio::main]
 async fn main() -> eyre::Result<()> {
     tracing_subscriber::fmt()
@@ -8,12 +18,21 @@ async fn main() -> eyre::Result<()> {
         .json()
         .init();
     dotenvy::dotenv().ok();
+    let args = Args::parse();
+    let config = args.config.loader("svc-risk").load_service()?;
+    if args.config.print_config {
+        println!("{}", config.render()?);
+        return Ok(());
+    }
+    let _telemetry = sniper_core::telemetry::init_telemetry("svc-risk", None)
+        .map_err(|e| eyre::eyre!("{}", e))?;
+    let shutdown = Shutdown::on_signal();
 
     let bus = InMemoryBus::new(1024);
 
     // Demo: publisher task
     let tx_bus = bus.clone();
-    tokio::spawn(async move {
+    shutdown.spawn(async move {
         loop {
             let sig = Signal {
                 source: "dex".into(),
@@ -34,7 +53,7 @@ async fn main() -> eyre::Result<()> {
 
     // Demo: subscriber task
     let mut rx = bus.subscribe("signals.>");
-    tokio::spawn(async move {
+    shutdown.spawn(async move {
         loop {
             if let Ok(bytes) = rx.recv().await {
                 if let Ok(sig) = serde_json::from_slice::<Signal>(&bytes) {
@@ -44,8 +63,8 @@ async fn main() -> eyre::Result<()> {
         }
     });
 
-    // Keep running; a real service would expose HTTP/gRPC, healthz, etc.
-    loop {
-        sleep(Duration::from_secs(3600)).await;
-    }
+    // Run until SIGINT or SIGTERM, then let the background tasks stop
+    shutdown.wait().await;
+    shutdown.join().await;
+    Ok(())
 }
@@ -1,6 +1,25 @@
+use clap::Parser;
+use sniper_core::config::ConfigArgs;
+use sniper_core::shutdown::Shutdown;
 use sniper_core::{bus::InMemoryBus, prelude::*};
+use sniper_signals::{spawn_listeners, SignalsConfig};
 use tokio::time::{sleep, Duration};
 
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Chains to watch, e.g. configs/signals.toml; demo signals are published without it
+    #[clap(long)]
+    signals_config: Option<String>,
+
+    /// OTLP endpoint spans are exported to, defaulting to OTEL_EXPORTER_OTLP_ENDPOINT
+    #[clap(long)]
+    otlp_endpoint: Option<String>,
+
+    #[clap(flatten)]
+    config: ConfigArgs,
+}
+
 #[tokio::main]
 async fn main() -> eyre::Result<()> {
     tracing_subscriber::fmt()
@@ -8,33 +27,48 @@ async fn main() -> eyre::Result<()> {
         .json()
         .init();
     dotenvy::dotenv().ok();
+    let args = Args::parse();
+    let config = args.config.loader("svc-signals").load_service()?;
+    if args.config.print_config {
+        println!("{}", config.render()?);
+        return Ok(());
+    }
+    let _telemetry = sniper_core::telemetry::init_telemetry("svc-signals", args.otlp_endpoint.as_deref())
+        .map_err(|e| eyre::eyre!("{}", e))?;
+    let shutdown = Shutdown::on_signal();
 
     let bus = InMemoryBus::new(1024);
 
-    // Demo: publisher task
-    let tx_bus = bus.clone();
-    tokio::spawn(async move {
-        loop {
-            let sig = Signal {
-                source: "dex".into(),
-                kind: "pair_created".into(),
-                chain: ChainRef {
-                    name: "ethereum".into(),
-                    id: 1,
-                },
-                token0: None,
-                token1: None,
-                extra: serde_json::json!({"demo":true}),
-                seen_at_ms: 0,
-            };
-            let _ = tx_bus.publish("signals.dex.pair_created", &sig).await;
-            sleep(Duration::from_secs(5)).await;
-        }
-    });
+    if let Some(path) = &args.signals_config {
+        let config = SignalsConfig::load(path).map_err(|e| eyre::eyre!("{}", e))?;
+        tracing::info!("Watching {} chains from {}", config.chains.len(), path);
+        spawn_listeners(&config, &bus);
+    } else {
+        // Demo: publisher task
+        let tx_bus = bus.clone();
+        shutdown.spawn(async move {
+            loop {
+                let sig = Signal {
+                    source: "dex".into(),
+                    kind: "pair_created".into(),
+                    chain: ChainRef {
+                        name: "ethereum".into(),
+                        id: 1,
+                    },
+                    token0: None,
+                    token1: None,
+                    extra: serde_json::json!({"demo":true}),
+                    seen_at_ms: 0,
+                };
+                let _ = tx_bus.publish("signals.dex.pair_created", &sig).await;
+                sleep(Duration::from_secs(5)).await;
+            }
+        });
+    }
 
     // Demo: subscriber task
     let mut rx = bus.subscribe("signals.>");
-    tokio::spawn(async move {
+    shutdown.spawn(async move {
         loop {
             if let Ok(bytes) = rx.recv().await {
                 if let Ok(sig) = serde_json::from_slice::<Signal>(&bytes) {
@@ -44,8 +78,8 @@ async fn main() -> eyre::Result<()> {
         }
     });
 
-    // Keep running; a real service would expose HTTP/gRPC, healthz, etc.
-    loop {
-        sleep(Duration::from_secs(3600)).await;
-    }
+    // Run until SIGINT or SIGTERM, then let the background tasks stop
+    shutdown.wait().await;
+    shutdown.join().await;
+    Ok(())
 }
@@ -1,7 +1,20 @@
+use clap::Parser;
 use sniper_core::{bus::InMemoryBus, prelude::*};
+use sniper_core::config::ConfigArgs;
+use sniper_core::shutdown::Shutdown;
 use sniper_core::types::{Signal, TradePlan, ChainRef, ExecMode, GasPolicy, ExitRules};
+use sniper_schedule::{SessionConfig, SessionScheduler, DEFAULT_TENANT};
+use std::sync::Arc;
+use tokio::sync::RwLock;
 use tokio::time::{sleep, Duration};
 
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[clap(flatten)]
+    config: ConfigArgs,
+}
+
 #[tokio::main]
 async fn main() -> eyre::Result<()> {
     tracing_subscriber::fmt()
@@ -9,20 +22,58 @@ async fn main() -> eyre::Result<()> {
         .json()
         .init();
     dotenvy::dotenv().ok();
+    let args = Args::parse();
+    let config = args.config.loader("svc-strategy").load_service()?;
+    if args.config.print_config {
+        println!("{}", config.render()?);
+        return Ok(());
+    }
+    let _telemetry = sniper_core::telemetry::init_telemetry("svc-strategy", None)
+        .map_err(|e| eyre::eyre!("{}", e))?;
+    let shutdown = Shutdown::on_signal();
 
     let bus = InMemoryBus::new(1024);
 
+    // Trading-session scheduler gating which strategies may run
+    let session_config = SessionConfig::load_default().unwrap_or_else(|e| {
+        tracing::warn!("failed to load session config, strategies always enabled: {}", e);
+        SessionConfig::default()
+    });
+    let scheduler = Arc::new(RwLock::new(
+        SessionScheduler::from_config(session_config).with_bus(bus.clone()),
+    ));
+
+    // Session task - publishes session open/close events
+    let tick_scheduler = scheduler.clone();
+    shutdown.spawn(async move {
+        loop {
+            let events = tick_scheduler.write().await.tick(chrono::Utc::now()).await;
+            for event in events {
+                tracing::info!(tenant = %event.tenant_id, kind = ?event.kind, "trading session changed");
+            }
+            sleep(Duration::from_secs(60)).await;
+        }
+    });
+
     // Signal subscriber task - listens for signals and generates trade plans
     let rx_bus = bus.clone();
-    tokio::spawn(async move {
+    let gas = config.gas.clone();
+    shutdown.spawn(async move {
         let mut rx = rx_bus.subscribe("signals.>");
         loop {
             if let Ok(bytes) = rx.recv().await {
                 if let Ok(sig) = serde_json::from_slice::<Signal>(&bytes) {
                     tracing::info!(?sig.kind, "received signal");
                     
+                    // Skip signals for strategies outside their trading session
+                    let strategy_id = strategy_for_signal(&sig.kind);
+                    if !scheduler.read().await.strategy_enabled(DEFAULT_TENANT, strategy_id, chrono::Utc::now()) {
+                        tracing::info!(strategy = strategy_id, "strategy disabled by session calendar");
+                        continue;
+                    }
+                    
                     // Process the signal and generate a trade plan
-                    if let Some(plan) = process_signal(&sig).await {
+                    if let Some(plan) = process_signal(&sig, gas.get(&sig.chain.name)).await {
                         // Publish the trade plan
                         let _ = rx_bus.publish("plan.created", &plan).await;
                         tracing::info!("published trade plan");
@@ -34,7 +85,7 @@ async fn main() -> eyre::Result<()> {
 
     // Demo: publisher task - simulates signal generation
     let tx_bus = bus.clone();
-    tokio::spawn(async move {
+    shutdown.spawn(async move {
         let signals = vec![
             Signal {
                 source: "dex".into(),
@@ -69,14 +120,25 @@ async fn main() -> eyre::Result<()> {
         }
     });
 
-    // Keep running
-    loop {
-        sleep(Duration::from_secs(3600)).await;
+    // Run until SIGINT or SIGTERM, then let the background tasks stop
+    shutdown.wait().await;
+    shutdown.join().await;
+    Ok(())
+}
+
+/// Map a signal kind to the strategy handling it
+fn strategy_for_signal(kind: &str) -> &str {
+    match kind {
+        "pair_created" => "launch_snipe",
+        "trading_enabled" => "trading_enable",
+        other => other,
     }
 }
 
 /// Process a signal and generate a trade plan if applicable
-async fn process_signal(signal: &Signal) -> Option<TradePlan> {
+///
+/// Plans use the chain's configured gas policy, if any, over the strategy's own.
+async fn process_signal(signal: &Signal, gas: Option<&GasPolicy>) -> Option<TradePlan> {
     match signal.kind.as_str() {
         "pair_created" => {
             tracing::info!("processing pair created signal");
@@ -90,16 +152,18 @@ async fn process_signal(signal: &Signal) -> Option<TradePlan> {
                 amount_in: 1000000000000000000, // 1 ETH/BNB
                 min_out: 900000000000000000,    // 0.9 tokens (10% slippage)
                 mode: ExecMode::Mempool,
-                gas: GasPolicy {
+                gas: gas.cloned().unwrap_or(GasPolicy {
                     max_fee_gwei: 50,
                     max_priority_gwei: 2,
-                },
+                }),
                 exits: ExitRules {
                     take_profit_pct: Some(20.0),
                     stop_loss_pct: Some(10.0),
                     trailing_pct: Some(5.0),
                 },
                 idem_key: format!("plan_{}", signal.seen_at_ms),
+                quote: None,
+                correlation_id: None,
             })
         },
         "trading_enabled" => {
@@ -114,16 +178,18 @@ async fn process_signal(signal: &Signal) -> Option<TradePlan> {
                 amount_in: 500000000000000000, // 0.5 ETH/BNB
                 min_out: 450000000000000000,   // 0.45 tokens (10% slippage)
                 mode: ExecMode::Mempool,
-                gas: GasPolicy {
+                gas: gas.cloned().unwrap_or(GasPolicy {
                     max_fee_gwei: 40,
                     max_priority_gwei: 1,
-                },
+                }),
                 exits: ExitRules {
                     take_profit_pct: Some(15.0),
                     stop_loss_pct: Some(7.5),
                     trailing_pct: Some(3.0),
                 },
                 idem_key: format!("plan_{}", signal.seen_at_ms),
+                quote: None,
+                correlation_id: None,
             })
         },
         _ => {
@@ -0,0 +1,909 @@
+//! Treasury service for the sniper-rs enterprise features.
+//!
+//! This service provides REST APIs for balance tracking across wallets, chains
+//! and venues, internal transfers, sweep rules, low-balance alerts, the
+//! deposit/withdrawal approval workflow and the treasury audit trail.
+//! Funding requests and approvals act as the caller of the verified access
+//! token, never as a user named in the request body. Withdrawals only debit
+//! a balance once their funding request is approved, broadcast and reconciled.
+
+use anyhow::Result;
+use clap::Parser;
+use sniper_core::config::ConfigArgs;
+use sniper_core::errors::ApiError;
+use sniper_core::rate_limit::{RateLimitConfig, RateLimiter};
+use sniper_core::shutdown::Shutdown;
+use serde::{Deserialize, Serialize};
+use axum::{
+    handler::Handler,
+    routing::{get, post, put},
+    Json, Router, Extension,
+};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use sniper_core::types::ChainRef;
+use sniper_treasury::{
+    TreasuryManager,
+    TreasuryAccount,
+    AccountKind,
+    Balance,
+    InternalTransfer,
+    SweepRule,
+    LowBalanceAlert,
+    TreasuryAuditEntry,
+    TreasuryError,
+};
+use sniper_treasury::funding::{FundingManager, FundingRequest, OnChainTransfer, ReconciliationReport};
+use sniper_users::http::Viewer;
+use sniper_users::redaction::mask_address;
+use sniper_users::{redact, redact_all, require_permission, require_tenant, ApprovalPolicy, AuthLayer, JwtAuth, Redact, UserContext};
+
+/// Port used when neither the configuration nor --port sets one
+const DEFAULT_PORT: u16 = 8098;
+
+/// CLI arguments for the treasury service
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Port to listen on, overriding the configured port (default 8098)
+    #[clap(short, long)]
+    port: Option<u16>,
+
+    /// Withdrawal amount above which approvals are required
+    #[clap(long, default_value = "10000.0")]
+    approval_threshold: f64,
+
+    /// Number of approvals required above the threshold
+    #[clap(long, default_value = "2")]
+    required_approvals: usize,
+
+    /// OTLP endpoint spans are exported to, defaulting to OTEL_EXPORTER_OTLP_ENDPOINT
+    #[clap(long)]
+    otlp_endpoint: Option<String>,
+
+    #[clap(flatten)]
+    config: ConfigArgs,
+}
+
+/// Treasury service state
+struct AppState {
+    treasury_manager: RwLock<TreasuryManager>,
+    funding_manager: RwLock<FundingManager>,
+}
+
+/// Account creation request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CreateAccountRequest {
+    pub name: String,
+    pub kind: String,
+    pub chain_id: u64,
+    pub chain_name: String,
+    pub venue: String,
+    pub address: String,
+    pub tenant_id: String,
+    pub actor: String,
+}
+
+/// Deposit or withdrawal request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BalanceChangeRequest {
+    pub asset: String,
+    pub amount: f64,
+    pub actor: String,
+}
+
+/// Withdrawal from an account, requested by the caller
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WithdrawRequest {
+    pub asset: String,
+    pub amount: f64,
+    pub external_address: String,
+}
+
+/// Internal transfer request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TransferRequest {
+    pub from_account: String,
+    pub to_account: String,
+    pub asset: String,
+    pub amount: f64,
+    pub actor: String,
+}
+
+/// Sweep rule creation request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CreateSweepRuleRequest {
+    pub source_account: String,
+    pub destination_account: String,
+    pub asset: String,
+    pub threshold: f64,
+    pub retain_amount: f64,
+    pub actor: String,
+}
+
+/// Sweep rule update request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct UpdateSweepRuleRequest {
+    pub enabled: bool,
+    pub actor: String,
+}
+
+/// Sweep run request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RunSweepsRequest {
+    pub actor: String,
+}
+
+/// Low balance threshold request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SetThresholdRequest {
+    pub asset: String,
+    pub min_amount: f64,
+    pub actor: String,
+}
+
+/// Funding request creation request, made by the caller
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CreateFundingRequest {
+    pub account_id: String,
+    pub asset: String,
+    pub amount: f64,
+    pub external_address: String,
+}
+
+/// Funding rejection request, made by the caller
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RejectFundingRequest {
+    pub reason: String,
+}
+
+/// Funding broadcast request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BroadcastFundingRequest {
+    pub tx_hash: String,
+}
+
+/// Reconciliation request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ReconcileRequest {
+    pub transfers: Vec<OnChainTransfer>,
+}
+
+/// Standard response format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ApiResponse<T> {
+    pub success: bool,
+    pub data: Option<T>,
+    pub message: Option<String>,
+}
+
+/// Account response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AccountResponse {
+    pub id: String,
+    pub name: String,
+    pub kind: String,
+    pub chain_id: u64,
+    pub chain_name: String,
+    pub venue: String,
+    pub address: String,
+    pub tenant_id: String,
+    pub created_at: String,
+}
+
+impl From<TreasuryAccount> for AccountResponse {
+    fn from(account: TreasuryAccount) -> Self {
+        AccountResponse {
+            id: account.id,
+            name: account.name,
+            kind: format!("{:?}", account.kind),
+            chain_id: account.chain.id,
+            chain_name: account.chain.name,
+            venue: account.venue,
+            address: account.address,
+            tenant_id: account.tenant_id,
+            created_at: account.created_at.to_rfc3339(),
+        }
+    }
+}
+
+impl Redact for AccountResponse {
+    fn redact_fields(&mut self, _viewer: &UserContext) {
+        self.address = mask_address(&self.address);
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let args = Args::parse();
+    let config = args.config.loader("svc-treasury")
+        .with_default("port", DEFAULT_PORT)
+        .with_override("port", args.port)
+        .load_service()?;
+    if args.config.print_config {
+        println!("{}", config.render()?);
+        return Ok(());
+    }
+    let _telemetry = sniper_core::telemetry::init_telemetry("svc-treasury", args.otlp_endpoint.as_deref())?;
+    let shutdown = Shutdown::on_signal();
+
+    // Create managers
+    let treasury_manager = TreasuryManager::new();
+    let funding_manager = FundingManager::new(ApprovalPolicy::new(
+        args.approval_threshold,
+        args.required_approvals,
+    ));
+
+    // Create app state
+    let app_state = Arc::new(AppState {
+        treasury_manager: RwLock::new(treasury_manager),
+        funding_manager: RwLock::new(funding_manager),
+    });
+
+    // Verify access tokens issued by svc-users; funding acts as the caller
+    let auth = Arc::new(JwtAuth::from_config(&config.auth)?);
+    let authenticated = require_tenant();
+    let withdrawals = require_permission("request_withdrawals");
+
+    // Throttle callers per tenant, separately for reads and trades
+    let rate_limiter = RateLimiter::new(config.section::<RateLimitConfig>("rate_limit")?.unwrap_or_default());
+
+    // Create router
+    let app = Router::new()
+        .route("/health", get(health_check))
+        .route("/accounts", post(create_account))
+        .route("/accounts/:id", get(get_account))
+        .route("/accounts/tenant/:tenant_id", get(list_tenant_accounts))
+        .route("/accounts/:id/balances", get(get_account_balances))
+        .route("/accounts/:id/deposit", post(deposit))
+        .route("/accounts/:id/withdraw", post(withdraw.layer(withdrawals)))
+        .route("/accounts/:id/threshold", post(set_low_balance_threshold))
+        .route("/accounts/:id/audit", get(get_account_audit_trail))
+        .route("/transfers", post(create_transfer))
+        .route("/transfers/tenant/:tenant_id", get(list_tenant_transfers))
+        .route("/sweep-rules", post(create_sweep_rule))
+        .route("/sweep-rules/:id", put(update_sweep_rule))
+        .route("/sweep-rules/tenant/:tenant_id", get(list_tenant_sweep_rules))
+        .route("/sweeps/run", post(run_sweeps))
+        .route("/alerts/tenant/:tenant_id", get(get_low_balance_alerts))
+        .route("/audit/tenant/:tenant_id", get(get_tenant_audit_trail))
+        .route("/funding/deposits", post(request_deposit.layer(authenticated)))
+        .route("/funding/withdrawals", post(request_withdrawal.layer(withdrawals)))
+        .route("/funding/reconcile", post(reconcile_funding))
+        .route("/funding/:id", get(get_funding_request))
+        .route("/funding/:id/approve", post(approve_funding_request.layer(authenticated)))
+        .route("/funding/:id/reject", post(reject_funding_request.layer(authenticated)))
+        .route("/funding/:id/broadcast", post(broadcast_funding_request))
+        .route("/funding/tenant/:tenant_id", get(list_tenant_funding_requests))
+        .layer(Extension(app_state))
+        .layer(rate_limiter.layer())
+        .layer(AuthLayer::new(auth))
+        .layer(axum::middleware::from_fn(sniper_core::telemetry::trace_http));
+
+    // Run server
+    let addr = format!("0.0.0.0:{}", config.port.unwrap_or(DEFAULT_PORT));
+    tracing::info!("Treasury service listening on http://{}", addr);
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    sniper_core::shutdown::serve(listener, app, &shutdown).await?;
+
+    Ok(())
+}
+
+/// Health check endpoint
+async fn health_check() -> Json<ApiResponse<String>> {
+    let response = ApiResponse {
+        success: true,
+        data: Some("Treasury service is healthy".to_string()),
+        message: None,
+    };
+    Json(response)
+}
+
+/// Create a treasury account
+async fn create_account(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(payload): Json<CreateAccountRequest>,
+) -> Result<Json<ApiResponse<AccountResponse>>, ApiError> {
+    // Parse account kind from string
+    let kind = match payload.kind.as_str() {
+        "HotWallet" => AccountKind::HotWallet,
+        "ColdWallet" => AccountKind::ColdWallet,
+        "Exchange" => AccountKind::Exchange,
+        _ => return Err(ApiError::invalid(format!("Unknown account kind: {}", payload.kind))),
+    };
+
+    let chain_ref = ChainRef {
+        name: payload.chain_name,
+        id: payload.chain_id,
+    };
+
+    let account = state.treasury_manager.write().await.create_account(
+        &payload.name,
+        kind,
+        chain_ref,
+        &payload.venue,
+        &payload.address,
+        &payload.tenant_id,
+        &payload.actor,
+    );
+
+    let response = ApiResponse {
+        success: true,
+        data: Some(AccountResponse::from(account)),
+        message: Some("Account created successfully".to_string()),
+    };
+    Ok(Json(response))
+}
+
+/// Get an account by ID
+async fn get_account(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<ApiResponse<AccountResponse>>, ApiError> {
+    let account = state.treasury_manager.read().await.get_account(&id).cloned()
+        .and_then(|account| redact(AccountResponse::from(account), &viewer))
+        .ok_or_else(|| ApiError::not_found("Account not found"))?;
+
+    let response = ApiResponse {
+        success: true,
+        data: Some(account),
+        message: None,
+    };
+    Ok(Json(response))
+}
+
+/// List accounts for a tenant
+async fn list_tenant_accounts(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    axum::extract::Path(tenant_id): axum::extract::Path<String>,
+) -> Json<ApiResponse<Vec<AccountResponse>>> {
+    let accounts = state.treasury_manager.read().await.list_tenant_accounts(&tenant_id)
+        .iter()
+        .map(|&account| AccountResponse::from(account.clone()))
+        .collect::<Vec<AccountResponse>>();
+
+    let response = ApiResponse {
+        success: true,
+        data: Some(redact_all(accounts, &viewer)),
+        message: None,
+    };
+    Json(response)
+}
+
+/// Get all balances held by an account
+async fn get_account_balances(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<ApiResponse<Vec<Balance>>>, ApiError> {
+    let manager = state.treasury_manager.read().await;
+    if manager.get_account(&id).is_none() {
+        return Err(ApiError::not_found("Account not found"));
+    }
+
+    let balances = manager.list_account_balances(&id)
+        .into_iter()
+        .cloned()
+        .collect::<Vec<Balance>>();
+
+    let response = ApiResponse {
+        success: true,
+        data: Some(balances),
+        message: None,
+    };
+    Ok(Json(response))
+}
+
+/// Record a deposit into an account
+async fn deposit(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(payload): Json<BalanceChangeRequest>,
+) -> Result<Json<ApiResponse<Balance>>, ApiError> {
+    let result = state.treasury_manager.write().await.deposit(
+        &id,
+        &payload.asset,
+        payload.amount,
+        &payload.actor,
+    );
+
+    let balance = result.map_err(|e| ApiError::from(e).context("Failed to record deposit"))?;
+    let response = ApiResponse {
+        success: true,
+        data: Some(balance),
+        message: Some("Deposit recorded successfully".to_string()),
+    };
+    Ok(Json(response))
+}
+
+/// Request a withdrawal from an account through the funding approval workflow
+async fn withdraw(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(payload): Json<WithdrawRequest>,
+) -> Result<Json<ApiResponse<FundingRequest>>, ApiError> {
+    let treasury = state.treasury_manager.read().await;
+    let result = state.funding_manager.write().await.request_withdrawal(
+        &treasury,
+        &id,
+        &payload.asset,
+        payload.amount,
+        &payload.external_address,
+        &viewer,
+    );
+
+    funding_response(result, "Withdrawal requested successfully", "Failed to request withdrawal")
+}
+
+/// Set a low balance threshold for an account
+async fn set_low_balance_threshold(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(payload): Json<SetThresholdRequest>,
+) -> Result<Json<ApiResponse<bool>>, ApiError> {
+    let result = state.treasury_manager.write().await.set_low_balance_threshold(
+        &id,
+        &payload.asset,
+        payload.min_amount,
+        &payload.actor,
+    );
+
+    result.map_err(|e| ApiError::from(e).context("Failed to set threshold"))?;
+    let response = ApiResponse {
+        success: true,
+        data: Some(true),
+        message: Some("Threshold set successfully".to_string()),
+    };
+    Ok(Json(response))
+}
+
+/// Get the audit trail for an account
+async fn get_account_audit_trail(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Json<ApiResponse<Vec<TreasuryAuditEntry>>> {
+    let entries = state.treasury_manager.read().await.get_account_audit_trail(&id)
+        .into_iter()
+        .cloned()
+        .collect::<Vec<TreasuryAuditEntry>>();
+
+    let response = ApiResponse {
+        success: true,
+        data: Some(entries),
+        message: None,
+    };
+    Json(response)
+}
+
+/// Transfer funds between two accounts
+async fn create_transfer(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(payload): Json<TransferRequest>,
+) -> Result<Json<ApiResponse<InternalTransfer>>, ApiError> {
+    let result = state.treasury_manager.write().await.transfer(
+        &payload.from_account,
+        &payload.to_account,
+        &payload.asset,
+        payload.amount,
+        &payload.actor,
+    );
+
+    let transfer = result.map_err(|e| ApiError::from(e).context("Failed to transfer funds"))?;
+    let response = ApiResponse {
+        success: true,
+        data: Some(transfer),
+        message: Some("Transfer completed successfully".to_string()),
+    };
+    Ok(Json(response))
+}
+
+/// List internal transfers for a tenant
+async fn list_tenant_transfers(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(tenant_id): axum::extract::Path<String>,
+) -> Json<ApiResponse<Vec<InternalTransfer>>> {
+    let transfers = state.treasury_manager.read().await.list_tenant_transfers(&tenant_id)
+        .into_iter()
+        .cloned()
+        .collect::<Vec<InternalTransfer>>();
+
+    let response = ApiResponse {
+        success: true,
+        data: Some(transfers),
+        message: None,
+    };
+    Json(response)
+}
+
+/// Create a sweep rule
+async fn create_sweep_rule(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(payload): Json<CreateSweepRuleRequest>,
+) -> Result<Json<ApiResponse<SweepRule>>, ApiError> {
+    let result = state.treasury_manager.write().await.create_sweep_rule(
+        &payload.source_account,
+        &payload.destination_account,
+        &payload.asset,
+        payload.threshold,
+        payload.retain_amount,
+        &payload.actor,
+    );
+
+    let rule = result.map_err(|e| ApiError::from(e).context("Failed to create sweep rule"))?;
+    let response = ApiResponse {
+        success: true,
+        data: Some(rule),
+        message: Some("Sweep rule created successfully".to_string()),
+    };
+    Ok(Json(response))
+}
+
+/// Enable or disable a sweep rule
+async fn update_sweep_rule(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(payload): Json<UpdateSweepRuleRequest>,
+) -> Result<Json<ApiResponse<bool>>, ApiError> {
+    let result = state.treasury_manager.write().await.set_sweep_rule_enabled(
+        &id,
+        payload.enabled,
+        &payload.actor,
+    );
+
+    result.map_err(|e| ApiError::from(e).context("Failed to update sweep rule"))?;
+    let response = ApiResponse {
+        success: true,
+        data: Some(true),
+        message: Some("Sweep rule updated successfully".to_string()),
+    };
+    Ok(Json(response))
+}
+
+/// List sweep rules for a tenant
+async fn list_tenant_sweep_rules(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(tenant_id): axum::extract::Path<String>,
+) -> Json<ApiResponse<Vec<SweepRule>>> {
+    let rules = state.treasury_manager.read().await.list_tenant_sweep_rules(&tenant_id)
+        .into_iter()
+        .cloned()
+        .collect::<Vec<SweepRule>>();
+
+    let response = ApiResponse {
+        success: true,
+        data: Some(rules),
+        message: None,
+    };
+    Json(response)
+}
+
+/// Run all enabled sweep rules
+async fn run_sweeps(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(payload): Json<RunSweepsRequest>,
+) -> Json<ApiResponse<Vec<InternalTransfer>>> {
+    let transfers = state.treasury_manager.write().await.run_sweeps(&payload.actor);
+
+    let response = ApiResponse {
+        success: true,
+        message: Some(format!("{} sweep(s) executed", transfers.len())),
+        data: Some(transfers),
+    };
+    Json(response)
+}
+
+/// Get low balance alerts for a tenant
+async fn get_low_balance_alerts(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(tenant_id): axum::extract::Path<String>,
+) -> Json<ApiResponse<Vec<LowBalanceAlert>>> {
+    let alerts = state.treasury_manager.read().await.check_low_balances(&tenant_id);
+
+    let response = ApiResponse {
+        success: true,
+        data: Some(alerts),
+        message: None,
+    };
+    Json(response)
+}
+
+/// Get the audit trail for a tenant
+async fn get_tenant_audit_trail(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(tenant_id): axum::extract::Path<String>,
+) -> Json<ApiResponse<Vec<TreasuryAuditEntry>>> {
+    let entries = state.treasury_manager.read().await.get_tenant_audit_trail(&tenant_id)
+        .into_iter()
+        .cloned()
+        .collect::<Vec<TreasuryAuditEntry>>();
+
+    let response = ApiResponse {
+        success: true,
+        data: Some(entries),
+        message: None,
+    };
+    Json(response)
+}
+
+/// Request a deposit into an account
+async fn request_deposit(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    Json(payload): Json<CreateFundingRequest>,
+) -> Result<Json<ApiResponse<FundingRequest>>, ApiError> {
+    let treasury = state.treasury_manager.read().await;
+    let result = state.funding_manager.write().await.request_deposit(
+        &treasury,
+        &payload.account_id,
+        &payload.asset,
+        payload.amount,
+        &payload.external_address,
+        &viewer,
+    );
+
+    funding_response(result, "Deposit requested successfully", "Failed to request deposit")
+}
+
+/// Request a withdrawal from an account
+async fn request_withdrawal(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    Json(payload): Json<CreateFundingRequest>,
+) -> Result<Json<ApiResponse<FundingRequest>>, ApiError> {
+    let treasury = state.treasury_manager.read().await;
+    let result = state.funding_manager.write().await.request_withdrawal(
+        &treasury,
+        &payload.account_id,
+        &payload.asset,
+        payload.amount,
+        &payload.external_address,
+        &viewer,
+    );
+
+    funding_response(result, "Withdrawal requested successfully", "Failed to request withdrawal")
+}
+
+/// Get a funding request by ID
+async fn get_funding_request(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<ApiResponse<FundingRequest>>, ApiError> {
+    let request = state.funding_manager.read().await.get_request(&id).cloned()
+        .and_then(|request| redact(request, &viewer))
+        .ok_or_else(|| ApiError::not_found("Funding request not found"))?;
+
+    let response = ApiResponse {
+        success: true,
+        data: Some(request),
+        message: None,
+    };
+    Ok(Json(response))
+}
+
+/// Approve a funding request
+async fn approve_funding_request(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<ApiResponse<FundingRequest>>, ApiError> {
+    let result = state.funding_manager.write().await.approve(&id, &viewer);
+
+    funding_response(result, "Funding request approved successfully", "Failed to approve funding request")
+}
+
+/// Reject a funding request
+async fn reject_funding_request(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(payload): Json<RejectFundingRequest>,
+) -> Result<Json<ApiResponse<FundingRequest>>, ApiError> {
+    let result = state.funding_manager.write().await.reject(&id, &viewer, &payload.reason);
+
+    funding_response(result, "Funding request rejected successfully", "Failed to reject funding request")
+}
+
+/// Record the broadcast transaction of a funding request
+async fn broadcast_funding_request(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(payload): Json<BroadcastFundingRequest>,
+) -> Result<Json<ApiResponse<FundingRequest>>, ApiError> {
+    let result = state.funding_manager.write().await.mark_broadcast(&id, &payload.tx_hash);
+
+    funding_response(result, "Funding request broadcast recorded successfully", "Failed to record broadcast")
+}
+
+/// Reconcile broadcast funding requests against on-chain transfers
+async fn reconcile_funding(
+    Extension(state): Extension<Arc<AppState>>,
+    Json(payload): Json<ReconcileRequest>,
+) -> Json<ApiResponse<ReconciliationReport>> {
+    let mut treasury = state.treasury_manager.write().await;
+    let report = state.funding_manager.write().await.reconcile(&mut treasury, &payload.transfers);
+
+    let response = ApiResponse {
+        success: true,
+        message: Some(format!("{} funding request(s) confirmed", report.confirmed.len())),
+        data: Some(report),
+    };
+    Json(response)
+}
+
+/// List funding requests for a tenant
+async fn list_tenant_funding_requests(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    axum::extract::Path(tenant_id): axum::extract::Path<String>,
+) -> Json<ApiResponse<Vec<FundingRequest>>> {
+    let requests = state.funding_manager.read().await.list_tenant_requests(&tenant_id)
+        .into_iter()
+        .cloned()
+        .collect::<Vec<FundingRequest>>();
+
+    let response = ApiResponse {
+        success: true,
+        data: Some(redact_all(requests, &viewer)),
+        message: None,
+    };
+    Json(response)
+}
+
+/// Build a response for a funding workflow result
+fn funding_response(
+    result: Result<FundingRequest, TreasuryError>,
+    success_message: &str,
+    failure_message: &str,
+) -> Result<Json<ApiResponse<FundingRequest>>, ApiError> {
+    let request = result.map_err(|e| ApiError::from(e).context(failure_message))?;
+    let response = ApiResponse {
+        success: true,
+        data: Some(request),
+        message: Some(success_message.to_string()),
+    };
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args_parsing() {
+        let args = Args::parse_from(["svc-treasury", "--port", "8099", "--required-approvals", "3"]);
+        assert_eq!(args.port, Some(8099));
+        assert_eq!(args.required_approvals, 3);
+        assert_eq!(args.approval_threshold, 10000.0);
+    }
+
+    #[tokio::test]
+    async fn test_treasury_service_creation() -> Result<()> {
+        let treasury_manager = TreasuryManager::new();
+        let funding_manager = FundingManager::new(ApprovalPolicy::default());
+
+        let _app_state = Arc::new(AppState {
+            treasury_manager: RwLock::new(treasury_manager),
+            funding_manager: RwLock::new(funding_manager),
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_handler_errors_map_to_status() {
+        use axum::extract::Path;
+        use axum::http::StatusCode;
+
+        let state = Arc::new(AppState {
+            treasury_manager: RwLock::new(TreasuryManager::new()),
+            funding_manager: RwLock::new(FundingManager::new(ApprovalPolicy::default())),
+        });
+        let account = state.treasury_manager.write().await.create_account(
+            "Hot",
+            AccountKind::HotWallet,
+            ChainRef { name: "ethereum".to_string(), id: 1 },
+            "onchain",
+            "0xHot",
+            "tenant-1",
+            "ops",
+        );
+        let change = |amount| Json(BalanceChangeRequest {
+            asset: "USDC".to_string(),
+            amount,
+            actor: "ops".to_string(),
+        });
+
+        let error = deposit(Extension(state.clone()), Path("missing".to_string()), change(10.0)).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        let error = deposit(Extension(state.clone()), Path(account.id.clone()), change(-1.0)).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let viewer = Viewer(UserContext {
+            user_id: "ops".to_string(),
+            tenant_id: "tenant-1".to_string(),
+            roles: vec![],
+            permissions: vec!["request_withdrawals".to_string()],
+        });
+        let withdrawal = Json(WithdrawRequest {
+            asset: "USDC".to_string(),
+            amount: 10.0,
+            external_address: "0xCold".to_string(),
+        });
+        let error = withdraw(Extension(state.clone()), viewer, Path(account.id.clone()), withdrawal).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::CONFLICT);
+        assert_eq!(error.message, "Failed to request withdrawal: Insufficient balance");
+
+        let request = CreateAccountRequest {
+            name: "Vault".to_string(),
+            kind: "Vault".to_string(),
+            chain_id: 1,
+            chain_name: "ethereum".to_string(),
+            venue: "onchain".to_string(),
+            address: "0xVault".to_string(),
+            tenant_id: "tenant-1".to_string(),
+            actor: "ops".to_string(),
+        };
+        let error = create_account(Extension(state), Json(request)).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_funding_acts_as_the_caller() {
+        use axum::extract::Path;
+        use axum::http::StatusCode;
+        use sniper_treasury::funding::FundingStatus;
+
+        let state = Arc::new(AppState {
+            treasury_manager: RwLock::new(TreasuryManager::new()),
+            funding_manager: RwLock::new(FundingManager::new(ApprovalPolicy::new(1000.0, 1))),
+        });
+        let account = state.treasury_manager.write().await.create_account(
+            "Hot",
+            AccountKind::HotWallet,
+            ChainRef { name: "ethereum".to_string(), id: 1 },
+            "onchain",
+            "0xHot",
+            "tenant-1",
+            "ops",
+        );
+        state.treasury_manager.write().await.deposit(&account.id, "USDC", 5000.0, "ops").unwrap();
+        let user = |user_id: &str| Viewer(UserContext {
+            user_id: user_id.to_string(),
+            tenant_id: "tenant-1".to_string(),
+            roles: vec![],
+            permissions: vec!["approve_withdrawals".to_string()],
+        });
+
+        let Json(response) = request_withdrawal(Extension(state.clone()), user("alice"), Json(CreateFundingRequest {
+            account_id: account.id.clone(),
+            asset: "USDC".to_string(),
+            amount: 2000.0,
+            external_address: "0xCold".to_string(),
+        })).await.unwrap();
+        let request = response.data.unwrap();
+        assert_eq!(request.requested_by, "alice");
+
+        // The requester cannot approve their own withdrawal
+        let error = approve_funding_request(Extension(state.clone()), user("alice"), Path(request.id.clone())).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::FORBIDDEN);
+
+        let Json(response) = approve_funding_request(Extension(state.clone()), user("bob"), Path(request.id)).await.unwrap();
+        assert_eq!(response.data.unwrap().approvals, vec!["bob".to_string()]);
+
+        // Withdrawing from an account opens a funding request instead of debiting it
+        let Json(response) = withdraw(Extension(state.clone()), user("alice"), Path(account.id.clone()), Json(WithdrawRequest {
+            asset: "USDC".to_string(),
+            amount: 3000.0,
+            external_address: "0xCold".to_string(),
+        })).await.unwrap();
+        let request = response.data.unwrap();
+        assert_eq!(request.status, FundingStatus::Requested);
+        assert_eq!(request.approvals_required, 1);
+        assert_eq!(state.treasury_manager.read().await.get_balance(&account.id, "USDC"), 5000.0);
+    }
+}
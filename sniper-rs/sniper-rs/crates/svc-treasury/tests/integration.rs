@@ -0,0 +1,67 @@
+//! Integration tests for the treasury service
+
+use sniper_core::types::ChainRef;
+use sniper_treasury::{AccountKind, TreasuryAction, TreasuryManager};
+
+fn chain(name: &str, id: u64) -> ChainRef {
+    ChainRef {
+        name: name.to_string(),
+        id,
+    }
+}
+
+#[test]
+fn test_treasury_multi_venue_balances() {
+    let mut treasury_manager = TreasuryManager::new();
+
+    let eth_hot = treasury_manager.create_account(
+        "eth-hot", AccountKind::HotWallet, chain("ethereum", 1), "onchain", "0xEthHot", "treasury-tenant-1", "ops",
+    );
+    let bsc_hot = treasury_manager.create_account(
+        "bsc-hot", AccountKind::HotWallet, chain("bsc", 56), "onchain", "0xBscHot", "treasury-tenant-1", "ops",
+    );
+    let exchange = treasury_manager.create_account(
+        "binance", AccountKind::Exchange, chain("ethereum", 1), "binance", "sub-account-1", "treasury-tenant-1", "ops",
+    );
+
+    treasury_manager.deposit(&eth_hot.id, "USDC", 10000.0, "ops").expect("Failed to deposit");
+    treasury_manager.deposit(&bsc_hot.id, "USDC", 5000.0, "ops").expect("Failed to deposit");
+    treasury_manager.transfer(&eth_hot.id, &exchange.id, "USDC", 2500.0, "ops").expect("Failed to transfer");
+
+    assert_eq!(treasury_manager.get_balance(&eth_hot.id, "USDC"), 7500.0);
+    assert_eq!(treasury_manager.get_balance(&exchange.id, "USDC"), 2500.0);
+    assert_eq!(treasury_manager.total_tenant_balance("treasury-tenant-1", "USDC"), 15000.0);
+    assert_eq!(treasury_manager.list_tenant_accounts("treasury-tenant-1").len(), 3);
+}
+
+#[test]
+fn test_treasury_sweep_and_alert_workflow() {
+    let mut treasury_manager = TreasuryManager::new();
+
+    let hot = treasury_manager.create_account(
+        "hot", AccountKind::HotWallet, chain("ethereum", 1), "onchain", "0xHot", "treasury-tenant-2", "ops",
+    );
+    let cold = treasury_manager.create_account(
+        "cold", AccountKind::ColdWallet, chain("ethereum", 1), "onchain", "0xCold", "treasury-tenant-2", "ops",
+    );
+
+    treasury_manager.create_sweep_rule(&hot.id, &cold.id, "WETH", 10.0, 2.0, "ops")
+        .expect("Failed to create sweep rule");
+    treasury_manager.set_low_balance_threshold(&hot.id, "WETH", 1.0, "ops")
+        .expect("Failed to set threshold");
+
+    treasury_manager.deposit(&hot.id, "WETH", 12.0, "ops").expect("Failed to deposit");
+    let swept = treasury_manager.run_sweeps("scheduler");
+    assert_eq!(swept.len(), 1);
+    assert_eq!(treasury_manager.get_balance(&cold.id, "WETH"), 10.0);
+    assert!(treasury_manager.check_low_balances("treasury-tenant-2").is_empty());
+
+    treasury_manager.withdraw(&hot.id, "WETH", 1.5, "ops").expect("Failed to withdraw");
+    let alerts = treasury_manager.check_low_balances("treasury-tenant-2");
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].asset, "WETH");
+
+    let trail = treasury_manager.get_tenant_audit_trail("treasury-tenant-2");
+    assert!(trail.iter().any(|entry| entry.action == TreasuryAction::Sweep));
+    assert!(trail.iter().any(|entry| entry.action == TreasuryAction::Withdrawal));
+}
@@ -1,56 +1,145 @@
 //! User management service for the sniper-rs enterprise features.
 //! 
 //! This service provides REST APIs for multi-user support with isolated contexts,
-//! advanced RBAC, and audit logging.
+//! advanced RBAC with custom roles defined at runtime, a persistent and
+//! queryable audit log, notification preferences and password and API key
+//! credentials.
+//! Authenticating issues the access token the other services verify.
+//! The OpenAPI document is served on `/openapi.json`, with a Swagger UI on
+//! `/docs`.
 
 use anyhow::Result;
 use clap::Parser;
+use sniper_core::config::ConfigArgs;
+use sniper_core::errors::{ApiError, ErrorKind};
+use sniper_core::openapi::{self, BearerAuth, ErrorResponse};
+use sniper_core::rate_limit::{RateLimitConfig, RateLimiter};
+use sniper_core::shutdown::Shutdown;
 use serde::{Deserialize, Serialize};
 use axum::{
-    routing::{get, post},
+    extract::Query,
+    handler::Handler,
+    routing::{delete, get, post, put},
     Json, Router, Extension,
 };
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use sniper_users::{UserManager, UserRole, User, UserContext, AuditLog};
+use tokio::sync::{RwLock, RwLockWriteGuard};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use sniper_users::{UserManager, UserRole, User, UserContext, UserError, AuditLog, NotificationPreferences, JwtAuth, AuthLayer, require_permission, ApiKey, NewApiKey, RoleDefinition, AccessRequest, AuditQuery, AuditStore, RetentionPolicy};
+use sniper_users::credentials::MIN_PASSWORD_LEN;
+use sniper_users::http::Viewer;
+use sniper_monitoring::http::{instrument, ServiceMetrics};
+
+/// Port used when neither the configuration nor --port sets one
+const DEFAULT_PORT: u16 = 8084;
 
 /// CLI arguments for the user service
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    /// Port to listen on
-    #[clap(short, long, default_value = "8084")]
-    port: u16,
+    /// Port to listen on, overriding the configured port (default 8084)
+    #[clap(short, long)]
+    port: Option<u16>,
+
+    /// Append-only audit log file; audit logs are kept in memory only when unset
+    #[clap(long)]
+    audit_log: Option<std::path::PathBuf>,
+
+    /// Days to keep audit logs
+    #[clap(long)]
+    audit_retention_days: Option<i64>,
+
+    /// Most audit logs to keep
+    #[clap(long)]
+    audit_max_entries: Option<usize>,
+
+    /// OTLP endpoint spans are exported to, defaulting to OTEL_EXPORTER_OTLP_ENDPOINT
+    #[clap(long)]
+    otlp_endpoint: Option<String>,
+
+    #[clap(flatten)]
+    config: ConfigArgs,
 }
 
+/// Interval between audit retention passes
+const AUDIT_RETENTION_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
 /// User service state
 struct AppState {
     user_manager: RwLock<UserManager>,
+    metrics: Arc<ServiceMetrics>,
+    auth: Arc<JwtAuth>,
+}
+
+/// Administrator created at startup, in the `bootstrap_admin` section
+///
+/// Creating users takes `manage_users`, so the first administrator cannot be
+/// created over the API.
+#[derive(Debug, Clone, Deserialize)]
+struct BootstrapAdmin {
+    pub username: String,
+    pub email: String,
+    pub tenant_id: String,
+    /// Usually `${ADMIN_PASSWORD}`, to keep it out of the file
+    pub password: String,
 }
 
 /// User creation request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct CreateUserRequest {
     pub username: String,
     pub email: String,
     pub roles: Vec<String>, // Will be parsed into UserRole
     pub tenant_id: String,
+    /// Initial password; users without one can only log in with API keys
+    #[serde(default)]
+    pub password: Option<String>,
 }
 
-/// User authentication request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// User authentication request, by username and password or by API key
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
 struct AuthenticateUserRequest {
-    pub username: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub api_key: Option<String>,
+}
+
+/// Password change request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct ChangePasswordRequest {
+    /// Required unless an administrator is resetting the password
+    #[serde(default)]
+    pub current_password: Option<String>,
+    pub new_password: String,
+}
+
+/// API key creation request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct CreateApiKeyRequest {
+    pub name: String,
+    /// Permissions the key grants
+    pub scopes: Vec<String>,
+    /// Lifetime of the key; it never expires when absent
+    #[serde(default)]
+    pub ttl_secs: Option<i64>,
 }
 
 /// Role assignment request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct AssignRoleRequest {
     pub role: String, // Will be parsed into UserRole
 }
 
+/// Custom role definition request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct DefineRoleRequest {
+    /// Flat permission names and `resource:action:scope` grants; `!` denies
+    pub permissions: Vec<String>,
+}
+
 /// Standard response format
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
@@ -58,7 +147,7 @@ struct ApiResponse<T> {
 }
 
 /// User response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct UserResponse {
     pub id: String,
     pub username: String,
@@ -75,7 +164,7 @@ impl From<User> for UserResponse {
             id: user.id,
             username: user.username,
             email: user.email,
-            roles: user.roles.iter().map(|r| format!("{:?}", r)).collect(),
+            roles: user.roles.iter().map(|r| r.name().to_string()).collect(),
             tenant_id: user.tenant_id,
             created_at: user.created_at.to_rfc3339(),
             last_login: user.last_login.map(|dt| dt.to_rfc3339()),
@@ -84,7 +173,7 @@ impl From<User> for UserResponse {
 }
 
 /// User context response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct UserContextResponse {
     pub user_id: String,
     pub tenant_id: String,
@@ -97,21 +186,32 @@ impl From<UserContext> for UserContextResponse {
         UserContextResponse {
             user_id: context.user_id,
             tenant_id: context.tenant_id,
-            roles: context.roles.iter().map(|r| format!("{:?}", r)).collect(),
+            roles: context.roles.iter().map(|r| r.name().to_string()).collect(),
             permissions: context.permissions,
         }
     }
 }
 
+/// Authentication response carrying the access token
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct AuthenticationResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_at: String,
+    pub context: UserContextResponse,
+}
+
 /// Audit log response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct AuditLogResponse {
     pub id: String,
     pub user_id: String,
+    pub tenant_id: String,
     pub action: String,
     pub resource: String,
     pub timestamp: String,
     pub details: Option<String>,
+    pub correlation_id: Option<String>,
 }
 
 impl From<AuditLog> for AuditLogResponse {
@@ -119,51 +219,143 @@ impl From<AuditLog> for AuditLogResponse {
         AuditLogResponse {
             id: log.id,
             user_id: log.user_id,
+            tenant_id: log.tenant_id,
             action: log.action,
             resource: log.resource,
             timestamp: log.timestamp.to_rfc3339(),
             details: log.details,
+            correlation_id: log.correlation_id,
         }
     }
 }
 
+/// OpenAPI document of the service, served on `/openapi.json`
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        health_check, create_user, get_user, authenticate_user, change_password, list_api_keys, create_api_key,
+        rotate_api_key, revoke_api_key, assign_role, list_roles, define_role, remove_role, get_user_context,
+        get_user_audit_logs, query_audit_logs, export_audit_logs, get_notification_preferences,
+        set_notification_preferences,
+    ),
+    modifiers(&BearerAuth),
+    security(("bearer_auth" = [])),
+    tags(
+        (name = "auth", description = "Access tokens"),
+        (name = "users", description = "Users and their contexts"),
+        (name = "credentials", description = "Passwords and API keys"),
+        (name = "roles", description = "Built-in and custom roles"),
+        (name = "audit", description = "Audit log queries and exports"),
+        (name = "notifications", description = "Notification preferences"),
+    )
+)]
+struct ApiDoc;
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
     
     let args = Args::parse();
+    let config = args.config.loader("svc-users")
+        .with_default("port", DEFAULT_PORT)
+        .with_override("port", args.port)
+        .load_service()?;
+    if args.config.print_config {
+        println!("{}", config.render()?);
+        return Ok(());
+    }
+    let _telemetry = sniper_core::telemetry::init_telemetry("svc-users", args.otlp_endpoint.as_deref())?;
+    let shutdown = Shutdown::on_signal();
     
     // Create user manager
-    let user_manager = UserManager::new();
+    let retention = RetentionPolicy {
+        max_age_days: args.audit_retention_days,
+        max_entries: args.audit_max_entries,
+    };
+    let audit = match &args.audit_log {
+        Some(path) => AuditStore::open(path)?,
+        None => AuditStore::in_memory(),
+    };
+    let mut user_manager = UserManager::new().with_audit_store(audit.with_retention(retention));
+    user_manager.apply_audit_retention()?;
+    if let Some(admin) = config.section::<BootstrapAdmin>("bootstrap_admin")? {
+        let user = user_manager.create_user(&admin.username, &admin.email, vec![UserRole::Admin], &admin.tenant_id)?;
+        user_manager.set_password(&user.id, &admin.password)?;
+        tracing::info!("created administrator {} of tenant {}", admin.username, admin.tenant_id);
+    }
+    
+    // Sign access tokens with the secret shared by the services
+    let auth = Arc::new(JwtAuth::from_config(&config.auth)?);
+    
+    
+    // Create service metrics
+    let mut metrics = ServiceMetrics::new("svc-users")?;
+    metrics.register_counter("users_created_total", "Total users created")?;
+    metrics.register_counter("logins_total", "Total successful logins")?;
+    let metrics = Arc::new(metrics);
     
     // Create app state
     let app_state = Arc::new(AppState {
         user_manager: RwLock::new(user_manager),
+        metrics: metrics.clone(),
+        auth: auth.clone(),
     });
     
+    // Drop audit logs past retention in the background
+    shutdown.spawn(enforce_audit_retention(app_state.clone()));
+    
+    // Throttle callers per tenant, separately for reads and trades
+    let rate_limiter = RateLimiter::new(config.section::<RateLimitConfig>("rate_limit")?.unwrap_or_default())
+        .with_registry(metrics.registry(), "svc-users")?;
+
+    // Creating users and granting roles takes a user administrator
+    let user_admin = require_permission("manage_users");
+
     // Create router
     let app = Router::new()
         .route("/health", get(health_check))
-        .route("/users", post(create_user))
+        .route("/users", post(create_user.layer(user_admin)))
         .route("/users/:id", get(get_user))
         .route("/auth", post(authenticate_user))
-        .route("/users/:id/roles", post(assign_role))
+        .route("/users/:id/password", put(change_password))
+        .route("/users/:id/api-keys", get(list_api_keys).post(create_api_key))
+        .route("/api-keys/:id/rotate", post(rotate_api_key))
+        .route("/api-keys/:id", delete(revoke_api_key))
+        .route("/users/:id/roles", post(assign_role.layer(user_admin)))
+        .route("/roles", get(list_roles))
+        .route("/roles/:name", put(define_role).delete(remove_role))
         .route("/users/:id/context", get(get_user_context))
         .route("/users/:id/audit", get(get_user_audit_logs))
-        .route("/audit", get(get_all_audit_logs))
-        .layer(Extension(app_state));
+        .route("/audit", get(query_audit_logs))
+        .route("/audit/export", get(export_audit_logs))
+        .route("/users/:id/notifications", get(get_notification_preferences).post(set_notification_preferences))
+        .layer(Extension(app_state))
+        .layer(rate_limiter.layer())
+        .layer(AuthLayer::new(auth))
+        .layer(axum::middleware::from_fn(sniper_core::telemetry::trace_http));
+    let app = instrument(app, metrics).merge(openapi::router(ApiDoc::openapi()));
     
     // Run server
-    let addr = format!("0.0.0.0:{}", args.port);
+    let addr = format!("0.0.0.0:{}", config.port.unwrap_or(DEFAULT_PORT));
     tracing::info!("User service listening on http://{}", addr);
     
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    sniper_core::shutdown::serve(listener, app, &shutdown).await?;
+    shutdown.join().await;
         
     Ok(())
 }
 
 /// Health check endpoint
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    security(()),
+    responses(
+        (status = 200, description = "Service is healthy", body = ApiResponse<String>),
+    )
+)]
 async fn health_check() -> Json<ApiResponse<String>> {
     let response = ApiResponse {
         success: true,
@@ -174,175 +366,325 @@ async fn health_check() -> Json<ApiResponse<String>> {
 }
 
 /// Create a new user
+#[utoipa::path(
+    post,
+    path = "/users",
+    tag = "users",
+    request_body = CreateUserRequest,
+    responses(
+        (status = 200, description = "User created", body = ApiResponse<UserResponse>),
+        (status = 401, description = "No access token", body = ErrorResponse),
+        (status = 403, description = "Missing `manage_users`, or another tenant", body = ErrorResponse),
+        (status = 422, description = "Password too short or undefined roles", body = ErrorResponse),
+    )
+)]
 async fn create_user(
     Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
     Json(payload): Json<CreateUserRequest>,
-) -> Json<ApiResponse<UserResponse>> {
-    // Parse roles from strings to UserRole enum
+) -> Result<Json<ApiResponse<UserResponse>>, ApiError> {
+    if !may_manage_users(&viewer, &payload.tenant_id) {
+        return Err(forbidden("Not allowed to create users in this tenant"));
+    }
+    if payload.password.as_ref().is_some_and(|password| password.chars().count() < MIN_PASSWORD_LEN) {
+        return Err(UserError::PasswordTooShort.into());
+    }
+    
+    let mut user_manager = state.user_manager.write().await;
+    
+    // Parse roles from strings to built-in or custom roles
     let roles: Vec<UserRole> = payload.roles
         .iter()
-        .map(|role| match role.as_str() {
-            "Admin" => UserRole::Admin,
-            "Trader" => UserRole::Trader,
-            "Analyst" => UserRole::Analyst,
-            "Auditor" => UserRole::Auditor,
-            _ => UserRole::Guest,
-        })
+        .map(|role| parse_role(&user_manager, role))
         .collect();
     
-    let result = state.user_manager.write().await.create_user(
+    let user = user_manager.create_user(
         &payload.username,
         &payload.email,
         roles,
         &payload.tenant_id,
-    );
+    ).and_then(|user| {
+        if let Some(password) = &payload.password {
+            user_manager.set_password(&user.id, password)?;
+        }
+        Ok(user)
+    }).map_err(|e| ApiError::from(UserError::from(e)).context("Failed to create user"))?;
     
-    match result {
-        Ok(user) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(UserResponse::from(user)),
-                message: Some("User created successfully".to_string()),
-            };
-            Json(response)
-        },
-        Err(e) => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some(format!("Failed to create user: {}", e)),
-            };
-            Json(response)
-        },
-    }
+    state.metrics.increment_counter("users_created_total");
+    let response = ApiResponse {
+        success: true,
+        data: Some(UserResponse::from(user)),
+        message: Some("User created successfully".to_string()),
+    };
+    Ok(Json(response))
 }
 
 /// Get a user by ID
+#[utoipa::path(
+    get,
+    path = "/users/{id}",
+    tag = "users",
+    params(("id" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "The user", body = ApiResponse<UserResponse>),
+        (status = 403, description = "Neither the user nor allowed to read the tenant's users", body = ErrorResponse),
+        (status = 404, description = "No such user", body = ErrorResponse),
+    )
+)]
 async fn get_user(
     Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<ApiResponse<UserResponse>> {
-    let user_opt = state.user_manager.read().await.get_user(&id).cloned();
-    
-    match user_opt {
-        Some(user) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(UserResponse::from(user)),
-                message: None,
-            };
-            Json(response)
-        },
-        None => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some("User not found".to_string()),
-            };
-            Json(response)
-        },
+) -> Result<Json<ApiResponse<UserResponse>>, ApiError> {
+    let user = state.user_manager.read().await.get_user(&id).cloned()
+        .ok_or(UserError::UserNotFound(id))?;
+    if !may_read_user(&viewer, &user) {
+        return Err(forbidden("Not allowed to read this user"));
     }
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(UserResponse::from(user)),
+        message: None,
+    };
+    Ok(Json(response))
 }
 
-/// Authenticate a user
+/// Authenticate a user and issue their access token
+#[utoipa::path(
+    post,
+    path = "/auth",
+    tag = "auth",
+    request_body = AuthenticateUserRequest,
+    security(()),
+    responses(
+        (status = 200, description = "Access token of the authenticated user", body = ApiResponse<AuthenticationResponse>),
+        (status = 401, description = "Unknown user, wrong password or invalid API key", body = ErrorResponse),
+    )
+)]
 async fn authenticate_user(
     Extension(state): Extension<Arc<AppState>>,
     Json(payload): Json<AuthenticateUserRequest>,
-) -> Json<ApiResponse<UserContextResponse>> {
-    let context_opt = state.user_manager.write().await.authenticate_user(&payload.username);
+) -> Result<Json<ApiResponse<AuthenticationResponse>>, ApiError> {
+    let context_opt = {
+        let mut user_manager = state.user_manager.write().await;
+        match (&payload.username, &payload.password, &payload.api_key) {
+            (Some(username), Some(password), None) => user_manager.authenticate_user(username, password),
+            (None, None, Some(api_key)) => user_manager.authenticate_api_key(api_key),
+            _ => None,
+        }
+    };
     
-    match context_opt {
-        Some(context) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(UserContextResponse::from(context)),
-                message: Some("User authenticated successfully".to_string()),
-            };
-            Json(response)
-        },
-        None => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some("Authentication failed".to_string()),
-            };
-            Json(response)
-        },
+    let context = context_opt.ok_or_else(|| ApiError::new(ErrorKind::Unauthenticated, "Authentication failed"))?;
+    let issued = state.auth.issue(&context)
+        .map_err(|e| ApiError::from(e).context("Failed to issue access token"))?;
+    
+    state.metrics.increment_counter("logins_total");
+    let response = ApiResponse {
+        success: true,
+        data: Some(AuthenticationResponse {
+            access_token: issued.token,
+            token_type: "Bearer".to_string(),
+            expires_at: issued.expires_at.to_rfc3339(),
+            context: UserContextResponse::from(context),
+        }),
+        message: Some("User authenticated successfully".to_string()),
+    };
+    Ok(Json(response))
+}
+
+/// Whether the viewer may define and remove custom roles
+fn may_manage_roles(viewer: &UserContext) -> bool {
+    !viewer.is_anonymous() && viewer.permissions.iter().any(|p| p == "manage_roles")
+}
+
+/// List built-in and custom roles with their permissions
+#[utoipa::path(
+    get,
+    path = "/roles",
+    tag = "roles",
+    responses(
+        (status = 200, description = "Built-in and custom roles", body = ApiResponse<Vec<RoleDefinition>>),
+    )
+)]
+async fn list_roles(
+    Extension(state): Extension<Arc<AppState>>,
+) -> Json<ApiResponse<Vec<RoleDefinition>>> {
+    let roles = state.user_manager.read().await.list_roles();
+    Json(ApiResponse {
+        success: true,
+        data: Some(roles),
+        message: None,
+    })
+}
+
+/// Define or replace a custom role
+#[utoipa::path(
+    put,
+    path = "/roles/{name}",
+    tag = "roles",
+    params(("name" = String, Path, description = "Role name")),
+    request_body = DefineRoleRequest,
+    responses(
+        (status = 200, description = "Role defined", body = ApiResponse<RoleDefinition>),
+        (status = 403, description = "Missing `manage_roles`, or the role is built in", body = ErrorResponse),
+        (status = 422, description = "Invalid role name or permissions", body = ErrorResponse),
+    )
+)]
+async fn define_role(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    axum::extract::Path(name): axum::extract::Path<String>,
+    Json(payload): Json<DefineRoleRequest>,
+) -> Result<Json<ApiResponse<RoleDefinition>>, ApiError> {
+    if !may_manage_roles(&viewer) {
+        return Err(forbidden("Not allowed to manage roles"));
     }
+    
+    let role = state.user_manager.write().await.define_role(&viewer.user_id, &name, payload.permissions.clone())
+        .map_err(|e| ApiError::from(UserError::from(e)).context("Failed to define role"))?;
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(RoleDefinition {
+            name: role.name().to_string(),
+            built_in: false,
+            permissions: payload.permissions,
+        }),
+        message: Some("Role defined successfully".to_string()),
+    }))
+}
+
+/// Remove a custom role no user holds
+#[utoipa::path(
+    delete,
+    path = "/roles/{name}",
+    tag = "roles",
+    params(("name" = String, Path, description = "Role name")),
+    responses(
+        (status = 200, description = "Role removed", body = ApiResponse<bool>),
+        (status = 403, description = "Missing `manage_roles`, or the role is built in", body = ErrorResponse),
+        (status = 404, description = "No such role", body = ErrorResponse),
+        (status = 409, description = "Users still hold the role", body = ErrorResponse),
+    )
+)]
+async fn remove_role(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    axum::extract::Path(name): axum::extract::Path<String>,
+) -> Result<Json<ApiResponse<bool>>, ApiError> {
+    if !may_manage_roles(&viewer) {
+        return Err(forbidden("Not allowed to manage roles"));
+    }
+    
+    state.user_manager.write().await.remove_role(&viewer.user_id, &name)
+        .map_err(|e| ApiError::from(UserError::from(e)).context("Failed to remove role"))?;
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(true),
+        message: Some("Role removed successfully".to_string()),
+    }))
+}
+
+/// Resolve a role name, falling back to Guest for unknown names
+fn parse_role(user_manager: &UserManager, name: &str) -> UserRole {
+    user_manager.role(name).unwrap_or(UserRole::Guest)
 }
 
 /// Assign a role to a user
+#[utoipa::path(
+    post,
+    path = "/users/{id}/roles",
+    tag = "roles",
+    params(("id" = String, Path, description = "User ID")),
+    request_body = AssignRoleRequest,
+    responses(
+        (status = 200, description = "Role assigned", body = ApiResponse<bool>),
+        (status = 401, description = "No access token", body = ErrorResponse),
+        (status = 403, description = "Missing `manage_users`, or a user of another tenant", body = ErrorResponse),
+        (status = 404, description = "No such user", body = ErrorResponse),
+    )
+)]
 async fn assign_role(
     Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
     axum::extract::Path(id): axum::extract::Path<String>,
     Json(payload): Json<AssignRoleRequest>,
-) -> Json<ApiResponse<bool>> {
-    // Parse role from string to UserRole enum
-    let role = match payload.role.as_str() {
-        "Admin" => UserRole::Admin,
-        "Trader" => UserRole::Trader,
-        "Analyst" => UserRole::Analyst,
-        "Auditor" => UserRole::Auditor,
-        _ => UserRole::Guest,
-    };
-    
-    let result = state.user_manager.write().await.add_user_role(&id, role);
-    
-    match result {
-        Ok(_) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(true),
-                message: Some("Role assigned successfully".to_string()),
-            };
-            Json(response)
-        },
-        Err(e) => {
-            let response = ApiResponse {
-                success: false,
-                data: Some(false),
-                message: Some(format!("Failed to assign role: {}", e)),
-            };
-            Json(response)
-        },
+) -> Result<Json<ApiResponse<bool>>, ApiError> {
+    let mut user_manager = state.user_manager.write().await;
+    let user = user_manager.get_user(&id).ok_or_else(|| UserError::UserNotFound(id.clone()))?;
+    if !may_manage_users(&viewer, &user.tenant_id) {
+        return Err(forbidden("Not allowed to manage this user's roles"));
     }
+    let role = parse_role(&user_manager, &payload.role);
+    user_manager.add_user_role(&id, role)
+        .map_err(|e| ApiError::from(UserError::from(e)).context("Failed to assign role"))?;
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(true),
+        message: Some("Role assigned successfully".to_string()),
+    };
+    Ok(Json(response))
 }
 
 /// Get user context
+#[utoipa::path(
+    get,
+    path = "/users/{id}/context",
+    tag = "users",
+    params(("id" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "Roles and permissions of the user", body = ApiResponse<UserContextResponse>),
+        (status = 403, description = "Neither the user nor allowed to read the tenant's users", body = ErrorResponse),
+        (status = 404, description = "No such user", body = ErrorResponse),
+    )
+)]
 async fn get_user_context(
     Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<ApiResponse<UserContextResponse>> {
-    let context_opt = state.user_manager.read().await.get_user_context(&id);
-    
-    match context_opt {
-        Some(context) => {
-            let response = ApiResponse {
-                success: true,
-                data: Some(UserContextResponse::from(context)),
-                message: None,
-            };
-            Json(response)
-        },
-        None => {
-            let response = ApiResponse {
-                success: false,
-                data: None,
-                message: Some("User context not found".to_string()),
-            };
-            Json(response)
-        },
+) -> Result<Json<ApiResponse<UserContextResponse>>, ApiError> {
+    let user_manager = state.user_manager.read().await;
+    let user = user_manager.get_user(&id).ok_or_else(|| UserError::UserNotFound(id.clone()))?;
+    if !may_read_user(&viewer, user) {
+        return Err(forbidden("Not allowed to read this user"));
     }
+    let context = user_manager.get_user_context(&id)
+        .ok_or(UserError::UserNotFound(id))?;
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(UserContextResponse::from(context)),
+        message: None,
+    };
+    Ok(Json(response))
 }
 
 /// Get user audit logs
+#[utoipa::path(
+    get,
+    path = "/users/{id}/audit",
+    tag = "audit",
+    params(("id" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "Audit logs of the user in the caller's tenant, newest first", body = ApiResponse<Vec<AuditLogResponse>>),
+        (status = 403, description = "Not allowed to read the tenant's audit logs", body = ErrorResponse),
+    )
+)]
 async fn get_user_audit_logs(
     Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Json<ApiResponse<Vec<AuditLogResponse>>> {
-    let logs = state.user_manager.read().await.get_user_audit_logs(&id)
-        .iter()
-        .map(|&log| AuditLogResponse::from(log.clone()))
+) -> Result<Json<ApiResponse<Vec<AuditLogResponse>>>, ApiError> {
+    let query = AuditQuery {
+        user_id: Some(id),
+        ..AuditQuery::default()
+    };
+    let query = scope_audit_query(&viewer, query)
+        .ok_or_else(|| forbidden("Not allowed to read this tenant's audit logs"))?;
+    let logs = refreshed_audit_logs(&state).await.query_audit_logs(&query)
+        .into_iter()
+        .map(AuditLogResponse::from)
         .collect::<Vec<AuditLogResponse>>();
     
     let response = ApiResponse {
@@ -350,16 +692,47 @@ async fn get_user_audit_logs(
         data: Some(logs),
         message: None,
     };
-    Json(response)
+    Ok(Json(response))
+}
+
+/// Scope an audit query to a tenant the viewer may read, defaulting to their own
+fn scope_audit_query(viewer: &UserContext, mut query: AuditQuery) -> Option<AuditQuery> {
+    let tenant_id = query.tenant_id.get_or_insert_with(|| viewer.tenant_id.clone());
+    viewer
+        .can(&AccessRequest::new("audit", "read", tenant_id))
+        .then_some(query)
 }
 
-/// Get all audit logs
-async fn get_all_audit_logs(
+/// User manager with the audit entries other services appended to the shared log loaded
+async fn refreshed_audit_logs(state: &AppState) -> RwLockWriteGuard<'_, UserManager> {
+    let mut user_manager = state.user_manager.write().await;
+    if let Err(e) = user_manager.refresh_audit_logs() {
+        tracing::error!("failed to load new audit logs: {}", e);
+    }
+    user_manager
+}
+
+/// Query audit logs by time range, action, resource, tenant and correlation ID
+#[utoipa::path(
+    get,
+    path = "/audit",
+    tag = "audit",
+    params(AuditQuery),
+    responses(
+        (status = 200, description = "Audit logs matching the query, newest first", body = ApiResponse<Vec<AuditLogResponse>>),
+        (status = 403, description = "Not allowed to read the tenant's audit logs", body = ErrorResponse),
+    )
+)]
+async fn query_audit_logs(
     Extension(state): Extension<Arc<AppState>>,
-) -> Json<ApiResponse<Vec<AuditLogResponse>>> {
-    let logs = state.user_manager.read().await.get_all_audit_logs()
-        .iter()
-        .map(|log| AuditLogResponse::from(log.clone()))
+    Viewer(viewer): Viewer,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<ApiResponse<Vec<AuditLogResponse>>>, ApiError> {
+    let query = scope_audit_query(&viewer, query)
+        .ok_or_else(|| forbidden("Not allowed to read this tenant's audit logs"))?;
+    let logs = refreshed_audit_logs(&state).await.query_audit_logs(&query)
+        .into_iter()
+        .map(AuditLogResponse::from)
         .collect::<Vec<AuditLogResponse>>();
     
     let response = ApiResponse {
@@ -367,17 +740,346 @@ async fn get_all_audit_logs(
         data: Some(logs),
         message: None,
     };
-    Json(response)
+    Ok(Json(response))
+}
+
+/// Audit export format, given next to the audit filter
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ExportAuditParams {
+    /// `json` or `csv`
+    #[serde(default = "default_export_format")]
+    pub format: String,
+}
+
+fn default_export_format() -> String {
+    "json".to_string()
+}
+
+/// Export audit logs matching a query as JSON or CSV
+#[utoipa::path(
+    get,
+    path = "/audit/export",
+    tag = "audit",
+    params(AuditQuery, ExportAuditParams),
+    responses(
+        (status = 200, description = "Audit logs matching the query, rendered as JSON or CSV", body = ApiResponse<String>),
+        (status = 403, description = "Not allowed to read the tenant's audit logs", body = ErrorResponse),
+        (status = 422, description = "Unsupported export format", body = ErrorResponse),
+    )
+)]
+async fn export_audit_logs(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    Query(query): Query<AuditQuery>,
+    Query(params): Query<ExportAuditParams>,
+) -> Result<Json<ApiResponse<String>>, ApiError> {
+    let query = scope_audit_query(&viewer, query)
+        .ok_or_else(|| forbidden("Not allowed to read this tenant's audit logs"))?;
+    let result = refreshed_audit_logs(&state).await.export_audit_logs(&query, &params.format);
+    let data = result.and_then(|data| Ok(String::from_utf8(data)?))
+        .map_err(|e| ApiError::from(UserError::from(e)).context("Failed to export audit logs"))?;
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(data),
+        message: Some("Audit logs exported successfully".to_string()),
+    }))
+}
+
+/// Drop audit logs outside the retention policy periodically
+async fn enforce_audit_retention(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(AUDIT_RETENTION_INTERVAL);
+    loop {
+        interval.tick().await;
+        match state.user_manager.write().await.apply_audit_retention() {
+            Ok(0) => {},
+            Ok(dropped) => tracing::info!("dropped {} audit logs past retention", dropped),
+            Err(e) => tracing::error!("failed to apply audit retention: {}", e),
+        }
+    }
+}
+
+/// Whether the viewer is a user administrator of `tenant_id`
+fn may_manage_users(viewer: &UserContext, tenant_id: &str) -> bool {
+    !viewer.is_anonymous() && viewer.tenant_id == tenant_id && viewer.permissions.iter().any(|p| p == "manage_users")
+}
+
+/// Whether the viewer may manage a user's credentials: the user themselves or
+/// a user administrator of the same tenant
+fn may_manage_credentials(viewer: &UserContext, user: &User) -> bool {
+    (!viewer.is_anonymous() && viewer.user_id == user.id) || may_manage_users(viewer, &user.tenant_id)
+}
+
+/// Whether the viewer may read a user: the user themselves or a reader of
+/// the users of the user's tenant
+fn may_read_user(viewer: &UserContext, user: &User) -> bool {
+    (!viewer.is_anonymous() && viewer.user_id == user.id)
+        || viewer.can(&AccessRequest::new("users", "read", &user.tenant_id).owned_by(&user.id))
+}
+
+fn forbidden(message: &str) -> ApiError {
+    ApiError::new(ErrorKind::Forbidden, message)
+}
+
+/// Change a user's password; administrators may reset it without the current one
+#[utoipa::path(
+    put,
+    path = "/users/{id}/password",
+    tag = "credentials",
+    params(("id" = String, Path, description = "User ID")),
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 200, description = "Password changed", body = ApiResponse<bool>),
+        (status = 403, description = "Wrong current password", body = ErrorResponse),
+        (status = 404, description = "No such user", body = ErrorResponse),
+        (status = 422, description = "Missing current password, or an unacceptable new one", body = ErrorResponse),
+    )
+)]
+async fn change_password(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<Json<ApiResponse<bool>>, ApiError> {
+    let mut user_manager = state.user_manager.write().await;
+    let user = user_manager.get_user(&id).cloned()
+        .ok_or_else(|| UserError::UserNotFound(id.clone()))?;
+    
+    let result = match &payload.current_password {
+        Some(current) => user_manager.change_password(&id, current, &payload.new_password),
+        None if may_manage_credentials(&viewer, &user) && viewer.user_id != user.id => {
+            user_manager.set_password(&id, &payload.new_password)
+        },
+        None => return Err(ApiError::invalid("Current password is required")),
+    };
+    result.map_err(|e| ApiError::from(UserError::from(e)).context("Failed to update password"))?;
+    
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(true),
+        message: Some("Password updated successfully".to_string()),
+    }))
+}
+
+/// Create an API key for a user; the secret is only returned here
+#[utoipa::path(
+    post,
+    path = "/users/{id}/api-keys",
+    tag = "credentials",
+    params(("id" = String, Path, description = "User ID")),
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 200, description = "API key created; its secret is only shown here", body = ApiResponse<NewApiKey>),
+        (status = 403, description = "Not allowed to manage the user's API keys, or a scope the user does not hold", body = ErrorResponse),
+        (status = 404, description = "No such user", body = ErrorResponse),
+        (status = 422, description = "No scopes or a non-positive lifetime", body = ErrorResponse),
+    )
+)]
+async fn create_api_key(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> Result<Json<ApiResponse<NewApiKey>>, ApiError> {
+    let mut user_manager = state.user_manager.write().await;
+    match user_manager.get_user(&id) {
+        Some(user) if may_manage_credentials(&viewer, user) => {},
+        Some(_) => return Err(forbidden("Not allowed to manage this user's API keys")),
+        None => return Err(UserError::UserNotFound(id).into()),
+    }
+    
+    let ttl = payload.ttl_secs.map(chrono::Duration::seconds);
+    let created = user_manager.create_api_key(&id, &payload.name, payload.scopes, ttl)
+        .map_err(|e| ApiError::from(UserError::from(e)).context("Failed to create API key"))?;
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(created),
+        message: Some("API key created; store the secret now, it is not shown again".to_string()),
+    }))
+}
+
+/// List a user's API keys, without their secrets
+#[utoipa::path(
+    get,
+    path = "/users/{id}/api-keys",
+    tag = "credentials",
+    params(("id" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "API keys of the user", body = ApiResponse<Vec<ApiKey>>),
+        (status = 403, description = "Not allowed to view the user's API keys", body = ErrorResponse),
+        (status = 404, description = "No such user", body = ErrorResponse),
+    )
+)]
+async fn list_api_keys(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<ApiResponse<Vec<ApiKey>>>, ApiError> {
+    let user_manager = state.user_manager.read().await;
+    match user_manager.get_user(&id) {
+        Some(user) if may_manage_credentials(&viewer, user) => Ok(Json(ApiResponse {
+            success: true,
+            data: Some(user_manager.list_api_keys(&id)),
+            message: None,
+        })),
+        Some(_) => Err(forbidden("Not allowed to view this user's API keys")),
+        None => Err(UserError::UserNotFound(id).into()),
+    }
+}
+
+/// Check that the viewer may manage the credentials of an API key's owner
+fn api_key_owner(user_manager: &UserManager, viewer: &UserContext, key_id: &str) -> Result<(), ApiError> {
+    let user = user_manager
+        .get_api_key(key_id)
+        .and_then(|key| user_manager.get_user(&key.user_id))
+        .ok_or_else(|| UserError::ApiKeyNotFound(key_id.to_string()))?;
+    if may_manage_credentials(viewer, user) {
+        Ok(())
+    } else {
+        Err(forbidden("Not allowed to manage this API key"))
+    }
+}
+
+/// Replace an API key with a new secret
+#[utoipa::path(
+    post,
+    path = "/api-keys/{id}/rotate",
+    tag = "credentials",
+    params(("id" = String, Path, description = "API key ID")),
+    responses(
+        (status = 200, description = "Replacement key; the old one is revoked", body = ApiResponse<NewApiKey>),
+        (status = 403, description = "Not allowed to manage the API key", body = ErrorResponse),
+        (status = 404, description = "No such API key", body = ErrorResponse),
+        (status = 409, description = "The API key is revoked or expired", body = ErrorResponse),
+    )
+)]
+async fn rotate_api_key(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<ApiResponse<NewApiKey>>, ApiError> {
+    let mut user_manager = state.user_manager.write().await;
+    api_key_owner(&user_manager, &viewer, &id)?;
+    
+    let rotated = user_manager.rotate_api_key(&id)
+        .map_err(|e| ApiError::from(UserError::from(e)).context("Failed to rotate API key"))?;
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(rotated),
+        message: Some("API key rotated; the previous secret no longer works".to_string()),
+    }))
+}
+
+/// Revoke an API key
+#[utoipa::path(
+    delete,
+    path = "/api-keys/{id}",
+    tag = "credentials",
+    params(("id" = String, Path, description = "API key ID")),
+    responses(
+        (status = 200, description = "API key revoked", body = ApiResponse<ApiKey>),
+        (status = 403, description = "Not allowed to manage the API key", body = ErrorResponse),
+        (status = 404, description = "No such API key", body = ErrorResponse),
+    )
+)]
+async fn revoke_api_key(
+    Extension(state): Extension<Arc<AppState>>,
+    Viewer(viewer): Viewer,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<ApiResponse<ApiKey>>, ApiError> {
+    let mut user_manager = state.user_manager.write().await;
+    api_key_owner(&user_manager, &viewer, &id)?;
+    
+    let key = user_manager.revoke_api_key(&id)
+        .map_err(|e| ApiError::from(UserError::from(e)).context("Failed to revoke API key"))?;
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(key),
+        message: Some("API key revoked".to_string()),
+    }))
+}
+
+/// Get a user's notification preferences
+#[utoipa::path(
+    get,
+    path = "/users/{id}/notifications",
+    tag = "notifications",
+    params(("id" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "Notification preferences of the user", body = ApiResponse<NotificationPreferences>),
+        (status = 404, description = "No such user", body = ErrorResponse),
+    )
+)]
+async fn get_notification_preferences(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<ApiResponse<NotificationPreferences>>, ApiError> {
+    let preferences = state.user_manager.read().await.get_notification_preferences(&id)
+        .ok_or(UserError::UserNotFound(id))?;
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(preferences),
+        message: None,
+    };
+    Ok(Json(response))
+}
+
+/// Update a user's notification preferences
+#[utoipa::path(
+    post,
+    path = "/users/{id}/notifications",
+    tag = "notifications",
+    params(("id" = String, Path, description = "User ID")),
+    request_body = NotificationPreferences,
+    responses(
+        (status = 200, description = "Notification preferences updated", body = ApiResponse<NotificationPreferences>),
+        (status = 404, description = "No such user", body = ErrorResponse),
+    )
+)]
+async fn set_notification_preferences(
+    Extension(state): Extension<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(payload): Json<NotificationPreferences>,
+) -> Result<Json<ApiResponse<NotificationPreferences>>, ApiError> {
+    state.user_manager.write().await.set_notification_preferences(&id, payload.clone())
+        .map_err(|e| ApiError::from(UserError::from(e)).context("Failed to update notification preferences"))?;
+    
+    let response = ApiResponse {
+        success: true,
+        data: Some(payload),
+        message: Some("Notification preferences updated successfully".to_string()),
+    };
+    Ok(Json(response))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn admin(tenant_id: &str) -> UserContext {
+        UserContext {
+            user_id: "admin-1".to_string(),
+            tenant_id: tenant_id.to_string(),
+            roles: vec![UserRole::Admin],
+            permissions: vec!["manage_users".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_openapi_document() {
+        let document = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        for path in ["/auth", "/users/{id}/api-keys", "/roles/{name}", "/audit/export"] {
+            assert!(document["paths"][path].is_object(), "{} is not documented", path);
+        }
+        assert!(document["paths"]["/auth"]["post"]["security"].is_array());
+        assert!(document["components"]["schemas"]["NotificationPreferences"]["properties"]["digest_frequency"].is_object());
+    }
+
     #[test]
     fn test_args_parsing() {
         let args = Args::parse_from(["svc-users", "--port", "8085"]);
-        assert_eq!(args.port, 8085);
+        assert_eq!(args.port, Some(8085));
     }
 
     #[tokio::test]
@@ -385,8 +1087,95 @@ mod tests {
         let user_manager = UserManager::new();
         let _app_state = Arc::new(AppState {
             user_manager: RwLock::new(user_manager),
+            metrics: Arc::new(ServiceMetrics::new("svc-users")?),
+            auth: Arc::new(JwtAuth::new("svc-users-test-secret-of-32-bytes")?),
         });
         
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_handler_errors_map_to_status() -> Result<()> {
+        use axum::http::StatusCode;
+
+        let state = Arc::new(AppState {
+            user_manager: RwLock::new(UserManager::new()),
+            metrics: Arc::new(ServiceMetrics::new("svc-users")?),
+            auth: Arc::new(JwtAuth::new("svc-users-test-secret-of-32-bytes")?),
+        });
+        let missing = || axum::extract::Path("missing".to_string());
+
+        let error = get_user(Extension(state.clone()), Viewer(admin("tenant-1")), missing()).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        let request = CreateUserRequest {
+            username: "alice".to_string(),
+            email: "alice@example.com".to_string(),
+            roles: vec![],
+            tenant_id: "tenant-1".to_string(),
+            password: Some("short".to_string()),
+        };
+        let error = create_user(Extension(state.clone()), Viewer(admin("tenant-1")), Json(request.clone())).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        // Users are only created by a user administrator of their tenant
+        let error = create_user(Extension(state.clone()), Viewer(admin("tenant-2")), Json(request.clone())).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::FORBIDDEN);
+        let error = create_user(Extension(state.clone()), Viewer(UserContext::anonymous()), Json(request)).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::FORBIDDEN);
+        let request = AuthenticateUserRequest {
+            username: Some("alice".to_string()),
+            password: Some("wrong password".to_string()),
+            api_key: None,
+        };
+        let error = authenticate_user(Extension(state.clone()), Json(request)).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::UNAUTHORIZED);
+        let error = remove_role(Extension(state.clone()), Viewer(UserContext::anonymous()), missing()).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::FORBIDDEN);
+
+        // Roles are only granted by a user administrator of the user's tenant
+        let user = state.user_manager.write().await.create_user("bob", "bob@example.com", vec![], "tenant-1")?;
+        let grant = || Json(AssignRoleRequest { role: "admin".to_string() });
+        let path = || axum::extract::Path(user.id.clone());
+        let error = assign_role(Extension(state.clone()), Viewer(admin("tenant-2")), path(), grant()).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::FORBIDDEN);
+        assert!(assign_role(Extension(state), Viewer(admin("tenant-1")), path(), grant()).await.is_ok());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_user_reads_are_scoped() -> Result<()> {
+        use axum::http::StatusCode;
+
+        let state = Arc::new(AppState {
+            user_manager: RwLock::new(UserManager::new()),
+            metrics: Arc::new(ServiceMetrics::new("svc-users")?),
+            auth: Arc::new(JwtAuth::new("svc-users-test-secret-of-32-bytes")?),
+        });
+        let user = state.user_manager.write().await.create_user("bob", "bob@example.com", vec![], "tenant-1")?;
+        let path = || axum::extract::Path(user.id.clone());
+        let viewer = |user_id: &str, tenant_id: &str, permissions: &[&str]| UserContext {
+            user_id: user_id.to_string(),
+            tenant_id: tenant_id.to_string(),
+            roles: vec![],
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+        };
+
+        // Users read themselves, and readers of their tenant's users read them
+        assert!(get_user(Extension(state.clone()), Viewer(viewer(&user.id, "tenant-1", &[])), path()).await.is_ok());
+        let reader = viewer("carol", "tenant-1", &["users:read:tenant"]);
+        assert!(get_user_context(Extension(state.clone()), Viewer(reader), path()).await.is_ok());
+        for stranger in [UserContext::anonymous(), viewer("dave", "tenant-2", &["*:*:tenant"])] {
+            let error = get_user(Extension(state.clone()), Viewer(stranger.clone()), path()).await.unwrap_err();
+            assert_eq!(error.status(), StatusCode::FORBIDDEN);
+            let error = get_user_context(Extension(state.clone()), Viewer(stranger), path()).await.unwrap_err();
+            assert_eq!(error.status(), StatusCode::FORBIDDEN);
+        }
+
+        // A user's audit logs take the same permission as the tenant's
+        let auditor = viewer("erin", "tenant-1", &["audit:read:tenant"]);
+        let logs = get_user_audit_logs(Extension(state.clone()), Viewer(auditor), path()).await.unwrap().0.data.unwrap();
+        assert!(!logs.is_empty() && logs.iter().all(|log| log.user_id == user.id));
+        let error = get_user_audit_logs(Extension(state), Viewer(UserContext::anonymous()), path()).await.unwrap_err();
+        assert_eq!(error.status(), StatusCode::FORBIDDEN);
+        Ok(())
+    }
 }
\ No newline at end of file
@@ -21,7 +21,9 @@ fn test_user_management_enterprise_features() {
     assert_eq!(user.roles.len(), 3);
     
     // Test user authentication
-    let context = user_manager.authenticate_user("enterprise_user");
+    user_manager.set_password(&user.id, "enterprise password")
+        .expect("Failed to set password");
+    let context = user_manager.authenticate_user("enterprise_user", "enterprise password");
     assert!(context.is_some());
     let context = context.unwrap();
     assert_eq!(context.user_id, user.id);
@@ -55,18 +57,20 @@ fn test_audit_logging_enterprise_features() {
     ).expect("Failed to create audit user");
     
     // Perform actions that should be logged
-    user_manager.authenticate_user("audit_user");
+    user_manager.set_password(&user.id, "audit user password")
+        .expect("Failed to set password");
+    user_manager.authenticate_user("audit_user", "audit user password");
     user_manager.add_user_role(&user.id, UserRole::Trader)
         .expect("Failed to add trader role");
     
     // Check audit logs
     let user_logs = user_manager.get_user_audit_logs(&user.id);
     assert!(!user_logs.is_empty());
-    assert_eq!(user_logs.len(), 3); // create, authenticate, add_role
+    assert_eq!(user_logs.len(), 4); // create, set_password, authenticate, add_role
     
     let all_logs = user_manager.get_all_audit_logs();
     assert!(!all_logs.is_empty());
-    assert!(all_logs.len() >= 3);
+    assert!(all_logs.len() >= 4);
     
     // Check log content
     let first_log = &user_logs[0];
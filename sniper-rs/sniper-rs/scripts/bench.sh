@@ -0,0 +1,11 @@
+#!/usr/bin/env bash
+set -euo pipefail
+# Run the hot path benchmarks and gate on regressions against the baseline.
+# Pass --update to record this run as the new baseline.
+BASELINE=crates/sniper-bench/baselines/main.json
+cargo bench -p sniper-bench --bench hot_paths
+if [[ "${1:-}" == "--update" || ! -f "$BASELINE" ]]; then
+  cargo run -q -p sniper-bench -- snapshot --out "$BASELINE"
+else
+  cargo run -q -p sniper-bench -- compare --baseline "$BASELINE" --threshold-pct "${BENCH_THRESHOLD_PCT:-10}"
+fi
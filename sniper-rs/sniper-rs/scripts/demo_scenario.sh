@@ -0,0 +1,9 @@
+#!/usr/bin/env bash
+set -euo pipefail
+# Seed the locally running services with a synthetic demo scenario and drive
+# a scripted trading session against them. Services that are down are skipped.
+SEED=${SEED:-42}
+mkdir -p target/sim
+cargo run -q -p sniper-sim -- generate --seed "$SEED" --out target/sim/scenario.json
+cargo run -q -p sniper-sim -- run --scenario-file target/sim/scenario.json \
+  --tick-interval-ms "${TICK_INTERVAL_MS:-250}" --report target/sim/report.json
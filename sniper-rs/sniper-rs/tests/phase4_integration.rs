@@ -6,7 +6,7 @@
 //! and custom strategy development framework.
 
 use anyhow::Result;
-use sniper_portfolio::{PortfolioManager, AllocationSettings, Position};
+use sniper_portfolio::{PortfolioManager, AllocationSettings, NettingMode, Position};
 use sniper_orders::{OrderManager, AdvancedOrder, OrderType, TimeInForce, OrderStatus};
 use sniper_core::types::ChainRef;
 use std::collections::HashMap;
@@ -20,6 +20,7 @@ fn test_portfolio_management_system() -> Result<()> {
         diversification_targets: HashMap::new(),
         stop_loss_pct: 5.0,
         take_profit_pct: 10.0,
+        netting: NettingMode::SeparateLots,
     };
     
     let mut portfolio = PortfolioManager::new(100000.0, settings);
@@ -108,6 +109,9 @@ fn test_advanced_order_types() -> Result<()> {
         created_at: 1234567890,
         updated_at: 1234567890,
         status: OrderStatus::Pending,
+        fills: Vec::new(),
+        owner_id: None,
+        tenant_id: None,
     };
     
     let order_id = order_manager.create_order(market_order)?;
@@ -128,6 +132,9 @@ fn test_advanced_order_types() -> Result<()> {
         created_at: 1234567890,
         updated_at: 1234567890,
         status: OrderStatus::Pending,
+        fills: Vec::new(),
+        owner_id: None,
+        tenant_id: None,
     };
     
     let order_id = order_manager.create_order(limit_order)?;
@@ -163,6 +170,7 @@ fn test_cross_chain_arbitrage_simulation() -> Result<()> {
         diversification_targets: HashMap::new(),
         stop_loss_pct: 5.0,
         take_profit_pct: 10.0,
+        netting: NettingMode::SeparateLots,
     };
     
     let ethereum_portfolio = PortfolioManager::new(50000.0, settings.clone());
@@ -191,6 +199,9 @@ fn test_cross_chain_arbitrage_simulation() -> Result<()> {
         created_at: 1234567890,
         updated_at: 1234567890,
         status: OrderStatus::Pending,
+        fills: Vec::new(),
+        owner_id: None,
+        tenant_id: None,
     };
     
     let polygon_order = AdvancedOrder {
@@ -207,6 +218,9 @@ fn test_cross_chain_arbitrage_simulation() -> Result<()> {
         created_at: 1234567890,
         updated_at: 1234567890,
         status: OrderStatus::Pending,
+        fills: Vec::new(),
+        owner_id: None,
+        tenant_id: None,
     };
     
     order_manager.create_order(ethereum_order)?;
@@ -244,6 +258,9 @@ fn test_market_making_capabilities() -> Result<()> {
         created_at: 1234567890,
         updated_at: 1234567890,
         status: OrderStatus::Pending,
+        fills: Vec::new(),
+        owner_id: None,
+        tenant_id: None,
     };
     
     let ask_order = AdvancedOrder {
@@ -260,6 +277,9 @@ fn test_market_making_capabilities() -> Result<()> {
         created_at: 1234567890,
         updated_at: 1234567890,
         status: OrderStatus::Pending,
+        fills: Vec::new(),
+        owner_id: None,
+        tenant_id: None,
     };
     
     order_manager.create_order(bid_order)?;
@@ -289,6 +309,7 @@ fn test_advanced_position_sizing() -> Result<()> {
         diversification_targets: HashMap::new(),
         stop_loss_pct: 3.0, // Tighter stop loss
         take_profit_pct: 6.0, // Conservative take profit
+        netting: NettingMode::SeparateLots,
     };
     
     let mut portfolio = PortfolioManager::new(100000.0, settings);